@@ -8,7 +8,7 @@
 # foreground comparison also runs on macOS with HVF.
 #
 # Usage:
-#   bench/bench.sh [all|cold|foreground|sandbox|warm|fork|leak|race|pnpm]   (default: all)
+#   bench/bench.sh [all|cold|foreground|sandbox|warm|fork|leak|race|pnpm|memory]   (default: all)
 # Env:
 #   A3S_BOX   path to the a3s-box binary           (default: a3s-box on PATH)
 #   IMAGE     OCI image to benchmark                (default: alpine:latest)
@@ -35,6 +35,9 @@
 #   PNPM_MEMORY  memory for pnpm boxes/containers     (default: 4g)
 #   PNPM_DOCKER  1 compares Docker cold/hot baselines, 0 skips (default: 1)
 #   PNPM_RESET_A3S_CACHE 1 removes a3s-cache-pnpm before cold A3S samples (default: 0)
+#   MEMORY_RUNS  samples per guest-memory variant   (default: 5)
+#   MEMORY_SIZE  guest memory for memory mode       (default: 2g)
+#   MEMORY_TOUCH_MB MiB written inside the guest per sample (default: 1024)
 #
 # Exit code is non-zero if the leak assertion fails, so it is CI-gateable
 # (wire it into the self-hosted KVM job — see docs/ci-kvm-runner.md).
@@ -70,6 +73,9 @@ PNPM_LOG_DIR="${PNPM_LOG_DIR:-/tmp/a3s-bench-pnpm}"
 PNPM_DOCKER_STORE_VOLUME="${PNPM_DOCKER_STORE_VOLUME:-a3s-bench-pnpm-store}"
 PNPM_A3S_CACHE_VOLUME="${PNPM_A3S_CACHE_VOLUME:-a3s-cache-pnpm}"
 PNPM_RESET_A3S_CACHE="${PNPM_RESET_A3S_CACHE:-0}"
+MEMORY_RUNS="${MEMORY_RUNS:-5}"
+MEMORY_SIZE="${MEMORY_SIZE:-2g}"
+MEMORY_TOUCH_MB="${MEMORY_TOUCH_MB:-1024}"
 MODE="${1:-all}"
 
 now_ms() {
@@ -467,6 +473,43 @@ bench_fork() {
   done
 }
 
+# Compare default 4K-page guest RAM with --hugepages and --mem-prealloc:
+# boot latency, time to write MEMORY_TOUCH_MB inside the guest, and how much
+# of the VM process ended up on transparent hugepages (from `stats`).
+bench_memory() {
+  echo "## Guest memory backing ($MEMORY_RUNS runs per variant, --memory $MEMORY_SIZE, touch ${MEMORY_TOUCH_MB}MiB)"
+  local thp="unavailable"
+  [ -r /sys/kernel/mm/transparent_hugepage/enabled ] && thp=$(cat /sys/kernel/mm/transparent_hugepage/enabled)
+  echo "  host THP: $thp"
+  "$A3S_BOX" pull "$IMAGE" >/dev/null 2>&1 || true
+  local touch_cmd="dd if=/dev/zero of=/scratch/fill bs=1M count=$MEMORY_TOUCH_MB 2>/dev/null"
+  for flags in "" "--hugepages" "--mem-prealloc" "--hugepages --mem-prealloc"; do
+    local label="${flags:-default}"
+    local boot="" touch="" huge=""
+    for i in $(seq 1 "$MEMORY_RUNS"); do
+      local name="a3s-bench-mem-$$-$i"
+      local s e
+      s=$(now_ms)
+      # shellcheck disable=SC2086
+      "$A3S_BOX" run -d --name "$name" --memory "$MEMORY_SIZE" $flags \
+        --tmpfs "/scratch:size=$(( MEMORY_TOUCH_MB + 64 ))m" "$IMAGE" -- sleep 600 >/dev/null 2>&1 || {
+        echo "  $label: run failed"; continue
+      }
+      e=$(now_ms); boot="$boot $(( e - s ))"
+      s=$(now_ms)
+      "$A3S_BOX" exec "$name" -- sh -c "$touch_cmd" >/dev/null 2>&1
+      e=$(now_ms); touch="$touch $(( e - s ))"
+      local bytes
+      bytes=$("$A3S_BOX" stats --no-stream --format json "$name" 2>/dev/null \
+        | python3 -c 'import json,sys; rows=json.load(sys.stdin); print((rows[0].get("anon_huge_bytes") or 0) if rows else 0)' 2>/dev/null)
+      huge="$huge $(( ${bytes:-0} / 1048576 ))"
+      "$A3S_BOX" rm -f "$name" >/dev/null 2>&1
+    done
+    printf '  %-26s boot p50=%sms  touch p50=%sms  min=%sms  anon-huge p50=%sMiB\n' \
+      "$label:" "$(pct "$boot" 50)" "$(pct "$touch" 50)" "$(pct "$touch" 1)" "$(pct "$huge" 50)"
+  done
+}
+
 bench_leak() {
   echo "## Leak assertion ($CHURN create/run/remove cycles)"
   local b_shim b_mount b_dir
@@ -738,6 +781,7 @@ case "$MODE" in
   leak) bench_leak || rc=$? ;;
   race) bench_race || rc=$? ;;
   pnpm) bench_pnpm || rc=$? ;;
+  memory) bench_memory ;;
   all)
     bench_cold
     bench_foreground || rc=$?
@@ -746,6 +790,6 @@ case "$MODE" in
     bench_leak || rc=$?
     bench_race || rc=$?
     ;;
-  *) echo "unknown mode: $MODE (use all|cold|foreground|sandbox|warm|fork|leak|race|pnpm)"; exit 2 ;;
+  *) echo "unknown mode: $MODE (use all|cold|foreground|sandbox|warm|fork|leak|race|pnpm|memory)"; exit 2 ;;
 esac
 exit "$rc"
@@ -13,7 +13,11 @@ from .client import (
     VolumeBuilder,
 )
 from .connection import A3SConnectionConfig, A3SRemoteConnection
-from .exceptions import A3SBoxError, A3SBoxNotInstalledError
+from .exceptions import (
+    A3SAttestationError,
+    A3SBoxError,
+    A3SBoxNotInstalledError,
+)
 from .models import (
     BuildImageInfo,
     CommandResult,
@@ -32,6 +36,7 @@ from .models import (
     RuntimeDiagnostics,
     RuntimeDiskUsage,
     RuntimeVirtualization,
+    SandboxAttestation,
     SandboxLogEntry,
     SandboxNetwork,
     SandboxStats,
@@ -59,6 +64,7 @@ from .sandbox import (
 __all__ = [
     "A3SAsyncBoxClient",
     "A3SAsyncLocalRuntime",
+    "A3SAttestationError",
     "A3SBoxClient",
     "A3SBoxError",
     "A3SBoxNotInstalledError",
@@ -92,6 +98,7 @@ __all__ = [
     "RuntimeDiskUsage",
     "RuntimeVirtualization",
     "Sandbox",
+    "SandboxAttestation",
     "SandboxBuilder",
     "SandboxLogEntry",
     "SandboxNetwork",
@@ -22,6 +22,7 @@ from .models import (
     RuntimeDiagnostics,
     RuntimeDiskUsage,
     RuntimeVirtualization,
+    SandboxAttestation,
     SandboxLogEntry,
     SandboxStats,
     SandboxSummary,
@@ -209,6 +210,21 @@ def sandbox_stats(result: Mapping[str, object]) -> SandboxStats:
     )
 
 
+def sandbox_attestation(result: Mapping[str, object]) -> SandboxAttestation:
+    return SandboxAttestation(
+        box_id=str(result["box_id"]),
+        verified=boolean(result["verified"]),
+        simulated=boolean(result["simulated"]),
+        nonce=str(result["nonce"]),
+        measurement=str(result["measurement"]),
+        chip_id=str(result["chip_id"]),
+        tcb=str(result["tcb"]),
+        runtime_claims=optional_string(result.get("runtime_claims")),
+        failures=tuple(str(value) for value in sequence(result["failures"])),
+        verified_at=str(result["verified_at"]),
+    )
+
+
 def runtime_diagnostics(result: Mapping[str, object]) -> RuntimeDiagnostics:
     virtualization = mapping(result["virtualization"])
     return RuntimeDiagnostics(
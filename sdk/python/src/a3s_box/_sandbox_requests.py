@@ -83,6 +83,24 @@ def create_request(
     return request
 
 
+def attest_request(
+    sandbox: SandboxIdentity,
+    policy: Mapping[str, object] | None,
+    allow_simulated: bool,
+    offline: bool,
+) -> dict[str, object]:
+    request: dict[str, object] = {
+        "operation": "sandbox_attest",
+        "sandbox_id": sandbox.sandbox_id,
+        "generation": sandbox.generation,
+        "allow_simulated": allow_simulated,
+        "offline": offline,
+    }
+    if policy is not None:
+        request["policy"] = dict(policy)
+    return request
+
+
 def command_request(
     sandbox: SandboxIdentity,
     command: str | Sequence[str],
@@ -2,6 +2,8 @@
 
 from __future__ import annotations
 
+from .models import SandboxAttestation
+
 
 class A3SBoxError(RuntimeError):
     """Base error returned by the local A3S Box runtime."""
@@ -11,6 +13,18 @@ class A3SBoxError(RuntimeError):
         self.code = code
 
 
+class A3SAttestationError(A3SBoxError):
+    """A Sandbox's TEE failed attestation and must not be trusted."""
+
+    def __init__(self, attestation: SandboxAttestation) -> None:
+        reasons = "; ".join(attestation.failures) or "verification failed"
+        super().__init__(
+            f"Sandbox {attestation.box_id} failed attestation: {reasons}",
+            code="attestation_failed",
+        )
+        self.attestation = attestation
+
+
 class A3SBoxNotInstalledError(A3SBoxError):
     """The local ``a3s-box`` executable could not be found."""
 
@@ -197,6 +197,22 @@ class SandboxStats:
     block_write_bytes: int
 
 
+@dataclass(frozen=True, slots=True)
+class SandboxAttestation:
+    """Verdict of a nonce challenge against a Sandbox's TEE."""
+
+    box_id: str
+    verified: bool
+    simulated: bool
+    nonce: str
+    measurement: str
+    chip_id: str
+    tcb: str
+    runtime_claims: str | None
+    failures: tuple[str, ...]
+    verified_at: str
+
+
 @dataclass(frozen=True, slots=True)
 class RuntimeVirtualization:
     available: bool
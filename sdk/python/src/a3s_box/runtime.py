@@ -51,6 +51,7 @@ SUPPORTED_BRIDGE_OPERATIONS = (
     "sandbox_logs",
     "sandbox_stats",
     "sandbox_snapshot_create",
+    "sandbox_attest",
     "filesystem_snapshot_list",
     "filesystem_snapshot_get",
     "filesystem_snapshot_size",
@@ -13,12 +13,14 @@ from ._bridge_values import (
     filesystem_snapshot_info as _snapshot_info,
     mapping as _mapping,
     mapping_sequence as _mapping_sequence,
+    sandbox_attestation as _sandbox_attestation,
     sandbox_log_entry as _sandbox_log_entry,
     sandbox_stats as _sandbox_stats,
 )
-from .exceptions import A3SBoxError
+from .exceptions import A3SAttestationError, A3SBoxError
 from ._sandbox_requests import (
     DEFAULT_IMAGE,
+    attest_request as _attest_request,
     command_request as _command_request,
     create_request as _create_request,
 )
@@ -27,6 +29,7 @@ from .models import (
     EntryInfo,
     FilesystemSnapshotInfo,
     PortMapping,
+    SandboxAttestation,
     SandboxNetwork,
     SandboxLogEntry,
     SandboxStats,
@@ -58,6 +61,7 @@ class Sandbox:
         self.generation = generation
         self.state = state
         self._runtime = runtime
+        self._attestation: tuple[int, SandboxAttestation] | None = None
         self.commands = Commands(self)
         self.files = Filesystem(self)
 
@@ -91,6 +95,9 @@ class Sandbox:
         read_only: bool = False,
         persistent: bool = False,
         auto_remove: bool = True,
+        attested: bool = False,
+        attestation_policy: Mapping[str, object] | None = None,
+        allow_simulated_attestation: bool = False,
         runtime: LocalRuntime | None = None,
     ) -> Sandbox:
         local_runtime = runtime or A3SLocalRuntime()
@@ -120,20 +127,37 @@ class Sandbox:
                 auto_remove,
             )
         )
-        return cls._from_result(result, local_runtime)
+        sandbox = cls._from_result(result, local_runtime)
+        if attested:
+            try:
+                sandbox._require_attestation(
+                    attestation_policy, allow_simulated_attestation
+                )
+            except BaseException:
+                sandbox.kill()
+                raise
+        return sandbox
 
     @classmethod
     def connect(
         cls,
         sandbox_id: str,
         *,
+        attested: bool = False,
+        attestation_policy: Mapping[str, object] | None = None,
+        allow_simulated_attestation: bool = False,
         runtime: LocalRuntime | None = None,
     ) -> Sandbox:
         local_runtime = runtime or A3SLocalRuntime()
         result = local_runtime.request(
             {"operation": "sandbox_inspect", "sandbox_id": sandbox_id}
         )
-        return cls._from_result(result, local_runtime)
+        sandbox = cls._from_result(result, local_runtime)
+        if attested:
+            sandbox._require_attestation(
+                attestation_policy, allow_simulated_attestation
+            )
+        return sandbox
 
     @classmethod
     def _from_result(
@@ -263,6 +287,46 @@ class Sandbox:
         self._update_lifecycle(result, fallback_state=self.state)
         return _snapshot_info(result)
 
+    @property
+    def attestation(self) -> SandboxAttestation | None:
+        """The cached attestation verdict for the current generation."""
+        cached = self._attestation
+        if cached is None or cached[0] != self.generation:
+            return None
+        return cached[1]
+
+    def attest(
+        self,
+        *,
+        policy: Mapping[str, object] | None = None,
+        allow_simulated: bool = False,
+        offline: bool = False,
+        refresh: bool = False,
+    ) -> SandboxAttestation:
+        """Challenge the Sandbox's TEE with a fresh nonce and verify it.
+
+        The verdict is cached on this handle until the Sandbox restarts;
+        pass ``refresh=True`` to challenge again.
+        """
+        cached = self.attestation
+        if cached is not None and not refresh:
+            return cached
+        result = self._runtime.request(
+            _attest_request(self, policy, allow_simulated, offline)
+        )
+        verdict = _sandbox_attestation(_mapping(result["attestation"]))
+        self._attestation = (self.generation, verdict)
+        return verdict
+
+    def _require_attestation(
+        self,
+        policy: Mapping[str, object] | None,
+        allow_simulated: bool,
+    ) -> None:
+        verdict = self.attest(policy=policy, allow_simulated=allow_simulated)
+        if not verdict.verified:
+            raise A3SAttestationError(verdict)
+
     def script(self, source: str | bytes | Script) -> ScriptBuilder:
         return self.commands.script(source)
 
@@ -498,6 +562,7 @@ class AsyncSandbox:
         self.generation = generation
         self.state = state
         self._runtime = runtime
+        self._attestation: tuple[int, SandboxAttestation] | None = None
         self.commands = AsyncCommands(self)
         self.files = AsyncFilesystem(self)
 
@@ -531,6 +596,9 @@ class AsyncSandbox:
         read_only: bool = False,
         persistent: bool = False,
         auto_remove: bool = True,
+        attested: bool = False,
+        attestation_policy: Mapping[str, object] | None = None,
+        allow_simulated_attestation: bool = False,
         runtime: AsyncLocalRuntime | None = None,
     ) -> AsyncSandbox:
         local_runtime = runtime or A3SAsyncLocalRuntime()
@@ -560,20 +628,37 @@ class AsyncSandbox:
                 auto_remove,
             )
         )
-        return cls._from_result(result, local_runtime)
+        sandbox = cls._from_result(result, local_runtime)
+        if attested:
+            try:
+                await sandbox._require_attestation(
+                    attestation_policy, allow_simulated_attestation
+                )
+            except BaseException:
+                await sandbox.kill()
+                raise
+        return sandbox
 
     @classmethod
     async def connect(
         cls,
         sandbox_id: str,
         *,
+        attested: bool = False,
+        attestation_policy: Mapping[str, object] | None = None,
+        allow_simulated_attestation: bool = False,
         runtime: AsyncLocalRuntime | None = None,
     ) -> AsyncSandbox:
         local_runtime = runtime or A3SAsyncLocalRuntime()
         result = await local_runtime.request(
             {"operation": "sandbox_inspect", "sandbox_id": sandbox_id}
         )
-        return cls._from_result(result, local_runtime)
+        sandbox = cls._from_result(result, local_runtime)
+        if attested:
+            await sandbox._require_attestation(
+                attestation_policy, allow_simulated_attestation
+            )
+        return sandbox
 
     @classmethod
     def _from_result(
@@ -707,6 +792,46 @@ class AsyncSandbox:
         self._update_lifecycle(result, fallback_state=self.state)
         return _snapshot_info(result)
 
+    @property
+    def attestation(self) -> SandboxAttestation | None:
+        """The cached attestation verdict for the current generation."""
+        cached = self._attestation
+        if cached is None or cached[0] != self.generation:
+            return None
+        return cached[1]
+
+    async def attest(
+        self,
+        *,
+        policy: Mapping[str, object] | None = None,
+        allow_simulated: bool = False,
+        offline: bool = False,
+        refresh: bool = False,
+    ) -> SandboxAttestation:
+        """Challenge the Sandbox's TEE with a fresh nonce and verify it.
+
+        The verdict is cached on this handle until the Sandbox restarts;
+        pass ``refresh=True`` to challenge again.
+        """
+        cached = self.attestation
+        if cached is not None and not refresh:
+            return cached
+        result = await self._runtime.request(
+            _attest_request(self, policy, allow_simulated, offline)
+        )
+        verdict = _sandbox_attestation(_mapping(result["attestation"]))
+        self._attestation = (self.generation, verdict)
+        return verdict
+
+    async def _require_attestation(
+        self,
+        policy: Mapping[str, object] | None,
+        allow_simulated: bool,
+    ) -> None:
+        verdict = await self.attest(policy=policy, allow_simulated=allow_simulated)
+        if not verdict.verified:
+            raise A3SAttestationError(verdict)
+
     def script(self, source: str | bytes | Script) -> AsyncScriptBuilder:
         return self.commands.script(source)
 
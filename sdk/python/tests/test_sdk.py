@@ -13,6 +13,7 @@ from unittest.mock import patch
 import a3s_box
 from a3s_box import (
     A3SAsyncBoxClient,
+    A3SAttestationError,
     A3SBoxClient,
     A3SRemoteConnection,
     AsyncSandbox,
@@ -201,6 +202,22 @@ def response_for(request: Mapping[str, object]) -> dict[str, Any]:
             "state": "running",
             "generation": request["generation"],
         }
+    if operation == "sandbox_attest":
+        verified = bool(request["allow_simulated"])
+        return {
+            "attestation": {
+                "box_id": request["sandbox_id"],
+                "verified": verified,
+                "simulated": True,
+                "nonce": "ab" * 32,
+                "measurement": "00" * 48,
+                "chip_id": "a3" * 64,
+                "tcb": "3:0:8:115",
+                "runtime_claims": None,
+                "failures": [] if verified else ["Simulated report rejected"],
+                "verified_at": "2026-07-23T00:00:00Z",
+            }
+        }
     if operation == "filesystem_snapshot_list":
         return {"snapshots": [filesystem_snapshot_response("ci-base")]}
     if operation == "filesystem_snapshot_get":
@@ -398,6 +415,7 @@ class SdkTests(unittest.TestCase):
             "RuntimeDiagnostics",
             "RuntimeDiskUsage",
             "RuntimeVirtualization",
+            "SandboxAttestation",
             "SandboxLogEntry",
             "SandboxStats",
             "SandboxSummary",
@@ -684,6 +702,48 @@ class SdkTests(unittest.TestCase):
         self.assertEqual(sandbox.state, "paused")
         self.assertEqual(runtime.requests[0]["operation"], "sandbox_inspect")
 
+    def test_attested_sandbox_caches_its_verdict_per_generation(self) -> None:
+        runtime = FakeRuntime()
+
+        sandbox = Sandbox.create(
+            attested=True,
+            attestation_policy={"require_no_smt": True},
+            allow_simulated_attestation=True,
+            runtime=runtime,
+        )
+        attestation = sandbox.attestation
+        self.assertIsNotNone(attestation)
+        assert attestation is not None
+        self.assertTrue(attestation.verified)
+        self.assertEqual(attestation.tcb, "3:0:8:115")
+        attest_request = runtime.requests[1]
+        self.assertEqual(attest_request["operation"], "sandbox_attest")
+        self.assertEqual(attest_request["generation"], 1)
+        self.assertEqual(attest_request["policy"], {"require_no_smt": True})
+
+        self.assertIs(sandbox.attest(), attestation)
+        self.assertEqual(len(runtime.requests), 2)
+        sandbox.restart()
+        self.assertIsNone(sandbox.attestation)
+        sandbox.attest(allow_simulated=True)
+        self.assertEqual(runtime.requests[-1]["generation"], 2)
+
+    def test_failed_attestation_kills_the_new_sandbox(self) -> None:
+        runtime = FakeRuntime()
+
+        with self.assertRaises(A3SAttestationError) as raised:
+            Sandbox.create(attested=True, runtime=runtime)
+
+        self.assertEqual(raised.exception.code, "attestation_failed")
+        self.assertFalse(raised.exception.attestation.verified)
+        self.assertEqual(
+            [request["operation"] for request in runtime.requests],
+            ["sandbox_create", "sandbox_attest", "sandbox_kill"],
+        )
+        with self.assertRaises(A3SAttestationError):
+            Sandbox.connect("existing-local", attested=True, runtime=runtime)
+        self.assertEqual(runtime.requests[-1]["operation"], "sandbox_attest")
+
     def test_runtime_managed_filesystem_snapshot_lifecycle(self) -> None:
         runtime = FakeRuntime()
 
@@ -866,6 +926,22 @@ class AsyncSdkTests(unittest.IsolatedAsyncioTestCase):
         self.assertEqual(size, 4096)
         self.assertTrue(deleted)
 
+    async def test_async_attested_sandbox_matches_sync_surface(self) -> None:
+        runtime = AsyncFakeRuntime()
+        sandbox = await AsyncSandbox.create(
+            attested=True,
+            allow_simulated_attestation=True,
+            runtime=runtime,
+        )
+        attestation = await sandbox.attest()
+        self.assertIs(sandbox.attestation, attestation)
+        self.assertTrue(attestation.verified)
+        self.assertEqual(len(runtime.requests), 2)
+
+        with self.assertRaises(A3SAttestationError):
+            await AsyncSandbox.create(attested=True, runtime=runtime)
+        self.assertEqual(runtime.requests[-1]["operation"], "sandbox_kill")
+
 
 if __name__ == "__main__":
     unittest.main()
@@ -21,6 +21,7 @@ import type {
 import type {
   EntryInfo,
   FilesystemSnapshotInfo,
+  SandboxAttestation,
 } from './sandbox.js'
 
 export function buildImageInfo(result: BridgeResult): BuildImageInfo {
@@ -195,6 +196,22 @@ export function sandboxStats(result: BridgeResult): SandboxStats {
   }
 }
 
+export function sandboxAttestation(result: BridgeResult): SandboxAttestation {
+  const runtimeClaims = optionalString(result, 'runtime_claims')
+  return {
+    boxId: requiredString(result, 'box_id'),
+    verified: requiredBoolean(result, 'verified'),
+    simulated: requiredBoolean(result, 'simulated'),
+    nonce: requiredString(result, 'nonce'),
+    measurement: requiredString(result, 'measurement'),
+    chipId: requiredString(result, 'chip_id'),
+    tcb: requiredString(result, 'tcb'),
+    ...(runtimeClaims === undefined ? {} : { runtimeClaims }),
+    failures: stringArray(result.failures),
+    verifiedAt: requiredString(result, 'verified_at'),
+  }
+}
+
 export function runtimeDiagnostics(result: BridgeResult): RuntimeDiagnostics {
   const virtualization = asRecord(result.virtualization)
   return {
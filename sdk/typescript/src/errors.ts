@@ -1,3 +1,5 @@
+import type { SandboxAttestation } from './sandbox.js'
+
 export class A3SBoxError extends Error {
   readonly code: string
 
@@ -18,3 +20,17 @@ export class A3SBoxNotInstalledError extends A3SBoxError {
     this.name = 'A3SBoxNotInstalledError'
   }
 }
+
+export class A3SAttestationError extends A3SBoxError {
+  readonly attestation: SandboxAttestation
+
+  constructor(attestation: SandboxAttestation) {
+    const reasons = attestation.failures.join('; ') || 'verification failed'
+    super(
+      `Sandbox ${attestation.boxId} failed attestation: ${reasons}`,
+      'attestation_failed'
+    )
+    this.name = 'A3SAttestationError'
+    this.attestation = attestation
+  }
+}
@@ -5,7 +5,11 @@ export {
   type A3SRemoteEnvironment,
   type OfficialSdkConnectionOptions,
 } from './connection.js'
-export { A3SBoxError, A3SBoxNotInstalledError } from './errors.js'
+export {
+  A3SAttestationError,
+  A3SBoxError,
+  A3SBoxNotInstalledError,
+} from './errors.js'
 export {
   A3SBoxClient,
   ImageBuilder,
@@ -48,6 +52,7 @@ export {
   Filesystem,
   Sandbox,
   ScriptBuilder,
+  type AttestOptions,
   type CommandResult,
   type CommandRunOptions,
   type EntryInfo,
@@ -55,6 +60,8 @@ export {
   type FilesystemSnapshotInfo,
   type Isolation,
   type PortMapping,
+  type SandboxAttestation,
+  type SandboxAttestationOptions,
   type SandboxNetwork,
   type Script,
   type SandboxConnectOptions,
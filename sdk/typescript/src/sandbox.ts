@@ -1,6 +1,6 @@
 import { randomUUID } from 'node:crypto'
 
-import { A3SBoxError } from './errors.js'
+import { A3SAttestationError, A3SBoxError } from './errors.js'
 import {
   asRecord,
   decodeBase64,
@@ -9,6 +9,7 @@ import {
   requiredNumber,
   requiredRecord,
   requiredString,
+  sandboxAttestation,
   sandboxLogEntry,
   sandboxStats,
   unknownRecordArray,
@@ -59,7 +60,15 @@ export interface Script {
   interpreter?: readonly string[]
 }
 
-export interface SandboxCreateOptions {
+export interface SandboxAttestationOptions {
+  /** Challenge the Sandbox's TEE before returning it; fail if unverified. */
+  attested?: boolean
+  /** Attestation policy; defaults to the one the box was started with. */
+  attestationPolicy?: Readonly<Record<string, unknown>>
+  allowSimulatedAttestation?: boolean
+}
+
+export interface SandboxCreateOptions extends SandboxAttestationOptions {
   timeoutMs?: number
   envs?: Readonly<Record<string, string>>
   metadata?: Readonly<Record<string, string>>
@@ -88,6 +97,27 @@ export interface SandboxConnectOptions {
   runtime?: LocalRuntime
 }
 
+export interface AttestOptions {
+  policy?: Readonly<Record<string, unknown>>
+  allowSimulated?: boolean
+  offline?: boolean
+  /** Challenge again even if this handle holds a verdict. */
+  refresh?: boolean
+}
+
+export interface SandboxAttestation {
+  boxId: string
+  verified: boolean
+  simulated: boolean
+  nonce: string
+  measurement: string
+  chipId: string
+  tcb: string
+  runtimeClaims?: string
+  failures: string[]
+  verifiedAt: string
+}
+
 export interface CommandRunOptions {
   timeoutMs?: number
   envs?: Readonly<Record<string, string>>
@@ -142,6 +172,10 @@ export class Sandbox {
   readonly commands: Commands
   readonly files: Filesystem
   private readonly runtime: LocalRuntime
+  private cachedAttestation?: {
+    generation: number
+    verdict: SandboxAttestation
+  }
 
   protected constructor(
     sandboxId: string,
@@ -205,19 +239,30 @@ export class Sandbox {
       persistent: options.persistent ?? false,
       auto_remove: options.autoRemove ?? true,
     })
-    return Sandbox.fromResult(result, runtime)
+    const sandbox = Sandbox.fromResult(result, runtime)
+    if (options.attested) {
+      try {
+        await sandbox.requireAttestation(options)
+      } catch (error) {
+        await sandbox.kill()
+        throw error
+      }
+    }
+    return sandbox
   }
 
   static async connect(
     sandboxId: string,
-    options: SandboxConnectOptions = {}
+    options: SandboxConnectOptions & SandboxAttestationOptions = {}
   ): Promise<Sandbox> {
     const runtime = options.runtime ?? new A3SLocalRuntime()
     const result = await runtime.request({
       operation: 'sandbox_inspect',
       sandbox_id: sandboxId,
     })
-    return Sandbox.fromResult(result, runtime)
+    const sandbox = Sandbox.fromResult(result, runtime)
+    if (options.attested) await sandbox.requireAttestation(options)
+    return sandbox
   }
 
   private static fromResult(
@@ -350,6 +395,30 @@ export class Sandbox {
     return filesystemSnapshotInfo(result)
   }
 
+  /** The cached attestation verdict for the current generation. */
+  get attestation(): SandboxAttestation | undefined {
+    const cached = this.cachedAttestation
+    return cached?.generation === this.generation ? cached.verdict : undefined
+  }
+
+  /**
+   * Challenge the Sandbox's TEE with a fresh nonce and verify it. The verdict
+   * is cached on this handle until the Sandbox restarts.
+   */
+  async attest(options: AttestOptions = {}): Promise<SandboxAttestation> {
+    const cached = this.attestation
+    if (cached !== undefined && !options.refresh) return cached
+    const result = await this.runtime.request({
+      ...this.lifecycleRequest('sandbox_attest'),
+      allow_simulated: options.allowSimulated ?? false,
+      offline: options.offline ?? false,
+      ...(options.policy === undefined ? {} : { policy: options.policy }),
+    })
+    const verdict = sandboxAttestation(requiredRecord(result, 'attestation'))
+    this.cachedAttestation = { generation: this.generation, verdict }
+    return verdict
+  }
+
   script(source: string | Uint8Array | Script): ScriptBuilder {
     return this.commands.script(source)
   }
@@ -396,6 +465,16 @@ export class Sandbox {
     return this.runtime.request(request)
   }
 
+  private async requireAttestation(
+    options: SandboxAttestationOptions
+  ): Promise<void> {
+    const verdict = await this.attest({
+      policy: options.attestationPolicy,
+      allowSimulated: options.allowSimulatedAttestation,
+    })
+    if (!verdict.verified) throw new A3SAttestationError(verdict)
+  }
+
   private lifecycleRequest(
     operation: string
   ): Readonly<Record<string, unknown>> {
@@ -2,6 +2,7 @@ import assert from 'node:assert/strict'
 import { readFile } from 'node:fs/promises'
 
 import SandboxDefault, {
+  A3SAttestationError,
   A3SBoxClient,
   A3SLocalRuntime,
   A3SRemoteConnection,
@@ -163,6 +164,23 @@ class FakeRuntime {
         }
       case 'sandbox_stats':
         return { stats: sandboxStatsResponse(request.sandbox_id) }
+      case 'sandbox_attest':
+        return {
+          attestation: {
+            box_id: request.sandbox_id,
+            verified: request.allow_simulated,
+            simulated: true,
+            nonce: 'ab'.repeat(32),
+            measurement: '00'.repeat(48),
+            chip_id: 'a3'.repeat(64),
+            tcb: '3:0:8:115',
+            runtime_claims: null,
+            failures: request.allow_simulated
+              ? []
+              : ['Simulated report rejected'],
+            verified_at: '2026-07-23T00:00:00Z',
+          },
+        }
       case 'sandbox_snapshot_create':
         return {
           snapshot_id: request.snapshot_id,
@@ -626,6 +644,45 @@ assert.equal(
   'ci-base-source'
 )
 
+const attestRuntime = new FakeRuntime()
+const attestedSandbox = await Sandbox.create(DEFAULT_IMAGE, {
+  attested: true,
+  attestationPolicy: { require_no_smt: true },
+  allowSimulatedAttestation: true,
+  runtime: attestRuntime,
+})
+const attestation = attestedSandbox.attestation
+assert.equal(attestation.verified, true)
+assert.equal(attestation.tcb, '3:0:8:115')
+assert.equal(attestation.runtimeClaims, undefined)
+assert.deepEqual(attestRuntime.requests[1], {
+  operation: 'sandbox_attest',
+  sandbox_id: 'sandbox-local-1',
+  generation: 1,
+  allow_simulated: true,
+  offline: false,
+  policy: { require_no_smt: true },
+})
+assert.equal(await attestedSandbox.attest(), attestation)
+assert.equal(attestRuntime.requests.length, 2)
+await attestedSandbox.restart()
+assert.equal(attestedSandbox.attestation, undefined)
+await attestedSandbox.attest({ allowSimulated: true })
+assert.equal(attestRuntime.requests.at(-1).generation, 2)
+
+const rejectedRuntime = new FakeRuntime()
+await assert.rejects(
+  Sandbox.create(DEFAULT_IMAGE, { attested: true, runtime: rejectedRuntime }),
+  (error) =>
+    error instanceof A3SAttestationError &&
+    error.code === 'attestation_failed' &&
+    error.attestation.verified === false
+)
+assert.deepEqual(
+  rejectedRuntime.requests.map((request) => request.operation),
+  ['sandbox_create', 'sandbox_attest', 'sandbox_kill']
+)
+
 const savedEnvironment = {
   E2B_API_KEY: process.env.E2B_API_KEY,
   A3S_BOX_API_KEY: process.env.A3S_BOX_API_KEY,
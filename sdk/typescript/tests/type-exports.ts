@@ -3,6 +3,7 @@ import type {
   RuntimeDiagnostics,
   RuntimeDiskUsage,
   RuntimeVirtualization,
+  SandboxAttestation,
   SandboxLogEntry,
   SandboxStats,
   SandboxSummary,
@@ -13,6 +14,7 @@ type PublicInspectionTypes = [
   RuntimeDiagnostics,
   RuntimeDiskUsage,
   RuntimeVirtualization,
+  SandboxAttestation,
   SandboxLogEntry,
   SandboxStats,
   SandboxSummary,
@@ -0,0 +1,208 @@
+//! Box configuration.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use a3s_box_core::{ExecutionIsolation, PortMapping, PortProtocol};
+use a3s_box_sdk::{A3sBoxClient, Sandbox, SandboxCreateOptions, SandboxNetwork, VolumeMount};
+
+use crate::{BoxHandle, Result};
+
+/// Isolation backend for a box.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Isolation {
+    /// A dedicated MicroVM per box (the default).
+    #[default]
+    MicroVm,
+    /// A shared-kernel OCI sandbox; needs a certified Linux host.
+    Sandbox,
+}
+
+/// Network attachment for a box.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Network {
+    /// Outbound networking through the host (the default).
+    #[default]
+    Default,
+    /// No networking at all.
+    Disabled,
+    /// Join an existing a3s-box bridge network by name.
+    Bridge(String),
+}
+
+/// Configures and starts a box.
+///
+/// Every option has a default, so `BoxBuilder::new(image).start()` is a
+/// complete call.
+#[derive(Debug, Clone)]
+pub struct BoxBuilder {
+    client: A3sBoxClient,
+    options: SandboxCreateOptions,
+}
+
+impl BoxBuilder {
+    /// Start configuring a box that runs `image`.
+    pub fn new(image: impl Into<String>) -> Self {
+        Self {
+            client: A3sBoxClient::new(),
+            options: SandboxCreateOptions::new(image),
+        }
+    }
+
+    /// Keep runtime state under `home` instead of the default `~/.a3s`.
+    pub fn home(mut self, home: impl Into<PathBuf>) -> Self {
+        self.client = A3sBoxClient::from_home(home);
+        self
+    }
+
+    /// Give the box a name.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.options.name = Some(name.into());
+        self
+    }
+
+    /// Set an environment variable for the box's processes.
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.envs.insert(key.into(), value.into());
+        self
+    }
+
+    /// Number of virtual CPUs.
+    pub fn cpus(mut self, cpus: u32) -> Self {
+        self.options.cpus = Some(cpus);
+        self
+    }
+
+    /// Memory size in MiB.
+    pub fn memory_mb(mut self, memory_mb: u32) -> Self {
+        self.options.memory_mb = Some(memory_mb);
+        self
+    }
+
+    /// Stop the box automatically after `timeout` (rounded up to seconds).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        let secs = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+        self.options.timeout_seconds = secs;
+        self
+    }
+
+    /// Select the isolation backend.
+    pub fn isolation(mut self, isolation: Isolation) -> Self {
+        self.options.isolation = match isolation {
+            Isolation::MicroVm => ExecutionIsolation::Microvm,
+            Isolation::Sandbox => ExecutionIsolation::Sandbox,
+        };
+        self
+    }
+
+    /// Working directory for the box's processes.
+    pub fn workdir(mut self, path: impl Into<String>) -> Self {
+        self.options.workdir = Some(path.into());
+        self
+    }
+
+    /// User the box's processes run as (`name`, `uid`, or `uid:gid`).
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.options.user = Some(user.into());
+        self
+    }
+
+    /// Hostname inside the box.
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.options.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Mount a host directory into the box.
+    pub fn mount(
+        mut self,
+        host_path: impl Into<PathBuf>,
+        guest_path: impl Into<String>,
+        read_only: bool,
+    ) -> Self {
+        self.options
+            .mounts
+            .push(VolumeMount::bind(host_path, guest_path).read_only(read_only));
+        self
+    }
+
+    /// Attach the box to a network.
+    pub fn network(mut self, network: Network) -> Self {
+        self.options.network = match network {
+            Network::Default => SandboxNetwork::Tsi,
+            Network::Disabled => SandboxNetwork::Disabled,
+            Network::Bridge(name) => SandboxNetwork::bridge(name),
+        };
+        self
+    }
+
+    /// Publish a guest TCP port on a host port (`0` picks a free one).
+    pub fn publish_tcp(mut self, host_port: u16, guest_port: u16) -> Self {
+        self.options.ports.push(PortMapping {
+            host_ip: None,
+            host_port,
+            guest_port,
+            protocol: PortProtocol::Tcp,
+        });
+        self
+    }
+
+    /// Mount the root filesystem read-only.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.options.read_only = read_only;
+        self
+    }
+
+    /// Remove the box's record and resources once it stops.
+    pub fn auto_remove(mut self, auto_remove: bool) -> Self {
+        self.options.auto_remove = auto_remove;
+        self
+    }
+
+    /// Pull the image if needed, boot the box, and return its handle.
+    pub async fn start(self) -> Result<BoxHandle> {
+        let sandbox = Sandbox::create_with_client(self.client.clone(), self.options).await?;
+        Ok(BoxHandle::started(self.client, sandbox))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_maps_options() {
+        let builder = BoxBuilder::new("alpine:3.20")
+            .name("agent")
+            .env("MODE", "test")
+            .cpus(2)
+            .memory_mb(512)
+            .timeout(Duration::from_millis(1500))
+            .isolation(Isolation::Sandbox)
+            .network(Network::Bridge("agents".to_string()))
+            .publish_tcp(0, 8080)
+            .mount("/tmp/work", "/workspace", true);
+
+        let options = &builder.options;
+        assert_eq!(options.image, "alpine:3.20");
+        assert_eq!(options.name.as_deref(), Some("agent"));
+        assert_eq!(options.envs.get("MODE").map(String::as_str), Some("test"));
+        assert_eq!(options.cpus, Some(2));
+        assert_eq!(options.memory_mb, Some(512));
+        assert_eq!(options.timeout_seconds, 2);
+        assert_eq!(options.isolation, ExecutionIsolation::Sandbox);
+        assert_eq!(options.network, SandboxNetwork::bridge("agents"));
+        assert_eq!(options.ports[0].guest_port, 8080);
+        assert!(options.mounts[0].read_only);
+    }
+
+    #[test]
+    fn test_builder_defaults_to_microvm_and_default_network() {
+        let builder = BoxBuilder::new("alpine");
+
+        assert_eq!(builder.options.isolation, ExecutionIsolation::Microvm);
+        assert_eq!(builder.options.network, SandboxNetwork::Tsi);
+    }
+}
@@ -0,0 +1,70 @@
+//! Error type for the embedding API.
+
+use a3s_box_sdk::ClientError;
+
+/// Result alias used throughout the crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors returned by the embedding API.
+///
+/// Variants classify the failure; the message carries the detail from the
+/// runtime and is not part of the stable API.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// An option or argument was rejected before reaching the runtime.
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+    /// The box does not exist (or was removed).
+    #[error("box not found: {0}")]
+    NotFound(String),
+    /// The operation needs a running box.
+    #[error("box is not running: {0}")]
+    NotRunning(String),
+    /// The runtime or guest failed the operation.
+    #[error("runtime error: {0}")]
+    Runtime(String),
+}
+
+impl From<ClientError> for Error {
+    fn from(error: ClientError) -> Self {
+        match error {
+            ClientError::Validation(message) if message.contains("is not running") => {
+                Self::NotRunning(message)
+            }
+            ClientError::Validation(message) => Self::InvalidInput(message),
+            ClientError::BoxNotFound(query) => Self::NotFound(query),
+            ClientError::Execution(a3s_box_core::ExecutionManagerError::NotFound(id)) => {
+                Self::NotFound(id.to_string())
+            }
+            other => Self::Runtime(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_errors_map_to_stable_kinds() {
+        assert!(matches!(
+            Error::from(ClientError::Validation("bad name".to_string())),
+            Error::InvalidInput(_)
+        ));
+        assert!(matches!(
+            Error::from(ClientError::Validation(
+                "sandbox abc is not running".to_string()
+            )),
+            Error::NotRunning(_)
+        ));
+        assert!(matches!(
+            Error::from(ClientError::BoxNotFound("abc".to_string())),
+            Error::NotFound(_)
+        ));
+        assert!(matches!(
+            Error::from(ClientError::Guest("exec failed".to_string())),
+            Error::Runtime(_)
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+//! Lifecycle and exec events for a box handle.
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before the oldest are dropped.
+const EVENT_CAPACITY: usize = 256;
+
+/// What happened to a box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EventKind {
+    /// The box booted.
+    Started,
+    /// The box was paused.
+    Paused,
+    /// A paused box resumed.
+    Resumed,
+    /// The box stopped.
+    Stopped,
+    /// The box and its resources were removed.
+    Removed,
+    /// A command started inside the box.
+    ExecStarted {
+        /// The command's argv.
+        command: Vec<String>,
+    },
+    /// A command inside the box finished.
+    ExecFinished {
+        /// The command's argv.
+        command: Vec<String>,
+        /// The command's exit code.
+        exit_code: i32,
+    },
+}
+
+/// One event from a box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// ID of the box the event is about.
+    pub box_id: String,
+    /// What happened.
+    pub kind: EventKind,
+    /// When it happened.
+    pub at: DateTime<Utc>,
+}
+
+/// Receives events from a [`crate::BoxHandle`] and its clones.
+///
+/// A subscriber only sees events emitted after it subscribed. One that falls
+/// more than 256 events behind skips the oldest ones.
+pub struct EventStream {
+    receiver: broadcast::Receiver<Event>,
+}
+
+impl EventStream {
+    /// Wait for the next event; `None` once every handle has been dropped.
+    pub async fn next(&mut self) -> Option<Event> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Return the next event if one is already queued.
+    pub fn try_next(&mut self) -> Option<Event> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("EventStream")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sending side shared by a handle and its clones.
+#[derive(Clone)]
+pub(crate) struct EventSender {
+    box_id: String,
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventSender {
+    pub(crate) fn new(box_id: impl Into<String>) -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            box_id: box_id.into(),
+            sender,
+        }
+    }
+
+    pub(crate) fn emit(&self, kind: EventKind) {
+        let _ = self.sender.send(Event {
+            box_id: self.box_id.clone(),
+            kind,
+            at: Utc::now(),
+        });
+    }
+
+    pub(crate) fn subscribe(&self) -> EventStream {
+        EventStream {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subscribers_see_events_after_subscribing() {
+        let sender = EventSender::new("box-1");
+        sender.emit(EventKind::Started);
+        let mut stream = sender.subscribe();
+
+        sender.emit(EventKind::ExecFinished {
+            command: vec!["true".to_string()],
+            exit_code: 0,
+        });
+        sender.emit(EventKind::Stopped);
+
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.box_id, "box-1");
+        assert!(matches!(
+            event.kind,
+            EventKind::ExecFinished { exit_code: 0, .. }
+        ));
+        assert_eq!(stream.try_next().unwrap().kind, EventKind::Stopped);
+        assert!(stream.try_next().is_none());
+
+        drop(sender);
+        assert!(stream.next().await.is_none());
+    }
+}
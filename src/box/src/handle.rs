@@ -0,0 +1,317 @@
+//! Handle to a running (or stopped) box.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use a3s_box_sdk::{A3sBoxClient, CommandRunOptions, Sandbox, SandboxCommand};
+
+use crate::event::EventSender;
+use crate::{Error, EventKind, EventStream, Result};
+
+/// Options for one command run with [`BoxHandle::exec_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecOptions {
+    env: BTreeMap<String, String>,
+    workdir: Option<String>,
+    user: Option<String>,
+    stdin: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+}
+
+impl ExecOptions {
+    /// Set an environment variable for the command.
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Run the command in `path`.
+    pub fn workdir(mut self, path: impl Into<String>) -> Self {
+        self.workdir = Some(path.into());
+        self
+    }
+
+    /// Run the command as `user`.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Feed `data` to the command's stdin, then close it.
+    pub fn stdin(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.stdin = Some(data.into());
+        self
+    }
+
+    /// Kill the command if it runs longer than `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn into_run_options(self) -> CommandRunOptions {
+        let mut options = CommandRunOptions::default();
+        for (key, value) in self.env {
+            options = options.env(key, value);
+        }
+        if let Some(workdir) = self.workdir {
+            options = options.cwd(workdir);
+        }
+        if let Some(user) = self.user {
+            options = options.user(user);
+        }
+        if let Some(stdin) = self.stdin {
+            options = options.stdin(stdin);
+        }
+        if let Some(timeout) = self.timeout {
+            options = options.timeout(timeout);
+        }
+        options
+    }
+}
+
+/// Captured result of a finished command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecOutput {
+    /// Everything the command wrote to stdout.
+    pub stdout: Vec<u8>,
+    /// Everything the command wrote to stderr.
+    pub stderr: Vec<u8>,
+    /// The command's exit code.
+    pub exit_code: i32,
+    /// Whether output exceeded the capture limit and was cut short.
+    pub truncated: bool,
+}
+
+impl ExecOutput {
+    /// Whether the command exited with status 0.
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    /// stdout as text, replacing invalid UTF-8.
+    pub fn stdout_text(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).into_owned()
+    }
+
+    /// stderr as text, replacing invalid UTF-8.
+    pub fn stderr_text(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).into_owned()
+    }
+}
+
+/// A box started with [`crate::BoxBuilder`] or reattached with
+/// [`BoxHandle::connect`].
+///
+/// Handles are cheap to clone; clones share state and events.
+#[derive(Clone)]
+pub struct BoxHandle {
+    client: A3sBoxClient,
+    sandbox: Sandbox,
+    events: EventSender,
+}
+
+impl std::fmt::Debug for BoxHandle {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("BoxHandle")
+            .field("id", &self.id())
+            .finish_non_exhaustive()
+    }
+}
+
+impl BoxHandle {
+    pub(crate) fn started(client: A3sBoxClient, sandbox: Sandbox) -> Self {
+        let handle = Self::attach(client, sandbox);
+        handle.events.emit(EventKind::Started);
+        handle
+    }
+
+    fn attach(client: A3sBoxClient, sandbox: Sandbox) -> Self {
+        let events = EventSender::new(sandbox.id());
+        Self {
+            client,
+            sandbox,
+            events,
+        }
+    }
+
+    /// Reattach to an existing box by ID.
+    pub async fn connect(id: impl Into<String>) -> Result<Self> {
+        Self::connect_with_client(A3sBoxClient::new(), id).await
+    }
+
+    /// Reattach to an existing box whose state lives under `home`.
+    pub async fn connect_in(home: impl Into<PathBuf>, id: impl Into<String>) -> Result<Self> {
+        Self::connect_with_client(A3sBoxClient::from_home(home), id).await
+    }
+
+    async fn connect_with_client(client: A3sBoxClient, id: impl Into<String>) -> Result<Self> {
+        let sandbox = Sandbox::connect_with_client(client.clone(), id).await?;
+        Ok(Self::attach(client, sandbox))
+    }
+
+    /// The box's ID.
+    pub fn id(&self) -> &str {
+        self.sandbox.id()
+    }
+
+    /// Whether the box is currently running.
+    pub async fn is_running(&self) -> Result<bool> {
+        Ok(self.sandbox.is_running().await?)
+    }
+
+    /// Subscribe to this box's events from now on.
+    pub fn events(&self) -> EventStream {
+        self.events.subscribe()
+    }
+
+    /// Run `command` (an argv) to completion and capture its output.
+    pub async fn exec<I, S>(&self, command: I) -> Result<ExecOutput>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exec_with(command, ExecOptions::default()).await
+    }
+
+    /// Run `command` with explicit options.
+    pub async fn exec_with<I, S>(&self, command: I, options: ExecOptions) -> Result<ExecOutput>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let command = argv(command)?;
+        self.events.emit(EventKind::ExecStarted {
+            command: command.clone(),
+        });
+        let result = self
+            .sandbox
+            .commands
+            .run_with_options(
+                SandboxCommand::argv(command.clone()),
+                options.into_run_options(),
+            )
+            .await?;
+        self.events.emit(EventKind::ExecFinished {
+            command,
+            exit_code: result.exit_code,
+        });
+        Ok(ExecOutput {
+            stdout: result.stdout_bytes().to_vec(),
+            stderr: result.stderr_bytes().to_vec(),
+            exit_code: result.exit_code,
+            truncated: result.truncated,
+        })
+    }
+
+    /// Start `command` (an argv) as an interactive session with streaming
+    /// stdin and output.
+    #[cfg(unix)]
+    pub async fn session<I, S>(&self, command: I) -> Result<crate::SessionHandle>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let command = argv(command)?;
+        let exec = self.client.exec_client(self.id()).await?;
+        crate::session::SessionHandle::start(&exec, command, self.events.clone()).await
+    }
+
+    /// Read a file from the box.
+    pub async fn read_file(&self, path: impl Into<String>) -> Result<Vec<u8>> {
+        Ok(self.sandbox.files.read(path).await?)
+    }
+
+    /// Write a file in the box, creating or replacing it.
+    pub async fn write_file(&self, path: impl Into<String>, data: impl AsRef<[u8]>) -> Result<()> {
+        self.sandbox.files.write(path, data).await?;
+        Ok(())
+    }
+
+    /// Pause the box; `keep_memory` keeps guest memory resident.
+    pub async fn pause(&self, keep_memory: bool) -> Result<()> {
+        self.sandbox.pause(keep_memory).await?;
+        self.events.emit(EventKind::Paused);
+        Ok(())
+    }
+
+    /// Resume a paused box.
+    pub async fn resume(&self) -> Result<()> {
+        self.sandbox.resume().await?;
+        self.events.emit(EventKind::Resumed);
+        Ok(())
+    }
+
+    /// Stop the box, keeping its record so it can be inspected or removed.
+    pub async fn stop(&self) -> Result<()> {
+        self.sandbox.stop().await?;
+        self.events.emit(EventKind::Stopped);
+        Ok(())
+    }
+
+    /// Stop the box if needed and remove it with its resources.
+    pub async fn remove(&self) -> Result<()> {
+        self.sandbox.kill().await?;
+        self.events.emit(EventKind::Removed);
+        Ok(())
+    }
+}
+
+fn argv<I, S>(command: I) -> Result<Vec<String>>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let command: Vec<String> = command.into_iter().map(Into::into).collect();
+    if command.is_empty() {
+        return Err(Error::InvalidInput("command must not be empty".to_string()));
+    }
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec_options_map_to_run_options() {
+        let options = ExecOptions::default()
+            .env("A", "1")
+            .workdir("/srv")
+            .user("1000")
+            .stdin("input")
+            .timeout(Duration::from_secs(5))
+            .into_run_options();
+
+        assert_eq!(options.envs.get("A").map(String::as_str), Some("1"));
+        assert_eq!(options.cwd.as_deref(), Some("/srv"));
+        assert_eq!(options.user.as_deref(), Some("1000"));
+        assert_eq!(options.stdin.as_deref(), Some(&b"input"[..]));
+        assert_eq!(options.timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_argv_rejects_empty_command() {
+        assert!(matches!(
+            argv(Vec::<String>::new()),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(argv(["ls", "-la"]).unwrap(), vec!["ls", "-la"]);
+    }
+
+    #[test]
+    fn test_exec_output_text_helpers() {
+        let output = ExecOutput {
+            stdout: b"hi\n".to_vec(),
+            stderr: vec![0xff],
+            exit_code: 0,
+            truncated: false,
+        };
+
+        assert!(output.success());
+        assert_eq!(output.stdout_text(), "hi\n");
+        assert_eq!(output.stderr_text(), "\u{fffd}");
+    }
+}
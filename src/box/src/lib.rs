@@ -0,0 +1,45 @@
+//! a3s-box — embed hardware-isolated sandboxes in Rust applications.
+//!
+//! This crate is the semver-stable embedding surface. It wraps the runtime
+//! and SDK crates behind a small set of owned types so applications are not
+//! exposed to their internal module layout:
+//!
+//! - [`BoxBuilder`] configures and starts a box from an OCI image.
+//! - [`BoxHandle`] runs commands, moves files, and drives the lifecycle.
+//! - [`SessionHandle`] streams one interactive command (stdin in, output out).
+//! - [`EventStream`] reports lifecycle and exec events for a handle.
+//!
+//! Types here change only in semver-compatible ways: enums are
+//! `#[non_exhaustive]` and new options arrive as builder methods. Code that
+//! needs lower-level management APIs can depend on `a3s-box-sdk` directly.
+//!
+//! ```rust,no_run
+//! use a3s_box::BoxBuilder;
+//!
+//! # async fn example() -> a3s_box::Result<()> {
+//! let sandbox = BoxBuilder::new("python:3.12-alpine")
+//!     .cpus(2)
+//!     .memory_mb(1024)
+//!     .start()
+//!     .await?;
+//! let output = sandbox.exec(["python", "-c", "print(6 * 7)"]).await?;
+//! assert_eq!(output.stdout_text().trim(), "42");
+//! sandbox.remove().await?;
+//! # Ok(()) }
+//! ```
+
+#![deny(missing_docs)]
+
+mod builder;
+mod error;
+mod event;
+mod handle;
+#[cfg(unix)]
+mod session;
+
+pub use builder::{BoxBuilder, Isolation, Network};
+pub use error::{Error, Result};
+pub use event::{Event, EventKind, EventStream};
+pub use handle::{BoxHandle, ExecOptions, ExecOutput};
+#[cfg(unix)]
+pub use session::{SessionHandle, SessionOutput};
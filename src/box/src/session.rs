@@ -0,0 +1,135 @@
+//! Interactive command sessions.
+
+use a3s_box_core::exec::{ExecEvent, ExecRequest, StreamType};
+use a3s_box_sdk::{ExecClient, StreamingExec, StreamingExecInput};
+
+use crate::event::EventSender;
+use crate::{Error, EventKind, Result};
+
+/// Output from a running session, in the order the box produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SessionOutput {
+    /// Bytes written to stdout.
+    Stdout(Vec<u8>),
+    /// Bytes written to stderr.
+    Stderr(Vec<u8>),
+    /// The command exited with this code; no more output follows.
+    Exit(i32),
+}
+
+/// A command running inside a box with streaming stdin and output.
+///
+/// Started with [`crate::BoxHandle::session`].
+pub struct SessionHandle {
+    stream: StreamingExec,
+    input: StreamingExecInput,
+    command: Vec<String>,
+    events: EventSender,
+}
+
+impl std::fmt::Debug for SessionHandle {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("SessionHandle")
+            .field("command", &self.command)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionHandle {
+    pub(crate) async fn start(
+        exec: &ExecClient,
+        command: Vec<String>,
+        events: EventSender,
+    ) -> Result<Self> {
+        let request = ExecRequest {
+            request_id: None,
+            cmd: command.clone(),
+            timeout_ns: 0,
+            env: vec![],
+            working_dir: None,
+            rootfs: None,
+            stdin: None,
+            stdin_streaming: true,
+            user: None,
+            streaming: true,
+        };
+        let stream = exec
+            .exec_stream(&request)
+            .await
+            .map_err(|e| Error::Runtime(e.to_string()))?;
+        events.emit(EventKind::ExecStarted {
+            command: command.clone(),
+        });
+        Ok(Self {
+            input: stream.input(),
+            stream,
+            command,
+            events,
+        })
+    }
+
+    /// Send bytes to the command's stdin.
+    pub async fn write_stdin(&self, data: &[u8]) -> Result<()> {
+        self.input
+            .write_stdin(data)
+            .await
+            .map_err(|e| Error::Runtime(e.to_string()))
+    }
+
+    /// Close the command's stdin; the command keeps running.
+    pub async fn close_stdin(&self) -> Result<()> {
+        self.input
+            .close_stdin()
+            .await
+            .map_err(|e| Error::Runtime(e.to_string()))
+    }
+
+    /// Ask the box to kill the command. Output up to the exit still arrives.
+    pub async fn cancel(&self) -> Result<()> {
+        self.input
+            .cancel()
+            .await
+            .map_err(|e| Error::Runtime(e.to_string()))
+    }
+
+    /// Wait for the next piece of output; `None` after [`SessionOutput::Exit`].
+    pub async fn next_output(&mut self) -> Result<Option<SessionOutput>> {
+        loop {
+            let event = self
+                .stream
+                .next_event()
+                .await
+                .map_err(|e| Error::Runtime(e.to_string()))?;
+            let output = match event {
+                None => return Ok(None),
+                Some(ExecEvent::FlushAck) => continue,
+                Some(ExecEvent::Chunk(chunk)) => match chunk.stream {
+                    StreamType::Stdout => SessionOutput::Stdout(chunk.data),
+                    StreamType::Stderr => SessionOutput::Stderr(chunk.data),
+                },
+                Some(ExecEvent::Exit(exit)) => {
+                    self.events.emit(EventKind::ExecFinished {
+                        command: self.command.clone(),
+                        exit_code: exit.exit_code,
+                    });
+                    SessionOutput::Exit(exit.exit_code)
+                }
+            };
+            return Ok(Some(output));
+        }
+    }
+
+    /// Discard remaining output and return the exit code.
+    pub async fn wait(mut self) -> Result<i32> {
+        while let Some(output) = self.next_output().await? {
+            if let SessionOutput::Exit(code) = output {
+                return Ok(code);
+            }
+        }
+        Err(Error::Runtime(
+            "session ended without an exit status".to_string(),
+        ))
+    }
+}
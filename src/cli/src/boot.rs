@@ -25,6 +25,8 @@ pub struct BootResult {
     pub stop_signal: Option<String>,
     /// Anonymous volumes present after boot.
     pub anonymous_volumes: Vec<String>,
+    /// Published ports as booted, with auto-assigned host ports resolved.
+    pub port_map: Vec<String>,
 }
 
 /// How a successful boot should update the restart counter.
@@ -72,7 +74,7 @@ impl BootResourceGuard {
     }
 }
 
-fn boot_network_name(record: &BoxRecord) -> Option<&str> {
+pub(crate) fn boot_network_name(record: &BoxRecord) -> Option<&str> {
     record
         .network_name
         .as_deref()
@@ -108,6 +110,8 @@ pub fn apply_boot_result(
     record.started_at = Some(chrono::Utc::now());
     record.stopped_by_user = false;
     record.exit_code = None;
+    record.labels.remove(crate::status::WAITING_ON_LABEL);
+    record.port_map = result.port_map;
 
     for volume_name in result.anonymous_volumes {
         if !record
@@ -156,7 +160,7 @@ fn select_locked_boot_record(record: Option<&BoxRecord>) -> LockedBootRecord {
     }
     if !matches!(
         record.status.as_str(),
-        "created" | "stopped" | "dead" | "failed" | "running"
+        "created" | "stopped" | "dead" | "waiting" | "failed" | "running"
     ) || record.managed_execution.is_some()
     {
         return LockedBootRecord::Conflict(format!(
@@ -266,11 +270,11 @@ fn ensure_network_connected_with_store(
         if network.endpoints.contains_key(&record.id) {
             return Ok(());
         }
-        network.connect(&record.id, &record.name).map_err(
-            |error| -> Box<dyn std::error::Error> {
+        network
+            .connect_with_aliases(&record.id, &record.name, &record.network_aliases)
+            .map_err(|error| -> Box<dyn std::error::Error> {
                 format!("Failed to connect to network: {error}").into()
-            },
-        )?;
+            })?;
         Ok(())
     })
 }
@@ -285,6 +289,7 @@ pub async fn boot_from_record(
     let config =
         config_from_record(record).map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
     a3s_box_core::resolve_execution(&config)?;
+    let port_map = config.port_map.clone();
     let emitter = EventEmitter::new(256);
     let mut vm = VmManager::with_box_id(config, emitter, record.id.clone());
     vm.set_healthcheck_disabled(record.healthcheck_disabled);
@@ -301,6 +306,9 @@ pub async fn boot_from_record(
         return Err(error.into());
     }
     resource_guard.disarm();
+    if let Some(network_name) = boot_network_name(record) {
+        crate::commands::network::refresh_network_peers(network_name, &record.id).await;
+    }
 
     // Create rootfs baseline snapshot for `diff` command (best-effort).
     if let Err(error) = crate::commands::diff::create_box_baseline_snapshot(&record.box_dir) {
@@ -344,6 +352,7 @@ pub async fn boot_from_record(
         health_check,
         stop_signal,
         anonymous_volumes,
+        port_map,
     })
 }
 
@@ -388,6 +397,15 @@ fn config_from_record(record: &BoxRecord) -> Result<BoxConfig, String> {
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect(),
         port_map,
+        dns: record.dns.clone(),
+        dns_search: record.dns_search.clone(),
+        dns_options: record.dns_option.clone(),
+        network_aliases: record.network_aliases.clone(),
+        network_policy: record.network_policy.clone(),
+        network_bandwidth: record.network_bandwidth,
+        network_max_conns: record.network_max_conns,
+        http_proxy: record.http_proxy,
+        socket_mounts: record.socket_mounts.clone(),
         add_hosts: record.add_host.clone(),
         network: record.network_mode.clone(),
         tmpfs,
@@ -397,6 +415,7 @@ fn config_from_record(record: &BoxRecord) -> Result<BoxConfig, String> {
         cap_drop: record.cap_drop.clone(),
         security_opt: record.security_opt.clone(),
         privileged: record.privileged,
+        workspace_template: record.workspace_template.clone(),
         // Retained records are Docker-style stopped containers: their writable
         // rootfs must survive a failed or successful stop/start cycle. Records
         // created with --rm have no restartable filesystem contract.
@@ -467,6 +486,15 @@ mod tests {
             resource_limits: a3s_box_core::config::ResourceLimits::default(),
             log_config: a3s_box_core::log::LogConfig::default(),
             add_host: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            dns_option: vec![],
+            network_aliases: vec![],
+            network_policy: vec![],
+            network_bandwidth: None,
+            network_max_conns: None,
+            http_proxy: None,
+            socket_mounts: vec![],
             platform: None,
             init: false,
             read_only: false,
@@ -481,6 +509,8 @@ mod tests {
             stop_timeout: None,
             oom_kill_disable: false,
             oom_score_adj: None,
+            workspace_template: None,
+            metadata: Default::default(),
         }
     }
 
@@ -657,6 +687,20 @@ mod tests {
         assert_eq!(config.add_hosts, vec!["db.local:10.88.0.10"]);
     }
 
+    #[test]
+    fn test_config_from_record_dns_settings() {
+        let mut record = sample_record();
+        record.dns = vec!["10.0.0.2".to_string()];
+        record.dns_search = vec!["corp.example".to_string()];
+        record.dns_option = vec!["ndots:2".to_string()];
+
+        let config = config_from_record(&record).unwrap();
+
+        assert_eq!(config.dns, vec!["10.0.0.2"]);
+        assert_eq!(config.dns_search, vec!["corp.example"]);
+        assert_eq!(config.dns_options, vec!["ndots:2"]);
+    }
+
     #[test]
     fn test_config_from_record_rejects_invalid_add_host() {
         let mut record = sample_record();
@@ -745,13 +789,14 @@ mod tests {
         record.network_mode = a3s_box_core::NetworkMode::Bridge {
             network: "dev".to_string(),
         };
+        record.network_aliases = vec!["api".to_string()];
 
         ensure_network_connected_with_store(&network_store, &record, "dev").unwrap();
         ensure_network_connected_with_store(&network_store, &record, "dev").unwrap();
 
         let network = network_store.get("dev").unwrap().unwrap();
         assert_eq!(network.endpoints.len(), 1);
-        assert!(network.endpoints.contains_key(&record.id));
+        assert_eq!(network.endpoints[&record.id].aliases, vec!["api"]);
     }
 
     fn sample_boot_result() -> BootResult {
@@ -768,6 +813,7 @@ mod tests {
             }),
             stop_signal: Some("SIGINT".to_string()),
             anonymous_volumes: vec!["old-anon".to_string(), "new-anon".to_string()],
+            port_map: vec!["18080:80".to_string()],
         }
     }
 
@@ -800,6 +846,7 @@ mod tests {
             record.anonymous_volumes,
             vec!["old-anon".to_string(), "new-anon".to_string()]
         );
+        assert_eq!(record.port_map, vec!["18080:80".to_string()]);
     }
 
     #[test]
@@ -0,0 +1,186 @@
+//! `a3s-box annotate` command — Annotate a box and keep notes on it.
+//!
+//! Annotations and notes are for the people juggling many boxes: which
+//! experiment a box belongs to, who owns it, what was tried. They never
+//! affect how the box runs, can change at any time (unlike labels), and show
+//! up in `inspect` under `metadata` next to the command line that created
+//! the box.
+
+use clap::Args;
+
+use crate::resolve;
+use crate::state::StateFile;
+use a3s_box_runtime::{BoxMetadata, BoxNote};
+
+#[derive(Args)]
+pub struct AnnotateArgs {
+    /// Box name or ID
+    pub r#box: String,
+
+    /// Annotations to set (KEY=VALUE) or remove (KEY-); with no changes,
+    /// print the box's annotations and notes
+    #[arg(value_name = "KEY=VALUE|KEY-")]
+    pub annotations: Vec<String>,
+
+    /// Append a note, can be repeated
+    #[arg(long = "note", value_name = "TEXT")]
+    pub notes: Vec<String>,
+
+    /// Delete the existing notes (before appending any --note)
+    #[arg(long)]
+    pub clear_notes: bool,
+}
+
+/// One positional change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    Set(String, String),
+    Remove(String),
+}
+
+pub async fn execute(args: AnnotateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let changes = args
+        .annotations
+        .iter()
+        .map(|spec| parse_change(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if changes.is_empty() && args.notes.is_empty() && !args.clear_notes {
+        let state = StateFile::load_default()?;
+        let record = resolve::resolve(&state, &args.r#box)?;
+        print!("{}", render(&record.metadata));
+        return Ok(());
+    }
+
+    let name = StateFile::modify(|state| -> Result<String, Box<dyn std::error::Error>> {
+        let record = resolve::resolve_mut(state, &args.r#box)?;
+        apply(
+            &mut record.metadata,
+            &changes,
+            &args.notes,
+            args.clear_notes,
+            chrono::Utc::now(),
+        );
+        Ok(record.name.clone())
+    })?;
+    println!("{name}");
+    Ok(())
+}
+
+fn parse_change(spec: &str) -> Result<Change, String> {
+    if let Some((key, value)) = spec.split_once('=') {
+        if !key.is_empty() {
+            return Ok(Change::Set(key.to_string(), value.to_string()));
+        }
+    } else if let Some(key) = spec.strip_suffix('-').filter(|key| !key.is_empty()) {
+        return Ok(Change::Remove(key.to_string()));
+    }
+    Err(format!(
+        "Invalid annotation '{spec}': expected KEY=VALUE to set or KEY- to remove"
+    ))
+}
+
+fn apply(
+    metadata: &mut BoxMetadata,
+    changes: &[Change],
+    notes: &[String],
+    clear_notes: bool,
+    now: chrono::DateTime<chrono::Utc>,
+) {
+    for change in changes {
+        match change {
+            Change::Set(key, value) => {
+                metadata.annotations.insert(key.clone(), value.clone());
+            }
+            Change::Remove(key) => {
+                metadata.annotations.remove(key);
+            }
+        }
+    }
+    if clear_notes {
+        metadata.notes.clear();
+    }
+    metadata.notes.extend(notes.iter().map(|text| BoxNote {
+        added_at: now,
+        text: text.clone(),
+    }));
+}
+
+fn render(metadata: &BoxMetadata) -> String {
+    let mut out = String::new();
+    if let Some(args) = &metadata.created_by {
+        out.push_str(&format!("Created by: {}\n", args.join(" ")));
+    }
+    if !metadata.annotations.is_empty() {
+        out.push_str("Annotations:\n");
+        for (key, value) in &metadata.annotations {
+            out.push_str(&format!("  {key}={value}\n"));
+        }
+    }
+    if !metadata.notes.is_empty() {
+        out.push_str("Notes:\n");
+        for note in &metadata.notes {
+            let added = note.added_at.format("%Y-%m-%d %H:%M");
+            out.push_str(&format!("  {added}  {}\n", note.text));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_change() {
+        assert_eq!(
+            parse_change("owner=alice").unwrap(),
+            Change::Set("owner".into(), "alice".into())
+        );
+        assert_eq!(
+            parse_change("cmd=a=b").unwrap(),
+            Change::Set("cmd".into(), "a=b".into())
+        );
+        assert_eq!(
+            parse_change("stage-").unwrap(),
+            Change::Remove("stage".into())
+        );
+        for bad in ["owner", "=x", "-", ""] {
+            assert!(parse_change(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_apply_and_render() {
+        let now = chrono::Utc.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap();
+        let mut metadata = BoxMetadata {
+            created_by: Some(vec!["a3s-box".into(), "run".into(), "alpine".into()]),
+            ..Default::default()
+        };
+        metadata.annotations.insert("stage".into(), "draft".into());
+        metadata.notes.push(BoxNote {
+            added_at: now,
+            text: "old".into(),
+        });
+
+        apply(
+            &mut metadata,
+            &[
+                Change::Set("owner".into(), "alice".into()),
+                Change::Remove("stage".into()),
+            ],
+            &["lr=3e-4 diverged".to_string()],
+            true,
+            now,
+        );
+
+        assert_eq!(
+            render(&metadata),
+            "Created by: a3s-box run alpine\n\
+             Annotations:\n  owner=alice\n\
+             Notes:\n  2026-03-01 09:30  lr=3e-4 diverged\n"
+        );
+        assert_eq!(render(&BoxMetadata::default()), "");
+    }
+}
@@ -1,12 +1,14 @@
 //! `a3s-box attach` command — attach to a running box.
 //!
-//! Without `-it`, tails the console log (read-only, original behavior).
+//! Without `-it`, tails the console log; Ctrl-C follows the shared
+//! [`crate::interrupt`] bindings (interrupt, detach, kill).
 //! With `-it`, opens an interactive PTY session to a shell inside the box.
 
 use clap::Args;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 
+use crate::interrupt::{Interrupt, InterruptRouter};
 use crate::resolve;
 use crate::state::{BoxRecord, StateFile};
 
@@ -28,6 +30,11 @@ pub struct AttachArgs {
     /// Allocate a pseudo-TTY
     #[arg(short = 't', long = "tty")]
     pub tty: bool,
+
+    /// Key sequence that detaches from `-it` and leaves the box running
+    /// (default ctrl-p,ctrl-q; empty disables)
+    #[arg(long, value_name = "KEYS")]
+    pub detach_keys: Option<String>,
 }
 
 pub async fn execute(args: AttachArgs) -> Result<(), Box<dyn std::error::Error>> {
@@ -39,7 +46,7 @@ pub async fn execute(args: AttachArgs) -> Result<(), Box<dyn std::error::Error>>
     // Interactive PTY mode
     if args.tty {
         #[cfg(not(windows))]
-        return execute_pty_attach(&record).await;
+        return execute_pty_attach(&record, args.detach_keys.as_deref()).await;
         #[cfg(windows)]
         return Err(crate::platform::unsupported_command(
             "attach -it",
@@ -52,7 +59,7 @@ pub async fn execute(args: AttachArgs) -> Result<(), Box<dyn std::error::Error>>
         return Err(missing_console_log_message(&record.name, &streams.stdout).into());
     }
 
-    println!("Attached to box {}. Press Ctrl-C to detach.", record.name);
+    println!("Attached to box {}. {}.", record.name, Interrupt::help());
 
     let runtime_filter = streams
         .filter_runtime_noise
@@ -89,9 +96,21 @@ pub async fn execute(args: AttachArgs) -> Result<(), Box<dyn std::error::Error>>
         );
     });
 
-    let end_reason = tokio::select! {
-        _ = tokio::signal::ctrl_c() => AttachEndReason::UserDetached,
-        _ = wait_for_attached_box_exit(&record) => AttachEndReason::BoxExited,
+    let mut interrupts = InterruptRouter::new();
+    let exec_socket = crate::socket_paths::exec(&record);
+    let end_reason = loop {
+        tokio::select! {
+            action = interrupts.next() => match action {
+                Interrupt::Interrupt => {
+                    if !crate::process::deliver_signal_via_guest(&exec_socket, libc::SIGINT).await {
+                        eprintln!("\nCould not deliver SIGINT to box {}.", record.name);
+                    }
+                }
+                Interrupt::Detach => break AttachEndReason::UserDetached,
+                Interrupt::Kill => break AttachEndReason::Killed,
+            },
+            _ = wait_for_attached_box_exit(&record) => break AttachEndReason::BoxExited,
+        }
     };
 
     if end_reason == AttachEndReason::BoxExited {
@@ -124,6 +143,14 @@ pub async fn execute(args: AttachArgs) -> Result<(), Box<dyn std::error::Error>>
     match end_reason {
         AttachEndReason::UserDetached => println!("\nDetached from box {}.", record.name),
         AttachEndReason::BoxExited => println!("\nBox {} exited.", record.name),
+        AttachEndReason::Killed => {
+            println!();
+            super::kill::execute(super::kill::KillArgs {
+                boxes: vec![record.id.clone()],
+                signal: "KILL".to_string(),
+            })
+            .await?;
+        }
     }
 
     Ok(())
@@ -133,6 +160,7 @@ pub async fn execute(args: AttachArgs) -> Result<(), Box<dyn std::error::Error>>
 enum AttachEndReason {
     UserDetached,
     BoxExited,
+    Killed,
 }
 
 async fn wait_for_attached_box_exit(record: &BoxRecord) {
@@ -256,13 +284,21 @@ fn missing_console_log_message(name: &str, console_log: &std::path::Path) -> Str
     )
 }
 
-/// Attach to a running box with an interactive PTY session.
+/// Attach to a running box with an interactive PTY session: the main
+/// process of a box started with `run -it` if it is detached, otherwise a
+/// new shell.
 #[cfg(not(windows))]
 async fn execute_pty_attach(
     record: &crate::state::BoxRecord,
+    detach_keys: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    use super::exec::PtySessionEnd;
     use crate::terminal;
-    use a3s_box_core::pty::PtyRequest;
+    use a3s_box_core::pty::{PtyRequest, MAIN_PTY_SESSION};
+
+    let detach_keys =
+        crate::detach_keys::parse(detach_keys.unwrap_or(crate::detach_keys::DEFAULT_DETACH_KEYS))
+            .map_err(|e| format!("--detach-keys: {e}"))?;
 
     let pty_socket_path = crate::socket_paths::require_runtime_socket(
         record,
@@ -276,7 +312,7 @@ async fn execute_pty_attach(
         super::exec::connect_pty_with_retry(&pty_socket_path, std::time::Duration::from_secs(10))
             .await?;
 
-    // Attach opens a shell
+    // Resume the detached `run -it` process, or open a shell
     let request = PtyRequest {
         cmd: vec!["/bin/sh".to_string()],
         env: vec![],
@@ -285,13 +321,22 @@ async fn execute_pty_attach(
         user: None,
         cols,
         rows,
+        session: Some(MAIN_PTY_SESSION.to_string()),
+        resume: true,
     };
     client.send_request(&request).await?;
 
     let (read_half, write_half) = client.into_split();
-    let exit_code = {
+    let end = {
         let _raw_mode = terminal::raw_mode()?;
-        super::exec::run_pty_session(read_half, write_half).await
+        super::exec::run_detachable_pty_session(read_half, write_half, detach_keys, true).await
+    };
+    let exit_code = match end {
+        PtySessionEnd::Exited(exit_code) => exit_code,
+        PtySessionEnd::Detached => {
+            println!("\nDetached from box {}.", record.name);
+            return Ok(());
+        }
     };
 
     if exit_code != 0 {
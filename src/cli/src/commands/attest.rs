@@ -2,9 +2,10 @@
 //!
 //! Connects to a running box's agent socket, requests a hardware-signed
 //! SNP attestation report, optionally verifies it against a policy, and
-//! outputs the result as JSON.
+//! outputs the result as JSON. `attest verify <box>` challenges the box with
+//! a fresh nonce and prints a pass/fail line for every check the policy makes.
 
-use clap::Args;
+use clap::{Args, Subcommand};
 use std::path::PathBuf;
 
 #[cfg(not(windows))]
@@ -13,15 +14,23 @@ use crate::resolve;
 use crate::state::StateFile;
 
 #[cfg(not(windows))]
-use a3s_box_runtime::{verify_attestation, AttestationPolicy, RaTlsAttestationClient};
+use a3s_box_runtime::{
+    verify_attestation, AmdKdsClient, AttestationPolicy, RaTlsAttestationClient,
+};
 
 #[derive(Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct AttestArgs {
+    #[command(subcommand)]
+    pub command: Option<AttestCommand>,
+
     /// Box name or ID
-    pub r#box: String,
+    #[arg(required = true)]
+    pub r#box: Option<String>,
 
-    /// Path to attestation policy JSON file.
-    /// If not provided, a default policy (require_no_debug=true) is used.
+    /// Path to an attestation policy file (YAML or JSON). If not provided,
+    /// the box's `--attestation-policy` is used, falling back to a default
+    /// policy (require_no_debug=true).
     #[arg(long, short)]
     pub policy: Option<PathBuf>,
 
@@ -37,6 +46,15 @@ pub struct AttestArgs {
     #[arg(long)]
     pub allow_simulated: bool,
 
+    /// Never contact AMD KDS: if the report carries no certificates, use
+    /// only the local cache (see `a3s-box tee import-certs`).
+    #[arg(long)]
+    pub offline: bool,
+
+    /// EPYC product line for fetching missing certificates: milan or genoa
+    #[arg(long, default_value = "milan")]
+    pub product: String,
+
     /// Use RA-TLS for attestation verification (recommended).
     /// Verifies the TEE during the TLS handshake instead of fetching a raw report.
     #[arg(long)]
@@ -47,6 +65,43 @@ pub struct AttestArgs {
     pub quiet: bool,
 }
 
+/// Attest subcommands.
+#[derive(Subcommand)]
+pub enum AttestCommand {
+    /// Challenge a box and report each policy check as pass or fail
+    Verify(AttestVerifyArgs),
+    /// Fetch AMD certificates for offline verification (same as
+    /// `tee fetch-certs`)
+    FetchCerts(crate::commands::tee::FetchCertsArgs),
+}
+
+#[derive(Args)]
+pub struct AttestVerifyArgs {
+    /// Box name or ID
+    pub r#box: String,
+
+    /// Attestation policy file (YAML or JSON). Defaults to the box's
+    /// `--attestation-policy`, then to the default policy.
+    #[arg(long, short)]
+    pub policy: Option<PathBuf>,
+
+    /// Accept a simulated (non-hardware) report
+    #[arg(long)]
+    pub allow_simulated: bool,
+
+    /// Never contact AMD KDS; use only cached certificates
+    #[arg(long)]
+    pub offline: bool,
+
+    /// EPYC product line for fetching missing certificates: milan or genoa
+    #[arg(long, default_value = "milan")]
+    pub product: String,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
 /// JSON output for the attest command.
 #[cfg(not(windows))]
 #[derive(serde::Serialize)]
@@ -81,8 +136,19 @@ pub async fn execute(_args: AttestArgs) -> Result<(), Box<dyn std::error::Error>
 
 #[cfg(not(windows))]
 pub async fn execute(args: AttestArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match args.command {
+        Some(AttestCommand::Verify(args)) => return execute_verify(args).await,
+        Some(AttestCommand::FetchCerts(args)) => {
+            return crate::commands::tee::execute_fetch_certs(args).await
+        }
+        None => {}
+    }
+    let box_ref = args
+        .r#box
+        .as_deref()
+        .ok_or("a box name or ID is required")?;
     let state = StateFile::load_default()?;
-    let record = resolve::resolve(&state, &args.r#box)?;
+    let record = resolve::resolve(&state, box_ref)?;
 
     // Generate or parse nonce
     let nonce_bytes = match &args.nonce {
@@ -99,15 +165,7 @@ pub async fn execute(args: AttestArgs) -> Result<(), Box<dyn std::error::Error>>
 
     // RA-TLS mode: verify attestation via TLS handshake
     if args.ratls {
-        let policy = match &args.policy {
-            Some(path) => {
-                let data = std::fs::read_to_string(path)
-                    .map_err(|e| format!("Failed to read policy file {}: {}", path.display(), e))?;
-                serde_json::from_str::<AttestationPolicy>(&data)
-                    .map_err(|e| format!("Failed to parse policy file {}: {}", path.display(), e))?
-            }
-            None => AttestationPolicy::default(),
-        };
+        let policy = resolve_policy(args.policy.as_deref(), &record.box_dir)?;
 
         let client = RaTlsAttestationClient::new(socket_path);
         let result = client.verify(policy, args.allow_simulated).await?;
@@ -146,7 +204,7 @@ pub async fn execute(args: AttestArgs) -> Result<(), Box<dyn std::error::Error>>
     // attestation server speaks RA-TLS + framed messages (not plain HTTP) and
     // carries the signed report in its TLS certificate.
     let client = RaTlsAttestationClient::new(socket_path);
-    let report = client.fetch_report(args.allow_simulated).await?;
+    let mut report = client.fetch_report(args.allow_simulated).await?;
 
     // Under RA-TLS the report's nonce is bound to the server's TLS public key,
     // so verification and output use that embedded nonce.
@@ -172,15 +230,16 @@ pub async fn execute(args: AttestArgs) -> Result<(), Box<dyn std::error::Error>>
     }
 
     // Load or create verification policy
-    let policy = match &args.policy {
-        Some(path) => {
-            let data = std::fs::read_to_string(path)
-                .map_err(|e| format!("Failed to read policy file {}: {}", path.display(), e))?;
-            serde_json::from_str::<AttestationPolicy>(&data)
-                .map_err(|e| format!("Failed to parse policy file {}: {}", path.display(), e))?
-        }
-        None => AttestationPolicy::default(),
-    };
+    let policy = resolve_policy(args.policy.as_deref(), &record.box_dir)?;
+
+    // Hosts without extended-report support hand the guest only the bare
+    // report; take the certificates from the KDS cache instead.
+    let mut kds = AmdKdsClient::new(Some(AmdKdsClient::default_cache_dir()));
+    if args.offline {
+        kds = kds.offline();
+    }
+    kds.complete_report(&mut report, AmdKdsClient::product_name(&args.product))
+        .await?;
 
     // Verify the report
     let result = verify_attestation(&report, &report_nonce, &policy, args.allow_simulated)?;
@@ -218,6 +277,161 @@ pub async fn execute(args: AttestArgs) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+#[cfg(not(windows))]
+async fn execute_verify(args: AttestVerifyArgs) -> Result<(), Box<dyn std::error::Error>> {
+    use a3s_box_runtime::tee::BOX_ATTESTATION_POLICY_FILE;
+
+    let state = StateFile::load_default()?;
+    let record = resolve::resolve(&state, &args.r#box)?;
+    let socket_path = crate::socket_paths::require_runtime_socket(
+        record,
+        crate::socket_paths::RuntimeSocket::Attest,
+    )?;
+    let policy = resolve_policy(args.policy.as_deref(), &record.box_dir)?;
+    let policy_source = match &args.policy {
+        Some(path) => path.display().to_string(),
+        None if record.box_dir.join(BOX_ATTESTATION_POLICY_FILE).is_file() => {
+            "box (--attestation-policy)".to_string()
+        }
+        None => "default".to_string(),
+    };
+
+    let client = RaTlsAttestationClient::new(&socket_path);
+    let mut challenge = client.challenge(args.allow_simulated).await?;
+    let mut kds = AmdKdsClient::new(Some(AmdKdsClient::default_cache_dir()));
+    if args.offline {
+        kds = kds.offline();
+    }
+    kds.complete_report(
+        &mut challenge.report,
+        AmdKdsClient::product_name(&args.product),
+    )
+    .await?;
+    let result = a3s_box_runtime::verify_attestation_with_time(
+        &challenge.report,
+        &challenge.nonce,
+        &policy,
+        args.allow_simulated,
+        Some(challenge.issued_at),
+    )?;
+    let simulated = a3s_box_runtime::tee::is_simulated_report(&challenge.report.report);
+    let checks = check_outcomes(&result, &policy, simulated);
+
+    if args.json {
+        let output = serde_json::json!({
+            "box_id": record.id,
+            "box_name": record.name,
+            "policy": policy_source,
+            "simulated": simulated,
+            "verified": result.verified,
+            "checks": checks,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        println!("Box:       {}", record.name);
+        println!("Policy:    {policy_source}");
+        println!(
+            "Report:    {}",
+            if simulated { "simulated" } else { "hardware" }
+        );
+        for check in &checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            match &check.detail {
+                Some(detail) => println!("  {status}  {:<20} {detail}", check.check),
+                None => println!("  {status}  {}", check.check),
+            }
+        }
+        println!(
+            "Result:    {}",
+            if result.verified { "PASS" } else { "FAIL" }
+        );
+    }
+    if !result.verified {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// One line of the `attest verify` report.
+#[cfg(not(windows))]
+#[derive(Debug, serde::Serialize)]
+struct CheckOutcome {
+    check: String,
+    passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+/// The checks behind `result`: report authenticity and freshness first,
+/// then every check `policy` makes, with the reasons for any failure.
+#[cfg(not(windows))]
+fn check_outcomes(
+    result: &a3s_box_runtime::VerificationResult,
+    policy: &AttestationPolicy,
+    simulated: bool,
+) -> Vec<CheckOutcome> {
+    let skipped = simulated.then(|| "skipped for a simulated report".to_string());
+    let mut outcomes = vec![
+        CheckOutcome {
+            check: "signature".to_string(),
+            passed: result.signature_valid,
+            detail: skipped.clone(),
+        },
+        CheckOutcome {
+            check: "cert_chain".to_string(),
+            passed: result.cert_chain_valid,
+            detail: skipped,
+        },
+        CheckOutcome {
+            check: "nonce".to_string(),
+            passed: result.nonce_valid,
+            detail: None,
+        },
+    ];
+    if policy.max_report_age_secs.is_some() {
+        outcomes.push(CheckOutcome {
+            check: "report_age".to_string(),
+            passed: result.report_age_valid,
+            detail: None,
+        });
+    }
+    for check in policy.checks() {
+        let reasons: Vec<&str> = result
+            .policy_result
+            .violations
+            .iter()
+            .filter(|violation| violation.check == check)
+            .map(|violation| violation.reason.as_str())
+            .collect();
+        outcomes.push(CheckOutcome {
+            check: check.to_string(),
+            passed: reasons.is_empty(),
+            detail: (!reasons.is_empty()).then(|| reasons.join("; ")),
+        });
+    }
+    outcomes
+}
+
+/// Pick the policy to verify against: `--policy`, else the one the box was
+/// started with, else the default. Unless the policy pins runtime claims
+/// itself, reports must carry the claims the box was launched with.
+#[cfg(not(windows))]
+pub(crate) fn resolve_policy(
+    path: Option<&std::path::Path>,
+    box_dir: &std::path::Path,
+) -> Result<AttestationPolicy, Box<dyn std::error::Error>> {
+    let mut policy = match path {
+        Some(path) => AttestationPolicy::load(path)?,
+        None => AttestationPolicy::load_for_box(box_dir)?.unwrap_or_default(),
+    };
+    if policy.expected_runtime_claims.is_none() {
+        policy.expected_runtime_claims =
+            a3s_box_runtime::tee::RuntimeClaims::load_for_box(box_dir)?
+                .map(|claims| claims.digest_hex());
+    }
+    Ok(policy)
+}
+
 /// Generate a random 64-byte nonce.
 #[cfg(any(not(windows), test))]
 fn generate_random_nonce() -> Vec<u8> {
@@ -273,6 +487,57 @@ mod tests {
         assert_eq!(bytes_to_hex(&[]), "");
     }
 
+    #[cfg(not(windows))]
+    #[test]
+    fn test_check_outcomes_cover_every_policy_check() {
+        use a3s_box_runtime::{MinTcbPolicy, PolicyResult, VerificationResult};
+
+        let policy = AttestationPolicy {
+            allowed_measurements: vec!["ab".repeat(48)],
+            min_tcb: Some(MinTcbPolicy {
+                snp: Some(8),
+                ..Default::default()
+            }),
+            max_report_age_secs: Some(60),
+            ..Default::default()
+        };
+        let result = VerificationResult {
+            verified: false,
+            platform: Default::default(),
+            policy_result: PolicyResult::from_violations(vec![
+                a3s_box_runtime::tee::PolicyViolation {
+                    check: "tcb.snp".to_string(),
+                    reason: "SNP SVN 5 < minimum 8".to_string(),
+                },
+            ]),
+            signature_valid: true,
+            cert_chain_valid: true,
+            nonce_valid: true,
+            report_age_valid: true,
+            failures: vec![],
+        };
+
+        let outcomes = check_outcomes(&result, &policy, true);
+        let summary: Vec<(&str, bool)> = outcomes
+            .iter()
+            .map(|o| (o.check.as_str(), o.passed))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("signature", true),
+                ("cert_chain", true),
+                ("nonce", true),
+                ("report_age", true),
+                ("measurement", true),
+                ("debug", true),
+                ("tcb.snp", false),
+            ]
+        );
+        assert_eq!(outcomes[6].detail.as_deref(), Some("SNP SVN 5 < minimum 8"));
+        assert!(outcomes[0].detail.is_some());
+    }
+
     #[test]
     fn test_generate_random_nonce() {
         let nonce = generate_random_nonce();
@@ -1,13 +1,23 @@
 //! `a3s-box audit` command — View the audit log.
 //!
-//! Reads persistent audit events with optional filters.
+//! Reads persistent audit events with optional filters. `audit net <box>`
+//! shows the HTTP(S) requests a box made through its `--http-proxy`.
 
 use a3s_box_core::audit::{AuditAction, AuditOutcome};
+use a3s_box_core::http_proxy::{HttpAuditRecord, HTTP_AUDIT_FILE};
 use a3s_box_runtime::{read_audit_log, AuditLog, AuditQuery};
-use clap::Args;
+use clap::{Args, Subcommand};
+
+use crate::output;
+use crate::resolve;
+use crate::state::{BoxRecord, StateFile};
 
 #[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct AuditArgs {
+    #[command(subcommand)]
+    pub command: Option<AuditCommand>,
+
     /// Filter by action (e.g., "box_create", "exec_command", "image_pull")
     #[arg(long)]
     pub action: Option<String>,
@@ -29,7 +39,32 @@ pub struct AuditArgs {
     pub json: bool,
 }
 
+/// Audit subcommands.
+#[derive(Subcommand)]
+pub enum AuditCommand {
+    /// Show the HTTP(S) requests a box made through --http-proxy
+    Net(AuditNetArgs),
+}
+
+#[derive(Args)]
+pub struct AuditNetArgs {
+    /// Box name or ID
+    pub r#box: String,
+
+    /// Maximum number of requests to show (most recent)
+    #[arg(short = 'n', long, default_value = "50")]
+    pub limit: usize,
+
+    /// Output as raw JSON lines
+    #[arg(long)]
+    pub json: bool,
+}
+
 pub async fn execute(args: AuditArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(AuditCommand::Net(args)) = args.command {
+        return execute_net(args);
+    }
+
     let audit_log = AuditLog::default_path()?;
     let path = audit_log.path();
 
@@ -113,6 +148,68 @@ pub async fn execute(args: AuditArgs) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+fn execute_net(args: AuditNetArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let state = StateFile::load_default()?;
+    let record = resolve::resolve(&state, &args.r#box)?;
+    let requests = box_requests(record, args.limit)?;
+
+    if args.json {
+        for request in &requests {
+            println!("{}", serde_json::to_string(request)?);
+        }
+        return Ok(());
+    }
+    if requests.is_empty() {
+        println!("No HTTP requests recorded.");
+        return Ok(());
+    }
+    let mut table = output::new_table(&[
+        "TIME", "METHOD", "TARGET", "STATUS", "SENT", "RECEIVED", "DURATION",
+    ]);
+    for request in &requests {
+        table.add_row(request_row(request));
+    }
+    println!("{table}");
+    Ok(())
+}
+
+/// The last `limit` requests recorded by a box's proxy, oldest first.
+fn box_requests(record: &BoxRecord, limit: usize) -> Result<Vec<HttpAuditRecord>, String> {
+    if record.http_proxy.is_none() {
+        return Err(format!(
+            "box {} does not use an HTTP proxy; request records need a box started with --http-proxy",
+            record.name
+        ));
+    }
+    let path = record.box_dir.join(HTTP_AUDIT_FILE);
+    let mut requests = a3s_box_core::http_proxy::read_audit_log(&path)
+        .map_err(|error| format!("failed to read {}: {error}", path.display()))?;
+    let skip = requests.len().saturating_sub(limit);
+    requests.drain(..skip);
+    Ok(requests)
+}
+
+fn request_row(request: &HttpAuditRecord) -> Vec<String> {
+    let status = match (request.status, &request.error) {
+        (Some(status), _) => status.to_string(),
+        (None, Some(_)) => "error".to_string(),
+        (None, None) => "-".to_string(),
+    };
+    vec![
+        request
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        request.method.clone(),
+        request.target(),
+        status,
+        output::format_bytes(request.request_bytes),
+        output::format_bytes(request.response_bytes),
+        format!("{}ms", request.duration_ms),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,4 +231,49 @@ mod tests {
         let result: Result<AuditAction, _> = serde_json::from_str("\"nonexistent\"");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_box_requests_requires_http_proxy() {
+        let record = crate::test_helpers::fixtures::make_record("box-1", "web", "running", Some(1));
+
+        let error = box_requests(&record, 50).unwrap_err();
+
+        assert!(error.contains("--http-proxy"), "got: {error}");
+    }
+
+    #[test]
+    fn test_box_requests_keeps_most_recent() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut record =
+            crate::test_helpers::fixtures::make_record("box-1", "web", "running", Some(1));
+        record.box_dir = dir.path().to_path_buf();
+        record.http_proxy = Some(a3s_box_core::http_proxy::HttpProxyMode::Audit);
+        assert!(box_requests(&record, 50).unwrap().is_empty());
+
+        let lines: String = ["/a", "/b", "/c"]
+            .iter()
+            .map(|path| {
+                let request = HttpAuditRecord {
+                    timestamp: chrono::Utc::now(),
+                    method: "GET".to_string(),
+                    scheme: "https".to_string(),
+                    host: "example.com".to_string(),
+                    port: 443,
+                    path: Some(path.to_string()),
+                    status: Some(200),
+                    request_bytes: 10,
+                    response_bytes: 20,
+                    duration_ms: 5,
+                    error: None,
+                };
+                format!("{}\n", serde_json::to_string(&request).unwrap())
+            })
+            .collect();
+        std::fs::write(dir.path().join(HTTP_AUDIT_FILE), lines).unwrap();
+
+        let requests = box_requests(&record, 2).unwrap();
+        let paths: Vec<_> = requests.iter().map(|r| r.path.as_deref()).collect();
+        assert_eq!(paths, [Some("/b"), Some("/c")]);
+        assert_eq!(request_row(&requests[0])[2], "https://example.com/b");
+    }
 }
@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use clap::{Args, ValueEnum};
+use clap::{Parser, ValueEnum};
 
 #[path = "build_buildkit_vm.rs"]
 mod buildkit_vm;
@@ -15,6 +15,7 @@ mod buildkit_vm;
 const BUILD_RUN_POOL_SOCKET_ENV: &str = "A3S_BOX_BUILD_RUN_POOL_SOCKET";
 const BUILD_RUN_CACHE_DIR_ENV: &str = "A3S_BOX_BUILD_RUN_CACHE_DIR";
 const DEFAULT_BUILD_RUN_POOL_GUEST_ROOTFS: &str = "/run/a3s/build-rootfs";
+const SOURCE_DATE_EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
 pub enum BuildBackend {
@@ -26,7 +27,7 @@ pub enum BuildBackend {
     BuildkitVm,
 }
 
-#[derive(Args)]
+#[derive(Parser)]
 pub struct BuildArgs {
     /// Build context directory (contains Dockerfile/Containerfile and source files)
     #[arg(default_value = ".")]
@@ -62,6 +63,13 @@ pub struct BuildArgs {
     #[arg(long = "no-cache")]
     pub no_cache: bool,
 
+    /// Record this Unix timestamp as the image creation time instead of the
+    /// build time, so identical inputs build the same image digest.
+    ///
+    /// Defaults to $SOURCE_DATE_EPOCH. Layer contents are always reproducible.
+    #[arg(long = "source-date-epoch", value_name = "SECONDS")]
+    pub source_date_epoch: Option<i64>,
+
     /// Build backend: auto, host, or buildkit-vm.
     ///
     /// On macOS, auto delegates Dockerfiles containing RUN to BuildKit in an A3S VM.
@@ -125,6 +133,36 @@ pub struct BuildArgs {
     pub run_cache_dir: Option<String>,
 }
 
+/// Build a project's image (see [`crate::project`]) unless the local store
+/// already has it.
+pub(super) async fn ensure_project_image(
+    project: &crate::project::ProjectConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(build) = &project.build else {
+        return Ok(());
+    };
+    if super::images_dir().exists() {
+        let images = super::open_image_store()?.list().await;
+        if crate::image_usage::resolve_stored_image(&images, &project.image)?.is_some() {
+            return Ok(());
+        }
+    }
+    execute(BuildArgs::try_parse_from(project_build_argv(
+        &project.image,
+        build,
+    ))?)
+    .await
+}
+
+fn project_build_argv(image: &str, build: &crate::project::ProjectBuild) -> Vec<String> {
+    let mut argv = vec!["build".to_string(), "--tag".to_string(), image.to_string()];
+    if let Some(dockerfile) = &build.dockerfile {
+        argv.extend(["--file".to_string(), dockerfile.clone()]);
+    }
+    argv.push(build.context.clone());
+    argv
+}
+
 pub async fn execute(args: BuildArgs) -> Result<(), Box<dyn std::error::Error>> {
     let context_dir = PathBuf::from(&args.path)
         .canonicalize()
@@ -144,6 +182,7 @@ pub async fn execute(args: BuildArgs) -> Result<(), Box<dyn std::error::Error>>
     let build_args = parse_build_args(&args.build_arg)?;
 
     let platforms = parse_platforms(args.platform.as_deref())?;
+    let source_date_epoch = resolve_source_date_epoch(&args)?;
 
     let run_pool = resolve_run_pool_config(&args)?;
     if run_pool.is_some() && args.builder == BuildBackend::BuildkitVm {
@@ -175,6 +214,7 @@ pub async fn execute(args: BuildArgs) -> Result<(), Box<dyn std::error::Error>>
             platform: args.platform.clone(),
             target: args.target.clone(),
             no_cache: args.no_cache,
+            source_date_epoch,
             push: args.push,
             plain_http: args.plain_http,
             image: args
@@ -207,6 +247,7 @@ pub async fn execute(args: BuildArgs) -> Result<(), Box<dyn std::error::Error>>
         no_cache: args.no_cache,
         metrics: None,
         run_pool,
+        source_date_epoch,
     };
 
     let result = a3s_box_runtime::oci::build::engine::build(config, store).await?;
@@ -218,6 +259,18 @@ pub async fn execute(args: BuildArgs) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+fn resolve_source_date_epoch(args: &BuildArgs) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    if args.source_date_epoch.is_some() {
+        return Ok(args.source_date_epoch);
+    }
+    match std::env::var(SOURCE_DATE_EPOCH_ENV) {
+        Ok(value) if !value.trim().is_empty() => value.trim().parse().map(Some).map_err(|_| {
+            format!("Invalid {SOURCE_DATE_EPOCH_ENV} '{value}': expected seconds").into()
+        }),
+        _ => Ok(None),
+    }
+}
+
 fn resolve_run_pool_config(
     args: &BuildArgs,
 ) -> Result<Option<a3s_box_runtime::BuildRunPoolConfig>, Box<dyn std::error::Error>> {
@@ -429,6 +482,7 @@ mod tests {
             platform: None,
             target: None,
             no_cache: false,
+            source_date_epoch: None,
             builder: BuildBackend::Auto,
             buildkit_image: None,
             buildkit_cpus: None,
@@ -446,6 +500,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_project_build_argv_parses_as_build() {
+        let build = crate::project::ProjectBuild {
+            context: "agent".to_string(),
+            dockerfile: Some("agent/Dockerfile".to_string()),
+        };
+        let args =
+            BuildArgs::try_parse_from(project_build_argv("helper-agent:latest", &build)).unwrap();
+        assert_eq!(args.path, "agent");
+        assert_eq!(args.tag.as_deref(), Some("helper-agent:latest"));
+        assert_eq!(args.file.as_deref(), Some("agent/Dockerfile"));
+        assert_eq!(args.run_pool_memory, build_args().run_pool_memory);
+    }
+
     #[test]
     fn test_parse_build_args_valid() {
         let args = vec!["VERSION=1.0".to_string(), "DEBUG=true".to_string()];
@@ -567,6 +635,23 @@ mod tests {
         assert_eq!(config.run_cache_dir, cache_dir);
     }
 
+    #[test]
+    fn test_resolve_source_date_epoch_flag_overrides_env() {
+        let _guard = EnvGuard::set(SOURCE_DATE_EPOCH_ENV, "1700000000");
+        let mut args = build_args();
+        assert_eq!(
+            resolve_source_date_epoch(&args).unwrap(),
+            Some(1_700_000_000)
+        );
+
+        args.source_date_epoch = Some(42);
+        assert_eq!(resolve_source_date_epoch(&args).unwrap(), Some(42));
+
+        let _guard = EnvGuard::set(SOURCE_DATE_EPOCH_ENV, "yesterday");
+        args.source_date_epoch = None;
+        assert!(resolve_source_date_epoch(&args).is_err());
+    }
+
     #[test]
     fn test_dockerfile_has_run_detects_run_instruction() {
         let tmp = tempfile::tempdir().unwrap();
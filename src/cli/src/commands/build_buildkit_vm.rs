@@ -22,6 +22,7 @@ pub(super) struct Build {
     pub(super) platform: Option<String>,
     pub(super) target: Option<String>,
     pub(super) no_cache: bool,
+    pub(super) source_date_epoch: Option<i64>,
     pub(super) push: bool,
     pub(super) plain_http: bool,
     pub(super) image: String,
@@ -233,6 +234,10 @@ fn buildctl_args(
     if options.no_cache {
         args.push("--no-cache".to_string());
     }
+    if let Some(epoch) = options.source_date_epoch {
+        args.push("--opt".to_string());
+        args.push(format!("build-arg:SOURCE_DATE_EPOCH={epoch}"));
+    }
 
     args.push("--output".to_string());
     args.push(output_attr(options, output_name)?);
@@ -390,6 +395,7 @@ mod tests {
             platform: Some("linux/arm64".to_string()),
             target: Some("builder".to_string()),
             no_cache: true,
+            source_date_epoch: Some(1_700_000_000),
             push: false,
             plain_http: false,
             image: "moby/buildkit:latest".to_string(),
@@ -439,6 +445,7 @@ mod tests {
         assert!(build_args.contains(&"platform=linux/arm64".to_string()));
         assert!(build_args.contains(&"target=builder".to_string()));
         assert!(build_args.contains(&"--no-cache".to_string()));
+        assert!(build_args.contains(&"build-arg:SOURCE_DATE_EPOCH=1700000000".to_string()));
         assert!(build_args.contains(&"type=oci,dest=/out/image.tar".to_string()));
     }
 
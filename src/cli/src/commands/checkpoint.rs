@@ -0,0 +1,600 @@
+//! `a3s-box checkpoint create/ls/rm` and `start --from-checkpoint`.
+//!
+//! A checkpoint is a copy of a stopped box's writable filesystem (the overlay
+//! upper, the persistent rootfs, or the case-sensitive APFS image) kept under
+//! `<box_dir>/checkpoints/<name>/` next to a `checkpoint.json` recording the
+//! image and its digest. Starting from a checkpoint puts that state back and
+//! boots the box, so an agent session can be stopped in the evening and
+//! resumed from the same files the next day, or rolled back to a known-good
+//! point. Guest memory is not captured: processes start fresh on the restored
+//! filesystem. Checkpoints live and die with the box; named volumes are not
+//! included.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+
+use crate::output;
+use crate::resolve;
+use crate::state::{BoxRecord, StateFile};
+
+/// Directory under the box directory holding its checkpoints.
+const CHECKPOINTS_DIR: &str = "checkpoints";
+/// Metadata file inside each checkpoint directory.
+const METADATA_FILE: &str = "checkpoint.json";
+/// Copy of the writable state inside each checkpoint directory.
+const STATE_DIR: &str = "state";
+/// Entries of a box directory that hold guest writes across stop/start.
+const WRITABLE_ENTRIES: &[&str] = &["upper", "rootfs", "rootfs-apfs-v2.sparseimage"];
+
+/// Save and restore the filesystem state of a box.
+#[derive(Parser)]
+pub struct CheckpointArgs {
+    #[command(subcommand)]
+    pub action: CheckpointAction,
+}
+
+/// Checkpoint subcommands.
+#[derive(Subcommand)]
+pub enum CheckpointAction {
+    /// Checkpoint a stopped box
+    Create(CheckpointCreateArgs),
+    /// List the checkpoints of a box
+    Ls(CheckpointLsArgs),
+    /// Remove checkpoints from a box
+    Rm(CheckpointRmArgs),
+}
+
+/// Arguments for `checkpoint create`.
+#[derive(Parser)]
+pub struct CheckpointCreateArgs {
+    /// Box name or ID
+    #[arg(value_name = "BOX")]
+    pub box_id: String,
+    /// Checkpoint name (default: checkpoint-<UTC timestamp>)
+    pub name: Option<String>,
+}
+
+/// Arguments for `checkpoint ls`.
+#[derive(Parser)]
+pub struct CheckpointLsArgs {
+    /// Box name or ID
+    #[arg(value_name = "BOX")]
+    pub box_id: String,
+    /// Only print checkpoint names
+    #[arg(short, long)]
+    pub quiet: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Arguments for `checkpoint rm`.
+#[derive(Parser)]
+pub struct CheckpointRmArgs {
+    /// Box name or ID
+    #[arg(value_name = "BOX")]
+    pub box_id: String,
+    /// Checkpoint name(s) to remove
+    #[arg(required = true)]
+    pub names: Vec<String>,
+}
+
+/// Contents of `checkpoint.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CheckpointMetadata {
+    pub name: String,
+    pub box_id: String,
+    pub image: String,
+    /// Digest of the image the box ran when checkpointed, if still stored.
+    pub image_digest: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+}
+
+pub async fn execute(args: CheckpointArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match args.action {
+        CheckpointAction::Create(a) => execute_create(a).await,
+        CheckpointAction::Ls(a) => execute_ls(a),
+        CheckpointAction::Rm(a) => execute_rm(a).await,
+    }
+}
+
+async fn execute_create(args: CheckpointCreateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let name = args
+        .name
+        .unwrap_or_else(|| format!("checkpoint-{}", Utc::now().format("%Y%m%d-%H%M%S")));
+    validate_name(&name)?;
+
+    let initial_state = StateFile::load_default()?;
+    let box_id = resolve::resolve(&initial_state, &args.box_id)?.id.clone();
+    let _lifecycle_lock = crate::lifecycle::acquire_box_lifecycle_lock(&box_id).await?;
+    let state = StateFile::load_default()?;
+    let record = state
+        .find_by_id(&box_id)
+        .ok_or_else(|| format!("Box '{box_id}' was removed while waiting to checkpoint it"))?;
+    require_stopped(record, "checkpoint")?;
+
+    let image_digest = image_digest(&record.image).await;
+    let metadata = create(record, &name, image_digest)?;
+    println!("{}", metadata.name);
+    Ok(())
+}
+
+fn execute_ls(args: CheckpointLsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let state = StateFile::load_default()?;
+    let record = resolve::resolve(&state, &args.box_id)?;
+    let checkpoints = list(&record.box_dir)?;
+
+    if crate::output::structured().is_some() {
+        return crate::output::emit("CheckpointList", &checkpoints);
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&checkpoints)?);
+        return Ok(());
+    }
+    if args.quiet {
+        for checkpoint in &checkpoints {
+            println!("{}", checkpoint.name);
+        }
+        return Ok(());
+    }
+
+    let mut table = output::new_table(&["NAME", "IMAGE", "DIGEST", "SIZE", "CREATED"]);
+    for checkpoint in &checkpoints {
+        let digest = checkpoint
+            .image_digest
+            .as_deref()
+            .map(|digest| digest.strip_prefix("sha256:").unwrap_or(digest))
+            .map(|digest| digest[..digest.len().min(12)].to_string())
+            .unwrap_or_else(|| "<none>".to_string());
+        table.add_row(vec![
+            checkpoint.name.clone(),
+            checkpoint.image.clone(),
+            digest,
+            output::format_bytes(checkpoint.size_bytes),
+            output::format_ago(&checkpoint.created_at),
+        ]);
+    }
+    println!("{table}");
+    Ok(())
+}
+
+async fn execute_rm(args: CheckpointRmArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let state = StateFile::load_default()?;
+    let box_id = resolve::resolve(&state, &args.box_id)?.id.clone();
+    let _lifecycle_lock = crate::lifecycle::acquire_box_lifecycle_lock(&box_id).await?;
+    let state = StateFile::load_default()?;
+    let record = state
+        .find_by_id(&box_id)
+        .ok_or_else(|| format!("Box '{box_id}' was removed while waiting for its lock"))?;
+
+    let mut errors = Vec::new();
+    for name in &args.names {
+        match remove(&record.box_dir, name) {
+            Ok(()) => println!("{name}"),
+            Err(e) => errors.push(format!("{name}: {e}")),
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n").into())
+    }
+}
+
+/// Put checkpoint `name` back as the writable state of `record`, which must
+/// be stopped and locked by the caller. Warns when the box's image has moved
+/// to a different digest since, since the saved changes sit on top of it.
+pub(crate) async fn restore_for_start(
+    record: &BoxRecord,
+    name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if record.managed_execution.is_some() {
+        return Err(format!(
+            "Box {} is managed by the execution API; --from-checkpoint only supports CLI boxes",
+            record.name
+        )
+        .into());
+    }
+    require_stopped(record, "restore a checkpoint into")?;
+    let metadata = load(&record.box_dir, name)?;
+    if let (Some(saved), Some(current)) = (
+        metadata.image_digest.as_deref(),
+        image_digest(&record.image).await,
+    ) {
+        if saved != current {
+            eprintln!(
+                "warning: {} now resolves to {current}; checkpoint {name} was taken on {saved}",
+                record.image
+            );
+        }
+    }
+    restore(&record.box_dir, name)?;
+    Ok(())
+}
+
+fn require_stopped(record: &BoxRecord, action: &str) -> Result<(), String> {
+    if record.is_active() {
+        return Err(format!(
+            "Cannot {action} active box '{}': stop it first",
+            record.name
+        ));
+    }
+    Ok(())
+}
+
+async fn image_digest(image: &str) -> Option<String> {
+    let store = super::open_image_store().ok()?;
+    let images = store.list().await;
+    crate::image_usage::resolve_stored_image(&images, image)
+        .ok()
+        .flatten()
+        .map(|stored| stored.digest)
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid checkpoint name '{name}': use letters, digits, '_', '.' and '-', \
+             starting with a letter or digit"
+        ))
+    }
+}
+
+fn checkpoints_dir(box_dir: &Path) -> PathBuf {
+    box_dir.join(CHECKPOINTS_DIR)
+}
+
+/// Copy the writable state of a stopped box into a new checkpoint.
+fn create(
+    record: &BoxRecord,
+    name: &str,
+    image_digest: Option<String>,
+) -> Result<CheckpointMetadata, Box<dyn std::error::Error>> {
+    let dir = checkpoints_dir(&record.box_dir);
+    let destination = dir.join(name);
+    if destination.exists() {
+        return Err(format!("Checkpoint '{name}' already exists for box {}", record.name).into());
+    }
+    let entries: Vec<&str> = WRITABLE_ENTRIES
+        .iter()
+        .copied()
+        .filter(|entry| record.box_dir.join(entry).exists())
+        .collect();
+    if entries.is_empty() {
+        return Err(format!(
+            "Box {} has no filesystem state to checkpoint; start it once first",
+            record.name
+        )
+        .into());
+    }
+
+    // Build the checkpoint beside its final name and rename it into place, so
+    // an interrupted copy never shows up as a checkpoint.
+    let staging = dir.join(format!(".{name}.tmp-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::create_dir_all(staging.join(STATE_DIR))?;
+    let result = (|| -> Result<CheckpointMetadata, Box<dyn std::error::Error>> {
+        for entry in &entries {
+            copy_tree(
+                &record.box_dir.join(entry),
+                &staging.join(STATE_DIR).join(entry),
+            )?;
+        }
+        let metadata = CheckpointMetadata {
+            name: name.to_string(),
+            box_id: record.id.clone(),
+            image: record.image.clone(),
+            image_digest,
+            created_at: Utc::now(),
+            size_bytes: tree_size(&staging.join(STATE_DIR)),
+        };
+        std::fs::write(
+            staging.join(METADATA_FILE),
+            serde_json::to_vec_pretty(&metadata)?,
+        )?;
+        std::fs::rename(&staging, &destination)?;
+        Ok(metadata)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+fn load(box_dir: &Path, name: &str) -> Result<CheckpointMetadata, Box<dyn std::error::Error>> {
+    validate_name(name)?;
+    let path = checkpoints_dir(box_dir).join(name).join(METADATA_FILE);
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!("No checkpoint named '{name}'").into())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    serde_json::from_slice(&bytes)
+        .map_err(|e| format!("Corrupt checkpoint metadata {}: {e}", path.display()).into())
+}
+
+/// Checkpoints of a box, oldest first.
+fn list(box_dir: &Path) -> Result<Vec<CheckpointMetadata>, Box<dyn std::error::Error>> {
+    let entries = match std::fs::read_dir(checkpoints_dir(box_dir)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut checkpoints = Vec::new();
+    for entry in entries {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        checkpoints.push(load(box_dir, &name)?);
+    }
+    checkpoints.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.name.cmp(&b.name)));
+    Ok(checkpoints)
+}
+
+fn remove(box_dir: &Path, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    load(box_dir, name)?;
+    std::fs::remove_dir_all(checkpoints_dir(box_dir).join(name))?;
+    Ok(())
+}
+
+/// Replace the box's writable state with the checkpoint's copy. Overlay
+/// scratch directories are dropped so the next mount starts clean.
+fn restore(box_dir: &Path, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    restore_with(box_dir, name, copy_tree)
+}
+
+/// [`restore`] copying with `copy` instead of [`copy_tree`].
+fn restore_with(
+    box_dir: &Path,
+    name: &str,
+    copy: impl Fn(&Path, &Path) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let saved = checkpoints_dir(box_dir).join(name).join(STATE_DIR);
+    // Copy the checkpoint in beside the box's state first, so a failed copy
+    // (a full disk, say) leaves the current state as it was. Only renames
+    // touch the box after that.
+    let staging = box_dir.join(format!(".restore-{name}.tmp-{}", std::process::id()));
+    let replaced = box_dir.join(format!(".restore-{name}.old-{}", std::process::id()));
+    for dir in [&staging, &replaced] {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    std::fs::create_dir_all(&staging)?;
+    let staged = (|| -> Result<(), Box<dyn std::error::Error>> {
+        let mut copied = false;
+        for entry in WRITABLE_ENTRIES {
+            let source = saved.join(entry);
+            if source.exists() {
+                copy(&source, &staging.join(entry))?;
+                copied = true;
+            }
+        }
+        if !copied {
+            return Err(format!("Checkpoint '{name}' has no saved state").into());
+        }
+        std::fs::create_dir(&replaced)?;
+        Ok(())
+    })();
+    if let Err(e) = staged {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    let swapped = swap_in_staged_state(box_dir, &staging, &replaced);
+    if swapped.is_err() {
+        roll_back_swap(box_dir, &replaced);
+    }
+    let _ = std::fs::remove_dir_all(&staging);
+    let _ = std::fs::remove_dir_all(&replaced);
+    swapped
+}
+
+/// Move the box's writable state and overlay scratch into `replaced`, then
+/// the staged copy into the box.
+fn swap_in_staged_state(
+    box_dir: &Path,
+    staging: &Path,
+    replaced: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for entry in WRITABLE_ENTRIES.iter().chain(&["work", "merged"]) {
+        let path = box_dir.join(entry);
+        match std::fs::symlink_metadata(&path) {
+            Ok(_) => std::fs::rename(&path, replaced.join(entry))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    for entry in WRITABLE_ENTRIES {
+        let source = staging.join(entry);
+        if std::fs::symlink_metadata(&source).is_ok() {
+            std::fs::rename(&source, box_dir.join(entry))?;
+        }
+    }
+    Ok(())
+}
+
+/// Best effort: put back whatever [`swap_in_staged_state`] moved aside.
+fn roll_back_swap(box_dir: &Path, replaced: &Path) {
+    for entry in WRITABLE_ENTRIES.iter().chain(&["work", "merged"]) {
+        let original = replaced.join(entry);
+        if std::fs::symlink_metadata(&original).is_err() {
+            continue;
+        }
+        let path = box_dir.join(entry);
+        match std::fs::symlink_metadata(&path) {
+            Ok(meta) if meta.is_dir() => {
+                let _ = std::fs::remove_dir_all(&path);
+            }
+            Ok(_) => {
+                let _ = std::fs::remove_file(&path);
+            }
+            Err(_) => {}
+        }
+        if let Err(e) = std::fs::rename(&original, &path) {
+            tracing::warn!(
+                path = %path.display(),
+                error = %e,
+                "Failed to put back box state after an interrupted checkpoint restore"
+            );
+        }
+    }
+}
+
+/// Copy a file or directory tree preserving ownership, modes, timestamps,
+/// xattrs and special files. Overlay whiteouts are 0/0 character devices and
+/// opaque directories are marked by xattrs, which `cp -a` carries over.
+fn copy_tree(source: &Path, destination: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let output = std::process::Command::new("cp")
+        .arg("-a")
+        .arg(source)
+        .arg(destination)
+        .output()
+        .map_err(|e| format!("Failed to run cp: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to copy {} to {}: {}",
+            source.display(),
+            destination.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn tree_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| tree_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::fixtures::make_record;
+
+    fn stopped_box(dir: &Path) -> BoxRecord {
+        let mut record = make_record("box-1", "agent", "stopped", None);
+        record.box_dir = dir.to_path_buf();
+        record
+    }
+
+    #[test]
+    fn test_validate_name() {
+        validate_name("before-refactor_2.1").unwrap();
+        for name in ["", ".hidden", "-x", "a/b", "a b", ".."] {
+            assert!(validate_name(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_create_list_restore_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let record = stopped_box(dir.path());
+        std::fs::create_dir_all(dir.path().join("upper/work")).unwrap();
+        std::fs::write(dir.path().join("upper/work/notes.md"), "day one").unwrap();
+
+        let metadata = create(&record, "day1", Some("sha256:abc".into())).unwrap();
+        assert_eq!(metadata.box_id, "box-1");
+        assert_eq!(metadata.size_bytes, 7);
+        assert!(create(&record, "day1", None).is_err());
+
+        std::fs::write(dir.path().join("upper/work/notes.md"), "day two").unwrap();
+        std::fs::write(dir.path().join("upper/scratch"), "x").unwrap();
+        std::fs::create_dir_all(dir.path().join("work/work")).unwrap();
+        restore(dir.path(), "day1").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("upper/work/notes.md")).unwrap(),
+            "day one"
+        );
+        assert!(!dir.path().join("upper/scratch").exists());
+        assert!(!dir.path().join("work").exists());
+
+        let listed = list(dir.path()).unwrap();
+        assert_eq!(listed, vec![metadata]);
+        remove(dir.path(), "day1").unwrap();
+        assert!(list(dir.path()).unwrap().is_empty());
+        assert!(remove(dir.path(), "day1").is_err());
+    }
+
+    #[test]
+    fn test_failed_restore_copy_keeps_current_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let record = stopped_box(dir.path());
+        std::fs::create_dir_all(dir.path().join("upper")).unwrap();
+        std::fs::create_dir_all(dir.path().join("rootfs")).unwrap();
+        std::fs::write(dir.path().join("upper/notes.md"), "day one").unwrap();
+        create(&record, "day1", None).unwrap();
+        std::fs::write(dir.path().join("upper/notes.md"), "day two").unwrap();
+
+        // The first entry copies, then the disk "fills up".
+        let copies = std::cell::Cell::new(0);
+        let err = restore_with(dir.path(), "day1", |source, destination| {
+            copies.set(copies.get() + 1);
+            if copies.get() > 1 {
+                return Err("No space left on device".into());
+            }
+            copy_tree(source, destination)
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("No space left"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("upper/notes.md")).unwrap(),
+            "day two"
+        );
+        assert!(dir.path().join("rootfs").is_dir());
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with('.'))
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+
+        // A checkpoint whose state is gone does not wipe the box either.
+        std::fs::remove_dir_all(dir.path().join("checkpoints/day1/state")).unwrap();
+        assert!(restore(dir.path(), "day1")
+            .unwrap_err()
+            .to_string()
+            .contains("no saved state"));
+        assert!(dir.path().join("upper/notes.md").exists());
+    }
+
+    #[test]
+    fn test_create_requires_state_and_stopped_box() {
+        let dir = tempfile::tempdir().unwrap();
+        let record = stopped_box(dir.path());
+        let err = create(&record, "empty", None).unwrap_err();
+        assert!(err.to_string().contains("start it once first"));
+        assert!(list(dir.path()).unwrap().is_empty());
+
+        let running = make_record("box-2", "busy", "running", Some(std::process::id()));
+        let err = require_stopped(&running, "checkpoint").unwrap_err();
+        assert!(err.contains("stop it first"));
+    }
+}
@@ -55,7 +55,8 @@ pub(crate) fn resolve_isolation(value: Option<IsolationArg>) -> ExecutionIsolati
 /// Common arguments shared between `run` and `create` commands.
 #[derive(Args)]
 pub struct CommonBoxArgs {
-    /// OCI image reference
+    /// OCI image reference (default: the image in ./a3s-box.toml)
+    #[arg(default_value = "", hide_default_value = true)]
     pub image: String,
 
     /// Use the shared-kernel sandbox backend (omit for MicroVM isolation)
@@ -78,6 +79,11 @@ pub struct CommonBoxArgs {
     #[arg(short = 'v', long = "volume")]
     pub volumes: Vec<String>,
 
+    /// Attach a mount (type=bind|volume|tmpfs|socket,source=...,target=...[,readonly]
+    /// [,driver=local|tmpfs|nfs|block][,volume-opt=KEY=VALUE]), can be repeated
+    #[arg(long = "mount")]
+    pub mounts: Vec<String>,
+
     /// Environment variable (KEY=VALUE), can be repeated
     #[arg(short = 'e', long = "env")]
     pub env: Vec<String>,
@@ -90,6 +96,14 @@ pub struct CommonBoxArgs {
     #[arg(long)]
     pub dns: Vec<String>,
 
+    /// Set a DNS search domain, can be repeated ("." for none)
+    #[arg(long)]
+    pub dns_search: Vec<String>,
+
+    /// Set a resolver option (e.g. "ndots:2"), can be repeated
+    #[arg(long)]
+    pub dns_option: Vec<String>,
+
     /// Override the image entrypoint
     #[arg(long)]
     pub entrypoint: Option<String>,
@@ -102,6 +116,10 @@ pub struct CommonBoxArgs {
     #[arg(short = 'u', long)]
     pub user: Option<String>,
 
+    /// Add a supplementary group (GID or group name), can be repeated
+    #[arg(long)]
+    pub group_add: Vec<String>,
+
     /// Working directory inside the box
     #[arg(short = 'w', long)]
     pub workdir: Option<String>,
@@ -126,6 +144,34 @@ pub struct CommonBoxArgs {
     #[arg(long)]
     pub network: Option<String>,
 
+    /// Add a DNS alias for the box on its --network, can be repeated
+    #[arg(long)]
+    pub network_alias: Vec<String>,
+
+    /// Restrict outbound traffic on the --network: allow=<targets> or
+    /// deny=<targets> (CIDRs or domains, comma-separated), can be repeated
+    #[arg(long)]
+    pub network_policy: Vec<String>,
+
+    /// Limit outbound bandwidth on the --network (e.g., "10mbit", "512kbit")
+    #[arg(long, value_parser = a3s_box_core::egress::parse_bandwidth)]
+    pub network_bandwidth: Option<u64>,
+
+    /// Limit concurrent outbound TCP connections on the --network
+    #[arg(long)]
+    pub network_max_conns: Option<u32>,
+
+    /// Route the box's HTTP(S) traffic through an audited proxy; `mitm` also
+    /// decrypts HTTPS with a per-box CA (view with `a3s-box audit net`)
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "audit"
+    )]
+    pub http_proxy: Option<a3s_box_core::http_proxy::HttpProxyMode>,
+
     /// Health check command (e.g., "curl -f http://localhost/health")
     #[arg(long)]
     pub health_cmd: Option<String>,
@@ -154,6 +200,11 @@ pub struct CommonBoxArgs {
     #[arg(long)]
     pub cpuset_cpus: Option<String>,
 
+    /// Place vCPUs and guest memory on one host NUMA node (Linux). Narrows
+    /// --cpuset-cpus to the node's CPUs and binds guest RAM to its memory
+    #[arg(long)]
+    pub numa_node: Option<u32>,
+
     /// Set ulimit (e.g., "nofile=1024:4096"), can be repeated
     #[arg(long = "ulimit")]
     pub ulimits: Vec<String>,
@@ -178,6 +229,11 @@ pub struct CommonBoxArgs {
     #[arg(long)]
     pub memory_swap: Option<String>,
 
+    /// Storage driver options (supported: size=<N>, e.g. "size=10g" caps the
+    /// box's writable layer and default workspace; Linux only)
+    #[arg(long)]
+    pub storage_opt: Vec<String>,
+
     /// Read environment variables from a file, can be repeated
     #[arg(long)]
     pub env_file: Vec<String>,
@@ -194,7 +250,8 @@ pub struct CommonBoxArgs {
     #[arg(long)]
     pub init: bool,
 
-    /// Mount the root filesystem as read-only
+    /// Mount the root filesystem as read-only, with writable tmpfs at /tmp and
+    /// /run unless --tmpfs or a volume covers them
     #[arg(long)]
     pub read_only: bool,
 
@@ -214,7 +271,9 @@ pub struct CommonBoxArgs {
     #[arg(long)]
     pub privileged: bool,
 
-    /// Add a host device to the box (currently unsupported by the libkrun backend)
+    /// Expose a device node (host[:container][:rwm]). /dev/fuse, /dev/net/tun
+    /// and /dev/loop-control are virtualized by the guest kernel; serial, video
+    /// and hidraw devices (e.g. /dev/ttyUSB0) are proxied as a byte stream
     #[arg(long)]
     pub device: Vec<String>,
 
@@ -222,7 +281,7 @@ pub struct CommonBoxArgs {
     #[arg(long)]
     pub gpus: Option<String>,
 
-    /// Size of /dev/shm (e.g., "64m", "1g")
+    /// Size of /dev/shm (e.g., "64m", "1g"; default: 64m)
     #[arg(long)]
     pub shm_size: Option<String>,
 
@@ -246,9 +305,143 @@ pub struct CommonBoxArgs {
     #[arg(long)]
     pub oom_score_adj: Option<i32>,
 
+    /// Mark guest memory KSM-mergeable so the host dedups identical pages
+    /// across co-located boxes (Linux 6.4+; needs /sys/kernel/mm/ksm/run=1)
+    #[arg(long)]
+    pub ksm: bool,
+
+    /// Back guest RAM with transparent hugepages (Linux; host THP must be
+    /// "always" or "madvise")
+    #[arg(long)]
+    pub hugepages: bool,
+
+    /// Prefault all guest RAM at boot instead of on first touch (Linux 5.14+)
+    #[arg(long)]
+    pub mem_prealloc: bool,
+
+    /// Set a guest kernel parameter at boot (e.g.,
+    /// "fs.inotify.max_user_watches=524288"), can be repeated
+    #[arg(long, value_name = "NAME=VALUE")]
+    pub sysctl: Vec<String>,
+
+    /// Load a guest kernel module at boot, before --sysctl values are
+    /// applied, can be repeated
+    #[arg(long, value_name = "NAME")]
+    pub kernel_module: Vec<String>,
+
     /// Preserve filesystem changes across stop/start cycles
     #[arg(long)]
     pub persistent: bool,
+
+    /// Seed the box's new workspace from a directory or git URL (append
+    /// `#<ref>` to pick a branch or tag); `{{NAME}}` in text files is replaced
+    /// by --workspace-var values and `{{BOX_ID}}`
+    #[arg(long, value_name = "DIR|GIT-URL")]
+    pub workspace_template: Option<String>,
+
+    /// Set a workspace template variable (NAME=VALUE), can be repeated
+    #[arg(long, value_name = "NAME=VALUE", requires = "workspace_template")]
+    pub workspace_var: Vec<String>,
+}
+
+/// Without an image on the command line, take the box from `./a3s-box.toml`
+/// (see [`crate::project`]). Returns the project so its image can be built.
+pub(crate) fn apply_project(
+    common: &mut CommonBoxArgs,
+    cmd: &mut Vec<String>,
+) -> Result<Option<crate::project::ProjectConfig>, String> {
+    if !common.image.is_empty() {
+        return Ok(None);
+    }
+    let dir = std::env::current_dir().map_err(|e| e.to_string())?;
+    let Some(path) = crate::project::find(&dir) else {
+        return Err(format!(
+            "an image is required (or run in a directory with {}; see 'a3s-box init')",
+            crate::project::PROJECT_FILE
+        ));
+    };
+    let project = crate::project::load(&path)?;
+    merge_project(common, cmd, &project);
+    Ok(Some(project))
+}
+
+/// Project settings first, so flags on the command line win.
+pub(super) fn merge_project(
+    common: &mut CommonBoxArgs,
+    cmd: &mut Vec<String>,
+    project: &crate::project::ProjectConfig,
+) {
+    common.image = project.image.clone();
+    if common.name.is_none() {
+        common.name = project.name.clone();
+    }
+    if cmd.is_empty() {
+        cmd.clone_from(&project.cmd);
+    }
+    let env = project
+        .env
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"));
+    common.env = env.chain(common.env.drain(..)).collect();
+    common.volumes = [project.volumes.clone(), std::mem::take(&mut common.volumes)].concat();
+    common.publish = [project.ports.clone(), std::mem::take(&mut common.publish)].concat();
+}
+
+/// Metadata for a box this invocation creates, recording its command line
+/// as provenance for `inspect`.
+pub(crate) fn created_by_metadata() -> a3s_box_runtime::BoxMetadata {
+    a3s_box_runtime::BoxMetadata {
+        created_by: Some(
+            std::env::args_os()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+        ),
+        ..Default::default()
+    }
+}
+
+/// Parse the `--sysctl NAME=VALUE` options.
+pub(crate) fn sysctls(common: &CommonBoxArgs) -> Result<Vec<(String, String)>, String> {
+    common
+        .sysctl
+        .iter()
+        .map(|spec| a3s_box_core::kernel::parse_sysctl(spec))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("--sysctl: {e}"))
+}
+
+/// Build the workspace template from `--workspace-template` and
+/// `--workspace-var`. A local directory is resolved to an absolute path so
+/// it still names the template when the box starts later.
+pub(crate) fn workspace_template(
+    common: &CommonBoxArgs,
+) -> Result<Option<a3s_box_core::workspace_template::WorkspaceTemplate>, String> {
+    use a3s_box_core::workspace_template::{is_git_url, parse_var, WorkspaceTemplate};
+
+    let Some(source) = common.workspace_template.as_deref() else {
+        return Ok(None);
+    };
+    let vars = common
+        .workspace_var
+        .iter()
+        .map(|spec| parse_var(spec))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("--workspace-var: {e}"))?;
+    let source = if is_git_url(source) {
+        source.to_string()
+    } else {
+        let path = std::path::Path::new(source);
+        if !path.is_dir() {
+            return Err(format!(
+                "--workspace-template: {source} is not a directory or git URL"
+            ));
+        }
+        path.canonicalize()
+            .map_err(|e| format!("--workspace-template: {source}: {e}"))?
+            .display()
+            .to_string()
+    };
+    Ok(Some(WorkspaceTemplate { source, vars }))
 }
 
 /// Parse KEY=VALUE pairs into a HashMap.
@@ -262,7 +455,8 @@ pub(crate) fn parse_env_vars(vars: &[String]) -> Result<HashMap<String, String>,
 
 /// Load environment variables from a file.
 ///
-/// Each line should be KEY=VALUE. Empty lines and lines starting with '#' are skipped.
+/// See [`a3s_box_core::env::parse_env_file_content`] for the quoting and
+/// comment rules; values are not interpolated.
 pub(crate) fn parse_env_file(
     path: &str,
 ) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
@@ -277,19 +471,41 @@ pub(crate) fn parse_env_file(
 
 /// Build the effective CLI environment map.
 ///
-/// CLI `--env` values take precedence over `--env-file` values, matching the
-/// existing a3s-box run/create behavior.
+/// Later `--env-file`s override earlier ones and `--env` overrides them all;
+/// image defaults are merged underneath by the caller. `${VAR}` in `--env`
+/// values expands from the host environment here (see
+/// [`a3s_box_core::env::expand_braced_vars`]), so the box keeps the resolved
+/// value across restarts. A bare `$` and env file values stay verbatim.
 pub(crate) fn build_env_map(
     common: &CommonBoxArgs,
 ) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
-    // Runtime `--env` honors `docker run -e KEY` host-env passthrough (bare key).
-    let mut env: HashMap<String, String> = a3s_box_core::env::parse_runtime_env_vars(&common.env)
-        .into_iter()
-        .collect();
+    build_env_map_with_environment(common, &a3s_box_core::env::host_environment())
+}
+
+/// [`build_env_map`] resolving `--env` against `environment` instead of the
+/// host environment.
+fn build_env_map_with_environment(
+    common: &CommonBoxArgs,
+    environment: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    let mut env = HashMap::new();
     for env_file in &common.env_file {
-        for (key, value) in parse_env_file(env_file)? {
-            env.entry(key).or_insert(value);
-        }
+        env.extend(parse_env_file(env_file)?);
+    }
+    for var in &common.env {
+        let (key, value) = match var.split_once('=') {
+            Some((key, value)) => (
+                key.to_string(),
+                a3s_box_core::env::expand_braced_vars(value, environment)
+                    .map_err(|e| format!("Invalid --env {key}: {e}"))?,
+            ),
+            // A bare `--env KEY` copies the host value, as `docker run -e KEY` does.
+            None => (
+                var.clone(),
+                environment.get(var).cloned().unwrap_or_default(),
+            ),
+        };
+        env.insert(key, value);
     }
     Ok(env)
 }
@@ -395,21 +611,22 @@ pub(crate) fn normalize_port_maps(entries: &[String]) -> Result<Vec<String>, Str
 /// the shim/passt actually forward it and `port` prints the real number
 /// (matching Docker). A non-zero host port is returned unchanged.
 fn resolve_auto_host_port(entry: String) -> Result<String, String> {
-    let Some((host, guest)) = entry.split_once(':') else {
-        return Ok(entry);
-    };
-    if host != "0" {
+    let mut mapping = a3s_box_core::parse_port_mapping(&entry)?;
+    if mapping.host_port != 0 {
         return Ok(entry);
     }
-    let listener = std::net::TcpListener::bind("0.0.0.0:0")
+    let bind_ip = mapping
+        .host_ip
+        .unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED));
+    let listener = std::net::TcpListener::bind((bind_ip, 0))
         .map_err(|e| format!("failed to allocate a host port for '{entry}': {e}"))?;
-    let port = listener
+    mapping.host_port = listener
         .local_addr()
         .map_err(|e| format!("failed to read allocated host port: {e}"))?
         .port();
     // Release it so the box can bind the port (small TOCTOU window, as Docker has).
     drop(listener);
-    Ok(format!("{port}:{guest}"))
+    Ok(mapping.runtime_entry())
 }
 
 /// Reject runtime options that a3s-box cannot enforce yet.
@@ -427,11 +644,11 @@ pub(crate) fn validate_runtime_options(common: &CommonBoxArgs) -> Result<(), Str
         );
     }
 
-    if !common.device.is_empty() {
-        return Err(
-            "--device is not supported by the libkrun backend; device passthrough is not available"
-                .to_string(),
-        );
+    let (_, proxied) = a3s_box_core::device::partition_devices(&common.device)
+        .map_err(|e| format!("--device: {e}"))?;
+    for device in &proxied {
+        a3s_box_core::device::check_host_device(&device.source)
+            .map_err(|e| format!("--device: {e}"))?;
     }
     if common.gpus.is_some() {
         return Err("--gpus is not implemented; GPU passthrough is not available".to_string());
@@ -458,15 +675,112 @@ pub(crate) fn validate_runtime_options(common: &CommonBoxArgs) -> Result<(), Str
         );
     }
 
+    // KSM is a host-side opt-in; marking memory mergeable is a no-op while
+    // ksmd is stopped, so say so rather than let the operator assume savings.
+    if common.ksm && !a3s_box_runtime::ksm::host_state().is_running() {
+        eprintln!(
+            "a3s-box: warning: --ksm has no effect until host KSM is running (echo 1 > /sys/kernel/mm/ksm/run)"
+        );
+    }
+    if common.hugepages && !a3s_box_runtime::hugepages::host_thp_mode().allows_advised() {
+        eprintln!(
+            "a3s-box: warning: --hugepages has no effect while host transparent hugepages are disabled (echo madvise > /sys/kernel/mm/transparent_hugepage/enabled)"
+        );
+    }
+    if (common.hugepages || common.mem_prealloc) && !cfg!(target_os = "linux") {
+        eprintln!(
+            "a3s-box: warning: --hugepages and --mem-prealloc are only applied on Linux hosts"
+        );
+    }
+
+    let sysctls = sysctls(common)?;
+    for module in &common.kernel_module {
+        a3s_box_core::kernel::validate_kernel_module_name(module)
+            .map_err(|e| format!("--kernel-module: {e}"))?;
+    }
+
     normalize_user_option(common.user.as_deref())?;
+    validate_group_add_option(&common.group_add)?;
     validate_workdir_option(common.workdir.as_deref())?;
-    normalize_port_maps(&common.publish)?;
+    let port_maps = a3s_box_core::normalize_port_maps(&common.publish)?;
+    if common.network.is_none()
+        && port_maps
+            .iter()
+            .filter_map(|entry| a3s_box_core::parse_port_mapping(entry).ok())
+            .any(|mapping| mapping.host_ip.is_some())
+    {
+        // TSI publishes through libkrun, which always listens on every host
+        // address; only the bridge backends can honour a bound host IP.
+        return Err("publishing on a specific host IP requires --network".to_string());
+    }
     if let Some(hostname) = common.hostname.as_deref() {
         a3s_box_core::dns::validate_hostname(hostname)
             .map_err(|e| format!("Invalid --hostname: {e}"))?;
     }
     a3s_box_core::dns::parse_add_host_entries(&common.add_host)
         .map_err(|e| format!("Invalid --add-host: {e}"))?;
+    for domain in &common.dns_search {
+        a3s_box_core::dns::validate_dns_search(domain)
+            .map_err(|e| format!("Invalid --dns-search: {e}"))?;
+    }
+    for option in &common.dns_option {
+        a3s_box_core::dns::validate_dns_option(option)
+            .map_err(|e| format!("Invalid --dns-option: {e}"))?;
+    }
+    if !common.network_alias.is_empty() && common.network.is_none() {
+        return Err("--network-alias requires --network".to_string());
+    }
+    for alias in &common.network_alias {
+        a3s_box_core::dns::validate_hostname(alias)
+            .map_err(|e| format!("Invalid --network-alias: {e}"))?;
+    }
+    if !common.network_policy.is_empty() {
+        a3s_box_core::egress::EgressPolicy::parse_specs(&common.network_policy)?;
+    }
+    if common.http_proxy.is_some() {
+        #[cfg(windows)]
+        return Err("--http-proxy is not supported on Windows".to_string());
+        #[cfg(not(windows))]
+        if execution_isolation(common).is_sandbox() {
+            return Err("--http-proxy requires MicroVM isolation".to_string());
+        }
+    }
+    if common.network_max_conns == Some(0) {
+        return Err("--network-max-conns must be at least 1".to_string());
+    }
+    let egress_flag = if !common.network_policy.is_empty() {
+        Some("--network-policy")
+    } else if common.network_bandwidth.is_some() {
+        Some("--network-bandwidth")
+    } else if common.network_max_conns.is_some() {
+        Some("--network-max-conns")
+    } else {
+        None
+    };
+    if let Some(flag) = egress_flag {
+        if common.network.is_none() {
+            // TSI proxies guest sockets through the host, so the in-guest
+            // firewall and qdisc never see the traffic.
+            return Err(format!("{flag} requires --network"));
+        }
+        if common.privileged
+            || common.cap_add.iter().any(|cap| {
+                matches!(
+                    cap.to_ascii_uppercase().as_str(),
+                    "ALL" | "NET_ADMIN" | "CAP_NET_ADMIN"
+                )
+            })
+        {
+            return Err(format!(
+                "{flag} cannot be combined with --privileged or --cap-add NET_ADMIN"
+            ));
+        }
+        if common.http_proxy.is_some() {
+            // Proxied requests leave from the host, outside the guest's
+            // firewall and qdisc.
+            return Err(format!("{flag} cannot be combined with --http-proxy"));
+        }
+    }
 
     let network = match common.network.as_ref() {
         Some(network) => a3s_box_core::NetworkMode::Bridge {
@@ -482,6 +796,8 @@ pub(crate) fn validate_runtime_options(common: &CommonBoxArgs) -> Result<(), Str
         cap_drop: common.cap_drop.clone(),
         security_opt: common.security_opt.clone(),
         privileged: common.privileged,
+        sysctls,
+        kernel_modules: common.kernel_module.clone(),
         ..Default::default()
     };
     a3s_box_core::resolve_execution(&compatibility_config).map_err(|error| error.to_string())?;
@@ -579,6 +895,24 @@ pub(crate) fn validate_workdir_option(workdir: Option<&str>) -> Result<(), Strin
     Ok(())
 }
 
+/// Validate `--group-add` entries: a numeric GID or a bare group name.
+///
+/// Names are resolved against the image's `/etc/group` inside the guest, so
+/// only the shape is checked here.
+pub(crate) fn validate_group_add_option(groups: &[String]) -> Result<(), String> {
+    for group in groups {
+        if group.is_empty() {
+            return Err("--group-add must not be empty".to_string());
+        }
+        if group.contains([',', ':', '\0']) || group.chars().any(char::is_whitespace) {
+            return Err(format!(
+                "invalid --group-add '{group}' (expected a GID or group name)"
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// Parse a memory size string (e.g., "256m", "1g", "1073741824") into bytes.
 pub(crate) fn parse_memory_bytes(s: &str) -> Result<u64, String> {
     let s = s.trim().to_lowercase();
@@ -652,9 +986,18 @@ pub(crate) fn build_resource_limits(
         None => None,
     };
 
+    let cpuset_cpus = match args.numa_node {
+        Some(node) => Some(a3s_box_runtime::numa::resolve_numa_cpuset(
+            node,
+            args.cpuset_cpus.as_deref(),
+        )?),
+        None => args.cpuset_cpus.clone(),
+    };
+
     Ok(ResourceLimits {
         pids_limit: args.pids_limit,
-        cpuset_cpus: args.cpuset_cpus.clone(),
+        cpuset_cpus,
+        numa_node: args.numa_node,
         ulimits: args.ulimits.clone(),
         cpu_shares: args.cpu_shares,
         cpu_quota: args.cpu_quota,
@@ -662,9 +1005,50 @@ pub(crate) fn build_resource_limits(
         memory_reservation,
         memory_swap,
         sandbox_memory_limit_bytes: None,
+        disk_limit_bytes: parse_storage_opts(&args.storage_opt)?,
     })
 }
 
+/// Parse `--shm-size`; `None` leaves guest init's 64m default in place.
+pub(crate) fn parse_shm_size(value: Option<&str>) -> Result<Option<u64>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let bytes = parse_memory_bytes(value).map_err(|e| format!("Invalid --shm-size: {e}"))?;
+    // tmpfs treats size=0 as unlimited, which is never what `--shm-size 0` means.
+    if bytes == 0 {
+        return Err("Invalid --shm-size: must be greater than zero".to_string());
+    }
+    Ok(Some(bytes))
+}
+
+/// Parse `--storage-opt` values into a disk quota in bytes.
+pub(crate) fn parse_storage_opts(opts: &[String]) -> Result<Option<u64>, String> {
+    let mut size = None;
+    for opt in opts {
+        let Some((key, value)) = opt.split_once('=') else {
+            return Err(format!(
+                "Invalid --storage-opt '{opt}' (expected key=value)"
+            ));
+        };
+        match key.trim() {
+            "size" => {
+                let bytes = parse_memory_bytes(value.trim())
+                    .map_err(|e| format!("Invalid --storage-opt size: {e}"))?;
+                a3s_box_runtime::rootfs::quota::validate_disk_limit(bytes)
+                    .map_err(|e| format!("--storage-opt size: {e}"))?;
+                size = Some(bytes);
+            }
+            other => {
+                return Err(format!(
+                    "Unsupported --storage-opt '{other}' (supported: size)"
+                ))
+            }
+        }
+    }
+    Ok(size)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -682,6 +1066,11 @@ mod tests {
         assert_eq!(guest, "80");
         assert_ne!(host, "0");
         assert!(host.parse::<u16>().unwrap() > 0);
+        // A bound host address is kept while its port is resolved.
+        let resolved = resolve_auto_host_port("127.0.0.1:0:80".to_string()).unwrap();
+        let mapping = a3s_box_core::parse_port_mapping(&resolved).unwrap();
+        assert_eq!(mapping.host_ip, Some("127.0.0.1".parse().unwrap()));
+        assert_ne!(mapping.host_port, 0);
     }
 
     // --- parse_env_vars tests ---
@@ -787,6 +1176,15 @@ mod tests {
         assert!(validate_runtime_options(&args).is_ok());
     }
 
+    #[test]
+    fn test_validate_group_add_option() {
+        assert!(validate_group_add_option(&["video".to_string(), "999".to_string()]).is_ok());
+        assert!(validate_group_add_option(&[String::new()]).is_err());
+        let err = validate_group_add_option(&["video,audio".to_string()]).unwrap_err();
+        assert!(err.contains("video,audio"), "got: {err}");
+        assert!(validate_group_add_option(&["wheel:10".to_string()]).is_err());
+    }
+
     #[test]
     fn test_parse_memory_bytes_kilobytes() {
         assert_eq!(parse_memory_bytes("1k").unwrap(), 1024);
@@ -916,6 +1314,56 @@ mod tests {
         assert_eq!(map.get("BAZ").map(String::as_str), Some("cli"));
     }
 
+    #[test]
+    fn test_build_env_map_later_env_file_wins_and_env_interpolates() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.env");
+        let second = dir.path().join("second.env");
+        std::fs::write(&first, "MODEL=small\nREGION=us\n").unwrap();
+        std::fs::write(&second, "MODEL='large'\nPASSWORD=pa$$word$KEY\n").unwrap();
+        let environment = HashMap::from([
+            ("KEY".to_string(), "sk-test".to_string()),
+            ("HOST_ONLY".to_string(), "from-host".to_string()),
+        ]);
+        let mut args = default_common_args();
+        args.env_file = vec![
+            first.to_string_lossy().to_string(),
+            second.to_string_lossy().to_string(),
+        ];
+        args.env = vec![
+            "API_KEY=${KEY}".to_string(),
+            "DB_PASSWORD=ab$cd$KEY".to_string(),
+            "REGION_HINT=${UNSET:-eu}".to_string(),
+            "HOST_ONLY".to_string(),
+        ];
+
+        let map = build_env_map_with_environment(&args, &environment).unwrap();
+
+        assert_eq!(map.get("MODEL").map(String::as_str), Some("large"));
+        assert_eq!(map.get("REGION").map(String::as_str), Some("us"));
+        // Env file values are not interpolated.
+        assert_eq!(
+            map.get("PASSWORD").map(String::as_str),
+            Some("pa$$word$KEY")
+        );
+        assert_eq!(map.get("API_KEY").map(String::as_str), Some("sk-test"));
+        // A bare `$` in --env is literal, as with docker run -e.
+        assert_eq!(
+            map.get("DB_PASSWORD").map(String::as_str),
+            Some("ab$cd$KEY")
+        );
+        assert_eq!(map.get("REGION_HINT").map(String::as_str), Some("eu"));
+        assert_eq!(map.get("HOST_ONLY").map(String::as_str), Some("from-host"));
+
+        for env in ["API_KEY=${UNSET}", "API_KEY=${UNSET:?needed}"] {
+            args.env = vec![env.to_string()];
+            let err = build_env_map_with_environment(&args, &environment)
+                .unwrap_err()
+                .to_string();
+            assert!(err.starts_with("Invalid --env API_KEY: "), "{err}");
+        }
+    }
+
     // --- build_resource_limits tests ---
 
     /// Helper to create a CommonBoxArgs with defaults for testing.
@@ -927,18 +1375,27 @@ mod tests {
             cpus: DEFAULT_VCPUS,
             memory: "512m".to_string(),
             volumes: vec![],
+            mounts: vec![],
             env: vec![],
             publish: vec![],
             dns: vec![],
+            dns_search: vec![],
+            dns_option: vec![],
             entrypoint: None,
             hostname: None,
             user: None,
+            group_add: vec![],
             workdir: None,
             restart: "no".to_string(),
             labels: vec![],
             tmpfs: vec![],
             virtiofs_cache: None,
             network: None,
+            network_alias: vec![],
+            network_policy: vec![],
+            network_bandwidth: None,
+            network_max_conns: None,
+            http_proxy: None,
             health_cmd: None,
             health_interval: 30,
             health_timeout: 5,
@@ -946,12 +1403,14 @@ mod tests {
             health_start_period: 0,
             pids_limit: None,
             cpuset_cpus: None,
+            numa_node: None,
             ulimits: vec![],
             cpu_shares: None,
             cpu_quota: None,
             cpu_period: None,
             memory_reservation: None,
             memory_swap: None,
+            storage_opt: vec![],
             env_file: vec![],
             add_host: vec![],
             platform: None,
@@ -969,16 +1428,55 @@ mod tests {
             no_healthcheck: false,
             oom_kill_disable: false,
             oom_score_adj: None,
+            ksm: false,
+            hugepages: false,
+            mem_prealloc: false,
+            sysctl: vec![],
+            kernel_module: vec![],
             persistent: false,
+            workspace_template: None,
+            workspace_var: vec![],
         }
     }
 
+    #[test]
+    fn test_workspace_template_resolves_sources() {
+        let mut args = default_common_args();
+        assert!(workspace_template(&args).unwrap().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        args.workspace_template = Some(dir.path().display().to_string());
+        args.workspace_var = vec!["TASK=demo".to_string()];
+        let template = workspace_template(&args).unwrap().unwrap();
+        assert_eq!(
+            template.source,
+            dir.path().canonicalize().unwrap().display().to_string()
+        );
+        assert_eq!(
+            template.vars,
+            vec![("TASK".to_string(), "demo".to_string())]
+        );
+
+        args.workspace_template = Some("https://example.com/template.git#main".to_string());
+        assert_eq!(
+            workspace_template(&args).unwrap().unwrap().source,
+            "https://example.com/template.git#main"
+        );
+
+        args.workspace_var = vec!["TASK".to_string()];
+        assert!(workspace_template(&args).is_err());
+        args.workspace_var.clear();
+        args.workspace_template = Some(dir.path().join("missing").display().to_string());
+        assert!(workspace_template(&args).is_err());
+    }
+
     #[test]
     fn test_build_resource_limits_defaults() {
         let args = default_common_args();
         let limits = build_resource_limits(&args).unwrap();
         assert!(limits.pids_limit.is_none());
         assert!(limits.cpuset_cpus.is_none());
+        assert!(limits.numa_node.is_none());
         assert!(limits.cpu_shares.is_none());
         assert!(limits.memory_reservation.is_none());
         assert!(limits.memory_swap.is_none());
@@ -1116,13 +1614,26 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_runtime_options_rejects_devices() {
+    fn test_validate_runtime_options_checks_devices() {
         let mut args = default_common_args();
         args.device = vec!["/dev/fuse".to_string()];
+        assert!(validate_runtime_options(&args).is_ok());
 
+        #[cfg(unix)]
+        {
+            args.device = vec!["/dev/null:/dev/ttyUSB0".to_string()];
+            let err = validate_runtime_options(&args).unwrap_err();
+            assert!(err.contains("allowlist"), "got: {err}");
+            args.device = vec!["/dev/mem".to_string()];
+            assert!(validate_runtime_options(&args).is_err());
+        }
+
+        args.device = vec!["/dev/a3s-missing".to_string()];
         let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("--device"), "got: {err}");
 
-        assert!(err.contains("--device is not supported"));
+        args.device = vec!["/etc/passwd:/dev/passwd".to_string()];
+        assert!(validate_runtime_options(&args).is_err());
     }
 
     #[test]
@@ -1251,6 +1762,95 @@ mod tests {
         validate_runtime_options(&args).unwrap();
     }
 
+    #[test]
+    fn test_validate_runtime_options_network_alias_requires_network() {
+        let mut args = default_common_args();
+        args.network_alias = vec!["db".to_string()];
+
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("requires --network"));
+
+        args.network = Some("backend".to_string());
+        validate_runtime_options(&args).unwrap();
+
+        args.network_alias = vec!["bad alias".to_string()];
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("Invalid --network-alias"));
+    }
+
+    #[test]
+    fn test_validate_runtime_options_network_policy() {
+        let mut args = default_common_args();
+        args.network_policy = vec!["allow=api.anthropic.com".to_string()];
+
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("requires --network"));
+
+        args.network = Some("agents".to_string());
+        validate_runtime_options(&args).unwrap();
+
+        args.cap_add = vec!["net_admin".to_string()];
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("cannot be combined"));
+
+        args.cap_add.clear();
+        args.network_policy = vec!["block=10.0.0.0/8".to_string()];
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("unknown list 'block'"));
+    }
+
+    #[test]
+    fn test_validate_runtime_options_network_limits() {
+        let mut args = default_common_args();
+        args.network_bandwidth = Some(1_250_000);
+
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("--network-bandwidth requires --network"));
+
+        args.network = Some("agents".to_string());
+        args.network_max_conns = Some(64);
+        validate_runtime_options(&args).unwrap();
+
+        args.privileged = true;
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("cannot be combined"));
+
+        args.privileged = false;
+        args.network_max_conns = Some(0);
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("at least 1"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_validate_runtime_options_http_proxy() {
+        let mut args = default_common_args();
+        args.http_proxy = Some(a3s_box_core::http_proxy::HttpProxyMode::Mitm);
+        validate_runtime_options(&args).unwrap();
+
+        args.network = Some("agents".to_string());
+        args.network_policy = vec!["allow=api.anthropic.com".to_string()];
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("--network-policy cannot be combined with --http-proxy"));
+
+        args.network_policy.clear();
+        args.isolation = Some(IsolationArg::Sandbox);
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("--http-proxy requires MicroVM isolation"));
+    }
+
+    #[test]
+    fn test_validate_runtime_options_host_ip_port_requires_network() {
+        let mut args = default_common_args();
+        args.publish = vec!["[::1]:8080:80".to_string()];
+
+        let err = validate_runtime_options(&args).unwrap_err();
+        assert!(err.contains("specific host IP requires --network"));
+
+        args.network = Some("backend".to_string());
+        validate_runtime_options(&args).unwrap();
+    }
+
     #[test]
     fn test_validate_runtime_options_rejects_unsupported_security_opts() {
         let mut args = default_common_args();
@@ -1306,6 +1906,15 @@ mod tests {
         assert_eq!(limits.memory_swap, Some(-1));
     }
 
+    #[test]
+    fn test_build_resource_limits_rejects_unknown_numa_node() {
+        let mut args = default_common_args();
+        args.numa_node = Some(u32::MAX);
+
+        let err = build_resource_limits(&args).unwrap_err();
+        assert!(err.to_string().contains("--numa-node"), "got: {err}");
+    }
+
     #[test]
     fn test_build_resource_limits_memory_swap_value() {
         let mut args = default_common_args();
@@ -1314,4 +1923,34 @@ mod tests {
         let limits = build_resource_limits(&args).unwrap();
         assert_eq!(limits.memory_swap, Some(1024 * 1024 * 1024));
     }
+
+    #[test]
+    fn test_parse_shm_size() {
+        assert_eq!(parse_shm_size(None).unwrap(), None);
+        assert_eq!(
+            parse_shm_size(Some("1g")).unwrap(),
+            Some(1024 * 1024 * 1024)
+        );
+        assert!(parse_shm_size(Some("0"))
+            .unwrap_err()
+            .contains("greater than zero"));
+        assert!(parse_shm_size(Some("lots")).is_err());
+    }
+
+    #[test]
+    fn test_parse_storage_opts() {
+        assert_eq!(parse_storage_opts(&[]).unwrap(), None);
+        assert!(parse_storage_opts(&["size=1m".to_string()])
+            .unwrap_err()
+            .contains("at least 64 MiB"));
+        assert!(parse_storage_opts(&["size".to_string()]).is_err());
+        assert!(parse_storage_opts(&["inodes=10".to_string()])
+            .unwrap_err()
+            .contains("Unsupported"));
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            parse_storage_opts(&["size=1g".to_string()]).unwrap(),
+            Some(1024 * 1024 * 1024)
+        );
+    }
 }
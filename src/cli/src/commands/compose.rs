@@ -38,11 +38,16 @@ const LABEL_PROJECT: &str = "com.a3s.compose.project";
 const LABEL_SERVICE: &str = "com.a3s.compose.service";
 /// Label key for the normalized service configuration digest.
 const LABEL_CONFIG_HASH: &str = "com.a3s.compose.config-hash";
+/// Label key for the `depends_on` entries honoured when the monitor restarts a
+/// service box (`db=healthy,cache=started`).
+const LABEL_DEPENDS_ON: &str = "com.a3s.compose.depends-on";
 type ExistingService = (ServiceBox, Option<String>);
 
 /// Default compose file names to search for.
 const COMPOSE_FILES: &[&str] = &[
     "compose.acl",
+    "a3s-compose.yaml",
+    "a3s-compose.yml",
     "compose.yaml",
     "compose.yml",
     "docker-compose.yaml",
@@ -153,9 +158,9 @@ fn load_compose_file_with_environment(
         .join(".env");
     match std::fs::read_to_string(&environment_path) {
         Ok(contents) => {
-            for (key, value) in a3s_box_core::env::parse_env_file_content(&contents) {
-                environment.insert(key, value);
-            }
+            let entries = a3s_box_core::env::parse_env_file_content(&contents)
+                .map_err(|e| format!("Invalid {}: {e}", environment_path.display()))?;
+            environment.extend(entries);
         }
         Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
         Err(error) => {
@@ -505,16 +510,37 @@ async fn execute_up(
             }
         };
         box_config.volumes = resolved_volumes.clone();
+        // Resolve `0:guest` to a concrete host port so passt forwards it and
+        // the record shows the port `compose port` and `ps` report.
+        box_config.port_map = match common::normalize_port_maps(&box_config.port_map) {
+            Ok(port_map) => port_map,
+            Err(error) => {
+                return rollback_compose_up(
+                    &mut state,
+                    &started_services,
+                    &created_networks,
+                    format!("Invalid ports for service '{}': {}", svc_name, error),
+                )
+                .await;
+            }
+        };
+        let record_port_map = box_config.port_map.clone();
         let image = box_config.image.clone();
         let record_env: HashMap<String, String> = box_config.extra_env.iter().cloned().collect();
         let record_hostname = box_config.hostname.clone();
         let record_add_hosts = box_config.add_hosts.clone();
+        let record_dns = box_config.dns.clone();
         let network_mode = box_config.network.clone();
         let record_isolation = box_config.isolation;
         let network_name = match &network_mode {
             a3s_box_core::NetworkMode::Bridge { network } => Some(network.clone()),
             _ => None,
         };
+        let record_network_aliases = if network_name.is_some() {
+            project.service_network_aliases(svc_name)
+        } else {
+            Vec::new()
+        };
 
         // Create VmManager and boot
         let emitter = EventEmitter::new(256);
@@ -553,7 +579,7 @@ async fn execute_up(
 
         // Connect to network before boot
         if let Some(net_name) = network_name.as_deref() {
-            let network_aliases = project.service_network_aliases(svc_name);
+            let network_aliases = record_network_aliases.clone();
             // Atomic load → validate → allocate-IP → save under the store's
             // cross-process lock. A get → connect → update reads the network
             // outside the lock, so a concurrent connect (another compose up, or
@@ -644,10 +670,11 @@ async fn execute_up(
         labels.insert(LABEL_PROJECT.to_string(), project_name.to_string());
         labels.insert(LABEL_SERVICE.to_string(), svc_name.to_string());
         labels.insert(LABEL_CONFIG_HASH.to_string(), config_hash);
+        if let Some(depends_on) = svc.and_then(depends_on_label) {
+            labels.insert(LABEL_DEPENDS_ON.to_string(), depends_on);
+        }
 
         // Get service config for extra fields
-        let port_map: Vec<String> = svc.map(|s| s.ports.clone()).unwrap_or_default();
-
         // Compose healthcheck overrides image HEALTHCHECK; disable blocks fallback.
         let service_health_check = project.healthcheck(svc_name).map(|hc| HealthCheck {
             cmd: hc.cmd,
@@ -708,7 +735,7 @@ async fn execute_up(
             user: None,
             workdir: svc.and_then(|s| s.working_dir.clone()),
             restart_policy,
-            port_map,
+            port_map: record_port_map,
             labels,
             stopped_by_user: false,
             restart_count: 0,
@@ -727,6 +754,15 @@ async fn execute_up(
             resource_limits: Default::default(),
             log_config: Default::default(),
             add_host: record_add_hosts,
+            dns: record_dns,
+            dns_search: vec![],
+            dns_option: vec![],
+            network_aliases: record_network_aliases,
+            network_policy: vec![],
+            network_bandwidth: None,
+            network_max_conns: None,
+            http_proxy: None,
+            socket_mounts: vec![],
             platform: None,
             init: false,
             read_only: false,
@@ -741,6 +777,8 @@ async fn execute_up(
             stop_timeout: None,
             oom_kill_disable: false,
             oom_score_adj: None,
+            workspace_template: None,
+            metadata: common::created_by_metadata(),
         };
 
         let service_box = ServiceBox::from_record(&record);
@@ -869,6 +907,57 @@ fn find_existing_service(
     }))
 }
 
+/// Encode a service's `started`/`healthy` dependencies for [`LABEL_DEPENDS_ON`].
+///
+/// `service_completed_successfully` dependencies are one-shot setup jobs and
+/// are only waited on by `compose up`, not on every restart.
+fn depends_on_label(service: &ServiceConfig) -> Option<String> {
+    use a3s_box_core::compose::DependsOn;
+
+    let mut entries = match &service.depends_on {
+        DependsOn::Empty => vec![],
+        DependsOn::List(services) => services
+            .iter()
+            .map(|name| format!("{name}=started"))
+            .collect(),
+        DependsOn::Map(map) => map
+            .iter()
+            .filter_map(|(name, condition)| match condition.condition.as_str() {
+                "service_started" => Some(format!("{name}=started")),
+                "service_healthy" => Some(format!("{name}=healthy")),
+                _ => None,
+            })
+            .collect(),
+    };
+    entries.sort();
+    (!entries.is_empty()).then(|| entries.join(","))
+}
+
+/// First unmet dependency of a Compose service box, rendered as
+/// `"<service> (<condition>)"`.
+///
+/// `started` needs the dependency's box to be active; `healthy` additionally
+/// needs a passing health check. A dependency whose box was removed is unmet,
+/// so the dependent waits instead of booting against a missing peer.
+pub(crate) fn unmet_dependency(records: &[BoxRecord], record: &BoxRecord) -> Option<String> {
+    let project = record.labels.get(LABEL_PROJECT)?;
+    let depends_on = record.labels.get(LABEL_DEPENDS_ON)?;
+    depends_on
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .find(|(service, condition)| {
+            let dependency = records.iter().find(|candidate| {
+                candidate.labels.get(LABEL_PROJECT) == Some(project)
+                    && candidate.labels.get(LABEL_SERVICE).map(String::as_str) == Some(*service)
+            });
+            !dependency.is_some_and(|dependency| {
+                status::is_active(dependency)
+                    && (*condition != "healthy" || dependency.health_status == "healthy")
+            })
+        })
+        .map(|(service, condition)| format!("{service} ({condition})"))
+}
+
 fn resolve_service_volumes(
     volume_specs: &[String],
 ) -> Result<(Vec<String>, Vec<String>), Box<dyn std::error::Error>> {
@@ -211,13 +211,17 @@ pub async fn execute_start(
 ) -> Result<(), Box<dyn std::error::Error>> {
     let boxes = select_boxes(project_name, config, &args.services, false)?;
     let queries = matching_ids(&boxes, |status| {
-        matches!(status, "created" | "stopped" | "dead")
+        matches!(status, "created" | "stopped" | "dead" | "waiting")
     });
     if queries.is_empty() {
         println!("All selected services are already active.");
         return Ok(());
     }
-    super::super::start::execute(super::super::start::StartArgs { boxes: queries }).await
+    super::super::start::execute(super::super::start::StartArgs {
+        boxes: queries,
+        from_checkpoint: None,
+    })
+    .await
 }
 
 pub async fn execute_stop(
@@ -390,7 +394,7 @@ pub async fn execute_port(
             continue;
         }
         found = true;
-        println!("0.0.0.0:{}", mapping.host_port);
+        println!("{}", mapping.host_address());
     }
     if requested.is_some() && !found {
         return Err(format!(
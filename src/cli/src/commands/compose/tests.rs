@@ -128,8 +128,9 @@ fn test_load_compose_file_rejects_unknown_yaml_fields_with_structured_path() {
 
 #[test]
 fn test_compose_files_constant() {
-    assert_eq!(COMPOSE_FILES.len(), 5);
+    assert_eq!(COMPOSE_FILES.len(), 7);
     assert_eq!(COMPOSE_FILES[0], "compose.acl");
+    assert!(COMPOSE_FILES.contains(&"a3s-compose.yaml"));
     assert!(COMPOSE_FILES.contains(&"compose.yaml"));
     assert!(COMPOSE_FILES.contains(&"docker-compose.yml"));
 }
@@ -147,6 +148,18 @@ fn test_default_discovery_prefers_compose_acl() {
     assert_eq!(selected, acl_path);
 }
 
+#[test]
+fn test_default_discovery_prefers_a3s_compose_yaml_over_compose_yaml() {
+    let directory = tempfile::TempDir::new().unwrap();
+    let a3s_path = directory.path().join("a3s-compose.yaml");
+    std::fs::write(&a3s_path, "services: {}\n").unwrap();
+    std::fs::write(directory.path().join("compose.yaml"), "services: {}\n").unwrap();
+
+    let selected = resolve_compose_path(None, directory.path()).unwrap();
+
+    assert_eq!(selected, a3s_path);
+}
+
 #[test]
 fn test_load_compose_acl_uses_dotenv_and_shell_environment() {
     let directory = tempfile::TempDir::new().unwrap();
@@ -399,3 +412,69 @@ fn windows_compose_without_declared_health_continues_to_image_resolution() {
     let image_config = cached_image_config_with_health(None);
     assert!(validate_known_compose_health("api", false, None, Some(&image_config)).is_ok());
 }
+
+#[test]
+fn depends_on_label_keeps_started_and_healthy_conditions() {
+    use a3s_box_core::compose::{DependsOn, DependsOnCondition};
+
+    let condition = |condition: &str| DependsOnCondition {
+        condition: condition.to_string(),
+    };
+    let service = ServiceConfig {
+        depends_on: DependsOn::Map(HashMap::from([
+            ("db".to_string(), condition("service_healthy")),
+            ("cache".to_string(), condition("service_started")),
+            (
+                "migrate".to_string(),
+                condition("service_completed_successfully"),
+            ),
+        ])),
+        ..Default::default()
+    };
+    assert_eq!(
+        depends_on_label(&service).as_deref(),
+        Some("cache=started,db=healthy")
+    );
+    assert_eq!(depends_on_label(&ServiceConfig::default()), None);
+}
+
+#[test]
+fn unmet_dependency_requires_active_and_healthy_peers() {
+    use crate::test_helpers::fixtures::make_record;
+
+    let service_box = |id: &str, service: &str, status: &str| {
+        let mut record = make_record(id, &format!("app-{service}"), status, None);
+        record
+            .labels
+            .insert(LABEL_PROJECT.to_string(), "app".to_string());
+        record
+            .labels
+            .insert(LABEL_SERVICE.to_string(), service.to_string());
+        record
+    };
+    let mut api = service_box("id-api", "api", "dead");
+    api.labels.insert(
+        LABEL_DEPENDS_ON.to_string(),
+        "cache=started,db=healthy".to_string(),
+    );
+    let cache = service_box("id-cache", "cache", "running");
+    let mut db = service_box("id-db", "db", "running");
+    db.health_status = "starting".to_string();
+
+    // A removed dependency is unmet.
+    assert_eq!(
+        unmet_dependency(&[api.clone(), db.clone()], &api).as_deref(),
+        Some("cache (started)")
+    );
+    let mut records = vec![api.clone(), cache, db];
+    assert_eq!(
+        unmet_dependency(&records, &api).as_deref(),
+        Some("db (healthy)")
+    );
+    records[2].health_status = "healthy".to_string();
+    assert_eq!(unmet_dependency(&records, &api), None);
+
+    // Boxes outside Compose never wait.
+    let plain = make_record("id-plain", "plain", "dead", None);
+    assert_eq!(unmet_dependency(&records, &plain), None);
+}
@@ -1,8 +1,16 @@
 //! `a3s-box cp` command — Copy files or directories between host and a running box.
 //!
-//! Uses the exec channel to transfer content via base64 encoding.
-//! Single files are transferred as raw base64. Directories are archived
-//! with `tar` before transfer.
+//! Both directions stream a tar archive over the exec channel: `tar` runs on
+//! the sending side and its output is fed chunk by chunk to `tar` on the
+//! receiving side, so nothing is buffered whole in memory or passed on a
+//! command line. Directories, symlinks (copied as links), modes and, when the
+//! receiving side runs as root, ownership survive the copy. Sparse files stay
+//! sparse when the sending `tar` supports `--sparse` (GNU tar).
+//!
+//! A directory source copies its contents into the destination directory,
+//! creating it if needed. A file or symlink source lands inside the
+//! destination when that is an existing directory, and otherwise becomes the
+//! destination path.
 //!
 //! Syntax:
 //!   a3s-box cp <box>:/path/in/box /host/path   (box → host)
@@ -11,18 +19,34 @@
 use clap::Args;
 
 #[cfg(not(windows))]
-use a3s_box_core::exec::{ExecRequest, DEFAULT_EXEC_TIMEOUT_NS};
+use std::path::{Path, PathBuf};
+#[cfg(not(windows))]
+use std::process::Stdio;
+
+#[cfg(not(windows))]
+use a3s_box_core::exec::{ExecEvent, ExecRequest, StreamType, DEFAULT_EXEC_TIMEOUT_NS};
 #[cfg(not(windows))]
-use a3s_box_runtime::ExecClient;
+use a3s_box_runtime::{ExecClient, StreamingExec};
 
+#[cfg(not(windows))]
+use crate::output;
 #[cfg(not(windows))]
 use crate::resolve;
 #[cfg(not(windows))]
 use crate::state::StateFile;
 
-/// Timeout for directory transfers (60 seconds).
+/// Upper bound for one streamed transfer (1 hour).
+#[cfg(not(windows))]
+const TRANSFER_TIMEOUT_NS: u64 = 3_600_000_000_000;
+
+/// Bytes read from the host `tar` per stdin frame sent to the box.
 #[cfg(not(windows))]
-const DIR_TRANSFER_TIMEOUT_NS: u64 = 60_000_000_000;
+const STREAM_CHUNK_BYTES: usize = 256 * 1024;
+
+/// Guest shell prefix that sets `$S` to `--sparse` when the box's `tar`
+/// supports it (GNU tar; BusyBox does not).
+#[cfg(not(windows))]
+const GUEST_SPARSE_PROBE: &str = "S=; tar --help 2>&1 | grep -q -e --sparse && S=--sparse; ";
 
 #[derive(Args)]
 pub struct CpArgs {
@@ -31,6 +55,10 @@ pub struct CpArgs {
 
     /// Destination path (HOST_PATH or BOX:CONTAINER_PATH)
     pub dst: String,
+
+    /// Suppress progress output during the copy
+    #[arg(short, long)]
+    pub quiet: bool,
 }
 
 /// Parsed copy endpoint — either a host path or a box:path pair.
@@ -73,10 +101,10 @@ pub async fn execute(args: CpArgs) -> Result<(), Box<dyn std::error::Error>> {
 
         match (src, dst) {
             (Endpoint::Box { name, path }, Endpoint::Host(host_path)) => {
-                copy_from_box(&name, &path, &host_path).await
+                copy_from_box(&name, &path, &host_path, args.quiet).await
             }
             (Endpoint::Host(host_path), Endpoint::Box { name, path }) => {
-                copy_to_box(&host_path, &name, &path).await
+                copy_to_box(&host_path, &name, &path, args.quiet).await
             }
             (Endpoint::Host(_), Endpoint::Host(_)) => Err(
                 "Both source and destination are host paths. One must be a box path (BOX:/path)."
@@ -89,20 +117,73 @@ pub async fn execute(args: CpArgs) -> Result<(), Box<dyn std::error::Error>> {
     } // #[cfg(not(windows))]
 }
 
+/// What a copy source is, decided before the transfer starts.
+#[cfg(not(windows))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SourceKind {
+    /// A real directory: its contents are archived.
+    Directory,
+    /// A file, symlink or other single entry: archived under its own name.
+    Entry,
+}
+
 /// Copy a file or directory from a box to the host.
 #[cfg(not(windows))]
 async fn copy_from_box(
     box_name: &str,
     box_path: &str,
     host_path: &str,
+    quiet: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let client = connect_exec(box_name).await?;
+    let kind = box_source_kind(&client, box_path).await?;
+
+    // A single entry is unpacked into a staging directory beside the
+    // destination and renamed into place, unless the destination is an
+    // existing directory it can land in directly.
+    let mut staging = None;
+    let (unpack_dir, name) = match kind {
+        SourceKind::Directory => {
+            std::fs::create_dir_all(host_path)
+                .map_err(|e| format!("Failed to create directory {host_path}: {e}"))?;
+            (PathBuf::from(host_path), None)
+        }
+        SourceKind::Entry => {
+            let (_, name) = split_entry(box_path);
+            let host = Path::new(host_path);
+            if is_real_dir(host) {
+                (host.to_path_buf(), None)
+            } else {
+                let parent = host
+                    .parent()
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                let dir = tempfile::Builder::new()
+                    .prefix(".a3s-cp-")
+                    .tempdir_in(parent)
+                    .map_err(|e| format!("Failed to stage copy in {}: {e}", parent.display()))?;
+                let unpack_dir = dir.path().to_path_buf();
+                staging = Some(dir);
+                (unpack_dir, Some(name))
+            }
+        }
+    };
 
-    if is_directory_in_box(&client, box_path).await? {
-        copy_dir_from_box(&client, box_name, box_path, host_path).await
-    } else {
-        copy_file_from_box(&client, box_name, box_path, host_path).await
+    let request = transfer_request(archive_script(box_path, kind), false);
+    let stream = client.exec_stream(&request).await?;
+    let bytes = receive_archive(stream, &unpack_dir, box_path, quiet).await?;
+
+    if let Some(name) = name {
+        std::fs::rename(unpack_dir.join(&name), host_path)
+            .map_err(|e| format!("Failed to move {name} to {host_path}: {e}"))?;
     }
+    drop(staging);
+
+    println!(
+        "{box_name}:{box_path} → {host_path} ({} archived)",
+        output::format_bytes(bytes)
+    );
+    Ok(())
 }
 
 /// Copy a file or directory from the host to a box.
@@ -111,321 +192,337 @@ async fn copy_to_box(
     host_path: &str,
     box_name: &str,
     box_path: &str,
+    quiet: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let meta =
-        std::fs::metadata(host_path).map_err(|e| format!("Failed to stat {host_path}: {e}"))?;
-
-    let client = connect_exec(box_name).await?;
-
-    if meta.is_dir() {
-        copy_dir_to_box(&client, host_path, box_name, box_path).await
+    let meta = std::fs::symlink_metadata(host_path)
+        .map_err(|e| format!("Failed to stat {host_path}: {e}"))?;
+    let (kind, dir, name) = if meta.is_dir() {
+        (
+            SourceKind::Directory,
+            host_path.to_string(),
+            ".".to_string(),
+        )
     } else {
-        copy_file_to_box(&client, host_path, box_name, box_path).await
-    }
-}
-
-/// Check if a path is a directory inside the box.
-#[cfg(not(windows))]
-async fn is_directory_in_box(
-    client: &ExecClient,
-    box_path: &str,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    let request = ExecRequest {
-        request_id: None,
-        cmd: vec!["test".to_string(), "-d".to_string(), box_path.to_string()],
-        timeout_ns: DEFAULT_EXEC_TIMEOUT_NS,
-        env: vec![],
-        working_dir: None,
-        rootfs: None,
-        stdin: None,
-        stdin_streaming: false,
-        user: None,
-        streaming: false,
+        let (dir, name) = split_entry(host_path);
+        (SourceKind::Entry, dir, name)
     };
 
-    let output = client.exec_command(&request).await?;
-    Ok(output.exit_code == 0)
-}
-
-// ---------------------------------------------------------------------------
-// Single-file transfers
-// ---------------------------------------------------------------------------
-
-/// Copy a single file from a box to the host.
-#[cfg(not(windows))]
-async fn copy_file_from_box(
-    client: &ExecClient,
-    box_name: &str,
-    box_path: &str,
-    host_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let request = ExecRequest {
-        request_id: None,
-        cmd: vec![
-            "sh".to_string(),
-            "-c".to_string(),
-            format!("base64 < {}", shell_escape(box_path)),
-        ],
-        timeout_ns: DEFAULT_EXEC_TIMEOUT_NS,
-        env: vec![],
-        working_dir: None,
-        rootfs: None,
-        stdin: None,
-        stdin_streaming: false,
-        user: None,
-        streaming: false,
-    };
+    let client = connect_exec(box_name).await?;
 
-    let output = client.exec_command(&request).await?;
+    let mut tar = host_tar_create(Path::new(&dir), &name)
+        .spawn()
+        .map_err(|e| format!("Failed to run tar: {e}"))?;
+    let mut tar_stdout = tar.stdout.take().ok_or("tar stdout unavailable")?;
+
+    let request = transfer_request(extract_script(box_path, kind, &name), true);
+    let mut stream = client.exec_stream(&request).await?;
+
+    // Feed the archive from a separate task so guest output keeps being read
+    // while stdin frames are in flight.
+    let input = stream.input();
+    let pump = tokio::spawn(async move {
+        use tokio::io::AsyncReadExt;
+
+        let mut progress = Progress::new(quiet);
+        let mut buf = vec![0u8; STREAM_CHUNK_BYTES];
+        loop {
+            let n = tar_stdout
+                .read(&mut buf)
+                .await
+                .map_err(|e| format!("Failed to read tar output: {e}"))?;
+            if n == 0 {
+                break;
+            }
+            input
+                .write_stdin(&buf[..n])
+                .await
+                .map_err(|e| e.to_string())?;
+            progress.add(n);
+        }
+        progress.finish();
+        input.close_stdin().await.map_err(|e| e.to_string())?;
+        Ok::<u64, String>(progress.bytes)
+    });
+
+    let (exit_code, stderr) = drain_guest_output(&mut stream).await?;
+    let sent = pump.await.map_err(|e| format!("Copy task failed: {e}"))?;
+    let tar_output = tar
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to wait for tar: {e}"))?;
 
-    if output.exit_code != 0 {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to read {box_path} in box: {stderr}").into());
+    if !tar_output.status.success() {
+        let stderr = String::from_utf8_lossy(&tar_output.stderr);
+        return Err(format!("tar failed: {}", stderr.trim()).into());
     }
-
-    use base64::Engine;
-    let encoded = String::from_utf8_lossy(&output.stdout);
-    let clean: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
-    let decoded = base64::engine::general_purpose::STANDARD
-        .decode(&clean)
-        .map_err(|e| format!("Failed to decode file content: {e}"))?;
-
-    std::fs::write(host_path, &decoded)
-        .map_err(|e| format!("Failed to write to {host_path}: {e}"))?;
+    if exit_code != 0 {
+        return Err(format!(
+            "Failed to extract archive in box at {box_path}: {}",
+            String::from_utf8_lossy(&stderr).trim()
+        )
+        .into());
+    }
+    let sent = sent?;
 
     println!(
-        "{box_name}:{box_path} → {host_path} ({} bytes)",
-        decoded.len()
+        "{host_path} → {box_name}:{box_path} ({} archived)",
+        output::format_bytes(sent)
     );
     Ok(())
 }
 
-/// Copy a single file from the host to a box.
+/// Whether `box_path` is a real directory or a single entry in the box.
 #[cfg(not(windows))]
-async fn copy_file_to_box(
+async fn box_source_kind(
     client: &ExecClient,
-    host_path: &str,
-    box_name: &str,
     box_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let content =
-        std::fs::read(host_path).map_err(|e| format!("Failed to read {host_path}: {e}"))?;
-    let len = content.len();
-    let mode = host_file_mode(host_path);
-
-    // Stream the raw bytes over the exec channel's stdin (not the command line)
-    // so large files do not exceed ARG_MAX, and restore the source file's mode
-    // (Docker `cp` preserves permissions).
-    let dst = shell_escape(box_path);
+) -> Result<SourceKind, Box<dyn std::error::Error>> {
+    let path = shell_escape(box_path);
     let request = ExecRequest {
         request_id: None,
         cmd: vec![
             "sh".to_string(),
             "-c".to_string(),
-            format!("cat > {dst} && chmod {mode:o} {dst}"),
+            format!(
+                "if [ -L {path} ]; then echo entry; elif [ -d {path} ]; then echo dir; \
+                 elif [ -e {path} ]; then echo entry; else exit 1; fi"
+            ),
         ],
         timeout_ns: DEFAULT_EXEC_TIMEOUT_NS,
         env: vec![],
         working_dir: None,
         rootfs: None,
-        stdin: Some(content),
+        stdin: None,
         stdin_streaming: false,
         user: None,
         streaming: false,
     };
 
     let output = client.exec_command(&request).await?;
-
     if output.exit_code != 0 {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to write {box_path} in box: {stderr}").into());
+        return Err(format!("No such file or directory in box: {box_path}").into());
     }
-
-    println!("{host_path} → {box_name}:{box_path} ({len} bytes)");
-    Ok(())
+    Ok(match String::from_utf8_lossy(&output.stdout).trim() {
+        "dir" => SourceKind::Directory,
+        _ => SourceKind::Entry,
+    })
 }
 
-/// Source file's permission bits (lower 12) for `cp` to restore in the box;
-/// defaults to 0o644 off-Unix or on stat failure.
-fn host_file_mode(host_path: &str) -> u32 {
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        std::fs::metadata(host_path)
-            .map(|m| m.permissions().mode() & 0o7777)
-            .unwrap_or(0o644)
-    }
-    #[cfg(not(unix))]
-    {
-        let _ = host_path;
-        0o644
-    }
-}
-
-// ---------------------------------------------------------------------------
-// Directory transfers
-// ---------------------------------------------------------------------------
-
-/// Copy a directory from a box to the host using tar.
+/// A streaming exec request running `script` under `sh -c`.
 #[cfg(not(windows))]
-async fn copy_dir_from_box(
-    client: &ExecClient,
-    box_name: &str,
-    box_path: &str,
-    host_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Archive the directory inside the box and base64-encode it
-    let request = ExecRequest {
+fn transfer_request(script: String, stdin_streaming: bool) -> ExecRequest {
+    ExecRequest {
         request_id: None,
-        cmd: vec![
-            "sh".to_string(),
-            "-c".to_string(),
-            // `set -o pipefail` so a `tar` failure (EACCES, missing file)
-            // propagates instead of being masked by base64's exit 0 — otherwise
-            // a truncated archive extracts and `cp` falsely reports success.
-            format!(
-                "set -o pipefail; tar -cf - -C {} . | base64",
-                shell_escape(box_path)
-            ),
-        ],
-        timeout_ns: DIR_TRANSFER_TIMEOUT_NS,
+        cmd: vec!["sh".to_string(), "-c".to_string(), script],
+        timeout_ns: TRANSFER_TIMEOUT_NS,
         env: vec![],
         working_dir: None,
         rootfs: None,
         stdin: None,
-        stdin_streaming: false,
+        stdin_streaming,
         user: None,
-        streaming: false,
-    };
-
-    let output = client.exec_command(&request).await?;
-
-    if output.exit_code != 0 {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to archive {box_path} in box: {stderr}").into());
+        streaming: true,
     }
-
-    // Decode base64 tar archive
-    use base64::Engine;
-    let encoded = String::from_utf8_lossy(&output.stdout);
-    let clean: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
-    let tar_data = base64::engine::general_purpose::STANDARD
-        .decode(&clean)
-        .map_err(|e| format!("Failed to decode tar archive: {e}"))?;
-
-    // Create destination directory and extract
-    std::fs::create_dir_all(host_path)
-        .map_err(|e| format!("Failed to create directory {host_path}: {e}"))?;
-
-    extract_tar_to_dir(&tar_data, host_path)?;
-
-    println!(
-        "{box_name}:{box_path}/ → {host_path}/ ({} bytes archived)",
-        tar_data.len()
-    );
-    Ok(())
 }
 
-/// Copy a directory from the host to a box using tar.
+/// Guest script writing a tar of `box_path` to stdout.
 #[cfg(not(windows))]
-async fn copy_dir_to_box(
-    client: &ExecClient,
-    host_path: &str,
-    box_name: &str,
-    box_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Create tar archive of the host directory
-    let tar_data = create_tar_from_dir(host_path)?;
-
-    // Base64-encode and send to box
-    use base64::Engine;
-    let encoded = base64::engine::general_purpose::STANDARD.encode(&tar_data);
-
-    // Create destination directory and extract inside the box
-    let request = ExecRequest {
-        request_id: None,
-        cmd: vec![
-            "sh".to_string(),
-            "-c".to_string(),
-            format!(
-                "set -o pipefail; mkdir -p {} && echo '{}' | base64 -d | tar -xf - -C {}",
-                shell_escape(box_path),
-                encoded,
-                shell_escape(box_path)
-            ),
-        ],
-        timeout_ns: DIR_TRANSFER_TIMEOUT_NS,
-        env: vec![],
-        working_dir: None,
-        rootfs: None,
-        stdin: None,
-        stdin_streaming: false,
-        user: None,
-        streaming: false,
+fn archive_script(box_path: &str, kind: SourceKind) -> String {
+    let (dir, name) = match kind {
+        SourceKind::Directory => (box_path.to_string(), ".".to_string()),
+        SourceKind::Entry => split_entry(box_path),
     };
+    format!(
+        "{GUEST_SPARSE_PROBE}exec tar $S -cf - -C {} {}",
+        shell_escape(&dir),
+        shell_escape(&name)
+    )
+}
 
-    let output = client.exec_command(&request).await?;
-
-    if output.exit_code != 0 {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to extract archive in box at {box_path}: {stderr}").into());
+/// Guest script unpacking a tar from stdin at `box_path`.
+#[cfg(not(windows))]
+fn extract_script(box_path: &str, kind: SourceKind, name: &str) -> String {
+    let dst = shell_escape(box_path);
+    match kind {
+        SourceKind::Directory => format!("mkdir -p {dst} && exec tar -xf - -C {dst}"),
+        SourceKind::Entry => format!(
+            "if [ -d {dst} ] && [ ! -L {dst} ]; then exec tar -xf - -C {dst}; fi; \
+             t=$(mktemp -d \"$(dirname {dst})/.a3s-cp.XXXXXX\") || exit 1; \
+             tar -xf - -C \"$t\" && mv -f \"$t\"/{} {dst}; s=$?; rm -rf \"$t\"; exit $s",
+            shell_escape(name)
+        ),
     }
-
-    println!(
-        "{host_path}/ → {box_name}:{box_path}/ ({} bytes archived)",
-        tar_data.len()
-    );
-    Ok(())
 }
 
-/// Create a tar archive from a host directory using the `tar` command.
+/// Pipe the archive the box writes to stdout into a host `tar` unpacking at
+/// `dir`. Returns the archive size.
 #[cfg(not(windows))]
-fn create_tar_from_dir(dir_path: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    let output = std::process::Command::new("tar")
-        .args(["-cf", "-", "-C", dir_path, "."])
-        .output()
+async fn receive_archive(
+    mut stream: StreamingExec,
+    dir: &Path,
+    box_path: &str,
+    quiet: bool,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut tar = host_tar_extract(dir)
+        .spawn()
         .map_err(|e| format!("Failed to run tar: {e}"))?;
+    let mut tar_stdin = tar.stdin.take().ok_or("tar stdin unavailable")?;
+
+    let mut progress = Progress::new(quiet);
+    let mut stderr = Vec::new();
+    let mut exit_code = -1;
+    let mut write_error = None;
+    while let Some(event) = stream.next_event().await? {
+        match event {
+            ExecEvent::Chunk(chunk) => match chunk.stream {
+                StreamType::Stdout => {
+                    if write_error.is_none() {
+                        if let Err(e) = tar_stdin.write_all(&chunk.data).await {
+                            write_error = Some(e);
+                            let _ = stream.cancel().await;
+                        }
+                    }
+                    progress.add(chunk.data.len());
+                }
+                StreamType::Stderr => stderr.extend_from_slice(&chunk.data),
+            },
+            ExecEvent::FlushAck => {}
+            ExecEvent::Exit(exit) => exit_code = exit.exit_code,
+        }
+    }
+    progress.finish();
+    drop(tar_stdin);
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("tar failed: {stderr}").into());
+    let tar_output = tar
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to wait for tar: {e}"))?;
+    if !tar_output.status.success() || write_error.is_some() {
+        let stderr = String::from_utf8_lossy(&tar_output.stderr);
+        return Err(format!("tar extraction failed: {}", stderr.trim()).into());
+    }
+    if exit_code != 0 {
+        return Err(format!(
+            "Failed to archive {box_path} in box: {}",
+            String::from_utf8_lossy(&stderr).trim()
+        )
+        .into());
     }
+    Ok(progress.bytes)
+}
 
-    Ok(output.stdout)
+/// Read a streaming exec to completion, returning its exit code and stderr.
+#[cfg(not(windows))]
+async fn drain_guest_output(
+    stream: &mut StreamingExec,
+) -> Result<(i32, Vec<u8>), Box<dyn std::error::Error>> {
+    let mut stderr = Vec::new();
+    let mut exit_code = -1;
+    while let Some(event) = stream.next_event().await? {
+        match event {
+            ExecEvent::Chunk(chunk) if chunk.stream == StreamType::Stderr => {
+                stderr.extend_from_slice(&chunk.data)
+            }
+            ExecEvent::Exit(exit) => exit_code = exit.exit_code,
+            _ => {}
+        }
+    }
+    Ok((exit_code, stderr))
 }
 
-/// Extract a tar archive to a host directory using the `tar` command.
+/// Host `tar` writing an archive of `name` under `dir` to stdout.
 #[cfg(not(windows))]
-fn extract_tar_to_dir(tar_data: &[u8], dir_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    use std::io::Write;
-    use std::process::Stdio;
+fn host_tar_create(dir: &Path, name: &str) -> tokio::process::Command {
+    let mut command = tokio::process::Command::new("tar");
+    // bsdtar (macOS) detects holes itself and has no create-mode --sparse.
+    if cfg!(target_os = "linux") {
+        command.arg("--sparse");
+    }
+    command
+        .args(["-cf", "-", "-C"])
+        .arg(dir)
+        .arg(name)
+        // Keep macOS tar from adding `._*` AppleDouble entries.
+        .env("COPYFILE_DISABLE", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    command
+}
 
-    let mut child = std::process::Command::new("tar")
-        .args(["-xf", "-", "-C", dir_path])
+/// Host `tar` unpacking an archive from stdin into `dir`, keeping modes.
+#[cfg(not(windows))]
+fn host_tar_extract(dir: &Path) -> tokio::process::Command {
+    let mut command = tokio::process::Command::new("tar");
+    command
+        .args(["-xpf", "-", "-C"])
+        .arg(dir)
         .stdin(Stdio::piped())
         .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to run tar: {e}"))?;
+        .stderr(Stdio::piped());
+    command
+}
 
-    if let Some(ref mut stdin) = child.stdin {
-        stdin
-            .write_all(tar_data)
-            .map_err(|e| format!("Failed to write tar data: {e}"))?;
+/// Split a path into its parent directory and final component, so a single
+/// entry can be archived under its own name.
+#[cfg(not(windows))]
+fn split_entry(path: &str) -> (String, String) {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some(("", name)) => ("/".to_string(), name.to_string()),
+        Some((dir, name)) => (dir.to_string(), name.to_string()),
+        None => (".".to_string(), trimmed.to_string()),
     }
-    // Close stdin by dropping it
-    drop(child.stdin.take());
+}
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for tar: {e}"))?;
+/// A directory that is not a symlink.
+#[cfg(not(windows))]
+fn is_real_dir(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.is_dir())
+}
+
+/// Bytes-transferred counter redrawn on stderr while it is a terminal.
+#[cfg(not(windows))]
+struct Progress {
+    enabled: bool,
+    bytes: u64,
+    drawn: Option<std::time::Instant>,
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("tar extraction failed: {stderr}").into());
+#[cfg(not(windows))]
+impl Progress {
+    /// Transfers below this size finish without drawing anything.
+    const MIN_BYTES: u64 = 4 * 1024 * 1024;
+    const REDRAW: std::time::Duration = std::time::Duration::from_millis(200);
+
+    fn new(quiet: bool) -> Self {
+        use std::io::IsTerminal;
+        Self {
+            enabled: !quiet && std::io::stderr().is_terminal(),
+            bytes: 0,
+            drawn: None,
+        }
     }
 
-    Ok(())
+    fn add(&mut self, n: usize) {
+        self.bytes += n as u64;
+        if !self.enabled || self.bytes < Self::MIN_BYTES {
+            return;
+        }
+        if self.drawn.is_some_and(|at| at.elapsed() < Self::REDRAW) {
+            return;
+        }
+        eprint!("\rCopying... {}", output::format_bytes(self.bytes));
+        self.drawn = Some(std::time::Instant::now());
+    }
+
+    fn finish(&self) {
+        if self.drawn.is_some() {
+            eprint!("\r\x1b[2K");
+        }
+    }
 }
 
 /// Connect to a box's exec server.
@@ -512,60 +609,103 @@ mod tests {
         );
     }
 
-    // --- Tar helper tests ---
+    // --- Path and script tests ---
 
     #[test]
-    fn test_create_tar_from_dir() {
-        let tmp = tempfile::TempDir::new().unwrap();
-        let dir = tmp.path();
-
-        // Create some test files
-        std::fs::write(dir.join("file1.txt"), "hello").unwrap();
-        std::fs::write(dir.join("file2.txt"), "world").unwrap();
-        std::fs::create_dir(dir.join("subdir")).unwrap();
-        std::fs::write(dir.join("subdir").join("nested.txt"), "nested").unwrap();
-
-        let tar_data = create_tar_from_dir(dir.to_str().unwrap()).unwrap();
-        assert!(!tar_data.is_empty());
+    fn test_split_entry() {
+        assert_eq!(split_entry("/etc/hosts"), ("/etc".into(), "hosts".into()));
+        assert_eq!(split_entry("/data/dir/"), ("/data".into(), "dir".into()));
+        assert_eq!(split_entry("/hosts"), ("/".into(), "hosts".into()));
+        assert_eq!(split_entry("notes.txt"), (".".into(), "notes.txt".into()));
     }
 
     #[test]
-    fn test_create_and_extract_tar_roundtrip() {
+    fn test_guest_scripts() {
+        assert_eq!(
+            archive_script("/var/log", SourceKind::Directory),
+            format!("{GUEST_SPARSE_PROBE}exec tar $S -cf - -C '/var/log' '.'")
+        );
+        assert!(archive_script("/etc/my file", SourceKind::Entry).ends_with("-C '/etc' 'my file'"));
+        assert_eq!(
+            extract_script("/srv/app", SourceKind::Directory, "."),
+            "mkdir -p '/srv/app' && exec tar -xf - -C '/srv/app'"
+        );
+        let entry = extract_script("/srv/app.conf", SourceKind::Entry, "local.conf");
+        assert!(entry.contains("mv -f \"$t\"/'local.conf' '/srv/app.conf'"));
+        assert!(entry.contains("rm -rf \"$t\""));
+    }
+
+    // --- Host tar tests ---
+
+    async fn host_roundtrip(dir: &Path, name: &str, dst: &Path) {
+        let archive = host_tar_create(dir, name).output().await.unwrap();
+        assert!(archive.status.success());
+
+        let mut tar = host_tar_extract(dst).spawn().unwrap();
+        {
+            use tokio::io::AsyncWriteExt;
+            let mut stdin = tar.stdin.take().unwrap();
+            stdin.write_all(&archive.stdout).await.unwrap();
+        }
+        assert!(tar.wait_with_output().await.unwrap().status.success());
+    }
+
+    #[tokio::test]
+    async fn test_host_tar_roundtrip_keeps_modes_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
         let src_dir = tempfile::TempDir::new().unwrap();
         let dst_dir = tempfile::TempDir::new().unwrap();
+        let src = src_dir.path();
+        std::fs::create_dir(src.join("sub")).unwrap();
+        std::fs::write(src.join("sub").join("nested.txt"), "nested content").unwrap();
+        std::fs::write(src.join("run.sh"), "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(src.join("run.sh"), std::fs::Permissions::from_mode(0o750))
+            .unwrap();
+        std::os::unix::fs::symlink("sub/nested.txt", src.join("link")).unwrap();
+
+        host_roundtrip(src, ".", dst_dir.path()).await;
+
+        let dst = dst_dir.path();
+        let nested = std::fs::read_to_string(dst.join("sub").join("nested.txt")).unwrap();
+        assert_eq!(nested, "nested content");
+        let mode = std::fs::metadata(dst.join("run.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7777, 0o750);
+        assert_eq!(
+            std::fs::read_link(dst.join("link")).unwrap(),
+            PathBuf::from("sub/nested.txt")
+        );
+    }
 
-        // Create test content
+    #[tokio::test]
+    async fn test_host_tar_single_entry_keeps_its_name() {
+        let src_dir = tempfile::TempDir::new().unwrap();
+        let dst_dir = tempfile::TempDir::new().unwrap();
         std::fs::write(src_dir.path().join("hello.txt"), "hello world").unwrap();
-        std::fs::create_dir(src_dir.path().join("sub")).unwrap();
-        std::fs::write(
-            src_dir.path().join("sub").join("nested.txt"),
-            "nested content",
-        )
-        .unwrap();
 
-        // Tar and extract
-        let tar_data = create_tar_from_dir(src_dir.path().to_str().unwrap()).unwrap();
-        extract_tar_to_dir(&tar_data, dst_dir.path().to_str().unwrap()).unwrap();
+        host_roundtrip(src_dir.path(), "hello.txt", dst_dir.path()).await;
 
-        // Verify content
         let hello = std::fs::read_to_string(dst_dir.path().join("hello.txt")).unwrap();
         assert_eq!(hello, "hello world");
-
-        let nested =
-            std::fs::read_to_string(dst_dir.path().join("sub").join("nested.txt")).unwrap();
-        assert_eq!(nested, "nested content");
+        assert_eq!(std::fs::read_dir(dst_dir.path()).unwrap().count(), 1);
     }
 
-    #[test]
-    fn test_create_tar_nonexistent_dir() {
-        let result = create_tar_from_dir("/nonexistent/path/a3s_test_12345");
-        assert!(result.is_err());
+    #[tokio::test]
+    async fn test_host_tar_create_nonexistent_dir() {
+        let output = host_tar_create(Path::new("/nonexistent/path/a3s_test_12345"), ".")
+            .output()
+            .await
+            .unwrap();
+        assert!(!output.status.success());
     }
 
     // --- Constant tests ---
 
     #[test]
-    fn test_dir_transfer_timeout() {
-        assert_eq!(DIR_TRANSFER_TIMEOUT_NS, 60_000_000_000);
+    fn test_transfer_timeout() {
+        assert_eq!(TRANSFER_TIMEOUT_NS, 3_600_000_000_000);
     }
 }
@@ -21,7 +21,10 @@ pub struct CreateArgs {
     pub cmd: Vec<String>,
 }
 
-pub async fn execute(args: CreateArgs) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn execute(mut args: CreateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(project) = common::apply_project(&mut args.common, &mut args.cmd)? {
+        super::build::ensure_project_image(&project).await?;
+    }
     common::validate_runtime_options(&args.common)
         .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
 
@@ -67,25 +70,17 @@ pub async fn execute(args: CreateArgs) -> Result<(), Box<dyn std::error::Error>>
     let name = args.common.name.unwrap_or_else(generate_name);
 
     // Parse --shm-size
-    let shm_size = match &args.common.shm_size {
-        Some(s) => {
-            Some(common::parse_memory_bytes(s).map_err(|e| format!("Invalid --shm-size: {e}"))?)
-        }
-        None => None,
-    };
+    let shm_size = common::parse_shm_size(args.common.shm_size.as_deref())?;
 
     let home = a3s_box_core::dirs_home();
 
-    // Resolve named volumes
-    let mut resolved_volumes = Vec::new();
-    let mut volume_names = Vec::new();
-    for vol_spec in &args.common.volumes {
-        let (resolved, vol_name) = super::volume::resolve_named_volume(vol_spec)?;
-        if let Some(name) = vol_name {
-            volume_names.push(name);
-        }
-        resolved_volumes.push(resolved);
-    }
+    // Resolve named volumes and --mount flags
+    let mounts = super::volume::resolve_box_mounts(
+        &args.common.volumes,
+        &args.common.tmpfs,
+        &args.common.mounts,
+    )?;
+    let volume_names = mounts.volume_names;
 
     let entrypoint = args
         .common
@@ -114,9 +109,10 @@ pub async fn execute(args: CreateArgs) -> Result<(), Box<dyn std::error::Error>>
         cmd: args.cmd.clone(),
         entrypoint_override: entrypoint,
         user: args.common.user.clone(),
+        group_add: args.common.group_add.clone(),
         workdir: args.common.workdir.clone(),
         hostname: args.common.hostname.clone(),
-        volumes: resolved_volumes,
+        volumes: mounts.volumes,
         virtiofs_cache: args
             .common
             .virtiofs_cache
@@ -124,15 +120,32 @@ pub async fn execute(args: CreateArgs) -> Result<(), Box<dyn std::error::Error>>
         extra_env,
         port_map,
         dns: args.common.dns.clone(),
+        dns_search: args.common.dns_search.clone(),
+        dns_options: args.common.dns_option.clone(),
+        network_aliases: args.common.network_alias.clone(),
+        network_policy: args.common.network_policy.clone(),
+        network_bandwidth: args.common.network_bandwidth,
+        network_max_conns: args.common.network_max_conns,
+        http_proxy: args.common.http_proxy,
         add_hosts: args.common.add_host.clone(),
         network: network_mode,
-        tmpfs: args.common.tmpfs.clone(),
+        tmpfs: mounts.tmpfs,
+        block_volumes: mounts.block_volumes,
+        socket_mounts: mounts.socket_mounts,
         resource_limits,
         read_only: args.common.read_only,
         cap_add: args.common.cap_add.clone(),
         cap_drop: args.common.cap_drop.clone(),
         security_opt: args.common.security_opt.clone(),
         privileged: args.common.privileged,
+        devices: args.common.device.clone(),
+        ksm: args.common.ksm,
+        hugepages: args.common.hugepages,
+        mem_prealloc: args.common.mem_prealloc,
+        sysctls: common::sysctls(&args.common)?,
+        kernel_modules: args.common.kernel_module.clone(),
+        workspace_template: common::workspace_template(&args.common)?,
+        metadata: common::created_by_metadata(),
         // A created box is restartable and therefore retains its writable
         // filesystem until an explicit remove.
         persistent: true,
@@ -0,0 +1,143 @@
+//! `a3s-box debug` subcommands — Show how the runtime interprets its inputs.
+//!
+//! `debug map-config` prints the container process an image config maps to
+//! (executable, args, environment, workdir, user, mounts) after applying the
+//! same overrides `run` accepts. It uses the mapping the boot path uses, so
+//! its output is what a box would actually run and the format the runtime's
+//! golden conformance fixtures are written in.
+
+use std::path::PathBuf;
+
+use a3s_box_core::config::BoxConfig;
+use a3s_box_runtime::oci::OciImageConfig;
+use clap::{Args, Subcommand};
+
+use crate::image_usage;
+
+/// Debugging tools.
+#[derive(Args)]
+pub struct DebugArgs {
+    #[command(subcommand)]
+    pub command: DebugCommand,
+}
+
+#[derive(Subcommand)]
+pub enum DebugCommand {
+    /// Show how an image config maps to the box's main process
+    MapConfig(MapConfigArgs),
+}
+
+#[derive(Args)]
+pub struct MapConfigArgs {
+    /// Image reference or ID (must already be pulled)
+    #[arg(required_unless_present = "config_file")]
+    pub image: Option<String>,
+
+    /// Map a raw image config JSON blob instead of a stored image
+    #[arg(long, conflicts_with = "image")]
+    pub config_file: Option<PathBuf>,
+
+    /// Override the image entrypoint
+    #[arg(long)]
+    pub entrypoint: Option<String>,
+
+    /// Run as a specific user
+    #[arg(short = 'u', long)]
+    pub user: Option<String>,
+
+    /// Working directory inside the box
+    #[arg(short = 'w', long)]
+    pub workdir: Option<String>,
+
+    /// Environment variable (KEY=VALUE), can be repeated
+    #[arg(short = 'e', long = "env")]
+    pub env: Vec<String>,
+
+    /// Volume mount (host:guest[:ro]), can be repeated
+    #[arg(short = 'v', long = "volume")]
+    pub volumes: Vec<String>,
+
+    /// Mount a tmpfs (path[:options]), can be repeated
+    #[arg(long)]
+    pub tmpfs: Vec<String>,
+
+    /// Command override (replaces the image CMD)
+    #[arg(last = true)]
+    pub cmd: Vec<String>,
+}
+
+pub async fn execute(args: DebugArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match args.command {
+        DebugCommand::MapConfig(args) => map_config(args).await,
+    }
+}
+
+async fn map_config(args: MapConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let oci_config = match (&args.config_file, &args.image) {
+        (Some(path), _) => {
+            let content = std::fs::read(path)
+                .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            OciImageConfig::from_config_json(&content)?
+        }
+        (None, Some(image)) => {
+            let store = super::open_image_store()?;
+            let images = store.list().await;
+            let stored = image_usage::resolve_required_stored_image(&images, image)?;
+            a3s_box_runtime::OciImage::from_path(&stored.path)?
+                .config()
+                .clone()
+        }
+        (None, None) => return Err("an image or --config-file is required".into()),
+    };
+
+    let config = box_config_from_args(&args)?;
+    let mapping = a3s_box_runtime::map_image_config(&config, Some(&oci_config))?;
+    println!("{}", serde_json::to_string_pretty(&mapping)?);
+    Ok(())
+}
+
+/// The subset of `run` options that affect the process mapping.
+fn box_config_from_args(args: &MapConfigArgs) -> Result<BoxConfig, String> {
+    Ok(BoxConfig {
+        cmd: args.cmd.clone(),
+        entrypoint_override: args
+            .entrypoint
+            .as_ref()
+            .map(|ep| ep.split_whitespace().map(String::from).collect()),
+        user: args.user.clone(),
+        workdir: args.workdir.clone(),
+        extra_env: a3s_box_core::env::parse_env_vars(&args.env)?,
+        volumes: args.volumes.clone(),
+        tmpfs: args.tmpfs.clone(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_config_from_args_splits_entrypoint_and_env() {
+        let args = MapConfigArgs {
+            image: Some("alpine".to_string()),
+            config_file: None,
+            entrypoint: Some("/bin/sh -c".to_string()),
+            user: Some("1000".to_string()),
+            workdir: None,
+            env: vec!["A=1".to_string()],
+            volumes: vec![],
+            tmpfs: vec![],
+            cmd: vec!["echo hi".to_string()],
+        };
+
+        let config = box_config_from_args(&args).unwrap();
+
+        assert_eq!(
+            config.entrypoint_override,
+            Some(vec!["/bin/sh".to_string(), "-c".to_string()])
+        );
+        assert_eq!(config.extra_env, vec![("A".to_string(), "1".to_string())]);
+        assert_eq!(config.cmd, vec!["echo hi"]);
+    }
+}
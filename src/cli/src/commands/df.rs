@@ -1,11 +1,22 @@
-//! `a3s-box df` command — Show disk usage.
+//! `a3s-box df` / `a3s-box system df` — Show disk usage.
 //!
-//! Displays disk usage for images and boxes, similar to `docker system df`.
+//! Aggregates the space used by images, boxes, volumes, the rootfs/layer
+//! caches, and warm-pool templates, similar to `docker system df`. The
+//! RECLAIMABLE column reports what `system prune --all` would free: images not
+//! used by an active box, and created/stopped/dead boxes. Volumes count as
+//! reclaimable when no box uses them (`volume prune`); caches and pool
+//! templates are never pruned implicitly and always report zero.
 
+use std::collections::HashMap;
+use std::path::Path;
+
+use a3s_box_runtime::StoredImage;
 use clap::Args;
 
+use crate::image_usage::{self, ImagePruneMode, ImageReferenceScope};
 use crate::output;
-use crate::state::StateFile;
+use crate::state::{BoxRecord, StateFile};
+use crate::status;
 
 #[derive(Args)]
 pub struct DfArgs {
@@ -14,70 +25,139 @@ pub struct DfArgs {
     pub verbose: bool,
 }
 
+/// One row of the summary table.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct UsageSummary {
+    total: usize,
+    active: usize,
+    size: u64,
+    reclaimable: u64,
+}
+
+impl UsageSummary {
+    fn reclaimable_cell(&self) -> String {
+        let pct = if self.size > 0 {
+            (self.reclaimable as f64 / self.size as f64 * 100.0) as u64
+        } else {
+            0
+        };
+        format!("{} ({pct}%)", output::format_bytes(self.reclaimable))
+    }
+}
+
+/// Per-image usage with Docker-style shared-layer accounting.
+#[derive(Debug, PartialEq, Eq)]
+struct ImageUsage {
+    reference: String,
+    size: u64,
+    /// Bytes of blobs (layers, configs) also present in another image.
+    shared: u64,
+    boxes: usize,
+}
+
+impl ImageUsage {
+    fn unique(&self) -> u64 {
+        self.size.saturating_sub(self.shared)
+    }
+}
+
 pub async fn execute(args: DfArgs) -> Result<(), Box<dyn std::error::Error>> {
     let store = super::open_image_store()?;
     let state = StateFile::load_default()?;
+    let home = a3s_box_core::dirs_home();
 
-    // Image stats
+    // Images
     let images = store.list().await;
-    let image_count = images.len();
-    let image_total_size = store.total_size().await;
+    let image_usage = image_usages(&images, &state);
+    let active_references =
+        image_usage::referenced_images(&state, ImageReferenceScope::ActiveBoxes);
+    let image_summary = UsageSummary {
+        total: images.len(),
+        active: image_usage.iter().filter(|image| image.boxes > 0).count(),
+        size: store.total_size().await,
+        reclaimable: reclaimable_image_bytes(&images, &active_references),
+    };
 
-    // Box stats
+    // Boxes
     let boxes = state.list(true);
-    let box_count = boxes.len();
-    let active_boxes = boxes.iter().filter(|b| b.status == "running").count();
-    let box_total_size: u64 = boxes.iter().map(|b| dir_size(&b.box_dir)).sum();
+    let box_sizes: Vec<u64> = boxes.iter().map(|b| dir_size(&b.box_dir)).collect();
+    let box_summary = UsageSummary {
+        total: boxes.len(),
+        active: boxes.iter().filter(|b| status::is_active(b)).count(),
+        size: box_sizes.iter().sum(),
+        reclaimable: boxes
+            .iter()
+            .zip(&box_sizes)
+            .filter(|(b, _)| super::system_prune::is_prunable_box(b))
+            .map(|(_, size)| size)
+            .sum(),
+    };
 
-    // Reclaimable: stopped/dead boxes + unused images
-    let reclaimable_boxes: u64 = boxes
+    // Volumes
+    let volumes = a3s_box_runtime::VolumeStore::default_path()
+        .and_then(|store| store.list())
+        .unwrap_or_default();
+    let volume_sizes: Vec<u64> = volumes
         .iter()
-        .filter(|b| b.status != "running")
-        .map(|b| dir_size(&b.box_dir))
-        .sum();
-
-    // Summary table
-    let mut table = output::new_table(&["TYPE", "TOTAL", "ACTIVE", "SIZE", "RECLAIMABLE"]);
-
-    table.add_row([
-        "Images",
-        &image_count.to_string(),
-        &image_count.to_string(),
-        &output::format_bytes(image_total_size),
-        &format!("{} (0%)", output::format_bytes(0)),
-    ]);
-
-    let reclaim_pct = if box_total_size > 0 {
-        (reclaimable_boxes as f64 / box_total_size as f64 * 100.0) as u64
-    } else {
-        0
+        .map(|volume| dir_size(Path::new(&volume.mount_point)))
+        .collect();
+    let volume_summary = UsageSummary {
+        total: volumes.len(),
+        active: volumes.iter().filter(|volume| volume.is_in_use()).count(),
+        size: volume_sizes.iter().sum(),
+        reclaimable: volumes
+            .iter()
+            .zip(&volume_sizes)
+            .filter(|(volume, _)| !volume.is_in_use())
+            .map(|(_, size)| size)
+            .sum(),
     };
 
-    table.add_row([
-        "Boxes",
-        &box_count.to_string(),
-        &active_boxes.to_string(),
-        &output::format_bytes(box_total_size),
-        &format!(
-            "{} ({reclaim_pct}%)",
-            output::format_bytes(reclaimable_boxes)
-        ),
-    ]);
-
-    let total_size = image_total_size + box_total_size;
-    let total_reclaimable = reclaimable_boxes;
-    let total_pct = if total_size > 0 {
-        (total_reclaimable as f64 / total_size as f64 * 100.0) as u64
-    } else {
-        0
+    // Rootfs/layer caches and warm-pool templates
+    let caches = child_dir_sizes(&home.join("cache"));
+    let cache_summary = UsageSummary {
+        total: caches.len(),
+        active: 0,
+        size: caches.iter().map(|(_, size)| size).sum(),
+        reclaimable: 0,
+    };
+    let templates = child_dir_sizes(&home.join("pool"));
+    let pool_summary = UsageSummary {
+        total: templates.len(),
+        active: 0,
+        size: templates.iter().map(|(_, size)| size).sum(),
+        reclaimable: 0,
     };
 
+    let mut table = output::new_table(&["TYPE", "TOTAL", "ACTIVE", "SIZE", "RECLAIMABLE"]);
+    let rows = [
+        ("Images", &image_summary),
+        ("Boxes", &box_summary),
+        ("Local Volumes", &volume_summary),
+        ("Build Cache", &cache_summary),
+        ("Warm Pool", &pool_summary),
+    ];
+    for (kind, summary) in rows {
+        table.add_row([
+            kind,
+            &summary.total.to_string(),
+            &summary.active.to_string(),
+            &output::format_bytes(summary.size),
+            &summary.reclaimable_cell(),
+        ]);
+    }
+    let total = UsageSummary {
+        total: 0,
+        active: 0,
+        size: rows.iter().map(|(_, summary)| summary.size).sum(),
+        reclaimable: rows.iter().map(|(_, summary)| summary.reclaimable).sum(),
+    };
     table.add_row([
         "Total",
         "",
         "",
-        &output::format_bytes(total_size),
-        &format!("{} ({total_pct}%)", output::format_bytes(total_reclaimable)),
+        &output::format_bytes(total.size),
+        &total.reclaimable_cell(),
     ]);
 
     println!("{table}");
@@ -85,28 +165,196 @@ pub async fn execute(args: DfArgs) -> Result<(), Box<dyn std::error::Error>> {
     // Verbose: per-item details
     if args.verbose {
         println!();
-        println!("Images:");
-        let mut img_table = output::new_table(&["REPOSITORY", "SIZE"]);
-        for image in &images {
-            img_table.add_row([&image.reference, &output::format_bytes(image.size_bytes)]);
+        println!("Images space usage:");
+        let mut img_table =
+            output::new_table(&["REPOSITORY", "SIZE", "SHARED SIZE", "UNIQUE SIZE", "BOXES"]);
+        for image in &image_usage {
+            img_table.add_row([
+                &image.reference,
+                &output::format_bytes(image.size),
+                &output::format_bytes(image.shared),
+                &output::format_bytes(image.unique()),
+                &image.boxes.to_string(),
+            ]);
         }
         println!("{img_table}");
 
         println!();
-        println!("Boxes:");
-        let mut box_table = output::new_table(&["NAME", "STATUS", "SIZE"]);
-        for b in &boxes {
-            let size = dir_size(&b.box_dir);
-            box_table.add_row([&b.name, &b.status, &output::format_bytes(size)]);
+        println!("Boxes space usage:");
+        let mut box_table =
+            output::new_table(&["BOX ID", "NAME", "IMAGE", "STATUS", "SIZE", "DISK QUOTA"]);
+        for (b, size) in boxes.iter().zip(&box_sizes) {
+            let quota = b
+                .resource_limits
+                .disk_limit_bytes
+                .and_then(|limit| a3s_box_runtime::rootfs::quota::disk_usage(&b.box_dir, limit))
+                .map(|usage| {
+                    format!(
+                        "{} / {}",
+                        output::format_bytes(usage.used_bytes),
+                        output::format_bytes(usage.limit_bytes)
+                    )
+                })
+                .unwrap_or_else(|| "--".to_string());
+            box_table.add_row([
+                &b.short_id,
+                &b.name,
+                &b.image,
+                &b.status,
+                &output::format_bytes(*size),
+                &quota,
+            ]);
         }
         println!("{box_table}");
+
+        println!();
+        println!("Local Volumes space usage:");
+        let mut volume_table = output::new_table(&["VOLUME NAME", "LINKS", "SIZE"]);
+        for (volume, size) in volumes.iter().zip(&volume_sizes) {
+            volume_table.add_row([
+                &volume.name,
+                &volume.in_use_by.len().to_string(),
+                &output::format_bytes(*size),
+            ]);
+        }
+        println!("{volume_table}");
+
+        println!();
+        println!("Build cache usage:");
+        let mut cache_table = output::new_table(&["CACHE", "SIZE"]);
+        for (name, size) in &caches {
+            cache_table.add_row([name, &output::format_bytes(*size)]);
+        }
+        println!("{cache_table}");
+
+        println!();
+        println!("Warm pool usage:");
+        let mut pool_table = output::new_table(&["TEMPLATE", "SIZE"]);
+        for (name, size) in &templates {
+            pool_table.add_row([name, &output::format_bytes(*size)]);
+        }
+        println!("{pool_table}");
     }
 
     Ok(())
 }
 
+/// Per-image sizes with shared-blob accounting and box reference counts.
+fn image_usages(images: &[StoredImage], state: &StateFile) -> Vec<ImageUsage> {
+    let blobs: Vec<Vec<(String, u64)>> = images
+        .iter()
+        .map(|image| image_blobs(&image.path))
+        .collect();
+    shared_image_usage(images, &blobs, &state.list(true))
+}
+
+fn shared_image_usage(
+    images: &[StoredImage],
+    blobs: &[Vec<(String, u64)>],
+    boxes: &[&BoxRecord],
+) -> Vec<ImageUsage> {
+    // References to the same digest share one content directory, so count a
+    // blob as shared only when it appears under more than one digest.
+    let mut owners: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (image, image_blobs) in images.iter().zip(blobs) {
+        for (digest, _) in image_blobs {
+            let entry = owners.entry(digest.as_str()).or_default();
+            if !entry.contains(&image.digest.as_str()) {
+                entry.push(image.digest.as_str());
+            }
+        }
+    }
+
+    images
+        .iter()
+        .zip(blobs)
+        .map(|(image, image_blobs)| {
+            let aliases = image_usage::reference_aliases(&image.reference);
+            ImageUsage {
+                reference: image.reference.clone(),
+                size: image.size_bytes,
+                shared: image_blobs
+                    .iter()
+                    .filter(|(digest, _)| owners[digest.as_str()].len() > 1)
+                    .map(|(_, size)| size)
+                    .sum(),
+                boxes: boxes
+                    .iter()
+                    .filter(|b| {
+                        image_usage::reference_aliases(&b.image)
+                            .iter()
+                            .any(|alias| aliases.contains(alias))
+                    })
+                    .count(),
+            }
+        })
+        .collect()
+}
+
+/// Bytes `system prune --all` frees: content dirs whose every reference is
+/// unused by active boxes.
+fn reclaimable_image_bytes(
+    images: &[StoredImage],
+    active_references: &std::collections::HashSet<String>,
+) -> u64 {
+    let mut by_digest: HashMap<&str, (u64, bool)> = HashMap::new();
+    for image in images {
+        let prunable = image_usage::is_prunable_reference(
+            &image.reference,
+            active_references,
+            ImagePruneMode::Unused,
+        );
+        let entry = by_digest
+            .entry(image.digest.as_str())
+            .or_insert((image.size_bytes, true));
+        entry.1 &= prunable;
+    }
+    by_digest
+        .values()
+        .filter(|(_, prunable)| *prunable)
+        .map(|(size, _)| size)
+        .sum()
+}
+
+/// Blobs in an OCI image layout, as `(digest hex, size)`.
+fn image_blobs(layout: &Path) -> Vec<(String, u64)> {
+    let Ok(entries) = std::fs::read_dir(layout.join("blobs").join("sha256")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            meta.is_file()
+                .then(|| (entry.file_name().to_string_lossy().into_owned(), meta.len()))
+        })
+        .collect()
+}
+
+/// Sizes of the immediate subdirectories of `dir`, sorted by name.
+fn child_dir_sizes(dir: &Path) -> Vec<(String, u64)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut sizes: Vec<(String, u64)> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().into_owned(),
+                dir_size(&entry.path()),
+            )
+        })
+        .collect();
+    sizes.sort();
+    sizes
+}
+
 /// Calculate total size of a directory recursively.
-fn dir_size(path: &std::path::Path) -> u64 {
+///
+/// A sparse disk-quota image counts at its allocated size, so it reports the
+/// data written rather than its capacity.
+pub(super) fn dir_size(path: &std::path::Path) -> u64 {
     let mut total = 0;
     if let Ok(entries) = std::fs::read_dir(path) {
         for entry in entries.flatten() {
@@ -114,13 +362,28 @@ fn dir_size(path: &std::path::Path) -> u64 {
             if p.is_dir() {
                 total += dir_size(&p);
             } else if let Ok(meta) = p.metadata() {
-                total += meta.len();
+                total += if entry.file_name() == a3s_box_runtime::rootfs::quota::QUOTA_IMAGE_NAME {
+                    allocated_size(&meta)
+                } else {
+                    meta.len()
+                };
             }
         }
     }
     total
 }
 
+#[cfg(unix)]
+fn allocated_size(meta: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.len().min(meta.blocks() * 512)
+}
+
+#[cfg(not(unix))]
+fn allocated_size(meta: &std::fs::Metadata) -> u64 {
+    meta.len()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,4 +418,70 @@ mod tests {
         let path = std::path::Path::new("/nonexistent/a3s_test_12345");
         assert_eq!(dir_size(path), 0);
     }
+
+    fn stored_image(reference: &str, digest: &str, size_bytes: u64) -> StoredImage {
+        StoredImage {
+            reference: reference.to_string(),
+            digest: digest.to_string(),
+            size_bytes,
+            pulled_at: chrono::Utc::now(),
+            last_used: chrono::Utc::now(),
+            path: std::path::PathBuf::from("/nonexistent"),
+        }
+    }
+
+    #[test]
+    fn test_shared_image_usage_counts_blobs_across_digests() {
+        let images = [
+            stored_image("app:v1", "sha256:aaa", 300),
+            stored_image("app:v2", "sha256:bbb", 250),
+            stored_image("app:latest", "sha256:bbb", 250),
+        ];
+        let base = ("base".to_string(), 200);
+        let blobs = [
+            vec![base.clone(), ("v1".to_string(), 100)],
+            vec![base.clone(), ("v2".to_string(), 50)],
+            vec![base, ("v2".to_string(), 50)],
+        ];
+        let mut record =
+            crate::test_helpers::fixtures::make_record("id", "web", "running", Some(1));
+        record.image = "app:v1".to_string();
+
+        let usage = shared_image_usage(&images, &blobs, &[&record]);
+
+        assert_eq!(usage[0].shared, 200);
+        assert_eq!(usage[0].unique(), 100);
+        assert_eq!(usage[0].boxes, 1);
+        // The v2-only blob lives under one digest, so aliases don't share it.
+        assert_eq!(usage[1].shared, 200);
+        assert_eq!(usage[1].unique(), 50);
+        assert_eq!(usage[1].boxes, 0);
+    }
+
+    #[test]
+    fn test_reclaimable_image_bytes_keeps_digests_with_active_references() {
+        let images = [
+            stored_image("app:v1", "sha256:aaa", 300),
+            stored_image("app:v2", "sha256:bbb", 250),
+            stored_image("app:latest", "sha256:bbb", 250),
+            stored_image("old:1", "sha256:ccc", 100),
+        ];
+        let active = image_usage::reference_aliases("app:latest");
+
+        assert_eq!(reclaimable_image_bytes(&images, &active), 400);
+    }
+
+    #[test]
+    fn test_child_dir_sizes_sorted_by_name() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir(tmp.path().join("rootfs")).unwrap();
+        fs::create_dir(tmp.path().join("layers")).unwrap();
+        fs::write(tmp.path().join("layers").join("blob"), "abcd").unwrap();
+        fs::write(tmp.path().join("stray-file"), "x").unwrap();
+
+        assert_eq!(
+            child_dir_sizes(tmp.path()),
+            vec![("layers".to_string(), 4), ("rootfs".to_string(), 0)]
+        );
+    }
 }
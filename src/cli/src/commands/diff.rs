@@ -2,9 +2,14 @@
 //!
 //! Compares the box's rootfs against the original image layers to detect
 //! added, changed, and deleted files, similar to `docker diff`.
+//!
+//! Boxes on the overlay provider are diffed from their writable layer
+//! (`<box_dir>/upper`), which holds exactly what the box wrote and is readable
+//! whether or not the box is running. Other boxes compare the current rootfs
+//! against the baseline snapshot taken when the rootfs was first prepared.
 
 use std::collections::HashMap;
-use std::path::{Component, Path};
+use std::path::{Component, Path, PathBuf};
 
 use clap::Args;
 
@@ -39,46 +44,78 @@ pub async fn execute(args: DiffArgs) -> Result<(), Box<dyn std::error::Error>> {
     let state = StateFile::load_default()?;
     let record = resolve::resolve(&state, &args.name)?;
 
-    let rootfs_dir = super::resolve_box_rootfs(&record.box_dir).ok_or_else(|| {
-        format!(
-            "Rootfs not found for box '{}' under {} (looked for merged/ and rootfs/)",
-            args.name,
-            record.box_dir.display()
-        )
-    })?;
+    let baseline = load_baseline(&record.box_dir)?;
+    let upper_dir = record.box_dir.join("upper");
 
-    // Snapshot the original image to compare against
-    let snapshot_path = record.box_dir.join("rootfs_snapshot.json");
-    if !snapshot_path.exists() {
-        println!("No baseline snapshot found — cannot compute diff.");
-        println!("(Snapshot is created at box creation time.)");
-        return Ok(());
+    let changes = if upper_dir.is_dir() {
+        let lower = Lower {
+            baseline: baseline.as_ref(),
+            dir: Some(record.box_dir.join("rootfs")).filter(|dir| dir.is_dir()),
+        };
+        upper_changes(&upper_dir, &lower)?
+    } else {
+        let rootfs_dir = super::resolve_box_rootfs(&record.box_dir).ok_or_else(|| {
+            format!(
+                "Rootfs not found for box '{}' under {} (looked for merged/ and rootfs/)",
+                args.name,
+                record.box_dir.display()
+            )
+        })?;
+        let Some(baseline) = baseline else {
+            println!("No baseline snapshot found — cannot compute diff.");
+            println!("(Snapshot is created at box creation time.)");
+            return Ok(());
+        };
+        baseline_changes(&baseline, &walk_dir(&rootfs_dir)?)
+    };
+
+    if changes.is_empty() {
+        println!("No changes detected.");
+    } else {
+        for (kind, path) in &changes {
+            println!("{kind} {path}");
+        }
     }
 
+    Ok(())
+}
+
+fn load_baseline(
+    box_dir: &Path,
+) -> Result<Option<HashMap<String, FileInfo>>, Box<dyn std::error::Error>> {
+    let snapshot_path = box_dir.join("rootfs_snapshot.json");
+    if !snapshot_path.exists() {
+        return Ok(None);
+    }
     let snapshot_data = std::fs::read_to_string(&snapshot_path)
         .map_err(|e| format!("Failed to read snapshot: {e}"))?;
-    let baseline: HashMap<String, FileInfo> = serde_json::from_str(&snapshot_data)
+    let baseline = serde_json::from_str(&snapshot_data)
         .map_err(|e| format!("Failed to parse snapshot: {e}"))?;
+    Ok(Some(baseline))
+}
 
-    // Walk current rootfs
-    let current = walk_dir(&rootfs_dir)?;
-
-    // Compute diff
+/// Compare a rootfs walk against the baseline snapshot, sorted by path.
+fn baseline_changes(
+    baseline: &HashMap<String, FileInfo>,
+    current: &HashMap<String, FileInfo>,
+) -> Vec<(ChangeKind, String)> {
     let mut changes = Vec::new();
 
-    // Check for added and changed files
-    for (path, info) in &current {
+    for (path, info) in current {
         match baseline.get(path) {
             None => changes.push((ChangeKind::Added, path.clone())),
             Some(base_info) => {
-                if info.size != base_info.size || info.mode != base_info.mode {
+                // Snapshots written before mtime was recorded carry 0; fall
+                // back to size and mode for those.
+                let mtime_changed =
+                    info.mtime != 0 && base_info.mtime != 0 && info.mtime != base_info.mtime;
+                if info.size != base_info.size || info.mode != base_info.mode || mtime_changed {
                     changes.push((ChangeKind::Changed, path.clone()));
                 }
             }
         }
     }
 
-    // Check for deleted files
     for path in baseline.keys() {
         if !current.contains_key(path) {
             changes.push((ChangeKind::Deleted, path.clone()));
@@ -86,18 +123,192 @@ pub async fn execute(args: DiffArgs) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     changes.sort_by(|a, b| a.1.cmp(&b.1));
+    changes
+}
 
-    if changes.is_empty() {
-        println!("No changes detected.");
-    } else {
-        for (kind, path) in &changes {
-            println!("{kind} {path}");
+/// What the box started from, used to tell an added path from a changed one
+/// when reading the overlay upper dir.
+struct Lower<'a> {
+    baseline: Option<&'a HashMap<String, FileInfo>>,
+    /// The overlay lower dir when it lives in the box (`<box_dir>/rootfs`).
+    dir: Option<PathBuf>,
+}
+
+impl Lower<'_> {
+    fn contains(&self, path: &str) -> bool {
+        self.baseline.is_some_and(|b| b.contains_key(path))
+            || self.dir.as_ref().is_some_and(|dir| {
+                std::fs::symlink_metadata(dir.join(path.trim_start_matches('/'))).is_ok()
+            })
+    }
+
+    /// Direct children of `dir` in the lower layer.
+    fn children(&self, dir: &str) -> Vec<String> {
+        let mut children = Vec::new();
+        if let Some(baseline) = self.baseline {
+            children.extend(
+                baseline
+                    .keys()
+                    .filter(|path| parent_path(path) == dir)
+                    .cloned(),
+            );
+        }
+        if let Some(lower_dir) = &self.dir {
+            if let Ok(entries) = std::fs::read_dir(lower_dir.join(dir.trim_start_matches('/'))) {
+                children.extend(
+                    entries
+                        .flatten()
+                        .map(|entry| join_path(dir, &entry.file_name().to_string_lossy())),
+                );
+            }
+        }
+        children.sort();
+        children.dedup();
+        children
+    }
+}
+
+/// Read the changes straight out of an overlay upper dir, sorted by path.
+///
+/// Every entry in the upper dir is something the box wrote: it is `C` when
+/// the lower layer has the same path and `A` otherwise. Whiteouts (0/0
+/// character devices, or `.wh.<name>` files) are `D`, and an opaque directory
+/// deletes whatever the lower layer had beneath it. As with `docker diff`,
+/// the parent directories of a change show up as `C`, since overlayfs copies
+/// them up.
+fn upper_changes(
+    upper_dir: &Path,
+    lower: &Lower<'_>,
+) -> Result<Vec<(ChangeKind, String)>, Box<dyn std::error::Error>> {
+    let mut changes = Vec::new();
+    walk_upper(upper_dir, upper_dir, lower, &mut changes)?;
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+    changes.dedup_by(|a, b| a.1 == b.1);
+    Ok(changes)
+}
+
+const WHITEOUT_PREFIX: &str = ".wh.";
+const OPAQUE_MARKER: &str = ".wh..wh..opq";
+
+fn walk_upper(
+    root: &Path,
+    current: &Path,
+    lower: &Lower<'_>,
+    changes: &mut Vec<(ChangeKind, String)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = std::fs::read_dir(current)
+        .map_err(|e| format!("Failed to read {}: {e}", current.display()))?;
+    let rel_dir = rootfs_path_string(current.strip_prefix(root).unwrap_or(current));
+
+    let mut present = Vec::new();
+    let mut opaque = is_opaque_dir(current);
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        let meta = entry.metadata()?;
+
+        if name == OPAQUE_MARKER {
+            opaque = true;
+            continue;
+        }
+        if let Some(deleted) = name.strip_prefix(WHITEOUT_PREFIX) {
+            changes.push((ChangeKind::Deleted, join_path(&rel_dir, deleted)));
+            continue;
+        }
+        let rel = join_path(&rel_dir, &name);
+        if is_whiteout(&meta) {
+            changes.push((ChangeKind::Deleted, rel));
+            continue;
+        }
+
+        present.push(rel.clone());
+        let kind = if lower.contains(&rel) {
+            ChangeKind::Changed
+        } else {
+            ChangeKind::Added
+        };
+        changes.push((kind, rel));
+        if meta.is_dir() {
+            walk_upper(root, &path, lower, changes)?;
+        }
+    }
+
+    if opaque {
+        for child in lower.children(&rel_dir) {
+            if !present.contains(&child) {
+                changes.push((ChangeKind::Deleted, child));
+            }
         }
     }
 
     Ok(())
 }
 
+/// overlayfs records a deletion as a character device with device number 0/0.
+fn is_whiteout(meta: &std::fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+        meta.file_type().is_char_device() && meta.rdev() == 0
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        false
+    }
+}
+
+/// overlayfs marks a directory that hides the lower layer's contents with an
+/// `overlay.opaque` xattr (`trusted.` as root, `user.` for rootless mounts).
+fn is_opaque_dir(dir: &Path) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+            return false;
+        };
+        ["trusted.overlay.opaque", "user.overlay.opaque"]
+            .iter()
+            .any(|name| {
+                let name = std::ffi::CString::new(*name).unwrap();
+                let mut value = [0u8; 1];
+                // SAFETY: both strings are NUL-terminated and `value` is a
+                // valid buffer of the length passed.
+                let len = unsafe {
+                    libc::lgetxattr(
+                        path.as_ptr(),
+                        name.as_ptr(),
+                        value.as_mut_ptr().cast(),
+                        value.len(),
+                    )
+                };
+                len == 1 && value[0] == b'y'
+            })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = dir;
+        false
+    }
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if dir == "/" {
+        format!("/{name}")
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+fn parent_path(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(index) => &path[..index],
+    }
+}
+
 /// Create the per-box baseline snapshot used by `a3s-box diff`.
 ///
 /// The caller should invoke this after the rootfs is prepared and before user
@@ -123,6 +334,9 @@ pub struct FileInfo {
     pub size: u64,
     pub mode: u32,
     pub is_dir: bool,
+    /// Modification time in seconds; 0 in snapshots that predate it.
+    #[serde(default)]
+    pub mtime: i64,
 }
 
 /// Walk a directory tree and collect file metadata, keyed by relative path.
@@ -163,12 +377,19 @@ fn walk_recursive(
         #[cfg(not(unix))]
         let mode = 0u32;
 
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+
         map.insert(
             rel,
             FileInfo {
                 size: meta.len(),
                 mode,
                 is_dir: meta.is_dir(),
+                mtime,
             },
         );
 
@@ -334,4 +555,123 @@ mod tests {
         }
         assert!(changed.contains(&"/file.txt".to_string()));
     }
+
+    fn snapshot(paths: &[&str]) -> HashMap<String, FileInfo> {
+        paths
+            .iter()
+            .map(|path| {
+                let info = FileInfo {
+                    size: 0,
+                    mode: 0o644,
+                    is_dir: false,
+                    mtime: 0,
+                };
+                (path.to_string(), info)
+            })
+            .collect()
+    }
+
+    fn rendered(changes: &[(ChangeKind, String)]) -> Vec<String> {
+        changes
+            .iter()
+            .map(|(kind, path)| format!("{kind} {path}"))
+            .collect()
+    }
+
+    #[test]
+    fn test_baseline_changes_uses_mtime_when_recorded() {
+        let info = |mtime| FileInfo {
+            size: 5,
+            mode: 0o644,
+            is_dir: false,
+            mtime,
+        };
+        let baseline = HashMap::from([
+            ("/same.txt".to_string(), info(100)),
+            ("/edited.txt".to_string(), info(100)),
+            ("/legacy.txt".to_string(), info(0)),
+            ("/gone.txt".to_string(), info(100)),
+        ]);
+        let current = HashMap::from([
+            ("/same.txt".to_string(), info(100)),
+            ("/edited.txt".to_string(), info(200)),
+            ("/legacy.txt".to_string(), info(200)),
+            ("/new.txt".to_string(), info(200)),
+        ]);
+        assert_eq!(
+            rendered(&baseline_changes(&baseline, &current)),
+            ["C /edited.txt", "D /gone.txt", "A /new.txt"]
+        );
+
+        // Snapshots written before mtime existed still parse.
+        let legacy: FileInfo =
+            serde_json::from_str(r#"{"size":1,"mode":420,"is_dir":false}"#).unwrap();
+        assert_eq!(legacy.mtime, 0);
+    }
+
+    #[test]
+    fn test_upper_changes_classifies_against_lower() {
+        let dir = tempfile::tempdir().unwrap();
+        let upper = dir.path().join("upper");
+        std::fs::create_dir_all(upper.join("etc")).unwrap();
+        std::fs::write(upper.join("etc/hosts"), "127.0.0.1 box").unwrap();
+        std::fs::write(upper.join("etc/new.conf"), "x").unwrap();
+        std::fs::create_dir_all(upper.join("app/cache")).unwrap();
+        std::fs::write(upper.join("app/cache/a"), "x").unwrap();
+        std::fs::write(upper.join(".wh.tmpfile"), "").unwrap();
+
+        let baseline = snapshot(&["/etc", "/etc/hosts", "/tmpfile"]);
+        let lower = Lower {
+            baseline: Some(&baseline),
+            dir: None,
+        };
+        assert_eq!(
+            rendered(&upper_changes(&upper, &lower).unwrap()),
+            [
+                "A /app",
+                "A /app/cache",
+                "A /app/cache/a",
+                "C /etc",
+                "C /etc/hosts",
+                "A /etc/new.conf",
+                "D /tmpfile",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upper_changes_opaque_dir_deletes_lower_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let lower_dir = dir.path().join("rootfs");
+        std::fs::create_dir_all(lower_dir.join("var/log")).unwrap();
+        std::fs::write(lower_dir.join("var/log/old.log"), "x").unwrap();
+        std::fs::write(lower_dir.join("var/log/keep.log"), "x").unwrap();
+
+        let upper = dir.path().join("upper");
+        std::fs::create_dir_all(upper.join("var/log")).unwrap();
+        std::fs::write(upper.join("var/log").join(OPAQUE_MARKER), "").unwrap();
+        std::fs::write(upper.join("var/log/keep.log"), "y").unwrap();
+
+        let lower = Lower {
+            baseline: None,
+            dir: Some(lower_dir),
+        };
+        assert_eq!(
+            rendered(&upper_changes(&upper, &lower).unwrap()),
+            [
+                "C /var",
+                "C /var/log",
+                "C /var/log/keep.log",
+                "D /var/log/old.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_helpers() {
+        assert_eq!(join_path("/", "etc"), "/etc");
+        assert_eq!(join_path("/etc", "hosts"), "/etc/hosts");
+        assert_eq!(parent_path("/etc"), "/");
+        assert_eq!(parent_path("/etc/hosts"), "/etc");
+    }
 }
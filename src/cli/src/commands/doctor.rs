@@ -0,0 +1,564 @@
+//! `a3s-box doctor` command — Diagnose the host environment.
+//!
+//! Runs the host preflight that `info` summarizes, then checks what a box
+//! needs beyond the host itself: the `a3s-box-shim` binary and the libkrun it
+//! links, the static guest init, the host RNG guests are seeded from, and the
+//! integrity of every stored image.
+//! Each problem comes with the command or setting that fixes it, and the
+//! command exits non-zero when anything failed.
+
+use std::path::Path;
+
+use a3s_box_core::error::BoxError;
+use a3s_box_runtime::{PreflightCheck, PreflightCheckKind, PreflightStatus};
+use clap::Args;
+
+#[derive(Args)]
+pub struct DoctorArgs {
+    /// Check that stored images are present without re-hashing their layers
+    #[arg(long)]
+    pub quick: bool,
+}
+
+/// One line of the report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Finding {
+    name: &'static str,
+    status: PreflightStatus,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Finding {
+    fn new(name: &'static str, status: PreflightStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// A runtime error as a failure, splitting off its remediation hint.
+    fn from_error(name: &'static str, error: BoxError) -> Self {
+        match error {
+            BoxError::BoxBootError { message, hint } => Self {
+                name,
+                status: PreflightStatus::Fail,
+                detail: message,
+                hint,
+            },
+            other => Self::new(name, PreflightStatus::Fail, other.to_string()),
+        }
+    }
+}
+
+impl From<&PreflightCheck> for Finding {
+    fn from(check: &PreflightCheck) -> Self {
+        let hint = match (&check.hint, check.kind, check.status) {
+            (Some(hint), _, _) => Some(hint.clone()),
+            (None, PreflightCheckKind::Virtualization, PreflightStatus::Fail) => {
+                virtualization_hint()
+            }
+            _ => None,
+        };
+        Self {
+            name: check.kind.name(),
+            status: check.status,
+            detail: check.detail.clone(),
+            hint,
+        }
+    }
+}
+
+pub async fn execute(args: DoctorArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let home = a3s_box_core::dirs_home();
+    let preflight = a3s_box_runtime::preflight(&a3s_box_runtime::PreflightOptions {
+        home_dir: home.clone(),
+        ..Default::default()
+    });
+
+    let mut findings: Vec<Finding> = preflight.checks.iter().map(Finding::from).collect();
+    match a3s_box_runtime::VmController::find_shim() {
+        Ok(path) => {
+            findings.push(Finding::new(
+                "shim",
+                PreflightStatus::Pass,
+                path.display().to_string(),
+            ));
+            findings.push(libkrun_check(&path));
+        }
+        Err(error) => {
+            findings.push(
+                Finding::new("shim", PreflightStatus::Fail, error.to_string()).with_hint(
+                    "Install a3s-box-shim next to a3s-box or in ~/.a3s/bin \
+                     (cargo build -p a3s-box-shim)",
+                ),
+            );
+            findings.push(Finding::new(
+                "libkrun",
+                PreflightStatus::Skip,
+                "no shim to inspect",
+            ));
+        }
+    }
+    findings.push(match a3s_box_runtime::VmManager::find_guest_init() {
+        Ok(path) => Finding::new(
+            "guest_init",
+            PreflightStatus::Pass,
+            format!("{} (static Linux ELF)", path.display()),
+        ),
+        Err(error) => Finding::from_error("guest_init", error),
+    });
+    findings.push(entropy_check());
+    findings.push(image_store_check(args.quick).await);
+
+    print!("{}", render(&findings));
+    let failed = count(&findings, PreflightStatus::Fail);
+    if failed > 0 {
+        return Err(format!(
+            "{failed} check{} failed",
+            if failed == 1 { "" } else { "s" }
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// `info` only shows the virtualization error; doctor adds how to fix it.
+fn virtualization_hint() -> Option<String> {
+    if cfg!(target_os = "linux") {
+        Some(if Path::new("/dev/kvm").exists() {
+            "Give your user access to /dev/kvm: sudo usermod -aG kvm $USER, then log in again"
+                .to_string()
+        } else {
+            "Enable VT-x/AMD-V in the firmware and load KVM: sudo modprobe kvm_intel \
+             (or kvm_amd)"
+                .to_string()
+        })
+    } else if cfg!(target_os = "macos") {
+        Some("Boxes need Hypervisor.framework on Apple Silicon".to_string())
+    } else if cfg!(target_os = "windows") {
+        Some("Enable the 'Windows Hypervisor Platform' optional feature and reboot".to_string())
+    } else {
+        None
+    }
+}
+
+/// Whether the shim's libkrun resolves, and which version it is.
+#[cfg(target_os = "linux")]
+fn libkrun_check(shim: &Path) -> Finding {
+    let output = match std::process::Command::new("ldd").arg(shim).output() {
+        Ok(output) => output,
+        Err(error) => {
+            return Finding::new(
+                "libkrun",
+                PreflightStatus::Warn,
+                format!("cannot run ldd: {error}"),
+            )
+        }
+    };
+    match ldd_linkage(&String::from_utf8_lossy(&output.stdout), "libkrun.so") {
+        Linkage::Resolved(path) => {
+            let real = std::fs::canonicalize(&path).unwrap_or(path);
+            let version = real
+                .file_name()
+                .and_then(|name| library_version(&name.to_string_lossy()))
+                .unwrap_or_else(|| "unknown version".to_string());
+            Finding::new(
+                "libkrun",
+                PreflightStatus::Pass,
+                format!("{version} ({})", real.display()),
+            )
+        }
+        Linkage::Missing(soname) => Finding::new(
+            "libkrun",
+            PreflightStatus::Fail,
+            format!("{soname} not found by the dynamic loader"),
+        )
+        .with_hint(
+            "Install libkrun from the release archive's lib directory, or add its \
+             directory to /etc/ld.so.conf.d and run sudo ldconfig",
+        ),
+        Linkage::NotLinked => Finding::new(
+            "libkrun",
+            PreflightStatus::Warn,
+            format!("{} does not link libkrun dynamically", shim.display()),
+        ),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn libkrun_check(shim: &Path) -> Finding {
+    let output = match std::process::Command::new("otool")
+        .arg("-L")
+        .arg(shim)
+        .output()
+    {
+        Ok(output) => output,
+        Err(error) => {
+            return Finding::new(
+                "libkrun",
+                PreflightStatus::Warn,
+                format!("cannot run otool: {error}"),
+            )
+        }
+    };
+    match otool_libkrun(&String::from_utf8_lossy(&output.stdout)) {
+        Some((path, version)) => Finding::new(
+            "libkrun",
+            PreflightStatus::Pass,
+            format!("{version} ({path})"),
+        ),
+        None => Finding::new(
+            "libkrun",
+            PreflightStatus::Warn,
+            format!("{} does not link libkrun dynamically", shim.display()),
+        ),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn libkrun_check(shim: &Path) -> Finding {
+    let dir = shim.parent().unwrap_or(Path::new("."));
+    let missing: Vec<&str> = ["krun.dll", "libkrunfw.dll"]
+        .into_iter()
+        .filter(|dll| !dir.join(dll).is_file())
+        .collect();
+    if missing.is_empty() {
+        Finding::new(
+            "libkrun",
+            PreflightStatus::Pass,
+            format!("krun.dll and libkrunfw.dll in {}", dir.display()),
+        )
+    } else {
+        Finding::new(
+            "libkrun",
+            PreflightStatus::Fail,
+            format!("{} missing from {}", missing.join(", "), dir.display()),
+        )
+        .with_hint("Install the matching DLLs from the Windows release archive next to the shim")
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn libkrun_check(_shim: &Path) -> Finding {
+    Finding::new(
+        "libkrun",
+        PreflightStatus::Skip,
+        "not inspected on this platform",
+    )
+}
+
+/// Boxes seed their guest CRNG from the host RNG at boot; a host whose own
+/// CRNG is not ready stalls every box start.
+#[cfg(target_os = "linux")]
+fn entropy_check() -> Finding {
+    let mut byte = 0u8;
+    // SAFETY: the buffer is one writable byte.
+    let rc = unsafe { libc::getrandom((&mut byte as *mut u8).cast(), 1, libc::GRND_NONBLOCK) };
+    let ready = rc == 1 || std::io::Error::last_os_error().raw_os_error() != Some(libc::EAGAIN);
+    let entropy_avail = std::fs::read_to_string("/proc/sys/kernel/random/entropy_avail")
+        .ok()
+        .and_then(|value| value.trim().parse().ok());
+    entropy_finding(ready, entropy_avail)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn entropy_check() -> Finding {
+    Finding::new(
+        "entropy",
+        PreflightStatus::Pass,
+        "the system RNG does not block",
+    )
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn entropy_finding(ready: bool, entropy_avail: Option<u32>) -> Finding {
+    let pool = entropy_avail
+        .map(|bits| format!(" ({bits} bits in the input pool)"))
+        .unwrap_or_default();
+    if ready {
+        Finding::new(
+            "entropy",
+            PreflightStatus::Pass,
+            format!("host CRNG ready{pool}"),
+        )
+    } else {
+        Finding::new(
+            "entropy",
+            PreflightStatus::Warn,
+            format!("host CRNG not initialized{pool}; box starts block until it is"),
+        )
+        .with_hint(
+            "Start rngd (rng-tools) or haveged, or give the host a hardware RNG \
+             (virtio-rng when it is itself a VM)",
+        )
+    }
+}
+
+/// How a binary links a shared library, per `ldd`.
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, PartialEq, Eq)]
+enum Linkage {
+    Resolved(std::path::PathBuf),
+    /// Linked, but the loader cannot find it; carries the soname.
+    Missing(String),
+    NotLinked,
+}
+
+/// Find the library whose soname starts with `prefix` in `ldd` output.
+#[cfg(any(target_os = "linux", test))]
+fn ldd_linkage(output: &str, prefix: &str) -> Linkage {
+    for line in output.lines() {
+        let Some((soname, target)) = line.trim().split_once(" => ") else {
+            continue;
+        };
+        if !soname.starts_with(prefix) {
+            continue;
+        }
+        return match target.split_whitespace().next() {
+            Some(path) if path != "not" => Linkage::Resolved(path.into()),
+            _ => Linkage::Missing(soname.to_string()),
+        };
+    }
+    Linkage::NotLinked
+}
+
+/// The libkrun install name and current version from `otool -L` output.
+#[cfg(any(target_os = "macos", test))]
+fn otool_libkrun(output: &str) -> Option<(String, String)> {
+    output.lines().find_map(|line| {
+        let (path, rest) = line.trim().split_once(" (")?;
+        let file = path.rsplit('/').next()?;
+        if !file.starts_with("libkrun.") {
+            return None;
+        }
+        let version = rest
+            .split(", ")
+            .find_map(|field| field.strip_prefix("current version "))?
+            .trim_end_matches(')');
+        Some((path.to_string(), version.to_string()))
+    })
+}
+
+/// The version in a versioned library file name (`libkrun.so.1.15.1`).
+#[cfg(any(target_os = "linux", test))]
+fn library_version(file_name: &str) -> Option<String> {
+    let (_, version) = file_name.split_once(".so.")?;
+    (!version.is_empty() && version.split('.').all(|part| part.parse::<u32>().is_ok()))
+        .then(|| version.to_string())
+}
+
+/// Every stored image must load as a valid OCI layout; without `quick`, its
+/// manifest, config and layer digests are verified too.
+async fn image_store_check(quick: bool) -> Finding {
+    let store = match super::open_image_store() {
+        Ok(store) => store,
+        Err(error) => {
+            return Finding::new("image_store", PreflightStatus::Fail, error.to_string())
+                .with_hint("Set A3S_HOME to a writable directory")
+        }
+    };
+    let images = store.list().await;
+    let total = images.len();
+    let checked = tokio::task::spawn_blocking(move || {
+        images
+            .iter()
+            .filter_map(|image| {
+                let problem = if quick {
+                    (!image.path.join("index.json").is_file())
+                        .then(|| "missing index.json".to_string())
+                } else {
+                    a3s_box_runtime::OciImage::from_path(&image.path)
+                        .err()
+                        .map(|error| error.to_string())
+                };
+                problem.map(|problem| (image.reference.clone(), problem))
+            })
+            .collect::<Vec<_>>()
+    })
+    .await;
+    match checked {
+        Ok(broken) => image_store_finding(total, &broken, quick),
+        Err(error) => Finding::new(
+            "image_store",
+            PreflightStatus::Warn,
+            format!("verification task failed: {error}"),
+        ),
+    }
+}
+
+fn image_store_finding(total: usize, broken: &[(String, String)], quick: bool) -> Finding {
+    let what = if quick { "present" } else { "verified" };
+    if broken.is_empty() {
+        return Finding::new(
+            "image_store",
+            PreflightStatus::Pass,
+            format!("{total} image{} {what}", if total == 1 { "" } else { "s" }),
+        );
+    }
+    let detail = broken
+        .iter()
+        .map(|(reference, problem)| format!("{reference}: {problem}"))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let first = &broken[0].0;
+    Finding::new(
+        "image_store",
+        PreflightStatus::Fail,
+        format!("{} of {total} images damaged ({detail})", broken.len()),
+    )
+    .with_hint(format!(
+        "Remove and pull each damaged image again, e.g. a3s-box rmi {first} && a3s-box pull {first}"
+    ))
+}
+
+fn count(findings: &[Finding], status: PreflightStatus) -> usize {
+    findings
+        .iter()
+        .filter(|finding| finding.status == status)
+        .count()
+}
+
+fn render(findings: &[Finding]) -> String {
+    let mut out = String::new();
+    for finding in findings {
+        out.push_str(&format!(
+            "[{}] {}: {}\n",
+            finding.status, finding.name, finding.detail
+        ));
+        if let Some(hint) = &finding.hint {
+            out.push_str(&format!("       fix: {hint}\n"));
+        }
+    }
+    out.push_str(&format!(
+        "\n{} passed, {} warnings, {} failed, {} skipped\n",
+        count(findings, PreflightStatus::Pass),
+        count(findings, PreflightStatus::Warn),
+        count(findings, PreflightStatus::Fail),
+        count(findings, PreflightStatus::Skip)
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ldd_linkage() {
+        let output = "\tlinux-vdso.so.1 (0x00007ffc)\n\
+                      \tlibkrun.so.1 => /usr/local/lib64/libkrun.so.1 (0x00007f10)\n\
+                      \tlibc.so.6 => /lib64/libc.so.6 (0x00007f20)\n";
+        assert_eq!(
+            ldd_linkage(output, "libkrun.so"),
+            Linkage::Resolved(PathBuf::from("/usr/local/lib64/libkrun.so.1"))
+        );
+        assert_eq!(
+            ldd_linkage("\tlibkrun.so.1 => not found\n", "libkrun.so"),
+            Linkage::Missing("libkrun.so.1".to_string())
+        );
+        assert_eq!(
+            ldd_linkage("\tstatically linked\n", "libkrun.so"),
+            Linkage::NotLinked
+        );
+    }
+
+    #[test]
+    fn test_library_versions() {
+        assert_eq!(
+            library_version("libkrun.so.1.15.1").as_deref(),
+            Some("1.15.1")
+        );
+        assert_eq!(library_version("libkrun.so"), None);
+        assert_eq!(library_version("libkrun.so.x"), None);
+
+        let otool = "/opt/a3s/bin/a3s-box-shim:\n\
+                     \t@rpath/libkrun.1.dylib (compatibility version 1.0.0, current version 1.15.1)\n\
+                     \t/usr/lib/libSystem.B.dylib (compatibility version 1.0.0, current version 1351.0.0)\n";
+        assert_eq!(
+            otool_libkrun(otool),
+            Some(("@rpath/libkrun.1.dylib".to_string(), "1.15.1".to_string()))
+        );
+        assert_eq!(otool_libkrun("/usr/lib/libSystem.B.dylib (x)\n"), None);
+    }
+
+    #[test]
+    fn test_findings_from_preflight_and_errors() {
+        let failure = Finding::from_error(
+            "guest_init",
+            BoxError::BoxBootError {
+                message: "Linux guest init binary not found".to_string(),
+                hint: Some("cargo build -p a3s-box-guest-init".to_string()),
+            },
+        );
+        assert_eq!(failure.status, PreflightStatus::Fail);
+        assert_eq!(failure.detail, "Linux guest init binary not found");
+        assert_eq!(
+            failure.hint.as_deref(),
+            Some("cargo build -p a3s-box-guest-init")
+        );
+
+        let mut check = PreflightCheck {
+            kind: PreflightCheckKind::Virtualization,
+            status: PreflightStatus::Fail,
+            detail: "KVM is not available".to_string(),
+            hint: None,
+        };
+        let finding = Finding::from(&check);
+        assert_eq!(finding.name, "virtualization");
+        assert_eq!(finding.hint.is_some(), cfg!(target_os = "linux"));
+
+        check.kind = PreflightCheckKind::Passt;
+        check.hint = Some("Install passt".to_string());
+        assert_eq!(Finding::from(&check).hint.as_deref(), Some("Install passt"));
+    }
+
+    #[test]
+    fn test_entropy_finding() {
+        let ready = entropy_finding(true, Some(256));
+        assert_eq!(ready.status, PreflightStatus::Pass);
+        assert_eq!(ready.detail, "host CRNG ready (256 bits in the input pool)");
+
+        let starved = entropy_finding(false, None);
+        assert_eq!(starved.status, PreflightStatus::Warn);
+        assert_eq!(
+            starved.detail,
+            "host CRNG not initialized; box starts block until it is"
+        );
+        assert!(starved.hint.unwrap().contains("rngd"));
+    }
+
+    #[test]
+    fn test_image_store_finding_and_render() {
+        let ok = image_store_finding(1, &[], false);
+        assert_eq!(ok.detail, "1 image verified");
+
+        let broken = image_store_finding(
+            3,
+            &[("alpine:latest".to_string(), "missing layer".to_string())],
+            true,
+        );
+        assert_eq!(broken.status, PreflightStatus::Fail);
+        assert_eq!(
+            broken.detail,
+            "1 of 3 images damaged (alpine:latest: missing layer)"
+        );
+
+        assert_eq!(
+            render(&[ok, broken]),
+            "[pass] image_store: 1 image verified\n\
+             [fail] image_store: 1 of 3 images damaged (alpine:latest: missing layer)\n       \
+             fix: Remove and pull each damaged image again, e.g. \
+             a3s-box rmi alpine:latest && a3s-box pull alpine:latest\n\
+             \n1 passed, 0 warnings, 1 failed, 0 skipped\n"
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! `a3s-box eval` command — Run scored agent evaluation tasks.
+//!
+//! Every task in the suite directory boots a fresh box with its fixtures
+//! mounted, runs the agent command with the task prompt on stdin, then runs
+//! the task's assertion script through the exec server. Tasks run in parallel
+//! and the results are summarized as a weighted score, so agent and skill
+//! changes can be regression-tested in-repo.
+
+use std::path::PathBuf;
+
+use a3s_box_core::eval::{EvalReport, EvalTask, EvalTaskResult};
+use clap::{Args, ValueEnum};
+
+use crate::output;
+
+#[derive(Args)]
+pub struct EvalArgs {
+    /// Suite directory (one subdirectory with a task.yaml per task)
+    pub suite: PathBuf,
+
+    /// Image for tasks that do not name one
+    #[arg(long)]
+    pub image: Option<String>,
+
+    /// Only run tasks whose name contains this string
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Number of tasks to run at once
+    #[arg(short = 'j', long, default_value = "4")]
+    pub parallel: usize,
+
+    /// Number of vCPUs per task box
+    #[arg(long, default_value = "2")]
+    pub cpus: u32,
+
+    /// Memory per task box (e.g., "1g")
+    #[arg(long, default_value = "1g")]
+    pub memory: String,
+
+    /// Fail unless the weighted suite score reaches this value (0..1);
+    /// by default every task must pass
+    #[arg(long)]
+    pub min_score: Option<f64>,
+
+    /// Write the JSON report to a file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = EvalFormat::Table)]
+    format: EvalFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum EvalFormat {
+    Table,
+    Json,
+}
+
+pub async fn execute(args: EvalArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.parallel == 0 {
+        return Err("--parallel must be at least 1".into());
+    }
+    if let Some(min_score) = args.min_score {
+        if !(0.0..=1.0).contains(&min_score) {
+            return Err("--min-score must be between 0 and 1".into());
+        }
+    }
+    let memory_mb =
+        output::parse_memory(&args.memory).map_err(|e| format!("Invalid --memory: {e}"))?;
+
+    let mut tasks = a3s_box_core::eval::discover_eval_tasks(&args.suite)?;
+    if let Some(filter) = &args.filter {
+        tasks.retain(|task| task.name.contains(filter.as_str()));
+    }
+    if tasks.is_empty() {
+        return Err(format!("no eval tasks found in {}", args.suite.display()).into());
+    }
+    if let Some(task) = tasks.iter().find(|t| t.image.is_none()) {
+        if args.image.is_none() {
+            return Err(format!("task '{}' names no image; pass --image", task.name).into());
+        }
+    }
+
+    let started_at = chrono::Utc::now();
+    let start = std::time::Instant::now();
+    let runner = TaskRunner {
+        default_image: args.image.clone().unwrap_or_default(),
+        cpus: args.cpus,
+        memory_mb,
+    };
+    let results = run_tasks(&runner, tasks, args.parallel).await;
+    let report = EvalReport::new(
+        args.suite.display().to_string(),
+        started_at,
+        start.elapsed().as_millis() as u64,
+        results,
+    );
+
+    if let Some(path) = &args.output {
+        std::fs::write(path, serde_json::to_string_pretty(&report)? + "\n")
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    }
+    match args.format {
+        EvalFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        EvalFormat::Table => print_report(&report),
+    }
+
+    let ok = match args.min_score {
+        Some(min_score) => report.score >= min_score,
+        None => report.failed == 0,
+    };
+    if !ok {
+        return Err(format!(
+            "eval failed: score {:.3}, {} of {} task(s) failed",
+            report.score,
+            report.failed,
+            report.tasks.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn print_report(report: &EvalReport) {
+    let mut table = output::new_table(&["TASK", "RESULT", "SCORE", "WEIGHT", "DURATION", "DETAIL"]);
+    for task in &report.tasks {
+        table.add_row(task_row(task));
+    }
+    println!("{table}");
+    println!(
+        "score {:.3} — {} passed, {} failed in {:.1}s",
+        report.score,
+        report.passed,
+        report.failed,
+        report.duration_ms as f64 / 1000.0
+    );
+}
+
+fn task_row(task: &EvalTaskResult) -> Vec<String> {
+    let result = if task.error.is_some() {
+        "error"
+    } else if task.passed {
+        "pass"
+    } else {
+        "fail"
+    };
+    let detail = task
+        .error
+        .clone()
+        .or_else(|| task.output.lines().last().map(str::to_string))
+        .unwrap_or_default();
+    vec![
+        task.name.clone(),
+        result.to_string(),
+        format!("{:.2}", task.score),
+        format!("{}", task.weight),
+        format!("{:.1}s", task.duration_ms as f64 / 1000.0),
+        truncate_detail(&detail, 60),
+    ]
+}
+
+fn truncate_detail(detail: &str, max_chars: usize) -> String {
+    if detail.chars().count() <= max_chars {
+        return detail.to_string();
+    }
+    let kept: String = detail.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{kept}...")
+}
+
+/// Settings shared by every task box in a run.
+struct TaskRunner {
+    default_image: String,
+    cpus: u32,
+    memory_mb: u32,
+}
+
+impl TaskRunner {
+    fn image_for(&self, task: &EvalTask) -> String {
+        task.image
+            .clone()
+            .unwrap_or_else(|| self.default_image.clone())
+    }
+}
+
+/// Run `tasks` at most `parallel` at a time; results come back in any order.
+async fn run_tasks(
+    runner: &TaskRunner,
+    tasks: Vec<EvalTask>,
+    parallel: usize,
+) -> Vec<EvalTaskResult> {
+    use futures::StreamExt;
+
+    futures::stream::iter(tasks)
+        .map(|task| async move {
+            let start = std::time::Instant::now();
+            let mut result = EvalTaskResult {
+                name: task.name.clone(),
+                image: runner.image_for(&task),
+                weight: task.weight,
+                score: 0.0,
+                passed: false,
+                duration_ms: 0,
+                agent_exit_code: None,
+                assert_exit_code: None,
+                output: String::new(),
+                error: None,
+            };
+            if let Err(error) = run_task(runner, &task, &mut result).await {
+                result.score = 0.0;
+                result.passed = false;
+                result.error = Some(error);
+            }
+            result.duration_ms = start.elapsed().as_millis() as u64;
+            eprintln!(
+                "a3s-box: eval {}: {} ({:.2})",
+                result.name,
+                if result.passed { "pass" } else { "fail" },
+                result.score
+            );
+            result
+        })
+        .buffer_unordered(parallel)
+        .collect()
+        .await
+}
+
+/// Keep the task box alive while the agent and assertion run over exec.
+#[cfg(not(windows))]
+fn keepalive_cmd() -> Vec<String> {
+    vec![
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        "trap 'exit 0' TERM INT; while :; do sleep 3600; done".to_string(),
+    ]
+}
+
+/// Boot a fresh box for `task`, run it, and always tear the box down.
+#[cfg(not(windows))]
+async fn run_task(
+    runner: &TaskRunner,
+    task: &EvalTask,
+    result: &mut EvalTaskResult,
+) -> Result<(), String> {
+    use a3s_box_core::config::{BoxConfig, ResourceConfig};
+    use a3s_box_core::event::EventEmitter;
+
+    let config = BoxConfig {
+        image: result.image.clone(),
+        resources: ResourceConfig {
+            vcpus: runner.cpus,
+            memory_mb: runner.memory_mb,
+            ..Default::default()
+        },
+        cmd: keepalive_cmd(),
+        // Ignore the image ENTRYPOINT so the keepalive runs as the main process.
+        entrypoint_override: Some(Vec::new()),
+        volumes: task.fixtures.clone(),
+        ..Default::default()
+    };
+    let mut vm = a3s_box_runtime::VmManager::new(config, EventEmitter::new(256));
+    let outcome = async {
+        vm.boot()
+            .await
+            .map_err(|e| format!("failed to boot box: {e}"))?;
+        vm.wait_for_exec_available(std::time::Duration::from_secs(120))
+            .await
+            .map_err(|e| format!("exec server did not come up: {e}"))?;
+        score_task(&vm, task, result).await
+    }
+    .await;
+    if let Err(error) = vm.destroy().await {
+        tracing::warn!(task = %task.name, %error, "Failed to destroy eval box");
+    }
+    outcome
+}
+
+#[cfg(windows)]
+async fn run_task(
+    _runner: &TaskRunner,
+    _task: &EvalTask,
+    _result: &mut EvalTaskResult,
+) -> Result<(), String> {
+    Err(crate::platform::unsupported_command("eval", "guest exec channel support").to_string())
+}
+
+/// Run the agent command, then the assertion, inside a booted task box.
+#[cfg(not(windows))]
+async fn score_task(
+    vm: &a3s_box_runtime::VmManager,
+    task: &EvalTask,
+    result: &mut EvalTaskResult,
+) -> Result<(), String> {
+    use a3s_box_core::eval::{
+        EVAL_AGENT_EXIT_ENV, EVAL_AGENT_OUTPUT_ENV, EVAL_AGENT_OUTPUT_PATH, EVAL_PROMPT_ENV,
+    };
+    use a3s_box_core::exec::ExecRequest;
+
+    let mut env: Vec<String> = task.env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    env.push(format!("{EVAL_PROMPT_ENV}={}", task.prompt));
+
+    let mut agent_stdout = Vec::new();
+    if !task.command.is_empty() {
+        let output = vm
+            .exec_request(&ExecRequest {
+                request_id: None,
+                cmd: task.command.clone(),
+                timeout_ns: task.timeout_secs.saturating_mul(1_000_000_000),
+                env: env.clone(),
+                working_dir: None,
+                rootfs: None,
+                stdin: Some(task.prompt.clone().into_bytes()),
+                stdin_streaming: false,
+                user: None,
+                streaming: false,
+            })
+            .await
+            .map_err(|e| format!("agent command failed to run: {e}"))?;
+        result.agent_exit_code = Some(output.exit_code);
+        agent_stdout = output.stdout;
+    }
+
+    // Hand the agent's stdout to the assertion as a guest file.
+    vm.exec_request(&ExecRequest {
+        request_id: None,
+        cmd: vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            format!("cat > {EVAL_AGENT_OUTPUT_PATH}"),
+        ],
+        timeout_ns: 30_000_000_000,
+        env: Vec::new(),
+        working_dir: None,
+        rootfs: None,
+        stdin: Some(agent_stdout),
+        stdin_streaming: false,
+        user: None,
+        streaming: false,
+    })
+    .await
+    .map_err(|e| format!("failed to store agent output: {e}"))?;
+
+    env.push(format!(
+        "{EVAL_AGENT_EXIT_ENV}={}",
+        result.agent_exit_code.unwrap_or(0)
+    ));
+    env.push(format!("{EVAL_AGENT_OUTPUT_ENV}={EVAL_AGENT_OUTPUT_PATH}"));
+    let output = vm
+        .exec_request(&ExecRequest {
+            request_id: None,
+            cmd: vec!["/bin/sh".to_string(), "-s".to_string()],
+            timeout_ns: task.timeout_secs.saturating_mul(1_000_000_000),
+            env,
+            working_dir: None,
+            rootfs: None,
+            stdin: Some(task.assert_script.clone().into_bytes()),
+            stdin_streaming: false,
+            user: None,
+            streaming: false,
+        })
+        .await
+        .map_err(|e| format!("assertion failed to run: {e}"))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    result.assert_exit_code = Some(output.exit_code);
+    result.score = a3s_box_core::eval::assertion_score(&stdout, output.exit_code);
+    result.passed = output.exit_code == 0 && result.score >= 1.0;
+    result.output = output_tail(&stdout, &String::from_utf8_lossy(&output.stderr));
+    Ok(())
+}
+
+/// Last lines of the assertion's combined output, kept for the report.
+fn output_tail(stdout: &str, stderr: &str) -> String {
+    const TAIL_LINES: usize = 20;
+    let lines: Vec<&str> = stdout
+        .lines()
+        .chain(stderr.lines())
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    lines[lines.len().saturating_sub(TAIL_LINES)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_tail_keeps_last_lines() {
+        let stdout: String = (0..30).map(|i| format!("line {i}\n")).collect();
+
+        let tail = output_tail(&stdout, "boom\n");
+
+        assert_eq!(tail.lines().count(), 20);
+        assert!(tail.starts_with("line 11"));
+        assert!(tail.ends_with("boom"));
+    }
+
+    #[test]
+    fn test_task_row_prefers_error_detail() {
+        let result = EvalTaskResult {
+            name: "t".to_string(),
+            image: "alpine".to_string(),
+            weight: 1.0,
+            score: 0.0,
+            passed: false,
+            duration_ms: 1500,
+            agent_exit_code: None,
+            assert_exit_code: None,
+            output: "ignored".to_string(),
+            error: Some("failed to boot box".to_string()),
+        };
+
+        let row = task_row(&result);
+
+        assert_eq!(row[1], "error");
+        assert_eq!(row[4], "1.5s");
+        assert_eq!(row[5], "failed to boot box");
+    }
+}
@@ -1,11 +1,13 @@
 //! `a3s-box exec` command — Execute a command in a running box.
 //!
 //! Connects to the exec server inside the guest VM via the exec Unix socket
-//! and runs the specified command, printing stdout/stderr and exiting with
-//! the command's exit code.
+//! and runs the specified command, streaming stdout/stderr as it is produced
+//! (and stdin with `-i`) and exiting with the command's exit code.
 //!
 //! When `-t` (tty) is specified, allocates a PTY in the guest for interactive
-//! terminal sessions (e.g., `a3s-box exec -it mybox /bin/sh`).
+//! terminal sessions (e.g., `a3s-box exec -it mybox /bin/sh`). Local terminal
+//! size changes are forwarded to the guest PTY, and stdin is only forwarded
+//! with `-i`, as in Docker.
 
 use clap::Args;
 
@@ -120,20 +122,6 @@ pub async fn execute(args: ExecArgs) -> Result<(), Box<dyn std::error::Error>> {
 
     let timeout_ns = timeout_secs_to_ns(args.timeout);
 
-    // Read stdin if interactive mode
-    let stdin_data = if args.interactive {
-        use std::io::Read;
-        let mut buf = Vec::new();
-        std::io::stdin().read_to_end(&mut buf)?;
-        if buf.is_empty() {
-            None
-        } else {
-            Some(buf)
-        }
-    } else {
-        None
-    };
-
     let request = ExecRequest {
         request_id: None,
         cmd: args.cmd,
@@ -141,13 +129,13 @@ pub async fn execute(args: ExecArgs) -> Result<(), Box<dyn std::error::Error>> {
         env: args.envs,
         working_dir: args.workdir,
         rootfs: None,
-        stdin: stdin_data,
-        stdin_streaming: false,
+        stdin: None,
+        stdin_streaming: args.interactive,
         user,
-        streaming: false,
+        streaming: true,
     };
 
-    let output = client.exec_command(&request).await?;
+    let mut exec = client.exec_stream(&request).await?;
     // Record that an exec happened (best-effort) before the exit-code branch
     // below may std::process::exit. The container command's own exit code is
     // separate from whether the exec was delivered.
@@ -158,23 +146,105 @@ pub async fn execute(args: ExecArgs) -> Result<(), Box<dyn std::error::Error>> {
         &format!("exec command in box {}", record.name),
     );
 
-    if !output.stdout.is_empty() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        print!("{}", stdout);
-    }
-
-    if !output.stderr.is_empty() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprint!("{}", stderr);
+    if args.interactive {
+        pump_stdin(exec.input());
     }
+    let exit_code = relay_exec_output(&mut exec).await?;
 
-    if output.exit_code != 0 {
-        std::process::exit(output.exit_code);
+    if exit_code != 0 {
+        std::process::exit(exit_code);
     }
 
     Ok(())
 }
 
+/// Forward local stdin to the running command, closing its stdin at EOF.
+///
+/// The blocking read runs on a detached OS thread (see
+/// [`run_detachable_pty_session`]) so it cannot keep the runtime alive once
+/// the command exits.
+#[cfg(not(windows))]
+fn pump_stdin(input: a3s_box_runtime::StreamingExecInput) {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+    std::thread::spawn(move || {
+        use std::io::Read;
+        let mut stdin = std::io::stdin();
+        let mut buf = [0u8; 16 * 1024];
+        loop {
+            match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    tokio::spawn(async move {
+        while let Some(bytes) = rx.recv().await {
+            if input.write_stdin(&bytes).await.is_err() {
+                return;
+            }
+        }
+        let _ = input.close_stdin().await;
+    });
+}
+
+/// Copy the command's output to local stdout/stderr as it arrives and
+/// return its exit code.
+///
+/// Ctrl-C sends SIGTERM to the command and keeps waiting for it to exit; a
+/// second Ctrl-C kills it.
+#[cfg(not(windows))]
+async fn relay_exec_output(
+    exec: &mut a3s_box_runtime::StreamingExec,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    use a3s_box_core::exec::{ExecEvent, StreamType};
+    use a3s_box_core::ExecutionProcessSignal;
+
+    let input = exec.input();
+    let interrupts = tokio::spawn(async move {
+        let mut signal = ExecutionProcessSignal::Terminate;
+        while tokio::signal::ctrl_c().await.is_ok() {
+            let _ = input.send_signal(signal).await;
+            signal = ExecutionProcessSignal::Kill;
+        }
+    });
+
+    let mut stdout = tokio::io::stdout();
+    let mut stderr = tokio::io::stderr();
+    let result = loop {
+        match exec.next_event().await {
+            Ok(Some(ExecEvent::Chunk(chunk))) => {
+                let written = match chunk.stream {
+                    StreamType::Stdout => write_flushed(&mut stdout, &chunk.data).await,
+                    StreamType::Stderr => write_flushed(&mut stderr, &chunk.data).await,
+                };
+                if let Err(error) = written {
+                    break Err(error.into());
+                }
+            }
+            Ok(Some(ExecEvent::FlushAck)) => {}
+            Ok(Some(ExecEvent::Exit(exit))) => break Ok(exit.exit_code),
+            Ok(None) => break Err("exec stream closed before the command exited".into()),
+            Err(error) => break Err(error.into()),
+        }
+    };
+    interrupts.abort();
+    result
+}
+
+#[cfg(not(windows))]
+async fn write_flushed<W: tokio::io::AsyncWrite + Unpin>(
+    out: &mut W,
+    data: &[u8],
+) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+    out.write_all(data).await?;
+    out.flush().await
+}
+
 #[cfg(not(windows))]
 fn timeout_secs_to_ns(timeout_secs: u64) -> u64 {
     if timeout_secs == 0 {
@@ -196,6 +266,10 @@ async fn execute_pty(
     use crate::terminal;
     use a3s_box_core::pty::PtyRequest;
 
+    if args.interactive && !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return Err("the input device is not a TTY; drop -t to pipe stdin with -i".into());
+    }
+
     let pty_socket_path = crate::socket_paths::require_runtime_socket(
         record,
         crate::socket_paths::RuntimeSocket::Pty,
@@ -218,6 +292,8 @@ async fn execute_pty(
         user,
         cols,
         rows,
+        session: None,
+        resume: false,
     };
     client.send_request(&request).await?;
     // Record the interactive (pty) exec once the request is delivered — opening
@@ -232,9 +308,17 @@ async fn execute_pty(
     // Split the PTY client stream for concurrent read/write
     let (read_half, write_half) = client.into_split();
 
-    let exit_code = {
+    // Without -i the local terminal keeps its own line discipline, so typed
+    // keys are not sent and Ctrl-C ends this command as usual.
+    let end = if args.interactive {
         let _raw_mode = terminal::raw_mode()?;
-        run_pty_session(read_half, write_half).await
+        run_detachable_pty_session(read_half, write_half, Vec::new(), true).await
+    } else {
+        run_detachable_pty_session(read_half, write_half, Vec::new(), false).await
+    };
+    let exit_code = match end {
+        PtySessionEnd::Exited(exit_code) => exit_code,
+        PtySessionEnd::Detached => 0,
     };
 
     if exit_code != 0 {
@@ -252,13 +336,39 @@ async fn execute_pty(
 /// Returns the process exit code.
 #[cfg(not(windows))]
 pub(crate) async fn run_pty_session(
+    reader: a3s_transport::FrameReader<tokio::io::ReadHalf<tokio::net::UnixStream>>,
+    writer: a3s_transport::FrameWriter<tokio::io::WriteHalf<tokio::net::UnixStream>>,
+) -> i32 {
+    match run_detachable_pty_session(reader, writer, Vec::new(), true).await {
+        PtySessionEnd::Exited(exit_code) => exit_code,
+        PtySessionEnd::Detached => 0,
+    }
+}
+
+/// How an interactive PTY session ended.
+#[cfg(not(windows))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PtySessionEnd {
+    /// The guest process exited with this code.
+    Exited(i32),
+    /// The user typed the detach sequence; the guest process keeps running.
+    Detached,
+}
+
+/// Like [`run_pty_session`], but ends the session early when stdin carries
+/// `detach_keys` (see [`crate::detach_keys`]). With `forward_stdin` unset,
+/// local input is not read and only output and resizes are relayed.
+#[cfg(not(windows))]
+pub(crate) async fn run_detachable_pty_session(
     mut reader: a3s_transport::FrameReader<tokio::io::ReadHalf<tokio::net::UnixStream>>,
     mut writer: a3s_transport::FrameWriter<tokio::io::WriteHalf<tokio::net::UnixStream>>,
-) -> i32 {
+    detach_keys: Vec<u8>,
+    forward_stdin: bool,
+) -> PtySessionEnd {
     use a3s_box_core::pty::{FRAME_PTY_DATA, FRAME_PTY_ERROR, FRAME_PTY_EXIT};
 
     // Task 1: Read from guest PTY → write to stdout
-    let reader_task = tokio::spawn(async move {
+    let mut reader_task = tokio::spawn(async move {
         let mut stdout = tokio::io::stdout();
         loop {
             match reader.read_frame().await {
@@ -300,38 +410,49 @@ pub(crate) async fn run_pty_session(
     // readiness events for TTY fds in raw mode, causing reads to block
     // indefinitely. Use a detached OS thread instead of spawn_blocking so a
     // blocked stdin read cannot keep the Tokio runtime alive after PTY exit.
+    let (detached_tx, detached_rx) = tokio::sync::oneshot::channel::<()>();
     let writer_task = tokio::spawn(async move {
         let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<u8>>(64);
-
-        std::thread::spawn(move || {
-            use std::io::Read;
-            let mut stdin = std::io::stdin();
-            let mut buf = [0u8; 4096];
-            loop {
-                match stdin.read(&mut buf) {
-                    Ok(0) | Err(_) => break,
-                    Ok(n) => {
-                        if tx.blocking_send(buf[..n].to_vec()).is_err() {
-                            break;
+        let mut detach = crate::detach_keys::DetachMatcher::new(detach_keys);
+
+        if forward_stdin {
+            std::thread::spawn(move || {
+                use std::io::Read;
+                let mut stdin = std::io::stdin();
+                let mut buf = [0u8; 4096];
+                loop {
+                    match stdin.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => {
+                            if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                                break;
+                            }
                         }
                     }
                 }
-            }
-        });
+            });
+        }
 
         let mut sigwinch =
             tokio::signal::unix::signal(tokio::signal::unix::SignalKind::window_change()).ok();
 
         loop {
             tokio::select! {
-                data = rx.recv() => {
+                data = rx.recv(), if forward_stdin => {
                     match data {
                         Some(bytes) => {
-                            // Send PTY_DATA frame (0x02), not generic Data frame (0x01)
-                            let ft = a3s_transport::FrameType::try_from(a3s_box_core::pty::FRAME_PTY_DATA)
-                                .unwrap_or(a3s_transport::FrameType::Data);
-                            let frame = a3s_transport::Frame { frame_type: ft, payload: bytes };
-                            if writer.write_frame(&frame).await.is_err() {
+                            let (bytes, detached) = detach.feed(&bytes);
+                            if !bytes.is_empty() {
+                                // Send PTY_DATA frame (0x02), not generic Data frame (0x01)
+                                let ft = a3s_transport::FrameType::try_from(a3s_box_core::pty::FRAME_PTY_DATA)
+                                    .unwrap_or(a3s_transport::FrameType::Data);
+                                let frame = a3s_transport::Frame { frame_type: ft, payload: bytes };
+                                if writer.write_frame(&frame).await.is_err() {
+                                    break;
+                                }
+                            }
+                            if detached {
+                                let _ = detached_tx.send(());
                                 break;
                             }
                         }
@@ -358,13 +479,19 @@ pub(crate) async fn run_pty_session(
         }
     });
 
-    // Wait for the reader to finish (it returns the exit code)
-    let exit_code = reader_task.await.unwrap_or(1);
+    // Wait for the reader to finish (it returns the exit code) or a detach.
+    // A writer that ends without detaching drops the sender, which disables
+    // that branch.
+    let end = tokio::select! {
+        exit_code = &mut reader_task => PtySessionEnd::Exited(exit_code.unwrap_or(1)),
+        Ok(()) = detached_rx => PtySessionEnd::Detached,
+    };
 
-    // Abort the writer task
+    // Abort the remaining tasks; dropping the stream ends the guest session.
+    reader_task.abort();
     writer_task.abort();
 
-    exit_code
+    end
 }
 
 #[cfg(all(test, not(windows)))]
@@ -0,0 +1,98 @@
+//! `a3s-box --explain <CODE>` and the `Error[A3S-xxxx]` report format.
+
+use a3s_box_core::error_catalog::CatalogEntry;
+use a3s_box_core::{BoxError, ErrorCode};
+
+use crate::output::{self, ErrorData, OutputFormat};
+
+pub fn execute(code: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let entry = ErrorCode::parse(code)
+        .and_then(ErrorCode::entry)
+        .ok_or_else(|| format!("unknown error code '{code}' (codes look like A3S-0001)"))?;
+    println!("{}", explanation(entry));
+    Ok(())
+}
+
+fn explanation(entry: &CatalogEntry) -> String {
+    format!(
+        "{} {}: {}\n\n{}\n\nHint: {}",
+        entry.code, entry.name, entry.summary, entry.explanation, entry.hint
+    )
+}
+
+/// How `main` prints a failed command: runtime errors carry their catalog
+/// code and a pointer to `--explain`, everything else prints as before.
+pub fn error_report(error: &(dyn std::error::Error + 'static)) -> String {
+    match error.downcast_ref::<BoxError>() {
+        Some(box_error) => format!(
+            "Error[{code}]: {box_error}\n(run 'a3s-box --explain {code}' for details)",
+            code = box_error.code()
+        ),
+        None => format!("Error: {error}"),
+    }
+}
+
+/// How `main` prints a failed command under `--output`: an `Error` document
+/// with the same code and message as [`error_report`].
+pub fn error_document(format: OutputFormat, error: &(dyn std::error::Error + 'static)) -> String {
+    let data = ErrorData {
+        code: error
+            .downcast_ref::<BoxError>()
+            .map(|box_error| box_error.code().to_string()),
+        message: error.to_string(),
+    };
+    output::render(format, "Error", data).unwrap_or_else(|_| error_report(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explanation_lists_code_summary_and_hint() {
+        let text = explanation(ErrorCode::STORE_CORRUPTED.entry().unwrap());
+        assert!(text.starts_with("A3S-0019 StoreCorrupted: Store corrupted\n\n"));
+        assert!(text.contains("\n\nHint: Move the named file aside"));
+        assert!(execute("a3s-19").is_ok());
+        assert!(execute("A3S-9999").is_err());
+        assert!(execute("oops").is_err());
+    }
+
+    #[test]
+    fn test_error_report_prefixes_box_errors_with_code() {
+        let error: Box<dyn std::error::Error> =
+            BoxError::ConfigError("bad memory".to_string()).into();
+        assert_eq!(
+            error_report(error.as_ref()),
+            "Error[A3S-0005]: Configuration error: bad memory\n\
+             (run 'a3s-box --explain A3S-0005' for details)"
+        );
+
+        let error: Box<dyn std::error::Error> = "Box not found: web".into();
+        assert_eq!(error_report(error.as_ref()), "Error: Box not found: web");
+    }
+
+    #[test]
+    fn test_error_document_carries_code() {
+        let error: Box<dyn std::error::Error> =
+            BoxError::ConfigError("bad memory".to_string()).into();
+        let document: serde_json::Value =
+            serde_json::from_str(&error_document(OutputFormat::Json, error.as_ref())).unwrap();
+        assert_eq!(document["kind"], "Error");
+        assert_eq!(document["data"]["code"], "A3S-0005");
+        assert_eq!(
+            document["data"]["message"],
+            "Configuration error: bad memory"
+        );
+    }
+
+    #[test]
+    fn test_explain_flag_conflicts_with_subcommands() {
+        use clap::Parser;
+
+        let cli = super::super::Cli::try_parse_from(["a3s-box", "--explain", "A3S-0001"]).unwrap();
+        assert_eq!(cli.explain.as_deref(), Some("A3S-0001"));
+        assert!(cli.command.is_none());
+        assert!(super::super::Cli::try_parse_from(["a3s-box", "--explain", "1", "ps"]).is_err());
+    }
+}
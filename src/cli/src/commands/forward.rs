@@ -0,0 +1,60 @@
+//! `a3s-box forward` command — Forward host ports to a running box.
+//!
+//! Each forward listens on a host TCP address or Unix socket and relays every
+//! accepted connection to a guest port over vsock, so it works in any network
+//! mode, including TSI. Forwards run until the command is interrupted.
+
+use clap::Args;
+
+use a3s_box_core::forward::ForwardSpec;
+
+#[derive(Args)]
+pub struct ForwardArgs {
+    /// Box name or ID
+    pub r#box: String,
+
+    /// Forwards: GUEST_PORT, HOST_PORT:GUEST_PORT, HOST_IP:HOST_PORT:GUEST_PORT,
+    /// or unix:PATH:GUEST_PORT. Host addresses default to 127.0.0.1; a host
+    /// port of 0 picks a free port.
+    #[arg(required = true, value_name = "FORWARD")]
+    pub forwards: Vec<ForwardSpec>,
+}
+
+#[cfg(windows)]
+pub async fn execute(_args: ForwardArgs) -> Result<(), Box<dyn std::error::Error>> {
+    Err(crate::platform::unsupported_command(
+        "forward",
+        "Unix socket forwarding channel support",
+    ))
+}
+
+#[cfg(not(windows))]
+pub async fn execute(args: ForwardArgs) -> Result<(), Box<dyn std::error::Error>> {
+    use a3s_box_runtime::PortForward;
+
+    use crate::resolve;
+    use crate::state::StateFile;
+
+    let state = StateFile::load_default()?;
+    let record = resolve::resolve(&state, &args.r#box)?;
+    let socket_path = crate::socket_paths::require_runtime_socket(
+        record,
+        crate::socket_paths::RuntimeSocket::Forward,
+    )?;
+
+    let mut forwards = Vec::with_capacity(args.forwards.len());
+    for spec in &args.forwards {
+        let forward = PortForward::start(&socket_path, spec).await?;
+        println!(
+            "Forwarding {} -> {}:{}",
+            forward.listen(),
+            record.name,
+            forward.guest_port()
+        );
+        forwards.push(forward);
+    }
+
+    tokio::signal::ctrl_c().await?;
+    drop(forwards);
+    Ok(())
+}
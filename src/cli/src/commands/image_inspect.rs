@@ -14,7 +14,14 @@ pub async fn execute(args: ImageInspectArgs) -> Result<(), Box<dyn std::error::E
     let store = super::open_image_store()?;
     let images = store.list().await;
     let stored = image_usage::resolve_required_stored_image(&images, &args.image)?;
-    println!("{}", build_image_inspect_json(&stored)?);
+    let json = build_image_inspect_json(&stored)?;
+    if crate::output::structured().is_some() {
+        return crate::output::emit(
+            "ImageInspect",
+            serde_json::from_str::<serde_json::Value>(&json)?,
+        );
+    }
+    println!("{json}");
     Ok(())
 }
 
@@ -2,50 +2,54 @@
 
 use clap::Args;
 
+use serde::Serialize;
+
+use crate::filter::Filters;
 use crate::output;
+use crate::template::Template;
 
 use super::images_dir;
 
+/// Keys accepted by `images --filter`.
+const IMAGE_FILTERS: &[&str] = &["reference", "label"];
+
 #[derive(Args)]
 pub struct ImagesArgs {
     /// Only show image references (one per line)
     #[arg(short, long)]
     pub quiet: bool,
 
-    /// Format output using placeholders: {{.Repository}}, {{.Tag}}, {{.Digest}},
-    /// {{.Size}}, {{.Pulled}}, {{.Reference}}
+    /// Format output as a Go-style template over {{.Repository}}, {{.Tag}},
+    /// {{.Digest}}, {{.Size}}, {{.Pulled}}, {{.Reference}}
     #[arg(long)]
     pub format: Option<String>,
 
     /// Filter output: `reference=<pattern>` (glob on repo[:tag]) or
-    /// `label=<key>[=<value>]`. Can be repeated (all must match).
+    /// `label=<key>[=<value>]`. Different keys must all match; a repeated
+    /// key matches any value
     #[arg(long = "filter")]
     pub filter: Vec<String>,
 }
 
-/// A parsed `--filter` predicate.
+/// One `--filter` predicate.
 enum ImageFilter {
     Reference(String),
     Label(String, Option<String>),
 }
 
 impl ImageFilter {
-    fn parse(spec: &str) -> Result<Self, String> {
-        let (key, value) = spec
-            .split_once('=')
-            .ok_or_else(|| format!("Invalid --filter (expected key=value): {spec}"))?;
+    /// The predicate for a filter key in [`IMAGE_FILTERS`].
+    fn new(key: &str, value: &str) -> Option<Self> {
         match key {
-            "reference" => Ok(ImageFilter::Reference(value.to_string())),
+            "reference" => Some(ImageFilter::Reference(value.to_string())),
             "label" => {
                 let (lk, lv) = match value.split_once('=') {
                     Some((k, v)) => (k.to_string(), Some(v.to_string())),
                     None => (value.to_string(), None),
                 };
-                Ok(ImageFilter::Label(lk, lv))
+                Some(ImageFilter::Label(lk, lv))
             }
-            other => Err(format!(
-                "Unsupported image filter '{other}' (supported: reference, label)"
-            )),
+            _ => None,
         }
     }
 }
@@ -78,8 +82,14 @@ fn glob_match(pattern: &str, text: &str) -> bool {
 }
 
 pub async fn execute(args: ImagesArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let filters = Filters::parse(&args.filter, IMAGE_FILTERS)?;
+    let template = args.format.as_deref().map(Template::parse).transpose()?;
+
     let images_dir = images_dir();
     if !images_dir.exists() {
+        if output::structured().is_some() {
+            return output::emit("ImageList", Vec::<a3s_box_runtime::StoredImage>::new());
+        }
         if !args.quiet && args.format.is_none() {
             let table = output::new_table(&["REPOSITORY", "TAG", "DIGEST", "SIZE", "PULLED"]);
             println!("{table}");
@@ -90,14 +100,17 @@ pub async fn execute(args: ImagesArgs) -> Result<(), Box<dyn std::error::Error>>
     let store = super::open_image_store()?;
     let mut images = store.list().await;
 
-    // --filter: keep only images matching every predicate.
-    if !args.filter.is_empty() {
-        let filters: Vec<ImageFilter> = args
-            .filter
-            .iter()
-            .map(|f| ImageFilter::parse(f))
-            .collect::<Result<_, _>>()?;
-        images.retain(|img| filters.iter().all(|f| image_matches(img, f)));
+    // --filter: keep only images matching every key.
+    if !filters.is_empty() {
+        images.retain(|img| {
+            filters.matches(|key, value| {
+                ImageFilter::new(key, value).is_some_and(|f| image_matches(img, &f))
+            })
+        });
+    }
+
+    if output::structured().is_some() {
+        return output::emit("ImageList", &images);
     }
 
     // --quiet: print only references
@@ -112,9 +125,9 @@ pub async fn execute(args: ImagesArgs) -> Result<(), Box<dyn std::error::Error>>
     let rows: Vec<ImageRow> = images.iter().map(ImageRow::from_stored).collect();
 
     // --format: custom template output
-    if let Some(ref fmt) = args.format {
+    if let Some(template) = &template {
         for row in &rows {
-            println!("{}", row.apply_format(fmt));
+            println!("{}", row.apply_format(template));
         }
         return Ok(());
     }
@@ -167,7 +180,9 @@ fn image_matches(image: &a3s_box_runtime::StoredImage, filter: &ImageFilter) ->
     }
 }
 
-/// Pre-computed display fields for a single image row.
+/// Pre-computed display fields for a single image row, named for templates.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
 struct ImageRow {
     reference: String,
     repository: String,
@@ -217,14 +232,9 @@ impl ImageRow {
         }
     }
 
-    /// Apply a format template, replacing `{{.Field}}` placeholders.
-    fn apply_format(&self, fmt: &str) -> String {
-        fmt.replace("{{.Repository}}", &self.repository)
-            .replace("{{.Tag}}", &self.tag)
-            .replace("{{.Digest}}", &self.digest)
-            .replace("{{.Size}}", &self.size)
-            .replace("{{.Pulled}}", &self.pulled)
-            .replace("{{.Reference}}", &self.reference)
+    /// Render a `--format` template for this row.
+    fn apply_format(&self, template: &Template) -> String {
+        template.render(&serde_json::to_value(self).unwrap_or_default())
     }
 }
 
@@ -261,19 +271,19 @@ mod tests {
     #[test]
     fn test_image_filter_parse() {
         assert!(matches!(
-            ImageFilter::parse("reference=alpine").unwrap(),
+            ImageFilter::new("reference", "alpine").unwrap(),
             ImageFilter::Reference(p) if p == "alpine"
         ));
         assert!(matches!(
-            ImageFilter::parse("label=tier=web").unwrap(),
+            ImageFilter::new("label", "tier=web").unwrap(),
             ImageFilter::Label(k, Some(v)) if k == "tier" && v == "web"
         ));
         assert!(matches!(
-            ImageFilter::parse("label=tier").unwrap(),
+            ImageFilter::new("label", "tier").unwrap(),
             ImageFilter::Label(k, None) if k == "tier"
         ));
-        assert!(ImageFilter::parse("nocolon").is_err());
-        assert!(ImageFilter::parse("dangling=true").is_err());
+        assert!(Filters::parse(&["nocolon".to_string()], IMAGE_FILTERS).is_err());
+        assert!(Filters::parse(&["dangling=true".to_string()], IMAGE_FILTERS).is_err());
     }
 
     #[test]
@@ -396,7 +406,7 @@ mod tests {
         };
 
         assert_eq!(
-            row.apply_format("{{.Repository}}:{{.Tag}}"),
+            row.apply_format(&Template::parse("{{.Repository}}:{{.Tag}}").unwrap()),
             "docker.io/library/nginx:1.25"
         );
     }
@@ -412,7 +422,9 @@ mod tests {
             pulled: "5 minutes ago".to_string(),
         };
 
-        let result = row.apply_format("{{.Reference}} {{.Digest}} {{.Size}} {{.Pulled}}");
+        let result = row.apply_format(
+            &Template::parse("{{.Reference}} {{.Digest}} {{.Size}} {{.Pulled}}").unwrap(),
+        );
         assert_eq!(
             result,
             "nginx:1.25 sha256:abcdef123456 1.0 KB 5 minutes ago"
@@ -430,7 +442,10 @@ mod tests {
             pulled: "5 minutes ago".to_string(),
         };
 
-        assert_eq!(row.apply_format("plain text"), "plain text");
+        assert_eq!(
+            row.apply_format(&Template::parse("plain text").unwrap()),
+            "plain text"
+        );
     }
 
     #[test]
@@ -444,6 +459,9 @@ mod tests {
             pulled: "5 minutes ago".to_string(),
         };
 
-        assert_eq!(row.apply_format("{{.Tag}}-{{.Tag}}"), "1.25-1.25");
+        assert_eq!(
+            row.apply_format(&Template::parse("{{.Tag}}-{{.Tag}}").unwrap()),
+            "1.25-1.25"
+        );
     }
 }
@@ -25,20 +25,24 @@ pub async fn execute(_args: InfoArgs) -> Result<(), Box<dyn std::error::Error>>
     println!("a3s-box version {}", a3s_box_core::VERSION);
     let capabilities = a3s_box_core::PlatformCapabilities::current();
 
-    // Virtualization support
-    match a3s_box_runtime::check_virtualization_support() {
-        Ok(support) => {
-            println!("Virtualization: {} ({})", support.backend, support.details);
-        }
-        Err(e) => {
-            println!("Virtualization: not available ({e})");
+    // Host preflight (virtualization first, then the optional capabilities)
+    let home = a3s_box_core::dirs_home();
+    let preflight = a3s_box_runtime::preflight(&a3s_box_runtime::PreflightOptions {
+        home_dir: home.clone(),
+        ..Default::default()
+    });
+    match preflight.get(a3s_box_runtime::PreflightCheckKind::Virtualization) {
+        Some(check) if check.status == a3s_box_runtime::PreflightStatus::Pass => {
+            println!("Virtualization: {}", check.detail);
         }
+        Some(check) => println!("Virtualization: not available ({})", check.detail),
+        None => {}
     }
 
     // Home directory
-    let home = a3s_box_core::dirs_home();
     println!("Home directory: {}", home.display());
     print_capabilities(&capabilities);
+    print_preflight(&preflight);
 
     // Box count
     match StateFile::load_default() {
@@ -108,6 +112,24 @@ fn print_capabilities(capabilities: &a3s_box_core::PlatformCapabilities) {
     );
 }
 
+fn print_preflight(report: &a3s_box_runtime::PreflightReport) {
+    println!("Host preflight:");
+    for check in &report.checks {
+        if check.kind == a3s_box_runtime::PreflightCheckKind::Virtualization {
+            continue;
+        }
+        println!(
+            "  [{}] {}: {}",
+            check.status,
+            check.kind.name(),
+            check.detail
+        );
+        if let Some(hint) = &check.hint {
+            println!("         {hint}");
+        }
+    }
+}
+
 fn availability(value: bool) -> &'static str {
     if value {
         "available"
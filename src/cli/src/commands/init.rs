@@ -0,0 +1,211 @@
+//! `a3s-box init` command — Scaffold an agent project.
+//!
+//! Writes an `a3s-box.toml` project file, a sample `SKILL.md`, a Dockerfile
+//! that bakes the skill into a custom agent image, and a compose file. The
+//! project file names the image and how to build it, so `a3s-box run` in the
+//! directory builds the image on first use and starts the box.
+
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::project::PROJECT_FILE;
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// Project directory, created if missing
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Project name, used for the box, image and skill (default: directory name)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Base image for the agent Dockerfile
+    #[arg(long, default_value = "alpine:latest")]
+    pub base: String,
+
+    /// Overwrite files that already exist
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+pub async fn execute(args: InitArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let name = match &args.name {
+        Some(name) => validate_name(name)?,
+        None => name_from_dir(&args.dir)?,
+    };
+    let files = scaffold(&name, &args.base);
+
+    let existing: Vec<&str> = files
+        .iter()
+        .map(|(file, _)| *file)
+        .filter(|file| args.dir.join(file).exists())
+        .collect();
+    if !existing.is_empty() && !args.force {
+        return Err(format!(
+            "{} already exists in {}; use --force to overwrite",
+            existing.join(", "),
+            args.dir.display()
+        )
+        .into());
+    }
+
+    std::fs::create_dir_all(&args.dir)?;
+    for (file, contents) in &files {
+        std::fs::write(args.dir.join(file), contents)?;
+        println!("Created {}", args.dir.join(file).display());
+    }
+    println!();
+    println!("Edit SKILL.md, then start the box from the project directory:");
+    println!("  a3s-box run");
+    Ok(())
+}
+
+/// Project names end up in an image reference, so they use its alphabet.
+fn validate_name(name: &str) -> Result<String, String> {
+    let valid = !name.is_empty()
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "Invalid project name '{name}': use lowercase letters, digits, '-' and '_'"
+        ))
+    }
+}
+
+/// A project name from the directory's name, lowercased with other
+/// characters turned into `-`.
+fn name_from_dir(dir: &Path) -> Result<String, String> {
+    let absolute = std::path::absolute(dir).map_err(|e| e.to_string())?;
+    let raw = absolute
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    validate_name(name.trim_matches('-')).map_err(|_| {
+        format!(
+            "Cannot derive a project name from {}; pass --name",
+            dir.display()
+        )
+    })
+}
+
+/// The files `init` writes, relative to the project directory.
+fn scaffold(name: &str, base: &str) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            PROJECT_FILE,
+            format!(
+                r#"# a3s-box project. `a3s-box run` in this directory builds the image (once)
+# and starts the box; flags on the command line still apply on top.
+name = "{name}"
+image = "{name}:latest"
+cmd = ["sleep", "infinity"]
+# volumes = ["./workspace:/workspace"]
+# ports = ["8080:8080"]
+
+[build]
+context = "."
+dockerfile = "Dockerfile"
+
+[env]
+SKILLS_DIR = "/agent/skills"
+"#
+            ),
+        ),
+        (
+            "SKILL.md",
+            format!(
+                r#"---
+name: {name}
+description: Describe what this skill does and when an agent should use it.
+---
+
+# {name}
+
+Write the steps an agent follows when this skill applies: the commands to
+run, the files to read, and how to tell that the task is done.
+"#
+            ),
+        ),
+        (
+            "Dockerfile",
+            format!(
+                r#"FROM {base}
+WORKDIR /agent
+COPY SKILL.md /agent/skills/{name}/SKILL.md
+CMD ["sleep", "infinity"]
+"#
+            ),
+        ),
+        (
+            "compose.yaml",
+            format!(
+                r#"# Build the image first: a3s-box build -t {name}:latest .
+services:
+  {name}:
+    image: {name}:latest
+    command: ["sleep", "infinity"]
+    environment:
+      SKILLS_DIR: /agent/skills
+"#
+            ),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_names() {
+        assert_eq!(validate_name("helper-2").unwrap(), "helper-2");
+        assert!(validate_name("Helper").is_err());
+        assert!(validate_name("-x").is_err());
+        assert!(validate_name("a:b").is_err());
+        assert_eq!(
+            name_from_dir(Path::new("/work/My Agent")).unwrap(),
+            "my-agent"
+        );
+        assert!(name_from_dir(Path::new("/work/!!!")).is_err());
+    }
+
+    #[test]
+    fn test_scaffold_project_file_loads() {
+        let files = scaffold("helper", "alpine:latest");
+        let names: Vec<_> = files.iter().map(|(file, _)| *file).collect();
+        assert_eq!(
+            names,
+            [PROJECT_FILE, "SKILL.md", "Dockerfile", "compose.yaml"]
+        );
+
+        let project = crate::project::ProjectConfig::parse(&files[0].1).unwrap();
+        assert_eq!(project.name.as_deref(), Some("helper"));
+        assert_eq!(project.image, "helper:latest");
+        assert_eq!(
+            project.build.unwrap().dockerfile.as_deref(),
+            Some("Dockerfile")
+        );
+        assert!(files[1].1.starts_with("---\nname: helper\n"));
+        assert!(files[2]
+            .1
+            .contains("COPY SKILL.md /agent/skills/helper/SKILL.md"));
+        let compose: serde_yaml::Value = serde_yaml::from_str(&files[3].1).unwrap();
+        assert_eq!(compose["services"]["helper"]["image"], "helper:latest");
+    }
+}
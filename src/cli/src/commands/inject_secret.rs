@@ -2,7 +2,12 @@
 //!
 //! Connects to a running box's RA-TLS attestation server, verifies the TEE,
 //! then injects secrets over the encrypted channel. Secrets are stored in
-//! `/run/secrets/<name>` inside the guest (tmpfs, mode 0600).
+//! `/run/secrets/<name>` inside the guest (tmpfs, mode 0400).
+//!
+//! A box started with `run --await-secrets` holds its main process until
+//! secrets arrive; `--start` releases it once they are provisioned.
+//! `--open-workspace` opens a restarted box's encrypted workspace the same
+//! way.
 
 use clap::Args;
 
@@ -12,7 +17,7 @@ use crate::resolve;
 use crate::state::StateFile;
 
 #[cfg(not(windows))]
-use a3s_box_runtime::{tee::AttestationPolicy, SecretEntry, SecretInjector};
+use a3s_box_runtime::{SecretEntry, SecretInjector};
 
 #[derive(Args)]
 pub struct InjectSecretArgs {
@@ -34,6 +39,29 @@ pub struct InjectSecretArgs {
     /// Read secrets from a file (one NAME=VALUE per line)
     #[arg(long)]
     pub file: Option<String>,
+
+    /// Key Broker Service to fetch --kbs-secret resources from
+    #[arg(long, value_name = "URL", requires = "kbs_secrets")]
+    pub kbs_url: Option<String>,
+
+    /// Secret fetched from the KBS as NAME=REPOSITORY/TYPE/TAG, can be
+    /// repeated. The guest answers the KBS challenge and decrypts it itself.
+    #[arg(
+        long = "kbs-secret",
+        value_name = "NAME=RESOURCE",
+        requires = "kbs_url"
+    )]
+    pub kbs_secrets: Vec<String>,
+
+    /// Format and open the box's encrypted workspace (`run
+    /// --encrypted-workspace`) after attestation, e.g. after a restart
+    #[arg(long)]
+    pub open_workspace: bool,
+
+    /// Start the main process of a box waiting for secrets (`run --await-secrets`)
+    /// once they are provisioned
+    #[arg(long)]
+    pub start: bool,
 }
 
 /// JSON output for the inject-secret command.
@@ -43,6 +71,8 @@ struct InjectOutput {
     box_name: String,
     injected: usize,
     secrets: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    workspace_opened: bool,
 }
 
 #[cfg(windows)]
@@ -57,22 +87,73 @@ pub async fn execute(_args: InjectSecretArgs) -> Result<(), Box<dyn std::error::
 pub async fn execute(args: InjectSecretArgs) -> Result<(), Box<dyn std::error::Error>> {
     let state = StateFile::load_default()?;
     let record = resolve::resolve(&state, &args.r#box)?;
-    let attest_socket_path = crate::socket_paths::require_runtime_socket(
-        record,
-        crate::socket_paths::RuntimeSocket::Attest,
-    )
-    .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
-    let socket_path = &attest_socket_path;
 
-    // Collect secrets from --secret and --file
+    let entries = collect_secrets(&args.secrets, args.file.as_deref(), args.set_env)?;
+    let kbs_secrets = args
+        .kbs_secrets
+        .iter()
+        .map(|spec| parse_kbs_secret(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    if entries.is_empty() && kbs_secrets.is_empty() && !args.open_workspace {
+        return Err(
+            "No secrets provided. Use --secret NAME=VALUE, --file PATH, or --kbs-secret".into(),
+        );
+    }
+
+    if args.open_workspace {
+        open_encrypted_workspace(record, args.allow_simulated).await?;
+    }
+    let mut injected = 0;
+    if !entries.is_empty() {
+        injected += provision_secrets(record, &entries, args.allow_simulated).await?;
+    }
+    if let Some(kbs_url) = &args.kbs_url {
+        injected += provision_kbs_secrets(
+            record,
+            kbs_url,
+            &kbs_secrets,
+            args.set_env,
+            args.allow_simulated,
+        )
+        .await?;
+    }
+    if args.start {
+        start_main(record).await?;
+    }
+
+    let secret_names: Vec<String> = entries
+        .iter()
+        .map(|e| e.name.clone())
+        .chain(kbs_secrets.into_iter().map(|(name, _)| name))
+        .collect();
+
+    let output = InjectOutput {
+        box_name: record.name.clone(),
+        injected,
+        secrets: secret_names,
+        workspace_opened: args.open_workspace,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Collect secrets from `--secret NAME=VALUE` values and a secrets file
+/// (one NAME=VALUE per line, `#` comments).
+#[cfg(not(windows))]
+pub(crate) fn collect_secrets(
+    secrets: &[String],
+    file: Option<&str>,
+    set_env: bool,
+) -> Result<Vec<SecretEntry>, Box<dyn std::error::Error>> {
     let mut entries = Vec::new();
 
-    for secret_str in &args.secrets {
-        let entry = parse_secret(secret_str, args.set_env)?;
+    for secret_str in secrets {
+        let entry = parse_secret(secret_str, set_env)?;
         entries.push(entry);
     }
 
-    if let Some(path) = &args.file {
+    if let Some(path) = file {
         let content = std::fs::read_to_string(path)
             .map_err(|e| format!("Failed to read secrets file '{}': {}", path, e))?;
         for line in content.lines() {
@@ -80,29 +161,124 @@ pub async fn execute(args: InjectSecretArgs) -> Result<(), Box<dyn std::error::E
             if trimmed.is_empty() || trimmed.starts_with('#') {
                 continue;
             }
-            let entry = parse_secret(trimmed, args.set_env)?;
+            let entry = parse_secret(trimmed, set_env)?;
             entries.push(entry);
         }
     }
 
-    if entries.is_empty() {
-        return Err("No secrets provided. Use --secret NAME=VALUE or --file PATH".into());
+    Ok(entries)
+}
+
+/// Attest the box against its policy and inject `entries` over RA-TLS.
+/// Fails unless the guest stored every secret.
+#[cfg(not(windows))]
+pub(crate) async fn provision_secrets(
+    record: &crate::state::BoxRecord,
+    entries: &[SecretEntry],
+    allow_simulated: bool,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let attest_socket_path = crate::socket_paths::require_runtime_socket(
+        record,
+        crate::socket_paths::RuntimeSocket::Attest,
+    )
+    .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+
+    let policy = super::attest::resolve_policy(None, &record.box_dir)?;
+    let injector = SecretInjector::new(&attest_socket_path);
+    let result = injector.inject(entries, policy, allow_simulated).await?;
+    if !result.errors.is_empty() {
+        return Err(format!(
+            "guest rejected {} secret(s): {}",
+            result.errors.len(),
+            result.errors.join("; ")
+        )
+        .into());
     }
+    Ok(result.injected)
+}
 
-    let injector = SecretInjector::new(socket_path);
-    let result = injector
-        .inject(&entries, AttestationPolicy::default(), args.allow_simulated)
-        .await?;
+/// Fetch each `(name, resource path)` from the KBS at `kbs_url` into the box.
+/// The box's policy is checked before the guest is asked for KBS evidence.
+#[cfg(not(windows))]
+pub(crate) async fn provision_kbs_secrets(
+    record: &crate::state::BoxRecord,
+    kbs_url: &str,
+    secrets: &[(String, String)],
+    set_env: bool,
+    allow_simulated: bool,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    use a3s_box_runtime::tee::{KbsClient, KbsConfig};
 
-    let secret_names: Vec<String> = entries.iter().map(|e| e.name.clone()).collect();
+    let attest_socket_path = crate::socket_paths::require_runtime_socket(
+        record,
+        crate::socket_paths::RuntimeSocket::Attest,
+    )
+    .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
 
-    let output = InjectOutput {
-        box_name: record.name.clone(),
-        injected: result.injected,
-        secrets: secret_names,
-    };
+    let policy = super::attest::resolve_policy(None, &record.box_dir)?;
+    let injector = SecretInjector::new(&attest_socket_path);
+    let kbs = KbsClient::new(KbsConfig {
+        url: kbs_url.to_string(),
+        ..Default::default()
+    });
+    for (name, resource_path) in secrets {
+        injector
+            .inject_from_kbs(
+                &kbs,
+                resource_path,
+                name,
+                set_env,
+                policy.clone(),
+                allow_simulated,
+            )
+            .await?;
+    }
+    Ok(secrets.len())
+}
 
-    println!("{}", serde_json::to_string_pretty(&output)?);
+/// Attest the box against its policy, then format its encrypted workspace
+/// and release the key to the guest, which mounts it at `/workspace`.
+#[cfg(not(windows))]
+pub(crate) async fn open_encrypted_workspace(
+    record: &crate::state::BoxRecord,
+    allow_simulated: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let attest_socket_path = crate::socket_paths::require_runtime_socket(
+        record,
+        crate::socket_paths::RuntimeSocket::Attest,
+    )
+    .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+    let image = record
+        .box_dir
+        .join(a3s_box_runtime::tee::ENCRYPTED_WORKSPACE_IMAGE);
+    if !image.is_file() {
+        return Err(format!(
+            "box {} has no encrypted workspace; was it run with --encrypted-workspace?",
+            record.name
+        )
+        .into());
+    }
+
+    let policy = super::attest::resolve_policy(None, &record.box_dir)?;
+    SecretInjector::new(&attest_socket_path)
+        .release_workspace_key(&image, policy, allow_simulated)
+        .await?;
+    Ok(())
+}
+
+/// Start the main process of a box that booted waiting for its secrets.
+#[cfg(not(windows))]
+pub(crate) async fn start_main(
+    record: &crate::state::BoxRecord,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = a3s_box_runtime::ExecClient::connect(&record.exec_socket_path).await?;
+    if !client.spawn_main(None).await? {
+        return Err(format!(
+            "box {} did not start its main process; was it run with --await-secrets?",
+            record.name
+        )
+        .into());
+    }
     Ok(())
 }
 
@@ -124,6 +300,22 @@ fn parse_secret(s: &str, set_env: bool) -> Result<SecretEntry, String> {
     })
 }
 
+/// Parse a "NAME=REPOSITORY/TYPE/TAG" KBS secret.
+#[cfg(not(windows))]
+pub(crate) fn parse_kbs_secret(s: &str) -> Result<(String, String), String> {
+    let (name, resource_path) = s.split_once('=').ok_or_else(|| {
+        format!(
+            "Invalid KBS secret (expected NAME=REPOSITORY/TYPE/TAG): {}",
+            s
+        )
+    })?;
+    if name.is_empty() {
+        return Err(format!("Secret name cannot be empty: {}", s));
+    }
+    a3s_box_runtime::tee::kbs::parse_resource_path(resource_path).map_err(|e| e.to_string())?;
+    Ok((name.to_string(), resource_path.to_string()))
+}
+
 #[cfg(test)]
 #[cfg(not(windows))]
 mod tests {
@@ -168,4 +360,32 @@ mod tests {
     fn test_parse_secret_empty_name() {
         assert!(parse_secret("=value", false).is_err());
     }
+
+    #[test]
+    fn test_parse_kbs_secret() {
+        assert_eq!(
+            parse_kbs_secret("MODEL_KEY=default/keys/model").unwrap(),
+            ("MODEL_KEY".to_string(), "default/keys/model".to_string())
+        );
+        assert!(parse_kbs_secret("default/keys/model").is_err());
+        assert!(parse_kbs_secret("=default/keys/model").is_err());
+        assert!(parse_kbs_secret("MODEL_KEY=keys/model").is_err());
+    }
+
+    #[test]
+    fn test_collect_secrets_from_flags_and_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("secrets.env");
+        std::fs::write(&file, "# model credentials\nMODEL_KEY=abc\n\nREGION=eu\n").unwrap();
+
+        let entries = collect_secrets(
+            &["API_KEY=sk-1".to_string()],
+            Some(file.to_str().unwrap()),
+            true,
+        )
+        .unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["API_KEY", "MODEL_KEY", "REGION"]);
+        assert!(entries.iter().all(|e| e.set_env));
+    }
 }
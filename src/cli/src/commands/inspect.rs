@@ -6,6 +6,7 @@ use serde::Serialize;
 use crate::resolve::{self, ResolveError};
 use crate::state::{BoxRecord, StateFile};
 use crate::status;
+use crate::template::Template;
 
 use super::image_inspect;
 
@@ -13,29 +14,48 @@ use super::image_inspect;
 pub struct InspectArgs {
     /// Container or image name/ID
     pub r#box: String,
+
+    /// Format the output using a Go-style template over the inspect
+    /// document, e.g. '{{.State.Status}}' or '{{json .labels}}'
+    #[arg(short, long)]
+    pub format: Option<String>,
 }
 
 pub async fn execute(args: InspectArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let template = args.format.as_deref().map(Template::parse).transpose()?;
     let state = StateFile::load_default()?;
 
     // `docker inspect` is polymorphic: try a container first, then fall back to
     // an image so `inspect <image>` works the same as `inspect <container>`.
-    match resolve::resolve(&state, &args.r#box) {
-        Ok(record) => {
-            println!("{}", inspect_json(record)?);
-            Ok(())
-        }
+    let (kind, json) = match resolve::resolve(&state, &args.r#box) {
+        Ok(record) => ("BoxInspect", inspect_json(record)?),
         Err(ResolveError::NotFound(_)) => {
             match image_inspect::try_image_inspect_json(&args.r#box).await? {
-                Some(json) => {
-                    println!("{json}");
-                    Ok(())
-                }
-                None => Err(format!("No such container or image: {}", args.r#box).into()),
+                Some(json) => ("ImageInspect", json),
+                None => return Err(format!("No such container or image: {}", args.r#box).into()),
             }
         }
-        Err(other) => Err(other.into()),
+        Err(other) => return Err(other.into()),
+    };
+    if output::structured().is_some() {
+        return output::emit(kind, serde_json::from_str::<serde_json::Value>(&json)?);
     }
+    match template {
+        Some(template) => println!("{}", render_inspect(&json, &template)?),
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+/// Render `template` once per inspected object (the box document is a
+/// one-element array, the image document a single object).
+fn render_inspect(json: &str, template: &Template) -> Result<String, serde_json::Error> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let lines: Vec<String> = match &value {
+        serde_json::Value::Array(items) => items.iter().map(|v| template.render(v)).collect(),
+        other => vec![template.render(other)],
+    };
+    Ok(lines.join("\n"))
 }
 
 /// Docker-shaped `State` sub-object so tooling can read `.[0].State.Running` etc.
@@ -51,6 +71,14 @@ struct DockerState {
     exit_code: i32,
 }
 
+/// Docker-shaped `HostConfig` subset for settings with an implicit default.
+#[derive(Serialize)]
+struct DockerHostConfig {
+    /// Effective `/dev/shm` size in bytes (64m unless `--shm-size` was given).
+    #[serde(rename = "ShmSize")]
+    shm_size: u64,
+}
+
 #[derive(Serialize)]
 struct InspectView<'a> {
     #[serde(flatten)]
@@ -58,6 +86,8 @@ struct InspectView<'a> {
     status_detail: status::StatusDetails,
     #[serde(rename = "State")]
     state: DockerState,
+    #[serde(rename = "HostConfig")]
+    host_config: DockerHostConfig,
 }
 
 fn inspect_json(record: &BoxRecord) -> Result<String, serde_json::Error> {
@@ -71,6 +101,11 @@ fn inspect_json(record: &BoxRecord) -> Result<String, serde_json::Error> {
             paused: record.status == "paused",
             exit_code: record.exit_code.unwrap_or(0),
         },
+        host_config: DockerHostConfig {
+            shm_size: record
+                .shm_size
+                .unwrap_or(a3s_box_core::config::DEFAULT_SHM_SIZE_BYTES),
+        },
     };
     // `docker inspect` returns a top-level JSON array, even for one container.
     serde_json::to_string_pretty(&vec![view])
@@ -115,4 +150,32 @@ mod tests {
         assert_eq!(parsed[0]["State"]["Running"], true);
         assert_eq!(parsed[0]["State"]["Paused"], true);
     }
+
+    #[test]
+    fn test_inspect_reports_effective_shm_size() {
+        let mut record = make_record("id", "box", "running", Some(1));
+        let parsed: serde_json::Value =
+            serde_json::from_str(&inspect_json(&record).unwrap()).unwrap();
+        assert_eq!(parsed[0]["HostConfig"]["ShmSize"], 64 * 1024 * 1024);
+
+        record.shm_size = Some(1024 * 1024 * 1024);
+        let parsed: serde_json::Value =
+            serde_json::from_str(&inspect_json(&record).unwrap()).unwrap();
+        assert_eq!(parsed[0]["HostConfig"]["ShmSize"], 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_inspect_format_renders_each_document() {
+        let mut record = make_record("id", "box", "dead", None);
+        record.exit_code = Some(137);
+        let json = inspect_json(&record).unwrap();
+        let template = Template::parse("{{.name}} {{.State.Status}} {{.State.ExitCode}}").unwrap();
+        assert_eq!(render_inspect(&json, &template).unwrap(), "box dead 137");
+
+        let template = Template::parse("{{.Reference}}").unwrap();
+        assert_eq!(
+            render_inspect(r#"{"Reference":"alpine:3.19"}"#, &template).unwrap(),
+            "alpine:3.19"
+        );
+    }
 }
@@ -107,7 +107,11 @@ async fn stream_logs(
     if let Some(tail_n) = args.tail {
         let file = std::fs::File::open(&log_path)?;
         let mut reader = BufReader::new(file);
-        let mut lines = Vec::new();
+        let mut lines = if use_json {
+            rotated_lines(&log_path)?
+        } else {
+            Vec::new()
+        };
         loop {
             let mut line = String::new();
             if reader.read_line(&mut line)? == 0 {
@@ -134,9 +138,18 @@ async fn stream_logs(
             }
         }
     } else if !args.follow {
+        let history = if use_json {
+            rotated_lines(&log_path)?
+        } else {
+            Vec::new()
+        };
         let file = std::fs::File::open(&log_path)?;
         let reader = BufReader::new(file);
-        for line in reader.lines() {
+        let lines = history
+            .into_iter()
+            .map(Ok::<_, std::io::Error>)
+            .chain(reader.lines());
+        for line in lines {
             let line = line?;
             if use_json {
                 print_json_line(
@@ -243,6 +256,24 @@ async fn stream_logs(
     Ok(())
 }
 
+/// Lines of the rotated, gzipped generations of a json-file log, oldest
+/// first, so `logs` without `--follow` shows history from before the last
+/// rotation. A generation rotated away while we list them is skipped.
+fn rotated_lines(log_path: &Path) -> std::io::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for path in a3s_box_runtime::log::rotated_log_paths(log_path) {
+        let file = match std::fs::File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for line in BufReader::new(flate2::read::GzDecoder::new(file)).lines() {
+            lines.push(line?);
+        }
+    }
+    Ok(lines)
+}
+
 /// True if the file currently at `path` has a different inode than `open_meta` —
 /// i.e. it was rotated (renamed away, a new one created) under our open fd. Lets
 /// `--follow` re-open the new file (`tail -F`) instead of stalling on the old
@@ -654,6 +685,29 @@ mod tests {
         assert_eq!(source.path, record.console_log);
     }
 
+    #[test]
+    fn test_rotated_lines_reads_generations_oldest_first() {
+        use std::io::Write;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let json_log = a3s_box_runtime::log::json_log_path(tmp.path());
+        for (index, content) in [(2, "oldest\n"), (1, "newer\nnewest\n")] {
+            let file = std::fs::File::create(tmp.path().join(format!("container.json.{index}.gz")))
+                .unwrap();
+            let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+            encoder.write_all(content.as_bytes()).unwrap();
+            encoder.finish().unwrap();
+        }
+
+        assert_eq!(
+            rotated_lines(&json_log).unwrap(),
+            vec!["oldest", "newer", "newest"]
+        );
+        assert!(rotated_lines(&tmp.path().join("other.json"))
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_resolve_log_source_missing_is_empty() {
         let tmp = tempfile::tempdir().unwrap();
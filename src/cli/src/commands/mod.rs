@@ -1,20 +1,27 @@
 //! CLI command definitions and dispatch.
 
+mod annotate;
 mod attach;
 mod attest;
 mod audit;
 mod build;
+mod checkpoint;
 mod commit;
 pub(crate) mod common;
 mod compose;
 mod container_update;
 mod cp;
 mod create;
+mod debug;
 mod df;
+mod doctor;
 pub(crate) mod diff;
+mod eval;
 mod events;
 pub(crate) mod exec;
+pub mod explain;
 mod export;
+mod forward;
 mod history;
 mod image_inspect;
 mod image_prune;
@@ -22,6 +29,7 @@ mod image_tag;
 mod images;
 mod import;
 mod info;
+mod init;
 mod inject_secret;
 mod inspect;
 mod kill;
@@ -32,8 +40,10 @@ mod logs;
 mod monitor;
 mod monitor_metrics;
 mod monitor_service;
+mod net_flows;
 pub(crate) mod network;
 mod pause;
+mod plugin;
 mod pool;
 mod port;
 mod prune;
@@ -53,7 +63,9 @@ mod snapshot;
 mod start;
 mod stats;
 mod stop;
+mod system;
 mod system_prune;
+mod tee;
 mod top;
 mod unpause;
 mod unseal;
@@ -72,10 +84,31 @@ const IMAGE_CACHE_SIZE_ENV: &str = "A3S_IMAGE_CACHE_SIZE";
 
 /// A3S Box — Docker-like MicroVM runtime.
 #[derive(Parser)]
-#[command(name = "a3s-box", version, about)]
+#[command(
+    name = "a3s-box",
+    version,
+    about,
+    arg_required_else_help = true,
+    args_conflicts_with_subcommands = true
+)]
 pub struct Cli {
     #[command(subcommand)]
-    pub command: Command,
+    pub command: Option<Command>,
+
+    /// Explain an error code from the catalog (e.g. A3S-0001) and exit
+    #[arg(long, value_name = "CODE")]
+    pub explain: Option<String>,
+
+    /// Print a versioned JSON or YAML document instead of human output,
+    /// errors included; commands without structured output refuse to run
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub output: Option<crate::output::OutputFormat>,
+
+    // `main` takes this off the command line before parsing (see
+    // `crate::remote`); it is declared here for `--help`.
+    /// Run the command on another machine: ssh://[user@]host[:port] (default: $A3S_HOST)
+    #[arg(long, short = 'H', value_name = "URL")]
+    pub host: Option<String>,
 }
 
 /// Available commands.
@@ -123,12 +156,24 @@ pub enum Command {
     Unseal(unseal::UnsealArgs),
     /// Inject secrets into a running TEE box via RA-TLS
     InjectSecret(inject_secret::InjectSecretArgs),
+    /// Manage TEE verification material (AMD certificate cache)
+    Tee(tee::TeeArgs),
     /// Block until one or more boxes stop
     Wait(wait::WaitArgs),
     /// Rename a box
     Rename(rename::RenameArgs),
+    /// Annotate a box and keep notes on it
+    Annotate(annotate::AnnotateArgs),
     /// List port mappings for a box
     Port(port::PortArgs),
+    /// Forward host TCP ports or Unix sockets to ports in a running box
+    Forward(forward::ForwardArgs),
+    /// Show the network connections a box has made
+    NetFlows(net_flows::NetFlowsArgs),
+    /// Run scored agent evaluation tasks against fresh boxes
+    Eval(eval::EvalArgs),
+    /// Debugging tools (e.g. show how an image config maps to a process)
+    Debug(debug::DebugArgs),
     /// Export a box's filesystem to a tar archive
     Export(export::ExportArgs),
     /// Create an image from a box's changes
@@ -143,6 +188,10 @@ pub enum Command {
     Compose(compose::ComposeArgs),
     /// Manage VM snapshots (create, restore, list, remove)
     Snapshot(snapshot::SnapshotArgs),
+    /// Save and restore a box's filesystem state (create, list, remove)
+    Checkpoint(checkpoint::CheckpointArgs),
+    /// Scaffold an agent project (a3s-box.toml, SKILL.md, Dockerfile, compose file)
+    Init(init::InitArgs),
     /// Build an image from a Dockerfile or Containerfile
     Build(build::BuildArgs),
     /// List cached images
@@ -182,12 +231,16 @@ pub enum Command {
     /// Remove all stopped boxes (Docker `container prune`)
     #[command(visible_alias = "container-prune")]
     Prune(prune::PruneArgs),
-    /// Remove all unused data (stopped boxes and unused images)
+    /// Remove all unused data (stopped boxes, unused images and networks)
     SystemPrune(system_prune::SystemPruneArgs),
+    /// Manage system-wide resources (df, prune)
+    System(system::SystemArgs),
     /// Show version information
     Version(version::VersionArgs),
     /// Show system information
     Info(info::InfoArgs),
+    /// Diagnose the host, runtime binaries and image store, with fixes
+    Doctor(doctor::DoctorArgs),
     /// Background daemon that monitors and restarts dead boxes
     Monitor(monitor::MonitorArgs),
     /// Manage the warm VM pool (pre-boot VMs for instant start)
@@ -197,6 +250,9 @@ pub enum Command {
     /// Structured bridge used by the native language SDKs
     #[command(name = "sdk-bridge", hide = true)]
     SdkBridge(sdk_bridge::SdkBridgeArgs),
+    /// Run an `a3s-box-<name>` plugin from PATH
+    #[command(external_subcommand)]
+    Plugin(Vec<String>),
 }
 
 /// Return the path to the image store directory (~/.a3s/images).
@@ -617,7 +673,22 @@ mod console_tail_tests {
 
 /// Dispatch a parsed CLI to the appropriate command handler.
 pub async fn dispatch(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
-    match cli.command {
+    if let Some(code) = cli.explain {
+        return explain::execute(&code);
+    }
+    let Some(command) = cli.command else {
+        return Err("missing command (see 'a3s-box --help')".into());
+    };
+    if let Some(format) = cli.output {
+        crate::output::set_structured(format);
+        if !has_structured_output(&command) {
+            return Err("this command has no structured output; run it without --output".into());
+        }
+    }
+    if checks_home_layout(&command) {
+        a3s_box_runtime::home_layout::ensure_current(&a3s_box_core::dirs_home())?;
+    }
+    match command {
         Command::Run(args) => run::execute(args).await,
         Command::Create(args) => create::execute(args).await,
         Command::Start(args) => start::execute(args).await,
@@ -639,9 +710,15 @@ pub async fn dispatch(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Command::Seal(args) => seal::execute(args).await,
         Command::Unseal(args) => unseal::execute(args).await,
         Command::InjectSecret(args) => inject_secret::execute(args).await,
+        Command::Tee(args) => tee::execute(args).await,
         Command::Wait(args) => wait::execute(args).await,
         Command::Rename(args) => rename::execute(args).await,
+        Command::Annotate(args) => annotate::execute(args).await,
         Command::Port(args) => port::execute(args).await,
+        Command::Forward(args) => forward::execute(args).await,
+        Command::NetFlows(args) => net_flows::execute(args).await,
+        Command::Eval(args) => eval::execute(args).await,
+        Command::Debug(args) => debug::execute(args).await,
         Command::Export(args) => export::execute(args).await,
         Command::Commit(args) => commit::execute(args).await,
         Command::Diff(args) => diff::execute(args).await,
@@ -649,6 +726,8 @@ pub async fn dispatch(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Command::ContainerUpdate(args) => container_update::execute(args).await,
         Command::Compose(args) => compose::execute(args).await,
         Command::Snapshot(args) => snapshot::execute(args).await,
+        Command::Checkpoint(args) => checkpoint::execute(args).await,
+        Command::Init(args) => init::execute(args).await,
         Command::Build(args) => build::execute(args).await,
         Command::Images(args) => images::execute(args).await,
         Command::Pull(args) => pull::execute(args).await,
@@ -669,15 +748,57 @@ pub async fn dispatch(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Command::Df(args) => df::execute(args).await,
         Command::Prune(args) => prune::execute(args).await,
         Command::SystemPrune(args) => system_prune::execute(args).await,
+        Command::System(args) => system::execute(args).await,
         Command::Version(args) => version::execute(args).await,
         Command::Info(args) => info::execute(args).await,
+        Command::Doctor(args) => doctor::execute(args).await,
         Command::Monitor(args) => monitor::execute(args).await,
         Command::Pool(args) => pool::execute(args).await,
         Command::Shell(args) => shell::execute(args).await,
         Command::SdkBridge(args) => sdk_bridge::execute(args).await,
+        Command::Plugin(args) => plugin::execute(args).await,
     }
 }
 
+/// Whether `command` prints a document under `--output` (see
+/// [`crate::output`]). Commands that only act, or only print text, are
+/// refused rather than mixing text into a stream tooling parses.
+fn has_structured_output(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::Ps(_)
+            | Command::Images(_)
+            | Command::Inspect(_)
+            | Command::ImageInspect(_)
+            | Command::Stats(_)
+            | Command::Version(_)
+            | Command::Volume(volume::VolumeArgs {
+                command: volume::VolumeCommand::Ls(_) | volume::VolumeCommand::Inspect(_),
+            })
+            | Command::Network(network::NetworkArgs {
+                command: network::NetworkCommand::Ls(_) | network::NetworkCommand::Inspect(_),
+            })
+            | Command::Snapshot(snapshot::SnapshotArgs {
+                action: snapshot::SnapshotAction::Ls(_) | snapshot::SnapshotAction::Inspect(_),
+            })
+            | Command::Checkpoint(checkpoint::CheckpointArgs {
+                action: checkpoint::CheckpointAction::Ls(_),
+            })
+    )
+}
+
+/// Whether `command` must see the home directory in the current on-disk
+/// layout first. `system migrate` reports on the layout itself.
+fn checks_home_layout(command: &Command) -> bool {
+    !matches!(
+        command,
+        Command::Version(_)
+            | Command::System(system::SystemArgs {
+                command: system::SystemCommand::Migrate(_),
+            })
+    )
+}
+
 #[cfg(test)]
 mod isolation_cli_tests {
     use super::*;
@@ -689,7 +810,7 @@ mod isolation_cli_tests {
             Cli::try_parse_from(["a3s-box", "run", "--isolation", "sandbox", "alpine:latest"])
                 .unwrap();
 
-        let Command::Run(args) = cli.command else {
+        let Some(Command::Run(args)) = cli.command else {
             panic!("expected run command");
         };
         assert_eq!(args.common.isolation, Some(common::IsolationArg::Sandbox));
@@ -699,7 +820,7 @@ mod isolation_cli_tests {
     fn run_omission_preserves_microvm_default() {
         let cli = Cli::try_parse_from(["a3s-box", "run", "alpine:latest"]).unwrap();
 
-        let Command::Run(args) = cli.command else {
+        let Some(Command::Run(args)) = cli.command else {
             panic!("expected run command");
         };
         assert_eq!(
@@ -723,7 +844,7 @@ mod isolation_cli_tests {
         let cli =
             Cli::try_parse_from(["a3s-box", "compose", "up", "--isolation", "sandbox"]).unwrap();
 
-        let Command::Compose(args) = cli.command else {
+        let Some(Command::Compose(args)) = cli.command else {
             panic!("expected compose command");
         };
         let compose::ComposeCommand::Up(args) = args.command else {
@@ -733,3 +854,47 @@ mod isolation_cli_tests {
         assert!(args.services.is_empty());
     }
 }
+
+#[cfg(test)]
+mod output_cli_tests {
+    use super::*;
+    use clap::Parser;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(args).unwrap()
+    }
+
+    #[test]
+    fn output_flag_goes_before_the_subcommand() {
+        let cli = parse(&["a3s-box", "--output", "yaml", "ps", "-a"]);
+        assert_eq!(cli.output, Some(crate::output::OutputFormat::Yaml));
+        // Subcommands keep their own `--output FILE`.
+        let cli = parse(&["a3s-box", "export", "web", "--output", "web.tar"]);
+        assert_eq!(cli.output, None);
+    }
+
+    #[test]
+    fn only_commands_with_documents_accept_output() {
+        for args in [
+            &["a3s-box", "ps"][..],
+            &["a3s-box", "volume", "ls"],
+            &["a3s-box", "network", "inspect", "backend"],
+            &["a3s-box", "checkpoint", "ls", "web"],
+        ] {
+            assert!(
+                has_structured_output(&parse(args).command.unwrap()),
+                "{args:?}"
+            );
+        }
+        for args in [
+            &["a3s-box", "rm", "web"][..],
+            &["a3s-box", "volume", "rm", "data"],
+            &["a3s-box", "logs", "web"],
+        ] {
+            assert!(
+                !has_structured_output(&parse(args).command.unwrap()),
+                "{args:?}"
+            );
+        }
+    }
+}
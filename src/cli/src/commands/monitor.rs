@@ -3,7 +3,10 @@
 //! Polls `boxes.json` periodically, detects dead VMs via PID liveness checks,
 //! and restarts boxes according to their restart policy. Also monitors health
 //! check status and restarts unhealthy boxes. Uses exponential backoff to
-//! prevent crash loops.
+//! prevent crash loops. Compose service boxes whose `depends_on` peers are not
+//! yet started (or healthy) are parked in the `waiting` state instead of being
+//! booted against a missing peer. With `--notify`, crashes and failing health
+//! checks also raise desktop notifications (see [`crate::notify`]).
 //!
 //! Usage: `a3s-box monitor` (long-running, typically run as a background service)
 
@@ -15,6 +18,7 @@ use clap::Args;
 use crate::boot;
 #[cfg(not(windows))]
 use crate::health;
+use crate::notify::{self, Notifier, NotifyEvent};
 use crate::state::{policy, BoxRecord, StateFile};
 use crate::status;
 
@@ -48,6 +52,11 @@ pub struct MonitorArgs {
     #[arg(long)]
     pub metrics_addr: Option<String>,
 
+    /// Show a desktop notification on these events (comma-separated:
+    /// crashed, unhealthy); uses osascript on macOS and notify-send on Linux
+    #[arg(long, value_delimiter = ',', value_name = "EVENTS")]
+    pub notify: Vec<NotifyEvent>,
+
     /// Internal: run the process-owned health checker for one box.
     #[arg(long, hide = true, requires = "health_generation")]
     pub health_worker: Option<String>,
@@ -162,7 +171,7 @@ pub async fn execute(args: MonitorArgs) -> Result<(), Box<dyn std::error::Error>
         return crate::health::run_detached_health_worker(box_id.clone(), generation).await;
     }
     if args.install {
-        return super::monitor_service::install(args.interval);
+        return super::monitor_service::install(args.interval, &args.notify);
     }
     if args.uninstall {
         return super::monitor_service::uninstall();
@@ -170,6 +179,7 @@ pub async fn execute(args: MonitorArgs) -> Result<(), Box<dyn std::error::Error>
 
     let interval = Duration::from_secs(args.interval);
     let mut tracker = BackoffTracker::new();
+    let mut notifier = Notifier::new(&args.notify);
 
     println!(
         "a3s-box monitor started (poll interval: {}s)",
@@ -203,7 +213,7 @@ pub async fn execute(args: MonitorArgs) -> Result<(), Box<dyn std::error::Error>
     // mid-poll orphaned the in-flight boot).
     let mut shutdown = std::pin::pin!(monitor_shutdown_signal());
     loop {
-        if let Err(e) = poll_once(&mut tracker).await {
+        if let Err(e) = poll_once(&mut tracker, &mut notifier).await {
             eprintln!("monitor: poll error: {e}");
         }
         // Mark the loop alive (a hung poll_once stops updating this, so /healthz
@@ -252,9 +262,17 @@ async fn monitor_shutdown_signal() {
 
 /// Single poll iteration: load state, find dead boxes, restart eligible ones.
 /// Also checks for unhealthy boxes that have a restart policy.
-async fn poll_once(tracker: &mut BackoffTracker) -> Result<(), Box<dyn std::error::Error>> {
+async fn poll_once(
+    tracker: &mut BackoffTracker,
+    notifier: &mut Notifier,
+) -> Result<(), Box<dyn std::error::Error>> {
     let state = StateFile::load_default()?;
 
+    for notification in notifier.observe(state.records()) {
+        println!("monitor: {}: {}", notification.title, notification.body);
+        notify::send(&notification).await;
+    }
+
     // Track active boxes for stability detection.
     for record in state.records() {
         if status::is_active(record) {
@@ -364,6 +382,10 @@ async fn poll_once(tracker: &mut BackoffTracker) -> Result<(), Box<dyn std::erro
             // avoid a re-entrant deadlock.
             drop(lifecycle_lock);
         } else {
+            let waiting_on = super::compose::unmet_dependency(state.records(), &record);
+            if !update_waiting_state(&record, waiting_on.as_deref())? {
+                continue;
+            }
             tracker.mark_dead(&box_id);
             println!("{}", restart_log_line(&record, RestartReason::Dead));
         }
@@ -415,6 +437,64 @@ async fn poll_once(tracker: &mut BackoffTracker) -> Result<(), Box<dyn std::erro
     Ok(())
 }
 
+/// Park a restart candidate in `waiting` while a dependency is unmet, or
+/// return it to `dead` once every dependency is ready.
+///
+/// Returns `true` when the restart should proceed this cycle.
+fn update_waiting_state(
+    record: &BoxRecord,
+    waiting_on: Option<&str>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let was_waiting_on = record
+        .labels
+        .get(status::WAITING_ON_LABEL)
+        .map(String::as_str);
+    if record.status != "waiting" && waiting_on.is_none() {
+        return Ok(true);
+    }
+    if record.status == "waiting" && was_waiting_on == waiting_on {
+        return Ok(waiting_on.is_none());
+    }
+
+    let updated = StateFile::modify(|s| {
+        Ok::<bool, std::io::Error>(match s.find_by_id_mut(&record.id) {
+            Some(rec) if matches!(rec.status.as_str(), "dead" | "waiting") => {
+                apply_waiting_state(rec, waiting_on);
+                true
+            }
+            _ => false,
+        })
+    })?;
+    if updated {
+        match waiting_on {
+            Some(dependency) => println!(
+                "monitor: box {} ({}) waiting on dependency {dependency}",
+                record.name, record.short_id
+            ),
+            None => println!(
+                "monitor: box {} ({}) dependencies ready",
+                record.name, record.short_id
+            ),
+        }
+    }
+    Ok(updated && waiting_on.is_none())
+}
+
+fn apply_waiting_state(record: &mut BoxRecord, waiting_on: Option<&str>) {
+    match waiting_on {
+        Some(dependency) => {
+            record.status = "waiting".to_string();
+            record
+                .labels
+                .insert(status::WAITING_ON_LABEL.to_string(), dependency.to_string());
+        }
+        None => {
+            record.status = "dead".to_string();
+            record.labels.remove(status::WAITING_ON_LABEL);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum RestartReason {
     Dead,
@@ -777,6 +857,25 @@ mod tests {
         assert!(is_unhealthy_restart_candidate(&record));
     }
 
+    #[test]
+    fn test_apply_waiting_state_roundtrip() {
+        let mut record = make_record("id-1", "app-api", "dead", None);
+
+        apply_waiting_state(&mut record, Some("db (healthy)"));
+        assert_eq!(record.status, "waiting");
+        assert_eq!(
+            record
+                .labels
+                .get(status::WAITING_ON_LABEL)
+                .map(String::as_str),
+            Some("db (healthy)")
+        );
+
+        apply_waiting_state(&mut record, None);
+        assert_eq!(record.status, "dead");
+        assert!(!record.labels.contains_key(status::WAITING_ON_LABEL));
+    }
+
     #[test]
     fn test_restart_log_line_for_dead_includes_policy_and_exit_code() {
         let mut record = make_record("id-1", "box", "dead", None);
@@ -6,11 +6,14 @@
 //! set it serves:
 //!
 //! - `GET /healthz` → `200 ok` (liveness probe)
-//! - `GET /metrics` → Prometheus text of box-state metrics
+//! - `GET /metrics` → Prometheus text of box-state metrics, plus per-box
+//!   network flow counts and bytes for running bridge-networked boxes
 //!
 //! The endpoint is **off by default** and intended to bind loopback (e.g.
 //! `127.0.0.1:9100`); there is no auth, so do not expose it publicly.
 
+use a3s_box_runtime::network::flows::FlowSummary;
+
 use crate::state::{BoxRecord, StateFile};
 
 /// The minimal per-box view the metrics need — decouples the renderer from the
@@ -79,6 +82,46 @@ fn render_from<'a>(items: impl Iterator<Item = BoxMetric<'a>>) -> String {
     out
 }
 
+/// Render per-box flow metrics for running bridge-networked boxes.
+fn render_flow_metrics(records: &[BoxRecord]) -> String {
+    render_flow_metrics_from(
+        records
+            .iter()
+            .filter(|r| r.status == "running")
+            .filter_map(|r| {
+                let flows = super::net_flows::box_flows(r).ok()?;
+                Some((r.name.as_str(), FlowSummary::from_flows(&flows)))
+            }),
+    )
+}
+
+fn render_flow_metrics_from<'a>(items: impl Iterator<Item = (&'a str, FlowSummary)>) -> String {
+    let mut flows = String::new();
+    let mut bytes = String::new();
+    for (name, summary) in items {
+        let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+        flows.push_str(&format!(
+            "a3s_box_net_flows{{box=\"{name}\",direction=\"outbound\"}} {}\n\
+             a3s_box_net_flows{{box=\"{name}\",direction=\"inbound\"}} {}\n",
+            summary.outbound, summary.inbound
+        ));
+        bytes.push_str(&format!(
+            "a3s_box_net_flow_bytes_total{{box=\"{name}\",direction=\"sent\"}} {}\n\
+             a3s_box_net_flow_bytes_total{{box=\"{name}\",direction=\"received\"}} {}\n",
+            summary.bytes_sent, summary.bytes_received
+        ));
+    }
+
+    let mut out = String::new();
+    out.push_str("# HELP a3s_box_net_flows Network flows seen on a box's bridge interface.\n");
+    out.push_str("# TYPE a3s_box_net_flows gauge\n");
+    out.push_str(&flows);
+    out.push_str("# HELP a3s_box_net_flow_bytes_total Frame bytes carried by a box's flows.\n");
+    out.push_str("# TYPE a3s_box_net_flow_bytes_total counter\n");
+    out.push_str(&bytes);
+    out
+}
+
 /// Build a minimal HTTP/1.1 response (no keep-alive).
 fn http_response(status_line: &str, content_type: &str, body: &str) -> String {
     format!(
@@ -180,7 +223,11 @@ pub async fn serve(
                 }
                 Some("/metrics") => {
                     let mut body = StateFile::load_readonly()
-                        .map(|s| render_box_metrics(s.records()))
+                        .map(|s| {
+                            let mut body = render_box_metrics(s.records());
+                            body.push_str(&render_flow_metrics(s.records()));
+                            body
+                        })
                         .unwrap_or_else(|e| format!("# state load error: {e}\n"));
                     body.push_str(&render_monitor_liveness(
                         last_poll.load(Ordering::Relaxed),
@@ -238,6 +285,22 @@ mod tests {
         assert!(out.contains("# TYPE a3s_box_state gauge"));
     }
 
+    #[test]
+    fn renders_flow_metrics_per_box() {
+        let summary = FlowSummary {
+            outbound: 3,
+            inbound: 1,
+            bytes_sent: 1200,
+            bytes_received: 64000,
+        };
+        let out = render_flow_metrics_from(std::iter::once(("agent", summary)));
+        assert!(out.contains("# TYPE a3s_box_net_flows gauge"));
+        assert!(out.contains("a3s_box_net_flows{box=\"agent\",direction=\"outbound\"} 3"));
+        assert!(out.contains("a3s_box_net_flows{box=\"agent\",direction=\"inbound\"} 1"));
+        assert!(out
+            .contains("a3s_box_net_flow_bytes_total{box=\"agent\",direction=\"received\"} 64000"));
+    }
+
     #[test]
     fn parse_get_path_handles_method_and_query() {
         assert_eq!(
@@ -10,6 +10,8 @@
 #[cfg(any(target_os = "linux", target_os = "macos", test))]
 use std::path::{Path, PathBuf};
 
+use crate::notify::NotifyEvent;
+
 /// systemd unit name.
 #[cfg(any(target_os = "linux", test))]
 const SYSTEMD_UNIT: &str = "a3s-box-monitor.service";
@@ -19,7 +21,10 @@ const LAUNCHD_LABEL: &str = "com.a3s-box.monitor";
 
 /// Render the systemd **user** unit that supervises `a3s-box monitor`.
 #[cfg(any(target_os = "linux", test))]
-pub fn systemd_unit(exe: &Path, interval: u64) -> String {
+pub fn systemd_unit(exe: &Path, interval: u64, notify: &[NotifyEvent]) -> String {
+    let notify = notify_value(notify)
+        .map(|events| format!(" --notify {events}"))
+        .unwrap_or_default();
     format!(
         "[Unit]\n\
          Description=a3s-box monitor — restarts dead/unhealthy detached boxes\n\
@@ -28,7 +33,7 @@ pub fn systemd_unit(exe: &Path, interval: u64) -> String {
          \n\
          [Service]\n\
          Type=simple\n\
-         ExecStart={exe} monitor --interval {interval}\n\
+         ExecStart={exe} monitor --interval {interval}{notify}\n\
          Restart=always\n\
          RestartSec=2\n\
          \n\
@@ -40,7 +45,15 @@ pub fn systemd_unit(exe: &Path, interval: u64) -> String {
 
 /// Render the launchd LaunchAgent plist that supervises `a3s-box monitor`.
 #[cfg(any(target_os = "macos", test))]
-pub fn launchd_plist(exe: &Path, interval: u64, log_path: &Path) -> String {
+pub fn launchd_plist(exe: &Path, interval: u64, notify: &[NotifyEvent], log_path: &Path) -> String {
+    let notify = notify_value(notify)
+        .map(|events| {
+            format!(
+                "\x20\x20\x20 <string>--notify</string>\n\
+                 \x20\x20\x20 <string>{events}</string>\n"
+            )
+        })
+        .unwrap_or_default();
     format!(
         "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
          <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
@@ -53,6 +66,7 @@ pub fn launchd_plist(exe: &Path, interval: u64, log_path: &Path) -> String {
          \x20\x20\x20 <string>monitor</string>\n\
          \x20\x20\x20 <string>--interval</string>\n\
          \x20\x20\x20 <string>{interval}</string>\n\
+         {notify}\
          \x20 </array>\n\
          \x20 <key>RunAtLoad</key><true/>\n\
          \x20 <key>KeepAlive</key><true/>\n\
@@ -66,6 +80,18 @@ pub fn launchd_plist(exe: &Path, interval: u64, log_path: &Path) -> String {
     )
 }
 
+/// The `--notify` value that reproduces `events`, if any are set.
+#[cfg(any(target_os = "linux", target_os = "macos", test))]
+fn notify_value(events: &[NotifyEvent]) -> Option<String> {
+    (!events.is_empty()).then(|| {
+        events
+            .iter()
+            .map(|event| event.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    })
+}
+
 /// Escape XML element-content special characters. Without this, an exe or home
 /// path containing `&`, `<`, or `>` produces a malformed plist that `launchctl`
 /// rejects — silently leaving the monitor unsupervised.
@@ -100,8 +126,8 @@ fn launchd_plist_path() -> PathBuf {
 }
 
 /// Install and enable the monitor as a supervised per-user service.
-pub fn install(interval: u64) -> Result<(), Box<dyn std::error::Error>> {
-    install_impl(interval)
+pub fn install(interval: u64, notify: &[NotifyEvent]) -> Result<(), Box<dyn std::error::Error>> {
+    install_impl(interval, notify)
 }
 
 /// Disable and remove the installed monitor service.
@@ -110,13 +136,13 @@ pub fn uninstall() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[cfg(target_os = "linux")]
-fn install_impl(interval: u64) -> Result<(), Box<dyn std::error::Error>> {
+fn install_impl(interval: u64, notify: &[NotifyEvent]) -> Result<(), Box<dyn std::error::Error>> {
     let exe = std::env::current_exe()?;
     let path = systemd_unit_path();
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    std::fs::write(&path, systemd_unit(&exe, interval))?;
+    std::fs::write(&path, systemd_unit(&exe, interval, notify))?;
     println!("Wrote systemd user unit: {}", path.display());
     let ok = run_quiet("systemctl", &["--user", "daemon-reload"])
         && run_quiet("systemctl", &["--user", "enable", "--now", SYSTEMD_UNIT]);
@@ -137,7 +163,7 @@ fn install_impl(interval: u64) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[cfg(target_os = "macos")]
-fn install_impl(interval: u64) -> Result<(), Box<dyn std::error::Error>> {
+fn install_impl(interval: u64, notify: &[NotifyEvent]) -> Result<(), Box<dyn std::error::Error>> {
     let exe = std::env::current_exe()?;
     let path = launchd_plist_path();
     if let Some(parent) = path.parent() {
@@ -147,7 +173,7 @@ fn install_impl(interval: u64) -> Result<(), Box<dyn std::error::Error>> {
     if let Some(parent) = log.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    std::fs::write(&path, launchd_plist(&exe, interval, &log))?;
+    std::fs::write(&path, launchd_plist(&exe, interval, notify, &log))?;
     println!("Wrote launchd agent: {}", path.display());
     let _ = run_quiet("launchctl", &["unload", &path.to_string_lossy()]);
     if run_quiet("launchctl", &["load", "-w", &path.to_string_lossy()]) {
@@ -166,7 +192,7 @@ fn install_impl(interval: u64) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-fn install_impl(_interval: u64) -> Result<(), Box<dyn std::error::Error>> {
+fn install_impl(_interval: u64, _notify: &[NotifyEvent]) -> Result<(), Box<dyn std::error::Error>> {
     Err("monitor --install is only supported on Linux (systemd) and macOS (launchd)".into())
 }
 
@@ -217,8 +243,8 @@ mod tests {
 
     #[test]
     fn systemd_unit_has_execstart_and_restart() {
-        let unit = systemd_unit(Path::new("/usr/local/bin/a3s-box"), 5);
-        assert!(unit.contains("ExecStart=/usr/local/bin/a3s-box monitor --interval 5"));
+        let unit = systemd_unit(Path::new("/usr/local/bin/a3s-box"), 5, &[]);
+        assert!(unit.contains("ExecStart=/usr/local/bin/a3s-box monitor --interval 5\n"));
         assert!(unit.contains("Restart=always"));
         assert!(unit.contains("WantedBy=default.target"));
     }
@@ -228,6 +254,7 @@ mod tests {
         let plist = launchd_plist(
             Path::new("/usr/local/bin/a3s-box"),
             7,
+            &[],
             Path::new("/home/u/.a3s/monitor.log"),
         );
         assert!(plist.contains("<key>Label</key><string>com.a3s-box.monitor</string>"));
@@ -237,6 +264,23 @@ mod tests {
         assert!(plist.contains("/home/u/.a3s/monitor.log"));
     }
 
+    #[test]
+    fn service_passes_notify_events_through() {
+        let events = [NotifyEvent::Crashed, NotifyEvent::Unhealthy];
+        let unit = systemd_unit(Path::new("/usr/local/bin/a3s-box"), 5, &events);
+        assert!(unit.contains("monitor --interval 5 --notify crashed,unhealthy\n"));
+
+        let plist = launchd_plist(
+            Path::new("/usr/local/bin/a3s-box"),
+            5,
+            &events[..1],
+            Path::new("/home/u/.a3s/monitor.log"),
+        );
+        assert!(plist.contains(
+            "<string>5</string>\n    <string>--notify</string>\n    <string>crashed</string>\n  </array>"
+        ));
+    }
+
     #[test]
     fn install_paths_are_user_scoped() {
         assert!(systemd_unit_path().ends_with(
@@ -255,6 +299,7 @@ mod tests {
         let plist = launchd_plist(
             Path::new("/opt/a&b/<bin>/a3s-box"),
             5,
+            &[],
             Path::new("/home/a&b/.a3s/monitor.log"),
         );
         assert!(plist.contains("/opt/a&amp;b/&lt;bin&gt;/a3s-box"));
@@ -0,0 +1,148 @@
+//! `a3s-box net-flows` command — Show the connections a box has made.
+//!
+//! Flows are reconstructed from the packet capture of the box's passt relay,
+//! so they are available for bridge-networked boxes (`--network`) on Linux.
+//! Each row is one TCP connection or UDP/ICMP exchange with the remote
+//! address, bytes in each direction, and duration.
+
+use clap::{Args, ValueEnum};
+
+use a3s_box_runtime::network::flows::{FlowDirection, NetFlow};
+
+use crate::output;
+use crate::resolve;
+use crate::state::{BoxRecord, StateFile};
+
+#[derive(Args)]
+pub struct NetFlowsArgs {
+    /// Box name or ID
+    pub r#box: String,
+
+    /// Only show flows the box opened
+    #[arg(long)]
+    pub outbound: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = NetFlowsFormat::Table)]
+    format: NetFlowsFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum NetFlowsFormat {
+    Table,
+    Json,
+}
+
+pub async fn execute(args: NetFlowsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let state = StateFile::load_default()?;
+    let record = resolve::resolve(&state, &args.r#box)?;
+
+    let mut flows = box_flows(record)?;
+    if args.outbound {
+        flows.retain(|flow| flow.direction == FlowDirection::Outbound);
+    }
+
+    match args.format {
+        NetFlowsFormat::Json => println!("{}", serde_json::to_string(&flows)?),
+        NetFlowsFormat::Table => {
+            let mut table = output::new_table(&[
+                "PROTO",
+                "DIRECTION",
+                "LOCAL",
+                "REMOTE",
+                "SENT",
+                "RECEIVED",
+                "DURATION",
+                "STATE",
+                "FIRST SEEN",
+            ]);
+            for flow in &flows {
+                table.add_row(flow_row(flow));
+            }
+            println!("{table}");
+        }
+    }
+    Ok(())
+}
+
+/// Flows recorded for a box, oldest first.
+///
+/// Boxes without bridge networking have no relay to observe and are rejected;
+/// a bridge box whose capture does not exist yet simply has no flows.
+pub(super) fn box_flows(record: &BoxRecord) -> Result<Vec<NetFlow>, String> {
+    if crate::cleanup::record_network_name(record).is_none() {
+        return Err(format!(
+            "box {} does not use bridge networking; flow records need a box started with --network",
+            record.name
+        ));
+    }
+    let (Some(pcap_path), Some(guest_mac)) = (
+        super::stats::passt_pcap_path(record),
+        super::stats::guest_mac_address(record),
+    ) else {
+        return Err(format!(
+            "box {} has no network endpoint to attribute traffic to",
+            record.name
+        ));
+    };
+    match a3s_box_runtime::network::flows::read_pcap_flows(&pcap_path, guest_mac) {
+        Ok(flows) => Ok(flows),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(format!(
+            "failed to read flow capture {}: {error}",
+            pcap_path.display()
+        )),
+    }
+}
+
+fn flow_row(flow: &NetFlow) -> Vec<String> {
+    let first_seen = chrono::DateTime::from_timestamp_micros(flow.first_seen_us as i64)
+        .map(|at| output::format_ago(&at))
+        .unwrap_or_else(|| "-".to_string());
+    vec![
+        flow.protocol.to_string(),
+        flow.direction.to_string(),
+        format_endpoint(flow.local_ip, flow.local_port),
+        format_endpoint(flow.remote_ip, flow.remote_port),
+        output::format_bytes(flow.bytes_sent),
+        output::format_bytes(flow.bytes_received),
+        format!("{:.1}s", flow.duration().as_secs_f64()),
+        if flow.closed { "closed" } else { "open" }.to_string(),
+        first_seen,
+    ]
+}
+
+fn format_endpoint(ip: std::net::IpAddr, port: u16) -> String {
+    match (ip, port) {
+        (ip, 0) => ip.to_string(),
+        (std::net::IpAddr::V6(ip), port) => format!("[{ip}]:{port}"),
+        (ip, port) => format!("{ip}:{port}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_endpoint() {
+        assert_eq!(
+            format_endpoint("93.184.216.34".parse().unwrap(), 443),
+            "93.184.216.34:443"
+        );
+        assert_eq!(
+            format_endpoint("2001:db8::1".parse().unwrap(), 53),
+            "[2001:db8::1]:53"
+        );
+        assert_eq!(format_endpoint("10.0.0.1".parse().unwrap(), 0), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_box_flows_requires_bridge_network() {
+        let record = crate::test_helpers::fixtures::make_record("box-1", "web", "running", Some(1));
+
+        let error = box_flows(&record).unwrap_err();
+
+        assert!(error.contains("--network"), "got: {error}");
+    }
+}
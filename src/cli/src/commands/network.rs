@@ -2,6 +2,13 @@
 //!
 //! Provides create/ls/rm/inspect/connect/disconnect for user-defined
 //! bridge networks that enable container-to-container communication.
+//!
+//! Name resolution between boxes goes through a small resolver guest init
+//! runs at 127.0.0.11, which answers box names and aliases from a records
+//! file. A box gets its records at boot; whenever the set of endpoints changes
+//! afterwards (connect, disconnect, a peer booting onto the network), running
+//! peers have theirs rewritten over the exec channel and the resolver picks
+//! the change up on the next query.
 
 use a3s_box_core::network::{IsolationMode, NetworkConfig, NetworkEndpoint, NetworkMode};
 use a3s_box_runtime::NetworkStore;
@@ -42,6 +49,19 @@ pub struct CreateArgs {
     #[arg(long, default_value = "10.89.0.0/24")]
     pub subnet: String,
 
+    /// Gateway address inside the subnet (default: first host address)
+    #[arg(long)]
+    pub gateway: Option<String>,
+
+    /// Enable IPv6 (dual-stack) addressing on the network
+    #[arg(long)]
+    pub ipv6: bool,
+
+    /// IPv6 subnet in CIDR notation, /64 to /120 (implies --ipv6; default: a
+    /// unique-local /64 derived from --subnet)
+    #[arg(long)]
+    pub subnet6: Option<String>,
+
     /// Network driver
     #[arg(long, default_value = "bridge")]
     pub driver: String,
@@ -85,6 +105,10 @@ pub struct ConnectArgs {
 
     /// Box name or ID
     pub container: String,
+
+    /// Add a DNS alias for the box on this network, can be repeated
+    #[arg(long = "alias", visible_alias = "network-alias")]
+    pub aliases: Vec<String>,
 }
 
 #[derive(Args)]
@@ -121,14 +145,14 @@ pub async fn execute(args: NetworkArgs) -> Result<(), Box<dyn std::error::Error>
 }
 
 /// Networks that mirror Docker's predefined networks and are never pruned.
-fn is_predefined_network(name: &str) -> bool {
+pub(super) fn is_predefined_network(name: &str) -> bool {
     matches!(name, "bridge" | "host" | "none")
 }
 
 /// Whether a network has no attachments: no live endpoints and no box record
 /// (running or stopped) configured for it. Matches `docker network prune`,
 /// which removes networks not used by at least one container.
-fn network_is_unused(
+pub(super) fn network_is_unused(
     config: &NetworkConfig,
     in_use_names: &std::collections::HashSet<String>,
 ) -> bool {
@@ -136,8 +160,7 @@ fn network_is_unused(
 }
 
 /// Remove every unused, non-predefined network from `store`. Returns the names
-/// removed and any per-network errors. Shared by `network prune` and
-/// `system prune` (Docker's `system prune` also reaps unused networks).
+/// removed and any per-network errors.
 pub(crate) fn prune_unused_networks(
     store: &NetworkStore,
     state: &crate::state::StateFile,
@@ -210,6 +233,26 @@ async fn execute_create(args: CreateArgs) -> Result<(), Box<dyn std::error::Erro
 
     config.driver = args.driver;
 
+    if let Some(gateway) = &args.gateway {
+        let gateway: std::net::Ipv4Addr = gateway
+            .parse()
+            .map_err(|_| format!("Invalid gateway address '{gateway}'"))?;
+        config
+            .set_gateway(gateway)
+            .map_err(|e| format!("Invalid network configuration: {e}"))?;
+    }
+
+    if args.ipv6 || args.subnet6.is_some() {
+        let subnet6 = match &args.subnet6 {
+            Some(subnet6) => subnet6.clone(),
+            None => default_ipv6_subnet(&config.subnet)
+                .ok_or_else(|| format!("Invalid subnet '{}'", config.subnet))?,
+        };
+        config
+            .set_ipv6_subnet(&subnet6)
+            .map_err(|e| format!("Invalid network configuration: {e}"))?;
+    }
+
     // Parse isolation mode
     config.policy.isolation = match args.isolation.as_str() {
         "none" => IsolationMode::None,
@@ -245,6 +288,15 @@ async fn execute_create(args: CreateArgs) -> Result<(), Box<dyn std::error::Erro
             )
             .into());
         }
+        if let (Some(subnet6), Some(existing6)) = (&config.subnet6, &existing.subnet6) {
+            if ipv6_subnets_overlap(subnet6, existing6) {
+                return Err(format!(
+                    "IPv6 subnet {subnet6} overlaps with existing network '{}' ({existing6})",
+                    existing.name
+                )
+                .into());
+            }
+        }
     }
 
     store.create(config)?;
@@ -285,6 +337,44 @@ fn subnets_overlap(a: &str, b: &str) -> bool {
     (net_a & mask) == (net_b & mask)
 }
 
+/// Unique-local /64 derived from an IPv4 subnet's network address, e.g.
+/// `10.89.0.0/24` → `fd00:a3b0:a59::/64`. IPv4 subnets never overlap, so
+/// the derived IPv6 subnets are distinct too.
+fn default_ipv6_subnet(subnet: &str) -> Option<String> {
+    let (network, _) = parse_cidr(subnet)?;
+    let network = std::net::Ipv6Addr::new(
+        0xfd00,
+        0xa3b0,
+        (network >> 16) as u16,
+        network as u16,
+        0,
+        0,
+        0,
+        0,
+    );
+    Some(format!("{network}/64"))
+}
+
+/// Whether two IPv6 subnets overlap (one contains the other's network address).
+fn ipv6_subnets_overlap(a: &str, b: &str) -> bool {
+    let parse = |cidr: &str| -> Option<(u128, u32)> {
+        let (addr, prefix) = cidr.split_once('/')?;
+        let ip: std::net::Ipv6Addr = addr.trim().parse().ok()?;
+        let prefix: u32 = prefix.trim().parse().ok()?;
+        (prefix <= 128).then_some((u128::from(ip), prefix))
+    };
+    let (Some((net_a, pa)), Some((net_b, pb))) = (parse(a), parse(b)) else {
+        return false;
+    };
+    let shorter = pa.min(pb);
+    let mask = if shorter == 0 {
+        0
+    } else {
+        u128::MAX << (128 - shorter)
+    };
+    (net_a & mask) == (net_b & mask)
+}
+
 pub(crate) fn validate_attachable_network(config: &NetworkConfig) -> Result<(), String> {
     config.validate_runtime()
 }
@@ -304,6 +394,10 @@ async fn execute_ls(args: LsArgs) -> Result<(), Box<dyn std::error::Error>> {
     let mut networks = store.list()?;
     networks.sort_by(|a, b| a.name.cmp(&b.name));
 
+    if crate::output::structured().is_some() {
+        return crate::output::emit("NetworkList", &networks);
+    }
+
     if args.quiet {
         for net in &networks {
             println!("{}", net.name);
@@ -327,7 +421,10 @@ async fn execute_ls(args: LsArgs) -> Result<(), Box<dyn std::error::Error>> {
         table.add_row(vec![
             net.name.clone(),
             net.driver.clone(),
-            net.subnet.clone(),
+            match &net.subnet6 {
+                Some(subnet6) => format!("{},{subnet6}", net.subnet),
+                None => net.subnet.clone(),
+            },
             net.gateway.to_string(),
             isolation,
             net.endpoints.len().to_string(),
@@ -436,6 +533,9 @@ async fn execute_inspect(args: InspectArgs) -> Result<(), Box<dyn std::error::Er
         .get(&args.name)?
         .ok_or_else(|| format!("network '{}' not found", args.name))?;
 
+    if crate::output::structured().is_some() {
+        return crate::output::emit("NetworkInspect", &config);
+    }
     let json = serde_json::to_string_pretty(&config)?;
     println!("{json}");
     Ok(())
@@ -448,6 +548,9 @@ async fn execute_connect(args: ConnectArgs) -> Result<(), Box<dyn std::error::Er
     // Resolve box name/ID using Docker-compatible resolution
     let record = crate::resolve::resolve(&state, &args.container)?.clone();
     require_inactive_for_network_change(&record, "connect to a network")?;
+    for alias in &args.aliases {
+        a3s_box_core::dns::validate_hostname(alias).map_err(|e| format!("Invalid --alias: {e}"))?;
+    }
 
     if let Some(existing) = crate::cleanup::record_network_name(&record) {
         if existing != args.network {
@@ -470,7 +573,7 @@ async fn execute_connect(args: ConnectArgs) -> Result<(), Box<dyn std::error::Er
                         format!("network '{}' not found", args.network).into()
                     })?;
             validate_attachable_network(config)?;
-            ensure_endpoint(config, &record.id, &record.name).map_err(
+            ensure_endpoint(config, &record.id, &record.name, &args.aliases).map_err(
                 |e| -> Box<dyn std::error::Error> { format!("Failed to connect: {e}").into() },
             )
         },
@@ -479,13 +582,25 @@ async fn execute_connect(args: ConnectArgs) -> Result<(), Box<dyn std::error::Er
     {
         let state_record = crate::resolve::resolve_mut(&mut state, &record.id)?;
         set_record_network(state_record, &args.network);
+        state_record.network_aliases = endpoint.aliases.clone();
     }
     state.save()?;
+    refresh_network_peers(&args.network, &record.id).await;
 
-    println!(
-        "Connected {} to {} (IP: {})",
-        record.name, args.network, endpoint.ip_address
-    );
+    if endpoint.aliases.is_empty() {
+        println!(
+            "Connected {} to {} (IP: {})",
+            record.name, args.network, endpoint.ip_address
+        );
+    } else {
+        println!(
+            "Connected {} to {} (IP: {}, aliases: {})",
+            record.name,
+            args.network,
+            endpoint.ip_address,
+            endpoint.aliases.join(", ")
+        );
+    }
     Ok(())
 }
 
@@ -533,21 +648,132 @@ async fn execute_disconnect(args: DisconnectArgs) -> Result<(), Box<dyn std::err
         clear_record_network(state_record);
         state.save()?;
     }
+    refresh_network_peers(&args.network, &record.id).await;
 
     println!("Disconnected {} from {}", record.name, args.network);
     Ok(())
 }
 
+/// Rewrite the DNS records of every running box on `network_name` except
+/// `changed_box_id`, so peers resolve the network's current endpoints.
+///
+/// Best-effort: a peer that cannot be reached keeps its old records until its
+/// next boot, and failures are only logged.
+pub(crate) async fn refresh_network_peers(network_name: &str, changed_box_id: &str) {
+    let config = match NetworkStore::default_path().and_then(|store| store.get(network_name)) {
+        Ok(Some(config)) => config,
+        Ok(None) => return,
+        Err(error) => {
+            tracing::warn!(network = network_name, %error, "Failed to load network for peer DNS refresh");
+            return;
+        }
+    };
+    let state = match crate::state::StateFile::load_readonly() {
+        Ok(state) => state,
+        Err(error) => {
+            tracing::warn!(network = network_name, %error, "Failed to load state for peer DNS refresh");
+            return;
+        }
+    };
+
+    for endpoint in config.endpoints.values() {
+        if endpoint.box_id == changed_box_id {
+            continue;
+        }
+        let Some(record) = state.find_by_id(&endpoint.box_id) else {
+            continue;
+        };
+        if record.status != "running" {
+            continue;
+        }
+        if let Err(error) = write_peer_records(&config, record).await {
+            tracing::warn!(
+                box_id = %record.id,
+                network = network_name,
+                %error,
+                "Failed to refresh DNS records in running peer"
+            );
+        }
+    }
+}
+
+/// The DNS records a box on `config` should serve, mirroring what the runtime
+/// writes at boot.
+#[cfg_attr(windows, allow(dead_code))]
+fn peer_dns_records(config: &NetworkConfig, record: &crate::state::BoxRecord) -> Option<String> {
+    let endpoint = config.endpoints.get(&record.id)?;
+    let mut own_names = endpoint.dns_names();
+    if let Some(hostname) = record.hostname.as_deref().filter(|h| !h.is_empty()) {
+        if !own_names.iter().any(|name| name == hostname) {
+            own_names.push(hostname.to_string());
+        }
+    }
+    Some(a3s_box_core::dns::generate_dns_records(
+        &endpoint.ip_address.to_string(),
+        &own_names,
+        &config.peer_endpoints(&record.id),
+    ))
+}
+
+#[cfg(not(windows))]
+async fn write_peer_records(
+    config: &NetworkConfig,
+    record: &crate::state::BoxRecord,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use base64::Engine;
+
+    let Some(content) = peer_dns_records(config, record) else {
+        return Ok(());
+    };
+    let exec_socket_path =
+        crate::socket_paths::runtime_socket(record, crate::socket_paths::RuntimeSocket::Exec);
+    let client = a3s_box_runtime::ExecClient::connect(&exec_socket_path).await?;
+    let response = client
+        .file_transfer(&a3s_box_core::exec::FileRequest {
+            op: a3s_box_core::exec::FileOp::Upload,
+            guest_path: a3s_box_core::dns::EMBEDDED_DNS_RECORDS_PATH.to_string(),
+            data: Some(base64::engine::general_purpose::STANDARD.encode(content)),
+            user: None,
+        })
+        .await?;
+    if !response.success {
+        return Err(response
+            .error
+            .unwrap_or_else(|| "guest rejected the write".to_string())
+            .into());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+async fn write_peer_records(
+    _config: &NetworkConfig,
+    _record: &crate::state::BoxRecord,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+/// Connect a box, or refresh the name and aliases of its existing endpoint.
+///
+/// Aliases only replace the existing ones when some are given, so reconnecting
+/// without `--alias` keeps the names a box was configured with.
 fn ensure_endpoint(
     config: &mut NetworkConfig,
     box_id: &str,
     box_name: &str,
+    aliases: &[String],
 ) -> Result<NetworkEndpoint, String> {
     if let Some(endpoint) = config.endpoints.get_mut(box_id) {
         endpoint.box_name = box_name.to_string();
+        let aliases = if aliases.is_empty() {
+            endpoint.aliases.clone()
+        } else {
+            aliases.to_vec()
+        };
+        endpoint.set_aliases(&aliases);
         return Ok(endpoint.clone());
     }
-    config.connect(box_id, box_name)
+    config.connect_with_aliases(box_id, box_name, aliases)
 }
 
 fn require_inactive_for_network_change(
@@ -559,7 +785,7 @@ fn require_inactive_for_network_change(
     }
 
     Err(format!(
-        "Cannot {action} box {} because network hot-plug is not supported yet. Stop it first, run the network command, then start it again.",
+        "Cannot {action} box {} because network hot-plug is not supported yet. Stop it first, run the network command, then start it again; running peers pick up the change immediately.",
         record.name
     ))
 }
@@ -574,6 +800,7 @@ fn set_record_network(record: &mut crate::state::BoxRecord, network: &str) {
 fn clear_record_network(record: &mut crate::state::BoxRecord) {
     record.network_mode = NetworkMode::Tsi;
     record.network_name = None;
+    record.network_aliases.clear();
 }
 
 #[cfg(test)]
@@ -608,14 +835,57 @@ mod tests {
     #[test]
     fn test_ensure_endpoint_reuses_existing_endpoint_and_updates_name() {
         let mut config = NetworkConfig::new("testnet", "10.89.0.0/24").unwrap();
-        let first = ensure_endpoint(&mut config, "box-1", "old-name").unwrap();
-        let second = ensure_endpoint(&mut config, "box-1", "new-name").unwrap();
+        let first = ensure_endpoint(&mut config, "box-1", "old-name", &[]).unwrap();
+        let second = ensure_endpoint(&mut config, "box-1", "new-name", &[]).unwrap();
 
         assert_eq!(first.ip_address, second.ip_address);
         assert_eq!(second.box_name, "new-name");
         assert_eq!(config.endpoints.get("box-1").unwrap().box_name, "new-name");
     }
 
+    #[test]
+    fn test_ensure_endpoint_sets_and_keeps_aliases() {
+        let mut config = NetworkConfig::new("testnet", "10.89.0.0/24").unwrap();
+        let aliases = vec!["api".to_string(), "api-v2".to_string()];
+
+        let first = ensure_endpoint(&mut config, "box-1", "web", &aliases).unwrap();
+        let second = ensure_endpoint(&mut config, "box-1", "web", &[]).unwrap();
+        let third = ensure_endpoint(&mut config, "box-1", "web", &["www".to_string()]).unwrap();
+
+        assert_eq!(first.aliases, aliases);
+        assert_eq!(second.aliases, aliases);
+        assert_eq!(third.aliases, vec!["www"]);
+    }
+
+    #[test]
+    fn test_peer_dns_records_list_current_endpoints() {
+        let mut config = NetworkConfig::new("testnet", "10.89.0.0/24").unwrap();
+        let web = config.connect("box-1", "web").unwrap();
+        let db = config
+            .connect_with_aliases("box-2", "db", &["postgres".to_string()])
+            .unwrap();
+
+        let mut record =
+            crate::test_helpers::fixtures::make_record("box-1", "web", "running", Some(123));
+        record.hostname = Some("frontend".to_string());
+        record.add_host = vec!["registry:10.0.0.9".to_string()];
+
+        let records = peer_dns_records(&config, &record).unwrap();
+        assert!(records.contains(&format!("{} web frontend", web.ip_address)));
+        assert!(records.contains(&format!("{} db", db.ip_address)));
+        assert!(records.contains(&format!("{} postgres", db.ip_address)));
+        // --add-host entries stay in /etc/hosts, not the resolver's records.
+        assert!(!records.contains("registry"));
+
+        config.disconnect("box-2").unwrap();
+        let records = peer_dns_records(&config, &record).unwrap();
+        assert!(!records.contains(" db"));
+
+        let stranger =
+            crate::test_helpers::fixtures::make_record("box-9", "other", "running", Some(9));
+        assert!(peer_dns_records(&config, &stranger).is_none());
+    }
+
     #[test]
     fn test_require_inactive_for_network_change_rejects_active_boxes() {
         let running =
@@ -937,4 +1207,24 @@ mod tests {
         // Host bits are masked, so a host address resolves to its network.
         assert!(subnets_overlap("10.89.0.5/24", "10.89.0.200/24"));
     }
+
+    #[test]
+    fn test_default_ipv6_subnet_is_derived_from_ipv4_subnet() {
+        assert_eq!(
+            default_ipv6_subnet("10.89.0.0/24").as_deref(),
+            Some("fd00:a3b0:a59::/64")
+        );
+        assert_eq!(
+            default_ipv6_subnet("192.168.10.0/24").as_deref(),
+            Some("fd00:a3b0:c0a8:a00::/64")
+        );
+        assert!(default_ipv6_subnet("not-a-subnet").is_none());
+    }
+
+    #[test]
+    fn test_ipv6_subnets_overlap() {
+        assert!(ipv6_subnets_overlap("fd00::/64", "fd00::/64"));
+        assert!(ipv6_subnets_overlap("fd00::/48", "fd00:0:0:1::/64"));
+        assert!(!ipv6_subnets_overlap("fd00::/64", "fd00:0:0:1::/64"));
+    }
 }
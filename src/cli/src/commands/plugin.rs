@@ -0,0 +1,185 @@
+//! External plugin subcommands (`a3s-box <name>` → `a3s-box-<name>`).
+//!
+//! A subcommand that is not built in runs the first `a3s-box-<name>`
+//! executable in `~/.a3s/plugins` or on `PATH` with the remaining arguments,
+//! and `a3s-box` exits with the plugin's status. The plugin receives the
+//! home directory, the CLI path, its own name and the plugin API version
+//! through the environment described in [`a3s_box_sdk::plugin`].
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use a3s_box_sdk::plugin::{self, PluginContext, CLI_ENV, CONTEXT_ENV, HOME_ENV, PLUGIN_NAME_ENV};
+
+pub async fn execute(args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let Some((name, rest)) = args.split_first() else {
+        return Err("missing command".into());
+    };
+    let home = a3s_box_core::dirs_home();
+    let plugins_dir = plugin::plugins_dir(&home);
+    let plugin_path = valid_name(name)
+        .then(|| find_plugin(name, &plugins_dir, std::env::var_os("PATH").as_deref()))
+        .flatten()
+        .ok_or_else(|| {
+            format!(
+                "unknown command '{name}': not a built-in command and no {} \
+                 plugin in {} or on PATH (see 'a3s-box --help')",
+                plugin::executable_name(name),
+                plugins_dir.display()
+            )
+        })?;
+
+    let mut context = PluginContext::new(name.as_str(), home);
+    context.cli = std::env::current_exe().ok();
+    run(plugin_command(&plugin_path, rest, &context), &plugin_path)
+}
+
+/// The plugin invocation: the JSON handshake plus the same context as
+/// separate variables, for plugins written as shell scripts.
+fn plugin_command(path: &Path, args: &[String], context: &PluginContext) -> Command {
+    let mut command = Command::new(path);
+    command
+        .args(args)
+        .env(CONTEXT_ENV, context.to_json())
+        .env(HOME_ENV, &context.home)
+        .env(PLUGIN_NAME_ENV, &context.name);
+    if let Some(cli) = &context.cli {
+        command.env(CLI_ENV, cli);
+    }
+    command
+}
+
+/// Replace this process with the plugin so signals, the terminal and the
+/// exit status belong to it directly.
+#[cfg(unix)]
+fn run(mut command: Command, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::process::CommandExt;
+    let error = command.exec();
+    Err(format!("Failed to run plugin {}: {error}", path.display()).into())
+}
+
+#[cfg(not(unix))]
+fn run(mut command: Command, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let status = command
+        .status()
+        .map_err(|e| format!("Failed to run plugin {}: {e}", path.display()))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+/// Plugin names are plain words, so a subcommand can never name a path.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The first executable `a3s-box-<name>` in `plugins_dir`, then in the
+/// `path` directories. Plugins installed for a3s-box win over ones that
+/// happen to be on `PATH`.
+fn find_plugin(name: &str, plugins_dir: &Path, path: Option<&OsStr>) -> Option<PathBuf> {
+    let file_name = format!(
+        "{}{}",
+        plugin::executable_name(name),
+        std::env::consts::EXE_SUFFIX
+    );
+    std::iter::once(plugins_dir.to_path_buf())
+        .chain(path.into_iter().flat_map(std::env::split_paths))
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_subcommands_parse_as_plugins() {
+        use clap::Parser;
+
+        let cli = super::super::Cli::try_parse_from(["a3s-box", "scan", "--deep", "web"]).unwrap();
+        let Some(super::super::Command::Plugin(args)) = cli.command else {
+            panic!("expected plugin command");
+        };
+        assert_eq!(args, ["scan", "--deep", "web"]);
+    }
+
+    #[test]
+    fn test_valid_name() {
+        assert!(valid_name("scan"));
+        assert!(valid_name("org-audit_2"));
+        assert!(!valid_name(""));
+        assert!(!valid_name("--flag"));
+        assert!(!valid_name("../scan"));
+        assert!(!valid_name("a/b"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_plugin_takes_first_executable_on_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let not_executable = first.path().join("a3s-box-scan");
+        std::fs::write(&not_executable, "#!/bin/sh\n").unwrap();
+        let plugin = second.path().join("a3s-box-scan");
+        std::fs::write(&plugin, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path = std::env::join_paths([first.path(), second.path()]).unwrap();
+        let no_plugins = first.path().join("plugins");
+        assert_eq!(
+            find_plugin("scan", &no_plugins, Some(path.as_os_str())),
+            Some(plugin.clone())
+        );
+        assert_eq!(
+            find_plugin("other", &no_plugins, Some(path.as_os_str())),
+            None
+        );
+        assert_eq!(find_plugin("scan", &no_plugins, None), None);
+
+        // The plugins directory is searched first, and works without PATH.
+        let installed = tempfile::tempdir().unwrap();
+        let preferred = installed.path().join("a3s-box-scan");
+        std::fs::write(&preferred, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&preferred, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(
+            find_plugin("scan", installed.path(), Some(path.as_os_str())),
+            Some(preferred.clone())
+        );
+        assert_eq!(find_plugin("scan", installed.path(), None), Some(preferred));
+    }
+
+    #[test]
+    fn test_plugin_command_passes_context() {
+        let mut context = PluginContext::new("scan", "/srv/a3s");
+        context.cli = Some(PathBuf::from("/usr/local/bin/a3s-box"));
+        let command = plugin_command(Path::new("/bin/a3s-box-scan"), &["web".into()], &context);
+        let env: std::collections::HashMap<_, _> = command
+            .get_envs()
+            .filter_map(|(key, value)| Some((key.to_str()?, value?.to_str()?)))
+            .collect();
+        assert_eq!(PluginContext::from_json(env[CONTEXT_ENV]).unwrap(), context);
+        assert_eq!(env[HOME_ENV], "/srv/a3s");
+        assert_eq!(env[PLUGIN_NAME_ENV], "scan");
+        assert_eq!(env[CLI_ENV], "/usr/local/bin/a3s-box");
+        assert_eq!(command.get_args().collect::<Vec<_>>(), ["web"]);
+    }
+}
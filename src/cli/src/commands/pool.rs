@@ -236,8 +236,9 @@ pub struct PoolStartArgs {
     #[arg(long)]
     pub snapshot_fork: bool,
 
-    /// Serve Prometheus metrics (warm-pool hit/miss, VM boot, cache) on this
-    /// address (e.g. `127.0.0.1:9101`). Off when unset. Bind loopback — no auth.
+    /// Serve Prometheus metrics (warm-pool hit/miss, VM boot, cache) and a
+    /// `/readyz` host preflight probe on this address (e.g. `127.0.0.1:9101`).
+    /// Off when unset. Bind loopback — no auth.
     #[arg(long)]
     pub metrics_addr: Option<String>,
 
@@ -951,8 +952,9 @@ async fn reap_expired_leases_task(registry: std::sync::Arc<PoolRegistry>, lease_
 }
 
 /// Serve a Prometheus `/metrics` endpoint exposing the pool daemon's runtime
-/// metrics (warm_pool hit/miss, vm_boot, cache). Minimal raw-HTTP server,
-/// mirroring the monitor's metrics endpoint.
+/// metrics (warm_pool hit/miss, vm_boot, cache), plus `/readyz`, which runs
+/// the host preflight on each probe. Minimal raw-HTTP server, mirroring the
+/// monitor's metrics endpoint.
 async fn serve_pool_metrics(addr: String, metrics: a3s_box_runtime::RuntimeMetrics) {
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio::net::TcpListener;
@@ -979,13 +981,25 @@ async fn serve_pool_metrics(addr: String, metrics: a3s_box_runtime::RuntimeMetri
             };
             let req = String::from_utf8_lossy(&buf[..n]);
             let path = req.split_whitespace().nth(1).unwrap_or("");
-            let (status, body) = if path.starts_with("/metrics") {
-                ("200 OK", metrics.encode())
+            let (status, content_type, body) = if path.starts_with("/metrics") {
+                ("200 OK", "text/plain; version=0.0.4", metrics.encode())
+            } else if path.starts_with("/readyz") {
+                let report = tokio::task::spawn_blocking(|| {
+                    a3s_box_runtime::preflight(&a3s_box_runtime::PreflightOptions::default())
+                })
+                .await;
+                match report {
+                    Ok(report) => {
+                        let (status, body) = readyz_response(&report);
+                        (status, "application/json", body)
+                    }
+                    Err(_) => ("500 Internal Server Error", "text/plain", String::new()),
+                }
             } else {
-                ("404 Not Found", String::new())
+                ("404 Not Found", "text/plain", String::new())
             };
             let response = format!(
-                "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
                 body.len()
             );
             let _ = sock.write_all(response.as_bytes()).await;
@@ -993,6 +1007,20 @@ async fn serve_pool_metrics(addr: String, metrics: a3s_box_runtime::RuntimeMetri
     }
 }
 
+/// `/readyz` status line and JSON body: 503 while any preflight check fails.
+fn readyz_response(report: &a3s_box_runtime::PreflightReport) -> (&'static str, String) {
+    let status = if report.is_ready() {
+        "200 OK"
+    } else {
+        "503 Service Unavailable"
+    };
+    let body = serde_json::json!({
+        "ready": report.is_ready(),
+        "checks": report.checks,
+    });
+    (status, body.to_string())
+}
+
 /// Accept `pool run` connections until Ctrl-C, serving each request concurrently
 /// so independent sandboxes don't queue behind one another. On shutdown, stop the
 /// replenisher and destroy idle VMs (in-flight requests keep their own acquired VM).
@@ -1397,6 +1425,32 @@ mod tests {
         assert!(json.ends_with('}'));
     }
 
+    #[test]
+    fn test_readyz_response_reflects_preflight() {
+        use a3s_box_runtime::{
+            PreflightCheck, PreflightCheckKind, PreflightReport, PreflightStatus,
+        };
+
+        let mut report = PreflightReport {
+            checks: vec![PreflightCheck {
+                kind: PreflightCheckKind::DiskSpace,
+                status: PreflightStatus::Pass,
+                detail: "20.0 GiB free".to_string(),
+                hint: None,
+            }],
+        };
+        let (status, body) = readyz_response(&report);
+        assert_eq!(status, "200 OK");
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["ready"], true);
+        assert_eq!(body["checks"][0]["kind"], "disk_space");
+
+        report.checks[0].status = PreflightStatus::Fail;
+        let (status, body) = readyz_response(&report);
+        assert_eq!(status, "503 Service Unavailable");
+        assert!(body.contains("\"ready\":false"));
+    }
+
     #[test]
     fn test_keepalive_cmd_is_a_sleep_loop() {
         let c = keepalive_cmd();
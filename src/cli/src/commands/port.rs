@@ -31,10 +31,10 @@ fn format_persisted_port_mapping(mapping: &str) -> Result<String, String> {
     let mapping = a3s_box_core::parse_port_mapping(mapping)
         .map_err(|e| format!("Invalid persisted port mapping: {e}"))?;
     Ok(format!(
-        "{}/{} -> 0.0.0.0:{}",
+        "{}/{} -> {}",
         mapping.guest_port,
         mapping.protocol.as_str(),
-        mapping.host_port
+        mapping.host_address()
     ))
 }
 
@@ -56,6 +56,13 @@ mod tests {
         assert_eq!(output, "443/tcp -> 0.0.0.0:10443");
     }
 
+    #[test]
+    fn format_persisted_port_mapping_shows_bound_ipv6_address() {
+        let output = format_persisted_port_mapping("[::1]:18080:80").unwrap();
+
+        assert_eq!(output, "80/tcp -> [::1]:18080");
+    }
+
     #[test]
     fn format_persisted_port_mapping_preserves_auto_host_port_zero() {
         let output = format_persisted_port_mapping("0:8080").unwrap();
@@ -2,9 +2,14 @@
 
 use clap::Args;
 
+use crate::filter::{match_label, Filters};
 use crate::output;
 use crate::state::{BoxRecord, StateFile};
 use crate::status;
+use crate::template::Template;
+
+/// Keys accepted by `ps --filter`.
+const PS_FILTERS: &[&str] = &["status", "name", "ancestor", "id", "label", "health"];
 
 #[derive(Args)]
 pub struct PsArgs {
@@ -16,26 +21,43 @@ pub struct PsArgs {
     #[arg(short, long)]
     pub quiet: bool,
 
-    /// Format output as `json` or using placeholders: {{.ID}}, {{.Image}},
-    /// {{.Status}}, {{.Created}}, {{.Names}}, {{.Ports}}, {{.Command}}
+    /// Format output as `json` or a Go-style template over {{.ID}},
+    /// {{.FullID}}, {{.Image}}, {{.Status}}, {{.State}}, {{.Health}},
+    /// {{.Created}}, {{.CreatedAt}}, {{.Names}}, {{.Ports}}, {{.Command}},
+    /// {{.Labels}} and {{.Label "key"}}
     #[arg(long)]
     pub format: Option<String>,
 
-    /// Filter boxes (e.g., status=running, name=dev, ancestor=alpine)
+    /// Filter boxes: status=, name=, ancestor=<image>, id=, label=<key>[=<value>],
+    /// health=. Different keys must all match; a repeated key matches any value
     #[arg(short, long = "filter")]
     pub filters: Vec<String>,
 }
 
 pub async fn execute(args: PsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let filters = Filters::parse(&args.filters, PS_FILTERS)?;
+    let template = match args.format.as_deref() {
+        Some(fmt) if !fmt.trim().eq_ignore_ascii_case("json") => Some(Template::parse(fmt)?),
+        _ => None,
+    };
+
     let state = StateFile::load_default()?;
     let boxes = select_records(&state, args.all);
 
     // Apply filters
     let boxes: Vec<&BoxRecord> = boxes
         .into_iter()
-        .filter(|r| matches_filters(r, &args.filters))
+        .filter(|r| matches_filters(r, &filters))
         .collect();
 
+    if output::structured().is_some() {
+        let rows = boxes
+            .iter()
+            .map(|record| ps_json(record))
+            .collect::<Vec<_>>();
+        return output::emit("BoxList", rows);
+    }
+
     // --quiet: print only IDs
     if args.quiet {
         for record in &boxes {
@@ -44,18 +66,17 @@ pub async fn execute(args: PsArgs) -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    // --format: json or custom template output
-    if let Some(ref fmt) = args.format {
-        if fmt.trim().eq_ignore_ascii_case("json") {
-            print_json(&boxes)?;
-            return Ok(());
-        }
-
+    // --format: custom template or json output
+    if let Some(template) = &template {
         for record in &boxes {
-            println!("{}", apply_format(record, fmt));
+            println!("{}", apply_format(record, template));
         }
         return Ok(());
     }
+    if args.format.is_some() {
+        print_json(&boxes)?;
+        return Ok(());
+    }
 
     // Default: table output
     let mut table = output::new_table(&["BOX ID", "IMAGE", "STATUS", "CREATED", "PORTS", "NAMES"]);
@@ -109,34 +130,25 @@ fn ps_json(record: &BoxRecord) -> serde_json::Value {
     })
 }
 
-/// Check if a box record matches all the given filters.
+/// Check if a box record matches the given filters.
 ///
 /// Supported filters:
 /// - `status=<value>` — match box status (created, running, paused, stopped, dead)
 /// - `name=<value>` — match box name (substring)
 /// - `ancestor=<value>` — match image reference (substring)
 /// - `id=<value>` — match box ID prefix
-fn matches_filters(record: &BoxRecord, filters: &[String]) -> bool {
-    for filter in filters {
-        let (key, value) = match filter.split_once('=') {
-            Some((k, v)) => (k, v),
-            None => continue,
-        };
-
-        let matched = match key {
-            "status" => record.status == value,
-            "name" => record.name.contains(value),
-            "ancestor" => record.image.contains(value),
-            "id" => record.id.starts_with(value) || record.short_id.starts_with(value),
-            "label" => match_label(&record.labels, value),
-            _ => true, // Ignore unknown filters
-        };
-
-        if !matched {
-            return false;
-        }
-    }
-    true
+/// - `label=<key>[=<value>]` — match a label
+/// - `health=<value>` — match health status (starting, healthy, unhealthy, none)
+fn matches_filters(record: &BoxRecord, filters: &Filters) -> bool {
+    filters.matches(|key, value| match key {
+        "status" => record.status == value,
+        "name" => record.name.contains(value),
+        "ancestor" => record.image.contains(value),
+        "id" => record.id.starts_with(value) || record.short_id.starts_with(value),
+        "label" => match_label(&record.labels, value),
+        "health" => record.health_status == value,
+        _ => false,
+    })
 }
 
 fn select_records(state: &StateFile, all: bool) -> Vec<&BoxRecord> {
@@ -151,31 +163,29 @@ fn select_records(state: &StateFile, all: bool) -> Vec<&BoxRecord> {
         .collect()
 }
 
-/// Apply a format template, replacing `{{.Field}}` placeholders.
-fn apply_format(record: &BoxRecord, fmt: &str) -> String {
-    let labels_str = format_labels(&record.labels);
-    let status = status::format_status(record);
-    fmt.replace("{{.ID}}", &record.short_id)
-        .replace("{{.Image}}", &record.image)
-        .replace("{{.Status}}", &status)
-        .replace("{{.Created}}", &output::format_ago(&record.created_at))
-        .replace("{{.Names}}", &record.name)
-        .replace("{{.Command}}", &record.cmd.join(" "))
-        .replace("{{.Ports}}", &record.port_map.join(", "))
-        .replace("{{.Labels}}", &labels_str)
+/// Render a `--format` template for one box.
+fn apply_format(record: &BoxRecord, template: &Template) -> String {
+    template.render(&template_data(record))
 }
 
-/// Check if a box's labels match a label filter value.
-///
-/// Supports two forms:
-/// - `label=key` — check if the label key exists
-/// - `label=key=value` — check if the label key has the exact value
-fn match_label(labels: &std::collections::HashMap<String, String>, filter_value: &str) -> bool {
-    if let Some((key, value)) = filter_value.split_once('=') {
-        labels.get(key).is_some_and(|v| v == value)
-    } else {
-        labels.contains_key(filter_value)
-    }
+/// The fields a `ps --format` template can use, named as in `docker ps`.
+fn template_data(record: &BoxRecord) -> serde_json::Value {
+    serde_json::json!({
+        "ID": &record.short_id,
+        "FullID": &record.id,
+        "Image": &record.image,
+        "Command": record.cmd.join(" "),
+        "Created": output::format_ago(&record.created_at),
+        "CreatedAt": record.created_at.to_rfc3339(),
+        "Status": status::format_status(record),
+        "State": &record.status,
+        "Health": &record.health_status,
+        "Names": &record.name,
+        "Ports": record.port_map.join(", "),
+        "Labels": format_labels(&record.labels),
+        "Label": &record.labels,
+        "Pid": record.pid,
+    })
 }
 
 /// Format labels as a comma-separated "key=value" string.
@@ -191,6 +201,14 @@ mod tests {
     use std::collections::HashMap;
     use std::path::PathBuf;
 
+    fn matches_filters(record: &BoxRecord, specs: &[String]) -> bool {
+        super::matches_filters(record, &Filters::parse(specs, PS_FILTERS).unwrap())
+    }
+
+    fn apply_format(record: &BoxRecord, fmt: &str) -> String {
+        super::apply_format(record, &Template::parse(fmt).unwrap())
+    }
+
     fn make_record(name: &str, status: &str, labels: HashMap<String, String>) -> BoxRecord {
         let id = format!("test-id-{name}");
         let short_id = BoxRecord::make_short_id(&id);
@@ -243,6 +261,15 @@ mod tests {
             resource_limits: a3s_box_core::config::ResourceLimits::default(),
             log_config: a3s_box_core::log::LogConfig::default(),
             add_host: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            dns_option: vec![],
+            network_aliases: vec![],
+            network_policy: vec![],
+            network_bandwidth: None,
+            network_max_conns: None,
+            http_proxy: None,
+            socket_mounts: vec![],
             platform: None,
             init: false,
             read_only: false,
@@ -257,6 +284,8 @@ mod tests {
             stop_timeout: None,
             oom_kill_disable: false,
             oom_score_adj: None,
+            workspace_template: None,
+            metadata: Default::default(),
         }
     }
 
@@ -453,9 +482,33 @@ mod tests {
     }
 
     #[test]
-    fn test_filter_unknown_key_ignored() {
-        let record = make_record("box1", "running", HashMap::new());
-        assert!(matches_filters(&record, &["unknown=value".to_string()]));
+    fn test_filter_unknown_key_rejected() {
+        assert!(Filters::parse(&["unknown=value".to_string()], PS_FILTERS).is_err());
+    }
+
+    #[test]
+    fn test_filter_repeated_key_matches_any_value() {
+        let record = make_record("box1", "paused", HashMap::new());
+        assert!(matches_filters(
+            &record,
+            &["status=running".to_string(), "status=paused".to_string()]
+        ));
+        assert!(!matches_filters(&record, &["health=healthy".to_string()]));
+    }
+
+    #[test]
+    fn test_apply_format_template_fields() {
+        let mut labels = HashMap::new();
+        labels.insert("env".to_string(), "prod".to_string());
+        let record = make_record("box1", "running", labels);
+        assert_eq!(
+            apply_format(&record, "{{.Names}}\\t{{.State}}\\t{{.Label \"env\"}}"),
+            "box1\trunning\tprod"
+        );
+        assert_eq!(
+            apply_format(&record, "{{json .Label}}"),
+            r#"{"env":"prod"}"#
+        );
     }
 
     // --- format_status tests ---
@@ -261,7 +261,7 @@ fn restart_plan(record: &crate::state::BoxRecord, timeout: u64) -> Result<Restar
     }
 
     match record.status.as_str() {
-        "created" | "stopped" | "dead" => Ok(RestartPlan::LegacyStartOnly),
+        "created" | "stopped" | "dead" | "waiting" => Ok(RestartPlan::LegacyStartOnly),
         other => Err(format!("Cannot restart box in state: {other}")),
     }
 }
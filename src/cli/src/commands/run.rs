@@ -17,6 +17,7 @@ use super::common::{self, CommonBoxArgs};
 use super::pool::{
     PoolAutoStartConfig, DEFAULT_AUTOSTART_POOL_MAX, DEFAULT_AUTOSTART_POOL_SIZE, DEFAULT_SOCKET,
 };
+use crate::interrupt::{Interrupt, InterruptRouter};
 use crate::output::parse_memory;
 use crate::state::{generate_name, BoxRecord, StateFile};
 use a3s_box_runtime::pool::PoolClientRun;
@@ -70,10 +71,20 @@ pub struct RunArgs {
     #[arg(short = 't', long = "tty")]
     pub tty: bool,
 
+    /// Key sequence that detaches from `-it` and leaves the box running
+    /// (default ctrl-p,ctrl-q; empty disables)
+    #[arg(long, value_name = "KEYS")]
+    pub detach_keys: Option<String>,
+
     /// Stop the box if the foreground run exceeds this many seconds
     #[arg(long, value_name = "SECONDS")]
     pub timeout: Option<u64>,
 
+    /// After the foreground run, print wall and boot time, peak memory, CPU
+    /// time, workspace writes and network egress
+    #[arg(long, visible_alias = "time")]
+    pub stats_on_exit: bool,
+
     /// Automatically remove the box when it stops
     #[arg(long)]
     pub rm: bool,
@@ -105,7 +116,7 @@ pub struct RunArgs {
     #[arg(last = true)]
     pub cmd: Vec<String>,
 
-    /// Logging driver (json-file, none) [default: json-file]
+    /// Logging driver (json-file, syslog, journald, fluentd, none) [default: json-file]
     #[arg(long, default_value = "json-file")]
     pub log_driver: String,
 
@@ -126,6 +137,44 @@ pub struct RunArgs {
     #[arg(long)]
     pub tee_simulate: bool,
 
+    /// Attestation policy (YAML or JSON) that `attest` and `inject-secret`
+    /// check this box's reports against. Requires --tee or --tee-simulate.
+    #[arg(long, value_name = "PATH")]
+    pub attestation_policy: Option<std::path::PathBuf>,
+
+    /// Secret (NAME=VALUE) provisioned after the box passes attestation and
+    /// before its main process starts; can be repeated. Requires --tee or
+    /// --tee-simulate. Stored under /run/secrets and set in the environment.
+    #[arg(long = "secret", value_name = "NAME=VALUE")]
+    pub secrets: Vec<String>,
+
+    /// Read secrets to provision from a file (one NAME=VALUE per line)
+    #[arg(long, value_name = "PATH")]
+    pub secret_file: Option<String>,
+
+    /// Key Broker Service (confidential-containers KBS protocol) to fetch
+    /// --kbs-secret resources from; also passed to the guest as A3S_KBS_URL
+    #[arg(long, value_name = "URL")]
+    pub kbs_url: Option<String>,
+
+    /// Secret fetched from the KBS before the main process starts, as
+    /// NAME=REPOSITORY/TYPE/TAG; can be repeated. The guest answers the KBS
+    /// challenge and decrypts the resource itself.
+    #[arg(long = "kbs-secret", value_name = "NAME=RESOURCE")]
+    pub kbs_secrets: Vec<String>,
+
+    /// Hold the main process until secrets are provisioned later with
+    /// `inject-secret --start`, e.g. by a remote relying party
+    #[arg(long)]
+    pub await_secrets: bool,
+
+    /// Mount an encrypted scratch disk of SIZE (e.g. 10g) at /workspace
+    /// instead of the workspace share. Requires --tee or --tee-simulate. The
+    /// disk gets a fresh key each boot, released to the guest only after
+    /// attestation, so its contents do not survive a restart.
+    #[arg(long, value_name = "SIZE", value_parser = crate::output::parse_size_bytes)]
+    pub encrypted_workspace: Option<u64>,
+
     /// Sidecar OCI image to run alongside the main container inside the VM.
     /// Intended for security proxies such as SafeClaw.
     /// Example: --sidecar ghcr.io/a3s-lab/safeclaw:latest
@@ -135,6 +184,12 @@ pub struct RunArgs {
     /// Vsock port for the sidecar process (default: 4092)
     #[arg(long, default_value = "4092")]
     pub sidecar_vsock_port: u32,
+
+    /// JSON file listing extra services guest init supervises next to the
+    /// main process: name, command, restart policy, dependencies, and health
+    /// check for each
+    #[arg(long, value_name = "FILE")]
+    pub services: Option<String>,
 }
 
 /// Intermediate state produced by the setup phase, consumed by the run phase.
@@ -149,13 +204,25 @@ struct RunContext {
     exec_socket_path: PathBuf,
     #[cfg_attr(windows, allow(dead_code))]
     pty_socket_path: PathBuf,
+    /// Image ENTRYPOINT and CMD, which `run -it` resolves its command from.
+    #[cfg_attr(windows, allow(dead_code))]
+    image_entrypoint: Vec<String>,
+    #[cfg_attr(windows, allow(dead_code))]
+    image_cmd: Vec<String>,
     anonymous_volumes: Vec<String>,
     health_checker: Option<tokio::task::JoinHandle<()>>,
 }
 
-pub async fn execute(args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn execute(mut args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let started = std::time::Instant::now();
+    if let Some(project) = common::apply_project(&mut args.common, &mut args.cmd)? {
+        super::build::ensure_project_image(&project).await?;
+    }
     validate_run_mode(&args, std::io::stdin().is_terminal())
         .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+    if let Some(keys) = &args.detach_keys {
+        crate::detach_keys::parse(keys).map_err(|e| format!("--detach-keys: {e}"))?;
+    }
 
     let env_pool_socket = std::env::var(RUN_POOL_SOCKET_ENV).ok();
     if let Some(pool_socket) = selected_pool_socket(&args, env_pool_socket.as_deref()) {
@@ -169,7 +236,11 @@ pub async fn execute(args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
         return execute_pool_run(&args, &pool_socket).await;
     }
 
+    let boot_start = std::time::Instant::now();
     let mut ctx = setup_and_boot(&args).await?;
+    let usage = args
+        .stats_on_exit
+        .then(|| usage::UsageTracker::start(started, boot_start.elapsed(), &ctx.record));
     crate::audit::record(
         a3s_box_core::audit::AuditAction::BoxStart,
         a3s_box_core::audit::AuditOutcome::Success,
@@ -196,7 +267,7 @@ pub async fn execute(args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
         return run_tty(ctx, &args).await;
     }
 
-    run_foreground(ctx, &args).await
+    run_foreground(ctx, &args, usage).await
 }
 
 fn validate_run_mode(args: &RunArgs, stdin_is_terminal: bool) -> Result<(), &'static str> {
@@ -212,6 +283,12 @@ fn validate_run_mode(args: &RunArgs, stdin_is_terminal: bool) -> Result<(), &'st
     if args.timeout.is_some() && args.tty {
         return Err("Cannot use --timeout with -t (tty)");
     }
+    if args.stats_on_exit && args.detach {
+        return Err("Cannot use --stats-on-exit with -d (detach)");
+    }
+    if args.stats_on_exit && args.tty {
+        return Err("Cannot use --stats-on-exit with -t (tty)");
+    }
     if matches!(args.timeout, Some(0)) {
         return Err("--timeout must be greater than zero seconds");
     }
@@ -253,7 +330,16 @@ fn pool_run_mode_error(args: &RunArgs) -> Option<&'static str> {
         || args.tee
         || args.tee_simulate
         || args.tee_workload_id.is_some()
+        || args.attestation_policy.is_some()
+        || !args.secrets.is_empty()
+        || args.secret_file.is_some()
+        || args.await_secrets
+        || args.encrypted_workspace.is_some()
+        || args.kbs_url.is_some()
+        || !args.kbs_secrets.is_empty()
         || args.sidecar.is_some()
+        || args.services.is_some()
+        || args.stats_on_exit
     {
         return Some("--pool currently supports only image, --rm, command, --user, --workdir, --env, --env-file, --volume, --cpus, --memory, --timeout, and --package-cache");
     }
@@ -302,11 +388,19 @@ fn has_unsupported_pool_common_options(common: &CommonBoxArgs) -> bool {
     common.name.is_some()
         || !common.publish.is_empty()
         || !common.dns.is_empty()
+        || !common.dns_search.is_empty()
+        || !common.dns_option.is_empty()
+        || !common.network_alias.is_empty()
+        || !common.network_policy.is_empty()
+        || common.network_bandwidth.is_some()
+        || common.network_max_conns.is_some()
+        || common.http_proxy.is_some()
         || common.entrypoint.is_some()
         || common.hostname.is_some()
         || common.restart != "no"
         || !common.labels.is_empty()
         || !common.tmpfs.is_empty()
+        || !common.mounts.is_empty()
         || common.virtiofs_cache.is_some()
         || common.network.is_some()
         || common.health_cmd.is_some()
@@ -316,12 +410,14 @@ fn has_unsupported_pool_common_options(common: &CommonBoxArgs) -> bool {
         || common.health_start_period != 0
         || common.pids_limit.is_some()
         || common.cpuset_cpus.is_some()
+        || common.numa_node.is_some()
         || !common.ulimits.is_empty()
         || common.cpu_shares.is_some()
         || common.cpu_quota.is_some()
         || common.cpu_period.is_some()
         || common.memory_reservation.is_some()
         || common.memory_swap.is_some()
+        || !common.storage_opt.is_empty()
         || !common.add_host.is_empty()
         || common.platform.is_some()
         || common.init
@@ -338,6 +434,12 @@ fn has_unsupported_pool_common_options(common: &CommonBoxArgs) -> bool {
         || common.no_healthcheck
         || common.oom_kill_disable
         || common.oom_score_adj.is_some()
+        || common.ksm
+        || common.hugepages
+        || common.mem_prealloc
+        || !common.sysctl.is_empty()
+        || !common.kernel_module.is_empty()
+        || !common.group_add.is_empty()
         || common.persistent
 }
 
@@ -392,6 +494,7 @@ fn build_pool_client_run(
 }
 
 mod setup;
+mod usage;
 
 use setup::setup_and_boot;
 #[cfg(test)]
@@ -404,10 +507,34 @@ use setup::{
 // Phase 2a: Interactive PTY mode
 // ============================================================================
 
+/// The command `run -it` starts on the PTY, resolved like a main process:
+/// `--entrypoint` replaces the image ENTRYPOINT and drops its CMD, and
+/// command arguments replace the CMD. Falls back to `/bin/sh`.
+#[cfg_attr(windows, allow(dead_code))]
+fn tty_command(
+    entrypoint_override: Option<Vec<String>>,
+    cmd: &[String],
+    image_entrypoint: &[String],
+    image_cmd: &[String],
+) -> Vec<String> {
+    let overridden = entrypoint_override.is_some();
+    let mut command = entrypoint_override.unwrap_or_else(|| image_entrypoint.to_vec());
+    if !cmd.is_empty() {
+        command.extend_from_slice(cmd);
+    } else if !overridden {
+        command.extend_from_slice(image_cmd);
+    }
+    if command.is_empty() {
+        command.push("/bin/sh".to_string());
+    }
+    command
+}
+
 #[cfg(not(windows))]
 async fn run_tty(mut ctx: RunContext, args: &RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    use super::exec::PtySessionEnd;
     use crate::terminal;
-    use a3s_box_core::pty::PtyRequest;
+    use a3s_box_core::pty::{PtyRequest, MAIN_PTY_SESSION};
 
     let pty_socket_path = ctx.pty_socket_path.clone();
 
@@ -417,13 +544,17 @@ async fn run_tty(mut ctx: RunContext, args: &RunArgs) -> Result<(), Box<dyn std:
         .as_ref()
         .map(|ep| ep.split_whitespace().map(String::from).collect::<Vec<_>>());
 
-    let pty_cmd = if !args.cmd.is_empty() {
-        args.cmd.clone()
-    } else if let Some(ref ep) = entrypoint_override {
-        ep.clone()
-    } else {
-        vec!["/bin/sh".to_string()]
-    };
+    let pty_cmd = tty_command(
+        entrypoint_override,
+        &args.cmd,
+        &ctx.image_entrypoint,
+        &ctx.image_cmd,
+    );
+    let detach_keys = crate::detach_keys::parse(
+        args.detach_keys
+            .as_deref()
+            .unwrap_or(crate::detach_keys::DEFAULT_DETACH_KEYS),
+    )?;
 
     let (cols, rows) = terminal::size().unwrap_or((80, 24));
     let user = common::normalize_user_option(args.common.user.as_deref())
@@ -444,13 +575,26 @@ async fn run_tty(mut ctx: RunContext, args: &RunArgs) -> Result<(), Box<dyn std:
             user,
             cols,
             rows,
+            // Park the session on detach so `attach -it` can resume it.
+            session: Some(MAIN_PTY_SESSION.to_string()),
+            resume: false,
         })
         .await?;
 
     let (read_half, write_half) = client.into_split();
-    let exit_code = {
+    let end = {
         let _raw_mode = terminal::raw_mode()?;
-        super::exec::run_pty_session(read_half, write_half).await
+        super::exec::run_detachable_pty_session(read_half, write_half, detach_keys, true).await
+    };
+    let exit_code = match end {
+        PtySessionEnd::Exited(exit_code) => exit_code,
+        PtySessionEnd::Detached => {
+            println!(
+                "\nDetached from box {}; it keeps running. Reattach with `a3s-box attach -it {}`.",
+                ctx.name, ctx.name
+            );
+            return Ok(());
+        }
     };
 
     // Cleanup
@@ -478,6 +622,7 @@ async fn run_tty(_ctx: RunContext, _args: &RunArgs) -> Result<(), Box<dyn std::e
 enum ForegroundStopReason {
     ProcessExited,
     UserInterrupted(i32),
+    Detached,
     VmUnhealthy,
     TimedOut,
 }
@@ -495,6 +640,14 @@ const FOREGROUND_SIGINT: i32 = 2;
 const FOREGROUND_SIGTERM: i32 = libc::SIGTERM;
 #[cfg(not(unix))]
 const FOREGROUND_SIGTERM: i32 = 15;
+#[cfg(unix)]
+const FOREGROUND_SIGKILL: i32 = libc::SIGKILL;
+#[cfg(not(unix))]
+const FOREGROUND_SIGKILL: i32 = 9;
+
+/// How long a Ctrl-C may take to reach the workload before the box is
+/// stopped instead.
+const FOREGROUND_INTERRUPT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
 
 const FOREGROUND_LOG_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
 const FOREGROUND_EXIT_POLL: std::time::Duration = std::time::Duration::from_millis(20);
@@ -533,12 +686,14 @@ async fn recv_foreground_terminate(_signal: &mut ForegroundTerminateSignal) {
 async fn run_foreground(
     mut ctx: RunContext,
     args: &RunArgs,
+    mut usage: Option<usage::UsageTracker>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let foreground_start = std::time::Instant::now();
     println!(
-        "Box {} ({}) started. Press Ctrl-C to stop.",
+        "Box {} ({}) started. {}.",
         ctx.name,
-        BoxRecord::make_short_id(&ctx.box_id)
+        BoxRecord::make_short_id(&ctx.box_id),
+        Interrupt::help()
     );
 
     #[cfg(target_os = "windows")]
@@ -591,6 +746,7 @@ async fn run_foreground(
     });
 
     let name = ctx.name.clone();
+    let mut interrupts = InterruptRouter::new();
     let mut terminate_signal = foreground_terminate_signal();
     let timeout_at = args
         .timeout
@@ -608,10 +764,21 @@ async fn run_foreground(
     health_poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     let stop_reason = loop {
         tokio::select! {
-            _ = tokio::signal::ctrl_c() => {
-                println!("\nStopping box {}...", name);
-                break ForegroundStopReason::UserInterrupted(FOREGROUND_SIGINT);
-            }
+            action = interrupts.next() => match action {
+                Interrupt::Interrupt => {
+                    if interrupt_workload(&ctx).await {
+                        println!("\nSent SIGINT to box {}.", name);
+                    } else {
+                        println!("\nStopping box {}...", name);
+                        break ForegroundStopReason::UserInterrupted(FOREGROUND_SIGINT);
+                    }
+                }
+                Interrupt::Detach => break ForegroundStopReason::Detached,
+                Interrupt::Kill => {
+                    println!("\nKilling box {}...", name);
+                    break ForegroundStopReason::UserInterrupted(FOREGROUND_SIGKILL);
+                }
+            },
             _ = recv_foreground_terminate(&mut terminate_signal) => {
                 println!("\nStopping box {} after SIGTERM...", name);
                 break ForegroundStopReason::UserInterrupted(FOREGROUND_SIGTERM);
@@ -626,6 +793,9 @@ async fn run_foreground(
                 }
             }
             _ = health_poll.tick() => {
+                if let Some(usage) = usage.as_mut() {
+                    usage.sample(&ctx.record);
+                }
                 if !managed_runtime_healthy(&ctx).await {
                     break ForegroundStopReason::VmUnhealthy;
                 }
@@ -637,6 +807,22 @@ async fn run_foreground(
         foreground_start.elapsed(),
     );
 
+    if stop_reason == ForegroundStopReason::Detached {
+        // Leave the box running exactly as `run -d` would have.
+        tail_stop.store(true, Ordering::Release);
+        log_handle.abort();
+        if let Some(ref handle) = ctx.health_checker {
+            handle.abort();
+        }
+        crate::health::spawn_detached_health_checker(&ctx.record)
+            .map_err(|error| -> Box<dyn std::error::Error> { error.into() })?;
+        println!(
+            "\n{}",
+            foreground_completion_message(stop_reason, args.rm, &ctx.name)
+        );
+        return Ok(());
+    }
+
     let sandbox_natural_exit =
         stop_reason == ForegroundStopReason::ProcessExited && ctx.record.isolation.is_sandbox();
     if sandbox_natural_exit {
@@ -679,6 +865,8 @@ async fn run_foreground(
         );
     }
 
+    // Read the workspace before --rm removes the box.
+    let usage = usage.map(|usage| usage.finish());
     let persisted_exit_code = a3s_box_runtime::rootfs::read_persisted_exit_code(&ctx.box_dir);
     let exit_code = foreground_exit_code(stop_reason, persisted_exit_code);
     let archive_start = std::time::Instant::now();
@@ -699,6 +887,9 @@ async fn run_foreground(
         "{}",
         foreground_completion_message(stop_reason, args.rm, &ctx.name)
     );
+    if let Some(usage) = usage {
+        eprint!("{}", usage.render(&ctx.name));
+    }
 
     if let Some(code) = exit_code {
         if code != 0 {
@@ -799,11 +990,27 @@ fn foreground_exit_code(reason: ForegroundStopReason, vm_exit_code: Option<i32>)
         // make foreground `run --rm` fall through to CLI exit status 0.
         ForegroundStopReason::ProcessExited => vm_exit_code.or(Some(1)),
         ForegroundStopReason::UserInterrupted(signal) => vm_exit_code.or(Some(128 + signal)),
+        ForegroundStopReason::Detached => None,
         ForegroundStopReason::VmUnhealthy => vm_exit_code.or(Some(1)),
         ForegroundStopReason::TimedOut => Some(124),
     }
 }
 
+/// Deliver SIGINT to the workload's main process inside the guest. Returns
+/// `false` for sandbox boxes and when the guest exec server cannot be reached,
+/// in which case the caller stops the box instead.
+async fn interrupt_workload(ctx: &RunContext) -> bool {
+    if ctx.record.isolation.is_sandbox() {
+        return false;
+    }
+    tokio::time::timeout(
+        FOREGROUND_INTERRUPT_TIMEOUT,
+        crate::process::deliver_signal_via_guest(&ctx.exec_socket_path, FOREGROUND_SIGINT),
+    )
+    .await
+    .unwrap_or(false)
+}
+
 fn managed_process_alive(ctx: &RunContext) -> bool {
     ctx.record.pid.is_some_and(|pid| {
         a3s_box_runtime::is_process_alive_with_identity(pid, ctx.record.pid_start_time)
@@ -845,6 +1052,9 @@ fn foreground_completion_message(
         (ForegroundStopReason::ProcessExited, false) => format!("Box {name} exited."),
         (ForegroundStopReason::UserInterrupted(_), true) => format!("Box {name} removed."),
         (ForegroundStopReason::UserInterrupted(_), false) => format!("Box {name} stopped."),
+        (ForegroundStopReason::Detached, _) => {
+            format!("Detached from box {name}; it keeps running.")
+        }
         (ForegroundStopReason::VmUnhealthy, true) => {
             format!("Box {name} stopped after VM health check failed and was removed.")
         }
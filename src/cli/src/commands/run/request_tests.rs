@@ -28,6 +28,8 @@ fn test_managed_run_request_preserves_complete_caller_intent() {
     args.common.image = "registry.example/worker:v2".to_string();
     args.common.cpus = 6;
     args.common.dns = vec!["1.1.1.1".to_string(), "8.8.8.8".to_string()];
+    args.common.dns_search = vec!["corp.example".to_string()];
+    args.common.dns_option = vec!["ndots:2".to_string()];
     args.common.hostname = Some("worker".to_string());
     args.common.user = Some("root".to_string());
     args.common.workdir = Some("/workspace".to_string());
@@ -62,6 +64,7 @@ fn test_managed_run_request_preserves_complete_caller_intent() {
         workload_id: "worker-v2".to_string(),
         generation: Default::default(),
         simulate: true,
+        kbs_url: None,
     };
     let config = build_box_config(
         &args,
@@ -127,6 +130,8 @@ fn test_managed_run_request_preserves_complete_caller_intent() {
         vec![("MODE".to_string(), "test".to_string())]
     );
     assert_eq!(request.config.dns, vec!["1.1.1.1", "8.8.8.8"]);
+    assert_eq!(request.config.dns_search, vec!["corp.example"]);
+    assert_eq!(request.config.dns_options, vec!["ndots:2"]);
     assert_eq!(request.config.cap_add, vec!["NET_ADMIN"]);
     assert_eq!(request.config.cap_drop, vec!["NET_RAW"]);
     assert_eq!(request.config.security_opt, vec!["no-new-privileges"]);
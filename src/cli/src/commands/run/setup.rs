@@ -56,15 +56,14 @@ pub(super) async fn setup_and_boot(
         .map(|ep| ep.split_whitespace().map(String::from).collect::<Vec<_>>());
     let mut volume_specs = args.common.volumes.clone();
     apply_package_caches(&args.package_cache, &mut volume_specs, &mut env);
-    let (resolved_volumes, volume_names) = resolve_volumes(&volume_specs)?;
+    let mounts = super::super::volume::resolve_box_mounts(
+        &volume_specs,
+        &args.common.tmpfs,
+        &args.common.mounts,
+    )?;
 
     // Parse --shm-size once; reuse for both tmpfs entry and the box record.
-    let shm_size = match &args.common.shm_size {
-        Some(s) => {
-            Some(common::parse_memory_bytes(s).map_err(|e| format!("Invalid --shm-size: {e}"))?)
-        }
-        None => None,
-    };
+    let shm_size = common::parse_shm_size(args.common.shm_size.as_deref())?;
     let network_mode = match &args.common.network {
         Some(name) => a3s_box_core::NetworkMode::Bridge {
             network: name.clone(),
@@ -90,20 +89,34 @@ pub(super) async fn setup_and_boot(
     }
 
     let tee = build_tee_config(args);
+    #[cfg(unix)]
+    let attestation_policy = load_attestation_policy(args)?;
+    #[cfg(windows)]
+    load_attestation_policy(args)?;
+    #[cfg(unix)]
+    let launch_secrets = load_launch_secrets(args)?;
+    #[cfg(windows)]
+    load_launch_secrets(args)?;
 
-    let config = build_box_config(
+    let mut config = build_box_config(
         args,
         memory_mb,
         resource_limits.clone(),
         entrypoint_override.clone(),
-        resolved_volumes.clone(),
+        mounts.volumes,
         env.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
         port_map.clone(),
         network_mode.clone(),
-        args.common.tmpfs.clone(),
+        mounts.tmpfs,
         tee,
     )
     .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+    config.block_volumes = mounts.block_volumes;
+    config.socket_mounts = mounts.socket_mounts;
+    #[cfg(unix)]
+    {
+        config.await_secrets = args.await_secrets || !launch_secrets.is_empty();
+    }
     a3s_box_core::resolve_execution(&config)?;
 
     // Freeze image-defined lifecycle defaults into the managed creation
@@ -138,7 +151,7 @@ pub(super) async fn setup_and_boot(
             max_restart_count,
             health_check,
             log_config,
-            volume_names,
+            volume_names: mounts.volume_names,
             shm_size,
             stop_signal: effective_stop_signal,
         },
@@ -177,12 +190,29 @@ pub(super) async fn setup_and_boot(
         .cloned()
         .ok_or_else(|| format!("managed run {box_id} disappeared after startup"))?;
     let box_dir = record.box_dir.clone();
+    #[cfg(unix)]
+    if let Some(policy) = &attestation_policy {
+        if let Err(error) = policy.save_for_box(&box_dir) {
+            cleanup_failed_managed_run(&box_id);
+            return Err(error.into());
+        }
+    }
+    #[cfg(unix)]
+    if !launch_secrets.is_empty() || args.encrypted_workspace.is_some() {
+        if let Err(error) = provision_launch_secrets(&record, &launch_secrets, args).await {
+            cleanup_failed_managed_run(&box_id);
+            return Err(error);
+        }
+    }
     let exec_socket_path = record.exec_socket_path.clone();
     let pty_socket_path = exec_socket_path
         .parent()
         .map(|parent| parent.join("pty.sock"))
         .unwrap_or_else(|| box_dir.join("sockets/pty.sock"));
     let anonymous_volumes = record.anonymous_volumes.clone();
+    if let Some(network_name) = crate::boot::boot_network_name(&record) {
+        crate::commands::network::refresh_network_peers(network_name, &box_id).await;
+    }
 
     if should_create_diff_baseline(args) {
         if let Err(error) = crate::commands::diff::create_box_baseline_snapshot(&box_dir) {
@@ -209,6 +239,8 @@ pub(super) async fn setup_and_boot(
         record,
         exec_socket_path,
         pty_socket_path,
+        image_entrypoint: image_config.entrypoint.clone().unwrap_or_default(),
+        image_cmd: image_config.cmd.clone().unwrap_or_default(),
         anonymous_volumes,
         health_checker: None,
     };
@@ -333,12 +365,137 @@ fn build_tee_config(args: &RunArgs) -> TeeConfig {
                 .unwrap_or_else(|| args.common.image.clone()),
             generation: Default::default(),
             simulate: args.tee_simulate,
+            kbs_url: args.kbs_url.clone(),
         }
     } else {
         TeeConfig::None
     }
 }
 
+/// Load the `--attestation-policy` file, so a bad policy fails the run before
+/// anything is created.
+#[cfg(unix)]
+fn load_attestation_policy(
+    args: &RunArgs,
+) -> Result<Option<a3s_box_runtime::AttestationPolicy>, Box<dyn std::error::Error>> {
+    let Some(path) = &args.attestation_policy else {
+        return Ok(None);
+    };
+    if !args.tee && !args.tee_simulate {
+        return Err("--attestation-policy requires --tee or --tee-simulate".into());
+    }
+    Ok(Some(a3s_box_runtime::AttestationPolicy::load(path)?))
+}
+
+#[cfg(windows)]
+fn load_attestation_policy(args: &RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.attestation_policy.is_some() {
+        return Err(
+            "--attestation-policy requires TEE support, which is unavailable on Windows".into(),
+        );
+    }
+    Ok(())
+}
+
+/// Secrets provisioned into a TEE box before its main process starts.
+#[cfg(unix)]
+struct LaunchSecrets {
+    /// `--secret` and `--secret-file` values
+    entries: Vec<a3s_box_runtime::SecretEntry>,
+    /// `--kbs-secret` values: (secret name, KBS resource path)
+    kbs: Vec<(String, String)>,
+}
+
+#[cfg(unix)]
+impl LaunchSecrets {
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.kbs.is_empty()
+    }
+}
+
+/// Read the `--secret`, `--secret-file`, and `--kbs-secret` values, so a bad
+/// secret fails the run before anything is created.
+#[cfg(unix)]
+fn load_launch_secrets(args: &RunArgs) -> Result<LaunchSecrets, Box<dyn std::error::Error>> {
+    use crate::commands::inject_secret::{collect_secrets, parse_kbs_secret};
+
+    let secrets = LaunchSecrets {
+        entries: collect_secrets(&args.secrets, args.secret_file.as_deref(), true)?,
+        kbs: args
+            .kbs_secrets
+            .iter()
+            .map(|spec| parse_kbs_secret(spec))
+            .collect::<Result<_, _>>()?,
+    };
+    if (args.await_secrets
+        || args.encrypted_workspace.is_some()
+        || args.kbs_url.is_some()
+        || !secrets.is_empty())
+        && !args.tee
+        && !args.tee_simulate
+    {
+        return Err("--secret, --secret-file, --kbs-url, --kbs-secret, --await-secrets, and --encrypted-workspace require --tee or --tee-simulate".into());
+    }
+    if !secrets.kbs.is_empty() && args.kbs_url.is_none() {
+        return Err("--kbs-secret requires --kbs-url".into());
+    }
+    Ok(secrets)
+}
+
+#[cfg(windows)]
+fn load_launch_secrets(args: &RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if !args.secrets.is_empty()
+        || args.secret_file.is_some()
+        || args.await_secrets
+        || args.encrypted_workspace.is_some()
+        || args.kbs_url.is_some()
+        || !args.kbs_secrets.is_empty()
+    {
+        return Err(
+            "secret provisioning requires TEE support, which is unavailable on Windows".into(),
+        );
+    }
+    Ok(())
+}
+
+/// Attest the freshly started box, open its encrypted workspace, provision
+/// its secrets, and release the main process that has been waiting for them.
+#[cfg(unix)]
+async fn provision_launch_secrets(
+    record: &BoxRecord,
+    secrets: &LaunchSecrets,
+    args: &RunArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::commands::inject_secret::{
+        open_encrypted_workspace, provision_kbs_secrets, provision_secrets, start_main,
+    };
+
+    if args.encrypted_workspace.is_some() {
+        open_encrypted_workspace(record, args.tee_simulate)
+            .await
+            .map_err(|error| format!("encrypted workspace setup failed: {error}"))?;
+        println!("Opened encrypted workspace after attestation");
+    }
+    if secrets.is_empty() {
+        start_main(record).await?;
+        return Ok(());
+    }
+    let mut injected = 0;
+    if !secrets.entries.is_empty() {
+        injected += provision_secrets(record, &secrets.entries, args.tee_simulate)
+            .await
+            .map_err(|error| format!("secret provisioning failed: {error}"))?;
+    }
+    if let Some(kbs_url) = &args.kbs_url {
+        injected += provision_kbs_secrets(record, kbs_url, &secrets.kbs, true, args.tee_simulate)
+            .await
+            .map_err(|error| format!("KBS secret provisioning failed: {error}"))?;
+    }
+    start_main(record).await?;
+    println!("Provisioned {injected} secret(s) after attestation");
+    Ok(())
+}
+
 /// Build BoxConfig from parsed run arguments.
 #[allow(clippy::too_many_arguments)]
 pub(super) fn build_box_config(
@@ -374,6 +531,7 @@ pub(super) fn build_box_config(
         stdin_open: args.interactive && !args.no_stdin,
         entrypoint_override,
         user: common::normalize_user_option(args.common.user.as_deref())?,
+        group_add: args.common.group_add.clone(),
         workdir: args.common.workdir.clone(),
         hostname: args.common.hostname.clone(),
         volumes: resolved_volumes,
@@ -384,21 +542,43 @@ pub(super) fn build_box_config(
         extra_env,
         port_map,
         dns: args.common.dns.clone(),
+        dns_search: args.common.dns_search.clone(),
+        dns_options: args.common.dns_option.clone(),
+        network_aliases: args.common.network_alias.clone(),
+        network_policy: args.common.network_policy.clone(),
+        network_bandwidth: args.common.network_bandwidth,
+        network_max_conns: args.common.network_max_conns,
+        http_proxy: args.common.http_proxy,
         add_hosts: args.common.add_host.clone(),
         network,
         tmpfs,
         resource_limits,
         tee,
+        encrypted_workspace_bytes: args.encrypted_workspace,
         read_only: args.common.read_only,
         cap_add: args.common.cap_add.clone(),
         cap_drop: args.common.cap_drop.clone(),
         security_opt: args.common.security_opt.clone(),
         privileged: args.common.privileged,
+        devices: args.common.device.clone(),
+        ksm: args.common.ksm,
+        hugepages: args.common.hugepages,
+        mem_prealloc: args.common.mem_prealloc,
+        sysctls: common::sysctls(&args.common)?,
+        kernel_modules: args.common.kernel_module.clone(),
         sidecar: args.sidecar.as_ref().map(|image| SidecarConfig {
             image: image.clone(),
             vsock_port: args.sidecar_vsock_port,
             env: vec![],
         }),
+        services: args
+            .services
+            .as_deref()
+            .map(read_services_file)
+            .transpose()?
+            .unwrap_or_default(),
+        workspace_template: common::workspace_template(&args.common)?,
+        metadata: common::created_by_metadata(),
         // A box without `--rm` survives its stop like a Docker stopped
         // container: keep its dir (logs + overlay upper) so `logs`/`start` work
         // afterwards. `--rm` boxes and CRI pods stay non-persistent (removed on
@@ -408,6 +588,16 @@ pub(super) fn build_box_config(
     })
 }
 
+/// Read and validate a `--services` file.
+fn read_services_file(path: &str) -> Result<Vec<a3s_box_core::service::ServiceSpec>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read services file {path}: {error}"))?;
+    let services: Vec<a3s_box_core::service::ServiceSpec> = serde_json::from_str(&content)
+        .map_err(|error| format!("Invalid services file {path}: {error}"))?;
+    a3s_box_core::service::service_start_order(&services)?;
+    Ok(services)
+}
+
 pub(super) fn should_create_diff_baseline(args: &RunArgs) -> bool {
     !args.rm || args.detach
 }
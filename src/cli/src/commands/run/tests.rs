@@ -11,18 +11,27 @@ fn default_run_args() -> RunArgs {
             cpus: 2,
             memory: "512m".to_string(),
             volumes: vec![],
+            mounts: vec![],
             env: vec![],
             publish: vec![],
             dns: vec![],
+            dns_search: vec![],
+            dns_option: vec![],
             entrypoint: None,
             hostname: None,
             user: None,
+            group_add: vec![],
             workdir: None,
             restart: "no".to_string(),
             labels: vec![],
             tmpfs: vec![],
             virtiofs_cache: None,
             network: None,
+            network_alias: vec![],
+            network_policy: vec![],
+            network_bandwidth: None,
+            network_max_conns: None,
+            http_proxy: None,
             health_cmd: None,
             health_interval: 30,
             health_timeout: 5,
@@ -30,12 +39,14 @@ fn default_run_args() -> RunArgs {
             health_start_period: 0,
             pids_limit: None,
             cpuset_cpus: None,
+            numa_node: None,
             ulimits: vec![],
             cpu_shares: None,
             cpu_quota: None,
             cpu_period: None,
             memory_reservation: None,
             memory_swap: None,
+            storage_opt: vec![],
             env_file: vec![],
             add_host: vec![],
             platform: None,
@@ -53,13 +64,22 @@ fn default_run_args() -> RunArgs {
             no_healthcheck: false,
             oom_kill_disable: false,
             oom_score_adj: None,
+            ksm: false,
+            hugepages: false,
+            mem_prealloc: false,
+            sysctl: vec![],
+            kernel_module: vec![],
             persistent: false,
+            workspace_template: None,
+            workspace_var: vec![],
         },
         detach: false,
         interactive: false,
         no_stdin: false,
         tty: false,
+        detach_keys: None,
         timeout: None,
+        stats_on_exit: false,
         rm: false,
         pool: false,
         pool_socket: DEFAULT_SOCKET.to_string(),
@@ -72,8 +92,16 @@ fn default_run_args() -> RunArgs {
         tee: false,
         tee_workload_id: None,
         tee_simulate: false,
+        attestation_policy: None,
+        secrets: vec![],
+        secret_file: None,
+        kbs_url: None,
+        kbs_secrets: vec![],
+        await_secrets: false,
+        encrypted_workspace: None,
         sidecar: None,
         sidecar_vsock_port: 4092,
+        services: None,
     }
 }
 
@@ -85,6 +113,40 @@ fn default_pool_run_args() -> RunArgs {
     args
 }
 
+#[test]
+fn test_project_fills_run_args_under_command_line_flags() {
+    use clap::Parser;
+
+    let cli =
+        super::super::Cli::try_parse_from(["a3s-box", "run", "-e", "LOG_LEVEL=debug"]).unwrap();
+    let Some(super::super::Command::Run(mut args)) = cli.command else {
+        panic!("expected run command");
+    };
+    assert_eq!(args.common.image, "");
+
+    let project = crate::project::ProjectConfig::parse(
+        r#"
+name = "helper"
+image = "helper-agent:latest"
+cmd = ["sleep", "infinity"]
+ports = ["8080:8080"]
+
+[env]
+LOG_LEVEL = "info"
+MODE = "agent"
+"#,
+    )
+    .unwrap();
+    common::merge_project(&mut args.common, &mut args.cmd, &project);
+    assert_eq!(args.common.image, "helper-agent:latest");
+    assert_eq!(args.common.name.as_deref(), Some("helper"));
+    assert_eq!(args.cmd, ["sleep", "infinity"]);
+    assert_eq!(args.common.publish, ["8080:8080"]);
+    let env = common::parse_env_vars(&args.common.env).unwrap();
+    assert_eq!(env["LOG_LEVEL"], "debug");
+    assert_eq!(env["MODE"], "agent");
+}
+
 #[test]
 fn test_foreground_auto_remove_skips_diff_baseline() {
     let mut args = default_run_args();
@@ -236,6 +298,21 @@ fn test_validate_run_mode_rejects_invalid_timeout_modes() {
     assert!(err.contains("tty"));
 }
 
+#[test]
+fn test_validate_run_mode_stats_on_exit_is_foreground_only() {
+    let mut args = default_run_args();
+    args.stats_on_exit = true;
+    assert!(validate_run_mode(&args, false).is_ok());
+
+    args.detach = true;
+    let err = validate_run_mode(&args, false).unwrap_err();
+    assert!(err.contains("--stats-on-exit"));
+
+    let mut args = default_pool_run_args();
+    args.stats_on_exit = true;
+    assert!(pool_run_mode_error(&args).is_some());
+}
+
 #[test]
 fn test_validate_pool_run_mode_requires_auto_remove_and_command() {
     let mut args = default_pool_run_args();
@@ -563,6 +640,32 @@ fn test_apply_package_caches_deduplicates_npm_volume() {
     assert_eq!(volumes, vec![NPM_CACHE_VOLUME_SPEC.to_string()]);
 }
 
+#[test]
+fn test_tty_command_resolves_like_the_main_process() {
+    let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let image_entrypoint = strings(&["/docker-entrypoint.sh"]);
+    let image_cmd = strings(&["bash"]);
+
+    assert_eq!(
+        tty_command(None, &[], &image_entrypoint, &image_cmd),
+        strings(&["/docker-entrypoint.sh", "bash"])
+    );
+    assert_eq!(
+        tty_command(None, &strings(&["zsh"]), &image_entrypoint, &image_cmd),
+        strings(&["/docker-entrypoint.sh", "zsh"])
+    );
+    assert_eq!(
+        tty_command(
+            Some(strings(&["python3"])),
+            &[],
+            &image_entrypoint,
+            &image_cmd
+        ),
+        strings(&["python3"])
+    );
+    assert_eq!(tty_command(None, &[], &[], &[]), strings(&["/bin/sh"]));
+}
+
 #[test]
 fn test_build_box_config_uses_keepalive_for_interactive_tty_boot() {
     let mut args = default_run_args();
@@ -612,6 +715,45 @@ fn test_build_box_config_plumbs_virtiofs_cache_mode() {
     assert_eq!(config.virtiofs_cache.as_deref(), Some("always"));
 }
 
+#[test]
+fn test_build_box_config_reads_services_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("services.json");
+    std::fs::write(
+        &path,
+        r#"[{"name":"proxy","command":["envoy"]},
+            {"name":"app-agent","command":["agent"],"depends_on":["proxy"]}]"#,
+    )
+    .unwrap();
+    let mut args = default_run_args();
+    args.services = Some(path.display().to_string());
+    let build = |args: &RunArgs| {
+        build_box_config(
+            args,
+            512,
+            Default::default(),
+            None,
+            vec![],
+            vec![],
+            vec![],
+            a3s_box_core::NetworkMode::Tsi,
+            vec![],
+            TeeConfig::None,
+        )
+    };
+
+    let config = build(&args).unwrap();
+    assert_eq!(config.services.len(), 2);
+    assert_eq!(config.services[1].depends_on, vec!["proxy"]);
+
+    std::fs::write(
+        &path,
+        r#"[{"name":"a","command":["x"],"depends_on":["missing"]}]"#,
+    )
+    .unwrap();
+    assert!(build(&args).unwrap_err().contains("unknown service"));
+}
+
 #[test]
 fn test_build_box_config_preserves_non_tty_command() {
     let mut args = default_run_args();
@@ -741,6 +883,8 @@ async fn test_cleanup_failure_is_reported_and_preserves_recovery_state() {
         record,
         exec_socket_path: temporary.path().join("exec.sock"),
         pty_socket_path: temporary.path().join("pty.sock"),
+        image_entrypoint: Vec::new(),
+        image_cmd: Vec::new(),
         anonymous_volumes: Vec::new(),
         health_checker: None,
     };
@@ -807,6 +951,10 @@ fn test_foreground_exit_code_has_deterministic_fallbacks() {
         foreground_exit_code(ForegroundStopReason::TimedOut, None),
         Some(124)
     );
+    assert_eq!(
+        foreground_exit_code(ForegroundStopReason::Detached, None),
+        None
+    );
 }
 
 #[test]
@@ -815,6 +963,7 @@ fn test_foreground_stop_reason_user_flag() {
     assert!(!ForegroundStopReason::ProcessExited.stopped_by_user());
     assert!(!ForegroundStopReason::VmUnhealthy.stopped_by_user());
     assert!(!ForegroundStopReason::TimedOut.stopped_by_user());
+    assert!(!ForegroundStopReason::Detached.stopped_by_user());
 }
 
 #[test]
@@ -885,6 +1034,10 @@ fn test_foreground_completion_messages() {
         foreground_completion_message(ForegroundStopReason::TimedOut, false, "box"),
         "Box box stopped after --timeout expired."
     );
+    assert_eq!(
+        foreground_completion_message(ForegroundStopReason::Detached, true, "box"),
+        "Detached from box box; it keeps running."
+    );
 }
 
 #[test]
@@ -915,6 +1068,38 @@ fn test_build_box_config_passes_security_options() {
     assert!(config.privileged);
 }
 
+#[test]
+fn test_build_box_config_passes_kernel_tuning() {
+    let mut args = default_run_args();
+    args.common.sysctl = vec!["net.core.somaxconn=4096".to_string()];
+    args.common.kernel_module = vec!["br_netfilter".to_string()];
+
+    let config = build_box_config(
+        &args,
+        512,
+        a3s_box_core::config::ResourceLimits::default(),
+        None,
+        vec![],
+        vec![],
+        vec![],
+        a3s_box_core::NetworkMode::Tsi,
+        vec![],
+        TeeConfig::None,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.sysctls,
+        vec![("net.core.somaxconn".to_string(), "4096".to_string())]
+    );
+    assert_eq!(config.kernel_modules, vec!["br_netfilter"]);
+
+    args.common.sysctl = vec!["net.core.somaxconn".to_string()];
+    assert!(common::validate_runtime_options(&args.common)
+        .unwrap_err()
+        .contains("--sysctl"));
+}
+
 #[test]
 fn test_build_box_config_passes_user_and_workdir() {
     let mut args = default_run_args();
@@ -0,0 +1,207 @@
+//! `run --stats-on-exit`: what one foreground run used.
+//!
+//! Peak memory and CPU time belong to the VMM process and can only be read
+//! while it is alive, so the foreground loop samples it on every health
+//! poll. The rest is read once the workload has exited and before `--rm`
+//! removes the box: growth of the box's workspace and bytes the box sent
+//! through its network relay.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::output::format_bytes;
+use crate::state::BoxRecord;
+
+/// Samples a running box for [`RunUsage`].
+pub(super) struct UsageTracker {
+    started: Instant,
+    boot: Duration,
+    workspace: PathBuf,
+    workspace_before: u64,
+    peak_memory_bytes: Option<u64>,
+    cpu_seconds: Option<f64>,
+    network_tx_bytes: Option<u64>,
+}
+
+/// The summary printed after the run.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct RunUsage {
+    pub(super) wall: Duration,
+    pub(super) boot: Duration,
+    pub(super) peak_memory_bytes: Option<u64>,
+    pub(super) cpu_seconds: Option<f64>,
+    pub(super) workspace_written_bytes: u64,
+    pub(super) network_tx_bytes: Option<u64>,
+}
+
+impl UsageTracker {
+    /// Start tracking a box that `started` a command and took `boot` to
+    /// create and start.
+    pub(super) fn start(started: Instant, boot: Duration, record: &BoxRecord) -> Self {
+        let workspace = workspace_dir(record);
+        let mut tracker = Self {
+            started,
+            boot,
+            workspace_before: super::super::df::dir_size(&workspace),
+            workspace,
+            peak_memory_bytes: None,
+            cpu_seconds: None,
+            network_tx_bytes: None,
+        };
+        tracker.sample(record);
+        tracker
+    }
+
+    /// Record the VMM process's peak memory, CPU time and egress so far.
+    pub(super) fn sample(&mut self, record: &BoxRecord) {
+        if let Some(pid) = record.pid {
+            if let Some((memory, cpu)) = process_usage(pid) {
+                self.peak_memory_bytes = self.peak_memory_bytes.max(Some(memory));
+                self.cpu_seconds = cpu.or(self.cpu_seconds);
+            }
+        }
+        let network = super::super::stats::collect_network_stats(record);
+        self.network_tx_bytes = self.network_tx_bytes.max(Some(network.tx_bytes));
+    }
+
+    /// Read what is left on disk and stop the clock.
+    pub(super) fn finish(self) -> RunUsage {
+        let workspace_after = super::super::df::dir_size(&self.workspace);
+        RunUsage {
+            wall: self.started.elapsed(),
+            boot: self.boot,
+            peak_memory_bytes: self.peak_memory_bytes,
+            cpu_seconds: self.cpu_seconds,
+            workspace_written_bytes: workspace_after.saturating_sub(self.workspace_before),
+            network_tx_bytes: self.network_tx_bytes,
+        }
+    }
+}
+
+impl RunUsage {
+    pub(super) fn render(&self, name: &str) -> String {
+        let unknown = || "n/a".to_string();
+        let rows = [
+            ("Wall time", format!("{:.2}s", self.wall.as_secs_f64())),
+            ("Boot time", format!("{:.2}s", self.boot.as_secs_f64())),
+            (
+                "Peak memory",
+                self.peak_memory_bytes.map_or_else(unknown, format_bytes),
+            ),
+            (
+                "CPU time",
+                self.cpu_seconds
+                    .map_or_else(unknown, |secs| format!("{secs:.2}s")),
+            ),
+            (
+                "Workspace written",
+                format_bytes(self.workspace_written_bytes),
+            ),
+            (
+                "Network egress",
+                self.network_tx_bytes.map_or_else(unknown, format_bytes),
+            ),
+        ];
+        let mut out = format!("Resource usage for box {name}:\n");
+        for (label, value) in rows {
+            out.push_str(&format!("  {:<19}{value}\n", format!("{label}:")));
+        }
+        out
+    }
+}
+
+/// The host directory shared as the box's `/workspace`. With a disk quota it
+/// lives on the quota volume mounted at `<box_dir>/rootfs`.
+fn workspace_dir(record: &BoxRecord) -> PathBuf {
+    if record.resource_limits.disk_limit_bytes.is_some() {
+        record.box_dir.join("rootfs").join("workspace")
+    } else {
+        record.box_dir.join("workspace")
+    }
+}
+
+/// Peak resident memory and, where the platform reports it, CPU seconds.
+#[cfg(target_os = "linux")]
+fn process_usage(pid: u32) -> Option<(u64, Option<f64>)> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // SAFETY: sysconf has no memory-safety preconditions.
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    let cpu = (ticks_per_sec > 0)
+        .then(|| cpu_ticks(&stat))
+        .flatten()
+        .map(|ticks| ticks as f64 / ticks_per_sec as f64);
+    Some((peak_rss_bytes(&status)?, cpu))
+}
+
+/// Without a high-water mark, the largest sampled resident size stands in.
+#[cfg(not(target_os = "linux"))]
+fn process_usage(pid: u32) -> Option<(u64, Option<f64>)> {
+    let pid = sysinfo::Pid::from_u32(pid);
+    let mut sys = sysinfo::System::new();
+    sys.refresh_process(pid);
+    sys.process(pid).map(|process| (process.memory(), None))
+}
+
+/// `VmHWM` from `/proc/<pid>/status`, in bytes.
+#[cfg(any(target_os = "linux", test))]
+fn peak_rss_bytes(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line["VmHWM:".len()..]
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// User plus system time from `/proc/<pid>/stat`, in clock ticks. The
+/// command name may contain spaces, so fields are counted after its `)`.
+#[cfg(any(target_os = "linux", test))]
+fn cpu_ticks(stat: &str) -> Option<u64> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    let mut fields = rest.split_whitespace().skip(11);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(utime + stime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_usage() {
+        let status = "Name:\ta3s-box-shim\nVmPeak:\t  900000 kB\nVmHWM:\t  204800 kB\n";
+        assert_eq!(peak_rss_bytes(status), Some(204800 * 1024));
+        assert_eq!(peak_rss_bytes("Name:\tx\n"), None);
+
+        let stat = "4242 (a3s box (shim)) S 1 4242 4242 0 -1 4194560 \
+                    100 0 0 0 250 70 0 0 20 0 3 0 12345";
+        assert_eq!(cpu_ticks(stat), Some(320));
+        assert_eq!(cpu_ticks("4242 (x) S 1"), None);
+    }
+
+    #[test]
+    fn test_render_usage() {
+        let usage = RunUsage {
+            wall: Duration::from_millis(12_340),
+            boot: Duration::from_millis(810),
+            peak_memory_bytes: Some(200 * 1024 * 1024),
+            cpu_seconds: Some(3.2),
+            workspace_written_bytes: 2048,
+            network_tx_bytes: None,
+        };
+        assert_eq!(
+            usage.render("bench"),
+            "Resource usage for box bench:\n\
+             \x20 Wall time:         12.34s\n\
+             \x20 Boot time:         0.81s\n\
+             \x20 Peak memory:       200.0 MB\n\
+             \x20 CPU time:          3.20s\n\
+             \x20 Workspace written: 2.0 KB\n\
+             \x20 Network egress:    n/a\n"
+        );
+    }
+}
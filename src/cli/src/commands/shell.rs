@@ -69,6 +69,8 @@ pub async fn execute(args: ShellArgs) -> Result<(), Box<dyn std::error::Error>>
             user,
             cols,
             rows,
+            session: None,
+            resume: false,
         })
         .await?;
 
@@ -324,6 +324,15 @@ async fn execute_restore(args: SnapshotRestoreArgs) -> Result<(), Box<dyn std::e
         resource_limits: a3s_box_core::config::ResourceLimits::default(),
         log_config: a3s_box_core::log::LogConfig::default(),
         add_host: vec![],
+        dns: vec![],
+        dns_search: vec![],
+        dns_option: vec![],
+        network_aliases: vec![],
+        network_policy: vec![],
+        network_bandwidth: None,
+        network_max_conns: None,
+        http_proxy: None,
+        socket_mounts: vec![],
         platform: None,
         init: false,
         read_only: false,
@@ -338,6 +347,8 @@ async fn execute_restore(args: SnapshotRestoreArgs) -> Result<(), Box<dyn std::e
         stop_timeout: None,
         oom_kill_disable: false,
         oom_score_adj: None,
+        workspace_template: None,
+        metadata: super::common::created_by_metadata(),
     };
 
     // Atomic append under the state lock so a concurrent writer (run/monitor/
@@ -356,6 +367,9 @@ async fn execute_ls(args: SnapshotLsArgs) -> Result<(), Box<dyn std::error::Erro
     let store = SnapshotStore::default_path()?;
     let snapshots = store.list()?;
 
+    if crate::output::structured().is_some() {
+        return crate::output::emit("SnapshotList", &snapshots);
+    }
     if args.json {
         println!("{}", serde_json::to_string_pretty(&snapshots)?);
         return Ok(());
@@ -462,6 +476,9 @@ async fn execute_inspect(args: SnapshotInspectArgs) -> Result<(), Box<dyn std::e
         .get(&args.id)?
         .ok_or_else(|| format!("Snapshot '{}' not found", args.id))?;
 
+    if crate::output::structured().is_some() {
+        return crate::output::emit("SnapshotInspect", &meta);
+    }
     println!("{}", serde_json::to_string_pretty(&meta)?);
     Ok(())
 }
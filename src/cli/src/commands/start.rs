@@ -13,14 +13,21 @@ pub struct StartArgs {
     /// Box name(s) or ID(s)
     #[arg(required = true)]
     pub boxes: Vec<String>,
+    /// Restore the box's filesystem from this checkpoint before starting
+    /// (see `a3s-box checkpoint`)
+    #[arg(long, value_name = "NAME")]
+    pub from_checkpoint: Option<String>,
 }
 
 pub async fn execute(args: StartArgs) -> Result<(), Box<dyn std::error::Error>> {
     let state = StateFile::load_default()?;
     let mut errors: Vec<String> = Vec::new();
+    if args.from_checkpoint.is_some() && args.boxes.len() > 1 {
+        return Err("--from-checkpoint starts a single box".into());
+    }
 
     for query in &args.boxes {
-        if let Err(e) = start_one(&state, query).await {
+        if let Err(e) = start_one(&state, query, args.from_checkpoint.as_deref()).await {
             errors.push(format!("{query}: {e}"));
         }
     }
@@ -32,7 +39,11 @@ pub async fn execute(args: StartArgs) -> Result<(), Box<dyn std::error::Error>>
     }
 }
 
-async fn start_one(state: &StateFile, query: &str) -> Result<(), Box<dyn std::error::Error>> {
+async fn start_one(
+    state: &StateFile,
+    query: &str,
+    from_checkpoint: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
     let box_id = resolve::resolve(state, query)?.id.clone();
     let mut lifecycle_lock = Some(crate::lifecycle::acquire_box_lifecycle_lock(&box_id).await?);
     // The caller's state snapshot may have waited behind commit/restart. Reload
@@ -49,6 +60,9 @@ async fn start_one(state: &StateFile, query: &str) -> Result<(), Box<dyn std::er
         .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
     let plan =
         start_plan(&record).map_err(|error| -> Box<dyn std::error::Error> { error.into() })?;
+    if let Some(checkpoint) = from_checkpoint {
+        super::checkpoint::restore_for_start(&record, checkpoint).await?;
+    }
 
     let name = record.name.clone();
 
@@ -178,7 +192,7 @@ fn start_plan(record: &crate::state::BoxRecord) -> Result<StartPlan, String> {
 
 fn validate_start_status(name: &str, status: &str) -> Result<(), String> {
     match status {
-        "created" | "stopped" | "dead" => Ok(()),
+        "created" | "stopped" | "dead" | "waiting" => Ok(()),
         "running" => Err(format!("Box {name} is already running")),
         other => Err(format!("Cannot start box in state: {other}")),
     }
@@ -225,6 +239,7 @@ mod tests {
         assert!(validate_start_status("web", "created").is_ok());
         assert!(validate_start_status("web", "stopped").is_ok());
         assert!(validate_start_status("web", "dead").is_ok());
+        assert!(validate_start_status("web", "waiting").is_ok());
     }
 
     #[test]
@@ -2,15 +2,27 @@
 //!
 //! Shows CPU, memory, network, and block I/O usage for active boxes, similar to `docker stats`.
 //! By default streams updates every second; use `--no-stream` for a single snapshot.
+//! Boxes started with `--ksm` also report guest memory currently shared through
+//! host Kernel Samepage Merging; boxes started with `--hugepages` report guest
+//! memory backed by transparent hugepages in `--format json`.
+//! While streaming, network throughput is the change in traffic counters
+//! between refreshes, shown next to any `--network-bandwidth` limit.
+//!
+//! CPU, memory, block I/O and process counts come from guest-init, which
+//! samples the guest's own `/proc` and also names the busiest processes.
+//! Guests that cannot answer (older images, TEE boxes without the exec
+//! server) fall back to the host's view of the VMM process.
 
 use clap::{Args, ValueEnum};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use sysinfo::{Pid, System};
 
 #[cfg(not(windows))]
-use a3s_box_core::exec::{ExecRequest, DEFAULT_EXEC_TIMEOUT_NS};
+use a3s_box_core::exec::{ExecRequest, GuestMetricsRequest, DEFAULT_EXEC_TIMEOUT_NS};
+use a3s_box_core::exec::{GuestCgroupMetrics, GuestMetrics, GuestProcessMetrics};
 #[cfg(not(windows))]
 use a3s_box_runtime::ExecClient;
 
@@ -40,6 +52,14 @@ enum StatsFormat {
 }
 
 const PIDS_CURRENT_TIMEOUT: Duration = Duration::from_millis(750);
+/// Window over which guest-init measures CPU usage.
+#[cfg(not(windows))]
+const GUEST_METRICS_SAMPLE_MS: u32 = 200;
+#[cfg(not(windows))]
+const GUEST_METRICS_TIMEOUT: Duration = Duration::from_millis(1500);
+/// Busiest guest processes listed per box.
+#[cfg(not(windows))]
+const GUEST_TOP_PROCESSES: u32 = 5;
 
 /// Collected stats for a single box.
 #[derive(Serialize)]
@@ -55,12 +75,42 @@ struct BoxStats {
     memory_limit_bytes: u64,
     network_rx_bytes: u64,
     network_tx_bytes: u64,
+    /// Throughput since the previous refresh, in bytes per second.
+    network_rate: Option<NetworkStats>,
+    network_bandwidth_limit: Option<u64>,
     block_read_bytes: u64,
     block_write_bytes: u64,
     pids_current: Option<u64>,
+    ksm: Option<a3s_box_runtime::ksm::KsmProcessStats>,
+    disk: Option<a3s_box_runtime::rootfs::quota::DiskQuotaUsage>,
+    anon_huge_bytes: Option<u64>,
+    /// Busiest guest processes; empty without guest metrics.
+    top_processes: Vec<GuestProcessMetrics>,
+    /// Per-cgroup usage inside the guest; empty without guest metrics.
+    guest_cgroups: Vec<GuestCgroupMetrics>,
+    /// Whether CPU, memory and I/O came from inside the guest.
+    guest_metrics: bool,
 }
 
 impl BoxStats {
+    /// Replace the VMM process figures with the guest's own. Guest network
+    /// counters are used only when the guest has a network interface that
+    /// saw traffic; with TSI networking the host relay is the only record.
+    fn apply_guest_metrics(&mut self, guest: GuestMetrics) {
+        self.cpu_percent = guest.cpu_percent as f32;
+        self.memory_bytes = guest.memory_used_bytes;
+        self.block_read_bytes = guest.disk_read_bytes;
+        self.block_write_bytes = guest.disk_write_bytes;
+        if guest.network_rx_bytes > 0 || guest.network_tx_bytes > 0 {
+            self.network_rx_bytes = guest.network_rx_bytes;
+            self.network_tx_bytes = guest.network_tx_bytes;
+        }
+        self.pids_current = Some(guest.processes);
+        self.top_processes = guest.top;
+        self.guest_cgroups = guest.cgroups;
+        self.guest_metrics = true;
+    }
+
     fn mem_percent(&self) -> f64 {
         if self.memory_limit_bytes > 0 {
             (self.memory_bytes as f64 / self.memory_limit_bytes as f64) * 100.0
@@ -81,10 +131,10 @@ struct ResourceStats {
     block_write_bytes: u64,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-struct NetworkStats {
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(super) struct NetworkStats {
     rx_bytes: u64,
-    tx_bytes: u64,
+    pub(super) tx_bytes: u64,
 }
 
 /// Collect stats for a process by PID.
@@ -120,8 +170,11 @@ fn print_stats(stats: &[BoxStats]) {
         "CPU %",
         "MEM USAGE / LIMIT",
         "MEM %",
+        "KSM SHARED",
+        "DISK USAGE / QUOTA",
         "PID",
         "NET I/O",
+        "NET RATE",
         "IO",
     ]);
 
@@ -137,17 +190,50 @@ fn print_stats(stats: &[BoxStats]) {
                 output::format_bytes(s.memory_limit_bytes)
             ),
             &format!("{:.1}%", s.mem_percent()),
+            &s.ksm
+                .map(|ksm| output::format_bytes(ksm.shared_bytes()))
+                .unwrap_or_else(|| "--".to_string()),
+            &s.disk
+                .map(|disk| {
+                    format!(
+                        "{} / {}",
+                        output::format_bytes(disk.used_bytes),
+                        output::format_bytes(disk.limit_bytes)
+                    )
+                })
+                .unwrap_or_else(|| "--".to_string()),
             &s.pid.to_string(),
             &format_io_usage(s.network_rx_bytes, s.network_tx_bytes),
+            &format_network_rate(s.network_rate, s.network_bandwidth_limit),
             &format_io_usage(s.block_read_bytes, s.block_write_bytes),
         ]);
     }
 
     println!("{table}");
+
+    if stats.iter().any(|s| !s.top_processes.is_empty()) {
+        let mut top = output::new_table(&["BOX ID", "PID", "CPU %", "MEM", "COMMAND"]);
+        for s in stats {
+            for process in &s.top_processes {
+                top.add_row([
+                    &s.short_id,
+                    &process.pid.to_string(),
+                    &format!("{:.2}%", process.cpu_percent),
+                    &output::format_bytes(process.memory_bytes),
+                    &process.command,
+                ]);
+            }
+        }
+        println!();
+        println!("{top}");
+    }
 }
 
-fn print_stats_json(stats: &[BoxStats]) -> Result<(), serde_json::Error> {
+fn print_stats_json(stats: &[BoxStats]) -> Result<(), Box<dyn std::error::Error>> {
     let rows = stats.iter().map(stats_json).collect::<Vec<_>>();
+    if output::structured().is_some() {
+        return output::emit("BoxStats", rows);
+    }
     println!("{}", serde_json::to_string(&rows)?);
     Ok(())
 }
@@ -168,12 +254,26 @@ fn stats_json(stats: &BoxStats) -> serde_json::Value {
         "memory_percent": stats.mem_percent(),
         "network_rx_bytes": stats.network_rx_bytes,
         "network_tx_bytes": stats.network_tx_bytes,
+        "network_rx_bytes_per_sec": stats.network_rate.map(|rate| rate.rx_bytes),
+        "network_tx_bytes_per_sec": stats.network_rate.map(|rate| rate.tx_bytes),
+        "network_bandwidth_limit_bytes_per_sec": stats.network_bandwidth_limit,
         "block_read_bytes": stats.block_read_bytes,
         "block_write_bytes": stats.block_write_bytes,
         "pids_current": stats.pids_current,
         "pids": {
             "current": stats.pids_current,
         },
+        "ksm": stats.ksm.map(|ksm| serde_json::json!({
+            "merging_pages": ksm.merging_pages,
+            "shared_bytes": ksm.shared_bytes(),
+            "profit_bytes": ksm.profit_bytes,
+        })),
+        "disk_bytes": stats.disk.map(|disk| disk.used_bytes),
+        "disk_limit_bytes": stats.disk.map(|disk| disk.limit_bytes),
+        "anon_huge_bytes": stats.anon_huge_bytes,
+        "metrics_source": if stats.guest_metrics { "guest" } else { "host" },
+        "top_processes": stats.top_processes,
+        "guest_cgroups": stats.guest_cgroups,
     })
 }
 
@@ -185,6 +285,40 @@ fn format_io_usage(read_bytes: u64, write_bytes: u64) -> String {
     )
 }
 
+/// Throughput as `RX / TX` per second, with the egress limit if one is set.
+fn format_network_rate(rate: Option<NetworkStats>, limit: Option<u64>) -> String {
+    let Some(rate) = rate else {
+        return "--".to_string();
+    };
+    let rate = format!(
+        "{}/s / {}/s",
+        output::format_bytes(rate.rx_bytes),
+        output::format_bytes(rate.tx_bytes)
+    );
+    match limit {
+        Some(limit) => format!("{rate} (max {}/s)", output::format_bytes(limit)),
+        None => rate,
+    }
+}
+
+/// Per-second traffic between two counter samples; counters that went
+/// backwards (the box restarted) count as zero.
+fn network_rate(
+    previous: NetworkStats,
+    current: NetworkStats,
+    elapsed: Duration,
+) -> Option<NetworkStats> {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return None;
+    }
+    let per_sec = |before: u64, after: u64| (after.saturating_sub(before) as f64 / secs) as u64;
+    Some(NetworkStats {
+        rx_bytes: per_sec(previous.rx_bytes, current.rx_bytes),
+        tx_bytes: per_sec(previous.tx_bytes, current.tx_bytes),
+    })
+}
+
 fn select_targets(
     state: &StateFile,
     query: Option<&str>,
@@ -220,12 +354,67 @@ fn build_box_stats(sys: &mut System, record: &BoxRecord) -> Option<BoxStats> {
         memory_limit_bytes,
         network_rx_bytes: network.rx_bytes,
         network_tx_bytes: network.tx_bytes,
+        network_rate: None,
+        network_bandwidth_limit: record.network_bandwidth,
         block_read_bytes: stats.block_read_bytes,
         block_write_bytes: stats.block_write_bytes,
         pids_current: None,
+        ksm: ksm_enabled(record)
+            .then(|| a3s_box_runtime::ksm::process_stats(pid))
+            .flatten(),
+        disk: record
+            .resource_limits
+            .disk_limit_bytes
+            .and_then(|limit| a3s_box_runtime::rootfs::quota::disk_usage(&record.box_dir, limit)),
+        anon_huge_bytes: hugepages_enabled(record)
+            .then(|| a3s_box_runtime::hugepages::process_anon_huge_bytes(pid))
+            .flatten(),
+        top_processes: Vec::new(),
+        guest_cgroups: Vec::new(),
+        guest_metrics: false,
     })
 }
 
+/// Whether the box was created with hugepage-backed guest memory.
+fn hugepages_enabled(record: &BoxRecord) -> bool {
+    record
+        .managed_execution
+        .as_ref()
+        .is_some_and(|managed| managed.request.config.hugepages)
+}
+
+/// Whether the box was created with KSM-mergeable guest memory.
+fn ksm_enabled(record: &BoxRecord) -> bool {
+    record
+        .managed_execution
+        .as_ref()
+        .is_some_and(|managed| managed.request.config.ksm)
+}
+
+async fn collect_guest_metrics(record: &BoxRecord) -> Option<GuestMetrics> {
+    #[cfg(not(windows))]
+    {
+        let exec_socket_path =
+            crate::socket_paths::runtime_socket(record, crate::socket_paths::RuntimeSocket::Exec);
+        let request = GuestMetricsRequest {
+            sample_ms: GUEST_METRICS_SAMPLE_MS,
+            top: GUEST_TOP_PROCESSES,
+        };
+        tokio::time::timeout(GUEST_METRICS_TIMEOUT, async {
+            let client = ExecClient::connect(&exec_socket_path).await.ok()?;
+            client.metrics(&request).await.ok()
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+    #[cfg(windows)]
+    {
+        let _ = record;
+        None
+    }
+}
+
 async fn collect_pids_current(record: &BoxRecord) -> Option<u64> {
     #[cfg(not(windows))]
     {
@@ -296,7 +485,7 @@ fn is_process_count_probe(command: &str) -> bool {
         || command.starts_with("/usr/bin/ps -eo pid,args")
 }
 
-fn collect_network_stats(record: &BoxRecord) -> NetworkStats {
+pub(super) fn collect_network_stats(record: &BoxRecord) -> NetworkStats {
     read_network_stats_file(&record.box_dir.join("sockets").join("net.stats.json"))
         .or_else(|| collect_passt_pcap_stats(record))
         .unwrap_or_default()
@@ -312,12 +501,18 @@ fn read_network_stats_file(path: &Path) -> Option<NetworkStats> {
 }
 
 fn collect_passt_pcap_stats(record: &BoxRecord) -> Option<NetworkStats> {
-    let pcap_path = record.exec_socket_path.parent()?.join("passt.pcap");
+    let pcap_path = passt_pcap_path(record)?;
     let guest_mac = guest_mac_address(record)?;
     read_passt_pcap_stats(&pcap_path, guest_mac)
 }
 
-fn guest_mac_address(record: &BoxRecord) -> Option<[u8; 6]> {
+/// Packet capture written by the box's passt relay (bridge networking).
+pub(super) fn passt_pcap_path(record: &BoxRecord) -> Option<std::path::PathBuf> {
+    Some(record.exec_socket_path.parent()?.join("passt.pcap"))
+}
+
+/// MAC address of the box's endpoint on its bridge network.
+pub(super) fn guest_mac_address(record: &BoxRecord) -> Option<[u8; 6]> {
     let network_name = crate::cleanup::record_network_name(record)?;
     let store = a3s_box_runtime::NetworkStore::default_path().ok()?;
     let network = store.get(network_name).ok()??;
@@ -340,68 +535,30 @@ fn read_passt_pcap_stats(path: &Path, guest_mac: [u8; 6]) -> Option<NetworkStats
 }
 
 fn parse_passt_pcap_stats(data: &[u8], guest_mac: [u8; 6]) -> Option<NetworkStats> {
-    let endian = PcapEndian::from_magic(data.get(..4)?)?;
-    if data.len() < 24 {
-        return None;
-    }
-
-    let mut offset = 24;
     let mut stats = NetworkStats::default();
-    while offset + 16 <= data.len() {
-        let incl_len = endian.read_u32(data.get(offset + 8..offset + 12)?) as usize;
-        let orig_len = endian.read_u32(data.get(offset + 12..offset + 16)?) as u64;
-        offset += 16;
-        if offset + incl_len > data.len() {
-            break;
+    for frame in a3s_box_runtime::network::flows::PcapFrames::new(data)? {
+        let frame_data = frame.data;
+        if frame_data.len() < 14 {
+            continue;
         }
-
-        let frame = &data[offset..offset + incl_len];
-        if frame.len() >= 14 {
-            let frame_len = if orig_len == 0 {
-                incl_len as u64
-            } else {
-                orig_len
-            };
-            if frame[6..12] == guest_mac {
-                stats.tx_bytes = stats.tx_bytes.saturating_add(frame_len);
-            } else if frame[0..6] == guest_mac || frame[0..6] == [0xff; 6] {
-                stats.rx_bytes = stats.rx_bytes.saturating_add(frame_len);
-            }
+        if frame_data[6..12] == guest_mac {
+            stats.tx_bytes = stats.tx_bytes.saturating_add(frame.len);
+        } else if frame_data[0..6] == guest_mac || frame_data[0..6] == [0xff; 6] {
+            stats.rx_bytes = stats.rx_bytes.saturating_add(frame.len);
         }
-
-        offset += incl_len;
     }
 
     Some(stats)
 }
 
-#[derive(Clone, Copy)]
-enum PcapEndian {
-    Little,
-    Big,
-}
-
-impl PcapEndian {
-    fn from_magic(bytes: &[u8]) -> Option<Self> {
-        match bytes {
-            [0xd4, 0xc3, 0xb2, 0xa1] | [0x4d, 0x3c, 0xb2, 0xa1] => Some(Self::Little),
-            [0xa1, 0xb2, 0xc3, 0xd4] | [0xa1, 0xb2, 0x3c, 0x4d] => Some(Self::Big),
-            _ => None,
-        }
-    }
-
-    fn read_u32(self, bytes: &[u8]) -> u32 {
-        let mut arr = [0u8; 4];
-        arr.copy_from_slice(&bytes[..4]);
-        match self {
-            Self::Little => u32::from_le_bytes(arr),
-            Self::Big => u32::from_be_bytes(arr),
-        }
-    }
-}
-
 pub async fn execute(args: StatsArgs) -> Result<(), Box<dyn std::error::Error>> {
     let mut sys = System::new();
+    let mut network_samples: HashMap<String, (NetworkStats, Instant)> = HashMap::new();
+    let format = if output::structured().is_some() {
+        StatsFormat::Json
+    } else {
+        args.format
+    };
 
     loop {
         let state = StateFile::load_default()?;
@@ -410,9 +567,9 @@ pub async fn execute(args: StatsArgs) -> Result<(), Box<dyn std::error::Error>>
         let targets = select_targets(&state, args.r#box.as_deref())?;
 
         if targets.is_empty() {
-            match args.format {
+            match format {
                 StatsFormat::Table => println!("No active boxes"),
-                StatsFormat::Json => println!("[]"),
+                StatsFormat::Json => print_stats_json(&[])?,
             }
             return Ok(());
         }
@@ -421,14 +578,30 @@ pub async fn execute(args: StatsArgs) -> Result<(), Box<dyn std::error::Error>>
         let mut stats = Vec::new();
         for record in &targets {
             if let Some(mut box_stats) = build_box_stats(&mut sys, record) {
-                if args.format == StatsFormat::Json {
+                // A paused guest cannot answer; keep the host view for it.
+                if record.status == "running" {
+                    if let Some(guest) = collect_guest_metrics(record).await {
+                        box_stats.apply_guest_metrics(guest);
+                    }
+                }
+                let sample = NetworkStats {
+                    rx_bytes: box_stats.network_rx_bytes,
+                    tx_bytes: box_stats.network_tx_bytes,
+                };
+                let now = Instant::now();
+                if let Some((previous, at)) =
+                    network_samples.insert(record.id.clone(), (sample, now))
+                {
+                    box_stats.network_rate = network_rate(previous, sample, now - at);
+                }
+                if format == StatsFormat::Json && !box_stats.guest_metrics {
                     box_stats.pids_current = collect_pids_current(record).await;
                 }
                 stats.push(box_stats);
             }
         }
 
-        match args.format {
+        match format {
             StatsFormat::Table => {
                 // Clear screen for streaming mode (except first iteration)
                 if !args.no_stream {
@@ -495,6 +668,38 @@ mod tests {
         assert_eq!(format_io_usage(1024, 2 * 1024 * 1024), "1.0 KB / 2.0 MB");
     }
 
+    #[test]
+    fn test_network_rate_divides_counter_delta_by_elapsed() {
+        let previous = NetworkStats {
+            rx_bytes: 1000,
+            tx_bytes: 5000,
+        };
+        let current = NetworkStats {
+            rx_bytes: 4000,
+            tx_bytes: 1000,
+        };
+
+        assert_eq!(
+            network_rate(previous, current, Duration::from_millis(1500)),
+            Some(NetworkStats {
+                rx_bytes: 2000,
+                tx_bytes: 0,
+            })
+        );
+        assert_eq!(network_rate(previous, current, Duration::ZERO), None);
+        assert_eq!(format_network_rate(None, Some(1000)), "--");
+        assert_eq!(
+            format_network_rate(
+                Some(NetworkStats {
+                    rx_bytes: 2048,
+                    tx_bytes: 512,
+                }),
+                Some(1024 * 1024),
+            ),
+            "2.0 KB/s / 512 B/s (max 1.0 MB/s)"
+        );
+    }
+
     #[test]
     fn test_read_network_stats_file_reads_netproxy_snapshot() {
         let tmp = tempfile::tempdir().unwrap();
@@ -530,9 +735,26 @@ mod tests {
             memory_limit_bytes: 512 * 1024 * 1024,
             network_rx_bytes: 1024,
             network_tx_bytes: 2048,
+            network_rate: Some(NetworkStats {
+                rx_bytes: 100,
+                tx_bytes: 200,
+            }),
+            network_bandwidth_limit: Some(1_250_000),
             block_read_bytes: 4096,
             block_write_bytes: 8192,
             pids_current: Some(7),
+            ksm: Some(a3s_box_runtime::ksm::KsmProcessStats {
+                merging_pages: 16,
+                profit_bytes: Some(60000),
+            }),
+            disk: Some(a3s_box_runtime::rootfs::quota::DiskQuotaUsage {
+                used_bytes: 1024 * 1024,
+                limit_bytes: 64 * 1024 * 1024,
+            }),
+            anon_huge_bytes: Some(32 * 1024 * 1024),
+            top_processes: Vec::new(),
+            guest_cgroups: Vec::new(),
+            guest_metrics: false,
         };
 
         let json = stats_json(&row);
@@ -550,10 +772,86 @@ mod tests {
         assert_eq!(json["memory_percent"], 12.5);
         assert_eq!(json["network_rx_bytes"], 1024);
         assert_eq!(json["network_tx_bytes"], 2048);
+        assert_eq!(json["network_rx_bytes_per_sec"], 100);
+        assert_eq!(json["network_tx_bytes_per_sec"], 200);
+        assert_eq!(json["network_bandwidth_limit_bytes_per_sec"], 1_250_000);
         assert_eq!(json["block_read_bytes"], 4096);
         assert_eq!(json["block_write_bytes"], 8192);
         assert_eq!(json["pids_current"], 7);
         assert_eq!(json["pids"]["current"], 7);
+        assert_eq!(json["ksm"]["merging_pages"], 16);
+        assert_eq!(json["ksm"]["shared_bytes"], 16 * 4096);
+        assert_eq!(json["ksm"]["profit_bytes"], 60000);
+        assert_eq!(json["disk_bytes"], 1024 * 1024);
+        assert_eq!(json["disk_limit_bytes"], 64 * 1024 * 1024);
+        assert_eq!(json["anon_huge_bytes"], 32 * 1024 * 1024);
+        assert_eq!(json["metrics_source"], "host");
+        assert_eq!(json["top_processes"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_apply_guest_metrics_replaces_vmm_figures() {
+        let record = make_record("id-1", "dev", "running", Some(1));
+        let mut row = BoxStats {
+            id: record.id.clone(),
+            name: record.name.clone(),
+            short_id: record.short_id.clone(),
+            status: record.status.clone(),
+            pid: 1,
+            cpus: 2,
+            cpu_percent: 99.0,
+            memory_bytes: 900,
+            memory_limit_bytes: 1000,
+            network_rx_bytes: 10,
+            network_tx_bytes: 20,
+            network_rate: None,
+            network_bandwidth_limit: None,
+            block_read_bytes: 1,
+            block_write_bytes: 2,
+            pids_current: None,
+            ksm: None,
+            disk: None,
+            anon_huge_bytes: None,
+            top_processes: Vec::new(),
+            guest_cgroups: Vec::new(),
+            guest_metrics: false,
+        };
+
+        row.apply_guest_metrics(GuestMetrics {
+            cpus: 2,
+            cpu_percent: 150.0,
+            memory_used_bytes: 300,
+            disk_read_bytes: 4096,
+            processes: 3,
+            top: vec![GuestProcessMetrics {
+                pid: 7,
+                command: "python app.py".to_string(),
+                cpu_percent: 100.0,
+                memory_bytes: 200,
+            }],
+            cgroups: vec![GuestCgroupMetrics {
+                name: "box-7-1".to_string(),
+                memory_bytes: 200,
+                pids: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        assert_eq!(row.cpu_percent, 150.0);
+        assert_eq!(row.memory_bytes, 300);
+        assert_eq!(row.block_read_bytes, 4096);
+        // No guest interface traffic: the host relay counters stay.
+        assert_eq!((row.network_rx_bytes, row.network_tx_bytes), (10, 20));
+        assert_eq!(row.pids_current, Some(3));
+        let json = stats_json(&row);
+        assert_eq!(json["metrics_source"], "guest");
+        assert_eq!(json["top_processes"][0]["command"], "python app.py");
+        assert_eq!(json["guest_cgroups"][0]["name"], "box-7-1");
+        assert_eq!(
+            json["guest_cgroups"][0]["memory_limit_bytes"],
+            serde_json::Value::Null
+        );
     }
 
     #[cfg(not(windows))]
@@ -89,6 +89,23 @@ async fn stop_one(
         return Ok(());
     }
 
+    if record.status == "waiting" {
+        // No VM runs while a box waits on a dependency; stopping only cancels
+        // the pending monitor restart.
+        StateFile::modify(|s| {
+            if let Some(record) = s.find_by_id_mut(&box_id) {
+                if record.status == "waiting" {
+                    record.status = "stopped".to_string();
+                    record.stopped_by_user = true;
+                    record.labels.remove(status::WAITING_ON_LABEL);
+                }
+            }
+            Ok::<(), std::io::Error>(())
+        })?;
+        println!("{}", record.name);
+        return Ok(());
+    }
+
     status::require_active(&record, "stop")
         .map_err(|error| -> Box<dyn std::error::Error> { error.into() })?;
     let pid = lifecycle::require_live_pid(&record, "stop")
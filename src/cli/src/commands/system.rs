@@ -0,0 +1,128 @@
+//! `a3s-box system` subcommands — Docker-compatible system management.
+//!
+//! Groups the disk usage report and prune under the names `docker system`
+//! users expect; the top-level `df` and `system-prune` commands remain.
+//! `system migrate` brings the home directory's on-disk layout up to date.
+
+use std::path::Path;
+
+use a3s_box_runtime::home_layout::{self, Migration, LAYOUT_VERSION};
+use clap::{Args, Subcommand};
+
+use super::{df, system_prune};
+
+/// Manage system-wide resources.
+#[derive(Args)]
+pub struct SystemArgs {
+    #[command(subcommand)]
+    pub command: SystemCommand,
+}
+
+/// System subcommands.
+#[derive(Subcommand)]
+pub enum SystemCommand {
+    /// Show disk usage by images, boxes, volumes, caches, and the warm pool
+    Df(df::DfArgs),
+    /// Remove all unused data (stopped boxes, unused images and networks)
+    Prune(system_prune::SystemPruneArgs),
+    /// Migrate ~/.a3s to the on-disk layout this binary uses
+    Migrate(MigrateArgs),
+}
+
+#[derive(Args)]
+pub struct MigrateArgs {
+    /// Show the migration steps without applying them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+pub async fn execute(args: SystemArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match args.command {
+        SystemCommand::Df(a) => df::execute(a).await,
+        SystemCommand::Prune(a) => system_prune::execute(a).await,
+        SystemCommand::Migrate(a) => execute_migrate(a),
+    }
+}
+
+fn execute_migrate(args: MigrateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let home = a3s_box_core::dirs_home();
+    let Some(version) = home_layout::read_version(&home)? else {
+        println!("{} does not exist; nothing to migrate", home.display());
+        return Ok(());
+    };
+    let steps = if args.dry_run {
+        home_layout::pending_migrations(&home)?
+    } else {
+        home_layout::migrate(&home)?
+    };
+    println!("{}", migrate_report(&home, version, &steps, args.dry_run));
+    Ok(())
+}
+
+fn migrate_report(home: &Path, version: u32, steps: &[&Migration], dry_run: bool) -> String {
+    if steps.is_empty() {
+        return format!(
+            "{} is at layout {version}, which is current",
+            home.display()
+        );
+    }
+    let mut report = format!(
+        "{} {} from layout {version} to {LAYOUT_VERSION}:",
+        home.display(),
+        if dry_run { "would migrate" } else { "migrated" }
+    );
+    for step in steps {
+        report.push_str(&format!(
+            "\n  {} -> {}: {}",
+            step.from,
+            step.from + 1,
+            step.description
+        ));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Cli, Command};
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_system_df_parses_verbose() {
+        let cli = Cli::try_parse_from(["a3s-box", "system", "df", "-v"]).unwrap();
+
+        let Some(Command::System(args)) = cli.command else {
+            panic!("expected system command");
+        };
+        let SystemCommand::Df(args) = args.command else {
+            panic!("expected system df");
+        };
+        assert!(args.verbose);
+    }
+
+    #[test]
+    fn test_migrate_report() {
+        let home = Path::new("/home/u/.a3s");
+        let steps: Vec<&Migration> = home_layout::MIGRATIONS.iter().collect();
+        let report = migrate_report(home, 0, &steps, true);
+        assert!(report.starts_with("/home/u/.a3s would migrate from layout 0 to 1:\n  0 -> 1: "));
+        assert_eq!(
+            migrate_report(home, LAYOUT_VERSION, &[], false),
+            format!("/home/u/.a3s is at layout {LAYOUT_VERSION}, which is current")
+        );
+
+        let cli = Cli::try_parse_from(["a3s-box", "system", "migrate", "--dry-run"]).unwrap();
+        let Some(Command::System(args)) = cli.command else {
+            panic!("expected system command");
+        };
+        assert!(matches!(
+            args.command,
+            SystemCommand::Migrate(MigrateArgs { dry_run: true })
+        ));
+        assert!(!super::super::checks_home_layout(&Command::System(args)));
+
+        let cli = Cli::try_parse_from(["a3s-box", "system", "df"]).unwrap();
+        assert!(super::super::checks_home_layout(&cli.command.unwrap()));
+    }
+}
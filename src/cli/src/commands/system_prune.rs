@@ -1,12 +1,21 @@
 //! `a3s-box system-prune` command — Remove all unused data.
 //!
-//! Removes stopped boxes and unused images in one operation.
+//! Removes stopped boxes, unused images and networks, and with `--volumes`
+//! unused volumes, in one operation. The removal is planned up front, so
+//! `--dry-run` lists exactly what a real run would remove and how much space
+//! it would free. Rootfs and layer caches are left alone: cached rootfs trees
+//! serve as the read-only lower layer of existing boxes.
 
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
 use clap::Args;
 
-use crate::image_usage::{self, ImagePruneMode, ImageReferenceScope};
+use crate::filter::Filters;
+use crate::image_usage::{self, ImagePruneMode};
 use crate::output;
-use crate::state::StateFile;
+use crate::state::{BoxRecord, StateFile};
 
 #[derive(Args)]
 pub struct SystemPruneArgs {
@@ -17,103 +26,327 @@ pub struct SystemPruneArgs {
     /// Skip confirmation prompt
     #[arg(short, long)]
     pub force: bool,
+
+    /// Also remove volumes not used by any box
+    #[arg(long)]
+    pub volumes: bool,
+
+    /// List what would be removed and the space it would free, without
+    /// removing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Only remove objects created before a time:
+    /// until=<RFC 3339 timestamp|duration ago, e.g. 24h>
+    #[arg(long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+}
+
+/// Something a prune removes, with the bytes removing it frees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PruneItem {
+    /// Box ID, image reference, volume or network name.
+    id: String,
+    /// Name shown to the user.
+    name: String,
+    size: u64,
+}
+
+/// Everything one prune removes.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PrunePlan {
+    boxes: Vec<PruneItem>,
+    images: Vec<PruneItem>,
+    volumes: Vec<PruneItem>,
+    networks: Vec<PruneItem>,
+}
+
+impl PrunePlan {
+    fn size(&self) -> u64 {
+        [&self.boxes, &self.images, &self.volumes, &self.networks]
+            .into_iter()
+            .flatten()
+            .map(|item| item.size)
+            .sum()
+    }
 }
 
 pub async fn execute(args: SystemPruneArgs) -> Result<(), Box<dyn std::error::Error>> {
-    if !args.force {
+    let filters = parse_filters(&args.filters)?;
+    if !args.force && !args.dry_run {
         println!("WARNING: This will remove:");
         println!("  - all created, stopped, and dead boxes");
         println!("  - all networks not used by at least one box");
+        if args.volumes {
+            println!("  - all volumes not used by at least one box");
+        }
         if args.all {
             println!("  - all images not used by active boxes");
         } else {
             println!("  - all dangling images");
         }
+        if !filters.is_empty() {
+            println!("  (only objects matching: {})", args.filters.join(", "));
+        }
         println!();
-        println!("Use --force to skip this prompt.");
+        println!("Use --dry-run to list them, or --force to skip this prompt.");
         return Ok(());
     }
 
-    let mut boxes_removed: usize = 0;
-    let mut images_removed: usize = 0;
-    let mut networks_removed: usize = 0;
-    let mut space_freed: u64 = 0;
+    let plan = build_plan(&args, &filters).await?;
+    if args.dry_run {
+        print!("{}", render_dry_run(&plan));
+        return Ok(());
+    }
+
+    let mut summary = PrunePlan::default();
 
     // Phase 1: Remove stopped/dead boxes
     let mut state = StateFile::load_default()?;
-    let all_boxes = state.list(true);
-
-    let to_remove: Vec<(String, String, std::path::PathBuf)> = all_boxes
-        .iter()
-        .filter(|r| is_prunable_box(r))
-        .map(|r| (r.id.clone(), r.name.clone(), r.box_dir.clone()))
-        .collect();
-
-    for (box_id, name, box_dir) in &to_remove {
-        if box_dir.exists() {
-            let _ = std::fs::remove_dir_all(box_dir);
+    for item in &plan.boxes {
+        if let Some(record) = state.find_by_id(&item.id) {
+            if record.box_dir.exists() {
+                let _ = std::fs::remove_dir_all(&record.box_dir);
+            }
         }
-        if state.remove(box_id).is_ok() {
-            boxes_removed += 1;
-            println!("Removed box: {name}");
+        if state.remove(&item.id).is_ok() {
+            println!("Removed box: {}", item.name);
+            summary.boxes.push(item.clone());
         }
     }
 
     // Phase 2: Remove unused images
-    // Reload state to get current active boxes after removal.
-    let state = StateFile::load_default()?;
-    let protected_images = active_image_references(&state);
-    let prune_mode = image_prune_mode(args.all);
+    if !plan.images.is_empty() {
+        let store = super::open_image_store()?;
+        for item in &plan.images {
+            if store.remove(&item.id).await.is_ok() {
+                println!("Removed image: {}", item.name);
+                summary.images.push(item.clone());
+            }
+        }
+    }
 
-    let images_dir = super::images_dir();
-    if images_dir.exists() {
-        if let Ok(store) = super::open_image_store() {
-            let all_images = store.list().await;
-
-            for image in &all_images {
-                if image_usage::is_prunable_reference(
-                    &image.reference,
-                    &protected_images,
-                    prune_mode,
-                ) && store.remove(&image.reference).await.is_ok()
-                {
-                    space_freed += image.size_bytes;
-                    images_removed += 1;
-                    println!("Removed image: {}", image.reference);
-                }
+    // Phase 3: Remove unused volumes. Drop the removed boxes from each
+    // volume's users first, so the store sees them as unused.
+    if !plan.volumes.is_empty() {
+        let store = a3s_box_runtime::VolumeStore::default_path()?;
+        super::volume::reconcile_volume_users(&store, &StateFile::load_default()?)?;
+        for item in &plan.volumes {
+            if store.remove(&item.id, false).is_ok() {
+                println!("Removed volume: {}", item.name);
+                summary.volumes.push(item.clone());
             }
         }
     }
 
-    // Phase 3: Remove unused networks (mirrors `docker system prune`).
-    // Reload state so freshly-removed boxes no longer count as network users.
-    let state = StateFile::load_default()?;
-    if let Ok(network_store) = a3s_box_runtime::NetworkStore::default_path() {
-        let (removed, _errors) = super::network::prune_unused_networks(&network_store, &state);
-        for name in &removed {
-            networks_removed += 1;
-            println!("Removed network: {name}");
+    // Phase 4: Remove unused networks (mirrors `docker system prune`).
+    if !plan.networks.is_empty() {
+        let store = a3s_box_runtime::NetworkStore::default_path()?;
+        for item in &plan.networks {
+            if store.remove(&item.id).is_ok() {
+                println!("Removed network: {}", item.name);
+                summary.networks.push(item.clone());
+            }
         }
     }
 
     println!();
     println!(
-        "Removed {} box(es), {} image(s), {} network(s), freed {}",
-        boxes_removed,
-        images_removed,
-        networks_removed,
-        output::format_bytes(space_freed)
+        "Removed {} box(es), {} image(s), {} volume(s), {} network(s), freed {}",
+        summary.boxes.len(),
+        summary.images.len(),
+        summary.volumes.len(),
+        summary.networks.len(),
+        output::format_bytes(summary.size())
     );
 
     Ok(())
 }
 
-fn is_prunable_box(record: &crate::state::BoxRecord) -> bool {
-    matches!(record.status.as_str(), "stopped" | "dead" | "created")
+/// Parse `--filter` flags, rejecting `until` values that are not a time.
+fn parse_filters(specs: &[String]) -> Result<Filters, String> {
+    let filters = Filters::parse(specs, &["until"])?;
+    for spec in specs {
+        if let Some(value) = spec.strip_prefix("until=") {
+            parse_until(value, Utc::now())?;
+        }
+    }
+    Ok(filters)
 }
 
-fn active_image_references(state: &StateFile) -> std::collections::HashSet<String> {
-    image_usage::referenced_images(state, ImageReferenceScope::ActiveBoxes)
+/// An `until` value: an RFC 3339 timestamp, or a duration meaning that long
+/// before `now`.
+fn parse_until(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let secs = output::parse_duration_secs(value)
+        .map_err(|_| format!("Invalid --filter until={value}: expected a timestamp or duration"))?;
+    Ok(now - chrono::Duration::seconds(secs as i64))
+}
+
+/// Whether an object created at `created` passes the filters. An object
+/// with an unknown creation time only passes when no `until` is given.
+fn created_before(filters: &Filters, created: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    filters.matches(|key, value| {
+        key == "until"
+            && created
+                .is_some_and(|created| parse_until(value, now).is_ok_and(|until| created < until))
+    })
+}
+
+fn parse_created_at(created_at: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(created_at)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Work out everything `args` would remove, without removing anything.
+async fn build_plan(
+    args: &SystemPruneArgs,
+    filters: &Filters,
+) -> Result<PrunePlan, Box<dyn std::error::Error>> {
+    let now = Utc::now();
+    let state = StateFile::load_default()?;
+    let mut plan = PrunePlan::default();
+
+    let (removed, remaining) = split_boxes(state.records(), filters, now);
+    plan.boxes = removed
+        .iter()
+        .map(|record| PruneItem {
+            id: record.id.clone(),
+            name: record.name.clone(),
+            size: super::df::dir_size(&record.box_dir),
+        })
+        .collect();
+
+    let protected_images = protected_image_references(&remaining);
+    let prune_mode = image_prune_mode(args.all);
+    if super::images_dir().exists() {
+        if let Ok(store) = super::open_image_store() {
+            plan.images = store
+                .list()
+                .await
+                .into_iter()
+                .filter(|image| {
+                    image_usage::is_prunable_reference(
+                        &image.reference,
+                        &protected_images,
+                        prune_mode,
+                    ) && created_before(filters, Some(image.pulled_at), now)
+                })
+                .map(|image| PruneItem {
+                    id: image.reference.clone(),
+                    name: image.reference,
+                    size: image.size_bytes,
+                })
+                .collect();
+        }
+    }
+
+    let remaining_ids: HashSet<&str> = remaining.iter().map(|r| r.id.as_str()).collect();
+    if args.volumes {
+        let store = a3s_box_runtime::VolumeStore::default_path()?;
+        let mut volumes: Vec<PruneItem> = store
+            .list()?
+            .into_iter()
+            .filter(|volume| {
+                !volume
+                    .in_use_by
+                    .iter()
+                    .any(|id| remaining_ids.contains(id.as_str()))
+                    && created_before(filters, parse_created_at(&volume.created_at), now)
+            })
+            .map(|volume| PruneItem {
+                size: super::df::dir_size(Path::new(&volume.mount_point)),
+                id: volume.name.clone(),
+                name: volume.name,
+            })
+            .collect();
+        volumes.sort_by(|a, b| a.name.cmp(&b.name));
+        plan.volumes = volumes;
+    }
+
+    if let Ok(store) = a3s_box_runtime::NetworkStore::default_path() {
+        let in_use: HashSet<String> = remaining
+            .iter()
+            .filter_map(|record| crate::cleanup::record_network_name(record).map(str::to_string))
+            .collect();
+        let mut networks: Vec<PruneItem> = store
+            .list()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|network| {
+                !super::network::is_predefined_network(&network.name)
+                    && super::network::network_is_unused(network, &in_use)
+                    && created_before(filters, parse_created_at(&network.created_at), now)
+            })
+            .map(|network| PruneItem {
+                id: network.name.clone(),
+                name: network.name,
+                size: 0,
+            })
+            .collect();
+        networks.sort_by(|a, b| a.name.cmp(&b.name));
+        plan.networks = networks;
+    }
+
+    Ok(plan)
+}
+
+/// Split box records into those the prune removes and those it keeps.
+fn split_boxes<'a>(
+    records: &'a [BoxRecord],
+    filters: &Filters,
+    now: DateTime<Utc>,
+) -> (Vec<&'a BoxRecord>, Vec<&'a BoxRecord>) {
+    records.iter().partition(|record| {
+        is_prunable_box(record) && created_before(filters, Some(record.created_at), now)
+    })
+}
+
+/// Images stay while any box that survives the prune refers to them.
+fn protected_image_references(remaining: &[&BoxRecord]) -> HashSet<String> {
+    remaining
+        .iter()
+        .flat_map(|record| image_usage::reference_aliases(&record.image))
+        .collect()
+}
+
+fn render_dry_run(plan: &PrunePlan) -> String {
+    let mut out = String::new();
+    let sections = [
+        ("box", &plan.boxes),
+        ("image", &plan.images),
+        ("volume", &plan.volumes),
+        ("network", &plan.networks),
+    ];
+    for (kind, items) in sections {
+        for item in items {
+            if item.size > 0 {
+                out.push_str(&format!(
+                    "Would remove {kind}: {} ({})\n",
+                    item.name,
+                    output::format_bytes(item.size)
+                ));
+            } else {
+                out.push_str(&format!("Would remove {kind}: {}\n", item.name));
+            }
+        }
+    }
+    out.push_str(&format!(
+        "\nWould remove {} box(es), {} image(s), {} volume(s), {} network(s), reclaiming {}\n",
+        plan.boxes.len(),
+        plan.images.len(),
+        plan.volumes.len(),
+        plan.networks.len(),
+        output::format_bytes(plan.size())
+    ));
+    out
+}
+
+pub(super) fn is_prunable_box(record: &crate::state::BoxRecord) -> bool {
+    matches!(record.status.as_str(), "stopped" | "dead" | "created")
 }
 
 fn image_prune_mode(all: bool) -> ImagePruneMode {
@@ -153,21 +386,84 @@ mod tests {
     }
 
     #[test]
-    fn test_active_image_references_include_paused() {
+    fn test_kept_boxes_protect_their_images() {
+        let now = Utc::now();
         let mut running = make_record("id-1", "running", "running", Some(1));
         running.image = "alpine:latest".to_string();
         let mut paused = make_record("id-2", "paused", "paused", Some(1));
         paused.image = "redis:latest".to_string();
         let mut stopped = make_record("id-3", "stopped", "stopped", None);
         stopped.image = "nginx:latest".to_string();
-        let (_tmp, state) = setup_state(vec![running, paused, stopped]);
-
-        let used_images = active_image_references(&state);
+        stopped.created_at = now - chrono::Duration::hours(48);
+        let mut recent = make_record("id-4", "stopped", "stopped", None);
+        recent.image = "busybox:latest".to_string();
+        recent.created_at = now - chrono::Duration::hours(1);
+        let (_tmp, state) = setup_state(vec![running, paused, stopped, recent]);
 
+        let (removed, remaining) = split_boxes(state.records(), &Filters::default(), now);
+        let ids: Vec<_> = removed.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["id-3", "id-4"]);
+        let used_images = protected_image_references(&remaining);
         assert!(used_images.contains("alpine:latest"));
         assert!(used_images.contains("docker.io/library/alpine:latest"));
         assert!(used_images.contains("redis:latest"));
         assert!(!used_images.contains("nginx:latest"));
+
+        // `until=24h` keeps the recent box, and with it its image.
+        let filters = parse_filters(&["until=24h".to_string()]).unwrap();
+        let (removed, remaining) = split_boxes(state.records(), &filters, now);
+        let ids: Vec<_> = removed.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["id-3"]);
+        assert!(protected_image_references(&remaining).contains("busybox:latest"));
+    }
+
+    #[test]
+    fn test_until_filter() {
+        let now = Utc::now();
+        let day_ago = parse_until("24h", now).unwrap();
+        assert_eq!(day_ago, now - chrono::Duration::hours(24));
+        assert_eq!(
+            parse_until("2026-01-02T03:04:05Z", now).unwrap(),
+            parse_created_at("2026-01-02T03:04:05+00:00").unwrap()
+        );
+        assert!(parse_filters(&["until=yesterday".to_string()]).is_err());
+        assert!(parse_filters(&["label=a".to_string()]).is_err());
+
+        let filters = parse_filters(&["until=24h".to_string()]).unwrap();
+        let old = Some(now - chrono::Duration::hours(25));
+        let new = Some(now - chrono::Duration::hours(23));
+        assert!(created_before(&filters, old, now));
+        assert!(!created_before(&filters, new, now));
+        assert!(!created_before(&filters, None, now));
+        assert!(created_before(&Filters::default(), None, now));
+    }
+
+    #[test]
+    fn test_dry_run_lists_items_and_total() {
+        let plan = PrunePlan {
+            boxes: vec![PruneItem {
+                id: "id-1".into(),
+                name: "web".into(),
+                size: 2048,
+            }],
+            networks: vec![PruneItem {
+                id: "backend".into(),
+                name: "backend".into(),
+                size: 0,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            render_dry_run(&plan),
+            format!(
+                "Would remove box: web ({})\n\
+                 Would remove network: backend\n\
+                 \n\
+                 Would remove 1 box(es), 0 image(s), 0 volume(s), 1 network(s), reclaiming {}\n",
+                output::format_bytes(2048),
+                output::format_bytes(2048)
+            )
+        );
     }
 
     #[test]
@@ -0,0 +1,539 @@
+//! `a3s-box tee` subcommands — Inspect TEE support, attest boxes, and
+//! manage verification material.
+//!
+//! `status`, `attest`, and `verify` wrap the runtime verifier for scripts:
+//! `attest` saves a box's report for a chosen nonce and `verify` checks a
+//! saved report against a policy, possibly on another host.
+//!
+//! Verifying an SNP report needs the AMD certificate chain for the chip and
+//! TCB version that signed it. The `*-certs` commands fill the local AMD KDS
+//! cache ahead of time and move it between hosts as an offline bundle, so
+//! hosts without network access can still verify reports. `fetch-certs --out`
+//! writes the chain as a bundle into a directory that air-gapped hosts can
+//! point `A3S_AMD_CERT_BUNDLE_DIR` at instead of importing it.
+
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+
+/// Inspect TEE support, attest boxes, and manage verification material.
+#[derive(Args)]
+pub struct TeeArgs {
+    #[command(subcommand)]
+    pub command: TeeCommand,
+}
+
+/// TEE subcommands.
+#[derive(Subcommand)]
+pub enum TeeCommand {
+    /// Report this host's SEV-SNP support and certificate cache
+    Status(StatusArgs),
+    /// Challenge a box with a nonce and print its attestation report
+    Attest(AttestArgs),
+    /// Verify a saved attestation report against a policy
+    Verify(VerifyArgs),
+    /// Fetch AMD certificates for a box's chip into the local cache
+    FetchCerts(FetchCertsArgs),
+    /// Write the cached AMD certificates to an offline bundle
+    ExportCerts(ExportCertsArgs),
+    /// Add an offline bundle's AMD certificates to the local cache
+    ImportCerts(ImportCertsArgs),
+}
+
+#[derive(Args)]
+pub struct StatusArgs {
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct AttestArgs {
+    /// Box name or ID
+    pub r#box: String,
+
+    /// Hex-encoded 32-byte nonce; random if omitted
+    #[arg(long)]
+    pub nonce: Option<String>,
+
+    /// Write the raw report to this file, for `tee verify`
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// Accept a simulated (non-hardware) report
+    #[arg(long)]
+    pub allow_simulated: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Raw report file written by `tee attest --output`
+    pub report: PathBuf,
+
+    /// Attestation policy file (YAML or JSON); defaults to the default policy
+    #[arg(long, short)]
+    pub policy: Option<PathBuf>,
+
+    /// Hex-encoded nonce the report must carry. Without it the report's
+    /// freshness is not checked.
+    #[arg(long)]
+    pub nonce: Option<String>,
+
+    /// Accept a simulated (non-hardware) report
+    #[arg(long)]
+    pub allow_simulated: bool,
+
+    /// Never contact AMD KDS; use only cached certificates
+    #[arg(long)]
+    pub offline: bool,
+
+    /// EPYC product line for fetching missing certificates: milan or genoa
+    #[arg(long, default_value = "milan")]
+    pub product: String,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct FetchCertsArgs {
+    /// Box whose attestation report names the chip and TCB version
+    #[arg(required_unless_present = "chip_id", conflicts_with = "chip_id")]
+    pub r#box: Option<String>,
+
+    /// Hex-encoded chip ID, instead of reading it from a box
+    #[arg(long, requires = "tcb")]
+    pub chip_id: Option<String>,
+
+    /// TCB version as BOOT_LOADER:TEE:SNP:MICROCODE (with --chip-id)
+    #[arg(long, requires = "chip_id")]
+    pub tcb: Option<String>,
+
+    /// EPYC product line: milan or genoa
+    #[arg(long, alias = "model", default_value = "milan")]
+    pub product: String,
+
+    /// Also write the chain as a bundle file into this directory
+    #[arg(long, value_name = "DIR")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ExportCertsArgs {
+    /// Bundle file to write
+    pub output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct ImportCertsArgs {
+    /// Bundle file written by `tee export-certs`
+    pub input: PathBuf,
+}
+
+#[cfg(windows)]
+pub async fn execute(_args: TeeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    Err(crate::platform::unsupported_command(
+        "tee",
+        "TEE attestation support",
+    ))
+}
+
+#[cfg(not(windows))]
+pub async fn execute(args: TeeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match args.command {
+        TeeCommand::Status(a) => execute_status(a).await,
+        TeeCommand::Attest(a) => execute_attest(a).await,
+        TeeCommand::Verify(a) => execute_verify(a).await,
+        TeeCommand::FetchCerts(a) => execute_fetch_certs(a).await,
+        TeeCommand::ExportCerts(a) => execute_export_certs(a).await,
+        TeeCommand::ImportCerts(a) => execute_import_certs(a).await,
+    }
+}
+
+#[cfg(not(windows))]
+fn kds_client() -> a3s_box_runtime::AmdKdsClient {
+    use a3s_box_runtime::AmdKdsClient;
+
+    AmdKdsClient::new(Some(AmdKdsClient::default_cache_dir()))
+}
+
+#[cfg(not(windows))]
+async fn execute_status(args: StatusArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let support = a3s_box_runtime::tee::check_sev_snp_support()?;
+    let cache_dir = a3s_box_runtime::AmdKdsClient::default_cache_dir();
+    let cached = kds_client().export_bundle().await?.entries;
+    let simulate = a3s_box_runtime::tee::is_simulate_mode();
+
+    if args.json {
+        let status = serde_json::json!({
+            "sev_snp": {
+                "available": support.available,
+                "reason": support.reason,
+            },
+            "simulate": simulate,
+            "cert_cache": {
+                "path": cache_dir,
+                "chains": cached.len(),
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+
+    match &support.reason {
+        None => println!("SEV-SNP:     available"),
+        Some(reason) => println!("SEV-SNP:     unavailable ({reason})"),
+    }
+    println!(
+        "Simulation:  {}",
+        if simulate {
+            "enabled (A3S_TEE_SIMULATE)"
+        } else {
+            "disabled"
+        }
+    );
+    println!(
+        "Cert cache:  {} ({} chain(s))",
+        cache_dir.display(),
+        cached.len()
+    );
+    Ok(())
+}
+
+#[cfg(not(windows))]
+async fn execute_attest(args: AttestArgs) -> Result<(), Box<dyn std::error::Error>> {
+    use a3s_box_runtime::RaTlsAttestationClient;
+
+    let state = crate::state::StateFile::load_default()?;
+    let record = crate::resolve::resolve(&state, &args.r#box)?;
+    let socket_path = crate::socket_paths::require_runtime_socket(
+        record,
+        crate::socket_paths::RuntimeSocket::Attest,
+    )?;
+
+    let client = RaTlsAttestationClient::new(&socket_path);
+    let challenge = match &args.nonce {
+        Some(nonce) => {
+            client
+                .challenge_with_nonce(parse_nonce(nonce)?, args.allow_simulated)
+                .await?
+        }
+        None => client.challenge(args.allow_simulated).await?,
+    };
+    if let Some(output) = &args.output {
+        std::fs::write(output, &challenge.report.report)
+            .map_err(|e| format!("failed to write {}: {e}", output.display()))?;
+    }
+
+    let report = ReportOutput::new(&challenge.report.report, &challenge.report.platform);
+    if args.json {
+        let output = serde_json::json!({
+            "box_id": record.id,
+            "box_name": record.name,
+            "nonce": hex::encode(&challenge.nonce),
+            "report": report,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        println!("Box:           {}", record.name);
+        println!("Nonce:         {}", hex::encode(&challenge.nonce));
+        report.print();
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+async fn execute_verify(args: VerifyArgs) -> Result<(), Box<dyn std::error::Error>> {
+    use a3s_box_runtime::tee::{parse_platform_info, AttestationReport, CertificateChain};
+    use a3s_box_runtime::{verify_attestation, AmdKdsClient, AttestationPolicy};
+
+    let data = std::fs::read(&args.report)
+        .map_err(|e| format!("failed to read {}: {e}", args.report.display()))?;
+    let platform = parse_platform_info(&data)
+        .ok_or_else(|| format!("{} is not an SNP report", args.report.display()))?;
+    let policy = match &args.policy {
+        Some(path) => AttestationPolicy::load(path)?,
+        None => AttestationPolicy::default(),
+    };
+    let nonce = match &args.nonce {
+        Some(nonce) => hex_decode(nonce)?,
+        None => Vec::new(),
+    };
+
+    let mut report = AttestationReport {
+        report: data,
+        cert_chain: CertificateChain::default(),
+        platform,
+    };
+    let mut kds = kds_client();
+    if args.offline {
+        kds = kds.offline();
+    }
+    kds.complete_report(&mut report, AmdKdsClient::product_name(&args.product))
+        .await?;
+    let result = verify_attestation(&report, &nonce, &policy, args.allow_simulated)?;
+
+    if args.json {
+        let output = serde_json::json!({
+            "verified": result.verified,
+            "nonce_checked": args.nonce.is_some(),
+            "report": ReportOutput::new(&report.report, &result.platform),
+            "failures": result.failures,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        ReportOutput::new(&report.report, &result.platform).print();
+        if args.nonce.is_none() {
+            println!("Nonce:         not checked (no --nonce)");
+        }
+        println!(
+            "Verified:      {}",
+            if result.verified { "yes" } else { "no" }
+        );
+        for failure in &result.failures {
+            println!("  - {failure}");
+        }
+    }
+    if !result.verified {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The report fields `tee attest` and `tee verify` print.
+#[cfg(not(windows))]
+#[derive(serde::Serialize)]
+struct ReportOutput<'a> {
+    simulated: bool,
+    version: u32,
+    guest_svn: u32,
+    policy: String,
+    debug: bool,
+    measurement: &'a str,
+    host_data: &'a str,
+    report_data: &'a str,
+    tcb: String,
+    chip_id: &'a str,
+}
+
+#[cfg(not(windows))]
+impl<'a> ReportOutput<'a> {
+    fn new(report: &[u8], platform: &'a a3s_box_runtime::PlatformInfo) -> Self {
+        let tcb = &platform.tcb_version;
+        Self {
+            simulated: a3s_box_runtime::tee::is_simulated_report(report),
+            version: platform.version,
+            guest_svn: platform.guest_svn,
+            policy: format!("{:#x}", platform.policy),
+            // Guest policy bit 19 allows debugging the guest.
+            debug: (platform.policy >> 19) & 1 == 1,
+            measurement: &platform.measurement,
+            host_data: &platform.host_data,
+            report_data: &platform.report_data,
+            tcb: format!(
+                "{}:{}:{}:{}",
+                tcb.boot_loader, tcb.tee, tcb.snp, tcb.microcode
+            ),
+            chip_id: &platform.chip_id,
+        }
+    }
+
+    fn print(&self) {
+        println!(
+            "Report:        v{}{}",
+            self.version,
+            if self.simulated { " (simulated)" } else { "" }
+        );
+        println!("Guest SVN:     {}", self.guest_svn);
+        println!(
+            "Guest policy:  {}{}",
+            self.policy,
+            if self.debug { " (debug enabled)" } else { "" }
+        );
+        println!("Measurement:   {}", self.measurement);
+        println!("Host data:     {}", self.host_data);
+        println!("Report data:   {}", self.report_data);
+        println!("TCB:           {}", self.tcb);
+        println!("Chip ID:       {}", self.chip_id);
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) async fn execute_fetch_certs(
+    args: FetchCertsArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use a3s_box_runtime::tee::{parse_platform_info, TcbVersion};
+    use a3s_box_runtime::{AmdKdsClient, RaTlsAttestationClient};
+
+    let (chip_id, tcb) = match (&args.r#box, &args.chip_id, &args.tcb) {
+        (_, Some(chip_id), Some(tcb)) => (chip_id.clone(), parse_tcb(tcb)?),
+        (Some(name), _, _) => {
+            let state = crate::state::StateFile::load_default()?;
+            let record = crate::resolve::resolve(&state, name)?;
+            let socket_path = crate::socket_paths::require_runtime_socket(
+                record,
+                crate::socket_paths::RuntimeSocket::Attest,
+            )?;
+            let report = RaTlsAttestationClient::new(&socket_path)
+                .fetch_report(false)
+                .await?;
+            let platform = parse_platform_info(&report.report)
+                .ok_or("box returned a malformed attestation report")?;
+            (platform.chip_id, platform.tcb_version)
+        }
+        _ => return Err("specify a box, or --chip-id with --tcb".into()),
+    };
+
+    let product = AmdKdsClient::product_name(&args.product);
+    let cached = kds_client()
+        .fetch_cached_chain(&chip_id, &tcb, product)
+        .await?;
+    let TcbVersion {
+        boot_loader,
+        tee,
+        snp,
+        microcode,
+    } = tcb;
+    println!(
+        "Cached {product} certificates for chip {} (TCB {boot_loader}:{tee}:{snp}:{microcode})",
+        &chip_id[..chip_id.len().min(16)]
+    );
+    if let Some(dir) = &args.out {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(bundle_file_name(product, &chip_id, &cached.tcb));
+        a3s_box_runtime::tee::CertBundle::new(vec![cached]).save(&path)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// File name for a one-chain bundle written by `fetch-certs --out`.
+#[cfg(any(not(windows), test))]
+fn bundle_file_name(
+    product: &str,
+    chip_id: &str,
+    tcb: &a3s_box_runtime::tee::TcbVersion,
+) -> String {
+    format!(
+        "amd-{}-{}-{}.{}.{}.{}.json",
+        product.to_lowercase(),
+        &chip_id[..chip_id.len().min(16)],
+        tcb.boot_loader,
+        tcb.tee,
+        tcb.snp,
+        tcb.microcode
+    )
+}
+
+#[cfg(not(windows))]
+async fn execute_export_certs(args: ExportCertsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let bundle = kds_client().export_bundle().await?;
+    if bundle.entries.is_empty() {
+        return Err("certificate cache is empty; run `a3s-box tee fetch-certs` first".into());
+    }
+    bundle.save(&args.output)?;
+    println!(
+        "Exported {} certificate chain(s) to {}",
+        bundle.entries.len(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+#[cfg(not(windows))]
+async fn execute_import_certs(args: ImportCertsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let bundle = a3s_box_runtime::tee::CertBundle::load(&args.input)?;
+    let imported = kds_client().import_bundle(&bundle).await?;
+    println!("Imported {imported} certificate chain(s)");
+    Ok(())
+}
+
+/// Decode a hex string, with or without a `0x` prefix.
+#[cfg(not(windows))]
+fn hex_decode(value: &str) -> Result<Vec<u8>, String> {
+    let value = value.trim();
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|e| format!("invalid hex '{value}': {e}"))
+}
+
+/// Parse a challenge nonce: hex for exactly
+/// [`a3s_box_core::tee::CHALLENGE_NONCE_LEN`] bytes.
+#[cfg(not(windows))]
+fn parse_nonce(value: &str) -> Result<Vec<u8>, String> {
+    let nonce = hex_decode(value)?;
+    let expected = a3s_box_core::tee::CHALLENGE_NONCE_LEN;
+    if nonce.len() != expected {
+        return Err(format!(
+            "nonce must be {expected} bytes ({} hex digits), got {}",
+            expected * 2,
+            nonce.len()
+        ));
+    }
+    Ok(nonce)
+}
+
+/// Parse a `BOOT_LOADER:TEE:SNP:MICROCODE` TCB version.
+#[cfg(not(windows))]
+fn parse_tcb(value: &str) -> Result<a3s_box_runtime::tee::TcbVersion, String> {
+    let parts = value
+        .split(':')
+        .map(|part| part.trim().parse::<u8>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid TCB '{value}': components must be 0-255"))?;
+    let [boot_loader, tee, snp, microcode] = parts[..] else {
+        return Err(format!(
+            "invalid TCB '{value}': expected BOOT_LOADER:TEE:SNP:MICROCODE"
+        ));
+    };
+    Ok(a3s_box_runtime::tee::TcbVersion {
+        boot_loader,
+        tee,
+        snp,
+        microcode,
+    })
+}
+
+#[cfg(test)]
+#[cfg(not(windows))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tcb() {
+        let tcb = parse_tcb("3:0:8:115").unwrap();
+        assert_eq!(
+            (tcb.boot_loader, tcb.tee, tcb.snp, tcb.microcode),
+            (3, 0, 8, 115)
+        );
+        assert!(parse_tcb("3:0:8").is_err());
+        assert!(parse_tcb("3:0:8:300").is_err());
+    }
+
+    #[test]
+    fn test_bundle_file_name() {
+        let tcb = a3s_box_runtime::tee::TcbVersion {
+            boot_loader: 3,
+            tee: 0,
+            snp: 8,
+            microcode: 115,
+        };
+        assert_eq!(
+            bundle_file_name("Milan", &"ab".repeat(64), &tcb),
+            "amd-milan-abababababababab-3.0.8.115.json"
+        );
+    }
+
+    #[test]
+    fn test_parse_nonce() {
+        let nonce = "ab".repeat(32);
+        assert_eq!(parse_nonce(&nonce).unwrap(), vec![0xab; 32]);
+        assert_eq!(parse_nonce(&format!("0x{nonce}")).unwrap(), vec![0xab; 32]);
+        assert!(parse_nonce("abab").is_err());
+        assert!(parse_nonce(&"zz".repeat(32)).is_err());
+    }
+}
@@ -1,12 +1,20 @@
 //! `a3s-box top` command — Display running processes in a box.
 //!
 //! Convenience wrapper that runs `ps` inside the box via the exec channel.
+//! Anything after the box name is passed to `ps`, as with `docker top`.
+//!
+//! `--by-owner` asks guest init for its process tree instead of running `ps`
+//! and groups processes by the container main, service, exec, or PTY session
+//! that started them, counting jobs orphaned by an exited shell against it.
 
 use clap::{Args, ValueEnum};
 use serde::Serialize;
 
 #[cfg(not(windows))]
-use a3s_box_core::exec::{ExecRequest, DEFAULT_EXEC_TIMEOUT_NS};
+use a3s_box_core::exec::{
+    ExecOutput, ExecRequest, GuestProcess, GuestProcessTree, ProcessOwner, ProcessOwnerKind,
+    DEFAULT_EXEC_TIMEOUT_NS,
+};
 #[cfg(not(windows))]
 use a3s_box_runtime::ExecClient;
 
@@ -30,8 +38,17 @@ pub struct TopArgs {
     #[arg(long, value_enum, default_value_t = TopFormat::Table)]
     pub format: TopFormat,
 
-    /// Arguments to pass to ps (default: aux)
-    #[arg(last = true)]
+    /// Group processes by the main process, service, exec, or PTY session
+    /// that started them, orphans included
+    #[arg(long)]
+    pub by_owner: bool,
+
+    /// Refresh every second until interrupted
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Options passed to ps, e.g. `-eo pid,user,pcpu,args` (default: aux)
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub ps_args: Vec<String>,
 }
 
@@ -52,6 +69,24 @@ struct TopProcess {
     command: String,
 }
 
+/// Processes started for one main process, service, exec, or PTY session.
+#[cfg(not(windows))]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct OwnerGroup {
+    kind: Option<ProcessOwnerKind>,
+    name: Option<String>,
+    root_pid: Option<u32>,
+    orphans: usize,
+    processes: Vec<GuestProcess>,
+}
+
+#[cfg(not(windows))]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct OwnerReport {
+    groups: Vec<OwnerGroup>,
+    orphans_reaped: u64,
+}
+
 #[cfg(windows)]
 pub async fn execute(_args: TopArgs) -> Result<(), Box<dyn std::error::Error>> {
     Err(crate::platform::unsupported_command(
@@ -70,19 +105,61 @@ pub async fn execute(args: TopArgs) -> Result<(), Box<dyn std::error::Error>> {
     )
     .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
 
-    let client = ExecClient::connect(&exec_socket_path).await?;
-
-    let ps_args = if args.ps_args.is_empty() && args.format == TopFormat::Json {
-        JSON_PS_ARGS.iter().map(|s| s.to_string()).collect()
-    } else if args.ps_args.is_empty() {
-        DEFAULT_PS_ARGS.iter().map(|s| s.to_string()).collect()
-    } else {
-        args.ps_args
-    };
+    if args.by_owner && !args.ps_args.is_empty() {
+        return Err("--by-owner does not run ps; drop the ps options".into());
+    }
 
-    let mut cmd = vec!["ps".to_string()];
-    cmd.extend(ps_args);
+    let client = ExecClient::connect(&exec_socket_path).await?;
+    let cmd = build_ps_command(args.format, &args.ps_args);
+
+    loop {
+        if args.by_owner {
+            let report = group_by_owner(client.process_tree().await?);
+            if args.stream && args.format == TopFormat::Table {
+                print!("\x1B[2J\x1B[H");
+            }
+            match args.format {
+                TopFormat::Table => print_owner_groups(&report),
+                TopFormat::Json => println!("{}", serde_json::to_string(&report)?),
+            }
+            if !args.stream {
+                return Ok(());
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            continue;
+        }
+
+        let output = run_in_guest(&client, cmd.clone()).await?;
+        if !output.stderr.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            eprint!("{stderr}");
+        }
+        if output.exit_code != 0 {
+            std::process::exit(output.exit_code);
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        if args.stream && args.format == TopFormat::Table {
+            // Use ANSI escape to move cursor to top and clear
+            print!("\x1B[2J\x1B[H");
+        }
+        match args.format {
+            TopFormat::Table => print!("{stdout}"),
+            TopFormat::Json => print_top_json(&stdout)?,
+        }
+
+        if !args.stream {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
 
+#[cfg(not(windows))]
+async fn run_in_guest(
+    client: &ExecClient,
+    cmd: Vec<String>,
+) -> Result<ExecOutput, Box<dyn std::error::Error>> {
     let request = ExecRequest {
         request_id: None,
         cmd,
@@ -95,25 +172,81 @@ pub async fn execute(args: TopArgs) -> Result<(), Box<dyn std::error::Error>> {
         user: None,
         streaming: false,
     };
+    Ok(client.exec_command(&request).await?)
+}
 
-    let output = client.exec_command(&request).await?;
-
-    if !output.stderr.is_empty() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprint!("{stderr}");
+/// Group the process tree by owner: the main process first, then services,
+/// execs, and PTY sessions in start order, and unattributed processes last.
+#[cfg(not(windows))]
+fn group_by_owner(tree: GuestProcessTree) -> OwnerReport {
+    let mut grouped: Vec<(Option<ProcessOwner>, Vec<GuestProcess>)> = Vec::new();
+    for process in tree.processes {
+        match grouped
+            .iter_mut()
+            .find(|(owner, _)| *owner == process.owner)
+        {
+            Some((_, processes)) => processes.push(process),
+            None => grouped.push((process.owner.clone(), vec![process])),
+        }
     }
-
-    if output.exit_code != 0 {
-        std::process::exit(output.exit_code);
+    grouped.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+
+    let groups = grouped
+        .into_iter()
+        .map(|(owner, processes)| OwnerGroup {
+            kind: owner.as_ref().map(|owner| owner.kind),
+            root_pid: owner.as_ref().map(|owner| owner.root_pid),
+            name: owner.and_then(|owner| owner.name),
+            orphans: processes.iter().filter(|p| p.orphaned).count(),
+            processes,
+        })
+        .collect();
+    OwnerReport {
+        groups,
+        orphans_reaped: tree.orphans_reaped,
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    match args.format {
-        TopFormat::Table => print!("{stdout}"),
-        TopFormat::Json => print_top_json(&stdout)?,
+#[cfg(not(windows))]
+fn print_owner_groups(report: &OwnerReport) {
+    println!(
+        "{:<8} {:<20} {:>8} {:>5} {:>7}  COMMAND",
+        "OWNER", "NAME", "ROOT", "PROCS", "ORPHANS"
+    );
+    for group in &report.groups {
+        let kind = match group.kind {
+            Some(ProcessOwnerKind::Main) => "main",
+            Some(ProcessOwnerKind::Service) => "service",
+            Some(ProcessOwnerKind::Exec) => "exec",
+            Some(ProcessOwnerKind::Pty) => "pty",
+            None => "-",
+        };
+        // The root stands in for the group; once it has exited, its oldest
+        // remaining descendant does.
+        let command = group
+            .processes
+            .iter()
+            .find(|p| Some(p.pid) == group.root_pid)
+            .or_else(|| group.processes.first())
+            .map(|p| p.command.as_str())
+            .unwrap_or("-");
+        let root = group
+            .root_pid
+            .map_or_else(|| "-".to_string(), |pid| pid.to_string());
+        println!(
+            "{:<8} {:<20} {:>8} {:>5} {:>7}  {}",
+            kind,
+            group.name.as_deref().unwrap_or("-"),
+            root,
+            group.processes.len(),
+            group.orphans,
+            command
+        );
     }
-
-    Ok(())
+    println!("Orphans reaped since boot: {}", report.orphans_reaped);
 }
 
 #[cfg(not(windows))]
@@ -201,7 +334,7 @@ fn parse_percent(value: &str) -> Option<f32> {
 }
 
 /// Build the ps command from user-provided arguments or defaults.
-#[cfg(all(test, not(windows)))]
+#[cfg(not(windows))]
 fn build_ps_command(format: TopFormat, ps_args: &[String]) -> Vec<String> {
     let mut cmd = vec!["ps".to_string()];
     if ps_args.is_empty() && format == TopFormat::Json {
@@ -277,4 +410,72 @@ mod tests {
         assert_eq!(rows[0].elapsed.as_deref(), Some("00:01"));
         assert_eq!(rows[0].command, "worker --serve");
     }
+
+    #[test]
+    fn test_ps_options_follow_the_box_name() {
+        use clap::Parser;
+
+        let cli = crate::commands::Cli::try_parse_from([
+            "a3s-box",
+            "top",
+            "web",
+            "--stream",
+            "-eo",
+            "pid,user,args",
+        ])
+        .unwrap();
+        let Some(crate::commands::Command::Top(args)) = cli.command else {
+            panic!("expected top");
+        };
+        assert!(args.stream);
+        assert_eq!(args.ps_args, ["-eo", "pid,user,args"]);
+    }
+
+    #[test]
+    fn test_group_by_owner_counts_orphans() {
+        let owned =
+            |pid: u32, kind: ProcessOwnerKind, root_pid: u32, orphaned: bool| GuestProcess {
+                pid,
+                ppid: if orphaned { 1 } else { root_pid },
+                pgid: root_pid,
+                sid: 0,
+                command: format!("cmd-{pid}"),
+                owner: Some(ProcessOwner {
+                    kind,
+                    root_pid,
+                    name: None,
+                }),
+                orphaned,
+            };
+        let mut init = owned(1, ProcessOwnerKind::Main, 1, false);
+        init.owner = None;
+
+        let report = group_by_owner(GuestProcessTree {
+            processes: vec![
+                init,
+                owned(10, ProcessOwnerKind::Main, 10, false),
+                owned(21, ProcessOwnerKind::Exec, 20, true),
+                owned(22, ProcessOwnerKind::Exec, 20, true),
+                owned(30, ProcessOwnerKind::Service, 30, false),
+                owned(31, ProcessOwnerKind::Service, 30, false),
+            ],
+            orphans_reaped: 4,
+        });
+
+        let summary: Vec<_> = report
+            .groups
+            .iter()
+            .map(|g| (g.kind, g.root_pid, g.processes.len(), g.orphans))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some(ProcessOwnerKind::Main), Some(10), 1, 0),
+                (Some(ProcessOwnerKind::Service), Some(30), 2, 0),
+                (Some(ProcessOwnerKind::Exec), Some(20), 2, 2),
+                (None, None, 1, 0),
+            ]
+        );
+        assert_eq!(report.orphans_reaped, 4);
+    }
 }
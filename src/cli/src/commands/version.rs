@@ -6,6 +6,12 @@ use clap::Args;
 pub struct VersionArgs;
 
 pub async fn execute(_args: VersionArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if crate::output::structured().is_some() {
+        return crate::output::emit(
+            "Version",
+            serde_json::json!({ "version": a3s_box_core::VERSION }),
+        );
+    }
     println!("{}", version_line());
     Ok(())
 }
@@ -1,12 +1,25 @@
 //! `a3s-box volume` subcommands — Manage named volumes.
 //!
 //! Provides create/ls/rm/inspect/prune for persistent named volumes
-//! that can be shared across box instances.
-
-use a3s_box_core::volume::VolumeConfig;
-use a3s_box_runtime::VolumeStore;
+//! that can be shared across box instances, and export/import of volume
+//! contents as tar archives for backup and host-to-host migration. Mount tracking (`in_use_by`) is
+//! reconciled against the box state file, so a box removed without detaching
+//! does not pin its volumes forever.
+
+use a3s_box_core::volume::{BlockVolume, SocketMount, VolumeConfig, MAX_SOCKET_MOUNTS};
+use std::path::{Path, PathBuf};
+
+use a3s_box_runtime::volume::{
+    export_volume_archive, import_volume_archive, VolumeArchiveMetadata,
+};
+use a3s_box_runtime::{volume_driver, VolumeAttachment, VolumeStore};
 use clap::{Args, Subcommand};
 
+use crate::output;
+use crate::resolve;
+use crate::state::{BoxRecord, StateFile};
+use crate::status;
+
 /// Manage volumes.
 #[derive(Args)]
 pub struct VolumeArgs {
@@ -27,6 +40,10 @@ pub enum VolumeCommand {
     Inspect(InspectArgs),
     /// Remove all unused volumes
     Prune(PruneArgs),
+    /// Write a volume's contents to a tar archive
+    Export(ExportArgs),
+    /// Restore a volume from a tar archive
+    Import(ImportArgs),
 }
 
 #[derive(Args)]
@@ -34,13 +51,18 @@ pub struct CreateArgs {
     /// Volume name
     pub name: String,
 
-    /// Volume driver
-    #[arg(long, default_value = "local")]
+    /// Volume driver: local, tmpfs, nfs, or block
+    #[arg(short = 'd', long, default_value = "local")]
     pub driver: String,
 
     /// Set metadata labels (KEY=VALUE), can be repeated
     #[arg(short = 'l', long = "label")]
     pub labels: Vec<String>,
+
+    /// Set driver options (KEY=VALUE), can be repeated.
+    /// local: size; tmpfs: size, mode; nfs: addr, device, o; block: device, fstype
+    #[arg(short = 'o', long = "opt")]
+    pub opts: Vec<String>,
 }
 
 #[derive(Args)]
@@ -48,6 +70,11 @@ pub struct LsArgs {
     /// Only display volume names
     #[arg(short, long)]
     pub quiet: bool,
+
+    /// Filter output (dangling=true|false, driver=NAME, label=KEY[=VALUE],
+    /// name=SUBSTRING), can be repeated
+    #[arg(short, long = "filter")]
+    pub filters: Vec<String>,
 }
 
 #[derive(Args)]
@@ -73,6 +100,36 @@ pub struct PruneArgs {
     pub force: bool,
 }
 
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Volume name
+    pub name: String,
+
+    /// Write the archive to FILE instead of stdout. The archive is
+    /// zstd-compressed unless FILE ends in `.tar`
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Export while BOX is running: flush its writes with `sync` over the
+    /// exec channel, then pause it until the export finishes
+    #[arg(long, value_name = "BOX")]
+    pub from_box: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Volume name (created with the archive's labels if missing)
+    pub name: String,
+
+    /// Archive to read (default: stdin); plain or zstd-compressed tar
+    #[arg(value_name = "FILE")]
+    pub input: Option<PathBuf>,
+
+    /// Import into a volume that already has data, overwriting files
+    #[arg(short, long)]
+    pub force: bool,
+}
+
 /// Dispatch volume subcommands.
 pub async fn execute(args: VolumeArgs) -> Result<(), Box<dyn std::error::Error>> {
     match args.command {
@@ -81,6 +138,8 @@ pub async fn execute(args: VolumeArgs) -> Result<(), Box<dyn std::error::Error>>
         VolumeCommand::Rm(a) => execute_rm(a).await,
         VolumeCommand::Inspect(a) => execute_inspect(a).await,
         VolumeCommand::Prune(a) => execute_prune(a).await,
+        VolumeCommand::Export(a) => execute_export(a).await,
+        VolumeCommand::Import(a) => execute_import(a).await,
     }
 }
 
@@ -97,17 +156,145 @@ async fn execute_create(args: CreateArgs) -> Result<(), Box<dyn std::error::Erro
             .ok_or_else(|| format!("Invalid label (expected KEY=VALUE): {label}"))?;
         config.labels.insert(key.to_string(), value.to_string());
     }
+    apply_driver_options(&mut config, &args.opts)?;
 
     store.create(config)?;
     println!("{}", args.name);
     Ok(())
 }
 
+/// Validate `--opt` values against the volume's driver and record them on
+/// `config`.
+fn apply_driver_options(
+    config: &mut VolumeConfig,
+    opts: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let driver = volume_driver(&config.driver)?;
+    for opt in opts {
+        let (key, value) = opt
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid option (expected KEY=VALUE): {opt}"))?;
+        if !driver.options().contains(&key) {
+            return Err(format!(
+                "unsupported option '{key}' for the {} driver (supported: {})",
+                driver.name(),
+                driver.options().join(", ")
+            )
+            .into());
+        }
+        if key == "size" {
+            config.size_limit = super::common::parse_memory_bytes(value)
+                .map_err(|e| format!("Invalid size option: {e}"))?;
+        }
+        config.options.insert(key.to_string(), value.to_string());
+    }
+    driver.validate(config)?;
+    Ok(())
+}
+
+/// A `volume ls` filter.
+#[derive(Debug, PartialEq, Eq)]
+enum VolumeFilter {
+    Dangling(bool),
+    Driver(String),
+    Label(String, Option<String>),
+    Name(String),
+}
+
+fn parse_volume_filter(filter: &str) -> Result<VolumeFilter, String> {
+    let (key, value) = filter
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid filter '{filter}' (expected KEY=VALUE)"))?;
+    match key {
+        "dangling" => match value {
+            "true" | "1" => Ok(VolumeFilter::Dangling(true)),
+            "false" | "0" => Ok(VolumeFilter::Dangling(false)),
+            _ => Err(format!(
+                "Invalid dangling filter '{value}' (expected true or false)"
+            )),
+        },
+        "driver" => Ok(VolumeFilter::Driver(value.to_string())),
+        "label" => Ok(match value.split_once('=') {
+            Some((label, expected)) => {
+                VolumeFilter::Label(label.to_string(), Some(expected.to_string()))
+            }
+            None => VolumeFilter::Label(value.to_string(), None),
+        }),
+        "name" => Ok(VolumeFilter::Name(value.to_string())),
+        _ => Err(format!(
+            "Invalid filter '{key}' (supported: dangling, driver, label, name)"
+        )),
+    }
+}
+
+fn volume_matches(config: &VolumeConfig, users: &[&BoxRecord], filter: &VolumeFilter) -> bool {
+    match filter {
+        VolumeFilter::Dangling(dangling) => users.is_empty() == *dangling,
+        VolumeFilter::Driver(driver) => config.driver == *driver,
+        VolumeFilter::Label(key, expected) => match (config.labels.get(key), expected) {
+            (Some(value), Some(expected)) => value == expected,
+            (Some(_), None) => true,
+            (None, _) => false,
+        },
+        VolumeFilter::Name(name) => config.name.contains(name.as_str()),
+    }
+}
+
+/// Boxes in the state file that currently use `config`.
+///
+/// Stale `in_use_by` entries (boxes that no longer exist) are ignored.
+fn volume_users<'a>(config: &VolumeConfig, state: &'a StateFile) -> Vec<&'a BoxRecord> {
+    config
+        .in_use_by
+        .iter()
+        .filter_map(|id| state.find_by_id(id))
+        .collect()
+}
+
+/// Drop `in_use_by` entries for boxes that no longer exist.
+pub(super) fn reconcile_volume_users(
+    store: &VolumeStore,
+    state: &StateFile,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for config in store.list()? {
+        if config
+            .in_use_by
+            .iter()
+            .any(|id| state.find_by_id(id).is_none())
+        {
+            store.modify(&config.name, |config| {
+                config.in_use_by.retain(|id| state.find_by_id(id).is_some())
+            })?;
+        }
+    }
+    Ok(())
+}
+
 async fn execute_ls(args: LsArgs) -> Result<(), Box<dyn std::error::Error>> {
     let store = VolumeStore::default_path()?;
+    let state = StateFile::load_default()?;
+    let filters = args
+        .filters
+        .iter()
+        .map(|filter| parse_volume_filter(filter))
+        .collect::<Result<Vec<_>, _>>()?;
     let mut volumes = store.list()?;
+    volumes.retain(|vol| {
+        let users = volume_users(vol, &state);
+        filters
+            .iter()
+            .all(|filter| volume_matches(vol, &users, filter))
+    });
     volumes.sort_by(|a, b| a.name.cmp(&b.name));
 
+    if crate::output::structured().is_some() {
+        let rows = volumes
+            .iter()
+            .map(|vol| inspect_json(vol, &volume_users(vol, &state)))
+            .collect::<Vec<_>>();
+        return crate::output::emit("VolumeList", rows);
+    }
+
     if args.quiet {
         for vol in &volumes {
             println!("{}", vol.name);
@@ -120,10 +307,15 @@ async fn execute_ls(args: LsArgs) -> Result<(), Box<dyn std::error::Error>> {
     table.set_header(vec!["DRIVER", "VOLUME NAME", "MOUNT POINT", "IN USE BY"]);
 
     for vol in &volumes {
-        let in_use = if vol.in_use_by.is_empty() {
+        let users = volume_users(vol, &state);
+        let in_use = if users.is_empty() {
             "-".to_string()
         } else {
-            format!("{} box(es)", vol.in_use_by.len())
+            users
+                .iter()
+                .map(|record| record.name.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
         };
         table.add_row(vec![
             vol.driver.clone(),
@@ -143,6 +335,8 @@ async fn execute_rm(args: RmArgs) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let store = VolumeStore::default_path()?;
+    let state = StateFile::load_default()?;
+    reconcile_volume_users(&store, &state)?;
 
     let mut errors = Vec::new();
     for name in &args.names {
@@ -162,24 +356,59 @@ async fn execute_rm(args: RmArgs) -> Result<(), Box<dyn std::error::Error>> {
 
 async fn execute_inspect(args: InspectArgs) -> Result<(), Box<dyn std::error::Error>> {
     let store = VolumeStore::default_path()?;
+    let state = StateFile::load_default()?;
 
     let config = store
         .get(&args.name)?
         .ok_or_else(|| format!("volume '{}' not found", args.name))?;
 
-    // Match `docker volume inspect`: a top-level JSON array of PascalCase objects
-    // (Mountpoint, Scope, etc.), not the raw snake_case VolumeConfig.
-    let output = serde_json::json!([{
+    let output = serde_json::json!([inspect_json(&config, &volume_users(&config, &state))]);
+    if crate::output::structured().is_some() {
+        return crate::output::emit("VolumeInspect", output);
+    }
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Match `docker volume inspect`: PascalCase objects (Mountpoint, Scope, etc.),
+/// not the raw snake_case VolumeConfig, plus the boxes mounting the volume.
+fn inspect_json(config: &VolumeConfig, users: &[&BoxRecord]) -> serde_json::Value {
+    let size = dir_size(std::path::Path::new(&config.mount_point));
+    serde_json::json!({
         "Name": config.name,
         "Driver": config.driver,
         "Mountpoint": config.mount_point,
         "Scope": "local",
         "Labels": config.labels,
-        "Options": serde_json::Map::new(),
+        "Options": config.options,
         "CreatedAt": config.created_at,
-    }]);
-    println!("{}", serde_json::to_string_pretty(&output)?);
-    Ok(())
+        "UsageData": {
+            "Size": size,
+            "RefCount": users.len(),
+        },
+        "UsedBy": users
+            .iter()
+            .map(|record| serde_json::json!({
+                "Id": record.id,
+                "Name": record.name,
+                "Status": record.status,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn dir_size(path: &std::path::Path) -> u64 {
+    let mut total = 0;
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            match entry.metadata() {
+                Ok(meta) if meta.is_dir() => total += dir_size(&entry.path()),
+                Ok(meta) => total += meta.len(),
+                Err(_) => {}
+            }
+        }
+    }
+    total
 }
 
 async fn execute_prune(args: PruneArgs) -> Result<(), Box<dyn std::error::Error>> {
@@ -197,17 +426,267 @@ async fn execute_prune(args: PruneArgs) -> Result<(), Box<dyn std::error::Error>
     }
 
     let store = VolumeStore::default_path()?;
-    let pruned = store.prune()?;
-
-    if pruned.is_empty() {
-        println!("Total reclaimed space: 0B");
-    } else {
+    let state = StateFile::load_default()?;
+    reconcile_volume_users(&store, &state)?;
+    let sizes: std::collections::HashMap<String, u64> = store
+        .list()?
+        .into_iter()
+        .filter(|vol| !vol.is_in_use())
+        .map(|vol| {
+            let size = dir_size(std::path::Path::new(&vol.mount_point));
+            (vol.name, size)
+        })
+        .collect();
+    let mut pruned = store.prune()?;
+    pruned.sort();
+
+    if !pruned.is_empty() {
+        println!("Deleted Volumes:");
         for name in &pruned {
             println!("{name}");
         }
-        println!("Total reclaimed space: {} volume(s)", pruned.len());
+        println!();
+    }
+    let reclaimed: u64 = pruned.iter().filter_map(|name| sizes.get(name)).sum();
+    println!("Total reclaimed space: {}", output::format_bytes(reclaimed));
+
+    Ok(())
+}
+
+/// Host directory holding a volume's data, for drivers that have one.
+///
+/// NFS volumes are mounted on demand; callers release them with
+/// [`release_if_unused`] when done.
+fn volume_data_dir(config: &VolumeConfig) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    match volume_driver(&config.driver)?.attach(config)? {
+        VolumeAttachment::HostDir(path) => Ok(path),
+        _ => Err(format!(
+            "volume '{}' uses the {} driver, which keeps no data on the host to archive",
+            config.name, config.driver
+        )
+        .into()),
+    }
+}
+
+async fn execute_export(args: ExportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::IsTerminal;
+
+    let store = VolumeStore::default_path()?;
+    let state = StateFile::load_default()?;
+    reconcile_volume_users(&store, &state)?;
+    let config = store
+        .get(&args.name)?
+        .ok_or_else(|| format!("volume '{}' not found", args.name))?;
+    if args.output.is_none() && std::io::stdout().is_terminal() {
+        return Err("refusing to write a volume archive to a terminal; pass -o FILE".into());
+    }
+
+    let from_box = match args.from_box.as_deref() {
+        Some(query) => {
+            let record = resolve::resolve(&state, query)?;
+            if !config.in_use_by.contains(&record.id) {
+                return Err(format!(
+                    "box {} does not mount volume '{}'",
+                    record.name, config.name
+                )
+                .into());
+            }
+            Some(record)
+        }
+        None => None,
+    };
+    if let Some(writer) = volume_users(&config, &state)
+        .into_iter()
+        .find(|user| status::is_active(user) && from_box.is_none_or(|b| b.id != user.id))
+    {
+        return Err(format!(
+            "volume '{}' is in use by running box {}; stop it or pass --from-box {} to pause it during the export",
+            config.name, writer.name, writer.name
+        )
+        .into());
+    }
+
+    let dir = volume_data_dir(&config)?;
+    let paused_pid = match from_box {
+        Some(record) => quiesce_box(record).await.inspect_err(|_| {
+            release_if_unused(&store, &config.name);
+        })?,
+        None => None,
+    };
+    let result = write_volume_export(&dir, &config, args.output.as_deref());
+    if let Some(pid) = paused_pid {
+        resume_box(pid);
+    }
+    release_if_unused(&store, &config.name);
+    let entries = result?;
+    if let Some(path) = &args.output {
+        eprintln!(
+            "Exported volume '{}' ({entries} entries) to {}",
+            config.name,
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+fn write_volume_export(
+    dir: &Path,
+    config: &VolumeConfig,
+    output: Option<&Path>,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let metadata = VolumeArchiveMetadata::from_config(config);
+    let Some(path) = output.filter(|path| *path != Path::new("-")) else {
+        let stdout = std::io::stdout().lock();
+        return Ok(export_volume_archive(dir, &metadata, stdout, true)?);
+    };
+
+    let compress = path.extension().is_none_or(|ext| ext != "tar");
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    let mut writer = std::io::BufWriter::new(file);
+    match export_volume_archive(dir, &metadata, &mut writer, compress) {
+        Ok(entries) => {
+            use std::io::Write;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+            Ok(entries)
+        }
+        Err(error) => {
+            drop(writer);
+            let _ = std::fs::remove_file(path);
+            Err(error.into())
+        }
+    }
+}
+
+/// Flush a running box's writes and pause it for a consistent export.
+///
+/// Returns the shim PID to resume afterwards, or `None` when the box was
+/// already paused (and is left paused).
+#[cfg(unix)]
+async fn quiesce_box(record: &BoxRecord) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+    use a3s_box_core::exec::{ExecRequest, DEFAULT_EXEC_TIMEOUT_NS};
+
+    if record.isolation.is_sandbox() {
+        return Err("--from-box supports MicroVM boxes only".into());
+    }
+    let pid = crate::lifecycle::require_live_pid(record, "export from")?;
+    if record.status == "paused" {
+        return Ok(None);
+    }
+
+    let exec_socket = crate::socket_paths::require_runtime_socket(
+        record,
+        crate::socket_paths::RuntimeSocket::Exec,
+    )?;
+    let client = a3s_box_runtime::ExecClient::connect(&exec_socket).await?;
+    let output = client
+        .exec_command(&ExecRequest {
+            request_id: None,
+            cmd: vec!["sync".to_string()],
+            timeout_ns: DEFAULT_EXEC_TIMEOUT_NS,
+            env: vec![],
+            working_dir: None,
+            rootfs: None,
+            stdin: None,
+            stdin_streaming: false,
+            user: None,
+            streaming: false,
+        })
+        .await?;
+    if output.exit_code != 0 {
+        return Err(format!(
+            "sync in box {} failed (exit {}): {}",
+            record.name,
+            output.exit_code,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    crate::process::send_signal(pid, libc::SIGSTOP)
+        .map_err(|e| format!("Failed to pause box {} with SIGSTOP: {e}", record.name))?;
+    Ok(Some(pid))
+}
+
+#[cfg(windows)]
+async fn quiesce_box(_record: &BoxRecord) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+    Err(crate::platform::unsupported_command(
+        "volume export --from-box",
+        "host process suspension support",
+    ))
+}
+
+#[cfg(unix)]
+fn resume_box(pid: u32) {
+    if let Err(error) = crate::process::send_signal(pid, libc::SIGCONT) {
+        eprintln!("a3s-box: warning: failed to resume box (pid {pid}) after export: {error}");
+    }
+}
+
+#[cfg(windows)]
+fn resume_box(_pid: u32) {}
+
+async fn execute_import(args: ImportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let store = VolumeStore::default_path()?;
+    let state = StateFile::load_default()?;
+    reconcile_volume_users(&store, &state)?;
+
+    let (config, created) = match store.get(&args.name)? {
+        Some(config) => (config, false),
+        None => (store.create(VolumeConfig::new(&args.name, ""))?, true),
+    };
+    let result = import_into_volume(&store, &state, &config, created, &args);
+    release_if_unused(&store, &config.name);
+    if result.is_err() && created {
+        let _ = store.remove(&config.name, true);
     }
+    result
+}
 
+fn import_into_volume(
+    store: &VolumeStore,
+    state: &StateFile,
+    config: &VolumeConfig,
+    created: bool,
+    args: &ImportArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = volume_data_dir(config)?;
+    if let Some(user) = volume_users(config, state)
+        .into_iter()
+        .find(|user| status::is_active(user))
+    {
+        return Err(format!(
+            "volume '{}' is in use by running box {}; stop it before importing",
+            config.name, user.name
+        )
+        .into());
+    }
+    let has_data = std::fs::read_dir(&dir).is_ok_and(|mut entries| entries.next().is_some());
+    if has_data && !args.force {
+        return Err(format!(
+            "volume '{}' already contains data; pass --force to overwrite",
+            config.name
+        )
+        .into());
+    }
+
+    let metadata = match args.input.as_deref().filter(|path| *path != Path::new("-")) {
+        Some(path) => {
+            let file = std::fs::File::open(path)
+                .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+            import_volume_archive(file, &dir)?
+        }
+        None => import_volume_archive(std::io::stdin().lock(), &dir)?,
+    };
+
+    if created {
+        store.modify(&config.name, |config| {
+            config.labels = metadata.labels.clone();
+            config.size_limit = metadata.size_limit;
+        })?;
+    }
+    println!("{}", config.name);
     Ok(())
 }
 
@@ -251,9 +730,17 @@ pub fn resolve_named_volume(
     // under the store's cross-process lock, so two concurrent first-time
     // `run -v name:/path` share one volume instead of racing to an error.
     let config = store.get_or_create(VolumeConfig::new(volume_name, ""))?;
+    let VolumeAttachment::HostDir(host_dir) = volume_driver(&config.driver)?.attach(&config)?
+    else {
+        return Err(format!(
+            "volume '{volume_name}' uses the {} driver; attach it with --mount type=volume,source={volume_name},target=PATH",
+            config.driver
+        )
+        .into());
+    };
 
     // Replace the named volume with the host mount point path
-    let mut resolved = config.mount_point.clone();
+    let mut resolved = host_dir.to_string_lossy().into_owned();
     for part in &parts[1..] {
         resolved.push(':');
         resolved.push_str(part);
@@ -262,6 +749,282 @@ pub fn resolve_named_volume(
     Ok((resolved, Some(volume_name.to_string())))
 }
 
+/// Mounts collected from `-v`, `--tmpfs`, and `--mount`, split by how the box
+/// attaches them.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct BoxMounts {
+    /// virtio-fs shares in `host:guest[:ro]` form.
+    pub volumes: Vec<String>,
+    /// Named volumes to attach to the box record.
+    pub volume_names: Vec<String>,
+    /// In-guest tmpfs mounts in `--tmpfs` form.
+    pub tmpfs: Vec<String>,
+    /// Block volumes attached as virtio-blk disks.
+    pub block_volumes: Vec<BlockVolume>,
+    /// Host Unix sockets relayed into the guest.
+    pub socket_mounts: Vec<SocketMount>,
+}
+
+/// A parsed `--mount` flag.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MountSpec {
+    kind: String,
+    source: Option<String>,
+    target: String,
+    read_only: bool,
+    driver: Option<String>,
+    volume_opts: Vec<String>,
+    tmpfs_size: Option<String>,
+    tmpfs_mode: Option<String>,
+}
+
+/// Parse Docker's `--mount type=...,source=...,target=...[,readonly]` syntax.
+fn parse_mount_spec(spec: &str) -> Result<MountSpec, String> {
+    let mut mount = MountSpec {
+        kind: "volume".to_string(),
+        ..Default::default()
+    };
+    let mut target = None;
+    for field in spec.split(',').filter(|field| !field.is_empty()) {
+        let (key, value) = match field.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (field, None),
+        };
+        match (key, value) {
+            ("type", Some(value)) => mount.kind = value.to_string(),
+            ("source" | "src", Some(value)) => mount.source = Some(value.to_string()),
+            ("target" | "destination" | "dst", Some(value)) => target = Some(value.to_string()),
+            ("readonly" | "ro", None | Some("true" | "1")) => mount.read_only = true,
+            ("readonly" | "ro", Some("false" | "0")) => mount.read_only = false,
+            ("driver" | "volume-driver", Some(value)) => mount.driver = Some(value.to_string()),
+            ("volume-opt", Some(value)) => mount.volume_opts.push(value.to_string()),
+            ("tmpfs-size", Some(value)) => mount.tmpfs_size = Some(value.to_string()),
+            ("tmpfs-mode", Some(value)) => mount.tmpfs_mode = Some(value.to_string()),
+            _ => return Err(format!("Invalid --mount field '{field}' in '{spec}'")),
+        }
+    }
+    mount.target = target.ok_or_else(|| format!("Invalid --mount '{spec}': target is required"))?;
+    if !mount.target.starts_with('/') {
+        return Err(format!(
+            "Invalid --mount '{spec}': target must be an absolute path"
+        ));
+    }
+    if !matches!(mount.kind.as_str(), "bind" | "volume" | "tmpfs" | "socket") {
+        return Err(format!(
+            "Invalid --mount type '{}' (supported: bind, volume, tmpfs, socket)",
+            mount.kind
+        ));
+    }
+    if mount.kind == "socket" && mount.read_only {
+        return Err(format!(
+            "Invalid --mount '{spec}': readonly does not apply to type=socket"
+        ));
+    }
+    if mount.kind != "volume" && (mount.driver.is_some() || !mount.volume_opts.is_empty()) {
+        return Err(format!(
+            "Invalid --mount '{spec}': driver and volume-opt need type=volume"
+        ));
+    }
+    Ok(mount)
+}
+
+/// Build a `--tmpfs` spec for `target`.
+fn tmpfs_spec(target: &str, size: Option<u64>, mode: Option<&str>, read_only: bool) -> String {
+    let options: Vec<String> = size
+        .map(|size| format!("size={size}"))
+        .into_iter()
+        .chain(mode.map(|mode| format!("mode={mode}")))
+        .chain(read_only.then(|| "ro".to_string()))
+        .collect();
+    if options.is_empty() {
+        target.to_string()
+    } else {
+        format!("{target}:{}", options.join(","))
+    }
+}
+
+/// Resolve `-v`, `--tmpfs`, and `--mount` flags into the mounts a box gets.
+///
+/// Named volumes are created on first use and attached through their driver,
+/// so an NFS volume is mounted on the host and a block volume is validated
+/// before the box boots.
+pub(crate) fn resolve_box_mounts(
+    volume_specs: &[String],
+    tmpfs_specs: &[String],
+    mount_specs: &[String],
+) -> Result<BoxMounts, Box<dyn std::error::Error>> {
+    let mut mounts = BoxMounts {
+        tmpfs: tmpfs_specs.to_vec(),
+        ..Default::default()
+    };
+    for spec in volume_specs {
+        let (resolved, name) = resolve_named_volume(spec)?;
+        mounts.volumes.push(resolved);
+        mounts.volume_names.extend(name);
+    }
+    if mount_specs.is_empty() {
+        return Ok(mounts);
+    }
+
+    let store = VolumeStore::default_path()?;
+    for spec in mount_specs {
+        let mount = parse_mount_spec(spec)?;
+        let ro_suffix = if mount.read_only { ":ro" } else { "" };
+        match mount.kind.as_str() {
+            "bind" => {
+                let source = mount
+                    .source
+                    .ok_or_else(|| format!("Invalid --mount '{spec}': source is required"))?;
+                mounts
+                    .volumes
+                    .push(format!("{source}:{}{ro_suffix}", mount.target));
+            }
+            "socket" => {
+                let source = mount
+                    .source
+                    .ok_or_else(|| format!("Invalid --mount '{spec}': source is required"))?;
+                if mounts.socket_mounts.len() == MAX_SOCKET_MOUNTS {
+                    return Err(format!(
+                        "Too many socket mounts (at most {MAX_SOCKET_MOUNTS} per box)"
+                    )
+                    .into());
+                }
+                validate_socket_source(&source)
+                    .map_err(|e| format!("Invalid --mount '{spec}': {e}"))?;
+                mounts.socket_mounts.push(SocketMount {
+                    source,
+                    target: mount.target,
+                });
+            }
+            "tmpfs" => {
+                let size = mount
+                    .tmpfs_size
+                    .as_deref()
+                    .map(super::common::parse_memory_bytes)
+                    .transpose()
+                    .map_err(|e| format!("Invalid tmpfs-size: {e}"))?;
+                mounts.tmpfs.push(tmpfs_spec(
+                    &mount.target,
+                    size,
+                    mount.tmpfs_mode.as_deref(),
+                    mount.read_only,
+                ));
+            }
+            _ => {
+                let name = mount
+                    .source
+                    .clone()
+                    .ok_or_else(|| format!("Invalid --mount '{spec}': source is required"))?;
+                let config = named_volume_for_mount(&store, &name, &mount)?;
+                match volume_driver(&config.driver)?.attach(&config)? {
+                    VolumeAttachment::HostDir(host_dir) => mounts.volumes.push(format!(
+                        "{}:{}{ro_suffix}",
+                        host_dir.display(),
+                        mount.target
+                    )),
+                    VolumeAttachment::Tmpfs { size_bytes, mode } => mounts.tmpfs.push(tmpfs_spec(
+                        &mount.target,
+                        size_bytes,
+                        mode.as_deref(),
+                        mount.read_only,
+                    )),
+                    VolumeAttachment::Block { source, fstype } => {
+                        ensure_block_volume_free(&store, &config, mount.read_only)?;
+                        mounts.block_volumes.push(BlockVolume {
+                            // virtio-blk serials are limited to 20 bytes.
+                            block_id: format!("a3s-blk{}", mounts.block_volumes.len()),
+                            source: source.to_string_lossy().into_owned(),
+                            target: mount.target.clone(),
+                            fstype,
+                            read_only: mount.read_only,
+                        });
+                    }
+                }
+                mounts.volume_names.push(name);
+            }
+        }
+    }
+    Ok(mounts)
+}
+
+/// Check that a `--mount type=socket` source is a host Unix socket.
+///
+/// libkrun connects to the path when the guest first uses the socket, so a
+/// typo would otherwise only surface as a failed connection inside the box.
+#[cfg(unix)]
+fn validate_socket_source(source: &str) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if !source.starts_with('/') {
+        return Err("socket source must be an absolute path".to_string());
+    }
+    match std::fs::metadata(source) {
+        Ok(metadata) if metadata.file_type().is_socket() => Ok(()),
+        Ok(_) => Err(format!("{source} is not a Unix socket")),
+        Err(e) => Err(format!("cannot access {source}: {e}")),
+    }
+}
+
+#[cfg(not(unix))]
+fn validate_socket_source(_source: &str) -> Result<(), String> {
+    Err("type=socket is not supported on this platform".to_string())
+}
+
+/// Get or create the named volume for a `--mount type=volume` flag.
+fn named_volume_for_mount(
+    store: &VolumeStore,
+    name: &str,
+    mount: &MountSpec,
+) -> Result<VolumeConfig, Box<dyn std::error::Error>> {
+    if let Some(existing) = store.get(name)? {
+        if let Some(driver) = mount.driver.as_deref() {
+            if driver != existing.driver {
+                return Err(format!(
+                    "volume '{name}' already exists with the {} driver, not {driver}",
+                    existing.driver
+                )
+                .into());
+            }
+        }
+        if !mount.volume_opts.is_empty() {
+            return Err(format!(
+                "volume '{name}' already exists; volume-opt only applies when it is created"
+            )
+            .into());
+        }
+        return Ok(existing);
+    }
+
+    let mut config = VolumeConfig::new(name, "");
+    config.driver = mount.driver.clone().unwrap_or_else(|| "local".to_string());
+    apply_driver_options(&mut config, &mount.volume_opts)?;
+    Ok(store.get_or_create(config)?)
+}
+
+/// A writable block volume may only be attached to one live box, since two
+/// guests mounting the same filesystem read-write corrupt it.
+fn ensure_block_volume_free(
+    store: &VolumeStore,
+    config: &VolumeConfig,
+    read_only: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if read_only || !config.is_in_use() {
+        return Ok(());
+    }
+    reconcile_volume_users(store, &StateFile::load_default()?)?;
+    if store
+        .get(&config.name)?
+        .is_some_and(|config| config.is_in_use())
+    {
+        return Err(format!(
+            "block volume '{}' is already attached to another box; mount it with readonly",
+            config.name
+        )
+        .into());
+    }
+    Ok(())
+}
+
 /// Attach named volumes to a box in the VolumeStore.
 pub fn attach_volumes(
     volume_names: &[String],
@@ -296,10 +1059,25 @@ pub fn detach_volumes(volume_names: &[String], box_id: &str) {
     if let Ok(store) = VolumeStore::default_path() {
         for name in volume_names {
             store.modify(name, |config| config.detach(box_id)).ok();
+            release_if_unused(&store, name);
         }
     }
 }
 
+/// Let the driver release host resources (e.g. unmount an NFS export) once
+/// the last box has detached.
+fn release_if_unused(store: &VolumeStore, name: &str) {
+    let Ok(Some(config)) = store.get(name) else {
+        return;
+    };
+    if config.is_in_use() {
+        return;
+    }
+    if let Err(e) = volume_driver(&config.driver).and_then(|driver| driver.release(&config)) {
+        tracing::warn!(volume = name, error = %e, "Failed to release volume");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -492,4 +1270,186 @@ mod tests {
         assert_eq!(resolved, "/host:/guest:ro");
         assert!(name.is_none());
     }
+
+    #[test]
+    fn test_apply_driver_options_sets_size_limit() {
+        let mut config = VolumeConfig::new("data", "");
+        apply_driver_options(&mut config, &["size=1g".to_string()]).unwrap();
+
+        assert_eq!(config.size_limit, 1024 * 1024 * 1024);
+        assert_eq!(config.options.get("size").unwrap(), "1g");
+        assert!(apply_driver_options(&mut config, &["type=nfs".to_string()]).is_err());
+        assert!(apply_driver_options(&mut config, &["size".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_apply_driver_options_checks_driver() {
+        let mut config = VolumeConfig::new("share", "");
+        config.driver = "nfs".to_string();
+        let err = apply_driver_options(&mut config, &["addr=10.0.0.5".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("-o device="), "got: {err}");
+        apply_driver_options(
+            &mut config,
+            &["addr=10.0.0.5".to_string(), "device=:/export".to_string()],
+        )
+        .unwrap();
+
+        config.driver = "ceph".to_string();
+        assert!(apply_driver_options(&mut config, &[]).is_err());
+    }
+
+    #[test]
+    fn test_volume_data_dir_requires_host_data() {
+        let (dir, store) = temp_store();
+        let local = store.create(VolumeConfig::new("workspace", "")).unwrap();
+        assert_eq!(
+            volume_data_dir(&local).unwrap(),
+            PathBuf::from(&local.mount_point)
+        );
+
+        let mut tmpfs = VolumeConfig::new("scratch", dir.path().to_str().unwrap());
+        tmpfs.driver = "tmpfs".to_string();
+        let err = volume_data_dir(&tmpfs).unwrap_err();
+        assert!(err.to_string().contains("keeps no data on the host"));
+    }
+
+    #[test]
+    fn test_parse_mount_spec() {
+        let mount = parse_mount_spec(
+            "type=volume,src=cache,dst=/cache,readonly,driver=nfs,volume-opt=addr=10.0.0.5",
+        )
+        .unwrap();
+        assert_eq!(mount.kind, "volume");
+        assert_eq!(mount.source.as_deref(), Some("cache"));
+        assert_eq!(mount.target, "/cache");
+        assert!(mount.read_only);
+        assert_eq!(mount.driver.as_deref(), Some("nfs"));
+        assert_eq!(mount.volume_opts, vec!["addr=10.0.0.5"]);
+
+        assert!(parse_mount_spec("type=volume,source=cache").is_err());
+        assert!(parse_mount_spec("type=volume,target=relative").is_err());
+        assert!(parse_mount_spec("type=npipe,target=/x").is_err());
+        assert!(parse_mount_spec("type=bind,source=/a,target=/b,driver=nfs").is_err());
+    }
+
+    #[test]
+    fn test_resolve_box_mounts_bind_and_tmpfs() {
+        let mounts = resolve_box_mounts(
+            &[],
+            &["/run".to_string()],
+            &[
+                "type=bind,source=/srv/data,target=/data,ro".to_string(),
+                "type=tmpfs,target=/scratch,tmpfs-size=64m,tmpfs-mode=1777".to_string(),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(mounts.volumes, vec!["/srv/data:/data:ro"]);
+        assert_eq!(
+            mounts.tmpfs,
+            vec!["/run", "/scratch:size=67108864,mode=1777"]
+        );
+        assert!(mounts.volume_names.is_empty());
+        assert!(mounts.block_volumes.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_box_mounts_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("agent.sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let socket = socket.to_str().unwrap();
+
+        let mounts = resolve_box_mounts(
+            &[],
+            &[],
+            &[format!("type=socket,src={socket},dst=/ssh-agent")],
+        )
+        .unwrap();
+        assert_eq!(
+            mounts.socket_mounts,
+            vec![SocketMount {
+                source: socket.to_string(),
+                target: "/ssh-agent".to_string(),
+            }]
+        );
+        assert!(mounts.volumes.is_empty());
+
+        let not_socket = dir.path().join("plain");
+        std::fs::write(&not_socket, "").unwrap();
+        for spec in [
+            format!("type=socket,src={},dst=/s", not_socket.display()),
+            "type=socket,src=agent.sock,dst=/s".to_string(),
+            format!("type=socket,src={socket},dst=/s,ro"),
+            "type=socket,dst=/s".to_string(),
+        ] {
+            assert!(
+                resolve_box_mounts(&[], &[], &[spec.clone()]).is_err(),
+                "{spec}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_volume_filter() {
+        assert_eq!(
+            parse_volume_filter("dangling=true").unwrap(),
+            VolumeFilter::Dangling(true)
+        );
+        assert_eq!(
+            parse_volume_filter("label=env=prod").unwrap(),
+            VolumeFilter::Label("env".to_string(), Some("prod".to_string()))
+        );
+        assert_eq!(
+            parse_volume_filter("label=env").unwrap(),
+            VolumeFilter::Label("env".to_string(), None)
+        );
+        assert!(parse_volume_filter("dangling=maybe").is_err());
+        assert!(parse_volume_filter("scope=local").is_err());
+    }
+
+    #[test]
+    fn test_volume_users_ignore_removed_boxes() {
+        use crate::test_helpers::fixtures::{make_record, setup_state};
+
+        let (_state_dir, state) =
+            setup_state(vec![make_record("box-1", "web", "running", Some(1))]);
+        let mut config = VolumeConfig::new("data", "");
+        config.attach("box-1");
+        config.attach("box-gone");
+
+        let users = volume_users(&config, &state);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name, "web");
+        assert!(!volume_matches(
+            &config,
+            &users,
+            &VolumeFilter::Dangling(true)
+        ));
+        assert!(volume_matches(&config, &[], &VolumeFilter::Dangling(true)));
+
+        let json = inspect_json(&config, &users);
+        assert_eq!(json["UsageData"]["RefCount"], 1);
+        assert_eq!(json["UsedBy"][0]["Name"], "web");
+    }
+
+    #[test]
+    fn test_reconcile_volume_users_drops_stale_entries() {
+        use crate::test_helpers::fixtures::{make_record, setup_state};
+
+        let (_dir, store) = temp_store();
+        let (_state_dir, state) =
+            setup_state(vec![make_record("box-1", "web", "running", Some(1))]);
+        store.create(VolumeConfig::new("data", "")).unwrap();
+        attach_volumes_with_store(&store, &["data".to_string()], "box-1").unwrap();
+        attach_volumes_with_store(&store, &["data".to_string()], "box-gone").unwrap();
+
+        reconcile_volume_users(&store, &state).unwrap();
+
+        assert_eq!(
+            store.get("data").unwrap().unwrap().in_use_by,
+            vec!["box-1".to_string()]
+        );
+    }
 }
@@ -1,6 +1,14 @@
-//! `a3s-box wait` command — Block until one or more boxes stop, then print exit codes.
+//! `a3s-box wait` command — Block until one or more boxes reach a condition,
+//! then print exit codes.
+//!
+//! Boxes are polled together. With `--all` (the default) the command returns
+//! once every box has reached the condition, with `--any` as soon as one has;
+//! exit codes print one per line in argument order. `--exit-code` turns the
+//! batch into a gate: the command exits with the first non-zero box exit code.
 
-use clap::Args;
+use std::time::{Duration, Instant};
+
+use clap::{Args, ValueEnum};
 
 use crate::process;
 use crate::resolve;
@@ -15,6 +23,26 @@ pub struct WaitArgs {
     #[arg(required = true)]
     pub boxes: Vec<String>,
 
+    /// What to wait for
+    #[arg(long, value_enum, default_value_t = WaitCondition::NotRunning)]
+    pub condition: WaitCondition,
+
+    /// Return as soon as any box reaches the condition
+    #[arg(long, conflicts_with = "all")]
+    pub any: bool,
+
+    /// Return once every box reaches the condition (default)
+    #[arg(long)]
+    pub all: bool,
+
+    /// Give up after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Exit with the first non-zero box exit code instead of 0
+    #[arg(long)]
+    pub exit_code: bool,
+
     /// Seconds between stderr keepalive messages while waiting (0 disables)
     #[arg(long, default_value_t = DEFAULT_HEARTBEAT_SECS)]
     pub heartbeat_interval: u64,
@@ -24,46 +52,161 @@ pub struct WaitArgs {
     pub no_heartbeat: bool,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WaitCondition {
+    /// The box has stopped; prints its exit code
+    NotRunning,
+    /// The box's health check reports healthy; prints nothing
+    Healthy,
+    /// The box has been removed; prints its last exit code
+    Removed,
+}
+
+/// Where one box stands against the condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WaitProgress {
+    Pending,
+    /// Reached, with the exit code to print (none for `healthy`).
+    Done(Option<i32>),
+}
+
+/// Per-box state carried between polls.
+#[derive(Debug, Default)]
+struct WaitTarget {
+    seen: bool,
+    last_exit_code: Option<i32>,
+    outcome: Option<Option<i32>>,
+}
+
 pub async fn execute(args: WaitArgs) -> Result<(), Box<dyn std::error::Error>> {
-    let heartbeat_interval = wait_heartbeat_interval(&args);
-    for query in &args.boxes {
-        wait_one(query, heartbeat_interval).await?;
+    let mut heartbeat = WaitHeartbeat::new(wait_heartbeat_interval(&args));
+    let deadline = args
+        .timeout
+        .map(|secs| Instant::now() + Duration::from_secs(secs));
+    let mut targets: Vec<WaitTarget> = args.boxes.iter().map(|_| WaitTarget::default()).collect();
+
+    loop {
+        let state = StateFile::load_default()?;
+        for (query, target) in args.boxes.iter().zip(&mut targets) {
+            if target.outcome.is_some() {
+                continue;
+            }
+            if let WaitProgress::Done(exit_code) =
+                poll_target(&state, query, args.condition, target)?
+            {
+                target.outcome = Some(exit_code);
+            }
+        }
+
+        let finished = targets.iter().filter(|t| t.outcome.is_some()).count();
+        if finished == targets.len() || (args.any && finished > 0) {
+            break;
+        }
+        let pending = args
+            .boxes
+            .iter()
+            .zip(&targets)
+            .filter(|(_, t)| t.outcome.is_none())
+            .map(|(query, _)| query.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(format!(
+                "timed out after {}s waiting for {pending}",
+                args.timeout.unwrap_or_default()
+            )
+            .into());
+        }
+        heartbeat.maybe_emit(&pending);
+        tokio::time::sleep(tokio::time::Duration::from_millis(WAIT_POLL_MILLIS)).await;
+    }
+
+    let outcomes: Vec<Option<i32>> = targets.iter().filter_map(|t| t.outcome).collect();
+    for exit_code in outcomes.iter().flatten() {
+        println!("{exit_code}");
+    }
+    if args.exit_code {
+        let code = aggregate_exit_code(&outcomes);
+        if code != 0 {
+            std::process::exit(code);
+        }
     }
     Ok(())
 }
 
-async fn wait_one(
+fn poll_target(
+    state: &StateFile,
     query: &str,
-    heartbeat_interval: Option<std::time::Duration>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut heartbeat = WaitHeartbeat::new(heartbeat_interval);
-    loop {
-        let state = StateFile::load_default()?;
-        let record = match resolve::resolve(&state, query) {
-            Ok(record) => record,
-            Err(error @ resolve::ResolveError::NotFound(_)) => {
-                if let Some(exit_code) = archived_wait_exit_code(query)? {
-                    println!("{exit_code}");
-                    return Ok(());
-                }
+    condition: WaitCondition,
+    target: &mut WaitTarget,
+) -> Result<WaitProgress, Box<dyn std::error::Error>> {
+    let record = match resolve::resolve(state, query) {
+        Ok(record) => record,
+        Err(error @ resolve::ResolveError::NotFound(_)) => {
+            if condition == WaitCondition::Healthy {
                 return Err(error.into());
             }
-            Err(error) => return Err(error.into()),
-        };
+            let archived = archived_wait_exit_code(query)?;
+            if condition == WaitCondition::Removed && target.seen {
+                return Ok(WaitProgress::Done(Some(
+                    archived.or(target.last_exit_code).unwrap_or(0),
+                )));
+            }
+            return match archived {
+                Some(exit_code) => Ok(WaitProgress::Done(Some(exit_code))),
+                None => Err(error.into()),
+            };
+        }
+        Err(error) => return Err(error.into()),
+    };
+    target.seen = true;
+    target.last_exit_code = record.exit_code;
+    condition_progress(record, condition).map_err(Into::into)
+}
 
-        match wait_poll_action(record) {
-            WaitPollAction::Finish(exit_code) => {
-                println!("{exit_code}");
-                return Ok(());
+fn condition_progress(
+    record: &BoxRecord,
+    condition: WaitCondition,
+) -> Result<WaitProgress, String> {
+    let stopped = match wait_poll_action(record) {
+        WaitPollAction::Finish(exit_code) => Some(exit_code),
+        WaitPollAction::Sleep => None,
+    };
+    match condition {
+        WaitCondition::NotRunning => Ok(match stopped {
+            Some(exit_code) => WaitProgress::Done(Some(exit_code)),
+            None => WaitProgress::Pending,
+        }),
+        WaitCondition::Healthy => {
+            if record.health_check.is_none() {
+                return Err(format!("box {} has no health check", record.name));
             }
-            WaitPollAction::Sleep => {
-                heartbeat.maybe_emit(query);
-                tokio::time::sleep(tokio::time::Duration::from_millis(WAIT_POLL_MILLIS)).await;
+            if let Some(exit_code) = stopped {
+                return Err(format!(
+                    "box {} stopped (exit code {exit_code}) before becoming healthy",
+                    record.name
+                ));
             }
+            Ok(if record.health_status == "healthy" {
+                WaitProgress::Done(None)
+            } else {
+                WaitProgress::Pending
+            })
         }
+        WaitCondition::Removed => Ok(WaitProgress::Pending),
     }
 }
 
+/// The first non-zero exit code, in argument order, or 0.
+fn aggregate_exit_code(outcomes: &[Option<i32>]) -> i32 {
+    outcomes
+        .iter()
+        .flatten()
+        .copied()
+        .find(|code| *code != 0)
+        .unwrap_or(0)
+}
+
 fn archived_wait_exit_code(query: &str) -> Result<Option<i32>, String> {
     Ok(crate::log_archive::resolve_archive(query)?.map(|archive| archive.exit_code.unwrap_or(0)))
 }
@@ -173,17 +316,105 @@ mod tests {
 
     #[test]
     fn test_wait_heartbeat_interval_can_be_disabled() {
-        assert!(wait_heartbeat_interval(&WaitArgs {
-            boxes: vec!["box".to_string()],
-            heartbeat_interval: 60,
-            no_heartbeat: true,
-        })
-        .is_none());
-        assert!(wait_heartbeat_interval(&WaitArgs {
+        let args = |heartbeat_interval, no_heartbeat| WaitArgs {
             boxes: vec!["box".to_string()],
-            heartbeat_interval: 0,
-            no_heartbeat: false,
-        })
-        .is_none());
+            condition: WaitCondition::NotRunning,
+            any: false,
+            all: false,
+            timeout: None,
+            exit_code: false,
+            heartbeat_interval,
+            no_heartbeat,
+        };
+        assert!(wait_heartbeat_interval(&args(60, true)).is_none());
+        assert!(wait_heartbeat_interval(&args(0, false)).is_none());
+        assert!(wait_heartbeat_interval(&args(60, false)).is_some());
+    }
+
+    #[test]
+    fn test_condition_healthy() {
+        let mut record = crate::test_helpers::fixtures::make_record(
+            "id",
+            "box",
+            "running",
+            Some(std::process::id()),
+        );
+        assert!(condition_progress(&record, WaitCondition::Healthy)
+            .unwrap_err()
+            .contains("no health check"));
+
+        record.health_check = Some(crate::state::HealthCheck {
+            cmd: vec!["true".to_string()],
+            interval_secs: 1,
+            timeout_secs: 1,
+            retries: 1,
+            start_period_secs: 0,
+        });
+        record.health_status = "starting".to_string();
+        assert_eq!(
+            condition_progress(&record, WaitCondition::Healthy),
+            Ok(WaitProgress::Pending)
+        );
+        record.health_status = "healthy".to_string();
+        assert_eq!(
+            condition_progress(&record, WaitCondition::Healthy),
+            Ok(WaitProgress::Done(None))
+        );
+
+        record.status = "stopped".to_string();
+        record.exit_code = Some(3);
+        assert!(condition_progress(&record, WaitCondition::Healthy)
+            .unwrap_err()
+            .contains("exit code 3"));
+    }
+
+    #[test]
+    fn test_condition_removed_waits_past_stop() {
+        let (_tmp, state) = crate::test_helpers::fixtures::setup_state(vec![{
+            let mut record =
+                crate::test_helpers::fixtures::make_record("id-1", "done", "stopped", None);
+            record.exit_code = Some(7);
+            record
+        }]);
+        let mut target = WaitTarget::default();
+        assert_eq!(
+            poll_target(&state, "done", WaitCondition::NotRunning, &mut target).unwrap(),
+            WaitProgress::Done(Some(7))
+        );
+        assert_eq!(
+            poll_target(&state, "done", WaitCondition::Removed, &mut target).unwrap(),
+            WaitProgress::Pending
+        );
+        assert_eq!(target.last_exit_code, Some(7));
+    }
+
+    #[test]
+    fn test_aggregate_exit_code_takes_first_failure() {
+        assert_eq!(aggregate_exit_code(&[Some(0), None, Some(0)]), 0);
+        assert_eq!(aggregate_exit_code(&[Some(0), Some(2), Some(1)]), 2);
+        assert_eq!(aggregate_exit_code(&[]), 0);
+    }
+
+    #[test]
+    fn test_any_conflicts_with_all() {
+        use clap::Parser;
+
+        let parse = |args: &[&str]| crate::commands::Cli::try_parse_from(args);
+        assert!(parse(&["a3s-box", "wait", "--any", "--all", "a", "b"]).is_err());
+        let cli = parse(&[
+            "a3s-box",
+            "wait",
+            "--condition",
+            "healthy",
+            "--timeout",
+            "30",
+            "a",
+        ])
+        .unwrap();
+        let Some(crate::commands::Command::Wait(args)) = cli.command else {
+            panic!("expected wait");
+        };
+        assert_eq!(args.condition, WaitCondition::Healthy);
+        assert_eq!(args.timeout, Some(30));
     }
 }
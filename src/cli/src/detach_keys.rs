@@ -0,0 +1,116 @@
+//! Detach key sequences for interactive PTY sessions (`--detach-keys`).
+//!
+//! The format follows Docker: a comma-separated list of keys, each either a
+//! single character or `ctrl-<key>` with `<key>` one of `a`-`z`, `@`, `[`,
+//! `\`, `]`, `^`, `_`. An empty list disables detaching.
+
+/// Sequence used when `--detach-keys` is not given.
+pub const DEFAULT_DETACH_KEYS: &str = "ctrl-p,ctrl-q";
+
+/// Parse a `--detach-keys` value into the bytes the terminal sends.
+pub fn parse(spec: &str) -> Result<Vec<u8>, String> {
+    if spec.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    spec.split(',').map(|key| parse_key(key.trim())).collect()
+}
+
+fn parse_key(key: &str) -> Result<u8, String> {
+    let invalid = || format!("Invalid detach key '{key}' (expected a character or ctrl-<key>)");
+    if key.len() == 1 {
+        return Ok(key.as_bytes()[0]);
+    }
+    let Some(ctrl) = key
+        .get(..5)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("ctrl-"))
+        .map(|_| &key[5..])
+    else {
+        return Err(invalid());
+    };
+    match ctrl.as_bytes() {
+        [c @ b'a'..=b'z'] | [c @ b'A'..=b'Z'] => Ok(c.to_ascii_lowercase() - b'a' + 1),
+        [b'@'] => Ok(0),
+        [b'['] => Ok(27),
+        [b'\\'] => Ok(28),
+        [b']'] => Ok(29),
+        [b'^'] => Ok(30),
+        [b'_'] => Ok(31),
+        _ => Err(invalid()),
+    }
+}
+
+/// Watches terminal input for the detach sequence.
+///
+/// Bytes that could start the sequence are held back until it either
+/// completes (they are dropped) or breaks off (they are passed through).
+pub struct DetachMatcher {
+    keys: Vec<u8>,
+    matched: usize,
+}
+
+impl DetachMatcher {
+    /// Match `keys`; an empty sequence never matches.
+    pub fn new(keys: Vec<u8>) -> Self {
+        Self { keys, matched: 0 }
+    }
+
+    /// Filter one chunk of input. Returns the bytes to forward and whether
+    /// the detach sequence completed; input after the sequence is dropped.
+    pub fn feed(&mut self, input: &[u8]) -> (Vec<u8>, bool) {
+        let mut forward = Vec::with_capacity(input.len());
+        if self.keys.is_empty() {
+            forward.extend_from_slice(input);
+            return (forward, false);
+        }
+        for &byte in input {
+            if byte != self.keys[self.matched] {
+                forward.extend_from_slice(&self.keys[..self.matched]);
+                self.matched = 0;
+                if byte != self.keys[0] {
+                    forward.push(byte);
+                    continue;
+                }
+            }
+            self.matched += 1;
+            if self.matched == self.keys.len() {
+                self.matched = 0;
+                return (forward, true);
+            }
+        }
+        (forward, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_detach_keys() {
+        assert_eq!(parse(DEFAULT_DETACH_KEYS).unwrap(), vec![0x10, 0x11]);
+        assert_eq!(parse("ctrl-a,x, CTRL-[").unwrap(), vec![0x01, b'x', 27]);
+        assert_eq!(parse("ctrl-@,ctrl-_").unwrap(), vec![0, 31]);
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("ctrl-1").is_err());
+        assert!(parse("alt-a").is_err());
+        assert!(parse("ctrl-p,").is_err());
+    }
+
+    #[test]
+    fn test_matcher_detaches_on_the_full_sequence() {
+        let mut matcher = DetachMatcher::new(vec![0x10, 0x11]);
+        assert_eq!(matcher.feed(b"ls\r"), (b"ls\r".to_vec(), false));
+        assert_eq!(matcher.feed(&[b'a', 0x10]), (b"a".to_vec(), false));
+        assert_eq!(matcher.feed(&[0x11, b'z']), (Vec::new(), true));
+    }
+
+    #[test]
+    fn test_matcher_passes_through_broken_sequences() {
+        let mut matcher = DetachMatcher::new(vec![0x10, 0x11]);
+        assert_eq!(matcher.feed(&[0x10, b'x']), (vec![0x10, b'x'], false));
+        assert_eq!(matcher.feed(&[0x10, 0x10, 0x11]), (vec![0x10], true));
+
+        let mut disabled = DetachMatcher::new(Vec::new());
+        assert_eq!(disabled.feed(&[0x10, 0x11]), (vec![0x10, 0x11], false));
+    }
+}
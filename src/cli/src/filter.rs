@@ -0,0 +1,102 @@
+//! `--filter key=value` handling shared by the listing commands.
+//!
+//! Each command names the keys it supports and decides what a key matches;
+//! this module parses the flags and combines them the way Docker does:
+//! filters with different keys must all match, while repeating a key
+//! matches any of its values (`--filter status=running --filter
+//! status=paused`).
+
+use std::collections::HashMap;
+
+/// Parsed `--filter` flags, grouped by key in first-seen order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filters {
+    groups: Vec<(String, Vec<String>)>,
+}
+
+impl Filters {
+    /// Parse `key=value` specs, rejecting keys not in `supported`.
+    pub fn parse(specs: &[String], supported: &[&str]) -> Result<Self, String> {
+        let mut filters = Self::default();
+        for spec in specs {
+            let (key, value) = spec
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid --filter (expected key=value): {spec}"))?;
+            if !supported.contains(&key) {
+                return Err(format!(
+                    "Unsupported filter '{key}' (supported: {})",
+                    supported.join(", ")
+                ));
+            }
+            match filters.groups.iter_mut().find(|(k, _)| k == key) {
+                Some((_, values)) => values.push(value.to_string()),
+                None => filters
+                    .groups
+                    .push((key.to_string(), vec![value.to_string()])),
+            }
+        }
+        Ok(filters)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Whether an item passes: `matches(key, value)` must hold for at least
+    /// one value of every key.
+    pub fn matches(&self, mut matches: impl FnMut(&str, &str) -> bool) -> bool {
+        self.groups
+            .iter()
+            .all(|(key, values)| values.iter().any(|value| matches(key, value)))
+    }
+}
+
+/// Match a `label=` filter value against labels.
+///
+/// Supports two forms:
+/// - `label=key` — check if the label key exists
+/// - `label=key=value` — check if the label key has the exact value
+pub fn match_label(labels: &HashMap<String, String>, filter_value: &str) -> bool {
+    if let Some((key, value)) = filter_value.split_once('=') {
+        labels.get(key).is_some_and(|v| v == value)
+    } else {
+        labels.contains_key(filter_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn specs(specs: &[&str]) -> Vec<String> {
+        specs.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_same_key_ors_and_different_keys_and() {
+        let filters = Filters::parse(
+            &specs(&["status=running", "name=web", "status=paused"]),
+            &["status", "name"],
+        )
+        .unwrap();
+        let item = |status: &'static str, name: &'static str| {
+            move |key: &str, value: &str| match key {
+                "status" => status == value,
+                _ => name.contains(value),
+            }
+        };
+        assert!(filters.matches(item("running", "web-1")));
+        assert!(filters.matches(item("paused", "web-2")));
+        assert!(!filters.matches(item("stopped", "web-1")));
+        assert!(!filters.matches(item("running", "db")));
+        assert!(Filters::default().matches(|_, _| false));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_specs() {
+        let err = Filters::parse(&specs(&["colour=red"]), &["status"]).unwrap_err();
+        assert_eq!(err, "Unsupported filter 'colour' (supported: status)");
+        assert!(Filters::parse(&specs(&["status"]), &["status"]).is_err());
+        assert!(Filters::parse(&specs(&["label=a=b"]), &["label"]).is_ok());
+    }
+}
@@ -0,0 +1,166 @@
+//! Ctrl-C routing for foreground commands (`run` and `attach`).
+//!
+//! Presses that follow each other within [`INTERRUPT_BURST`] form one burst,
+//! and the burst's size picks the action: one press interrupts the workload
+//! (SIGINT to its main process), two detach the terminal and leave the box
+//! running, three kill the box. A third press acts at once; shorter bursts
+//! act when the burst window closes.
+
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// How long after a press further presses still join its burst.
+pub const INTERRUPT_BURST: Duration = Duration::from_millis(500);
+
+/// What a burst of Ctrl-C presses asks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    /// Deliver SIGINT to the workload and keep following it.
+    Interrupt,
+    /// Stop following the box and leave it running.
+    Detach,
+    /// Kill the box.
+    Kill,
+}
+
+impl Interrupt {
+    /// The action for a burst of `presses` Ctrl-C presses.
+    pub fn from_presses(presses: u32) -> Option<Self> {
+        match presses {
+            0 => None,
+            1 => Some(Self::Interrupt),
+            2 => Some(Self::Detach),
+            _ => Some(Self::Kill),
+        }
+    }
+
+    /// One-line reminder of the bindings, for foreground banners.
+    pub fn help() -> &'static str {
+        "Ctrl-C interrupts, twice detaches, three times kills the box"
+    }
+}
+
+/// Turns Ctrl-C presses into [`Interrupt`] actions.
+pub struct InterruptRouter {
+    presses: mpsc::UnboundedReceiver<()>,
+    burst: Duration,
+    pending: u32,
+    deadline: Option<Instant>,
+}
+
+impl InterruptRouter {
+    /// Listen for Ctrl-C on this process.
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if tx.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self::from_presses(rx, INTERRUPT_BURST)
+    }
+
+    /// Route presses from `presses` instead of the process's Ctrl-C.
+    pub fn from_presses(presses: mpsc::UnboundedReceiver<()>, burst: Duration) -> Self {
+        Self {
+            presses,
+            burst,
+            pending: 0,
+            deadline: None,
+        }
+    }
+
+    /// Wait for the next complete burst and return its action.
+    ///
+    /// Cancel-safe: presses already counted stay pending, so this can be
+    /// polled from a `select!` loop alongside other branches.
+    pub async fn next(&mut self) -> Interrupt {
+        loop {
+            let deadline = self.deadline;
+            tokio::select! {
+                press = self.presses.recv() => match press {
+                    Some(()) => {
+                        self.pending += 1;
+                        if self.pending >= 3 {
+                            return self.take();
+                        }
+                        self.deadline = Some(Instant::now() + self.burst);
+                    }
+                    // The listener is gone: finish the pending burst, then
+                    // never fire again.
+                    None if self.pending > 0 => return self.take(),
+                    None => std::future::pending::<()>().await,
+                },
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)),
+                    if deadline.is_some() =>
+                {
+                    return self.take();
+                }
+            }
+        }
+    }
+
+    fn take(&mut self) -> Interrupt {
+        let action = Interrupt::from_presses(self.pending).unwrap_or(Interrupt::Interrupt);
+        self.pending = 0;
+        self.deadline = None;
+        action
+    }
+}
+
+impl Default for InterruptRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_presses() {
+        assert_eq!(Interrupt::from_presses(0), None);
+        assert_eq!(Interrupt::from_presses(1), Some(Interrupt::Interrupt));
+        assert_eq!(Interrupt::from_presses(2), Some(Interrupt::Detach));
+        assert_eq!(Interrupt::from_presses(3), Some(Interrupt::Kill));
+        assert_eq!(Interrupt::from_presses(7), Some(Interrupt::Kill));
+    }
+
+    #[tokio::test]
+    async fn test_router_groups_presses_into_bursts() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut router = InterruptRouter::from_presses(rx, Duration::from_millis(100));
+
+        tx.send(()).unwrap();
+        assert_eq!(router.next().await, Interrupt::Interrupt);
+
+        tx.send(()).unwrap();
+        tx.send(()).unwrap();
+        assert_eq!(router.next().await, Interrupt::Detach);
+
+        for _ in 0..3 {
+            tx.send(()).unwrap();
+        }
+        tx.send(()).unwrap();
+        assert_eq!(router.next().await, Interrupt::Kill);
+        // The fourth press starts a new burst.
+        assert_eq!(router.next().await, Interrupt::Interrupt);
+    }
+
+    #[tokio::test]
+    async fn test_router_keeps_pending_presses_when_cancelled() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut router = InterruptRouter::from_presses(rx, Duration::from_millis(100));
+
+        tx.send(()).unwrap();
+        let cancelled = tokio::time::timeout(Duration::from_millis(10), router.next()).await;
+        assert!(cancelled.is_err());
+        tx.send(()).unwrap();
+        assert_eq!(router.next().await, Interrupt::Detach);
+    }
+}
@@ -4,17 +4,24 @@ pub mod audit;
 pub mod boot;
 pub mod cleanup;
 pub mod commands;
+pub mod detach_keys;
+pub mod filter;
 pub mod health;
 pub mod image_usage;
+pub mod interrupt;
 pub mod lifecycle;
 pub(crate) mod log_archive;
+pub mod notify;
 pub mod output;
 pub mod platform;
 pub mod process;
+pub mod project;
+pub mod remote;
 pub mod resolve;
 pub mod socket_paths;
 pub mod state;
 pub mod status;
+pub mod template;
 #[cfg(not(windows))]
 pub mod terminal;
 pub mod test_helpers;
@@ -3,7 +3,8 @@
 use clap::Parser;
 use tracing_subscriber::EnvFilter;
 
-use a3s_box_cli::commands::{dispatch, Cli};
+use a3s_box_cli::commands::{dispatch, explain, Cli};
+use a3s_box_cli::{output, remote};
 
 #[tokio::main]
 async fn main() {
@@ -16,10 +17,32 @@ async fn main() {
         .with_writer(std::io::stderr)
         .init();
 
-    let cli = Cli::parse();
+    let (host, args) = match remote::split_host(
+        std::env::args_os().collect(),
+        std::env::var(remote::HOST_ENV).ok(),
+    ) {
+        Ok(split) => split,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(2);
+        }
+    };
+    if let Some(host) = host {
+        if let Err(e) = remote::execute(&host, &args[1..]) {
+            eprintln!("{}", explain::error_report(e.as_ref()));
+            std::process::exit(1);
+        }
+    }
+
+    let cli = Cli::parse_from(args);
 
     if let Err(e) = dispatch(cli).await {
-        eprintln!("Error: {e}");
+        match output::structured() {
+            // Tooling reads one stream: the error document replaces the
+            // command's output on stdout.
+            Some(format) => println!("{}", explain::error_document(format, e.as_ref())),
+            None => eprintln!("{}", explain::error_report(e.as_ref())),
+        }
         std::process::exit(1);
     }
 }
@@ -0,0 +1,222 @@
+//! Desktop notifications for `a3s-box monitor --notify`.
+//!
+//! The monitor hands every poll's records to a [`Notifier`], which compares
+//! them with the previous poll and reports each watched event once: a box
+//! that was running dies without being stopped (`crashed`), or its health
+//! check turns `unhealthy`. Notifications are shown with `osascript` on macOS
+//! and `notify-send` on Linux; elsewhere, or when the tool is missing, the
+//! monitor only prints them.
+
+use std::collections::HashSet;
+
+use crate::state::BoxRecord;
+use crate::status;
+
+/// An event the monitor can notify about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum NotifyEvent {
+    /// A running box died without a user stop and without exit code 0.
+    Crashed,
+    /// A box's health check turned unhealthy.
+    Unhealthy,
+}
+
+impl NotifyEvent {
+    /// The name used on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Crashed => "crashed",
+            Self::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+/// One notification to show.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+}
+
+/// Turns record changes between polls into notifications.
+pub struct Notifier {
+    events: HashSet<NotifyEvent>,
+    running: HashSet<String>,
+    unhealthy: HashSet<String>,
+}
+
+impl Notifier {
+    /// Watch `events`; with none, [`Notifier::observe`] never reports.
+    pub fn new(events: &[NotifyEvent]) -> Self {
+        Self {
+            events: events.iter().copied().collect(),
+            running: HashSet::new(),
+            unhealthy: HashSet::new(),
+        }
+    }
+
+    /// Record this poll's state and return the events that happened since
+    /// the previous one. Boxes already dead at the first poll are not
+    /// reported.
+    pub fn observe(&mut self, records: &[BoxRecord]) -> Vec<Notification> {
+        let mut notifications = Vec::new();
+        for record in records {
+            if status::is_active(record) {
+                self.running.insert(record.id.clone());
+            } else if self.running.remove(&record.id) && crashed(record) {
+                self.push(NotifyEvent::Crashed, record, &mut notifications);
+            }
+
+            if record.health_status == "unhealthy" {
+                if self.unhealthy.insert(record.id.clone()) {
+                    self.push(NotifyEvent::Unhealthy, record, &mut notifications);
+                }
+            } else {
+                self.unhealthy.remove(&record.id);
+            }
+        }
+
+        let present: HashSet<&str> = records.iter().map(|r| r.id.as_str()).collect();
+        self.running.retain(|id| present.contains(id.as_str()));
+        self.unhealthy.retain(|id| present.contains(id.as_str()));
+        notifications
+    }
+
+    fn push(&self, event: NotifyEvent, record: &BoxRecord, out: &mut Vec<Notification>) {
+        if !self.events.contains(&event) {
+            return;
+        }
+        let body = match event {
+            NotifyEvent::Crashed => match record.exit_code {
+                Some(code) => format!("exited with code {code}"),
+                None => "stopped unexpectedly".to_string(),
+            },
+            NotifyEvent::Unhealthy => "health check is failing".to_string(),
+        };
+        out.push(Notification {
+            title: format!("a3s-box: {} {}", record.name, event.as_str()),
+            body: format!("Box {} ({}) {body}", record.name, record.short_id),
+        });
+    }
+}
+
+fn crashed(record: &BoxRecord) -> bool {
+    record.status == "dead" && !record.stopped_by_user && record.exit_code != Some(0)
+}
+
+/// Show `notification` on the desktop, best effort. Returns whether a
+/// notifier tool ran successfully.
+pub async fn send(notification: &Notification) -> bool {
+    let Some((program, args)) = desktop_command(std::env::consts::OS, notification) else {
+        return false;
+    };
+    tokio::process::Command::new(program)
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
+}
+
+/// The command that shows `notification` on `os`, if it has one.
+fn desktop_command(os: &str, notification: &Notification) -> Option<(&'static str, Vec<String>)> {
+    match os {
+        "macos" => Some((
+            "osascript",
+            vec![
+                "-e".to_string(),
+                format!(
+                    "display notification {} with title {}",
+                    applescript_string(&notification.body),
+                    applescript_string(&notification.title)
+                ),
+            ],
+        )),
+        "linux" => Some((
+            "notify-send",
+            vec![
+                "--app-name=a3s-box".to_string(),
+                notification.title.clone(),
+                notification.body.clone(),
+            ],
+        )),
+        _ => None,
+    }
+}
+
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::make_record;
+
+    #[test]
+    fn test_notifier_reports_crashes_once() {
+        let mut notifier = Notifier::new(&[NotifyEvent::Crashed]);
+        let mut record = make_record("id-1", "web", "running", None);
+        let mut already_dead = make_record("id-2", "old", "dead", None);
+        already_dead.exit_code = Some(1);
+        assert!(notifier
+            .observe(&[record.clone(), already_dead.clone()])
+            .is_empty());
+
+        record.status = "dead".to_string();
+        record.pid = None;
+        record.exit_code = Some(137);
+        let notifications = notifier.observe(&[record.clone(), already_dead]);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].title, "a3s-box: web crashed");
+        assert!(notifications[0].body.contains("exited with code 137"));
+        assert!(notifier.observe(&[record]).is_empty());
+    }
+
+    #[test]
+    fn test_notifier_skips_user_stops_and_unwatched_events() {
+        let mut notifier = Notifier::new(&[NotifyEvent::Crashed]);
+        let mut record = make_record("id-1", "web", "running", None);
+        record.health_status = "unhealthy".to_string();
+        assert!(notifier.observe(&[record.clone()]).is_empty());
+
+        record.status = "dead".to_string();
+        record.pid = None;
+        record.stopped_by_user = true;
+        assert!(notifier.observe(&[record]).is_empty());
+    }
+
+    #[test]
+    fn test_notifier_reports_unhealthy_transitions() {
+        let mut notifier = Notifier::new(&[NotifyEvent::Unhealthy]);
+        let mut record = make_record("id-1", "web", "running", None);
+        record.health_status = "unhealthy".to_string();
+        assert_eq!(notifier.observe(&[record.clone()]).len(), 1);
+        assert!(notifier.observe(&[record.clone()]).is_empty());
+
+        record.health_status = "healthy".to_string();
+        assert!(notifier.observe(&[record.clone()]).is_empty());
+        record.health_status = "unhealthy".to_string();
+        assert_eq!(notifier.observe(&[record]).len(), 1);
+    }
+
+    #[test]
+    fn test_desktop_command_quotes_applescript() {
+        let notification = Notification {
+            title: "a3s-box: web crashed".to_string(),
+            body: "say \"hi\"".to_string(),
+        };
+        let (program, args) = desktop_command("macos", &notification).unwrap();
+        assert_eq!(program, "osascript");
+        assert_eq!(
+            args[1],
+            "display notification \"say \\\"hi\\\"\" with title \"a3s-box: web crashed\""
+        );
+        assert_eq!(
+            desktop_command("linux", &notification).unwrap().0,
+            "notify-send"
+        );
+        assert!(desktop_command("windows", &notification).is_none());
+    }
+}
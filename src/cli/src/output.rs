@@ -1,6 +1,82 @@
-//! Table formatting helpers for CLI output.
+//! Table formatting helpers for CLI output, and the machine-readable
+//! documents `--output json|yaml` prints instead.
+//!
+//! Every structured document has the same envelope: a `schema_version`, a
+//! `kind` naming the shape of `data`, and `data` itself. A failed command
+//! prints a document of kind `Error` whose data carries the catalog code (see
+//! `--explain`) and the message. Field names only change together with
+//! [`OUTPUT_SCHEMA_VERSION`]; new fields may appear within a version.
+
+use std::sync::OnceLock;
 
 use comfy_table::{ContentArrangement, Table};
+use serde::Serialize;
+
+/// Version of the `--output` document envelope and the data shapes in it.
+pub const OUTPUT_SCHEMA_VERSION: u32 = 1;
+
+/// Formats for `--output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+}
+
+static STRUCTURED: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Switch the process to structured output. Set once, before dispatch.
+pub fn set_structured(format: OutputFormat) {
+    let _ = STRUCTURED.set(format);
+}
+
+/// The `--output` format, if the command line asked for one.
+pub fn structured() -> Option<OutputFormat> {
+    STRUCTURED.get().copied()
+}
+
+/// The envelope around every structured document.
+#[derive(Debug, Serialize)]
+pub struct Document<'a, T: Serialize> {
+    pub schema_version: u32,
+    pub kind: &'a str,
+    pub data: T,
+}
+
+/// Data of an `Error` document.
+#[derive(Debug, Serialize)]
+pub struct ErrorData {
+    /// Catalog code such as `A3S-0005`, when the error has one.
+    pub code: Option<String>,
+    pub message: String,
+}
+
+/// Render `data` as a document of `kind`. YAML documents start with `---` so
+/// streamed documents (`stats` without `--no-stream`) stay separable; JSON
+/// documents are one line each.
+pub fn render<T: Serialize>(
+    format: OutputFormat,
+    kind: &str,
+    data: T,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let document = Document {
+        schema_version: OUTPUT_SCHEMA_VERSION,
+        kind,
+        data,
+    };
+    Ok(match format {
+        OutputFormat::Json => serde_json::to_string(&document)?,
+        OutputFormat::Yaml => format!("---\n{}", serde_yaml::to_string(&document)?)
+            .trim_end()
+            .to_string(),
+    })
+}
+
+/// Print `data` as a document of `kind` in the `--output` format.
+pub fn emit<T: Serialize>(kind: &str, data: T) -> Result<(), Box<dyn std::error::Error>> {
+    let format = structured().ok_or("structured output was not requested")?;
+    println!("{}", render(format, kind, data)?);
+    Ok(())
+}
 
 /// Create a styled table with the given headers.
 pub fn new_table(headers: &[&str]) -> Table {
@@ -250,6 +326,44 @@ mod tests {
         assert!(parse_duration_secs("30 s").is_err());
     }
 
+    // --- structured output tests ---
+
+    #[test]
+    fn test_render_wraps_data_in_versioned_envelope() {
+        let data = serde_json::json!([{"name": "web"}]);
+        assert_eq!(
+            render(OutputFormat::Json, "BoxList", &data).unwrap(),
+            r#"{"schema_version":1,"kind":"BoxList","data":[{"name":"web"}]}"#
+        );
+        assert_eq!(
+            render(OutputFormat::Yaml, "BoxList", &data).unwrap(),
+            "---\nschema_version: 1\nkind: BoxList\ndata:\n- name: web"
+        );
+        let error = ErrorData {
+            code: None,
+            message: "Box not found: web".to_string(),
+        };
+        assert_eq!(
+            render(OutputFormat::Json, "Error", error).unwrap(),
+            r#"{"schema_version":1,"kind":"Error","data":{"code":null,"message":"Box not found: web"}}"#
+        );
+    }
+
+    #[test]
+    fn test_machine_output_doc_examples_are_rendered() {
+        let doc = include_str!("../../../docs/machine-output.md");
+        let data = serde_json::json!([{"name": "web"}]);
+        for format in [OutputFormat::Json, OutputFormat::Yaml] {
+            let example = render(format, "BoxList", &data).unwrap();
+            assert!(doc.contains(&example), "{example}");
+        }
+        let error = ErrorData {
+            code: None,
+            message: "Box not found: web".to_string(),
+        };
+        assert!(doc.contains(&render(OutputFormat::Json, "Error", error).unwrap()));
+    }
+
     // --- format_bytes tests ---
 
     #[test]
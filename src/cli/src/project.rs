@@ -0,0 +1,127 @@
+//! `a3s-box.toml` project files.
+//!
+//! A project file, written by `a3s-box init`, describes the box a directory
+//! runs: `a3s-box run` (and `create`) without an image in that directory
+//! takes the image, name, command, environment, volumes and ports from it.
+//! Flags given on the command line still apply on top. When the file has a
+//! `[build]` section and the image is not in the local store yet, `run`
+//! builds it first.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Project file name, looked up in the current directory.
+pub const PROJECT_FILE: &str = "a3s-box.toml";
+
+/// Contents of `a3s-box.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Box name, unless `--name` is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Image to run.
+    pub image: String,
+    /// Command, unless one is given after `--`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cmd: Vec<String>,
+    /// Volume mounts (`host:guest`), as for `-v`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    /// Published ports (`host:guest`), as for `-p`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    /// How to build `image` from this directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<ProjectBuild>,
+    /// Environment variables; `-e` overrides them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// The `[build]` section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectBuild {
+    /// Build context, relative to the project directory.
+    #[serde(default = "default_context")]
+    pub context: String,
+    /// Dockerfile, relative to the project directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+}
+
+fn default_context() -> String {
+    ".".to_string()
+}
+
+impl ProjectConfig {
+    /// Parse a project file's contents.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(text).map_err(|e| e.to_string())?;
+        if config.image.trim().is_empty() {
+            return Err("image must not be empty".to_string());
+        }
+        Ok(config)
+    }
+}
+
+/// The project file in `dir`, if there is one.
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    let path = dir.join(PROJECT_FILE);
+    path.is_file().then_some(path)
+}
+
+/// Read and parse the project file at `path`.
+pub fn load(path: &Path) -> Result<ProjectConfig, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    ProjectConfig::parse(&text).map_err(|e| format!("Invalid {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_project_file() {
+        let config = ProjectConfig::parse(
+            r#"
+name = "helper"
+image = "helper-agent:latest"
+cmd = ["sh", "-c", "sleep infinity"]
+volumes = ["./workspace:/workspace"]
+
+[build]
+dockerfile = "Dockerfile"
+
+[env]
+LOG_LEVEL = "info"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.name.as_deref(), Some("helper"));
+        assert_eq!(config.cmd, ["sh", "-c", "sleep infinity"]);
+        assert_eq!(config.build.as_ref().unwrap().context, ".");
+        assert_eq!(config.env["LOG_LEVEL"], "info");
+        assert!(config.ports.is_empty());
+
+        assert!(ProjectConfig::parse("image = \"\"").is_err());
+        assert!(ProjectConfig::parse("name = \"x\"").is_err());
+        assert!(ProjectConfig::parse("image = \"a\"\nimgae = \"b\"").is_err());
+    }
+
+    #[test]
+    fn test_find_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(find(dir.path()), None);
+        std::fs::write(dir.path().join(PROJECT_FILE), "image = \"alpine\"\n").unwrap();
+        let path = find(dir.path()).unwrap();
+        assert_eq!(load(&path).unwrap().image, "alpine");
+
+        std::fs::write(&path, "image = [").unwrap();
+        assert!(load(&path).unwrap_err().starts_with("Invalid "));
+    }
+}
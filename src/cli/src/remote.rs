@@ -0,0 +1,261 @@
+//! Running commands on another machine (`--host ssh://user@server`,
+//! `A3S_HOST`).
+//!
+//! a3s-box has no daemon: every command acts on the state of the machine it
+//! runs on. Targeting a remote host therefore runs the same command line with
+//! that host's `a3s-box` over `ssh`, which carries stdin, stdout, stderr, the
+//! terminal and the exit status. `logs -f`, `exec -it` and `attach` behave as
+//! they do locally, and SSH keys, agents and `~/.ssh/config` aliases apply as
+//! usual. Paths in arguments (`cp`, `build`, `-v`, `--env-file`) name files on
+//! the remote host.
+
+use std::ffi::{OsStr, OsString};
+use std::io::IsTerminal;
+use std::process::Command;
+
+/// Environment variable naming the default host.
+pub const HOST_ENV: &str = "A3S_HOST";
+
+/// A remote host reached over SSH.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshTarget {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl SshTarget {
+    /// Parse `ssh://[user@]host[:port]`. `unix://` and `tcp://` endpoints
+    /// address a daemon API, which a3s-box does not have.
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let Some(rest) = url.strip_prefix("ssh://") else {
+            return Err(if url.starts_with("unix://") || url.starts_with("tcp://") {
+                format!(
+                    "--host {url}: a3s-box has no daemon to connect to; use \
+                     ssh://[user@]host[:port] to run commands on another machine"
+                )
+            } else {
+                format!("--host {url}: expected ssh://[user@]host[:port]")
+            });
+        };
+        let rest = rest.strip_suffix('/').unwrap_or(rest);
+        let (user, address) = match rest.rsplit_once('@') {
+            Some((user, address)) => (Some(user.to_string()), address),
+            None => (None, rest),
+        };
+        let (host, port) =
+            split_port(address).ok_or_else(|| format!("--host {url}: invalid port"))?;
+        if host.is_empty() || host.contains('/') || user.as_deref() == Some("") {
+            return Err(format!("--host {url}: expected ssh://[user@]host[:port]"));
+        }
+        Ok(Self {
+            user,
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.clone(),
+        }
+    }
+}
+
+/// Split `host:port` or `[v6addr]:port`; `None` when the port is not a number.
+fn split_port(address: &str) -> Option<(&str, Option<u16>)> {
+    if let Some(bracketed) = address.strip_prefix('[') {
+        let (host, after) = bracketed.split_once(']')?;
+        return match after.strip_prefix(':') {
+            Some(port) => Some((host, Some(port.parse().ok()?))),
+            None if after.is_empty() => Some((host, None)),
+            None => None,
+        };
+    }
+    match address.split_once(':') {
+        Some((host, port)) => Some((host, Some(port.parse().ok()?))),
+        None => Some((address, None)),
+    }
+}
+
+/// Take `--host`/`-H` off the front of the command line, before the
+/// subcommand, falling back to `env_host` (`$A3S_HOST`). An empty value means
+/// the local machine.
+pub fn split_host(
+    args: Vec<OsString>,
+    env_host: Option<String>,
+) -> Result<(Option<String>, Vec<OsString>), String> {
+    let mut host = None;
+    let mut rest = Vec::with_capacity(args.len());
+    let mut args = args.into_iter();
+    rest.extend(args.next());
+    while let Some(arg) = args.next() {
+        let text = arg.to_string_lossy();
+        if text == "--host" || text == "-H" {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{text} requires a value"))?;
+            host = Some(value.to_string_lossy().into_owned());
+        } else if let Some(value) = text
+            .strip_prefix("--host=")
+            .or_else(|| text.strip_prefix("-H"))
+        {
+            host = Some(value.strip_prefix('=').unwrap_or(value).to_string());
+        } else if text.starts_with('-') && text != "--" {
+            rest.push(arg);
+        } else {
+            rest.push(arg);
+            break;
+        }
+    }
+    rest.extend(args);
+    let host = host.or(env_host).filter(|host| !host.is_empty());
+    Ok((host, rest))
+}
+
+/// The `ssh` invocation that runs `a3s-box <args>` on `target`. A remote
+/// terminal is allocated only when both local stdin and stdout are terminals,
+/// so piped output stays free of carriage returns and stderr stays separate.
+fn ssh_command(target: &SshTarget, args: &[OsString], tty: bool) -> Command {
+    let mut command = Command::new("ssh");
+    if let Some(port) = target.port {
+        command.arg("-p").arg(port.to_string());
+    }
+    if tty {
+        // The remote CLI handles detach keys; keep ssh from eating `~`.
+        command.args(["-t", "-e", "none"]);
+    } else {
+        command.arg("-T");
+    }
+    command
+        .arg("--")
+        .arg(target.destination())
+        .arg(remote_command_line(args));
+    command
+}
+
+/// `ssh` hands the command to the remote login shell as one string.
+fn remote_command_line(args: &[OsString]) -> String {
+    std::iter::once("a3s-box".to_string())
+        .chain(args.iter().map(|arg| shell_quote(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shell_quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c))
+    {
+        arg.into_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Run `args` (without the program name) on `host` and exit with its status.
+pub fn execute(host: &str, args: &[OsString]) -> Result<(), Box<dyn std::error::Error>> {
+    let target = SshTarget::parse(host)?;
+    let tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    run(ssh_command(&target, args, tty))
+}
+
+/// Replace this process with `ssh` so signals, the terminal and the exit
+/// status belong to the remote command.
+#[cfg(unix)]
+fn run(mut command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::process::CommandExt;
+    let error = command.exec();
+    Err(format!("Failed to run ssh: {error}").into())
+}
+
+#[cfg(not(unix))]
+fn run(mut command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    let status = command
+        .status()
+        .map_err(|e| format!("Failed to run ssh: {e}"))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_parse_ssh_targets() {
+        assert_eq!(
+            SshTarget::parse("ssh://dev@sev-host:2222").unwrap(),
+            SshTarget {
+                user: Some("dev".to_string()),
+                host: "sev-host".to_string(),
+                port: Some(2222),
+            }
+        );
+        let alias = SshTarget::parse("ssh://sev-host/").unwrap();
+        assert_eq!((alias.user, alias.port), (None, None));
+        assert_eq!(SshTarget::parse("ssh://[::1]:22").unwrap().host, "::1");
+
+        assert!(SshTarget::parse("ssh://host:ssh").is_err());
+        assert!(SshTarget::parse("ssh://@host").is_err());
+        assert!(SshTarget::parse("ssh://").is_err());
+        assert!(SshTarget::parse("sev-host").is_err());
+        let err = SshTarget::parse("tcp://10.0.0.5:2375").unwrap_err();
+        assert!(err.contains("no daemon"));
+    }
+
+    #[test]
+    fn test_split_host_only_before_subcommand() {
+        let (host, rest) = split_host(
+            os(&["a3s-box", "--host", "ssh://a", "exec", "web", "--host", "x"]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(host.as_deref(), Some("ssh://a"));
+        assert_eq!(rest, os(&["a3s-box", "exec", "web", "--host", "x"]));
+
+        let (host, rest) = split_host(os(&["a3s-box", "-Hssh://b", "ps"]), None).unwrap();
+        assert_eq!(host.as_deref(), Some("ssh://b"));
+        assert_eq!(rest, os(&["a3s-box", "ps"]));
+
+        let (host, rest) = split_host(os(&["a3s-box", "ps"]), Some("ssh://env".into())).unwrap();
+        assert_eq!(host.as_deref(), Some("ssh://env"));
+        assert_eq!(rest, os(&["a3s-box", "ps"]));
+
+        let (host, _) =
+            split_host(os(&["a3s-box", "--host=", "ps"]), Some("ssh://env".into())).unwrap();
+        assert_eq!(host, None);
+        assert!(split_host(os(&["a3s-box", "-H"]), None).is_err());
+    }
+
+    #[test]
+    fn test_ssh_command_line() {
+        let target = SshTarget::parse("ssh://dev@sev-host:2222").unwrap();
+        let command = ssh_command(
+            &target,
+            &os(&["exec", "web", "--", "sh", "-c", "echo 'hi' $HOME"]),
+            false,
+        );
+        let args: Vec<_> = command.get_args().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(
+            args,
+            [
+                "-p",
+                "2222",
+                "-T",
+                "--",
+                "dev@sev-host",
+                "a3s-box exec web -- sh -c 'echo '\\''hi'\\'' $HOME'",
+            ]
+        );
+
+        let command = ssh_command(&target, &os(&["attach", "web"]), true);
+        assert!(command.get_args().any(|a| a == "-t"));
+    }
+}
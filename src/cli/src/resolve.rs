@@ -127,6 +127,15 @@ mod tests {
             resource_limits: a3s_box_core::config::ResourceLimits::default(),
             log_config: a3s_box_core::log::LogConfig::default(),
             add_host: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            dns_option: vec![],
+            network_aliases: vec![],
+            network_policy: vec![],
+            network_bandwidth: None,
+            network_max_conns: None,
+            http_proxy: None,
+            socket_mounts: vec![],
             platform: None,
             init: false,
             read_only: false,
@@ -141,6 +150,8 @@ mod tests {
             stop_timeout: None,
             oom_kill_disable: false,
             oom_score_adj: None,
+            workspace_template: None,
+            metadata: Default::default(),
         }
     }
 
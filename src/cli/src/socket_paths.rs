@@ -10,6 +10,7 @@ pub enum RuntimeSocket {
     Exec,
     Pty,
     Attest,
+    Forward,
 }
 
 impl RuntimeSocket {
@@ -18,6 +19,7 @@ impl RuntimeSocket {
             Self::Exec => "exec.sock",
             Self::Pty => "pty.sock",
             Self::Attest => "attest.sock",
+            Self::Forward => a3s_box_core::forward::FORWARD_SOCKET_FILE,
         }
     }
 
@@ -26,6 +28,7 @@ impl RuntimeSocket {
             Self::Exec => "exec",
             Self::Pty => "PTY",
             Self::Attest => "attestation",
+            Self::Forward => "port-forward",
         }
     }
 
@@ -34,6 +37,7 @@ impl RuntimeSocket {
             Self::Exec => "exec in",
             Self::Pty => "open a PTY in",
             Self::Attest => "request attestation from",
+            Self::Forward => "forward ports to",
         }
     }
 }
@@ -67,7 +71,9 @@ pub fn attest(record: &BoxRecord) -> PathBuf {
 pub fn runtime_socket(record: &BoxRecord, socket: RuntimeSocket) -> PathBuf {
     match socket {
         RuntimeSocket::Exec => exec(record),
-        RuntimeSocket::Pty | RuntimeSocket::Attest => sibling(record, socket.file_name()),
+        RuntimeSocket::Pty | RuntimeSocket::Attest | RuntimeSocket::Forward => {
+            sibling(record, socket.file_name())
+        }
     }
 }
 
@@ -122,6 +128,17 @@ mod tests {
         assert_eq!(pty(&record), PathBuf::from("/tmp/a3s-custom/pty.sock"));
     }
 
+    #[test]
+    fn test_forward_uses_exec_socket_sibling() {
+        let mut record = make_record("id", "box", "running", Some(1));
+        record.exec_socket_path = PathBuf::from("/tmp/a3s-custom/exec.sock");
+
+        assert_eq!(
+            runtime_socket(&record, RuntimeSocket::Forward),
+            PathBuf::from("/tmp/a3s-custom/forward.sock")
+        );
+    }
+
     #[test]
     fn test_require_running_returns_actionable_error() {
         let record = make_record("id", "box", "dead", None);
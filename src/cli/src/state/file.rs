@@ -294,14 +294,15 @@ impl StateFile {
         })
     }
 
-    /// Get box IDs that are pending restart (dead boxes with active restart policy).
+    /// Get box IDs that are pending restart (dead or dependency-waiting boxes with
+    /// an active restart policy).
     ///
     /// This can be called after load to check if any boxes need restarting.
     pub fn pending_restarts(&self) -> Vec<String> {
         self.store
             .records()
             .iter()
-            .filter(|r| r.status == "dead" && should_restart(r))
+            .filter(|r| matches!(r.status.as_str(), "dead" | "waiting") && should_restart(r))
             .map(|r| r.id.clone())
             .collect()
     }
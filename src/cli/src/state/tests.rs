@@ -70,6 +70,15 @@ fn sample_record(id: &str, name: &str, status: &str) -> BoxRecord {
         resource_limits: a3s_box_core::config::ResourceLimits::default(),
         log_config: a3s_box_core::log::LogConfig::default(),
         add_host: vec![],
+        dns: vec![],
+        dns_search: vec![],
+        dns_option: vec![],
+        network_aliases: vec![],
+        network_policy: vec![],
+        network_bandwidth: None,
+        network_max_conns: None,
+        http_proxy: None,
+        socket_mounts: vec![],
         platform: None,
         init: false,
         read_only: false,
@@ -84,6 +93,8 @@ fn sample_record(id: &str, name: &str, status: &str) -> BoxRecord {
         stop_timeout: None,
         oom_kill_disable: false,
         oom_score_adj: None,
+        workspace_template: None,
+        metadata: Default::default(),
     }
 }
 
@@ -29,6 +29,10 @@ pub struct StatusDetails {
     pub hint: Option<String>,
 }
 
+/// Label naming the dependency a `waiting` box is blocked on, set by the
+/// monitor while it defers a restart.
+pub const WAITING_ON_LABEL: &str = "com.a3s.compose.waiting-on";
+
 pub fn is_active(record: &BoxRecord) -> bool {
     is_active_status(&record.status)
 }
@@ -113,6 +117,15 @@ fn status_hint(record: &BoxRecord) -> Option<String> {
             "Use `a3s-box start {}` to start it again or `a3s-box rm {}` to remove it.",
             record.name, record.name
         )),
+        "waiting" => Some(format!(
+            "Waiting on dependency {}; the monitor starts it once the dependency is ready. Use `a3s-box stop {}` to stop waiting.",
+            record
+                .labels
+                .get(WAITING_ON_LABEL)
+                .map(String::as_str)
+                .unwrap_or("services"),
+            record.name
+        )),
         "dead" if record.exit_code == Some(0) => Some(format!(
             "The box exited successfully. Run `a3s-box logs {}` to review output, then `a3s-box restart {}` or `a3s-box rm {}`.",
             record.name, record.name, record.name
@@ -218,6 +231,23 @@ mod tests {
         assert!(details.hint.unwrap().contains("a3s-box unpause box"));
     }
 
+    #[test]
+    fn test_status_details_names_waiting_dependency() {
+        let mut record = make_record("id", "app-api", "waiting", None);
+        record
+            .labels
+            .insert(WAITING_ON_LABEL.to_string(), "db (healthy)".to_string());
+        let details = status_details(&record);
+
+        assert!(!details.active);
+        let hint = details.hint.unwrap();
+        assert!(
+            hint.contains("Waiting on dependency db (healthy)"),
+            "{hint}"
+        );
+        assert!(hint.contains("a3s-box stop app-api"));
+    }
+
     #[test]
     fn test_status_details_does_not_call_exit_zero_a_failure() {
         let mut record = make_record("id", "box", "dead", None);
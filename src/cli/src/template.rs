@@ -0,0 +1,252 @@
+//! Go-template-style `--format` strings for listing and inspect commands.
+//!
+//! A template is literal text with `{{ ... }}` actions evaluated against a
+//! JSON value, covering the subset of Go templates that Docker users script
+//! with:
+//!
+//! - `{{.}}`, `{{.Name}}`, `{{.State.Status}}`: the value or a field path
+//! - `{{.Labels "env"}}`, `{{index .Labels "env"}}`: look up a map key
+//! - `{{json .Labels}}`: the value as compact JSON
+//! - `{{upper .Name}}`, `{{lower .Name}}`
+//!
+//! `\t` and `\n` in the literal text become a tab and a newline, so
+//! `--format '{{.ID}}\t{{.Names}}'` needs no shell quoting tricks. Missing
+//! fields render as `<no value>`, as in Go.
+
+use serde_json::Value;
+
+/// A parsed `--format` template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Action(Action),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    /// A field path, optionally indexed by map keys.
+    Field(Vec<String>, Vec<String>),
+    Json(Box<Action>),
+    Upper(Box<Action>),
+    Lower(Box<Action>),
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(unescape(&rest[..start])));
+            }
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| format!("template: unclosed action in '{template}'"))?;
+            parts.push(Part::Action(parse_action(after[..end].trim())?));
+            rest = &after[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(unescape(rest)));
+        }
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, data: &Value) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Action(action) => match eval(action, data) {
+                    Some(value) => out.push_str(&display(&value)),
+                    None => out.push_str("<no value>"),
+                },
+            }
+        }
+        out
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\t", "\t").replace("\\n", "\n")
+}
+
+fn parse_action(action: &str) -> Result<Action, String> {
+    let words = split_words(action)?;
+    let (first, args) = words
+        .split_first()
+        .ok_or_else(|| "template: empty action {{}}".to_string())?;
+    match first.as_str() {
+        "json" | "upper" | "lower" => {
+            let [arg] = args else {
+                return Err(format!("template: {first} takes one argument"));
+            };
+            let inner = Box::new(parse_action(arg)?);
+            Ok(match first.as_str() {
+                "json" => Action::Json(inner),
+                "upper" => Action::Upper(inner),
+                _ => Action::Lower(inner),
+            })
+        }
+        "index" => {
+            let (target, keys) = args
+                .split_first()
+                .ok_or_else(|| "template: index needs a value and a key".to_string())?;
+            field_action(target, keys)
+        }
+        _ => field_action(first, args),
+    }
+}
+
+fn field_action(path: &str, keys: &[String]) -> Result<Action, String> {
+    let Some(path) = path.strip_prefix('.') else {
+        return Err(format!("template: unknown function '{path}'"));
+    };
+    let fields = if path.is_empty() {
+        Vec::new()
+    } else {
+        path.split('.').map(str::to_string).collect()
+    };
+    if fields.iter().any(String::is_empty) {
+        return Err(format!("template: bad field path '.{path}'"));
+    }
+    let keys = keys
+        .iter()
+        .map(|key| {
+            key.strip_prefix('"')
+                .and_then(|k| k.strip_suffix('"'))
+                .map(str::to_string)
+                .ok_or_else(|| format!("template: expected a quoted key, got {key}"))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Action::Field(fields, keys))
+}
+
+/// Split an action into words, keeping quoted strings (with their quotes)
+/// together.
+fn split_words(action: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut chars = action.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            let mut word = String::from(chars.next().unwrap());
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => word.push(c),
+                    None => return Err(format!("template: unterminated string in {action}")),
+                }
+            }
+            word.push('"');
+            words.push(word);
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            words.push(word);
+        }
+    }
+    Ok(words)
+}
+
+fn eval(action: &Action, data: &Value) -> Option<Value> {
+    match action {
+        Action::Field(fields, keys) => {
+            let mut value = data;
+            for name in fields.iter().chain(keys) {
+                value = value.as_object()?.get(name)?;
+            }
+            Some(value.clone())
+        }
+        Action::Json(inner) => Some(Value::String(eval(inner, data)?.to_string())),
+        Action::Upper(inner) => Some(Value::String(display(&eval(inner, data)?).to_uppercase())),
+        Action::Lower(inner) => Some(Value::String(display(&eval(inner, data)?).to_lowercase())),
+    }
+}
+
+/// Render a value the way Go's `fmt` prints it in a template.
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(display).collect();
+            format!("[{}]", items.join(" "))
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<String> = map
+                .iter()
+                .map(|(key, value)| format!("{key}:{}", display(value)))
+                .collect();
+            entries.sort();
+            format!("map[{}]", entries.join(" "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, data: &Value) -> String {
+        Template::parse(template).unwrap().render(data)
+    }
+
+    #[test]
+    fn test_render_fields_and_paths() {
+        let data = json!({
+            "Names": "web",
+            "State": {"Status": "running", "Running": true, "Pid": 42},
+            "Ports": ["8080:80", "8443:443"],
+        });
+        assert_eq!(
+            render("{{.Names}}\\t{{ .State.Status }}", &data),
+            "web\trunning"
+        );
+        assert_eq!(
+            render("{{.State.Running}} {{.State.Pid}}", &data),
+            "true 42"
+        );
+        assert_eq!(render("{{.Ports}}", &data), "[8080:80 8443:443]");
+        assert_eq!(render("{{.Missing}}", &data), "<no value>");
+        assert_eq!(render("plain", &data), "plain");
+    }
+
+    #[test]
+    fn test_render_functions_and_keys() {
+        let data = json!({"Name": "web", "Labels": {"env": "prod", "tier": "api"}});
+        assert_eq!(render("{{.Labels \"env\"}}", &data), "prod");
+        assert_eq!(render("{{index .Labels \"tier\"}}", &data), "api");
+        assert_eq!(render("{{.Labels}}", &data), "map[env:prod tier:api]");
+        assert_eq!(
+            render("{{json .Labels}}", &data),
+            r#"{"env":"prod","tier":"api"}"#
+        );
+        assert_eq!(render("{{upper .Name}}", &data), "WEB");
+        assert_eq!(render("{{json .}}", &json!({"a": 1})), r#"{"a":1}"#);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{{.Name").is_err());
+        assert!(Template::parse("{{}}").is_err());
+        assert!(Template::parse("{{Name}}").is_err());
+        assert!(Template::parse("{{json}}").is_err());
+        assert!(Template::parse("{{.Labels env}}").is_err());
+        assert!(Template::parse("{{.Labels \"env}}").is_err());
+    }
+}
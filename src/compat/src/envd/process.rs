@@ -125,6 +125,8 @@ impl ProcessBroker {
                         user,
                         cols: size.cols,
                         rows: size.rows,
+                        session: None,
+                        resume: false,
                     },
                 )
                 .await
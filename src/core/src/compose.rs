@@ -15,7 +15,9 @@ mod schema;
 
 pub use acl::ComposeAclError;
 pub use diagnostic::{ComposeDiagnostic, ComposeDiagnosticCode, ComposeNormalizationError};
-pub use interpolation::{interpolate_compose_yaml, ComposeInterpolationError};
+pub use interpolation::{
+    interpolate_compose_scalar, interpolate_compose_yaml, ComposeInterpolationError,
+};
 pub use normalization::{normalize_compose, normalize_compose_config, ComposeSourceFormat};
 pub use normalized::{
     NormalizedComposeConfig, NormalizedDependsOn, NormalizedHealthcheckConfig,
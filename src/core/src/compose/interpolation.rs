@@ -42,7 +42,9 @@ pub fn interpolate_compose_yaml(
     serde_yaml::to_string(&yaml).map_err(ComposeInterpolationError::from)
 }
 
-pub(super) fn interpolate_compose_scalar(
+/// Expand Compose variables (`$VAR`, `${VAR}`, `${VAR:-default}`, ...) in
+/// one string; `$$` is a literal `$`.
+pub fn interpolate_compose_scalar(
     input: &str,
     environment: &HashMap<String, String>,
 ) -> Result<String, ComposeInterpolationError> {
@@ -41,6 +41,10 @@ pub enum TeeConfig {
         /// Enable simulation mode (no hardware required, for development)
         #[serde(default)]
         simulate: bool,
+        /// Key Broker Service the box fetches secrets from, speaking the
+        /// confidential-containers KBS protocol (e.g. "https://kbs.example.com")
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        kbs_url: Option<String>,
     },
 
     /// Intel TDX (Trust Domain Extensions) — stub, not yet implemented at runtime.
@@ -232,6 +236,9 @@ impl Default for PoolConfig {
     }
 }
 
+/// Size of the `/dev/shm` tmpfs when `--shm-size` is not given (Docker's default).
+pub const DEFAULT_SHM_SIZE_BYTES: u64 = 64 * 1024 * 1024;
+
 /// Resource limits for a box instance.
 ///
 /// Tier 1 limits (rlimits, cpuset) work on all platforms.
@@ -248,6 +255,12 @@ pub struct ResourceLimits {
     #[serde(default)]
     pub cpuset_cpus: Option<String>,
 
+    /// NUMA node to place the box on (--numa-node).
+    /// The CLI narrows `cpuset_cpus` to the node's CPUs; the shim binds guest
+    /// memory to the node with set_mempolicy(MPOL_BIND) (Linux only).
+    #[serde(default)]
+    pub numa_node: Option<u32>,
+
     /// Custom rlimits (--ulimit), format: "RESOURCE=SOFT:HARD".
     #[serde(default)]
     pub ulimits: Vec<String>,
@@ -284,6 +297,12 @@ pub struct ResourceLimits {
     /// byte-granular and must not silently round the requested limit.
     #[serde(default)]
     pub sandbox_memory_limit_bytes: Option<u64>,
+
+    /// Hard quota in bytes for the writable rootfs layer and default
+    /// workspace (--storage-opt size=...).
+    /// Enforced with a loop-mounted ext4 image (Linux only).
+    #[serde(default)]
+    pub disk_limit_bytes: Option<u64>,
 }
 
 /// Box configuration
@@ -333,6 +352,13 @@ pub struct BoxConfig {
     #[serde(default)]
     pub user: Option<String>,
 
+    /// Supplementary groups for the container process (`--group-add`).
+    ///
+    /// Each entry is a numeric GID or a group name resolved against the
+    /// image's `/etc/group` inside the guest.
+    #[serde(default)]
+    pub group_add: Vec<String>,
+
     /// Working directory override for the initial container process.
     #[serde(default)]
     pub workdir: Option<String>,
@@ -369,12 +395,43 @@ pub struct BoxConfig {
     #[serde(default)]
     pub deferred_main: bool,
 
+    /// Boot a TEE box IDLE until secrets are provisioned: the container main
+    /// starts on the `spawn-main` trigger the host sends after attesting the
+    /// guest and injecting its secrets over RA-TLS, so the workload never runs
+    /// without them.
+    #[serde(default)]
+    pub await_secrets: bool,
+
+    /// Size in bytes of an encrypted scratch disk mounted at `/workspace` in
+    /// place of the workspace share (TEE only). The disk is formatted with a
+    /// fresh key each boot, and the box boots IDLE until the host releases
+    /// the key to the attested guest.
+    #[serde(default)]
+    pub encrypted_workspace_bytes: Option<u64>,
+
+    /// Guest kernel to boot instead of the one bundled with libkrunfw (an
+    /// ELF `vmlinux`). Not supported for TEE boxes, whose measured launch
+    /// covers the bundled kernel.
+    #[serde(default)]
+    pub kernel: Option<PathBuf>,
+
     /// Mark guest memory KSM-mergeable so the host kernel dedups identical pages
     /// across same-image VMs (Linux 6.4+; needs /sys/kernel/mm/ksm/run=1 on the
     /// host). Most valuable for pools of same-image sandboxes.
     #[serde(default)]
     pub ksm: bool,
 
+    /// Back guest RAM with transparent hugepages to cut TLB misses for
+    /// memory-heavy workloads such as inference (Linux; host THP must not be
+    /// `never`).
+    #[serde(default)]
+    pub hugepages: bool,
+
+    /// Prefault all guest RAM at boot so the workload never pays first-touch
+    /// host page faults (Linux 5.14+). Commits the full memory size up front.
+    #[serde(default)]
+    pub mem_prealloc: bool,
+
     /// Snapshot-fork (per-VM): file-backed guest RAM path for a snapshot TEMPLATE
     /// (paired with `snapshot_sock`), or the RAM file to MAP_PRIVATE CoW-restore
     /// from (paired with `restore_from`).
@@ -402,6 +459,36 @@ pub struct BoxConfig {
     #[serde(default)]
     pub dns: Vec<String>,
 
+    /// DNS search domains for the guest `/etc/resolv.conf` (`--dns-search`).
+    #[serde(default)]
+    pub dns_search: Vec<String>,
+
+    /// Resolver options for the guest `/etc/resolv.conf` (`--dns-option`).
+    #[serde(default)]
+    pub dns_options: Vec<String>,
+
+    /// Extra DNS names for the box on its bridge network (`--network-alias`).
+    #[serde(default)]
+    pub network_aliases: Vec<String>,
+
+    /// Egress allow/deny clauses (`--network-policy`), see
+    /// [`crate::egress::EgressPolicy::parse_specs`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network_policy: Vec<String>,
+
+    /// Outbound bandwidth limit in bytes per second (`--network-bandwidth`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_bandwidth: Option<u64>,
+
+    /// Concurrent outbound TCP connection limit (`--network-max-conns`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_max_conns: Option<u32>,
+
+    /// Route the workload's HTTP(S) through an audited host proxy
+    /// (`--http-proxy`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_proxy: Option<crate::http_proxy::HttpProxyMode>,
+
     /// Static host-to-IP mappings for `/etc/hosts` (`HOST:IP`).
     #[serde(default)]
     pub add_hosts: Vec<String>,
@@ -412,9 +499,20 @@ pub struct BoxConfig {
 
     /// tmpfs mounts (ephemeral in-guest filesystems).
     /// Format: "/path" or "/path:size=100m"
+    /// Without a `/dev/shm` entry, guest init mounts one of
+    /// [`DEFAULT_SHM_SIZE_BYTES`].
     #[serde(default)]
     pub tmpfs: Vec<String>,
 
+    /// Block devices / disk images attached as virtio-blk disks and mounted
+    /// by guest init (`block` volume driver).
+    #[serde(default)]
+    pub block_volumes: Vec<crate::volume::BlockVolume>,
+
+    /// Host Unix sockets shared into the guest (`--mount type=socket`).
+    #[serde(default)]
+    pub socket_mounts: Vec<crate::volume::SocketMount>,
+
     /// Resource limits (PID limits, CPU pinning, ulimits, cgroup controls).
     #[serde(default)]
     pub resource_limits: ResourceLimits,
@@ -433,15 +531,26 @@ pub struct BoxConfig {
 
     /// Kernel sysctls (name → value) applied in the guest at boot.
     ///
-    /// Pod-level sysctls from the CRI `PodSandboxConfig`; the guest writes each
+    /// From `--sysctl` or the CRI `PodSandboxConfig`; the guest writes each
     /// to `/proc/sys/<name with '.' as '/'>` once the VM is up.
     #[serde(default)]
     pub sysctls: Vec<(String, String)>,
 
+    /// Guest kernel modules loaded at boot, before the sysctls.
+    #[serde(default)]
+    pub kernel_modules: Vec<String>,
+
     /// Run in privileged mode (disables all security restrictions)
     #[serde(default)]
     pub privileged: bool,
 
+    /// Device nodes to expose (`--device host[:container][:rwm]`).
+    ///
+    /// Limited to the devices the guest kernel virtualizes; see
+    /// [`crate::device::VIRTUALIZED_DEVICES`].
+    #[serde(default)]
+    pub devices: Vec<String>,
+
     /// Mount the container rootfs as read-only.
     ///
     /// Volume mounts (-v host:guest) remain writable by default.
@@ -457,6 +566,11 @@ pub struct BoxConfig {
     #[serde(default)]
     pub sidecar: Option<SidecarConfig>,
 
+    /// Extra long-running services guest init supervises next to the main
+    /// container, with restart policies, dependencies, and health checks.
+    #[serde(default)]
+    pub services: Vec<crate::service::ServiceSpec>,
+
     /// Preserve the box filesystem across stop/start cycles.
     ///
     /// When true, the overlay upper layer (or copy rootfs) is kept on disk
@@ -467,6 +581,11 @@ pub struct BoxConfig {
     /// giving a clean slate on each start.
     #[serde(default)]
     pub persistent: bool,
+
+    /// Template that seeds the workspace when it is first created
+    /// (`--workspace-template`).
+    #[serde(default)]
+    pub workspace_template: Option<crate::workspace_template::WorkspaceTemplate>,
 }
 
 impl Default for BoxConfig {
@@ -485,6 +604,7 @@ impl Default for BoxConfig {
             stdin_open: false,
             entrypoint_override: None,
             user: None,
+            group_add: vec![],
             workdir: None,
             hostname: None,
             volumes: vec![],
@@ -493,24 +613,42 @@ impl Default for BoxConfig {
             cache: CacheConfig::default(),
             pool: PoolConfig::default(),
             deferred_main: false,
+            await_secrets: false,
+            encrypted_workspace_bytes: None,
+            kernel: None,
             ksm: false,
+            hugepages: false,
+            mem_prealloc: false,
             snapshot_mem_file: None,
             snapshot_sock: None,
             restore_from: None,
             port_map: vec![],
             dns: vec![],
+            dns_search: vec![],
+            dns_options: vec![],
+            network_aliases: vec![],
+            network_policy: vec![],
+            network_bandwidth: None,
+            network_max_conns: None,
+            http_proxy: None,
             add_hosts: vec![],
             network: NetworkMode::default(),
             tmpfs: vec![],
+            block_volumes: vec![],
+            socket_mounts: vec![],
             resource_limits: ResourceLimits::default(),
             cap_add: vec![],
             cap_drop: vec![],
             security_opt: vec![],
             sysctls: vec![],
+            kernel_modules: vec![],
             privileged: false,
+            devices: vec![],
             read_only: false,
             sidecar: None,
+            services: vec![],
             persistent: false,
+            workspace_template: None,
         }
     }
 }
@@ -681,6 +819,7 @@ mod tests {
     fn test_box_config_user_workdir_serde() {
         let config = BoxConfig {
             user: Some("1000:1000".to_string()),
+            group_add: vec!["video".to_string(), "999".to_string()],
             workdir: Some("/app".to_string()),
             ..Default::default()
         };
@@ -689,6 +828,7 @@ mod tests {
         let parsed: BoxConfig = serde_json::from_str(&json).unwrap();
 
         assert_eq!(parsed.user.as_deref(), Some("1000:1000"));
+        assert_eq!(parsed.group_add, vec!["video", "999"]);
         assert_eq!(parsed.workdir.as_deref(), Some("/app"));
     }
 
@@ -848,6 +988,7 @@ mod tests {
             workload_id: "test-agent".to_string(),
             generation: SevSnpGeneration::Milan,
             simulate: false,
+            kbs_url: None,
         };
 
         match tee {
@@ -855,6 +996,7 @@ mod tests {
                 workload_id,
                 generation,
                 simulate,
+                ..
             } => {
                 assert_eq!(workload_id, "test-agent");
                 assert_eq!(generation, SevSnpGeneration::Milan);
@@ -882,6 +1024,7 @@ mod tests {
             workload_id: "my-workload".to_string(),
             generation: SevSnpGeneration::Genoa,
             simulate: false,
+            kbs_url: None,
         };
 
         let json = serde_json::to_string(&tee).unwrap();
@@ -940,6 +1083,7 @@ mod tests {
                 workload_id: "secure-agent".to_string(),
                 generation: SevSnpGeneration::Milan,
                 simulate: false,
+                kbs_url: None,
             },
             ..Default::default()
         };
@@ -952,6 +1096,7 @@ mod tests {
                 workload_id,
                 generation,
                 simulate,
+                ..
             } => {
                 assert_eq!(workload_id, "secure-agent");
                 assert_eq!(generation, SevSnpGeneration::Milan);
@@ -1140,6 +1285,7 @@ mod tests {
         let limits = ResourceLimits {
             pids_limit: Some(100),
             cpuset_cpus: Some("0,1".to_string()),
+            numa_node: Some(1),
             ulimits: vec!["nofile=1024:4096".to_string()],
             cpu_shares: Some(512),
             cpu_quota: Some(50000),
@@ -1147,6 +1293,7 @@ mod tests {
             memory_reservation: Some(256 * 1024 * 1024),
             memory_swap: Some(1024 * 1024 * 1024),
             sandbox_memory_limit_bytes: Some(256 * 1024 * 1024),
+            disk_limit_bytes: Some(1024 * 1024 * 1024),
         };
 
         let json = serde_json::to_string(&limits).unwrap();
@@ -1154,6 +1301,7 @@ mod tests {
 
         assert_eq!(parsed.pids_limit, Some(100));
         assert_eq!(parsed.cpuset_cpus, Some("0,1".to_string()));
+        assert_eq!(parsed.numa_node, Some(1));
         assert_eq!(parsed.ulimits, vec!["nofile=1024:4096"]);
         assert_eq!(parsed.cpu_shares, Some(512));
         assert_eq!(parsed.cpu_quota, Some(50000));
@@ -0,0 +1,502 @@
+//! Device node passthrough policy.
+//!
+//! libkrun cannot hand arbitrary host character devices to a guest, so
+//! `--device` serves two kinds of request:
+//!
+//! - Devices the guest kernel virtualizes itself (FUSE, TUN, loop control).
+//!   Guest init creates the node with the matching major/minor inside the
+//!   container.
+//! - Allowlisted host device families ([`PROXYABLE_DEVICE_PREFIXES`]: serial
+//!   adapters, video capture, raw HID) are proxied as a byte stream: the shim
+//!   opens the host device and bridges it over vsock port
+//!   [`device_proxy_vsock_port`]`(n)`, and guest init exposes the stream at
+//!   the container path as a raw pseudo-terminal. Reads, writes and poll
+//!   work; device-specific ioctls (V4L2 capture, serial line settings) do not
+//!   reach the host device.
+//!
+//! Everything else is rejected, so memory and port devices such as
+//! `/dev/mem` never reach a guest.
+
+use serde::{Deserialize, Serialize};
+
+/// A device the guest kernel provides, keyed by its canonical `/dev` path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualDevice {
+    /// Canonical device path requested on the command line.
+    pub path: &'static str,
+    /// Character device major number.
+    pub major: u32,
+    /// Character device minor number.
+    pub minor: u32,
+}
+
+/// Devices that may be requested with `--device`.
+pub const VIRTUALIZED_DEVICES: &[VirtualDevice] = &[
+    VirtualDevice {
+        path: "/dev/fuse",
+        major: 10,
+        minor: 229,
+    },
+    VirtualDevice {
+        path: "/dev/net/tun",
+        major: 10,
+        minor: 200,
+    },
+    VirtualDevice {
+        path: "/dev/loop-control",
+        major: 10,
+        minor: 237,
+    },
+];
+
+/// Environment prefix carrying resolved device nodes to guest init.
+///
+/// Format: `BOX_DEVICE_<index>=<container_path>:<major>:<minor>:<mode>` with
+/// the mode in octal.
+pub const DEVICE_ENV_PREFIX: &str = "BOX_DEVICE_";
+
+/// A character device node guest init creates in the container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuestDeviceNode {
+    /// Absolute path of the node inside the container.
+    pub path: String,
+    /// Character device major number.
+    pub major: u32,
+    /// Character device minor number.
+    pub minor: u32,
+    /// Permission bits applied to the node.
+    pub mode: u32,
+}
+
+impl GuestDeviceNode {
+    /// Encode for the `BOX_DEVICE_<n>` environment channel.
+    pub fn to_env_value(&self) -> String {
+        format!(
+            "{}:{}:{}:{:o}",
+            self.path, self.major, self.minor, self.mode
+        )
+    }
+
+    /// Decode a `BOX_DEVICE_<n>` value produced by [`Self::to_env_value`].
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        let mut parts = value.rsplitn(4, ':');
+        let mode = parts.next();
+        let minor = parts.next();
+        let major = parts.next();
+        let path = parts.next();
+        let (Some(path), Some(major), Some(minor), Some(mode)) = (path, major, minor, mode) else {
+            return Err(format!("Invalid device node entry '{value}'"));
+        };
+        validate_container_path(path)?;
+        Ok(Self {
+            path: path.to_string(),
+            major: major
+                .parse()
+                .map_err(|_| format!("Invalid device major in '{value}'"))?,
+            minor: minor
+                .parse()
+                .map_err(|_| format!("Invalid device minor in '{value}'"))?,
+            mode: u32::from_str_radix(mode, 8)
+                .map_err(|_| format!("Invalid device mode in '{value}'"))?,
+        })
+    }
+}
+
+/// Host devices that may be proxied: a family prefix followed by the device
+/// number (`/dev/ttyUSB0`).
+pub const PROXYABLE_DEVICE_PREFIXES: &[&str] = &[
+    "/dev/ttyUSB",
+    "/dev/ttyACM",
+    "/dev/ttyS",
+    "/dev/ttyAMA",
+    "/dev/video",
+    "/dev/hidraw",
+];
+
+/// udev directories of stable serial links; a link is proxyable when its
+/// target is.
+const PROXYABLE_LINK_DIRS: &[&str] = &["/dev/serial/by-id/", "/dev/serial/by-path/"];
+
+/// Whether `path` names a device that may be proxied.
+pub fn is_proxyable_device(path: &str) -> bool {
+    is_proxyable_device_node(path)
+        || PROXYABLE_LINK_DIRS.iter().any(|dir| {
+            path.strip_prefix(dir)
+                .is_some_and(|name| !name.is_empty() && !name.contains('/'))
+        })
+}
+
+fn is_proxyable_device_node(path: &str) -> bool {
+    PROXYABLE_DEVICE_PREFIXES.iter().any(|prefix| {
+        path.strip_prefix(prefix)
+            .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+/// Environment prefix carrying proxied host devices to guest init.
+///
+/// Format: `BOX_DEVICE_PROXY_<index>=<container_path>:<ro|rw>`; the stream is
+/// relayed over vsock port [`device_proxy_vsock_port`]`(index)`.
+pub const DEVICE_PROXY_ENV_PREFIX: &str = "BOX_DEVICE_PROXY_";
+
+/// Vsock port of the first proxied host device.
+pub const DEVICE_PROXY_VSOCK_PORT_BASE: u32 = 4140;
+
+/// Most proxied host devices one box may have.
+pub const MAX_PROXIED_DEVICES: usize = 16;
+
+/// Vsock port carrying the `index`th proxied host device.
+pub fn device_proxy_vsock_port(index: usize) -> u32 {
+    DEVICE_PROXY_VSOCK_PORT_BASE + index as u32
+}
+
+/// File name of the host socket the shim serves for the `index`th proxied
+/// device, next to the box's exec socket.
+pub fn device_proxy_socket_file(index: usize) -> String {
+    format!("device-{index}.sock")
+}
+
+/// A host character device relayed into the guest as a byte stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxiedDevice {
+    /// Host device path the shim opens.
+    pub source: String,
+    /// Absolute path of the node inside the container.
+    pub target: String,
+    /// Open the host device read-only.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl ProxiedDevice {
+    /// Encode for the `BOX_DEVICE_PROXY_<n>` environment channel.
+    pub fn to_env_value(&self) -> String {
+        format!(
+            "{}:{}",
+            self.target,
+            if self.read_only { "ro" } else { "rw" }
+        )
+    }
+
+    /// Decode a `BOX_DEVICE_PROXY_<n>` value into the container path and
+    /// whether it is read-only.
+    pub fn parse_env_value(value: &str) -> Result<(String, bool), String> {
+        let (target, access) = value
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Invalid proxied device entry '{value}'"))?;
+        validate_container_path(target)?;
+        let read_only = match access {
+            "ro" => true,
+            "rw" => false,
+            _ => return Err(format!("Invalid proxied device access in '{value}'")),
+        };
+        Ok((target.to_string(), read_only))
+    }
+}
+
+/// How a `--device` request reaches the container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceRequest {
+    /// A device the guest kernel provides; guest init creates the node.
+    Virtual(GuestDeviceNode),
+    /// A host device relayed over vsock.
+    Proxied(ProxiedDevice),
+}
+
+/// Classify a `--device host[:container][:permissions]` request.
+///
+/// Virtualized devices resolve to guest nodes as in [`resolve_device`];
+/// proxyable ones (see [`is_proxyable_device`]) are relayed from the host,
+/// and anything else is rejected. Whether the host path is a character
+/// device is checked on the host with [`check_host_device`].
+pub fn parse_device(input: &str) -> Result<DeviceRequest, String> {
+    let (host_path, container_path, permissions) = split_device(input)?;
+    if VIRTUALIZED_DEVICES
+        .iter()
+        .any(|device| device.path == host_path)
+    {
+        return resolve_device(input).map(DeviceRequest::Virtual);
+    }
+    validate_container_path(host_path)
+        .map_err(|_| format!("Invalid host device path '{host_path}' (must be under /dev)"))?;
+    if !is_proxyable_device(host_path) {
+        return Err(format!(
+            "Device '{host_path}' is not in the passthrough allowlist (supported: {}, \
+             and proxied {}<n> devices)",
+            VIRTUALIZED_DEVICES
+                .iter()
+                .map(|device| device.path)
+                .collect::<Vec<_>>()
+                .join(", "),
+            PROXYABLE_DEVICE_PREFIXES.join("<n>, ")
+        ));
+    }
+    Ok(DeviceRequest::Proxied(ProxiedDevice {
+        source: host_path.to_string(),
+        target: container_path.to_string(),
+        read_only: !permissions.contains('w'),
+    }))
+}
+
+/// Split `--device` requests into guest nodes and proxied host devices.
+pub fn partition_devices(
+    inputs: &[String],
+) -> Result<(Vec<GuestDeviceNode>, Vec<ProxiedDevice>), String> {
+    let mut nodes = Vec::new();
+    let mut proxied = Vec::new();
+    for input in inputs {
+        match parse_device(input)? {
+            DeviceRequest::Virtual(node) => nodes.push(node),
+            DeviceRequest::Proxied(device) => proxied.push(device),
+        }
+    }
+    if proxied.len() > MAX_PROXIED_DEVICES {
+        return Err(format!(
+            "Too many proxied devices (at most {MAX_PROXIED_DEVICES} per box)"
+        ));
+    }
+    Ok((nodes, proxied))
+}
+
+/// Check that `path` is an allowlisted host character device that can be
+/// proxied. A link must resolve to an allowlisted device too.
+pub fn check_host_device(path: &str) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        if !is_proxyable_device(path) {
+            return Err(format!(
+                "Host device '{path}' is not in the passthrough allowlist"
+            ));
+        }
+        let target = std::fs::canonicalize(path)
+            .map_err(|e| format!("Host device '{path}' is not available: {e}"))?;
+        if !target.to_str().is_some_and(is_proxyable_device_node) {
+            return Err(format!(
+                "Host device '{path}' resolves to '{}', which is not in the passthrough allowlist",
+                target.display()
+            ));
+        }
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| format!("Host device '{path}' is not available: {e}"))?;
+        if !metadata.file_type().is_char_device() {
+            return Err(format!(
+                "Host device '{path}' is not a character device; only character devices can be proxied"
+            ));
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        Err(format!(
+            "Host device '{path}' cannot be proxied: host device passthrough is not supported on this platform"
+        ))
+    }
+}
+
+/// Resolve a `--device host[:container][:permissions]` request for a device
+/// the guest kernel virtualizes.
+///
+/// Permissions follow Docker's `rwm` letters; `r`/`w` select the node's
+/// read/write bits and `m` is accepted for compatibility.
+pub fn resolve_device(input: &str) -> Result<GuestDeviceNode, String> {
+    let (host_path, container_path, permissions) = split_device(input)?;
+
+    let device = VIRTUALIZED_DEVICES
+        .iter()
+        .find(|device| device.path == host_path)
+        .ok_or_else(|| {
+            format!(
+                "Device '{host_path}' is not in the passthrough allowlist (supported: {})",
+                VIRTUALIZED_DEVICES
+                    .iter()
+                    .map(|device| device.path)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+
+    let mut mode = 0;
+    if permissions.contains('r') {
+        mode |= 0o444;
+    }
+    if permissions.contains('w') {
+        mode |= 0o222;
+    }
+    Ok(GuestDeviceNode {
+        path: container_path.to_string(),
+        major: device.major,
+        minor: device.minor,
+        mode,
+    })
+}
+
+/// Split a request into host path, container path and permissions.
+fn split_device(input: &str) -> Result<(&str, &str, &str), String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Invalid device: value must not be empty".to_string());
+    }
+
+    let parts: Vec<&str> = input.split(':').collect();
+    let (host_path, container_path, permissions) = match parts.as_slice() {
+        [host] => (*host, *host, "rwm"),
+        [host, second] if is_permissions(second) => (*host, *host, *second),
+        [host, container] => (*host, *container, "rwm"),
+        [host, container, permissions] => (*host, *container, *permissions),
+        _ => return Err(format!("Invalid device '{input}'")),
+    };
+    if !is_permissions(permissions) {
+        return Err(format!(
+            "Invalid device permissions '{permissions}' in '{input}' (expected a combination of r, w, m)"
+        ));
+    }
+    validate_container_path(container_path)?;
+    Ok((host_path, container_path, permissions))
+}
+
+fn is_permissions(value: &str) -> bool {
+    !value.is_empty() && value.len() <= 3 && value.chars().all(|c| matches!(c, 'r' | 'w' | 'm'))
+}
+
+fn validate_container_path(path: &str) -> Result<(), String> {
+    if !path.starts_with("/dev/") || path.split('/').any(|part| part == "..") {
+        return Err(format!(
+            "Invalid device path '{path}' (must be an absolute path under /dev)"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_device_host_only() {
+        let node = resolve_device("/dev/fuse").unwrap();
+        assert_eq!(node.path, "/dev/fuse");
+        assert_eq!((node.major, node.minor), (10, 229));
+        assert_eq!(node.mode, 0o666);
+    }
+
+    #[test]
+    fn test_resolve_device_container_path_and_permissions() {
+        let node = resolve_device("/dev/net/tun:/dev/tun0:r").unwrap();
+        assert_eq!(node.path, "/dev/tun0");
+        assert_eq!((node.major, node.minor), (10, 200));
+        assert_eq!(node.mode, 0o444);
+
+        let node = resolve_device("/dev/fuse:rw").unwrap();
+        assert_eq!(node.path, "/dev/fuse");
+        assert_eq!(node.mode, 0o666);
+    }
+
+    #[test]
+    fn test_resolve_device_rejects_unlisted_device() {
+        let err = resolve_device("/dev/sda").unwrap_err();
+        assert!(err.contains("allowlist"), "got: {err}");
+        assert!(err.contains("/dev/fuse"), "got: {err}");
+    }
+
+    #[test]
+    fn test_resolve_device_rejects_bad_paths_and_permissions() {
+        assert!(resolve_device("/dev/fuse:/etc/fuse").is_err());
+        assert!(resolve_device("/dev/fuse:/dev/../etc/x").is_err());
+        assert!(resolve_device("/dev/fuse:/dev/fuse:rx").is_err());
+        assert!(resolve_device("").is_err());
+    }
+
+    #[test]
+    fn test_guest_device_node_env_roundtrip() {
+        let node = resolve_device("/dev/loop-control").unwrap();
+        let value = node.to_env_value();
+        assert_eq!(value, "/dev/loop-control:10:237:666");
+        assert_eq!(GuestDeviceNode::from_env_value(&value).unwrap(), node);
+        assert!(GuestDeviceNode::from_env_value("/dev/fuse:10").is_err());
+    }
+
+    #[test]
+    fn test_parse_device_proxies_other_host_devices() {
+        assert!(matches!(
+            parse_device("/dev/fuse").unwrap(),
+            DeviceRequest::Virtual(_)
+        ));
+        assert_eq!(
+            parse_device("/dev/ttyUSB0:/dev/ttyS9:r").unwrap(),
+            DeviceRequest::Proxied(ProxiedDevice {
+                source: "/dev/ttyUSB0".to_string(),
+                target: "/dev/ttyS9".to_string(),
+                read_only: true,
+            })
+        );
+        assert!(parse_device("/etc/passwd:/dev/passwd").is_err());
+
+        let inputs = vec!["/dev/net/tun".to_string(), "/dev/video0".to_string()];
+        let (nodes, proxied) = partition_devices(&inputs).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(proxied[0].target, "/dev/video0");
+        assert!(!proxied[0].read_only);
+        let too_many = vec!["/dev/ttyACM0".to_string(); MAX_PROXIED_DEVICES + 1];
+        assert!(partition_devices(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_proxied_device_env_roundtrip_and_ports() {
+        let device = ProxiedDevice {
+            source: "/dev/ttyUSB0".to_string(),
+            target: "/dev/ttyUSB0".to_string(),
+            read_only: false,
+        };
+        assert_eq!(device.to_env_value(), "/dev/ttyUSB0:rw");
+        assert_eq!(
+            ProxiedDevice::parse_env_value("/dev/ttyUSB0:rw").unwrap(),
+            ("/dev/ttyUSB0".to_string(), false)
+        );
+        assert!(ProxiedDevice::parse_env_value("/dev/ttyUSB0").is_err());
+        assert!(ProxiedDevice::parse_env_value("/tmp/x:ro").is_err());
+
+        // The range must stay clear of shared socket mounts.
+        assert!(
+            device_proxy_vsock_port(0)
+                >= crate::volume::socket_mount_vsock_port(crate::volume::MAX_SOCKET_MOUNTS)
+        );
+        assert_eq!(device_proxy_socket_file(1), "device-1.sock");
+    }
+
+    #[test]
+    fn test_parse_device_rejects_unlisted_host_devices() {
+        for device in [
+            "/dev/mem",
+            "/dev/kmem",
+            "/dev/port",
+            "/dev/sda",
+            "/dev/ttyUSB",
+        ] {
+            let err = parse_device(device).unwrap_err();
+            assert!(err.contains("allowlist"), "{device}: {err}");
+        }
+        assert!(parse_device("/dev/mem:/dev/ttyUSB0").is_err());
+        assert!(matches!(
+            parse_device("/dev/serial/by-id/usb-FTDI_FT232R-if00-port0").unwrap(),
+            DeviceRequest::Proxied(_)
+        ));
+        assert!(is_proxyable_device("/dev/ttyAMA0"));
+        assert!(!is_proxyable_device("/dev/serial/by-id/x/y"));
+        assert!(!is_proxyable_device("/dev/video0x"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_host_device() {
+        // Character devices outside the allowlist are refused on the host too.
+        assert!(check_host_device("/dev/null")
+            .unwrap_err()
+            .contains("allowlist"));
+        assert!(check_host_device("/dev/mem").is_err());
+        assert!(check_host_device("/dev/ttyUSB4242")
+            .unwrap_err()
+            .contains("not available"));
+    }
+}
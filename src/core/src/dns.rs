@@ -3,11 +3,22 @@
 //! Generates /etc/resolv.conf content from user-specified DNS servers,
 //! host configuration, or sensible defaults.
 
-use std::net::IpAddr;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
 
 /// Default DNS servers (Google Public DNS).
 const DEFAULT_DNS: &[&str] = &["8.8.8.8", "8.8.4.4"];
 
+/// Address of the resolver guest init runs for bridge-networked boxes.
+///
+/// Guests on a user-defined network point `/etc/resolv.conf` here. It answers
+/// box names and aliases from [`EMBEDDED_DNS_RECORDS_PATH`] and forwards every
+/// other query to the configured upstream servers.
+pub const EMBEDDED_DNS_ADDR: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 11);
+
+/// Guest path of the name records served by the embedded resolver.
+pub const EMBEDDED_DNS_RECORDS_PATH: &str = "/etc/a3s-box/dns-records";
+
 /// A static host-to-IP mapping for `/etc/hosts`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HostEntry {
@@ -46,6 +57,60 @@ pub fn generate_resolv_conf(custom_dns: &[String]) -> String {
         + "\n"
 }
 
+/// Generate resolv.conf content with `--dns-search` / `--dns-option` applied.
+///
+/// Nameservers are chosen as in [`generate_resolv_conf`]. Search domains and
+/// options come only from the box configuration (the host's are never
+/// inherited); a lone `.` search domain means "no search domains", as in
+/// `docker run --dns-search .`.
+pub fn generate_resolv_conf_with_options(
+    custom_dns: &[String],
+    searches: &[String],
+    options: &[String],
+) -> String {
+    let mut out = generate_resolv_conf(custom_dns);
+    let searches: Vec<&str> = searches
+        .iter()
+        .map(String::as_str)
+        .filter(|domain| *domain != ".")
+        .collect();
+    if !searches.is_empty() {
+        out.push_str("search ");
+        out.push_str(&searches.join(" "));
+        out.push('\n');
+    }
+    if !options.is_empty() {
+        out.push_str("options ");
+        out.push_str(&options.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+/// Validate a `--dns-search` domain.
+pub fn validate_dns_search(domain: &str) -> Result<(), String> {
+    if domain == "." {
+        return Ok(());
+    }
+    validate_hostname(domain).map_err(|e| format!("invalid search domain '{domain}': {e}"))
+}
+
+/// Validate a `--dns-option` resolver option (`NAME` or `NAME:VALUE`).
+pub fn validate_dns_option(option: &str) -> Result<(), String> {
+    let name = option.split_once(':').map_or(option, |(name, _)| name);
+    if name.is_empty()
+        || option.chars().any(char::is_whitespace)
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "invalid resolver option '{option}' (expected NAME or NAME:VALUE, e.g. ndots:2)"
+        ));
+    }
+    Ok(())
+}
+
 /// Render `/etc/resolv.conf` content from explicit DNS settings.
 ///
 /// Emits one `nameserver` line per server, a single `search` line (when any
@@ -129,6 +194,47 @@ pub fn generate_hosts_file_with_entries(
     lines.join("\n") + "\n"
 }
 
+/// Generate the embedded resolver's name records for a box on a network.
+///
+/// Uses the hosts-file layout (`IP NAME...` per line) so the file stays
+/// readable when debugging from inside the guest. Peers come first so the
+/// box's own names cannot shadow them.
+pub fn generate_dns_records(
+    own_ip: &str,
+    own_names: &[String],
+    peers: &[(String, String)], // (ip, name)
+) -> String {
+    let mut lines = vec!["# Generated by a3s-box; served at 127.0.0.11".to_string()];
+    for (ip, name) in peers {
+        lines.push(format!("{} {}", ip, name));
+    }
+    if !own_names.is_empty() {
+        lines.push(format!("{} {}", own_ip, own_names.join(" ")));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Parse name records into a lowercase name → IPv4 map.
+///
+/// Comments, malformed lines, and non-IPv4 addresses are ignored; the first
+/// line naming a host wins.
+pub fn parse_dns_records(content: &str) -> HashMap<String, Ipv4Addr> {
+    let mut records = HashMap::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let Some(ip) = fields.next().and_then(|ip| ip.parse::<Ipv4Addr>().ok()) else {
+            continue;
+        };
+        for name in fields {
+            records
+                .entry(name.trim_end_matches('.').to_ascii_lowercase())
+                .or_insert(ip);
+        }
+    }
+    records
+}
+
 /// Validate a hostname or DNS name accepted by a3s-box runtime options.
 pub fn validate_hostname(hostname: &str) -> Result<(), String> {
     if hostname.is_empty() {
@@ -205,6 +311,35 @@ mod tests {
         assert_eq!(result, "nameserver 1.1.1.1\nnameserver 1.0.0.1\n");
     }
 
+    #[test]
+    fn test_generate_resolv_conf_with_options() {
+        let result = generate_resolv_conf_with_options(
+            &["10.0.0.2".to_string()],
+            &["corp.example".to_string(), "example".to_string()],
+            &["ndots:2".to_string(), "edns0".to_string()],
+        );
+        assert_eq!(
+            result,
+            "nameserver 10.0.0.2\nsearch corp.example example\noptions ndots:2 edns0\n"
+        );
+
+        let result =
+            generate_resolv_conf_with_options(&["10.0.0.2".to_string()], &[".".to_string()], &[]);
+        assert_eq!(result, "nameserver 10.0.0.2\n");
+    }
+
+    #[test]
+    fn test_validate_dns_search_and_option() {
+        assert!(validate_dns_search("corp.example").is_ok());
+        assert!(validate_dns_search(".").is_ok());
+        assert!(validate_dns_search("bad domain").is_err());
+
+        assert!(validate_dns_option("ndots:2").is_ok());
+        assert!(validate_dns_option("single-request-reopen").is_ok());
+        assert!(validate_dns_option("ndots: 2").is_err());
+        assert!(validate_dns_option(":2").is_err());
+    }
+
     #[test]
     fn test_render_resolv_conf() {
         let servers = vec!["10.10.10.10".to_string(), "10.10.10.11".to_string()];
@@ -322,4 +457,31 @@ mod tests {
         assert!(parse_add_host_entry("bad_host:10.0.0.1").is_err());
         assert!(parse_add_host_entry("host:not-an-ip").is_err());
     }
+
+    #[test]
+    fn test_dns_records_roundtrip() {
+        let content = generate_dns_records(
+            "10.88.0.2",
+            &["web".to_string()],
+            &[
+                ("10.88.0.3".to_string(), "proj-db".to_string()),
+                ("10.88.0.3".to_string(), "DB".to_string()),
+            ],
+        );
+        let records = parse_dns_records(&content);
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records["db"], Ipv4Addr::new(10, 88, 0, 3));
+        assert_eq!(records["proj-db"], Ipv4Addr::new(10, 88, 0, 3));
+        assert_eq!(records["web"], Ipv4Addr::new(10, 88, 0, 2));
+    }
+
+    #[test]
+    fn test_parse_dns_records_skips_invalid_lines() {
+        let records =
+            parse_dns_records("# comment\nnot-an-ip web\n2001:db8::1 v6\n10.0.0.5 api. # x\n");
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records["api"], Ipv4Addr::new(10, 0, 0, 5));
+    }
 }
@@ -0,0 +1,412 @@
+//! Per-box egress network policy.
+//!
+//! `--network-policy allow=<targets>` / `deny=<targets>` restricts where a box
+//! may connect. Targets are CIDRs (`10.0.0.0/8`, `2001:db8::/32`) or domain
+//! names (`api.anthropic.com`, which also covers its subdomains). Any `allow`
+//! clause makes the policy deny-by-default.
+//!
+//! Guest init enforces the policy: CIDRs become nftables rules on the output
+//! hook, and domains are enforced by the embedded resolver, which refuses
+//! names the policy blocks and adds the addresses it resolves for allowed
+//! names to the firewall's allow set.
+//!
+//! `--network-bandwidth` and `--network-max-conns` cap how much the box can
+//! send and how many outbound connections it can hold open. Guest init shapes
+//! the interface with a token-bucket qdisc and adds a connection-count rule to
+//! the same firewall table.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+
+/// Guest environment variable carrying the policy as JSON.
+pub const EGRESS_POLICY_ENV: &str = "A3S_NET_EGRESS_POLICY";
+
+/// Guest environment variable carrying the bandwidth limit in bytes per second.
+pub const BANDWIDTH_ENV: &str = "A3S_NET_BANDWIDTH";
+
+/// Guest environment variable carrying the outbound connection limit.
+pub const MAX_CONNS_ENV: &str = "A3S_NET_MAX_CONNS";
+
+/// Smallest accepted bandwidth limit (8kbit), in bytes per second.
+const MIN_BANDWIDTH: u64 = 1000;
+
+/// Parse a `--network-bandwidth` rate into bytes per second.
+///
+/// Rates use tc units: `bit`, `kbit`, `mbit`, `gbit` (bits per second, powers
+/// of 1000), e.g. `512kbit` or `1.5mbit`.
+pub fn parse_bandwidth(value: &str) -> Result<u64, String> {
+    let lower = value.trim().to_ascii_lowercase();
+    let invalid = || format!("invalid --network-bandwidth '{value}': expected e.g. 10mbit");
+    let number = lower.strip_suffix("bit").ok_or_else(invalid)?;
+    let (number, multiplier) = match number.as_bytes().last() {
+        Some(b'k') => (&number[..number.len() - 1], 1e3),
+        Some(b'm') => (&number[..number.len() - 1], 1e6),
+        Some(b'g') => (&number[..number.len() - 1], 1e9),
+        _ => (number, 1.0),
+    };
+    let bits: f64 = number.parse().map_err(|_| invalid())?;
+    if !bits.is_finite() || bits <= 0.0 {
+        return Err(invalid());
+    }
+    let bytes = bits * multiplier / 8.0;
+    if bytes < MIN_BANDWIDTH as f64 {
+        return Err(format!(
+            "invalid --network-bandwidth '{value}': must be at least 8kbit"
+        ));
+    }
+    if bytes > u64::MAX as f64 {
+        return Err(format!("invalid --network-bandwidth '{value}': too large"));
+    }
+    Ok(bytes as u64)
+}
+
+/// An IPv4 or IPv6 network in CIDR notation, with host bits cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    /// Network address.
+    pub addr: IpAddr,
+    /// Prefix length (0-32 for IPv4, 0-128 for IPv6).
+    pub prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Parse `addr/prefix`, or a bare address as a host route.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("'{value}' is not an IP address or CIDR"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("invalid prefix length in '{value}' (0-{max})"))?,
+            None => max,
+        };
+        Ok(Self {
+            addr: mask_addr(addr, prefix_len),
+            prefix_len,
+        })
+    }
+
+    /// Whether `ip` falls inside this network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        ip.is_ipv4() == self.addr.is_ipv4() && mask_addr(ip, self.prefix_len) == self.addr
+    }
+
+    /// Network mask bytes (4 for IPv4, 16 for IPv6).
+    pub fn mask_bytes(&self) -> Vec<u8> {
+        let len = if self.addr.is_ipv4() { 4 } else { 16 };
+        (0..len)
+            .map(|i| {
+                let bits = (self.prefix_len as usize).saturating_sub(i * 8).min(8);
+                (0xffu16 << (8 - bits)) as u8
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+fn mask_addr(addr: IpAddr, prefix_len: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(ip) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            IpAddr::V4((u32::from(ip) & mask).into())
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            IpAddr::V6((u128::from(ip) & mask).into())
+        }
+    }
+}
+
+/// One allow or deny target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum EgressTarget {
+    /// An address range.
+    Cidr(IpCidr),
+    /// A domain name and its subdomains (lowercase, no trailing dot).
+    Domain(String),
+}
+
+impl EgressTarget {
+    /// Parse a CIDR, bare IP address, or domain name.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err("network policy target must not be empty".to_string());
+        }
+        if value.contains('/') || value.parse::<IpAddr>().is_ok() {
+            return IpCidr::parse(value).map(Self::Cidr);
+        }
+        crate::dns::validate_hostname(value)
+            .map_err(|e| format!("invalid network policy domain: {e}"))?;
+        Ok(Self::Domain(
+            value.trim_end_matches('.').to_ascii_lowercase(),
+        ))
+    }
+
+    fn matches_domain(&self, name: &str) -> bool {
+        match self {
+            Self::Domain(domain) => {
+                name == domain
+                    || name
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            Self::Cidr(_) => false,
+        }
+    }
+
+    fn matches_ip(&self, ip: IpAddr) -> bool {
+        matches!(self, Self::Cidr(cidr) if cidr.contains(ip))
+    }
+}
+
+impl TryFrom<String> for EgressTarget {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<EgressTarget> for String {
+    fn from(target: EgressTarget) -> Self {
+        target.to_string()
+    }
+}
+
+impl fmt::Display for EgressTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cidr(cidr) => cidr.fmt(f),
+            Self::Domain(domain) => f.write_str(domain),
+        }
+    }
+}
+
+/// Allow/deny lists for a box's outbound connections.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EgressPolicy {
+    /// Destinations the box may reach; non-empty means deny-by-default.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<EgressTarget>,
+    /// Destinations the box may never reach; checked before `allow`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<EgressTarget>,
+}
+
+impl EgressPolicy {
+    /// Parse `--network-policy` values: `allow=<t>[,<t>...]` or
+    /// `deny=<t>[,<t>...]`, each clause repeatable.
+    pub fn parse_specs(specs: &[String]) -> Result<Self, String> {
+        let mut policy = Self::default();
+        for spec in specs {
+            let (kind, targets) = spec.split_once('=').ok_or_else(|| {
+                format!(
+                    "invalid --network-policy '{spec}': expected allow=<targets> or deny=<targets>"
+                )
+            })?;
+            let list = match kind.trim() {
+                "allow" => &mut policy.allow,
+                "deny" => &mut policy.deny,
+                other => {
+                    return Err(format!(
+                        "invalid --network-policy '{spec}': unknown list '{other}'"
+                    ))
+                }
+            };
+            for target in targets.split(',') {
+                let target = EgressTarget::parse(target)
+                    .map_err(|e| format!("invalid --network-policy '{spec}': {e}"))?;
+                if !list.contains(&target) {
+                    list.push(target);
+                }
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Whether the policy restricts anything.
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Whether destinations not on the allow list are blocked.
+    pub fn default_deny(&self) -> bool {
+        !self.allow.is_empty()
+    }
+
+    /// Whether the box may resolve (and then reach) `name`.
+    pub fn allows_domain(&self, name: &str) -> bool {
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        if self.deny.iter().any(|target| target.matches_domain(&name)) {
+            return false;
+        }
+        !self.default_deny() || self.allow.iter().any(|target| target.matches_domain(&name))
+    }
+
+    /// Whether `name` is explicitly allowed, so its resolved addresses must be
+    /// added to the firewall's allow set.
+    pub fn explicitly_allows_domain(&self, name: &str) -> bool {
+        self.default_deny() && self.allows_domain(name)
+    }
+
+    /// Whether the box may connect to `ip` by address rules alone.
+    pub fn allows_ip(&self, ip: IpAddr) -> bool {
+        if self.deny.iter().any(|target| target.matches_ip(ip)) {
+            return false;
+        }
+        !self.default_deny() || self.allow.iter().any(|target| target.matches_ip(ip))
+    }
+
+    /// CIDR targets in the allow list.
+    pub fn allowed_cidrs(&self) -> impl Iterator<Item = &IpCidr> {
+        cidrs(&self.allow)
+    }
+
+    /// CIDR targets in the deny list.
+    pub fn denied_cidrs(&self) -> impl Iterator<Item = &IpCidr> {
+        cidrs(&self.deny)
+    }
+
+    /// Encode for the guest environment.
+    pub fn to_env_value(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Decode the guest environment value.
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        serde_json::from_str(value).map_err(|e| format!("invalid egress policy: {e}"))
+    }
+}
+
+fn cidrs(targets: &[EgressTarget]) -> impl Iterator<Item = &IpCidr> {
+    targets.iter().filter_map(|target| match target {
+        EgressTarget::Cidr(cidr) => Some(cidr),
+        EgressTarget::Domain(_) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(specs: &[&str]) -> EgressPolicy {
+        let specs: Vec<String> = specs.iter().map(|spec| spec.to_string()).collect();
+        EgressPolicy::parse_specs(&specs).unwrap()
+    }
+
+    #[test]
+    fn test_ip_cidr_parse_masks_host_bits() {
+        let cidr = IpCidr::parse("10.1.2.3/8").unwrap();
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+        assert!(cidr.contains("10.200.0.1".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("::1".parse().unwrap()));
+
+        assert_eq!(IpCidr::parse("2001:db8::1").unwrap().prefix_len, 128);
+        assert_eq!(IpCidr::parse("0.0.0.0/0").unwrap().mask_bytes(), vec![0; 4]);
+        assert_eq!(
+            IpCidr::parse("10.0.0.0/12").unwrap().mask_bytes(),
+            vec![255, 240, 0, 0]
+        );
+        assert!(IpCidr::parse("10.0.0.0/33").is_err());
+        assert!(IpCidr::parse("example.com/8").is_err());
+    }
+
+    #[test]
+    fn test_parse_specs_collects_allow_and_deny_lists() {
+        let policy = policy(&[
+            "allow=api.anthropic.com,10.0.0.0/8",
+            "deny=169.254.169.254",
+            "allow=API.Anthropic.com.",
+        ]);
+
+        assert_eq!(
+            policy.allow,
+            vec![
+                EgressTarget::Domain("api.anthropic.com".to_string()),
+                EgressTarget::Cidr(IpCidr::parse("10.0.0.0/8").unwrap()),
+            ]
+        );
+        assert_eq!(policy.deny.len(), 1);
+        assert!(policy.default_deny());
+    }
+
+    #[test]
+    fn test_parse_specs_rejects_bad_clauses() {
+        for spec in ["allow", "permit=10.0.0.0/8", "allow=", "deny=bad_name"] {
+            assert!(
+                EgressPolicy::parse_specs(&[spec.to_string()]).is_err(),
+                "{spec}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_allow_list_denies_everything_else() {
+        let policy = policy(&["allow=anthropic.com,10.0.0.0/8", "deny=evil.anthropic.com"]);
+
+        assert!(policy.allows_domain("anthropic.com"));
+        assert!(policy.allows_domain("API.anthropic.com."));
+        assert!(!policy.allows_domain("evil.anthropic.com"));
+        assert!(!policy.allows_domain("notanthropic.com"));
+        assert!(!policy.allows_domain("example.com"));
+        assert!(policy.explicitly_allows_domain("api.anthropic.com"));
+
+        assert!(policy.allows_ip("10.1.2.3".parse().unwrap()));
+        assert!(!policy.allows_ip("8.8.8.8".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_deny_list_alone_allows_everything_else() {
+        let policy = policy(&["deny=169.254.0.0/16,tracker.example"]);
+
+        assert!(!policy.default_deny());
+        assert!(policy.allows_domain("example.com"));
+        assert!(!policy.allows_domain("cdn.tracker.example"));
+        assert!(!policy.explicitly_allows_domain("example.com"));
+        assert!(policy.allows_ip("8.8.8.8".parse().unwrap()));
+        assert!(!policy.allows_ip("169.254.169.254".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_bandwidth_uses_tc_bit_units() {
+        assert_eq!(parse_bandwidth("10mbit").unwrap(), 1_250_000);
+        assert_eq!(parse_bandwidth("1.5MBit").unwrap(), 187_500);
+        assert_eq!(parse_bandwidth("512kbit").unwrap(), 64_000);
+        assert_eq!(parse_bandwidth("1gbit").unwrap(), 125_000_000);
+        assert_eq!(parse_bandwidth("8000bit").unwrap(), 1000);
+
+        for value in ["10", "10mb", "mbit", "-1mbit", "0kbit", "4kbit", "nanbit"] {
+            assert!(parse_bandwidth(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_env_value_roundtrip() {
+        let policy = policy(&["allow=api.openai.com,2001:db8::/32", "deny=10.0.0.0/8"]);
+
+        let value = policy.to_env_value();
+        assert_eq!(
+            value,
+            r#"{"allow":["api.openai.com","2001:db8::/32"],"deny":["10.0.0.0/8"]}"#
+        );
+        assert_eq!(EgressPolicy::from_env_value(&value).unwrap(), policy);
+        assert!(EgressPolicy::from_env_value(r#"{"allow":["bad_name"]}"#).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+//! Guest entropy seeding.
+//!
+//! A freshly booted guest kernel may not have initialized its CRNG yet, and
+//! until it has, `getrandom()` blocks: TLS handshakes and key generation in
+//! the first seconds of a box stall. libkrun attaches a virtio-rng device fed
+//! by the host RNG, but guest kernels without the `virtio_rng` driver and
+//! confidential builds go without one. The host therefore stages
+//! [`ENTROPY_SEED_LEN`] bytes from its own RNG at
+//! [`RUNTIME_ENTROPY_SEED_PATH`] and points guest init at the file through
+//! [`ENTROPY_SEED_ENV`]. Guest init deletes the file before any workload
+//! starts, mixes the seed into the kernel pool and credits it, then checks
+//! that the CRNG is ready.
+//!
+//! A TEE guest does not trust the host, so confidential boxes get no seed and
+//! rely on the CPU's RNG instead.
+
+/// Fixed in-guest location of the runtime-staged entropy seed.
+pub const RUNTIME_ENTROPY_SEED_PATH: &str = "/.a3s-box-entropy-seed";
+
+/// Guest-init environment variable pointing at the staged seed.
+pub const ENTROPY_SEED_ENV: &str = "BOX_ENTROPY_SEED_FILE";
+
+/// Seed size in bytes; twice the 256 bits the kernel CRNG needs.
+pub const ENTROPY_SEED_LEN: usize = 64;
+
+/// Check a staged seed before guest init credits it to the kernel pool.
+pub fn validate_seed(seed: &[u8]) -> Result<(), String> {
+    if seed.len() != ENTROPY_SEED_LEN {
+        return Err(format!(
+            "entropy seed is {} bytes; expected {ENTROPY_SEED_LEN}",
+            seed.len()
+        ));
+    }
+    // A zeroed seed means the host RNG failed; crediting it would mark the
+    // pool ready without any entropy in it.
+    if seed.iter().all(|&byte| byte == 0) {
+        return Err("entropy seed is all zeros".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_seed() {
+        let mut seed = [0u8; ENTROPY_SEED_LEN];
+        assert!(validate_seed(&seed).unwrap_err().contains("all zeros"));
+        seed[7] = 0x5a;
+        assert!(validate_seed(&seed).is_ok());
+        assert!(validate_seed(&seed[..32])
+            .unwrap_err()
+            .contains("32 bytes; expected 64"));
+    }
+}
@@ -1,5 +1,6 @@
 //! Environment variable parsing and merging helpers.
 
+use std::collections::HashMap;
 use std::path::Path;
 
 /// Parse `KEY=VALUE` strings into ordered pairs.
@@ -34,36 +35,171 @@ pub fn parse_runtime_env_vars(vars: &[String]) -> Vec<(String, String)> {
     vars.iter().map(|var| parse_runtime_env_var(var)).collect()
 }
 
-/// Load environment variables from a Docker-style env file.
+/// Expand the braced `${VAR}`, `${VAR:-default}` and `${VAR:?message}` forms
+/// in `value` from `environment`.
+///
+/// Everything else, a bare `$VAR` included, is kept literally, as
+/// `docker run -e` keeps the whole value, and `$${` writes a literal `${`.
+/// An unset `${VAR}` without a default is an error rather than an empty
+/// string, so a typo does not silently blank a value.
+pub fn expand_braced_vars(
+    value: &str,
+    environment: &HashMap<String, String>,
+) -> Result<String, String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        if rest[..start].ends_with('$') {
+            // `$${`: the first `$` is already out; keep the brace literal.
+            expanded.push('{');
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let reference = &rest[start + 2..];
+        let end = reference
+            .find('}')
+            .ok_or_else(|| format!("unterminated ${{ in {value:?}"))?;
+        expanded.push_str(&expand_reference(&reference[..end], environment)?);
+        rest = &reference[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Resolve the body of one `${...}` reference.
+fn expand_reference(body: &str, environment: &HashMap<String, String>) -> Result<String, String> {
+    let split = body
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(body.len());
+    let (name, modifier) = body.split_at(split);
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!("invalid variable reference ${{{body}}}"));
+    }
+    let non_empty = environment.get(name).filter(|value| !value.is_empty());
+    if modifier.is_empty() {
+        environment.get(name).cloned().ok_or_else(|| {
+            format!("${{{name}}} is not set; use ${{{name}:-default}} for a fallback")
+        })
+    } else if let Some(default) = modifier.strip_prefix(":-") {
+        Ok(non_empty.cloned().unwrap_or_else(|| default.to_string()))
+    } else if let Some(message) = modifier.strip_prefix(":?") {
+        non_empty.cloned().ok_or_else(|| match message {
+            "" => format!("{name} is required"),
+            message => format!("{name}: {message}"),
+        })
+    } else {
+        Err(format!("unsupported variable reference ${{{body}}}"))
+    }
+}
+
+/// The host environment, for [`expand_braced_vars`].
+pub fn host_environment() -> HashMap<String, String> {
+    std::env::vars().collect()
+}
+
+/// Load environment variables from an env file (see [`parse_env_file_content`]).
 pub fn parse_env_file(path: impl AsRef<Path>) -> Result<Vec<(String, String)>, String> {
     let path = path.as_ref();
     let content = std::fs::read_to_string(path)
         .map_err(|e| format!("Failed to read env file '{}': {}", path.display(), e))?;
-    Ok(parse_env_file_content(&content))
+    parse_env_file_content(&content)
+        .map_err(|e| format!("Invalid env file '{}': {e}", path.display()))
 }
 
-/// Parse Docker-style env file content.
+/// Parse shell-style env file content.
 ///
-/// Empty lines and `#` comments are skipped. A key without `=` gets an empty
-/// value. The value after the first `=` is kept VERBATIM (leading/trailing
-/// whitespace preserved), matching Docker — only the key is trimmed, and a
+/// Lines are `KEY=VALUE`, optionally prefixed with `export `. Empty lines and
+/// `#` comments are skipped, a key without `=` gets an empty value, and a
 /// trailing CR from Windows line endings is stripped.
-pub fn parse_env_file_content(content: &str) -> Vec<(String, String)> {
-    content
-        .lines()
-        .filter_map(|line| {
-            // Decide blank/comment on a leading-trimmed view without mutating
-            // the value's whitespace.
-            if line.trim_start().is_empty() || line.trim_start().starts_with('#') {
-                return None;
-            }
-            let line = line.strip_suffix('\r').unwrap_or(line);
-            match line.split_once('=') {
-                Some((key, value)) => Some((key.trim().to_string(), value.to_string())),
-                None => Some((line.trim().to_string(), String::new())),
-            }
-        })
-        .collect()
+///
+/// - An unquoted value is kept verbatim, leading/trailing whitespace and `$`
+///   included, as Docker does.
+/// - A `'single-quoted'` value is literal.
+/// - A `"double-quoted"` value may span lines and understands `\"`, `\\`,
+///   `\n` and `\t`.
+/// - After a closing quote only whitespace and a `# comment` may follow.
+///
+/// Values are never interpolated, so a `$` in a password or regex survives.
+pub fn parse_env_file_content(content: &str) -> Result<Vec<(String, String)>, String> {
+    let mut entries = Vec::new();
+    let mut lines = content.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        // Decide blank/comment on a leading-trimmed view without mutating
+        // the value's whitespace.
+        if line.trim_start().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line_no = index + 1;
+        let (key, raw) = line.split_once('=').unwrap_or((line, ""));
+        let key = key.trim();
+        let key = key.strip_prefix("export ").map(str::trim).unwrap_or(key);
+        if key.is_empty() {
+            return Err(format!("line {line_no}: missing variable name"));
+        }
+
+        let value = if let Some(quoted) = raw.strip_prefix('\'') {
+            let (value, rest) = quoted
+                .split_once('\'')
+                .ok_or_else(|| format!("line {line_no}: unterminated single quote"))?;
+            check_after_quote(rest, line_no)?;
+            value.to_string()
+        } else if let Some(quoted) = raw.strip_prefix('"') {
+            let mut text = quoted.to_string();
+            let (value, rest) = loop {
+                if let Some(split) = split_double_quoted(&text) {
+                    break split;
+                }
+                let (_, next) = lines
+                    .next()
+                    .ok_or_else(|| format!("line {line_no}: unterminated double quote"))?;
+                text.push('\n');
+                text.push_str(next.strip_suffix('\r').unwrap_or(next));
+            };
+            check_after_quote(&rest, line_no)?;
+            value
+        } else {
+            raw.to_string()
+        };
+        entries.push((key.to_string(), value));
+    }
+    Ok(entries)
+}
+
+/// Split the body of a double-quoted value at its closing quote, resolving
+/// backslash escapes.
+fn split_double_quoted(text: &str) -> Option<(String, String)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, text[index + 1..].to_string())),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some(c @ ('"' | '\\')) => value.push(c),
+                Some(c) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => value.push('\\'),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+fn check_after_quote(rest: &str, line_no: usize) -> Result<(), String> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!(
+            "line {line_no}: unexpected text after closing quote"
+        ))
+    }
 }
 
 /// Merge environment overrides into a base vector.
@@ -132,7 +268,7 @@ mod tests {
     #[test]
     fn test_parse_env_file_preserves_value_whitespace() {
         // Docker keeps the value verbatim after the first '='; only the key is trimmed.
-        let parsed = parse_env_file_content("PADDED=  spaced value  \nKEY=v\n");
+        let parsed = parse_env_file_content("PADDED=  spaced value  \nKEY=v\n").unwrap();
         assert_eq!(
             parsed[0],
             ("PADDED".to_string(), "  spaced value  ".to_string())
@@ -149,7 +285,8 @@ FOO=bar
 EMPTY
 WITH_EQUALS=a=b
 "#,
-        );
+        )
+        .unwrap();
 
         assert_eq!(
             parsed,
@@ -161,6 +298,70 @@ WITH_EQUALS=a=b
         );
     }
 
+    #[test]
+    fn test_parse_env_file_quoting_keeps_dollars() {
+        let parsed = parse_env_file_content(
+            r#"export API_BASE="https://api.example.com" # inline comment
+SINGLE='${HOME} stays literal'
+DOUBLE="line one\nsaid \"hi\" at $5"
+PASSWORD=pa$$word$HOME
+QUOTED="${API_BASE}/v1"
+MULTI="first
+second"
+"#,
+        )
+        .unwrap();
+        let get = |key: &str| {
+            parsed
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .unwrap()
+        };
+
+        assert_eq!(get("API_BASE"), "https://api.example.com");
+        assert_eq!(get("SINGLE"), "${HOME} stays literal");
+        assert_eq!(get("DOUBLE"), "line one\nsaid \"hi\" at $5");
+        assert_eq!(get("PASSWORD"), "pa$$word$HOME");
+        assert_eq!(get("QUOTED"), "${API_BASE}/v1");
+        assert_eq!(get("MULTI"), "first\nsecond");
+    }
+
+    #[test]
+    fn test_parse_env_file_content_errors_name_the_line() {
+        let err = parse_env_file_content("A=1\nB='open\n").unwrap_err();
+        assert_eq!(err, "line 2: unterminated single quote");
+        let err = parse_env_file_content("A=\"open\nstill open\n").unwrap_err();
+        assert_eq!(err, "line 1: unterminated double quote");
+        let err = parse_env_file_content("A=\"x\" trailing\n").unwrap_err();
+        assert!(err.contains("after closing quote"));
+        assert!(parse_env_file_content("=value\n").is_err());
+    }
+
+    #[test]
+    fn test_expand_braced_vars() {
+        let env = HashMap::from([
+            ("MODEL".to_string(), "small".to_string()),
+            ("EMPTY".to_string(), String::new()),
+        ]);
+        let expand = |value| expand_braced_vars(value, &env);
+        assert_eq!(expand("m-${MODEL}").unwrap(), "m-small");
+        assert_eq!(expand("${EMPTY}").unwrap(), "");
+        assert_eq!(expand("${MISSING:-dflt}/${EMPTY:-e}").unwrap(), "dflt/e");
+        // Only braced references expand; a bare `$` is literal.
+        assert_eq!(expand("ab$cd $MODEL $$5 $").unwrap(), "ab$cd $MODEL $$5 $");
+        assert_eq!(expand("$${MODEL}").unwrap(), "${MODEL}");
+
+        assert!(expand("${MISSING}").unwrap_err().contains("not set"));
+        assert_eq!(
+            expand("${MISSING:?set a model}").unwrap_err(),
+            "MISSING: set a model"
+        );
+        assert!(expand("${MODEL").unwrap_err().contains("unterminated"));
+        assert!(expand("${MODEL/x/y}").unwrap_err().contains("unsupported"));
+        assert!(expand("${}").unwrap_err().contains("invalid"));
+    }
+
     #[test]
     fn test_parse_env_file() {
         let dir = TempDir::new().unwrap();
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::error_catalog::{CatalogEntry, ErrorCode};
+
 /// A3S Box error types
 #[derive(Error, Debug)]
 pub enum BoxError {
@@ -78,6 +80,10 @@ pub enum BoxError {
     #[error("Resize error: {0}")]
     ResizeError(String),
 
+    /// Persisted store document is corrupt and no good version was recoverable
+    #[error("Store corrupted: {path}: {message}")]
+    StoreCorrupted { path: String, message: String },
+
     /// Generic error
     #[error("{0}")]
     Other(String),
@@ -96,6 +102,41 @@ impl From<serde_yaml::Error> for BoxError {
 }
 
 /// Result type alias for A3S Box operations
+impl BoxError {
+    /// The stable catalog code for this error's class.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::BoxBootError { .. } => ErrorCode::BOX_BOOT,
+            Self::TimeoutError(_) => ErrorCode::TIMEOUT,
+            Self::IoError(_) => ErrorCode::IO,
+            Self::SerializationError(_) => ErrorCode::SERIALIZATION,
+            Self::ConfigError(_) => ErrorCode::CONFIG,
+            Self::TeeConfig(_) => ErrorCode::TEE_CONFIG,
+            Self::TeeNotSupported(_) => ErrorCode::TEE_NOT_SUPPORTED,
+            Self::AttestationError(_) => ErrorCode::ATTESTATION,
+            Self::OciImageError(_) => ErrorCode::OCI_IMAGE,
+            Self::RegistryError { .. } => ErrorCode::REGISTRY,
+            Self::CacheError(_) => ErrorCode::CACHE,
+            Self::PoolError(_) => ErrorCode::POOL,
+            Self::ExecError(_) => ErrorCode::EXEC,
+            Self::BuildError(_) => ErrorCode::BUILD,
+            Self::NetworkError(_) => ErrorCode::NETWORK,
+            Self::StateError(_) => ErrorCode::STATE,
+            Self::AuditError(_) => ErrorCode::AUDIT,
+            Self::ResizeError(_) => ErrorCode::RESIZE,
+            Self::StoreCorrupted { .. } => ErrorCode::STORE_CORRUPTED,
+            Self::Other(_) => ErrorCode::OTHER,
+        }
+    }
+
+    /// The catalog entry for [`BoxError::code`].
+    pub fn catalog_entry(&self) -> &'static CatalogEntry {
+        self.code()
+            .entry()
+            .expect("every BoxError code has a catalog entry")
+    }
+}
+
 pub type Result<T> = std::result::Result<T, BoxError>;
 
 #[cfg(test)]
@@ -274,4 +315,62 @@ mod tests {
         let error = BoxError::BuildError("Dockerfile parse failed".to_string());
         assert_eq!(error.to_string(), "Build error: Dockerfile parse failed");
     }
+
+    #[test]
+    fn test_store_corrupted_display() {
+        let error = BoxError::StoreCorrupted {
+            path: "/home/u/.a3s/volumes.json".to_string(),
+            message: "expected value at line 1 column 1".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Store corrupted: /home/u/.a3s/volumes.json: expected value at line 1 column 1"
+        );
+    }
+
+    #[test]
+    fn test_error_codes_match_message_prefixes() {
+        let errors = [
+            BoxError::BoxBootError {
+                message: "no kernel".to_string(),
+                hint: None,
+            },
+            BoxError::TimeoutError("t".to_string()),
+            BoxError::IoError(std::io::Error::other("io")),
+            BoxError::SerializationError("s".to_string()),
+            BoxError::ConfigError("c".to_string()),
+            BoxError::TeeConfig("t".to_string()),
+            BoxError::TeeNotSupported("t".to_string()),
+            BoxError::AttestationError("a".to_string()),
+            BoxError::OciImageError("o".to_string()),
+            BoxError::RegistryError {
+                registry: "r".to_string(),
+                message: "m".to_string(),
+            },
+            BoxError::CacheError("c".to_string()),
+            BoxError::PoolError("p".to_string()),
+            BoxError::ExecError("e".to_string()),
+            BoxError::BuildError("b".to_string()),
+            BoxError::NetworkError("n".to_string()),
+            BoxError::StateError("s".to_string()),
+            BoxError::AuditError("a".to_string()),
+            BoxError::ResizeError("r".to_string()),
+            BoxError::StoreCorrupted {
+                path: "p".to_string(),
+                message: "m".to_string(),
+            },
+        ];
+        for error in &errors {
+            let entry = error.catalog_entry();
+            assert!(
+                error
+                    .to_string()
+                    .starts_with(&format!("{}: ", entry.summary)),
+                "{} summary does not prefix '{error}'",
+                entry.code
+            );
+        }
+        assert_eq!(BoxError::Other("x".to_string()).code(), ErrorCode::OTHER);
+        assert_eq!(errors.len() + 1, crate::error_catalog::ERROR_CATALOG.len());
+    }
 }
@@ -0,0 +1,315 @@
+//! Numbered error catalog (`A3S-xxxx`).
+//!
+//! Every [`BoxError`](crate::error::BoxError) variant maps to one catalog
+//! entry through [`BoxError::code`](crate::error::BoxError::code). Codes and
+//! summaries are stable: runbooks, SDK error mapping and translations key off
+//! them instead of the free-form detail in the error message. A code is never
+//! reused once published; retired variants keep their entry.
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A catalog code such as `A3S-0001`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ErrorCode(u16);
+
+impl ErrorCode {
+    pub const BOX_BOOT: Self = Self(1);
+    pub const TIMEOUT: Self = Self(2);
+    pub const IO: Self = Self(3);
+    pub const SERIALIZATION: Self = Self(4);
+    pub const CONFIG: Self = Self(5);
+    pub const TEE_CONFIG: Self = Self(6);
+    pub const TEE_NOT_SUPPORTED: Self = Self(7);
+    pub const ATTESTATION: Self = Self(8);
+    pub const OCI_IMAGE: Self = Self(9);
+    pub const REGISTRY: Self = Self(10);
+    pub const CACHE: Self = Self(11);
+    pub const POOL: Self = Self(12);
+    pub const EXEC: Self = Self(13);
+    pub const BUILD: Self = Self(14);
+    pub const NETWORK: Self = Self(15);
+    pub const STATE: Self = Self(16);
+    pub const AUDIT: Self = Self(17);
+    pub const RESIZE: Self = Self(18);
+    pub const STORE_CORRUPTED: Self = Self(19);
+    pub const OTHER: Self = Self(20);
+
+    /// The numeric part of the code.
+    pub const fn number(self) -> u16 {
+        self.0
+    }
+
+    /// Parse `A3S-0042`, `a3s-42` or `42`. Unknown codes parse; use
+    /// [`ErrorCode::entry`] to check whether the catalog has them.
+    pub fn parse(code: &str) -> Option<Self> {
+        let code = code.trim();
+        let number = match code.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("A3S-") => &code[4..],
+            _ => code,
+        };
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        number.parse().ok().map(Self)
+    }
+
+    /// The catalog entry for this code, if it is a known code.
+    pub fn entry(self) -> Option<&'static CatalogEntry> {
+        ERROR_CATALOG.iter().find(|entry| entry.code == self)
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "A3S-{:04}", self.0)
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Self::parse(&code)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid error code '{code}'")))
+    }
+}
+
+/// One documented error class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatalogEntry {
+    pub code: ErrorCode,
+    /// The `BoxError` variant the code stands for.
+    pub name: &'static str,
+    /// Stable one-line summary; the prefix of the error message.
+    pub summary: &'static str,
+    /// What the error means and common causes.
+    pub explanation: &'static str,
+    /// What to try first.
+    pub hint: &'static str,
+}
+
+/// Every known code, in numeric order.
+pub const ERROR_CATALOG: &[CatalogEntry] = &[
+    CatalogEntry {
+        code: ErrorCode::BOX_BOOT,
+        name: "BoxBootError",
+        summary: "VM boot failed",
+        explanation: "The MicroVM could not be started: the VMM failed to \
+            launch, the guest kernel or init did not come up, or guest init \
+            did not report readiness before the boot deadline.",
+        hint: "Check that virtualization is available (KVM on Linux, HVF on \
+            macOS, WHPX on Windows), then read the box's console log with \
+            `a3s-box logs` for the guest-side failure.",
+    },
+    CatalogEntry {
+        code: ErrorCode::TIMEOUT,
+        name: "TimeoutError",
+        summary: "Timeout",
+        explanation: "An operation did not finish within its deadline, such \
+            as waiting for a guest service, a health check or a registry.",
+        hint: "Retry with a longer timeout where the command offers one, and \
+            check whether the box or remote service is overloaded.",
+    },
+    CatalogEntry {
+        code: ErrorCode::IO,
+        name: "IoError",
+        summary: "I/O error",
+        explanation: "A host filesystem or socket operation failed, for \
+            example a missing file, a permission problem or a full disk.",
+        hint: "Check the path in the message, its permissions and the free \
+            space under the a3s-box home directory (`A3S_HOME`, default \
+            `~/.a3s`).",
+    },
+    CatalogEntry {
+        code: ErrorCode::SERIALIZATION,
+        name: "SerializationError",
+        summary: "Serialization error",
+        explanation: "JSON or YAML could not be read or written: a config \
+            file, image manifest or protocol message was malformed.",
+        hint: "Validate the file named in the message; for compose files, \
+            `a3s-box compose config` shows how it was parsed.",
+    },
+    CatalogEntry {
+        code: ErrorCode::CONFIG,
+        name: "ConfigError",
+        summary: "Configuration error",
+        explanation: "A box, compose or runtime option is invalid or \
+            contradicts another option.",
+        hint: "Fix the option named in the message; `a3s-box <command> \
+            --help` lists accepted values.",
+    },
+    CatalogEntry {
+        code: ErrorCode::TEE_CONFIG,
+        name: "TeeConfig",
+        summary: "TEE configuration error",
+        explanation: "The confidential-computing configuration for a box \
+            could not be applied by the VMM.",
+        hint: "Check the `--tee` options and that the runtime was built with \
+            TEE support.",
+    },
+    CatalogEntry {
+        code: ErrorCode::TEE_NOT_SUPPORTED,
+        name: "TeeNotSupported",
+        summary: "TEE hardware not available",
+        explanation: "A TEE box was requested on a host without the required \
+            hardware or firmware support (for example AMD SEV-SNP).",
+        hint: "Run `a3s-box info` to see detected TEE capabilities, or use \
+            simulation mode for development.",
+    },
+    CatalogEntry {
+        code: ErrorCode::ATTESTATION,
+        name: "AttestationError",
+        summary: "Attestation error",
+        explanation: "A TEE attestation report could not be produced or did \
+            not verify against the requested policy.",
+        hint: "Compare the report with the policy using `a3s-box attest`, and \
+            check the certificate chain and measurements.",
+    },
+    CatalogEntry {
+        code: ErrorCode::OCI_IMAGE,
+        name: "OciImageError",
+        summary: "OCI image error",
+        explanation: "An image reference, manifest, config or layer is \
+            invalid, missing from the local store or unsupported.",
+        hint: "Check the image reference and platform, then pull it again \
+            with `a3s-box pull`.",
+    },
+    CatalogEntry {
+        code: ErrorCode::REGISTRY,
+        name: "RegistryError",
+        summary: "Registry error",
+        explanation: "A container registry rejected a request or could not \
+            be reached.",
+        hint: "Check network access to the registry and log in with \
+            `a3s-box login <registry>` for private images.",
+    },
+    CatalogEntry {
+        code: ErrorCode::CACHE,
+        name: "CacheError",
+        summary: "Cache error",
+        explanation: "The image, layer or rootfs cache could not be read or \
+            updated.",
+        hint: "Run `a3s-box system-prune` to drop cached data; it is rebuilt \
+            on demand.",
+    },
+    CatalogEntry {
+        code: ErrorCode::POOL,
+        name: "PoolError",
+        summary: "Pool error",
+        explanation: "The warm VM pool could not provide or replenish a VM.",
+        hint: "Check `a3s-box pool status` and the pool's min-idle and \
+            max-size settings.",
+    },
+    CatalogEntry {
+        code: ErrorCode::EXEC,
+        name: "ExecError",
+        summary: "Exec error",
+        explanation: "A command or file operation inside the box failed to \
+            run, or the guest exec server could not be reached.",
+        hint: "Make sure the box is running (`a3s-box ps`) and that the \
+            command exists in the image.",
+    },
+    CatalogEntry {
+        code: ErrorCode::BUILD,
+        name: "BuildError",
+        summary: "Build error",
+        explanation: "An image build failed while parsing the Dockerfile or \
+            running one of its steps.",
+        hint: "Read the failing step in the build output and reproduce it \
+            with `a3s-box run` on the previous stage's image.",
+    },
+    CatalogEntry {
+        code: ErrorCode::NETWORK,
+        name: "NetworkError",
+        summary: "Network error",
+        explanation: "A box network, port mapping or network relay could not \
+            be set up or used.",
+        hint: "Check `a3s-box network ls` and that the published host ports \
+            are free.",
+    },
+    CatalogEntry {
+        code: ErrorCode::STATE,
+        name: "StateError",
+        summary: "VM state error",
+        explanation: "The operation is not valid in the box's current state, \
+            for example starting a running box or a state transition that \
+            lost a race with another command.",
+        hint: "Check the box status with `a3s-box ps -a` and retry once it \
+            has settled.",
+    },
+    CatalogEntry {
+        code: ErrorCode::AUDIT,
+        name: "AuditError",
+        summary: "Audit error",
+        explanation: "The audit log could not be written or read.",
+        hint: "Check permissions and free space for the audit log under the \
+            a3s-box home directory.",
+    },
+    CatalogEntry {
+        code: ErrorCode::RESIZE,
+        name: "ResizeError",
+        summary: "Resize error",
+        explanation: "A running box's resources could not be changed; not \
+            every resource can be hot-resized.",
+        hint: "Stop the box and apply the change with `a3s-box \
+            container-update` before starting it again.",
+    },
+    CatalogEntry {
+        code: ErrorCode::STORE_CORRUPTED,
+        name: "StoreCorrupted",
+        summary: "Store corrupted",
+        explanation: "A persisted state document (boxes, volumes, networks) \
+            is unreadable and no intact backup generation was found.",
+        hint: "Move the named file aside and recreate the affected objects; \
+            back it up first if you need to recover entries by hand.",
+    },
+    CatalogEntry {
+        code: ErrorCode::OTHER,
+        name: "Other",
+        summary: "Error",
+        explanation: "An error without a more specific class.",
+        hint: "Read the message for details; run with `RUST_LOG=debug` for \
+            more context.",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_codes_are_unique_and_ordered() {
+        for pair in ERROR_CATALOG.windows(2) {
+            assert!(pair[0].code < pair[1].code, "{} out of order", pair[1].code);
+        }
+        for entry in ERROR_CATALOG {
+            assert_eq!(entry.code.entry(), Some(entry));
+            assert!(!entry.hint.contains("  "), "{} hint spacing", entry.code);
+        }
+    }
+
+    #[test]
+    fn test_code_format_and_parse() {
+        assert_eq!(ErrorCode::BOX_BOOT.to_string(), "A3S-0001");
+        assert_eq!(
+            ErrorCode::parse("A3S-0019"),
+            Some(ErrorCode::STORE_CORRUPTED)
+        );
+        assert_eq!(ErrorCode::parse("a3s-5"), Some(ErrorCode::CONFIG));
+        assert_eq!(ErrorCode::parse("13"), Some(ErrorCode::EXEC));
+        assert_eq!(ErrorCode::parse("A3S-"), None);
+        assert_eq!(ErrorCode::parse("E0042"), None);
+        assert!(ErrorCode::parse("A3S-9999").unwrap().entry().is_none());
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::REGISTRY).unwrap(),
+            "\"A3S-0010\""
+        );
+    }
+}
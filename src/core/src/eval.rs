@@ -0,0 +1,403 @@
+//! Agent evaluation tasks and scored reports.
+//!
+//! An eval suite is a directory with one subdirectory per task. Each task has
+//! a `task.yaml`:
+//!
+//! ```yaml
+//! prompt: "Fix the failing test in /workspace"
+//! image: ghcr.io/example/agent:latest   # optional, defaults to --image
+//! command: ["agent", "run"]             # receives the prompt on stdin
+//! fixtures:
+//!   - repo:/workspace                   # host path relative to the task dir
+//! assert: check.sh                      # script run in the box afterwards
+//! timeout: 300                          # seconds for the agent command (max 86400)
+//! weight: 2
+//! env:
+//!   LOG_LEVEL: debug
+//! ```
+//!
+//! The assertion script exits 0 to pass. It may print a final `score: <0..1>`
+//! line to award partial credit. [`EvalReport`] aggregates task outcomes into a
+//! weighted score.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// File name of a task definition inside its directory.
+pub const EVAL_TASK_FILE: &str = "task.yaml";
+
+/// Environment variable carrying the task prompt to the agent and assertion.
+pub const EVAL_PROMPT_ENV: &str = "A3S_EVAL_PROMPT";
+
+/// Environment variable carrying the agent's exit code to the assertion.
+pub const EVAL_AGENT_EXIT_ENV: &str = "A3S_EVAL_AGENT_EXIT_CODE";
+
+/// Environment variable naming the guest file holding the agent's stdout.
+pub const EVAL_AGENT_OUTPUT_ENV: &str = "A3S_EVAL_AGENT_OUTPUT";
+
+/// Guest path the agent's stdout is written to before the assertion runs.
+pub const EVAL_AGENT_OUTPUT_PATH: &str = "/tmp/a3s-eval-agent.out";
+
+/// Default agent timeout in seconds.
+pub const DEFAULT_EVAL_TIMEOUT_SECS: u64 = 300;
+
+/// Longest agent timeout a task may ask for, in seconds (one day).
+pub const MAX_EVAL_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+
+/// A task definition as written in `task.yaml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalTaskSpec {
+    /// Task name; defaults to the directory name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Image to boot; defaults to the suite-wide image.
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Prompt handed to the agent.
+    pub prompt: String,
+    /// Agent command. Empty means only the assertion runs.
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// Host paths to mount, as `src:/guest[:ro]` with `src` relative to the
+    /// task directory.
+    #[serde(default)]
+    pub fixtures: Vec<String>,
+    /// Assertion script, relative to the task directory.
+    pub assert: String,
+    /// Agent timeout in seconds.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// Relative weight of this task in the suite score.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// Extra environment for the agent and assertion.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// A loaded task with paths resolved against its directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalTask {
+    pub name: String,
+    pub dir: PathBuf,
+    pub image: Option<String>,
+    pub prompt: String,
+    pub command: Vec<String>,
+    /// Volume specs with absolute host paths.
+    pub fixtures: Vec<String>,
+    /// Contents of the assertion script.
+    pub assert_script: String,
+    pub timeout_secs: u64,
+    pub weight: f64,
+    pub env: BTreeMap<String, String>,
+}
+
+/// Load the task in `dir`.
+pub fn load_eval_task(dir: &Path) -> Result<EvalTask, String> {
+    let path = dir.join(EVAL_TASK_FILE);
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let spec: EvalTaskSpec =
+        serde_yaml::from_str(&content).map_err(|e| format!("invalid {}: {e}", path.display()))?;
+    resolve_eval_task(dir, spec)
+}
+
+/// Validate `spec` and resolve its fixture and assertion paths against `dir`.
+pub fn resolve_eval_task(dir: &Path, spec: EvalTaskSpec) -> Result<EvalTask, String> {
+    let name = spec
+        .name
+        .clone()
+        .or_else(|| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .ok_or_else(|| format!("task in {} has no name", dir.display()))?;
+    if !(spec.weight.is_finite() && spec.weight > 0.0) {
+        return Err(format!("task '{name}': weight must be positive"));
+    }
+    if spec.timeout == Some(0) {
+        return Err(format!("task '{name}': timeout must be at least 1 second"));
+    }
+    if spec.timeout > Some(MAX_EVAL_TIMEOUT_SECS) {
+        return Err(format!(
+            "task '{name}': timeout must be at most {MAX_EVAL_TIMEOUT_SECS} seconds"
+        ));
+    }
+
+    let mut fixtures = Vec::with_capacity(spec.fixtures.len());
+    for fixture in &spec.fixtures {
+        let (source, rest) = fixture
+            .split_once(':')
+            .ok_or_else(|| format!("task '{name}': fixture '{fixture}' must be SRC:/GUEST"))?;
+        if !rest.starts_with('/') {
+            return Err(format!(
+                "task '{name}': fixture '{fixture}' needs an absolute guest path"
+            ));
+        }
+        let source = resolve_inside(dir, source)
+            .map_err(|e| format!("task '{name}': fixture '{fixture}': {e}"))?;
+        fixtures.push(format!("{}:{rest}", source.display()));
+    }
+
+    let assert_path = resolve_inside(dir, &spec.assert)
+        .map_err(|e| format!("task '{name}': assert '{}': {e}", spec.assert))?;
+    let assert_script = std::fs::read_to_string(&assert_path)
+        .map_err(|e| format!("task '{name}': failed to read assert script: {e}"))?;
+
+    Ok(EvalTask {
+        name,
+        dir: dir.to_path_buf(),
+        image: spec.image,
+        prompt: spec.prompt,
+        command: spec.command,
+        fixtures,
+        assert_script,
+        timeout_secs: spec.timeout.unwrap_or(DEFAULT_EVAL_TIMEOUT_SECS),
+        weight: spec.weight,
+        env: spec.env,
+    })
+}
+
+/// Resolve a relative path against `dir`, refusing paths that escape it.
+fn resolve_inside(dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    if Path::new(relative).is_absolute() {
+        return Err("path must be relative to the task directory".to_string());
+    }
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| format!("{}: {e}", dir.display()))?;
+    let path = dir
+        .join(relative)
+        .canonicalize()
+        .map_err(|e| format!("{relative}: {e}"))?;
+    if !path.starts_with(&dir) {
+        return Err("path escapes the task directory".to_string());
+    }
+    Ok(path)
+}
+
+/// Load every task under `suite_dir`, sorted by name.
+///
+/// A `suite_dir` that itself contains `task.yaml` is a one-task suite.
+pub fn discover_eval_tasks(suite_dir: &Path) -> Result<Vec<EvalTask>, String> {
+    if suite_dir.join(EVAL_TASK_FILE).is_file() {
+        return Ok(vec![load_eval_task(suite_dir)?]);
+    }
+    let entries = std::fs::read_dir(suite_dir)
+        .map_err(|e| format!("failed to read {}: {e}", suite_dir.display()))?;
+    let mut tasks = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.join(EVAL_TASK_FILE).is_file() {
+            tasks.push(load_eval_task(&path)?);
+        }
+    }
+    tasks.sort_by(|a, b| a.name.cmp(&b.name));
+    if let Some(pair) = tasks.windows(2).find(|pair| pair[0].name == pair[1].name) {
+        return Err(format!("duplicate task name '{}'", pair[0].name));
+    }
+    Ok(tasks)
+}
+
+/// Score of an assertion run: the last `score: X` line when present (clamped
+/// to 0..=1), otherwise 1 for exit code 0 and 0 for anything else.
+///
+/// A failing exit code always scores 0, so a script cannot pass by printing a
+/// score and then crashing.
+pub fn assertion_score(stdout: &str, exit_code: i32) -> f64 {
+    if exit_code != 0 {
+        return 0.0;
+    }
+    stdout
+        .lines()
+        .rev()
+        .find_map(|line| {
+            line.trim()
+                .strip_prefix("score:")
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| value.is_finite())
+        })
+        .map(|value| value.clamp(0.0, 1.0))
+        .unwrap_or(1.0)
+}
+
+/// Result of running one task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalTaskResult {
+    pub name: String,
+    pub image: String,
+    pub weight: f64,
+    /// 0..=1 score from the assertion.
+    pub score: f64,
+    /// Whether the assertion exited 0 with a full score.
+    pub passed: bool,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assert_exit_code: Option<i32>,
+    /// Tail of the assertion output, for failure triage.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub output: String,
+    /// Infrastructure error (boot, exec) that prevented scoring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Scored report for a suite run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub suite: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub duration_ms: u64,
+    /// Weighted mean of task scores, 0..=1.
+    pub score: f64,
+    pub passed: usize,
+    pub failed: usize,
+    pub tasks: Vec<EvalTaskResult>,
+}
+
+impl EvalReport {
+    /// Build a report from task results, sorted by task name.
+    pub fn new(
+        suite: String,
+        started_at: chrono::DateTime<chrono::Utc>,
+        duration_ms: u64,
+        mut tasks: Vec<EvalTaskResult>,
+    ) -> Self {
+        tasks.sort_by(|a, b| a.name.cmp(&b.name));
+        let total_weight: f64 = tasks.iter().map(|t| t.weight).sum();
+        let score = if total_weight > 0.0 {
+            tasks.iter().map(|t| t.score * t.weight).sum::<f64>() / total_weight
+        } else {
+            0.0
+        };
+        let passed = tasks.iter().filter(|t| t.passed).count();
+        Self {
+            suite,
+            started_at,
+            duration_ms,
+            score,
+            passed,
+            failed: tasks.len() - passed,
+            tasks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_task(dir: &Path, yaml: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(EVAL_TASK_FILE), yaml).unwrap();
+        std::fs::write(dir.join("check.sh"), "test -f /workspace/done\n").unwrap();
+    }
+
+    fn result(name: &str, weight: f64, score: f64) -> EvalTaskResult {
+        EvalTaskResult {
+            name: name.to_string(),
+            image: "alpine".to_string(),
+            weight,
+            score,
+            passed: score >= 1.0,
+            duration_ms: 10,
+            agent_exit_code: Some(0),
+            assert_exit_code: Some(0),
+            output: String::new(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_discover_eval_tasks_resolves_fixtures_and_assert() {
+        let suite = tempfile::tempdir().unwrap();
+        let task_dir = suite.path().join("fix-test");
+        write_task(
+            &task_dir,
+            "prompt: fix it\ncommand: [agent]\nfixtures: ['repo:/workspace:ro']\nassert: check.sh\n",
+        );
+        std::fs::create_dir(task_dir.join("repo")).unwrap();
+        std::fs::create_dir(suite.path().join("not-a-task")).unwrap();
+
+        let tasks = discover_eval_tasks(suite.path()).unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        let task = &tasks[0];
+        assert_eq!(task.name, "fix-test");
+        assert_eq!(task.timeout_secs, DEFAULT_EVAL_TIMEOUT_SECS);
+        assert_eq!(task.assert_script, "test -f /workspace/done\n");
+        let repo = task_dir.join("repo").canonicalize().unwrap();
+        assert_eq!(
+            task.fixtures,
+            vec![format!("{}:/workspace:ro", repo.display())]
+        );
+    }
+
+    #[test]
+    fn test_eval_task_rejects_paths_outside_task_dir() {
+        let suite = tempfile::tempdir().unwrap();
+        let task_dir = suite.path().join("escape");
+        write_task(
+            &task_dir,
+            "prompt: x\nfixtures: ['..:/host']\nassert: check.sh\n",
+        );
+
+        let error = load_eval_task(&task_dir).unwrap_err();
+
+        assert!(error.contains("escapes"), "got: {error}");
+    }
+
+    #[test]
+    fn test_eval_task_rejects_out_of_range_timeout() {
+        let suite = tempfile::tempdir().unwrap();
+        let task_dir = suite.path().join("slow");
+        for (timeout, expected) in [
+            ("0", "at least 1 second"),
+            ("86401", "at most 86400 seconds"),
+            ("20000000000", "at most 86400 seconds"),
+        ] {
+            write_task(
+                &task_dir,
+                &format!("prompt: x\ntimeout: {timeout}\nassert: check.sh\n"),
+            );
+            let error = load_eval_task(&task_dir).unwrap_err();
+            assert!(error.contains(expected), "{timeout}: {error}");
+        }
+
+        write_task(&task_dir, "prompt: x\ntimeout: 86400\nassert: check.sh\n");
+        assert_eq!(
+            load_eval_task(&task_dir).unwrap().timeout_secs,
+            MAX_EVAL_TIMEOUT_SECS
+        );
+    }
+
+    #[test]
+    fn test_assertion_score() {
+        assert_eq!(assertion_score("ok\n", 0), 1.0);
+        assert_eq!(assertion_score("score: 0.5\n", 0), 0.5);
+        assert_eq!(assertion_score("score: 0.2\nscore: 3\n", 0), 1.0);
+        assert_eq!(assertion_score("score: 1\n", 1), 0.0);
+        assert_eq!(assertion_score("score: nope\n", 0), 1.0);
+    }
+
+    #[test]
+    fn test_eval_report_weights_scores() {
+        let report = EvalReport::new(
+            "suite".to_string(),
+            chrono::Utc::now(),
+            100,
+            vec![result("b", 3.0, 0.0), result("a", 1.0, 1.0)],
+        );
+
+        assert_eq!(report.score, 0.25);
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.tasks[0].name, "a");
+    }
+}
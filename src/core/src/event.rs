@@ -147,6 +147,13 @@ pub mod events {
     pub const BOX_RESTARTED: &str = "box.restarted";
     pub const BOX_RESTART_FAILED: &str = "box.restart.failed";
     pub const BOX_RESTART_BACKOFF: &str = "box.restart.backoff";
+
+    // TEE re-attestation events
+    pub const BOX_ATTESTATION_OK: &str = "box.attestation.ok";
+    pub const BOX_ATTESTATION_FAILED: &str = "box.attestation.failed";
+
+    // Guest service supervision events
+    pub const BOX_SERVICE_STATE: &str = "box.service.state";
 }
 
 #[cfg(test)]
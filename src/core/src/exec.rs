@@ -283,6 +283,143 @@ pub struct FilesystemResponse {
     pub error: Option<String>,
 }
 
+/// Longest CPU sampling window a [`GuestMetricsRequest`] may ask for.
+pub const MAX_GUEST_METRICS_SAMPLE_MS: u32 = 1000;
+
+/// Request for a resource usage sample taken inside the guest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestMetricsRequest {
+    /// Window over which CPU usage is measured, capped at
+    /// [`MAX_GUEST_METRICS_SAMPLE_MS`].
+    pub sample_ms: u32,
+    /// Number of processes to return in [`GuestMetrics::top`].
+    #[serde(default)]
+    pub top: u32,
+}
+
+/// Resource usage of the whole guest, read from its `/proc`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GuestMetrics {
+    /// Online vCPUs.
+    pub cpus: u32,
+    /// CPU busy time over the sample window; 100 per fully busy vCPU.
+    pub cpu_percent: f64,
+    /// CPU busy time summed over all vCPUs since boot, in nanoseconds. Zero
+    /// from guests that predate the field.
+    #[serde(default)]
+    pub cpu_busy_ns: u64,
+    /// `MemTotal` of the guest kernel.
+    pub memory_total_bytes: u64,
+    /// `MemTotal - MemAvailable`.
+    pub memory_used_bytes: u64,
+    /// Bytes read from the guest's block devices since boot.
+    pub disk_read_bytes: u64,
+    /// Bytes written to the guest's block devices since boot.
+    pub disk_write_bytes: u64,
+    /// Bytes received on non-loopback interfaces since boot.
+    pub network_rx_bytes: u64,
+    /// Bytes sent on non-loopback interfaces since boot.
+    pub network_tx_bytes: u64,
+    /// Number of processes in the guest.
+    pub processes: u64,
+    /// Top consumers by CPU over the sample window, then resident memory.
+    #[serde(default)]
+    pub top: Vec<GuestProcessMetrics>,
+    /// Cgroups directly under the guest's cgroup v2 root, such as the ones
+    /// guest init creates for container resource limits. Empty without
+    /// cgroup v2 and from guests that predate the field.
+    #[serde(default)]
+    pub cgroups: Vec<GuestCgroupMetrics>,
+}
+
+/// One guest process in [`GuestMetrics::top`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GuestProcessMetrics {
+    pub pid: u32,
+    /// Command line, or the kernel's `comm` name when it has none.
+    pub command: String,
+    /// CPU over the sample window; 100 per fully busy vCPU.
+    pub cpu_percent: f64,
+    /// Resident set size.
+    pub memory_bytes: u64,
+}
+
+/// One guest cgroup in [`GuestMetrics::cgroups`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestCgroupMetrics {
+    /// Directory name under the cgroup root.
+    pub name: String,
+    /// `usage_usec` from `cpu.stat`, in nanoseconds.
+    pub cpu_usage_ns: u64,
+    /// `memory.current`.
+    pub memory_bytes: u64,
+    /// `memory.max`, or `None` when unlimited.
+    pub memory_limit_bytes: Option<u64>,
+    /// `pids.current`.
+    pub pids: u64,
+    /// `oom_kill` from `memory.events`.
+    pub oom_kills: u64,
+}
+
+/// Request for the guest process tree with each process attributed to the
+/// main process, service, exec, or PTY session that started it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessTreeRequest {}
+
+/// What started a group of guest processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessOwnerKind {
+    /// The container main process.
+    Main,
+    /// A service supervised by guest init.
+    Service,
+    /// A command run over the exec channel.
+    Exec,
+    /// An interactive PTY session.
+    Pty,
+}
+
+/// The root process a guest process descends from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ProcessOwner {
+    pub kind: ProcessOwnerKind,
+    /// PID guest init started; it may have exited since.
+    pub root_pid: u32,
+    /// Service name, or the program an exec or PTY session ran.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// One guest process in a [`GuestProcessTree`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestProcess {
+    pub pid: u32,
+    pub ppid: u32,
+    /// Process group.
+    pub pgid: u32,
+    /// Session.
+    pub sid: u32,
+    /// Command line, or the kernel's `comm` name when it has none.
+    pub command: String,
+    /// `None` for guest init itself and processes no root accounts for.
+    #[serde(default)]
+    pub owner: Option<ProcessOwner>,
+    /// Reparented to guest init after its parent exited, such as a job a
+    /// shell put in the background; attributed by process group or session.
+    #[serde(default)]
+    pub orphaned: bool,
+}
+
+/// Response to a [`ProcessTreeRequest`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestProcessTree {
+    pub processes: Vec<GuestProcess>,
+    /// Orphans guest init has reaped since boot.
+    #[serde(default)]
+    pub orphans_reaped: u64,
+}
+
 /// Versioned non-exec request sent over the guest execution session.
 ///
 /// Exec requests predate this envelope and remain bare JSON for wire
@@ -295,6 +432,12 @@ pub enum GuestSessionRequest {
     File(FileRequest),
     /// Inspect or mutate workload filesystem metadata.
     Filesystem(FilesystemRequest),
+    /// Sample guest resource usage.
+    Metrics(GuestMetricsRequest),
+    /// Stream the states of the supervised guest services.
+    ServiceEvents(crate::service::ServiceEventsRequest),
+    /// List guest processes with their owners.
+    ProcessTree(ProcessTreeRequest),
 }
 
 #[cfg(test)]
@@ -705,6 +848,19 @@ mod tests {
         assert!(serde_json::from_value::<GuestSessionRequest>(value).is_ok());
     }
 
+    #[test]
+    fn metrics_session_request_has_an_unambiguous_wire_discriminator() {
+        let request = GuestSessionRequest::Metrics(GuestMetricsRequest {
+            sample_ms: 200,
+            top: 5,
+        });
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["request_type"], "metrics");
+        assert_eq!(value["request"]["sample_ms"], 200);
+        assert!(serde_json::from_value::<GuestSessionRequest>(value).is_ok());
+    }
+
     #[test]
     fn test_frame_exec_constants() {
         assert_eq!(FRAME_EXEC_CHUNK, 0x01);
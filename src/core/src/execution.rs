@@ -130,6 +130,9 @@ pub fn validate_sandbox_compatibility(config: &BoxConfig) -> Result<()> {
     if config.ksm {
         unsupported.push("KSM");
     }
+    if config.hugepages || config.mem_prealloc {
+        unsupported.push("hugepages and memory preallocation");
+    }
     if config.snapshot_mem_file.is_some()
         || config.snapshot_sock.is_some()
         || config.restore_from.is_some()
@@ -139,6 +142,15 @@ pub fn validate_sandbox_compatibility(config: &BoxConfig) -> Result<()> {
     if config.privileged {
         unsupported.push("privileged mode");
     }
+    if !config.devices.is_empty() {
+        unsupported.push("device passthrough");
+    }
+    if !config.block_volumes.is_empty() {
+        unsupported.push("block volumes");
+    }
+    if !config.socket_mounts.is_empty() {
+        unsupported.push("socket mounts");
+    }
     if config.sidecar.is_some() {
         unsupported.push("vsock sidecars");
     }
@@ -151,6 +163,9 @@ pub fn validate_sandbox_compatibility(config: &BoxConfig) -> Result<()> {
     if !config.sysctls.is_empty() {
         unsupported.push("custom sysctls");
     }
+    if !config.kernel_modules.is_empty() {
+        unsupported.push("kernel modules");
+    }
     let disallowed_capabilities: Vec<String> = config
         .cap_add
         .iter()
@@ -0,0 +1,383 @@
+//! General-purpose port forwarding (`a3s-box forward`).
+//!
+//! The shim bridges the box's [`FORWARD_SOCKET_FILE`] to guest vsock port
+//! [`FORWARD_VSOCK_PORT`], where guest init accepts one connection per
+//! forwarded stream. Each stream opens with a [`FORWARD_REQUEST_LEN`]-byte
+//! request naming the guest TCP port; guest init answers with one status byte
+//! and, on success, relays bytes until either side closes. A version-2
+//! request instead carries a [`ForwardTarget`], so a stream can also reach
+//! another guest host or a guest Unix socket.
+//!
+//! Unlike published ports (`-p`), forwards need no network mode and can be
+//! added and removed while the box runs, which makes them the way to reach
+//! debuggers, dev servers, and databases inside TSI-mode boxes.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Vsock port guest init accepts forwarded streams on.
+pub const FORWARD_VSOCK_PORT: u32 = 4095;
+
+/// Host Unix socket bridged to [`FORWARD_VSOCK_PORT`], next to the exec socket.
+pub const FORWARD_SOCKET_FILE: &str = "forward.sock";
+
+/// Magic prefix of a forward request.
+pub const FORWARD_MAGIC: &[u8; 4] = b"A3SF";
+
+/// Forward request protocol version.
+pub const FORWARD_VERSION: u8 = 1;
+
+/// Forward request version whose last two bytes are the big-endian length of
+/// a [`ForwardTarget`] in text form, sent right after the request.
+pub const FORWARD_TARGET_VERSION: u8 = 2;
+
+/// Length of a forward request: magic, version, big-endian guest port (or
+/// target length).
+pub const FORWARD_REQUEST_LEN: usize = 7;
+
+/// Longest target text a version-2 request may carry.
+pub const FORWARD_TARGET_MAX_LEN: usize = 4096;
+
+/// Status byte: guest init connected to the target port.
+pub const FORWARD_STATUS_OK: u8 = 0;
+
+/// Status byte: nothing accepted the connection on the target port.
+pub const FORWARD_STATUS_REFUSED: u8 = 1;
+
+/// Status byte: the request was malformed or the connection failed otherwise.
+pub const FORWARD_STATUS_ERROR: u8 = 2;
+
+/// Encode the request that opens a stream to `guest_port`.
+pub fn encode_forward_request(guest_port: u16) -> [u8; FORWARD_REQUEST_LEN] {
+    let mut request = [0u8; FORWARD_REQUEST_LEN];
+    request[..4].copy_from_slice(FORWARD_MAGIC);
+    request[4] = FORWARD_VERSION;
+    request[5..].copy_from_slice(&guest_port.to_be_bytes());
+    request
+}
+
+/// Decode a forward request, returning the guest port it names.
+pub fn decode_forward_request(request: &[u8; FORWARD_REQUEST_LEN]) -> Option<u16> {
+    if &request[..4] != FORWARD_MAGIC || request[4] != FORWARD_VERSION {
+        return None;
+    }
+    let port = u16::from_be_bytes([request[5], request[6]]);
+    (port != 0).then_some(port)
+}
+
+/// Encode the request that opens a stream to `target`, followed by the target
+/// itself for anything but a loopback port.
+pub fn encode_forward_target_request(target: &ForwardTarget) -> Vec<u8> {
+    if let ForwardTarget::Port(port) = target {
+        return encode_forward_request(*port).to_vec();
+    }
+    let text = target.to_string();
+    let mut request = Vec::with_capacity(FORWARD_REQUEST_LEN + text.len());
+    request.extend_from_slice(FORWARD_MAGIC);
+    request.push(FORWARD_TARGET_VERSION);
+    request.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    request.extend_from_slice(text.as_bytes());
+    request
+}
+
+/// Decode a version-2 forward request, returning the length of the target
+/// text that follows it.
+pub fn decode_forward_target_len(request: &[u8; FORWARD_REQUEST_LEN]) -> Option<usize> {
+    if &request[..4] != FORWARD_MAGIC || request[4] != FORWARD_TARGET_VERSION {
+        return None;
+    }
+    let len = usize::from(u16::from_be_bytes([request[5], request[6]]));
+    (1..=FORWARD_TARGET_MAX_LEN).contains(&len).then_some(len)
+}
+
+/// Encode `target` as the payload of a multiplexed port-forward `OPEN` frame
+/// (the [`PORT_FWD_VSOCK_PORT`](crate::PORT_FWD_VSOCK_PORT) channel). A
+/// loopback port keeps the original two-byte big-endian form; other targets
+/// use their text form, which is never two bytes long.
+pub fn encode_open_target(target: &ForwardTarget) -> Vec<u8> {
+    match target {
+        ForwardTarget::Port(port) => port.to_be_bytes().to_vec(),
+        _ => target.to_string().into_bytes(),
+    }
+}
+
+/// Decode the payload of a multiplexed port-forward `OPEN` frame.
+pub fn decode_open_target(payload: &[u8]) -> Option<ForwardTarget> {
+    if let [high, low] = payload {
+        let port = u16::from_be_bytes([*high, *low]);
+        return (port != 0).then_some(ForwardTarget::Port(port));
+    }
+    if payload.len() > FORWARD_TARGET_MAX_LEN {
+        return None;
+    }
+    std::str::from_utf8(payload).ok()?.parse().ok()
+}
+
+/// Human-readable meaning of a non-OK status byte.
+pub fn forward_status_message(status: u8) -> &'static str {
+    match status {
+        FORWARD_STATUS_OK => "connected",
+        FORWARD_STATUS_REFUSED => "connection refused",
+        _ => "guest forwarder error",
+    }
+}
+
+/// Where the host side of a forward listens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardListen {
+    /// A TCP address; port `0` picks a free port.
+    Tcp(SocketAddr),
+    /// A Unix socket path.
+    Unix(PathBuf),
+}
+
+impl fmt::Display for ForwardListen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "{address}"),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Where a forwarded stream connects inside the guest.
+///
+/// Text forms:
+///
+/// - `PORT` — a TCP port on the guest's loopback
+/// - `HOST:PORT` — a TCP host and port, resolved in the guest; IPv6 in
+///   brackets
+/// - `unix:PATH` — an absolute Unix socket path in the guest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardTarget {
+    /// A TCP port on the guest's loopback.
+    Port(u16),
+    /// A TCP host name or address and port.
+    Tcp { host: String, port: u16 },
+    /// A Unix socket path.
+    Unix(PathBuf),
+}
+
+impl fmt::Display for ForwardTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Port(port) => write!(f, "{port}"),
+            Self::Tcp { host, port } if host.contains(':') => write!(f, "[{host}]:{port}"),
+            Self::Tcp { host, port } => write!(f, "{host}:{port}"),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for ForwardTarget {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = input.strip_prefix("unix:") {
+            if !path.starts_with('/') {
+                return Err(format!(
+                    "Invalid forward target '{input}': socket path must be absolute"
+                ));
+            }
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        let Some((host, port)) = input.rsplit_once(':') else {
+            return parse_guest_port(input, input).map(Self::Port);
+        };
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+        if host.is_empty() || host.contains(['[', ']', '/']) {
+            return Err(format!(
+                "Invalid forward target '{input}': expected HOST:PORT"
+            ));
+        }
+        Ok(Self::Tcp {
+            host: host.to_string(),
+            port: parse_guest_port(input, port)?,
+        })
+    }
+}
+
+/// One host-to-guest forward.
+///
+/// Accepted forms:
+///
+/// - `GUEST_PORT` — `127.0.0.1:GUEST_PORT` on the host
+/// - `HOST_PORT:GUEST_PORT` — `127.0.0.1:HOST_PORT`
+/// - `HOST_IP:HOST_PORT:GUEST_PORT`, with IPv6 in brackets
+/// - `unix:PATH:GUEST_PORT` — a Unix socket on the host
+///
+/// Unlike `-p`, an omitted host address means loopback, not every address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardSpec {
+    /// Host-side listener.
+    pub listen: ForwardListen,
+    /// Guest TCP port the forward connects to.
+    pub guest_port: u16,
+}
+
+impl FromStr for ForwardSpec {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        if let Some(rest) = input.strip_prefix("unix:") {
+            let (path, port) = rest.rsplit_once(':').ok_or_else(|| {
+                format!("Invalid forward '{input}': expected unix:PATH:GUEST_PORT")
+            })?;
+            if path.is_empty() {
+                return Err(format!(
+                    "Invalid forward '{input}': socket path must not be empty"
+                ));
+            }
+            return Ok(Self {
+                listen: ForwardListen::Unix(PathBuf::from(path)),
+                guest_port: parse_guest_port(input, port)?,
+            });
+        }
+
+        if !input.contains(':') {
+            let guest_port = parse_guest_port(input, input)?;
+            return Ok(Self {
+                listen: ForwardListen::Tcp(SocketAddr::new(
+                    IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    guest_port,
+                )),
+                guest_port,
+            });
+        }
+
+        let explicit_address = input.starts_with('[') || input.matches(':').count() == 2;
+        let mapping = crate::port::parse_port_mapping(input)
+            .map_err(|error| error.replace("port mapping", "forward"))?;
+        let host_ip = match mapping.host_ip {
+            Some(address) => address,
+            // `parse_port_mapping` folds an explicit wildcard into `None`.
+            None if explicit_address && input.starts_with('[') => {
+                IpAddr::V6(std::net::Ipv6Addr::UNSPECIFIED)
+            }
+            None if explicit_address => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            None => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        };
+        Ok(Self {
+            listen: ForwardListen::Tcp(SocketAddr::new(host_ip, mapping.host_port)),
+            guest_port: mapping.guest_port,
+        })
+    }
+}
+
+fn parse_guest_port(input: &str, value: &str) -> Result<u16, String> {
+    match value.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(port),
+        _ => Err(format!(
+            "Invalid forward '{input}': guest port '{value}' must be 1..=65535"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tcp(spec: &str) -> (String, u16) {
+        let spec: ForwardSpec = spec.parse().unwrap();
+        (spec.listen.to_string(), spec.guest_port)
+    }
+
+    #[test]
+    fn test_request_round_trips() {
+        let request = encode_forward_request(5432);
+        assert_eq!(&request[..4], b"A3SF");
+        assert_eq!(decode_forward_request(&request), Some(5432));
+
+        let mut bad_magic = request;
+        bad_magic[0] = b'X';
+        assert_eq!(decode_forward_request(&bad_magic), None);
+        assert_eq!(decode_forward_request(&encode_forward_request(0)), None);
+    }
+
+    #[test]
+    fn test_target_request_round_trips() {
+        let target: ForwardTarget = "unix:/run/app.sock".parse().unwrap();
+        let request = encode_forward_target_request(&target);
+        let header: [u8; FORWARD_REQUEST_LEN] = request[..FORWARD_REQUEST_LEN].try_into().unwrap();
+        assert_eq!(decode_forward_request(&header), None);
+        let len = decode_forward_target_len(&header).unwrap();
+        let text = std::str::from_utf8(&request[FORWARD_REQUEST_LEN..]).unwrap();
+        assert_eq!(text.len(), len);
+        assert_eq!(text.parse::<ForwardTarget>().unwrap(), target);
+
+        // Loopback ports keep the version-1 request older guests understand.
+        let request = encode_forward_target_request(&ForwardTarget::Port(5432));
+        assert_eq!(request, encode_forward_request(5432));
+    }
+
+    #[test]
+    fn test_parse_forward_targets() {
+        for (input, target) in [
+            ("8080", ForwardTarget::Port(8080)),
+            (
+                "db.internal:5432",
+                ForwardTarget::Tcp {
+                    host: "db.internal".to_string(),
+                    port: 5432,
+                },
+            ),
+            (
+                "[fd00::2]:80",
+                ForwardTarget::Tcp {
+                    host: "fd00::2".to_string(),
+                    port: 80,
+                },
+            ),
+            (
+                "unix:/run/docker.sock",
+                ForwardTarget::Unix(PathBuf::from("/run/docker.sock")),
+            ),
+        ] {
+            assert_eq!(input.parse::<ForwardTarget>().unwrap(), target);
+            assert_eq!(target.to_string(), input);
+            assert_eq!(
+                decode_open_target(&encode_open_target(&target)),
+                Some(target)
+            );
+        }
+        for input in ["0", "x", ":80", "host:0", "unix:relative.sock", "[::1:80"] {
+            assert!(input.parse::<ForwardTarget>().is_err(), "{input}");
+        }
+        assert_eq!(decode_open_target(&[0, 0]), None);
+    }
+
+    #[test]
+    fn test_parse_tcp_forms_default_to_loopback() {
+        assert_eq!(tcp("8080"), ("127.0.0.1:8080".to_string(), 8080));
+        assert_eq!(tcp("9000:8080"), ("127.0.0.1:9000".to_string(), 8080));
+        assert_eq!(tcp("0:5432"), ("127.0.0.1:0".to_string(), 5432));
+        assert_eq!(tcp("0.0.0.0:9229:9229"), ("0.0.0.0:9229".to_string(), 9229));
+        assert_eq!(tcp("[::1]:9000:80"), ("[::1]:9000".to_string(), 80));
+        assert_eq!(tcp("[::]:9000:80"), ("[::]:9000".to_string(), 80));
+    }
+
+    #[test]
+    fn test_parse_unix_form() {
+        let spec: ForwardSpec = "unix:/tmp/pg.sock:5432".parse().unwrap();
+        assert_eq!(
+            spec.listen,
+            ForwardListen::Unix(PathBuf::from("/tmp/pg.sock"))
+        );
+        assert_eq!(spec.guest_port, 5432);
+        assert_eq!(spec.listen.to_string(), "unix:/tmp/pg.sock");
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_forwards() {
+        for input in ["0", "x", "unix:/tmp/a.sock", "unix::80", "8080:0", "1:2:3"] {
+            let error = input.parse::<ForwardSpec>().unwrap_err();
+            assert!(error.starts_with("Invalid forward"), "{input}: {error}");
+        }
+    }
+}
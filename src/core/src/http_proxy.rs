@@ -0,0 +1,229 @@
+//! Runtime-managed HTTP(S) forward proxy (`--http-proxy`).
+//!
+//! The shim serves the proxy on a host Unix socket that libkrun exposes to
+//! the guest as vsock port [`HTTP_PROXY_VSOCK_PORT`]. Guest init listens on
+//! [`GUEST_PROXY_ADDR`] and relays every connection to that port, and the
+//! runtime points the workload's `HTTP_PROXY` / `HTTPS_PROXY` at it.
+//!
+//! Every request the proxy handles is appended to the box's
+//! [`HTTP_AUDIT_FILE`] as one [`HttpAuditRecord`] per line:
+//!
+//! - [`HttpProxyMode::Audit`] records plain HTTP requests in full and HTTPS
+//!   as opaque `CONNECT host:port` tunnels with their byte counts.
+//! - [`HttpProxyMode::Mitm`] also terminates TLS with a per-box CA that the
+//!   runtime installs in the guest, so HTTPS requests are recorded with their
+//!   method, path, and status.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Vsock port the guest relays proxy connections to.
+pub const HTTP_PROXY_VSOCK_PORT: u32 = 4094;
+
+/// Guest environment variable carrying the address guest init listens on.
+pub const HTTP_PROXY_ENV: &str = "A3S_HTTP_PROXY";
+
+/// Address the workload's proxy variables point at.
+pub const GUEST_PROXY_ADDR: &str = "127.0.0.1:3128";
+
+/// Per-box audit log, in the box directory.
+pub const HTTP_AUDIT_FILE: &str = "http-audit.jsonl";
+
+/// Per-box interception CA certificate (PEM), in the box directory.
+pub const PROXY_CA_CERT_FILE: &str = "http-proxy-ca.pem";
+
+/// Per-box interception CA private key (PEM), in the box directory.
+pub const PROXY_CA_KEY_FILE: &str = "http-proxy-ca-key.pem";
+
+/// How much of the box's HTTPS traffic the proxy can see.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpProxyMode {
+    /// Forward HTTPS as opaque tunnels; record only the destination.
+    #[default]
+    Audit,
+    /// Terminate HTTPS with the per-box CA and record each request.
+    Mitm,
+}
+
+impl fmt::Display for HttpProxyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Audit => "audit",
+            Self::Mitm => "mitm",
+        })
+    }
+}
+
+impl std::str::FromStr for HttpProxyMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "audit" => Ok(Self::Audit),
+            "mitm" => Ok(Self::Mitm),
+            other => Err(format!(
+                "invalid --http-proxy mode '{other}': expected audit or mitm"
+            )),
+        }
+    }
+}
+
+/// Proxy settings the shim needs to serve one box.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpProxyInstanceConfig {
+    /// Interception mode.
+    pub mode: HttpProxyMode,
+    /// Host Unix socket bridged to [`HTTP_PROXY_VSOCK_PORT`].
+    pub socket_path: PathBuf,
+    /// JSON-lines audit log the proxy appends to.
+    pub audit_log: PathBuf,
+    /// CA certificate and key used to sign interception certificates
+    /// ([`HttpProxyMode::Mitm`] only).
+    #[serde(default)]
+    pub ca: Option<ProxyCaPaths>,
+}
+
+/// Location of a PEM CA certificate and its private key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyCaPaths {
+    /// CA certificate (PEM).
+    pub cert: PathBuf,
+    /// CA private key (PEM, PKCS#8).
+    pub key: PathBuf,
+}
+
+impl ProxyCaPaths {
+    /// The CA files kept in `box_dir`.
+    pub fn in_dir(box_dir: &Path) -> Self {
+        Self {
+            cert: box_dir.join(PROXY_CA_CERT_FILE),
+            key: box_dir.join(PROXY_CA_KEY_FILE),
+        }
+    }
+}
+
+/// One request handled by the proxy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpAuditRecord {
+    /// When the request arrived.
+    pub timestamp: DateTime<Utc>,
+    /// Request method (`CONNECT` for an opaque tunnel).
+    pub method: String,
+    /// `http` or `https`.
+    pub scheme: String,
+    /// Destination host.
+    pub host: String,
+    /// Destination port.
+    pub port: u16,
+    /// Request path and query; `None` for an opaque tunnel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Response status; `None` when no response was received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Bytes the box sent, including headers.
+    pub request_bytes: u64,
+    /// Bytes returned to the box, including headers.
+    pub response_bytes: u64,
+    /// Time from the request to the end of the response.
+    pub duration_ms: u64,
+    /// Why the request failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HttpAuditRecord {
+    /// The request target: a full URL, or `host:port` for a tunnel.
+    pub fn target(&self) -> String {
+        let default_port = match self.scheme.as_str() {
+            "https" => 443,
+            _ => 80,
+        };
+        let authority = if self.port == default_port {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        };
+        match &self.path {
+            Some(path) => format!("{}://{}{}", self.scheme, authority, path),
+            None => format!("{}:{}", self.host, self.port),
+        }
+    }
+}
+
+/// Read a box's audit log, oldest first. A missing log has no records, and
+/// lines that do not parse (a write cut short by a crash) are skipped.
+pub fn read_audit_log(path: &Path) -> std::io::Result<Vec<HttpAuditRecord>> {
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    Ok(data
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(scheme: &str, port: u16, path: Option<&str>) -> HttpAuditRecord {
+        HttpAuditRecord {
+            timestamp: Utc::now(),
+            method: "GET".to_string(),
+            scheme: scheme.to_string(),
+            host: "example.com".to_string(),
+            port,
+            path: path.map(str::to_string),
+            status: Some(200),
+            request_bytes: 64,
+            response_bytes: 512,
+            duration_ms: 12,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_mode_round_trips() {
+        assert_eq!("audit".parse::<HttpProxyMode>(), Ok(HttpProxyMode::Audit));
+        assert_eq!("mitm".parse::<HttpProxyMode>(), Ok(HttpProxyMode::Mitm));
+        assert!("tls".parse::<HttpProxyMode>().is_err());
+        assert_eq!(HttpProxyMode::Mitm.to_string(), "mitm");
+        assert_eq!(
+            serde_json::to_string(&HttpProxyMode::Audit).unwrap(),
+            "\"audit\""
+        );
+    }
+
+    #[test]
+    fn test_record_target() {
+        assert_eq!(
+            record("https", 443, Some("/v1/messages")).target(),
+            "https://example.com/v1/messages"
+        );
+        assert_eq!(
+            record("http", 8080, Some("/")).target(),
+            "http://example.com:8080/"
+        );
+        assert_eq!(record("https", 443, None).target(), "example.com:443");
+    }
+
+    #[test]
+    fn test_read_audit_log_skips_torn_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HTTP_AUDIT_FILE);
+        assert!(read_audit_log(&path).unwrap().is_empty());
+
+        let line = serde_json::to_string(&record("http", 80, Some("/"))).unwrap();
+        std::fs::write(&path, format!("{line}\n{{\"timestamp\":")).unwrap();
+
+        let records = read_audit_log(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].target(), "http://example.com/");
+    }
+}
@@ -0,0 +1,156 @@
+//! Guest kernel tuning applied by guest init at boot.
+//!
+//! Many developer workloads trip over guest kernel defaults (a small
+//! `net.core.somaxconn`, too few `fs.inotify.max_user_watches`) or need a
+//! module the kernel does not load on its own. The host passes the box's
+//! kernel modules as `BOX_KERNEL_MODULE_<index>=<name>` and its sysctls as
+//! `BOX_SYSCTL_<index>=<name>=<value>`; guest init loads the modules first,
+//! since a module may add the sysctls that follow, then writes each sysctl
+//! to `/proc/sys/<name with '.' as '/'>`.
+
+use std::collections::HashMap;
+
+/// Guest-init environment prefix of the indexed sysctl entries.
+pub const SYSCTL_ENV_PREFIX: &str = "BOX_SYSCTL_";
+
+/// Guest-init environment prefix of the indexed kernel module entries.
+pub const KERNEL_MODULE_ENV_PREFIX: &str = "BOX_KERNEL_MODULE_";
+
+/// Check that a sysctl name maps onto a path under `/proc/sys`.
+///
+/// The guest substitutes `/` for `.`, so a name must be a non-empty
+/// dot-separated key without path separators or traversal segments (e.g.
+/// `../../proc/sysrq-trigger` must not escape `/proc/sys`).
+pub fn validate_sysctl_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.contains('/')
+        && !name.split('.').any(|seg| seg.is_empty() || seg == "..")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid sysctl name {name:?}"))
+    }
+}
+
+/// Parse a `name=value` sysctl assignment.
+pub fn parse_sysctl(spec: &str) -> Result<(String, String), String> {
+    let (name, value) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("invalid sysctl {spec:?}: expected NAME=VALUE"))?;
+    let name = name.trim();
+    validate_sysctl_name(name)?;
+    if value.contains('\n') {
+        return Err(format!("invalid sysctl {spec:?}: value spans lines"));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Check that a kernel module name is a plain module name, as `modprobe`
+/// takes it.
+pub fn validate_kernel_module_name(name: &str) -> Result<(), String> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    {
+        Ok(())
+    } else {
+        Err(format!("invalid kernel module name {name:?}"))
+    }
+}
+
+/// The guest-init environment carrying `sysctls` and `modules`. Sysctls are
+/// sorted by name so they apply in a deterministic order.
+pub fn guest_env(sysctls: &HashMap<String, String>, modules: &[String]) -> Vec<(String, String)> {
+    let mut sorted: Vec<_> = sysctls.iter().collect();
+    sorted.sort();
+    modules
+        .iter()
+        .enumerate()
+        .map(|(index, module)| (format!("{KERNEL_MODULE_ENV_PREFIX}{index}"), module.clone()))
+        .chain(
+            sorted
+                .into_iter()
+                .enumerate()
+                .map(|(index, (name, value))| {
+                    (
+                        format!("{SYSCTL_ENV_PREFIX}{index}"),
+                        format!("{name}={value}"),
+                    )
+                }),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sysctl_names_stay_under_proc_sys() {
+        assert!(validate_sysctl_name("net.core.somaxconn").is_ok());
+        assert!(validate_sysctl_name("net.ipv4.conf.eth0-1.forwarding").is_ok());
+        for name in [
+            "",
+            "../../proc/sysrq-trigger",
+            "net..core",
+            "kernel/sysrq",
+            "a b",
+        ] {
+            assert!(validate_sysctl_name(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_parse_sysctl() {
+        assert_eq!(
+            parse_sysctl("fs.inotify.max_user_watches=524288").unwrap(),
+            (
+                "fs.inotify.max_user_watches".to_string(),
+                "524288".to_string()
+            )
+        );
+        assert_eq!(
+            parse_sysctl("net.ipv4.ip_local_port_range=1024 65000")
+                .unwrap()
+                .1,
+            "1024 65000"
+        );
+        assert!(parse_sysctl("net.core.somaxconn").is_err());
+        assert!(parse_sysctl("kernel/core_pattern=x").is_err());
+    }
+
+    #[test]
+    fn test_guest_env_lists_modules_then_sorted_sysctls() {
+        let sysctls = HashMap::from([
+            ("net.core.somaxconn".to_string(), "4096".to_string()),
+            (
+                "fs.inotify.max_user_watches".to_string(),
+                "524288".to_string(),
+            ),
+        ]);
+        let modules = vec!["br_netfilter".to_string()];
+        assert_eq!(
+            guest_env(&sysctls, &modules),
+            vec![
+                (
+                    "BOX_KERNEL_MODULE_0".to_string(),
+                    "br_netfilter".to_string()
+                ),
+                (
+                    "BOX_SYSCTL_0".to_string(),
+                    "fs.inotify.max_user_watches=524288".to_string()
+                ),
+                (
+                    "BOX_SYSCTL_1".to_string(),
+                    "net.core.somaxconn=4096".to_string()
+                ),
+            ]
+        );
+        assert!(validate_kernel_module_name("nf_conntrack").is_ok());
+        assert!(validate_kernel_module_name("../evil").is_err());
+    }
+}
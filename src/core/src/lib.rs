@@ -6,14 +6,22 @@
 pub mod audit;
 pub mod compose;
 pub mod config;
+pub mod device;
 pub mod dns;
+pub mod egress;
+pub mod entropy;
 pub mod env;
 pub mod error;
+pub mod error_catalog;
+pub mod eval;
 pub mod event;
 pub mod exec;
 pub mod execution;
+pub mod forward;
 pub mod fs_atomic;
 pub mod guest_exec;
+pub mod http_proxy;
+pub mod kernel;
 pub mod lifecycle_profile;
 pub mod log;
 pub mod network;
@@ -24,6 +32,7 @@ pub mod pty;
 pub mod rootfs_metadata;
 pub mod scale;
 pub mod security;
+pub mod service;
 pub mod snapshot;
 pub mod tee;
 pub mod traits;
@@ -32,18 +41,22 @@ pub mod volume;
 #[cfg(windows)]
 pub mod windows_file;
 pub mod workload;
+pub mod workspace_template;
 
 // Re-export commonly used types
 pub use audit::{AuditAction, AuditConfig, AuditEvent, AuditOutcome};
 pub use compose::ComposeConfig;
 pub use config::{BoxConfig, ExecutionIsolation, ResourceConfig, ResourceLimits};
 pub use error::{BoxError, Result};
+pub use error_catalog::ErrorCode;
 pub use event::{BoxEvent, EventEmitter};
 pub use exec::{ExecChunk, ExecEvent, ExecExit, ExecMetrics, StreamType};
 pub use exec::{ExecOutput, ExecRequest};
 pub use exec::{
     FileOp, FileRequest, FileResponse, FilesystemEntry, FilesystemEntryKind, FilesystemOp,
-    FilesystemRequest, FilesystemResponse, GuestSessionRequest,
+    FilesystemRequest, FilesystemResponse, GuestCgroupMetrics, GuestMetrics, GuestMetricsRequest,
+    GuestProcess, GuestProcessMetrics, GuestProcessTree, GuestSessionRequest, ProcessOwner,
+    ProcessOwnerKind, ProcessTreeRequest,
 };
 pub use exec::{EXEC_VSOCK_PORT, PORT_FWD_VSOCK_PORT};
 pub use execution::{
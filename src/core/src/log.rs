@@ -17,6 +17,18 @@ pub enum LogDriver {
     /// - `syslog-facility`: Syslog facility (default: "daemon")
     /// - `tag`: Log tag template (default: box name)
     Syslog,
+    /// Write each line to the local systemd journal (Linux only).
+    ///
+    /// Entries carry the tag as `SYSLOG_IDENTIFIER` and the stream as
+    /// `PRIORITY` (6 for stdout, 3 for stderr).
+    Journald,
+    /// Forward logs to a Fluentd / Fluent Bit `forward` input.
+    ///
+    /// Options:
+    /// - `fluentd-address`: `host:port`, `tcp://host:port` or `unix:///path`
+    ///   (default: "localhost:24224")
+    /// - `tag`: Fluentd tag (default: "a3s-box")
+    Fluentd,
     /// Disable logging entirely.
     None,
 }
@@ -26,6 +38,8 @@ impl std::fmt::Display for LogDriver {
         match self {
             Self::JsonFile => write!(f, "json-file"),
             Self::Syslog => write!(f, "syslog"),
+            Self::Journald => write!(f, "journald"),
+            Self::Fluentd => write!(f, "fluentd"),
             Self::None => write!(f, "none"),
         }
     }
@@ -38,9 +52,11 @@ impl std::str::FromStr for LogDriver {
         match s {
             "json-file" => Ok(Self::JsonFile),
             "syslog" => Ok(Self::Syslog),
+            "journald" => Ok(Self::Journald),
+            "fluentd" => Ok(Self::Fluentd),
             "none" => Ok(Self::None),
             _ => Err(format!(
-                "unknown log driver: '{}' (supported: json-file, syslog, none)",
+                "unknown log driver: '{}' (supported: json-file, syslog, journald, fluentd, none)",
                 s
             )),
         }
@@ -101,7 +117,16 @@ impl LogConfig {
             .unwrap_or("daemon")
     }
 
-    /// Log tag (used by syslog driver as the program name).
+    /// Fluentd forward address (e.g., "localhost:24224").
+    /// Only relevant when driver is `Fluentd`.
+    pub fn fluentd_address(&self) -> &str {
+        self.options
+            .get("fluentd-address")
+            .map(|s| s.as_str())
+            .unwrap_or("localhost:24224")
+    }
+
+    /// Log tag (syslog program name, journald identifier, Fluentd tag).
     pub fn tag(&self) -> Option<&str> {
         self.options.get("tag").map(|s| s.as_str())
     }
@@ -168,7 +193,8 @@ fn parse_size(s: &str) -> std::result::Result<u64, String> {
 
 // ===========================================================================
 // Log processor — tails the VM console (`console.log`) and produces structured
-// Docker-compatible output (`container.json`) or forwards to syslog.
+// Docker-compatible output (`container.json`) or forwards to syslog, journald
+// or Fluentd.
 //
 // This runs in the SHIM (the box's own per-process lifetime), not the ephemeral
 // CLI: the CLI exits on `run -d` detach, which would kill an in-CLI processor
@@ -242,6 +268,17 @@ pub fn json_log_path(log_dir: &Path) -> PathBuf {
     log_dir.join("container.json")
 }
 
+/// Rotated generations of the json-file log at `path` that still exist,
+/// oldest first (`container.json.3.gz`, `.2.gz`, `.1.gz`).
+pub fn rotated_log_paths(path: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = (1..)
+        .map(|index| rotated_path(path, index))
+        .take_while(|rotated| rotated.exists())
+        .collect();
+    paths.reverse();
+    paths
+}
+
 /// The phase-aware filter for libkrun's C-init console preamble.
 ///
 /// C-init emits a small, fixed set of diagnostics before calling `execvp`.
@@ -626,6 +663,12 @@ fn run_log_processor_streams_with_ready_and_eof_policy(
         LogDriver::Syslog => {
             run_syslog_processor(stdout_log, stderr_log, config, stop, ready, eof_policy)
         }
+        LogDriver::Journald => {
+            run_journald_processor(stdout_log, stderr_log, config, stop, ready, eof_policy)
+        }
+        LogDriver::Fluentd => {
+            run_fluentd_processor(stdout_log, stderr_log, config, stop, ready, eof_policy)
+        }
     }
 }
 
@@ -1006,6 +1049,194 @@ fn run_syslog_processor(
     }
 }
 
+/// Tail both console streams through `emit` with the runtime preamble
+/// filtered and the raw console bounded, as the forwarding drivers need.
+fn run_forwarding_tails(
+    console_log: &Path,
+    err_log: &Path,
+    config: &LogConfig,
+    emit: &(dyn Fn(&str, &str) + Sync),
+    stop: &AtomicBool,
+    ready: Option<&std::sync::atomic::AtomicUsize>,
+    eof_policy: ConsoleEofPolicy,
+) {
+    let cap = Some(console_cap(config.max_size(), config.max_file()));
+    let runtime_filter = RuntimeConsoleFilter::new();
+    std::thread::scope(|s| {
+        for (file, stream) in [(console_log, "stdout"), (err_log, "stderr")] {
+            let runtime_filter = &runtime_filter;
+            s.spawn(move || {
+                run_tagged_tail(
+                    file,
+                    stop,
+                    emit,
+                    TaggedTailOptions {
+                        stream,
+                        runtime_filter: Some(runtime_filter),
+                        bound: cap,
+                        ready,
+                        eof_policy,
+                    },
+                )
+            });
+        }
+    });
+}
+
+/// The systemd journal's native-protocol socket.
+#[cfg(target_os = "linux")]
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Forward both console streams to the local systemd journal.
+#[cfg(target_os = "linux")]
+fn run_journald_processor(
+    console_log: &Path,
+    err_log: &Path,
+    config: &LogConfig,
+    stop: &AtomicBool,
+    ready: Option<&std::sync::atomic::AtomicUsize>,
+    eof_policy: ConsoleEofPolicy,
+) {
+    let socket = match std::os::unix::net::UnixDatagram::unbound() {
+        Ok(socket) => socket,
+        Err(_) => return,
+    };
+    let tag = config.tag().unwrap_or("a3s-box");
+    let emit = |line: &str, stream: &str| {
+        let _ = socket.send_to(&journald_entry(line, stream, tag), JOURNALD_SOCKET);
+    };
+    run_forwarding_tails(console_log, err_log, config, &emit, stop, ready, eof_policy);
+}
+
+/// Hosts without systemd have no journal; drain and bound the console like
+/// the `none` driver so it cannot fill the disk.
+#[cfg(not(target_os = "linux"))]
+fn run_journald_processor(
+    console_log: &Path,
+    err_log: &Path,
+    config: &LogConfig,
+    stop: &AtomicBool,
+    ready: Option<&std::sync::atomic::AtomicUsize>,
+    eof_policy: ConsoleEofPolicy,
+) {
+    tracing::warn!("journald log driver is only available on Linux; discarding logs");
+    run_discard_processor(
+        console_log,
+        err_log,
+        Some(console_cap(config.max_size(), config.max_file())),
+        stop,
+        ready,
+        eof_policy,
+    );
+}
+
+/// Encode one journal entry in the native protocol: `KEY=value` lines, with
+/// the length-prefixed binary form for values that contain a newline.
+fn journald_entry(line: &str, stream: &str, tag: &str) -> Vec<u8> {
+    let priority = if stream == "stderr" { "3" } else { "6" };
+    let mut entry = Vec::with_capacity(line.len() + tag.len() + 48);
+    for (key, value) in [
+        ("MESSAGE", line),
+        ("PRIORITY", priority),
+        ("SYSLOG_IDENTIFIER", tag),
+    ] {
+        entry.extend_from_slice(key.as_bytes());
+        if value.contains('\n') {
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    }
+    entry
+}
+
+/// Forward both console streams to a Fluentd `forward` input.
+///
+/// The connection is opened lazily and re-dialed after a failed write, so a
+/// collector that starts after the box, or restarts, still receives later
+/// lines; lines emitted while it is unreachable are dropped.
+fn run_fluentd_processor(
+    console_log: &Path,
+    err_log: &Path,
+    config: &LogConfig,
+    stop: &AtomicBool,
+    ready: Option<&std::sync::atomic::AtomicUsize>,
+    eof_policy: ConsoleEofPolicy,
+) {
+    let address = config.fluentd_address();
+    let tag = config.tag().unwrap_or("a3s-box");
+    let connection: std::sync::Mutex<Option<Box<dyn Write + Send>>> = std::sync::Mutex::new(None);
+    let emit = |line: &str, stream: &str| {
+        let message = fluentd_message(tag, chrono::Utc::now(), line, stream);
+        let Ok(mut connection) = connection.lock() else {
+            return;
+        };
+        for _ in 0..2 {
+            if connection.is_none() {
+                *connection = connect_fluentd(address).ok();
+            }
+            match connection.as_mut() {
+                Some(stream) if stream.write_all(&message).is_ok() => return,
+                Some(_) => *connection = None,
+                None => return,
+            }
+        }
+    };
+    run_forwarding_tails(console_log, err_log, config, &emit, stop, ready, eof_policy);
+}
+
+fn connect_fluentd(address: &str) -> std::io::Result<Box<dyn Write + Send>> {
+    #[cfg(unix)]
+    if let Some(path) = address.strip_prefix("unix://") {
+        return Ok(Box::new(std::os::unix::net::UnixStream::connect(path)?));
+    }
+    let address = address.strip_prefix("tcp://").unwrap_or(address);
+    Ok(Box::new(std::net::TcpStream::connect(address)?))
+}
+
+/// Encode one Fluentd forward-protocol message, `[tag, time, record]`, in
+/// MessagePack. The time is an `EventTime` so sub-second order survives;
+/// the record carries Docker's `log` and `source` keys.
+fn fluentd_message(
+    tag: &str,
+    time: chrono::DateTime<chrono::Utc>,
+    line: &str,
+    stream: &str,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(line.len() + tag.len() + 32);
+    message.push(0x93);
+    msgpack_str(&mut message, tag);
+    // EventTime: fixext 8, type 0, big-endian seconds then nanoseconds.
+    message.extend_from_slice(&[0xd7, 0x00]);
+    message.extend_from_slice(&(time.timestamp() as u32).to_be_bytes());
+    message.extend_from_slice(&time.timestamp_subsec_nanos().to_be_bytes());
+    message.push(0x82);
+    msgpack_str(&mut message, "log");
+    msgpack_str(&mut message, line);
+    msgpack_str(&mut message, "source");
+    msgpack_str(&mut message, stream);
+    message
+}
+
+fn msgpack_str(out: &mut Vec<u8>, value: &str) {
+    let len = value.len();
+    if len < 32 {
+        out.push(0xa0 | len as u8);
+    } else if len <= u8::MAX as usize {
+        out.extend_from_slice(&[0xd9, len as u8]);
+    } else if len <= u16::MAX as usize {
+        out.push(0xda);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0xdb);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    out.extend_from_slice(value.as_bytes());
+}
+
 /// A file writer that rotates (and gzips) when the file exceeds `max_size`.
 struct RotatingWriter {
     path: PathBuf,
@@ -1105,6 +1336,11 @@ mod tests {
             LogDriver::JsonFile
         );
         assert_eq!("syslog".parse::<LogDriver>().unwrap(), LogDriver::Syslog);
+        assert_eq!(
+            "journald".parse::<LogDriver>().unwrap(),
+            LogDriver::Journald
+        );
+        assert_eq!("fluentd".parse::<LogDriver>().unwrap(), LogDriver::Fluentd);
         assert_eq!("none".parse::<LogDriver>().unwrap(), LogDriver::None);
         assert!("unknown".parse::<LogDriver>().is_err());
     }
@@ -1207,6 +1443,68 @@ mod tests {
         assert_eq!(config.tag(), Some("myapp"));
     }
 
+    #[test]
+    fn test_journald_entry_encoding() {
+        assert_eq!(
+            journald_entry("hello", "stdout", "web"),
+            b"MESSAGE=hello\nPRIORITY=6\nSYSLOG_IDENTIFIER=web\n"
+        );
+        let entry = journald_entry("a\nb", "stderr", "web");
+        assert!(entry.starts_with(b"MESSAGE\n\x03\0\0\0\0\0\0\0a\nb\nPRIORITY=3\n"));
+    }
+
+    #[test]
+    fn test_fluentd_message_encoding() {
+        let time = chrono::DateTime::from_timestamp(1_700_000_000, 5).unwrap();
+        let message = fluentd_message("web", time, "hi", "stderr");
+        let mut expected = vec![0x93, 0xa3, b'w', b'e', b'b', 0xd7, 0x00];
+        expected.extend_from_slice(&1_700_000_000u32.to_be_bytes());
+        expected.extend_from_slice(&5u32.to_be_bytes());
+        expected.extend_from_slice(&[0x82, 0xa3, b'l', b'o', b'g', 0xa2, b'h', b'i']);
+        expected.extend_from_slice(&[0xa6, b's', b'o', b'u', b'r', b'c', b'e']);
+        expected.extend_from_slice(&[0xa6, b's', b't', b'd', b'e', b'r', b'r']);
+        assert_eq!(message, expected);
+
+        let mut long = Vec::new();
+        msgpack_str(&mut long, &"x".repeat(300));
+        assert_eq!(&long[..3], &[0xda, 0x01, 0x2c]);
+    }
+
+    #[test]
+    fn test_fluentd_processor_forwards_lines() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let console = dir.path().join("console.log");
+        std::fs::write(&console, "one\n").unwrap();
+        std::fs::write(stderr_console_path(&console), "").unwrap();
+        let mut config = LogConfig {
+            driver: LogDriver::Fluentd,
+            options: HashMap::new(),
+        };
+        config.options.insert(
+            "fluentd-address".to_string(),
+            format!("tcp://{}", listener.local_addr().unwrap()),
+        );
+
+        let stop = AtomicBool::new(true);
+        run_log_processor_with_ready_and_eof_policy(
+            &console,
+            dir.path(),
+            &config,
+            &stop,
+            None,
+            ConsoleEofPolicy::WriterClosed,
+        );
+
+        let (mut received, _) = listener.accept().unwrap();
+        let mut bytes = Vec::new();
+        std::io::Read::read_to_end(&mut received, &mut bytes).unwrap();
+        assert!(bytes.starts_with(&[0x93, 0xa7]));
+        assert!(
+            bytes.ends_with(&[0xa3, b'o', b'n', b'e', 0xa6, b's', b't', b'd', b'o', b'u', b't'])
+        );
+    }
+
     #[test]
     fn test_log_driver_display() {
         assert_eq!(LogDriver::JsonFile.to_string(), "json-file");
@@ -1680,5 +1978,13 @@ mod tests {
             rotated_path(&path, 1).exists(),
             "expected a rotated .1.gz file"
         );
+        assert_eq!(
+            rotated_log_paths(&path),
+            vec![
+                rotated_path(&path, 3),
+                rotated_path(&path, 2),
+                rotated_path(&path, 1)
+            ]
+        );
     }
 }
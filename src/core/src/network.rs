@@ -6,7 +6,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
 
 /// Network mode for a box.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -48,6 +49,15 @@ pub struct NetworkConfig {
     /// Gateway IP address (e.g., "10.88.0.1").
     pub gateway: Ipv4Addr,
 
+    /// IPv6 subnet in CIDR notation (e.g., "fd00:a3b0::/64") for dual-stack
+    /// networks; `None` for IPv4-only networks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnet6: Option<String>,
+
+    /// IPv6 gateway address, set together with `subnet6`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway6: Option<Ipv6Addr>,
+
     /// Network driver (currently only "bridge" is supported).
     #[serde(default = "default_driver")]
     pub driver: String,
@@ -89,8 +99,38 @@ pub struct NetworkEndpoint {
     /// Assigned IPv4 address.
     pub ip_address: Ipv4Addr,
 
+    /// Assigned IPv6 address on a dual-stack network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip6_address: Option<Ipv6Addr>,
+
     /// Assigned MAC address (hex string, e.g., "02:42:0a:58:00:02").
     pub mac_address: String,
+
+    /// Network namespace holding the endpoint's host-side interface, when a
+    /// CNI plugin rather than this network's IPAM set it up. passt runs
+    /// inside it so the box's traffic leaves through that interface.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub netns: Option<PathBuf>,
+}
+
+impl NetworkEndpoint {
+    /// Replace the endpoint's aliases, dropping empty names, duplicates, and
+    /// the box name itself.
+    pub fn set_aliases(&mut self, aliases: &[String]) {
+        self.aliases.clear();
+        for alias in aliases {
+            if !alias.is_empty() && *alias != self.box_name && !self.aliases.contains(alias) {
+                self.aliases.push(alias.clone());
+            }
+        }
+    }
+
+    /// DNS names this endpoint answers to: the box name, then its aliases.
+    pub fn dns_names(&self) -> Vec<String> {
+        std::iter::once(self.box_name.clone())
+            .chain(self.aliases.iter().cloned())
+            .collect()
+    }
 }
 
 /// Network isolation policy.
@@ -435,6 +475,8 @@ impl NetworkConfig {
             name: name.to_string(),
             subnet: ipam.cidr(),
             gateway: ipam.gateway(),
+            subnet6: None,
+            gateway6: None,
             driver: "bridge".to_string(),
             labels: HashMap::new(),
             endpoints: HashMap::new(),
@@ -443,6 +485,66 @@ impl NetworkConfig {
         })
     }
 
+    /// Use a custom gateway instead of the subnet's first host address.
+    ///
+    /// The gateway must be a host address inside the subnet; IPAM never hands
+    /// it out to a box.
+    pub fn set_gateway(&mut self, gateway: Ipv4Addr) -> Result<(), String> {
+        let ipam = Ipam::new(&self.subnet)?;
+        let network = Ipv4Addr::from(u32::from(ipam.gateway()) - 1);
+        let prefix_len = self
+            .subnet
+            .split_once('/')
+            .and_then(|(_, prefix)| prefix.parse::<u32>().ok())
+            .unwrap_or(32);
+        let mask = u32::MAX << (32 - prefix_len);
+        if u32::from(gateway) & mask != u32::from(network) & mask {
+            return Err(format!(
+                "gateway {gateway} is outside subnet {}",
+                self.subnet
+            ));
+        }
+        if gateway == network || gateway == ipam.broadcast() {
+            return Err(format!(
+                "gateway {gateway} must be a host address in subnet {}",
+                self.subnet
+            ));
+        }
+        if let Some(endpoint) = self.endpoints.values().find(|e| e.ip_address == gateway) {
+            return Err(format!(
+                "gateway {gateway} is already assigned to box '{}'",
+                endpoint.box_name
+            ));
+        }
+        self.gateway = gateway;
+        Ok(())
+    }
+
+    /// Make the network dual-stack with the given IPv6 subnet.
+    ///
+    /// Boxes connected afterwards also get an IPv6 address; the gateway is
+    /// the subnet's first host address.
+    pub fn set_ipv6_subnet(&mut self, subnet6: &str) -> Result<(), String> {
+        let ipam = Ipam6::new(subnet6)?;
+        if !self.endpoints.is_empty() {
+            return Err(format!(
+                "cannot enable IPv6 on network '{}' while boxes are connected",
+                self.name
+            ));
+        }
+        self.subnet6 = Some(ipam.cidr());
+        self.gateway6 = Some(ipam.gateway());
+        Ok(())
+    }
+
+    /// Prefix length of the IPv6 subnet, if the network is dual-stack.
+    pub fn ipv6_prefix_len(&self) -> Option<u8> {
+        self.subnet6
+            .as_deref()
+            .and_then(|subnet| subnet.split_once('/'))
+            .and_then(|(_, prefix)| prefix.parse().ok())
+    }
+
     /// Validate the driver and policy that the runtime can enforce today.
     pub fn validate_runtime(&self) -> Result<(), String> {
         if self.driver != "bridge" {
@@ -477,22 +579,88 @@ impl NetworkConfig {
         }
 
         let ipam = Ipam::new(&self.subnet)?;
-        let used: Vec<Ipv4Addr> = self.endpoints.values().map(|e| e.ip_address).collect();
+        // A custom gateway is not IPAM's default, so reserve it explicitly.
+        let used: Vec<Ipv4Addr> = self
+            .endpoints
+            .values()
+            .map(|e| e.ip_address)
+            .chain(std::iter::once(self.gateway))
+            .collect();
         let ip = ipam.allocate(&used)?;
         let mac = Ipam::mac_from_ip(&ip);
+        let ip6 = match self.subnet6.as_deref() {
+            Some(subnet6) => {
+                let used6: Vec<Ipv6Addr> = self
+                    .endpoints
+                    .values()
+                    .filter_map(|e| e.ip6_address)
+                    .collect();
+                Some(Ipam6::new(subnet6)?.allocate(&used6)?)
+            }
+            None => None,
+        };
 
-        let endpoint = NetworkEndpoint {
+        let mut endpoint = NetworkEndpoint {
             box_id: box_id.to_string(),
             box_name: box_name.to_string(),
-            aliases: aliases
-                .iter()
-                .filter(|a| !a.is_empty() && *a != box_name)
-                .cloned()
-                .collect(),
+            aliases: Vec::new(),
             ip_address: ip,
+            ip6_address: ip6,
             mac_address: mac,
+            netns: None,
         };
+        endpoint.set_aliases(aliases);
+
+        self.endpoints.insert(box_id.to_string(), endpoint.clone());
+        Ok(endpoint)
+    }
+
+    /// Register an endpoint whose IP was assigned outside this network's
+    /// IPAM, by a CNI plugin whose interface lives in `netns`.
+    pub fn attach(
+        &mut self,
+        box_id: &str,
+        box_name: &str,
+        ip: Ipv4Addr,
+        netns: PathBuf,
+    ) -> Result<NetworkEndpoint, String> {
+        if self.endpoints.contains_key(box_id) {
+            return Err(format!(
+                "box '{}' is already connected to network '{}'",
+                box_id, self.name
+            ));
+        }
+        let ipam = Ipam::new(&self.subnet)?;
+        let network = u32::from(ipam.gateway()) - 1;
+        let mask = u32::MAX << (32 - ipam.prefix_len as u32);
+        if u32::from(ip) & mask != network & mask
+            || u32::from(ip) == network
+            || ip == ipam.broadcast()
+        {
+            return Err(format!(
+                "{ip} is not a host address in subnet {}",
+                self.subnet
+            ));
+        }
+        if ip == self.gateway {
+            return Err(format!("{ip} is the gateway of network '{}'", self.name));
+        }
+        if let Some(endpoint) = self.endpoints.values().find(|e| e.ip_address == ip) {
+            return Err(format!(
+                "{ip} is already assigned to box '{}'",
+                endpoint.box_name
+            ));
+        }
 
+        let endpoint = NetworkEndpoint {
+            box_id: box_id.to_string(),
+            box_name: box_name.to_string(),
+            aliases: Vec::new(),
+            ip_address: ip,
+            ip6_address: None,
+            mac_address: Ipam::mac_from_ip(&ip),
+            netns: Some(netns),
+        };
         self.endpoints.insert(box_id.to_string(), endpoint.clone());
         Ok(endpoint)
     }
@@ -861,6 +1029,32 @@ mod tests {
         assert!(peers.iter().any(|(ip, name)| ip == &db_ip && name == "db"));
     }
 
+    #[test]
+    fn test_network_config_custom_gateway_is_reserved() {
+        let mut net = NetworkConfig::new("mynet", "10.88.0.0/24").unwrap();
+        net.set_gateway("10.88.0.2".parse().unwrap()).unwrap();
+        assert_eq!(net.gateway, Ipv4Addr::new(10, 88, 0, 2));
+
+        let ep = net.connect("box1", "web").unwrap();
+        assert_eq!(ep.ip_address, Ipv4Addr::new(10, 88, 0, 3));
+    }
+
+    #[test]
+    fn test_network_config_rejects_invalid_gateway() {
+        let mut net = NetworkConfig::new("mynet", "10.88.0.0/24").unwrap();
+        for gateway in ["10.89.0.1", "10.88.0.0", "10.88.0.255"] {
+            assert!(
+                net.set_gateway(gateway.parse().unwrap()).is_err(),
+                "{gateway}"
+            );
+        }
+
+        let ep = net.connect("box1", "web").unwrap();
+        let err = net.set_gateway(ep.ip_address).unwrap_err();
+        assert!(err.contains("already assigned"), "got: {err}");
+        assert_eq!(net.gateway, Ipv4Addr::new(10, 88, 0, 1));
+    }
+
     #[test]
     fn test_connect_alias_skips_empty_and_self_duplicate() {
         let mut net = NetworkConfig::new("mynet", "10.88.0.0/24").unwrap();
@@ -871,6 +1065,19 @@ mod tests {
         assert!(ep.aliases.is_empty());
     }
 
+    #[test]
+    fn test_endpoint_set_aliases_dedupes_and_replaces() {
+        let mut net = NetworkConfig::new("mynet", "10.88.0.0/24").unwrap();
+        let mut ep = net
+            .connect_with_aliases("b", "web", &["api".to_string(), "api".to_string()])
+            .unwrap();
+        assert_eq!(ep.aliases, vec!["api".to_string()]);
+
+        ep.set_aliases(&["www".to_string(), "web".to_string()]);
+
+        assert_eq!(ep.dns_names(), vec!["web".to_string(), "www".to_string()]);
+    }
+
     #[test]
     fn test_peer_endpoints_empty_when_alone() {
         let mut net = NetworkConfig::new("mynet", "10.88.0.0/24").unwrap();
@@ -902,6 +1109,46 @@ mod tests {
         assert_eq!(peers.len(), 2);
     }
 
+    #[test]
+    fn test_attach_external_endpoint() {
+        let mut net = NetworkConfig::new("cni-k8s", "10.244.1.0/24").unwrap();
+        let ep = net
+            .attach(
+                "sb-1",
+                "web",
+                Ipv4Addr::new(10, 244, 1, 7),
+                PathBuf::from("/var/run/netns/a3s-sb-1"),
+            )
+            .unwrap();
+        assert_eq!(ep.mac_address, "02:42:0a:f4:01:07");
+        assert_eq!(
+            ep.netns.as_deref(),
+            Some(std::path::Path::new("/var/run/netns/a3s-sb-1"))
+        );
+        // IPAM skips externally assigned addresses.
+        assert_eq!(
+            net.connect("box-2", "api").unwrap().ip_address,
+            Ipv4Addr::new(10, 244, 1, 2)
+        );
+
+        let netns = || PathBuf::from("/var/run/netns/x");
+        assert!(net
+            .attach("sb-2", "a", Ipv4Addr::new(10, 244, 1, 7), netns())
+            .is_err());
+        assert!(net
+            .attach("sb-2", "a", Ipv4Addr::new(10, 244, 2, 7), netns())
+            .is_err());
+        assert!(net
+            .attach("sb-2", "a", Ipv4Addr::new(10, 244, 1, 1), netns())
+            .is_err());
+        assert!(net
+            .attach("sb-2", "a", Ipv4Addr::new(10, 244, 1, 255), netns())
+            .is_err());
+        assert!(net
+            .attach("sb-1", "a", Ipv4Addr::new(10, 244, 1, 8), netns())
+            .is_err());
+    }
+
     // --- NetworkEndpoint tests ---
 
     #[test]
@@ -911,7 +1158,9 @@ mod tests {
             box_name: "web".to_string(),
             aliases: vec!["app".to_string()],
             ip_address: Ipv4Addr::new(10, 88, 0, 2),
+            ip6_address: Some("fd00::2".parse().unwrap()),
             mac_address: "02:42:0a:58:00:02".to_string(),
+            netns: None,
         };
 
         let json = serde_json::to_string(&ep).unwrap();
@@ -922,6 +1171,35 @@ mod tests {
         let legacy = r#"{"box_id":"x","box_name":"web","ip_address":"10.88.0.3","mac_address":"02:42:0a:58:00:03"}"#;
         let parsed_legacy: NetworkEndpoint = serde_json::from_str(legacy).unwrap();
         assert!(parsed_legacy.aliases.is_empty());
+        assert!(parsed_legacy.ip6_address.is_none());
+    }
+
+    #[test]
+    fn test_dual_stack_network_assigns_ipv6_addresses() {
+        let mut net = NetworkConfig::new("dual", "10.88.0.0/24").unwrap();
+        net.set_ipv6_subnet("fd00:a3b0::/64").unwrap();
+        assert_eq!(net.subnet6.as_deref(), Some("fd00:a3b0::/64"));
+        assert_eq!(net.gateway6, Some("fd00:a3b0::1".parse().unwrap()));
+        assert_eq!(net.ipv6_prefix_len(), Some(64));
+
+        let first = net.connect("box-1", "web").unwrap();
+        let second = net.connect("box-2", "db").unwrap();
+        assert_eq!(first.ip6_address, Some("fd00:a3b0::2".parse().unwrap()));
+        assert_eq!(second.ip6_address, Some("fd00:a3b0::3".parse().unwrap()));
+
+        let err = net.set_ipv6_subnet("fd00:b::/64").unwrap_err();
+        assert!(err.contains("while boxes are connected"));
+    }
+
+    #[test]
+    fn test_ipv4_only_network_has_no_ipv6() {
+        let mut net = NetworkConfig::new("v4", "10.88.0.0/24").unwrap();
+        let endpoint = net.connect("box-1", "web").unwrap();
+
+        assert!(net.ipv6_prefix_len().is_none());
+        assert!(endpoint.ip6_address.is_none());
+        let json = serde_json::to_string(&net).unwrap();
+        assert!(!json.contains("subnet6"));
     }
 
     // --- NetworkPolicy tests ---
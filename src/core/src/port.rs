@@ -1,8 +1,11 @@
 //! Port publishing validation.
 //!
-//! a3s-box currently supports Docker-style TCP port publishing in the
-//! `host_port:guest_port[/tcp]` form. Unsupported protocols and bind-specific
-//! host IPs are rejected before a box record is persisted or a VM boots.
+//! a3s-box supports Docker-style TCP port publishing in the
+//! `[host_ip:]host_port:guest_port[/tcp]` form, with IPv6 host addresses in
+//! brackets (`[::1]:8080:80`). Unsupported protocols, ranges, and shorthand
+//! forms are rejected before a box record is persisted or a VM boots.
+
+use std::net::IpAddr;
 
 /// Supported published-port protocol.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,6 +26,8 @@ impl PortProtocol {
 /// Parsed published-port mapping.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PortMapping {
+    /// Host address to bind; `None` publishes on every host address.
+    pub host_ip: Option<IpAddr>,
     /// Host-side port. `0` means host auto-assignment where supported.
     pub host_port: u16,
     /// Guest/container port.
@@ -37,9 +42,21 @@ impl PortMapping {
         parse_port_mapping(&format!("{host_port}:{guest_port}"))
     }
 
-    /// Convert to the normalized runtime `host:guest` format.
+    /// Convert to the normalized runtime `[host_ip:]host:guest` format.
     pub fn runtime_entry(&self) -> String {
-        format!("{}:{}", self.host_port, self.guest_port)
+        match self.host_ip {
+            Some(_) => format!("{}:{}", self.host_address(), self.guest_port),
+            None => format!("{}:{}", self.host_port, self.guest_port),
+        }
+    }
+
+    /// Host socket address as shown to users (`0.0.0.0:8080`, `[::1]:8080`).
+    pub fn host_address(&self) -> String {
+        match self.host_ip {
+            Some(IpAddr::V6(ip)) => format!("[{ip}]:{}", self.host_port),
+            Some(IpAddr::V4(ip)) => format!("{ip}:{}", self.host_port),
+            None => format!("0.0.0.0:{}", self.host_port),
+        }
     }
 }
 
@@ -80,10 +97,11 @@ pub fn parse_port_mapping(input: &str) -> Result<PortMapping, String> {
         ));
     }
 
-    let parts: Vec<&str> = port_part.split(':').collect();
+    let (host_ip, ports) = split_host_ip(input, port_part)?;
+    let parts: Vec<&str> = ports.split(':').collect();
     if parts.len() != 2 {
         return Err(format!(
-            "Invalid port mapping '{input}': expected host_port:guest_port[/tcp]; bind-specific host IPs, single-port shorthand, and port ranges are not supported"
+            "Invalid port mapping '{input}': expected [host_ip:]host_port:guest_port[/tcp]; single-port shorthand and port ranges are not supported"
         ));
     }
 
@@ -91,12 +109,42 @@ pub fn parse_port_mapping(input: &str) -> Result<PortMapping, String> {
     let guest_port = parse_port(input, parts[1], "guest", false)?;
 
     Ok(PortMapping {
+        host_ip,
         host_port,
         guest_port,
         protocol,
     })
 }
 
+/// Split an optional leading host address off `host_port:guest_port`.
+///
+/// IPv6 addresses must be bracketed (`[::1]:8080:80`) since their colons are
+/// otherwise ambiguous. Wildcard addresses (`0.0.0.0`, `::`) mean "every host
+/// address" and normalize to no host IP.
+fn split_host_ip<'a>(input: &str, port_part: &'a str) -> Result<(Option<IpAddr>, &'a str), String> {
+    let (address, ports) = if let Some(rest) = port_part.strip_prefix('[') {
+        let (address, ports) = rest.split_once("]:").ok_or_else(|| {
+            format!("Invalid port mapping '{input}': expected [ipv6]:host_port:guest_port")
+        })?;
+        let address = address.parse::<std::net::Ipv6Addr>().map_err(|_| {
+            format!("Invalid port mapping '{input}': '{address}' is not an IPv6 address")
+        })?;
+        (IpAddr::V6(address), ports)
+    } else {
+        if port_part.matches(':').count() != 2 {
+            return Ok((None, port_part));
+        }
+        let (address, ports) = port_part.split_once(':').unwrap_or_default();
+        let address = address.parse::<std::net::Ipv4Addr>().map_err(|_| {
+            format!(
+                "Invalid port mapping '{input}': host IP '{address}' must be an IPv4 address or a bracketed IPv6 address"
+            )
+        })?;
+        (IpAddr::V4(address), ports)
+    };
+    Ok(((!address.is_unspecified()).then_some(address), ports))
+}
+
 fn parse_port(input: &str, value: &str, label: &str, allow_zero: bool) -> Result<u16, String> {
     if value.is_empty() {
         return Err(format!(
@@ -173,10 +221,51 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_port_mapping_rejects_host_ip() {
-        let error = parse_port_mapping("127.0.0.1:8080:80").unwrap_err();
+    fn test_parse_port_mapping_host_ipv4() {
+        let mapping = parse_port_mapping("127.0.0.1:8080:80").unwrap();
+
+        assert_eq!(mapping.host_ip, Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(mapping.runtime_entry(), "127.0.0.1:8080:80");
+        assert_eq!(mapping.host_address(), "127.0.0.1:8080");
+    }
 
-        assert!(error.contains("bind-specific host IPs"));
+    #[test]
+    fn test_parse_port_mapping_host_ipv6() {
+        let mapping = parse_port_mapping("[::1]:8080:80/tcp").unwrap();
+
+        assert_eq!(mapping.host_ip, Some("::1".parse().unwrap()));
+        assert_eq!(mapping.host_port, 8080);
+        assert_eq!(mapping.guest_port, 80);
+        assert_eq!(mapping.runtime_entry(), "[::1]:8080:80");
+        assert_eq!(
+            parse_port_mapping(&mapping.runtime_entry()).unwrap(),
+            mapping
+        );
+    }
+
+    #[test]
+    fn test_parse_port_mapping_wildcard_host_ip_is_dropped() {
+        assert_eq!(
+            parse_port_mapping("0.0.0.0:8080:80")
+                .unwrap()
+                .runtime_entry(),
+            "8080:80"
+        );
+        assert_eq!(
+            parse_port_mapping("[::]:8080:80").unwrap().runtime_entry(),
+            "8080:80"
+        );
+    }
+
+    #[test]
+    fn test_parse_port_mapping_rejects_bad_host_ip() {
+        assert!(parse_port_mapping("localhost:8080:80")
+            .unwrap_err()
+            .contains("bracketed IPv6"));
+        assert!(parse_port_mapping("[10.0.0.1]:8080:80")
+            .unwrap_err()
+            .contains("not an IPv6 address"));
+        assert!(parse_port_mapping("::1:8080:80").is_err());
     }
 
     #[test]
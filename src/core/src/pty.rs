@@ -15,6 +15,9 @@ pub const PTY_VSOCK_PORT: u32 = a3s_transport::ports::PTY_SERVER;
 /// Maximum frame payload size: 64 KiB.
 pub const MAX_FRAME_PAYLOAD: usize = 64 * 1024;
 
+/// Session name `run -it` gives the box's interactive main process.
+pub const MAIN_PTY_SESSION: &str = "main";
+
 /// Frame type: PTY session request (host → guest).
 pub const FRAME_PTY_REQUEST: u8 = 0x01;
 /// Frame type: terminal data (bidirectional).
@@ -51,6 +54,15 @@ pub struct PtyRequest {
     pub cols: u16,
     /// Terminal height in rows.
     pub rows: u16,
+    /// Name under which the guest parks the session when the host
+    /// disconnects before the process exits, so a later request can resume
+    /// it. Unnamed sessions are killed on disconnect.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// Resume the parked session named `session` if there is one; otherwise
+    /// start `cmd` as an unnamed session.
+    #[serde(default)]
+    pub resume: bool,
 }
 
 /// Terminal resize notification.
@@ -229,6 +241,8 @@ mod tests {
             user: None,
             cols: 80,
             rows: 24,
+            session: None,
+            resume: false,
         };
 
         let mut buf = Vec::new();
@@ -249,6 +263,24 @@ mod tests {
         );
         assert_eq!(parsed.cols, 80);
         assert_eq!(parsed.rows, 24);
+        assert_eq!(parsed.session, None);
+        assert!(!parsed.resume);
+    }
+
+    #[test]
+    fn test_request_session_fields_default_when_absent() {
+        let parsed: PtyRequest =
+            serde_json::from_str(r#"{"cmd":["/bin/sh"],"cols":80,"rows":24}"#).unwrap();
+        assert_eq!(parsed.session, None);
+        assert!(!parsed.resume);
+
+        let named = PtyRequest {
+            session: Some(MAIN_PTY_SESSION.to_string()),
+            resume: true,
+            ..parsed
+        };
+        let json = serde_json::to_string(&named).unwrap();
+        assert!(json.contains(r#""session":"main""#), "{json}");
     }
 
     #[test]
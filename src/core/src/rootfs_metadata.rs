@@ -63,6 +63,8 @@ pub fn is_runtime_internal_rootfs_path(path: &Path) -> bool {
         ".a3s_exit_code",
         ".a3s_host_live_logs_drained",
         ".a3s_host_result_collected",
+        // Entropy seed a guest that failed to boot never consumed.
+        ".a3s-box-entropy-seed",
         // Written by libkrun around PID 1 startup and exit. These files can
         // change after guest-init captures terminal metadata.
         "init-rust.log",
@@ -325,7 +327,7 @@ pub fn runtime_managed_rootfs_mode(path: &Path) -> Option<u32> {
     match path.to_str() {
         Some("etc/hostname" | "etc/hosts" | "etc/resolv.conf") => Some(0o644),
         Some("sbin/init" | "usr/sbin/init") => Some(0o755),
-        Some(".a3s-box-env") => Some(0o600),
+        Some(".a3s-box-env" | ".a3s-box-entropy-seed") => Some(0o600),
         Some(path) if path == RUNTIME_EXEC_CONFIG_PATH.trim_start_matches('/') => Some(0o600),
         _ => None,
     }
@@ -411,6 +413,7 @@ mod tests {
             ".a3s_exit_code",
             ".a3s_host_live_logs_drained",
             ".a3s-box-exec.json",
+            ".a3s-box-entropy-seed",
             "guest-init.stdout.log",
             "init-rust.log",
             "init.krun.log",
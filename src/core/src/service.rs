@@ -0,0 +1,319 @@
+//! Guest services supervised by guest init.
+//!
+//! Besides the container main, a box can declare extra long-running services
+//! (sidecar-style workloads such as a log shipper or a local proxy). The host
+//! passes the list to guest init as JSON in [`SERVICES_ENV`]; guest init starts
+//! them in dependency order, restarts them per their [`ServiceRestartPolicy`]
+//! with exponential backoff, runs their health checks, and streams every
+//! state change back over the exec channel as a [`ServiceStatus`].
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Guest-init environment variable carrying the JSON service list.
+pub const SERVICES_ENV: &str = "BOX_SERVICES";
+
+/// Delay before the first restart of a crashed service.
+pub const SERVICE_RESTART_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Longest delay between restarts of a crash-looping service.
+pub const SERVICE_RESTART_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// A service that stays up this long resets its backoff.
+pub const SERVICE_STABLE_AFTER: Duration = Duration::from_secs(10);
+
+/// One supervised service.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceSpec {
+    /// Unique name, used in dependencies and status reports.
+    pub name: String,
+    /// Program and arguments, resolved against the container `PATH`.
+    pub command: Vec<String>,
+    /// Extra `KEY=VALUE` environment entries on top of the container's.
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Working directory; the container's when unset.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// What to do when the service exits.
+    #[serde(default)]
+    pub restart: ServiceRestartPolicy,
+    /// Services that must be running (and healthy, if they have a health
+    /// check) before this one starts.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Command whose success marks the service healthy.
+    #[serde(default)]
+    pub health_check: Option<ServiceHealthCheck>,
+}
+
+/// When guest init restarts an exited service.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceRestartPolicy {
+    /// Leave the service stopped.
+    Never,
+    /// Restart after a non-zero exit or a signal.
+    #[default]
+    OnFailure,
+    /// Restart after every exit.
+    Always,
+}
+
+impl ServiceRestartPolicy {
+    /// Whether a service that exited with `exit_code` is started again.
+    pub fn should_restart(self, exit_code: i32) -> bool {
+        match self {
+            Self::Never => false,
+            Self::OnFailure => exit_code != 0,
+            Self::Always => true,
+        }
+    }
+}
+
+/// Health check run inside the guest while a service is up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceHealthCheck {
+    /// Program and arguments; exit status 0 means healthy.
+    pub command: Vec<String>,
+    /// Time between checks.
+    #[serde(default = "default_health_interval_ms")]
+    pub interval_ms: u64,
+    /// A check running longer than this counts as failed.
+    #[serde(default = "default_health_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Consecutive failures before the service is marked unhealthy.
+    #[serde(default = "default_health_retries")]
+    pub retries: u32,
+}
+
+fn default_health_interval_ms() -> u64 {
+    10_000
+}
+
+fn default_health_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_health_retries() -> u32 {
+    3
+}
+
+/// Lifecycle state of a supervised service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceState {
+    /// Waiting for its dependencies.
+    Waiting,
+    /// Running; without a health check this is as far as it gets.
+    Running,
+    /// Running and its health check passes.
+    Healthy,
+    /// Running but its health check keeps failing.
+    Unhealthy,
+    /// Exited and will be started again after a backoff delay.
+    Restarting,
+    /// Exited and its restart policy leaves it stopped.
+    Exited,
+    /// Could not be started at all.
+    Failed,
+}
+
+impl ServiceState {
+    /// Whether services depending on this one may start.
+    pub fn satisfies_dependency(self, has_health_check: bool) -> bool {
+        match self {
+            Self::Healthy => true,
+            Self::Running => !has_health_check,
+            _ => false,
+        }
+    }
+}
+
+/// State of one service, as streamed to the host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub name: String,
+    pub state: ServiceState,
+    /// Guest PID while the service runs.
+    #[serde(default)]
+    pub pid: Option<u32>,
+    /// Times the service has been restarted.
+    #[serde(default)]
+    pub restarts: u32,
+    /// Exit status of the last run; `128 + signal` when killed.
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Why the service failed to start or was marked unhealthy.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Request to stream service states over the exec channel.
+///
+/// The guest answers with one data frame per service holding its current
+/// [`ServiceStatus`], then, when `follow` is set, one frame per state change
+/// until the host closes the stream.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceEventsRequest {
+    #[serde(default)]
+    pub follow: bool,
+}
+
+/// Delay before restart number `restarts` (zero-based) of a crashed service.
+pub fn restart_delay(restarts: u32) -> Duration {
+    SERVICE_RESTART_BASE_DELAY
+        .saturating_mul(1u32.checked_shl(restarts).unwrap_or(u32::MAX))
+        .min(SERVICE_RESTART_MAX_DELAY)
+}
+
+/// Validate a service list and return the indices in start order, each
+/// service after everything it depends on.
+pub fn service_start_order(services: &[ServiceSpec]) -> Result<Vec<usize>, String> {
+    let mut index = HashMap::new();
+    for (position, service) in services.iter().enumerate() {
+        if service.name.is_empty()
+            || !service
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(format!("Invalid service name {:?}", service.name));
+        }
+        if service.command.is_empty() {
+            return Err(format!("Service {} has no command", service.name));
+        }
+        if let Some(check) = &service.health_check {
+            if check.command.is_empty() {
+                return Err(format!(
+                    "Service {} has a health check without a command",
+                    service.name
+                ));
+            }
+        }
+        if index.insert(service.name.as_str(), position).is_some() {
+            return Err(format!("Duplicate service {}", service.name));
+        }
+    }
+    for service in services {
+        if let Some(missing) = service
+            .depends_on
+            .iter()
+            .find(|dependency| !index.contains_key(dependency.as_str()))
+        {
+            return Err(format!(
+                "Service {} depends on unknown service {missing}",
+                service.name
+            ));
+        }
+    }
+
+    // Depth-first topological sort; `visiting` holds the current path.
+    fn visit(
+        position: usize,
+        services: &[ServiceSpec],
+        index: &HashMap<&str, usize>,
+        visiting: &mut HashSet<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), String> {
+        if order.contains(&position) {
+            return Ok(());
+        }
+        if !visiting.insert(position) {
+            return Err(format!(
+                "Service dependency cycle through {}",
+                services[position].name
+            ));
+        }
+        for dependency in &services[position].depends_on {
+            visit(index[dependency.as_str()], services, index, visiting, order)?;
+        }
+        visiting.remove(&position);
+        order.push(position);
+        Ok(())
+    }
+
+    let mut order = Vec::with_capacity(services.len());
+    let mut visiting = HashSet::new();
+    for position in 0..services.len() {
+        visit(position, services, &index, &mut visiting, &mut order)?;
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str, depends_on: &[&str]) -> ServiceSpec {
+        ServiceSpec {
+            name: name.to_string(),
+            command: vec!["/bin/true".to_string()],
+            env: Vec::new(),
+            working_dir: None,
+            restart: ServiceRestartPolicy::default(),
+            depends_on: depends_on.iter().map(|name| name.to_string()).collect(),
+            health_check: None,
+        }
+    }
+
+    #[test]
+    fn test_start_order_puts_dependencies_first() {
+        let services = [
+            service("app", &["db", "cache"]),
+            service("cache", &[]),
+            service("db", &["cache"]),
+        ];
+        assert_eq!(service_start_order(&services).unwrap(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_start_order_rejects_invalid_lists() {
+        let cycle = [service("a", &["b"]), service("b", &["a"])];
+        assert!(service_start_order(&cycle).unwrap_err().contains("cycle"));
+        let unknown = [service("a", &["missing"])];
+        assert!(service_start_order(&unknown)
+            .unwrap_err()
+            .contains("unknown service missing"));
+        let duplicate = [service("a", &[]), service("a", &[])];
+        assert!(service_start_order(&duplicate)
+            .unwrap_err()
+            .contains("Duplicate"));
+        assert!(service_start_order(&[service("bad name", &[])]).is_err());
+        let mut empty = service("a", &[]);
+        empty.command.clear();
+        assert!(service_start_order(&[empty]).is_err());
+    }
+
+    #[test]
+    fn test_restart_policy_and_backoff() {
+        assert!(!ServiceRestartPolicy::Never.should_restart(1));
+        assert!(!ServiceRestartPolicy::OnFailure.should_restart(0));
+        assert!(ServiceRestartPolicy::OnFailure.should_restart(137));
+        assert!(ServiceRestartPolicy::Always.should_restart(0));
+
+        assert_eq!(restart_delay(0), Duration::from_millis(200));
+        assert_eq!(restart_delay(3), Duration::from_millis(1600));
+        assert_eq!(restart_delay(40), SERVICE_RESTART_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_service_spec_json_defaults() {
+        let services: Vec<ServiceSpec> = serde_json::from_str(
+            r#"[{"name":"proxy","command":["envoy"],"restart":"always",
+                "health_check":{"command":["curl","-f","localhost:9901/ready"]}}]"#,
+        )
+        .unwrap();
+        assert_eq!(services[0].restart, ServiceRestartPolicy::Always);
+        assert!(services[0].depends_on.is_empty());
+        let check = services[0].health_check.as_ref().unwrap();
+        assert_eq!(
+            (check.interval_ms, check.timeout_ms, check.retries),
+            (10_000, 5_000, 3)
+        );
+        assert!(ServiceState::Running.satisfies_dependency(false));
+        assert!(!ServiceState::Running.satisfies_dependency(true));
+    }
+}
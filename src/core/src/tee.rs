@@ -17,6 +17,111 @@ use serde::{Deserialize, Serialize};
 /// Vsock port for the attestation server.
 pub const ATTEST_VSOCK_PORT: u32 = a3s_transport::ports::TEE_CHANNEL;
 
+/// Env var carrying a TEE box's runtime-claims digest (64 hex characters) to
+/// guest init, which places it in the REPORT_DATA of its attestation reports.
+pub const RUNTIME_CLAIMS_ENV: &str = "A3S_TEE_RUNTIME_CLAIMS";
+
+/// Where the runtime-claims digest sits in REPORT_DATA: after the 32-byte
+/// RA-TLS public key hash.
+pub const RUNTIME_CLAIMS_REPORT_DATA_RANGE: std::ops::Range<usize> = 32..64;
+
+/// Decode a runtime-claims digest from its hex form.
+pub fn decode_runtime_claims_digest(value: &str) -> Option<[u8; 32]> {
+    let value = value.as_bytes();
+    if value.len() != 64 {
+        return None;
+    }
+    let nibble = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(value.chunks_exact(2)) {
+        *byte = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Some(digest)
+}
+
+/// Env var carrying a TEE box's measured rootfs ([`VerityRootfs`]) to guest
+/// init, which opens it with dm-verity instead of mounting the virtio-fs root.
+pub const VERITY_ROOTFS_ENV: &str = "A3S_TEE_VERITY_ROOTFS";
+
+/// virtio-blk serial of the measured rootfs data image.
+pub const VERITY_DATA_BLOCK_ID: &str = "a3s-rootfs";
+
+/// virtio-blk serial of the measured rootfs hash tree.
+pub const VERITY_HASH_BLOCK_ID: &str = "a3s-rootfs-hash";
+
+/// Block size of measured rootfs images and their hash trees.
+pub const VERITY_BLOCK_SIZE: u64 = 4096;
+
+/// A read-only rootfs image protected by a dm-verity hash tree.
+///
+/// The tree is SHA-256 without a salt or superblock, so the root hash
+/// depends only on the image contents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerityRootfs {
+    /// Hex-encoded root hash of the tree.
+    pub root_hash: String,
+    /// Number of [`VERITY_BLOCK_SIZE`] blocks in the data image.
+    pub data_blocks: u64,
+}
+
+impl VerityRootfs {
+    /// Encode for [`VERITY_ROOTFS_ENV`].
+    pub fn to_env_value(&self) -> String {
+        format!("{}:{}", self.root_hash, self.data_blocks)
+    }
+
+    /// Decode a [`VERITY_ROOTFS_ENV`] value produced by [`Self::to_env_value`].
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid measured rootfs '{value}'");
+        let (root_hash, data_blocks) = value.split_once(':').ok_or_else(invalid)?;
+        if decode_runtime_claims_digest(root_hash).is_none() {
+            return Err(invalid());
+        }
+        let data_blocks = data_blocks.parse::<u64>().map_err(|_| invalid())?;
+        if data_blocks == 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            root_hash: root_hash.to_ascii_lowercase(),
+            data_blocks,
+        })
+    }
+}
+
+/// Fold a measured rootfs root hash into a runtime-claims digest.
+///
+/// The runtime hands guest init the digest of the other claims, and guest
+/// init binds the root hash it actually opened, so a host cannot attest one
+/// rootfs while booting another.
+pub fn bind_verity_root_hash(claims_digest: &[u8; 32], root_hash: &str) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"a3s-box/runtime-claims/verity-rootfs/v1");
+    hasher.update([0]);
+    hasher.update(claims_digest);
+    hasher.update(root_hash.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Env var telling guest init that `/workspace` is an encrypted scratch disk
+/// whose key arrives over RA-TLS ([`AttestRoute::WorkspaceKey`]) instead of
+/// the virtio-fs workspace share.
+pub const ENCRYPTED_WORKSPACE_ENV: &str = "A3S_TEE_ENCRYPTED_WORKSPACE";
+
+/// virtio-blk serial of the encrypted workspace disk.
+pub const ENCRYPTED_WORKSPACE_BLOCK_ID: &str = "a3s-workspace";
+
+/// Byte offset of the encrypted data in the workspace disk; the LUKS2 header
+/// occupies everything before it.
+pub const ENCRYPTED_WORKSPACE_DATA_OFFSET: u64 = 16 * 1024 * 1024;
+
+/// dm-crypt cipher of the encrypted workspace.
+pub const ENCRYPTED_WORKSPACE_CIPHER: &str = "aes-xts-plain64";
+
+/// Volume key length of the encrypted workspace (AES-256 in XTS mode).
+pub const ENCRYPTED_WORKSPACE_KEY_LEN: usize = 64;
+
 // ---------------------------------------------------------------------------
 // TEE self-detection API
 // ---------------------------------------------------------------------------
@@ -122,12 +227,97 @@ pub enum AttestRoute {
     Unseal,
     /// Forward a message to the local agent for processing.
     Process,
+    /// Answer a KBS challenge with fresh evidence ([`KbsEvidenceRequest`]).
+    KbsEvidence,
+    /// Decrypt and store a KBS resource ([`KbsResourceRequest`]).
+    KbsResource,
+    /// Answer a verifier's nonce with a fresh report ([`AttestChallenge`]).
+    Challenge,
+    /// Open and mount the encrypted workspace ([`WorkspaceKeyRequest`]).
+    WorkspaceKey,
+}
+
+/// Length of an [`AttestChallenge`] nonce.
+pub const CHALLENGE_NONCE_LEN: usize = 32;
+
+/// Payload of [`AttestRoute::Challenge`].
+///
+/// The guest answers with a report generated for this request, carrying the
+/// nonce in REPORT_DATA[0..32] and its runtime claims (if any) after it, so
+/// the report cannot be a replay of the RA-TLS certificate's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestChallenge {
+    /// [`CHALLENGE_NONCE_LEN`] random bytes chosen by the verifier.
+    pub nonce: Vec<u8>,
+}
+
+/// Payload of [`AttestRoute::KbsEvidence`]: the nonce of a KBS challenge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KbsEvidenceRequest {
+    pub nonce: String,
+}
+
+/// Guest answer to a KBS challenge, sent to the KBS as-is as the body of
+/// `POST /kbs/v0/attest`.
+///
+/// `tee-pubkey` is a fresh key held only by the guest; the KBS encrypts
+/// resources to it. The evidence's REPORT_DATA binds it to the challenge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KbsEvidence {
+    #[serde(rename = "tee-pubkey")]
+    pub tee_pubkey: serde_json::Value,
+    #[serde(rename = "tee-evidence")]
+    pub tee_evidence: String,
+}
+
+/// Payload of [`AttestRoute::KbsResource`]: a KBS resource response (JWE)
+/// for the guest to decrypt with the key from its last [`KbsEvidence`] and
+/// store as the secret `name`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KbsResourceRequest {
+    pub name: String,
+    #[serde(default)]
+    pub set_env: bool,
+    pub response: serde_json::Value,
+}
+
+/// Payload of [`AttestRoute::WorkspaceKey`]: the volume key of the encrypted
+/// workspace, sent only over an attested RA-TLS session.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WorkspaceKeyRequest {
+    /// [`ENCRYPTED_WORKSPACE_KEY_LEN`] bytes.
+    pub volume_key: Vec<u8>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_decode_runtime_claims_digest() {
+        let digest = decode_runtime_claims_digest(&"0aF1".repeat(16)).unwrap();
+        assert_eq!(&digest[..2], &[0x0a, 0xf1]);
+        assert!(decode_runtime_claims_digest("0a").is_none());
+        assert!(decode_runtime_claims_digest(&"zz".repeat(32)).is_none());
+    }
+
+    #[test]
+    fn test_verity_rootfs_env_roundtrip_and_binding() {
+        let rootfs = VerityRootfs {
+            root_hash: "ab".repeat(32),
+            data_blocks: 4096,
+        };
+        let value = rootfs.to_env_value();
+        assert_eq!(VerityRootfs::from_env_value(&value).unwrap(), rootfs);
+        assert!(VerityRootfs::from_env_value("abab:4096").is_err());
+        assert!(VerityRootfs::from_env_value(&format!("{}:0", "ab".repeat(32))).is_err());
+
+        let claims = [7u8; 32];
+        let bound = bind_verity_root_hash(&claims, &rootfs.root_hash);
+        assert_ne!(bound, claims);
+        assert_ne!(bound, bind_verity_root_hash(&claims, &"cd".repeat(32)));
+    }
+
     // -- TEE detection tests --
 
     #[test]
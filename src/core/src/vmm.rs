@@ -9,7 +9,8 @@
 //! - [`VmmProvider`] — start VMs from an [`InstanceSpec`]
 //! - [`VmHandler`] — lifecycle operations on a running VM
 
-use std::net::Ipv4Addr;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
 #[cfg(target_os = "macos")]
 use std::os::fd::RawFd;
 use std::path::PathBuf;
@@ -90,6 +91,18 @@ pub struct NetworkInstanceConfig {
     /// MAC address as 6 bytes.
     pub mac_address: [u8; 6],
 
+    /// Assigned IPv6 address on a dual-stack network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipv6_address: Option<Ipv6Addr>,
+
+    /// IPv6 gateway address on a dual-stack network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipv6_gateway: Option<Ipv6Addr>,
+
+    /// IPv6 subnet prefix length (e.g., 64); unused without `ipv6_address`.
+    #[serde(default)]
+    pub ipv6_prefix_len: u8,
+
     /// DNS servers to configure inside the guest.
     #[serde(default)]
     pub dns_servers: Vec<Ipv4Addr>,
@@ -128,17 +141,52 @@ pub struct InstanceSpec {
     #[serde(default)]
     pub port_forward_socket_path: PathBuf,
 
+    /// Path to the Unix socket bridged to the guest forwarder
+    /// (`a3s-box forward`); empty disables it.
+    #[serde(default)]
+    pub forward_socket_path: PathBuf,
+
     /// Filesystem mounts (virtio-fs shares)
     pub fs_mounts: Vec<FsMount>,
 
+    /// Raw block devices / disk images attached as virtio-blk disks.
+    #[serde(default)]
+    pub block_devices: Vec<crate::volume::BlockVolume>,
+
+    /// Host Unix sockets shared into the guest; the `n`th is bridged to
+    /// vsock port [`crate::volume::socket_mount_vsock_port`]`(n)`.
+    #[serde(default)]
+    pub socket_mounts: Vec<crate::volume::SocketMount>,
+
+    /// Host character devices proxied into the guest; the `n`th is served on
+    /// [`crate::device::device_proxy_socket_file`]`(n)` next to the exec
+    /// socket and bridged to vsock port
+    /// [`crate::device::device_proxy_vsock_port`]`(n)`.
+    #[serde(default)]
+    pub device_proxies: Vec<crate::device::ProxiedDevice>,
+
     /// Guest agent entrypoint
     pub entrypoint: Entrypoint,
 
+    /// Guest kernel image to boot; `None` boots the libkrunfw kernel. The
+    /// shim reads it from `A3S_BOX_KERNEL`.
+    #[serde(default)]
+    pub kernel_path: Option<PathBuf>,
+
     /// Mark guest memory KSM-mergeable (host page dedup across same-image VMs;
     /// Linux 6.4+, requires /sys/kernel/mm/ksm/run=1 on the host).
     #[serde(default)]
     pub ksm: bool,
 
+    /// Back guest RAM with transparent hugepages (Linux; the shim advises
+    /// libkrun's guest memory `MADV_HUGEPAGE`).
+    #[serde(default)]
+    pub hugepages: bool,
+
+    /// Prefault all guest RAM at boot (`MADV_POPULATE_WRITE`, Linux 5.14+).
+    #[serde(default)]
+    pub mem_prealloc: bool,
+
     /// Snapshot-fork (per-VM): file-backed guest RAM path. When set (with
     /// `snapshot_sock`), this VM boots as a snapshot TEMPLATE — guest RAM is
     /// file-backed so it can be snapshotted on demand.
@@ -189,6 +237,20 @@ pub struct InstanceSpec {
     /// lifetime (so detached `run -d` logs aren't truncated when the CLI exits).
     #[serde(default)]
     pub log_config: crate::log::LogConfig,
+
+    /// HTTP(S) forward proxy the shim serves for the box's lifetime.
+    #[serde(default)]
+    pub http_proxy: Option<crate::http_proxy::HttpProxyInstanceConfig>,
+
+    /// Guest kernel sysctls (name → value); guest init applies them at boot
+    /// from the `BOX_SYSCTL_*` entries of the entrypoint environment.
+    #[serde(default)]
+    pub sysctls: HashMap<String, String>,
+
+    /// Guest kernel modules; guest init loads them at boot, before the
+    /// sysctls, from the `BOX_KERNEL_MODULE_*` entries.
+    #[serde(default)]
+    pub modules: Vec<String>,
 }
 
 impl Default for InstanceSpec {
@@ -202,13 +264,20 @@ impl Default for InstanceSpec {
             pty_socket_path: PathBuf::new(),
             attest_socket_path: PathBuf::new(),
             port_forward_socket_path: PathBuf::new(),
+            forward_socket_path: PathBuf::new(),
             fs_mounts: Vec::new(),
+            block_devices: Vec::new(),
+            socket_mounts: Vec::new(),
+            device_proxies: Vec::new(),
             entrypoint: Entrypoint {
                 executable: String::new(),
                 args: Vec::new(),
                 env: Vec::new(),
             },
+            kernel_path: None,
             ksm: false,
+            hugepages: false,
+            mem_prealloc: false,
             snapshot_mem_file: None,
             snapshot_sock: None,
             restore_from: None,
@@ -220,6 +289,9 @@ impl Default for InstanceSpec {
             network: None,
             resource_limits: ResourceLimits::default(),
             log_config: crate::log::LogConfig::default(),
+            http_proxy: None,
+            sysctls: HashMap::new(),
+            modules: Vec::new(),
         }
     }
 }
@@ -432,7 +504,10 @@ mod tests {
     fn test_instance_spec_serde_roundtrip() {
         let spec = InstanceSpec {
             box_id: "test-box-123".to_string(),
+            kernel_path: None,
             ksm: false,
+            hugepages: false,
+            mem_prealloc: false,
             snapshot_mem_file: None,
             snapshot_sock: None,
             restore_from: None,
@@ -443,11 +518,22 @@ mod tests {
             pty_socket_path: PathBuf::from("/tmp/pty.sock"),
             attest_socket_path: PathBuf::from("/tmp/attest.sock"),
             port_forward_socket_path: PathBuf::from("/tmp/portfwd.sock"),
+            forward_socket_path: PathBuf::from("/tmp/forward.sock"),
             fs_mounts: vec![FsMount {
                 tag: "workspace".to_string(),
                 host_path: PathBuf::from("/home/user/project"),
                 read_only: false,
             }],
+            block_devices: vec![],
+            socket_mounts: vec![crate::volume::SocketMount {
+                source: "/run/user/1000/ssh-agent.sock".to_string(),
+                target: "/ssh-agent".to_string(),
+            }],
+            device_proxies: vec![crate::device::ProxiedDevice {
+                source: "/dev/ttyUSB0".to_string(),
+                target: "/dev/ttyUSB0".to_string(),
+                read_only: false,
+            }],
             entrypoint: Entrypoint {
                 executable: "/usr/bin/agent".to_string(),
                 args: vec!["--port".to_string(), "8080".to_string()],
@@ -461,12 +547,17 @@ mod tests {
             network: None,
             resource_limits: ResourceLimits::default(),
             log_config: crate::log::LogConfig::default(),
+            http_proxy: None,
+            sysctls: HashMap::from([("net.core.somaxconn".to_string(), "4096".to_string())]),
+            modules: vec!["br_netfilter".to_string()],
         };
 
         let json = serde_json::to_string(&spec).unwrap();
         let deserialized: InstanceSpec = serde_json::from_str(&json).unwrap();
 
         assert_eq!(deserialized.box_id, "test-box-123");
+        assert_eq!(deserialized.sysctls["net.core.somaxconn"], "4096");
+        assert_eq!(deserialized.modules, vec!["br_netfilter"]);
         assert_eq!(deserialized.vcpus, 4);
         assert_eq!(deserialized.memory_mib, 2048);
         assert_eq!(deserialized.workdir, "/app");
@@ -480,6 +571,12 @@ mod tests {
             deserialized.port_forward_socket_path,
             PathBuf::from("/tmp/portfwd.sock")
         );
+        assert_eq!(
+            deserialized.forward_socket_path,
+            PathBuf::from("/tmp/forward.sock")
+        );
+        assert_eq!(deserialized.socket_mounts, spec.socket_mounts);
+        assert_eq!(deserialized.device_proxies, spec.device_proxies);
         assert_eq!(deserialized.port_map, vec!["8080:80"]);
         assert_eq!(deserialized.user, Some("1000:1000".to_string()));
     }
@@ -518,6 +615,9 @@ mod tests {
                 gateway: "10.0.0.1".parse().unwrap(),
                 prefix_len: 24,
                 mac_address: [0x02, 0x42, 0xac, 0x11, 0x00, 0x02],
+                ipv6_address: Some("fd00::2".parse().unwrap()),
+                ipv6_gateway: Some("fd00::1".parse().unwrap()),
+                ipv6_prefix_len: 64,
                 dns_servers: vec!["8.8.8.8".parse().unwrap()],
             }),
             ..Default::default()
@@ -538,6 +638,11 @@ mod tests {
         assert_eq!(net.ip_address, "10.0.0.2".parse::<Ipv4Addr>().unwrap());
         assert_eq!(net.gateway, "10.0.0.1".parse::<Ipv4Addr>().unwrap());
         assert_eq!(net.prefix_len, 24);
+        assert_eq!(
+            net.ipv6_address,
+            Some("fd00::2".parse::<Ipv6Addr>().unwrap())
+        );
+        assert_eq!(net.ipv6_prefix_len, 64);
         assert_eq!(net.dns_servers.len(), 1);
     }
 
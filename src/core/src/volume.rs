@@ -12,7 +12,7 @@ pub struct VolumeConfig {
     /// Volume name (unique identifier).
     pub name: String,
 
-    /// Volume driver (currently only "local" is supported).
+    /// Volume driver: "local", "tmpfs", "nfs", or "block".
     #[serde(default = "default_driver")]
     pub driver: String,
 
@@ -23,6 +23,10 @@ pub struct VolumeConfig {
     #[serde(default)]
     pub labels: HashMap<String, String>,
 
+    /// Driver options given at creation (`--opt KEY=VALUE`).
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+
     /// Box IDs currently using this volume.
     #[serde(default)]
     pub in_use_by: Vec<String>,
@@ -47,6 +51,7 @@ impl VolumeConfig {
             driver: "local".to_string(),
             mount_point: mount_point.to_string(),
             labels: HashMap::new(),
+            options: HashMap::new(),
             in_use_by: Vec::new(),
             size_limit: 0,
             created_at: chrono::Utc::now().to_rfc3339(),
@@ -101,6 +106,99 @@ impl VolumeConfig {
     }
 }
 
+/// Environment prefix carrying block volumes to guest init.
+///
+/// Format: `BOX_BLOCK_<index>=<block_id>:<fstype>:<ro|rw>:<target>`.
+pub const BLOCK_VOLUME_ENV_PREFIX: &str = "BOX_BLOCK_";
+
+/// A raw block device or disk image attached to a box as a virtio-blk disk
+/// and mounted by guest init (the `block` volume driver).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockVolume {
+    /// virtio-blk device ID; guest init finds the disk by this serial.
+    pub block_id: String,
+    /// Host block device or disk image.
+    pub source: String,
+    /// Absolute mount path inside the guest.
+    pub target: String,
+    /// Filesystem on the device.
+    pub fstype: String,
+    /// Attach and mount read-only.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl BlockVolume {
+    /// Encode for the `BOX_BLOCK_<n>` environment channel.
+    pub fn to_env_value(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.block_id,
+            self.fstype,
+            if self.read_only { "ro" } else { "rw" },
+            self.target
+        )
+    }
+
+    /// Decode a `BOX_BLOCK_<n>` value produced by [`Self::to_env_value`].
+    ///
+    /// The host source path is not carried to the guest and comes back empty.
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        let mut parts = value.splitn(4, ':');
+        let (Some(block_id), Some(fstype), Some(mode), Some(target)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!("Invalid block volume entry '{value}'"));
+        };
+        let read_only = match mode {
+            "ro" => true,
+            "rw" => false,
+            _ => return Err(format!("Invalid block volume mode in '{value}'")),
+        };
+        if block_id.is_empty() || fstype.is_empty() || !target.starts_with('/') {
+            return Err(format!("Invalid block volume entry '{value}'"));
+        }
+        Ok(Self {
+            block_id: block_id.to_string(),
+            source: String::new(),
+            target: target.to_string(),
+            fstype: fstype.to_string(),
+            read_only,
+        })
+    }
+}
+
+/// Environment prefix carrying shared host sockets to guest init.
+///
+/// Format: `BOX_SOCKET_<index>=<target>`; the socket is relayed over vsock
+/// port [`socket_mount_vsock_port`]`(index)`.
+pub const SOCKET_MOUNT_ENV_PREFIX: &str = "BOX_SOCKET_";
+
+/// Vsock port of the first shared socket.
+pub const SOCKET_MOUNT_VSOCK_PORT_BASE: u32 = 4100;
+
+/// Most shared sockets one box may have.
+pub const MAX_SOCKET_MOUNTS: usize = 32;
+
+/// Vsock port carrying the `index`th shared socket.
+pub fn socket_mount_vsock_port(index: usize) -> u32 {
+    SOCKET_MOUNT_VSOCK_PORT_BASE + index as u32
+}
+
+/// A host Unix socket shared into the guest (`--mount type=socket`), such as
+/// an SSH agent or a Docker daemon socket.
+///
+/// Guest init listens at `target` and relays each connection over vsock;
+/// libkrun connects the other end to `source` on the host, so nothing else
+/// of the host filesystem is exposed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SocketMount {
+    /// Host Unix socket.
+    pub source: String,
+    /// Absolute socket path inside the guest.
+    pub target: String,
+}
+
 /// Recursively calculate directory size in bytes.
 fn dir_size(path: &std::path::Path) -> u64 {
     let mut total = 0u64;
@@ -121,6 +219,17 @@ fn dir_size(path: &std::path::Path) -> u64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_socket_mount_vsock_ports_follow_index() {
+        assert_eq!(socket_mount_vsock_port(0), SOCKET_MOUNT_VSOCK_PORT_BASE);
+        assert_eq!(socket_mount_vsock_port(2), 4102);
+        // The range must stay clear of the fixed control ports.
+        assert!(
+            socket_mount_vsock_port(0) > crate::forward::FORWARD_VSOCK_PORT,
+            "socket mounts overlap the control ports"
+        );
+    }
+
     #[test]
     fn test_volume_config_new() {
         let vol = VolumeConfig::new("mydata", "/home/user/.a3s/volumes/mydata");
@@ -182,6 +291,26 @@ mod tests {
         assert_eq!(parsed.labels.get("env").unwrap(), "test");
     }
 
+    #[test]
+    fn test_block_volume_env_roundtrip() {
+        let volume = BlockVolume {
+            block_id: "blk0".to_string(),
+            source: "/dev/sdb".to_string(),
+            target: "/data:cache".to_string(),
+            fstype: "ext4".to_string(),
+            read_only: true,
+        };
+        let value = volume.to_env_value();
+        assert_eq!(value, "blk0:ext4:ro:/data:cache");
+
+        let parsed = BlockVolume::from_env_value(&value).unwrap();
+        assert_eq!(parsed.block_id, "blk0");
+        assert_eq!(parsed.target, "/data:cache");
+        assert!(parsed.read_only);
+        assert!(BlockVolume::from_env_value("blk0:ext4:rx:/data").is_err());
+        assert!(BlockVolume::from_env_value("blk0:ext4:rw:data").is_err());
+    }
+
     #[test]
     fn test_volume_default_driver() {
         let json = r#"{"name":"test","mount_point":"/tmp","created_at":"2024-01-01T00:00:00Z"}"#;
@@ -0,0 +1,143 @@
+//! Workspace templates (`--workspace-template`).
+//!
+//! A template is a local directory or a git repository whose files seed a
+//! box's workspace the first time the workspace is created, before the box
+//! boots. `{{NAME}}` placeholders in UTF-8 text files are replaced with the
+//! template variables (`--workspace-var NAME=VALUE`) and the built-in
+//! [`BOX_ID_VAR`]; placeholders naming no variable are kept as written.
+
+use serde::{Deserialize, Serialize};
+
+/// Built-in variable holding the ID of the box being seeded.
+pub const BOX_ID_VAR: &str = "BOX_ID";
+
+/// Where a fresh workspace is seeded from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceTemplate {
+    /// Local directory, or git URL with an optional `#<ref>` suffix.
+    pub source: String,
+    /// Variables substituted into seeded files; later entries win.
+    #[serde(default)]
+    pub vars: Vec<(String, String)>,
+}
+
+impl WorkspaceTemplate {
+    /// Whether the source is cloned with git rather than copied.
+    pub fn is_git(&self) -> bool {
+        is_git_url(&self.source)
+    }
+
+    /// Split a git source into the repository URL and the optional ref.
+    pub fn git_ref(&self) -> (&str, Option<&str>) {
+        match self.source.rsplit_once('#') {
+            Some((url, reference)) if !reference.is_empty() => (url, Some(reference)),
+            _ => (self.source.trim_end_matches('#'), None),
+        }
+    }
+}
+
+/// Whether `source` names a git repository: a URL git understands or a path
+/// ending in `.git`.
+pub fn is_git_url(source: &str) -> bool {
+    const PREFIXES: [&str; 6] = ["https://", "http://", "ssh://", "git://", "git@", "file://"];
+    let url = source.split('#').next().unwrap_or(source);
+    PREFIXES.iter().any(|prefix| url.starts_with(prefix)) || url.ends_with(".git")
+}
+
+/// Parse a `--workspace-var NAME=VALUE` argument.
+pub fn parse_var(spec: &str) -> Result<(String, String), String> {
+    let (name, value) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("invalid workspace variable '{spec}' (expected NAME=VALUE)"))?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!(
+            "invalid workspace variable name '{name}' (use letters, digits and '_')"
+        ));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Replace `{{NAME}}` placeholders in `text` with values from `vars`.
+///
+/// Whitespace inside the braces is ignored. When a name appears more than
+/// once in `vars`, the last value wins.
+pub fn substitute(text: &str, vars: &[(String, String)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let name = after[..end].trim();
+            vars.iter()
+                .rev()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| (end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_substitute_replaces_known_placeholders() {
+        let vars = vars(&[("NAME", "demo"), ("PORT", "80"), ("PORT", "8080")]);
+        assert_eq!(
+            substitute("app={{NAME}} port={{ PORT }}", &vars),
+            "app=demo port=8080"
+        );
+        assert_eq!(substitute("{{OTHER}} {{NAME", &vars), "{{OTHER}} {{NAME");
+        assert_eq!(substitute("${{NAME}}}", &vars), "$demo}");
+    }
+
+    #[test]
+    fn test_parse_var() {
+        assert_eq!(
+            parse_var("TASK=fix bug=1").unwrap(),
+            ("TASK".to_string(), "fix bug=1".to_string())
+        );
+        assert_eq!(parse_var("EMPTY=").unwrap().1, "");
+        assert!(parse_var("NOVALUE").is_err());
+        assert!(parse_var("=x").is_err());
+        assert!(parse_var("BAD-NAME=x").is_err());
+    }
+
+    #[test]
+    fn test_git_sources() {
+        assert!(is_git_url("https://github.com/org/repo"));
+        assert!(is_git_url("git@github.com:org/repo.git"));
+        assert!(is_git_url("/srv/templates/repo.git"));
+        assert!(!is_git_url("./templates/python"));
+
+        let template = WorkspaceTemplate {
+            source: "https://example.com/repo.git#v1.2".to_string(),
+            vars: vec![],
+        };
+        assert!(template.is_git());
+        assert_eq!(
+            template.git_ref(),
+            ("https://example.com/repo.git", Some("v1.2"))
+        );
+    }
+}
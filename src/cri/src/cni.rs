@@ -0,0 +1,616 @@
+//! CNI plugin execution for pod sandbox networking.
+//!
+//! With CNI enabled the runtime hands each pod sandbox to the node's CNI
+//! configuration the way containerd does: it creates a network namespace for
+//! the sandbox, runs every plugin of the first network in the config
+//! directory with `CNI_COMMAND=ADD`, and runs them in reverse with `DEL` on
+//! teardown. The sandbox's passt backend then runs inside that namespace with
+//! the address the plugins assigned, so pod traffic goes through the cluster
+//! network (network policy, service routing) rather than straight out of the
+//! host.
+//!
+//! Calls here block on plugin processes; the runtime service runs them on
+//! the blocking pool.
+
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use a3s_box_core::error::{BoxError, Result};
+
+/// Default directory holding CNI network configurations.
+pub const DEFAULT_CNI_CONF_DIR: &str = "/etc/cni/net.d";
+
+/// Default directory holding CNI plugin binaries.
+pub const DEFAULT_CNI_BIN_DIR: &str = "/opt/cni/bin";
+
+/// Where `ip netns` keeps named network namespaces.
+const NETNS_DIR: &str = "/var/run/netns";
+
+/// Interface name the plugins create inside the sandbox namespace.
+const CNI_IFNAME: &str = "eth0";
+
+/// Where the runtime looks for CNI configuration and plugins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CniOptions {
+    /// Directory with `*.conflist` / `*.conf` files; the first one by file
+    /// name is used.
+    pub conf_dir: PathBuf,
+    /// Directories searched for plugin binaries, in order.
+    pub bin_dirs: Vec<PathBuf>,
+}
+
+impl Default for CniOptions {
+    fn default() -> Self {
+        Self {
+            conf_dir: PathBuf::from(DEFAULT_CNI_CONF_DIR),
+            bin_dirs: vec![PathBuf::from(DEFAULT_CNI_BIN_DIR)],
+        }
+    }
+}
+
+/// A CNI network list: the plugins a sandbox is passed through, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CniNetwork {
+    pub name: String,
+    pub cni_version: String,
+    plugins: Vec<Map<String, Value>>,
+}
+
+/// One sandbox's view of a CNI invocation.
+#[derive(Debug, Clone, Default)]
+pub struct CniRuntimeConf {
+    /// Sandbox ID (`CNI_CONTAINERID`).
+    pub container_id: String,
+    /// Path of the sandbox's network namespace (`CNI_NETNS`).
+    pub netns: PathBuf,
+    /// `CNI_ARGS` key/value pairs, e.g. `K8S_POD_NAME`.
+    pub args: Vec<(String, String)>,
+    /// Host port mappings for plugins with the `portMappings` capability.
+    pub port_mappings: Vec<CniPortMapping>,
+}
+
+/// A `portMappings` capability entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CniPortMapping {
+    pub host_port: i32,
+    pub container_port: i32,
+    pub protocol: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub host_ip: String,
+}
+
+/// The parts of a CNI ADD result the runtime uses.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CniResult {
+    #[serde(default)]
+    pub ips: Vec<CniIpConfig>,
+}
+
+/// One address from a CNI result.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CniIpConfig {
+    /// Address with prefix length, e.g. `10.244.1.7/24`.
+    pub address: String,
+    #[serde(default)]
+    pub gateway: Option<String>,
+}
+
+/// The IPv4 address a sandbox is given, with its subnet and gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CniIpv4 {
+    pub address: Ipv4Addr,
+    pub prefix_len: u8,
+    pub gateway: Option<Ipv4Addr>,
+}
+
+/// An error a plugin reported on stdout.
+#[derive(Debug, Deserialize)]
+struct CniError {
+    #[serde(default)]
+    code: u32,
+    #[serde(default)]
+    msg: String,
+    #[serde(default)]
+    details: String,
+}
+
+impl CniNetwork {
+    /// Load the first network configuration in `conf_dir`, ordered by file
+    /// name as containerd and CRI-O do.
+    pub fn load(conf_dir: &Path) -> Result<Self> {
+        let entries = std::fs::read_dir(conf_dir).map_err(|e| {
+            BoxError::NetworkError(format!(
+                "Failed to read CNI config directory {}: {e}",
+                conf_dir.display()
+            ))
+        })?;
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == "conflist" || ext == "conf" || ext == "json")
+            })
+            .collect();
+        files.sort();
+
+        let Some(path) = files.first() else {
+            return Err(BoxError::NetworkError(format!(
+                "No CNI network configuration found in {}",
+                conf_dir.display()
+            )));
+        };
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            BoxError::NetworkError(format!("Failed to read {}: {e}", path.display()))
+        })?;
+        let is_list = path.extension().is_some_and(|ext| ext == "conflist");
+        Self::parse(&text, is_list)
+            .map_err(|e| BoxError::NetworkError(format!("Invalid {}: {e}", path.display())))
+    }
+
+    /// Parse a `.conflist` (`is_list`) or a single-plugin `.conf`.
+    fn parse(text: &str, is_list: bool) -> std::result::Result<Self, String> {
+        let value: Map<String, Value> = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let string = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_default()
+        };
+        let name = string("name");
+        if name.is_empty() {
+            return Err("network name must not be empty".to_string());
+        }
+        let plugins = if is_list {
+            value
+                .get("plugins")
+                .and_then(Value::as_array)
+                .ok_or("a network list needs a \"plugins\" array")?
+                .iter()
+                .map(|plugin| plugin.as_object().cloned().ok_or("plugins must be objects"))
+                .collect::<std::result::Result<Vec<_>, _>>()?
+        } else {
+            vec![value.clone()]
+        };
+        if plugins.is_empty() {
+            return Err("a network list needs at least one plugin".to_string());
+        }
+        if let Some(plugin) = plugins
+            .iter()
+            .find(|plugin| plugin.get("type").and_then(Value::as_str).is_none())
+        {
+            return Err(format!(
+                "plugin without a \"type\": {}",
+                Value::from(plugin.clone())
+            ));
+        }
+        Ok(Self {
+            name,
+            cni_version: string("cniVersion"),
+            plugins,
+        })
+    }
+
+    /// Run `ADD` through every plugin, each seeing the previous result, and
+    /// return the last plugin's result.
+    pub fn add(&self, options: &CniOptions, rt: &CniRuntimeConf) -> Result<CniResult> {
+        let mut prev_result = None;
+        for plugin in &self.plugins {
+            let conf = self.plugin_conf(plugin, prev_result.take(), rt);
+            prev_result = Some(exec_plugin(options, plugin, "ADD", &conf, rt)?);
+        }
+        let result = prev_result.unwrap_or_default();
+        serde_json::from_value(result)
+            .map_err(|e| BoxError::NetworkError(format!("Invalid CNI ADD result: {e}")))
+    }
+
+    /// Run `DEL` through every plugin in reverse order. Every plugin is
+    /// tried; the first failure is returned.
+    pub fn del(&self, options: &CniOptions, rt: &CniRuntimeConf) -> Result<()> {
+        let mut first_error = None;
+        for plugin in self.plugins.iter().rev() {
+            let conf = self.plugin_conf(plugin, None, rt);
+            if let Err(e) = exec_plugin(options, plugin, "DEL", &conf, rt) {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// The config a plugin reads on stdin: its own section plus the list's
+    /// name and version, the previous result and any runtime capabilities.
+    fn plugin_conf(
+        &self,
+        plugin: &Map<String, Value>,
+        prev_result: Option<Value>,
+        rt: &CniRuntimeConf,
+    ) -> Value {
+        let mut conf = plugin.clone();
+        conf.insert("name".to_string(), Value::from(self.name.clone()));
+        conf.insert(
+            "cniVersion".to_string(),
+            Value::from(self.cni_version.clone()),
+        );
+        if let Some(prev_result) = prev_result {
+            conf.insert("prevResult".to_string(), prev_result);
+        }
+        let wants_ports = plugin
+            .get("capabilities")
+            .and_then(|caps| caps.get("portMappings"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if wants_ports && !rt.port_mappings.is_empty() {
+            conf.insert(
+                "runtimeConfig".to_string(),
+                serde_json::json!({ "portMappings": rt.port_mappings }),
+            );
+        }
+        Value::Object(conf)
+    }
+}
+
+impl CniResult {
+    /// The first IPv4 address, which becomes the sandbox's PodIP.
+    pub fn ipv4(&self) -> Option<CniIpv4> {
+        self.ips.iter().find_map(|ip| {
+            let (address, prefix_len) = ip.address.split_once('/')?;
+            Some(CniIpv4 {
+                address: address.parse().ok()?,
+                prefix_len: prefix_len.parse().ok().filter(|len| *len <= 32)?,
+                gateway: ip.gateway.as_deref().and_then(|gw| gw.parse().ok()),
+            })
+        })
+    }
+
+    /// Every address other than the primary IPv4 one (e.g. the IPv6 half of
+    /// a dual-stack result), without prefix lengths.
+    pub fn additional_ips(&self) -> Vec<String> {
+        let primary = self.ipv4().map(|ip| ip.address.to_string());
+        self.ips
+            .iter()
+            .filter_map(|ip| ip.address.split_once('/').map(|(address, _)| address))
+            .filter(|address| Some(*address) != primary.as_deref())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl CniIpv4 {
+    /// The address's subnet in CIDR form, e.g. `10.244.1.0/24`.
+    pub fn subnet(&self) -> String {
+        let mask = u32::MAX
+            .checked_shl(32 - u32::from(self.prefix_len))
+            .unwrap_or(0);
+        let network = Ipv4Addr::from(u32::from(self.address) & mask);
+        format!("{network}/{}", self.prefix_len)
+    }
+}
+
+/// Run one plugin binary with the CNI environment and `conf` on stdin.
+fn exec_plugin(
+    options: &CniOptions,
+    plugin: &Map<String, Value>,
+    command: &str,
+    conf: &Value,
+    rt: &CniRuntimeConf,
+) -> Result<Value> {
+    let plugin_type = plugin
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let binary = options
+        .bin_dirs
+        .iter()
+        .map(|dir| dir.join(plugin_type))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            BoxError::NetworkError(format!(
+                "CNI plugin '{plugin_type}' not found in {}",
+                join_paths(&options.bin_dirs)
+            ))
+        })?;
+    let cni_args = rt
+        .args
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let mut child = Command::new(&binary)
+        .env("CNI_COMMAND", command)
+        .env("CNI_CONTAINERID", &rt.container_id)
+        .env("CNI_NETNS", &rt.netns)
+        .env("CNI_IFNAME", CNI_IFNAME)
+        .env("CNI_ARGS", cni_args)
+        .env("CNI_PATH", join_paths(&options.bin_dirs))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            BoxError::NetworkError(format!(
+                "Failed to run CNI plugin {}: {e}",
+                binary.display()
+            ))
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A plugin that exits without reading its config surfaces through
+        // its exit status below.
+        let _ = stdin.write_all(conf.to_string().as_bytes());
+    }
+    let output = child.wait_with_output().map_err(|e| {
+        BoxError::NetworkError(format!("CNI plugin '{plugin_type}' {command} failed: {e}"))
+    })?;
+
+    if !output.status.success() {
+        let reason = match serde_json::from_slice::<CniError>(&output.stdout) {
+            Ok(error) if !error.details.is_empty() => {
+                format!("{} (code {}): {}", error.msg, error.code, error.details)
+            }
+            Ok(error) => format!("{} (code {})", error.msg, error.code),
+            Err(_) => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        };
+        return Err(BoxError::NetworkError(format!(
+            "CNI plugin '{plugin_type}' {command} failed ({}): {reason}",
+            output.status
+        )));
+    }
+    if command != "ADD" {
+        return Ok(Value::Null);
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| {
+        BoxError::NetworkError(format!(
+            "CNI plugin '{plugin_type}' returned an invalid result: {e}"
+        ))
+    })
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Name of the network namespace created for a sandbox.
+pub fn netns_name(sandbox_id: &str) -> String {
+    format!("a3s-{sandbox_id}")
+}
+
+/// Path of a named network namespace.
+pub fn netns_path(name: &str) -> PathBuf {
+    Path::new(NETNS_DIR).join(name)
+}
+
+/// Create a named network namespace with `ip netns add`.
+pub fn create_netns(name: &str) -> Result<PathBuf> {
+    run_ip(&["netns", "add", name])?;
+    Ok(netns_path(name))
+}
+
+/// Delete a named network namespace; a namespace that is already gone is
+/// not an error.
+pub fn remove_netns(name: &str) -> Result<()> {
+    if !netns_path(name).exists() {
+        return Ok(());
+    }
+    run_ip(&["netns", "delete", name])
+}
+
+fn run_ip(args: &[&str]) -> Result<()> {
+    let output = Command::new("ip")
+        .args(args)
+        .output()
+        .map_err(|e| BoxError::NetworkError(format!("Failed to run ip {}: {e}", args.join(" "))))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(BoxError::NetworkError(format!(
+            "ip {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Write an executable plugin script into `dir`.
+    fn write_plugin(dir: &Path, name: &str, script: &str) {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{script}")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn runtime_conf(log: &Path) -> CniRuntimeConf {
+        CniRuntimeConf {
+            container_id: "sb-1".to_string(),
+            netns: log.to_path_buf(),
+            args: vec![
+                ("K8S_POD_NAMESPACE".to_string(), "default".to_string()),
+                ("K8S_POD_NAME".to_string(), "web".to_string()),
+            ],
+            port_mappings: vec![CniPortMapping {
+                host_port: 8080,
+                container_port: 80,
+                protocol: "tcp".to_string(),
+                host_ip: String::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_load_first_config_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(CniNetwork::load(dir.path()).is_err());
+
+        std::fs::write(
+            dir.path().join("20-other.conf"),
+            r#"{"cniVersion":"0.4.0","name":"other","type":"bridge"}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README"), "ignored").unwrap();
+        let network = CniNetwork::load(dir.path()).unwrap();
+        assert_eq!(network.name, "other");
+        assert_eq!(network.plugins.len(), 1);
+
+        std::fs::write(
+            dir.path().join("10-k8s.conflist"),
+            r#"{"cniVersion":"1.0.0","name":"k8s-pod-network","plugins":[
+                {"type":"bridge","ipam":{"type":"host-local"}},
+                {"type":"portmap","capabilities":{"portMappings":true}}]}"#,
+        )
+        .unwrap();
+        let network = CniNetwork::load(dir.path()).unwrap();
+        assert_eq!(network.name, "k8s-pod-network");
+        assert_eq!(network.cni_version, "1.0.0");
+        assert_eq!(network.plugins.len(), 2);
+
+        assert!(CniNetwork::parse(r#"{"name":"x","plugins":[]}"#, true).is_err());
+        assert!(CniNetwork::parse(r#"{"name":"x","plugins":[{}]}"#, true).is_err());
+        assert!(CniNetwork::parse(r#"{"type":"bridge"}"#, false).is_err());
+    }
+
+    #[test]
+    fn test_add_chains_results_and_del_runs_in_reverse() {
+        let bin = tempfile::tempdir().unwrap();
+        let log = bin.path().join("calls.log");
+        // Each plugin logs its command and config, and answers ADD with the
+        // previous result (or a fresh one).
+        write_plugin(
+            bin.path(),
+            "bridge",
+            r#"conf=$(cat)
+echo "bridge $CNI_COMMAND $CNI_CONTAINERID $CNI_IFNAME $CNI_ARGS $conf" >> "$CNI_NETNS"
+[ "$CNI_COMMAND" = ADD ] && echo '{"cniVersion":"1.0.0","ips":[{"address":"10.244.1.7/24","gateway":"10.244.1.1"},{"address":"fd00:10:244:1::7/64"}]}'
+exit 0
+"#,
+        );
+        write_plugin(
+            bin.path(),
+            "portmap",
+            r#"conf=$(cat)
+echo "portmap $CNI_COMMAND $conf" >> "$CNI_NETNS"
+[ "$CNI_COMMAND" = ADD ] && echo "$conf" | sed 's/.*"prevResult"://; s/,"runtimeConfig".*//'
+exit 0
+"#,
+        );
+        let network = CniNetwork::parse(
+            r#"{"cniVersion":"1.0.0","name":"k8s","plugins":[
+                {"type":"bridge"},
+                {"type":"portmap","capabilities":{"portMappings":true}}]}"#,
+            true,
+        )
+        .unwrap();
+        let options = CniOptions {
+            conf_dir: bin.path().to_path_buf(),
+            bin_dirs: vec![PathBuf::from("/nonexistent"), bin.path().to_path_buf()],
+        };
+        let rt = runtime_conf(&log);
+
+        let result = network.add(&options, &rt).unwrap();
+        let ipv4 = result.ipv4().unwrap();
+        assert_eq!(ipv4.address, Ipv4Addr::new(10, 244, 1, 7));
+        assert_eq!(ipv4.gateway, Some(Ipv4Addr::new(10, 244, 1, 1)));
+        assert_eq!(ipv4.subnet(), "10.244.1.0/24");
+        assert_eq!(result.additional_ips(), ["fd00:10:244:1::7"]);
+
+        let calls = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = calls.lines().collect();
+        assert!(lines[0]
+            .starts_with("bridge ADD sb-1 eth0 K8S_POD_NAMESPACE=default;K8S_POD_NAME=web {"));
+        assert!(lines[0].contains(r#""name":"k8s""#));
+        assert!(!lines[0].contains("runtimeConfig"));
+        assert!(lines[1].contains(r#""prevResult":{"#));
+        assert!(lines[1].contains(r#""runtimeConfig":{"portMappings":[{"#));
+        assert!(lines[1].contains(r#""hostPort":8080"#));
+        assert!(lines[1].contains(r#""containerPort":80"#));
+
+        std::fs::remove_file(&log).unwrap();
+        network.del(&options, &rt).unwrap();
+        let calls = std::fs::read_to_string(&log).unwrap();
+        let order: Vec<&str> = calls
+            .lines()
+            .map(|line| line.split(' ').take(2).collect::<Vec<_>>().join(" "))
+            .map(|call| {
+                if call.starts_with("bridge") {
+                    "bridge"
+                } else {
+                    "portmap"
+                }
+            })
+            .collect();
+        assert_eq!(order, ["portmap", "bridge"]);
+        assert!(!calls.contains("prevResult"));
+    }
+
+    #[test]
+    fn test_plugin_errors_are_reported() {
+        let bin = tempfile::tempdir().unwrap();
+        write_plugin(
+            bin.path(),
+            "bridge",
+            r#"cat >/dev/null
+echo '{"cniVersion":"1.0.0","code":11,"msg":"no IP addresses available","details":"range is full"}'
+exit 1
+"#,
+        );
+        let network = CniNetwork::parse(
+            r#"{"cniVersion":"1.0.0","name":"k8s","type":"bridge"}"#,
+            false,
+        )
+        .unwrap();
+        let options = CniOptions {
+            conf_dir: bin.path().to_path_buf(),
+            bin_dirs: vec![bin.path().to_path_buf()],
+        };
+        let rt = runtime_conf(&bin.path().join("unused"));
+
+        let err = network.add(&options, &rt).unwrap_err().to_string();
+        assert!(
+            err.contains("no IP addresses available (code 11): range is full"),
+            "{err}"
+        );
+
+        let missing = CniOptions {
+            bin_dirs: vec![PathBuf::from("/nonexistent")],
+            ..options
+        };
+        let err = network.del(&missing, &rt).unwrap_err().to_string();
+        assert!(
+            err.contains("CNI plugin 'bridge' not found in /nonexistent"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_result_without_ipv4() {
+        let result: CniResult = serde_json::from_str(
+            r#"{"cniVersion":"1.0.0","ips":[{"address":"fd00::7/64"}],"dns":{}}"#,
+        )
+        .unwrap();
+        assert_eq!(result.ipv4(), None);
+        assert_eq!(result.additional_ips(), ["fd00::7"]);
+        assert_eq!(CniResult::default().ipv4(), None);
+    }
+
+    #[test]
+    fn test_netns_paths() {
+        assert_eq!(netns_name("sb-1"), "a3s-sb-1");
+        assert_eq!(
+            netns_path("a3s-sb-1"),
+            PathBuf::from("/var/run/netns/a3s-sb-1")
+        );
+    }
+}
@@ -2,10 +2,20 @@
 //!
 //! Reads A3S-specific annotations from pod/container configs:
 //! - `a3s.box/agent-image` → optional sandbox VM agent/rootfs image override
-//! - `a3s.box/vcpus`, `a3s.box/memory-mb` → ResourceConfig
+//! - `a3s.box/vcpus`, `a3s.box/memory-mb` → ResourceConfig, falling back to
+//!   the pod's summed container limits
+//! - `a3s.box/memory-overhead` → guest RAM added on top of that sizing
 //! - `a3s.box/tee` → TeeConfig
+//! - `a3s.box/kernel` → a guest kernel from the RuntimeClass's kernel directory
+//! - `a3s.box/hugepages` → guest RAM backed by host transparent hugepages,
+//!   which pods requesting `hugepages-2Mi` also get along with a guest pool
+//!
+//! The pod's RuntimeClass handler supplies defaults for the same settings
+//! (see [`RuntimeHandler`]), so one node can run confidential and standard
+//! pods side by side.
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use a3s_box_core::error::{BoxError, Result};
 use a3s_box_core::{
@@ -13,7 +23,7 @@ use a3s_box_core::{
     NetworkMode,
 };
 
-use crate::cri_api::{port_mapping, PodSandboxConfig};
+use crate::cri_api::{port_mapping, LinuxContainerResources, PodSandboxConfig};
 
 /// Annotation keys for A3S Box configuration.
 pub const ANN_AGENT_IMAGE: &str = "a3s.box/agent-image";
@@ -22,35 +32,207 @@ pub const DEFAULT_AGENT_IMAGE: &str = "ghcr.io/a3s-box/code:v0.1.0";
 const ANN_VCPUS: &str = "a3s.box/vcpus";
 const ANN_MEMORY_MB: &str = "a3s.box/memory-mb";
 const ANN_DISK_MB: &str = "a3s.box/disk-mb";
+const ANN_MEMORY_OVERHEAD: &str = "a3s.box/memory-overhead";
 const ANN_TEE: &str = "a3s.box/tee";
 const ANN_TEE_WORKLOAD_ID: &str = "a3s.box/tee-workload-id";
+const ANN_KERNEL: &str = "a3s.box/kernel";
+const ANN_HUGEPAGES: &str = "a3s.box/hugepages";
+
+/// Huge page size of the guest's hugetlb pool: the default on x86_64 and on
+/// arm64 with 4K pages.
+const GUEST_HUGEPAGE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Smallest VM a pod memory limit is sized to; the guest agent does not
+/// boot in less.
+const MIN_POD_MEMORY_MB: u32 = 256;
+
+/// VM settings for the pods of one RuntimeClass handler, configured on the
+/// node with `--runtime-handler HANDLER:KEY=VALUE,...`. Pod annotations
+/// override them, except that a TEE handler cannot be downgraded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuntimeHandler {
+    /// Agent image for the handler's pods (`agent-image=`).
+    pub agent_image: Option<String>,
+    /// TEE every pod of the handler runs in (`tee=`), as `a3s.box/tee`.
+    pub tee: Option<String>,
+    /// Guest kernel for the handler's pods (`kernel=`).
+    pub kernel: Option<PathBuf>,
+    /// Directory of kernels pods may pick by name with `a3s.box/kernel`
+    /// (`kernel-dir=`). Without one the annotation is rejected.
+    pub kernel_dir: Option<PathBuf>,
+    /// Guest RAM in MiB added on top of the pod's sizing (`memory-overhead=`).
+    pub memory_overhead_mb: Option<u32>,
+}
+
+impl RuntimeHandler {
+    /// Parse a `--runtime-handler` value, `HANDLER:KEY=VALUE[,KEY=VALUE...]`.
+    pub fn parse(value: &str) -> std::result::Result<(String, Self), String> {
+        let invalid = |reason: &str| format!("Invalid --runtime-handler '{value}': {reason}");
+        let Some((name, settings)) = value.split_once(':') else {
+            return Err(invalid("expected HANDLER:KEY=VALUE[,KEY=VALUE...]"));
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid("handler must be non-empty"));
+        }
+
+        let mut handler = Self::default();
+        for setting in settings.split(',').filter(|s| !s.trim().is_empty()) {
+            let Some((key, val)) = setting.split_once('=') else {
+                return Err(invalid(&format!("expected KEY=VALUE, got '{setting}'")));
+            };
+            let val = val.trim();
+            if val.is_empty() {
+                return Err(invalid(&format!("'{}' has an empty value", key.trim())));
+            }
+            match key.trim() {
+                "agent-image" => handler.agent_image = Some(val.to_string()),
+                "tee" => {
+                    tee_kind(val).map_err(|e| invalid(&e.to_string()))?;
+                    handler.tee = Some(val.to_string());
+                }
+                "kernel" | "kernel-dir" if !Path::new(val).is_absolute() => {
+                    return Err(invalid(&format!(
+                        "'{}' must be an absolute path",
+                        key.trim()
+                    )));
+                }
+                "kernel" => handler.kernel = Some(PathBuf::from(val)),
+                "kernel-dir" => handler.kernel_dir = Some(PathBuf::from(val)),
+                "memory-overhead" => {
+                    handler.memory_overhead_mb = Some(
+                        parse_memory_mb(val)
+                            .ok_or_else(|| invalid(&format!("invalid memory-overhead '{val}'")))?,
+                    );
+                }
+                other => return Err(invalid(&format!("unknown key '{other}'"))),
+            }
+        }
+        Ok((name.to_string(), handler))
+    }
+}
 
-/// Convert a CRI PodSandboxConfig to an A3S BoxConfig.
+/// Convert a CRI PodSandboxConfig to an A3S BoxConfig, starting from the
+/// settings of the pod's RuntimeClass `handler`.
 pub fn pod_sandbox_config_to_box_config(
     config: &PodSandboxConfig,
     default_agent_image: &str,
+    handler: &RuntimeHandler,
 ) -> Result<BoxConfig> {
     let annotations = &config.annotations;
     let image = resolve_agent_image(annotations, default_agent_image)?;
 
-    let resources = parse_resources(annotations);
-    let tee = parse_tee_config(annotations)?;
+    let pod_resources = config
+        .linux
+        .as_ref()
+        .and_then(|linux| linux.resources.as_ref());
+    let hugepages = parse_hugepages(annotations, pod_resources)?;
+    let resources = parse_resources(
+        annotations,
+        pod_resources,
+        handler.memory_overhead_mb,
+        hugepages.pool_mb(),
+    )?;
+    let tee = parse_tee_config(annotations, handler.tee.as_deref())?;
+    let kernel = parse_kernel(annotations, handler)?;
+    if kernel.is_some() && !matches!(tee, TeeConfig::None) {
+        return Err(BoxError::ConfigError(
+            "A custom guest kernel cannot be used with a TEE; the measured launch covers the \
+             bundled kernel"
+                .to_string(),
+        ));
+    }
     let port_map = parse_port_mappings(config)?;
     let network = parse_network_mode(annotations)?;
     let hostname = parse_hostname(config)?;
+    let mut sysctls = parse_sysctls(config);
+    if hugepages.pool_pages > 0 {
+        sysctls.retain(|(name, _)| name != "vm.nr_hugepages");
+        sysctls.push((
+            "vm.nr_hugepages".to_string(),
+            hugepages.pool_pages.to_string(),
+        ));
+        sysctls.sort();
+    }
 
     Ok(BoxConfig {
         image,
         resources,
         tee,
+        kernel,
         port_map,
         network,
         hostname,
-        sysctls: parse_sysctls(config),
+        sysctls,
+        hugepages: hugepages.backed,
         ..Default::default()
     })
 }
 
+/// Hugepage backing for a pod VM.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PodHugepages {
+    /// Back guest RAM with host transparent hugepages.
+    backed: bool,
+    /// 2 MiB pages the guest reserves for the pod's `hugepages-2Mi` limit.
+    pool_pages: u64,
+}
+
+impl PodHugepages {
+    /// Guest RAM the pool takes on top of the pod's memory.
+    fn pool_mb(&self) -> u32 {
+        u32::try_from(self.pool_pages * (GUEST_HUGEPAGE_BYTES >> 20)).unwrap_or(u32::MAX)
+    }
+}
+
+/// Hugepages from `a3s.box/hugepages` and the pod's hugepage limits.
+///
+/// Pods that request hugepages get both: a pool in the guest, so hugetlbfs
+/// mappings inside the pod work, and host hugepages behind all of guest RAM.
+fn parse_hugepages(
+    annotations: &HashMap<String, String>,
+    pod: Option<&LinuxContainerResources>,
+) -> Result<PodHugepages> {
+    let backed = match annotations.get(ANN_HUGEPAGES) {
+        Some(value) => value.trim().parse::<bool>().map_err(|_| {
+            BoxError::ConfigError(format!(
+                "Invalid CRI annotation '{ANN_HUGEPAGES}': '{value}'; expected true or false"
+            ))
+        })?,
+        None => false,
+    };
+    let mut pool_bytes = 0u64;
+    for limit in pod.iter().flat_map(|pod| &pod.hugepage_limits) {
+        if limit.limit == 0 {
+            continue;
+        }
+        if hugepage_size_bytes(&limit.page_size) != Some(GUEST_HUGEPAGE_BYTES) {
+            return Err(BoxError::ConfigError(format!(
+                "Unsupported hugepage size '{}': pod VMs provide 2MB pages only",
+                limit.page_size
+            )));
+        }
+        pool_bytes = pool_bytes.saturating_add(limit.limit);
+    }
+    Ok(PodHugepages {
+        backed: backed || pool_bytes > 0,
+        pool_pages: pool_bytes.div_ceil(GUEST_HUGEPAGE_BYTES),
+    })
+}
+
+/// A kubelet hugepage size such as `2MB` or `1GB` (binary units).
+fn hugepage_size_bytes(page_size: &str) -> Option<u64> {
+    let page_size = page_size.trim();
+    let (number, shift) = if let Some(kb) = page_size.strip_suffix("KB") {
+        (kb, 10)
+    } else if let Some(mb) = page_size.strip_suffix("MB") {
+        (mb, 20)
+    } else {
+        (page_size.strip_suffix("GB")?, 30)
+    };
+    number.parse::<u64>().ok()?.checked_mul(1 << shift)
+}
+
 /// Extract pod-level sysctls from the CRI sandbox config.
 ///
 /// Sorted by name for deterministic ordering (the guest applies them in order).
@@ -62,7 +244,7 @@ fn parse_sysctls(config: &PodSandboxConfig) -> Vec<(String, String)> {
         .sysctls
         .iter()
         .filter(|(name, _)| {
-            let safe = is_safe_sysctl_name(name);
+            let safe = a3s_box_core::kernel::validate_sysctl_name(name).is_ok();
             if !safe {
                 tracing::warn!(sysctl = %name, "Dropping sysctl with an unsafe name");
             }
@@ -74,19 +256,6 @@ fn parse_sysctls(config: &PodSandboxConfig) -> Vec<(String, String)> {
     sysctls
 }
 
-/// A sysctl name is safe to map onto `/proc/sys/<name with '.'→'/'>` only if it
-/// is a non-empty dot-separated key with no path-traversal characters. Guards
-/// against a crafted name (e.g. `../../proc/sysrq-trigger`) escaping
-/// `/proc/sys` when the guest substitutes `.` for `/`.
-fn is_safe_sysctl_name(name: &str) -> bool {
-    !name.is_empty()
-        && !name.contains('/')
-        && !name.split('.').any(|seg| seg.is_empty() || seg == "..")
-        && name
-            .chars()
-            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
-}
-
 fn parse_hostname(config: &PodSandboxConfig) -> Result<Option<String>> {
     let hostname = config.hostname.trim();
     if hostname.is_empty() {
@@ -120,67 +289,191 @@ fn resolve_agent_image(
     Ok(default_agent_image.to_string())
 }
 
-/// Parse resource configuration from annotations.
-fn parse_resources(annotations: &HashMap<String, String>) -> ResourceConfig {
+/// Parse resource configuration from annotations, sizing the VM from the
+/// pod's CPU and memory limits where no annotation says otherwise. Unless
+/// `a3s.box/memory-mb` fixes the size outright, the memory overhead (from
+/// the annotation, else the handler) is added for the guest kernel and agent.
+fn parse_resources(
+    annotations: &HashMap<String, String>,
+    pod: Option<&LinuxContainerResources>,
+    handler_overhead_mb: Option<u32>,
+    hugepage_pool_mb: u32,
+) -> Result<ResourceConfig> {
     // Clamp to the VM spec's valid vCPU range. Downstream the count narrows to a
     // u8, so an out-of-range annotation (e.g. 256) would wrap to 0 vCPUs and
     // silently fail to boot; clamp to 1..=255 instead.
     let vcpus = annotations
         .get(ANN_VCPUS)
         .and_then(|v| v.parse::<u32>().ok())
+        .or_else(|| pod.and_then(pod_vcpus))
         .unwrap_or(2)
         .clamp(1, 255);
 
-    let memory_mb = annotations
+    let overhead_mb = match annotations.get(ANN_MEMORY_OVERHEAD) {
+        Some(value) => parse_memory_mb(value).ok_or_else(|| {
+            BoxError::ConfigError(format!(
+                "Invalid CRI annotation '{ANN_MEMORY_OVERHEAD}': '{value}'; expected MiB, \
+                 optionally with an Mi or Gi suffix"
+            ))
+        })?,
+        None => handler_overhead_mb.unwrap_or(0),
+    };
+    let memory_mb = match annotations
         .get(ANN_MEMORY_MB)
         .and_then(|v| v.parse::<u32>().ok())
-        .unwrap_or(1024);
+    {
+        Some(memory_mb) => memory_mb,
+        None => pod
+            .and_then(pod_memory_mb)
+            .unwrap_or(1024)
+            .saturating_add(overhead_mb)
+            .saturating_add(hugepage_pool_mb),
+    };
 
     let disk_mb = annotations
         .get(ANN_DISK_MB)
         .and_then(|v| v.parse::<u32>().ok())
         .unwrap_or(4096);
 
-    ResourceConfig {
+    Ok(ResourceConfig {
         vcpus,
         memory_mb,
         disk_mb,
         ..Default::default()
+    })
+}
+
+/// A memory amount in MiB: a plain number, or one with an `Mi`/`Gi` suffix.
+fn parse_memory_mb(value: &str) -> Option<u32> {
+    let value = value.trim();
+    if let Some(gib) = value.strip_suffix("Gi") {
+        return gib.parse::<u32>().ok()?.checked_mul(1024);
     }
+    value.strip_suffix("Mi").unwrap_or(value).parse().ok()
 }
 
-/// Parse TEE configuration from annotations.
-fn parse_tee_config(annotations: &HashMap<String, String>) -> Result<TeeConfig> {
-    match annotations.get(ANN_TEE).map(|s| s.as_str()) {
-        Some("sev-snp") => {
-            let workload_id = annotations
-                .get(ANN_TEE_WORKLOAD_ID)
-                .cloned()
-                .unwrap_or_else(|| "default".to_string());
-            Ok(TeeConfig::SevSnp {
-                workload_id,
-                generation: Default::default(),
-                simulate: false,
-            })
-        }
-        Some("tdx") => {
-            let workload_id = annotations
-                .get(ANN_TEE_WORKLOAD_ID)
-                .cloned()
-                .unwrap_or_else(|| "default".to_string());
-            Ok(TeeConfig::Tdx {
-                workload_id,
-                simulate: false,
-            })
-        }
-        Some("none") | None => Ok(TeeConfig::None),
-        Some(other) => Err(BoxError::ConfigError(format!(
-            "Unknown TEE type: '{}'. Expected: none, sev-snp, tdx",
+/// Whole vCPUs covering the pod's CPU limit (`quota / period`, rounded up).
+fn pod_vcpus(pod: &LinuxContainerResources) -> Option<u32> {
+    if pod.cpu_quota <= 0 || pod.cpu_period <= 0 {
+        return None;
+    }
+    let vcpus = (pod.cpu_quota as u64).div_ceil(pod.cpu_period as u64);
+    Some(u32::try_from(vcpus).unwrap_or(u32::MAX))
+}
+
+/// The pod's memory limit in MiB, rounded up and at least
+/// [`MIN_POD_MEMORY_MB`].
+fn pod_memory_mb(pod: &LinuxContainerResources) -> Option<u32> {
+    if pod.memory_limit_in_bytes <= 0 {
+        return None;
+    }
+    let mb = (pod.memory_limit_in_bytes as u64).div_ceil(1024 * 1024);
+    Some(u32::try_from(mb).unwrap_or(u32::MAX).max(MIN_POD_MEMORY_MB))
+}
+
+/// TEE kinds a pod can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TeeKind {
+    None,
+    SevSnp,
+    Tdx,
+}
+
+fn tee_kind(value: &str) -> Result<TeeKind> {
+    match value.trim() {
+        "none" => Ok(TeeKind::None),
+        "sev-snp" | "snp" => Ok(TeeKind::SevSnp),
+        "tdx" => Ok(TeeKind::Tdx),
+        other => Err(BoxError::ConfigError(format!(
+            "Unknown TEE type: '{}'. Expected: none, sev-snp (snp), tdx",
             other
         ))),
     }
 }
 
+/// Parse TEE configuration from annotations. A handler TEE applies when the
+/// pod names none, and a pod cannot ask a TEE handler for anything else.
+fn parse_tee_config(
+    annotations: &HashMap<String, String>,
+    handler_tee: Option<&str>,
+) -> Result<TeeConfig> {
+    let handler_kind = handler_tee.map(tee_kind).transpose()?;
+    let pod_kind = annotations
+        .get(ANN_TEE)
+        .map(|value| tee_kind(value))
+        .transpose()?;
+    let kind = match (handler_kind, pod_kind) {
+        (Some(handler), Some(pod)) if handler != TeeKind::None && pod != handler => {
+            return Err(BoxError::ConfigError(format!(
+                "Annotation '{ANN_TEE}' asks for {pod:?}, but the pod's RuntimeClass runs \
+                 every pod with {handler:?}"
+            )));
+        }
+        (_, Some(pod)) => pod,
+        (Some(handler), None) => handler,
+        (None, None) => TeeKind::None,
+    };
+
+    let workload_id = || {
+        annotations
+            .get(ANN_TEE_WORKLOAD_ID)
+            .cloned()
+            .unwrap_or_else(|| "default".to_string())
+    };
+    Ok(match kind {
+        TeeKind::None => TeeConfig::None,
+        TeeKind::SevSnp => TeeConfig::SevSnp {
+            workload_id: workload_id(),
+            generation: Default::default(),
+            simulate: false,
+            kbs_url: None,
+        },
+        TeeKind::Tdx => TeeConfig::Tdx {
+            workload_id: workload_id(),
+            simulate: false,
+        },
+    })
+}
+
+/// Resolve the guest kernel: `a3s.box/kernel` names a file in the handler's
+/// kernel directory (pods cannot point at arbitrary host paths); otherwise
+/// the handler's own kernel, if any.
+fn parse_kernel(
+    annotations: &HashMap<String, String>,
+    handler: &RuntimeHandler,
+) -> Result<Option<PathBuf>> {
+    let Some(name) = annotations
+        .get(ANN_KERNEL)
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+    else {
+        return Ok(handler.kernel.clone());
+    };
+    let Some(kernel_dir) = handler.kernel_dir.as_ref() else {
+        return Err(BoxError::ConfigError(format!(
+            "Annotation '{ANN_KERNEL}' is not allowed: the pod's RuntimeClass has no kernel \
+             directory"
+        )));
+    };
+    let mut components = Path::new(name).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    ) {
+        return Err(BoxError::ConfigError(format!(
+            "Invalid CRI annotation '{ANN_KERNEL}': '{name}' must be a file name"
+        )));
+    }
+    let kernel = kernel_dir.join(name);
+    if !kernel.is_file() {
+        return Err(BoxError::ConfigError(format!(
+            "Kernel '{name}' from annotation '{ANN_KERNEL}' is not in {}",
+            kernel_dir.display()
+        )));
+    }
+    Ok(Some(kernel))
+}
+
 fn parse_network_mode(annotations: &HashMap<String, String>) -> Result<NetworkMode> {
     let Some(network) = annotations.get(ANN_NETWORK).map(|network| network.trim()) else {
         return Ok(NetworkMode::Tsi);
@@ -276,10 +569,14 @@ mod tests {
         }
     }
 
+    fn default_box_config(config: &PodSandboxConfig) -> Result<BoxConfig> {
+        pod_sandbox_config_to_box_config(config, DEFAULT_AGENT_IMAGE, &RuntimeHandler::default())
+    }
+
     #[test]
     fn test_missing_image_annotation_uses_default_agent_image() {
         let config = make_config(HashMap::new());
-        let box_config = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap();
+        let box_config = default_box_config(&config).unwrap();
         assert_eq!(box_config.image, DEFAULT_AGENT_IMAGE);
     }
 
@@ -297,7 +594,7 @@ mod tests {
             ]),
             ..Default::default()
         });
-        let box_config = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap();
+        let box_config = default_box_config(&config).unwrap();
         assert_eq!(
             box_config.sysctls,
             vec![
@@ -313,14 +610,14 @@ mod tests {
     #[test]
     fn test_sysctls_empty_without_linux_config() {
         let config = make_config(HashMap::new());
-        let box_config = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap();
+        let box_config = default_box_config(&config).unwrap();
         assert!(box_config.sysctls.is_empty());
     }
 
     #[test]
     fn test_empty_default_agent_image_without_annotation_is_rejected() {
         let config = make_config(HashMap::new());
-        assert!(pod_sandbox_config_to_box_config(&config, "").is_err());
+        assert!(pod_sandbox_config_to_box_config(&config, "", &RuntimeHandler::default()).is_err());
     }
 
     #[test]
@@ -330,8 +627,12 @@ mod tests {
             "ghcr.io/a3s-box/code:v0.1.0".to_string(),
         )]);
         let config = make_config(annotations);
-        let box_config =
-            pod_sandbox_config_to_box_config(&config, "ghcr.io/a3s-box/default:v1").unwrap();
+        let box_config = pod_sandbox_config_to_box_config(
+            &config,
+            "ghcr.io/a3s-box/default:v1",
+            &RuntimeHandler::default(),
+        )
+        .unwrap();
         assert_eq!(box_config.image, "ghcr.io/a3s-box/code:v0.1.0");
     }
 
@@ -340,7 +641,7 @@ mod tests {
         let annotations = HashMap::from([(ANN_NETWORK.to_string(), "cri-net".to_string())]);
         let config = make_config(annotations);
 
-        let box_config = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap();
+        let box_config = default_box_config(&config).unwrap();
 
         assert!(matches!(
             box_config.network,
@@ -353,7 +654,7 @@ mod tests {
         let mut config = make_config(HashMap::new());
         config.hostname = "pod-web".to_string();
 
-        let box_config = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap();
+        let box_config = default_box_config(&config).unwrap();
 
         assert_eq!(box_config.hostname.as_deref(), Some("pod-web"));
     }
@@ -363,7 +664,7 @@ mod tests {
         let mut config = make_config(HashMap::new());
         config.hostname = "bad_host".to_string();
 
-        let err = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap_err();
+        let err = default_box_config(&config).unwrap_err();
 
         assert!(err.to_string().contains("Invalid CRI sandbox hostname"));
     }
@@ -373,7 +674,7 @@ mod tests {
         let annotations = HashMap::from([(ANN_NETWORK.to_string(), "bad/name".to_string())]);
         let config = make_config(annotations);
 
-        let err = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap_err();
+        let err = default_box_config(&config).unwrap_err();
 
         assert!(err.to_string().contains("Invalid CRI network annotation"));
     }
@@ -396,7 +697,7 @@ mod tests {
             },
         ];
 
-        let box_config = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap();
+        let box_config = default_box_config(&config).unwrap();
 
         // host_port == 0 publishes the container port on the same host port.
         assert_eq!(box_config.port_map, vec!["8080:80", "8080:8080"]);
@@ -412,7 +713,7 @@ mod tests {
             host_ip: String::new(),
         }];
 
-        let err = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap_err();
+        let err = default_box_config(&config).unwrap_err();
 
         assert!(err.to_string().contains("only TCP is supported"));
     }
@@ -427,7 +728,7 @@ mod tests {
             host_ip: "127.0.0.1".to_string(),
         }];
 
-        let err = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap_err();
+        let err = default_box_config(&config).unwrap_err();
 
         assert!(err.to_string().contains("host_ip"));
     }
@@ -442,7 +743,7 @@ mod tests {
             host_ip: String::new(),
         }];
 
-        let err = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap_err();
+        let err = default_box_config(&config).unwrap_err();
 
         assert!(err.to_string().contains("Invalid CRI container port"));
     }
@@ -455,12 +756,49 @@ mod tests {
             (ANN_MEMORY_MB.to_string(), "2048".to_string()),
         ]);
         let config = make_config(annotations);
-        let box_config = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap();
+        let box_config = default_box_config(&config).unwrap();
 
         assert_eq!(box_config.resources.vcpus, 4);
         assert_eq!(box_config.resources.memory_mb, 2048);
     }
 
+    #[test]
+    fn test_pod_limits_size_the_vm() {
+        use crate::cri_api::LinuxPodSandboxConfig;
+        let with_limits = |cpu_quota, memory_limit_in_bytes| {
+            let mut config = make_config(HashMap::new());
+            config.linux = Some(LinuxPodSandboxConfig {
+                resources: Some(LinuxContainerResources {
+                    cpu_period: 100_000,
+                    cpu_quota,
+                    memory_limit_in_bytes,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            config
+        };
+
+        // 1500m CPU and 1.5Gi round up to 2 vCPUs and 1536 MiB.
+        let config = with_limits(150_000, 1536 * 1024 * 1024);
+        let resources = default_box_config(&config).unwrap().resources;
+        assert_eq!((resources.vcpus, resources.memory_mb), (2, 1536));
+
+        // Tiny memory limits still get a bootable VM; no limits keep defaults.
+        let config = with_limits(0, 64 * 1024 * 1024);
+        let resources = default_box_config(&config).unwrap().resources;
+        assert_eq!(
+            (resources.vcpus, resources.memory_mb),
+            (2, MIN_POD_MEMORY_MB)
+        );
+
+        // Annotations win over pod limits.
+        let mut config = with_limits(400_000, 8 * 1024 * 1024 * 1024);
+        config.annotations = HashMap::from([(ANN_VCPUS.to_string(), "1".to_string())]);
+        let resources = default_box_config(&config).unwrap().resources;
+        assert_eq!((resources.vcpus, resources.memory_mb), (1, 8192));
+    }
+
     #[test]
     fn test_tee_sev_snp() {
         let annotations = HashMap::from([
@@ -469,7 +807,7 @@ mod tests {
             (ANN_TEE_WORKLOAD_ID.to_string(), "my-workload".to_string()),
         ]);
         let config = make_config(annotations);
-        let box_config = pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).unwrap();
+        let box_config = default_box_config(&config).unwrap();
 
         match box_config.tee {
             TeeConfig::SevSnp { workload_id, .. } => {
@@ -486,6 +824,181 @@ mod tests {
             (ANN_TEE.to_string(), "unknown".to_string()),
         ]);
         let config = make_config(annotations);
-        assert!(pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE).is_err());
+        assert!(default_box_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_runtime_handler_parse() {
+        let (name, handler) = RuntimeHandler::parse(
+            "a3s-snp:tee=snp,memory-overhead=1Gi,kernel-dir=/var/lib/a3s-box/kernels",
+        )
+        .unwrap();
+        assert_eq!(name, "a3s-snp");
+        assert_eq!(
+            handler,
+            RuntimeHandler {
+                tee: Some("snp".to_string()),
+                memory_overhead_mb: Some(1024),
+                kernel_dir: Some(PathBuf::from("/var/lib/a3s-box/kernels")),
+                ..Default::default()
+            }
+        );
+
+        for bad in [
+            "no-settings",
+            ":tee=snp",
+            "h:tee",
+            "h:tee=sgx",
+            "h:kernel=vmlinux",
+            "h:memory-overhead=lots",
+            "h:color=blue",
+        ] {
+            assert!(
+                RuntimeHandler::parse(bad).is_err(),
+                "{bad} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_runtime_handler_tee_applies_and_cannot_be_downgraded() {
+        let handler = RuntimeHandler {
+            tee: Some("sev-snp".to_string()),
+            ..Default::default()
+        };
+        let config = make_config(HashMap::new());
+        let box_config =
+            pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE, &handler).unwrap();
+        assert!(matches!(box_config.tee, TeeConfig::SevSnp { .. }));
+
+        let config = make_config(HashMap::from([(ANN_TEE.to_string(), "none".to_string())]));
+        assert!(pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE, &handler).is_err());
+
+        // A standard handler lets the pod opt in, with the short alias.
+        let config = make_config(HashMap::from([(ANN_TEE.to_string(), "snp".to_string())]));
+        let box_config = default_box_config(&config).unwrap();
+        assert!(matches!(box_config.tee, TeeConfig::SevSnp { .. }));
+    }
+
+    #[test]
+    fn test_memory_overhead_adds_to_pod_sizing() {
+        use crate::cri_api::LinuxPodSandboxConfig;
+        let handler = RuntimeHandler {
+            memory_overhead_mb: Some(128),
+            ..Default::default()
+        };
+        let mut config = make_config(HashMap::new());
+        config.linux = Some(LinuxPodSandboxConfig {
+            resources: Some(LinuxContainerResources {
+                memory_limit_in_bytes: 512 * 1024 * 1024,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let memory_mb = |config: &PodSandboxConfig| {
+            pod_sandbox_config_to_box_config(config, DEFAULT_AGENT_IMAGE, &handler)
+                .unwrap()
+                .resources
+                .memory_mb
+        };
+        assert_eq!(memory_mb(&config), 640);
+
+        // The annotation overrides the handler's overhead.
+        config
+            .annotations
+            .insert(ANN_MEMORY_OVERHEAD.to_string(), "256Mi".to_string());
+        assert_eq!(memory_mb(&config), 768);
+
+        // An explicit VM size is taken as-is.
+        config
+            .annotations
+            .insert(ANN_MEMORY_MB.to_string(), "2048".to_string());
+        assert_eq!(memory_mb(&config), 2048);
+
+        config
+            .annotations
+            .insert(ANN_MEMORY_OVERHEAD.to_string(), "lots".to_string());
+        assert!(pod_sandbox_config_to_box_config(&config, DEFAULT_AGENT_IMAGE, &handler).is_err());
+    }
+
+    #[test]
+    fn test_hugepages_from_annotation_and_pod_limits() {
+        use crate::cri_api::{HugepageLimit, LinuxPodSandboxConfig};
+
+        let config = make_config(HashMap::from([(
+            ANN_HUGEPAGES.to_string(),
+            "true".to_string(),
+        )]));
+        let box_config = default_box_config(&config).unwrap();
+        assert!(box_config.hugepages);
+        assert!(box_config.sysctls.is_empty());
+
+        let pod_with_hugepages = |page_size: &str| {
+            let mut config = make_config(HashMap::new());
+            config.linux = Some(LinuxPodSandboxConfig {
+                resources: Some(LinuxContainerResources {
+                    memory_limit_in_bytes: 512 * 1024 * 1024,
+                    hugepage_limits: vec![HugepageLimit {
+                        page_size: page_size.to_string(),
+                        limit: 64 * 1024 * 1024,
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            config
+        };
+        let box_config = default_box_config(&pod_with_hugepages("2MB")).unwrap();
+        assert!(box_config.hugepages);
+        assert_eq!(box_config.resources.memory_mb, 576);
+        assert_eq!(
+            box_config.sysctls,
+            vec![("vm.nr_hugepages".to_string(), "32".to_string())]
+        );
+
+        assert!(default_box_config(&pod_with_hugepages("1GB")).is_err());
+        assert!(default_box_config(&make_config(HashMap::from([(
+            ANN_HUGEPAGES.to_string(),
+            "yes".to_string(),
+        )])))
+        .is_err());
+    }
+
+    #[test]
+    fn test_kernel_annotation_resolves_in_handler_kernel_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("vmlinux-6.12"), b"\x7fELF").unwrap();
+        let handler = RuntimeHandler {
+            kernel: Some(PathBuf::from("/opt/a3s/vmlinux")),
+            kernel_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let kernel = |annotations: HashMap<String, String>, handler: &RuntimeHandler| {
+            pod_sandbox_config_to_box_config(
+                &make_config(annotations),
+                DEFAULT_AGENT_IMAGE,
+                handler,
+            )
+            .map(|config| config.kernel)
+        };
+
+        assert_eq!(
+            kernel(HashMap::new(), &handler).unwrap(),
+            Some(PathBuf::from("/opt/a3s/vmlinux"))
+        );
+        let pick = |name: &str| HashMap::from([(ANN_KERNEL.to_string(), name.to_string())]);
+        assert_eq!(
+            kernel(pick("vmlinux-6.12"), &handler).unwrap(),
+            Some(dir.path().join("vmlinux-6.12"))
+        );
+        assert!(kernel(pick("missing"), &handler).is_err());
+        assert!(kernel(pick("../vmlinux-6.12"), &handler).is_err());
+        assert!(kernel(pick("/etc/passwd"), &handler).is_err());
+        assert!(kernel(pick("vmlinux-6.12"), &RuntimeHandler::default()).is_err());
+
+        // TEE launches are measured against the bundled kernel.
+        let mut annotations = pick("vmlinux-6.12");
+        annotations.insert(ANN_TEE.to_string(), "tdx".to_string());
+        assert!(kernel(annotations, &handler).is_err());
     }
 }
@@ -105,6 +105,13 @@ pub struct Container {
     /// Guest-visible path of the prepared container rootfs.
     #[serde(default)]
     pub rootfs_guest_path: String,
+    /// Host CPUs kubelet's CPU manager assigned (CRI `cpuset_cpus`); empty
+    /// when the container is not pinned.
+    #[serde(default)]
+    pub cpuset_cpus: String,
+    /// Host NUMA nodes kubelet's memory manager assigned (CRI `cpuset_mems`).
+    #[serde(default)]
+    pub cpuset_mems: String,
 }
 
 impl Container {
@@ -229,6 +236,18 @@ impl ContainerStore {
         }
     }
 
+    /// Record the host CPUs and NUMA nodes assigned to a container.
+    pub async fn set_cpuset(&self, id: &str, cpus: &str, mems: &str) -> bool {
+        let mut store = self.containers.write().await;
+        if let Some(c) = store.get_mut(id) {
+            c.cpuset_cpus = cpus.to_string();
+            c.cpuset_mems = mems.to_string();
+            true
+        } else {
+            false
+        }
+    }
+
     /// Update container timestamps when started.
     pub async fn mark_started(&self, id: &str, started_at: i64) -> bool {
         let mut store = self.containers.write().await;
@@ -346,6 +365,8 @@ mod tests {
             log_path: format!("/var/log/pods/{}.log", id),
             rootfs_path: "/".to_string(),
             rootfs_guest_path: format!("/run/a3s/cri/container-rootfs/{sandbox_id}/{id}/rootfs"),
+            cpuset_cpus: String::new(),
+            cpuset_mems: String::new(),
         }
     }
 
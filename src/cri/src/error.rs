@@ -23,6 +23,9 @@ pub fn box_error_to_status(err: BoxError) -> Status {
         BoxError::TeeConfig(msg) => Status::failed_precondition(msg),
         BoxError::TeeNotSupported(msg) => Status::failed_precondition(msg),
         BoxError::ExecError(msg) => Status::internal(msg),
+        BoxError::StoreCorrupted { path, message } => {
+            Status::data_loss(format!("{}: {}", path, message))
+        }
         other => Status::internal(other.to_string()),
     }
 }
@@ -51,6 +54,17 @@ mod tests {
         assert!(status.message().contains("hint"));
     }
 
+    #[test]
+    fn test_store_corrupted_maps_to_data_loss() {
+        let err = BoxError::StoreCorrupted {
+            path: "/var/lib/a3s/networks.json".to_string(),
+            message: "EOF while parsing".to_string(),
+        };
+        let status = box_error_to_status(err);
+        assert_eq!(status.code(), tonic::Code::DataLoss);
+        assert!(status.message().contains("networks.json"));
+    }
+
     #[test]
     fn test_oci_image_error_maps_to_not_found() {
         let err = BoxError::OciImageError("bad image".to_string());
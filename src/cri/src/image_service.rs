@@ -2,6 +2,7 @@
 //!
 //! Maps CRI image operations to A3S Box ImageStore and ImagePuller.
 
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
 
@@ -9,7 +10,8 @@ use futures::Stream;
 use tonic::{Request, Response, Status};
 
 use a3s_box_core::StoredImage;
-use a3s_box_runtime::oci::{ImagePuller, ImageStore, OciImage, RegistryAuth};
+use a3s_box_runtime::oci::{ImagePuller, ImageReference, ImageStore, OciImage, RegistryAuth};
+use tokio::sync::{Mutex, OwnedMutexGuard};
 
 use crate::cri_api::image_service_server::ImageService;
 use crate::cri_api::*;
@@ -118,14 +120,27 @@ fn image_matches_filter(image: &Image, filter: &str) -> bool {
         || image.repo_digests.iter().any(|d| d == filter)
 }
 
-/// A3S Box implementation of the CRI ImageService.
+/// Username Docker credential helpers pair with an identity token.
+const IDENTITY_TOKEN_USERNAME: &str = "<token>";
+
 /// Convert a CRI per-request `AuthConfig` (kubelet fills it from a pod's
-/// `imagePullSecrets`) into a registry credential. Supports the standard
-/// username/password shape and the Docker-config base64 `auth` ("user:pass")
-/// field. Bearer tokens (`identity_token`/`registry_token`) are not modeled by
-/// `RegistryAuth` yet, so a token-only AuthConfig yields `None` and the caller
-/// falls back to the service-default credential.
+/// `imagePullSecrets` or a credential provider plugin) into a registry
+/// credential. Supports the standard username/password shape, which is also
+/// how ECR (`AWS`) and GCR (`oauth2accesstoken`) hand out short-lived
+/// tokens, and the Docker-config base64 `auth` ("user:pass") field. An
+/// `identity_token` is sent as the password for its username, or for
+/// Docker's `<token>` placeholder. A raw `registry_token` would need bearer
+/// auth, which `RegistryAuth` does not model, so a request carrying only
+/// that yields `None` and the caller falls back to the service default.
 fn auth_config_to_registry_auth(auth: &AuthConfig) -> Option<RegistryAuth> {
+    if !auth.identity_token.is_empty() {
+        let username = if auth.username.is_empty() {
+            IDENTITY_TOKEN_USERNAME
+        } else {
+            auth.username.as_str()
+        };
+        return Some(RegistryAuth::basic(username, auth.identity_token.clone()));
+    }
     if !auth.username.is_empty() {
         return Some(RegistryAuth::basic(
             auth.username.clone(),
@@ -145,21 +160,124 @@ fn auth_config_to_registry_auth(auth: &AuthConfig) -> Option<RegistryAuth> {
     None
 }
 
+/// Serializes pulls of the same image.
+///
+/// kubelet pulls once per pod, so pods scheduled together ask for the same
+/// image at the same time. The first pull fetches it; the others wait and
+/// then find it in the store. A waiter whose predecessor failed (say, on
+/// that pod's expired token) still pulls with its own credential.
+#[derive(Default)]
+struct PullLocks {
+    locks: std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+/// Held for the duration of one pull; the last holder drops the map entry.
+struct PullGuard<'a> {
+    locks: &'a PullLocks,
+    key: String,
+    lock: Arc<Mutex<()>>,
+    _held: OwnedMutexGuard<()>,
+}
+
+impl PullLocks {
+    async fn acquire(&self, key: &str) -> PullGuard<'_> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap_or_else(|e| e.into_inner());
+            locks.entry(key.to_string()).or_default().clone()
+        };
+        let held = lock.clone().lock_owned().await;
+        PullGuard {
+            locks: self,
+            key: key.to_string(),
+            lock,
+            _held: held,
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.locks.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl Drop for PullGuard<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.locks.lock().unwrap_or_else(|e| e.into_inner());
+        // The map, this guard and its lock guard are the only references
+        // when nobody else is waiting.
+        if Arc::strong_count(&self.lock) == 3 {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+/// A3S Box implementation of the CRI ImageService.
 pub struct BoxImageService {
     image_store: Arc<ImageStore>,
     image_puller: Arc<ImagePuller>,
+    /// Whether the service-default credential is anonymous, in which case
+    /// each pull looks up the registry's credential afresh.
+    default_anonymous: bool,
+    pull_locks: PullLocks,
 }
 
 impl BoxImageService {
     /// Create a new BoxImageService.
     pub fn new(image_store: Arc<ImageStore>, auth: RegistryAuth) -> Self {
+        let default_anonymous = auth.basic_credentials().is_none();
         let image_puller = Arc::new(ImagePuller::new(image_store.clone(), auth));
         Self {
             image_store,
             image_puller,
+            default_anonymous,
+            pull_locks: PullLocks::default(),
         }
     }
 
+    /// Pull `image` with the request's credential, else the service default.
+    ///
+    /// With no default configured, the registry's credential is resolved on
+    /// every pull from the A3S credential store and Docker config. Credential
+    /// helpers such as `docker-credential-ecr-login` then mint a current
+    /// token each time, instead of the CRI holding one that expires.
+    async fn pull_with_auth(
+        &self,
+        image: &str,
+        request_auth: Option<RegistryAuth>,
+    ) -> Result<(), Status> {
+        let auth = match request_auth {
+            Some(auth) => {
+                tracing::info!(image = %image, "CRI PullImage (request auth)");
+                Some(auth)
+            }
+            None if self.default_anonymous => {
+                tracing::info!(image = %image, "CRI PullImage");
+                let registry = ImageReference::parse(image)
+                    .map_err(box_error_to_status)?
+                    .registry;
+                let auth = tokio::task::spawn_blocking(move || {
+                    RegistryAuth::from_credential_store(&registry)
+                })
+                .await
+                .map_err(|e| Status::internal(format!("credential lookup failed: {e}")))?;
+                auth.basic_credentials().is_some().then_some(auth)
+            }
+            None => {
+                tracing::info!(image = %image, "CRI PullImage");
+                None
+            }
+        };
+        let pulled = match auth {
+            Some(auth) => {
+                ImagePuller::new(self.image_store.clone(), auth)
+                    .pull(image)
+                    .await
+            }
+            None => self.image_puller.pull(image).await,
+        };
+        pulled.map(drop).map_err(box_error_to_status)
+    }
+
     /// Resolve a CRI image reference to its stored content digest (the image
     /// id used by ListImages / ImageStatus).
     ///
@@ -273,25 +391,22 @@ impl ImageService for BoxImageService {
             ));
         }
 
-        // Honor kubelet's per-request credentials (imagePullSecrets). When the
-        // request carries usable auth, pull with a one-off puller built from it;
-        // otherwise fall back to the service-default registry credential.
-        match req.auth.as_ref().and_then(auth_config_to_registry_auth) {
-            Some(auth) => {
-                tracing::info!(image = %image_spec.image, "CRI PullImage (request auth)");
-                ImagePuller::new(self.image_store.clone(), auth)
-                    .pull(&image_spec.image)
-                    .await
-                    .map_err(box_error_to_status)?;
-            }
-            None => {
-                tracing::info!(image = %image_spec.image, "CRI PullImage");
-                self.image_puller
-                    .pull(&image_spec.image)
-                    .await
-                    .map_err(box_error_to_status)?;
-            }
+        // Honor kubelet's per-request credentials (imagePullSecrets). They are
+        // used for this pull only and never cached, so a short-lived registry
+        // token is always the one kubelet just fetched.
+        let request_auth = req.auth.as_ref().and_then(auth_config_to_registry_auth);
+        if request_auth.is_none() && req.auth.is_some_and(|auth| !auth.registry_token.is_empty()) {
+            tracing::warn!(
+                image = %image_spec.image,
+                "Ignoring unsupported registry_token pull credential"
+            );
         }
+        // Concurrent pulls of one image, however it is spelled, share a lock.
+        let key = ImageReference::parse(&image_spec.image)
+            .map(|reference| reference.full_reference())
+            .unwrap_or_else(|_| image_spec.image.clone());
+        let _pulling = self.pull_locks.acquire(&key).await;
+        self.pull_with_auth(&image_spec.image, request_auth).await?;
 
         // CRI uses the content-addressable image id (digest) as the canonical
         // image_ref, so callers can dedupe different tags of the same image.
@@ -756,9 +871,30 @@ mod tests {
         let dbg = format!("{ra:?}");
         assert!(dbg.contains("bob") && dbg.contains("pw123"), "got {dbg}");
 
-        // Bearer-token-only AuthConfig -> None (no RegistryAuth bearer support yet)
+        // Identity token -> password for the given or placeholder username
+        let ra = auth_config_to_registry_auth(&AuthConfig {
+            identity_token: "refresh-tok".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            ra.basic_credentials(),
+            Some(("<token>".to_string(), "refresh-tok".to_string()))
+        );
+        let ra = auth_config_to_registry_auth(&AuthConfig {
+            username: "svc".into(),
+            identity_token: "refresh-tok".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            ra.basic_credentials(),
+            Some(("svc".to_string(), "refresh-tok".to_string()))
+        );
+
+        // Raw registry bearer token -> None (no RegistryAuth bearer support)
         assert!(auth_config_to_registry_auth(&AuthConfig {
-            identity_token: "tok".into(),
+            registry_token: "tok".into(),
             ..Default::default()
         })
         .is_none());
@@ -766,4 +902,26 @@ mod tests {
         // Empty -> None (caller falls back to the service-default credential)
         assert!(auth_config_to_registry_auth(&AuthConfig::default()).is_none());
     }
+
+    #[tokio::test]
+    async fn test_pull_locks_serialize_same_image() {
+        let locks = Arc::new(PullLocks::default());
+        let first = locks.acquire("docker.io/library/nginx:latest").await;
+
+        // Another image is not held up.
+        drop(locks.acquire("docker.io/library/redis:latest").await);
+
+        let waiter = tokio::spawn({
+            let locks = locks.clone();
+            async move {
+                let _second = locks.acquire("docker.io/library/nginx:latest").await;
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        drop(first);
+        waiter.await.unwrap();
+        assert_eq!(locks.len(), 0);
+    }
 }
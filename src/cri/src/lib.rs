@@ -6,6 +6,7 @@
 
 #![allow(clippy::result_large_err)]
 
+pub mod cni;
 pub mod config_mapper;
 pub mod container;
 pub mod error;
@@ -17,6 +18,7 @@ pub mod server;
 pub mod spdy;
 pub mod state;
 pub mod streaming;
+pub mod websocket;
 
 /// Generated CRI v1 protobuf types.
 pub mod cri_api {
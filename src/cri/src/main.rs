@@ -11,8 +11,9 @@ use tracing_subscriber::EnvFilter;
 
 use a3s_box_runtime::oci::{ImageStore, RegistryAuth};
 
-use a3s_box_cri::config_mapper::DEFAULT_AGENT_IMAGE;
-use a3s_box_cri::runtime_service::CriRuntimeOptions;
+use a3s_box_cri::cni::{CniOptions, DEFAULT_CNI_BIN_DIR, DEFAULT_CNI_CONF_DIR};
+use a3s_box_cri::config_mapper::{RuntimeHandler, DEFAULT_AGENT_IMAGE};
+use a3s_box_cri::runtime_service::{CriRuntimeOptions, StatsCollection};
 use a3s_box_cri::server::CriServer;
 
 const AGENT_IMAGE_ENV: &str = "A3S_BOX_CRI_AGENT_IMAGE";
@@ -40,6 +41,77 @@ struct Args {
     /// RuntimeClass-specific agent image override, formatted as HANDLER=IMAGE.
     #[arg(long = "runtime-handler-agent-image", value_name = "HANDLER=IMAGE")]
     runtime_handler_agent_image: Vec<String>,
+
+    /// RuntimeClass-specific VM settings, formatted as
+    /// HANDLER:KEY=VALUE[,KEY=VALUE...] with keys agent-image, tee, kernel,
+    /// kernel-dir and memory-overhead.
+    #[arg(long = "runtime-handler", value_name = "HANDLER:SETTINGS")]
+    runtime_handler: Vec<String>,
+
+    /// How the stats RPCs collect usage: `on-demand` samples every call,
+    /// `cached` reuses samples younger than --stats-cache-ttl.
+    #[arg(long, value_enum, default_value = "on-demand")]
+    stats_collection: StatsMode,
+
+    /// Seconds a cached stats sample stays fresh.
+    #[arg(long, default_value = "10", value_name = "SECONDS")]
+    stats_cache_ttl: u64,
+
+    /// Set up pod networking with the node's CNI plugins instead of passt
+    /// alone.
+    #[arg(long)]
+    cni: bool,
+
+    /// Directory holding CNI network configurations.
+    #[arg(long, default_value = DEFAULT_CNI_CONF_DIR, value_name = "DIR")]
+    cni_conf_dir: PathBuf,
+
+    /// Directory holding CNI plugin binaries; may be repeated.
+    #[arg(long = "cni-bin-dir", default_value = DEFAULT_CNI_BIN_DIR, value_name = "DIR")]
+    cni_bin_dirs: Vec<PathBuf>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum StatsMode {
+    OnDemand,
+    Cached,
+}
+
+impl Args {
+    fn stats_collection(&self) -> StatsCollection {
+        match self.stats_collection {
+            StatsMode::OnDemand => StatsCollection::OnDemand,
+            StatsMode::Cached => StatsCollection::Cached {
+                max_age: std::time::Duration::from_secs(self.stats_cache_ttl),
+            },
+        }
+    }
+
+    fn cni(&self) -> Option<CniOptions> {
+        self.cni.then(|| CniOptions {
+            conf_dir: self.cni_conf_dir.clone(),
+            bin_dirs: self.cni_bin_dirs.clone(),
+        })
+    }
+}
+
+/// Build the per-handler settings: `--runtime-handler` entries, with
+/// `--runtime-handler-agent-image` setting the agent image.
+fn parse_runtime_handlers(
+    handlers: &[String],
+    agent_images: &[String],
+) -> Result<std::collections::HashMap<String, RuntimeHandler>, String> {
+    let mut parsed = std::collections::HashMap::new();
+    for value in handlers {
+        let (name, handler) = RuntimeHandler::parse(value)?;
+        if parsed.insert(name.clone(), handler).is_some() {
+            return Err(format!("Duplicate --runtime-handler for '{name}'"));
+        }
+    }
+    for (name, image) in parse_runtime_handler_agent_images(agent_images)? {
+        parsed.entry(name).or_default().agent_image = Some(image);
+    }
+    Ok(parsed)
 }
 
 fn parse_runtime_handler_agent_images(
@@ -83,12 +155,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         .clone()
         .or_else(|| std::env::var(AGENT_IMAGE_ENV).ok())
         .unwrap_or_else(|| DEFAULT_AGENT_IMAGE.to_string());
-    let runtime_handler_agent_images =
-        parse_runtime_handler_agent_images(&args.runtime_handler_agent_image)
+    let runtime_handlers =
+        parse_runtime_handlers(&args.runtime_handler, &args.runtime_handler_agent_image)
             .map_err(|e| format!("Invalid CRI runtime options: {e}"))?;
     let runtime_options = CriRuntimeOptions {
         default_agent_image,
-        runtime_handler_agent_images,
+        runtime_handlers,
+        stats_collection: args.stats_collection(),
+        cni: args.cni(),
     };
 
     // Resolve image directory (expand ~)
@@ -104,7 +178,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         image_dir = %image_dir.display(),
         cache_size = args.image_cache_size,
         agent_image = %runtime_options.default_agent_image,
-        runtime_handler_overrides = runtime_options.runtime_handler_agent_images.len(),
+        runtime_handlers = runtime_options.runtime_handlers.len(),
+        stats_collection = ?runtime_options.stats_collection,
+        cni_conf_dir = ?runtime_options.cni.as_ref().map(|cni| cni.conf_dir.display().to_string()),
         "Starting A3S Box CRI Runtime"
     );
 
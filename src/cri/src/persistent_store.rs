@@ -96,6 +96,16 @@ impl PersistentCriStore {
         }
     }
 
+    pub async fn set_container_cpuset(&self, id: &str, cpus: &str, mems: &str) -> bool {
+        let updated = self.containers.set_cpuset(id, cpus, mems).await;
+        if updated {
+            if let Err(e) = self.persist().await {
+                tracing::warn!(error = %e, "Failed to persist CRI state after set_container_cpuset");
+            }
+        }
+        updated
+    }
+
     pub async fn mark_container_started(&self, id: &str, started_at: i64) -> bool {
         let updated = self.containers.mark_started(id, started_at).await;
         if updated {
@@ -202,6 +212,8 @@ mod tests {
             additional_ips: vec![],
             dns: crate::sandbox::SandboxDns::default(),
             container_ports: vec![],
+            cni_network: None,
+            host_cgroup: None,
         }
     }
 
@@ -234,6 +246,8 @@ mod tests {
             log_path: String::new(),
             rootfs_path: "/".to_string(),
             rootfs_guest_path: format!("/run/a3s/cri/container-rootfs/{sandbox_id}/{id}/rootfs"),
+            cpuset_cpus: String::new(),
+            cpuset_mems: String::new(),
         }
     }
 
@@ -0,0 +1,515 @@
+//! Host cgroup placement for pod sandbox VMs.
+//!
+//! kubelet creates one cgroup per pod under its QoS tier and passes it as the
+//! sandbox's `cgroup_parent`: a path such as `/kubepods/burstable/pod<uid>`
+//! with the cgroupfs driver, or a slice name such as
+//! `kubepods-burstable-pod<uid>.slice` with the systemd driver, which
+//! [`expand_slice`] turns into its place in the hierarchy. Only the unified
+//! (v2) hierarchy is supported. In the microVM-per-pod model the shim process *is* the
+//! pod on the host, so it moves into a child cgroup there. kubelet then
+//! accounts the VM's CPU and memory to the pod and its QoS tier, and evicts
+//! by them. The child cgroup is capped at the pod's limits plus the VM's own
+//! overhead.
+//!
+//! kubelet's CPU and memory managers assign host CPUs and NUMA nodes per
+//! container. Every container of a pod runs on the pod VM's vCPUs, so the VM
+//! as a whole is confined to the union of its containers' assignments.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use a3s_box_runtime::numa::{format_cpu_list, parse_cpu_list};
+
+use crate::container::{Container, ContainerState};
+use crate::cri_api::{LinuxContainerResources, LinuxPodSandboxConfig};
+
+/// Where the unified cgroup hierarchy is mounted.
+pub(super) const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Memory the shim and VMM need beyond guest RAM, when the RuntimeClass
+/// declares no overhead.
+const DEFAULT_VM_OVERHEAD_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Kubernetes QoS class of a pod.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum QosClass {
+    Guaranteed,
+    Burstable,
+    BestEffort,
+}
+
+impl QosClass {
+    /// The class kubelet filed the pod under, from an expanded parent path:
+    /// `besteffort` and `burstable` pods live in a tier cgroup of that name
+    /// (`kubepods-burstable.slice` with the systemd driver), guaranteed pods
+    /// directly under the kubepods root.
+    fn from_cgroup_parent(cgroup_parent: &str) -> Self {
+        let in_tier = |tier: &str| {
+            cgroup_parent
+                .split('/')
+                .any(|part| match part.strip_suffix(".slice") {
+                    Some(slice) => slice.ends_with(&format!("-{tier}")),
+                    None => part == tier,
+                })
+        };
+        if in_tier("besteffort") {
+            Self::BestEffort
+        } else if in_tier("burstable") {
+            Self::Burstable
+        } else {
+            Self::Guaranteed
+        }
+    }
+
+    /// The `oom_score_adj` kubelet gives this class's processes, so the host
+    /// OOM killer picks pods in the order eviction would.
+    fn oom_score_adj(self) -> Option<i32> {
+        match self {
+            Self::Guaranteed => Some(-997),
+            Self::BestEffort => Some(1000),
+            Self::Burstable => None,
+        }
+    }
+}
+
+/// A sandbox's host cgroup and the caps written into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct PodCgroup {
+    /// Path below the cgroup root, e.g. `kubepods/burstable/pod<uid>/a3s-box-<id>`.
+    pub(super) path: PathBuf,
+    pub(super) qos: QosClass,
+    /// `memory.max`: guest RAM plus overhead, when the pod has a memory limit.
+    memory_max: Option<u64>,
+    /// `cpu.max` as `(quota, period)`, when the pod has a CPU limit.
+    cpu_max: Option<(i64, i64)>,
+    /// `cpu.weight` from the pod's CPU shares.
+    cpu_weight: Option<u64>,
+    oom_score_adj: Option<i32>,
+}
+
+impl PodCgroup {
+    /// The cgroup for a sandbox with the given Linux config and guest RAM,
+    /// or `None` when kubelet passed no usable `cgroup_parent`.
+    pub(super) fn for_sandbox(
+        sandbox_id: &str,
+        linux: &LinuxPodSandboxConfig,
+        vm_memory_bytes: u64,
+    ) -> Option<Self> {
+        let parent = linux.cgroup_parent.trim();
+        let parent = if parent.ends_with(".slice") && !parent.contains('/') {
+            expand_slice(parent)?
+        } else {
+            parent.trim_matches('/').to_string()
+        };
+        if parent.is_empty() || parent.split('/').any(|part| part == "..") {
+            return None;
+        }
+        let qos = QosClass::from_cgroup_parent(&parent);
+        let pod = linux.resources.clone().unwrap_or_default();
+        let overhead = linux.overhead.clone().unwrap_or_default();
+
+        let memory_max = (pod.memory_limit_in_bytes > 0).then(|| {
+            let overhead = u64::try_from(overhead.memory_limit_in_bytes)
+                .ok()
+                .filter(|bytes| *bytes > 0)
+                .unwrap_or(DEFAULT_VM_OVERHEAD_BYTES);
+            vm_memory_bytes.max(pod.memory_limit_in_bytes as u64) + overhead
+        });
+        let cpu_max = (pod.cpu_quota > 0 && pod.cpu_period > 0).then(|| {
+            (
+                pod.cpu_quota + overhead_quota(&overhead, pod.cpu_period),
+                pod.cpu_period,
+            )
+        });
+        let cpu_weight = (pod.cpu_shares > 0).then(|| shares_to_weight(pod.cpu_shares as u64));
+
+        Some(Self {
+            path: Path::new(&parent).join(format!("a3s-box-{sandbox_id}")),
+            qos,
+            memory_max,
+            cpu_max,
+            cpu_weight,
+            oom_score_adj: qos.oom_score_adj(),
+        })
+    }
+
+    /// Create the cgroup under `root`, write its caps, and move `pid` into
+    /// it.
+    pub(super) fn place(&self, root: &Path, pid: u32) -> std::io::Result<()> {
+        let dir = root.join(&self.path);
+        std::fs::create_dir_all(&dir)?;
+        if let Some(memory_max) = self.memory_max {
+            std::fs::write(dir.join("memory.max"), memory_max.to_string())?;
+        }
+        if let Some((quota, period)) = self.cpu_max {
+            std::fs::write(dir.join("cpu.max"), format!("{quota} {period}"))?;
+        }
+        if let Some(weight) = self.cpu_weight {
+            std::fs::write(dir.join("cpu.weight"), weight.to_string())?;
+        }
+        std::fs::write(dir.join("cgroup.procs"), pid.to_string())
+    }
+
+    /// Set the VM process's `oom_score_adj` for the pod's QoS class.
+    pub(super) fn apply_oom_score_adj(&self, proc_root: &Path, pid: u32) -> std::io::Result<()> {
+        match self.oom_score_adj {
+            Some(adj) => std::fs::write(
+                proc_root.join(pid.to_string()).join("oom_score_adj"),
+                adj.to_string(),
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The path below the cgroup root of a systemd slice, as systemd nests
+/// them: `kubepods-burstable-pod1.slice` lives at
+/// `kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod1.slice`.
+/// `None` for a malformed name.
+pub(super) fn expand_slice(slice: &str) -> Option<String> {
+    let name = slice.strip_suffix(".slice")?;
+    if name == "-" {
+        return Some(String::new());
+    }
+    if name.is_empty() || name.contains('/') || name.split('-').any(str::is_empty) {
+        return None;
+    }
+    let mut path = Vec::new();
+    let mut prefix = String::new();
+    for part in name.split('-') {
+        if !prefix.is_empty() {
+            prefix.push('-');
+        }
+        prefix.push_str(part);
+        path.push(format!("{prefix}.slice"));
+    }
+    Some(path.join("/"))
+}
+
+/// Whether the unified (v2) hierarchy is mounted at `root`; pod placement
+/// writes v2 interface files only.
+pub(super) fn is_unified_hierarchy(root: &Path) -> bool {
+    root.join("cgroup.controllers").is_file()
+}
+
+/// Host CPUs and NUMA nodes a pod VM is confined to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct PodCpuset {
+    pub(super) cpus: BTreeSet<u32>,
+    pub(super) mems: BTreeSet<u32>,
+}
+
+impl PodCpuset {
+    /// The union of the assignments of a pod's live containers, or `None`
+    /// when none of them is pinned.
+    pub(super) fn for_containers(containers: &[Container]) -> Option<Self> {
+        let mut cpuset = Self {
+            cpus: BTreeSet::new(),
+            mems: BTreeSet::new(),
+        };
+        for container in containers {
+            if container.state == ContainerState::Exited {
+                continue;
+            }
+            // Stored values were validated when kubelet sent them.
+            cpuset
+                .cpus
+                .extend(parse_cpu_list(&container.cpuset_cpus).unwrap_or_default());
+            cpuset
+                .mems
+                .extend(parse_cpu_list(&container.cpuset_mems).unwrap_or_default());
+        }
+        (!cpuset.cpus.is_empty() || !cpuset.mems.is_empty()).then_some(cpuset)
+    }
+
+    /// Write `cpuset.cpus` and `cpuset.mems` into the sandbox cgroup `path`
+    /// below `root`.
+    pub(super) fn place(&self, root: &Path, path: &Path) -> std::io::Result<()> {
+        let dir = root.join(path);
+        if !self.cpus.is_empty() {
+            std::fs::write(dir.join("cpuset.cpus"), format_cpu_list(&self.cpus))?;
+        }
+        if !self.mems.is_empty() {
+            std::fs::write(dir.join("cpuset.mems"), format_cpu_list(&self.mems))?;
+        }
+        Ok(())
+    }
+}
+
+/// Check a CRI `cpuset_cpus` / `cpuset_mems` list such as `0-3,8`.
+pub(super) fn validate_cpuset(field: &str, value: &str) -> Result<(), String> {
+    parse_cpu_list(value)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid {field} {value:?}: {e}"))
+}
+
+/// Remove a sandbox cgroup once its processes have exited; one that is
+/// already gone is fine.
+pub(super) fn remove_cgroup(root: &Path, path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_dir(root.join(path)) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// The RuntimeClass CPU overhead, rescaled to the pod's CFS period.
+fn overhead_quota(overhead: &LinuxContainerResources, period: i64) -> i64 {
+    if overhead.cpu_quota <= 0 || overhead.cpu_period <= 0 {
+        return 0;
+    }
+    overhead.cpu_quota * period / overhead.cpu_period
+}
+
+/// cgroup v1 CPU shares (2..=262144) to a v2 weight (1..=10000), as runc
+/// converts them.
+fn shares_to_weight(shares: u64) -> u64 {
+    let shares = shares.clamp(2, 262_144);
+    1 + ((shares - 2) * 9999) / 262_142
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linux(
+        cgroup_parent: &str,
+        resources: Option<LinuxContainerResources>,
+    ) -> LinuxPodSandboxConfig {
+        LinuxPodSandboxConfig {
+            cgroup_parent: cgroup_parent.to_string(),
+            resources,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pod_cgroup_from_kubelet_config() {
+        assert_eq!(
+            PodCgroup::for_sandbox("sb-1", &linux("", None), 1 << 30),
+            None
+        );
+        assert_eq!(
+            PodCgroup::for_sandbox("sb-1", &linux("/kubepods/../etc", None), 1 << 30),
+            None
+        );
+
+        let best_effort =
+            PodCgroup::for_sandbox("sb-1", &linux("/kubepods/besteffort/pod-a", None), 1 << 30)
+                .unwrap();
+        assert_eq!(
+            best_effort.path,
+            Path::new("kubepods/besteffort/pod-a/a3s-box-sb-1")
+        );
+        assert_eq!(best_effort.qos, QosClass::BestEffort);
+        assert_eq!(best_effort.memory_max, None);
+        assert_eq!(best_effort.oom_score_adj, Some(1000));
+
+        let resources = LinuxContainerResources {
+            cpu_period: 100_000,
+            cpu_quota: 50_000,
+            cpu_shares: 512,
+            memory_limit_in_bytes: 512 * 1024 * 1024,
+            ..Default::default()
+        };
+        let mut config = linux("/kubepods/pod-b", Some(resources));
+        let guaranteed = PodCgroup::for_sandbox("sb-2", &config, 512 * 1024 * 1024).unwrap();
+        assert_eq!(guaranteed.qos, QosClass::Guaranteed);
+        assert_eq!(guaranteed.memory_max, Some((512 + 128) * 1024 * 1024));
+        assert_eq!(guaranteed.cpu_max, Some((50_000, 100_000)));
+        assert_eq!(guaranteed.cpu_weight, Some(20));
+        assert_eq!(guaranteed.oom_score_adj, Some(-997));
+
+        // A RuntimeClass overhead replaces the default VM allowance, and a
+        // VM sized above the limit (the boot floor) is covered.
+        config.overhead = Some(LinuxContainerResources {
+            cpu_period: 10_000,
+            cpu_quota: 2_500,
+            memory_limit_in_bytes: 64 * 1024 * 1024,
+            ..Default::default()
+        });
+        let with_overhead = PodCgroup::for_sandbox("sb-2", &config, 1024 * 1024 * 1024).unwrap();
+        assert_eq!(with_overhead.memory_max, Some((1024 + 64) * 1024 * 1024));
+        assert_eq!(with_overhead.cpu_max, Some((75_000, 100_000)));
+
+        let burstable =
+            PodCgroup::for_sandbox("sb-3", &linux("kubepods/burstable/pod-c", None), 1 << 30)
+                .unwrap();
+        assert_eq!(burstable.qos, QosClass::Burstable);
+        assert_eq!(burstable.oom_score_adj, None);
+    }
+
+    #[test]
+    fn test_pod_cgroup_from_systemd_slice() {
+        let burstable = PodCgroup::for_sandbox(
+            "sb-1",
+            &linux("kubepods-burstable-pod1234.slice", None),
+            1 << 30,
+        )
+        .unwrap();
+        assert_eq!(
+            burstable.path,
+            Path::new(
+                "kubepods.slice/kubepods-burstable.slice/\
+                 kubepods-burstable-pod1234.slice/a3s-box-sb-1"
+            )
+        );
+        assert_eq!(burstable.qos, QosClass::Burstable);
+        assert_eq!(burstable.oom_score_adj, None);
+
+        let best_effort = PodCgroup::for_sandbox(
+            "sb-2",
+            &linux("kubepods-besteffort-pod5678.slice", None),
+            1 << 30,
+        )
+        .unwrap();
+        assert_eq!(best_effort.qos, QosClass::BestEffort);
+
+        let guaranteed =
+            PodCgroup::for_sandbox("sb-3", &linux("kubepods-pod9abc.slice", None), 1 << 30)
+                .unwrap();
+        assert_eq!(
+            guaranteed.path,
+            Path::new("kubepods.slice/kubepods-pod9abc.slice/a3s-box-sb-3")
+        );
+        assert_eq!(guaranteed.qos, QosClass::Guaranteed);
+
+        assert_eq!(expand_slice("-.slice").as_deref(), Some(""));
+        assert_eq!(expand_slice("kubepods--pod.slice"), None);
+        assert_eq!(expand_slice("-kubepods.slice"), None);
+        assert_eq!(
+            PodCgroup::for_sandbox("sb-4", &linux("-.slice", None), 1 << 30),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_unified_hierarchy() {
+        let root = tempfile::tempdir().unwrap();
+        assert!(!is_unified_hierarchy(root.path()));
+        std::fs::write(root.path().join("cgroup.controllers"), "cpu memory\n").unwrap();
+        assert!(is_unified_hierarchy(root.path()));
+    }
+
+    #[test]
+    fn test_place_writes_caps_and_moves_pid() {
+        let root = tempfile::tempdir().unwrap();
+        let resources = LinuxContainerResources {
+            cpu_period: 100_000,
+            cpu_quota: 200_000,
+            memory_limit_in_bytes: 256 * 1024 * 1024,
+            ..Default::default()
+        };
+        let cgroup = PodCgroup::for_sandbox(
+            "sb-1",
+            &linux("/kubepods/burstable/pod-a", Some(resources)),
+            256 * 1024 * 1024,
+        )
+        .unwrap();
+        cgroup.place(root.path(), 4242).unwrap();
+
+        let dir = root.path().join("kubepods/burstable/pod-a/a3s-box-sb-1");
+        let read = |file: &str| std::fs::read_to_string(dir.join(file)).unwrap();
+        assert_eq!(read("memory.max"), (384 * 1024 * 1024).to_string());
+        assert_eq!(read("cpu.max"), "200000 100000");
+        assert_eq!(read("cgroup.procs"), "4242");
+        assert!(!dir.join("cpu.weight").exists());
+
+        // Removal needs an empty directory, as a real cgroup is once its
+        // processes are gone.
+        for file in ["memory.max", "cpu.max", "cgroup.procs"] {
+            std::fs::remove_file(dir.join(file)).unwrap();
+        }
+        remove_cgroup(root.path(), &cgroup.path).unwrap();
+        assert!(!dir.exists());
+        remove_cgroup(root.path(), &cgroup.path).unwrap();
+    }
+
+    #[test]
+    fn test_oom_score_adj_by_qos() {
+        let proc_root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(proc_root.path().join("4242")).unwrap();
+        let guaranteed =
+            PodCgroup::for_sandbox("sb-1", &linux("/kubepods/pod-a", None), 1 << 30).unwrap();
+        guaranteed
+            .apply_oom_score_adj(proc_root.path(), 4242)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(proc_root.path().join("4242/oom_score_adj")).unwrap(),
+            "-997"
+        );
+    }
+
+    #[test]
+    fn test_pod_cpuset_unions_live_containers() {
+        let container = |state, cpus: &str, mems: &str| Container {
+            id: String::new(),
+            sandbox_id: "sb-1".to_string(),
+            name: String::new(),
+            attempt: 0,
+            image_ref: String::new(),
+            resolved_image_digest: String::new(),
+            resolved_image_path: String::new(),
+            command: Vec::new(),
+            args: Vec::new(),
+            env: Vec::new(),
+            working_dir: String::new(),
+            user: None,
+            stdin: false,
+            stdin_once: false,
+            tty: false,
+            mounts: Vec::new(),
+            state,
+            created_at: 0,
+            started_at: 0,
+            finished_at: 0,
+            exit_code: 0,
+            oom_killed: false,
+            labels: Default::default(),
+            annotations: Default::default(),
+            log_path: String::new(),
+            rootfs_path: String::new(),
+            rootfs_guest_path: String::new(),
+            cpuset_cpus: cpus.to_string(),
+            cpuset_mems: mems.to_string(),
+        };
+        assert_eq!(
+            PodCpuset::for_containers(&[container(ContainerState::Running, "", "")]),
+            None
+        );
+
+        let cpuset = PodCpuset::for_containers(&[
+            container(ContainerState::Running, "2-3", "0"),
+            container(ContainerState::Created, "6", ""),
+            container(ContainerState::Exited, "8-9", "1"),
+        ])
+        .unwrap();
+        assert_eq!(format_cpu_list(&cpuset.cpus), "2-3,6");
+        assert_eq!(format_cpu_list(&cpuset.mems), "0");
+
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("kubepods/pod-a/a3s-box-sb-1")).unwrap();
+        cpuset
+            .place(root.path(), Path::new("kubepods/pod-a/a3s-box-sb-1"))
+            .unwrap();
+        let dir = root.path().join("kubepods/pod-a/a3s-box-sb-1");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("cpuset.cpus")).unwrap(),
+            "2-3,6"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("cpuset.mems")).unwrap(),
+            "0"
+        );
+
+        assert!(validate_cpuset("cpuset_cpus", "0-3,8").is_ok());
+        assert!(validate_cpuset("cpuset_cpus", "3-1").is_err());
+    }
+
+    #[test]
+    fn test_shares_to_weight() {
+        assert_eq!(shares_to_weight(2), 1);
+        assert_eq!(shares_to_weight(1024), 39);
+        assert_eq!(shares_to_weight(262_144), 10_000);
+        assert_eq!(shares_to_weight(1), 1);
+    }
+}
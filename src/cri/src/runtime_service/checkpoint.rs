@@ -0,0 +1,392 @@
+//! CRI `CheckpointContainer` archives.
+//!
+//! A pod VM has no CRIU to dump process state from the host, so a checkpoint
+//! captures the container's filesystem while the pod VM is paused. The
+//! archive follows the layout kubelet's checkpoint API and `checkpointctl`
+//! expect from CRI-O:
+//!
+//! - `config.dump` — container identity, image and timestamps.
+//! - `spec.dump` — an OCI runtime spec with the process, mounts and the
+//!   pod's Kubernetes annotations.
+//! - `rootfs-diff.tar` — the container's root filesystem. It holds the whole
+//!   tree rather than a diff against the image, and leaves out what is
+//!   mounted from CRI mounts (volumes, secrets, service account tokens).
+//!
+//! There is no `checkpoint/` directory of CRIU images: the archive is for
+//! forensic inspection and cannot be restored into a running process.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::container::Container;
+use crate::sandbox::PodSandbox;
+
+/// Write the checkpoint archive of `container` to `location`.
+///
+/// The archive is built next to `location` and renamed into place, so
+/// `location` only ever holds a complete archive. Nothing is published once
+/// `cancelled` is set.
+pub(super) fn write_checkpoint_archive(
+    location: &Path,
+    container: &Container,
+    sandbox: &PodSandbox,
+    checkpointed_at: DateTime<Utc>,
+    cancelled: &AtomicBool,
+) -> io::Result<()> {
+    let parent = location
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} has no parent directory", location.display()),
+            )
+        })?;
+    std::fs::create_dir_all(parent)?;
+    let staging = tempfile::Builder::new()
+        .prefix(".a3s-checkpoint-")
+        .tempdir_in(parent)?;
+
+    let rootfs_tar = staging.path().join("rootfs-diff.tar");
+    write_rootfs_tar(
+        &rootfs_tar,
+        Path::new(&container.rootfs_path),
+        &excluded_mount_paths(container),
+    )?;
+
+    let archive_path = staging.path().join("checkpoint.tar");
+    let mut archive = tar::Builder::new(create_private(&archive_path)?);
+    append_json(
+        &mut archive,
+        "config.dump",
+        &config_dump(container, checkpointed_at),
+    )?;
+    append_json(&mut archive, "spec.dump", &spec_dump(container, sandbox))?;
+    archive.append_path_with_name(&rootfs_tar, "rootfs-diff.tar")?;
+    archive.into_inner()?.sync_all()?;
+
+    if cancelled.load(Ordering::Relaxed) {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "checkpoint was cancelled",
+        ));
+    }
+    std::fs::rename(&archive_path, location)
+}
+
+/// Create `path` readable by its owner only: the rootfs may hold credentials.
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Rootfs-relative paths of the container's CRI mounts.
+fn excluded_mount_paths(container: &Container) -> Vec<PathBuf> {
+    container
+        .mounts
+        .iter()
+        .map(|mount| PathBuf::from(mount.container_path.trim_start_matches('/')))
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect()
+}
+
+fn write_rootfs_tar(path: &Path, rootfs: &Path, excluded: &[PathBuf]) -> io::Result<()> {
+    let mut builder = tar::Builder::new(create_private(path)?);
+    builder.follow_symlinks(false);
+    append_tree(&mut builder, rootfs, Path::new(""), excluded)?;
+    builder.into_inner()?.sync_all()
+}
+
+fn append_tree(
+    builder: &mut tar::Builder<File>,
+    dir: &Path,
+    relative: &Path,
+    excluded: &[PathBuf],
+) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = relative.join(entry.file_name());
+        if excluded.iter().any(|mount| name.starts_with(mount)) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            // Sockets have no archive representation.
+            if file_type.is_socket() {
+                continue;
+            }
+        }
+        builder.append_path_with_name(entry.path(), &name)?;
+        if file_type.is_dir() {
+            append_tree(builder, &entry.path(), &name, excluded)?;
+        }
+    }
+    Ok(())
+}
+
+fn append_json(
+    builder: &mut tar::Builder<File>,
+    name: &str,
+    value: &serde_json::Value,
+) -> io::Result<()> {
+    let data = serde_json::to_vec_pretty(value)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data.as_slice())
+}
+
+fn rfc3339_from_nanos(nanos: i64) -> String {
+    DateTime::from_timestamp_nanos(nanos).to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+fn config_dump(container: &Container, checkpointed_at: DateTime<Utc>) -> serde_json::Value {
+    serde_json::json!({
+        "id": container.id,
+        "name": container.name,
+        "rootfsImage": container.image_ref,
+        "rootfsImageRef": container.status_image_ref(),
+        "rootfsImageName": container.image_ref,
+        "runtime": "a3s-box",
+        "createdTime": rfc3339_from_nanos(container.created_at),
+        "checkpointedTime": checkpointed_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+        "restored": false,
+    })
+}
+
+fn spec_dump(container: &Container, sandbox: &PodSandbox) -> serde_json::Value {
+    let mut annotations = container.annotations.clone();
+    annotations.extend(HashMap::from([
+        (
+            "io.kubernetes.container.name".to_string(),
+            container.name.clone(),
+        ),
+        ("io.kubernetes.pod.name".to_string(), sandbox.name.clone()),
+        (
+            "io.kubernetes.pod.namespace".to_string(),
+            sandbox.namespace.clone(),
+        ),
+        ("io.kubernetes.pod.uid".to_string(), sandbox.uid.clone()),
+        (
+            "io.kubernetes.cri.sandbox-id".to_string(),
+            sandbox.id.clone(),
+        ),
+        (
+            "io.kubernetes.cri.image-name".to_string(),
+            container.image_ref.clone(),
+        ),
+    ]));
+    let args: Vec<&String> = container.command.iter().chain(&container.args).collect();
+    let env: Vec<String> = container
+        .env
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect();
+    let cwd = if container.working_dir.is_empty() {
+        "/"
+    } else {
+        container.working_dir.as_str()
+    };
+    let mounts: Vec<serde_json::Value> = container
+        .mounts
+        .iter()
+        .map(|mount| {
+            serde_json::json!({
+                "destination": mount.container_path,
+                "type": "bind",
+                "source": mount.host_path,
+                "options": ["rbind", if mount.readonly { "ro" } else { "rw" }],
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "ociVersion": "1.0.2",
+        "process": {
+            "terminal": container.tty,
+            "args": args,
+            "env": env,
+            "cwd": cwd,
+        },
+        "root": { "path": "rootfs" },
+        "hostname": sandbox.name,
+        "mounts": mounts,
+        "annotations": annotations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::{ContainerMount, ContainerState};
+    use crate::sandbox::{SandboxDns, SandboxState};
+
+    fn container(rootfs: &Path) -> Container {
+        Container {
+            id: "c-1".to_string(),
+            sandbox_id: "sb-1".to_string(),
+            name: "app".to_string(),
+            attempt: 0,
+            image_ref: "docker.io/library/busybox:latest".to_string(),
+            resolved_image_digest: "sha256:abc".to_string(),
+            resolved_image_path: String::new(),
+            command: vec!["sleep".to_string()],
+            args: vec!["3600".to_string()],
+            env: vec![("HOME".to_string(), "/root".to_string())],
+            working_dir: String::new(),
+            user: None,
+            stdin: false,
+            stdin_once: false,
+            tty: false,
+            mounts: vec![ContainerMount {
+                container_path: "/var/run/secrets".to_string(),
+                host_path: "/var/lib/kubelet/pods/uid/secrets".to_string(),
+                readonly: true,
+                selinux_relabel: false,
+                propagation: 0,
+            }],
+            state: ContainerState::Running,
+            created_at: 1_700_000_000_000_000_000,
+            started_at: 0,
+            finished_at: 0,
+            exit_code: 0,
+            oom_killed: false,
+            labels: HashMap::new(),
+            annotations: HashMap::new(),
+            log_path: String::new(),
+            rootfs_path: rootfs.to_string_lossy().to_string(),
+            rootfs_guest_path: "/run/a3s/cri/container-rootfs/c-1".to_string(),
+            cpuset_cpus: String::new(),
+            cpuset_mems: String::new(),
+        }
+    }
+
+    fn sandbox() -> PodSandbox {
+        PodSandbox {
+            id: "sb-1".to_string(),
+            name: "web".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid".to_string(),
+            attempt: 0,
+            state: SandboxState::Ready,
+            created_at: 0,
+            labels: HashMap::new(),
+            annotations: HashMap::new(),
+            log_directory: String::new(),
+            runtime_handler: String::new(),
+            network_ip: String::new(),
+            additional_ips: Vec::new(),
+            dns: SandboxDns::default(),
+            container_ports: Vec::new(),
+            cni_network: None,
+            host_cgroup: None,
+        }
+    }
+
+    fn entry_names<R: io::Read>(archive: &mut tar::Archive<R>) -> Vec<String> {
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_checkpoint_archive_layout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rootfs = tmp.path().join("rootfs");
+        std::fs::create_dir_all(rootfs.join("etc")).unwrap();
+        std::fs::write(rootfs.join("etc/hostname"), "web\n").unwrap();
+        std::fs::create_dir_all(rootfs.join("var/run/secrets")).unwrap();
+        std::fs::write(rootfs.join("var/run/secrets/token"), "secret").unwrap();
+        let location = tmp.path().join("checkpoints/checkpoint.tar");
+
+        write_checkpoint_archive(
+            &location,
+            &container(&rootfs),
+            &sandbox(),
+            Utc::now(),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let mut archive = tar::Archive::new(File::open(&location).unwrap());
+        let mut config = None;
+        let mut spec = None;
+        let mut rootfs_entries = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().to_string();
+            match path.as_str() {
+                "config.dump" => config = Some(serde_json::from_reader(&mut entry).unwrap()),
+                "spec.dump" => spec = Some(serde_json::from_reader(&mut entry).unwrap()),
+                "rootfs-diff.tar" => rootfs_entries = entry_names(&mut tar::Archive::new(entry)),
+                other => panic!("unexpected archive entry {other}"),
+            }
+        }
+        let config: serde_json::Value = config.unwrap();
+        assert_eq!(config["id"], "c-1");
+        assert_eq!(config["rootfsImageRef"], "sha256:abc");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&location).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(config["createdTime"], "2023-11-14T22:13:20.000000000Z");
+        let spec: serde_json::Value = spec.unwrap();
+        assert_eq!(
+            spec["process"]["args"],
+            serde_json::json!(["sleep", "3600"])
+        );
+        assert_eq!(
+            spec["annotations"]["io.kubernetes.pod.namespace"],
+            "default"
+        );
+        assert_eq!(spec["mounts"][0]["options"][1], "ro");
+        assert!(rootfs_entries.contains(&"etc/hostname".to_string()));
+        assert!(rootfs_entries.contains(&"var/run".to_string()));
+        assert!(!rootfs_entries.iter().any(|name| name.contains("secrets")));
+        // The staging directory is gone once the archive is in place.
+        assert_eq!(
+            std::fs::read_dir(location.parent().unwrap())
+                .unwrap()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_cancelled_checkpoint_publishes_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rootfs = tmp.path().join("rootfs");
+        std::fs::create_dir_all(&rootfs).unwrap();
+        let location = tmp.path().join("checkpoint.tar");
+
+        let result = write_checkpoint_archive(
+            &location,
+            &container(&rootfs),
+            &sandbox(),
+            Utc::now(),
+            &AtomicBool::new(true),
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Interrupted);
+        assert!(!location.exists());
+    }
+}
@@ -101,11 +101,11 @@ pub(super) fn container_mount_to_cri(mount: &ContainerMount) -> Mount {
 /// with a clear error, matching the fail-closed handling of unsupported mount
 /// propagation above.
 ///
-/// `HostPID` (`pid == NODE`) is NOT rejected: all of a pod's processes already
-/// share the single VM-wide PID namespace (incl. the VM's PID 1), which is the
-/// broadest PID namespace available in the guest — there is no separate host
-/// PID namespace to be denied, so HostPID is legitimately satisfied. `POD`/
-/// `CONTAINER`/`TARGET` are likewise accepted (one shared VM namespace set).
+/// `HostPID` (`pid == NODE`) is NOT rejected: the VM-wide PID namespace (incl.
+/// the VM's PID 1) is the broadest PID namespace available in the guest —
+/// there is no separate host PID namespace to be denied, so HostPID is
+/// legitimately satisfied. `POD` shares that namespace too; `CONTAINER` and
+/// `TARGET` get a private or a sibling's namespace (see `pid_namespace_env`).
 pub(super) fn validate_namespace_options(
     options: Option<&NamespaceOption>,
     context: &str,
@@ -147,13 +147,10 @@ fn validate_container_mount(mount: &Mount) -> Result<(), Status> {
             mount.container_path
         )));
     }
-    // Writable mounts are accepted but materialized by COPYING the source into
-    // the container rootfs (microVM-backed containers cannot bind-mount host
-    // paths post-boot). The container sees the contents and may write to its
-    // copy, but writes do NOT propagate back to the host source — sufficient for
-    // read-oriented volumes (configMap/secret/downwardAPI) and the basic volume
-    // conformance; true host propagation (and the propagation modes below) needs
-    // a shared mount and is intentionally still rejected.
+    // Mounts are bind-mounted into the virtio-fs-shared container rootfs (see
+    // `mounts`), so writes reach the host source and every container of the pod
+    // that mounts the same emptyDir/hostPath sees them. Propagation modes other
+    // than private need a shared mount and are still rejected below.
     //
     // SELinux relabeling is a no-op on this non-SELinux runtime; accept it
     // rather than failing the container so labeled volumes still work.
@@ -422,23 +419,78 @@ pub(super) fn stop_container_wait_duration(timeout_seconds: i64) -> tokio::time:
     tokio::time::Duration::from_secs(timeout_seconds as u64)
 }
 
-// ── Container event helpers ──────────────────────────────────────────
-
-pub(super) fn container_event_response(
-    container_id: &str,
-    pod_sandbox_id: &str,
-    container_event_type: ContainerEventType,
-    created_at: i64,
-    reason: impl Into<String>,
-    message: impl Into<String>,
-) -> ContainerEventResponse {
-    ContainerEventResponse {
-        container_id: container_id.to_string(),
-        pod_sandbox_id: pod_sandbox_id.to_string(),
-        container_event_type: container_event_type as i32,
-        created_at,
-        reason: reason.into(),
-        message: message.into(),
+// ── Status helpers ───────────────────────────────────────────────────
+
+/// The CRI status of a pod sandbox, as reported by PodSandboxStatus and
+/// container events.
+pub(super) fn pod_sandbox_status_to_cri(sandbox: &PodSandbox) -> PodSandboxStatus {
+    let state = match sandbox.state {
+        SandboxState::Ready => PodSandboxState::SandboxReady,
+        SandboxState::NotReady | SandboxState::Removed => PodSandboxState::SandboxNotready,
+    };
+    PodSandboxStatus {
+        id: sandbox.id.clone(),
+        metadata: Some(PodSandboxMetadata {
+            name: sandbox.name.clone(),
+            uid: sandbox.uid.clone(),
+            namespace: sandbox.namespace.clone(),
+            attempt: sandbox.attempt,
+        }),
+        state: state.into(),
+        created_at: sandbox.created_at,
+        network: Some(PodSandboxNetworkStatus {
+            ip: sandbox.network_ip.clone(),
+            additional_ips: sandbox
+                .additional_ips
+                .iter()
+                .map(|ip| PodIp { ip: ip.clone() })
+                .collect(),
+        }),
+        linux: None,
+        labels: sandbox.labels.clone(),
+        annotations: sandbox.annotations.clone(),
+        runtime_handler: sandbox.runtime_handler.clone(),
+    }
+}
+
+/// The CRI status of a container, as reported by ContainerStatus and
+/// container events.
+pub(super) fn container_status_to_cri(container: &Container) -> ContainerStatus {
+    let state = match container.state {
+        ContainerState::Created => crate::cri_api::ContainerState::ContainerCreated,
+        ContainerState::Running => crate::cri_api::ContainerState::ContainerRunning,
+        ContainerState::Exited => crate::cri_api::ContainerState::ContainerExited,
+    };
+    let (reason, message) = match container.state {
+        ContainerState::Exited => container_exit_reason(container.exit_code, container.oom_killed),
+        ContainerState::Created | ContainerState::Running => ("", String::new()),
+    };
+    ContainerStatus {
+        id: container.id.clone(),
+        metadata: Some(ContainerMetadata {
+            name: container.name.clone(),
+            attempt: container.attempt,
+        }),
+        state: state.into(),
+        created_at: container.created_at,
+        started_at: container.started_at,
+        finished_at: container.finished_at,
+        exit_code: container.exit_code,
+        image: Some(ImageSpec {
+            image: container.image_ref.clone(),
+            annotations: Default::default(),
+        }),
+        image_ref: container.status_image_ref().to_string(),
+        reason: reason.to_string(),
+        message,
+        labels: container.labels.clone(),
+        annotations: container.annotations.clone(),
+        mounts: container
+            .mounts
+            .iter()
+            .map(container_mount_to_cri)
+            .collect(),
+        log_path: container.log_path.clone(),
     }
 }
 
@@ -494,6 +546,8 @@ mod tests {
             log_path: "/var/log/pods/container.log".to_string(),
             rootfs_path: String::new(),
             rootfs_guest_path: String::new(),
+            cpuset_cpus: String::new(),
+            cpuset_mems: String::new(),
         }
     }
 
@@ -514,6 +568,8 @@ mod tests {
             additional_ips: vec![],
             dns: Default::default(),
             container_ports: vec![],
+            cni_network: None,
+            host_cgroup: None,
         }
     }
 
@@ -814,7 +870,7 @@ mod tests {
     }
 
     #[test]
-    fn test_stop_timeout_helpers_and_container_event_response() {
+    fn test_stop_timeout_helpers() {
         assert_eq!(stop_container_timeout_ms(0), None);
         assert_eq!(stop_container_timeout_ms(-10), None);
         assert_eq!(stop_container_timeout_ms(5), Some(5_000));
@@ -828,24 +884,6 @@ mod tests {
             stop_container_wait_duration(7),
             tokio::time::Duration::from_secs(7)
         );
-
-        let event = container_event_response(
-            "c1",
-            "sb1",
-            ContainerEventType::ContainerStoppedEvent,
-            123,
-            "Stopped",
-            "done",
-        );
-        assert_eq!(event.container_id, "c1");
-        assert_eq!(event.pod_sandbox_id, "sb1");
-        assert_eq!(
-            event.container_event_type,
-            ContainerEventType::ContainerStoppedEvent as i32
-        );
-        assert_eq!(event.created_at, 123);
-        assert_eq!(event.reason, "Stopped");
-        assert_eq!(event.message, "done");
     }
 
     use crate::cri_api::namespace_option::NamespaceMode;
@@ -0,0 +1,147 @@
+//! CRI container events for kubelet's evented PLEG.
+//!
+//! Lifecycle changes are published on the service's runtime [`EventEmitter`]
+//! (shared with the pod VMs) as `cri.*` [`BoxEvent`]s naming the container and
+//! pod sandbox. GetContainerEvents turns each into a [`ContainerEventResponse`]
+//! carrying the pod's current sandbox and container statuses, which kubelet
+//! applies to its pod cache instead of relisting. Sandbox lifecycle events use
+//! the sandbox ID as the container ID, as containerd does.
+//!
+//! [`EventEmitter`]: a3s_box_core::event::EventEmitter
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use a3s_box_core::event::{BoxEvent, EventPayload};
+use futures::Stream;
+use tokio::sync::broadcast;
+use tonic::Status;
+
+use super::convert::{container_status_to_cri, pod_sandbox_status_to_cri};
+use crate::cri_api::{ContainerEventResponse, ContainerEventType};
+use crate::persistent_store::PersistentCriStore;
+
+const EVENT_TYPES: [ContainerEventType; 4] = [
+    ContainerEventType::ContainerCreatedEvent,
+    ContainerEventType::ContainerStartedEvent,
+    ContainerEventType::ContainerStoppedEvent,
+    ContainerEventType::ContainerDeletedEvent,
+];
+
+fn event_key(event_type: ContainerEventType) -> &'static str {
+    match event_type {
+        ContainerEventType::ContainerCreatedEvent => "cri.container.created",
+        ContainerEventType::ContainerStartedEvent => "cri.container.started",
+        ContainerEventType::ContainerStoppedEvent => "cri.container.stopped",
+        ContainerEventType::ContainerDeletedEvent => "cri.container.deleted",
+    }
+}
+
+/// The runtime event announcing a lifecycle change of `container_id`.
+pub(super) fn lifecycle_event(
+    event_type: ContainerEventType,
+    container_id: &str,
+    sandbox_id: &str,
+) -> BoxEvent {
+    BoxEvent::with_map(
+        event_key(event_type),
+        HashMap::from([
+            ("container_id".to_string(), container_id.into()),
+            ("pod_sandbox_id".to_string(), sandbox_id.into()),
+        ]),
+    )
+}
+
+/// The CRI events for the runtime events on `receiver`, skipping the rest.
+pub(super) fn container_event_stream(
+    receiver: broadcast::Receiver<BoxEvent>,
+    store: Arc<PersistentCriStore>,
+) -> impl Stream<Item = Result<ContainerEventResponse, Status>> {
+    futures::stream::unfold((receiver, store), |(mut receiver, store)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if let Some(event) = container_event_response(&store, &event).await {
+                        return Some((Ok(event), (receiver, store)));
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        skipped,
+                        "CRI container event stream lagged and dropped events"
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+/// The CRI event for a runtime event, with the pod's statuses as they are now.
+/// Returns `None` for runtime events that are not CRI lifecycle events.
+async fn container_event_response(
+    store: &PersistentCriStore,
+    event: &BoxEvent,
+) -> Option<ContainerEventResponse> {
+    let event_type = EVENT_TYPES
+        .into_iter()
+        .find(|event_type| event_key(*event_type) == event.key)?;
+    let EventPayload::Map(fields) = &event.payload else {
+        return None;
+    };
+    let field = |name: &str| fields.get(name).and_then(|value| value.as_str());
+    let container_id = field("container_id")?;
+    let sandbox_id = field("pod_sandbox_id")?;
+
+    let pod_sandbox_status = store
+        .sandboxes
+        .get(sandbox_id)
+        .await
+        .map(|sandbox| pod_sandbox_status_to_cri(&sandbox));
+    let containers_statuses = store
+        .containers
+        .list(Some(sandbox_id), None)
+        .await
+        .iter()
+        .map(container_status_to_cri)
+        .collect();
+
+    Some(ContainerEventResponse {
+        container_id: container_id.to_string(),
+        container_event_type: event_type.into(),
+        created_at: event.timestamp.timestamp_nanos_opt().unwrap_or(0),
+        pod_sandbox_status,
+        containers_statuses,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::NoopStateStore;
+
+    #[tokio::test]
+    async fn test_container_event_response_ignores_other_runtime_events() {
+        let store = PersistentCriStore::new(Arc::new(NoopStateStore));
+        assert!(
+            container_event_response(&store, &BoxEvent::empty("box.ready"))
+                .await
+                .is_none()
+        );
+
+        let event = lifecycle_event(ContainerEventType::ContainerDeletedEvent, "c-1", "sb-1");
+        let response = container_event_response(&store, &event).await.unwrap();
+        assert_eq!(response.container_id, "c-1");
+        assert_eq!(
+            response.container_event_type,
+            ContainerEventType::ContainerDeletedEvent as i32
+        );
+        assert_eq!(
+            response.created_at,
+            event.timestamp.timestamp_nanos_opt().unwrap()
+        );
+        // The pod is gone from the store, so there is no status to report.
+        assert!(response.pod_sandbox_status.is_none());
+        assert!(response.containers_statuses.is_empty());
+    }
+}
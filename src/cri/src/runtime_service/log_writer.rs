@@ -1,7 +1,9 @@
 //! CRI container log writer.
 //!
 //! Buffers workload stdout/stderr into line-oriented CRI log records
-//! (`<timestamp> <stream> F <line>`) for [`super::supervisor`].
+//! (`<timestamp> <stream> <tag> <line>`, the format the kubelet parses for
+//! `kubectl logs`) for [`super::supervisor`]. The tag is `F` for a complete
+//! line and `P` for a fragment that continues in the next record.
 
 use tokio::io::AsyncWriteExt;
 
@@ -23,22 +25,8 @@ impl CriLogWriter {
             return Ok(None);
         }
 
-        let path = std::path::Path::new(log_path);
-        if let Some(parent) = path
-            .parent()
-            .filter(|parent| !parent.as_os_str().is_empty())
-        {
-            tokio::fs::create_dir_all(parent).await?;
-        }
-
-        let file = tokio::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path)
-            .await?;
-
         Ok(Some(Self {
-            file,
+            file: open_log_file(log_path).await?,
             path: log_path.to_string(),
             stdout_partial: Vec::new(),
             stderr_partial: Vec::new(),
@@ -48,20 +36,12 @@ impl CriLogWriter {
     /// Reopen the log file at its path (CRI `ReopenContainerLog`). The kubelet
     /// rotates by renaming the current file, then calls this; we flush, drop the
     /// old handle, and open a fresh file at the original path so subsequent
-    /// output lands where the kubelet now expects it.
+    /// output lands where the kubelet now expects it. A line still in progress
+    /// is closed out in the old file as a `P` record, so the kubelet joins it
+    /// with the rest of the line in the new file instead of splitting it.
     pub(super) async fn reopen(&mut self) -> std::io::Result<()> {
-        self.flush_partials().await?;
-        if let Some(parent) = std::path::Path::new(&self.path)
-            .parent()
-            .filter(|parent| !parent.as_os_str().is_empty())
-        {
-            tokio::fs::create_dir_all(parent).await?;
-        }
-        self.file = tokio::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.path)
-            .await?;
+        self.write_partials(false).await?;
+        self.file = open_log_file(&self.path).await?;
         Ok(())
     }
 
@@ -105,15 +85,21 @@ impl CriLogWriter {
         Ok(())
     }
 
+    /// Write out buffered newline-less output as complete lines; the stream
+    /// has ended, so nothing will continue them.
     pub(super) async fn flush_partials(&mut self) -> std::io::Result<()> {
+        self.write_partials(true).await
+    }
+
+    async fn write_partials(&mut self, full: bool) -> std::io::Result<()> {
         if !self.stdout_partial.is_empty() {
             let line = std::mem::take(&mut self.stdout_partial);
-            self.write_record(a3s_box_core::exec::StreamType::Stdout, &line, true)
+            self.write_record(a3s_box_core::exec::StreamType::Stdout, &line, full)
                 .await?;
         }
         if !self.stderr_partial.is_empty() {
             let line = std::mem::take(&mut self.stderr_partial);
-            self.write_record(a3s_box_core::exec::StreamType::Stderr, &line, true)
+            self.write_record(a3s_box_core::exec::StreamType::Stderr, &line, full)
                 .await?;
         }
 
@@ -145,6 +131,25 @@ impl CriLogWriter {
     }
 }
 
+/// Open `log_path` for appending, creating it and its parent directories.
+/// New files get mode 0640, as with other CRI runtimes: readable by the
+/// kubelet's log readers, not by every local user.
+async fn open_log_file(log_path: &str) -> std::io::Result<tokio::fs::File> {
+    let path = std::path::Path::new(log_path);
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    options.mode(0o640);
+    options.open(path).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,7 +230,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn reopen_flushes_old_file_and_appends_to_new_path() {
+    async fn reopen_continues_open_line_in_new_file() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("container.log");
         let rotated = dir.path().join("container.log.1");
@@ -246,7 +251,23 @@ mod tests {
 
         let old_contents = std::fs::read_to_string(&rotated).unwrap();
         let new_contents = std::fs::read_to_string(&path).unwrap();
-        assert!(old_contents.contains(" stdout F before-rotate\n"));
+        assert!(old_contents.contains(" stdout P before-rotate\n"));
         assert!(new_contents.contains(" stdout F after-rotate\n"));
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn open_creates_log_file_owner_and_group_readable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("c.log");
+        CriLogWriter::open(path.to_str().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
 }
@@ -5,7 +5,7 @@
 //! - Container → Session within Box
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 
@@ -17,11 +17,12 @@ use a3s_box_core::event::EventEmitter;
 use a3s_box_runtime::oci::{ImageStore, OciRootfsBuilder, RegistryAuth};
 use a3s_box_runtime::pool::WarmPool;
 use a3s_box_runtime::vm::VmManager;
-use a3s_box_runtime::NetworkStore;
+use a3s_box_runtime::{NetworkStore, PreflightOptions, PreflightReport};
 
+use crate::cni::{CniOptions, CniRuntimeConf};
 #[cfg(test)]
 use crate::config_mapper::ANN_NETWORK;
-use crate::config_mapper::{pod_sandbox_config_to_box_config, DEFAULT_AGENT_IMAGE};
+use crate::config_mapper::{pod_sandbox_config_to_box_config, RuntimeHandler, DEFAULT_AGENT_IMAGE};
 use crate::container::{Container, ContainerMount, ContainerState};
 use crate::cri_api::runtime_service_server::RuntimeService;
 use crate::cri_api::*;
@@ -33,37 +34,47 @@ use crate::state::NoopStateStore;
 use crate::state::{default_state_path, JsonStateStore, StateStore};
 use crate::streaming::{SessionKind, StreamingHandle, StreamingInput, StreamingSession};
 
+mod cgroup;
+mod checkpoint;
 mod convert;
+mod events;
 mod log_writer;
 mod mounts;
 mod network;
+mod security;
 mod service_ops;
 mod stats;
 mod supervisor;
 #[cfg(test)]
 mod tests;
 
+use cgroup::{
+    is_unified_hierarchy, remove_cgroup, validate_cpuset, PodCgroup, PodCpuset, CGROUP_ROOT,
+};
+use checkpoint::write_checkpoint_archive;
 #[cfg(test)]
 use convert::ANN_ADDITIONAL_POD_IPS;
 use convert::{
-    container_event_response, container_exit_reason, container_mount_to_cri, container_state_label,
-    container_state_to_cri, container_summary, container_user_from_linux_config,
-    ensure_container_image_available, ensure_container_running, ensure_sandbox_ready,
-    ensure_vm_ready, merge_env, resolve_command_and_args, resolve_container_mounts,
-    sandbox_state_label, sandbox_summary, sanitize_path_component, stop_container_timeout_ms,
-    stop_container_wait_duration, ContainerRootfsPaths, ResolvedContainerImage, ANN_POD_IP,
+    container_state_label, container_state_to_cri, container_status_to_cri, container_summary,
+    container_user_from_linux_config, ensure_container_image_available, ensure_container_running,
+    ensure_sandbox_ready, ensure_vm_ready, merge_env, pod_sandbox_status_to_cri,
+    resolve_command_and_args, resolve_container_mounts, sandbox_state_label, sandbox_summary,
+    sanitize_path_component, stop_container_timeout_ms, stop_container_wait_duration,
+    ContainerRootfsPaths, ResolvedContainerImage, ANN_POD_IP,
 };
+use events::{container_event_stream, lifecycle_event};
 #[cfg(test)]
 use log_writer::CriLogWriter;
 use mounts::materialize_container_mount;
 use network::{
-    bridge_network_name, connect_sandbox_to_network_store, default_network_store,
-    disconnect_sandbox_from_network_store, sandbox_network_name,
-    sandbox_network_status_from_annotations, SandboxNetworkAllocation,
+    add_sandbox_to_cni, bridge_network_name, cni_runtime_conf, connect_sandbox_to_network_store,
+    default_network_store, disconnect_sandbox_from_network_store, remove_sandbox_from_cni,
+    sandbox_network_name, sandbox_network_status_from_annotations, SandboxNetworkAllocation,
 };
+use security::{container_security_env, pid_namespace_env, seccomp_profile_root};
 use stats::{
-    container_stats, metric_descriptors, pod_sandbox_metrics, pod_sandbox_stats, read_vm_usage,
-    VmUsage,
+    container_stats, metric_descriptors, pod_sandbox_metrics, pod_sandbox_stats,
+    read_guest_metrics, read_vm_usage, StatsCollector, VmUsage,
 };
 use supervisor::{spawn_container_exit_supervisor, ContainerExitSupervisor, SupervisedWorkload};
 
@@ -116,240 +127,59 @@ pub(super) struct LogReopenHandle {
     pub(super) done: Arc<Notify>,
 }
 type LogReopenMap = Arc<RwLock<HashMap<String, LogReopenHandle>>>;
-type ContainerEventSender = broadcast::Sender<ContainerEventResponse>;
 
 const CRI_CONTAINER_ROOTFS_HOST_DIR: &str = "cri-container-rootfs";
 const CRI_CONTAINER_ROOTFS_GUEST_BASE: &str = "/run/a3s/cri/container-rootfs";
 const CONTAINER_EVENT_BUFFER: usize = 1024;
 
-/// Capabilities a non-privileged container keeps by default (matches the
-/// containerd/runc default set). A privileged container keeps the full set.
-const DEFAULT_CAPABILITIES: &[&str] = &[
-    "CHOWN",
-    "DAC_OVERRIDE",
-    "FSETID",
-    "FOWNER",
-    "MKNOD",
-    "NET_RAW",
-    "SETGID",
-    "SETUID",
-    "SETFCAP",
-    "SETPCAP",
-    "NET_BIND_SERVICE",
-    "SYS_CHROOT",
-    "KILL",
-    "AUDIT_WRITE",
-];
-
-/// Compute the capability set a non-privileged container keeps: the default set
-/// unioned with `add_capabilities` and minus `drop_capabilities` (`ALL` is
-/// honored on either side). Returns `None` when the container should keep the
-/// full set (an `add` of `ALL`), in which case no keep-set is emitted.
-fn kept_capabilities(capabilities: Option<&Capability>) -> Option<Vec<String>> {
-    let normalize = |cap: &str| {
-        cap.trim()
-            .strip_prefix("CAP_")
-            .unwrap_or(cap.trim())
-            .to_uppercase()
-    };
-    let mut kept: std::collections::BTreeSet<String> =
-        DEFAULT_CAPABILITIES.iter().map(|c| c.to_string()).collect();
-    if let Some(capabilities) = capabilities {
-        if capabilities
-            .add_capabilities
-            .iter()
-            .any(|c| normalize(c) == "ALL")
-        {
-            return None;
-        }
-        if capabilities
-            .drop_capabilities
-            .iter()
-            .any(|c| normalize(c) == "ALL")
-        {
-            kept.clear();
-        } else {
-            for cap in &capabilities.drop_capabilities {
-                kept.remove(&normalize(cap));
-            }
-        }
-        for cap in &capabilities.add_capabilities {
-            kept.insert(normalize(cap));
-        }
-    }
-    Some(kept.into_iter().collect())
-}
-
-/// Whether an AppArmor profile of the given name is currently loaded on the
-/// host (listed in `/sys/kernel/security/apparmor/profiles`).
-///
-/// Returns `false` when AppArmor is unavailable (file absent/unreadable), which
-/// makes a requested Localhost profile fail closed rather than run unconfined.
-fn apparmor_profile_loaded(name: &str) -> bool {
-    let Ok(content) = std::fs::read_to_string("/sys/kernel/security/apparmor/profiles") else {
-        return false;
-    };
-    // Each line is `<profile-name> (<mode>)`.
-    content
-        .lines()
-        .any(|line| line.split_whitespace().next() == Some(name))
-}
-
-#[derive(serde::Deserialize)]
-struct OciSeccompProfile {
-    #[serde(rename = "defaultAction")]
-    default_action: String,
-    #[serde(default)]
-    syscalls: Vec<OciSeccompSyscall>,
-}
-
-#[derive(serde::Deserialize)]
-struct OciSeccompSyscall {
-    #[serde(default)]
-    names: Vec<String>,
-    action: String,
-}
-
-/// The node's seccomp profile root — kubelet default, override with
-/// `A3S_BOX_SECCOMP_PROFILE_ROOT`.
-fn seccomp_profile_root() -> PathBuf {
-    std::env::var("A3S_BOX_SECCOMP_PROFILE_ROOT")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from("/var/lib/kubelet/seccomp"))
-}
-
-/// Confine a CRI `localhostProfile` path to the seccomp profile `root` and reject
-/// `..` traversal.
-///
-/// `localhost_ref` comes from a pod's
-/// `securityContext.seccompProfile.localhostProfile` (attacker-settable through
-/// the CRI) and is read off the host disk — without confinement it is an
-/// arbitrary host-file open primitive. Mirrors kubelet/containerd semantics:
-/// profiles live under the configured seccomp root. A relative ref resolves under
-/// the root; an absolute ref must lie within it. `root` is injected so the check
-/// is testable without process-global env state.
-fn confined_seccomp_path(localhost_ref: &str, root: &std::path::Path) -> Result<PathBuf, String> {
-    let r = std::path::Path::new(localhost_ref);
-    if r.components()
-        .any(|c| matches!(c, std::path::Component::ParentDir))
-    {
-        return Err(format!(
-            "seccomp profile path contains '..': {localhost_ref}"
-        ));
-    }
-    let candidate = if r.is_absolute() {
-        r.to_path_buf()
-    } else {
-        root.join(r)
-    };
-    if !candidate.starts_with(root) {
-        return Err(format!(
-            "seccomp profile {} is outside the seccomp root {}",
-            candidate.display(),
-            root.display()
-        ));
-    }
-    Ok(candidate)
-}
-
-/// Parse a CRI localhost seccomp profile file and return the syscall names it
-/// blocks with `SCMP_ACT_ERRNO`/`SCMP_ACT_KILL*`.
-///
-/// Supports the conformance shape — a permissive default action
-/// (`SCMP_ACT_ALLOW`/`SCMP_ACT_LOG`) plus an ERRNO deny list. A deny-by-default
-/// profile (which would need a full allow-list compiled to BPF) is not yet
-/// supported; returns an error so the caller can fall back rather than silently
-/// run unconfined.
-fn parse_localhost_seccomp_deny(
-    localhost_ref: &str,
-    root: &std::path::Path,
-) -> Result<Vec<String>, String> {
-    let path = confined_seccomp_path(localhost_ref, root)?;
-    let raw = std::fs::read_to_string(&path)
-        .map_err(|e| format!("read seccomp profile {}: {e}", path.display()))?;
-    let profile: OciSeccompProfile =
-        serde_json::from_str(&raw).map_err(|e| format!("parse seccomp profile: {e}"))?;
-    if !matches!(
-        profile.default_action.as_str(),
-        "SCMP_ACT_ALLOW" | "SCMP_ACT_LOG"
-    ) {
-        return Err(format!(
-            "unsupported seccomp defaultAction {} (only allow-default profiles are supported)",
-            profile.default_action
-        ));
-    }
-    let deny: Vec<String> = profile
-        .syscalls
-        .into_iter()
-        .filter(|s| {
-            matches!(
-                s.action.as_str(),
-                "SCMP_ACT_ERRNO"
-                    | "SCMP_ACT_KILL"
-                    | "SCMP_ACT_KILL_THREAD"
-                    | "SCMP_ACT_KILL_PROCESS"
-            )
-        })
-        .flat_map(|s| s.names)
-        .collect();
-    Ok(deny)
-}
-
-#[cfg(test)]
-mod seccomp_confine_tests {
-    use super::confined_seccomp_path;
-    use std::path::Path;
-
-    #[test]
-    fn confines_localhost_profile_to_root_and_rejects_traversal() {
-        let root = Path::new("/var/lib/kubelet/seccomp");
-        // In-root relative/absolute refs are allowed (the check is lexical, so
-        // the files need not exist).
-        assert!(confined_seccomp_path("audit.json", root).is_ok());
-        assert!(confined_seccomp_path("profiles/audit.json", root).is_ok());
-        assert!(confined_seccomp_path("/var/lib/kubelet/seccomp/audit.json", root).is_ok());
-
-        // SECURITY: a malicious localhostProfile must not open arbitrary host
-        // files — traversal, out-of-root absolute paths, and prefix-confusion
-        // are all rejected (caller then falls back to RuntimeDefault).
-        for evil in [
-            "../../../../etc/passwd",
-            "sub/../../escape",
-            "/etc/passwd",
-            "/etc/shadow",
-            "/var/lib/kubelet/seccomp-evil/x",
-        ] {
-            assert!(
-                confined_seccomp_path(evil, root).is_err(),
-                "must reject malicious seccomp path: {evil}"
-            );
-        }
-    }
+/// How the stats RPCs collect pod usage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsCollection {
+    /// Sample the guest and walk container rootfs trees on every call.
+    #[default]
+    OnDemand,
+    /// Reuse samples younger than `max_age`.
+    Cached { max_age: std::time::Duration },
 }
 
 #[derive(Debug, Clone)]
 pub struct CriRuntimeOptions {
     pub default_agent_image: String,
-    pub runtime_handler_agent_images: HashMap<String, String>,
+    /// Per-RuntimeClass VM settings, keyed by CRI runtime handler.
+    pub runtime_handlers: HashMap<String, RuntimeHandler>,
+    pub stats_collection: StatsCollection,
+    /// CNI plugins that set up sandbox networking; `None` keeps passt-only
+    /// networking.
+    pub cni: Option<CniOptions>,
 }
 
 impl Default for CriRuntimeOptions {
     fn default() -> Self {
         Self {
             default_agent_image: DEFAULT_AGENT_IMAGE.to_string(),
-            runtime_handler_agent_images: HashMap::new(),
+            runtime_handlers: HashMap::new(),
+            stats_collection: StatsCollection::default(),
+            cni: None,
         }
     }
 }
 
 impl CriRuntimeOptions {
     pub fn agent_image_for(&self, runtime_handler: &str) -> &str {
-        self.runtime_handler_agent_images
+        self.runtime_handlers
             .get(runtime_handler)
-            .map(String::as_str)
+            .and_then(|handler| handler.agent_image.as_deref())
             .filter(|image| !image.trim().is_empty())
             .unwrap_or(&self.default_agent_image)
     }
+
+    /// The VM settings of `runtime_handler`; unknown handlers get none.
+    pub fn runtime_handler(&self, runtime_handler: &str) -> RuntimeHandler {
+        self.runtime_handlers
+            .get(runtime_handler)
+            .cloned()
+            .unwrap_or_default()
+    }
 }
 
 /// A3S Box implementation of the CRI RuntimeService.
@@ -381,36 +211,62 @@ pub struct BoxRuntimeService {
     workload_stops: WorkloadStopMap,
     /// Per-container signals for CRI ReopenContainerLog (log rotation).
     log_reopens: LogReopenMap,
-    /// Broadcasts CRI container lifecycle events to GetContainerEvents streams.
-    container_events: ContainerEventSender,
+    /// Runtime event emitter shared with the pod VMs; carries the CRI lifecycle
+    /// events behind GetContainerEvents.
+    runtime_events: EventEmitter,
     /// Optional warm pool for instant VM acquisition.
     warm_pool: Option<Arc<RwLock<WarmPool>>>,
     /// Runtime-level CRI defaults and RuntimeClass overrides.
     runtime_options: CriRuntimeOptions,
+    /// Last pod and rootfs usage samples behind the stats RPCs.
+    stats: Arc<StatsCollector>,
     /// Test-only hook for forcing VM acquisition failures without host virtualization.
     #[cfg(test)]
     test_vm_acquire_error: Option<String>,
     /// Test-only hook for attaching RunPodSandbox to a fake exec socket.
     #[cfg(test)]
     test_vm_exec_socket_path: Option<PathBuf>,
+    /// Test-only hook replacing the host preflight report read by Status.
+    #[cfg(test)]
+    test_preflight: Option<PreflightReport>,
 }
 
 impl BoxRuntimeService {
-    /// Real CPU + memory usage of a sandbox's microVM, read from the host-side
-    /// shim process (the shim *is* the pod in the microVM-per-pod model).
-    /// Returns zeros if the VM is not booted or its procfs is unreadable.
+    /// Real CPU + memory usage of a sandbox's microVM, from the guest metrics
+    /// channel, or the host-side shim process (the shim *is* the pod in the
+    /// microVM-per-pod model) when the guest does not answer. Returns zeros if
+    /// the VM is not booted.
     async fn sandbox_vm_usage(&self, sandbox_id: &str) -> VmUsage {
-        let pid = {
+        let now = std::time::Instant::now();
+        if let Some(usage) = self.stats.cached_vm_usage(sandbox_id, now) {
+            return usage;
+        }
+        let (pid, exec_socket_path) = {
             let vm_managers = self.vm_managers.read().await;
             match vm_managers.get(sandbox_id) {
-                Some(vm) => vm.pid().await,
-                None => None,
+                Some(vm) => (vm.pid().await, vm.exec_socket_path().map(PathBuf::from)),
+                None => (None, None),
             }
         };
-        match pid {
-            Some(pid) => read_vm_usage(pid),
-            None => VmUsage::default(),
+        let host = pid.map(read_vm_usage).unwrap_or_default();
+        let guest = match (pid, exec_socket_path) {
+            (Some(_), Some(path)) => read_guest_metrics(&path).await,
+            _ => None,
+        };
+        self.stats
+            .record_vm_usage(sandbox_id, host, guest.as_ref(), now)
+    }
+
+    /// Host preflight report behind the RuntimeReady condition.
+    async fn preflight_report(&self) -> Result<PreflightReport, Status> {
+        #[cfg(test)]
+        if let Some(report) = &self.test_preflight {
+            return Ok(report.clone());
         }
+
+        tokio::task::spawn_blocking(|| a3s_box_runtime::preflight(&PreflightOptions::default()))
+            .await
+            .map_err(|e| Status::internal(format!("Host preflight check panicked: {e}")))
     }
 
     /// Create a new BoxRuntimeService with JSON-backed persistent state.
@@ -444,13 +300,16 @@ impl BoxRuntimeService {
             workload_stdins: Arc::new(RwLock::new(HashMap::new())),
             workload_stops: Arc::new(RwLock::new(HashMap::new())),
             log_reopens: Arc::new(RwLock::new(HashMap::new())),
-            container_events: broadcast::channel(CONTAINER_EVENT_BUFFER).0,
+            runtime_events: EventEmitter::new(CONTAINER_EVENT_BUFFER),
             warm_pool: None,
             runtime_options: CriRuntimeOptions::default(),
+            stats: Arc::new(StatsCollector::default()),
             #[cfg(test)]
             test_vm_acquire_error: None,
             #[cfg(test)]
             test_vm_exec_socket_path: None,
+            #[cfg(test)]
+            test_preflight: None,
         }
     }
 
@@ -466,6 +325,7 @@ impl BoxRuntimeService {
 
     /// Override runtime-level CRI defaults.
     pub fn with_runtime_options(mut self, runtime_options: CriRuntimeOptions) -> Self {
+        self.stats = Arc::new(StatsCollector::new(runtime_options.stats_collection));
         self.runtime_options = runtime_options;
         self
     }
@@ -627,9 +487,10 @@ impl RuntimeService for BoxRuntimeService {
         // Convert CRI config to BoxConfig. The annotation may override the
         // runtime default, but ordinary Pods do not need A3S-specific fields.
         let agent_image = self.runtime_options.agent_image_for(&req.runtime_handler);
-        let mut box_config =
-            pod_sandbox_config_to_box_config(&config, agent_image).map_err(box_error_to_status)?;
-        let (mut network_ip, additional_ips) =
+        let handler = self.runtime_options.runtime_handler(&req.runtime_handler);
+        let mut box_config = pod_sandbox_config_to_box_config(&config, agent_image, &handler)
+            .map_err(box_error_to_status)?;
+        let (mut network_ip, mut additional_ips) =
             sandbox_network_status_from_annotations(&config.annotations)?;
         let rootfs_base = self.ensure_container_rootfs_mount_base().await?;
         box_config.volumes.push(format!(
@@ -645,14 +506,26 @@ impl RuntimeService for BoxRuntimeService {
         );
 
         let sandbox_id = uuid::Uuid::new_v4().to_string();
-        let network_allocation = self
-            .connect_sandbox_network(&box_config, &sandbox_id, &metadata.name)
-            .await?;
+        // Pods that name an A3S network keep it; the rest go through the CNI
+        // plugins when they are configured.
+        let network_allocation = match &self.runtime_options.cni {
+            Some(cni) if box_config.network == a3s_box_core::NetworkMode::Tsi => Some(
+                self.connect_sandbox_cni(cni, &mut box_config, &sandbox_id, &config)
+                    .await?,
+            ),
+            _ => {
+                self.connect_sandbox_network(&box_config, &sandbox_id, &metadata.name)
+                    .await?
+            }
+        };
         if let Some(allocation) = &network_allocation {
             if network_ip.is_empty() {
                 network_ip = allocation.ip.clone();
+                if additional_ips.is_empty() {
+                    additional_ips = allocation.additional_ips.clone();
+                }
             } else if network_ip != allocation.ip {
-                self.disconnect_sandbox_network_by_name(&allocation.network_name, &sandbox_id)
+                self.release_sandbox_network(allocation, &sandbox_id, metadata)
                     .await;
                 return Err(Status::invalid_argument(format!(
                     "Annotation {ANN_POD_IP} value {network_ip} does not match allocated network IP {}",
@@ -661,6 +534,8 @@ impl RuntimeService for BoxRuntimeService {
             }
         }
 
+        let vm_memory_bytes = u64::from(box_config.resources.memory_mb) * 1024 * 1024;
+
         // Acquire VM: from warm pool if available, otherwise cold boot
         let vm = match self
             .acquire_vm_with_box_id(box_config, sandbox_id.clone())
@@ -669,13 +544,24 @@ impl RuntimeService for BoxRuntimeService {
             Ok(vm) => vm,
             Err(status) => {
                 if let Some(allocation) = &network_allocation {
-                    self.disconnect_sandbox_network_by_name(&allocation.network_name, &sandbox_id)
+                    self.release_sandbox_network(allocation, &sandbox_id, metadata)
                         .await;
                 }
                 return Err(status);
             }
         };
 
+        // Charge the VM to the pod cgroup kubelet made for it, so pod
+        // accounting, QoS and eviction see what the pod really uses.
+        let host_cgroup = self
+            .place_sandbox_vm_in_pod_cgroup(
+                &sandbox_id,
+                config.linux.as_ref(),
+                &vm,
+                vm_memory_bytes,
+            )
+            .await;
+
         // Track the VM immediately (it used to be inserted only after
         // add_sandbox) so the cancel guard below can find and destroy it.
         self.vm_managers
@@ -685,27 +571,56 @@ impl RuntimeService for BoxRuntimeService {
 
         // Arm the cancel guard: from here until the sandbox is stored, a dropped
         // request future (kubelet deadline) must not leak the booted VM + IP.
-        let cancel_network_name = network_allocation.as_ref().map(|a| a.network_name.clone());
+        let cancel_network = network_allocation
+            .as_ref()
+            .map(|a| (a.network_name.clone(), a.cni));
         let mut cancel_guard = {
             let vm_managers = self.vm_managers.clone();
             let network_store = self.network_store.clone();
+            let cni_options = self.runtime_options.cni.clone().unwrap_or_default();
+            let cni_rt = cni_runtime_conf(
+                &sandbox_id,
+                &metadata.namespace,
+                &metadata.name,
+                &metadata.uid,
+                &[],
+            );
             let sid = sandbox_id.clone();
+            let cancel_cgroup = host_cgroup.clone();
             CancelGuard::new(move || {
-                // Network disconnect is synchronous — do it directly in Drop.
-                if let Some(network_name) = cancel_network_name {
-                    let _ = disconnect_sandbox_from_network_store(
-                        network_store.as_ref(),
-                        &network_name,
-                        &sid,
-                    );
+                let handle = tokio::runtime::Handle::try_current().ok();
+                match (cancel_network, &handle) {
+                    // CNI DEL runs plugin processes — hand it to the blocking pool.
+                    (Some((network_name, true)), Some(handle)) => {
+                        handle.spawn_blocking(move || {
+                            remove_sandbox_from_cni(
+                                &cni_options,
+                                network_store.as_ref(),
+                                &network_name,
+                                &cni_rt,
+                            )
+                        });
+                    }
+                    // Network disconnect is synchronous — do it directly in Drop.
+                    (Some((network_name, false)), _) => {
+                        let _ = disconnect_sandbox_from_network_store(
+                            network_store.as_ref(),
+                            &network_name,
+                            &sid,
+                        );
+                    }
+                    _ => {}
                 }
                 // VM destroy is async — spawn it on the still-running runtime (the
                 // request future is being dropped, not the server). Best-effort.
-                if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                if let Some(handle) = handle {
                     handle.spawn(async move {
                         if let Some(mut vm) = vm_managers.write().await.remove(&sid) {
                             let _ = vm.destroy().await;
                         }
+                        if let Some(path) = cancel_cgroup {
+                            let _ = remove_cgroup(Path::new(CGROUP_ROOT), Path::new(&path));
+                        }
                     });
                 }
             })
@@ -751,6 +666,11 @@ impl RuntimeService for BoxRuntimeService {
                 .map(|mapping| mapping.container_port)
                 .filter(|port| *port > 0)
                 .collect(),
+            cni_network: network_allocation
+                .as_ref()
+                .filter(|allocation| allocation.cni)
+                .map(|allocation| allocation.network_name.clone()),
+            host_cgroup,
         };
 
         self.store.add_sandbox(sandbox).await;
@@ -759,6 +679,17 @@ impl RuntimeService for BoxRuntimeService {
         // between add_sandbox and here, so a successfully-created sandbox can
         // never be torn down by a late cancellation.
         cancel_guard.disarm();
+        // Sandbox lifecycle events carry the sandbox ID as the container ID.
+        self.emit_container_event(
+            &sandbox_id,
+            &sandbox_id,
+            ContainerEventType::ContainerCreatedEvent,
+        );
+        self.emit_container_event(
+            &sandbox_id,
+            &sandbox_id,
+            ContainerEventType::ContainerStartedEvent,
+        );
 
         Ok(Response::new(RunPodSandboxResponse {
             pod_sandbox_id: sandbox_id,
@@ -823,9 +754,6 @@ impl RuntimeService for BoxRuntimeService {
                         &container.id,
                         &container.sandbox_id,
                         ContainerEventType::ContainerStoppedEvent,
-                        now_ns,
-                        "StopPodSandbox",
-                        "Container stopped by pod sandbox shutdown",
                     );
                 }
             }
@@ -853,6 +781,11 @@ impl RuntimeService for BoxRuntimeService {
         self.store
             .update_sandbox_state(sandbox_id, SandboxState::NotReady)
             .await;
+        self.emit_container_event(
+            sandbox_id,
+            sandbox_id,
+            ContainerEventType::ContainerStoppedEvent,
+        );
         destroy_result?;
 
         Ok(Response::new(StopPodSandboxResponse {}))
@@ -897,14 +830,10 @@ impl RuntimeService for BoxRuntimeService {
             }
         }
         for container in &removed_containers {
-            let event_time = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
             self.emit_container_event(
                 &container.id,
                 &container.sandbox_id,
                 ContainerEventType::ContainerDeletedEvent,
-                event_time,
-                "ContainerDeleted",
-                format!("Container {} removed with pod sandbox", container.name),
             );
             self.cleanup_container_rootfs_path(&container.rootfs_path)
                 .await;
@@ -913,6 +842,11 @@ impl RuntimeService for BoxRuntimeService {
 
         // Remove sandbox
         self.store.remove_sandbox(sandbox_id).await;
+        self.emit_container_event(
+            sandbox_id,
+            sandbox_id,
+            ContainerEventType::ContainerDeletedEvent,
+        );
 
         Ok(Response::new(RemovePodSandboxResponse {}))
     }
@@ -931,10 +865,6 @@ impl RuntimeService for BoxRuntimeService {
             .await
             .ok_or_else(|| Status::not_found(format!("Sandbox not found: {}", sandbox_id)))?;
 
-        let state = match sandbox.state {
-            SandboxState::Ready => PodSandboxState::SandboxReady,
-            SandboxState::NotReady | SandboxState::Removed => PodSandboxState::SandboxNotready,
-        };
         let info = if req.verbose {
             let vm_present = self.vm_managers.read().await.contains_key(sandbox_id);
             let container_count = self
@@ -961,32 +891,8 @@ impl RuntimeService for BoxRuntimeService {
             Default::default()
         };
 
-        let status = PodSandboxStatus {
-            id: sandbox.id.clone(),
-            metadata: Some(PodSandboxMetadata {
-                name: sandbox.name.clone(),
-                uid: sandbox.uid.clone(),
-                namespace: sandbox.namespace.clone(),
-                attempt: sandbox.attempt,
-            }),
-            state: state.into(),
-            created_at: sandbox.created_at,
-            network: Some(PodSandboxNetworkStatus {
-                ip: sandbox.network_ip.clone(),
-                additional_ips: sandbox
-                    .additional_ips
-                    .iter()
-                    .map(|ip| PodIp { ip: ip.clone() })
-                    .collect(),
-            }),
-            linux: None,
-            labels: sandbox.labels.clone(),
-            annotations: sandbox.annotations.clone(),
-            runtime_handler: sandbox.runtime_handler.clone(),
-        };
-
         Ok(Response::new(PodSandboxStatusResponse {
-            status: Some(status),
+            status: Some(pod_sandbox_status_to_cri(&sandbox)),
             info,
         }))
     }
@@ -1109,6 +1015,14 @@ impl RuntimeService for BoxRuntimeService {
             ));
         }
         let mounts = resolve_container_mounts(&config.mounts)?;
+        let (cpuset_cpus, cpuset_mems) = config
+            .linux
+            .as_ref()
+            .and_then(|linux| linux.resources.as_ref())
+            .map(|resources| (resources.cpuset_cpus.clone(), resources.cpuset_mems.clone()))
+            .unwrap_or_default();
+        validate_cpuset("cpuset_cpus", &cpuset_cpus).map_err(Status::invalid_argument)?;
+        validate_cpuset("cpuset_mems", &cpuset_mems).map_err(Status::invalid_argument)?;
 
         let image_ref = config
             .image
@@ -1173,144 +1087,47 @@ impl RuntimeService for BoxRuntimeService {
         } else {
             config.working_dir.clone()
         };
-        // CRI requires run_as_group to be set only alongside run_as_user or
-        // run_as_username; otherwise the runtime MUST reject the container.
         if let Some(sc) = config
             .linux
             .as_ref()
             .and_then(|linux| linux.security_context.as_ref())
         {
-            if sc.run_as_group.is_some()
-                && sc.run_as_user.is_none()
-                && sc.run_as_username.is_empty()
-            {
-                return Err(Status::invalid_argument(
-                    "run_as_group must not be set without run_as_user or run_as_username",
-                ));
-            }
-            // Carry CRI SupplementalGroups to guest-init over the env channel;
-            // the guest applies them with setgroups before dropping privileges.
-            if !sc.supplemental_groups.is_empty() {
-                let groups = sc
-                    .supplemental_groups
-                    .iter()
-                    .map(|gid| gid.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",");
-                env.push(("A3S_SEC_SUPPLEMENTAL_GROUPS".to_string(), groups));
-            }
-            // CRI MaskedPaths/ReadonlyPaths — ':'-separated absolute paths the
-            // guest masks (bind /dev/null or ro tmpfs) / remounts read-only
-            // inside the container rootfs.
-            if !sc.masked_paths.is_empty() {
-                env.push((
-                    "A3S_SEC_MASKED_PATHS".to_string(),
-                    sc.masked_paths.join(":"),
-                ));
-            }
-            if !sc.readonly_paths.is_empty() {
-                env.push((
-                    "A3S_SEC_READONLY_PATHS".to_string(),
-                    sc.readonly_paths.join(":"),
-                ));
-            }
-            // CRI seccomp: RuntimeDefault installs the default BPF filter in the
-            // guest (Seccomp: 2); Unconfined / unset leave the container
-            // unconfined; Localhost compiles the profile's deny list. Privileged
-            // containers bypass seccomp entirely — a requested profile is
-            // ignored when privileged (per the CRI contract).
-            if let Some(profile) = sc.seccomp.as_ref().filter(|_| !sc.privileged) {
-                use crate::cri_api::security_profile::ProfileType;
-                match profile.profile_type() {
-                    ProfileType::RuntimeDefault => {
-                        env.push(("A3S_SEC_SECCOMP".to_string(), "default".to_string()));
-                    }
-                    ProfileType::Localhost => {
-                        // Read + parse the localhost profile (allow-default +
-                        // ERRNO deny list) and pass the blocked syscalls to the
-                        // guest, which compiles them into a BPF filter.
-                        match parse_localhost_seccomp_deny(
-                            &profile.localhost_ref,
-                            &seccomp_profile_root(),
-                        ) {
-                            Ok(deny) => {
-                                env.push(("A3S_SEC_SECCOMP_LOCALHOST".to_string(), deny.join(",")));
-                            }
-                            Err(e) => {
-                                // Fall back to RuntimeDefault rather than running
-                                // unconfined when the profile can't be applied.
-                                tracing::warn!(
-                                    container = %metadata.name,
-                                    profile = %profile.localhost_ref,
-                                    error = %e,
-                                    "Localhost seccomp profile unsupported; applying RuntimeDefault instead"
-                                );
-                                env.push(("A3S_SEC_SECCOMP".to_string(), "default".to_string()));
-                            }
-                        }
-                    }
-                    ProfileType::Unconfined => {}
-                }
-            }
-            // CRI capabilities: a privileged container keeps the full set; a
-            // non-privileged one is restricted to the runtime default set,
-            // adjusted by add/drop (A3S_SEC_CAP_KEEP — the guest drops every
-            // capability not listed). This is what stops a non-privileged
-            // container from doing privileged operations (e.g. creating a bridge
-            // with CAP_NET_ADMIN). An `add` of `ALL` keeps the full set.
-            if !sc.privileged {
-                if let Some(kept) = kept_capabilities(sc.capabilities.as_ref()) {
-                    env.push(("A3S_SEC_CAP_KEEP".to_string(), kept.join(",")));
-                }
-            }
-            // no_new_privs: the guest sets PR_SET_NO_NEW_PRIVS before exec so a
-            // setuid/setgid (or file-capability) binary can no longer raise the
-            // process's privileges. Privileged containers opt out.
-            if sc.no_new_privs && !sc.privileged {
-                env.push(("A3S_SEC_NO_NEW_PRIVS".to_string(), "1".to_string()));
-            }
-            // readonly_rootfs: the guest remounts the container root read-only
-            // before exec. This is a deliberate config (not a hardening default),
-            // so it applies to privileged containers too.
-            if sc.readonly_rootfs {
-                env.push(("A3S_SEC_READONLY_ROOTFS".to_string(), "1".to_string()));
-            }
-            // AppArmor: a microVM cannot enforce an in-guest LSM profile, but a
-            // requested Localhost profile must not be silently ignored. Validate
-            // it against the host's loaded profiles and reject when it is not
-            // loaded (the CRI contract: an unloaded profile fails). A loaded
-            // profile is accepted but cannot be enforced in-guest. The modern
-            // `apparmor` SecurityProfile takes precedence over the deprecated
-            // `apparmor_profile` string.
-            #[allow(deprecated)] // clients (incl. critest) still send apparmor_profile
-            let apparmor_localhost = sc
-                .apparmor
+            let sandbox_privileged = req
+                .sandbox_config
                 .as_ref()
-                .filter(|profile| {
-                    profile.profile_type()
-                        == crate::cri_api::security_profile::ProfileType::Localhost
-                })
-                .map(|profile| profile.localhost_ref.clone())
-                .or_else(|| {
-                    sc.apparmor_profile
-                        .strip_prefix("localhost/")
-                        .map(|name| name.to_string())
+                .and_then(|sandbox| sandbox.linux.as_ref())
+                .map(|linux| {
+                    linux
+                        .security_context
+                        .as_ref()
+                        .is_some_and(|sc| sc.privileged)
                 });
-            if let Some(profile) = apparmor_localhost {
-                let profile = profile.trim();
-                if profile.is_empty() || !apparmor_profile_loaded(profile) {
-                    return Err(Status::failed_precondition(format!(
-                        "AppArmor profile 'localhost/{profile}' is not loaded"
-                    )));
-                }
-                tracing::warn!(
-                    container = %metadata.name,
-                    profile = %profile,
-                    "AppArmor localhost profile is loaded on the host but the microVM \
-                     runtime cannot enforce it in-guest; the container runs without \
-                     AppArmor confinement"
-                );
-            }
+            env.extend(container_security_env(
+                sc,
+                &metadata.name,
+                sandbox_privileged,
+                &seccomp_profile_root(),
+            )?);
+            // Every container of the pod shares the VM's network, so
+            // `localhost` reaches its siblings; the PID namespace follows the
+            // requested mode. A TARGET must be a container of this pod.
+            let namespace_options = sc.namespace_options.as_ref();
+            let target_rootfs = match namespace_options
+                .filter(|opts| opts.pid == namespace_option::NamespaceMode::Target as i32)
+            {
+                Some(opts) => self
+                    .store
+                    .containers
+                    .get(&opts.target_id)
+                    .await
+                    .filter(|target| &target.sandbox_id == sandbox_id)
+                    .map(|target| target.rootfs_guest_path),
+                None => None,
+            };
+            env.extend(pid_namespace_env(
+                namespace_options,
+                target_rootfs.as_deref(),
+            )?);
         }
         let user = container_user_from_linux_config(config.linux.as_ref())
             .or_else(|| image_config.and_then(|image| image.user.clone()));
@@ -1413,6 +1230,8 @@ impl RuntimeService for BoxRuntimeService {
             log_path,
             rootfs_path,
             rootfs_guest_path,
+            cpuset_cpus,
+            cpuset_mems,
         };
 
         // Re-validate the sandbox right before registering the container. The
@@ -1435,13 +1254,19 @@ impl RuntimeService for BoxRuntimeService {
         }
 
         self.store.add_container(container.clone()).await;
+        if !container.cpuset_cpus.is_empty() || !container.cpuset_mems.is_empty() {
+            if let Err(status) = self.apply_pod_cpuset(&container.sandbox_id).await {
+                tracing::warn!(
+                    container_id = %container.id,
+                    error = %status.message(),
+                    "Failed to apply CRI container cpuset"
+                );
+            }
+        }
         self.emit_container_event(
             &container.id,
             &container.sandbox_id,
             ContainerEventType::ContainerCreatedEvent,
-            container.created_at,
-            "ContainerCreated",
-            format!("Container {} created", container.name),
         );
 
         Ok(Response::new(CreateContainerResponse { container_id }))
@@ -1558,6 +1383,8 @@ impl RuntimeService for BoxRuntimeService {
                     user: exec_request.user.clone(),
                     cols: 80,
                     rows: 24,
+                    session: None,
+                    resume: false,
                 };
                 let stream = pty_client.start_stream(&pty_request).await.map_err(|e| {
                     Status::internal(format!("Failed to start TTY container workload: {}", e))
@@ -1632,9 +1459,6 @@ impl RuntimeService for BoxRuntimeService {
             &container_id,
             &container.sandbox_id,
             ContainerEventType::ContainerStartedEvent,
-            now_ns,
-            "ContainerStarted",
-            format!("Container {} started", container.name),
         );
 
         // Open (create) the container log file now, before StartContainer
@@ -1665,7 +1489,7 @@ impl RuntimeService for BoxRuntimeService {
             workload_stdins: self.workload_stdins.clone(),
             workload_stops: self.workload_stops.clone(),
             log_reopens: self.log_reopens.clone(),
-            container_events: self.container_events.clone(),
+            runtime_events: self.runtime_events.clone(),
             container_id: container_id.clone(),
             sandbox_id: container.sandbox_id.clone(),
             log_path: container.log_path.clone(),
@@ -1712,9 +1536,6 @@ impl RuntimeService for BoxRuntimeService {
                         container_id,
                         &container.sandbox_id,
                         ContainerEventType::ContainerStoppedEvent,
-                        now_ns,
-                        "StopContainer",
-                        "Created container stopped before workload start",
                     );
                 }
             }
@@ -1739,9 +1560,6 @@ impl RuntimeService for BoxRuntimeService {
                             container_id,
                             &container.sandbox_id,
                             ContainerEventType::ContainerStoppedEvent,
-                            now_ns,
-                            "StopContainer",
-                            "Container stopped by sandbox VM teardown fallback",
                         );
                     }
                 }
@@ -1786,14 +1604,10 @@ impl RuntimeService for BoxRuntimeService {
             self.workload_stdins.write().await.remove(container_id);
             self.workload_stops.write().await.remove(container_id);
             self.log_reopens.write().await.remove(container_id);
-            let event_time = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
             self.emit_container_event(
                 &removed.id,
                 &removed.sandbox_id,
                 ContainerEventType::ContainerDeletedEvent,
-                event_time,
-                "ContainerDeleted",
-                format!("Container {} removed", removed.name),
             );
             self.cleanup_container_rootfs_path(&removed.rootfs_path)
                 .await;
@@ -1816,17 +1630,6 @@ impl RuntimeService for BoxRuntimeService {
             .await
             .ok_or_else(|| Status::not_found(format!("Container not found: {}", container_id)))?;
 
-        let state = match container.state {
-            ContainerState::Created => crate::cri_api::ContainerState::ContainerCreated,
-            ContainerState::Running => crate::cri_api::ContainerState::ContainerRunning,
-            ContainerState::Exited => crate::cri_api::ContainerState::ContainerExited,
-        };
-        let (reason, message) = match container.state {
-            ContainerState::Exited => {
-                container_exit_reason(container.exit_code, container.oom_killed)
-            }
-            ContainerState::Created | ContainerState::Running => ("", String::new()),
-        };
         let info = if req.verbose {
             let vm_present = self
                 .vm_managers
@@ -1873,36 +1676,8 @@ impl RuntimeService for BoxRuntimeService {
             Default::default()
         };
 
-        let status = ContainerStatus {
-            id: container.id.clone(),
-            metadata: Some(ContainerMetadata {
-                name: container.name.clone(),
-                attempt: container.attempt,
-            }),
-            state: state.into(),
-            created_at: container.created_at,
-            started_at: container.started_at,
-            finished_at: container.finished_at,
-            exit_code: container.exit_code,
-            image: Some(ImageSpec {
-                image: container.image_ref.clone(),
-                annotations: Default::default(),
-            }),
-            image_ref: container.status_image_ref().to_string(),
-            reason: reason.to_string(),
-            message,
-            labels: container.labels.clone(),
-            annotations: container.annotations.clone(),
-            mounts: container
-                .mounts
-                .iter()
-                .map(container_mount_to_cri)
-                .collect(),
-            log_path: container.log_path.clone(),
-        };
-
         Ok(Response::new(ContainerStatusResponse {
-            status: Some(status),
+            status: Some(container_status_to_cri(&container)),
             info,
         }))
     }
@@ -2019,12 +1794,18 @@ impl RuntimeService for BoxRuntimeService {
         request: Request<StatusRequest>,
     ) -> Result<Response<StatusResponse>, Status> {
         let req = request.into_inner();
+        let preflight = self.preflight_report().await?;
+        let preflight_failure = preflight.failure_summary();
         let conditions = vec![
             RuntimeCondition {
                 r#type: "RuntimeReady".to_string(),
-                status: true,
-                reason: String::new(),
-                message: String::new(),
+                status: preflight_failure.is_none(),
+                reason: if preflight_failure.is_some() {
+                    "HostPreflightFailed".to_string()
+                } else {
+                    String::new()
+                },
+                message: preflight_failure.unwrap_or_default(),
             },
             RuntimeCondition {
                 r#type: "NetworkReady".to_string(),
@@ -2057,6 +1838,10 @@ impl RuntimeService for BoxRuntimeService {
                 .count();
 
             HashMap::from([
+                (
+                    "preflight".to_string(),
+                    serde_json::to_string(&preflight).unwrap_or_default(),
+                ),
                 ("sandbox_count".to_string(), sandboxes.len().to_string()),
                 (
                     "sandbox_ready_count".to_string(),
@@ -2142,31 +1927,128 @@ impl RuntimeService for BoxRuntimeService {
         request: Request<CheckpointContainerRequest>,
     ) -> Result<Response<CheckpointContainerResponse>, Status> {
         let req = request.into_inner();
-        Err(Status::unimplemented(format!(
-            "CheckpointContainer is not supported for microVM-backed container {}",
-            req.container_id
-        )))
+        let location = PathBuf::from(&req.location);
+        if !location.is_absolute() {
+            return Err(Status::invalid_argument(format!(
+                "Checkpoint location must be an absolute path: {:?}",
+                req.location
+            )));
+        }
+
+        let container = self
+            .store
+            .containers
+            .get(&req.container_id)
+            .await
+            .ok_or_else(|| {
+                Status::not_found(format!("Container not found: {}", req.container_id))
+            })?;
+        ensure_container_running(&container, "CheckpointContainer")?;
+        if container.rootfs_path.trim().is_empty() {
+            return Err(Status::failed_precondition(format!(
+                "Container {} has no prepared rootfs to checkpoint",
+                container.id
+            )));
+        }
+        let sandbox = self
+            .store
+            .sandboxes
+            .get(&container.sandbox_id)
+            .await
+            .ok_or_else(|| {
+                Status::not_found(format!("Sandbox not found: {}", container.sandbox_id))
+            })?;
+
+        // Pause the pod VM so the rootfs does not change under the archive.
+        {
+            let managers = self.vm_managers.read().await;
+            let vm = managers.get(&sandbox.id).ok_or_else(|| {
+                Status::failed_precondition(format!(
+                    "Sandbox {} not running (VM not found)",
+                    sandbox.id
+                ))
+            })?;
+            ensure_vm_ready(vm, "CheckpointContainer", &sandbox.id).await?;
+            vm.pause().await.map_err(box_error_to_status)?;
+        }
+        // A cancelled request must not leave the pod frozen.
+        let mut resume_guard = CancelGuard::new({
+            let vm_managers = Arc::clone(&self.vm_managers);
+            let sandbox_id = sandbox.id.clone();
+            move || {
+                if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                    handle.spawn(async move {
+                        if let Some(vm) = vm_managers.read().await.get(&sandbox_id) {
+                            let _ = vm.resume().await;
+                        }
+                    });
+                }
+            }
+        });
+
+        let cancelled = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let archive = tokio::task::spawn_blocking({
+            let location = location.clone();
+            let container = container.clone();
+            let sandbox = sandbox.clone();
+            let cancelled = Arc::clone(&cancelled);
+            move || {
+                write_checkpoint_archive(
+                    &location,
+                    &container,
+                    &sandbox,
+                    chrono::Utc::now(),
+                    &cancelled,
+                )
+            }
+        });
+        let result = if req.timeout > 0 {
+            tokio::time::timeout(std::time::Duration::from_secs(req.timeout as u64), archive).await
+        } else {
+            Ok(archive.await)
+        };
+
+        if let Some(vm) = self.vm_managers.read().await.get(&sandbox.id) {
+            if let Err(error) = vm.resume().await {
+                tracing::warn!(
+                    sandbox_id = %sandbox.id,
+                    error = %error,
+                    "Failed to resume pod VM after checkpoint"
+                );
+            }
+        }
+        resume_guard.disarm();
+
+        match result {
+            Err(_) => {
+                cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+                Err(Status::deadline_exceeded(format!(
+                    "Checkpoint of container {} did not finish within {}s",
+                    container.id, req.timeout
+                )))
+            }
+            Ok(Err(error)) => Err(Status::internal(format!("Checkpoint task failed: {error}"))),
+            Ok(Ok(Err(error))) => Err(Status::internal(format!(
+                "Failed to write checkpoint archive {}: {error}",
+                location.display()
+            ))),
+            Ok(Ok(Ok(()))) => {
+                tracing::info!(
+                    container_id = %container.id,
+                    sandbox_id = %sandbox.id,
+                    location = %location.display(),
+                    "CRI CheckpointContainer wrote archive"
+                );
+                Ok(Response::new(CheckpointContainerResponse {}))
+            }
+        }
     }
 
     async fn get_container_events(
         &self,
         _request: Request<GetEventsRequest>,
     ) -> Result<Response<Self::GetContainerEventsStream>, Status> {
-        let receiver = self.container_events.subscribe();
-        let stream = futures::stream::unfold(receiver, |mut receiver| async move {
-            loop {
-                match receiver.recv().await {
-                    Ok(event) => return Some((Ok(event), receiver)),
-                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
-                        tracing::warn!(
-                            skipped,
-                            "CRI container event stream lagged and dropped events"
-                        );
-                    }
-                    Err(broadcast::error::RecvError::Closed) => return None,
-                }
-            }
-        });
+        let stream = container_event_stream(self.runtime_events.subscribe(), self.store.clone());
         let stream: Self::GetContainerEventsStream = Box::pin(stream);
         Ok(Response::new(stream))
     }
@@ -2203,15 +2085,22 @@ impl RuntimeService for BoxRuntimeService {
             }
 
             let containers = self.store.containers.list(Some(&sandbox.id), None).await;
+            let vm_manager_present = vm_manager_ids.contains(&sandbox.id);
+            let usage = if vm_manager_present {
+                self.sandbox_vm_usage(&sandbox.id).await
+            } else {
+                VmUsage::default()
+            };
             metrics.push(pod_sandbox_metrics(
                 &sandbox,
                 &containers,
-                vm_manager_ids.contains(&sandbox.id),
+                vm_manager_present,
+                usage,
             ));
         }
 
         Ok(Response::new(ListPodSandboxMetricsResponse {
-            pod_sandbox_metrics: metrics,
+            pod_metrics: metrics,
         }))
     }
 
@@ -2228,7 +2117,7 @@ impl RuntimeService for BoxRuntimeService {
             .into_inner();
         let stream: Self::StreamPodSandboxMetricsStream = Box::pin(tokio_stream::iter(vec![Ok(
             StreamPodSandboxMetricsResponse {
-                pod_sandbox_metrics: response.pod_sandbox_metrics,
+                pod_metrics: response.pod_metrics,
             },
         )]));
         Ok(Response::new(stream))
@@ -2599,7 +2488,7 @@ impl RuntimeService for BoxRuntimeService {
             VmUsage::default()
         };
         Ok(Response::new(ContainerStatsResponse {
-            stats: Some(container_stats(&container, usage).await),
+            stats: Some(container_stats(&container, usage, &self.stats).await),
         }))
     }
 
@@ -2668,7 +2557,7 @@ impl RuntimeService for BoxRuntimeService {
             } else {
                 VmUsage::default()
             };
-            container_stats(c, usage)
+            container_stats(c, usage, &self.stats)
         }))
         .await;
 
@@ -2709,7 +2598,7 @@ impl RuntimeService for BoxRuntimeService {
         let vm_usage = self.sandbox_vm_usage(&sandbox_id).await;
 
         Ok(Response::new(PodSandboxStatsResponse {
-            stats: Some(pod_sandbox_stats(&sandbox, containers, vm_usage).await),
+            stats: Some(pod_sandbox_stats(&sandbox, containers, vm_usage, &self.stats).await),
         }))
     }
 
@@ -2735,7 +2624,7 @@ impl RuntimeService for BoxRuntimeService {
 
             let containers = self.store.containers.list(Some(&sandbox.id), None).await;
             let vm_usage = self.sandbox_vm_usage(&sandbox.id).await;
-            stats.push(pod_sandbox_stats(&sandbox, containers, vm_usage).await);
+            stats.push(pod_sandbox_stats(&sandbox, containers, vm_usage, &self.stats).await);
         }
 
         Ok(Response::new(ListPodSandboxStatsResponse { stats }))
@@ -2785,6 +2674,18 @@ impl RuntimeService for BoxRuntimeService {
         };
         ensure_container_running(&container, "UpdateContainerResources")?;
 
+        // kubelet's CPU and memory managers hand out host CPUs and NUMA
+        // nodes. Guest vCPUs are numbered independently of them, so the
+        // assignment pins the pod VM on the host instead of a guest cgroup.
+        if !linux.cpuset_cpus.is_empty() || !linux.cpuset_mems.is_empty() {
+            validate_cpuset("cpuset_cpus", &linux.cpuset_cpus).map_err(Status::invalid_argument)?;
+            validate_cpuset("cpuset_mems", &linux.cpuset_mems).map_err(Status::invalid_argument)?;
+            self.store
+                .set_container_cpuset(container_id, &linux.cpuset_cpus, &linux.cpuset_mems)
+                .await;
+            self.apply_pod_cpuset(&container.sandbox_id).await?;
+        }
+
         // Build a ResourceUpdate from the CRI request.
         // memory_limit_in_bytes maps to Tier 1 (immutable) — reject if set.
         // cpu_quota, cpu_period, cpu_shares map to Tier 2 (cgroup) — apply via exec.
@@ -2808,17 +2709,6 @@ impl RuntimeService for BoxRuntimeService {
         if linux.cpu_shares != 0 {
             update.limits.cpu_shares = Some(linux.cpu_shares as u64);
         }
-        if !linux.cpuset_cpus.is_empty() {
-            update.limits.cpuset_cpus = Some(linux.cpuset_cpus.clone());
-        }
-        if !linux.cpuset_mems.is_empty() {
-            // cpuset_mems is not directly supported, log and ignore
-            tracing::info!(
-                container_id = %container_id,
-                cpuset_mems = %linux.cpuset_mems,
-                "CRI cpuset_mems ignored (not supported in microVM)"
-            );
-        }
 
         if !update.has_tier2_changes() {
             tracing::info!(
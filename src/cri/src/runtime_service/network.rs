@@ -1,17 +1,22 @@
 //! Sandbox network helpers for the CRI runtime service.
 //!
-//! Bridge-network endpoint allocation, sandbox IP parsing, and
-//! NetworkStore connect/disconnect helpers used by [`super::BoxRuntimeService`].
+//! Bridge-network endpoint allocation, sandbox IP parsing, CNI setup and
+//! teardown, and NetworkStore connect/disconnect helpers used by
+//! [`super::BoxRuntimeService`].
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use tonic::Status;
 
 use a3s_box_core::error::BoxError;
+use a3s_box_core::network::NetworkConfig;
 use a3s_box_core::NetworkMode;
 use a3s_box_runtime::NetworkStore;
 
+use crate::cni::{self, CniIpv4, CniNetwork, CniOptions, CniPortMapping, CniRuntimeConf};
 use crate::config_mapper::ANN_NETWORK;
+use crate::cri_api::{port_mapping, PortMapping};
 use crate::error::box_error_to_status;
 use crate::sandbox::PodSandbox;
 
@@ -20,6 +25,10 @@ use super::convert::{ANN_ADDITIONAL_POD_IPS, ANN_POD_IP};
 pub(super) struct SandboxNetworkAllocation {
     pub(super) network_name: String,
     pub(super) ip: String,
+    /// Addresses beyond `ip` the network handed out (CNI dual-stack).
+    pub(super) additional_ips: Vec<String>,
+    /// Whether CNI plugins set the network up, so teardown runs CNI DEL.
+    pub(super) cni: bool,
 }
 
 pub(super) fn sandbox_network_status_from_annotations(
@@ -118,6 +127,8 @@ pub(super) fn connect_sandbox_to_network_store(
     Ok(SandboxNetworkAllocation {
         network_name: network_name.to_string(),
         ip,
+        additional_ips: Vec::new(),
+        cni: false,
     })
 }
 
@@ -139,6 +150,169 @@ pub(super) fn disconnect_sandbox_from_network_store(
         .map_err(box_error_to_status)
 }
 
+/// The CNI invocation for a sandbox: its namespace under `ip netns`, the
+/// Kubernetes `CNI_ARGS`, and the host ports for the portmap plugin.
+pub(super) fn cni_runtime_conf(
+    sandbox_id: &str,
+    namespace: &str,
+    name: &str,
+    uid: &str,
+    port_mappings: &[PortMapping],
+) -> CniRuntimeConf {
+    let args = [
+        ("IgnoreUnknown", "1"),
+        ("K8S_POD_NAMESPACE", namespace),
+        ("K8S_POD_NAME", name),
+        ("K8S_POD_INFRA_CONTAINER_ID", sandbox_id),
+        ("K8S_POD_UID", uid),
+    ];
+    CniRuntimeConf {
+        container_id: sandbox_id.to_string(),
+        netns: cni::netns_path(&cni::netns_name(sandbox_id)),
+        args: args
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        port_mappings: port_mappings
+            .iter()
+            .filter(|mapping| mapping.host_port > 0)
+            .map(|mapping| CniPortMapping {
+                host_port: mapping.host_port,
+                container_port: mapping.container_port,
+                protocol: port_mapping::Protocol::try_from(mapping.protocol)
+                    .unwrap_or(port_mapping::Protocol::Tcp)
+                    .as_str_name()
+                    .to_ascii_lowercase(),
+                host_ip: mapping.host_ip.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// Create the sandbox's network namespace, run CNI ADD in it, and register
+/// the address the plugins assigned in the network store so the VM's passt
+/// backend serves it from inside the namespace. Anything set up before a
+/// failure is torn down again.
+pub(super) fn add_sandbox_to_cni(
+    options: &CniOptions,
+    store: &NetworkStore,
+    rt: &CniRuntimeConf,
+    pod_name: &str,
+) -> Result<SandboxNetworkAllocation, Status> {
+    let network = CniNetwork::load(&options.conf_dir)
+        .map_err(|e| Status::failed_precondition(e.to_string()))?;
+    let network_name = format!("cni-{}", network.name);
+    cni::create_netns(&cni::netns_name(&rt.container_id)).map_err(box_error_to_status)?;
+
+    let added = network
+        .add(options, rt)
+        .map_err(box_error_to_status)
+        .and_then(|result| {
+            let ip = result.ipv4().ok_or_else(|| {
+                Status::failed_precondition(format!(
+                    "CNI network {} assigned no IPv4 address to sandbox {}",
+                    network.name, rt.container_id
+                ))
+            })?;
+            attach_sandbox_to_cni_network_store(
+                store,
+                &network_name,
+                ip,
+                &rt.container_id,
+                pod_name,
+                &rt.netns,
+            )?;
+            Ok(SandboxNetworkAllocation {
+                network_name: network_name.clone(),
+                ip: ip.address.to_string(),
+                additional_ips: result.additional_ips(),
+                cni: true,
+            })
+        });
+    if added.is_err() {
+        let _ = network.del(options, rt);
+        let _ = cni::remove_netns(&cni::netns_name(&rt.container_id));
+    }
+    added
+}
+
+/// Record a CNI-assigned address as an endpoint of the store network that
+/// mirrors the CNI network's subnet, creating that network on first use.
+fn attach_sandbox_to_cni_network_store(
+    store: &NetworkStore,
+    network_name: &str,
+    ip: CniIpv4,
+    sandbox_id: &str,
+    pod_name: &str,
+    netns: &Path,
+) -> Result<(), Status> {
+    let subnet = ip.subnet();
+    let outcome: Result<(), Status> = store
+        .with_write_lock(|networks| {
+            // The node's pod CIDR can change between runs; follow it once
+            // nothing is attached to the old one.
+            if let Some(existing) = networks.get(network_name) {
+                let moved = existing.subnet != subnet
+                    || ip.gateway.is_some_and(|gateway| gateway != existing.gateway);
+                if moved && !existing.endpoints.is_empty() {
+                    return Ok(Err(Status::failed_precondition(format!(
+                        "CNI network {network_name} moved to {subnet} while sandboxes are still attached to {}",
+                        existing.subnet
+                    ))));
+                }
+                if moved {
+                    networks.remove(network_name);
+                }
+            }
+            if !networks.contains_key(network_name) {
+                let created = NetworkConfig::new(network_name, &subnet).and_then(|mut network| {
+                    if let Some(gateway) = ip.gateway {
+                        network.set_gateway(gateway)?;
+                    }
+                    Ok(network)
+                });
+                match created {
+                    Ok(network) => networks.insert(network_name.to_string(), network),
+                    Err(e) => {
+                        return Ok(Err(Status::failed_precondition(format!(
+                            "Unsupported CNI address {}/{}: {e}",
+                            ip.address, ip.prefix_len
+                        ))))
+                    }
+                };
+            }
+            let network = networks
+                .get_mut(network_name)
+                .expect("CNI network was just ensured");
+            Ok::<_, BoxError>(
+                network
+                    .attach(sandbox_id, pod_name, ip.address, netns.to_path_buf())
+                    .map(|_| ())
+                    .map_err(|e| {
+                        Status::failed_precondition(format!(
+                            "Failed to attach sandbox {sandbox_id} to network {network_name}: {e}"
+                        ))
+                    }),
+            )
+        })
+        .map_err(box_error_to_status)?;
+    outcome
+}
+
+/// Run CNI DEL for a sandbox, delete its network namespace and drop its
+/// network store endpoint. Every step runs; the first failure is returned.
+pub(super) fn remove_sandbox_from_cni(
+    options: &CniOptions,
+    store: &NetworkStore,
+    network_name: &str,
+    rt: &CniRuntimeConf,
+) -> Result<(), Status> {
+    let deleted = CniNetwork::load(&options.conf_dir).and_then(|network| network.del(options, rt));
+    let removed = cni::remove_netns(&cni::netns_name(&rt.container_id));
+    disconnect_sandbox_from_network_store(store, network_name, &rt.container_id)?;
+    deleted.and(removed).map_err(box_error_to_status)
+}
+
 pub(super) fn default_network_store() -> NetworkStore {
     match NetworkStore::default_path() {
         Ok(store) => store,
@@ -199,4 +373,77 @@ mod tests {
             "every concurrent sandbox endpoint must be persisted"
         );
     }
+
+    #[test]
+    fn cni_addresses_are_mirrored_into_the_network_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = NetworkStore::new(dir.path().join("networks.json"));
+        let ip = |last: u8| CniIpv4 {
+            address: std::net::Ipv4Addr::new(10, 244, 1, last),
+            prefix_len: 24,
+            gateway: Some(std::net::Ipv4Addr::new(10, 244, 1, 1)),
+        };
+        let netns = Path::new("/var/run/netns/a3s-sb-1");
+
+        attach_sandbox_to_cni_network_store(&store, "cni-k8s", ip(7), "sb-1", "web", netns)
+            .unwrap();
+        let network = store.get("cni-k8s").unwrap().unwrap();
+        assert_eq!(network.subnet, "10.244.1.0/24");
+        assert_eq!(network.gateway, std::net::Ipv4Addr::new(10, 244, 1, 1));
+        assert_eq!(network.endpoints["sb-1"].netns.as_deref(), Some(netns));
+
+        // The same address twice is a conflict, not a silent overwrite.
+        let err =
+            attach_sandbox_to_cni_network_store(&store, "cni-k8s", ip(7), "sb-2", "api", netns)
+                .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+
+        // A pod CIDR change is refused while sandboxes use the old one, and
+        // followed once they are gone.
+        let moved = CniIpv4 {
+            address: std::net::Ipv4Addr::new(10, 244, 2, 5),
+            prefix_len: 24,
+            gateway: None,
+        };
+        assert!(attach_sandbox_to_cni_network_store(
+            &store, "cni-k8s", moved, "sb-2", "api", netns
+        )
+        .is_err());
+        disconnect_sandbox_from_network_store(&store, "cni-k8s", "sb-1").unwrap();
+        attach_sandbox_to_cni_network_store(&store, "cni-k8s", moved, "sb-2", "api", netns)
+            .unwrap();
+        assert_eq!(
+            store.get("cni-k8s").unwrap().unwrap().subnet,
+            "10.244.2.0/24"
+        );
+    }
+
+    #[test]
+    fn cni_runtime_conf_carries_pod_identity_and_host_ports() {
+        let mappings = [
+            PortMapping {
+                protocol: port_mapping::Protocol::Tcp as i32,
+                container_port: 80,
+                host_port: 8080,
+                host_ip: String::new(),
+            },
+            PortMapping {
+                protocol: port_mapping::Protocol::Tcp as i32,
+                container_port: 9090,
+                host_port: 0,
+                host_ip: String::new(),
+            },
+        ];
+        let rt = cni_runtime_conf("sb-1", "default", "web", "uid-1", &mappings);
+        assert_eq!(rt.netns, Path::new("/var/run/netns/a3s-sb-1"));
+        assert!(rt
+            .args
+            .contains(&("K8S_POD_NAME".to_string(), "web".to_string())));
+        assert!(rt
+            .args
+            .contains(&("K8S_POD_INFRA_CONTAINER_ID".to_string(), "sb-1".to_string())));
+        assert_eq!(rt.port_mappings.len(), 1);
+        assert_eq!(rt.port_mappings[0].protocol, "tcp");
+        assert_eq!(rt.port_mappings[0].host_port, 8080);
+    }
 }
@@ -0,0 +1,700 @@
+//! CRI container security context → guest-side confinement.
+//!
+//! guest-init confines the container process when it spawns it, and every
+//! exec into it. The runtime passes each control as an `A3S_SEC_*` entry of
+//! the container env: seccomp, the capability keep-set, `no_new_privs`,
+//! masked and read-only paths, and a read-only root. A request the guest
+//! cannot honor fails CreateContainer with a CRI error instead of running
+//! the container with weaker confinement than it asked for.
+
+use std::path::{Path, PathBuf};
+
+use tonic::Status;
+
+use crate::cri_api::namespace_option::NamespaceMode;
+use crate::cri_api::security_profile::ProfileType;
+use crate::cri_api::{Capability, LinuxContainerSecurityContext, NamespaceOption};
+
+/// Capability names the guest can drop or keep (`CAP_` prefix optional).
+const KNOWN_CAPABILITIES: &[&str] = &[
+    "CHOWN",
+    "DAC_OVERRIDE",
+    "DAC_READ_SEARCH",
+    "FOWNER",
+    "FSETID",
+    "KILL",
+    "SETGID",
+    "SETUID",
+    "SETPCAP",
+    "LINUX_IMMUTABLE",
+    "NET_BIND_SERVICE",
+    "NET_BROADCAST",
+    "NET_ADMIN",
+    "NET_RAW",
+    "IPC_LOCK",
+    "IPC_OWNER",
+    "SYS_MODULE",
+    "SYS_RAWIO",
+    "SYS_CHROOT",
+    "SYS_PTRACE",
+    "SYS_PACCT",
+    "SYS_ADMIN",
+    "SYS_BOOT",
+    "SYS_NICE",
+    "SYS_RESOURCE",
+    "SYS_TIME",
+    "SYS_TTY_CONFIG",
+    "MKNOD",
+    "LEASE",
+    "AUDIT_WRITE",
+    "AUDIT_CONTROL",
+    "SETFCAP",
+    "MAC_OVERRIDE",
+    "MAC_ADMIN",
+    "SYSLOG",
+    "WAKE_ALARM",
+    "BLOCK_SUSPEND",
+    "AUDIT_READ",
+    "PERFMON",
+    "BPF",
+    "CHECKPOINT_RESTORE",
+];
+
+/// Capabilities a non-privileged container keeps by default (matches the
+/// containerd/runc default set). A privileged container keeps the full set.
+const DEFAULT_CAPABILITIES: &[&str] = &[
+    "CHOWN",
+    "DAC_OVERRIDE",
+    "FSETID",
+    "FOWNER",
+    "MKNOD",
+    "NET_RAW",
+    "SETGID",
+    "SETUID",
+    "SETFCAP",
+    "SETPCAP",
+    "NET_BIND_SERVICE",
+    "SYS_CHROOT",
+    "KILL",
+    "AUDIT_WRITE",
+];
+
+/// Compute the capability set a non-privileged container keeps: the default set
+/// unioned with `add_capabilities` and minus `drop_capabilities` (`ALL` is
+/// honored on either side). Returns `None` when the container should keep the
+/// full set (an `add` of `ALL`), in which case no keep-set is emitted.
+fn kept_capabilities(capabilities: Option<&Capability>) -> Option<Vec<String>> {
+    let normalize = capability_name;
+    let mut kept: std::collections::BTreeSet<String> =
+        DEFAULT_CAPABILITIES.iter().map(|c| c.to_string()).collect();
+    if let Some(capabilities) = capabilities {
+        if capabilities
+            .add_capabilities
+            .iter()
+            .any(|c| normalize(c) == "ALL")
+        {
+            return None;
+        }
+        if capabilities
+            .drop_capabilities
+            .iter()
+            .any(|c| normalize(c) == "ALL")
+        {
+            kept.clear();
+        } else {
+            for cap in &capabilities.drop_capabilities {
+                kept.remove(&normalize(cap));
+            }
+        }
+        for cap in &capabilities.add_capabilities {
+            kept.insert(normalize(cap));
+        }
+    }
+    Some(kept.into_iter().collect())
+}
+
+/// Whether an AppArmor profile of the given name is currently loaded on the
+/// host (listed in `/sys/kernel/security/apparmor/profiles`).
+///
+/// Returns `false` when AppArmor is unavailable (file absent/unreadable), which
+/// makes a requested Localhost profile fail closed rather than run unconfined.
+fn apparmor_profile_loaded(name: &str) -> bool {
+    let Ok(content) = std::fs::read_to_string("/sys/kernel/security/apparmor/profiles") else {
+        return false;
+    };
+    // Each line is `<profile-name> (<mode>)`.
+    content
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some(name))
+}
+
+#[derive(serde::Deserialize)]
+struct OciSeccompProfile {
+    #[serde(rename = "defaultAction")]
+    default_action: String,
+    #[serde(default)]
+    syscalls: Vec<OciSeccompSyscall>,
+}
+
+#[derive(serde::Deserialize)]
+struct OciSeccompSyscall {
+    #[serde(default)]
+    names: Vec<String>,
+    action: String,
+    #[serde(default)]
+    args: Vec<serde_json::Value>,
+}
+
+/// The node's seccomp profile root — kubelet default, override with
+/// `A3S_BOX_SECCOMP_PROFILE_ROOT`.
+pub(super) fn seccomp_profile_root() -> PathBuf {
+    std::env::var("A3S_BOX_SECCOMP_PROFILE_ROOT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("/var/lib/kubelet/seccomp"))
+}
+
+/// Confine a CRI `localhostProfile` path to the seccomp profile `root` and reject
+/// `..` traversal.
+///
+/// `localhost_ref` comes from a pod's
+/// `securityContext.seccompProfile.localhostProfile` (attacker-settable through
+/// the CRI) and is read off the host disk — without confinement it is an
+/// arbitrary host-file open primitive. Mirrors kubelet/containerd semantics:
+/// profiles live under the configured seccomp root. A relative ref resolves under
+/// the root; an absolute ref must lie within it. `root` is injected so the check
+/// is testable without process-global env state.
+fn confined_seccomp_path(localhost_ref: &str, root: &std::path::Path) -> Result<PathBuf, String> {
+    let r = std::path::Path::new(localhost_ref);
+    if r.components()
+        .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return Err(format!(
+            "seccomp profile path contains '..': {localhost_ref}"
+        ));
+    }
+    let candidate = if r.is_absolute() {
+        r.to_path_buf()
+    } else {
+        root.join(r)
+    };
+    if !candidate.starts_with(root) {
+        return Err(format!(
+            "seccomp profile {} is outside the seccomp root {}",
+            candidate.display(),
+            root.display()
+        ));
+    }
+    Ok(candidate)
+}
+
+/// Parse a CRI localhost seccomp profile file and return the syscall names it
+/// blocks with `SCMP_ACT_ERRNO`/`SCMP_ACT_KILL*`.
+///
+/// Supports the conformance shape — a permissive default action
+/// (`SCMP_ACT_ALLOW`/`SCMP_ACT_LOG`) plus an ERRNO deny list. A deny-by-default
+/// profile (which would need a full allow-list compiled to BPF) and deny rules
+/// conditioned on syscall arguments are not yet supported; they return an
+/// error so the container fails to create rather than run under a different
+/// filter than the one asked for.
+pub(super) fn parse_localhost_seccomp_deny(
+    localhost_ref: &str,
+    root: &std::path::Path,
+) -> Result<Vec<String>, String> {
+    let path = confined_seccomp_path(localhost_ref, root)?;
+    let raw = std::fs::read_to_string(&path)
+        .map_err(|e| format!("read seccomp profile {}: {e}", path.display()))?;
+    let profile: OciSeccompProfile =
+        serde_json::from_str(&raw).map_err(|e| format!("parse seccomp profile: {e}"))?;
+    if !matches!(
+        profile.default_action.as_str(),
+        "SCMP_ACT_ALLOW" | "SCMP_ACT_LOG"
+    ) {
+        return Err(format!(
+            "unsupported seccomp defaultAction {} (only allow-default profiles are supported)",
+            profile.default_action
+        ));
+    }
+    let deny: Vec<OciSeccompSyscall> = profile
+        .syscalls
+        .into_iter()
+        .filter(|s| {
+            matches!(
+                s.action.as_str(),
+                "SCMP_ACT_ERRNO"
+                    | "SCMP_ACT_KILL"
+                    | "SCMP_ACT_KILL_THREAD"
+                    | "SCMP_ACT_KILL_PROCESS"
+            )
+        })
+        .collect();
+    if let Some(rule) = deny.iter().find(|s| !s.args.is_empty()) {
+        return Err(format!(
+            "unsupported seccomp rule for {}: argument filters are not supported",
+            rule.names.join(",")
+        ));
+    }
+    Ok(deny.into_iter().flat_map(|s| s.names).collect())
+}
+
+/// The seccomp profile a container asked for.
+#[derive(Debug, PartialEq, Eq)]
+enum SeccompRequest {
+    Unconfined,
+    RuntimeDefault,
+    Localhost(String),
+}
+
+/// Read the container's seccomp choice from the `seccomp` profile, or from
+/// the deprecated `seccomp_profile_path` string older kubelets still send.
+fn requested_seccomp(sc: &LinuxContainerSecurityContext) -> Result<SeccompRequest, Status> {
+    if let Some(profile) = sc.seccomp.as_ref() {
+        return match profile.profile_type() {
+            ProfileType::RuntimeDefault => Ok(SeccompRequest::RuntimeDefault),
+            ProfileType::Unconfined => Ok(SeccompRequest::Unconfined),
+            ProfileType::Localhost if profile.localhost_ref.trim().is_empty() => Err(
+                Status::invalid_argument("seccomp Localhost profile requires a localhost_ref"),
+            ),
+            ProfileType::Localhost => Ok(SeccompRequest::Localhost(
+                profile.localhost_ref.trim().to_string(),
+            )),
+        };
+    }
+    #[allow(deprecated)] // the only field older kubelets set
+    let path = sc.seccomp_profile_path.trim();
+    match path {
+        "" | "unconfined" => Ok(SeccompRequest::Unconfined),
+        "runtime/default" | "docker/default" => Ok(SeccompRequest::RuntimeDefault),
+        _ => match path.strip_prefix("localhost/") {
+            Some(localhost_ref) if !localhost_ref.is_empty() => {
+                Ok(SeccompRequest::Localhost(localhost_ref.to_string()))
+            }
+            _ => Err(Status::invalid_argument(format!(
+                "unsupported seccomp_profile_path {path:?}"
+            ))),
+        },
+    }
+}
+
+/// A capability name without its optional `CAP_` prefix, upper-cased.
+fn capability_name(cap: &str) -> String {
+    let cap = cap.trim().to_uppercase();
+    cap.strip_prefix("CAP_").unwrap_or(&cap).to_string()
+}
+
+/// Reject capability names the guest does not know, which it would
+/// otherwise skip, and ambient capabilities, which it does not raise.
+fn validate_capabilities(capabilities: Option<&Capability>) -> Result<(), Status> {
+    let Some(capabilities) = capabilities else {
+        return Ok(());
+    };
+    if !capabilities.add_ambient_capabilities.is_empty() {
+        return Err(Status::unimplemented(
+            "ambient capabilities are not yet supported for microVM-backed containers",
+        ));
+    }
+    for cap in capabilities
+        .add_capabilities
+        .iter()
+        .chain(&capabilities.drop_capabilities)
+    {
+        let name = capability_name(cap);
+        if name != "ALL" && !KNOWN_CAPABILITIES.contains(&name.as_str()) {
+            return Err(Status::invalid_argument(format!(
+                "unknown capability {cap:?}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Translate a container's security context into the `A3S_SEC_*` entries
+/// guest-init enforces. `sandbox_privileged` is the pod's own privileged
+/// flag, when the request carried the sandbox config; seccomp Localhost
+/// profiles resolve under `seccomp_root`.
+pub(super) fn container_security_env(
+    sc: &LinuxContainerSecurityContext,
+    container: &str,
+    sandbox_privileged: Option<bool>,
+    seccomp_root: &Path,
+) -> Result<Vec<(String, String)>, Status> {
+    let mut env = Vec::new();
+    // CRI requires run_as_group to be set only alongside run_as_user or
+    // run_as_username; otherwise the runtime MUST reject the container.
+    if sc.run_as_group.is_some() && sc.run_as_user.is_none() && sc.run_as_username.is_empty() {
+        return Err(Status::invalid_argument(
+            "run_as_group must not be set without run_as_user or run_as_username",
+        ));
+    }
+    // A privileged container needs a privileged pod, as with runc.
+    if sc.privileged && sandbox_privileged == Some(false) {
+        return Err(Status::invalid_argument(
+            "privileged container requires a privileged pod sandbox",
+        ));
+    }
+    validate_capabilities(sc.capabilities.as_ref())?;
+    let seccomp = requested_seccomp(sc)?;
+
+    // Carry CRI SupplementalGroups to guest-init over the env channel;
+    // the guest applies them with setgroups before dropping privileges.
+    if !sc.supplemental_groups.is_empty() {
+        let groups = sc
+            .supplemental_groups
+            .iter()
+            .map(|gid| gid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        env.push(("A3S_SEC_SUPPLEMENTAL_GROUPS".to_string(), groups));
+    }
+    // CRI MaskedPaths/ReadonlyPaths — ':'-separated absolute paths the
+    // guest masks (bind /dev/null or ro tmpfs) / remounts read-only
+    // inside the container rootfs.
+    if !sc.masked_paths.is_empty() {
+        env.push((
+            "A3S_SEC_MASKED_PATHS".to_string(),
+            sc.masked_paths.join(":"),
+        ));
+    }
+    if !sc.readonly_paths.is_empty() {
+        env.push((
+            "A3S_SEC_READONLY_PATHS".to_string(),
+            sc.readonly_paths.join(":"),
+        ));
+    }
+    // CRI seccomp: RuntimeDefault installs the default BPF filter in the
+    // guest (Seccomp: 2); Unconfined / unset leave the container
+    // unconfined; Localhost compiles the profile's deny list. Privileged
+    // containers bypass seccomp entirely — a requested profile is
+    // ignored when privileged (per the CRI contract).
+    if !sc.privileged {
+        match seccomp {
+            SeccompRequest::RuntimeDefault => {
+                env.push(("A3S_SEC_SECCOMP".to_string(), "default".to_string()));
+            }
+            SeccompRequest::Localhost(localhost_ref) => {
+                // Read + parse the localhost profile (allow-default +
+                // ERRNO deny list) and pass the blocked syscalls to the
+                // guest, which compiles them into a BPF filter.
+                let deny =
+                    parse_localhost_seccomp_deny(&localhost_ref, seccomp_root).map_err(|e| {
+                        Status::failed_precondition(format!(
+                            "seccomp profile localhost/{localhost_ref}: {e}"
+                        ))
+                    })?;
+                env.push(("A3S_SEC_SECCOMP_LOCALHOST".to_string(), deny.join(",")));
+            }
+            SeccompRequest::Unconfined => {}
+        }
+    }
+    // CRI capabilities: a privileged container keeps the full set; a
+    // non-privileged one is restricted to the runtime default set,
+    // adjusted by add/drop (A3S_SEC_CAP_KEEP — the guest drops every
+    // capability not listed). This is what stops a non-privileged
+    // container from doing privileged operations (e.g. creating a bridge
+    // with CAP_NET_ADMIN). An `add` of `ALL` keeps the full set.
+    if !sc.privileged {
+        if let Some(kept) = kept_capabilities(sc.capabilities.as_ref()) {
+            env.push(("A3S_SEC_CAP_KEEP".to_string(), kept.join(",")));
+        }
+    }
+    // no_new_privs: the guest sets PR_SET_NO_NEW_PRIVS before exec so a
+    // setuid/setgid (or file-capability) binary can no longer raise the
+    // process's privileges. Privileged containers opt out.
+    if sc.no_new_privs && !sc.privileged {
+        env.push(("A3S_SEC_NO_NEW_PRIVS".to_string(), "1".to_string()));
+    }
+    // readonly_rootfs: the guest remounts the container root read-only
+    // before exec. This is a deliberate config (not a hardening default),
+    // so it applies to privileged containers too.
+    if sc.readonly_rootfs {
+        env.push(("A3S_SEC_READONLY_ROOTFS".to_string(), "1".to_string()));
+    }
+    // AppArmor: a microVM cannot enforce an in-guest LSM profile, but a
+    // requested Localhost profile must not be silently ignored. Validate
+    // it against the host's loaded profiles and reject when it is not
+    // loaded (the CRI contract: an unloaded profile fails). A loaded
+    // profile is accepted but cannot be enforced in-guest. The modern
+    // `apparmor` SecurityProfile takes precedence over the deprecated
+    // `apparmor_profile` string.
+    #[allow(deprecated)] // clients (incl. critest) still send apparmor_profile
+    let apparmor_localhost = sc
+        .apparmor
+        .as_ref()
+        .filter(|profile| profile.profile_type() == ProfileType::Localhost)
+        .map(|profile| profile.localhost_ref.clone())
+        .or_else(|| {
+            sc.apparmor_profile
+                .strip_prefix("localhost/")
+                .map(|name| name.to_string())
+        });
+    if let Some(profile) = apparmor_localhost {
+        let profile = profile.trim();
+        if profile.is_empty() || !apparmor_profile_loaded(profile) {
+            return Err(Status::failed_precondition(format!(
+                "AppArmor profile 'localhost/{profile}' is not loaded"
+            )));
+        }
+        tracing::warn!(
+            container = %container,
+            profile = %profile,
+            "AppArmor localhost profile is loaded on the host but the microVM \
+             runtime cannot enforce it in-guest; the container runs without \
+             AppArmor confinement"
+        );
+    }
+    Ok(env)
+}
+
+/// The `A3S_SEC_PID_NS` entry for a container's CRI PID namespace mode.
+/// `CONTAINER` asks the guest for a private process tree; `TARGET` joins the
+/// one of the container whose guest rootfs is `target_rootfs` (looked up by
+/// the caller, and only within the same pod). `POD` and `NODE` keep the
+/// VM-wide namespace every container of the pod already shares.
+pub(super) fn pid_namespace_env(
+    options: Option<&NamespaceOption>,
+    target_rootfs: Option<&str>,
+) -> Result<Option<(String, String)>, Status> {
+    let Some(options) = options else {
+        return Ok(None);
+    };
+    let value = match NamespaceMode::try_from(options.pid) {
+        Ok(NamespaceMode::Container) => "private".to_string(),
+        Ok(NamespaceMode::Target) => match target_rootfs.filter(|rootfs| !rootfs.is_empty()) {
+            Some(rootfs) => format!("target:{rootfs}"),
+            None => {
+                return Err(Status::invalid_argument(format!(
+                    "PID namespace target container {:?} is not a container of this pod",
+                    options.target_id
+                )))
+            }
+        },
+        Ok(NamespaceMode::Pod | NamespaceMode::Node) => return Ok(None),
+        Err(_) => {
+            return Err(Status::invalid_argument(format!(
+                "invalid PID namespace mode {}",
+                options.pid
+            )))
+        }
+    };
+    Ok(Some(("A3S_SEC_PID_NS".to_string(), value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confines_localhost_profile_to_root_and_rejects_traversal() {
+        let root = Path::new("/var/lib/kubelet/seccomp");
+        // In-root relative/absolute refs are allowed (the check is lexical, so
+        // the files need not exist).
+        assert!(confined_seccomp_path("audit.json", root).is_ok());
+        assert!(confined_seccomp_path("profiles/audit.json", root).is_ok());
+        assert!(confined_seccomp_path("/var/lib/kubelet/seccomp/audit.json", root).is_ok());
+
+        // SECURITY: a malicious localhostProfile must not open arbitrary host
+        // files — traversal, out-of-root absolute paths, and prefix-confusion
+        // are all rejected (CreateContainer then fails).
+        for evil in [
+            "../../../../etc/passwd",
+            "sub/../../escape",
+            "/etc/passwd",
+            "/etc/shadow",
+            "/var/lib/kubelet/seccomp-evil/x",
+        ] {
+            assert!(
+                confined_seccomp_path(evil, root).is_err(),
+                "must reject malicious seccomp path: {evil}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_requested_seccomp_reads_deprecated_path() {
+        use crate::cri_api::SecurityProfile;
+
+        #[allow(deprecated)]
+        let with_path = |path: &str| LinuxContainerSecurityContext {
+            seccomp_profile_path: path.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            requested_seccomp(&with_path("")).unwrap(),
+            SeccompRequest::Unconfined
+        );
+        assert_eq!(
+            requested_seccomp(&with_path("runtime/default")).unwrap(),
+            SeccompRequest::RuntimeDefault
+        );
+        assert_eq!(
+            requested_seccomp(&with_path("localhost/audit.json")).unwrap(),
+            SeccompRequest::Localhost("audit.json".to_string())
+        );
+        assert!(requested_seccomp(&with_path("localhost/")).is_err());
+        assert!(requested_seccomp(&with_path("bogus")).is_err());
+
+        // The structured profile wins over the deprecated string.
+        let mut sc = with_path("unconfined");
+        sc.seccomp = Some(SecurityProfile {
+            profile_type: ProfileType::RuntimeDefault as i32,
+            ..Default::default()
+        });
+        assert_eq!(
+            requested_seccomp(&sc).unwrap(),
+            SeccompRequest::RuntimeDefault
+        );
+        sc.seccomp = Some(SecurityProfile {
+            profile_type: ProfileType::Localhost as i32,
+            ..Default::default()
+        });
+        assert_eq!(
+            requested_seccomp(&sc).unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn test_container_security_env_rejects_unsupported_requests() {
+        use crate::cri_api::SecurityProfile;
+
+        let root = tempfile::tempdir().unwrap();
+        let env = |sc: &LinuxContainerSecurityContext, sandbox_privileged| {
+            container_security_env(sc, "app", sandbox_privileged, root.path())
+        };
+
+        let unknown_cap = LinuxContainerSecurityContext {
+            capabilities: Some(Capability {
+                add_capabilities: vec!["CAP_NET_WIZARD".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            env(&unknown_cap, None).unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+        let ambient = LinuxContainerSecurityContext {
+            capabilities: Some(Capability {
+                add_ambient_capabilities: vec!["NET_BIND_SERVICE".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            env(&ambient, None).unwrap_err().code(),
+            tonic::Code::Unimplemented
+        );
+
+        let privileged = LinuxContainerSecurityContext {
+            privileged: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            env(&privileged, Some(false)).unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+        assert!(env(&privileged, Some(true)).unwrap().is_empty());
+        assert!(env(&privileged, None).unwrap().is_empty());
+
+        // A Localhost profile that cannot be applied fails the container
+        // instead of running it under RuntimeDefault.
+        let missing_profile = LinuxContainerSecurityContext {
+            seccomp: Some(SecurityProfile {
+                profile_type: ProfileType::Localhost as i32,
+                localhost_ref: "missing.json".to_string(),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            env(&missing_profile, None).unwrap_err().code(),
+            tonic::Code::FailedPrecondition
+        );
+        std::fs::write(
+            root.path().join("args.json"),
+            r#"{"defaultAction":"SCMP_ACT_ALLOW","syscalls":[{"names":["personality"],"action":"SCMP_ACT_ERRNO","args":[{"index":0,"value":8,"op":"SCMP_CMP_EQ"}]}]}"#,
+        )
+        .unwrap();
+        let args_profile = LinuxContainerSecurityContext {
+            seccomp: Some(SecurityProfile {
+                profile_type: ProfileType::Localhost as i32,
+                localhost_ref: "args.json".to_string(),
+            }),
+            ..Default::default()
+        };
+        let err = env(&args_profile, None).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+        assert!(err.message().contains("argument filters"));
+    }
+
+    #[test]
+    fn test_container_security_env_maps_controls() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("block-chmod.json"),
+            r#"{"defaultAction":"SCMP_ACT_ALLOW","syscalls":[{"names":["chmod"],"action":"SCMP_ACT_ERRNO"}]}"#,
+        )
+        .unwrap();
+        #[allow(deprecated)]
+        let sc = LinuxContainerSecurityContext {
+            seccomp_profile_path: "localhost/block-chmod.json".to_string(),
+            capabilities: Some(Capability {
+                drop_capabilities: vec!["ALL".to_string()],
+                add_capabilities: vec!["cap_net_bind_service".to_string()],
+                ..Default::default()
+            }),
+            no_new_privs: true,
+            readonly_rootfs: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            container_security_env(&sc, "app", Some(false), root.path()).unwrap(),
+            vec![
+                ("A3S_SEC_SECCOMP_LOCALHOST".to_string(), "chmod".to_string()),
+                (
+                    "A3S_SEC_CAP_KEEP".to_string(),
+                    "NET_BIND_SERVICE".to_string()
+                ),
+                ("A3S_SEC_NO_NEW_PRIVS".to_string(), "1".to_string()),
+                ("A3S_SEC_READONLY_ROOTFS".to_string(), "1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pid_namespace_env_maps_modes() {
+        let with_pid = |mode: NamespaceMode| NamespaceOption {
+            pid: mode as i32,
+            target_id: "sidecar".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(pid_namespace_env(None, None).unwrap(), None);
+        assert_eq!(
+            pid_namespace_env(Some(&with_pid(NamespaceMode::Pod)), None).unwrap(),
+            None
+        );
+        assert_eq!(
+            pid_namespace_env(Some(&with_pid(NamespaceMode::Node)), None).unwrap(),
+            None
+        );
+        assert_eq!(
+            pid_namespace_env(Some(&with_pid(NamespaceMode::Container)), None).unwrap(),
+            Some(("A3S_SEC_PID_NS".to_string(), "private".to_string()))
+        );
+        assert_eq!(
+            pid_namespace_env(
+                Some(&with_pid(NamespaceMode::Target)),
+                Some("/run/a3s/rootfs/sidecar")
+            )
+            .unwrap(),
+            Some((
+                "A3S_SEC_PID_NS".to_string(),
+                "target:/run/a3s/rootfs/sidecar".to_string()
+            ))
+        );
+        assert_eq!(
+            pid_namespace_env(Some(&with_pid(NamespaceMode::Target)), None)
+                .unwrap_err()
+                .code(),
+            tonic::Code::InvalidArgument
+        );
+    }
+}
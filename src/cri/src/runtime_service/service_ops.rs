@@ -144,12 +144,114 @@ impl BoxRuntimeService {
     }
 
     pub(super) async fn disconnect_sandbox_network(&self, sandbox: &PodSandbox) {
-        if let Some(network_name) = sandbox_network_name(sandbox) {
+        if let Some(network_name) = &sandbox.cni_network {
+            let rt = cni_runtime_conf(
+                &sandbox.id,
+                &sandbox.namespace,
+                &sandbox.name,
+                &sandbox.uid,
+                &[],
+            );
+            self.disconnect_sandbox_cni(network_name, rt).await;
+        } else if let Some(network_name) = sandbox_network_name(sandbox) {
             self.disconnect_sandbox_network_by_name(&network_name, &sandbox.id)
                 .await;
         }
     }
 
+    /// Hand a sandbox to the configured CNI plugins and point its VM's passt
+    /// backend at the namespace and address they set up.
+    pub(super) async fn connect_sandbox_cni(
+        &self,
+        options: &CniOptions,
+        box_config: &mut a3s_box_core::config::BoxConfig,
+        sandbox_id: &str,
+        config: &PodSandboxConfig,
+    ) -> Result<SandboxNetworkAllocation, Status> {
+        let metadata = config.metadata.clone().unwrap_or_default();
+        let rt = cni_runtime_conf(
+            sandbox_id,
+            &metadata.namespace,
+            &metadata.name,
+            &metadata.uid,
+            &config.port_mappings,
+        );
+        let options = options.clone();
+        let store = self.network_store.clone();
+        let allocation = tokio::task::spawn_blocking(move || {
+            add_sandbox_to_cni(&options, &store, &rt, &metadata.name)
+        })
+        .await
+        .map_err(|e| Status::internal(format!("CRI sandbox CNI setup task failed: {e}")))??;
+
+        box_config.network = a3s_box_core::NetworkMode::Bridge {
+            network: allocation.network_name.clone(),
+        };
+        // The portmap plugin forwards host ports to the pod IP, so passt only
+        // accepts the container ports inside the namespace.
+        box_config.port_map = config
+            .port_mappings
+            .iter()
+            .map(|mapping| format!("{0}:{0}", mapping.container_port))
+            .collect();
+        Ok(allocation)
+    }
+
+    pub(super) async fn disconnect_sandbox_cni(&self, network_name: &str, rt: CniRuntimeConf) {
+        // A runtime restarted without CNI still tears down what an earlier
+        // run set up, from the default locations.
+        let options = self.runtime_options.cni.clone().unwrap_or_default();
+        let store = self.network_store.clone();
+        let task_network_name = network_name.to_string();
+        let sandbox_id = rt.container_id.clone();
+        match tokio::task::spawn_blocking(move || {
+            remove_sandbox_from_cni(&options, &store, &task_network_name, &rt)
+        })
+        .await
+        {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => {
+                tracing::warn!(
+                    sandbox_id = %sandbox_id,
+                    network = %network_name,
+                    error = %error,
+                    "Failed to remove CRI sandbox from CNI network"
+                );
+            }
+            Err(error) => {
+                tracing::warn!(
+                    sandbox_id = %sandbox_id,
+                    network = %network_name,
+                    error = %error,
+                    "CRI sandbox CNI teardown task failed"
+                );
+            }
+        }
+    }
+
+    /// Undo a network allocation made for a sandbox that failed to start.
+    pub(super) async fn release_sandbox_network(
+        &self,
+        allocation: &SandboxNetworkAllocation,
+        sandbox_id: &str,
+        metadata: &PodSandboxMetadata,
+    ) {
+        if allocation.cni {
+            let rt = cni_runtime_conf(
+                sandbox_id,
+                &metadata.namespace,
+                &metadata.name,
+                &metadata.uid,
+                &[],
+            );
+            self.disconnect_sandbox_cni(&allocation.network_name, rt)
+                .await;
+        } else {
+            self.disconnect_sandbox_network_by_name(&allocation.network_name, sandbox_id)
+                .await;
+        }
+    }
+
     pub(super) async fn resolve_container_image(
         &self,
         image_ref: &str,
@@ -401,19 +503,12 @@ impl BoxRuntimeService {
         container_id: &str,
         sandbox_id: &str,
         container_event_type: ContainerEventType,
-        created_at: i64,
-        reason: impl Into<String>,
-        message: impl Into<String>,
     ) {
-        let event = container_event_response(
+        self.runtime_events.emit(lifecycle_event(
+            container_event_type,
             container_id,
             sandbox_id,
-            container_event_type,
-            created_at,
-            reason,
-            message,
-        );
-        let _ = self.container_events.send(event);
+        ));
     }
 
     pub(super) async fn acquire_vm_with_box_id(
@@ -462,7 +557,7 @@ impl BoxRuntimeService {
         #[cfg(test)]
         if let Some(exec_socket_path) = &self.test_vm_exec_socket_path {
             let box_id = box_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-            let mut vm = VmManager::with_box_id(box_config, EventEmitter::new(256), box_id);
+            let mut vm = VmManager::with_box_id(box_config, self.runtime_events.clone(), box_id);
             vm.attach_running_process(
                 std::process::id(),
                 exec_socket_path.clone(),
@@ -473,7 +568,7 @@ impl BoxRuntimeService {
             return Ok(vm);
         }
 
-        let event_emitter = EventEmitter::new(256);
+        let event_emitter = self.runtime_events.clone();
         let mut vm = match box_id {
             Some(box_id) => VmManager::with_box_id(box_config, event_emitter, box_id),
             None => VmManager::new(box_config, event_emitter),
@@ -488,19 +583,173 @@ impl BoxRuntimeService {
         timeout_ms: Option<u64>,
     ) -> Result<bool, Status> {
         let vm = self.vm_managers.write().await.remove(sandbox_id);
-        let Some(mut vm) = vm else {
-            return Ok(false);
+        self.stats.forget(sandbox_id);
+        let destroyed = match vm {
+            Some(mut vm) => {
+                match timeout_ms {
+                    Some(timeout_ms) => vm
+                        .destroy_with_timeout(timeout_ms)
+                        .await
+                        .map_err(box_error_to_status)?,
+                    None => vm.destroy().await.map_err(box_error_to_status)?,
+                }
+                true
+            }
+            None => false,
         };
 
-        match timeout_ms {
-            Some(timeout_ms) => vm
-                .destroy_with_timeout(timeout_ms)
-                .await
-                .map_err(box_error_to_status)?,
-            None => vm.destroy().await.map_err(box_error_to_status)?,
+        // The VM's processes are gone, so its cgroup is empty and kubelet can
+        // remove the pod cgroup around it.
+        if let Some(path) = self
+            .store
+            .sandboxes
+            .get(sandbox_id)
+            .await
+            .and_then(|sandbox| sandbox.host_cgroup)
+        {
+            if let Err(e) = remove_cgroup(Path::new(CGROUP_ROOT), Path::new(&path)) {
+                tracing::warn!(
+                    sandbox_id = %sandbox_id,
+                    cgroup = %path,
+                    error = %e,
+                    "Failed to remove CRI sandbox host cgroup"
+                );
+            }
+        }
+
+        Ok(destroyed)
+    }
+
+    /// Move a sandbox VM into the pod cgroup kubelet made for it and cap it
+    /// at the pod's limits. Returns the cgroup's path below the cgroup root
+    /// for teardown, or `None` when the VM stays where it is: no usable
+    /// `cgroup_parent`, no unified hierarchy, or a failed write. Placement is
+    /// best effort, so none of these fail the sandbox.
+    pub(super) async fn place_sandbox_vm_in_pod_cgroup(
+        &self,
+        sandbox_id: &str,
+        linux: Option<&LinuxPodSandboxConfig>,
+        vm: &VmManager,
+        vm_memory_bytes: u64,
+    ) -> Option<String> {
+        let linux = linux.filter(|linux| !linux.cgroup_parent.trim().is_empty())?;
+        let Some(cgroup) = PodCgroup::for_sandbox(sandbox_id, linux, vm_memory_bytes) else {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                cgroup_parent = %linux.cgroup_parent,
+                "Unrecognized pod cgroup_parent; leaving the sandbox VM in place"
+            );
+            return None;
+        };
+        if !cfg!(target_os = "linux") {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                "Pod cgroups are only supported on Linux; ignoring cgroup_parent"
+            );
+            return None;
+        }
+        if !is_unified_hierarchy(Path::new(CGROUP_ROOT)) {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                "Pod cgroups need the unified (v2) hierarchy; leaving the sandbox VM in place"
+            );
+            return None;
+        }
+        let Some(pid) = vm.pid().await else {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                "CRI sandbox VM has no process to place in the pod cgroup"
+            );
+            return None;
+        };
+
+        if let Err(e) = cgroup.place(Path::new(CGROUP_ROOT), pid) {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                cgroup = %cgroup.path.display(),
+                error = %e,
+                "Failed to place CRI sandbox VM in pod cgroup; leaving it in place"
+            );
+            // Only succeeds if the VM never moved in, which is the case to
+            // clean up.
+            let _ = remove_cgroup(Path::new(CGROUP_ROOT), &cgroup.path);
+            return None;
         }
+        if let Err(e) = cgroup.apply_oom_score_adj(Path::new("/proc"), pid) {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                pid,
+                error = %e,
+                "Failed to set CRI sandbox VM oom_score_adj"
+            );
+        }
+        tracing::info!(
+            sandbox_id = %sandbox_id,
+            cgroup = %cgroup.path.display(),
+            qos = ?cgroup.qos,
+            "Placed CRI sandbox VM in pod cgroup"
+        );
+        Some(cgroup.path.to_string_lossy().into_owned())
+    }
 
-        Ok(true)
+    /// Confine a sandbox VM to the host CPUs and NUMA nodes kubelet assigned
+    /// its containers: the cpuset of its pod cgroup, when it has one, and the
+    /// affinity of every VM thread. A pod without assignments is left alone.
+    pub(super) async fn apply_pod_cpuset(&self, sandbox_id: &str) -> Result<(), Status> {
+        let containers = self.store.containers.list(Some(sandbox_id), None).await;
+        let Some(cpuset) = PodCpuset::for_containers(&containers) else {
+            return Ok(());
+        };
+        if !cfg!(target_os = "linux") {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                "CPU and NUMA pinning is only supported on Linux; ignoring cpuset"
+            );
+            return Ok(());
+        }
+
+        let host_cgroup = self
+            .store
+            .sandboxes
+            .get(sandbox_id)
+            .await
+            .and_then(|sandbox| sandbox.host_cgroup);
+        match &host_cgroup {
+            Some(cgroup) => cpuset
+                .place(Path::new(CGROUP_ROOT), Path::new(cgroup))
+                .map_err(|e| {
+                    Status::internal(format!(
+                        "Failed to write cpuset of sandbox {sandbox_id} cgroup {cgroup}: {e}"
+                    ))
+                })?,
+            None if !cpuset.mems.is_empty() => tracing::warn!(
+                sandbox_id = %sandbox_id,
+                "NUMA node assignment needs a pod cgroup; guest memory is not bound"
+            ),
+            None => {}
+        }
+
+        let pid = match self.vm_managers.read().await.get(sandbox_id) {
+            Some(vm) => vm.pid().await,
+            None => None,
+        };
+        let Some(pid) = pid else {
+            return Err(Status::failed_precondition(format!(
+                "Sandbox {sandbox_id} not running (VM not found)"
+            )));
+        };
+        if !cpuset.cpus.is_empty() {
+            a3s_box_runtime::numa::set_process_affinity(pid, &cpuset.cpus).map_err(|e| {
+                Status::internal(format!("Failed to pin sandbox {sandbox_id} VM: {e}"))
+            })?;
+        }
+        tracing::info!(
+            sandbox_id = %sandbox_id,
+            cpus = %a3s_box_runtime::numa::format_cpu_list(&cpuset.cpus),
+            mems = %a3s_box_runtime::numa::format_cpu_list(&cpuset.mems),
+            "Pinned CRI sandbox VM"
+        );
+        Ok(())
     }
 }
 
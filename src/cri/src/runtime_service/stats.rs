@@ -1,28 +1,47 @@
 //! Container and pod sandbox statistics and metrics for the CRI runtime service.
 //!
 //! Filesystem usage collection plus CPU/memory/network stat and metric
-//! builders used by [`super::BoxRuntimeService`].
+//! builders used by [`super::BoxRuntimeService`]. Pod usage comes from the
+//! guest metrics channel when the guest answers, and from the host-side VMM
+//! process otherwise; [`StatsCollector`] keeps the last samples so CPU rates
+//! can be derived and, in cached mode, repeated stats calls reuse them.
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use a3s_box_core::exec::{GuestMetrics, GuestMetricsRequest};
 use futures::future::join_all;
+use parking_lot::Mutex;
 
+use super::StatsCollection;
 use crate::container::{Container, ContainerState};
 use crate::cri_api::*;
 use crate::sandbox::{PodSandbox, SandboxState};
 
-/// Real CPU + memory usage of a pod's microVM, read from the host-side shim
-/// process. In the microVM-per-pod model the shim process *is* the pod: its
-/// `/proc/<pid>/stat` CPU time (vcpu threads are aggregated into the process)
-/// and `/proc/<pid>/status` `VmRSS` (which backs the guest RAM) are the pod's
-/// real resource usage.
-#[derive(Debug, Clone, Copy, Default)]
+/// How long a stats call waits for the guest before using the host view.
+const GUEST_METRICS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// CPU + memory usage of a pod's microVM.
+///
+/// Read from the guest's own `/proc` when the guest metrics channel answers:
+/// busy CPU time across vCPUs, and `MemTotal - MemAvailable` as the working
+/// set. Otherwise it falls back to the host-side shim process, which *is* the
+/// pod in the microVM-per-pod model: its `/proc/<pid>/stat` CPU time (vcpu
+/// threads are aggregated into the process) and `/proc/<pid>/status` `VmRSS`
+/// (which backs the guest RAM).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub(super) struct VmUsage {
-    /// Cumulative CPU time in nanoseconds (utime + stime).
+    /// Cumulative CPU time in nanoseconds.
     cpu_core_nanos: u64,
-    /// Resident set size in bytes.
+    /// CPU rate since the previous sample, in nanocores; 0 until there is one.
+    nano_cores: u64,
+    /// Working set in bytes.
     memory_bytes: u64,
+    /// Memory the guest can still hand out; unknown from the host view.
+    memory_available_bytes: Option<u64>,
+    /// Guest `(rx, tx)` bytes on non-loopback interfaces; pod-level only.
+    network_bytes: Option<(u64, u64)>,
 }
 
 impl VmUsage {
@@ -34,8 +53,137 @@ impl VmUsage {
         let n = running.max(1) as u64;
         VmUsage {
             cpu_core_nanos: self.cpu_core_nanos / n,
+            nano_cores: self.nano_cores / n,
             memory_bytes: self.memory_bytes / n,
+            memory_available_bytes: self.memory_available_bytes,
+            network_bytes: None,
+        }
+    }
+
+    /// Replace the host view with the guest's. Guests that do not report CPU
+    /// time keep the host figure.
+    fn with_guest(self, guest: &GuestMetrics) -> VmUsage {
+        VmUsage {
+            cpu_core_nanos: if guest.cpu_busy_ns > 0 {
+                guest.cpu_busy_ns
+            } else {
+                self.cpu_core_nanos
+            },
+            nano_cores: 0,
+            memory_bytes: guest.memory_used_bytes,
+            memory_available_bytes: Some(
+                guest
+                    .memory_total_bytes
+                    .saturating_sub(guest.memory_used_bytes),
+            ),
+            network_bytes: Some((guest.network_rx_bytes, guest.network_tx_bytes)),
+        }
+    }
+
+    /// Fill in the CPU rate from an earlier sample taken `elapsed` ago.
+    fn with_rate_since(mut self, previous: &VmUsage, elapsed: Duration) -> VmUsage {
+        let used = self.cpu_core_nanos.saturating_sub(previous.cpu_core_nanos) as u128;
+        if let Some(rate) = (used * 1_000_000_000).checked_div(elapsed.as_nanos()) {
+            self.nano_cores = u64::try_from(rate).unwrap_or(u64::MAX);
+        }
+        self
+    }
+}
+
+/// Ask the guest for a usage sample over its exec channel.
+pub(super) async fn read_guest_metrics(exec_socket_path: &Path) -> Option<GuestMetrics> {
+    // No sampling window: the rate comes from consecutive cumulative samples.
+    let request = GuestMetricsRequest {
+        sample_ms: 0,
+        top: 0,
+    };
+    tokio::time::timeout(GUEST_METRICS_TIMEOUT, async {
+        let client = a3s_box_runtime::ExecClient::connect(exec_socket_path)
+            .await
+            .ok()?;
+        client.metrics(&request).await.ok()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Last usage samples per sandbox and per container rootfs.
+///
+/// On demand, every stats call samples afresh and the previous sample only
+/// feeds the CPU rate. Cached, a sample younger than the configured age is
+/// served as is, which keeps frequent kubelet and metrics-server polling from
+/// walking rootfs trees and waking guests on every call.
+#[derive(Debug, Default)]
+pub(super) struct StatsCollector {
+    collection: StatsCollection,
+    vms: Mutex<HashMap<String, (Instant, VmUsage)>>,
+    rootfs: Mutex<HashMap<String, (Instant, Option<PathUsage>)>>,
+}
+
+impl StatsCollector {
+    pub(super) fn new(collection: StatsCollection) -> Self {
+        Self {
+            collection,
+            ..Self::default()
+        }
+    }
+
+    fn is_fresh(&self, sampled_at: Instant, now: Instant) -> bool {
+        match self.collection {
+            StatsCollection::OnDemand => false,
+            StatsCollection::Cached { max_age } => now.duration_since(sampled_at) < max_age,
+        }
+    }
+
+    /// The sandbox's cached usage, if the collection mode allows reusing it.
+    pub(super) fn cached_vm_usage(&self, sandbox_id: &str, now: Instant) -> Option<VmUsage> {
+        self.vms
+            .lock()
+            .get(sandbox_id)
+            .filter(|(sampled_at, _)| self.is_fresh(*sampled_at, now))
+            .map(|(_, usage)| *usage)
+    }
+
+    /// Record a fresh sample taken at `now` and return it with its CPU rate.
+    pub(super) fn record_vm_usage(
+        &self,
+        sandbox_id: &str,
+        host: VmUsage,
+        guest: Option<&GuestMetrics>,
+        now: Instant,
+    ) -> VmUsage {
+        let mut usage = guest.map_or(host, |guest| host.with_guest(guest));
+        let mut vms = self.vms.lock();
+        if let Some((sampled_at, previous)) = vms.get(sandbox_id) {
+            usage = usage.with_rate_since(previous, now.duration_since(*sampled_at));
+        }
+        vms.insert(sandbox_id.to_string(), (now, usage));
+        usage
+    }
+
+    /// Drop a removed sandbox's samples.
+    pub(super) fn forget(&self, sandbox_id: &str) {
+        self.vms.lock().remove(sandbox_id);
+    }
+
+    async fn rootfs_usage(&self, rootfs_path: &str) -> Option<PathUsage> {
+        let now = Instant::now();
+        if let Some((_, usage)) = self
+            .rootfs
+            .lock()
+            .get(rootfs_path)
+            .filter(|(sampled_at, _)| self.is_fresh(*sampled_at, now))
+        {
+            return *usage;
+        }
+        let usage = rootfs_path_usage(rootfs_path).await;
+        if matches!(self.collection, StatsCollection::Cached { .. }) {
+            let mut rootfs = self.rootfs.lock();
+            rootfs.retain(|_, (sampled_at, _)| self.is_fresh(*sampled_at, now));
+            rootfs.insert(rootfs_path.to_string(), (now, usage));
         }
+        usage
     }
 }
 
@@ -94,12 +242,14 @@ fn cpu_usage(now_ns: i64, usage: VmUsage) -> CpuUsage {
     CpuUsage {
         timestamp: now_ns,
         // Cumulative CPU time; the kubelet derives the rate from deltas between
-        // samples. usage_nano_cores (instantaneous) needs two samples, so it is
-        // left at 0 and computed by the consumer.
+        // samples. usage_nano_cores needs an earlier sample of our own, so it
+        // is 0 on the first call and the consumer computes it.
         usage_core_nano_seconds: Some(UInt64Value {
             value: usage.cpu_core_nanos,
         }),
-        usage_nano_cores: Some(UInt64Value { value: 0 }),
+        usage_nano_cores: Some(UInt64Value {
+            value: usage.nano_cores,
+        }),
     }
 }
 
@@ -109,7 +259,9 @@ fn memory_usage(now_ns: i64, usage: VmUsage) -> MemoryUsage {
         working_set_bytes: Some(UInt64Value {
             value: usage.memory_bytes,
         }),
-        available_bytes: Some(UInt64Value { value: 0 }),
+        available_bytes: Some(UInt64Value {
+            value: usage.memory_available_bytes.unwrap_or(0),
+        }),
         usage_bytes: Some(UInt64Value {
             value: usage.memory_bytes,
         }),
@@ -202,9 +354,31 @@ fn writable_layer_usage(
     }
 }
 
-pub(super) async fn container_stats(container: &Container, usage: VmUsage) -> ContainerStats {
+fn network_usage(now_ns: i64, usage: VmUsage) -> NetworkUsage {
+    // The guest sums its non-loopback interfaces; CRI pods have one, eth0.
+    let default_interface = usage
+        .network_bytes
+        .map(|(rx_bytes, tx_bytes)| NetworkInterfaceUsage {
+            name: "eth0".to_string(),
+            rx_bytes: Some(UInt64Value { value: rx_bytes }),
+            rx_errors: Some(UInt64Value { value: 0 }),
+            tx_bytes: Some(UInt64Value { value: tx_bytes }),
+            tx_errors: Some(UInt64Value { value: 0 }),
+        });
+    NetworkUsage {
+        timestamp: now_ns,
+        interfaces: default_interface.iter().cloned().collect(),
+        default_interface,
+    }
+}
+
+pub(super) async fn container_stats(
+    container: &Container,
+    usage: VmUsage,
+    collector: &StatsCollector,
+) -> ContainerStats {
     let now_ns = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
-    let rootfs_usage = rootfs_path_usage(&container.rootfs_path).await;
+    let rootfs_usage = collector.rootfs_usage(&container.rootfs_path).await;
     ContainerStats {
         attributes: Some(ContainerAttributes {
             id: container.id.clone(),
@@ -229,6 +403,7 @@ pub(super) async fn pod_sandbox_stats(
     sandbox: &PodSandbox,
     containers: Vec<Container>,
     vm_usage: VmUsage,
+    collector: &StatsCollector,
 ) -> PodSandboxStats {
     let now_ns = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
     let running_containers: Vec<Container> = containers
@@ -242,7 +417,7 @@ pub(super) async fn pod_sandbox_stats(
     let container_stats = join_all(
         running_containers
             .iter()
-            .map(|c| container_stats(c, per_container)),
+            .map(|c| container_stats(c, per_container, collector)),
     )
     .await;
 
@@ -261,11 +436,7 @@ pub(super) async fn pod_sandbox_stats(
         linux: Some(LinuxPodSandboxStats {
             cpu: Some(cpu_usage(now_ns, vm_usage)),
             memory: Some(memory_usage(now_ns, vm_usage)),
-            network: Some(NetworkUsage {
-                timestamp: now_ns,
-                default_interface: None,
-                interfaces: vec![],
-            }),
+            network: Some(network_usage(now_ns, vm_usage)),
             process: Some(ProcessUsage {
                 timestamp: now_ns,
                 process_count: Some(UInt64Value {
@@ -284,119 +455,184 @@ pub(super) async fn pod_sandbox_stats(
     }
 }
 
-pub(super) fn metric_descriptors() -> Vec<MetricDescriptor> {
-    vec![
-        MetricDescriptor {
-            name: "a3s_box_pod_sandbox_ready".to_string(),
-            help: "Whether the CRI pod sandbox is Ready according to the runtime store."
-                .to_string(),
-            kind: "gauge".to_string(),
-            unit: "1".to_string(),
-        },
-        MetricDescriptor {
-            name: "a3s_box_pod_sandbox_vm_manager_present".to_string(),
-            help: "Whether the runtime has an in-process VM manager for the pod sandbox."
-                .to_string(),
-            kind: "gauge".to_string(),
-            unit: "1".to_string(),
-        },
-        MetricDescriptor {
-            name: "a3s_box_pod_sandbox_containers_total".to_string(),
-            help: "Number of containers tracked for the pod sandbox.".to_string(),
-            kind: "gauge".to_string(),
-            unit: "count".to_string(),
-        },
-        MetricDescriptor {
-            name: "a3s_box_pod_sandbox_containers_running".to_string(),
-            help: "Number of running containers tracked for the pod sandbox.".to_string(),
-            kind: "gauge".to_string(),
-            unit: "count".to_string(),
-        },
-        MetricDescriptor {
-            name: "a3s_box_pod_sandbox_containers_exited".to_string(),
-            help: "Number of exited containers tracked for the pod sandbox.".to_string(),
-            kind: "gauge".to_string(),
-            unit: "count".to_string(),
-        },
-    ]
+/// Label keys of pod-level metrics, in `label_values` order.
+const POD_METRIC_LABELS: &[&str] = &[
+    "pod_sandbox_id",
+    "namespace",
+    "name",
+    "uid",
+    "runtime_handler",
+];
+/// Label keys of container-level metrics, in `label_values` order.
+const CONTAINER_METRIC_LABELS: &[&str] = &["container_id", "container", "image"];
+
+fn metric_descriptor(name: &str, help: &str, label_keys: &[&str]) -> MetricDescriptor {
+    MetricDescriptor {
+        name: name.to_string(),
+        help: help.to_string(),
+        label_keys: label_keys.iter().map(|key| key.to_string()).collect(),
+    }
 }
 
-fn pod_sandbox_metric_labels(sandbox: &PodSandbox) -> HashMap<String, String> {
-    HashMap::from([
-        ("pod_sandbox_id".to_string(), sandbox.id.clone()),
-        ("namespace".to_string(), sandbox.namespace.clone()),
-        ("name".to_string(), sandbox.name.clone()),
-        ("uid".to_string(), sandbox.uid.clone()),
-        (
-            "runtime_handler".to_string(),
-            sandbox.runtime_handler.clone(),
+/// Descriptors for every metric [`pod_sandbox_metrics`] can report.
+pub(super) fn metric_descriptors() -> Vec<MetricDescriptor> {
+    vec![
+        metric_descriptor(
+            "a3s_box_pod_sandbox_ready",
+            "Whether the CRI pod sandbox is Ready according to the runtime store.",
+            POD_METRIC_LABELS,
+        ),
+        metric_descriptor(
+            "a3s_box_pod_sandbox_vm_manager_present",
+            "Whether the runtime has an in-process VM manager for the pod sandbox.",
+            POD_METRIC_LABELS,
+        ),
+        metric_descriptor(
+            "a3s_box_pod_sandbox_containers_total",
+            "Number of containers tracked for the pod sandbox.",
+            POD_METRIC_LABELS,
+        ),
+        metric_descriptor(
+            "a3s_box_pod_sandbox_containers_running",
+            "Number of running containers tracked for the pod sandbox.",
+            POD_METRIC_LABELS,
+        ),
+        metric_descriptor(
+            "a3s_box_pod_sandbox_containers_exited",
+            "Number of exited containers tracked for the pod sandbox.",
+            POD_METRIC_LABELS,
+        ),
+        metric_descriptor(
+            "container_network_receive_bytes_total",
+            "Bytes received by the pod VM on non-loopback interfaces.",
+            POD_METRIC_LABELS,
+        ),
+        metric_descriptor(
+            "container_network_transmit_bytes_total",
+            "Bytes transmitted by the pod VM on non-loopback interfaces.",
+            POD_METRIC_LABELS,
         ),
-    ])
+        metric_descriptor(
+            "container_cpu_usage_seconds_total",
+            "Cumulative CPU time consumed by the container, in whole seconds.",
+            CONTAINER_METRIC_LABELS,
+        ),
+        metric_descriptor(
+            "container_memory_working_set_bytes",
+            "Current working set of the container, in bytes.",
+            CONTAINER_METRIC_LABELS,
+        ),
+    ]
 }
 
-fn pod_metric(name: &str, labels: &HashMap<String, String>, value: f64, timestamp: i64) -> Metric {
+fn metric(
+    name: &str,
+    metric_type: MetricType,
+    label_values: &[String],
+    value: u64,
+    timestamp: i64,
+) -> Metric {
     Metric {
         name: name.to_string(),
-        labels: labels.clone(),
-        value,
         timestamp,
+        metric_type: metric_type.into(),
+        label_values: label_values.to_vec(),
+        value: Some(UInt64Value { value }),
     }
 }
 
+/// Pod-level lifecycle and network metrics plus per-container CPU and memory
+/// for the running containers, which share the pod VM's `usage` evenly.
 pub(super) fn pod_sandbox_metrics(
     sandbox: &PodSandbox,
     containers: &[Container],
     vm_manager_present: bool,
+    usage: VmUsage,
 ) -> PodSandboxMetrics {
     let now_ns = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
-    let labels = pod_sandbox_metric_labels(sandbox);
-    let running_containers = containers
+    let labels = [
+        sandbox.id.clone(),
+        sandbox.namespace.clone(),
+        sandbox.name.clone(),
+        sandbox.uid.clone(),
+        sandbox.runtime_handler.clone(),
+    ];
+    let running: Vec<_> = containers
         .iter()
         .filter(|container| container.state == ContainerState::Running)
-        .count();
+        .collect();
     let exited_containers = containers
         .iter()
         .filter(|container| container.state == ContainerState::Exited)
         .count();
+    let gauge = |name: &str, value: u64| metric(name, MetricType::Gauge, &labels, value, now_ns);
+
+    let mut metrics = vec![
+        gauge(
+            "a3s_box_pod_sandbox_ready",
+            u64::from(sandbox.state == SandboxState::Ready),
+        ),
+        gauge(
+            "a3s_box_pod_sandbox_vm_manager_present",
+            u64::from(vm_manager_present),
+        ),
+        gauge(
+            "a3s_box_pod_sandbox_containers_total",
+            containers.len() as u64,
+        ),
+        gauge(
+            "a3s_box_pod_sandbox_containers_running",
+            running.len() as u64,
+        ),
+        gauge(
+            "a3s_box_pod_sandbox_containers_exited",
+            exited_containers as u64,
+        ),
+    ];
+    if let Some((rx_bytes, tx_bytes)) = usage.network_bytes {
+        for (name, value) in [
+            ("container_network_receive_bytes_total", rx_bytes),
+            ("container_network_transmit_bytes_total", tx_bytes),
+        ] {
+            metrics.push(metric(name, MetricType::Counter, &labels, value, now_ns));
+        }
+    }
+
+    let per_container = usage.per_container(running.len());
+    let container_metrics = running
+        .iter()
+        .map(|container| {
+            let labels = [
+                container.id.clone(),
+                container.name.clone(),
+                container.image_ref.clone(),
+            ];
+            ContainerMetrics {
+                container_id: container.id.clone(),
+                metrics: vec![
+                    metric(
+                        "container_cpu_usage_seconds_total",
+                        MetricType::Counter,
+                        &labels,
+                        per_container.cpu_core_nanos / 1_000_000_000,
+                        now_ns,
+                    ),
+                    metric(
+                        "container_memory_working_set_bytes",
+                        MetricType::Gauge,
+                        &labels,
+                        per_container.memory_bytes,
+                        now_ns,
+                    ),
+                ],
+            }
+        })
+        .collect();
 
     PodSandboxMetrics {
         pod_sandbox_id: sandbox.id.clone(),
-        metrics: vec![
-            pod_metric(
-                "a3s_box_pod_sandbox_ready",
-                &labels,
-                if sandbox.state == SandboxState::Ready {
-                    1.0
-                } else {
-                    0.0
-                },
-                now_ns,
-            ),
-            pod_metric(
-                "a3s_box_pod_sandbox_vm_manager_present",
-                &labels,
-                if vm_manager_present { 1.0 } else { 0.0 },
-                now_ns,
-            ),
-            pod_metric(
-                "a3s_box_pod_sandbox_containers_total",
-                &labels,
-                containers.len() as f64,
-                now_ns,
-            ),
-            pod_metric(
-                "a3s_box_pod_sandbox_containers_running",
-                &labels,
-                running_containers as f64,
-                now_ns,
-            ),
-            pod_metric(
-                "a3s_box_pod_sandbox_containers_exited",
-                &labels,
-                exited_containers as f64,
-                now_ns,
-            ),
-        ],
+        metrics,
+        container_metrics,
     }
 }
 
@@ -421,6 +657,8 @@ mod stats_tests {
             additional_ips: vec![],
             dns: crate::sandbox::SandboxDns::default(),
             container_ports: vec![],
+            cni_network: None,
+            host_cgroup: None,
         }
     }
 
@@ -453,16 +691,20 @@ mod stats_tests {
             log_path: format!("/var/log/pods/{id}.log"),
             rootfs_path,
             rootfs_guest_path: format!("/run/a3s/cri/container-rootfs/sandbox-1/{id}/rootfs"),
+            cpuset_cpus: String::new(),
+            cpuset_mems: String::new(),
         }
     }
 
-    fn metric_value(metrics: &PodSandboxMetrics, name: &str) -> f64 {
+    fn metric_value(metrics: &[Metric], name: &str) -> u64 {
         metrics
-            .metrics
             .iter()
             .find(|metric| metric.name == name)
             .unwrap_or_else(|| panic!("missing metric {name}"))
             .value
+            .as_ref()
+            .unwrap()
+            .value
     }
 
     #[test]
@@ -470,6 +712,7 @@ mod stats_tests {
         let total = VmUsage {
             cpu_core_nanos: 900,
             memory_bytes: 300,
+            ..Default::default()
         };
         let per = total.per_container(3);
         assert_eq!(per.cpu_core_nanos, 300);
@@ -485,6 +728,7 @@ mod stats_tests {
         let usage = VmUsage {
             cpu_core_nanos: 42,
             memory_bytes: 2048,
+            ..Default::default()
         };
 
         let cpu = cpu_usage(123, usage);
@@ -499,6 +743,95 @@ mod stats_tests {
         assert_eq!(memory.rss_bytes.unwrap().value, 2048);
     }
 
+    #[test]
+    fn test_guest_metrics_replace_host_view_and_feed_cpu_rate() {
+        let host = VmUsage {
+            cpu_core_nanos: 7,
+            memory_bytes: 4096,
+            ..Default::default()
+        };
+        let guest = GuestMetrics {
+            cpu_busy_ns: 2_000_000_000,
+            memory_total_bytes: 1000,
+            memory_used_bytes: 600,
+            network_rx_bytes: 10,
+            network_tx_bytes: 20,
+            ..Default::default()
+        };
+        let collector = StatsCollector::new(StatsCollection::OnDemand);
+        let start = Instant::now();
+
+        let first = collector.record_vm_usage("sb", host, Some(&guest), start);
+        assert_eq!(first.cpu_core_nanos, 2_000_000_000);
+        assert_eq!(first.nano_cores, 0);
+        assert_eq!(memory_usage(0, first).available_bytes.unwrap().value, 400);
+        let network = network_usage(0, first);
+        assert_eq!(
+            network.default_interface.unwrap().tx_bytes.unwrap().value,
+            20
+        );
+        assert_eq!(network.interfaces.len(), 1);
+
+        // Half a core over two seconds.
+        let later = GuestMetrics {
+            cpu_busy_ns: 3_000_000_000,
+            ..guest.clone()
+        };
+        let second =
+            collector.record_vm_usage("sb", host, Some(&later), start + Duration::from_secs(2));
+        assert_eq!(second.nano_cores, 500_000_000);
+        assert_eq!(
+            cpu_usage(0, second).usage_nano_cores.unwrap().value,
+            500_000_000
+        );
+        assert!(collector.cached_vm_usage("sb", start).is_none());
+
+        // An old guest without CPU time keeps the host figure.
+        let old_guest = GuestMetrics {
+            cpu_busy_ns: 0,
+            ..guest
+        };
+        assert_eq!(host.with_guest(&old_guest).cpu_core_nanos, 7);
+        assert!(network_usage(0, host).default_interface.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cached_collection_reuses_recent_samples() {
+        let collector = StatsCollector::new(StatsCollection::Cached {
+            max_age: Duration::from_secs(10),
+        });
+        let start = Instant::now();
+        let usage = VmUsage {
+            memory_bytes: 100,
+            ..Default::default()
+        };
+        collector.record_vm_usage("sb", usage, None, start);
+        assert_eq!(
+            collector.cached_vm_usage("sb", start + Duration::from_secs(5)),
+            Some(usage)
+        );
+        assert!(collector
+            .cached_vm_usage("sb", start + Duration::from_secs(10))
+            .is_none());
+        collector.forget("sb");
+        assert!(collector.cached_vm_usage("sb", start).is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let rootfs = dir.path().to_string_lossy().to_string();
+        std::fs::write(dir.path().join("a"), b"abc").unwrap();
+        let first = collector.rootfs_usage(&rootfs).await.unwrap();
+        std::fs::write(dir.path().join("b"), b"def").unwrap();
+        assert_eq!(
+            collector.rootfs_usage(&rootfs).await.unwrap().inodes_used,
+            first.inodes_used
+        );
+        let fresh = StatsCollector::default()
+            .rootfs_usage(&rootfs)
+            .await
+            .unwrap();
+        assert_eq!(fresh.inodes_used, first.inodes_used + 1);
+    }
+
     #[test]
     fn test_should_collect_rootfs_usage_rejects_empty_and_host_root() {
         assert!(!should_collect_rootfs_usage(""));
@@ -555,7 +888,9 @@ mod stats_tests {
             VmUsage {
                 cpu_core_nanos: 99,
                 memory_bytes: 1024,
+                ..Default::default()
             },
+            &StatsCollector::default(),
         )
         .await;
 
@@ -593,7 +928,9 @@ mod stats_tests {
             VmUsage {
                 cpu_core_nanos: 1000,
                 memory_bytes: 3000,
+                ..Default::default()
             },
+            &StatsCollector::default(),
         )
         .await;
 
@@ -625,20 +962,33 @@ mod stats_tests {
     #[test]
     fn test_metric_descriptors_cover_pod_sandbox_metrics() {
         let descriptors = metric_descriptors();
-        let names: std::collections::HashSet<_> = descriptors
-            .iter()
-            .map(|descriptor| descriptor.name.as_str())
-            .collect();
-
-        assert_eq!(descriptors.len(), 5);
-        assert!(names.contains("a3s_box_pod_sandbox_ready"));
-        assert!(names.contains("a3s_box_pod_sandbox_vm_manager_present"));
-        assert!(names.contains("a3s_box_pod_sandbox_containers_total"));
-        assert!(names.contains("a3s_box_pod_sandbox_containers_running"));
-        assert!(names.contains("a3s_box_pod_sandbox_containers_exited"));
-        assert!(descriptors
+        let sandbox = test_sandbox(SandboxState::Ready);
+        let containers = vec![test_container(
+            "running",
+            ContainerState::Running,
+            "/".to_string(),
+        )];
+        let usage = VmUsage {
+            network_bytes: Some((1, 2)),
+            ..Default::default()
+        };
+        let metrics = pod_sandbox_metrics(&sandbox, &containers, true, usage);
+
+        // Every reported metric is described, with one label value per key.
+        let reported = metrics
+            .metrics
             .iter()
-            .all(|descriptor| descriptor.kind == "gauge"));
+            .chain(metrics.container_metrics.iter().flat_map(|c| &c.metrics));
+        let mut count = 0;
+        for metric in reported {
+            let descriptor = descriptors
+                .iter()
+                .find(|descriptor| descriptor.name == metric.name)
+                .unwrap_or_else(|| panic!("undescribed metric {}", metric.name));
+            assert_eq!(descriptor.label_keys.len(), metric.label_values.len());
+            count += 1;
+        }
+        assert_eq!(count, descriptors.len());
     }
 
     #[test]
@@ -649,54 +999,81 @@ mod stats_tests {
             test_container("running", ContainerState::Running, "/".to_string()),
             test_container("exited", ContainerState::Exited, "/".to_string()),
         ];
+        let usage = VmUsage {
+            cpu_core_nanos: 3_500_000_000,
+            memory_bytes: 4096,
+            ..Default::default()
+        };
 
-        let metrics = pod_sandbox_metrics(&sandbox, &containers, true);
+        let metrics = pod_sandbox_metrics(&sandbox, &containers, true, usage);
 
         assert_eq!(metrics.pod_sandbox_id, "sandbox-1");
-        assert_eq!(metric_value(&metrics, "a3s_box_pod_sandbox_ready"), 1.0);
+        let pod = &metrics.metrics;
+        assert_eq!(metric_value(pod, "a3s_box_pod_sandbox_ready"), 1);
         assert_eq!(
-            metric_value(&metrics, "a3s_box_pod_sandbox_vm_manager_present"),
-            1.0
+            metric_value(pod, "a3s_box_pod_sandbox_vm_manager_present"),
+            1
         );
+        assert_eq!(metric_value(pod, "a3s_box_pod_sandbox_containers_total"), 3);
         assert_eq!(
-            metric_value(&metrics, "a3s_box_pod_sandbox_containers_total"),
-            3.0
+            metric_value(pod, "a3s_box_pod_sandbox_containers_running"),
+            1
         );
         assert_eq!(
-            metric_value(&metrics, "a3s_box_pod_sandbox_containers_running"),
-            1.0
+            metric_value(pod, "a3s_box_pod_sandbox_containers_exited"),
+            1
         );
+        // Without a guest sample there is no network usage to report.
+        assert!(!pod
+            .iter()
+            .any(|metric| metric.name.starts_with("container_network_")));
         assert_eq!(
-            metric_value(&metrics, "a3s_box_pod_sandbox_containers_exited"),
-            1.0
+            pod[0].label_values,
+            ["sandbox-1", "default", "web", "uid-1", "a3s"]
         );
+        assert_eq!(pod[0].metric_type, MetricType::Gauge as i32);
 
-        let ready_metric = metrics
-            .metrics
-            .iter()
-            .find(|metric| metric.name == "a3s_box_pod_sandbox_ready")
-            .unwrap();
-        assert_eq!(ready_metric.labels["pod_sandbox_id"], "sandbox-1");
-        assert_eq!(ready_metric.labels["namespace"], "default");
-        assert_eq!(ready_metric.labels["name"], "web");
-        assert_eq!(ready_metric.labels["uid"], "uid-1");
-        assert_eq!(ready_metric.labels["runtime_handler"], "a3s");
+        // Only the running container gets usage metrics, and it gets it all.
+        assert_eq!(metrics.container_metrics.len(), 1);
+        let container = &metrics.container_metrics[0];
+        assert_eq!(container.container_id, "running");
+        assert_eq!(
+            metric_value(&container.metrics, "container_cpu_usage_seconds_total"),
+            3
+        );
+        assert_eq!(
+            metric_value(&container.metrics, "container_memory_working_set_bytes"),
+            4096
+        );
+        assert_eq!(
+            container.metrics[0].label_values,
+            [
+                "running",
+                "container-running",
+                "docker.io/library/nginx:latest"
+            ]
+        );
+        assert_eq!(container.metrics[0].metric_type, MetricType::Counter as i32);
     }
 
     #[test]
     fn test_pod_sandbox_metrics_not_ready_without_vm_manager() {
         let sandbox = test_sandbox(SandboxState::NotReady);
-        let metrics = pod_sandbox_metrics(&sandbox, &[], false);
+        let metrics = pod_sandbox_metrics(&sandbox, &[], false, VmUsage::default());
 
-        assert_eq!(metric_value(&metrics, "a3s_box_pod_sandbox_ready"), 0.0);
         assert_eq!(
-            metric_value(&metrics, "a3s_box_pod_sandbox_vm_manager_present"),
-            0.0
+            metric_value(&metrics.metrics, "a3s_box_pod_sandbox_ready"),
+            0
+        );
+        assert_eq!(
+            metric_value(&metrics.metrics, "a3s_box_pod_sandbox_vm_manager_present"),
+            0
         );
         assert_eq!(
-            metric_value(&metrics, "a3s_box_pod_sandbox_containers_total"),
-            0.0
+            metric_value(&metrics.metrics, "a3s_box_pod_sandbox_containers_total"),
+            0
         );
+        assert!(metrics.container_metrics.is_empty());
     }
 
     #[cfg(target_os = "linux")]
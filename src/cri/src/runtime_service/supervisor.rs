@@ -6,17 +6,15 @@
 
 use std::sync::Arc;
 
+use a3s_box_core::event::EventEmitter;
 use tokio::sync::{oneshot, Notify};
 
 use crate::cri_api::ContainerEventType;
 use crate::persistent_store::PersistentCriStore;
 
-use super::convert::container_event_response;
+use super::events::lifecycle_event;
 use super::log_writer::CriLogWriter;
-use super::{
-    AttachStreamMap, AttachStreamSender, ContainerEventSender, LogReopenMap, WorkloadStdinMap,
-    WorkloadStopMap,
-};
+use super::{AttachStreamMap, AttachStreamSender, LogReopenMap, WorkloadStdinMap, WorkloadStopMap};
 
 pub(super) enum SupervisedWorkload {
     Exec(a3s_box_runtime::StreamingExec),
@@ -61,7 +59,7 @@ pub(super) struct ContainerExitSupervisor {
     pub(super) workload_stdins: WorkloadStdinMap,
     pub(super) workload_stops: WorkloadStopMap,
     pub(super) log_reopens: LogReopenMap,
-    pub(super) container_events: ContainerEventSender,
+    pub(super) runtime_events: EventEmitter,
     pub(super) container_id: String,
     pub(super) sandbox_id: String,
     pub(super) log_path: String,
@@ -84,7 +82,7 @@ pub(super) fn spawn_container_exit_supervisor(supervisor: ContainerExitSuperviso
             workload_stdins,
             workload_stops,
             log_reopens,
-            container_events,
+            runtime_events,
             container_id,
             sandbox_id,
             log_path,
@@ -290,13 +288,10 @@ pub(super) fn spawn_container_exit_supervisor(supervisor: ContainerExitSuperviso
                 exit_code,
                 "Container workload exited"
             );
-            let _ = container_events.send(container_event_response(
+            runtime_events.emit(lifecycle_event(
+                ContainerEventType::ContainerStoppedEvent,
                 &container_id,
                 &sandbox_id,
-                ContainerEventType::ContainerStoppedEvent,
-                finished_ns,
-                "ContainerStopped",
-                format!("Container workload exited with code {exit_code}"),
             ));
         } else {
             tracing::debug!(
@@ -4,6 +4,7 @@ use super::*;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
+use a3s_box_runtime::{PreflightCheck, PreflightCheckKind, PreflightStatus};
 use futures::StreamExt;
 use sha2::{Digest, Sha256};
 use tokio::io::AsyncWriteExt;
@@ -101,11 +102,24 @@ fn make_test_service() -> BoxRuntimeService {
         workload_stdins: Arc::new(RwLock::new(HashMap::new())),
         workload_stops: Arc::new(RwLock::new(HashMap::new())),
         log_reopens: Arc::new(RwLock::new(HashMap::new())),
-        container_events: broadcast::channel(CONTAINER_EVENT_BUFFER).0,
+        runtime_events: EventEmitter::new(CONTAINER_EVENT_BUFFER),
         warm_pool: None,
         runtime_options: CriRuntimeOptions::default(),
+        stats: Arc::new(StatsCollector::default()),
         test_vm_acquire_error: None,
         test_vm_exec_socket_path: None,
+        test_preflight: Some(test_preflight_report(PreflightStatus::Pass)),
+    }
+}
+
+fn test_preflight_report(virtualization: PreflightStatus) -> PreflightReport {
+    PreflightReport {
+        checks: vec![PreflightCheck {
+            kind: PreflightCheckKind::Virtualization,
+            status: virtualization,
+            detail: "KVM".to_string(),
+            hint: None,
+        }],
     }
 }
 
@@ -916,6 +930,8 @@ fn test_sandbox(id: &str) -> PodSandbox {
         additional_ips: vec![],
         dns: crate::sandbox::SandboxDns::default(),
         container_ports: vec![],
+        cni_network: None,
+        host_cgroup: None,
     }
 }
 
@@ -969,6 +985,8 @@ fn test_container(id: &str, sandbox_id: &str) -> Container {
         log_path: String::new(),
         rootfs_path: "/".to_string(),
         rootfs_guest_path: format!("/run/a3s/cri/container-rootfs/{sandbox_id}/{id}/rootfs"),
+        cpuset_cpus: String::new(),
+        cpuset_mems: String::new(),
     }
 }
 
@@ -998,14 +1016,22 @@ async fn test_cri_log_writer_flushes_partial_lines() {
     assert!(log.contains(" stderr F warn\n"));
 }
 
+fn agent_image_handler(image: &str) -> RuntimeHandler {
+    RuntimeHandler {
+        agent_image: Some(image.to_string()),
+        ..Default::default()
+    }
+}
+
 #[test]
 fn test_runtime_options_resolve_runtime_handler_agent_image() {
     let options = CriRuntimeOptions {
         default_agent_image: "ghcr.io/a3s-box/default:v1".to_string(),
-        runtime_handler_agent_images: HashMap::from([(
+        runtime_handlers: HashMap::from([(
             "a3s-secure".to_string(),
-            "ghcr.io/a3s-box/secure:v1".to_string(),
+            agent_image_handler("ghcr.io/a3s-box/secure:v1"),
         )]),
+        ..Default::default()
     };
 
     assert_eq!(
@@ -1019,13 +1045,14 @@ fn test_runtime_options_resolve_runtime_handler_agent_image() {
 fn test_runtime_options_ignore_blank_runtime_handler_override() {
     let options = CriRuntimeOptions {
         default_agent_image: "ghcr.io/a3s-box/default:v1".to_string(),
-        runtime_handler_agent_images: HashMap::from([
-            ("blank".to_string(), "   ".to_string()),
+        runtime_handlers: HashMap::from([
+            ("blank".to_string(), agent_image_handler("   ")),
             (
                 "explicit".to_string(),
-                "ghcr.io/a3s-box/explicit:v1".to_string(),
+                agent_image_handler("ghcr.io/a3s-box/explicit:v1"),
             ),
         ]),
+        ..Default::default()
     };
 
     assert_eq!(
@@ -1138,13 +1165,16 @@ async fn test_list_container_stats_only_reports_running_containers() {
     assert_eq!(resp.stats[0].attributes.as_ref().unwrap().id, "c-running");
 }
 
-fn pod_metric_value(metrics: &PodSandboxMetrics, name: &str) -> f64 {
+fn pod_metric_value(metrics: &PodSandboxMetrics, name: &str) -> u64 {
     metrics
         .metrics
         .iter()
         .find(|metric| metric.name == name)
         .unwrap_or_else(|| panic!("missing pod sandbox metric {name}"))
         .value
+        .as_ref()
+        .unwrap()
+        .value
 }
 
 #[tokio::test]
@@ -1197,35 +1227,31 @@ async fn test_list_pod_sandbox_metrics_returns_lifecycle_snapshot() {
         .unwrap()
         .into_inner();
 
-    assert_eq!(resp.pod_sandbox_metrics.len(), 1);
-    let metrics = &resp.pod_sandbox_metrics[0];
+    assert_eq!(resp.pod_metrics.len(), 1);
+    let metrics = &resp.pod_metrics[0];
     assert_eq!(metrics.pod_sandbox_id, "sb-1");
-    assert_eq!(pod_metric_value(metrics, "a3s_box_pod_sandbox_ready"), 1.0);
+    assert_eq!(pod_metric_value(metrics, "a3s_box_pod_sandbox_ready"), 1);
     assert_eq!(
         pod_metric_value(metrics, "a3s_box_pod_sandbox_vm_manager_present"),
-        1.0
+        1
     );
     assert_eq!(
         pod_metric_value(metrics, "a3s_box_pod_sandbox_containers_total"),
-        3.0
+        3
     );
     assert_eq!(
         pod_metric_value(metrics, "a3s_box_pod_sandbox_containers_running"),
-        1.0
+        1
     );
     assert_eq!(
         pod_metric_value(metrics, "a3s_box_pod_sandbox_containers_exited"),
-        1.0
+        1
     );
     let first_metric = metrics.metrics.first().unwrap();
-    assert_eq!(
-        first_metric.labels.get("pod_sandbox_id"),
-        Some(&"sb-1".to_string())
-    );
-    assert_eq!(
-        first_metric.labels.get("namespace"),
-        Some(&"default".to_string())
-    );
+    assert_eq!(first_metric.label_values[0], "sb-1");
+    assert_eq!(first_metric.label_values[1], "default");
+    assert_eq!(metrics.container_metrics.len(), 1);
+    assert_eq!(metrics.container_metrics[0].container_id, "c-running");
 }
 
 #[tokio::test]
@@ -1242,8 +1268,8 @@ async fn test_stream_pod_sandbox_metrics_returns_snapshot() {
         .into_inner();
 
     let response = stream.next().await.unwrap().unwrap();
-    assert_eq!(response.pod_sandbox_metrics.len(), 1);
-    assert_eq!(response.pod_sandbox_metrics[0].pod_sandbox_id, "sb-1");
+    assert_eq!(response.pod_metrics.len(), 1);
+    assert_eq!(response.pod_metrics[0].pod_sandbox_id, "sb-1");
     assert!(stream.next().await.is_none());
 }
 
@@ -1268,18 +1294,36 @@ async fn test_stream_containers_returns_snapshot() {
 }
 
 #[tokio::test]
-async fn test_checkpoint_container_is_explicitly_unsupported() {
+async fn test_checkpoint_container_validates_request() {
     let svc = make_test_service();
-    let result = svc
-        .checkpoint_container(Request::new(CheckpointContainerRequest {
-            container_id: "c-1".to_string(),
-            location: "/tmp/checkpoints".to_string(),
+    let checkpoint = |container_id: &str, location: &str| {
+        svc.checkpoint_container(Request::new(CheckpointContainerRequest {
+            container_id: container_id.to_string(),
+            location: location.to_string(),
             timeout: 0,
         }))
+    };
+
+    let relative = checkpoint("c-1", "checkpoint.tar").await.unwrap_err();
+    assert_eq!(relative.code(), tonic::Code::InvalidArgument);
+
+    let missing = checkpoint("c-1", "/tmp/checkpoint.tar").await.unwrap_err();
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+
+    svc.store.sandboxes.add(test_sandbox("sb-1")).await;
+    svc.store
+        .containers
+        .add(test_container("c-1", "sb-1"))
         .await;
+    let created = checkpoint("c-1", "/tmp/checkpoint.tar").await.unwrap_err();
+    assert_eq!(created.code(), tonic::Code::FailedPrecondition);
 
-    assert!(result.is_err());
-    assert_eq!(result.unwrap_err().code(), tonic::Code::Unimplemented);
+    let mut running = test_container("c-2", "sb-1");
+    running.state = ContainerState::Running;
+    svc.store.containers.add(running).await;
+    let no_vm = checkpoint("c-2", "/tmp/checkpoint.tar").await.unwrap_err();
+    assert_eq!(no_vm.code(), tonic::Code::FailedPrecondition);
+    assert!(no_vm.message().contains("VM not found"));
 }
 
 #[tokio::test]
@@ -1316,12 +1360,17 @@ async fn test_get_container_events_streams_lifecycle_events() {
         .unwrap()
         .unwrap();
     assert_eq!(event.container_id, created.container_id);
-    assert_eq!(event.pod_sandbox_id, "sb-1");
     assert_eq!(
         event.container_event_type,
         ContainerEventType::ContainerCreatedEvent as i32
     );
-    assert_eq!(event.reason, "ContainerCreated");
+    assert_eq!(event.pod_sandbox_status.unwrap().id, "sb-1");
+    assert_eq!(event.containers_statuses.len(), 1);
+    assert_eq!(event.containers_statuses[0].id, created.container_id);
+    assert_eq!(
+        event.containers_statuses[0].state,
+        crate::cri_api::ContainerState::ContainerCreated as i32
+    );
 
     svc.stop_container(Request::new(StopContainerRequest {
         container_id: created.container_id.clone(),
@@ -1339,7 +1388,10 @@ async fn test_get_container_events_streams_lifecycle_events() {
         event.container_event_type,
         ContainerEventType::ContainerStoppedEvent as i32
     );
-    assert_eq!(event.reason, "StopContainer");
+    assert_eq!(
+        event.containers_statuses[0].state,
+        crate::cri_api::ContainerState::ContainerExited as i32
+    );
 
     svc.remove_container(Request::new(RemoveContainerRequest {
         container_id: created.container_id.clone(),
@@ -1356,7 +1408,7 @@ async fn test_get_container_events_streams_lifecycle_events() {
         event.container_event_type,
         ContainerEventType::ContainerDeletedEvent as i32
     );
-    assert_eq!(event.reason, "ContainerDeleted");
+    assert!(event.containers_statuses.is_empty());
 }
 
 // ── Version ──────────────────────────────────────────────────────
@@ -1454,6 +1506,35 @@ async fn test_status_verbose_info() {
         resp.info.get("warm_pool_enabled"),
         Some(&"false".to_string())
     );
+    let preflight: PreflightReport =
+        serde_json::from_str(resp.info.get("preflight").unwrap()).unwrap();
+    assert!(preflight.is_ready());
+}
+
+#[tokio::test]
+async fn test_status_reports_failed_host_preflight() {
+    let mut svc = make_test_service();
+    svc.test_preflight = Some(test_preflight_report(PreflightStatus::Fail));
+
+    let resp = svc
+        .status(Request::new(StatusRequest { verbose: false }))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let status = resp.status.unwrap();
+    let runtime = status
+        .conditions
+        .iter()
+        .find(|c| c.r#type == "RuntimeReady")
+        .unwrap();
+    assert!(!runtime.status);
+    assert_eq!(runtime.reason, "HostPreflightFailed");
+    assert_eq!(runtime.message, "virtualization: KVM");
+    assert!(status
+        .conditions
+        .iter()
+        .any(|c| c.r#type == "NetworkReady" && c.status));
 }
 
 // ── UpdateRuntimeConfig ──────────────────────────────────────────
@@ -1992,7 +2073,8 @@ fn test_parse_localhost_seccomp_deny_allow_default() {
         r#"{"defaultAction":"SCMP_ACT_ALLOW","syscalls":[{"names":["chmod","fchmodat"],"action":"SCMP_ACT_ERRNO"}]}"#,
     )
     .unwrap();
-    let deny = super::parse_localhost_seccomp_deny(path.to_str().unwrap(), dir.path()).unwrap();
+    let deny =
+        super::security::parse_localhost_seccomp_deny(path.to_str().unwrap(), dir.path()).unwrap();
     assert_eq!(deny, vec!["chmod".to_string(), "fchmodat".to_string()]);
 }
 
@@ -2018,7 +2100,8 @@ fn test_parse_localhost_seccomp_deny_log_default_filters_only_deny_actions() {
     )
     .unwrap();
 
-    let deny = super::parse_localhost_seccomp_deny("profiles/mixed.json", dir.path()).unwrap();
+    let deny =
+        super::security::parse_localhost_seccomp_deny("profiles/mixed.json", dir.path()).unwrap();
 
     assert_eq!(
         deny,
@@ -2037,7 +2120,7 @@ fn test_parse_localhost_seccomp_deny_defaults_missing_syscalls_to_empty() {
     let path = dir.path().join("empty.json");
     std::fs::write(&path, r#"{"defaultAction":"SCMP_ACT_ALLOW"}"#).unwrap();
 
-    let deny = super::parse_localhost_seccomp_deny("empty.json", dir.path()).unwrap();
+    let deny = super::security::parse_localhost_seccomp_deny("empty.json", dir.path()).unwrap();
 
     assert!(deny.is_empty());
 }
@@ -2048,23 +2131,28 @@ fn test_parse_localhost_seccomp_deny_rejects_deny_by_default() {
     let path = dir.path().join("deny-default.json");
     std::fs::write(&path, r#"{"defaultAction":"SCMP_ACT_ERRNO","syscalls":[]}"#).unwrap();
     // Deny-by-default profiles need a full allow-list; not supported -> Err so
-    // the caller falls back to RuntimeDefault rather than running unconfined.
-    assert!(super::parse_localhost_seccomp_deny(path.to_str().unwrap(), dir.path()).is_err());
+    // CreateContainer fails rather than running under a different filter.
+    assert!(
+        super::security::parse_localhost_seccomp_deny(path.to_str().unwrap(), dir.path()).is_err()
+    );
 }
 
 #[test]
 fn test_parse_localhost_seccomp_deny_reports_read_and_parse_errors() {
     let dir = tempfile::tempdir().unwrap();
 
-    let missing = super::parse_localhost_seccomp_deny("missing.json", dir.path()).unwrap_err();
+    let missing =
+        super::security::parse_localhost_seccomp_deny("missing.json", dir.path()).unwrap_err();
     assert!(missing.contains("read seccomp profile"));
 
     let malformed_path = dir.path().join("malformed.json");
     std::fs::write(&malformed_path, "{ not json").unwrap();
-    let malformed = super::parse_localhost_seccomp_deny("malformed.json", dir.path()).unwrap_err();
+    let malformed =
+        super::security::parse_localhost_seccomp_deny("malformed.json", dir.path()).unwrap_err();
     assert!(malformed.contains("parse seccomp profile"));
 
-    let escaped = super::parse_localhost_seccomp_deny("../escape.json", dir.path()).unwrap_err();
+    let escaped =
+        super::security::parse_localhost_seccomp_deny("../escape.json", dir.path()).unwrap_err();
     assert!(escaped.contains("contains '..'"));
 }
 
@@ -3454,6 +3542,37 @@ async fn test_update_container_resources_requires_running_container() {
     assert!(err.message().contains("requires a running container"));
 }
 
+#[tokio::test]
+async fn test_update_container_resources_records_cpuset() {
+    let svc = make_test_service();
+    let mut container = test_container("c-1", "sb-1");
+    container.state = ContainerState::Running;
+    svc.store.containers.add(container).await;
+    let update = |cpuset_cpus: &str| {
+        svc.update_container_resources(Request::new(UpdateContainerResourcesRequest {
+            container_id: "c-1".to_string(),
+            linux: Some(LinuxContainerResources {
+                cpuset_cpus: cpuset_cpus.to_string(),
+                cpuset_mems: "0".to_string(),
+                ..Default::default()
+            }),
+            annotations: HashMap::new(),
+        }))
+    };
+
+    let invalid = update("3-1").await.unwrap_err();
+    assert_eq!(invalid.code(), tonic::Code::InvalidArgument);
+
+    // The assignment is kept for the pod VM, which is not running here.
+    let result = update("2-3").await;
+    if cfg!(target_os = "linux") {
+        assert_eq!(result.unwrap_err().code(), tonic::Code::FailedPrecondition);
+    }
+    let stored = svc.store.containers.get("c-1").await.unwrap();
+    assert_eq!(stored.cpuset_cpus, "2-3");
+    assert_eq!(stored.cpuset_mems, "0");
+}
+
 #[tokio::test]
 async fn test_update_container_resources_rejects_exited_container() {
     let svc = make_test_service();
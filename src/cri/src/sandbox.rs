@@ -75,6 +75,14 @@ pub struct PodSandbox {
     /// stream); critest passes the port in the RPC and does not need this.
     #[serde(default)]
     pub container_ports: Vec<i32>,
+    /// Network store entry mirroring the CNI network the sandbox was added
+    /// to, when CNI plugins own its networking; teardown runs CNI DEL.
+    #[serde(default)]
+    pub cni_network: Option<String>,
+    /// Host cgroup (below the cgroup root) the sandbox VM was moved into,
+    /// inside the pod cgroup kubelet passed as `cgroup_parent`.
+    #[serde(default)]
+    pub host_cgroup: Option<String>,
 }
 
 /// In-memory store for pod sandboxes.
@@ -163,6 +171,8 @@ mod tests {
             additional_ips: vec![],
             dns: SandboxDns::default(),
             container_ports: vec![],
+            cni_network: None,
+            host_cgroup: None,
         }
     }
 
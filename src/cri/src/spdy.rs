@@ -1,11 +1,12 @@
 //! Minimal SPDY/3.1 server for the Kubernetes `remotecommand` (exec/attach)
 //! streaming protocol.
 //!
-//! `crictl` and the kubelet do not exec over a plain HTTP body or WebSocket —
-//! they upgrade the connection to `SPDY/3.1` and multiplex the
+//! `crictl` and the kubelet do not exec over a plain HTTP body — they upgrade
+//! the connection to `SPDY/3.1` and multiplex the
 //! error/stdin/stdout/stderr/resize channels as separate SPDY streams
 //! (`X-Stream-Protocol-Version: v4.channel.k8s.io`). This module implements just
-//! enough of SPDY/3.1 to serve that protocol.
+//! enough of SPDY/3.1 to serve that protocol; WebSocket clients are served by
+//! [`crate::websocket`].
 //!
 //! SPDY normally compresses `SYN_STREAM`/`SYN_REPLY` header blocks with a
 //! stateful zlib stream seeded by the SPDY/3 dictionary. We sidestep that
@@ -270,7 +271,7 @@ async fn collect_streams<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin>(
 /// Build the v4 error-stream `metav1.Status` payload for a finished command.
 /// Returns `None` for a successful (exit 0) command, where the client expects
 /// the error stream to simply close.
-fn exit_status_payload(exit_code: i32) -> Option<Vec<u8>> {
+pub(crate) fn exit_status_payload(exit_code: i32) -> Option<Vec<u8>> {
     if exit_code == 0 {
         return None;
     }
@@ -460,6 +461,8 @@ async fn serve_exec_tty(stream: TcpStream, session: &StreamingSession) -> Result
         user: None,
         cols: 80,
         rows: 24,
+        session: None,
+        resume: false,
     };
     let (mut pty_read, mut pty_write) = tokio::io::split(pty_stream);
     write_pty_frame(
@@ -675,15 +678,15 @@ pub async fn serve_attach(
 
 /// Terminal resize payload sent by the client on the resize stream.
 #[derive(serde::Deserialize)]
-struct TerminalSize {
+pub(crate) struct TerminalSize {
     #[serde(rename = "Width", alias = "width")]
-    width: u16,
+    pub(crate) width: u16,
     #[serde(rename = "Height", alias = "height")]
-    height: u16,
+    pub(crate) height: u16,
 }
 
 /// Write a guest PTY frame: `[u8 type][u32 BE len][payload]`.
-async fn write_pty_frame<W: AsyncWriteExt + Unpin>(
+pub(crate) async fn write_pty_frame<W: AsyncWriteExt + Unpin>(
     writer: &mut W,
     frame_type: u8,
     payload: &[u8],
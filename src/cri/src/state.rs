@@ -177,6 +177,8 @@ mod tests {
             additional_ips: vec![],
             dns: crate::sandbox::SandboxDns::default(),
             container_ports: vec![],
+            cni_network: None,
+            host_cgroup: None,
         }
     }
 
@@ -209,6 +211,8 @@ mod tests {
             log_path: String::new(),
             rootfs_path: "/".to_string(),
             rootfs_guest_path: format!("/run/a3s/cri/container-rootfs/{sandbox_id}/{id}/rootfs"),
+            cpuset_cpus: String::new(),
+            cpuset_mems: String::new(),
         }
     }
 
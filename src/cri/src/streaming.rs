@@ -22,12 +22,12 @@ pub(crate) const PORT_FORWARD_FRAME_OPEN: u8 = 1;
 pub(crate) const PORT_FORWARD_FRAME_OPEN_ACK: u8 = 2;
 pub(crate) const PORT_FORWARD_FRAME_DATA: u8 = 3;
 pub(crate) const PORT_FORWARD_FRAME_CLOSE: u8 = 4;
-const PORT_FORWARD_UNAVAILABLE_MESSAGE: &str =
+pub(crate) const PORT_FORWARD_UNAVAILABLE_MESSAGE: &str =
     "PortForward is not available for this sandbox: no guest port-forward control channel is configured.";
 const PORT_FORWARD_CONNECT_FAILED_MESSAGE: &str =
     "Failed to connect to the guest port-forward control channel.";
 const PORT_FORWARD_OPEN_FAILED_MESSAGE: &str = "Failed to open the requested guest port.";
-const PORT_FORWARD_MULTI_PORT_MESSAGE: &str =
+pub(crate) const PORT_FORWARD_MULTI_PORT_MESSAGE: &str =
     "PortForward currently supports exactly one port per streaming session.";
 const PORT_FORWARD_INVALID_PORT_MESSAGE: &str = "PortForward requested an invalid guest port.";
 const DEFAULT_STREAMING_SESSION_TTL: Duration = Duration::from_secs(60);
@@ -278,6 +278,8 @@ async fn handle_connection(
     tracing::debug!(peer = %peer, request = %request, "Streaming request received");
     // crictl/kubelet upgrade exec/attach to the SPDY/3.1 remotecommand protocol.
     let upgrade_spdy = request.to_ascii_lowercase().contains("upgrade: spdy");
+    // Clients without SPDY negotiate the same channels over a WebSocket.
+    let upgrade_websocket = crate::websocket::is_upgrade(&request);
 
     // Parse request line: GET /exec/<token> HTTP/1.1
     let first_line = request.lines().next().unwrap_or("");
@@ -287,7 +289,8 @@ async fn handle_connection(
         return Ok(());
     }
 
-    let path = parts[1];
+    // WebSocket port-forward names its ports in the query (`?port=8080`).
+    let path = parts[1].split('?').next().unwrap_or_default();
     let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
     if segments.len() != 2 {
         send_response(&mut stream, 404, "Not Found").await?;
@@ -326,15 +329,25 @@ async fn handle_connection(
     );
 
     match session.kind {
-        // Real clients (crictl, kubelet) always speak SPDY/3.1 remotecommand.
-        // The legacy bespoke handler is kept as a fallback for non-SPDY callers.
+        // crictl and the kubelet speak SPDY/3.1 remotecommand; newer clients
+        // may use the WebSocket `channel.k8s.io` protocols instead. The legacy
+        // bespoke handler is kept as a fallback for plain HTTP callers.
         SessionKind::Exec if upgrade_spdy => crate::spdy::serve_exec(stream, &session).await,
+        SessionKind::Exec if upgrade_websocket => {
+            crate::websocket::serve_exec(stream, &session, &request).await
+        }
         SessionKind::Exec => handle_exec_stream(&mut stream, &session).await,
         SessionKind::Attach if upgrade_spdy => crate::spdy::serve_attach(stream, &session).await,
+        SessionKind::Attach if upgrade_websocket => {
+            crate::websocket::serve_attach(stream, &session, &request).await
+        }
         SessionKind::Attach => handle_attach_stream(&mut stream, &session).await,
         SessionKind::PortForward if upgrade_spdy => {
             crate::spdy::serve_port_forward(stream, &session).await
         }
+        SessionKind::PortForward if upgrade_websocket => {
+            crate::websocket::serve_port_forward(stream, &session, &request).await
+        }
         SessionKind::PortForward => handle_port_forward_stream(&mut stream, &session).await,
     }
 }
@@ -503,6 +516,8 @@ async fn handle_pty_stream(
         user: None,
         cols: 80,
         rows: 24,
+        session: None,
+        resume: false,
     };
     let payload = serde_json::to_vec(&pty_req)?;
     write_pty_frame(
@@ -823,7 +838,7 @@ async fn handle_port_forward_stream(
 }
 
 /// Send a simple HTTP response.
-async fn send_response(
+pub(crate) async fn send_response(
     stream: &mut tokio::net::TcpStream,
     status: u16,
     body: &str,
@@ -0,0 +1,1029 @@
+//! WebSocket (RFC 6455) transport for the Kubernetes streaming protocols.
+//!
+//! Clients that do not speak SPDY/3.1 (newer kubelets and kubectl with
+//! WebSocket streaming enabled, or plain WebSocket tooling) upgrade the
+//! streaming URL to a WebSocket and negotiate one of the `channel.k8s.io`
+//! subprotocols. Every message then starts with a channel byte — 0 stdin,
+//! 1 stdout, 2 stderr, 3 error, 4 resize — and `v5.channel.k8s.io` adds a
+//! `[255, channel]` message that half-closes a channel (used for stdin EOF).
+//! The `base64.` variants carry the same messages as text frames with an
+//! ASCII channel digit and a base64 payload.
+//!
+//! Port-forward uses the same framing with one data/error channel pair per
+//! requested port (`?port=N` in the URL); the first message on each channel
+//! is the port as a little-endian `u16`. Like the SPDY path, one port per
+//! session is bridged to the guest port-forward control channel.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, WriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+use a3s_box_core::exec::{ExecEvent, StreamType as ExecStream};
+
+use crate::streaming::StreamingSession;
+
+type DynError = Box<dyn std::error::Error + Send + Sync>;
+type SharedWriter = Arc<Mutex<WriteHalf<TcpStream>>>;
+
+/// GUID appended to `Sec-WebSocket-Key` to derive `Sec-WebSocket-Accept`.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Largest message we accept from a client, across all fragments.
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+const CHANNEL_STDIN: u8 = 0;
+const CHANNEL_STDOUT: u8 = 1;
+const CHANNEL_STDERR: u8 = 2;
+const CHANNEL_ERROR: u8 = 3;
+const CHANNEL_RESIZE: u8 = 4;
+/// v5 control message: `[CHANNEL_CLOSE, channel]` half-closes `channel`.
+const CHANNEL_CLOSE: u8 = 255;
+
+/// Exec/attach subprotocols, as `(name, version, base64)`.
+const CHANNEL_PROTOCOLS: &[(&str, u8, bool)] = &[
+    ("v5.channel.k8s.io", 5, false),
+    ("v4.channel.k8s.io", 4, false),
+    ("v4.base64.channel.k8s.io", 4, true),
+    ("v3.channel.k8s.io", 3, false),
+    ("v2.channel.k8s.io", 2, false),
+    ("channel.k8s.io", 1, false),
+    ("base64.channel.k8s.io", 1, true),
+];
+
+/// Port-forward subprotocols, as `(name, version, base64)`.
+const PORT_FORWARD_PROTOCOLS: &[(&str, u8, bool)] = &[
+    ("v4.channel.k8s.io", 4, false),
+    ("v4.base64.channel.k8s.io", 4, true),
+];
+
+/// The negotiated `channel.k8s.io` subprotocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Protocol {
+    /// Name echoed in `Sec-WebSocket-Protocol`; empty when the client
+    /// offered none.
+    name: &'static str,
+    version: u8,
+    base64: bool,
+}
+
+/// Whether an HTTP request asks to upgrade to a WebSocket.
+pub(crate) fn is_upgrade(request: &str) -> bool {
+    header(request, "upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+/// Case-insensitive lookup of an HTTP request header.
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+    request.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then_some(value.trim())
+    })
+}
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`.
+fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(WEBSOCKET_GUID.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(hasher.finalize())
+}
+
+/// Pick the first protocol the client offered that we support. A client that
+/// offers none gets the original binary `channel.k8s.io` framing.
+fn negotiate(offered: Option<&str>, supported: &[(&'static str, u8, bool)]) -> Option<Protocol> {
+    let offered: Vec<&str> = offered
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect();
+    if offered.is_empty() {
+        return Some(Protocol {
+            name: "",
+            version: 1,
+            base64: false,
+        });
+    }
+    offered.iter().find_map(|name| {
+        supported
+            .iter()
+            .find(|(supported, _, _)| supported == name)
+            .map(|&(name, version, base64)| Protocol {
+                name,
+                version,
+                base64,
+            })
+    })
+}
+
+/// The `port` query parameters of the request line.
+fn query_ports(request: &str) -> Result<Vec<u16>, String> {
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("");
+    let Some((_, query)) = target.split_once('?') else {
+        return Ok(Vec::new());
+    };
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(key, _)| *key == "port")
+        .map(|(_, value)| {
+            value
+                .parse::<u16>()
+                .ok()
+                .filter(|port| *port != 0)
+                .ok_or_else(|| format!("invalid port {value:?}"))
+        })
+        .collect()
+}
+
+/// Complete the opening handshake. Returns `None` after answering with an
+/// HTTP error when the request cannot be upgraded.
+async fn handshake(
+    stream: &mut TcpStream,
+    request: &str,
+    supported: &[(&'static str, u8, bool)],
+) -> Result<Option<Protocol>, DynError> {
+    let Some(key) = header(request, "sec-websocket-key") else {
+        crate::streaming::send_response(stream, 400, "Missing Sec-WebSocket-Key").await?;
+        return Ok(None);
+    };
+    let Some(protocol) = negotiate(header(request, "sec-websocket-protocol"), supported) else {
+        crate::streaming::send_response(stream, 400, "Unsupported WebSocket subprotocol").await?;
+        return Ok(None);
+    };
+    let mut response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n",
+        accept_key(key)
+    );
+    if !protocol.name.is_empty() {
+        response.push_str(&format!("Sec-WebSocket-Protocol: {}\r\n", protocol.name));
+    }
+    response.push_str("\r\n");
+    let _ = stream.set_nodelay(true);
+    stream.write_all(response.as_bytes()).await?;
+    tracing::debug!(protocol = protocol.name, "websocket: 101 sent");
+    Ok(Some(protocol))
+}
+
+/// An unmasked server frame.
+fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 10);
+    out.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => out.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            out.push(126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            out.push(127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(payload);
+    out
+}
+
+/// A close frame with status 1000 (normal closure).
+fn close_frame() -> Vec<u8> {
+    frame(OPCODE_CLOSE, &1000u16.to_be_bytes())
+}
+
+/// A channel message in the negotiated framing.
+fn channel_frame(protocol: Protocol, channel: u8, data: &[u8]) -> Vec<u8> {
+    if protocol.base64 {
+        let mut payload = vec![b'0' + channel];
+        payload.extend_from_slice(
+            base64::engine::general_purpose::STANDARD
+                .encode(data)
+                .as_bytes(),
+        );
+        frame(OPCODE_TEXT, &payload)
+    } else {
+        let mut payload = vec![channel];
+        payload.extend_from_slice(data);
+        frame(OPCODE_BINARY, &payload)
+    }
+}
+
+/// Split a client message into its channel and data.
+fn decode_channel_message(protocol: Protocol, message: &[u8]) -> Option<(u8, Vec<u8>)> {
+    let (&channel, data) = message.split_first()?;
+    if !protocol.base64 {
+        return Some((channel, data.to_vec()));
+    }
+    let channel = channel.checked_sub(b'0').filter(|channel| *channel <= 9)?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .ok()?;
+    Some((channel, data))
+}
+
+/// One frame read off the wire, unmasked.
+#[derive(Debug, PartialEq, Eq)]
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> std::io::Result<Option<Frame>> {
+    let mut head = [0u8; 2];
+    match reader.read_exact(&mut head).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let fin = head[0] & 0x80 != 0;
+    let opcode = head[0] & 0x0F;
+    let masked = head[1] & 0x80 != 0;
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut ext = [0u8; 2];
+            reader.read_exact(&mut ext).await?;
+            u16::from_be_bytes(ext) as u64
+        }
+        127 => {
+            let mut ext = [0u8; 8];
+            reader.read_exact(&mut ext).await?;
+            u64::from_be_bytes(ext)
+        }
+        len => len as u64,
+    };
+    if len > MAX_MESSAGE_LEN as u64 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("websocket frame of {len} bytes exceeds the limit"),
+        ));
+    }
+    let mut mask = [0u8; 4];
+    if masked {
+        reader.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload).await?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+    Ok(Some(Frame {
+        fin,
+        opcode,
+        payload,
+    }))
+}
+
+/// Read the next complete data message, answering pings along the way.
+/// Returns `None` once the client closes the connection.
+async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    writer: &SharedWriter,
+) -> std::io::Result<Option<Vec<u8>>> {
+    let mut message = Vec::new();
+    loop {
+        let Some(frame) = read_frame(reader).await? else {
+            return Ok(None);
+        };
+        match frame.opcode {
+            OPCODE_PING => {
+                writer
+                    .lock()
+                    .await
+                    .write_all(&self::frame(OPCODE_PONG, &frame.payload))
+                    .await?;
+                continue;
+            }
+            OPCODE_PONG => continue,
+            OPCODE_CLOSE => {
+                let _ = writer.lock().await.write_all(&close_frame()).await;
+                return Ok(None);
+            }
+            OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {}
+            opcode => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("unknown websocket opcode {opcode:#x}"),
+                ))
+            }
+        }
+        if message.len() + frame.payload.len() > MAX_MESSAGE_LEN {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "websocket message exceeds the limit",
+            ));
+        }
+        message.extend_from_slice(&frame.payload);
+        if frame.fin {
+            return Ok(Some(message));
+        }
+    }
+}
+
+async fn send(writer: &SharedWriter, protocol: Protocol, channel: u8, data: &[u8]) -> bool {
+    writer
+        .lock()
+        .await
+        .write_all(&channel_frame(protocol, channel, data))
+        .await
+        .is_ok()
+}
+
+/// Tell the client the streams are ready with an empty message on the first
+/// channel it reads, as the kubelet does.
+async fn announce(writer: &SharedWriter, protocol: Protocol, session: &StreamingSession) {
+    let channel = if session.stdout {
+        CHANNEL_STDOUT
+    } else if session.stderr {
+        CHANNEL_STDERR
+    } else {
+        CHANNEL_ERROR
+    };
+    send(writer, protocol, channel, &[]).await;
+}
+
+/// Report the command's exit on the error channel and close the connection.
+/// v4 and later always send a `metav1.Status`; older versions send a plain
+/// message, and only on failure.
+async fn finish(writer: &SharedWriter, protocol: Protocol, exit_code: i32) {
+    let status = if protocol.version >= 4 {
+        Some(
+            crate::spdy::exit_status_payload(exit_code)
+                .unwrap_or_else(|| br#"{"metadata":{},"status":"Success"}"#.to_vec()),
+        )
+    } else {
+        (exit_code != 0).then(|| {
+            format!("command terminated with non-zero exit code: error executing command, exit code {exit_code}")
+                .into_bytes()
+        })
+    };
+    if let Some(status) = status {
+        send(writer, protocol, CHANNEL_ERROR, &status).await;
+    }
+    let _ = writer.lock().await.write_all(&close_frame()).await;
+}
+
+/// Serve a CRI exec over WebSocket. `request` is the raw upgrade request.
+pub async fn serve_exec(
+    mut stream: TcpStream,
+    session: &StreamingSession,
+    request: &str,
+) -> Result<(), DynError> {
+    let Some(protocol) = handshake(&mut stream, request, CHANNEL_PROTOCOLS).await? else {
+        return Ok(());
+    };
+    let (mut reader, writer) = tokio::io::split(stream);
+    let writer: SharedWriter = Arc::new(Mutex::new(writer));
+    if session.tty {
+        return serve_exec_tty(reader, writer, protocol, session).await;
+    }
+
+    let request = a3s_box_core::exec::ExecRequest {
+        request_id: None,
+        cmd: session.cmd.clone(),
+        timeout_ns: a3s_box_core::exec::DEFAULT_EXEC_TIMEOUT_NS,
+        env: vec![],
+        working_dir: None,
+        rootfs: session.rootfs.clone(),
+        stdin: None,
+        stdin_streaming: session.stdin,
+        user: None,
+        streaming: false,
+    };
+    let client = a3s_box_runtime::ExecClient::connect(Path::new(&session.exec_socket_path)).await?;
+    let mut exec = client.exec_stream(&request).await?;
+    let input = exec.input();
+    announce(&writer, protocol, session).await;
+
+    // Client → guest: stdin messages, and stdin EOF on v5.
+    let reader_task = {
+        let writer = writer.clone();
+        async move {
+            loop {
+                match read_message(&mut reader, &writer).await {
+                    Ok(Some(message)) => match decode_channel_message(protocol, &message) {
+                        Some((CHANNEL_STDIN, data)) if !data.is_empty() => {
+                            let _ = input.write_stdin(&data).await;
+                        }
+                        Some((CHANNEL_CLOSE, data)) if data.first() == Some(&CHANNEL_STDIN) => {
+                            let _ = input.close_stdin().await;
+                        }
+                        _ => {}
+                    },
+                    // The client went away: stop the command rather than
+                    // leave it running orphaned in the guest.
+                    Ok(None) | Err(_) => {
+                        let _ = input.cancel().await;
+                        break;
+                    }
+                }
+            }
+        }
+    };
+
+    // Guest → client: stdout/stderr, then the exit status.
+    let writer_task = {
+        let writer = writer.clone();
+        async move {
+            let mut exit_code = 0;
+            loop {
+                match exec.next_event().await {
+                    Ok(Some(ExecEvent::Chunk(chunk))) => {
+                        let channel = match chunk.stream {
+                            ExecStream::Stdout if session.stdout => CHANNEL_STDOUT,
+                            ExecStream::Stderr if session.stderr => CHANNEL_STDERR,
+                            _ => continue,
+                        };
+                        send(&writer, protocol, channel, &chunk.data).await;
+                    }
+                    Ok(Some(ExecEvent::Exit(exit))) => {
+                        exit_code = exit.exit_code;
+                        break;
+                    }
+                    Ok(Some(ExecEvent::FlushAck)) => {}
+                    Ok(None) | Err(_) => break,
+                }
+            }
+            finish(&writer, protocol, exit_code).await;
+        }
+    };
+
+    tokio::select! {
+        _ = writer_task => {}
+        _ = reader_task => {}
+    }
+    Ok(())
+}
+
+/// Serve an interactive TTY exec over WebSocket by bridging the guest PTY
+/// socket: stdin and resize messages go to the PTY, its output to stdout.
+async fn serve_exec_tty(
+    mut reader: tokio::io::ReadHalf<TcpStream>,
+    writer: SharedWriter,
+    protocol: Protocol,
+    session: &StreamingSession,
+) -> Result<(), DynError> {
+    use crate::spdy::{write_pty_frame, TerminalSize};
+    use a3s_box_core::pty;
+
+    let pty_stream = tokio::net::UnixStream::connect(&session.pty_socket_path).await?;
+    let request = pty::PtyRequest {
+        cmd: session.cmd.clone(),
+        env: vec![],
+        working_dir: None,
+        rootfs: session.rootfs.clone(),
+        user: None,
+        cols: 80,
+        rows: 24,
+        session: None,
+        resume: false,
+    };
+    let (mut pty_read, mut pty_write) = tokio::io::split(pty_stream);
+    write_pty_frame(
+        &mut pty_write,
+        pty::FRAME_PTY_REQUEST,
+        &serde_json::to_vec(&request)?,
+    )
+    .await?;
+    announce(&writer, protocol, session).await;
+
+    let client_to_pty = {
+        let writer = writer.clone();
+        async move {
+            while let Ok(Some(message)) = read_message(&mut reader, &writer).await {
+                match decode_channel_message(protocol, &message) {
+                    Some((CHANNEL_STDIN, data)) if !data.is_empty() => {
+                        let written =
+                            write_pty_frame(&mut pty_write, pty::FRAME_PTY_DATA, &data).await;
+                        if written.is_err() {
+                            break;
+                        }
+                    }
+                    Some((CHANNEL_RESIZE, data)) => {
+                        let Ok(size) = serde_json::from_slice::<TerminalSize>(&data) else {
+                            continue;
+                        };
+                        let resize = pty::PtyResize {
+                            cols: size.width,
+                            rows: size.height,
+                        };
+                        if let Ok(payload) = serde_json::to_vec(&resize) {
+                            let _ =
+                                write_pty_frame(&mut pty_write, pty::FRAME_PTY_RESIZE, &payload)
+                                    .await;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    let pty_to_client = {
+        let writer = writer.clone();
+        async move {
+            let mut exit_code = 0;
+            let mut header = [0u8; 5];
+            while pty_read.read_exact(&mut header).await.is_ok() {
+                let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+                if len > pty::MAX_FRAME_PAYLOAD {
+                    break;
+                }
+                let mut payload = vec![0u8; len];
+                if len > 0 && pty_read.read_exact(&mut payload).await.is_err() {
+                    break;
+                }
+                match header[0] {
+                    t if t == pty::FRAME_PTY_DATA => {
+                        send(&writer, protocol, CHANNEL_STDOUT, &payload).await;
+                    }
+                    t if t == pty::FRAME_PTY_EXIT => {
+                        exit_code = serde_json::from_slice::<pty::PtyExit>(&payload)
+                            .map(|exit| exit.exit_code)
+                            .unwrap_or(0);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            finish(&writer, protocol, exit_code).await;
+        }
+    };
+
+    tokio::select! {
+        _ = pty_to_client => {}
+        _ = client_to_pty => {}
+    }
+    Ok(())
+}
+
+/// Serve a CRI attach over WebSocket: relay the running workload's output
+/// and forward stdin to its stdin sink.
+pub async fn serve_attach(
+    mut stream: TcpStream,
+    session: &StreamingSession,
+    request: &str,
+) -> Result<(), DynError> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let Some(attach_stream) = session.attach_stream.as_ref() else {
+        crate::streaming::send_response(
+            &mut stream,
+            501,
+            "Attach is not available: no running workload stream is registered.",
+        )
+        .await?;
+        return Ok(());
+    };
+    let Some(protocol) = handshake(&mut stream, request, CHANNEL_PROTOCOLS).await? else {
+        return Ok(());
+    };
+    let (mut reader, writer) = tokio::io::split(stream);
+    let writer: SharedWriter = Arc::new(Mutex::new(writer));
+    let mut receiver = attach_stream.subscribe();
+    let stdin = session.attach_stdin.clone();
+    let stdin_once = session.stdin_once;
+    announce(&writer, protocol, session).await;
+
+    let reader_task = {
+        let writer = writer.clone();
+        async move {
+            let mut closed = false;
+            while let Ok(Some(message)) = read_message(&mut reader, &writer).await {
+                match decode_channel_message(protocol, &message) {
+                    Some((CHANNEL_STDIN, data)) if !data.is_empty() => {
+                        if let Some(input) = stdin.as_ref() {
+                            let _ = input.write_stdin(&data).await;
+                        }
+                    }
+                    Some((CHANNEL_CLOSE, data))
+                        if data.first() == Some(&CHANNEL_STDIN) && !closed =>
+                    {
+                        closed = true;
+                        if stdin_once {
+                            if let Some(input) = stdin.as_ref() {
+                                let _ = input.close_stdin().await;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // Detaching leaves the workload's stdin open unless the container
+            // asked for it to close after the first attach.
+            if stdin_once && !closed {
+                if let Some(input) = stdin.as_ref() {
+                    let _ = input.close_stdin().await;
+                }
+            }
+        }
+    };
+
+    let writer_task = {
+        let writer = writer.clone();
+        async move {
+            let mut exit_code = 0;
+            loop {
+                match receiver.recv().await {
+                    Ok(ExecEvent::Chunk(chunk)) => {
+                        let channel = match chunk.stream {
+                            ExecStream::Stdout if session.stdout => CHANNEL_STDOUT,
+                            ExecStream::Stderr if session.stderr => CHANNEL_STDERR,
+                            _ => continue,
+                        };
+                        send(&writer, protocol, channel, &chunk.data).await;
+                    }
+                    Ok(ExecEvent::FlushAck) => {}
+                    Ok(ExecEvent::Exit(exit)) => {
+                        exit_code = exit.exit_code;
+                        break;
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            finish(&writer, protocol, exit_code).await;
+        }
+    };
+
+    tokio::select! {
+        _ = writer_task => {}
+        _ = reader_task => {}
+    }
+    Ok(())
+}
+
+/// Serve a CRI port-forward over WebSocket. The port comes from the URL's
+/// `port` query parameter, falling back to the port in the CRI request.
+pub async fn serve_port_forward(
+    mut stream: TcpStream,
+    session: &StreamingSession,
+    request: &str,
+) -> Result<(), DynError> {
+    use crate::streaming::{
+        read_port_forward_frame, send_response, write_port_forward_frame, PORT_FORWARD_FRAME_CLOSE,
+        PORT_FORWARD_FRAME_DATA, PORT_FORWARD_FRAME_OPEN, PORT_FORWARD_FRAME_OPEN_ACK,
+        PORT_FORWARD_MULTI_PORT_MESSAGE, PORT_FORWARD_STREAM_ID, PORT_FORWARD_UNAVAILABLE_MESSAGE,
+    };
+
+    let ports = match query_ports(request) {
+        Ok(ports) if ports.is_empty() => session
+            .ports
+            .iter()
+            .filter_map(|port| u16::try_from(*port).ok())
+            .collect(),
+        Ok(ports) => ports,
+        Err(message) => {
+            send_response(&mut stream, 400, &message).await?;
+            return Ok(());
+        }
+    };
+    let port = match ports.as_slice() {
+        [port] => *port,
+        _ => {
+            send_response(&mut stream, 400, PORT_FORWARD_MULTI_PORT_MESSAGE).await?;
+            return Ok(());
+        }
+    };
+    if session.port_forward_socket_path.is_empty() {
+        send_response(&mut stream, 501, PORT_FORWARD_UNAVAILABLE_MESSAGE).await?;
+        return Ok(());
+    }
+    let Some(protocol) = handshake(&mut stream, request, PORT_FORWARD_PROTOCOLS).await? else {
+        return Ok(());
+    };
+    let (mut reader, writer) = tokio::io::split(stream);
+    let writer: SharedWriter = Arc::new(Mutex::new(writer));
+
+    // Data channel 0, error channel 1; each opens with the port number.
+    let (data_channel, error_channel) = (0u8, 1u8);
+    for channel in [data_channel, error_channel] {
+        send(&writer, protocol, channel, &port.to_le_bytes()).await;
+    }
+
+    let control = match tokio::net::UnixStream::connect(&session.port_forward_socket_path).await {
+        Ok(mut control) => {
+            write_port_forward_frame(
+                &mut control,
+                PORT_FORWARD_FRAME_OPEN,
+                PORT_FORWARD_STREAM_ID,
+                &port.to_be_bytes(),
+            )
+            .await?;
+            let ack = read_port_forward_frame(&mut control).await?;
+            ack.is_some_and(|frame| {
+                frame.kind == PORT_FORWARD_FRAME_OPEN_ACK
+                    && frame.stream_id == PORT_FORWARD_STREAM_ID
+                    && frame.payload.first().copied().unwrap_or(1) == 0
+            })
+            .then_some(control)
+        }
+        Err(error) => {
+            tracing::warn!(error = %error, "websocket pf: guest control connect failed");
+            None
+        }
+    };
+    let Some(control) = control else {
+        let message = format!("failed to open port {port} in the sandbox");
+        send(&writer, protocol, error_channel, message.as_bytes()).await;
+        let _ = writer.lock().await.write_all(&close_frame()).await;
+        return Ok(());
+    };
+    let (mut control_read, mut control_write) = tokio::io::split(control);
+
+    let client_to_guest = {
+        let writer = writer.clone();
+        async move {
+            while let Ok(Some(message)) = read_message(&mut reader, &writer).await {
+                let Some((channel, data)) = decode_channel_message(protocol, &message) else {
+                    continue;
+                };
+                if channel == data_channel
+                    && !data.is_empty()
+                    && write_port_forward_frame(
+                        &mut control_write,
+                        PORT_FORWARD_FRAME_DATA,
+                        PORT_FORWARD_STREAM_ID,
+                        &data,
+                    )
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            let _ = write_port_forward_frame(
+                &mut control_write,
+                PORT_FORWARD_FRAME_CLOSE,
+                PORT_FORWARD_STREAM_ID,
+                &[],
+            )
+            .await;
+        }
+    };
+
+    let guest_to_client = {
+        let writer = writer.clone();
+        async move {
+            while let Ok(Some(frame)) = read_port_forward_frame(&mut control_read).await {
+                if frame.stream_id != PORT_FORWARD_STREAM_ID {
+                    continue;
+                }
+                match frame.kind {
+                    PORT_FORWARD_FRAME_DATA => {
+                        let sent = send(&writer, protocol, data_channel, &frame.payload).await;
+                        if !sent {
+                            break;
+                        }
+                    }
+                    PORT_FORWARD_FRAME_CLOSE => break,
+                    _ => {}
+                }
+            }
+            let _ = writer.lock().await.write_all(&close_frame()).await;
+        }
+    };
+
+    tokio::pin!(client_to_guest);
+    tokio::pin!(guest_to_client);
+    tokio::select! {
+        _ = &mut guest_to_client => {}
+        _ = &mut client_to_guest => {
+            let _ = guest_to_client.await;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::{
+        read_port_forward_frame, write_port_forward_frame, SessionKind, PORT_FORWARD_FRAME_CLOSE,
+        PORT_FORWARD_FRAME_DATA, PORT_FORWARD_FRAME_OPEN, PORT_FORWARD_FRAME_OPEN_ACK,
+        PORT_FORWARD_STREAM_ID,
+    };
+
+    /// A masked client frame, as browsers and client libraries send them.
+    fn client_frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut out = vec![if fin { 0x80 } else { 0 } | opcode];
+        assert!(payload.len() < 126);
+        out.push(0x80 | payload.len() as u8);
+        out.extend_from_slice(&mask);
+        out.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        out
+    }
+
+    #[test]
+    fn test_accept_key_matches_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_negotiate_prefers_client_order() {
+        let protocol = negotiate(
+            Some("v4.base64.channel.k8s.io, v5.channel.k8s.io"),
+            CHANNEL_PROTOCOLS,
+        )
+        .unwrap();
+        assert_eq!((protocol.version, protocol.base64), (4, true));
+
+        let none = negotiate(None, CHANNEL_PROTOCOLS).unwrap();
+        assert_eq!((none.name, none.version), ("", 1));
+        assert!(negotiate(Some("v5.channel.k8s.io"), PORT_FORWARD_PROTOCOLS).is_none());
+    }
+
+    #[test]
+    fn test_query_ports() {
+        assert_eq!(
+            query_ports("GET /portforward/abc?port=8080 HTTP/1.1\r\n").unwrap(),
+            [8080]
+        );
+        assert!(query_ports("GET /portforward/abc HTTP/1.1\r\n")
+            .unwrap()
+            .is_empty());
+        assert!(query_ports("GET /portforward/abc?port=http HTTP/1.1\r\n").is_err());
+    }
+
+    #[test]
+    fn test_channel_messages_round_trip() {
+        let binary = negotiate(Some("v5.channel.k8s.io"), CHANNEL_PROTOCOLS).unwrap();
+        let encoded = channel_frame(binary, CHANNEL_STDOUT, b"hi");
+        assert_eq!(encoded, [0x82, 3, CHANNEL_STDOUT, b'h', b'i']);
+        assert_eq!(
+            decode_channel_message(binary, &[CHANNEL_CLOSE, CHANNEL_STDIN]),
+            Some((CHANNEL_CLOSE, vec![CHANNEL_STDIN]))
+        );
+
+        let text = negotiate(Some("base64.channel.k8s.io"), CHANNEL_PROTOCOLS).unwrap();
+        assert_eq!(
+            channel_frame(text, CHANNEL_STDERR, b"hi"),
+            frame(OPCODE_TEXT, b"2aGk=")
+        );
+        assert_eq!(
+            decode_channel_message(text, b"0aGk="),
+            Some((CHANNEL_STDIN, b"hi".to_vec()))
+        );
+        assert_eq!(decode_channel_message(text, b"x"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_unmasks_and_reassembles_fragments() {
+        let mut bytes = client_frame(false, OPCODE_BINARY, &[CHANNEL_STDIN, b'a']);
+        bytes.extend(client_frame(true, OPCODE_CONTINUATION, b"bc"));
+        let mut reader = std::io::Cursor::new(bytes);
+        assert_eq!(
+            read_frame(&mut reader).await.unwrap().unwrap(),
+            Frame {
+                fin: false,
+                opcode: OPCODE_BINARY,
+                payload: vec![CHANNEL_STDIN, b'a'],
+            }
+        );
+        let rest = read_frame(&mut reader).await.unwrap().unwrap();
+        assert_eq!((rest.fin, rest.payload), (true, b"bc".to_vec()));
+        assert!(read_frame(&mut reader).await.unwrap().is_none());
+
+        let long = frame(OPCODE_BINARY, &[7u8; 300]);
+        assert_eq!(&long[..4], &[0x82, 126, 1, 44]);
+        let decoded = read_frame(&mut std::io::Cursor::new(long))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded.payload.len(), 300);
+    }
+
+    #[tokio::test]
+    async fn test_serve_port_forward_bridges_guest_control_socket() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let sock_path = tmp.path().join("portfwd.sock");
+        let Ok(listener) = tokio::net::UnixListener::bind(&sock_path) else {
+            return;
+        };
+        let Ok(tcp_listener) = tokio::net::TcpListener::bind("127.0.0.1:0").await else {
+            return;
+        };
+
+        let guest = tokio::spawn(async move {
+            let (mut control, _) = listener.accept().await.unwrap();
+            let open = read_port_forward_frame(&mut control)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(open.kind, PORT_FORWARD_FRAME_OPEN);
+            assert_eq!(open.payload, 9090u16.to_be_bytes());
+            write_port_forward_frame(
+                &mut control,
+                PORT_FORWARD_FRAME_OPEN_ACK,
+                PORT_FORWARD_STREAM_ID,
+                &[0],
+            )
+            .await
+            .unwrap();
+            let data = read_port_forward_frame(&mut control)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(data.payload, b"ping");
+            write_port_forward_frame(
+                &mut control,
+                PORT_FORWARD_FRAME_DATA,
+                PORT_FORWARD_STREAM_ID,
+                b"pong",
+            )
+            .await
+            .unwrap();
+            write_port_forward_frame(
+                &mut control,
+                PORT_FORWARD_FRAME_CLOSE,
+                PORT_FORWARD_STREAM_ID,
+                &[],
+            )
+            .await
+            .unwrap();
+        });
+
+        let addr = tcp_listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let mut response = Vec::new();
+            while !response.ends_with(b"\r\n\r\n") {
+                let mut byte = [0u8; 1];
+                stream.read_exact(&mut byte).await.unwrap();
+                response.push(byte[0]);
+            }
+            let response = String::from_utf8(response).unwrap();
+            assert!(response.starts_with("HTTP/1.1 101 Switching Protocols"));
+            assert!(response.contains("Sec-WebSocket-Protocol: v4.channel.k8s.io\r\n"));
+
+            let mut messages = Vec::new();
+            for _ in 0..2 {
+                messages.push(read_frame(&mut stream).await.unwrap().unwrap().payload);
+            }
+            assert_eq!(messages, [vec![0, 0x82, 0x23], vec![1, 0x82, 0x23]]);
+            stream
+                .write_all(&client_frame(true, OPCODE_BINARY, b"\x00ping"))
+                .await
+                .unwrap();
+            let reply = read_frame(&mut stream).await.unwrap().unwrap();
+            assert_eq!(reply.payload, b"\x00pong");
+            let close = read_frame(&mut stream).await.unwrap().unwrap();
+            assert_eq!(close.opcode, OPCODE_CLOSE);
+        });
+
+        let (server_stream, _) = tcp_listener.accept().await.unwrap();
+        let session = StreamingSession {
+            kind: SessionKind::PortForward,
+            sandbox_id: "sb-ws".to_string(),
+            cmd: vec![],
+            rootfs: None,
+            tty: false,
+            stdin: false,
+            stdin_once: false,
+            stdout: true,
+            stderr: true,
+            attach_stream: None,
+            attach_stdin: None,
+            ports: vec![],
+            exec_socket_path: String::new(),
+            pty_socket_path: String::new(),
+            port_forward_socket_path: sock_path.to_string_lossy().to_string(),
+        };
+        let request = "GET /portforward/token?port=9090 HTTP/1.1\r\n\
+                       Upgrade: websocket\r\n\
+                       Connection: Upgrade\r\n\
+                       Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                       Sec-WebSocket-Protocol: v4.channel.k8s.io\r\n\r\n";
+        assert!(is_upgrade(request));
+        serve_port_forward(server_stream, &session, request)
+            .await
+            .unwrap();
+
+        client.await.unwrap();
+        guest.await.unwrap();
+    }
+}
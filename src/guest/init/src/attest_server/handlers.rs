@@ -30,6 +30,8 @@ const SIMULATED_REPORT_VERSION: u32 = 0xA3;
 /// - `seal` — Seal data bound to TEE identity
 /// - `unseal` — Unseal previously sealed data
 /// - `process` — Forward to local agent
+/// - `challenge` — Answer a verifier's nonce with a fresh report
+/// - `workspace_key` — Open and mount the encrypted workspace
 #[cfg(target_os = "linux")]
 pub(super) fn handle_tls_connection(
     fd: std::os::fd::OwnedFd,
@@ -70,6 +72,18 @@ pub(super) fn handle_tls_connection(
                             AttestRoute::Process => {
                                 handle_process_request(&req.payload, &mut tls);
                             }
+                            AttestRoute::KbsEvidence => {
+                                super::kbs::handle_kbs_evidence(&req.payload, &mut tls);
+                            }
+                            AttestRoute::KbsResource => {
+                                super::kbs::handle_kbs_resource(&req.payload, &mut tls);
+                            }
+                            AttestRoute::Challenge => {
+                                handle_challenge(&req.payload, &mut tls);
+                            }
+                            AttestRoute::WorkspaceKey => {
+                                handle_workspace_key(&req.payload, &mut tls);
+                            }
                             AttestRoute::Status => {
                                 send_data_response(&mut tls, b"{\"status\":\"ok\",\"tee\":true}");
                             }
@@ -93,6 +107,70 @@ pub(super) fn handle_tls_connection(
     Ok(())
 }
 
+// ============================================================================
+// Nonce challenge
+// ============================================================================
+
+/// Challenge response returned to the host. Field names match the runtime's
+/// `AttestationReport`, which fills in the platform info itself.
+#[cfg(target_os = "linux")]
+#[derive(serde::Serialize)]
+struct ChallengeResponse {
+    report: Vec<u8>,
+    cert_chain: super::snp::CertChain,
+}
+
+/// Handle a nonce challenge: generate a report for the verifier's nonce.
+#[cfg(target_os = "linux")]
+fn handle_challenge(payload: &serde_json::Value, tls: &mut impl Write) {
+    use a3s_box_core::tee::{
+        AttestChallenge, CHALLENGE_NONCE_LEN, RUNTIME_CLAIMS_REPORT_DATA_RANGE,
+    };
+
+    let req: AttestChallenge = match serde_json::from_value(payload.clone()) {
+        Ok(r) => r,
+        Err(e) => {
+            send_error_response(tls, &format!("Invalid challenge payload: {}", e));
+            return;
+        }
+    };
+    if req.nonce.len() != CHALLENGE_NONCE_LEN {
+        send_error_response(
+            tls,
+            &format!("Challenge nonce must be {} bytes", CHALLENGE_NONCE_LEN),
+        );
+        return;
+    }
+
+    let mut report_data = [0u8; super::SNP_USER_DATA_SIZE];
+    report_data[..CHALLENGE_NONCE_LEN].copy_from_slice(&req.nonce);
+    if let Some(digest) = super::runtime_claims_digest() {
+        report_data[RUNTIME_CLAIMS_REPORT_DATA_RANGE].copy_from_slice(&digest);
+    }
+
+    let response = if is_simulate_mode() {
+        ChallengeResponse {
+            report: build_simulated_report(&report_data),
+            cert_chain: super::snp::CertChain::default(),
+        }
+    } else {
+        match super::snp::get_snp_report(&report_data) {
+            Ok(resp) => ChallengeResponse {
+                report: resp.report,
+                cert_chain: resp.cert_chain,
+            },
+            Err(e) => {
+                send_error_response(tls, &format!("Failed to get SNP report: {}", e));
+                return;
+            }
+        }
+    };
+
+    debug!("Answered attestation challenge");
+    let body = serde_json::to_vec(&response).unwrap_or_default();
+    send_data_response(tls, &body);
+}
+
 // ============================================================================
 // Secret injection
 // ============================================================================
@@ -148,47 +226,109 @@ fn handle_secret_injection(payload: &serde_json::Value, tls: &mut impl Write) {
     let mut injected = 0;
     let mut errors = Vec::new();
 
-    // Ensure secrets directory exists
-    if let Err(e) = std::fs::create_dir_all(SECRETS_DIR) {
-        send_error_response(tls, &format!("Failed to create secrets dir: {}", e));
+    // Ensure secrets directory exists, in memory
+    if let Err(e) = ensure_secrets_tmpfs() {
+        send_error_response(tls, &format!("Failed to prepare secrets dir: {}", e));
         return;
     }
 
     for entry in &req.secrets {
-        // Validate name (alphanumeric, underscore, dash, dot only)
-        if !is_valid_secret_name(&entry.name) {
-            errors.push(format!("Invalid secret name: {}", entry.name));
-            continue;
+        match store_secret(&entry.name, entry.value.as_bytes(), entry.set_env) {
+            Ok(()) => injected += 1,
+            Err(e) => errors.push(e),
         }
+    }
 
-        // Write to /run/secrets/<name>
-        let path = format!("{}/{}", SECRETS_DIR, entry.name);
-        match std::fs::write(&path, entry.value.as_bytes()) {
-            Ok(()) => {
-                // Set restrictive permissions (owner read only)
-                #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
-                    let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o400));
-                }
+    let response = SecretInjectionResponse { injected, errors };
+    let body = serde_json::to_vec(&response).unwrap_or_else(|_| b"{\"injected\":0}".to_vec());
+    send_data_response(tls, &body);
+}
 
-                // Set environment variable if requested
-                if entry.set_env {
-                    std::env::set_var(&entry.name, &entry.value);
-                }
+/// Handle a workspace key: open the encrypted workspace with it.
+#[cfg(target_os = "linux")]
+fn handle_workspace_key(payload: &serde_json::Value, tls: &mut impl Write) {
+    use crate::encrypted_workspace;
+    use a3s_box_core::tee::WorkspaceKeyRequest;
 
-                injected += 1;
-                info!("Secret injected: {}", entry.name);
-            }
-            Err(e) => {
-                errors.push(format!("Failed to write {}: {}", entry.name, e));
-            }
+    if !encrypted_workspace::is_enabled() {
+        send_error_response(tls, "This box has no encrypted workspace");
+        return;
+    }
+    let mut req: WorkspaceKeyRequest = match serde_json::from_value(payload.clone()) {
+        Ok(r) => r,
+        Err(e) => {
+            send_error_response(tls, &format!("Invalid workspace key payload: {}", e));
+            return;
         }
+    };
+    let result = encrypted_workspace::open_and_mount(&req.volume_key);
+    req.volume_key.fill(0);
+    match result {
+        Ok(()) => {
+            info!(
+                "Encrypted workspace mounted at {}",
+                encrypted_workspace::WORKSPACE_PATH
+            );
+            send_data_response(tls, b"{\"mounted\":true}");
+        }
+        Err(e) => send_error_response(tls, &format!("Failed to open encrypted workspace: {}", e)),
     }
+}
 
-    let response = SecretInjectionResponse { injected, errors };
-    let body = serde_json::to_vec(&response).unwrap_or_else(|_| b"{\"injected\":0}".to_vec());
-    send_data_response(tls, &body);
+/// Store one secret in [`SECRETS_DIR`] and, if requested (and it is UTF-8),
+/// in the environment of the deferred main.
+#[cfg(target_os = "linux")]
+pub(super) fn store_secret(name: &str, value: &[u8], set_env: bool) -> Result<(), String> {
+    // Validate name (alphanumeric, underscore, dash, dot only)
+    if !is_valid_secret_name(name) {
+        return Err(format!("Invalid secret name: {}", name));
+    }
+
+    // Write to /run/secrets/<name>
+    let path = format!("{}/{}", SECRETS_DIR, name);
+    std::fs::write(&path, value).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+
+    // Set restrictive permissions (owner read only)
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o400));
+    }
+
+    // Set environment variable if requested
+    if set_env {
+        let value = std::str::from_utf8(value)
+            .map_err(|_| format!("Secret {} is not UTF-8; cannot set it as env", name))?;
+        std::env::set_var(name, value);
+        crate::exec_server::add_provisioned_env(name, value);
+    }
+
+    info!("Secret injected: {}", name);
+    Ok(())
+}
+
+/// Create [`SECRETS_DIR`] and mount a tmpfs on it, unless one is already
+/// there. The rootfs may be a host-backed share, which secrets must never
+/// reach.
+#[cfg(target_os = "linux")]
+pub(super) fn ensure_secrets_tmpfs() -> std::io::Result<()> {
+    use nix::mount::{mount, MsFlags};
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::create_dir_all(SECRETS_DIR)?;
+    let parent = std::path::Path::new(SECRETS_DIR)
+        .parent()
+        .unwrap_or(std::path::Path::new("/"));
+    if std::fs::metadata(SECRETS_DIR)?.dev() != std::fs::metadata(parent)?.dev() {
+        return Ok(());
+    }
+    mount(
+        None::<&str>,
+        SECRETS_DIR,
+        Some("tmpfs"),
+        MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+        Some("mode=0711,size=16m"),
+    )
+    .map_err(std::io::Error::from)
 }
 
 /// Validate a secret name: alphanumeric, underscore, dash, dot only.
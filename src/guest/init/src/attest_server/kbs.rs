@@ -0,0 +1,360 @@
+//! Guest side of the KBS (Key Broker Service) protocol.
+//!
+//! The host relays HTTP between a confidential-containers KBS and the guest
+//! (see `a3s_box_runtime::tee::kbs`); nothing secret leaves the guest. For
+//! each challenge the guest generates a P-256 key, binds it and the nonce into
+//! a fresh SNP report, and answers with the public key as a JWK. The KBS
+//! encrypts the resource to that key (JWE with `ECDH-ES` and `A256GCM`), so
+//! only this guest can decrypt it.
+
+use base64::Engine;
+use sha2::{Digest, Sha256, Sha384};
+
+#[cfg(target_os = "linux")]
+use super::frame::{send_data_response, send_error_response};
+#[cfg(target_os = "linux")]
+use std::io::Write;
+
+/// Key agreement algorithm advertised in `tee-pubkey` and accepted in JWEs.
+const JWE_ALG: &str = "ECDH-ES";
+
+/// Content encryption accepted in JWEs.
+const JWE_ENC: &str = "A256GCM";
+
+const B64URL: base64::engine::GeneralPurpose = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+/// Private key of the last challenge answered. It decrypts exactly one
+/// resource, so every resource fetch starts with a fresh challenge.
+#[cfg(target_os = "linux")]
+static PENDING_KEY: std::sync::Mutex<Option<ring::agreement::EphemeralPrivateKey>> =
+    std::sync::Mutex::new(None);
+
+/// Handle a KBS challenge: generate a key and evidence binding it to the nonce.
+#[cfg(target_os = "linux")]
+pub(super) fn handle_kbs_evidence(payload: &serde_json::Value, tls: &mut impl Write) {
+    use a3s_box_core::tee::{KbsEvidence, KbsEvidenceRequest};
+    use ring::agreement::{EphemeralPrivateKey, ECDH_P256};
+
+    let req: KbsEvidenceRequest = match serde_json::from_value(payload.clone()) {
+        Ok(r) => r,
+        Err(e) => {
+            send_error_response(tls, &format!("Invalid KBS evidence payload: {}", e));
+            return;
+        }
+    };
+
+    let rng = ring::rand::SystemRandom::new();
+    let key = match EphemeralPrivateKey::generate(&ECDH_P256, &rng) {
+        Ok(key) => key,
+        Err(_) => {
+            send_error_response(tls, "Failed to generate KBS key");
+            return;
+        }
+    };
+    let tee_pubkey = match key
+        .compute_public_key()
+        .ok()
+        .and_then(|p| ec_jwk(p.as_ref()))
+    {
+        Some(jwk) => jwk,
+        None => {
+            send_error_response(tls, "Failed to encode KBS key");
+            return;
+        }
+    };
+
+    let report_data = runtime_data_report_data(&req.nonce, &tee_pubkey);
+    let evidence = if super::handlers::is_simulate_mode() {
+        serde_json::json!({
+            "attestation_report": base64::engine::general_purpose::STANDARD
+                .encode(super::handlers::build_simulated_report(&report_data)),
+            "cert_chain": {},
+        })
+    } else {
+        match super::snp::get_snp_report(&report_data) {
+            Ok(resp) => {
+                let b64 = |der: &[u8]| base64::engine::general_purpose::STANDARD.encode(der);
+                serde_json::json!({
+                    "attestation_report": b64(&resp.report),
+                    "cert_chain": {
+                        "vcek": b64(&resp.cert_chain.vcek),
+                        "ask": b64(&resp.cert_chain.ask),
+                        "ark": b64(&resp.cert_chain.ark),
+                    },
+                })
+            }
+            Err(e) => {
+                send_error_response(tls, &format!("Failed to get SNP report: {}", e));
+                return;
+            }
+        }
+    };
+
+    *PENDING_KEY.lock().unwrap_or_else(|e| e.into_inner()) = Some(key);
+    let response = KbsEvidence {
+        tee_pubkey,
+        tee_evidence: evidence.to_string(),
+    };
+    let body = serde_json::to_vec(&response).unwrap_or_default();
+    send_data_response(tls, &body);
+}
+
+/// Handle a KBS resource: decrypt it with the pending key and store it as a
+/// secret.
+#[cfg(target_os = "linux")]
+pub(super) fn handle_kbs_resource(payload: &serde_json::Value, tls: &mut impl Write) {
+    use a3s_box_core::tee::KbsResourceRequest;
+    use ring::agreement::{agree_ephemeral, UnparsedPublicKey, ECDH_P256};
+    use tracing::info;
+
+    let req: KbsResourceRequest = match serde_json::from_value(payload.clone()) {
+        Ok(r) => r,
+        Err(e) => {
+            send_error_response(tls, &format!("Invalid KBS resource payload: {}", e));
+            return;
+        }
+    };
+    let Some(key) = PENDING_KEY.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        send_error_response(tls, "No pending KBS challenge; request evidence first");
+        return;
+    };
+
+    let secret = match decrypt_jwe(&req.response, |peer| {
+        agree_ephemeral(key, &UnparsedPublicKey::new(&ECDH_P256, peer), |z| {
+            z.to_vec()
+        })
+        .map_err(|_| "ECDH key agreement failed".to_string())
+    }) {
+        Ok(secret) => secret,
+        Err(e) => {
+            send_error_response(tls, &e);
+            return;
+        }
+    };
+
+    if let Err(e) = super::handlers::ensure_secrets_tmpfs() {
+        send_error_response(tls, &format!("Failed to prepare secrets dir: {}", e));
+        return;
+    }
+    if let Err(e) = super::handlers::store_secret(&req.name, &secret, req.set_env) {
+        send_error_response(tls, &e);
+        return;
+    }
+    info!("KBS resource stored as secret {}", req.name);
+    send_data_response(tls, b"{\"injected\":1}");
+}
+
+/// REPORT_DATA for a KBS challenge: SHA-384 of the runtime data
+/// `{"nonce":...,"tee-pubkey":...}`, zero-padded to 64 bytes.
+fn runtime_data_report_data(nonce: &str, tee_pubkey: &serde_json::Value) -> [u8; 64] {
+    let runtime_data = serde_json::json!({ "nonce": nonce, "tee-pubkey": tee_pubkey });
+    let hash = Sha384::digest(runtime_data.to_string().as_bytes());
+    let mut report_data = [0u8; 64];
+    report_data[..hash.len()].copy_from_slice(&hash);
+    report_data
+}
+
+/// Encode an uncompressed P-256 point (`0x04 || X || Y`) as a JWK.
+fn ec_jwk(point: &[u8]) -> Option<serde_json::Value> {
+    if point.len() != 65 || point[0] != 0x04 {
+        return None;
+    }
+    Some(serde_json::json!({
+        "alg": JWE_ALG,
+        "crv": "P-256",
+        "kty": "EC",
+        "x": B64URL.encode(&point[1..33]),
+        "y": B64URL.encode(&point[33..]),
+    }))
+}
+
+/// JWE in flattened JSON serialization, as returned by the KBS.
+#[derive(serde::Deserialize)]
+struct Jwe {
+    protected: String,
+    #[serde(default)]
+    encrypted_key: String,
+    iv: String,
+    ciphertext: String,
+    tag: String,
+}
+
+#[derive(serde::Deserialize)]
+struct JweHeader {
+    alg: String,
+    enc: String,
+    epk: Option<EcJwk>,
+    #[serde(default)]
+    apu: String,
+    #[serde(default)]
+    apv: String,
+}
+
+#[derive(serde::Deserialize)]
+struct EcJwk {
+    crv: String,
+    x: String,
+    y: String,
+}
+
+fn b64url_decode(field: &str, value: &str) -> Result<Vec<u8>, String> {
+    B64URL
+        .decode(value.trim_end_matches('='))
+        .map_err(|e| format!("Invalid JWE {}: {}", field, e))
+}
+
+/// Decrypt a KBS resource JWE. `agree` performs ECDH with the sender's
+/// ephemeral public key (an uncompressed P-256 point) and returns the shared
+/// secret.
+fn decrypt_jwe(
+    response: &serde_json::Value,
+    agree: impl FnOnce(&[u8]) -> Result<Vec<u8>, String>,
+) -> Result<Vec<u8>, String> {
+    use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+
+    let jwe: Jwe = serde_json::from_value(response.clone())
+        .map_err(|e| format!("Invalid KBS resource response: {}", e))?;
+    let header: JweHeader =
+        serde_json::from_slice(&b64url_decode("protected header", &jwe.protected)?)
+            .map_err(|e| format!("Invalid JWE protected header: {}", e))?;
+    if header.alg != JWE_ALG || header.enc != JWE_ENC {
+        return Err(format!(
+            "Unsupported KBS resource encryption {}/{} (expected {}/{})",
+            header.alg, header.enc, JWE_ALG, JWE_ENC
+        ));
+    }
+    if !jwe.encrypted_key.is_empty() {
+        return Err("JWE with direct key agreement must not carry an encrypted key".to_string());
+    }
+    let epk = header
+        .epk
+        .ok_or_else(|| "JWE is missing its ephemeral public key".to_string())?;
+    let x = b64url_decode("epk.x", &epk.x)?;
+    let y = b64url_decode("epk.y", &epk.y)?;
+    if epk.crv != "P-256" || x.len() != 32 || y.len() != 32 {
+        return Err(format!("Unsupported JWE ephemeral key on {}", epk.crv));
+    }
+    let mut peer = Vec::with_capacity(65);
+    peer.push(0x04);
+    peer.extend_from_slice(&x);
+    peer.extend_from_slice(&y);
+
+    let z = agree(&peer)?;
+    let cek = concat_kdf(
+        &z,
+        JWE_ENC,
+        &b64url_decode("apu", &header.apu)?,
+        &b64url_decode("apv", &header.apv)?,
+    );
+
+    let iv = b64url_decode("iv", &jwe.iv)?;
+    let nonce =
+        Nonce::try_assume_unique_for_key(&iv).map_err(|_| "JWE iv must be 12 bytes".to_string())?;
+    let mut in_out = b64url_decode("ciphertext", &jwe.ciphertext)?;
+    in_out.extend_from_slice(&b64url_decode("tag", &jwe.tag)?);
+    let key = UnboundKey::new(&AES_256_GCM, &cek)
+        .map(LessSafeKey::new)
+        .map_err(|_| "Invalid JWE content key".to_string())?;
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(jwe.protected.as_bytes()), &mut in_out)
+        .map_err(|_| "KBS resource decryption failed".to_string())?;
+    Ok(plaintext.to_vec())
+}
+
+/// Single-round Concat KDF deriving the A256GCM content key for ECDH-ES in
+/// direct key agreement mode (RFC 7518, section 4.6.2).
+fn concat_kdf(z: &[u8], enc: &str, apu: &[u8], apv: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(z);
+    for field in [enc.as_bytes(), apu, apv] {
+        hasher.update((field.len() as u32).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.update(256u32.to_be_bytes());
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+    use ring::agreement::{agree_ephemeral, EphemeralPrivateKey, UnparsedPublicKey, ECDH_P256};
+
+    /// Encrypt `plaintext` to `recipient` the way a KBS does.
+    fn seal_jwe(recipient: &[u8], plaintext: &[u8]) -> serde_json::Value {
+        let rng = ring::rand::SystemRandom::new();
+        let sender = EphemeralPrivateKey::generate(&ECDH_P256, &rng).unwrap();
+        let epk = ec_jwk(sender.compute_public_key().unwrap().as_ref()).unwrap();
+        let z = agree_ephemeral(
+            sender,
+            &UnparsedPublicKey::new(&ECDH_P256, recipient),
+            |z| z.to_vec(),
+        )
+        .unwrap();
+        let cek = concat_kdf(&z, JWE_ENC, b"", b"");
+
+        let protected = B64URL
+            .encode(serde_json::json!({ "alg": JWE_ALG, "enc": JWE_ENC, "epk": epk }).to_string());
+        let iv = [7u8; 12];
+        let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &cek).unwrap());
+        let mut in_out = plaintext.to_vec();
+        let tag = key
+            .seal_in_place_separate_tag(
+                Nonce::assume_unique_for_key(iv),
+                Aad::from(protected.as_bytes()),
+                &mut in_out,
+            )
+            .unwrap();
+        serde_json::json!({
+            "protected": protected,
+            "encrypted_key": "",
+            "iv": B64URL.encode(iv),
+            "ciphertext": B64URL.encode(&in_out),
+            "tag": B64URL.encode(tag.as_ref()),
+        })
+    }
+
+    #[test]
+    fn test_decrypt_jwe_round_trip() {
+        let rng = ring::rand::SystemRandom::new();
+        let key = EphemeralPrivateKey::generate(&ECDH_P256, &rng).unwrap();
+        let public = key.compute_public_key().unwrap();
+        let jwe = seal_jwe(public.as_ref(), b"model-api-key");
+
+        let secret = decrypt_jwe(&jwe, |peer| {
+            agree_ephemeral(key, &UnparsedPublicKey::new(&ECDH_P256, peer), |z| {
+                z.to_vec()
+            })
+            .map_err(|_| "agreement failed".to_string())
+        })
+        .unwrap();
+        assert_eq!(secret, b"model-api-key");
+    }
+
+    #[test]
+    fn test_decrypt_jwe_rejects_key_wrapping() {
+        let protected = B64URL.encode(r#"{"alg":"RSA1_5","enc":"A256GCM"}"#.as_bytes());
+        let jwe = serde_json::json!({
+            "protected": protected,
+            "encrypted_key": "AAAA",
+            "iv": "",
+            "ciphertext": "",
+            "tag": "",
+        });
+        let error = decrypt_jwe(&jwe, |_| unreachable!()).unwrap_err();
+        assert!(error.contains("RSA1_5"), "{error}");
+    }
+
+    #[test]
+    fn test_runtime_data_report_data_binds_nonce_and_key() {
+        let jwk = ec_jwk(&[4u8; 65]).unwrap();
+        let report_data = runtime_data_report_data("nonce-1", &jwk);
+
+        let expected =
+            Sha384::digest(format!(r#"{{"nonce":"nonce-1","tee-pubkey":{}}}"#, jwk).as_bytes());
+        assert_eq!(&report_data[..48], expected.as_slice());
+        assert_eq!(&report_data[48..], &[0u8; 16]);
+        assert_ne!(report_data, runtime_data_report_data("nonce-2", &jwk));
+    }
+}
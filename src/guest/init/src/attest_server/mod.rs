@@ -8,11 +8,15 @@
 //! ## Protocol
 //!
 //! 1. Server generates a P-384 key pair on startup
-//! 2. Server obtains an SNP report with SHA-384(public_key) as report_data
+//! 2. Server obtains an SNP report with SHA-256(public_key) as report_data,
+//!    followed by the host-supplied runtime-claims digest, if any
 //! 3. Server creates a self-signed X.509 cert embedding the report
 //! 4. Client connects, TLS handshake delivers the cert
 //! 5. Client's custom verifier extracts and verifies the SNP report
 //! 6. After handshake, client sends a simple request, server responds with status
+//!
+//! A verifier that wants proof of freshness sends a `challenge` request with
+//! its own nonce; the server answers with a new report carrying that nonce.
 
 use tracing::info;
 
@@ -20,6 +24,8 @@ use tracing::info;
 mod frame;
 #[cfg(any(target_os = "linux", test))]
 mod handlers;
+#[cfg(any(target_os = "linux", test))]
+mod kbs;
 #[cfg(target_os = "linux")]
 mod snp;
 
@@ -33,6 +39,23 @@ pub const ATTEST_VSOCK_PORT: u32 = a3s_transport::ports::TEE_CHANNEL;
 #[cfg(any(target_os = "linux", test))]
 pub(super) const SNP_USER_DATA_SIZE: usize = 64;
 
+/// Runtime-claims digest the host passed at launch, if any, with the root
+/// hash of the measured rootfs guest init booted from folded in.
+#[cfg(target_os = "linux")]
+fn runtime_claims_digest() -> Option<[u8; 32]> {
+    let digest = std::env::var(a3s_box_core::tee::RUNTIME_CLAIMS_ENV)
+        .ok()
+        .and_then(|value| a3s_box_core::tee::decode_runtime_claims_digest(&value))?;
+    // Boot fails unless the measured rootfs named here was opened.
+    match crate::verity::from_env() {
+        Ok(Some(rootfs)) => Some(a3s_box_core::tee::bind_verity_root_hash(
+            &digest,
+            &rootfs.root_hash,
+        )),
+        _ => Some(digest),
+    }
+}
+
 /// OID for the SNP attestation report extension.
 #[cfg(any(target_os = "linux", test))]
 const OID_SNP_REPORT: &[u64] = &[1, 3, 6, 1, 4, 1, 58270, 1, 1];
@@ -172,6 +195,13 @@ fn generate_ratls_config(
     let copy_len = hash.len().min(SNP_USER_DATA_SIZE);
     report_data[..copy_len].copy_from_slice(&hash[..copy_len]);
 
+    // Bind the runtime claims (image digest + launch config) the host passed
+    // at launch into the other half of report_data.
+    if let Some(digest) = runtime_claims_digest() {
+        report_data[a3s_box_core::tee::RUNTIME_CLAIMS_REPORT_DATA_RANGE].copy_from_slice(&digest);
+        info!("Binding runtime claims into attestation report");
+    }
+
     // Get attestation report
     let (report_bytes, cert_chain_json) = if handlers::is_simulate_mode() {
         info!("Generating simulated RA-TLS attestation report");
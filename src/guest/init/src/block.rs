@@ -0,0 +1,224 @@
+//! Block devices the host attaches for guest init itself: virtio-blk disk
+//! lookup by serial and single-target device-mapper devices.
+//!
+//! Guest images carry no `dmsetup`, so device-mapper devices are created
+//! with raw ioctls on `/dev/mapper/control`.
+
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+
+const DM_CONTROL: &str = "/dev/mapper/control";
+const DM_CONTROL_MAJOR: u32 = 10;
+const DM_CONTROL_MINOR: u32 = 236;
+
+const DM_VERSION: [u32; 3] = [4, 0, 0];
+const DM_NAME_LEN: usize = 128;
+const DM_UUID_LEN: usize = 129;
+const DM_MAX_TYPE_NAME: usize = 16;
+const DM_READONLY_FLAG: u32 = 1 << 0;
+
+const DM_DEV_CREATE: u8 = 3;
+const DM_DEV_SUSPEND: u8 = 6;
+const DM_TABLE_LOAD: u8 = 9;
+
+/// `struct dm_ioctl` from `<linux/dm-ioctl.h>`. Only the kernel reads most
+/// fields.
+#[allow(dead_code)]
+#[repr(C)]
+struct DmIoctl {
+    version: [u32; 3],
+    data_size: u32,
+    data_start: u32,
+    target_count: u32,
+    open_count: i32,
+    flags: u32,
+    event_nr: u32,
+    padding: u32,
+    dev: u64,
+    name: [u8; DM_NAME_LEN],
+    uuid: [u8; DM_UUID_LEN],
+    data: [u8; 7],
+}
+
+/// `struct dm_target_spec` from `<linux/dm-ioctl.h>`.
+#[allow(dead_code)]
+#[repr(C)]
+struct DmTargetSpec {
+    sector_start: u64,
+    length: u64,
+    status: i32,
+    next: u32,
+    target_type: [u8; DM_MAX_TYPE_NAME],
+}
+
+/// One device-mapper target covering a whole device.
+pub struct DmTarget<'a> {
+    /// Target type, e.g. `verity` or `crypt`.
+    pub target_type: &'a str,
+    /// Device length in 512-byte sectors.
+    pub length_sectors: u64,
+    /// Target parameters (the table line after the type).
+    pub params: &'a str,
+}
+
+/// Find the `/dev` node of the virtio-blk disk whose serial is `block_id`.
+pub fn find_block_device(block_id: &str) -> Option<String> {
+    std::fs::read_dir("/sys/block")
+        .ok()?
+        .flatten()
+        .find(|entry| {
+            std::fs::read_to_string(entry.path().join("serial"))
+                .is_ok_and(|serial| serial.trim_end_matches(['\0', '\n']) == block_id)
+        })
+        .map(|entry| format!("/dev/{}", entry.file_name().to_string_lossy()))
+}
+
+/// Size of a block device in 512-byte sectors, from sysfs.
+pub fn device_sectors(device: &str) -> io::Result<u64> {
+    let name = device.rsplit('/').next().unwrap_or(device);
+    std::fs::read_to_string(format!("/sys/class/block/{name}/size"))?
+        .trim()
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{device}: {e}")))
+}
+
+/// Create device-mapper device `name` with a single `target`, activate it,
+/// and return its `/dev/mapper` path.
+pub fn create_device(name: &str, target: &DmTarget<'_>, read_only: bool) -> io::Result<String> {
+    if name.is_empty() || name.len() >= DM_NAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid device-mapper name '{name}'"),
+        ));
+    }
+    if target.target_type.len() >= DM_MAX_TYPE_NAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid device-mapper target '{}'", target.target_type),
+        ));
+    }
+
+    let control = open_control()?;
+    let flags = if read_only { DM_READONLY_FLAG } else { 0 };
+    let created = dm_ioctl(&control, name, DM_DEV_CREATE, flags, None)?;
+    dm_ioctl(&control, name, DM_TABLE_LOAD, flags, Some(target))?;
+    // DM_DEV_SUSPEND without DM_SUSPEND_FLAG resumes the device, making the
+    // loaded table live.
+    dm_ioctl(&control, name, DM_DEV_SUSPEND, 0, None)?;
+
+    let path = format!("/dev/mapper/{name}");
+    if std::fs::metadata(&path).is_err() {
+        // devtmpfs names the node dm-N; give it the conventional name.
+        nix::sys::stat::mknod(
+            path.as_str(),
+            nix::sys::stat::SFlag::S_IFBLK,
+            nix::sys::stat::Mode::from_bits_truncate(0o600),
+            created.dev as libc::dev_t,
+        )
+        .map_err(io::Error::other)?;
+    }
+    Ok(path)
+}
+
+fn open_control() -> io::Result<File> {
+    if std::fs::metadata(DM_CONTROL).is_err() {
+        std::fs::create_dir_all("/dev/mapper")?;
+        nix::sys::stat::mknod(
+            DM_CONTROL,
+            nix::sys::stat::SFlag::S_IFCHR,
+            nix::sys::stat::Mode::from_bits_truncate(0o600),
+            libc::makedev(DM_CONTROL_MAJOR, DM_CONTROL_MINOR),
+        )
+        .map_err(io::Error::other)?;
+    }
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(DM_CONTROL)
+}
+
+/// Issue one device-mapper ioctl on `name`, optionally carrying a single
+/// target, and return the kernel's header.
+fn dm_ioctl(
+    control: &File,
+    name: &str,
+    command: u8,
+    flags: u32,
+    target: Option<&DmTarget<'_>>,
+) -> io::Result<DmIoctl> {
+    let header_size = std::mem::size_of::<DmIoctl>();
+    let spec_size = std::mem::size_of::<DmTargetSpec>();
+    let params = target.map(|t| t.params.as_bytes()).unwrap_or_default();
+    // Parameters are NUL-terminated and the buffer is padded to 8 bytes.
+    let size = (header_size + spec_size + params.len() + 1).next_multiple_of(8);
+    let mut buffer = vec![0u8; size.max(16 * 1024)];
+
+    let mut header = DmIoctl {
+        version: DM_VERSION,
+        data_size: buffer.len() as u32,
+        data_start: header_size as u32,
+        target_count: 0,
+        open_count: 0,
+        flags,
+        event_nr: 0,
+        padding: 0,
+        dev: 0,
+        name: [0; DM_NAME_LEN],
+        uuid: [0; DM_UUID_LEN],
+        data: [0; 7],
+    };
+    header.name[..name.len()].copy_from_slice(name.as_bytes());
+
+    if let Some(target) = target {
+        header.target_count = 1;
+        let mut spec = DmTargetSpec {
+            sector_start: 0,
+            length: target.length_sectors,
+            status: 0,
+            next: 0,
+            target_type: [0; DM_MAX_TYPE_NAME],
+        };
+        spec.target_type[..target.target_type.len()].copy_from_slice(target.target_type.as_bytes());
+        // SAFETY: the buffer holds header + spec + params (checked above) and
+        // both structs are plain `repr(C)` data.
+        unsafe {
+            std::ptr::write_unaligned(
+                buffer.as_mut_ptr().add(header_size).cast::<DmTargetSpec>(),
+                spec,
+            );
+        }
+        buffer[header_size + spec_size..header_size + spec_size + params.len()]
+            .copy_from_slice(params);
+    }
+    // SAFETY: see above.
+    unsafe { std::ptr::write_unaligned(buffer.as_mut_ptr().cast::<DmIoctl>(), header) };
+
+    // _IOWR(DM_IOCTL, command, struct dm_ioctl)
+    let request = (3u64 << 30) | ((header_size as u64) << 16) | (0xfd << 8) | command as u64;
+    // SAFETY: `buffer` is at least `data_size` bytes and outlives the call.
+    let rc = unsafe { libc::ioctl(control.as_raw_fd(), request as _, buffer.as_mut_ptr()) };
+    let error = io::Error::last_os_error();
+    // SAFETY: the kernel wrote a `dm_ioctl` header back into the buffer.
+    let header = unsafe { std::ptr::read_unaligned(buffer.as_ptr().cast::<DmIoctl>()) };
+    // Table parameters can carry key material (dm-crypt); clear them.
+    buffer.fill(0);
+    if rc != 0 {
+        return Err(io::Error::new(
+            error.kind(),
+            format!("device-mapper ioctl {command} failed: {error}"),
+        ));
+    }
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dm_structs_match_the_kernel_abi() {
+        assert_eq!(std::mem::size_of::<DmIoctl>(), 312);
+        assert_eq!(std::mem::size_of::<DmTargetSpec>(), 40);
+    }
+}
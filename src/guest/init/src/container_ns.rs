@@ -0,0 +1,329 @@
+//! Per-container PID and mount namespaces inside a pod VM.
+//!
+//! Every container in a pod chroots into its own rootfs in the one VM, and
+//! shares the VM's network (so `localhost` reaches its siblings), IPC and
+//! UTS namespaces. With CRI `pid: CONTAINER` the runtime also asks for a
+//! private process tree: `A3S_SEC_PID_NS=private` gives the container's
+//! main process new PID and mount namespaces, and it sees only its own
+//! processes through a `/proc` of its own. `A3S_SEC_PID_NS=target:<rootfs>`
+//! (CRI `TARGET`, used by ephemeral debug containers) enters the PID
+//! namespace of the container running in `<rootfs>` instead.
+//!
+//! Any later process for a rootfs whose container holds private namespaces
+//! (exec, attach, probes) joins them, whatever its env says. Containers
+//! without private namespaces keep the VM-wide ones.
+//!
+//! A process only enters a new PID namespace through `fork`, so the spawned
+//! process forks again after entering. The child goes on to exec the
+//! workload; the parent waits for it, forwards termination signals, and
+//! exits the same way it did.
+
+use std::ffi::CString;
+use std::fs::File;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+
+/// Env key selecting a container's PID namespace.
+pub(crate) const PID_NS_ENV: &str = "A3S_SEC_PID_NS";
+
+/// The PID namespace a spawn request asked for.
+#[derive(Debug, PartialEq, Eq)]
+enum PidNamespaceRequest {
+    /// The VM-wide namespace.
+    Shared,
+    /// A new namespace for this container.
+    Private,
+    /// The namespace of the container running in this rootfs.
+    Target(String),
+}
+
+fn pid_namespace_request(env: &[String]) -> PidNamespaceRequest {
+    let prefix = format!("{PID_NS_ENV}=");
+    match env.iter().find_map(|entry| entry.strip_prefix(&prefix)) {
+        Some("private") => PidNamespaceRequest::Private,
+        Some(value) => match value.strip_prefix("target:") {
+            Some(rootfs) if rootfs.starts_with('/') => {
+                PidNamespaceRequest::Target(rootfs.to_string())
+            }
+            _ => PidNamespaceRequest::Shared,
+        },
+        None => PidNamespaceRequest::Shared,
+    }
+}
+
+/// Namespaces a container process enters before it execs, opened before
+/// the fork so entering them is async-signal-safe.
+#[derive(Debug)]
+pub(crate) enum ContainerNamespaces {
+    /// Stay in the VM-wide namespaces.
+    Shared,
+    /// New PID and mount namespaces, with `/proc` mounted at `proc_target`.
+    Create { proc_target: CString },
+    /// The PID and mount namespaces of a running container.
+    Join { mnt: File, pid: File },
+    /// A running container's PID namespace, with a mount namespace of our
+    /// own and `/proc` mounted at `proc_target`.
+    JoinPid { pid: File, proc_target: CString },
+}
+
+impl ContainerNamespaces {
+    /// Work out the namespaces for a process spawned into `rootfs` with
+    /// request `env`. Failures fall back to the VM-wide namespaces.
+    pub(crate) fn resolve(env: &[String], rootfs: Option<&str>) -> Self {
+        let Some(rootfs) = rootfs else {
+            return Self::Shared;
+        };
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(pid) = namespaced_process(Path::new(rootfs)) {
+                return match open_namespaces(pid) {
+                    Ok((mnt, pid)) => Self::Join { mnt, pid },
+                    Err(error) => {
+                        tracing::warn!(rootfs, pid, %error, "Failed to open container namespaces");
+                        Self::Shared
+                    }
+                };
+            }
+            let proc_target =
+                || CString::new(format!("{}/proc", rootfs.trim_end_matches('/'))).ok();
+            match pid_namespace_request(env) {
+                PidNamespaceRequest::Shared => Self::Shared,
+                PidNamespaceRequest::Private => match proc_target() {
+                    Some(proc_target) => Self::Create { proc_target },
+                    None => Self::Shared,
+                },
+                PidNamespaceRequest::Target(target) => {
+                    let pid = namespaced_process(Path::new(&target))
+                        .and_then(|pid| File::open(format!("/proc/{pid}/ns/pid")).ok());
+                    match (pid, proc_target()) {
+                        (Some(pid), Some(proc_target)) => Self::JoinPid { pid, proc_target },
+                        _ => {
+                            tracing::warn!(
+                                rootfs,
+                                target = %target,
+                                "Target container has no PID namespace to join"
+                            );
+                            Self::Shared
+                        }
+                    }
+                }
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = pid_namespace_request(env);
+            Self::Shared
+        }
+    }
+
+    /// Enter the namespaces and fork the process that goes on to exec.
+    /// Returns only in that child; the parent stays behind to wait for it.
+    ///
+    /// # Safety
+    ///
+    /// Must be called in a freshly forked, single-threaded child (a
+    /// `pre_exec` hook or the PTY child). Uses only async-signal-safe calls.
+    #[cfg(target_os = "linux")]
+    pub(crate) unsafe fn enter(&self) -> std::io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let proc_target = match self {
+            Self::Shared => return Ok(()),
+            Self::Create { proc_target } => {
+                check(libc::unshare(libc::CLONE_NEWNS | libc::CLONE_NEWPID))?;
+                make_mounts_slave()?;
+                Some(proc_target)
+            }
+            Self::Join { mnt, pid } => {
+                check(libc::setns(mnt.as_raw_fd(), libc::CLONE_NEWNS))?;
+                check(libc::setns(pid.as_raw_fd(), libc::CLONE_NEWPID))?;
+                None
+            }
+            Self::JoinPid { pid, proc_target } => {
+                check(libc::setns(pid.as_raw_fd(), libc::CLONE_NEWPID))?;
+                check(libc::unshare(libc::CLONE_NEWNS))?;
+                make_mounts_slave()?;
+                Some(proc_target)
+            }
+        };
+        match libc::fork() {
+            -1 => return Err(std::io::Error::last_os_error()),
+            0 => {
+                // Do not outlive the supervisor if it is SIGKILLed.
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+            }
+            child => supervise(child),
+        }
+        if let Some(proc_target) = proc_target {
+            check(libc::mount(
+                c"proc".as_ptr(),
+                proc_target.as_ptr(),
+                c"proc".as_ptr(),
+                libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+                std::ptr::null(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Namespaces are Linux-only; elsewhere every process shares them.
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) unsafe fn enter(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn check(ret: libc::c_int) -> std::io::Result<()> {
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Keep receiving the VM's mounts without leaking ours back to it.
+#[cfg(target_os = "linux")]
+unsafe fn make_mounts_slave() -> std::io::Result<()> {
+    check(libc::mount(
+        std::ptr::null(),
+        c"/".as_ptr(),
+        std::ptr::null(),
+        libc::MS_REC | libc::MS_SLAVE,
+        std::ptr::null(),
+    ))
+}
+
+/// A process whose root is `rootfs` and which runs in a PID namespace other
+/// than the VM's: a member of that container's private namespaces.
+#[cfg(target_os = "linux")]
+fn namespaced_process(rootfs: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    let root = std::fs::metadata(rootfs).ok()?;
+    let vm_pid_ns = std::fs::read_link("/proc/self/ns/pid").ok()?;
+    std::fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .find_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let proc_dir = entry.path();
+            // Following `root` reaches the process's root directory even when it
+            // sits in another mount namespace.
+            let its_root = std::fs::metadata(proc_dir.join("root")).ok()?;
+            if (its_root.dev(), its_root.ino()) != (root.dev(), root.ino()) {
+                return None;
+            }
+            let pid_ns = std::fs::read_link(proc_dir.join("ns/pid")).ok()?;
+            (pid_ns != vm_pid_ns).then_some(pid)
+        })
+}
+
+#[cfg(target_os = "linux")]
+fn open_namespaces(pid: u32) -> std::io::Result<(File, File)> {
+    Ok((
+        File::open(format!("/proc/{pid}/ns/mnt"))?,
+        File::open(format!("/proc/{pid}/ns/pid"))?,
+    ))
+}
+
+/// The child to forward signals to; set once in the supervising process.
+#[cfg(target_os = "linux")]
+static SUPERVISED_CHILD: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[cfg(target_os = "linux")]
+extern "C" fn forward_signal(signal: libc::c_int) {
+    let child = SUPERVISED_CHILD.load(std::sync::atomic::Ordering::Relaxed);
+    if child > 0 {
+        // SAFETY: kill is async-signal-safe.
+        unsafe {
+            libc::kill(child, signal);
+        }
+    }
+}
+
+/// Wait for the namespaced child and exit as it did: with its status, or by
+/// its signal so the exec server still sees an OOM kill as `SIGKILL`.
+#[cfg(target_os = "linux")]
+unsafe fn supervise(child: libc::pid_t) -> ! {
+    SUPERVISED_CHILD.store(child, std::sync::atomic::Ordering::Relaxed);
+    for signal in [
+        libc::SIGTERM,
+        libc::SIGINT,
+        libc::SIGHUP,
+        libc::SIGQUIT,
+        libc::SIGUSR1,
+        libc::SIGUSR2,
+        libc::SIGWINCH,
+    ] {
+        libc::signal(signal, forward_signal as libc::sighandler_t);
+    }
+    // Drop every inherited descriptor, including the spawner's exec-status
+    // pipe, so the spawner waits on the child's exec rather than on us and
+    // output pipes reach EOF when the child is done.
+    if libc::syscall(libc::SYS_close_range, 0u32, u32::MAX, 0u32) != 0 {
+        for fd in 0..1024 {
+            libc::close(fd);
+        }
+    }
+    let mut status = 0;
+    while libc::waitpid(child, &mut status, 0) != child {
+        if std::io::Error::last_os_error().raw_os_error() != Some(libc::EINTR) {
+            libc::_exit(127);
+        }
+    }
+    if libc::WIFSIGNALED(status) {
+        let signal = libc::WTERMSIG(status);
+        libc::signal(signal, libc::SIG_DFL);
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, signal);
+        libc::sigprocmask(libc::SIG_UNBLOCK, &set, std::ptr::null_mut());
+        libc::kill(libc::getpid(), signal);
+        libc::_exit(128 + signal);
+    }
+    libc::_exit(libc::WEXITSTATUS(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn test_pid_namespace_request() {
+        assert_eq!(
+            pid_namespace_request(&env(&["PATH=/bin"])),
+            PidNamespaceRequest::Shared
+        );
+        assert_eq!(
+            pid_namespace_request(&env(&["A3S_SEC_PID_NS=private"])),
+            PidNamespaceRequest::Private
+        );
+        assert_eq!(
+            pid_namespace_request(&env(&[
+                "A3S_SEC_PID_NS=target:/run/a3s/cri/container-rootfs/c1"
+            ])),
+            PidNamespaceRequest::Target("/run/a3s/cri/container-rootfs/c1".to_string())
+        );
+        assert_eq!(
+            pid_namespace_request(&env(&["A3S_SEC_PID_NS=target:relative"])),
+            PidNamespaceRequest::Shared
+        );
+        assert_eq!(
+            pid_namespace_request(&env(&["A3S_SEC_PID_NS=bogus"])),
+            PidNamespaceRequest::Shared
+        );
+    }
+
+    #[test]
+    fn test_resolve_without_rootfs_is_shared() {
+        assert!(matches!(
+            ContainerNamespaces::resolve(&env(&["A3S_SEC_PID_NS=private"]), None),
+            ContainerNamespaces::Shared
+        ));
+    }
+}
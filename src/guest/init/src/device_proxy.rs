@@ -0,0 +1,147 @@
+//! Guest end of proxied `--device` passthrough.
+//!
+//! The host passes each proxied device's container path in
+//! `BOX_DEVICE_PROXY_<n>`. Guest init allocates a raw pseudo-terminal for it,
+//! links the path to the terminal, and relays the terminal's bytes over vsock
+//! port `device_proxy_vsock_port(n)`, which the shim connects to the host
+//! device. The workload opens the path like the device itself; line settings
+//! it applies stay on the pseudo-terminal.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::Duration;
+
+use a3s_box_core::device::{device_proxy_vsock_port, ProxiedDevice, DEVICE_PROXY_ENV_PREFIX};
+use nix::pty::openpty;
+use nix::sys::termios::{cfmakeraw, tcgetattr, tcsetattr, SetArg};
+use tracing::{debug, info};
+
+use crate::socket_share::connect_host;
+
+/// Pause before dialing the host again after the device stream ends.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// A pseudo-terminal standing in for a host device.
+pub struct ProxiedNode {
+    master: File,
+    /// Held open so reads on the master never fail with `EIO` while no
+    /// workload has the device open.
+    _slave: OwnedFd,
+    target: String,
+    vsock_port: u32,
+}
+
+/// Create the stand-in node for every proxied device the host configured.
+///
+/// Runs while the rootfs is still writable, because it creates the link at
+/// each container path. An existing node at the path is replaced.
+pub fn setup_from_env() -> io::Result<Vec<ProxiedNode>> {
+    let mut nodes = Vec::new();
+    while let Ok(value) = std::env::var(format!("{DEVICE_PROXY_ENV_PREFIX}{}", nodes.len())) {
+        let (target, read_only) = ProxiedDevice::parse_env_value(&value)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        let vsock_port = device_proxy_vsock_port(nodes.len());
+        let node = create_node(&target, read_only, vsock_port).map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!("failed to proxy device at {target}: {error}"),
+            )
+        })?;
+        info!(target = %target, vsock_port, read_only, "Proxied host device");
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+fn create_node(target: &str, read_only: bool, vsock_port: u32) -> io::Result<ProxiedNode> {
+    let pty = openpty(None, None).map_err(io::Error::from)?;
+    let mut termios = tcgetattr(&pty.slave).map_err(io::Error::from)?;
+    cfmakeraw(&mut termios);
+    tcsetattr(&pty.slave, SetArg::TCSANOW, &termios).map_err(io::Error::from)?;
+
+    let terminal = std::fs::read_link(format!("/proc/self/fd/{}", pty.slave.as_raw_fd()))?;
+    let mode = if read_only { 0o444 } else { 0o666 };
+    std::fs::set_permissions(&terminal, std::fs::Permissions::from_mode(mode))?;
+    link(Path::new(target), &terminal)?;
+
+    Ok(ProxiedNode {
+        master: File::from(pty.master),
+        _slave: pty.slave,
+        target: target.to_string(),
+        vsock_port,
+    })
+}
+
+fn link(target: &Path, terminal: &Path) -> io::Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::symlink_metadata(target) {
+        Ok(metadata) if metadata.is_dir() => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "path exists and is a directory",
+            ))
+        }
+        Ok(_) => std::fs::remove_file(target)?,
+        Err(_) => {}
+    }
+    std::os::unix::fs::symlink(terminal, target)
+}
+
+/// Relay the node to the host device for the VM's lifetime, redialing
+/// whenever the stream ends.
+pub fn serve(node: ProxiedNode) {
+    loop {
+        if let Err(error) = relay(&node) {
+            debug!(%error, target = %node.target, "Proxied device stream ended");
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+/// Shuttle bytes between the terminal and one host stream until the host
+/// closes it. Polling both ends lets a closed stream end the relay without
+/// waiting for the workload's next write.
+fn relay(node: &ProxiedNode) -> io::Result<()> {
+    let mut host = connect_host(node.vsock_port)?;
+    let mut master = &node.master;
+    let mut buf = [0u8; 4096];
+    loop {
+        let mut fds = [
+            libc::pollfd {
+                fd: master.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: host.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        // SAFETY: `fds` is a valid array of two pollfd entries.
+        if unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) } < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+
+        if fds[1].revents & (libc::POLLIN | libc::POLLHUP | libc::POLLERR) != 0 {
+            let n = host.read(&mut buf)?;
+            if n == 0 {
+                return Ok(());
+            }
+            master.write_all(&buf[..n])?;
+        }
+        if fds[0].revents & libc::POLLIN != 0 {
+            let n = master.read(&mut buf)?;
+            host.write_all(&buf[..n])?;
+        }
+    }
+}
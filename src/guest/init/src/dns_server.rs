@@ -0,0 +1,558 @@
+//! Embedded DNS resolver for bridge-networked boxes.
+//!
+//! Listens on `127.0.0.11:53` (UDP and TCP) and answers queries for box names
+//! and aliases from the records file the host keeps current on network
+//! connect/disconnect. Every other query is forwarded unchanged to the
+//! upstream servers. The records file is re-read whenever its modification
+//! time changes, so peers that join after boot resolve without a restart.
+//!
+//! With an egress policy, names the policy blocks get NXDOMAIN, and the
+//! addresses returned for explicitly allowed names are added to the guest
+//! firewall's allow sets before the answer reaches the client.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[cfg(target_os = "linux")]
+use a3s_box_core::egress::EgressPolicy;
+#[cfg(target_os = "linux")]
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use std::sync::{Arc, Mutex};
+#[cfg(target_os = "linux")]
+use std::time::{Duration, SystemTime};
+
+#[cfg(target_os = "linux")]
+use tracing::{debug, info, warn};
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const RCODE_NXDOMAIN: u8 = 3;
+const CLASS_IN: u16 = 1;
+/// Short TTL so a peer that moves is picked up quickly by caching resolvers.
+const RECORD_TTL: u32 = 10;
+#[cfg(target_os = "linux")]
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The single question of a standard DNS query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsQuestion {
+    /// Queried name, lowercase and without the trailing dot.
+    pub name: String,
+    /// Query type (1 = A, 28 = AAAA, ...).
+    pub qtype: u16,
+    /// Query class (1 = IN).
+    pub qclass: u16,
+    /// Offset just past the question section.
+    end: usize,
+}
+
+/// Parse a standard query carrying exactly one question.
+///
+/// Returns `None` for responses, other opcodes, multi-question messages, and
+/// malformed packets; those are forwarded untouched.
+pub fn parse_query(packet: &[u8]) -> Option<DnsQuestion> {
+    if packet.len() < 12 {
+        return None;
+    }
+    let flags = u16::from_be_bytes([packet[2], packet[3]]);
+    let is_response = flags & 0x8000 != 0;
+    let opcode = (flags >> 11) & 0xf;
+    let qdcount = u16::from_be_bytes([packet[4], packet[5]]);
+    if is_response || opcode != 0 || qdcount != 1 {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut offset = 12;
+    loop {
+        let len = *packet.get(offset)? as usize;
+        offset += 1;
+        if len == 0 {
+            break;
+        }
+        // Compression pointers are not valid in a query's first question.
+        if len > 63 {
+            return None;
+        }
+        let label = packet.get(offset..offset + len)?;
+        labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        offset += len;
+    }
+    let fixed = packet.get(offset..offset + 4)?;
+    Some(DnsQuestion {
+        name: labels.join("."),
+        qtype: u16::from_be_bytes([fixed[0], fixed[1]]),
+        qclass: u16::from_be_bytes([fixed[2], fixed[3]]),
+        end: offset + 4,
+    })
+}
+
+/// Answer `query` from `records`, or `None` when the name is not local.
+///
+/// A local name gets an authoritative answer: its address for `A`/`IN`
+/// queries and an empty (NODATA) answer for any other type, so resolvers do
+/// not fall through to upstream servers for box names.
+pub fn local_answer(
+    query: &[u8],
+    question: &DnsQuestion,
+    records: &HashMap<String, Ipv4Addr>,
+) -> Option<Vec<u8>> {
+    let ip = records.get(&question.name)?;
+    let answer_a = question.qtype == TYPE_A && question.qclass == CLASS_IN;
+
+    let mut response = Vec::with_capacity(question.end + 16);
+    response.extend_from_slice(&query[..2]);
+    // QR, opcode 0, AA, RD copied from the query, RA, RCODE 0.
+    let rd = query[2] & 0x01;
+    response.extend_from_slice(&[0x84 | rd, 0x80]);
+    response.extend_from_slice(&1u16.to_be_bytes());
+    response.extend_from_slice(&u16::from(answer_a).to_be_bytes());
+    response.extend_from_slice(&[0, 0, 0, 0]);
+    response.extend_from_slice(&query[12..question.end]);
+    if answer_a {
+        // Name is a pointer to the question at offset 12.
+        response.extend_from_slice(&[0xc0, 0x0c]);
+        response.extend_from_slice(&TYPE_A.to_be_bytes());
+        response.extend_from_slice(&CLASS_IN.to_be_bytes());
+        response.extend_from_slice(&RECORD_TTL.to_be_bytes());
+        response.extend_from_slice(&4u16.to_be_bytes());
+        response.extend_from_slice(&ip.octets());
+    }
+    Some(response)
+}
+
+/// NXDOMAIN response for a name the egress policy blocks.
+pub fn blocked_answer(query: &[u8], question: &DnsQuestion) -> Vec<u8> {
+    let mut response = Vec::with_capacity(question.end);
+    response.extend_from_slice(&query[..2]);
+    let rd = query[2] & 0x01;
+    response.extend_from_slice(&[0x80 | rd, 0x80 | RCODE_NXDOMAIN]);
+    response.extend_from_slice(&1u16.to_be_bytes());
+    response.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    response.extend_from_slice(&query[12..question.end]);
+    response
+}
+
+/// Addresses in the answer section of a successful response (A and AAAA
+/// records of class IN, including those at the end of a CNAME chain).
+pub fn answer_addresses(response: &[u8]) -> Vec<IpAddr> {
+    fn skip_name(packet: &[u8], mut offset: usize) -> Option<usize> {
+        loop {
+            let len = *packet.get(offset)? as usize;
+            if len == 0 {
+                return Some(offset + 1);
+            }
+            if len & 0xc0 == 0xc0 {
+                return Some(offset + 2);
+            }
+            offset += 1 + len;
+        }
+    }
+
+    fn parse(response: &[u8]) -> Option<Vec<IpAddr>> {
+        let header = response.get(..12)?;
+        if header[2] & 0x80 == 0 || header[3] & 0x0f != 0 {
+            return None;
+        }
+        let qdcount = u16::from_be_bytes([header[4], header[5]]);
+        let ancount = u16::from_be_bytes([header[6], header[7]]);
+        let mut offset = 12;
+        for _ in 0..qdcount {
+            offset = skip_name(response, offset)? + 4;
+        }
+        let mut addrs = Vec::new();
+        for _ in 0..ancount {
+            offset = skip_name(response, offset)?;
+            let fixed = response.get(offset..offset + 10)?;
+            let rtype = u16::from_be_bytes([fixed[0], fixed[1]]);
+            let class = u16::from_be_bytes([fixed[2], fixed[3]]);
+            let rdlen = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+            offset += 10;
+            let rdata = response.get(offset..offset + rdlen)?;
+            offset += rdlen;
+            if class != CLASS_IN {
+                continue;
+            }
+            match (rtype, rdata.len()) {
+                (TYPE_A, 4) => addrs.push(IpAddr::V4(Ipv4Addr::new(
+                    rdata[0], rdata[1], rdata[2], rdata[3],
+                ))),
+                (TYPE_AAAA, 16) => {
+                    let octets: [u8; 16] = rdata.try_into().ok()?;
+                    addrs.push(IpAddr::V6(Ipv6Addr::from(octets)));
+                }
+                _ => {}
+            }
+        }
+        Some(addrs)
+    }
+
+    parse(response).unwrap_or_default()
+}
+
+/// Forward a non-local query, applying the egress policy when there is one.
+#[cfg(target_os = "linux")]
+fn resolve_upstream(
+    query: &[u8],
+    policy: Option<&EgressPolicy>,
+    forward: impl FnOnce(&[u8]) -> Option<Vec<u8>>,
+) -> Option<Vec<u8>> {
+    let Some(policy) = policy else {
+        return forward(query);
+    };
+    // Under a policy only plain single-question queries are resolved, so
+    // every forwarded name is one the policy was checked against.
+    let question = parse_query(query)?;
+    if !policy.allows_domain(&question.name) {
+        debug!(name = %question.name, "Embedded DNS refused name blocked by egress policy");
+        return Some(blocked_answer(query, &question));
+    }
+    let response = forward(query)?;
+    if policy.explicitly_allows_domain(&question.name) {
+        let addrs = answer_addresses(&response);
+        if let Err(error) = crate::egress::admit(&addrs) {
+            warn!(%error, name = %question.name, "Failed to allow resolved addresses");
+        }
+    }
+    Some(response)
+}
+
+/// Name records, reloaded when the file on disk changes.
+#[cfg(target_os = "linux")]
+struct Records {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    entries: HashMap<String, Ipv4Addr>,
+}
+
+#[cfg(target_os = "linux")]
+impl Records {
+    fn refresh(&mut self) -> &HashMap<String, Ipv4Addr> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified != self.modified {
+            self.entries = std::fs::read_to_string(&self.path)
+                .map(|content| a3s_box_core::dns::parse_dns_records(&content))
+                .unwrap_or_default();
+            self.modified = modified;
+            debug!(
+                records = self.entries.len(),
+                "Reloaded embedded DNS records"
+            );
+        }
+        &self.entries
+    }
+
+    fn answer(&mut self, query: &[u8]) -> Option<Vec<u8>> {
+        let question = parse_query(query)?;
+        local_answer(query, &question, self.refresh())
+    }
+}
+
+/// Start the embedded resolver on `127.0.0.11:53`.
+///
+/// Binding happens before this returns, so a failure leaves the caller free to
+/// point `/etc/resolv.conf` at the upstream servers instead.
+#[cfg(target_os = "linux")]
+pub fn spawn_dns_server(
+    records_path: &Path,
+    upstreams: &[String],
+    policy: Option<EgressPolicy>,
+) -> std::io::Result<()> {
+    let addr = SocketAddr::from((a3s_box_core::dns::EMBEDDED_DNS_ADDR, 53));
+    let udp = UdpSocket::bind(addr)?;
+    let tcp = TcpListener::bind(addr)?;
+    let upstreams: Arc<Vec<SocketAddr>> = Arc::new(
+        upstreams
+            .iter()
+            .filter_map(|server| server.parse::<Ipv4Addr>().ok())
+            .filter(|server| *server != a3s_box_core::dns::EMBEDDED_DNS_ADDR)
+            .map(|server| SocketAddr::from((server, 53)))
+            .collect(),
+    );
+    let records = Arc::new(Mutex::new(Records {
+        path: records_path.to_path_buf(),
+        modified: None,
+        entries: HashMap::new(),
+    }));
+    let policy = policy.map(Arc::new);
+    info!(
+        %addr,
+        upstreams = ?upstreams,
+        egress_policy = policy.is_some(),
+        "Embedded DNS resolver listening"
+    );
+
+    {
+        let records = Arc::clone(&records);
+        let upstreams = Arc::clone(&upstreams);
+        let policy = policy.clone();
+        std::thread::spawn(move || serve_udp(udp, records, upstreams, policy));
+    }
+    std::thread::spawn(move || serve_tcp(tcp, records, upstreams, policy));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn serve_udp(
+    socket: UdpSocket,
+    records: Arc<Mutex<Records>>,
+    upstreams: Arc<Vec<SocketAddr>>,
+    policy: Option<Arc<EgressPolicy>>,
+) {
+    let mut buf = [0u8; 4096];
+    loop {
+        let (len, client) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(error) => {
+                warn!(%error, "Embedded DNS receive failed");
+                continue;
+            }
+        };
+        let query = buf[..len].to_vec();
+        let local = records
+            .lock()
+            .ok()
+            .and_then(|mut records| records.answer(&query));
+        if let Some(response) = local {
+            let _ = socket.send_to(&response, client);
+            continue;
+        }
+
+        let Ok(reply_socket) = socket.try_clone() else {
+            continue;
+        };
+        let upstreams = Arc::clone(&upstreams);
+        let policy = policy.clone();
+        std::thread::spawn(move || {
+            let response = resolve_upstream(&query, policy.as_deref(), |query| {
+                forward_udp(query, &upstreams)
+            });
+            if let Some(response) = response {
+                let _ = reply_socket.send_to(&response, client);
+            }
+        });
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn forward_udp(query: &[u8], upstreams: &[SocketAddr]) -> Option<Vec<u8>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.set_read_timeout(Some(FORWARD_TIMEOUT)).ok()?;
+    let mut buf = [0u8; 4096];
+    for upstream in upstreams {
+        if socket.send_to(query, upstream).is_err() {
+            continue;
+        }
+        while let Ok((len, from)) = socket.recv_from(&mut buf) {
+            if from == *upstream && buf[..2] == query[..2] {
+                return Some(buf[..len].to_vec());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn serve_tcp(
+    listener: TcpListener,
+    records: Arc<Mutex<Records>>,
+    upstreams: Arc<Vec<SocketAddr>>,
+    policy: Option<Arc<EgressPolicy>>,
+) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let records = Arc::clone(&records);
+        let upstreams = Arc::clone(&upstreams);
+        let policy = policy.clone();
+        std::thread::spawn(move || {
+            if let Err(error) = handle_tcp(stream, &records, &upstreams, policy.as_deref()) {
+                debug!(%error, "Embedded DNS TCP connection ended");
+            }
+        });
+    }
+}
+
+/// Serve length-prefixed queries on one TCP connection until the client closes it.
+#[cfg(target_os = "linux")]
+fn handle_tcp(
+    mut stream: TcpStream,
+    records: &Mutex<Records>,
+    upstreams: &[SocketAddr],
+    policy: Option<&EgressPolicy>,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    while let Some(query) = read_tcp_message(&mut stream)? {
+        let local = records
+            .lock()
+            .ok()
+            .and_then(|mut records| records.answer(&query));
+        let response = match local {
+            Some(response) => response,
+            None => match resolve_upstream(&query, policy, |query| forward_tcp(query, upstreams)) {
+                Some(response) => response,
+                None => return Ok(()),
+            },
+        };
+        write_tcp_message(&mut stream, &response)?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn forward_tcp(query: &[u8], upstreams: &[SocketAddr]) -> Option<Vec<u8>> {
+    upstreams.iter().find_map(|upstream| {
+        let mut stream = TcpStream::connect_timeout(upstream, FORWARD_TIMEOUT).ok()?;
+        stream.set_read_timeout(Some(FORWARD_TIMEOUT)).ok()?;
+        write_tcp_message(&mut stream, query).ok()?;
+        read_tcp_message(&mut stream).ok().flatten()
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_tcp_message(stream: &mut TcpStream) -> std::io::Result<Option<Vec<u8>>> {
+    use std::io::Read;
+
+    let mut len = [0u8; 2];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+    let mut message = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+#[cfg(target_os = "linux")]
+fn write_tcp_message(stream: &mut TcpStream, message: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let len = u16::try_from(message.len()).map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "DNS message too long")
+    })?;
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut packet = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&qtype.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet
+    }
+
+    fn records() -> HashMap<String, Ipv4Addr> {
+        HashMap::from([("db".to_string(), Ipv4Addr::new(10, 88, 0, 3))])
+    }
+
+    #[test]
+    fn test_parse_query_lowercases_name() {
+        let question = parse_query(&query("Api.Local", TYPE_A)).unwrap();
+
+        assert_eq!(question.name, "api.local");
+        assert_eq!(question.qtype, TYPE_A);
+        assert_eq!(question.qclass, CLASS_IN);
+    }
+
+    #[test]
+    fn test_parse_query_rejects_responses_and_truncated_packets() {
+        let mut response = query("db", TYPE_A);
+        response[2] |= 0x80;
+        assert!(parse_query(&response).is_none());
+
+        let packet = query("db", TYPE_A);
+        assert!(parse_query(&packet[..packet.len() - 2]).is_none());
+        assert!(parse_query(&[0; 4]).is_none());
+    }
+
+    #[test]
+    fn test_local_answer_for_a_query() {
+        let packet = query("db", TYPE_A);
+        let question = parse_query(&packet).unwrap();
+
+        let response = local_answer(&packet, &question, &records()).unwrap();
+
+        assert_eq!(&response[..2], &[0x12, 0x34]);
+        assert_eq!(response[2] & 0x80, 0x80, "QR bit");
+        assert_eq!(response[3] & 0x0f, 0, "RCODE");
+        assert_eq!(u16::from_be_bytes([response[6], response[7]]), 1);
+        assert_eq!(&response[response.len() - 4..], &[10, 88, 0, 3]);
+    }
+
+    #[test]
+    fn test_local_answer_nodata_for_other_types_and_none_for_unknown_names() {
+        let packet = query("db", 28);
+        let question = parse_query(&packet).unwrap();
+        let response = local_answer(&packet, &question, &records()).unwrap();
+        assert_eq!(u16::from_be_bytes([response[6], response[7]]), 0);
+        assert_eq!(response.len(), packet.len());
+
+        let packet = query("example.com", TYPE_A);
+        let question = parse_query(&packet).unwrap();
+        assert!(local_answer(&packet, &question, &records()).is_none());
+    }
+
+    #[test]
+    fn test_blocked_answer_is_nxdomain() {
+        let packet = query("evil.example", TYPE_A);
+        let question = parse_query(&packet).unwrap();
+
+        let response = blocked_answer(&packet, &question);
+
+        assert_eq!(&response[..2], &[0x12, 0x34]);
+        assert_eq!(response[2] & 0x80, 0x80, "QR bit");
+        assert_eq!(response[3] & 0x0f, RCODE_NXDOMAIN);
+        assert_eq!(u16::from_be_bytes([response[6], response[7]]), 0);
+        assert_eq!(response.len(), packet.len());
+    }
+
+    #[test]
+    fn test_answer_addresses_follows_cname_chain() {
+        let mut response = query("api.example.com", TYPE_A);
+        response[2] |= 0x80;
+        response[7] = 3;
+        // CNAME api.example.com -> cdn (compressed to the question name).
+        response.extend_from_slice(&[0xc0, 0x0c, 0, 5, 0, 1, 0, 0, 0, 60, 0, 6]);
+        response.extend_from_slice(&[3, b'c', b'd', b'n', 0xc0, 0x10]);
+        let cname = response.len() - 6;
+        for (rtype, rdata) in [
+            (TYPE_A, vec![203, 0, 113, 7]),
+            (TYPE_AAAA, Ipv6Addr::LOCALHOST.octets().to_vec()),
+        ] {
+            response.extend_from_slice(&[0xc0, cname as u8]);
+            response.extend_from_slice(&rtype.to_be_bytes());
+            response.extend_from_slice(&CLASS_IN.to_be_bytes());
+            response.extend_from_slice(&60u32.to_be_bytes());
+            response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            response.extend_from_slice(&rdata);
+        }
+
+        assert_eq!(
+            answer_addresses(&response),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)),
+                IpAddr::V6(Ipv6Addr::LOCALHOST)
+            ]
+        );
+
+        response[3] |= RCODE_NXDOMAIN;
+        assert!(answer_addresses(&response).is_empty());
+        assert!(answer_addresses(&query("db", TYPE_A)).is_empty());
+    }
+}
@@ -0,0 +1,729 @@
+//! In-guest egress firewall for `--network-policy`.
+//!
+//! The host passes the box's [`EgressPolicy`] in `A3S_NET_EGRESS_POLICY`.
+//! Guest init installs it as an nftables table (`inet a3s_egress`) with one
+//! output-hook chain, talking nfnetlink directly since the guest rootfs has
+//! no `nft` binary. The chain accepts loopback, established/related traffic,
+//! DNS to the upstream servers, and IPv6 neighbour discovery, then drops
+//! denied CIDRs and (when the policy has an allow list) accepts only allowed
+//! CIDRs plus the `allow4`/`allow6` sets. The embedded resolver fills those
+//! sets with the addresses it resolves for allowed domains via [`admit`].
+//!
+//! `A3S_NET_MAX_CONNS` (`--network-max-conns`) adds a rule to the same chain
+//! that resets new outbound TCP connections once the box already holds that
+//! many, so it also works without a policy.
+//!
+//! The workload runs without `CAP_NET_ADMIN`, so it cannot alter the table.
+
+use a3s_box_core::egress::{EgressPolicy, IpCidr, EGRESS_POLICY_ENV, MAX_CONNS_ENV};
+use std::net::IpAddr;
+
+const TABLE: &str = "a3s_egress";
+const CHAIN: &str = "output";
+const ALLOW4_SET: &str = "allow4";
+const ALLOW6_SET: &str = "allow6";
+const ALLOW4_SET_ID: u32 = 1;
+const ALLOW6_SET_ID: u32 = 2;
+
+// nfnetlink / nf_tables UAPI constants (linux/netfilter/nf_tables.h).
+const NFNL_SUBSYS_NFTABLES: u16 = 10;
+const NFNL_MSG_BATCH_BEGIN: u16 = 0x10;
+const NFNL_MSG_BATCH_END: u16 = 0x11;
+const NFT_MSG_NEWTABLE: u16 = 0;
+const NFT_MSG_NEWCHAIN: u16 = 3;
+const NFT_MSG_NEWRULE: u16 = 6;
+const NFT_MSG_NEWSET: u16 = 9;
+const NFT_MSG_NEWSETELEM: u16 = 12;
+
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_CREATE: u16 = 0x400;
+const NLM_F_APPEND: u16 = 0x800;
+const NLA_F_NESTED: u16 = 0x8000;
+
+const NFPROTO_INET: u8 = 1;
+const NFPROTO_IPV4: u8 = 2;
+const NFPROTO_IPV6: u8 = 10;
+const NF_INET_LOCAL_OUT: u32 = 3;
+const NF_DROP: u32 = 0;
+const NF_ACCEPT: u32 = 1;
+
+const NFTA_TABLE_NAME: u16 = 1;
+const NFTA_CHAIN_TABLE: u16 = 1;
+const NFTA_CHAIN_NAME: u16 = 3;
+const NFTA_CHAIN_HOOK: u16 = 4;
+const NFTA_CHAIN_POLICY: u16 = 5;
+const NFTA_CHAIN_TYPE: u16 = 7;
+const NFTA_HOOK_HOOKNUM: u16 = 1;
+const NFTA_HOOK_PRIORITY: u16 = 2;
+const NFTA_SET_TABLE: u16 = 1;
+const NFTA_SET_NAME: u16 = 2;
+const NFTA_SET_KEY_TYPE: u16 = 4;
+const NFTA_SET_KEY_LEN: u16 = 5;
+const NFTA_SET_ID: u16 = 10;
+const NFTA_SET_ELEM_LIST_TABLE: u16 = 1;
+const NFTA_SET_ELEM_LIST_SET: u16 = 2;
+const NFTA_SET_ELEM_LIST_ELEMENTS: u16 = 3;
+const NFTA_SET_ELEM_KEY: u16 = 1;
+const NFTA_RULE_TABLE: u16 = 1;
+const NFTA_RULE_CHAIN: u16 = 2;
+const NFTA_RULE_EXPRESSIONS: u16 = 4;
+const NFTA_LIST_ELEM: u16 = 1;
+const NFTA_EXPR_NAME: u16 = 1;
+const NFTA_EXPR_DATA: u16 = 2;
+const NFTA_DATA_VALUE: u16 = 1;
+const NFTA_DATA_VERDICT: u16 = 2;
+const NFTA_VERDICT_CODE: u16 = 1;
+
+/// nftables datatype IDs for set keys (`ipv4_addr`, `ipv6_addr`).
+const TYPE_IPV4_ADDR: u32 = 7;
+const TYPE_IPV6_ADDR: u32 = 8;
+
+const NFT_REG_VERDICT: u32 = 0;
+const NFT_REG_1: u32 = 1;
+const NFT_CMP_EQ: u32 = 0;
+const NFT_CMP_NEQ: u32 = 1;
+const NFT_CMP_LTE: u32 = 3;
+const NFT_CMP_GTE: u32 = 5;
+const NFT_META_OIF: u32 = 4;
+const NFT_META_NFPROTO: u32 = 15;
+const NFT_META_L4PROTO: u32 = 16;
+const NFT_PAYLOAD_NETWORK_HEADER: u32 = 1;
+const NFT_PAYLOAD_TRANSPORT_HEADER: u32 = 2;
+const NFT_CT_STATE: u32 = 0;
+/// `NF_CT_STATE_BIT(IP_CT_ESTABLISHED) | NF_CT_STATE_BIT(IP_CT_RELATED)`.
+const CT_STATE_ESTABLISHED_RELATED: u32 = 0x2 | 0x4;
+const CT_STATE_NEW: u32 = 0x8;
+const NFT_CONNLIMIT_F_INV: u32 = 1;
+const NFT_REJECT_TCP_RST: u32 = 1;
+
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+const IPPROTO_ICMPV6: u8 = 58;
+/// Router solicitation through neighbour advertisement.
+const ICMPV6_NDP_TYPES: (u8, u8) = (133, 136);
+
+/// Read the policy guest init must enforce, if the host set one.
+pub fn policy_from_env() -> Result<Option<EgressPolicy>, String> {
+    match std::env::var(EGRESS_POLICY_ENV) {
+        Ok(value) if !value.is_empty() => EgressPolicy::from_env_value(&value).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Read the outbound connection limit, if the host set one.
+pub fn max_conns_from_env() -> Result<Option<u32>, String> {
+    match std::env::var(MAX_CONNS_ENV) {
+        Ok(value) if !value.is_empty() => value
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid {MAX_CONNS_ENV} '{value}'")),
+        _ => Ok(None),
+    }
+}
+
+/// Install the policy's nftables ruleset, plus the connection limit if set.
+///
+/// `dns_servers` are the upstream resolvers the embedded resolver forwards to;
+/// port 53 traffic to them is always allowed so domain rules keep working.
+pub fn apply(
+    policy: &EgressPolicy,
+    max_conns: Option<u32>,
+    dns_servers: &[String],
+) -> std::io::Result<()> {
+    let dns_servers: Vec<IpAddr> = dns_servers
+        .iter()
+        .filter_map(|server| server.parse().ok())
+        .collect();
+    let (batch, messages) = ruleset_batch(policy, max_conns, &dns_servers);
+    send_batch(&batch, messages)
+}
+
+/// Allow connections to addresses the resolver returned for an allowed domain.
+///
+/// Elements already in the set are left as they are, so repeated lookups are
+/// harmless. Addresses stay allowed for the rest of the box's lifetime.
+pub fn admit(addrs: &[IpAddr]) -> std::io::Result<()> {
+    if addrs.is_empty() {
+        return Ok(());
+    }
+    let mut batch = Batch::new();
+    for (set, is_v4) in [(ALLOW4_SET, true), (ALLOW6_SET, false)] {
+        let mut elements = Attrs::default();
+        for addr in addrs.iter().filter(|addr| addr.is_ipv4() == is_v4) {
+            let key = Attrs::default().bytes(NFTA_DATA_VALUE, &ip_octets(*addr));
+            elements = elements.nested(
+                NFTA_LIST_ELEM,
+                Attrs::default().nested(NFTA_SET_ELEM_KEY, key),
+            );
+        }
+        if elements.0.is_empty() {
+            continue;
+        }
+        batch.message(
+            NFT_MSG_NEWSETELEM,
+            NLM_F_CREATE,
+            Attrs::default()
+                .string(NFTA_SET_ELEM_LIST_TABLE, TABLE)
+                .string(NFTA_SET_ELEM_LIST_SET, set)
+                .nested(NFTA_SET_ELEM_LIST_ELEMENTS, elements),
+        );
+    }
+    let (batch, messages) = batch.finish();
+    send_batch(&batch, messages)
+}
+
+/// Build the transaction that creates the table, chain, sets, and rules.
+///
+/// Returns the batch and the number of messages the kernel acknowledges.
+fn ruleset_batch(
+    policy: &EgressPolicy,
+    max_conns: Option<u32>,
+    dns_servers: &[IpAddr],
+) -> (Vec<u8>, u32) {
+    let mut batch = Batch::new();
+    batch.message(
+        NFT_MSG_NEWTABLE,
+        NLM_F_CREATE,
+        Attrs::default().string(NFTA_TABLE_NAME, TABLE),
+    );
+    batch.message(
+        NFT_MSG_NEWCHAIN,
+        NLM_F_CREATE,
+        Attrs::default()
+            .string(NFTA_CHAIN_TABLE, TABLE)
+            .string(NFTA_CHAIN_NAME, CHAIN)
+            .nested(
+                NFTA_CHAIN_HOOK,
+                Attrs::default()
+                    .u32(NFTA_HOOK_HOOKNUM, NF_INET_LOCAL_OUT)
+                    .u32(NFTA_HOOK_PRIORITY, 0),
+            )
+            .u32(
+                NFTA_CHAIN_POLICY,
+                if policy.default_deny() {
+                    NF_DROP
+                } else {
+                    NF_ACCEPT
+                },
+            )
+            .string(NFTA_CHAIN_TYPE, "filter"),
+    );
+    for (name, id, key_type, key_len) in [
+        (ALLOW4_SET, ALLOW4_SET_ID, TYPE_IPV4_ADDR, 4),
+        (ALLOW6_SET, ALLOW6_SET_ID, TYPE_IPV6_ADDR, 16),
+    ] {
+        batch.message(
+            NFT_MSG_NEWSET,
+            NLM_F_CREATE,
+            Attrs::default()
+                .string(NFTA_SET_TABLE, TABLE)
+                .string(NFTA_SET_NAME, name)
+                .u32(NFTA_SET_KEY_TYPE, key_type)
+                .u32(NFTA_SET_KEY_LEN, key_len)
+                .u32(NFTA_SET_ID, id),
+        );
+    }
+
+    for rule in ruleset(policy, max_conns, dns_servers) {
+        let mut expressions = Attrs::default();
+        for expr in rule {
+            expressions = expressions.nested(NFTA_LIST_ELEM, expr);
+        }
+        batch.message(
+            NFT_MSG_NEWRULE,
+            NLM_F_CREATE | NLM_F_APPEND,
+            Attrs::default()
+                .string(NFTA_RULE_TABLE, TABLE)
+                .string(NFTA_RULE_CHAIN, CHAIN)
+                .nested(NFTA_RULE_EXPRESSIONS, expressions),
+        );
+    }
+    batch.finish()
+}
+
+/// The chain's rules in evaluation order, each a list of expressions.
+fn ruleset(
+    policy: &EgressPolicy,
+    max_conns: Option<u32>,
+    dns_servers: &[IpAddr],
+) -> Vec<Vec<Attrs>> {
+    let mut rules = vec![
+        vec![
+            expr::meta(NFT_META_OIF),
+            // The loopback interface is always ifindex 1.
+            expr::cmp(NFT_CMP_EQ, &1u32.to_ne_bytes()),
+            expr::verdict(NF_ACCEPT),
+        ],
+        vec![
+            expr::ct_state(),
+            expr::bitwise(&CT_STATE_ESTABLISHED_RELATED.to_ne_bytes()),
+            expr::cmp(NFT_CMP_NEQ, &0u32.to_ne_bytes()),
+            expr::verdict(NF_ACCEPT),
+        ],
+    ];
+    if let Some(max_conns) = max_conns {
+        rules.push(vec![
+            expr::meta(NFT_META_L4PROTO),
+            expr::cmp(NFT_CMP_EQ, &[IPPROTO_TCP]),
+            expr::ct_state(),
+            expr::bitwise(&CT_STATE_NEW.to_ne_bytes()),
+            expr::cmp(NFT_CMP_NEQ, &0u32.to_ne_bytes()),
+            expr::connlimit_over(max_conns),
+            expr::reject_tcp_reset(),
+        ]);
+    }
+    for server in dns_servers {
+        for proto in [IPPROTO_UDP, IPPROTO_TCP] {
+            let mut rule = match_daddr(&host_cidr(*server));
+            rule.extend([
+                expr::meta(NFT_META_L4PROTO),
+                expr::cmp(NFT_CMP_EQ, &[proto]),
+                expr::payload(NFT_PAYLOAD_TRANSPORT_HEADER, 2, 2),
+                expr::cmp(NFT_CMP_EQ, &53u16.to_be_bytes()),
+                expr::verdict(NF_ACCEPT),
+            ]);
+            rules.push(rule);
+        }
+    }
+    rules.push(vec![
+        expr::meta(NFT_META_NFPROTO),
+        expr::cmp(NFT_CMP_EQ, &[NFPROTO_IPV6]),
+        expr::meta(NFT_META_L4PROTO),
+        expr::cmp(NFT_CMP_EQ, &[IPPROTO_ICMPV6]),
+        expr::payload(NFT_PAYLOAD_TRANSPORT_HEADER, 0, 1),
+        expr::cmp(NFT_CMP_GTE, &[ICMPV6_NDP_TYPES.0]),
+        expr::cmp(NFT_CMP_LTE, &[ICMPV6_NDP_TYPES.1]),
+        expr::verdict(NF_ACCEPT),
+    ]);
+
+    for cidr in policy.denied_cidrs() {
+        let mut rule = match_daddr(cidr);
+        rule.push(expr::verdict(NF_DROP));
+        rules.push(rule);
+    }
+    if policy.default_deny() {
+        for cidr in policy.allowed_cidrs() {
+            let mut rule = match_daddr(cidr);
+            rule.push(expr::verdict(NF_ACCEPT));
+            rules.push(rule);
+        }
+        for (family, set, id) in [
+            (NFPROTO_IPV4, ALLOW4_SET, ALLOW4_SET_ID),
+            (NFPROTO_IPV6, ALLOW6_SET, ALLOW6_SET_ID),
+        ] {
+            let mut rule = match_family(family);
+            rule.extend([
+                daddr_payload(family),
+                expr::lookup(set, id),
+                expr::verdict(NF_ACCEPT),
+            ]);
+            rules.push(rule);
+        }
+    }
+    rules
+}
+
+fn host_cidr(addr: IpAddr) -> IpCidr {
+    IpCidr {
+        addr,
+        prefix_len: if addr.is_ipv4() { 32 } else { 128 },
+    }
+}
+
+fn match_family(family: u8) -> Vec<Attrs> {
+    vec![
+        expr::meta(NFT_META_NFPROTO),
+        expr::cmp(NFT_CMP_EQ, &[family]),
+    ]
+}
+
+fn daddr_payload(family: u8) -> Attrs {
+    if family == NFPROTO_IPV4 {
+        expr::payload(NFT_PAYLOAD_NETWORK_HEADER, 16, 4)
+    } else {
+        expr::payload(NFT_PAYLOAD_NETWORK_HEADER, 24, 16)
+    }
+}
+
+/// Expressions matching packets whose destination is inside `cidr`.
+fn match_daddr(cidr: &IpCidr) -> Vec<Attrs> {
+    let family = if cidr.addr.is_ipv4() {
+        NFPROTO_IPV4
+    } else {
+        NFPROTO_IPV6
+    };
+    let mut exprs = match_family(family);
+    exprs.push(daddr_payload(family));
+    let full = if cidr.addr.is_ipv4() { 32 } else { 128 };
+    if cidr.prefix_len < full {
+        exprs.push(expr::bitwise(&cidr.mask_bytes()));
+    }
+    exprs.push(expr::cmp(NFT_CMP_EQ, &ip_octets(cidr.addr)));
+    exprs
+}
+
+fn ip_octets(addr: IpAddr) -> Vec<u8> {
+    match addr {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    }
+}
+
+/// nf_tables expression builders; every expression works on register 1.
+mod expr {
+    use super::*;
+
+    fn build(name: &str, data: Attrs) -> Attrs {
+        Attrs::default()
+            .string(NFTA_EXPR_NAME, name)
+            .nested(NFTA_EXPR_DATA, data)
+    }
+
+    fn data_value(value: &[u8]) -> Attrs {
+        Attrs::default().bytes(NFTA_DATA_VALUE, value)
+    }
+
+    pub(super) fn meta(key: u32) -> Attrs {
+        // NFTA_META_DREG, NFTA_META_KEY
+        build("meta", Attrs::default().u32(1, NFT_REG_1).u32(2, key))
+    }
+
+    pub(super) fn ct_state() -> Attrs {
+        // NFTA_CT_DREG, NFTA_CT_KEY
+        build(
+            "ct",
+            Attrs::default().u32(1, NFT_REG_1).u32(2, NFT_CT_STATE),
+        )
+    }
+
+    pub(super) fn payload(base: u32, offset: u32, len: u32) -> Attrs {
+        // NFTA_PAYLOAD_DREG, _BASE, _OFFSET, _LEN
+        build(
+            "payload",
+            Attrs::default()
+                .u32(1, NFT_REG_1)
+                .u32(2, base)
+                .u32(3, offset)
+                .u32(4, len),
+        )
+    }
+
+    pub(super) fn cmp(op: u32, value: &[u8]) -> Attrs {
+        // NFTA_CMP_SREG, _OP, _DATA
+        build(
+            "cmp",
+            Attrs::default()
+                .u32(1, NFT_REG_1)
+                .u32(2, op)
+                .nested(3, data_value(value)),
+        )
+    }
+
+    /// `reg1 = reg1 & mask`.
+    pub(super) fn bitwise(mask: &[u8]) -> Attrs {
+        // NFTA_BITWISE_SREG, _DREG, _LEN, _MASK, _XOR
+        build(
+            "bitwise",
+            Attrs::default()
+                .u32(1, NFT_REG_1)
+                .u32(2, NFT_REG_1)
+                .u32(3, mask.len() as u32)
+                .nested(4, data_value(mask))
+                .nested(5, data_value(&vec![0; mask.len()])),
+        )
+    }
+
+    pub(super) fn lookup(set: &str, set_id: u32) -> Attrs {
+        // NFTA_LOOKUP_SET, _SREG, _SET_ID
+        build(
+            "lookup",
+            Attrs::default()
+                .string(1, set)
+                .u32(2, NFT_REG_1)
+                .u32(4, set_id),
+        )
+    }
+
+    /// Matches while more than `count` connections are tracked.
+    pub(super) fn connlimit_over(count: u32) -> Attrs {
+        // NFTA_CONNLIMIT_COUNT, _FLAGS
+        build(
+            "connlimit",
+            Attrs::default().u32(1, count).u32(2, NFT_CONNLIMIT_F_INV),
+        )
+    }
+
+    pub(super) fn reject_tcp_reset() -> Attrs {
+        // NFTA_REJECT_TYPE
+        build("reject", Attrs::default().u32(1, NFT_REJECT_TCP_RST))
+    }
+
+    pub(super) fn verdict(code: u32) -> Attrs {
+        // NFTA_IMMEDIATE_DREG, _DATA
+        let verdict = Attrs::default().u32(NFTA_VERDICT_CODE, code);
+        build(
+            "immediate",
+            Attrs::default()
+                .u32(1, NFT_REG_VERDICT)
+                .nested(2, Attrs::default().nested(NFTA_DATA_VERDICT, verdict)),
+        )
+    }
+}
+
+/// Netlink attributes, encoded back to back with 4-byte padding.
+#[derive(Default)]
+struct Attrs(Vec<u8>);
+
+impl Attrs {
+    fn bytes(mut self, ty: u16, data: &[u8]) -> Self {
+        self.0
+            .extend_from_slice(&((4 + data.len()) as u16).to_ne_bytes());
+        self.0.extend_from_slice(&ty.to_ne_bytes());
+        self.0.extend_from_slice(data);
+        self.0.resize(self.0.len().next_multiple_of(4), 0);
+        self
+    }
+
+    fn string(self, ty: u16, value: &str) -> Self {
+        let mut data = value.as_bytes().to_vec();
+        data.push(0);
+        self.bytes(ty, &data)
+    }
+
+    fn u32(self, ty: u16, value: u32) -> Self {
+        self.bytes(ty, &value.to_be_bytes())
+    }
+
+    fn nested(self, ty: u16, inner: Attrs) -> Self {
+        self.bytes(ty | NLA_F_NESTED, &inner.0)
+    }
+}
+
+/// An nfnetlink transaction: batch begin, acked messages, batch end.
+struct Batch {
+    buf: Vec<u8>,
+    seq: u32,
+    acked: u32,
+}
+
+impl Batch {
+    fn new() -> Self {
+        let mut batch = Self {
+            buf: Vec::new(),
+            seq: 0,
+            acked: 0,
+        };
+        batch.push(
+            NFNL_MSG_BATCH_BEGIN,
+            NLM_F_REQUEST,
+            0,
+            NFNL_SUBSYS_NFTABLES,
+            &[],
+        );
+        batch
+    }
+
+    fn message(&mut self, msg: u16, flags: u16, attrs: Attrs) {
+        self.acked += 1;
+        self.push(
+            (NFNL_SUBSYS_NFTABLES << 8) | msg,
+            NLM_F_REQUEST | NLM_F_ACK | flags,
+            NFPROTO_INET,
+            0,
+            &attrs.0,
+        );
+    }
+
+    fn finish(mut self) -> (Vec<u8>, u32) {
+        self.push(
+            NFNL_MSG_BATCH_END,
+            NLM_F_REQUEST,
+            0,
+            NFNL_SUBSYS_NFTABLES,
+            &[],
+        );
+        (self.buf, self.acked)
+    }
+
+    fn push(&mut self, ty: u16, flags: u16, family: u8, res_id: u16, payload: &[u8]) {
+        self.seq += 1;
+        // nlmsghdr (16 bytes) + nfgenmsg (4 bytes).
+        let len = 16 + 4 + payload.len();
+        self.buf.extend_from_slice(&(len as u32).to_ne_bytes());
+        self.buf.extend_from_slice(&ty.to_ne_bytes());
+        self.buf.extend_from_slice(&flags.to_ne_bytes());
+        self.buf.extend_from_slice(&self.seq.to_ne_bytes());
+        self.buf.extend_from_slice(&0u32.to_ne_bytes());
+        self.buf.extend_from_slice(&[family, 0]);
+        self.buf.extend_from_slice(&res_id.to_be_bytes());
+        self.buf.extend_from_slice(payload);
+    }
+}
+
+/// Send one transaction and wait for every message's acknowledgement.
+fn send_batch(batch: &[u8], expected_acks: u32) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    const NETLINK_NETFILTER: libc::c_int = 12;
+    const NLMSG_ERROR: u16 = 2;
+
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            NETLINK_NETFILTER,
+        )
+    };
+    if fd < 0 {
+        return Err(Error::last_os_error());
+    }
+    let sock = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let timeout = libc::timeval {
+        tv_sec: 2,
+        tv_usec: 0,
+    };
+    unsafe {
+        libc::setsockopt(
+            sock.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            &timeout as *const libc::timeval as *const libc::c_void,
+            std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+
+    let sent = unsafe { libc::send(sock.as_raw_fd(), batch.as_ptr().cast(), batch.len(), 0) };
+    if sent < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    let mut acked = 0;
+    let mut buf = vec![0u8; 16 * 1024];
+    while acked < expected_acks {
+        let len = unsafe { libc::recv(sock.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+        if len < 0 {
+            return Err(Error::last_os_error());
+        }
+        let mut offset = 0;
+        let len = len as usize;
+        while offset + 16 <= len {
+            let msg_len = u32::from_ne_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize;
+            let msg_type = u16::from_ne_bytes(buf[offset + 4..offset + 6].try_into().unwrap());
+            if msg_len < 16 || offset + msg_len > len {
+                break;
+            }
+            if msg_type == NLMSG_ERROR && msg_len >= 20 {
+                let errno = i32::from_ne_bytes(buf[offset + 16..offset + 20].try_into().unwrap());
+                if errno != 0 {
+                    return Err(Error::from_raw_os_error(-errno));
+                }
+                acked += 1;
+            }
+            offset += msg_len.next_multiple_of(4);
+        }
+        if len == 0 {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "nfnetlink socket closed",
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(specs: &[&str]) -> EgressPolicy {
+        let specs: Vec<String> = specs.iter().map(|spec| spec.to_string()).collect();
+        EgressPolicy::parse_specs(&specs).unwrap()
+    }
+
+    /// Split a batch into (type, flags) pairs.
+    fn messages(batch: &[u8]) -> Vec<(u16, u16)> {
+        let mut messages = Vec::new();
+        let mut offset = 0;
+        while offset < batch.len() {
+            let len = u32::from_ne_bytes(batch[offset..offset + 4].try_into().unwrap()) as usize;
+            let ty = u16::from_ne_bytes(batch[offset + 4..offset + 6].try_into().unwrap());
+            let flags = u16::from_ne_bytes(batch[offset + 6..offset + 8].try_into().unwrap());
+            assert_eq!(len % 4, 0, "messages stay 4-byte aligned");
+            messages.push((ty, flags));
+            offset += len;
+        }
+        assert_eq!(offset, batch.len());
+        messages
+    }
+
+    #[test]
+    fn test_attrs_pad_to_four_bytes() {
+        let attrs = Attrs::default().string(1, "abc").u32(2, 7);
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&8u16.to_ne_bytes());
+        expected.extend_from_slice(&1u16.to_ne_bytes());
+        expected.extend_from_slice(b"abc\0");
+        expected.extend_from_slice(&8u16.to_ne_bytes());
+        expected.extend_from_slice(&2u16.to_ne_bytes());
+        expected.extend_from_slice(&[0, 0, 0, 7]);
+        assert_eq!(attrs.0, expected);
+
+        let attrs = Attrs::default().bytes(1, &[1]);
+        assert_eq!(attrs.0.len(), 8);
+        assert_eq!(u16::from_ne_bytes([attrs.0[0], attrs.0[1]]), 5);
+    }
+
+    #[test]
+    fn test_ruleset_batch_frames_one_transaction() {
+        let policy = policy(&["allow=api.anthropic.com,10.0.0.0/8", "deny=10.1.0.0/16"]);
+        let dns: Vec<IpAddr> = vec!["10.0.2.3".parse().unwrap()];
+
+        let (batch, acks) = ruleset_batch(&policy, None, &dns);
+        let messages = messages(&batch);
+
+        assert_eq!(messages.first().unwrap().0, NFNL_MSG_BATCH_BEGIN);
+        assert_eq!(messages.last().unwrap().0, NFNL_MSG_BATCH_END);
+        assert_eq!(acks as usize, messages.len() - 2);
+        let nft = |msg: u16| (NFNL_SUBSYS_NFTABLES << 8) | msg;
+        assert_eq!(messages[1].0, nft(NFT_MSG_NEWTABLE));
+        assert_eq!(messages[2].0, nft(NFT_MSG_NEWCHAIN));
+        assert_eq!(messages[3].0, nft(NFT_MSG_NEWSET));
+        assert_eq!(messages[4].0, nft(NFT_MSG_NEWSET));
+        // lo, ct state, 2 DNS, NDP, 1 deny, 1 allow CIDR, 2 set lookups.
+        let rules = messages
+            .iter()
+            .filter(|(ty, _)| *ty == nft(NFT_MSG_NEWRULE))
+            .count();
+        assert_eq!(rules, 9);
+        assert!(messages[1..messages.len() - 1]
+            .iter()
+            .all(|(_, flags)| flags & NLM_F_ACK != 0));
+    }
+
+    #[test]
+    fn test_deny_only_policy_skips_allow_rules() {
+        let policy = policy(&["deny=169.254.0.0/16"]);
+
+        let rules = ruleset(&policy, None, &[]);
+
+        // lo, ct state, NDP, 1 deny.
+        assert_eq!(rules.len(), 4);
+    }
+
+    #[test]
+    fn test_connection_limit_rule_follows_established_accept() {
+        let rules = ruleset(&EgressPolicy::default(), Some(32), &[]);
+
+        // lo, ct state, connection limit, NDP.
+        assert_eq!(rules.len(), 4);
+        let limit: Vec<u8> = rules[2].iter().flat_map(|expr| expr.0.clone()).collect();
+        let contains = |needle: &[u8]| limit.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"connlimit\0"));
+        assert!(contains(b"reject\0"));
+        assert!(contains(&32u32.to_be_bytes()));
+    }
+}
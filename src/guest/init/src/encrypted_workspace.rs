@@ -0,0 +1,118 @@
+//! Guest end of the encrypted TEE workspace.
+//!
+//! With [`a3s_box_core::tee::ENCRYPTED_WORKSPACE_ENV`] set, guest init leaves
+//! `/workspace` unmounted at boot. After attesting the guest, the host formats
+//! the workspace disk as LUKS2 and sends its volume key over RA-TLS; the
+//! attestation server then maps the disk with dm-crypt and mounts it. The key
+//! stays in the guest kernel's dm-crypt state, so the host only ever stores
+//! ciphertext and nothing on the disk is readable once the box stops.
+
+use std::fmt::Write as _;
+use std::io;
+
+use a3s_box_core::tee::{
+    ENCRYPTED_WORKSPACE_BLOCK_ID, ENCRYPTED_WORKSPACE_CIPHER, ENCRYPTED_WORKSPACE_DATA_OFFSET,
+    ENCRYPTED_WORKSPACE_ENV, ENCRYPTED_WORKSPACE_KEY_LEN,
+};
+
+use crate::block::{create_device, device_sectors, find_block_device, DmTarget};
+
+/// device-mapper name of the opened workspace.
+pub const WORKSPACE_DEVICE_NAME: &str = "a3s-workspace";
+
+/// Where the workspace is mounted.
+pub const WORKSPACE_PATH: &str = "/workspace";
+
+/// Whether the host configured an encrypted workspace.
+pub fn is_enabled() -> bool {
+    std::env::var_os(ENCRYPTED_WORKSPACE_ENV).is_some()
+}
+
+/// The dm-crypt table line for the LUKS2 data segment of `device`.
+///
+/// The host formats the disk with 512-byte sectors and the data segment at
+/// [`ENCRYPTED_WORKSPACE_DATA_OFFSET`], so the IV offset is zero.
+pub fn crypt_table(volume_key: &[u8], device: &str) -> String {
+    let mut table = String::with_capacity(volume_key.len() * 2 + 64);
+    table.push_str(ENCRYPTED_WORKSPACE_CIPHER);
+    table.push(' ');
+    for byte in volume_key {
+        let _ = write!(table, "{byte:02x}");
+    }
+    let _ = write!(
+        table,
+        " 0 {device} {}",
+        ENCRYPTED_WORKSPACE_DATA_OFFSET / 512
+    );
+    table
+}
+
+/// Map the workspace disk with `volume_key` and mount it at
+/// [`WORKSPACE_PATH`]. Fails if the workspace is already open.
+pub fn open_and_mount(volume_key: &[u8]) -> io::Result<()> {
+    use nix::mount::{mount, MsFlags};
+
+    if volume_key.len() != ENCRYPTED_WORKSPACE_KEY_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("workspace key must be {ENCRYPTED_WORKSPACE_KEY_LEN} bytes"),
+        ));
+    }
+    if std::fs::metadata(format!("/dev/mapper/{WORKSPACE_DEVICE_NAME}")).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "encrypted workspace is already open",
+        ));
+    }
+    let disk = find_block_device(ENCRYPTED_WORKSPACE_BLOCK_ID).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("workspace disk '{ENCRYPTED_WORKSPACE_BLOCK_ID}' not found"),
+        )
+    })?;
+    let data_offset = ENCRYPTED_WORKSPACE_DATA_OFFSET / 512;
+    let sectors = device_sectors(&disk)?;
+    if sectors <= data_offset {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("workspace disk {disk} is smaller than its LUKS2 header"),
+        ));
+    }
+
+    let table = crypt_table(volume_key, &disk);
+    let created = create_device(
+        WORKSPACE_DEVICE_NAME,
+        &DmTarget {
+            target_type: "crypt",
+            length_sectors: sectors - data_offset,
+            params: &table,
+        },
+        false,
+    );
+    table.into_bytes().fill(0);
+    let device = created?;
+
+    std::fs::create_dir_all(WORKSPACE_PATH)?;
+    mount(
+        Some(device.as_str()),
+        WORKSPACE_PATH,
+        Some("ext4"),
+        MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
+        None::<&str>,
+    )
+    .map_err(|e| io::Error::other(format!("failed to mount {device}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crypt_table() {
+        let key = [0xabu8; ENCRYPTED_WORKSPACE_KEY_LEN];
+        assert_eq!(
+            crypt_table(&key, "/dev/vdd"),
+            format!("aes-xts-plain64 {} 0 /dev/vdd 32768", "ab".repeat(64))
+        );
+    }
+}
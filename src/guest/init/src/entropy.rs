@@ -0,0 +1,150 @@
+//! Kernel CRNG seeding at boot.
+//!
+//! The host stages a seed from its own RNG (see `a3s_box_core::entropy`).
+//! Guest init consumes it before any workload starts and hands it to the
+//! kernel with `RNDADDENTROPY`, which mixes the bytes into the input pool and
+//! credits them, so the CRNG initializes even when the guest kernel has no
+//! virtio-rng driver. It then checks whether `getrandom()` would still block.
+
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+
+use a3s_box_core::entropy::{
+    validate_seed, ENTROPY_SEED_ENV, ENTROPY_SEED_LEN, RUNTIME_ENTROPY_SEED_PATH,
+};
+
+/// `_IOW('R', 0x03, int[2])` from `<linux/random.h>`.
+const RNDADDENTROPY: u64 = (1 << 30) | (8 << 16) | ((b'R' as u64) << 8) | 0x03;
+
+/// Credit the host's staged seed to the kernel pool, if the host sent one.
+pub fn seed_from_host() {
+    let mut seed = match take_seed() {
+        Ok(Some(seed)) => seed,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!("Ignoring the host entropy seed: {e}");
+            return;
+        }
+    };
+    match add_entropy(&seed) {
+        Ok(()) => tracing::info!("Seeded the kernel CRNG with {} host bytes", seed.len()),
+        Err(e) => tracing::warn!("Failed to credit the host entropy seed: {e}"),
+    }
+    seed.fill(0);
+}
+
+/// Warn when `getrandom()` would still block, naming what feeds the pool.
+pub fn check_ready() {
+    let hwrng = hwrng_source(Path::new("/sys")).unwrap_or_else(|| "none".to_string());
+    let entropy_avail = std::fs::read_to_string("/proc/sys/kernel/random/entropy_avail")
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok());
+    if crng_ready() {
+        tracing::info!(%hwrng, ?entropy_avail, "Kernel CRNG is ready");
+    } else {
+        tracing::warn!(
+            %hwrng,
+            ?entropy_avail,
+            "Kernel CRNG is not initialized; getrandom() blocks until it is, which \
+             stalls TLS and key generation. Use a guest kernel with the virtio_rng \
+             driver or boot it with random.trust_cpu=on"
+        );
+    }
+}
+
+/// Read and delete the seed named by the environment. The file is removed
+/// even when it turns out to be unusable.
+fn take_seed() -> io::Result<Option<Vec<u8>>> {
+    let Ok(path) = std::env::var(ENTROPY_SEED_ENV) else {
+        return Ok(None);
+    };
+    if path != RUNTIME_ENTROPY_SEED_PATH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported {ENTROPY_SEED_ENV} path {path:?}"),
+        ));
+    }
+    let path = Path::new(&path);
+    let metadata = std::fs::symlink_metadata(path)?;
+    let seed = if !metadata.file_type().is_file() || metadata.len() > ENTROPY_SEED_LEN as u64 {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a seed file", path.display()),
+        ))
+    } else {
+        std::fs::read(path)
+    };
+    std::fs::remove_file(path)?;
+    let seed = seed?;
+    validate_seed(&seed).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(Some(seed))
+}
+
+fn add_entropy(seed: &[u8]) -> io::Result<()> {
+    let urandom = File::options().write(true).open("/dev/urandom")?;
+    let mut info = rand_pool_info(seed);
+    // SAFETY: `info` is a complete `rand_pool_info` and outlives the call.
+    let rc = unsafe { libc::ioctl(urandom.as_raw_fd(), RNDADDENTROPY as _, info.as_ptr()) };
+    let error = io::Error::last_os_error();
+    info.fill(0);
+    if rc != 0 {
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// `struct rand_pool_info` carrying `seed`, every bit of it credited.
+fn rand_pool_info(seed: &[u8]) -> Vec<u8> {
+    let mut info = Vec::with_capacity(8 + seed.len());
+    info.extend_from_slice(&((seed.len() * 8) as i32).to_ne_bytes());
+    info.extend_from_slice(&(seed.len() as i32).to_ne_bytes());
+    info.extend_from_slice(seed);
+    info
+}
+
+/// Whether `getrandom()` returns without blocking. A kernel too old for
+/// `GRND_NONBLOCK` is assumed ready.
+fn crng_ready() -> bool {
+    let mut byte = 0u8;
+    // SAFETY: the buffer is one writable byte.
+    let rc = unsafe { libc::getrandom((&mut byte as *mut u8).cast(), 1, libc::GRND_NONBLOCK) };
+    rc == 1 || io::Error::last_os_error().raw_os_error() != Some(libc::EAGAIN)
+}
+
+/// The hardware RNG the kernel draws from, e.g. `virtio_rng.0`.
+fn hwrng_source(sys_root: &Path) -> Option<String> {
+    let current =
+        std::fs::read_to_string(sys_root.join("class/misc/hw_random/rng_current")).ok()?;
+    let current = current.trim();
+    (!current.is_empty() && current != "none").then(|| current.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rand_pool_info_credits_the_whole_seed() {
+        let seed = [0xabu8; ENTROPY_SEED_LEN];
+        let info = rand_pool_info(&seed);
+        assert_eq!(info.len(), 8 + ENTROPY_SEED_LEN);
+        assert_eq!(i32::from_ne_bytes(info[..4].try_into().unwrap()), 512);
+        assert_eq!(i32::from_ne_bytes(info[4..8].try_into().unwrap()), 64);
+        assert_eq!(&info[8..], &seed);
+        assert_eq!(RNDADDENTROPY, 0x4008_5203);
+    }
+
+    #[test]
+    fn test_hwrng_source() {
+        let sys = tempfile::tempdir().unwrap();
+        assert_eq!(hwrng_source(sys.path()), None);
+        let dir = sys.path().join("class/misc/hw_random");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("rng_current"), "none\n").unwrap();
+        assert_eq!(hwrng_source(sys.path()), None);
+        std::fs::write(dir.join("rng_current"), "virtio_rng.0\n").unwrap();
+        assert_eq!(hwrng_source(sys.path()).as_deref(), Some("virtio_rng.0"));
+    }
+}
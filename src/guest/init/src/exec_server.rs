@@ -16,6 +16,8 @@ use std::time::Duration;
 
 #[cfg(any(target_os = "linux", test))]
 use a3s_box_core::exec::GuestSessionRequest;
+#[cfg(target_os = "linux")]
+use a3s_box_core::exec::ProcessOwnerKind;
 use a3s_box_core::exec::{
     ExecChunk, ExecExit, ExecOutput, ExecRequest, FileOp, FileRequest, FileResponse,
     FilesystemEntry, FilesystemEntryKind, FilesystemOp, FilesystemRequest, FilesystemResponse,
@@ -37,6 +39,10 @@ static CONTAINER_PID: AtomicI32 = AtomicI32::new(-1);
 /// Record the main container PID for graceful-shutdown signal delivery.
 pub fn set_container_pid(pid: i32) {
     CONTAINER_PID.store(pid, Ordering::SeqCst);
+    #[cfg(target_os = "linux")]
+    if pid > 0 {
+        crate::process_tree::register(pid as u32, ProcessOwnerKind::Main, None);
+    }
 }
 
 /// The main container PID (-1 if not yet spawned, -2 while a deferred spawn is in
@@ -128,6 +134,20 @@ static DEFERRED_MAIN: std::sync::Mutex<Option<DeferredMainSpec>> = std::sync::Mu
 #[cfg(target_os = "linux")]
 static DEFERRED_CGROUP_PROCS: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
 
+/// Secrets provisioned over RA-TLS with `set_env`, added to the deferred main's
+/// environment so a box that awaits secrets starts its main with them.
+#[cfg(target_os = "linux")]
+static PROVISIONED_ENV: std::sync::Mutex<Vec<(String, String)>> = std::sync::Mutex::new(Vec::new());
+
+/// Record a provisioned secret for the deferred main's environment. A secret
+/// provisioned again replaces the earlier value.
+#[cfg(target_os = "linux")]
+pub fn add_provisioned_env(name: &str, value: &str) {
+    let mut env = PROVISIONED_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    env.retain(|(key, _)| key != name);
+    env.push((name.to_string(), value.to_string()));
+}
+
 /// Stash the per-container cgroup's `cgroup.procs` path so a later deferred-main
 /// spawn joins the cgroup, matching the boot-spawn path. `None` (no limit set /
 /// no cgroup) leaves the deferred main uncgrouped, as before.
@@ -198,6 +218,13 @@ fn spawn_deferred_main(frame: Option<DeferredMainSpec>) -> Result<i32, String> {
     cmd_vec.push(executable);
     cmd_vec.extend(args);
     let mut env_entries: Vec<String> = env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    env_entries.extend(
+        PROVISIONED_ENV
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(k, v)| format!("{k}={v}")),
+    );
     for (k, v) in std::env::vars() {
         if k.starts_with("A3S_SEC_") {
             env_entries.push(format!("{k}={v}"));
@@ -251,6 +278,7 @@ fn spawn_deferred_main(frame: Option<DeferredMainSpec>) -> Result<i32, String> {
             // Publish the real pid (over the -2 marker) while still MANAGED, then
             // release ownership: now the loop's `pid == container_pid` branch reaps.
             CONTAINER_PID.store(pid, Ordering::SeqCst);
+            crate::process_tree::register(pid as u32, ProcessOwnerKind::Main, None);
             std::mem::forget(child); // PID 1's reaper owns it — do not double-reap
             drop(guard);
             Ok(pid)
@@ -778,6 +806,20 @@ fn handle_connection(fd: std::os::fd::OwnedFd) -> Result<(), Box<dyn std::error:
             write_frame(&mut stream, FrameType::Data as u8, &response_payload)?;
             return Ok(());
         }
+        Ok(GuestSessionRequest::Metrics(request)) => {
+            let response_payload = serde_json::to_vec(&crate::metrics::collect(&request))?;
+            write_frame(&mut stream, FrameType::Data as u8, &response_payload)?;
+            return Ok(());
+        }
+        Ok(GuestSessionRequest::ServiceEvents(request)) => {
+            stream_service_events(&mut stream, request.follow)?;
+            return Ok(());
+        }
+        Ok(GuestSessionRequest::ProcessTree(_)) => {
+            let response_payload = serde_json::to_vec(&crate::process_tree::snapshot())?;
+            write_frame(&mut stream, FrameType::Data as u8, &response_payload)?;
+            return Ok(());
+        }
         Err(error) if declares_guest_session_request(&payload) => {
             send_error_frame(
                 &mut stream,
@@ -1418,6 +1460,33 @@ fn chown_file_path(_path: &Path, _owner: Option<ProcessUser>) -> std::io::Result
     Ok(())
 }
 
+/// Send the state of every supervised service, then, when `follow` is set,
+/// each state change until the host goes away. Idle streams get a heartbeat
+/// so a disconnected host is noticed and the subscription dropped.
+#[cfg(target_os = "linux")]
+fn stream_service_events(stream: &mut impl Write, follow: bool) -> std::io::Result<()> {
+    const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+    let (statuses, events) = crate::services::subscribe();
+    for status in statuses {
+        write_frame(stream, FrameType::Data as u8, &serde_json::to_vec(&status)?)?;
+    }
+    if !follow {
+        return Ok(());
+    }
+    loop {
+        match events.recv_timeout(HEARTBEAT_INTERVAL) {
+            Ok(status) => {
+                write_frame(stream, FrameType::Data as u8, &serde_json::to_vec(&status)?)?
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                write_frame(stream, FrameType::Heartbeat as u8, &[])?
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn stream_rootfs_archive(
     stream: &mut impl Write,
@@ -1916,18 +1985,26 @@ fn build_command(
         }
     }
 
-    // CRI SupplementalGroups arrive as A3S_SEC_SUPPLEMENTAL_GROUPS=gid,gid,...
-    // and are applied (setgroups) before dropping to the target uid/gid.
-    let mut supplemental_groups: Vec<u32> = spec
+    // CRI SupplementalGroups and `--group-add` arrive as
+    // A3S_SEC_SUPPLEMENTAL_GROUPS=gid|name,... and are applied (setgroups)
+    // before dropping to the target uid/gid.
+    let mut supplemental_groups: Vec<u32> = match spec
         .env
         .iter()
         .find_map(|entry| entry.strip_prefix("A3S_SEC_SUPPLEMENTAL_GROUPS="))
-        .map(|csv| {
-            csv.split(',')
-                .filter_map(|gid| gid.trim().parse::<u32>().ok())
-                .collect()
-        })
-        .unwrap_or_default();
+        .map(|csv| crate::user::resolve_supplemental_groups(csv, resolve_rootfs))
+        .transpose()
+    {
+        Ok(groups) => groups.unwrap_or_default(),
+        Err(error) => {
+            return Err(ExecOutput {
+                stdout: vec![],
+                stderr: error.into_bytes(),
+                exit_code: 1,
+                truncated: false,
+            });
+        }
+    };
     // runc-style initgroups: when running as a specific user, add the groups
     // that user belongs to per the image's /etc/group (resolved here, pre-fork).
     // CRI-supplied groups take precedence; image groups are appended + deduped.
@@ -2013,6 +2090,7 @@ fn build_command(
         seccomp_localhost,
         no_new_privs,
         cgroup_procs,
+        crate::container_ns::ContainerNamespaces::resolve(spec.env, spec.rootfs),
     );
     if spec.rootfs.is_none() {
         if let Some(dir) = spec.working_dir {
@@ -2158,6 +2236,8 @@ fn execute_command(
             };
         }
     };
+    #[cfg(target_os = "linux")]
+    crate::process_tree::register(child.id(), ProcessOwnerKind::Exec, Some(cmd[0].clone()));
 
     let output_readers = ChildOutputReaders::start(&mut child);
     write_child_stdin(&mut child, stdin_data, false);
@@ -2477,6 +2557,12 @@ fn execute_command_streaming(
             return Ok(());
         }
     };
+    #[cfg(target_os = "linux")]
+    crate::process_tree::register(
+        child.id(),
+        ProcessOwnerKind::Exec,
+        Some(spec.cmd[0].clone()),
+    );
 
     write_child_stdin(&mut child, spec.stdin_data, spec.stdin_streaming);
 
@@ -2559,6 +2645,7 @@ fn configure_child_process(
     seccomp_localhost: Vec<String>,
     no_new_privs: bool,
     cgroup_procs: Option<&str>,
+    namespaces: crate::container_ns::ContainerNamespaces,
 ) {
     use std::ffi::CString;
     use std::os::unix::process::CommandExt;
@@ -2636,6 +2723,11 @@ fn configure_child_process(
             if libc::setpgid(0, 0) != 0 {
                 return Err(std::io::Error::last_os_error());
             }
+            // Enter the container's PID/mount namespaces (pod containers with
+            // a private process tree). This forks: from here on we are the
+            // namespaced child, still in the cgroup and process group above,
+            // and still outside the rootfs so the new /proc can be mounted.
+            namespaces.enter()?;
             if let Some(rootfs) = rootfs.as_ref() {
                 if libc::chroot(rootfs.as_ptr()) != 0 {
                     return Err(std::io::Error::last_os_error());
@@ -2706,6 +2798,7 @@ fn configure_child_process(
     _seccomp_localhost: Vec<String>,
     _no_new_privs: bool,
     _cgroup_procs: Option<&str>,
+    _namespaces: crate::container_ns::ContainerNamespaces,
 ) {
 }
 
@@ -0,0 +1,303 @@
+//! Guest end of `a3s-box forward`.
+//!
+//! Guest init listens on vsock port 4095. Every accepted connection is one
+//! forwarded stream: it opens with a request naming a guest TCP port, or a
+//! [`ForwardTarget`] elsewhere in the guest, guest init connects to it,
+//! answers with a status byte, and then relays bytes both ways until either
+//! side closes. [`connect_target`] also dials the streams multiplexed over the
+//! CRI port-forward channel.
+
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use a3s_box_core::forward::{
+    decode_forward_request, decode_forward_target_len, ForwardTarget, FORWARD_REQUEST_LEN,
+    FORWARD_STATUS_ERROR, FORWARD_STATUS_OK, FORWARD_STATUS_REFUSED, FORWARD_VSOCK_PORT,
+};
+use nix::sys::socket::{
+    accept4, bind, listen, socket, AddressFamily, Backlog, SockFlag, SockType, VsockAddr,
+};
+use tracing::{debug, info, warn};
+
+/// How long the host has to send the request after connecting.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long connecting to the guest target may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Bind the forwarder's vsock listener.
+pub fn bind_listener() -> io::Result<OwnedFd> {
+    let fd = socket(
+        AddressFamily::Vsock,
+        SockType::Stream,
+        SockFlag::SOCK_CLOEXEC,
+        None,
+    )
+    .map_err(io::Error::other)?;
+    bind(
+        fd.as_raw_fd(),
+        &VsockAddr::new(libc::VMADDR_CID_ANY, FORWARD_VSOCK_PORT),
+    )
+    .map_err(io::Error::other)?;
+    listen(&fd, Backlog::new(64).map_err(io::Error::other)?).map_err(io::Error::other)?;
+    info!(vsock_port = FORWARD_VSOCK_PORT, "Port forwarder listening");
+    Ok(fd)
+}
+
+/// Serve forwarded streams for the VM's lifetime.
+pub fn serve(listener: OwnedFd) {
+    loop {
+        match accept4(listener.as_raw_fd(), SockFlag::SOCK_CLOEXEC) {
+            Ok(fd) => {
+                // SAFETY: accept4 returned a new descriptor owned by nobody else.
+                let stream = std::fs::File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+                std::thread::spawn(move || {
+                    if let Err(error) = handle(stream) {
+                        debug!(%error, "Forwarded stream failed");
+                    }
+                });
+            }
+            Err(nix::errno::Errno::EINTR) => {}
+            Err(error) => {
+                warn!(%error, "Port forwarder accept failed");
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+}
+
+fn handle(mut host: std::fs::File) -> io::Result<()> {
+    set_read_timeout(&host, Some(REQUEST_TIMEOUT))?;
+    let target = read_target(&mut host);
+    set_read_timeout(&host, None)?;
+
+    let Some(target) = target? else {
+        host.write_all(&[FORWARD_STATUS_ERROR])?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "malformed forward request",
+        ));
+    };
+
+    let stream = match connect_target(&target) {
+        Ok(stream) => stream,
+        Err(error) => {
+            host.write_all(&[connect_error_status(&error)])?;
+            return Err(error);
+        }
+    };
+    host.write_all(&[FORWARD_STATUS_OK])?;
+    debug!(%target, "Forwarded stream connected");
+    relay(host, stream)
+}
+
+/// Read a forward request and, for version 2, the target text after it.
+/// `None` means the request was malformed.
+fn read_target(host: &mut impl Read) -> io::Result<Option<ForwardTarget>> {
+    let mut request = [0u8; FORWARD_REQUEST_LEN];
+    host.read_exact(&mut request)?;
+    if let Some(port) = decode_forward_request(&request) {
+        return Ok(Some(ForwardTarget::Port(port)));
+    }
+    let Some(len) = decode_forward_target_len(&request) else {
+        return Ok(None);
+    };
+    let mut text = vec![0u8; len];
+    host.read_exact(&mut text)?;
+    Ok(String::from_utf8(text)
+        .ok()
+        .and_then(|text| text.parse().ok()))
+}
+
+/// The status byte reporting a failed connection to a target: nothing
+/// listening there is a refusal, anything else an error.
+fn connect_error_status(error: &io::Error) -> u8 {
+    match error.kind() {
+        io::ErrorKind::ConnectionRefused | io::ErrorKind::NotFound => FORWARD_STATUS_REFUSED,
+        _ => FORWARD_STATUS_ERROR,
+    }
+}
+
+/// A connected stream to a [`ForwardTarget`].
+#[derive(Debug)]
+pub enum TargetStream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl TargetStream {
+    pub fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Self::Tcp(stream) => stream.try_clone().map(Self::Tcp),
+            Self::Unix(stream) => stream.try_clone().map(Self::Unix),
+        }
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.shutdown(how),
+            Self::Unix(stream) => stream.shutdown(how),
+        }
+    }
+}
+
+impl Read for TargetStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.read(buf),
+            Self::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for TargetStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.write(buf),
+            Self::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.flush(),
+            Self::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Connect to `target` from inside the guest.
+pub fn connect_target(target: &ForwardTarget) -> io::Result<TargetStream> {
+    let stream = match target {
+        ForwardTarget::Port(port) => TargetStream::Tcp(connect_loopback(*port)?),
+        ForwardTarget::Tcp { host, port } => TargetStream::Tcp(connect_host(host, *port)?),
+        ForwardTarget::Unix(path) => TargetStream::Unix(UnixStream::connect(path)?),
+    };
+    if let TargetStream::Tcp(stream) = &stream {
+        let _ = stream.set_nodelay(true);
+    }
+    Ok(stream)
+}
+
+/// Connect to the first reachable address `host` resolves to.
+fn connect_host(host: &str, port: u16) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{host} resolved to no addresses"),
+        )
+    }))
+}
+
+/// Connect to `port` on IPv4 loopback, falling back to IPv6 for servers that
+/// bind `::1` only.
+fn connect_loopback(port: u16) -> io::Result<TcpStream> {
+    let v4 = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    match TcpStream::connect_timeout(&v4, CONNECT_TIMEOUT) {
+        Ok(stream) => Ok(stream),
+        Err(error) => {
+            let v6 = SocketAddr::from((Ipv6Addr::LOCALHOST, port));
+            TcpStream::connect_timeout(&v6, CONNECT_TIMEOUT).map_err(|_| error)
+        }
+    }
+}
+
+fn relay(host: std::fs::File, target: TargetStream) -> io::Result<()> {
+    let mut host_reader = host.try_clone()?;
+    let mut target_writer = target.try_clone()?;
+    let outbound = std::thread::spawn(move || {
+        let _ = io::copy(&mut host_reader, &mut target_writer);
+        let _ = target_writer.shutdown(Shutdown::Write);
+    });
+
+    let mut target_reader = target;
+    let mut host_writer = host;
+    let inbound = io::copy(&mut target_reader, &mut host_writer);
+    let _ = nix::sys::socket::shutdown(host_writer.as_raw_fd(), nix::sys::socket::Shutdown::Write);
+    let _ = outbound.join();
+    inbound.map(|_| ())
+}
+
+fn set_read_timeout(file: &std::fs::File, timeout: Option<Duration>) -> io::Result<()> {
+    // A zero timeval clears SO_RCVTIMEO.
+    let timeout = libc::timeval {
+        tv_sec: timeout.map_or(0, |timeout| timeout.as_secs() as libc::time_t),
+        tv_usec: 0,
+    };
+    // SAFETY: `timeout` is a valid timeval for the duration of the call and
+    // the length passed matches its size.
+    let result = unsafe {
+        libc::setsockopt(
+            file.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            (&timeout as *const libc::timeval).cast(),
+            std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a3s_box_core::forward::{encode_forward_request, encode_forward_target_request};
+    use std::io::Cursor;
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_read_target_accepts_both_request_versions() {
+        let mut port = Cursor::new(encode_forward_request(8080).to_vec());
+        assert_eq!(
+            read_target(&mut port).unwrap(),
+            Some(ForwardTarget::Port(8080))
+        );
+
+        let unix = ForwardTarget::Unix(PathBuf::from("/run/app.sock"));
+        let mut request = Cursor::new(encode_forward_target_request(&unix));
+        assert_eq!(read_target(&mut request).unwrap(), Some(unix));
+
+        let mut garbage = Cursor::new(b"GARBAGE".to_vec());
+        assert_eq!(read_target(&mut garbage).unwrap(), None);
+    }
+
+    #[test]
+    fn test_connect_target_reaches_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4];
+            stream.read_exact(&mut request).unwrap();
+            stream.write_all(&request).unwrap();
+        });
+
+        let mut stream = connect_target(&ForwardTarget::Unix(path)).unwrap();
+        stream.write_all(b"ping").unwrap();
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"ping");
+        server.join().unwrap();
+
+        // A socket nobody created is reported like a closed port.
+        let missing = ForwardTarget::Unix(dir.path().join("missing.sock"));
+        let error = connect_target(&missing).unwrap_err();
+        assert_eq!(connect_error_status(&error), FORWARD_STATUS_REFUSED);
+    }
+}
@@ -1,10 +1,14 @@
-//! Guest hostname and sysctl configuration.
+//! Guest hostname, kernel module, and sysctl configuration.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-/// Apply host configuration from the boot environment: pod sysctls and, if
-/// present, the hostname.
+use a3s_box_core::kernel::{KERNEL_MODULE_ENV_PREFIX, SYSCTL_ENV_PREFIX};
+
+/// Apply host configuration from the boot environment: kernel modules, then
+/// sysctls (a module may add the sysctls that follow), and, if present, the
+/// hostname.
 pub fn apply_from_env() -> Result<(), Box<dyn std::error::Error>> {
+    load_modules_from_env();
     apply_sysctls_from_env();
 
     let Ok(hostname) = std::env::var("BOX_HOSTNAME") else {
@@ -13,14 +17,142 @@ pub fn apply_from_env() -> Result<(), Box<dyn std::error::Error>> {
     apply_hostname(&hostname, Path::new("/etc/hostname"))
 }
 
-/// Apply pod sysctls passed as `BOX_SYSCTL_<index>=<name>=<value>`.
+/// Load kernel modules passed as `BOX_KERNEL_MODULE_<index>=<name>`.
+///
+/// Uses the rootfs `modprobe` when there is one, and otherwise resolves the
+/// module and its dependencies from `modules.dep` of the running kernel.
+/// Best-effort like sysctls: a module the guest kernel cannot load is logged
+/// and skipped.
+fn load_modules_from_env() {
+    let mut index = 0;
+    while let Ok(name) = std::env::var(format!("{KERNEL_MODULE_ENV_PREFIX}{index}")) {
+        index += 1;
+        match load_module(&name) {
+            Ok(true) => tracing::info!("Loaded kernel module {name}"),
+            Ok(false) => tracing::info!("Kernel module {name} is already loaded or built in"),
+            Err(e) => tracing::warn!("Failed to load kernel module {name}: {e}"),
+        }
+    }
+}
+
+/// Load `name`; `Ok(false)` when the kernel already has it.
+fn load_module(name: &str) -> Result<bool, String> {
+    a3s_box_core::kernel::validate_kernel_module_name(name)?;
+    if Path::new("/sys/module").join(module_key(name)).exists() {
+        return Ok(false);
+    }
+    if let Some(modprobe) = ["/sbin/modprobe", "/usr/sbin/modprobe"]
+        .into_iter()
+        .map(Path::new)
+        .find(|path| path.exists())
+    {
+        let status = std::process::Command::new(modprobe)
+            .arg(name)
+            .status()
+            .map_err(|e| format!("{}: {e}", modprobe.display()))?;
+        return if status.success() {
+            Ok(true)
+        } else {
+            Err(format!("{} exited with {status}", modprobe.display()))
+        };
+    }
+
+    let release = std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map_err(|e| format!("kernel release: {e}"))?;
+    let modules_dir = Path::new("/lib/modules").join(release.trim());
+    let modules_dep = std::fs::read_to_string(modules_dir.join("modules.dep"))
+        .map_err(|e| format!("{}: {e}", modules_dir.join("modules.dep").display()))?;
+    let chain = module_load_order(&modules_dep, name)
+        .ok_or_else(|| format!("not found in {}", modules_dir.display()))?;
+    for path in chain {
+        let loaded = Path::new("/sys/module").join(module_key(&module_name(&path)));
+        if !loaded.exists() {
+            insert_module(&modules_dir.join(&path))?;
+        }
+    }
+    Ok(true)
+}
+
+/// The files to insert for `name` from a `modules.dep` listing, dependencies
+/// first. Each line is `<module>: <dependency>...` with the deepest
+/// dependency last.
+fn module_load_order(modules_dep: &str, name: &str) -> Option<Vec<PathBuf>> {
+    let key = module_key(name);
+    modules_dep.lines().find_map(|line| {
+        let (module, dependencies) = line.split_once(':')?;
+        if module_key(&module_name(Path::new(module))) != key {
+            return None;
+        }
+        let mut chain: Vec<PathBuf> = dependencies
+            .split_whitespace()
+            .rev()
+            .map(PathBuf::from)
+            .collect();
+        chain.push(PathBuf::from(module.trim()));
+        Some(chain)
+    })
+}
+
+/// The module name of a `.ko` file, with any compression suffix dropped.
+fn module_name(path: &Path) -> String {
+    let file = path
+        .file_name()
+        .and_then(|file| file.to_str())
+        .unwrap_or_default();
+    file.split(".ko").next().unwrap_or(file).to_string()
+}
+
+/// The kernel treats `-` and `_` in module names alike and lists modules
+/// under `/sys/module` with `_`.
+fn module_key(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+#[cfg(target_os = "linux")]
+fn insert_module(path: &Path) -> Result<(), String> {
+    use std::os::fd::AsRawFd;
+
+    if path.extension().and_then(|ext| ext.to_str()) != Some("ko") {
+        return Err(format!(
+            "{}: compressed modules need modprobe in the rootfs",
+            path.display()
+        ));
+    }
+    let file = std::fs::File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    // SAFETY: finit_module reads the module from the open fd; the empty
+    // parameter string outlives the call.
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_finit_module,
+            file.as_raw_fd(),
+            b"\0".as_ptr().cast::<libc::c_char>(),
+            0 as libc::c_int,
+        )
+    };
+    let error = std::io::Error::last_os_error();
+    if ret == 0 || error.raw_os_error() == Some(libc::EEXIST) {
+        Ok(())
+    } else {
+        Err(format!("{}: {error}", path.display()))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn insert_module(path: &Path) -> Result<(), String> {
+    Err(format!(
+        "{}: not supported on this platform",
+        path.display()
+    ))
+}
+
+/// Apply sysctls passed as `BOX_SYSCTL_<index>=<name>=<value>`.
 ///
 /// Each is written to `/proc/sys/<name with '.' as '/'>`. Best-effort: a sysctl
 /// the guest kernel does not expose is logged and skipped rather than aborting
 /// VM startup.
 fn apply_sysctls_from_env() {
     let mut index = 0;
-    while let Ok(spec) = std::env::var(format!("BOX_SYSCTL_{index}")) {
+    while let Ok(spec) = std::env::var(format!("{SYSCTL_ENV_PREFIX}{index}")) {
         index += 1;
         let Some((name, value)) = spec.split_once('=') else {
             continue;
@@ -76,6 +208,30 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_module_load_order_puts_dependencies_first() {
+        let modules_dep = "kernel/net/llc/llc.ko:\n\
+             kernel/net/bridge/br_netfilter.ko.xz: kernel/net/bridge/bridge.ko.xz \
+             kernel/net/802/stp.ko.xz kernel/net/llc/llc.ko.xz\n\
+             kernel/fs/fuse/fuse.ko:\n";
+
+        assert_eq!(
+            module_load_order(modules_dep, "br-netfilter").unwrap(),
+            [
+                "kernel/net/llc/llc.ko.xz",
+                "kernel/net/802/stp.ko.xz",
+                "kernel/net/bridge/bridge.ko.xz",
+                "kernel/net/bridge/br_netfilter.ko.xz",
+            ]
+            .map(PathBuf::from)
+        );
+        assert_eq!(
+            module_load_order(modules_dep, "fuse").unwrap(),
+            [PathBuf::from("kernel/fs/fuse/fuse.ko")]
+        );
+        assert!(module_load_order(modules_dep, "missing").is_none());
+    }
+
     #[test]
     fn test_write_hostname_file() {
         let dir = TempDir::new().unwrap();
@@ -0,0 +1,83 @@
+//! Guest end of `--http-proxy`.
+//!
+//! The host passes the relay address in `A3S_HTTP_PROXY`, and the runtime
+//! points the workload's `HTTP_PROXY` / `HTTPS_PROXY` at it. Guest init
+//! accepts on that loopback address and relays each connection, byte for
+//! byte, to the shim's proxy on vsock port 4094; the shim parses, forwards,
+//! and records the requests.
+
+use std::io;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::os::fd::AsRawFd;
+
+use a3s_box_core::http_proxy::{HTTP_PROXY_ENV, HTTP_PROXY_VSOCK_PORT};
+use nix::sys::socket::{connect, socket, AddressFamily, SockFlag, SockType, VsockAddr};
+use tracing::{debug, info, warn};
+
+const HOST_CID: u32 = 2;
+
+/// The relay address guest init must listen on, if the host set one.
+pub fn listen_addr_from_env() -> Option<String> {
+    std::env::var(HTTP_PROXY_ENV)
+        .ok()
+        .filter(|addr| !addr.is_empty())
+}
+
+/// Bind the relay listener on `addr`.
+pub fn bind(addr: &str) -> io::Result<TcpListener> {
+    let listener = TcpListener::bind(addr)?;
+    info!(addr, "HTTP proxy relay listening");
+    Ok(listener)
+}
+
+/// Relay every accepted connection to the host proxy for the VM's lifetime.
+pub fn serve(listener: TcpListener) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                std::thread::spawn(move || {
+                    if let Err(error) = relay(stream) {
+                        debug!(%error, "HTTP proxy relay connection failed");
+                    }
+                });
+            }
+            Err(error) => warn!(%error, "HTTP proxy relay accept failed"),
+        }
+    }
+}
+
+fn relay(client: TcpStream) -> io::Result<()> {
+    let host = connect_host()?;
+    let _ = client.set_nodelay(true);
+
+    let mut client_reader = client.try_clone()?;
+    let mut host_writer = host.try_clone()?;
+    let outbound = std::thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut host_writer);
+        let _ =
+            nix::sys::socket::shutdown(host_writer.as_raw_fd(), nix::sys::socket::Shutdown::Write);
+    });
+
+    let mut host_reader = host;
+    let mut client_writer = client;
+    let inbound = io::copy(&mut host_reader, &mut client_writer);
+    let _ = client_writer.shutdown(Shutdown::Both);
+    let _ = outbound.join();
+    inbound.map(|_| ())
+}
+
+fn connect_host() -> io::Result<std::fs::File> {
+    let fd = socket(
+        AddressFamily::Vsock,
+        SockType::Stream,
+        SockFlag::SOCK_CLOEXEC,
+        None,
+    )
+    .map_err(io::Error::other)?;
+    connect(
+        fd.as_raw_fd(),
+        &VsockAddr::new(HOST_CID, HTTP_PROXY_VSOCK_PORT),
+    )
+    .map_err(io::Error::other)?;
+    Ok(std::fs::File::from(fd))
+}
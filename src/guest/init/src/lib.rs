@@ -7,18 +7,48 @@
 
 pub mod attest_server;
 #[cfg(target_os = "linux")]
+pub mod block;
+#[cfg(target_os = "linux")]
 pub mod cgroup;
+mod container_ns;
+#[cfg(target_os = "linux")]
+pub mod device_proxy;
+pub mod dns_server;
+#[cfg(target_os = "linux")]
+pub mod encrypted_workspace;
+#[cfg(target_os = "linux")]
+pub mod egress;
+#[cfg(target_os = "linux")]
+pub mod entropy;
 pub mod exec_server;
+#[cfg(target_os = "linux")]
+pub mod forward;
 pub mod host_config;
+#[cfg(target_os = "linux")]
+pub mod http_proxy;
 mod listener;
+#[cfg(target_os = "linux")]
+pub mod metrics;
 pub mod namespace;
 pub mod network;
 pub mod port_forward;
+#[cfg(target_os = "linux")]
+pub mod process_tree;
 pub mod pty_server;
+#[cfg(target_os = "linux")]
+mod pty_sessions;
 pub mod reaper;
 #[cfg(any(target_os = "linux", all(test, unix)))]
 pub mod rootfs_archive;
+#[cfg(target_os = "linux")]
+pub mod services;
+#[cfg(target_os = "linux")]
+pub mod shaping;
+#[cfg(target_os = "linux")]
+pub mod socket_share;
 pub mod user;
+#[cfg(target_os = "linux")]
+pub mod verity;
 
 pub use namespace::{spawn_isolated, NamespaceConfig, NamespaceError};
 pub use network::configure_guest_network;
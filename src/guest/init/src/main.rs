@@ -15,7 +15,8 @@ mod linux {
         GuestExecConfig, MAX_RUNTIME_EXEC_CONFIG_BYTES, RUNTIME_EXEC_CONFIG_PATH,
     };
     use a3s_box_guest_init::{
-        attest_server, exec_server, host_config, namespace, network, port_forward, pty_server,
+        attest_server, device_proxy, exec_server, forward, host_config, http_proxy, namespace,
+        network, port_forward, pty_server, services, socket_share,
     };
     use std::process;
     use std::sync::atomic::{AtomicI32, Ordering};
@@ -716,6 +717,13 @@ mod linux {
         let bootstrap_mode = BootstrapMode::from_env()?;
         info!(?bootstrap_mode, "Selected guest-init bootstrap mode");
 
+        // Orphans of the container, services, and exec sessions must come back
+        // here to be reaped and accounted, even when we are not PID 1.
+        #[cfg(target_os = "linux")]
+        if let Err(e) = a3s_box_guest_init::reaper::become_subreaper() {
+            warn!("Failed to become a child subreaper: {}", e);
+        }
+
         // Restore Linux uid/gid/mode before mounting procfs, workspace, or user
         // volumes so metadata replay can never mutate an attached host path.
         #[cfg(target_os = "linux")]
@@ -752,12 +760,35 @@ mod linux {
             mount_virtio_fs_shares()?;
             mount_devpts()?;
             mount_tmpfs_volumes()?;
+            mount_block_volumes()?;
+            create_device_nodes();
+
+            // Seed the kernel CRNG before anything asks for random numbers.
+            #[cfg(target_os = "linux")]
+            {
+                a3s_box_guest_init::entropy::seed_from_host();
+                a3s_box_guest_init::entropy::check_ready();
+            }
 
             // Make the unified hierarchy visible for nested runtimes in a VM.
             #[cfg(target_os = "linux")]
             let _ = a3s_box_guest_init::cgroup::ensure_cgroup2_ready();
         }
 
+        // Bind shared host sockets (`--mount type=socket`) while the rootfs is
+        // still writable; their relays start with the other services in Step 6.8.
+        let shared_sockets = if bootstrap_mode.is_host_sandbox() {
+            Vec::new()
+        } else {
+            socket_share::bind_from_env()?
+        };
+        // Likewise link proxied host devices (`--device`) to their terminals.
+        let proxied_devices = if bootstrap_mode.is_host_sandbox() {
+            Vec::new()
+        } else {
+            device_proxy::setup_from_env()?
+        };
+
         // Step 2.6: Bind the exec (vsock 4089) and PTY (vsock 4090) listening sockets
         // NOW, before the slower network bring-up and container spawn below. These are
         // pure socket/bind/listen syscalls on this (still single-threaded) main thread,
@@ -835,6 +866,30 @@ mod linux {
             }
         }
 
+        // Step 6.6: Start the --http-proxy relay before the workload, so its
+        // first request already finds the proxy it was pointed at.
+        if let Some(addr) = http_proxy::listen_addr_from_env() {
+            let listener = http_proxy::bind(&addr)?;
+            std::thread::spawn(move || http_proxy::serve(listener));
+        }
+
+        // Step 6.7: Accept `a3s-box forward` streams. Forwarding is a debugging
+        // aid, so a bind failure is logged rather than failing the boot.
+        match forward::bind_listener() {
+            Ok(listener) => {
+                std::thread::spawn(move || forward::serve(listener));
+            }
+            Err(error) => warn!(%error, "Port forwarder unavailable"),
+        }
+
+        // Step 6.8: Relay shared host sockets before the workload looks for them.
+        for shared in shared_sockets {
+            std::thread::spawn(move || socket_share::serve(shared));
+        }
+        for device in proxied_devices {
+            std::thread::spawn(move || device_proxy::serve(device));
+        }
+
         // Step 7: Launch container entrypoint
         info!("Launching container entrypoint");
 
@@ -991,6 +1046,20 @@ mod linux {
 
         expose_container_env_to_exec(&exec_config);
 
+        // Step 7.5: Start supervising the declared extra services. They inherit
+        // the container environment exposed above and run for the VM's
+        // lifetime; a bad service list is logged rather than failing the boot.
+        match services::from_env() {
+            Ok(Some(declared)) => {
+                info!(count = declared.len(), "Starting supervised services");
+                if let Err(e) = services::start(declared) {
+                    error!("Service supervisor failed to start: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error!("Ignoring service list: {}", e),
+        }
+
         // Step 8: Start the exec server accept loop on the socket bound in Step 2.6.
         // (set_container_pid above ran first, so a host signal-main frame still finds
         // the PID once the loop is serving.)
@@ -1232,15 +1301,24 @@ mod linux {
             // Check if /dev/root virtiofs is available by trying to mount it to a temp location
             std::fs::create_dir_all("/mnt/newroot").ok();
 
-            match mount(
-                Some("/dev/root"),
-                "/mnt/newroot",
-                Some("virtiofs"),
-                MsFlags::empty(),
-                None::<&str>,
-            ) {
+            // A TEE box with a measured rootfs boots the workload from its
+            // dm-verity image instead; failing to open it fails the boot.
+            let new_root = match a3s_box_guest_init::verity::from_env()? {
+                Some(rootfs) => {
+                    mount_measured_rootfs(&rootfs, "/mnt/newroot")?;
+                    Ok(())
+                }
+                None => mount(
+                    Some("/dev/root"),
+                    "/mnt/newroot",
+                    Some("virtiofs"),
+                    MsFlags::empty(),
+                    None::<&str>,
+                ),
+            };
+            match new_root {
                 Ok(_) => {
-                    info!("Successfully mounted /dev/root to /mnt/newroot");
+                    info!("Successfully mounted the root filesystem at /mnt/newroot");
 
                     // Now we need to pivot to the new root
                     // First, move essential mounts to the new root
@@ -1371,8 +1449,13 @@ mod linux {
             // Ensure workspace mount point exists
             std::fs::create_dir_all("/workspace").ok();
 
-            // Mount workspace share
-            mount_virtiofs("workspace", "/workspace", MsFlags::empty())?;
+            // Mount workspace share. An encrypted workspace is mounted later,
+            // once the host releases its key to the attested guest.
+            if a3s_box_guest_init::encrypted_workspace::is_enabled() {
+                info!("Encrypted workspace: waiting for its key after attestation");
+            } else {
+                mount_virtiofs("workspace", "/workspace", MsFlags::empty())?;
+            }
 
             // Mount user-defined volumes from environment variables.
             // Format: BOX_VOL_<index>=<tag>:<guest_path>[:ro]
@@ -1387,6 +1470,64 @@ mod linux {
         Ok(())
     }
 
+    /// Mount a measured rootfs at `target`: its dm-verity device as the
+    /// read-only lower layer of an overlay with a tmpfs upper, so the
+    /// workload can write while every image block it reads is verified.
+    #[cfg(target_os = "linux")]
+    fn mount_measured_rootfs(
+        rootfs: &a3s_box_core::tee::VerityRootfs,
+        target: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use a3s_box_core::tee::{VERITY_DATA_BLOCK_ID, VERITY_HASH_BLOCK_ID};
+        use nix::mount::{mount, MsFlags};
+
+        const LOWER: &str = "/mnt/.a3s-verity";
+        const WRITABLE: &str = "/mnt/.a3s-verity-rw";
+
+        let disk = |block_id: &str| {
+            a3s_box_guest_init::block::find_block_device(block_id)
+                .ok_or_else(|| format!("measured rootfs disk '{block_id}' not found"))
+        };
+        let device = a3s_box_guest_init::verity::open(
+            rootfs,
+            &disk(VERITY_DATA_BLOCK_ID)?,
+            &disk(VERITY_HASH_BLOCK_ID)?,
+        )
+        .map_err(|e| format!("failed to open measured rootfs: {e}"))?;
+
+        std::fs::create_dir_all(LOWER)?;
+        std::fs::create_dir_all(WRITABLE)?;
+        mount(
+            Some(device.as_str()),
+            LOWER,
+            Some("ext4"),
+            MsFlags::MS_RDONLY,
+            None::<&str>,
+        )
+        .map_err(|e| format!("failed to mount measured rootfs {device}: {e}"))?;
+        mount(
+            Some("tmpfs"),
+            WRITABLE,
+            Some("tmpfs"),
+            MsFlags::empty(),
+            Some("mode=0755"),
+        )?;
+        std::fs::create_dir_all(format!("{WRITABLE}/upper"))?;
+        std::fs::create_dir_all(format!("{WRITABLE}/work"))?;
+        let options = format!("lowerdir={LOWER},upperdir={WRITABLE}/upper,workdir={WRITABLE}/work");
+        mount(
+            Some("overlay"),
+            target,
+            Some("overlay"),
+            MsFlags::empty(),
+            Some(options.as_str()),
+        )
+        .map_err(|e| format!("failed to mount measured rootfs overlay: {e}"))?;
+
+        info!(root_hash = %rootfs.root_hash, "Mounted measured rootfs");
+        Ok(())
+    }
+
     #[cfg(target_os = "linux")]
     fn pivot_to_rootfs(new_root: &str) -> Result<(), Box<dyn std::error::Error>> {
         use nix::mount::{mount, umount2, MntFlags, MsFlags};
@@ -1602,11 +1743,13 @@ mod linux {
             use nix::mount::{mount, MsFlags};
 
             let mut index = 0;
+            let mut has_shared_memory = false;
             loop {
                 let env_key = format!("BOX_TMPFS_{}", index);
                 match std::env::var(&env_key) {
                     Ok(value) => {
                         let (path, options, read_only) = parse_tmpfs_mount(&value)?;
+                        has_shared_memory |= path.trim_end_matches('/') == "/dev/shm";
 
                         info!(
                             path = path,
@@ -1639,6 +1782,31 @@ mod linux {
             if index > 0 {
                 info!("Mounted {} tmpfs volume(s)", index);
             }
+
+            // Docker gives every container a 64m /dev/shm; without it POSIX
+            // shared memory falls back to the devtmpfs and competes with it.
+            if !has_shared_memory {
+                let options = format!(
+                    "size={},mode=1777",
+                    a3s_box_core::config::DEFAULT_SHM_SIZE_BYTES
+                );
+                let mounted = std::fs::create_dir_all("/dev/shm")
+                    .map_err(|e| e.to_string())
+                    .and_then(|()| {
+                        mount(
+                            None::<&str>,
+                            "/dev/shm",
+                            Some("tmpfs"),
+                            MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+                            Some(options.as_str()),
+                        )
+                        .map_err(|e| e.to_string())
+                    });
+                match mounted {
+                    Ok(()) => info!(options = options, "Mounted default /dev/shm"),
+                    Err(e) => warn!("Failed to mount default /dev/shm: {e}"),
+                }
+            }
         }
 
         #[cfg(not(target_os = "linux"))]
@@ -1649,6 +1817,140 @@ mod linux {
         Ok(())
     }
 
+    /// Mount block volumes passed via BOX_BLOCK_* environment variables.
+    ///
+    /// Each variable has the format `<block_id>:<fstype>:<ro|rw>:<target>` (see
+    /// `a3s_box_core::volume::BlockVolume`). The virtio-blk disk is found by
+    /// matching `/sys/block/vd*/serial` against the block ID, because disk
+    /// letters depend on attach order.
+    fn mount_block_volumes() -> Result<(), Box<dyn std::error::Error>> {
+        #[cfg(target_os = "linux")]
+        {
+            use a3s_box_core::volume::{BlockVolume, BLOCK_VOLUME_ENV_PREFIX};
+            use a3s_box_guest_init::block::find_block_device;
+            use nix::mount::{mount, MsFlags};
+
+            let mut index = 0;
+            while let Ok(value) = std::env::var(format!("{BLOCK_VOLUME_ENV_PREFIX}{index}")) {
+                index += 1;
+                let volume = BlockVolume::from_env_value(&value)?;
+                let device = find_block_device(&volume.block_id).ok_or_else(|| {
+                    format!(
+                        "block volume '{}' not found (no /sys/block/*/serial matches)",
+                        volume.block_id
+                    )
+                })?;
+                info!(
+                    device = %device,
+                    target = %volume.target,
+                    fstype = %volume.fstype,
+                    read_only = volume.read_only,
+                    "Mounting block volume"
+                );
+                std::fs::create_dir_all(&volume.target)?;
+                let mut flags = MsFlags::MS_NOSUID | MsFlags::MS_NODEV;
+                if volume.read_only {
+                    flags |= MsFlags::MS_RDONLY;
+                }
+                mount(
+                    Some(device.as_str()),
+                    volume.target.as_str(),
+                    Some(volume.fstype.as_str()),
+                    flags,
+                    None::<&str>,
+                )
+                .map_err(|e| {
+                    format!(
+                        "failed to mount block volume {} at {}: {e}",
+                        device, volume.target
+                    )
+                })?;
+            }
+
+            if index > 0 {
+                info!("Mounted {} block volume(s)", index);
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            info!("Skipping block volumes on non-Linux platform (development mode)");
+        }
+
+        Ok(())
+    }
+
+    /// Create allowlisted device nodes passed via BOX_DEVICE_* environment variables.
+    ///
+    /// Each variable has the format `<path>:<major>:<minor>:<mode>` (see
+    /// `a3s_box_core::device`). The devtmpfs usually already holds the node at
+    /// its canonical path; a renamed mapping or a kernel without devtmpfs
+    /// entries gets a fresh `mknod`. Best-effort: failures are logged.
+    fn create_device_nodes() {
+        #[cfg(target_os = "linux")]
+        {
+            use a3s_box_core::device::{GuestDeviceNode, DEVICE_ENV_PREFIX};
+            use std::os::unix::fs::PermissionsExt;
+
+            let mut index = 0;
+            while let Ok(value) = std::env::var(format!("{DEVICE_ENV_PREFIX}{index}")) {
+                index += 1;
+                let node = match GuestDeviceNode::from_env_value(&value) {
+                    Ok(node) => node,
+                    Err(e) => {
+                        warn!("Skipping device node: {e}");
+                        continue;
+                    }
+                };
+                let path = std::path::Path::new(&node.path);
+                if let Some(parent) = path.parent() {
+                    if let Err(e) = std::fs::create_dir_all(parent) {
+                        warn!("Failed to create {}: {e}", parent.display());
+                        continue;
+                    }
+                }
+                if !path.exists() {
+                    let Ok(cpath) = std::ffi::CString::new(node.path.as_str()) else {
+                        continue;
+                    };
+                    // SAFETY: mknod with a valid CString path and allowlisted
+                    // device numbers resolved by the host.
+                    let ret = unsafe {
+                        libc::mknod(
+                            cpath.as_ptr(),
+                            libc::S_IFCHR | node.mode,
+                            libc::makedev(node.major, node.minor),
+                        )
+                    };
+                    if ret != 0 {
+                        warn!(
+                            "Failed to mknod {}: {}",
+                            node.path,
+                            std::io::Error::last_os_error()
+                        );
+                        continue;
+                    }
+                }
+                if let Err(e) =
+                    std::fs::set_permissions(path, std::fs::Permissions::from_mode(node.mode))
+                {
+                    warn!("Failed to set permissions on {}: {e}", node.path);
+                }
+                info!(
+                    path = %node.path,
+                    major = node.major,
+                    minor = node.minor,
+                    "Device node ready"
+                );
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            info!("Skipping device nodes on non-Linux platform (development mode)");
+        }
+    }
+
     fn parse_tmpfs_mount(value: &str) -> std::io::Result<(&str, Option<String>, bool)> {
         let (path, options) = value
             .split_once(':')
@@ -1846,7 +2148,9 @@ mod linux {
                 } else {
                     // Orphan (reparented grandchild) or the sidecar: reap it here so it
                     // does not linger as a zombie. Keep draining for more.
-                    let _ = waitpid(pid, Some(WaitPidFlag::WNOHANG));
+                    if waitpid(pid, Some(WaitPidFlag::WNOHANG)).is_ok() {
+                        a3s_box_guest_init::process_tree::record_orphan_reaped();
+                    }
                 }
             }
 
@@ -1929,6 +2233,9 @@ mod linux {
     /// process directly), so it is gated to avoid a dead-code warning on macOS.
     #[cfg(target_os = "linux")]
     fn graceful_shutdown(timeout_ms: u64, signal: i32) {
+        // Keep the service supervisor from restarting what is being stopped.
+        services::shutdown();
+
         // Step 1: Send the requested signal to all processes except PID 1.
         #[cfg(target_os = "linux")]
         {
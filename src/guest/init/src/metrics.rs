@@ -0,0 +1,443 @@
+//! Guest resource metrics for `a3s-box stats`.
+//!
+//! The exec server answers a [`GuestMetricsRequest`] from the guest's own
+//! `/proc`: CPU busy time measured over a short window, memory from
+//! `/proc/meminfo`, block and network counters since boot, and the processes
+//! using the most CPU (then memory) during the window. Per-container figures
+//! come from the cgroup v2 controllers of each cgroup under
+//! `/sys/fs/cgroup`. Unlike the host-side view of the VMM process, this sees
+//! what the workload itself is doing.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use a3s_box_core::exec::{
+    GuestCgroupMetrics, GuestMetrics, GuestMetricsRequest, GuestProcessMetrics,
+    MAX_GUEST_METRICS_SAMPLE_MS,
+};
+
+/// `/proc/diskstats` counts 512-byte sectors regardless of the device.
+const SECTOR_BYTES: u64 = 512;
+
+/// `/proc/stat` counts in USER_HZ, which the kernel fixes at 100.
+const NANOS_PER_TICK: u64 = 1_000_000_000 / 100;
+
+/// Take one sample of the running guest.
+pub fn collect(request: &GuestMetricsRequest) -> GuestMetrics {
+    collect_from(
+        Path::new("/proc"),
+        Path::new("/sys/block"),
+        Path::new("/sys/fs/cgroup"),
+        request,
+    )
+}
+
+fn collect_from(
+    proc_root: &Path,
+    sys_block: &Path,
+    cgroup_root: &Path,
+    request: &GuestMetricsRequest,
+) -> GuestMetrics {
+    let read = |name: &str| std::fs::read_to_string(proc_root.join(name)).unwrap_or_default();
+
+    let cpu_before = parse_cpu_times(&read("stat"));
+    let procs_before = process_samples(proc_root);
+    std::thread::sleep(Duration::from_millis(
+        request.sample_ms.min(MAX_GUEST_METRICS_SAMPLE_MS) as u64,
+    ));
+    let stat = read("stat");
+    let cpu_after = parse_cpu_times(&stat);
+    let procs_after = process_samples(proc_root);
+
+    let cpus = count_cpus(&stat);
+    let window = cpu_after.total.saturating_sub(cpu_before.total);
+    // Percent of one vCPU: ticks over the window, summed across vCPUs, are
+    // `cpus` times the window of a single vCPU.
+    let percent = |ticks: u64| {
+        if window == 0 {
+            0.0
+        } else {
+            ticks as f64 * 100.0 * cpus.max(1) as f64 / window as f64
+        }
+    };
+
+    let mut top: Vec<(GuestProcessMetrics, &ProcessSample)> = procs_after
+        .iter()
+        .map(|(pid, after)| {
+            let before = procs_before.get(pid).map_or(0, |sample| sample.ticks);
+            let metrics = GuestProcessMetrics {
+                pid: *pid,
+                command: String::new(),
+                cpu_percent: percent(after.ticks.saturating_sub(before)),
+                memory_bytes: after.rss_bytes,
+            };
+            (metrics, after)
+        })
+        .collect();
+    top.sort_by(|(a, _), (b, _)| {
+        b.cpu_percent
+            .total_cmp(&a.cpu_percent)
+            .then(b.memory_bytes.cmp(&a.memory_bytes))
+            .then(a.pid.cmp(&b.pid))
+    });
+    top.truncate(request.top as usize);
+
+    let (memory_total_bytes, memory_used_bytes) = parse_meminfo(&read("meminfo"));
+    let (disk_read_bytes, disk_write_bytes) = parse_diskstats(&read("diskstats"), |name| {
+        !name.starts_with("loop") && !name.starts_with("ram") && sys_block.join(name).exists()
+    });
+    let (network_rx_bytes, network_tx_bytes) = parse_net_dev(&read("net/dev"));
+
+    GuestMetrics {
+        cpus,
+        cpu_percent: percent(
+            cpu_after
+                .busy()
+                .saturating_sub(cpu_before.busy())
+                .min(window),
+        ),
+        cpu_busy_ns: cpu_after.busy().saturating_mul(NANOS_PER_TICK),
+        memory_total_bytes,
+        memory_used_bytes,
+        disk_read_bytes,
+        disk_write_bytes,
+        network_rx_bytes,
+        network_tx_bytes,
+        processes: procs_after.len() as u64,
+        top: top
+            .into_iter()
+            .map(|(mut metrics, sample)| {
+                metrics.command = process_command(proc_root, metrics.pid, &sample.comm);
+                metrics
+            })
+            .collect(),
+        cgroups: cgroup_samples(cgroup_root),
+    }
+}
+
+/// One entry per child cgroup of `cgroup_root`, sorted by name. Controllers
+/// that are not enabled for a cgroup read as zero.
+fn cgroup_samples(cgroup_root: &Path) -> Vec<GuestCgroupMetrics> {
+    let Ok(entries) = std::fs::read_dir(cgroup_root) else {
+        return Vec::new();
+    };
+    let mut cgroups: Vec<GuestCgroupMetrics> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| {
+            let dir = entry.path();
+            let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap_or_default();
+            let number = |name: &str| read(name).trim().parse().unwrap_or(0);
+            GuestCgroupMetrics {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                cpu_usage_ns: flat_keyed(&read("cpu.stat"), "usage_usec").saturating_mul(1000),
+                memory_bytes: number("memory.current"),
+                memory_limit_bytes: read("memory.max").trim().parse().ok(),
+                pids: number("pids.current"),
+                oom_kills: flat_keyed(&read("memory.events"), "oom_kill"),
+            }
+        })
+        .collect();
+    cgroups.sort_by(|a, b| a.name.cmp(&b.name));
+    cgroups
+}
+
+/// The value of `key` in a cgroup "flat keyed" file such as `cpu.stat`.
+fn flat_keyed(content: &str, key: &str) -> u64 {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Aggregate jiffies from the `cpu` line of `/proc/stat`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct CpuTimes {
+    total: u64,
+    idle: u64,
+}
+
+impl CpuTimes {
+    fn busy(&self) -> u64 {
+        self.total.saturating_sub(self.idle)
+    }
+}
+
+fn parse_cpu_times(stat: &str) -> CpuTimes {
+    let Some(line) = stat.lines().find(|line| line.starts_with("cpu ")) else {
+        return CpuTimes::default();
+    };
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|field| field.parse().ok())
+        .collect();
+    // user nice system idle iowait irq softirq steal [guest guest_nice]; guest
+    // time is already included in user and nice.
+    CpuTimes {
+        total: fields.iter().take(8).sum(),
+        idle: fields.iter().skip(3).take(2).sum(),
+    }
+}
+
+fn count_cpus(stat: &str) -> u32 {
+    stat.lines()
+        .filter(|line| {
+            line.strip_prefix("cpu")
+                .and_then(|rest| rest.chars().next())
+                .is_some_and(|c| c.is_ascii_digit())
+        })
+        .count() as u32
+}
+
+/// `(MemTotal, MemTotal - MemAvailable)` in bytes.
+fn parse_meminfo(meminfo: &str) -> (u64, u64) {
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+            .map(|kib| kib * 1024)
+    };
+    let total = field("MemTotal").unwrap_or(0);
+    let available = field("MemAvailable").unwrap_or(total);
+    (total, total.saturating_sub(available))
+}
+
+/// Bytes read and written across the devices `include` accepts.
+fn parse_diskstats(diskstats: &str, include: impl Fn(&str) -> bool) -> (u64, u64) {
+    diskstats
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let name = *fields.get(2)?;
+            if !include(name) {
+                return None;
+            }
+            let read = fields.get(5)?.parse::<u64>().ok()?;
+            let written = fields.get(9)?.parse::<u64>().ok()?;
+            Some((read * SECTOR_BYTES, written * SECTOR_BYTES))
+        })
+        .fold((0, 0), |(r, w), (read, written)| (r + read, w + written))
+}
+
+/// Bytes received and sent across all interfaces except loopback.
+fn parse_net_dev(net_dev: &str) -> (u64, u64) {
+    net_dev
+        .lines()
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            if name.trim() == "lo" {
+                return None;
+            }
+            let fields: Vec<&str> = counters.split_whitespace().collect();
+            let rx = fields.first()?.parse::<u64>().ok()?;
+            let tx = fields.get(8)?.parse::<u64>().ok()?;
+            Some((rx, tx))
+        })
+        .fold((0, 0), |(r, t), (rx, tx)| (r + rx, t + tx))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProcessSample {
+    comm: String,
+    /// `utime + stime` in jiffies.
+    ticks: u64,
+    rss_bytes: u64,
+}
+
+fn process_samples(proc_root: &Path) -> HashMap<u32, ProcessSample> {
+    let Ok(entries) = std::fs::read_dir(proc_root) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            Some((pid, parse_pid_stat(&stat, page_size())?))
+        })
+        .collect()
+}
+
+/// Parse `/proc/<pid>/stat`. The command name is parenthesised and may
+/// itself contain spaces and parentheses, so fields are counted from the
+/// last `)`.
+fn parse_pid_stat(stat: &str, page_size: u64) -> Option<ProcessSample> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?.to_string();
+    // Fields after the name start at `state` (field 3 in proc(5)).
+    let fields: Vec<&str> = stat.get(close + 1..)?.split_whitespace().collect();
+    let field = |number: usize| fields.get(number - 3)?.parse::<u64>().ok();
+    Some(ProcessSample {
+        comm,
+        ticks: field(14)? + field(15)?,
+        rss_bytes: field(24)? * page_size,
+    })
+}
+
+pub(crate) fn process_command(proc_root: &Path, pid: u32, comm: &str) -> String {
+    let cmdline =
+        std::fs::read(proc_root.join(pid.to_string()).join("cmdline")).unwrap_or_default();
+    let command = cmdline
+        .split(|&byte| byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if command.is_empty() {
+        format!("[{comm}]")
+    } else {
+        command
+    }
+}
+
+fn page_size() -> u64 {
+    // SAFETY: sysconf has no preconditions.
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as u64
+    } else {
+        4096
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_times_and_count() {
+        let stat = "cpu  100 5 50 800 20 1 2 3 7 0\n\
+                    cpu0 50 2 25 400 10 0 1 1 0 0\n\
+                    cpu1 50 3 25 400 10 1 1 2 0 0\n\
+                    intr 12345\n";
+        let times = parse_cpu_times(stat);
+        assert_eq!(times.total, 981);
+        assert_eq!(times.idle, 820);
+        assert_eq!(times.busy(), 161);
+        assert_eq!(count_cpus(stat), 2);
+    }
+
+    #[test]
+    fn test_parse_meminfo_disk_and_network() {
+        let meminfo =
+            "MemTotal:        2048 kB\nMemFree:          512 kB\nMemAvailable:    1024 kB\n";
+        assert_eq!(parse_meminfo(meminfo), (2048 * 1024, 1024 * 1024));
+
+        let diskstats = "   7       0 loop0 10 0 80 0 0 0 0 0 0 0 0\n \
+                          253       0 vda 100 0 2000 0 50 0 400 0 0 0 0\n \
+                          253       1 vda1 100 0 2000 0 50 0 400 0 0 0 0\n";
+        assert_eq!(
+            parse_diskstats(diskstats, |name| name == "vda"),
+            (2000 * 512, 400 * 512)
+        );
+
+        let net_dev = "Inter-|   Receive                            |  Transmit\n \
+                        face |bytes    packets errs drop fifo frame compressed multicast|bytes\n    \
+                        lo:  999 1 0 0 0 0 0 0  999 1 0 0 0 0 0 0\n  \
+                        eth0: 1500 10 0 0 0 0 0 0  700 5 0 0 0 0 0 0\n";
+        assert_eq!(parse_net_dev(net_dev), (1500, 700));
+    }
+
+    #[test]
+    fn test_parse_pid_stat_handles_parenthesised_names() {
+        let stat = "42 (my (odd) app) S 1 42 42 0 -1 4194560 100 0 0 0 30 12 0 0 20 0 1 0 \
+                    500 10000000 25 18446744073709551615";
+        let sample = parse_pid_stat(stat, 4096).unwrap();
+        assert_eq!(sample.comm, "my (odd) app");
+        assert_eq!(sample.ticks, 42);
+        assert_eq!(sample.rss_bytes, 25 * 4096);
+        assert!(parse_pid_stat("garbage", 4096).is_none());
+    }
+
+    #[test]
+    fn test_collect_from_fake_proc() {
+        let proc_root = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = proc_root.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "stat",
+            "cpu  10 0 10 80 0 0 0 0 0 0\ncpu0 10 0 10 80 0 0 0 0 0 0\n",
+        );
+        write("meminfo", "MemTotal: 1000 kB\nMemAvailable: 400 kB\n");
+        write("net/dev", "eth0: 10 0 0 0 0 0 0 0 20 0 0 0 0 0 0 0\n");
+        write(
+            "1/stat",
+            "1 (init) S 0 1 1 0 -1 0 0 0 0 0 5 5 0 0 20 0 1 0 1 1 3 0",
+        );
+        write("1/cmdline", "/sbin/init\0--boot\0");
+        write(
+            "7/stat",
+            "7 (kworker) S 2 0 0 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 1 0 0 0",
+        );
+
+        let metrics = collect_from(
+            proc_root.path(),
+            proc_root.path(),
+            &proc_root.path().join("missing"),
+            &GuestMetricsRequest {
+                sample_ms: 0,
+                top: 1,
+            },
+        );
+        assert_eq!(metrics.cpus, 1);
+        assert_eq!(metrics.cpu_busy_ns, 20 * 10_000_000);
+        assert_eq!(metrics.memory_used_bytes, 600 * 1024);
+        assert_eq!(
+            (metrics.network_rx_bytes, metrics.network_tx_bytes),
+            (10, 20)
+        );
+        assert_eq!(metrics.processes, 2);
+        assert_eq!(metrics.top.len(), 1);
+        assert_eq!(metrics.top[0].pid, 1);
+        assert_eq!(metrics.top[0].command, "/sbin/init --boot");
+        assert!(metrics.cgroups.is_empty());
+    }
+
+    #[test]
+    fn test_cgroup_samples_read_v2_controllers() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = root.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("cgroup.procs", "1\n");
+        write(
+            "box-9-1/cpu.stat",
+            "usage_usec 1500\nuser_usec 1000\nsystem_usec 500\n",
+        );
+        write("box-9-1/memory.current", "8192\n");
+        write("box-9-1/memory.max", "max\n");
+        write(
+            "box-9-1/memory.events",
+            "low 0\nhigh 0\nmax 3\noom 1\noom_kill 1\n",
+        );
+        write("box-9-1/pids.current", "4\n");
+        write("box-3-2/memory.max", "1048576\n");
+
+        let cgroups = cgroup_samples(root.path());
+        assert_eq!(cgroups.len(), 2);
+        assert_eq!(cgroups[0].name, "box-3-2");
+        assert_eq!(cgroups[0].memory_limit_bytes, Some(1_048_576));
+        assert_eq!(cgroups[0].cpu_usage_ns, 0);
+        assert_eq!(
+            cgroups[1],
+            GuestCgroupMetrics {
+                name: "box-9-1".to_string(),
+                cpu_usage_ns: 1_500_000,
+                memory_bytes: 8192,
+                memory_limit_bytes: None,
+                pids: 4,
+                oom_kills: 1,
+            }
+        );
+    }
+}
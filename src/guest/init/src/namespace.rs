@@ -369,6 +369,15 @@ fn child_process(
     // and gather image supplemental groups. Done here (pre-fork, allocating) so
     // the pre_exec hook only performs async-signal-safe syscalls.
     let (process_user, supplemental_groups) = resolve_user_and_groups(user)?;
+    // Like a container runtime, give the user its passwd home unless the
+    // image or request set HOME.
+    if !env.iter().any(|(key, _)| *key == "HOME") {
+        if let Some(home) = process_user
+            .and_then(|process_user| crate::user::home_dir_for_uid("/", process_user.uid))
+        {
+            cmd.env("HOME", home);
+        }
+    }
 
     // Apply security restrictions + user before exec
     apply_security_before_exec(&mut cmd, process_user, supplemental_groups)?;
@@ -420,8 +429,10 @@ fn resolve_command_path(command: &str, env: &[(&str, &str)]) -> Option<PathBuf>
 }
 
 /// Resolve a container user string (`uid`, `uid:gid`, `root`, or a name) to a
-/// numeric [`ProcessUser`] plus its image supplementary groups, looking names up
-/// in the container rootfs (already pivoted to `/`). Returns `Ok((None, []))` when
+/// numeric [`ProcessUser`] plus its supplementary groups, looking names up in
+/// the container rootfs (already pivoted to `/`). Groups requested through
+/// `A3S_SEC_SUPPLEMENTAL_GROUPS` (`--group-add`, CRI `SupplementalGroups`) come
+/// first, followed by the user's image groups. Returns `Ok((None, groups))` when
 /// NO user is requested (run as default). When a user IS requested but cannot be
 /// resolved/parsed it returns `Err` (fail CLOSED): silently running as root
 /// instead would be a privilege escalation. Pure file reads — call pre-fork.
@@ -429,10 +440,30 @@ fn resolve_command_path(command: &str, env: &[(&str, &str)]) -> Option<PathBuf>
 fn resolve_user_and_groups(
     user: Option<&str>,
 ) -> Result<(Option<crate::user::ProcessUser>, Vec<u32>), NamespaceError> {
+    let mut groups = match std::env::var("A3S_SEC_SUPPLEMENTAL_GROUPS") {
+        Ok(csv) => crate::user::resolve_supplemental_groups(&csv, "/")
+            .map_err(NamespaceError::SecurityFailed)?,
+        Err(_) => Vec::new(),
+    };
     let Some(user) = user.map(str::trim).filter(|u| !u.is_empty()) else {
-        return Ok((None, Vec::new()));
+        return Ok((None, groups));
     };
-    // Names -> "uid:gid" via the container /etc/passwd; numeric/root pass through.
+    // A user the image has no account for gets one, with a home directory.
+    // If that fails (read-only rootfs), a numeric user still runs without a
+    // passwd entry and an unknown name fails closed below.
+    match crate::user::ensure_user_account(user, "/") {
+        Ok(Some(account)) => tracing::info!(
+            name = %account.name,
+            uid = account.uid,
+            gid = account.gid,
+            home = %account.home,
+            "Created container user account"
+        ),
+        Ok(None) => {}
+        Err(e) => tracing::warn!(user, error = %e, "Failed to create container user account"),
+    }
+    // Names -> "uid:gid" via the container /etc/passwd and /etc/group;
+    // numeric/root pass through.
     let resolved = crate::user::resolve_named_user(user, "/").unwrap_or_else(|| user.to_string());
     let mut process_user = match crate::user::parse_process_user(Some(&resolved)) {
         Ok(Some(pu)) => pu,
@@ -449,7 +480,14 @@ fn resolve_user_and_groups(
     if process_user.gid.is_none() {
         process_user.gid = crate::user::primary_gid_for_uid("/", process_user.uid);
     }
-    let groups = crate::user::resolve_image_groups("/", process_user.uid, process_user.gid, user);
+    groups.extend(crate::user::resolve_image_groups(
+        "/",
+        process_user.uid,
+        process_user.gid,
+        user,
+    ));
+    let mut seen = std::collections::HashSet::new();
+    groups.retain(|gid| seen.insert(*gid));
     Ok((Some(process_user), groups))
 }
 
@@ -532,7 +570,7 @@ fn apply_security_before_exec(
             //    server: supplemental groups -> capabilities -> setgid+setuid.
             //    Each step needs root/CAP_SET*; setuid is LAST because it clears
             //    the privileges needed by the earlier ones.
-            if process_user.is_some() && !supplemental_groups.is_empty() {
+            if !supplemental_groups.is_empty() {
                 let ret = libc::setgroups(
                     supplemental_groups.len() as _,
                     supplemental_groups.as_ptr() as *const libc::gid_t,
@@ -7,7 +7,17 @@
 //! Environment variables:
 //! - `A3S_NET_IP`: IPv4 address with prefix (e.g., "10.88.0.2/24")
 //! - `A3S_NET_GATEWAY`: Gateway IPv4 address (e.g., "10.88.0.1")
+//! - `A3S_NET_IP6`: IPv6 address with prefix on dual-stack networks
+//!   (e.g., "fd00:a3b0:a58::2/64")
+//! - `A3S_NET_GATEWAY6`: Gateway IPv6 address (e.g., "fd00:a3b0:a58::1")
 //! - `A3S_NET_DNS`: Comma-separated DNS servers (e.g., "8.8.8.8,8.8.4.4")
+//! - `A3S_NET_DNS_RECORDS`: Name records file for the embedded resolver
+//!   (bridge networks); when set, `/etc/resolv.conf` points at 127.0.0.11
+//! - `A3S_NET_EGRESS_POLICY`: `--network-policy` as JSON, enforced by
+//!   [`crate::egress`] and the embedded resolver
+//! - `A3S_NET_MAX_CONNS`: `--network-max-conns`, enforced by [`crate::egress`]
+//! - `A3S_NET_BANDWIDTH`: `--network-bandwidth` in bytes per second, applied
+//!   to eth0 by [`crate::shaping`]
 
 use std::fmt;
 use tracing::info;
@@ -19,8 +29,14 @@ pub struct GuestNetConfig {
     pub ip_cidr: String,
     /// Gateway address.
     pub gateway: String,
+    /// IPv6 address with prefix length on dual-stack networks.
+    pub ip6_cidr: Option<String>,
+    /// IPv6 gateway address.
+    pub gateway6: Option<String>,
     /// DNS servers.
     pub dns_servers: Vec<String>,
+    /// Name records served by the embedded resolver, if the host provided any.
+    pub dns_records: Option<String>,
 }
 
 /// Errors during guest network setup.
@@ -52,14 +68,24 @@ impl GuestNetConfig {
     pub fn from_env() -> Option<Self> {
         let ip_cidr = std::env::var("A3S_NET_IP").ok()?;
         let gateway = std::env::var("A3S_NET_GATEWAY").unwrap_or_default();
+        let ip6_cidr = std::env::var("A3S_NET_IP6").ok().filter(|v| !v.is_empty());
+        let gateway6 = std::env::var("A3S_NET_GATEWAY6")
+            .ok()
+            .filter(|v| !v.is_empty());
         let dns_servers: Vec<String> = std::env::var("A3S_NET_DNS")
             .map(|s| s.split(',').map(|d| d.trim().to_string()).collect())
             .unwrap_or_else(|_| vec!["8.8.8.8".to_string()]);
+        let dns_records = std::env::var("A3S_NET_DNS_RECORDS")
+            .ok()
+            .filter(|path| !path.is_empty());
 
         Some(Self {
             ip_cidr,
             gateway,
+            ip6_cidr,
+            gateway6,
             dns_servers,
+            dns_records,
         })
     }
 }
@@ -72,7 +98,9 @@ impl GuestNetConfig {
 ///    a. Assigns IP to eth0
 ///    b. Brings up eth0
 ///    c. Adds default route via gateway
-///    d. Writes /etc/resolv.conf
+///    d. On dual-stack networks, assigns the IPv6 address and default route
+///    e. Installs the egress firewall and bandwidth shaping, if requested
+///    f. Writes /etc/resolv.conf
 pub fn configure_guest_network() -> Result<(), Box<dyn std::error::Error>> {
     // Always bring up loopback — needed for listen() on 0.0.0.0 even in TSI mode
     #[cfg(target_os = "linux")]
@@ -94,6 +122,7 @@ pub fn configure_guest_network() -> Result<(), Box<dyn std::error::Error>> {
     info!(
         ip = %config.ip_cidr,
         gateway = %config.gateway,
+        ip6 = ?config.ip6_cidr,
         dns = ?config.dns_servers,
         "Configuring guest network"
     );
@@ -193,9 +222,72 @@ fn configure_interfaces(config: &GuestNetConfig) -> Result<(), Box<dyn std::erro
         add_default_route(&config.gateway)?;
     }
 
-    // Step 6: Write /etc/resolv.conf
-    info!(dns = ?config.dns_servers, "Writing /etc/resolv.conf");
-    write_resolv_conf(&config.dns_servers)?;
+    // Step 5b: Dual-stack networks also get an IPv6 address and route.
+    if let Some(ip6_cidr) = config.ip6_cidr.as_deref() {
+        // The address is statically assigned and unique within the network,
+        // so skip duplicate address detection instead of waiting it out.
+        let _ = std::fs::write("/proc/sys/net/ipv6/conf/eth0/accept_dad", "0");
+        info!(ip6 = %ip6_cidr, "Assigning IPv6 address to eth0");
+        add_address6("eth0", ip6_cidr)?;
+        if let Some(gateway6) = config.gateway6.as_deref() {
+            info!(gateway6 = %gateway6, "Adding IPv6 default route");
+            add_default_route(gateway6)?;
+        }
+    }
+
+    // Step 6: Install the egress firewall and bandwidth shaping before
+    // anything in the box can open a connection. A policy or limit that
+    // cannot be enforced fails the boot.
+    let egress_policy = crate::egress::policy_from_env().map_err(NetError::CommandFailed)?;
+    let max_conns = crate::egress::max_conns_from_env().map_err(NetError::CommandFailed)?;
+    if egress_policy.is_some() || max_conns.is_some() {
+        let policy = egress_policy.clone().unwrap_or_default();
+        info!(
+            allow = policy.allow.len(),
+            deny = policy.deny.len(),
+            max_conns = ?max_conns,
+            "Installing egress policy"
+        );
+        crate::egress::apply(&policy, max_conns, &config.dns_servers).map_err(|e| {
+            NetError::CommandFailed(format!("failed to install egress policy: {e}"))
+        })?;
+    }
+    if let Some(rate) = crate::shaping::bandwidth_from_env().map_err(NetError::CommandFailed)? {
+        info!(bytes_per_sec = rate, "Shaping eth0 bandwidth");
+        crate::shaping::apply("eth0", rate).map_err(|e| {
+            NetError::CommandFailed(format!("failed to apply bandwidth limit: {e}"))
+        })?;
+    }
+
+    // Step 7: Start the embedded resolver for peer names, then point
+    // /etc/resolv.conf at it (or straight at the upstreams if it cannot bind).
+    // Domain rules are enforced by the resolver, so with a policy it must run.
+    let nameservers = match config.dns_records.as_deref() {
+        Some(records) => match crate::dns_server::spawn_dns_server(
+            std::path::Path::new(records),
+            &config.dns_servers,
+            egress_policy.clone(),
+        ) {
+            Ok(()) => vec![a3s_box_core::dns::EMBEDDED_DNS_ADDR.to_string()],
+            Err(e) if egress_policy.is_some() => {
+                return Err(Box::new(NetError::CommandFailed(format!(
+                    "failed to start embedded DNS resolver for egress policy: {e}"
+                ))));
+            }
+            Err(e) => {
+                tracing::warn!("Failed to start embedded DNS resolver: {}", e);
+                config.dns_servers.clone()
+            }
+        },
+        None if egress_policy.is_some() => {
+            return Err(Box::new(NetError::CommandFailed(
+                "egress policy requires the embedded DNS resolver".to_string(),
+            )));
+        }
+        None => config.dns_servers.clone(),
+    };
+    info!(dns = ?nameservers, "Writing /etc/resolv.conf");
+    write_resolv_conf(&nameservers)?;
 
     info!("Guest network configuration complete");
     Ok(())
@@ -334,13 +426,17 @@ fn add_address(ifname: &str, ip_cidr: &str) -> Result<(), Box<dyn std::error::Er
     Ok(())
 }
 
-/// Add a default route via the given gateway using netlink (rtnetlink).
-/// Falls back to writing /proc/sys/net if netlink is unavailable.
+/// Add a default route via the given IPv4 or IPv6 gateway using netlink
+/// (rtnetlink).
 #[cfg(target_os = "linux")]
 fn add_default_route(gateway: &str) -> Result<(), Box<dyn std::error::Error>> {
-    use std::net::Ipv4Addr;
+    use std::net::IpAddr;
 
-    let gw: Ipv4Addr = gateway.parse()?;
+    let gw: IpAddr = gateway.parse()?;
+    let (family, gw_octets) = match gw {
+        IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
+        IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
+    };
 
     // Use raw socket + rtnetlink to add default route
     let sock = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_RAW, libc::NETLINK_ROUTE) };
@@ -370,11 +466,8 @@ fn add_default_route(gateway: &str) -> Result<(), Box<dyn std::error::Error>> {
         )));
     }
 
-    // Build RTM_NEWROUTE message
-    let gw_octets = gw.octets();
-
-    // nlmsghdr + rtmsg + RTA_GATEWAY attr
-    let rta_len = 4 + 4; // rta_len(2) + rta_type(2) + 4 bytes IPv4
+    // Build RTM_NEWROUTE message: nlmsghdr + rtmsg + RTA_GATEWAY attr
+    let rta_len = 4 + gw_octets.len(); // rta_len(2) + rta_type(2) + address
     let msg_len = std::mem::size_of::<libc::nlmsghdr>() + std::mem::size_of::<RtMsg>() + rta_len;
 
     let mut buf = vec![0u8; msg_len];
@@ -390,7 +483,7 @@ fn add_default_route(gateway: &str) -> Result<(), Box<dyn std::error::Error>> {
     // rtmsg
     let rtm_offset = std::mem::size_of::<libc::nlmsghdr>();
     let rtm = unsafe { &mut *(buf.as_mut_ptr().add(rtm_offset) as *mut RtMsg) };
-    rtm.rtm_family = libc::AF_INET as u8;
+    rtm.rtm_family = family as u8;
     rtm.rtm_dst_len = 0; // default route
     rtm.rtm_src_len = 0;
     #[allow(clippy::unnecessary_cast)]
@@ -412,7 +505,7 @@ fn add_default_route(gateway: &str) -> Result<(), Box<dyn std::error::Error>> {
     {
         rta.rta_type = libc::RTA_GATEWAY as u16;
     }
-    buf[rta_offset + 4..rta_offset + 8].copy_from_slice(&gw_octets);
+    buf[rta_offset + 4..rta_offset + rta_len].copy_from_slice(&gw_octets);
 
     // Send
     let sent = unsafe { libc::send(sock, buf.as_ptr() as *const _, buf.len(), 0) };
@@ -429,6 +522,66 @@ fn add_default_route(gateway: &str) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Add an IPv6 address to an interface using ioctl SIOCSIFADDR on an
+/// AF_INET6 socket.
+#[cfg(target_os = "linux")]
+fn add_address6(ifname: &str, ip_cidr: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use std::ffi::CString;
+
+    let (ip, prefix) = parse_ipv6_cidr(ip_cidr)
+        .ok_or_else(|| NetError::CommandFailed(format!("invalid IPv6 CIDR: {}", ip_cidr)))?;
+
+    let if_cstr = CString::new(ifname)?;
+    let ifindex = unsafe { libc::if_nametoindex(if_cstr.as_ptr()) };
+    if ifindex == 0 {
+        return Err(Box::new(NetError::CommandFailed(format!(
+            "interface {} not found",
+            ifname
+        ))));
+    }
+
+    let sock = unsafe { libc::socket(libc::AF_INET6, libc::SOCK_DGRAM, 0) };
+    if sock < 0 {
+        return Err(Box::new(NetError::CommandFailed(
+            "failed to create IPv6 socket (is IPv6 enabled in the guest kernel?)".to_string(),
+        )));
+    }
+
+    let req = In6Ifreq {
+        ifr6_addr: libc::in6_addr {
+            s6_addr: ip.octets(),
+        },
+        ifr6_prefixlen: u32::from(prefix),
+        ifr6_ifindex: ifindex as libc::c_int,
+    };
+    let result = unsafe { libc::ioctl(sock, libc::SIOCSIFADDR as _, &req) };
+    unsafe { libc::close(sock) };
+    if result < 0 {
+        return Err(Box::new(NetError::CommandFailed(format!(
+            "SIOCSIFADDR failed for {}: {}",
+            ifname, ip_cidr
+        ))));
+    }
+    Ok(())
+}
+
+/// Parse "fd00::2/64" into an address and prefix length.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_ipv6_cidr(ip_cidr: &str) -> Option<(std::net::Ipv6Addr, u8)> {
+    let (ip, prefix) = ip_cidr.split_once('/')?;
+    let prefix: u8 = prefix.parse().ok()?;
+    (prefix <= 128).then_some((ip.parse().ok()?, prefix))
+}
+
+/// Kernel `struct in6_ifreq` for IPv6 address ioctls.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct In6Ifreq {
+    ifr6_addr: libc::in6_addr,
+    ifr6_prefixlen: u32,
+    ifr6_ifindex: libc::c_int,
+}
+
 /// Minimal rtmsg struct for netlink route messages.
 #[cfg(target_os = "linux")]
 #[repr(C)]
@@ -472,12 +625,13 @@ fn prefix_to_netmask(prefix: u8) -> std::net::Ipv4Addr {
 }
 
 /// Write /etc/resolv.conf with the given DNS servers.
+///
+/// `search` and `options` lines the host staged (`--dns-search`,
+/// `--dns-option`) are kept; only the nameservers are replaced.
 #[cfg(target_os = "linux")]
 fn write_resolv_conf(dns_servers: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-    let mut content = String::from("# Generated by a3s-box guest init\n");
-    for server in dns_servers {
-        content.push_str(&format!("nameserver {}\n", server));
-    }
+    let existing = std::fs::read_to_string("/etc/resolv.conf").unwrap_or_default();
+    let content = resolv_conf_content(dns_servers, &existing);
 
     std::fs::write("/etc/resolv.conf", &content).map_err(|e| {
         Box::new(NetError::ResolvConf(format!(
@@ -489,6 +643,23 @@ fn write_resolv_conf(dns_servers: &[String]) -> Result<(), Box<dyn std::error::E
     Ok(())
 }
 
+/// Render resolv.conf with `dns_servers`, carrying over `search`/`options`
+/// lines from `existing`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn resolv_conf_content(dns_servers: &[String], existing: &str) -> String {
+    let mut content = String::from("# Generated by a3s-box guest init\n");
+    for server in dns_servers {
+        content.push_str(&format!("nameserver {}\n", server));
+    }
+    for line in existing.lines() {
+        if line.starts_with("search ") || line.starts_with("options ") {
+            content.push_str(line);
+            content.push('\n');
+        }
+    }
+    content
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -508,16 +679,53 @@ mod tests {
         std::env::set_var("A3S_NET_IP", "10.88.0.2/24");
         std::env::set_var("A3S_NET_GATEWAY", "10.88.0.1");
         std::env::set_var("A3S_NET_DNS", "8.8.8.8,1.1.1.1");
+        std::env::set_var("A3S_NET_DNS_RECORDS", "/etc/a3s-box/dns-records");
 
         let config = GuestNetConfig::from_env().unwrap();
         assert_eq!(config.ip_cidr, "10.88.0.2/24");
         assert_eq!(config.gateway, "10.88.0.1");
         assert_eq!(config.dns_servers, vec!["8.8.8.8", "1.1.1.1"]);
+        assert_eq!(
+            config.dns_records.as_deref(),
+            Some("/etc/a3s-box/dns-records")
+        );
 
         // Cleanup
         std::env::remove_var("A3S_NET_IP");
         std::env::remove_var("A3S_NET_GATEWAY");
         std::env::remove_var("A3S_NET_DNS");
+        std::env::remove_var("A3S_NET_DNS_RECORDS");
+    }
+
+    #[test]
+    #[serial]
+    fn test_guest_net_config_from_env_dual_stack() {
+        std::env::set_var("A3S_NET_IP", "10.88.0.2/24");
+        std::env::set_var("A3S_NET_IP6", "fd00:a3b0:a58::2/64");
+        std::env::set_var("A3S_NET_GATEWAY6", "fd00:a3b0:a58::1");
+
+        let config = GuestNetConfig::from_env().unwrap();
+        assert_eq!(config.ip6_cidr.as_deref(), Some("fd00:a3b0:a58::2/64"));
+        assert_eq!(config.gateway6.as_deref(), Some("fd00:a3b0:a58::1"));
+
+        std::env::remove_var("A3S_NET_IP6");
+        std::env::remove_var("A3S_NET_GATEWAY6");
+        let config = GuestNetConfig::from_env().unwrap();
+        assert!(config.ip6_cidr.is_none());
+        assert!(config.gateway6.is_none());
+
+        std::env::remove_var("A3S_NET_IP");
+    }
+
+    #[test]
+    fn test_parse_ipv6_cidr() {
+        assert_eq!(
+            parse_ipv6_cidr("fd00::2/64"),
+            Some(("fd00::2".parse().unwrap(), 64))
+        );
+        assert!(parse_ipv6_cidr("fd00::2").is_none());
+        assert!(parse_ipv6_cidr("fd00::2/129").is_none());
+        assert!(parse_ipv6_cidr("10.0.0.2/24").is_none());
     }
 
     #[test]
@@ -532,6 +740,18 @@ mod tests {
         std::env::remove_var("A3S_NET_IP");
     }
 
+    #[test]
+    fn test_resolv_conf_content_keeps_search_and_options() {
+        let existing = "nameserver 1.1.1.1\nsearch corp.example\noptions ndots:2\n";
+
+        let content = resolv_conf_content(&["127.0.0.11".to_string()], existing);
+
+        assert_eq!(
+            content,
+            "# Generated by a3s-box guest init\nnameserver 127.0.0.11\nsearch corp.example\noptions ndots:2\n"
+        );
+    }
+
     #[test]
     fn test_net_error_display() {
         let e = NetError::MissingEnv("A3S_NET_IP".to_string());
@@ -2,7 +2,7 @@
 use std::collections::HashMap;
 use std::io::{self, Read, Write};
 #[cfg(target_os = "linux")]
-use std::net::{Shutdown, TcpStream};
+use std::net::Shutdown;
 #[cfg(target_os = "linux")]
 use std::os::fd::{AsRawFd, OwnedFd};
 use std::sync::{Arc, Mutex};
@@ -14,6 +14,8 @@ use std::time::Duration;
 #[cfg(target_os = "linux")]
 use a3s_box_core::exec::WINDOWS_CONTROL_SIGNAL_FRAME;
 #[cfg(target_os = "linux")]
+use a3s_box_core::forward::decode_open_target;
+#[cfg(target_os = "linux")]
 use a3s_box_core::PORT_FWD_VSOCK_PORT;
 #[cfg(target_os = "linux")]
 use nix::sys::socket::{connect, socket, AddressFamily, SockFlag, SockType, VsockAddr};
@@ -21,6 +23,9 @@ use tracing::info;
 #[cfg(target_os = "linux")]
 use tracing::{debug, warn};
 
+#[cfg(target_os = "linux")]
+use crate::forward::{connect_target, TargetStream};
+
 const HOST_CID: u32 = 2;
 const ENV_WINDOWS_ENABLED: &str = "BOX_WINDOWS_PORT_FWD";
 const ENV_CRI_ENABLED: &str = "BOX_CRI_PORT_FWD";
@@ -38,7 +43,7 @@ fn decode_stop_signal_payload(payload: &[u8]) -> Option<i32> {
 
 type SharedWriter = Arc<Mutex<std::fs::File>>;
 #[cfg(target_os = "linux")]
-type StreamMap = Arc<Mutex<HashMap<u32, TcpStream>>>;
+type StreamMap = Arc<Mutex<HashMap<u32, TargetStream>>>;
 
 #[cfg(target_os = "linux")]
 pub fn run_port_forward_client(
@@ -185,21 +190,18 @@ fn serve_control(control: std::fs::File, request_shutdown: Option<fn(i32)>) -> i
 
         match frame.kind {
             FRAME_OPEN => {
-                if frame.payload.len() != 2 {
+                // A two-byte loopback port, or a target elsewhere in the guest.
+                let Some(target) = decode_open_target(&frame.payload) else {
                     write_frame(&writer, FRAME_OPEN_ACK, frame.stream_id, &[1])?;
                     continue;
-                }
+                };
 
-                let guest_port = u16::from_be_bytes([frame.payload[0], frame.payload[1]]);
-                match TcpStream::connect(("127.0.0.1", guest_port)) {
+                match connect_target(&target) {
                     Ok(stream) => {
-                        let _ = stream.set_nodelay(true);
                         let read_stream = stream.try_clone()?;
                         debug!(
                             stream_id = frame.stream_id,
-                            guest_port,
-                            peer = ?stream.peer_addr().ok(),
-                            local = ?stream.local_addr().ok(),
+                            %target,
                             "pf: connected guest target, spawned reader"
                         );
                         streams.lock().unwrap().insert(frame.stream_id, stream);
@@ -215,8 +217,8 @@ fn serve_control(control: std::fs::File, request_shutdown: Option<fn(i32)>) -> i
                         debug!(
                             error = %err,
                             stream_id = frame.stream_id,
-                            guest_port,
-                            "Failed to connect guest TCP target"
+                            %target,
+                            "Failed to connect guest target"
                         );
                         write_frame(&writer, FRAME_OPEN_ACK, frame.stream_id, &[1])?;
                     }
@@ -287,7 +289,7 @@ fn serve_control(control: std::fs::File, request_shutdown: Option<fn(i32)>) -> i
 #[cfg(target_os = "linux")]
 fn spawn_guest_reader(
     stream_id: u32,
-    mut stream: TcpStream,
+    mut stream: TargetStream,
     writer: SharedWriter,
     streams: StreamMap,
 ) {
@@ -0,0 +1,265 @@
+//! Guest process tree with per-owner attribution.
+//!
+//! Guest init registers every process it starts on someone's behalf — the
+//! container main, supervised services, exec commands, and PTY sessions —
+//! as a root. A process is attributed to the root it descends from. When a
+//! shell exits and leaves a background job behind, the job is reparented to
+//! guest init (PID 1, or the subreaper in host-sandbox mode) and its parent
+//! chain no longer leads to the root. Exec commands and services lead their
+//! own process group and PTY sessions their own session, and a reparented
+//! job keeps both, so such orphans are attributed by group or session.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use a3s_box_core::exec::{GuestProcess, GuestProcessTree, ProcessOwner, ProcessOwnerKind};
+
+/// Longest parent chain followed; guards against a `/proc` snapshot that
+/// changed under us into a loop.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone)]
+struct Root {
+    owner: ProcessOwner,
+    pgid: u32,
+    sid: u32,
+}
+
+static ROOTS: Mutex<Vec<Root>> = Mutex::new(Vec::new());
+
+static ORPHANS_REAPED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StatIds {
+    ppid: u32,
+    pgid: u32,
+    sid: u32,
+}
+
+/// Register `pid` as the root of processes started for `kind`. Call right
+/// after spawning it. Its process group and session are read again on every
+/// snapshot while it lives, so a root that has not called `setsid` yet is
+/// still attributed by session once it does.
+pub fn register(pid: u32, kind: ProcessOwnerKind, name: Option<String>) {
+    let ids = read_stat(Path::new("/proc"), pid).map(|(_, ids)| ids);
+    let mut roots = ROOTS.lock().unwrap_or_else(|e| e.into_inner());
+    roots.retain(|root| root.owner.root_pid != pid);
+    roots.push(Root {
+        owner: ProcessOwner {
+            kind,
+            root_pid: pid,
+            name,
+        },
+        pgid: ids.map_or(0, |ids| ids.pgid),
+        sid: ids.map_or(0, |ids| ids.sid),
+    });
+}
+
+/// Count an orphan reaped by the PID 1 supervision loop.
+pub fn record_orphan_reaped() {
+    ORPHANS_REAPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The current process tree. Roots with no live process left are dropped.
+pub fn snapshot() -> GuestProcessTree {
+    let mut roots = ROOTS.lock().unwrap_or_else(|e| e.into_inner());
+    let processes = snapshot_from(Path::new("/proc"), std::process::id(), &mut roots);
+    roots.retain(|root| {
+        processes
+            .iter()
+            .any(|process| process.owner.as_ref() == Some(&root.owner))
+    });
+    GuestProcessTree {
+        processes,
+        orphans_reaped: ORPHANS_REAPED.load(Ordering::Relaxed),
+    }
+}
+
+fn snapshot_from(proc_root: &Path, init_pid: u32, roots: &mut [Root]) -> Vec<GuestProcess> {
+    let Ok(entries) = std::fs::read_dir(proc_root) else {
+        return Vec::new();
+    };
+    let mut stats: HashMap<u32, (String, StatIds)> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| Some((pid, read_stat(proc_root, pid)?)))
+        .collect();
+    // Kernel threads descend from kthreadd (PID 2), not from guest init.
+    stats.retain(|pid, (_, ids)| *pid != 2 && ids.ppid != 2);
+    for root in roots.iter_mut() {
+        if let Some((_, ids)) = stats.get(&root.owner.root_pid) {
+            root.pgid = ids.pgid;
+            root.sid = ids.sid;
+        }
+    }
+    let roots: &[Root] = roots;
+
+    let init_ids = stats.get(&init_pid).map(|(_, ids)| *ids);
+    // A group or session shared with guest init says nothing about the owner.
+    let distinct = |id: u32, init_id: Option<u32>| id != 0 && Some(id) != init_id;
+    let by_pid: HashMap<u32, &Root> = roots
+        .iter()
+        .map(|root| (root.owner.root_pid, root))
+        .collect();
+
+    let mut processes: Vec<GuestProcess> = stats
+        .iter()
+        .map(|(&pid, (comm, ids))| {
+            let mut owner = None;
+            let mut reparented = false;
+            let mut current = pid;
+            for _ in 0..MAX_DEPTH {
+                if current == init_pid {
+                    break;
+                }
+                if let Some(root) = by_pid.get(&current) {
+                    owner = Some(root.owner.clone());
+                    break;
+                }
+                let Some((_, current_ids)) = stats.get(&current) else {
+                    break;
+                };
+                reparented = current_ids.ppid == init_pid;
+                current = current_ids.ppid;
+            }
+            let mut orphaned = false;
+            if owner.is_none() && reparented {
+                let root = roots
+                    .iter()
+                    .find(|root| {
+                        root.pgid == ids.pgid && distinct(ids.pgid, init_ids.map(|ids| ids.pgid))
+                    })
+                    .or_else(|| {
+                        roots.iter().find(|root| {
+                            root.sid == ids.sid && distinct(ids.sid, init_ids.map(|ids| ids.sid))
+                        })
+                    });
+                if let Some(root) = root {
+                    owner = Some(root.owner.clone());
+                    orphaned = true;
+                }
+            }
+            GuestProcess {
+                pid,
+                ppid: ids.ppid,
+                pgid: ids.pgid,
+                sid: ids.sid,
+                command: crate::metrics::process_command(proc_root, pid, comm),
+                owner,
+                orphaned,
+            }
+        })
+        .collect();
+    processes.sort_by_key(|process| process.pid);
+    processes
+}
+
+/// `comm`, parent, process group, and session from `/proc/<pid>/stat`.
+fn read_stat(proc_root: &Path, pid: u32) -> Option<(String, StatIds)> {
+    let stat = std::fs::read_to_string(proc_root.join(pid.to_string()).join("stat")).ok()?;
+    // `comm` may itself contain spaces and parentheses; it ends at the last ')'.
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?.to_string();
+    let mut fields = stat.get(close + 1..)?.split_whitespace().skip(1);
+    let mut next = || fields.next()?.parse::<u32>().ok();
+    let ids = StatIds {
+        ppid: next()?,
+        pgid: next()?,
+        sid: next()?,
+    };
+    Some((comm, ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(kind: ProcessOwnerKind, pid: u32, pgid: u32, sid: u32) -> Root {
+        Root {
+            owner: ProcessOwner {
+                kind,
+                root_pid: pid,
+                name: None,
+            },
+            pgid,
+            sid,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_attributes_descendants_and_orphans() {
+        let proc_root = tempfile::tempdir().unwrap();
+        let write = |pid: u32, comm: &str, ppid: u32, pgid: u32, sid: u32| {
+            let dir = proc_root.path().join(pid.to_string());
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join("stat"),
+                format!("{pid} ({comm}) S {ppid} {pgid} {sid} 0 -1 0"),
+            )
+            .unwrap();
+        };
+        write(1, "init", 0, 0, 0);
+        write(2, "kthreadd", 0, 0, 0);
+        write(3, "kworker/0:0", 2, 0, 0);
+        // Container main and a worker it forked.
+        write(10, "app", 1, 0, 0);
+        write(11, "worker", 10, 0, 0);
+        // An exec shell that backgrounded a job and exited: the job now
+        // hangs off init but keeps the exec's process group.
+        write(21, "sleep", 1, 20, 0);
+        // A PTY shell's job, left behind in the PTY session.
+        write(31, "tail", 1, 31, 30);
+        // A stray process nobody started.
+        write(40, "stray", 1, 0, 0);
+
+        let mut roots = [
+            // Registered before it set up its process group.
+            root(ProcessOwnerKind::Main, 10, 7, 7),
+            root(ProcessOwnerKind::Exec, 20, 20, 0),
+            root(ProcessOwnerKind::Pty, 30, 30, 30),
+        ];
+        let processes = snapshot_from(proc_root.path(), 1, &mut roots);
+        let owner = |pid: u32| {
+            let process = processes.iter().find(|process| process.pid == pid).unwrap();
+            (
+                process.owner.as_ref().map(|owner| owner.root_pid),
+                process.orphaned,
+            )
+        };
+
+        assert_eq!(
+            processes.iter().map(|p| p.pid).collect::<Vec<_>>(),
+            vec![1, 10, 11, 21, 31, 40]
+        );
+        assert_eq!(owner(1), (None, false));
+        assert_eq!(owner(10), (Some(10), false));
+        assert_eq!(owner(11), (Some(10), false));
+        assert_eq!(owner(21), (Some(20), true));
+        assert_eq!(owner(31), (Some(30), true));
+        // Sharing init's group and session is not evidence of an owner.
+        assert_eq!(owner(40), (None, false));
+        assert_eq!(processes[1].command, "[app]");
+        assert_eq!((roots[0].pgid, roots[0].sid), (0, 0));
+    }
+
+    #[test]
+    fn test_read_stat_handles_parenthesised_names() {
+        let proc_root = tempfile::tempdir().unwrap();
+        let dir = proc_root.path().join("42");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("stat"), "42 (my (odd) app) S 7 42 5 0 -1").unwrap();
+        let (comm, ids) = read_stat(proc_root.path(), 42).unwrap();
+        assert_eq!(comm, "my (odd) app");
+        assert_eq!(
+            ids,
+            StatIds {
+                ppid: 7,
+                pgid: 42,
+                sid: 5
+            }
+        );
+    }
+}
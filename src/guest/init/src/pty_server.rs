@@ -144,7 +144,8 @@ fn run_pty_accept_loop(sock_fd: std::os::fd::OwnedFd) -> Result<(), Box<dyn std:
 /// 6. On process exit → send PtyExit frame
 #[cfg(target_os = "linux")]
 fn handle_pty_connection(fd: std::os::fd::OwnedFd) -> Result<(), Box<dyn std::error::Error>> {
-    use a3s_box_core::pty::{parse_frame, read_frame, write_error, write_exit, PtyFrame};
+    use a3s_box_core::exec::ProcessOwnerKind;
+    use a3s_box_core::pty::{parse_frame, read_frame, write_error, PtyFrame};
     use nix::pty::openpty;
     use nix::unistd::{dup2, execvp, fork, setsid, ForkResult};
     use std::ffi::CString;
@@ -168,6 +169,19 @@ fn handle_pty_connection(fd: std::os::fd::OwnedFd) -> Result<(), Box<dyn std::er
         }
     };
 
+    // A resume takes back a parked session; without one it starts `cmd` as an
+    // ordinary session that is not parked again.
+    let park_as = match request.session.as_deref() {
+        Some(name) if request.resume => {
+            if let Some(parked) = crate::pty_sessions::take(name) {
+                info!(session = name, "PTY session resumed");
+                return resume_session(stream, parked, &request);
+            }
+            None
+        }
+        session => session.map(str::to_string),
+    };
+
     if request.cmd.is_empty() {
         write_error(&mut stream, "Empty command")?;
         return Ok(());
@@ -208,16 +222,19 @@ fn handle_pty_connection(fd: std::os::fd::OwnedFd) -> Result<(), Box<dyn std::er
     // running with full capabilities, no seccomp and no_new_privs unset despite
     // the pod's securityContext (#11). The same async-signal-safe namespace::
     // primitives the exec path uses are applied in the child below.
-    let mut sec_supplemental_groups: Vec<u32> = request
+    let mut sec_supplemental_groups: Vec<u32> = match request
         .env
         .iter()
         .find_map(|entry| entry.strip_prefix("A3S_SEC_SUPPLEMENTAL_GROUPS="))
-        .map(|csv| {
-            csv.split(',')
-                .filter_map(|gid| gid.trim().parse::<u32>().ok())
-                .collect()
-        })
-        .unwrap_or_default();
+        .map(|csv| crate::user::resolve_supplemental_groups(csv, resolve_rootfs))
+        .transpose()
+    {
+        Ok(groups) => groups.unwrap_or_default(),
+        Err(error) => {
+            write_error(&mut stream, &error)?;
+            return Ok(());
+        }
+    };
     if let Some(process_user) = process_user {
         sec_supplemental_groups.extend(resolve_image_groups(
             resolve_rootfs,
@@ -350,6 +367,10 @@ fn handle_pty_connection(fd: std::os::fd::OwnedFd) -> Result<(), Box<dyn std::er
         }
     }
 
+    // Namespaces are opened before the fork; the child only enters them.
+    let namespaces =
+        crate::container_ns::ContainerNamespaces::resolve(&request.env, request.rootfs.as_deref());
+
     // Step 2: Allocate PTY
     let pty = openpty(None, None)?;
     let master_fd = pty.master;
@@ -411,6 +432,13 @@ fn handle_pty_connection(fd: std::os::fd::OwnedFd) -> Result<(), Box<dyn std::er
                 drop(slave_fd);
             }
 
+            // Enter the container's PID/mount namespaces, if it has private
+            // ones. This forks; the namespaced child carries on below.
+            if let Err(error) = unsafe { namespaces.enter() } {
+                eprintln!("Failed to enter container namespaces: {error}");
+                std::process::exit(127);
+            }
+
             // Apply environment variables
             for entry in &request.env {
                 if let Some((key, value)) = entry.split_once('=') {
@@ -524,18 +552,97 @@ fn handle_pty_connection(fd: std::os::fd::OwnedFd) -> Result<(), Box<dyn std::er
 
             // Register the PTY child with the reaper so PID 1 leaves it for us to
             // reap (relay_pty_data waitpid's it for the real exit code). The guard
-            // unregisters when this branch returns.
-            let _reap_guard = crate::reaper::manage_pid(child.as_raw());
+            // unregisters when the session ends.
+            let reap_guard = crate::reaper::manage_pid(child.as_raw());
+            crate::process_tree::register(
+                child.as_raw() as u32,
+                ProcessOwnerKind::Pty,
+                request.cmd.first().cloned(),
+            );
+
+            finish_session(stream, master_fd, child, reap_guard, park_as);
+            Ok(())
+        }
+    }
+}
 
-            let exit_code = relay_pty_data(&mut stream, &master_fd, child);
+/// Relay a started or resumed session until its process exits, or park it
+/// under `park_as` if the host disconnects first.
+#[cfg(target_os = "linux")]
+fn finish_session(
+    mut stream: std::fs::File,
+    master: std::os::fd::OwnedFd,
+    child: nix::unistd::Pid,
+    reap_guard: crate::reaper::ManagedChild,
+    park_as: Option<String>,
+) {
+    use a3s_box_core::pty::write_exit;
+
+    let exit_code = match (relay_pty_data(&mut stream, &master, child), park_as) {
+        (Some(exit_code), _) => exit_code,
+        (None, Some(name)) => {
+            crate::pty_sessions::park(
+                &name,
+                crate::pty_sessions::ParkedSession {
+                    master,
+                    child,
+                    reap_guard,
+                    backlog: Vec::new(),
+                    exit_code: None,
+                },
+            );
+            return;
+        }
+        (None, None) => terminate_and_reap(child),
+    };
 
-            // Send exit frame
-            write_exit(&mut stream, exit_code).ok();
+    // Send exit frame
+    write_exit(&mut stream, exit_code).ok();
 
-            info!(exit_code, "PTY session ended");
+    info!(exit_code, "PTY session ended");
+    drop(reap_guard);
+}
 
-            Ok(())
-        }
+/// Hand a parked session to a new connection: replay what it wrote while
+/// detached, then relay as usual. It is parked again on the next disconnect.
+#[cfg(target_os = "linux")]
+fn resume_session(
+    mut stream: std::fs::File,
+    parked: crate::pty_sessions::ParkedSession,
+    request: &a3s_box_core::pty::PtyRequest,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use a3s_box_core::pty::{write_data, write_exit, MAX_FRAME_PAYLOAD};
+    use std::os::fd::AsRawFd;
+
+    set_winsize(parked.master.as_raw_fd(), request.cols, request.rows);
+    for chunk in parked.backlog.chunks(MAX_FRAME_PAYLOAD) {
+        write_data(&mut stream, chunk)?;
+    }
+    if let Some(exit_code) = parked.exit_code {
+        write_exit(&mut stream, exit_code).ok();
+        info!(exit_code, "PTY session ended while detached");
+        return Ok(());
+    }
+    finish_session(
+        stream,
+        parked.master,
+        parked.child,
+        parked.reap_guard,
+        request.session.clone(),
+    );
+    Ok(())
+}
+
+/// Kill a PTY child whose host went away and reap it.
+#[cfg(target_os = "linux")]
+pub(crate) fn terminate_and_reap(child: nix::unistd::Pid) -> i32 {
+    use nix::sys::wait::{waitpid, WaitStatus};
+
+    terminate_pty_child(child);
+    match waitpid(child, None) {
+        Ok(WaitStatus::Exited(_, code)) => code,
+        Ok(WaitStatus::Signaled(_, sig, _)) => 128 + sig as i32,
+        _ => 1,
     }
 }
 
@@ -596,13 +703,14 @@ fn apply_rootfs_chroot(rootfs: &str, workdir: &str) -> std::io::Result<()> {
 /// - Data from PTY master → send as PtyData frames to host
 /// - Frames from host → write PtyData to PTY master, handle PtyResize
 ///
-/// Returns the child process exit code.
+/// Returns the child process exit code, or `None` if the host disconnected
+/// while the child was still running.
 #[cfg(target_os = "linux")]
 fn relay_pty_data(
     stream: &mut std::fs::File,
     master: &std::os::fd::OwnedFd,
     child: nix::unistd::Pid,
-) -> i32 {
+) -> Option<i32> {
     use a3s_box_core::pty::{
         parse_frame, read_frame, write_data, PtyFrame, FRAME_PTY_DATA, FRAME_PTY_ERROR,
         FRAME_PTY_RESIZE,
@@ -740,17 +848,8 @@ fn relay_pty_data(
         }
     }
 
-    // Ensure child is reaped
-    if !child_exited {
-        terminate_pty_child(child);
-        match waitpid(child, None) {
-            Ok(WaitStatus::Exited(_, code)) => exit_code = code,
-            Ok(WaitStatus::Signaled(_, sig, _)) => exit_code = 128 + sig as i32,
-            _ => exit_code = 1,
-        }
-    }
-
-    exit_code
+    // The caller kills or parks a child that is still running.
+    child_exited.then_some(exit_code)
 }
 
 #[cfg(target_os = "linux")]
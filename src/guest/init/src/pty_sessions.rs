@@ -0,0 +1,141 @@
+//! Named PTY sessions that outlive their host connection.
+//!
+//! A PTY request that names a session (`run -it` names the box's main
+//! process [`a3s_box_core::pty::MAIN_PTY_SESSION`]) is parked instead of
+//! killed when the host disconnects first, as it does on a detach key. A
+//! drainer thread keeps reading the terminal so the process never blocks on
+//! output, retains the most recent output, and records the exit code if the
+//! process ends while detached. A later request with `resume` set takes the
+//! session back, replays the retained output, and continues the relay.
+
+use std::collections::HashMap;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use tracing::{info, warn};
+
+use crate::reaper::ManagedChild;
+
+/// Most output retained for a parked session; older output is dropped.
+const PARKED_BACKLOG_BYTES: usize = 64 * 1024;
+
+/// How often the drainer checks for a resume while the terminal is idle.
+const DRAIN_POLL_MS: i32 = 100;
+
+/// A session handed back to a resuming connection.
+pub(crate) struct ParkedSession {
+    pub master: OwnedFd,
+    pub child: Pid,
+    pub reap_guard: ManagedChild,
+    /// Output the process wrote while detached, oldest first.
+    pub backlog: Vec<u8>,
+    /// Set if the process exited while detached.
+    pub exit_code: Option<i32>,
+}
+
+struct Drainer {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<ParkedSession>,
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, Drainer>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, Drainer>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Park `session` under `name`. A session already parked under the name is
+/// killed, since only one can be resumed.
+pub(crate) fn park(name: &str, session: ParkedSession) {
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = Arc::clone(&stop);
+        std::thread::spawn(move || drain(session, &stop))
+    };
+    let previous = sessions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), Drainer { stop, thread });
+    if let Some(previous) = previous {
+        warn!(session = name, "Replacing parked PTY session");
+        if let Some(session) = stop_drainer(previous) {
+            crate::pty_server::terminate_and_reap(session.child);
+        }
+    }
+    info!(session = name, "PTY session parked");
+}
+
+/// Take back the session parked under `name`, if any.
+pub(crate) fn take(name: &str) -> Option<ParkedSession> {
+    let drainer = sessions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(name)?;
+    stop_drainer(drainer)
+}
+
+fn stop_drainer(drainer: Drainer) -> Option<ParkedSession> {
+    drainer.stop.store(true, Ordering::SeqCst);
+    drainer.thread.join().ok()
+}
+
+fn drain(mut session: ParkedSession, stop: &AtomicBool) -> ParkedSession {
+    let mut buf = [0u8; 4096];
+    while !stop.load(Ordering::SeqCst) {
+        let mut fds = [libc::pollfd {
+            fd: session.master.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        // SAFETY: `fds` is a valid array of one pollfd entry.
+        let ready = unsafe { libc::poll(fds.as_mut_ptr(), 1, DRAIN_POLL_MS) };
+        if ready > 0 && fds[0].revents & libc::POLLIN != 0 {
+            match nix::unistd::read(session.master.as_raw_fd(), &mut buf) {
+                Ok(n) if n > 0 => retain(&mut session.backlog, &buf[..n]),
+                _ => {}
+            }
+        } else if ready > 0 {
+            // Hung up: the process and its children closed the terminal.
+            std::thread::sleep(std::time::Duration::from_millis(DRAIN_POLL_MS as u64));
+        }
+
+        if session.exit_code.is_none() {
+            match waitpid(session.child, Some(WaitPidFlag::WNOHANG)) {
+                Ok(WaitStatus::Exited(_, code)) => session.exit_code = Some(code),
+                Ok(WaitStatus::Signaled(_, signal, _)) => {
+                    session.exit_code = Some(128 + signal as i32)
+                }
+                _ => {}
+            }
+        }
+    }
+    session
+}
+
+/// Append `data` to `backlog`, dropping the oldest bytes past the limit.
+fn retain(backlog: &mut Vec<u8>, data: &[u8]) {
+    backlog.extend_from_slice(data);
+    if backlog.len() > PARKED_BACKLOG_BYTES {
+        let excess = backlog.len() - PARKED_BACKLOG_BYTES;
+        backlog.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retain_keeps_the_newest_output() {
+        let mut backlog = Vec::new();
+        retain(&mut backlog, b"hello");
+        assert_eq!(backlog, b"hello");
+
+        retain(&mut backlog, &vec![b'x'; PARKED_BACKLOG_BYTES]);
+        assert_eq!(backlog.len(), PARKED_BACKLOG_BYTES);
+        assert!(backlog.iter().all(|&byte| byte == b'x'));
+    }
+}
@@ -14,6 +14,10 @@
 //! non-destructively (`waitid` with `WNOWAIT`): it reaps only the container (→
 //! lifecycle) and unmanaged children (true orphans + the sidecar), and leaves
 //! managed children for their handler to reap.
+//!
+//! Only PID 1 inherits orphans by default. When guest-init runs under another
+//! init (host-sandbox mode without its own PID namespace), it registers as a
+//! child subreaper so orphans still come back to it instead of escaping.
 
 use std::sync::Mutex;
 
@@ -67,6 +71,23 @@ pub fn manage_pid(pid: i32) -> ManagedChild {
     ManagedChild(pid)
 }
 
+/// Make this process the reaper for orphaned descendants. A no-op as PID 1,
+/// which the kernel already treats as the reaper of its namespace.
+#[cfg(target_os = "linux")]
+pub fn become_subreaper() -> std::io::Result<()> {
+    if std::process::id() == 1 {
+        return Ok(());
+    }
+    // SAFETY: PR_SET_CHILD_SUBREAPER takes a plain integer flag and touches no
+    // memory of ours.
+    let ret = unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0) };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
 /// Whether `pid` is owned by a request handler (the loop must not reap it).
 pub fn is_managed(pid: i32) -> bool {
     MANAGED
@@ -0,0 +1,464 @@
+//! Supervisor for the extra services declared in `BOX_SERVICES`.
+//!
+//! Each service gets a thread that waits for its dependencies, spawns it as
+//! a handler-managed child (so the PID 1 loop leaves its exit status alone),
+//! and applies its restart policy with exponential backoff when it exits.
+//! Services with a health check get a second thread running the check while
+//! they are up. Every state change is recorded in a process-wide registry
+//! that the exec server streams to the host on a
+//! [`GuestSessionRequest::ServiceEvents`] request.
+//!
+//! [`GuestSessionRequest::ServiceEvents`]: a3s_box_core::exec::GuestSessionRequest::ServiceEvents
+
+use std::collections::HashMap;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use a3s_box_core::exec::ProcessOwnerKind;
+use a3s_box_core::service::{
+    restart_delay, service_start_order, ServiceHealthCheck, ServiceSpec, ServiceState,
+    ServiceStatus, SERVICES_ENV, SERVICE_STABLE_AFTER,
+};
+use tracing::{info, warn};
+
+/// How often a running health check is polled for completion.
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+struct Registry {
+    statuses: Vec<ServiceStatus>,
+    subscribers: Vec<mpsc::Sender<ServiceStatus>>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    statuses: Vec::new(),
+    subscribers: Vec::new(),
+});
+
+/// Signalled on every state change, for services waiting on dependencies.
+static CHANGED: Condvar = Condvar::new();
+
+/// Set once guest init starts shutting down; no service is restarted after.
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+fn registry() -> MutexGuard<'static, Registry> {
+    // Recover from a poisoned lock rather than panicking inside PID 1.
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Parse the service list from the environment. `Ok(None)` when unset.
+pub fn from_env() -> Result<Option<Vec<ServiceSpec>>, String> {
+    let Ok(raw) = std::env::var(SERVICES_ENV) else {
+        return Ok(None);
+    };
+    let services: Vec<ServiceSpec> =
+        serde_json::from_str(&raw).map_err(|e| format!("invalid {SERVICES_ENV}: {e}"))?;
+    service_start_order(&services)?;
+    Ok(Some(services))
+}
+
+/// Start supervising `services`. Returns once every supervisor thread is
+/// running; the services themselves start as their dependencies come up.
+pub fn start(services: Vec<ServiceSpec>) -> Result<(), String> {
+    let order = service_start_order(&services)?;
+    let health_checked: Arc<HashMap<String, bool>> = Arc::new(
+        services
+            .iter()
+            .map(|service| (service.name.clone(), service.health_check.is_some()))
+            .collect(),
+    );
+    {
+        let mut registry = registry();
+        for position in &order {
+            let name = services[*position].name.clone();
+            registry.statuses.push(status(&name, ServiceState::Waiting));
+        }
+    }
+    for position in order {
+        let spec = services[position].clone();
+        let health_checked = Arc::clone(&health_checked);
+        std::thread::Builder::new()
+            .name(format!("service-{}", spec.name))
+            .spawn(move || supervise(spec, &health_checked))
+            .map_err(|e| format!("failed to start supervisor thread: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Stop restarting services; called when guest init begins shutting down.
+pub fn shutdown() {
+    STOPPING.store(true, Ordering::SeqCst);
+    CHANGED.notify_all();
+}
+
+/// The current status of every service, and a receiver for later changes.
+pub fn subscribe() -> (Vec<ServiceStatus>, mpsc::Receiver<ServiceStatus>) {
+    let (sender, receiver) = mpsc::channel();
+    let mut registry = registry();
+    registry.subscribers.push(sender);
+    (registry.statuses.clone(), receiver)
+}
+
+fn status(name: &str, state: ServiceState) -> ServiceStatus {
+    ServiceStatus {
+        name: name.to_string(),
+        state,
+        pid: None,
+        restarts: 0,
+        exit_code: None,
+        message: None,
+    }
+}
+
+/// Record a state change and fan it out to subscribers.
+fn publish(status: ServiceStatus) {
+    let mut registry = registry();
+    if let Some(current) = registry
+        .statuses
+        .iter_mut()
+        .find(|current| current.name == status.name)
+    {
+        *current = status.clone();
+    } else {
+        registry.statuses.push(status.clone());
+    }
+    registry
+        .subscribers
+        .retain(|subscriber| subscriber.send(status.clone()).is_ok());
+    drop(registry);
+    CHANGED.notify_all();
+}
+
+/// Apply a health result, unless the run it was measured on has ended.
+fn publish_health(name: &str, pid: u32, state: ServiceState, message: Option<String>) {
+    let current = registry()
+        .statuses
+        .iter()
+        .find(|current| current.name == name && current.pid == Some(pid))
+        .cloned();
+    if let Some(mut current) = current {
+        if current.state != state {
+            current.state = state;
+            current.message = message;
+            publish(current);
+        }
+    }
+}
+
+/// Block until every dependency of `spec` is up. `false` on shutdown.
+fn wait_for_dependencies(spec: &ServiceSpec, health_checked: &HashMap<String, bool>) -> bool {
+    let mut registry = registry();
+    loop {
+        if STOPPING.load(Ordering::SeqCst) {
+            return false;
+        }
+        let ready = spec.depends_on.iter().all(|dependency| {
+            registry.statuses.iter().any(|status| {
+                &status.name == dependency
+                    && status
+                        .state
+                        .satisfies_dependency(health_checked[dependency.as_str()])
+            })
+        });
+        if ready {
+            return true;
+        }
+        registry = CHANGED.wait(registry).unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+fn supervise(spec: ServiceSpec, health_checked: &HashMap<String, bool>) {
+    if !wait_for_dependencies(&spec, health_checked) {
+        return;
+    }
+
+    let mut restarts = 0u32;
+    let mut backoff = 0u32;
+    loop {
+        let mut current = status(&spec.name, ServiceState::Running);
+        current.restarts = restarts;
+
+        let started = Instant::now();
+        let (mut child, _guard) = match crate::reaper::spawn_managed(|| command(&spec).spawn()) {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                warn!(service = %spec.name, error = %e, "Failed to start service");
+                current.state = ServiceState::Failed;
+                current.message = Some(e.to_string());
+                publish(current);
+                return;
+            }
+        };
+        let pid = child.id();
+        crate::process_tree::register(pid, ProcessOwnerKind::Service, Some(spec.name.clone()));
+        info!(service = %spec.name, pid, restarts, "Service started");
+        current.pid = Some(pid);
+        publish(current.clone());
+
+        let checking = Arc::new(AtomicBool::new(true));
+        if let Some(check) = spec.health_check.clone() {
+            let name = spec.name.clone();
+            let checking = Arc::clone(&checking);
+            std::thread::spawn(move || run_health_checks(&name, pid, &check, &checking));
+        }
+
+        let exit_code = match child.wait() {
+            Ok(status) => exit_code(status),
+            Err(e) => {
+                warn!(service = %spec.name, pid, error = %e, "Failed to wait for service");
+                1
+            }
+        };
+        checking.store(false, Ordering::SeqCst);
+        current.pid = None;
+        current.exit_code = Some(exit_code);
+
+        if STOPPING.load(Ordering::SeqCst) || !spec.restart.should_restart(exit_code) {
+            info!(service = %spec.name, exit_code, "Service exited");
+            current.state = ServiceState::Exited;
+            publish(current);
+            return;
+        }
+        if started.elapsed() >= SERVICE_STABLE_AFTER {
+            backoff = 0;
+        }
+        let delay = restart_delay(backoff);
+        warn!(
+            service = %spec.name,
+            exit_code,
+            delay_ms = delay.as_millis() as u64,
+            "Service exited; restarting"
+        );
+        current.state = ServiceState::Restarting;
+        publish(current);
+        std::thread::sleep(delay);
+        backoff = backoff.saturating_add(1);
+        restarts = restarts.saturating_add(1);
+        if STOPPING.load(Ordering::SeqCst) {
+            return;
+        }
+    }
+}
+
+fn command(spec: &ServiceSpec) -> Command {
+    use std::os::unix::process::CommandExt;
+
+    let mut command = Command::new(&spec.command[0]);
+    // Lead a process group so jobs the service leaves behind are still
+    // attributed to it once they are reparented.
+    command
+        .process_group(0)
+        .args(&spec.command[1..])
+        .current_dir(spec.working_dir.as_deref().unwrap_or("/"))
+        .stdin(Stdio::null());
+    for entry in &spec.env {
+        if let Some((key, value)) = entry.split_once('=') {
+            command.env(key, value);
+        }
+    }
+    command
+}
+
+fn exit_code(status: ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
+
+fn run_health_checks(name: &str, pid: u32, check: &ServiceHealthCheck, checking: &AtomicBool) {
+    let interval = Duration::from_millis(check.interval_ms.max(1));
+    let timeout = Duration::from_millis(check.timeout_ms.max(1));
+    let mut failures = 0u32;
+    loop {
+        std::thread::sleep(interval);
+        if !checking.load(Ordering::SeqCst) {
+            return;
+        }
+        match run_health_check(&check.command, timeout) {
+            Ok(()) => {
+                failures = 0;
+                publish_health(name, pid, ServiceState::Healthy, None);
+            }
+            Err(message) => {
+                failures = failures.saturating_add(1);
+                if failures >= check.retries.max(1) {
+                    publish_health(name, pid, ServiceState::Unhealthy, Some(message));
+                }
+            }
+        }
+    }
+}
+
+/// Run one health check to completion, killing it after `timeout`.
+fn run_health_check(argv: &[String], timeout: Duration) -> Result<(), String> {
+    let (mut child, _guard) = crate::reaper::spawn_managed(|| {
+        Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    })
+    .map_err(|e| format!("health check failed to start: {e}"))?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => {
+                return Err(format!("health check exited with {}", exit_code(status)))
+            }
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "health check timed out after {}ms",
+                    timeout.as_millis()
+                ));
+            }
+            Ok(None) => std::thread::sleep(HEALTH_POLL_INTERVAL),
+            Err(e) => return Err(format!("health check wait failed: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a3s_box_core::service::ServiceRestartPolicy;
+
+    fn spec(name: &str, script: &str, restart: ServiceRestartPolicy) -> ServiceSpec {
+        ServiceSpec {
+            name: name.to_string(),
+            command: vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()],
+            env: vec!["SERVICE_TEST=1".to_string()],
+            working_dir: None,
+            restart,
+            depends_on: Vec::new(),
+            health_check: None,
+        }
+    }
+
+    fn wait_for(
+        events: &mpsc::Receiver<ServiceStatus>,
+        name: &str,
+        state: ServiceState,
+    ) -> ServiceStatus {
+        loop {
+            let status = events
+                .recv_timeout(Duration::from_secs(10))
+                .expect("service state change");
+            if status.name == name && status.state == state {
+                return status;
+            }
+        }
+    }
+
+    // The registry is process-wide, so each test uses its own service names.
+    #[test]
+    fn test_services_restart_and_start_after_dependencies() {
+        let marker = tempfile::tempdir().unwrap();
+        let marker = marker.path().join("failed-once");
+        let (_, events) = subscribe();
+        let mut dependent = spec("svc-test-dependent", "exit 0", ServiceRestartPolicy::Never);
+        dependent.depends_on = vec!["svc-test-flaky".to_string()];
+        start(vec![
+            dependent,
+            spec(
+                "svc-test-flaky",
+                &format!(
+                    "test \"$SERVICE_TEST\" = 1 && test ! -e {0} && touch {0} && exit 3; \
+                     sleep 30",
+                    marker.display()
+                ),
+                ServiceRestartPolicy::OnFailure,
+            ),
+        ])
+        .unwrap();
+
+        // The dependent may start as soon as the first run is up, so its
+        // events interleave with the flaky service's.
+        let mut seen: Vec<ServiceStatus> = Vec::new();
+        let done = |seen: &[ServiceStatus]| {
+            seen.iter().any(|status| status.restarts == 1)
+                && seen.iter().any(|status| {
+                    status.name == "svc-test-dependent" && status.state == ServiceState::Exited
+                })
+        };
+        while !done(&seen) {
+            let status = events
+                .recv_timeout(Duration::from_secs(10))
+                .expect("service state change");
+            if status.state != ServiceState::Waiting
+                && matches!(
+                    status.name.as_str(),
+                    "svc-test-flaky" | "svc-test-dependent"
+                )
+            {
+                seen.push(status);
+            }
+        }
+        let flaky: Vec<_> = seen
+            .iter()
+            .filter(|status| status.name == "svc-test-flaky")
+            .map(|status| (status.state, status.exit_code, status.restarts))
+            .collect();
+        assert_eq!(
+            flaky,
+            vec![
+                (ServiceState::Running, None, 0),
+                (ServiceState::Restarting, Some(3), 0),
+                (ServiceState::Running, None, 1),
+            ]
+        );
+        let dependent: Vec<_> = seen
+            .iter()
+            .filter(|status| status.name == "svc-test-dependent")
+            .map(|status| (status.state, status.exit_code))
+            .collect();
+        assert_eq!(
+            dependent,
+            vec![
+                (ServiceState::Running, None),
+                (ServiceState::Exited, Some(0))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_health_check_marks_service_healthy() {
+        let (_, events) = subscribe();
+        let mut service = spec("svc-test-healthy", "sleep 30", ServiceRestartPolicy::Never);
+        service.health_check = Some(ServiceHealthCheck {
+            command: vec!["/bin/true".to_string()],
+            interval_ms: 10,
+            timeout_ms: 1000,
+            retries: 1,
+        });
+        start(vec![service]).unwrap();
+
+        let healthy = wait_for(&events, "svc-test-healthy", ServiceState::Healthy);
+        let pid = healthy.pid.unwrap();
+        unsafe { libc::kill(pid as i32, libc::SIGKILL) };
+        let exited = wait_for(&events, "svc-test-healthy", ServiceState::Exited);
+        assert_eq!(exited.exit_code, Some(128 + libc::SIGKILL));
+    }
+
+    #[test]
+    fn test_run_health_check_reports_failure_and_timeout() {
+        assert!(run_health_check(&["/bin/true".to_string()], Duration::from_secs(5)).is_ok());
+        assert_eq!(
+            run_health_check(&["/bin/false".to_string()], Duration::from_secs(5)).unwrap_err(),
+            "health check exited with 1"
+        );
+        let error = run_health_check(
+            &["/bin/sleep".to_string(), "5".to_string()],
+            Duration::from_millis(50),
+        )
+        .unwrap_err();
+        assert!(error.contains("timed out"));
+    }
+}
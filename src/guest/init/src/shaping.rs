@@ -0,0 +1,245 @@
+//! In-guest bandwidth shaping for `--network-bandwidth`.
+//!
+//! The host passes the limit in bytes per second in `A3S_NET_BANDWIDTH`.
+//! Guest init replaces eth0's root qdisc with a token bucket filter (`tbf`)
+//! over rtnetlink, since the guest rootfs has no `tc` binary. Traffic leaving
+//! the box then queues at the configured rate instead of saturating the host
+//! uplink; TCP senders back off as the queue fills.
+//!
+//! The workload runs without `CAP_NET_ADMIN`, so it cannot remove the qdisc.
+
+use a3s_box_core::egress::BANDWIDTH_ENV;
+use std::io::{Error, ErrorKind};
+
+const RTM_NEWQDISC: u16 = 36;
+const NLMSG_ERROR: u16 = 2;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_REPLACE: u16 = 0x100;
+const NLM_F_CREATE: u16 = 0x400;
+
+const TCA_KIND: u16 = 1;
+const TCA_OPTIONS: u16 = 2;
+const TCA_TBF_PARMS: u16 = 1;
+const TCA_TBF_RATE64: u16 = 4;
+const TCA_TBF_BURST: u16 = 6;
+const TC_H_ROOT: u32 = 0xFFFF_FFFF;
+const TBF_HANDLE: u32 = 0x0001_0000;
+const TC_LINKLAYER_ETHERNET: u8 = 1;
+
+/// Smallest bucket: one full GSO segment must fit or it would never leave.
+const MIN_BURST: u64 = 64 * 1024;
+
+/// Read the bandwidth limit guest init must apply, if the host set one.
+pub fn bandwidth_from_env() -> Result<Option<u64>, String> {
+    match std::env::var(BANDWIDTH_ENV) {
+        Ok(value) if !value.is_empty() => match value.parse() {
+            Ok(0) | Err(_) => Err(format!("invalid {BANDWIDTH_ENV} '{value}'")),
+            Ok(rate) => Ok(Some(rate)),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Shape `ifname`'s outbound traffic to `rate` bytes per second.
+pub fn apply(ifname: &str, rate: u64) -> std::io::Result<()> {
+    let name = std::ffi::CString::new(ifname)
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "interface name contains NUL"))?;
+    let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if ifindex == 0 {
+        return Err(Error::last_os_error());
+    }
+    send_request(&tbf_request(ifindex, rate))
+}
+
+/// Bucket size and queue length in bytes for `rate`.
+///
+/// The bucket holds 10ms of traffic and the queue another 50ms, so short
+/// bursts pass at line rate and sustained senders see bounded latency.
+fn bucket_sizes(rate: u64) -> (u32, u32) {
+    let burst = (rate / 100).max(MIN_BURST);
+    let limit = burst + rate / 20;
+    (clamp_u32(burst), clamp_u32(limit))
+}
+
+fn clamp_u32(value: u64) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+/// Build the `RTM_NEWQDISC` request equivalent to
+/// `tc qdisc replace dev <ifindex> root handle 1: tbf rate ... burst ... limit ...`.
+fn tbf_request(ifindex: u32, rate: u64) -> Vec<u8> {
+    let (burst, limit) = bucket_sizes(rate);
+
+    // struct tc_tbf_qopt: rate and peakrate (struct tc_ratespec, 12 bytes
+    // each), then limit, buffer, and mtu. With TCA_TBF_BURST the kernel
+    // derives the buffer itself, and a zero peakrate disables it.
+    let mut qopt = Vec::with_capacity(36);
+    qopt.extend_from_slice(&[0, TC_LINKLAYER_ETHERNET]);
+    qopt.extend_from_slice(&[0; 6]);
+    qopt.extend_from_slice(&clamp_u32(rate).to_ne_bytes());
+    qopt.extend_from_slice(&[0; 12]);
+    qopt.extend_from_slice(&limit.to_ne_bytes());
+    qopt.extend_from_slice(&[0; 8]);
+
+    let mut options = Vec::new();
+    push_attr(&mut options, TCA_TBF_PARMS, &qopt);
+    if rate > u64::from(u32::MAX) {
+        push_attr(&mut options, TCA_TBF_RATE64, &rate.to_ne_bytes());
+    }
+    push_attr(&mut options, TCA_TBF_BURST, &burst.to_ne_bytes());
+
+    let mut payload = Vec::new();
+    // struct tcmsg: family, padding, ifindex, handle, parent, info.
+    payload.extend_from_slice(&[libc::AF_UNSPEC as u8, 0, 0, 0]);
+    payload.extend_from_slice(&(ifindex as i32).to_ne_bytes());
+    payload.extend_from_slice(&TBF_HANDLE.to_ne_bytes());
+    payload.extend_from_slice(&TC_H_ROOT.to_ne_bytes());
+    payload.extend_from_slice(&0u32.to_ne_bytes());
+    push_attr(&mut payload, TCA_KIND, b"tbf\0");
+    push_attr(&mut payload, TCA_OPTIONS, &options);
+
+    let mut msg = Vec::with_capacity(16 + payload.len());
+    msg.extend_from_slice(&((16 + payload.len()) as u32).to_ne_bytes());
+    msg.extend_from_slice(&RTM_NEWQDISC.to_ne_bytes());
+    msg.extend_from_slice(
+        &(NLM_F_REQUEST | NLM_F_ACK | NLM_F_CREATE | NLM_F_REPLACE).to_ne_bytes(),
+    );
+    msg.extend_from_slice(&1u32.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    msg.extend_from_slice(&payload);
+    msg
+}
+
+/// Append one rtnetlink attribute, padded to 4 bytes.
+fn push_attr(buf: &mut Vec<u8>, ty: u16, data: &[u8]) {
+    buf.extend_from_slice(&((4 + data.len()) as u16).to_ne_bytes());
+    buf.extend_from_slice(&ty.to_ne_bytes());
+    buf.extend_from_slice(data);
+    buf.resize(buf.len().next_multiple_of(4), 0);
+}
+
+/// Send one request and wait for its acknowledgement.
+fn send_request(msg: &[u8]) -> std::io::Result<()> {
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(Error::last_os_error());
+    }
+    let sock = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let timeout = libc::timeval {
+        tv_sec: 2,
+        tv_usec: 0,
+    };
+    unsafe {
+        libc::setsockopt(
+            sock.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            &timeout as *const libc::timeval as *const libc::c_void,
+            std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+
+    let sent = unsafe { libc::send(sock.as_raw_fd(), msg.as_ptr().cast(), msg.len(), 0) };
+    if sent < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    let mut buf = vec![0u8; 8 * 1024];
+    let len = unsafe { libc::recv(sock.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+    if len < 0 {
+        return Err(Error::last_os_error());
+    }
+    let len = len as usize;
+    if len < 20 || u16::from_ne_bytes([buf[4], buf[5]]) != NLMSG_ERROR {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "unexpected rtnetlink reply to RTM_NEWQDISC",
+        ));
+    }
+    match i32::from_ne_bytes(buf[16..20].try_into().unwrap()) {
+        0 => Ok(()),
+        errno => Err(Error::from_raw_os_error(-errno)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collect (type, payload) pairs from back-to-back attributes.
+    fn attrs(mut buf: &[u8]) -> Vec<(u16, Vec<u8>)> {
+        let mut attrs = Vec::new();
+        while buf.len() >= 4 {
+            let len = u16::from_ne_bytes([buf[0], buf[1]]) as usize;
+            let ty = u16::from_ne_bytes([buf[2], buf[3]]);
+            attrs.push((ty, buf[4..len].to_vec()));
+            buf = &buf[len.next_multiple_of(4).min(buf.len())..];
+        }
+        attrs
+    }
+
+    #[test]
+    fn test_bucket_sizes_scale_with_rate() {
+        // 1mbit: the bucket floor dominates.
+        assert_eq!(bucket_sizes(125_000), (65_536, 71_786));
+        // 1gbit: 10ms bucket plus 50ms queue.
+        assert_eq!(bucket_sizes(125_000_000), (1_250_000, 7_500_000));
+        assert_eq!(bucket_sizes(u64::MAX), (u32::MAX, u32::MAX));
+    }
+
+    #[test]
+    fn test_tbf_request_layout() {
+        let msg = tbf_request(2, 1_250_000);
+
+        assert_eq!(
+            u32::from_ne_bytes(msg[0..4].try_into().unwrap()) as usize,
+            msg.len()
+        );
+        assert_eq!(u16::from_ne_bytes([msg[4], msg[5]]), RTM_NEWQDISC);
+        let flags = u16::from_ne_bytes([msg[6], msg[7]]);
+        assert_eq!(
+            flags & (NLM_F_ACK | NLM_F_REPLACE),
+            NLM_F_ACK | NLM_F_REPLACE
+        );
+        assert_eq!(i32::from_ne_bytes(msg[20..24].try_into().unwrap()), 2);
+        assert_eq!(
+            u32::from_ne_bytes(msg[28..32].try_into().unwrap()),
+            TC_H_ROOT
+        );
+
+        let top = attrs(&msg[36..]);
+        assert_eq!(top[0], (TCA_KIND, b"tbf\0".to_vec()));
+        assert_eq!(top[1].0, TCA_OPTIONS);
+        let options = attrs(&top[1].1);
+        let qopt = &options[0].1;
+        assert_eq!(options[0].0, TCA_TBF_PARMS);
+        assert_eq!(qopt.len(), 36);
+        assert_eq!(qopt[1], TC_LINKLAYER_ETHERNET);
+        assert_eq!(
+            u32::from_ne_bytes(qopt[8..12].try_into().unwrap()),
+            1_250_000
+        );
+        assert_eq!(options[1].0, TCA_TBF_BURST);
+        assert_eq!(options[1].1, 65_536u32.to_ne_bytes());
+    }
+
+    #[test]
+    fn test_tbf_request_adds_rate64_above_u32() {
+        let msg = tbf_request(2, 10_000_000_000);
+
+        let top = attrs(&msg[36..]);
+        let options = attrs(&top[1].1);
+        assert_eq!(options[1].0, TCA_TBF_RATE64);
+        assert_eq!(options[1].1, 10_000_000_000u64.to_ne_bytes());
+    }
+}
@@ -0,0 +1,121 @@
+//! Guest end of `--mount type=socket`.
+//!
+//! The host passes each shared socket's guest path in `BOX_SOCKET_<n>`.
+//! Guest init listens on that path and relays every connection, byte for
+//! byte, over vsock port `socket_mount_vsock_port(n)`, which libkrun
+//! connects to the host socket.
+
+use std::io;
+use std::net::Shutdown;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+
+use a3s_box_core::volume::{socket_mount_vsock_port, SOCKET_MOUNT_ENV_PREFIX};
+use nix::sys::socket::{connect, socket, AddressFamily, SockFlag, SockType, VsockAddr};
+use tracing::{debug, info, warn};
+
+const HOST_CID: u32 = 2;
+
+/// A bound guest socket and the vsock port it relays to.
+pub struct SharedSocket {
+    listener: UnixListener,
+    vsock_port: u32,
+}
+
+/// Bind a listener for every shared socket the host configured.
+///
+/// Runs while the rootfs is still writable, because binding creates the
+/// socket file. A socket left over from a previous boot of a persistent
+/// rootfs is replaced.
+pub fn bind_from_env() -> io::Result<Vec<SharedSocket>> {
+    let mut shared = Vec::new();
+    while let Ok(target) = std::env::var(format!("{SOCKET_MOUNT_ENV_PREFIX}{}", shared.len())) {
+        let vsock_port = socket_mount_vsock_port(shared.len());
+        let listener = bind(Path::new(&target)).map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!("failed to share socket at {target}: {error}"),
+            )
+        })?;
+        info!(target = %target, vsock_port, "Shared host socket");
+        shared.push(SharedSocket {
+            listener,
+            vsock_port,
+        });
+    }
+    Ok(shared)
+}
+
+fn bind(target: &Path) -> io::Result<UnixListener> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::symlink_metadata(target) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(target)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "path exists and is not a socket",
+            ))
+        }
+        Err(_) => {}
+    }
+    let listener = UnixListener::bind(target)?;
+    // The box boundary is the access control; any workload user may connect,
+    // as with a bind-mounted socket.
+    std::fs::set_permissions(target, std::fs::Permissions::from_mode(0o666))?;
+    Ok(listener)
+}
+
+/// Relay every accepted connection to the host socket for the VM's lifetime.
+pub fn serve(shared: SharedSocket) {
+    let SharedSocket {
+        listener,
+        vsock_port,
+    } = shared;
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                std::thread::spawn(move || {
+                    if let Err(error) = relay(stream, vsock_port) {
+                        debug!(%error, vsock_port, "Shared socket connection failed");
+                    }
+                });
+            }
+            Err(error) => warn!(%error, vsock_port, "Shared socket accept failed"),
+        }
+    }
+}
+
+fn relay(client: UnixStream, vsock_port: u32) -> io::Result<()> {
+    let host = connect_host(vsock_port)?;
+
+    let mut client_reader = client.try_clone()?;
+    let mut host_writer = host.try_clone()?;
+    let outbound = std::thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut host_writer);
+        let _ =
+            nix::sys::socket::shutdown(host_writer.as_raw_fd(), nix::sys::socket::Shutdown::Write);
+    });
+
+    let mut host_reader = host;
+    let mut client_writer = client;
+    let inbound = io::copy(&mut host_reader, &mut client_writer);
+    let _ = client_writer.shutdown(Shutdown::Both);
+    let _ = outbound.join();
+    inbound.map(|_| ())
+}
+
+pub(crate) fn connect_host(vsock_port: u32) -> io::Result<std::fs::File> {
+    let fd = socket(
+        AddressFamily::Vsock,
+        SockType::Stream,
+        SockFlag::SOCK_CLOEXEC,
+        None,
+    )
+    .map_err(io::Error::other)?;
+    connect(fd.as_raw_fd(), &VsockAddr::new(HOST_CID, vsock_port)).map_err(io::Error::other)?;
+    Ok(std::fs::File::from(fd))
+}
@@ -1,4 +1,9 @@
-//! Numeric user parsing and application for guest child processes.
+//! Container user resolution and application for guest child processes.
+//!
+//! Users and groups are resolved against the container's own `/etc/passwd`
+//! and `/etc/group`, as container runtimes do. For the container main
+//! process guest init also creates a missing account on the fly, so a
+//! `USER 1001` image without a passwd entry still gets a name and a home.
 
 /// User/group identity for a process spawned by guest init.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -9,11 +14,11 @@ pub struct ProcessUser {
     pub gid: Option<u32>,
 }
 
-/// Parse a runtime user string.
+/// Parse a numeric runtime user string.
 ///
-/// Supported formats are `root`, `uid`, `root:root`, and `uid:gid`. Other
-/// named users are rejected because guest-init does not do `/etc/passwd`
-/// lookups yet.
+/// Supported formats are `root`, `uid`, `root:root`, and `uid:gid`. Resolve
+/// names to this form with [`resolve_named_user`] first; names left over
+/// were not found and are rejected.
 pub fn parse_process_user(user: Option<&str>) -> Result<Option<ProcessUser>, String> {
     let Some(user) = user else {
         return Ok(None);
@@ -39,36 +44,65 @@ pub fn parse_process_user(user: Option<&str>) -> Result<Option<ProcessUser>, Str
     Ok(Some(ProcessUser { uid, gid }))
 }
 
-/// Resolve a named user (e.g. CRI `RunAsUserName` "nobody") to a numeric
-/// `"uid:gid"` string by looking it up in the container's `<rootfs>/etc/passwd`.
+/// Resolve a named user or group (e.g. CRI `RunAsUserName` "nobody", or
+/// `USER app:staff`) to a numeric `"uid[:gid]"` string by looking the names
+/// up in the container's `<rootfs>/etc/passwd` and `<rootfs>/etc/group`.
 ///
-/// Returns `None` when no resolution is needed or possible — the user is
-/// numeric / `root` (handled by [`parse_process_user`]), the passwd file is
-/// missing, or the name is not found — leaving `parse_process_user` to accept
-/// the numeric form or reject the unresolved name. An explicit numeric group
-/// suffix (`name:gid`) is preserved.
+/// Returns `None` when no resolution is needed or possible — the user and
+/// group are numeric / `root` (handled by [`parse_process_user`]), the files
+/// are missing, or a name is not found — leaving `parse_process_user` to
+/// accept the numeric form or reject the unresolved name. A named user
+/// without a group takes the primary gid of its passwd entry.
 pub fn resolve_named_user(user: &str, rootfs: &str) -> Option<String> {
     let user = user.trim();
     let (name, group_suffix) = match user.split_once(':') {
         Some((name, group)) => (name, Some(group)),
         None => (user, None),
     };
-    if name.is_empty() || name == "root" || name.parse::<u32>().is_ok() {
+    if !is_name(name) && !group_suffix.is_some_and(is_name) {
         return None;
     }
+    let (uid, passwd_gid) = if is_name(name) {
+        let (uid, gid) = passwd_entry_for_name(rootfs, name)?;
+        (uid.to_string(), Some(gid))
+    } else {
+        // Numeric or `root`, with a named group.
+        (name.to_string(), None)
+    };
+    let gid = match (group_suffix, passwd_gid) {
+        (Some(group), _) if is_name(group) => gid_for_group_name(rootfs, group)?.to_string(),
+        // Numeric or `root`; parse_process_user validates it.
+        (Some(group), _) => group.to_string(),
+        (None, Some(gid)) => gid.to_string(),
+        (None, None) => return Some(uid),
+    };
+    Some(format!("{uid}:{gid}"))
+}
+
+/// Whether a user or group component is a name to look up rather than a
+/// numeric ID or the `root` alias.
+fn is_name(part: &str) -> bool {
+    !part.is_empty() && part != "root" && part.parse::<u32>().is_err()
+}
+
+/// Look up a user's uid and primary gid by name in `<rootfs>/etc/passwd`.
+fn passwd_entry_for_name(rootfs: &str, name: &str) -> Option<(u32, u32)> {
     let passwd = std::fs::read_to_string(std::path::Path::new(rootfs).join("etc/passwd")).ok()?;
-    for line in passwd.lines() {
-        let fields: Vec<&str> = line.split(':').collect();
-        if fields.len() >= 4 && fields[0] == name {
-            let uid: u32 = fields[2].parse().ok()?;
-            let gid: u32 = fields[3].parse().ok()?;
-            return Some(match group_suffix {
-                Some(group) => format!("{uid}:{group}"),
-                None => format!("{uid}:{gid}"),
-            });
-        }
-    }
-    None
+    passwd
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 4 && fields[0] == name)
+        .and_then(|fields| Some((fields[2].parse().ok()?, fields[3].parse().ok()?)))
+}
+
+/// Look up a group's gid by name in `<rootfs>/etc/group`.
+fn gid_for_group_name(rootfs: &str, name: &str) -> Option<u32> {
+    let group = std::fs::read_to_string(std::path::Path::new(rootfs).join("etc/group")).ok()?;
+    group
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 3 && fields[0] == name)
+        .and_then(|fields| fields[2].parse().ok())
 }
 
 /// Resolve the supplementary groups a user belongs to via the container's
@@ -91,8 +125,7 @@ pub fn resolve_image_groups(
     user: &str,
 ) -> Vec<u32> {
     let name_part = user.trim().split(':').next().unwrap_or("").trim();
-    let is_named =
-        !name_part.is_empty() && name_part != "root" && name_part.parse::<u32>().is_err();
+    let is_named = is_name(name_part);
     let passwd_entry = passwd_entry_for_uid(rootfs, uid);
     let username: Option<String> = if is_named {
         Some(name_part.to_string())
@@ -128,6 +161,197 @@ pub fn resolve_image_groups(
     groups
 }
 
+/// An account guest init added to the container's `/etc/passwd`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedAccount {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: String,
+}
+
+/// First ID handed to accounts guest init creates, as `useradd` does.
+const FIRST_CREATED_ID: u32 = 1000;
+
+/// Give the container user an account if the image has none for it.
+///
+/// A named user missing from `<rootfs>/etc/passwd` gets the first free uid
+/// from 1000 and, unless a group was given, a primary group of the same name.
+/// A numeric uid without an entry gets one named `user<uid>`, so tools that
+/// look up the current user (`whoami`, `ssh`, `git`) still work. Either way
+/// the home directory `/home/<name>` is created and owned by the user. An
+/// unknown named group is an error. Returns `Ok(None)` when the user already
+/// has an account, is `root`, or no user was requested.
+pub fn ensure_user_account(user: &str, rootfs: &str) -> Result<Option<CreatedAccount>, String> {
+    let user = user.trim();
+    let (name, group) = match user.split_once(':') {
+        Some((name, group)) => (name, Some(group)),
+        None => (user, None),
+    };
+    if name.is_empty() || name == "root" {
+        return Ok(None);
+    }
+    let etc = std::path::Path::new(rootfs).join("etc");
+    let passwd = std::fs::read_to_string(etc.join("passwd")).unwrap_or_default();
+    let groups = std::fs::read_to_string(etc.join("group")).unwrap_or_default();
+    let passwd_fields: Vec<Vec<&str>> = passwd
+        .lines()
+        .map(|line| line.split(':').collect())
+        .filter(|fields: &Vec<&str>| fields.len() >= 4)
+        .collect();
+    let group_fields: Vec<Vec<&str>> = groups
+        .lines()
+        .map(|line| line.split(':').collect())
+        .filter(|fields: &Vec<&str>| fields.len() >= 3)
+        .collect();
+    let uid_taken = |uid: u32| passwd_fields.iter().any(|f| f[2].parse() == Ok(uid));
+    let gid_taken = |gid: u32| group_fields.iter().any(|f| f[2].parse() == Ok(gid));
+
+    let (name, uid) = match name.parse::<u32>() {
+        Ok(uid) if uid_taken(uid) => return Ok(None),
+        Ok(uid) => (format!("user{uid}"), Some(uid)),
+        Err(_) => (name.to_string(), None),
+    };
+    if passwd_fields.iter().any(|fields| fields[0] == name) {
+        return Ok(None);
+    }
+    let uid = match uid {
+        Some(uid) => uid,
+        None => (FIRST_CREATED_ID..u32::MAX)
+            .find(|uid| !uid_taken(*uid))
+            .ok_or("no free uid left")?,
+    };
+
+    let mut new_group = None;
+    let gid = match group {
+        Some("root") => 0,
+        Some(group) => match group.parse::<u32>() {
+            Ok(gid) => gid,
+            Err(_) => group_fields
+                .iter()
+                .find(|fields| fields[0] == group)
+                .and_then(|fields| fields[2].parse().ok())
+                .ok_or_else(|| format!("unable to find group '{group}' in /etc/group"))?,
+        },
+        None => match group_fields.iter().find(|fields| fields[0] == name) {
+            Some(fields) => fields[2]
+                .parse()
+                .map_err(|_| format!("group '{name}' has an invalid gid"))?,
+            None => {
+                let gid = if gid_taken(uid) {
+                    (FIRST_CREATED_ID..u32::MAX)
+                        .find(|gid| !gid_taken(*gid))
+                        .ok_or("no free gid left")?
+                } else {
+                    uid
+                };
+                new_group = Some(format!("{name}:x:{gid}:"));
+                gid
+            }
+        },
+    };
+
+    let home = format!("/home/{name}");
+    let shell = if std::path::Path::new(rootfs).join("bin/sh").exists() {
+        "/bin/sh"
+    } else {
+        "/sbin/nologin"
+    };
+    append_line(
+        &etc.join("passwd"),
+        &passwd,
+        &format!("{name}:x:{uid}:{gid}::{home}:{shell}"),
+    )?;
+    if let Some(line) = new_group {
+        append_line(&etc.join("group"), &groups, &line)?;
+    }
+    create_home(
+        &std::path::Path::new(rootfs).join(home.trim_start_matches('/')),
+        uid,
+        gid,
+    )?;
+    Ok(Some(CreatedAccount {
+        name,
+        uid,
+        gid,
+        home,
+    }))
+}
+
+/// Append `line` to an account database whose current content is `current`.
+fn append_line(path: &std::path::Path, current: &str, line: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+    }
+    let separator = if current.is_empty() || current.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(format!("{separator}{line}\n").as_bytes()))
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn create_home(path: &std::path::Path, uid: u32, gid: u32) -> Result<(), String> {
+    if path.exists() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::os::unix::fs::chown(path, Some(uid), Some(gid))
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (uid, gid);
+    Ok(())
+}
+
+/// Resolve an `A3S_SEC_SUPPLEMENTAL_GROUPS` value to numeric gids.
+///
+/// Entries are comma-separated and are either numeric gids (CRI
+/// `SupplementalGroups`, numeric `--group-add`) or group names (`--group-add
+/// video`) looked up in `<rootfs>/etc/group`. An unknown name is an error so the
+/// workload never starts silently missing a group it was asked to join.
+pub fn resolve_supplemental_groups(csv: &str, rootfs: &str) -> Result<Vec<u32>, String> {
+    let mut group_file: Option<String> = None;
+    let mut groups = Vec::new();
+    for entry in csv
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        if let Ok(gid) = entry.parse::<u32>() {
+            groups.push(gid);
+            continue;
+        }
+        let contents = group_file.get_or_insert_with(|| {
+            std::fs::read_to_string(std::path::Path::new(rootfs).join("etc/group"))
+                .unwrap_or_default()
+        });
+        let gid = contents
+            .lines()
+            .map(|line| line.split(':').collect::<Vec<_>>())
+            .find(|fields| fields.len() >= 3 && fields[0] == entry)
+            .and_then(|fields| fields[2].parse::<u32>().ok())
+            .ok_or_else(|| format!("unable to find group '{entry}' in /etc/group"))?;
+        groups.push(gid);
+    }
+    let mut seen = std::collections::HashSet::new();
+    groups.retain(|gid| seen.insert(*gid));
+    Ok(groups)
+}
+
 /// The primary gid recorded for `uid` in `<rootfs>/etc/passwd`, if present.
 ///
 /// Used to default a container's primary group to the image's passwd entry when
@@ -180,9 +404,9 @@ fn parse_user_part(part: &str, label: &str, original: &str) -> Result<u32, Strin
     if part == "root" {
         return Ok(0);
     }
-    part.parse::<u32>().map_err(|_| {
-        format!("named {label} '{part}' is not supported yet; use root or a numeric UID[:GID]")
-    })
+    let database = if label == "user" { "passwd" } else { "group" };
+    part.parse::<u32>()
+        .map_err(|_| format!("named {label} '{part}' was not found in /etc/{database}"))
 }
 
 impl ProcessUser {
@@ -320,6 +544,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_supplemental_groups_numeric_and_named() {
+        let dir = write_rootfs("", "video:x:44:\ndocker:x:999:tester\n");
+        let rootfs = dir.path().to_str().unwrap();
+        assert_eq!(
+            resolve_supplemental_groups("video, 1234,docker,44", rootfs).unwrap(),
+            vec![44, 1234, 999]
+        );
+    }
+
+    #[test]
+    fn test_resolve_supplemental_groups_rejects_unknown_name() {
+        let dir = write_rootfs("", "video:x:44:\n");
+        let rootfs = dir.path().to_str().unwrap();
+        let error = resolve_supplemental_groups("render", rootfs).unwrap_err();
+        assert!(error.contains("render"));
+    }
+
+    #[test]
+    fn test_resolve_named_user_and_group() {
+        let dir = write_rootfs(
+            "root:x:0:0:root:/root:/sh\napp:x:1000:1000::/home/app:/sh\n",
+            "root:x:0:\napp:x:1000:\nstaff:x:50:app\n",
+        );
+        let rootfs = dir.path().to_str().unwrap();
+        assert_eq!(
+            resolve_named_user("app", rootfs).as_deref(),
+            Some("1000:1000")
+        );
+        assert_eq!(
+            resolve_named_user("app:staff", rootfs).as_deref(),
+            Some("1000:50")
+        );
+        assert_eq!(
+            resolve_named_user("app:7", rootfs).as_deref(),
+            Some("1000:7")
+        );
+        assert_eq!(
+            resolve_named_user("1001:staff", rootfs).as_deref(),
+            Some("1001:50")
+        );
+        assert_eq!(resolve_named_user("1001:7", rootfs), None);
+        assert_eq!(resolve_named_user("ghost", rootfs), None);
+        assert_eq!(resolve_named_user("app:ghosts", rootfs), None);
+
+        let err = parse_process_user(Some("1000:ghosts")).unwrap_err();
+        assert!(err.contains("/etc/group"));
+    }
+
+    #[test]
+    fn test_ensure_user_account_creates_missing_users() {
+        let dir = write_rootfs(
+            "root:x:0:0:root:/root:/sh\napp:x:1000:1000::/home/app:/sh",
+            "root:x:0:\napp:x:1000:\nstaff:x:50:\n",
+        );
+        let rootfs = dir.path().to_str().unwrap();
+        assert_eq!(ensure_user_account("root", rootfs).unwrap(), None);
+        assert_eq!(ensure_user_account("app", rootfs).unwrap(), None);
+        assert_eq!(ensure_user_account("1000:50", rootfs).unwrap(), None);
+        assert!(ensure_user_account("worker:ghosts", rootfs)
+            .unwrap_err()
+            .contains("ghosts"));
+
+        // Created homes are chowned to the new user, which needs root; an
+        // existing home is left alone.
+        std::fs::create_dir_all(dir.path().join("home/worker")).unwrap();
+        let created = ensure_user_account("worker", rootfs).unwrap().unwrap();
+        assert_eq!(
+            created,
+            CreatedAccount {
+                name: "worker".to_string(),
+                uid: 1001,
+                gid: 1001,
+                home: "/home/worker".to_string(),
+            }
+        );
+        assert_eq!(
+            resolve_named_user("worker", rootfs).as_deref(),
+            Some("1001:1001")
+        );
+        assert_eq!(
+            home_dir_for_uid(rootfs, 1001).as_deref(),
+            Some("/home/worker")
+        );
+        let group = std::fs::read_to_string(dir.path().join("etc/group")).unwrap();
+        assert!(group.ends_with("staff:x:50:\nworker:x:1001:\n"));
+        let passwd = std::fs::read_to_string(dir.path().join("etc/passwd")).unwrap();
+        assert!(passwd.contains("/home/app:/sh\nworker:x:1001:1001::/home/worker:"));
+
+        // A bare uid gets a synthesized name and keeps its explicit group.
+        std::fs::create_dir_all(dir.path().join("home/user4242")).unwrap();
+        let created = ensure_user_account("4242:staff", rootfs).unwrap().unwrap();
+        assert_eq!((created.name.as_str(), created.gid), ("user4242", 50));
+        assert_eq!(ensure_user_account("4242", rootfs).unwrap(), None);
+    }
+
     #[test]
     fn test_home_dir_for_uid_uses_passwd_home() {
         let dir = write_rootfs(
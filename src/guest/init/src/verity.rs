@@ -0,0 +1,72 @@
+//! Guest end of the measured TEE rootfs.
+//!
+//! The host attaches a read-only ext4 image and its dm-verity hash tree as
+//! virtio-blk disks and passes the root hash in
+//! [`a3s_box_core::tee::VERITY_ROOTFS_ENV`]. Guest init maps them to a
+//! dm-verity device through the device-mapper control node, so every block
+//! the workload reads is checked against the root hash, and the attestation
+//! server binds that root hash into its reports.
+
+use std::io;
+
+use a3s_box_core::tee::{VerityRootfs, VERITY_BLOCK_SIZE, VERITY_ROOTFS_ENV};
+
+use crate::block::DmTarget;
+
+/// device-mapper name of the measured rootfs.
+pub const VERITY_DEVICE_NAME: &str = "a3s-rootfs";
+
+/// The measured rootfs the host configured, if any.
+pub fn from_env() -> Result<Option<VerityRootfs>, String> {
+    match std::env::var(VERITY_ROOTFS_ENV) {
+        Ok(value) => VerityRootfs::from_env_value(&value).map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The dm-verity table line for `rootfs` on the given data and hash devices.
+pub fn verity_table(rootfs: &VerityRootfs, data_device: &str, hash_device: &str) -> String {
+    // Format 1, SHA-256, no salt; the hash tree starts at block 0 of its own
+    // device because it was built without a superblock.
+    format!(
+        "1 {data_device} {hash_device} {block} {block} {blocks} 0 sha256 {root_hash} -",
+        block = VERITY_BLOCK_SIZE,
+        blocks = rootfs.data_blocks,
+        root_hash = rootfs.root_hash,
+    )
+}
+
+/// Create the read-only dm-verity device for `rootfs` and return its
+/// `/dev/mapper` path.
+pub fn open(rootfs: &VerityRootfs, data_device: &str, hash_device: &str) -> io::Result<String> {
+    let table = verity_table(rootfs, data_device, hash_device);
+    crate::block::create_device(
+        VERITY_DEVICE_NAME,
+        &DmTarget {
+            target_type: "verity",
+            length_sectors: rootfs.data_blocks * (VERITY_BLOCK_SIZE / 512),
+            params: &table,
+        },
+        true,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verity_table() {
+        let rootfs = VerityRootfs {
+            root_hash: "ab".repeat(32),
+            data_blocks: 2048,
+        };
+        assert_eq!(
+            verity_table(&rootfs, "/dev/vdb", "/dev/vdc"),
+            format!(
+                "1 /dev/vdb /dev/vdc 4096 4096 2048 0 sha256 {} -",
+                "ab".repeat(32)
+            )
+        );
+    }
+}
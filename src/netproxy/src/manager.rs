@@ -1,5 +1,5 @@
 use std::io;
-use std::net::{Ipv4Addr, SocketAddrV4, TcpListener};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
 use std::os::fd::{FromRawFd, IntoRawFd, RawFd};
 use std::os::unix::net::UnixDatagram;
 use std::path::{Path, PathBuf};
@@ -196,7 +196,8 @@ pub(super) fn write_stats_file(path: &Path, stats: NetStatsSnapshot) -> io::Resu
 /// Parse `["8088:80", "443:443"]` into `Vec<PortForward>`.
 ///
 /// Each rule maps `host_port → guest_ip:guest_port`. Guest IP is always the
-/// IPAM-assigned `guest_ip`.
+/// IPAM-assigned `guest_ip`; the listener binds the mapping's host address,
+/// or every IPv4 address when it has none.
 pub(super) fn parse_port_forwards(
     port_map: &[String],
     guest_ip: Ipv4Addr,
@@ -207,8 +208,9 @@ pub(super) fn parse_port_forwards(
         let host_port = mapping.host_port;
         let guest_port = mapping.guest_port;
 
-        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, host_port))
-            .map_err(|e| format!("cannot bind 0.0.0.0:{host_port}: {e}"))?;
+        let bind_ip = mapping.host_ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let listener = TcpListener::bind(SocketAddr::new(bind_ip, host_port))
+            .map_err(|e| format!("cannot bind {}: {e}", mapping.host_address()))?;
         listener
             .set_nonblocking(true)
             .map_err(|e| format!("set_nonblocking on listener: {e}"))?;
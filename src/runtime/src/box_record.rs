@@ -1,6 +1,6 @@
 //! Canonical persisted metadata schema for local box executions.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 use a3s_box_core::config::ResourceLimits;
@@ -144,6 +144,33 @@ pub struct BoxRecord {
     /// Custom host-to-IP mappings.
     #[serde(default)]
     pub add_host: Vec<String>,
+    /// Custom DNS servers.
+    #[serde(default)]
+    pub dns: Vec<String>,
+    /// DNS search domains.
+    #[serde(default)]
+    pub dns_search: Vec<String>,
+    /// Resolver options.
+    #[serde(default)]
+    pub dns_option: Vec<String>,
+    /// Extra DNS names for the box on its bridge network.
+    #[serde(default)]
+    pub network_aliases: Vec<String>,
+    /// Egress allow/deny clauses (`--network-policy`).
+    #[serde(default)]
+    pub network_policy: Vec<String>,
+    /// Outbound bandwidth limit in bytes per second (`--network-bandwidth`).
+    #[serde(default)]
+    pub network_bandwidth: Option<u64>,
+    /// Concurrent outbound TCP connection limit (`--network-max-conns`).
+    #[serde(default)]
+    pub network_max_conns: Option<u32>,
+    /// Audited HTTP(S) proxy mode (`--http-proxy`).
+    #[serde(default)]
+    pub http_proxy: Option<a3s_box_core::http_proxy::HttpProxyMode>,
+    /// Host Unix sockets shared into the guest (`--mount type=socket`).
+    #[serde(default)]
+    pub socket_mounts: Vec<a3s_box_core::volume::SocketMount>,
     /// Target OCI platform.
     #[serde(default)]
     pub platform: Option<String>,
@@ -186,6 +213,34 @@ pub struct BoxRecord {
     /// Host OOM score adjustment.
     #[serde(default)]
     pub oom_score_adj: Option<i32>,
+    /// Template that seeds the workspace on first boot (`--workspace-template`).
+    #[serde(default)]
+    pub workspace_template: Option<a3s_box_core::workspace_template::WorkspaceTemplate>,
+    /// Annotations, notes and provenance kept for the operator.
+    #[serde(default)]
+    pub metadata: BoxMetadata,
+}
+
+/// Bookkeeping for telling boxes apart (`a3s-box annotate`). None of it
+/// affects how the box runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoxMetadata {
+    /// `KEY=VALUE` annotations. Unlike labels they can change after creation.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// Free-form notes, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<BoxNote>,
+    /// Command line that created the box, program name first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<Vec<String>>,
+}
+
+/// A timestamped note on a box.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoxNote {
+    pub added_at: DateTime<Utc>,
+    pub text: String,
 }
 
 impl BoxRecord {
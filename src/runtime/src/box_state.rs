@@ -2,15 +2,16 @@
 
 use std::path::{Path, PathBuf};
 
-use crate::file_lock::FileLock;
+use crate::storage::backend;
 use crate::store_io::quarantine_label;
 use crate::BoxRecord;
 use a3s_box_core::{ExecutionId, OperationId};
 
 /// Durable collection of local box execution records.
 ///
-/// All mutating operations use the sibling `boxes.json.lock` advisory lock and
-/// a durable temporary-file rename. Callers must keep transaction closures
+/// All mutating operations run as a [`crate::storage`] transaction: with the
+/// default file backend, the sibling `boxes.json.lock` advisory lock and a
+/// durable temporary-file rename. Callers must keep transaction closures
 /// synchronous and must not acquire the same store lock recursively.
 #[derive(Debug)]
 pub struct BoxStateStore {
@@ -54,8 +55,9 @@ impl BoxStateStore {
 
     /// Save this snapshot under the cross-process state lock.
     pub fn save(&self) -> std::io::Result<()> {
-        let _lock = FileLock::acquire(&self.path)?;
-        self.write_unlocked()
+        let mut transaction = backend().begin(&self.path)?;
+        transaction.write(&self.encode()?)?;
+        transaction.commit()
     }
 
     /// Apply a strict atomic read-modify-write transaction.
@@ -103,10 +105,14 @@ impl BoxStateStore {
     where
         E: From<std::io::Error>,
     {
-        let _lock = FileLock::acquire(path).map_err(E::from)?;
-        let mut store = Self::load_unlocked(path, policy, true).map_err(E::from)?;
+        let mut transaction = backend().begin(path).map_err(E::from)?;
+        let data = transaction.read().map_err(E::from)?;
+        let mut store = Self::parse(path, data, policy, true).map_err(E::from)?;
         let output = f(&mut store)?;
-        store.write_unlocked().map_err(E::from)?;
+        transaction
+            .write(&store.encode().map_err(E::from)?)
+            .map_err(E::from)?;
+        transaction.commit().map_err(E::from)?;
         Ok(output)
     }
 
@@ -115,17 +121,26 @@ impl BoxStateStore {
         corruption_policy: CorruptionPolicy,
         create_parent: bool,
     ) -> std::io::Result<Self> {
-        if !path.exists() {
+        let data = backend().read(path)?;
+        Self::parse(path, data, corruption_policy, create_parent)
+    }
+
+    fn parse(
+        path: &Path,
+        data: Option<Vec<u8>>,
+        corruption_policy: CorruptionPolicy,
+        create_parent: bool,
+    ) -> std::io::Result<Self> {
+        let Some(data) = data else {
             if create_parent {
                 if let Some(parent) = path.parent() {
                     std::fs::create_dir_all(parent)?;
                 }
             }
             return Ok(Self::from_records(path.to_path_buf(), Vec::new()));
-        }
+        };
 
-        let data = std::fs::read_to_string(path)?;
-        let parsed = serde_json::from_str::<Vec<BoxRecord>>(&data)
+        let parsed = serde_json::from_slice::<Vec<BoxRecord>>(&data)
             .map_err(|error| error.to_string())
             .and_then(|records| {
                 validate_managed_records(&records)?;
@@ -137,7 +152,7 @@ impl BoxStateStore {
                 Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error))
             }
             Err(error) => {
-                let preserved = quarantine_label(path);
+                let preserved = quarantine_label(path, &data);
                 eprintln!(
                     "a3s-box: WARNING: state file {} is corrupt ({error}); preserved a \
                      copy at {preserved} and started from empty state. Running boxes are \
@@ -150,15 +165,10 @@ impl BoxStateStore {
         }
     }
 
-    fn write_unlocked(&self) -> std::io::Result<()> {
+    fn encode(&self) -> std::io::Result<Vec<u8>> {
         validate_managed_records(&self.records)
             .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        let data = serde_json::to_vec_pretty(&self.records).map_err(std::io::Error::other)?;
-        let temporary_path = self.path.with_extension("json.tmp");
-        a3s_box_core::fs_atomic::write_durable(&temporary_path, &self.path, &data)
+        serde_json::to_vec_pretty(&self.records).map_err(std::io::Error::other)
     }
 
     /// Path of the durable state file.
@@ -0,0 +1,247 @@
+//! Host side of `a3s-box forward`.
+//!
+//! A [`PortForward`] listens on a host TCP address or Unix socket and opens
+//! one stream through the box's forward socket per accepted connection; see
+//! [`a3s_box_core::forward`] for the wire protocol.
+
+use std::path::{Path, PathBuf};
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::forward::{
+    encode_forward_request, forward_status_message, ForwardListen, ForwardSpec,
+    FORWARD_SOCKET_FILE, FORWARD_STATUS_OK,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener, UnixStream};
+use tokio::task::JoinHandle;
+
+/// Path of a box's forward socket, next to its exec socket.
+pub fn forward_socket_path(exec_socket_path: &Path) -> PathBuf {
+    exec_socket_path.with_file_name(FORWARD_SOCKET_FILE)
+}
+
+/// Open a stream to `guest_port` on the guest's loopback.
+///
+/// Returns once guest init has connected to the port, so the stream is ready
+/// to carry the forwarded bytes.
+pub async fn connect_guest_port(socket_path: &Path, guest_port: u16) -> Result<UnixStream> {
+    let mut stream = UnixStream::connect(socket_path).await.map_err(|error| {
+        BoxError::NetworkError(format!(
+            "failed to connect to forward socket {}: {error}",
+            socket_path.display()
+        ))
+    })?;
+    stream
+        .write_all(&encode_forward_request(guest_port))
+        .await
+        .map_err(|error| {
+            BoxError::NetworkError(format!("failed to send forward request: {error}"))
+        })?;
+    let status = stream.read_u8().await.map_err(|error| {
+        BoxError::NetworkError(format!(
+            "guest did not answer forward request for port {guest_port}: {error}"
+        ))
+    })?;
+    if status != FORWARD_STATUS_OK {
+        return Err(BoxError::NetworkError(format!(
+            "guest port {guest_port}: {}",
+            forward_status_message(status)
+        )));
+    }
+    Ok(stream)
+}
+
+/// A running host-to-guest forward. Dropping it stops accepting connections
+/// and removes its Unix socket; streams already open keep running.
+pub struct PortForward {
+    listen: ForwardListen,
+    guest_port: u16,
+    task: JoinHandle<()>,
+}
+
+impl PortForward {
+    /// Bind the host listener for `spec` and start forwarding through the
+    /// forward socket at `socket_path`.
+    pub async fn start(socket_path: impl Into<PathBuf>, spec: &ForwardSpec) -> Result<Self> {
+        let socket_path = socket_path.into();
+        let guest_port = spec.guest_port;
+        let bind_error = |error: std::io::Error| {
+            BoxError::NetworkError(format!("failed to listen on {}: {error}", spec.listen))
+        };
+
+        let (listen, task) = match &spec.listen {
+            ForwardListen::Tcp(address) => {
+                let listener = TcpListener::bind(address).await.map_err(bind_error)?;
+                let bound = listener.local_addr().map_err(bind_error)?;
+                let task = tokio::spawn(async move {
+                    loop {
+                        match listener.accept().await {
+                            Ok((client, peer)) => {
+                                let _ = client.set_nodelay(true);
+                                tracing::debug!(%peer, guest_port, "Forwarding connection");
+                                spawn_stream(client, socket_path.clone(), guest_port);
+                            }
+                            Err(error) => {
+                                tracing::warn!(%error, "Forward accept failed");
+                                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                            }
+                        }
+                    }
+                });
+                (ForwardListen::Tcp(bound), task)
+            }
+            ForwardListen::Unix(path) => {
+                remove_stale_socket(path).map_err(bind_error)?;
+                let listener = UnixListener::bind(path).map_err(bind_error)?;
+                let task = tokio::spawn(async move {
+                    loop {
+                        match listener.accept().await {
+                            Ok((client, _)) => {
+                                tracing::debug!(guest_port, "Forwarding connection");
+                                spawn_stream(client, socket_path.clone(), guest_port);
+                            }
+                            Err(error) => {
+                                tracing::warn!(%error, "Forward accept failed");
+                                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                            }
+                        }
+                    }
+                });
+                (ForwardListen::Unix(path.clone()), task)
+            }
+        };
+
+        Ok(Self {
+            listen,
+            guest_port,
+            task,
+        })
+    }
+
+    /// The bound host listener, with any port `0` resolved.
+    pub fn listen(&self) -> &ForwardListen {
+        &self.listen
+    }
+
+    /// The guest port connections are forwarded to.
+    pub fn guest_port(&self) -> u16 {
+        self.guest_port
+    }
+}
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        self.task.abort();
+        if let ForwardListen::Unix(path) = &self.listen {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn spawn_stream<S>(mut client: S, socket_path: PathBuf, guest_port: u16)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut guest = match connect_guest_port(&socket_path, guest_port).await {
+            Ok(guest) => guest,
+            Err(error) => {
+                tracing::warn!(%error, "Forwarded connection failed");
+                return;
+            }
+        };
+        if let Err(error) = tokio::io::copy_bidirectional(&mut client, &mut guest).await {
+            tracing::debug!(%error, guest_port, "Forwarded stream closed");
+        }
+    });
+}
+
+/// Remove a leftover socket at `path` so a restarted forward can rebind it.
+/// Anything other than a socket is left alone and makes the bind fail.
+fn remove_stale_socket(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a3s_box_core::forward::{
+        decode_forward_request, FORWARD_REQUEST_LEN, FORWARD_STATUS_REFUSED,
+    };
+
+    /// Stand-in for guest init: echoes on port 7, refuses everything else.
+    fn fake_guest(socket_path: &Path) -> JoinHandle<()> {
+        let listener = UnixListener::bind(socket_path).unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut request = [0u8; FORWARD_REQUEST_LEN];
+                    stream.read_exact(&mut request).await.unwrap();
+                    if decode_forward_request(&request) != Some(7) {
+                        stream.write_u8(FORWARD_STATUS_REFUSED).await.unwrap();
+                        return;
+                    }
+                    stream.write_u8(FORWARD_STATUS_OK).await.unwrap();
+                    let (mut reader, mut writer) = stream.split();
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                });
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_connect_guest_port_reports_refusal() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(FORWARD_SOCKET_FILE);
+        let _guest = fake_guest(&socket);
+
+        let error = connect_guest_port(&socket, 5432).await.unwrap_err();
+        assert!(error.to_string().contains("connection refused"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_tcp_forward_relays_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(FORWARD_SOCKET_FILE);
+        let _guest = fake_guest(&socket);
+
+        let forward = PortForward::start(&socket, &"0:7".parse().unwrap())
+            .await
+            .unwrap();
+        let ForwardListen::Tcp(address) = forward.listen().clone() else {
+            panic!("expected a TCP listener");
+        };
+        assert_ne!(address.port(), 0);
+
+        let mut client = tokio::net::TcpStream::connect(address).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        let mut reply = [0u8; 4];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"ping");
+    }
+
+    #[tokio::test]
+    async fn test_unix_forward_removes_socket_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(FORWARD_SOCKET_FILE);
+        let _guest = fake_guest(&socket);
+        let local = dir.path().join("local.sock");
+        let spec: ForwardSpec = format!("unix:{}:7", local.display()).parse().unwrap();
+
+        let forward = PortForward::start(&socket, &spec).await.unwrap();
+        let mut client = UnixStream::connect(&local).await.unwrap();
+        client.write_all(b"pong").await.unwrap();
+        let mut reply = [0u8; 4];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"pong");
+
+        drop(forward);
+        assert!(!local.exists());
+    }
+}
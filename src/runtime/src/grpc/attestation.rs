@@ -284,6 +284,92 @@ impl RaTlsAttestationClient {
             })?;
         crate::tee::ratls::extract_report_from_cert(cert.as_ref())
     }
+
+    /// Challenge the guest with a fresh random nonce.
+    ///
+    /// Unlike the RA-TLS certificate's report, which guest init generates
+    /// once at boot, the returned report is generated for this nonce, so a
+    /// verifier checking it with [`crate::tee::verify_attestation_with_time`]
+    /// knows the guest is live now. The report is not verified here.
+    pub async fn challenge(&self, allow_simulated: bool) -> Result<ChallengeReport> {
+        use rand::RngCore;
+
+        let mut nonce = vec![0u8; a3s_box_core::tee::CHALLENGE_NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        self.challenge_with_nonce(nonce, allow_simulated).await
+    }
+
+    /// Challenge the guest with a verifier-chosen nonce of
+    /// [`a3s_box_core::tee::CHALLENGE_NONCE_LEN`] bytes.
+    pub async fn challenge_with_nonce(
+        &self,
+        nonce: Vec<u8>,
+        allow_simulated: bool,
+    ) -> Result<ChallengeReport> {
+        use a3s_box_core::tee::{AttestChallenge, AttestRequest, AttestRoute, CHALLENGE_NONCE_LEN};
+
+        if nonce.len() != CHALLENGE_NONCE_LEN {
+            return Err(BoxError::AttestationError(format!(
+                "Challenge nonce must be {} bytes, got {}",
+                CHALLENGE_NONCE_LEN,
+                nonce.len()
+            )));
+        }
+        let issued_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let req = AttestRequest {
+            route: AttestRoute::Challenge,
+            payload: serde_json::to_value(AttestChallenge {
+                nonce: nonce.clone(),
+            })?,
+        };
+        let response = ratls_request(
+            &self.socket_path,
+            crate::tee::AttestationPolicy::default(),
+            allow_simulated,
+            &req,
+        )
+        .await?;
+
+        #[derive(serde::Deserialize)]
+        struct ChallengeResponse {
+            report: Vec<u8>,
+            #[serde(default)]
+            cert_chain: crate::tee::CertificateChain,
+        }
+        let response: ChallengeResponse = serde_json::from_slice(&response).map_err(|e| {
+            BoxError::AttestationError(format!("Invalid challenge response: {}", e))
+        })?;
+        let platform = crate::tee::parse_platform_info(&response.report).ok_or_else(|| {
+            BoxError::AttestationError(
+                "Guest answered the challenge with a malformed report".into(),
+            )
+        })?;
+
+        Ok(ChallengeReport {
+            nonce,
+            issued_at,
+            report: AttestationReport {
+                report: response.report,
+                cert_chain: response.cert_chain,
+                platform,
+            },
+        })
+    }
+}
+
+/// A report the guest generated for a verifier's nonce.
+#[derive(Debug, Clone)]
+pub struct ChallengeReport {
+    /// The nonce sent, expected in REPORT_DATA[0..32].
+    pub nonce: Vec<u8>,
+    /// When the nonce was generated (Unix seconds).
+    pub issued_at: u64,
+    /// The guest's answer.
+    pub report: AttestationReport,
 }
 
 /// A secret to inject into the TEE.
@@ -388,6 +474,116 @@ impl SecretInjector {
 
         Ok(result)
     }
+
+    /// Provision a secret from a KBS (Key Broker Service).
+    ///
+    /// The guest answers the KBS challenge itself and decrypts the resource
+    /// with a key that never leaves the TEE; this side only relays. The guest
+    /// stores the resource as secret `name`, like [`SecretInjector::inject`].
+    pub async fn inject_from_kbs(
+        &self,
+        kbs: &crate::tee::KbsClient,
+        resource_path: &str,
+        name: &str,
+        set_env: bool,
+        policy: crate::tee::AttestationPolicy,
+        allow_simulated: bool,
+    ) -> Result<()> {
+        use a3s_box_core::tee::{
+            AttestRequest, AttestRoute, KbsEvidenceRequest, KbsResourceRequest,
+        };
+
+        let evidence_policy = policy.clone();
+        let response = kbs
+            .fetch_resource(resource_path, |nonce| async move {
+                let req = AttestRequest {
+                    route: AttestRoute::KbsEvidence,
+                    payload: serde_json::to_value(KbsEvidenceRequest { nonce })?,
+                };
+                let data = ratls_request(&self.socket_path, evidence_policy, allow_simulated, &req)
+                    .await?;
+                serde_json::from_slice(&data).map_err(|e| {
+                    BoxError::AttestationError(format!("Invalid KBS evidence from guest: {}", e))
+                })
+            })
+            .await?;
+
+        let req = AttestRequest {
+            route: AttestRoute::KbsResource,
+            payload: serde_json::to_value(KbsResourceRequest {
+                name: name.to_string(),
+                set_env,
+                response,
+            })?,
+        };
+        ratls_request(&self.socket_path, policy, allow_simulated, &req).await?;
+        Ok(())
+    }
+
+    /// Format the encrypted workspace `image` and release its key to the
+    /// guest, which opens and mounts it.
+    ///
+    /// The RA-TLS handshake verifies the guest before the image is formatted,
+    /// so a volume key is only ever generated for an attested guest. It is
+    /// sent over that session and dropped.
+    pub async fn release_workspace_key(
+        &self,
+        image: &Path,
+        policy: crate::tee::AttestationPolicy,
+        allow_simulated: bool,
+    ) -> Result<()> {
+        use a3s_box_core::tee::{AttestRequest, AttestRoute, WorkspaceKeyRequest};
+
+        let mut tls_stream = connect_ratls(&self.socket_path, policy, allow_simulated).await?;
+        let image = image.to_path_buf();
+        let key =
+            tokio::task::spawn_blocking(move || crate::tee::format_encrypted_workspace(&image))
+                .await
+                .map_err(|e| {
+                    BoxError::AttestationError(format!("Workspace formatting failed: {}", e))
+                })??;
+
+        let req = AttestRequest {
+            route: AttestRoute::WorkspaceKey,
+            payload: serde_json::to_value(WorkspaceKeyRequest {
+                volume_key: key.as_bytes().to_vec(),
+            })?,
+        };
+        drop(key);
+        write_tls_frame(&mut tls_stream, 0x01, &serde_json::to_vec(&req)?).await?;
+
+        let (frame_type, response_data) = read_tls_frame(&mut tls_stream).await?;
+        if frame_type == 0x04 {
+            return Err(BoxError::AttestationError(format!(
+                "Guest failed to open the encrypted workspace: {}",
+                String::from_utf8_lossy(&response_data)
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Send one request over a fresh RA-TLS connection and return the guest's
+/// data response.
+async fn ratls_request(
+    socket_path: &Path,
+    policy: crate::tee::AttestationPolicy,
+    allow_simulated: bool,
+    req: &a3s_box_core::tee::AttestRequest,
+) -> Result<Vec<u8>> {
+    let mut tls_stream = connect_ratls(socket_path, policy, allow_simulated).await?;
+    let payload = serde_json::to_vec(req)?;
+    write_tls_frame(&mut tls_stream, 0x01, &payload).await?;
+
+    let (frame_type, response_data) = read_tls_frame(&mut tls_stream).await?;
+    if frame_type == 0x04 {
+        return Err(BoxError::AttestationError(format!(
+            "Guest rejected {:?} request: {}",
+            req.route,
+            String::from_utf8_lossy(&response_data)
+        )));
+    }
+    Ok(response_data)
 }
 
 /// Result of a seal operation from the guest.
@@ -393,6 +393,157 @@ impl ExecClient {
         }
     }
 
+    /// Sample guest resource usage (CPU, memory, block and network I/O, top
+    /// processes) from the guest's own `/proc`.
+    pub async fn metrics(
+        &self,
+        request: &a3s_box_core::GuestMetricsRequest,
+    ) -> Result<a3s_box_core::GuestMetrics> {
+        let mut stream = self.open_stream().await?;
+        let payload =
+            serde_json::to_vec(&a3s_box_core::GuestSessionRequest::Metrics(request.clone()))
+                .map_err(|error| {
+                    BoxError::ExecError(format!("Failed to serialize metrics request: {error}"))
+                })?;
+        let encoded = a3s_transport::Frame::data(payload)
+            .encode()
+            .map_err(|error| {
+                BoxError::ExecError(format!("Failed to encode metrics request: {error}"))
+            })?;
+        stream.write_all(&encoded).await.map_err(|error| {
+            BoxError::ExecError(format!("Metrics request write failed: {error}"))
+        })?;
+
+        let (read, _write) = tokio::io::split(stream);
+        let mut reader = a3s_transport::FrameReader::new(read);
+        let frame = reader
+            .read_frame()
+            .await
+            .map_err(|error| BoxError::ExecError(format!("Metrics response read failed: {error}")))?
+            .ok_or_else(|| {
+                BoxError::ExecError("Exec server closed without metrics response".to_string())
+            })?;
+        match frame.frame_type {
+            a3s_transport::FrameType::Data => {
+                serde_json::from_slice(&frame.payload).map_err(|error| {
+                    BoxError::ExecError(format!("Failed to parse metrics response: {error}"))
+                })
+            }
+            // Guests from before the metrics request reject the envelope.
+            a3s_transport::FrameType::Error => Err(BoxError::ExecError(format!(
+                "Guest metrics unavailable: {}",
+                String::from_utf8_lossy(&frame.payload)
+            ))),
+            other => Err(BoxError::ExecError(format!(
+                "Unexpected metrics response frame: {other:?}"
+            ))),
+        }
+    }
+
+    /// List guest processes, each attributed to the main process, service,
+    /// exec, or PTY session it descends from, orphans included.
+    pub async fn process_tree(&self) -> Result<a3s_box_core::GuestProcessTree> {
+        let mut stream = self.open_stream().await?;
+        let payload = serde_json::to_vec(&a3s_box_core::GuestSessionRequest::ProcessTree(
+            a3s_box_core::ProcessTreeRequest::default(),
+        ))
+        .map_err(|error| {
+            BoxError::ExecError(format!("Failed to serialize process tree request: {error}"))
+        })?;
+        let encoded = a3s_transport::Frame::data(payload)
+            .encode()
+            .map_err(|error| {
+                BoxError::ExecError(format!("Failed to encode process tree request: {error}"))
+            })?;
+        stream.write_all(&encoded).await.map_err(|error| {
+            BoxError::ExecError(format!("Process tree request write failed: {error}"))
+        })?;
+
+        let (read, _write) = tokio::io::split(stream);
+        let mut reader = a3s_transport::FrameReader::new(read);
+        let frame = reader
+            .read_frame()
+            .await
+            .map_err(|error| {
+                BoxError::ExecError(format!("Process tree response read failed: {error}"))
+            })?
+            .ok_or_else(|| {
+                BoxError::ExecError("Exec server closed without process tree response".to_string())
+            })?;
+        match frame.frame_type {
+            a3s_transport::FrameType::Data => {
+                serde_json::from_slice(&frame.payload).map_err(|error| {
+                    BoxError::ExecError(format!("Failed to parse process tree response: {error}"))
+                })
+            }
+            // Guests from before the process tree request reject the envelope.
+            a3s_transport::FrameType::Error => Err(BoxError::ExecError(format!(
+                "Guest process tree unavailable: {}",
+                String::from_utf8_lossy(&frame.payload)
+            ))),
+            other => Err(BoxError::ExecError(format!(
+                "Unexpected process tree response frame: {other:?}"
+            ))),
+        }
+    }
+
+    /// Stream the states of the guest's supervised services into `on_status`:
+    /// first the current state of each, then, when `follow` is set, every
+    /// change until the guest closes the stream.
+    pub async fn watch_services(
+        &self,
+        follow: bool,
+        mut on_status: impl FnMut(a3s_box_core::service::ServiceStatus),
+    ) -> Result<()> {
+        let mut stream = self.open_stream().await?;
+        let payload = serde_json::to_vec(&a3s_box_core::GuestSessionRequest::ServiceEvents(
+            a3s_box_core::service::ServiceEventsRequest { follow },
+        ))
+        .map_err(|error| {
+            BoxError::ExecError(format!(
+                "Failed to serialize service events request: {error}"
+            ))
+        })?;
+        let encoded = a3s_transport::Frame::data(payload)
+            .encode()
+            .map_err(|error| {
+                BoxError::ExecError(format!("Failed to encode service events request: {error}"))
+            })?;
+        stream.write_all(&encoded).await.map_err(|error| {
+            BoxError::ExecError(format!("Service events request write failed: {error}"))
+        })?;
+
+        let (read, _write) = tokio::io::split(stream);
+        let mut reader = a3s_transport::FrameReader::new(read);
+        while let Some(frame) = reader
+            .read_frame()
+            .await
+            .map_err(|error| BoxError::ExecError(format!("Service events read failed: {error}")))?
+        {
+            match frame.frame_type {
+                a3s_transport::FrameType::Data => {
+                    on_status(serde_json::from_slice(&frame.payload).map_err(|error| {
+                        BoxError::ExecError(format!("Failed to parse service status: {error}"))
+                    })?)
+                }
+                a3s_transport::FrameType::Heartbeat => {}
+                // Guests from before service supervision reject the envelope.
+                a3s_transport::FrameType::Error => {
+                    return Err(BoxError::ExecError(format!(
+                        "Guest service events unavailable: {}",
+                        String::from_utf8_lossy(&frame.payload)
+                    )))
+                }
+                other => {
+                    return Err(BoxError::ExecError(format!(
+                        "Unexpected service events frame: {other:?}"
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Send a Heartbeat frame and wait for a Heartbeat response.
     ///
     /// Returns `true` if the exec server responds, `false` otherwise.
@@ -996,6 +1147,156 @@ mod tests {
         assert!(response.entries.is_empty());
     }
 
+    #[tokio::test]
+    async fn metrics_uses_the_discriminated_guest_request() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let sock_path = tmp.path().join("metrics.sock");
+        let Some(listener) = bind_test_listener(&sock_path) else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            drop(stream);
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, write) = tokio::io::split(stream);
+            let mut reader = a3s_transport::FrameReader::new(read);
+            let mut writer = a3s_transport::FrameWriter::new(write);
+            let frame = reader.read_frame().await.unwrap().unwrap();
+            let request: a3s_box_core::GuestSessionRequest =
+                serde_json::from_slice(&frame.payload).unwrap();
+            match request {
+                a3s_box_core::GuestSessionRequest::Metrics(request) => {
+                    assert_eq!(request.sample_ms, 200);
+                    assert_eq!(request.top, 3);
+                }
+                other => panic!("unexpected guest request: {other:?}"),
+            }
+            writer
+                .write_data(
+                    &serde_json::to_vec(&a3s_box_core::GuestMetrics {
+                        cpus: 2,
+                        cpu_percent: 50.0,
+                        processes: 4,
+                        ..Default::default()
+                    })
+                    .unwrap(),
+                )
+                .await
+                .unwrap();
+        });
+
+        let client = ExecClient::connect(&sock_path).await.unwrap();
+        let metrics = client
+            .metrics(&a3s_box_core::GuestMetricsRequest {
+                sample_ms: 200,
+                top: 3,
+            })
+            .await
+            .unwrap();
+        assert_eq!(metrics.cpus, 2);
+        assert_eq!(metrics.processes, 4);
+    }
+
+    #[tokio::test]
+    async fn process_tree_parses_owned_processes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let sock_path = tmp.path().join("process-tree.sock");
+        let Some(listener) = bind_test_listener(&sock_path) else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            drop(stream);
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, write) = tokio::io::split(stream);
+            let mut reader = a3s_transport::FrameReader::new(read);
+            let mut writer = a3s_transport::FrameWriter::new(write);
+            let frame = reader.read_frame().await.unwrap().unwrap();
+            let request: a3s_box_core::GuestSessionRequest =
+                serde_json::from_slice(&frame.payload).unwrap();
+            assert!(matches!(
+                request,
+                a3s_box_core::GuestSessionRequest::ProcessTree(_)
+            ));
+            writer
+                .write_data(
+                    br#"{"processes":[{"pid":21,"ppid":1,"pgid":20,"sid":1,"command":"sleep 600",
+                        "owner":{"kind":"exec","root_pid":20,"name":"sh"},"orphaned":true}],
+                        "orphans_reaped":3}"#,
+                )
+                .await
+                .unwrap();
+        });
+
+        let client = ExecClient::connect(&sock_path).await.unwrap();
+        let tree = client.process_tree().await.unwrap();
+        assert_eq!(tree.orphans_reaped, 3);
+        let owner = tree.processes[0].owner.as_ref().unwrap();
+        assert_eq!(owner.kind, a3s_box_core::ProcessOwnerKind::Exec);
+        assert_eq!(owner.root_pid, 20);
+        assert!(tree.processes[0].orphaned);
+    }
+
+    #[tokio::test]
+    async fn watch_services_reads_statuses_until_the_guest_closes() {
+        use a3s_box_core::service::{ServiceState, ServiceStatus};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let sock_path = tmp.path().join("services.sock");
+        let Some(listener) = bind_test_listener(&sock_path) else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            drop(stream);
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, write) = tokio::io::split(stream);
+            let mut reader = a3s_transport::FrameReader::new(read);
+            let mut writer = a3s_transport::FrameWriter::new(write);
+            let frame = reader.read_frame().await.unwrap().unwrap();
+            match serde_json::from_slice(&frame.payload).unwrap() {
+                a3s_box_core::GuestSessionRequest::ServiceEvents(request) => {
+                    assert!(request.follow)
+                }
+                other => panic!("unexpected guest request: {other:?}"),
+            }
+            for (state, pid) in [
+                (ServiceState::Running, Some(7)),
+                (ServiceState::Exited, None),
+            ] {
+                let status = ServiceStatus {
+                    name: "proxy".to_string(),
+                    state,
+                    pid,
+                    restarts: 0,
+                    exit_code: None,
+                    message: None,
+                };
+                writer
+                    .write_data(&serde_json::to_vec(&status).unwrap())
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let client = ExecClient::connect(&sock_path).await.unwrap();
+        let mut states = Vec::new();
+        client
+            .watch_services(true, |status| states.push((status.name, status.state)))
+            .await
+            .unwrap();
+        assert_eq!(
+            states,
+            vec![
+                ("proxy".to_string(), ServiceState::Running),
+                ("proxy".to_string(), ServiceState::Exited),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_archive_rootfs_streams_data_until_done_marker() {
         let tmp = tempfile::TempDir::new().unwrap();
@@ -13,8 +13,8 @@ mod pty;
 
 #[cfg(unix)]
 pub use attestation::{
-    AttestationClient, RaTlsAttestationClient, SealClient, SealResult, SecretEntry,
-    SecretInjectionResult, SecretInjector, UnsealResult,
+    AttestationClient, ChallengeReport, RaTlsAttestationClient, SealClient, SealResult,
+    SecretEntry, SecretInjectionResult, SecretInjector, UnsealResult,
 };
 #[cfg(unix)]
 pub use exec::{ExecClient, StreamingExec, StreamingExecInput};
@@ -311,6 +311,8 @@ mod tests {
             user: Some("1000:1000".to_string()),
             cols: 100,
             rows: 30,
+            session: None,
+            resume: false,
         }
     }
 
@@ -578,6 +580,8 @@ mod tests {
             user: None,
             cols: 80,
             rows: 24,
+            session: None,
+            resume: false,
         };
         let mut stream = client.start_stream(&req).await.unwrap();
 
@@ -699,6 +703,8 @@ mod tests {
             user: None,
             cols: 80,
             rows: 24,
+            session: None,
+            resume: false,
         };
         let mut stream = client.start_stream(&req).await.unwrap();
         stream.cancel().await.unwrap();
@@ -0,0 +1,190 @@
+//! On-disk layout version of the a3s-box home directory (`~/.a3s`).
+//!
+//! `layout.json` at the top of the home directory records which layout the
+//! boxes, images, networks, volumes and caches below it are in. A binary
+//! migrates older layouts forward through [`MIGRATIONS`] before touching
+//! state, and refuses to run against a layout newer than [`LAYOUT_VERSION`]
+//! so an older binary cannot rewrite state it does not understand.
+//!
+//! A home without the marker is layout 0: everything written before
+//! versioning existed.
+
+use std::path::{Path, PathBuf};
+
+use a3s_box_core::error::{BoxError, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::file_lock::FileLock;
+
+/// The layout this binary reads and writes.
+pub const LAYOUT_VERSION: u32 = 1;
+
+/// Marker file name, relative to the home directory.
+pub const LAYOUT_FILE: &str = "layout.json";
+
+/// Contents of `layout.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutMarker {
+    pub version: u32,
+    /// a3s-box version that last wrote the marker.
+    pub written_by: String,
+}
+
+/// One forward step, from layout `from` to `from + 1`.
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    apply: fn(&Path) -> Result<()>,
+}
+
+/// Every step, in order; `MIGRATIONS[n].from == n`.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "record the layout version; existing boxes, images, networks, \
+                  volumes and caches are already in layout 1",
+    apply: record_only,
+}];
+
+fn record_only(_home: &Path) -> Result<()> {
+    Ok(())
+}
+
+pub fn marker_path(home: &Path) -> PathBuf {
+    home.join(LAYOUT_FILE)
+}
+
+/// The layout `home` is in: `None` when the home does not exist yet, `0`
+/// when it predates versioning.
+pub fn read_version(home: &Path) -> Result<Option<u32>> {
+    if !home.exists() {
+        return Ok(None);
+    }
+    let path = marker_path(home);
+    match std::fs::read(&path) {
+        Ok(bytes) => {
+            let marker: LayoutMarker =
+                serde_json::from_slice(&bytes).map_err(|e| BoxError::StoreCorrupted {
+                    path: path.display().to_string(),
+                    message: e.to_string(),
+                })?;
+            Ok(Some(marker.version))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Some(0)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The steps that would bring `home` to [`LAYOUT_VERSION`]. Fails when the
+/// home is in a newer layout.
+pub fn pending_migrations(home: &Path) -> Result<Vec<&'static Migration>> {
+    match read_version(home)? {
+        None => Ok(Vec::new()),
+        Some(version) => plan(home, version),
+    }
+}
+
+fn plan(home: &Path, version: u32) -> Result<Vec<&'static Migration>> {
+    if version > LAYOUT_VERSION {
+        return Err(BoxError::StateError(format!(
+            "{} is in on-disk layout {version}, but this a3s-box only supports \
+             layout {LAYOUT_VERSION}; upgrade a3s-box rather than running an \
+             older binary against newer state",
+            home.display()
+        )));
+    }
+    Ok(MIGRATIONS[version as usize..].iter().collect())
+}
+
+/// Bring `home` to [`LAYOUT_VERSION`], returning the steps applied. The
+/// marker is rewritten after every step, so an interrupted migration resumes
+/// where it stopped. A missing home is left alone.
+pub fn migrate(home: &Path) -> Result<Vec<&'static Migration>> {
+    let path = marker_path(home);
+    if !home.exists() {
+        return Ok(Vec::new());
+    }
+    let _lock = FileLock::acquire(&path)?;
+    let Some(version) = read_version(home)? else {
+        return Ok(Vec::new());
+    };
+    let steps = plan(home, version)?;
+    for step in &steps {
+        (step.apply)(home)?;
+        write_marker(home, step.from + 1)?;
+        tracing::info!(
+            home = %home.display(),
+            from = step.from,
+            to = step.from + 1,
+            "Migrated a3s-box home layout"
+        );
+    }
+    Ok(steps)
+}
+
+/// Fail on a newer layout and migrate an older one. Cheap when the home is
+/// already current.
+pub fn ensure_current(home: &Path) -> Result<()> {
+    match read_version(home)? {
+        Some(LAYOUT_VERSION) | None => Ok(()),
+        Some(_) => migrate(home).map(|_| ()),
+    }
+}
+
+fn write_marker(home: &Path, version: u32) -> Result<()> {
+    let marker = LayoutMarker {
+        version,
+        written_by: a3s_box_core::VERSION.to_string(),
+    };
+    let bytes = serde_json::to_vec_pretty(&marker)?;
+    let path = marker_path(home);
+    let tmp = home.join(format!(".{LAYOUT_FILE}.{}.tmp", std::process::id()));
+    a3s_box_core::fs_atomic::write_durable(&tmp, &path, &bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_contiguous_up_to_current() {
+        assert_eq!(MIGRATIONS.len(), LAYOUT_VERSION as usize);
+        for (index, step) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(step.from as usize, index);
+        }
+    }
+
+    #[test]
+    fn test_unversioned_home_migrates_to_current() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("boxes.json"), "{}").unwrap();
+        assert_eq!(read_version(dir.path()).unwrap(), Some(0));
+        assert_eq!(pending_migrations(dir.path()).unwrap().len(), 1);
+        // Previewing does not write the marker.
+        assert!(!marker_path(dir.path()).exists());
+
+        ensure_current(dir.path()).unwrap();
+        assert_eq!(read_version(dir.path()).unwrap(), Some(LAYOUT_VERSION));
+        assert!(pending_migrations(dir.path()).unwrap().is_empty());
+        assert!(migrate(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_home_is_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("absent");
+        assert_eq!(read_version(&home).unwrap(), None);
+        ensure_current(&home).unwrap();
+        assert!(!home.exists());
+    }
+
+    #[test]
+    fn test_newer_layout_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        write_marker(dir.path(), LAYOUT_VERSION + 1).unwrap();
+        let err = ensure_current(dir.path()).unwrap_err();
+        assert!(matches!(err, BoxError::StateError(_)));
+        assert!(err.to_string().contains("upgrade a3s-box"));
+        assert!(pending_migrations(dir.path()).is_err());
+    }
+}
@@ -4,8 +4,15 @@
 //! - macOS: Hypervisor.framework (Apple Silicon only)
 //! - Linux: KVM (/dev/kvm)
 //! - Windows: WHPX / Windows Hypervisor Platform
+//!
+//! [`preflight`] widens that into a [`PreflightReport`] covering everything a
+//! box needs from the host. `info`, the pool daemon's `/readyz`, and the CRI
+//! `Status` RPC all read the same report.
+
+use std::path::{Path, PathBuf};
 
 use a3s_box_core::error::{BoxError, Result};
+use serde::{Deserialize, Serialize};
 
 /// Information about virtualization support.
 #[derive(Debug, Clone)]
@@ -176,6 +183,470 @@ fn check_windows_whpx() -> Result<VirtualizationSupport> {
     }
 }
 
+/// Outcome of one preflight check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreflightStatus {
+    /// The host meets the requirement.
+    Pass,
+    /// Boxes run, but a feature is degraded or a limit is low.
+    Warn,
+    /// Boxes cannot run until this is fixed.
+    Fail,
+    /// An optional capability is absent or does not apply on this platform.
+    Skip,
+}
+
+impl std::fmt::Display for PreflightStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Pass => "pass",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+            Self::Skip => "skip",
+        })
+    }
+}
+
+/// What a preflight check inspects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightCheckKind {
+    /// Hardware virtualization (KVM, Hypervisor.framework, WHPX).
+    Virtualization,
+    /// Whether this host is itself a guest, or lets its guests nest.
+    NestedVirtualization,
+    /// AMD SEV-SNP, required by `--tee`.
+    SevSnp,
+    /// cgroup v2 with the cpu, memory, and pids controllers delegated.
+    CgroupV2,
+    /// The `passt` binary behind bridge networking.
+    Passt,
+    /// The Unix socket directory that libkrun bridges vsock ports through.
+    Vsock,
+    /// The soft open-file limit.
+    FileDescriptors,
+    /// Free space under the A3S home directory.
+    DiskSpace,
+}
+
+impl PreflightCheckKind {
+    /// Stable name used in CLI output and JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Virtualization => "virtualization",
+            Self::NestedVirtualization => "nested_virtualization",
+            Self::SevSnp => "sev_snp",
+            Self::CgroupV2 => "cgroup_v2",
+            Self::Passt => "passt",
+            Self::Vsock => "vsock",
+            Self::FileDescriptors => "file_descriptors",
+            Self::DiskSpace => "disk_space",
+        }
+    }
+}
+
+/// One line of a [`PreflightReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreflightCheck {
+    pub kind: PreflightCheckKind,
+    pub status: PreflightStatus,
+    /// What was found.
+    pub detail: String,
+    /// How to fix a `warn` or `fail`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl PreflightCheck {
+    fn new(kind: PreflightCheckKind, status: PreflightStatus, detail: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// Structured result of [`preflight`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// Whether the host can run boxes: no check failed.
+    pub fn is_ready(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|check| check.status == PreflightStatus::Fail)
+    }
+
+    /// The result of one check, if it ran.
+    pub fn get(&self, kind: PreflightCheckKind) -> Option<&PreflightCheck> {
+        self.checks.iter().find(|check| check.kind == kind)
+    }
+
+    /// Checks with the given status, in report order.
+    pub fn with_status(
+        &self,
+        status: PreflightStatus,
+    ) -> impl Iterator<Item = &PreflightCheck> + '_ {
+        self.checks
+            .iter()
+            .filter(move |check| check.status == status)
+    }
+
+    /// One-line summary of the failed checks, for readiness probes.
+    pub fn failure_summary(&self) -> Option<String> {
+        let failures: Vec<String> = self
+            .with_status(PreflightStatus::Fail)
+            .map(|check| format!("{}: {}", check.kind.name(), check.detail))
+            .collect();
+        (!failures.is_empty()).then(|| failures.join("; "))
+    }
+}
+
+/// Thresholds and locations for [`preflight`].
+#[derive(Debug, Clone)]
+pub struct PreflightOptions {
+    /// A3S home directory; disk space is measured on its filesystem.
+    pub home_dir: PathBuf,
+    /// Free space below which the disk check fails.
+    pub min_free_disk_bytes: u64,
+    /// Soft open-file limit below which the descriptor check warns.
+    pub min_open_files: u64,
+}
+
+impl Default for PreflightOptions {
+    fn default() -> Self {
+        Self {
+            home_dir: a3s_box_core::dirs_home(),
+            min_free_disk_bytes: 1024 * 1024 * 1024,
+            min_open_files: 4096,
+        }
+    }
+}
+
+/// Inspect the host without changing it.
+///
+/// Every check runs even when an earlier one fails, so a single report lists
+/// everything that needs fixing.
+pub fn preflight(options: &PreflightOptions) -> PreflightReport {
+    PreflightReport {
+        checks: vec![
+            virtualization_check(),
+            nested_virtualization_check(),
+            sev_snp_check(),
+            cgroup_v2_check(),
+            passt_check(),
+            vsock_check(&options.home_dir),
+            file_descriptor_check(options.min_open_files),
+            disk_space_check(&options.home_dir, options.min_free_disk_bytes),
+        ],
+    }
+}
+
+fn virtualization_check() -> PreflightCheck {
+    let kind = PreflightCheckKind::Virtualization;
+    match check_virtualization_support() {
+        Ok(support) => PreflightCheck::new(
+            kind,
+            PreflightStatus::Pass,
+            format!("{} ({})", support.backend, support.details),
+        ),
+        Err(error) => PreflightCheck::new(kind, PreflightStatus::Fail, error.to_string()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn nested_virtualization_check() -> PreflightCheck {
+    let kind = PreflightCheckKind::NestedVirtualization;
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    if cpuinfo_has_flag(&cpuinfo, "hypervisor") {
+        return PreflightCheck::new(
+            kind,
+            PreflightStatus::Warn,
+            "this host is itself a virtual machine; boxes run as nested guests",
+        )
+        .with_hint("Expect slower boot and I/O than on bare metal");
+    }
+    for module in ["kvm_intel", "kvm_amd"] {
+        let path = format!("/sys/module/{module}/parameters/nested");
+        if let Ok(value) = std::fs::read_to_string(&path) {
+            let enabled = matches!(value.trim(), "Y" | "1");
+            let detail = format!(
+                "{module} nested={}; boxes {} start their own VMs",
+                value.trim(),
+                if enabled { "can" } else { "cannot" }
+            );
+            let status = if enabled {
+                PreflightStatus::Pass
+            } else {
+                PreflightStatus::Skip
+            };
+            return PreflightCheck::new(kind, status, detail);
+        }
+    }
+    PreflightCheck::new(kind, PreflightStatus::Skip, "KVM module not loaded")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn nested_virtualization_check() -> PreflightCheck {
+    PreflightCheck::new(
+        PreflightCheckKind::NestedVirtualization,
+        PreflightStatus::Skip,
+        "not reported on this platform",
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn cpuinfo_has_flag(cpuinfo: &str, flag: &str) -> bool {
+    cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("flags"))
+        .filter_map(|line| line.split_once(':'))
+        .any(|(_, flags)| flags.split_whitespace().any(|value| value == flag))
+}
+
+fn sev_snp_check() -> PreflightCheck {
+    let kind = PreflightCheckKind::SevSnp;
+    if !cfg!(target_os = "linux") {
+        return PreflightCheck::new(kind, PreflightStatus::Skip, "requires Linux with KVM");
+    }
+    match crate::tee::check_sev_snp_support() {
+        Ok(support) if support.available => {
+            PreflightCheck::new(kind, PreflightStatus::Pass, "AMD SEV-SNP is available")
+        }
+        Ok(support) => PreflightCheck::new(
+            kind,
+            PreflightStatus::Skip,
+            support
+                .reason
+                .unwrap_or_else(|| "AMD SEV-SNP is unavailable".to_string()),
+        ),
+        Err(error) => PreflightCheck::new(kind, PreflightStatus::Skip, error.to_string()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn cgroup_v2_check() -> PreflightCheck {
+    let kind = PreflightCheckKind::CgroupV2;
+    let evidence = crate::sandbox::capability::probe_cgroup_v2();
+    let Some(path) = evidence.current_path else {
+        return PreflightCheck::new(kind, PreflightStatus::Warn, "cgroup v2 is not mounted")
+            .with_hint("Boot with systemd.unified_cgroup_hierarchy=1 to use sandbox isolation");
+    };
+    if evidence.delegated {
+        PreflightCheck::new(
+            kind,
+            PreflightStatus::Pass,
+            format!(
+                "{} delegated ({})",
+                path.display(),
+                evidence.controllers.join(" ")
+            ),
+        )
+    } else {
+        PreflightCheck::new(
+            kind,
+            PreflightStatus::Warn,
+            format!(
+                "{} is not delegated to this user (controllers: {})",
+                path.display(),
+                if evidence.controllers.is_empty() {
+                    "none".to_string()
+                } else {
+                    evidence.controllers.join(" ")
+                }
+            ),
+        )
+        .with_hint(
+            "Run under a systemd unit with Delegate=yes to use sandbox isolation \
+             and per-box resource limits",
+        )
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cgroup_v2_check() -> PreflightCheck {
+    PreflightCheck::new(
+        PreflightCheckKind::CgroupV2,
+        PreflightStatus::Skip,
+        "cgroups are Linux-only",
+    )
+}
+
+fn passt_check() -> PreflightCheck {
+    let kind = PreflightCheckKind::Passt;
+    let backend = a3s_box_core::PlatformCapabilities::current().bridge_network_backend;
+    if backend != a3s_box_core::BridgeNetworkBackend::Passt {
+        return PreflightCheck::new(
+            kind,
+            PreflightStatus::Skip,
+            format!("bridge networking uses {backend} on this platform"),
+        );
+    }
+    match find_in_path("passt") {
+        Some(path) => PreflightCheck::new(kind, PreflightStatus::Pass, path.display().to_string()),
+        None => PreflightCheck::new(kind, PreflightStatus::Warn, "passt not found in PATH")
+            .with_hint("Install passt to use --network with bridge networks"),
+    }
+}
+
+fn find_in_path(binary: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(unix)]
+fn vsock_check(home_dir: &Path) -> PreflightCheck {
+    let kind = PreflightCheckKind::Vsock;
+    let probe_id = format!("preflight-{}", std::process::id());
+    let probe_dir = crate::vm::runtime_socket_dir(home_dir, &probe_id);
+    let socket = probe_dir.join("probe.sock");
+    let result = std::fs::create_dir_all(&probe_dir)
+        .and_then(|()| std::os::unix::net::UnixListener::bind(&socket).map(drop));
+    let _ = std::fs::remove_dir_all(&probe_dir);
+    let socket_root = probe_dir.parent().unwrap_or(&probe_dir).to_path_buf();
+    match result {
+        Ok(()) => PreflightCheck::new(
+            kind,
+            PreflightStatus::Pass,
+            format!("vsock ports bridge through {}", socket_root.display()),
+        ),
+        Err(error) => PreflightCheck::new(
+            kind,
+            PreflightStatus::Fail,
+            format!(
+                "cannot create Unix sockets under {}: {error}",
+                socket_root.display()
+            ),
+        )
+        .with_hint("The box exec and control channels need a writable socket directory"),
+    }
+}
+
+#[cfg(not(unix))]
+fn vsock_check(_home_dir: &Path) -> PreflightCheck {
+    PreflightCheck::new(
+        PreflightCheckKind::Vsock,
+        PreflightStatus::Pass,
+        format!(
+            "host-guest channel: {}",
+            a3s_box_core::PlatformCapabilities::current().host_guest_channel
+        ),
+    )
+}
+
+#[cfg(unix)]
+fn file_descriptor_check(min_open_files: u64) -> PreflightCheck {
+    let kind = PreflightCheckKind::FileDescriptors;
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `limit` is a valid, writable rlimit owned by this frame.
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return PreflightCheck::new(
+            kind,
+            PreflightStatus::Warn,
+            format!(
+                "failed to read RLIMIT_NOFILE: {}",
+                std::io::Error::last_os_error()
+            ),
+        );
+    }
+    let soft = limit.rlim_cur as u64;
+    let detail = format!("soft limit {soft}, hard limit {}", limit.rlim_max as u64);
+    if soft >= min_open_files {
+        PreflightCheck::new(kind, PreflightStatus::Pass, detail)
+    } else {
+        PreflightCheck::new(kind, PreflightStatus::Warn, detail).with_hint(format!(
+            "Raise the open-file limit to at least {min_open_files} (ulimit -n {min_open_files}, \
+             or LimitNOFILE= in the service unit)"
+        ))
+    }
+}
+
+#[cfg(not(unix))]
+fn file_descriptor_check(_min_open_files: u64) -> PreflightCheck {
+    PreflightCheck::new(
+        PreflightCheckKind::FileDescriptors,
+        PreflightStatus::Skip,
+        "no per-process descriptor limit on this platform",
+    )
+}
+
+#[cfg(unix)]
+fn disk_space_check(home_dir: &Path, min_free_bytes: u64) -> PreflightCheck {
+    let kind = PreflightCheckKind::DiskSpace;
+    // The home directory may not exist before the first box; measure the
+    // filesystem it will be created on.
+    let Some(existing) = home_dir.ancestors().find(|path| path.exists()) else {
+        return PreflightCheck::new(
+            kind,
+            PreflightStatus::Warn,
+            format!("{} has no existing ancestor", home_dir.display()),
+        );
+    };
+    let Some(free) = available_bytes(existing) else {
+        return PreflightCheck::new(
+            kind,
+            PreflightStatus::Warn,
+            format!("failed to measure free space on {}", existing.display()),
+        );
+    };
+    let detail = format!("{} free on {}", format_gib(free), existing.display());
+    if free >= min_free_bytes {
+        PreflightCheck::new(kind, PreflightStatus::Pass, detail)
+    } else {
+        PreflightCheck::new(kind, PreflightStatus::Fail, detail).with_hint(format!(
+            "Free at least {} (a3s-box system prune) or set A3S_HOME to a larger disk",
+            format_gib(min_free_bytes)
+        ))
+    }
+}
+
+#[cfg(not(unix))]
+fn disk_space_check(home_dir: &Path, _min_free_bytes: u64) -> PreflightCheck {
+    PreflightCheck::new(
+        PreflightCheckKind::DiskSpace,
+        PreflightStatus::Skip,
+        format!(
+            "free space on {} is not measured on this platform",
+            home_dir.display()
+        ),
+    )
+}
+
+#[cfg(unix)]
+fn available_bytes(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid NUL-terminated string and `stat` is a
+    // writable statvfs buffer owned by this frame.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(unix)]
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,4 +666,73 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_preflight_report_readiness() {
+        let mut report = PreflightReport {
+            checks: vec![
+                PreflightCheck::new(
+                    PreflightCheckKind::Virtualization,
+                    PreflightStatus::Pass,
+                    "KVM",
+                ),
+                PreflightCheck::new(PreflightCheckKind::Passt, PreflightStatus::Warn, "missing"),
+                PreflightCheck::new(PreflightCheckKind::SevSnp, PreflightStatus::Skip, "absent"),
+            ],
+        };
+        assert!(report.is_ready());
+        assert_eq!(report.failure_summary(), None);
+
+        report.checks.push(PreflightCheck::new(
+            PreflightCheckKind::DiskSpace,
+            PreflightStatus::Fail,
+            "0.1 GiB free",
+        ));
+        assert!(!report.is_ready());
+        assert_eq!(
+            report.failure_summary().as_deref(),
+            Some("disk_space: 0.1 GiB free")
+        );
+        assert_eq!(
+            report.get(PreflightCheckKind::Passt).unwrap().status,
+            PreflightStatus::Warn
+        );
+
+        let json = serde_json::to_value(&report.checks[3]).unwrap();
+        assert_eq!(json["kind"], "disk_space");
+        assert_eq!(json["status"], "fail");
+        assert!(json.get("hint").is_none());
+    }
+
+    #[test]
+    fn test_preflight_runs_every_check() {
+        let home = tempfile::tempdir().unwrap();
+        let report = preflight(&PreflightOptions {
+            home_dir: home.path().join("not-yet-created"),
+            ..Default::default()
+        });
+        assert_eq!(report.checks.len(), 8);
+        assert!(report.get(PreflightCheckKind::DiskSpace).is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_disk_space_check_threshold() {
+        let home = tempfile::tempdir().unwrap();
+        assert_eq!(
+            disk_space_check(home.path(), 0).status,
+            PreflightStatus::Pass
+        );
+        let short = disk_space_check(home.path(), u64::MAX);
+        assert_eq!(short.status, PreflightStatus::Fail);
+        assert!(short.hint.is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cpuinfo_has_flag() {
+        let cpuinfo = "processor\t: 0\nflags\t\t: fpu vmx hypervisor\n";
+        assert!(cpuinfo_has_flag(cpuinfo, "hypervisor"));
+        assert!(!cpuinfo_has_flag(cpuinfo, "svm"));
+    }
 }
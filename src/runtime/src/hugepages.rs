@@ -0,0 +1,113 @@
+//! Host transparent hugepage (THP) helpers.
+//!
+//! Boxes started with `hugepages` have their guest RAM advised
+//! `MADV_HUGEPAGE` by the shim. These helpers report whether the host THP
+//! policy allows that to take effect and how much of a VM process is actually
+//! backed by hugepages, so the CLI can warn about no-op opt-ins and surface the
+//! result in `a3s-box stats`.
+
+use std::path::Path;
+
+/// Host THP policy, from `/sys/kernel/mm/transparent_hugepage/enabled`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThpMode {
+    /// `[always]`: every eligible anonymous mapping may use hugepages.
+    Always,
+    /// `[madvise]`: only regions advised `MADV_HUGEPAGE` use hugepages.
+    Madvise,
+    /// `[never]`: THP is disabled; advice is ignored.
+    Never,
+    /// The host kernel does not expose THP (non-Linux or `CONFIG_TRANSPARENT_HUGEPAGE=n`).
+    Unavailable,
+}
+
+impl ThpMode {
+    /// Whether advised guest RAM can be backed by hugepages.
+    pub fn allows_advised(self) -> bool {
+        matches!(self, Self::Always | Self::Madvise)
+    }
+}
+
+/// Read the host THP policy.
+pub fn host_thp_mode() -> ThpMode {
+    read_thp_mode(Path::new("/sys/kernel/mm/transparent_hugepage/enabled"))
+}
+
+fn read_thp_mode(path: &Path) -> ThpMode {
+    match std::fs::read_to_string(path) {
+        Ok(value) => parse_thp_mode(&value),
+        Err(_) => ThpMode::Unavailable,
+    }
+}
+
+/// Parse the sysfs format, where the active mode is bracketed:
+/// `always [madvise] never`.
+fn parse_thp_mode(value: &str) -> ThpMode {
+    let active = value
+        .split_whitespace()
+        .find(|word| word.starts_with('[') && word.ends_with(']'))
+        .map(|word| word.trim_matches(|c| c == '[' || c == ']'));
+    match active {
+        Some("always") => ThpMode::Always,
+        Some("madvise") => ThpMode::Madvise,
+        Some("never") => ThpMode::Never,
+        _ => ThpMode::Unavailable,
+    }
+}
+
+/// Bytes of a host process's anonymous memory backed by transparent
+/// hugepages, from `/proc/<pid>/smaps_rollup`.
+///
+/// Returns `None` when the kernel does not expose the rollup or the process is
+/// gone.
+pub fn process_anon_huge_bytes(pid: u32) -> Option<u64> {
+    let data = std::fs::read_to_string(format!("/proc/{pid}/smaps_rollup")).ok()?;
+    parse_anon_huge_bytes(&data)
+}
+
+fn parse_anon_huge_bytes(data: &str) -> Option<u64> {
+    data.lines().find_map(|line| {
+        let value = line.strip_prefix("AnonHugePages:")?;
+        let kib = value
+            .trim()
+            .strip_suffix("kB")?
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kib * 1024)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_thp_mode() {
+        assert_eq!(parse_thp_mode("[always] madvise never\n"), ThpMode::Always);
+        assert_eq!(parse_thp_mode("always [madvise] never\n"), ThpMode::Madvise);
+        assert_eq!(parse_thp_mode("always madvise [never]\n"), ThpMode::Never);
+        assert_eq!(parse_thp_mode("bogus"), ThpMode::Unavailable);
+        assert!(ThpMode::Madvise.allows_advised());
+        assert!(!ThpMode::Never.allows_advised());
+    }
+
+    #[test]
+    fn test_read_thp_mode_missing_file_is_unavailable() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            read_thp_mode(&tmp.path().join("enabled")),
+            ThpMode::Unavailable
+        );
+    }
+
+    #[test]
+    fn test_parse_anon_huge_bytes() {
+        let rollup = "55d0c0000000-7ffd00000000 ---p 00000000 00:00 0 [rollup]\n\
+                      Rss:             2105344 kB\n\
+                      AnonHugePages:   2048000 kB\n\
+                      ShmemHugePages:        0 kB\n";
+        assert_eq!(parse_anon_huge_bytes(rollup), Some(2048000 * 1024));
+        assert_eq!(parse_anon_huge_bytes("Rss: 12 kB\n"), None);
+    }
+}
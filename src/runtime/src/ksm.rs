@@ -0,0 +1,138 @@
+//! Host Kernel Samepage Merging (KSM) helpers.
+//!
+//! Boxes started with `ksm` enabled mark their guest memory mergeable from the
+//! shim (`PR_SET_MEMORY_MERGE`). These helpers report whether the host merge
+//! daemon is actually running and how many pages a given VM process currently
+//! shares, so the CLI can warn about no-op opt-ins and surface savings in
+//! `a3s-box stats`.
+
+use std::path::Path;
+
+/// Host page size assumed when converting KSM page counts to bytes.
+const PAGE_SIZE: u64 = 4096;
+
+/// State of the host KSM daemon, from `/sys/kernel/mm/ksm/run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KsmHostState {
+    /// `run=0`: merging is stopped; mergeable regions are ignored.
+    Stopped,
+    /// `run=1`: ksmd is scanning mergeable regions.
+    Running,
+    /// `run=2`: merging is stopped and previously merged pages were unmerged.
+    Unmerged,
+    /// The host kernel does not expose KSM (non-Linux or `CONFIG_KSM=n`).
+    Unavailable,
+}
+
+impl KsmHostState {
+    /// Whether mergeable guest memory will actually be deduplicated.
+    pub fn is_running(self) -> bool {
+        self == Self::Running
+    }
+}
+
+/// Per-process KSM counters from `/proc/<pid>/ksm_stat` (Linux 6.1+).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KsmProcessStats {
+    /// Pages of this process currently merged with identical pages elsewhere.
+    pub merging_pages: u64,
+    /// Net bytes saved for this process, as reported by the kernel.
+    pub profit_bytes: Option<i64>,
+}
+
+impl KsmProcessStats {
+    /// Bytes of this process backed by shared KSM pages.
+    pub fn shared_bytes(&self) -> u64 {
+        self.merging_pages.saturating_mul(PAGE_SIZE)
+    }
+}
+
+/// Read the host KSM daemon state.
+pub fn host_state() -> KsmHostState {
+    read_host_state(Path::new("/sys/kernel/mm/ksm/run"))
+}
+
+fn read_host_state(path: &Path) -> KsmHostState {
+    match std::fs::read_to_string(path) {
+        Ok(value) => parse_host_state(&value),
+        Err(_) => KsmHostState::Unavailable,
+    }
+}
+
+fn parse_host_state(value: &str) -> KsmHostState {
+    match value.trim() {
+        "0" => KsmHostState::Stopped,
+        "1" => KsmHostState::Running,
+        "2" => KsmHostState::Unmerged,
+        _ => KsmHostState::Unavailable,
+    }
+}
+
+/// Read KSM sharing counters for a host process.
+///
+/// Returns `None` when the kernel does not expose `ksm_stat` or the process
+/// is gone.
+pub fn process_stats(pid: u32) -> Option<KsmProcessStats> {
+    let data = std::fs::read_to_string(format!("/proc/{pid}/ksm_stat")).ok()?;
+    parse_process_stats(&data)
+}
+
+fn parse_process_stats(data: &str) -> Option<KsmProcessStats> {
+    let mut merging_pages = None;
+    let mut profit_bytes = None;
+    for line in data.lines() {
+        let Some((key, value)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        match key {
+            "ksm_merging_pages" => merging_pages = value.trim().parse::<u64>().ok(),
+            "ksm_process_profit" => profit_bytes = value.trim().parse::<i64>().ok(),
+            _ => {}
+        }
+    }
+    Some(KsmProcessStats {
+        merging_pages: merging_pages?,
+        profit_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host_state() {
+        assert_eq!(parse_host_state("0\n"), KsmHostState::Stopped);
+        assert_eq!(parse_host_state("1\n"), KsmHostState::Running);
+        assert_eq!(parse_host_state("2"), KsmHostState::Unmerged);
+        assert_eq!(parse_host_state("bogus"), KsmHostState::Unavailable);
+        assert!(parse_host_state("1").is_running());
+        assert!(!parse_host_state("0").is_running());
+    }
+
+    #[test]
+    fn test_read_host_state_missing_file_is_unavailable() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            read_host_state(&tmp.path().join("run")),
+            KsmHostState::Unavailable
+        );
+    }
+
+    #[test]
+    fn test_parse_process_stats() {
+        let stats = parse_process_stats(
+            "ksm_rmap_items 120\nksm_zero_pages 3\nksm_merging_pages 100\nksm_process_profit 401920\nksm_merge_any: yes\n",
+        )
+        .unwrap();
+
+        assert_eq!(stats.merging_pages, 100);
+        assert_eq!(stats.profit_bytes, Some(401920));
+        assert_eq!(stats.shared_bytes(), 100 * 4096);
+    }
+
+    #[test]
+    fn test_parse_process_stats_requires_merging_pages() {
+        assert_eq!(parse_process_stats("ksm_rmap_items 10\n"), None);
+    }
+}
@@ -10,6 +10,7 @@
 //! - `compose` — Multi-container compose orchestration (enabled by default)
 //! - `operator` — Kubernetes CRD autoscaler controller (enabled by default)
 //! - `build` — Dockerfile/Containerfile build engine (enabled by default)
+//! - `sqlite-storage` — SQLite [`storage`] backend for daemon/cluster state
 
 #![allow(clippy::result_large_err)]
 
@@ -21,13 +22,19 @@ pub mod box_record;
 pub mod box_state;
 pub mod cache;
 pub(crate) mod file_lock;
+#[cfg(unix)]
+pub mod forward;
 pub mod fs;
 pub mod grpc;
+pub mod home_layout;
 pub mod host_check;
+pub mod hugepages;
+pub mod ksm;
 pub mod local_execution;
 pub mod log;
 pub mod managed_execution_store;
 pub mod network;
+pub mod numa;
 pub mod oci;
 pub mod process;
 pub mod prom;
@@ -36,6 +43,7 @@ mod resolved_image;
 pub mod rootfs;
 pub mod sandbox;
 pub mod snapshot;
+pub mod storage;
 mod store_io;
 #[cfg(unix)]
 pub mod tee;
@@ -65,8 +73,9 @@ pub use a3s_runtime_driver::{BoxRuntimeDriver, BoxRuntimeDriverConfig};
 
 // Canonical local execution metadata
 pub use box_record::{
-    BoxRecord, HealthCheck, ManagedExecutionMetadata, ManagedExecutionOperation,
-    ManagedExecutionState, ManagedRestartCompletion, ManagedRestartOutcome,
+    BoxMetadata, BoxNote, BoxRecord, HealthCheck, ManagedExecutionMetadata,
+    ManagedExecutionOperation, ManagedExecutionState, ManagedRestartCompletion,
+    ManagedRestartOutcome,
 };
 pub use box_state::BoxStateStore;
 #[cfg(feature = "vm")]
@@ -84,14 +93,21 @@ pub use process::{is_process_alive, is_process_alive_with_identity, pid_start_ti
 // gRPC clients
 #[cfg(unix)]
 pub use grpc::{
-    AttestationClient, ExecClient, PtyClient, RaTlsAttestationClient, StreamingExec,
-    StreamingExecInput, StreamingPty, StreamingPtyInput,
+    AttestationClient, ChallengeReport, ExecClient, PtyClient, RaTlsAttestationClient,
+    StreamingExec, StreamingExecInput, StreamingPty, StreamingPtyInput,
 };
 #[cfg(unix)]
 pub use grpc::{SealClient, SecretEntry, SecretInjector};
 
+// Port forwarding
+#[cfg(unix)]
+pub use forward::{connect_guest_port, forward_socket_path, PortForward};
+
 // Host checks
-pub use host_check::check_virtualization_support;
+pub use host_check::{
+    check_virtualization_support, preflight, PreflightCheck, PreflightCheckKind, PreflightOptions,
+    PreflightReport, PreflightStatus,
+};
 
 // Network
 pub use network::NetworkStore;
@@ -125,7 +141,10 @@ pub use tee::{AttestationReport, AttestationRequest, PlatformInfo};
 
 // VM
 #[cfg(feature = "vm")]
-pub use vm::{BoxState, PullProgressFn, VmManager};
+pub use vm::{
+    map_image_config, BoxState, ConfigMapping, MappedVolume, MappedVolumeSource, PullProgressFn,
+    VmManager,
+};
 #[cfg(feature = "vm")]
 pub use vmm::{
     Entrypoint, FsMount, InstanceSpec, NetworkInstanceConfig, ShimHandler, TeeInstanceConfig,
@@ -136,7 +155,7 @@ pub use vmm::{
 pub use resize::{validate_update, ResizeResult, ResourceUpdate};
 
 // Volume
-pub use volume::VolumeStore;
+pub use volume::{volume_driver, VolumeAttachment, VolumeDriver, VolumeStore};
 
 // ── Feature-gated re-exports ──
 
@@ -88,6 +88,15 @@ pub(crate) fn build_managed_record(
         resource_limits: config.resource_limits.clone(),
         log_config: policy.log_config.clone(),
         add_host: config.add_hosts.clone(),
+        dns: config.dns.clone(),
+        dns_search: config.dns_search.clone(),
+        dns_option: config.dns_options.clone(),
+        network_aliases: config.network_aliases.clone(),
+        network_policy: config.network_policy.clone(),
+        network_bandwidth: config.network_bandwidth,
+        network_max_conns: config.network_max_conns,
+        http_proxy: config.http_proxy,
+        socket_mounts: config.socket_mounts.clone(),
         platform: policy.platform.clone(),
         init: policy.init,
         read_only: config.read_only,
@@ -102,6 +111,8 @@ pub(crate) fn build_managed_record(
         stop_timeout: policy.stop_timeout,
         oom_kill_disable: policy.oom_kill_disable,
         oom_score_adj: policy.oom_score_adj,
+        workspace_template: config.workspace_template.clone(),
+        metadata: Default::default(),
     })
 }
 
@@ -68,7 +68,7 @@ impl ExecutionResourceGuard {
                         return Ok(false);
                     }
                     network
-                        .connect(&record.id, &record.name)
+                        .connect_with_aliases(&record.id, &record.name, &record.network_aliases)
                         .map_err(BoxError::NetworkError)?;
                     Ok(true)
                 })
@@ -6,4 +6,6 @@
 //! `spawn_blocking` task in the ephemeral CLI). This module re-exports the parts
 //! the CLI still needs to locate and read the structured log file.
 
-pub use a3s_box_core::log::{is_runtime_console_noise, json_log_path, RuntimeConsoleFilter};
+pub use a3s_box_core::log::{
+    is_runtime_console_noise, json_log_path, rotated_log_paths, RuntimeConsoleFilter,
+};
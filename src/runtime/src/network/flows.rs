@@ -0,0 +1,501 @@
+//! Per-box network flow records derived from passt's packet capture.
+//!
+//! Every bridge-mode box runs its own passt relay, started with `--pcap` so
+//! all Ethernet frames between the guest and the relay land in
+//! `<socket_dir>/passt.pcap`. Aggregating those frames by protocol and
+//! address pair gives one record per connection (or UDP/ICMP exchange): who
+//! opened it, how many bytes went each way, and how long it lasted — without
+//! any agent inside the guest.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Transport protocol of a flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowProtocol {
+    Tcp,
+    Udp,
+    Icmp,
+}
+
+impl std::fmt::Display for FlowProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+            Self::Icmp => "icmp",
+        })
+    }
+}
+
+/// Which side opened a flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowDirection {
+    /// The guest initiated the flow (an outbound connection).
+    Outbound,
+    /// A remote peer initiated the flow (e.g. a published port).
+    Inbound,
+}
+
+impl std::fmt::Display for FlowDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Outbound => "outbound",
+            Self::Inbound => "inbound",
+        })
+    }
+}
+
+/// One connection or datagram exchange seen on a box's interface.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetFlow {
+    pub protocol: FlowProtocol,
+    pub direction: FlowDirection,
+    /// Guest-side address.
+    pub local_ip: IpAddr,
+    pub local_port: u16,
+    /// Address the guest talked to.
+    pub remote_ip: IpAddr,
+    pub remote_port: u16,
+    /// Frame bytes sent by the guest.
+    pub bytes_sent: u64,
+    /// Frame bytes received by the guest.
+    pub bytes_received: u64,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    /// Capture timestamp of the first frame, in Unix microseconds.
+    pub first_seen_us: u64,
+    /// Capture timestamp of the last frame, in Unix microseconds.
+    pub last_seen_us: u64,
+    /// Whether a TCP FIN or RST was seen.
+    pub closed: bool,
+}
+
+impl NetFlow {
+    /// Time between the first and last frame of the flow.
+    pub fn duration(&self) -> std::time::Duration {
+        std::time::Duration::from_micros(self.last_seen_us.saturating_sub(self.first_seen_us))
+    }
+}
+
+/// Totals over a set of flows, for metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlowSummary {
+    pub outbound: u64,
+    pub inbound: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl FlowSummary {
+    pub fn from_flows(flows: &[NetFlow]) -> Self {
+        flows.iter().fold(Self::default(), |mut summary, flow| {
+            match flow.direction {
+                FlowDirection::Outbound => summary.outbound += 1,
+                FlowDirection::Inbound => summary.inbound += 1,
+            }
+            summary.bytes_sent += flow.bytes_sent;
+            summary.bytes_received += flow.bytes_received;
+            summary
+        })
+    }
+}
+
+/// Read the flows in a passt capture, attributing frames by the guest's MAC.
+pub fn read_pcap_flows(path: &Path, guest_mac: [u8; 6]) -> std::io::Result<Vec<NetFlow>> {
+    let data = std::fs::read(path)?;
+    parse_pcap_flows(&data, guest_mac).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{} is not a pcap capture", path.display()),
+        )
+    })
+}
+
+/// Aggregate the frames of a pcap capture into flows, oldest first.
+///
+/// Returns `None` if `data` is not a pcap file. A truncated trailing record
+/// (passt may be mid-write) ends the scan.
+pub fn parse_pcap_flows(data: &[u8], guest_mac: [u8; 6]) -> Option<Vec<NetFlow>> {
+    let mut flows: Vec<NetFlow> = Vec::new();
+    let mut open: HashMap<FlowKey, usize> = HashMap::new();
+
+    for frame in PcapFrames::new(data)? {
+        let sent = frame.data.get(6..12) == Some(&guest_mac[..]);
+        if !sent && frame.data.get(0..6) != Some(&guest_mac[..]) {
+            continue;
+        }
+        let Some(packet) = parse_packet(frame.data) else {
+            continue;
+        };
+        let (local, remote) = if sent {
+            (packet.src, packet.dst)
+        } else {
+            (packet.dst, packet.src)
+        };
+        let key = FlowKey {
+            protocol: packet.protocol,
+            local,
+            remote,
+        };
+
+        let starts_connection = packet.tcp_syn && !packet.tcp_ack;
+        let index = match open.get(&key) {
+            Some(&index) if !(starts_connection && flows[index].closed) => index,
+            _ => {
+                // The first frame of a flow is normally its SYN (or first
+                // datagram), so its sender is the side that opened it.
+                let direction = if sent {
+                    FlowDirection::Outbound
+                } else {
+                    FlowDirection::Inbound
+                };
+                flows.push(NetFlow {
+                    protocol: packet.protocol,
+                    direction,
+                    local_ip: local.0,
+                    local_port: local.1,
+                    remote_ip: remote.0,
+                    remote_port: remote.1,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    packets_sent: 0,
+                    packets_received: 0,
+                    first_seen_us: frame.timestamp_us,
+                    last_seen_us: frame.timestamp_us,
+                    closed: false,
+                });
+                open.insert(key, flows.len() - 1);
+                flows.len() - 1
+            }
+        };
+
+        let flow = &mut flows[index];
+        if sent {
+            flow.bytes_sent += frame.len;
+            flow.packets_sent += 1;
+        } else {
+            flow.bytes_received += frame.len;
+            flow.packets_received += 1;
+        }
+        flow.last_seen_us = flow.last_seen_us.max(frame.timestamp_us);
+        flow.closed |= packet.tcp_fin_or_rst;
+    }
+
+    Some(flows)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FlowKey {
+    protocol: FlowProtocol,
+    local: (IpAddr, u16),
+    remote: (IpAddr, u16),
+}
+
+/// One captured frame.
+pub struct PcapFrame<'a> {
+    /// Capture timestamp in Unix microseconds.
+    pub timestamp_us: u64,
+    /// Original length of the frame on the wire.
+    pub len: u64,
+    /// Captured bytes (may be shorter than `len`).
+    pub data: &'a [u8],
+}
+
+/// Iterator over the records of a classic (libpcap) capture file.
+pub struct PcapFrames<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+    nanos: bool,
+}
+
+impl<'a> PcapFrames<'a> {
+    /// Start iterating a capture; `None` if the magic number is not pcap's.
+    pub fn new(data: &'a [u8]) -> Option<Self> {
+        let (big_endian, nanos) = match data.get(..4)? {
+            [0xd4, 0xc3, 0xb2, 0xa1] => (false, false),
+            [0x4d, 0x3c, 0xb2, 0xa1] => (false, true),
+            [0xa1, 0xb2, 0xc3, 0xd4] => (true, false),
+            [0xa1, 0xb2, 0x3c, 0x4d] => (true, true),
+            _ => return None,
+        };
+        if data.len() < 24 {
+            return None;
+        }
+        Some(Self {
+            data,
+            offset: 24,
+            big_endian,
+            nanos,
+        })
+    }
+
+    fn read_u32(&self, at: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
+
+impl<'a> Iterator for PcapFrames<'a> {
+    type Item = PcapFrame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = self.offset;
+        let seconds = u64::from(self.read_u32(header)?);
+        let fraction = u64::from(self.read_u32(header + 4)?);
+        let incl_len = self.read_u32(header + 8)? as usize;
+        let orig_len = u64::from(self.read_u32(header + 12)?);
+        let start = header + 16;
+        let data = self.data.get(start..start.checked_add(incl_len)?)?;
+        self.offset = start + incl_len;
+
+        let micros = if self.nanos {
+            fraction / 1000
+        } else {
+            fraction
+        };
+        Some(PcapFrame {
+            timestamp_us: seconds * 1_000_000 + micros,
+            len: if orig_len == 0 {
+                incl_len as u64
+            } else {
+                orig_len
+            },
+            data,
+        })
+    }
+}
+
+struct Packet {
+    protocol: FlowProtocol,
+    src: (IpAddr, u16),
+    dst: (IpAddr, u16),
+    tcp_syn: bool,
+    tcp_ack: bool,
+    tcp_fin_or_rst: bool,
+}
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+fn parse_packet(frame: &[u8]) -> Option<Packet> {
+    let mut ethertype = u16::from_be_bytes([*frame.get(12)?, *frame.get(13)?]);
+    let mut offset = 14;
+    if ethertype == ETHERTYPE_VLAN {
+        ethertype = u16::from_be_bytes([*frame.get(16)?, *frame.get(17)?]);
+        offset = 18;
+    }
+    let ip = frame.get(offset..)?;
+
+    let (proto, src, dst, l4) = match ethertype {
+        ETHERTYPE_IPV4 => {
+            let header_len = usize::from(ip.first()? & 0x0f) * 4;
+            if ip.first()? >> 4 != 4 || header_len < 20 {
+                return None;
+            }
+            // Only the first fragment carries the transport header.
+            let fragment_offset = u16::from_be_bytes([*ip.get(6)?, *ip.get(7)?]) & 0x1fff;
+            let l4 = if fragment_offset == 0 {
+                ip.get(header_len..)?
+            } else {
+                &[]
+            };
+            let src: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+            (
+                *ip.get(9)?,
+                IpAddr::V4(Ipv4Addr::from(src)),
+                IpAddr::V4(Ipv4Addr::from(dst)),
+                l4,
+            )
+        }
+        ETHERTYPE_IPV6 => {
+            if ip.first()? >> 4 != 6 {
+                return None;
+            }
+            let src: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+            (
+                *ip.get(6)?,
+                IpAddr::V6(Ipv6Addr::from(src)),
+                IpAddr::V6(Ipv6Addr::from(dst)),
+                ip.get(40..)?,
+            )
+        }
+        _ => return None,
+    };
+
+    let ports = |l4: &[u8]| -> (u16, u16) {
+        match l4.get(..4) {
+            Some(p) => (
+                u16::from_be_bytes([p[0], p[1]]),
+                u16::from_be_bytes([p[2], p[3]]),
+            ),
+            None => (0, 0),
+        }
+    };
+    let (protocol, (src_port, dst_port), flags) = match proto {
+        6 => (
+            FlowProtocol::Tcp,
+            ports(l4),
+            l4.get(13).copied().unwrap_or(0),
+        ),
+        17 => (FlowProtocol::Udp, ports(l4), 0),
+        1 | 58 => (FlowProtocol::Icmp, (0, 0), 0),
+        _ => return None,
+    };
+
+    Some(Packet {
+        protocol,
+        src: (src, src_port),
+        dst: (dst, dst_port),
+        tcp_syn: flags & 0x02 != 0,
+        tcp_ack: flags & 0x10 != 0,
+        tcp_fin_or_rst: flags & 0x05 != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUEST_MAC: [u8; 6] = [0x02, 0x42, 0x0a, 0x59, 0x00, 0x02];
+    const PASST_MAC: [u8; 6] = [0x9a, 0x55, 0x9a, 0x55, 0x9a, 0x55];
+    const GUEST_IP: [u8; 4] = [10, 89, 0, 2];
+    const REMOTE_IP: [u8; 4] = [93, 184, 216, 34];
+
+    fn ipv4_frame(
+        sent: bool,
+        proto: u8,
+        guest_port: u16,
+        remote_port: u16,
+        tcp_flags: u8,
+        payload: usize,
+    ) -> Vec<u8> {
+        let (dst_mac, src_mac) = if sent {
+            (PASST_MAC, GUEST_MAC)
+        } else {
+            (GUEST_MAC, PASST_MAC)
+        };
+        let (src_ip, dst_ip, src_port, dst_port) = if sent {
+            (GUEST_IP, REMOTE_IP, guest_port, remote_port)
+        } else {
+            (REMOTE_IP, GUEST_IP, remote_port, guest_port)
+        };
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&dst_mac);
+        frame.extend_from_slice(&src_mac);
+        frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        let mut ip = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, proto, 0, 0];
+        ip.extend_from_slice(&src_ip);
+        ip.extend_from_slice(&dst_ip);
+        frame.extend_from_slice(&ip);
+        frame.extend_from_slice(&src_port.to_be_bytes());
+        frame.extend_from_slice(&dst_port.to_be_bytes());
+        let mut l4_rest = vec![0u8; 16];
+        l4_rest[9] = tcp_flags;
+        frame.extend_from_slice(&l4_rest);
+        frame.extend_from_slice(&vec![0u8; payload]);
+        frame
+    }
+
+    fn pcap(frames: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&65535u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for (seconds, frame) in frames {
+            data.extend_from_slice(&seconds.to_le_bytes());
+            data.extend_from_slice(&0u32.to_le_bytes());
+            data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            data.extend_from_slice(frame);
+        }
+        data
+    }
+
+    #[test]
+    fn test_outbound_tcp_connection_becomes_one_flow() {
+        let data = pcap(&[
+            (100, ipv4_frame(true, 6, 40000, 443, 0x02, 0)),
+            (100, ipv4_frame(false, 6, 40000, 443, 0x12, 0)),
+            (101, ipv4_frame(true, 6, 40000, 443, 0x18, 100)),
+            (102, ipv4_frame(false, 6, 40000, 443, 0x18, 1000)),
+            (103, ipv4_frame(true, 6, 40000, 443, 0x11, 0)),
+        ]);
+
+        let flows = parse_pcap_flows(&data, GUEST_MAC).unwrap();
+
+        assert_eq!(flows.len(), 1);
+        let flow = &flows[0];
+        assert_eq!(flow.protocol, FlowProtocol::Tcp);
+        assert_eq!(flow.direction, FlowDirection::Outbound);
+        assert_eq!(flow.remote_ip, IpAddr::V4(Ipv4Addr::from(REMOTE_IP)));
+        assert_eq!((flow.local_port, flow.remote_port), (40000, 443));
+        assert_eq!((flow.packets_sent, flow.packets_received), (3, 2));
+        assert_eq!(flow.bytes_sent, 3 * 54 + 100);
+        assert_eq!(flow.bytes_received, 2 * 54 + 1000);
+        assert_eq!(flow.duration(), std::time::Duration::from_secs(3));
+        assert!(flow.closed);
+    }
+
+    #[test]
+    fn test_inbound_and_udp_flows_are_separated() {
+        let data = pcap(&[
+            (1, ipv4_frame(false, 6, 80, 51000, 0x02, 0)),
+            (1, ipv4_frame(true, 6, 80, 51000, 0x12, 0)),
+            (2, ipv4_frame(true, 17, 53000, 53, 0, 30)),
+            (2, ipv4_frame(false, 17, 53000, 53, 0, 80)),
+        ]);
+
+        let flows = parse_pcap_flows(&data, GUEST_MAC).unwrap();
+
+        assert_eq!(flows.len(), 2);
+        assert_eq!(flows[0].direction, FlowDirection::Inbound);
+        assert_eq!(flows[0].local_port, 80);
+        assert_eq!(flows[1].protocol, FlowProtocol::Udp);
+        assert_eq!(flows[1].direction, FlowDirection::Outbound);
+        assert_eq!(flows[1].remote_port, 53);
+
+        let summary = FlowSummary::from_flows(&flows);
+        assert_eq!((summary.outbound, summary.inbound), (1, 1));
+    }
+
+    #[test]
+    fn test_reused_port_after_close_starts_new_flow() {
+        let data = pcap(&[
+            (1, ipv4_frame(true, 6, 40000, 443, 0x02, 0)),
+            (2, ipv4_frame(true, 6, 40000, 443, 0x04, 0)),
+            (3, ipv4_frame(true, 6, 40000, 443, 0x02, 0)),
+        ]);
+
+        let flows = parse_pcap_flows(&data, GUEST_MAC).unwrap();
+
+        assert_eq!(flows.len(), 2);
+        assert!(flows[0].closed);
+        assert!(!flows[1].closed);
+    }
+
+    #[test]
+    fn test_pcap_frames_stop_at_truncated_record() {
+        let mut data = pcap(&[(1, ipv4_frame(true, 17, 1, 2, 0, 0))]);
+        data.extend_from_slice(&[0, 0, 0, 0, 10, 0, 0]);
+
+        assert_eq!(PcapFrames::new(&data).unwrap().count(), 1);
+        assert!(PcapFrames::new(b"not a pcap file at all!!").is_none());
+    }
+}
@@ -5,6 +5,7 @@
 //! - Linux: `PasstManager` (passt Unix stream socket)
 //! - macOS: `NetProxyManager` (pure-Rust vfkit server, no external binary)
 
+pub mod flows;
 #[cfg(any(target_os = "linux", test))]
 mod passt;
 mod store;
@@ -5,7 +5,7 @@
 //! its own passt process with a dedicated Unix socket.
 
 use a3s_box_core::error::{BoxError, Result};
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command};
 
@@ -20,6 +20,8 @@ pub struct PasstManager {
     child: Option<Child>,
     /// PID file path for the passt process.
     pid_file: PathBuf,
+    /// Network namespace to run passt in (None for the caller's own).
+    netns: Option<PathBuf>,
 }
 
 impl PasstManager {
@@ -40,9 +42,16 @@ impl PasstManager {
             pcap_path: socket_dir.join("passt.pcap"),
             pid_file: socket_dir.join("passt.pid"),
             child: None,
+            netns: None,
         }
     }
 
+    /// Run passt inside the network namespace at `netns`, so the box's
+    /// traffic leaves through the interfaces a CNI plugin put there.
+    pub fn set_netns(&mut self, netns: &Path) {
+        self.netns = Some(netns.to_path_buf());
+    }
+
     /// Get the passt socket path.
     pub fn socket_path(&self) -> &Path {
         &self.socket_path
@@ -58,6 +67,7 @@ impl PasstManager {
     /// Configures passt with:
     /// - Unix socket mode (no PID namespace)
     /// - The assigned IP, gateway, prefix length
+    /// - The assigned IPv6 address and gateway on dual-stack networks
     /// - DNS forwarding
     /// - No DHCP (static IP assignment)
     /// - Inbound TCP port forwarding for any published ports (`port_map`)
@@ -66,6 +76,7 @@ impl PasstManager {
         ip: Ipv4Addr,
         gateway: Ipv4Addr,
         prefix_len: u8,
+        ipv6: Option<(Ipv6Addr, Ipv6Addr)>,
         dns_servers: &[Ipv4Addr],
         port_map: &[String],
     ) -> Result<()> {
@@ -108,7 +119,15 @@ impl PasstManager {
             std::fs::remove_file(&self.pcap_path).ok();
         }
 
-        let mut cmd = Command::new("passt");
+        // nsenter execs passt in place, so the child PID stays passt's own.
+        let mut cmd = match &self.netns {
+            Some(netns) => {
+                let mut cmd = Command::new("nsenter");
+                cmd.arg(format!("--net={}", netns.display())).arg("passt");
+                cmd
+            }
+            None => Command::new("passt"),
+        };
         cmd.arg("--socket")
             .arg(&self.socket_path)
             .arg("--pid")
@@ -125,6 +144,14 @@ impl PasstManager {
             .arg("--netmask")
             .arg(format!("{}", prefix_to_netmask(prefix_len)));
 
+        // passt takes one --address/--gateway per address family.
+        if let Some((ip6, gateway6)) = ipv6 {
+            cmd.arg("--address")
+                .arg(ip6.to_string())
+                .arg("--gateway")
+                .arg(gateway6.to_string());
+        }
+
         // Add DNS servers
         for dns in dns_servers {
             cmd.arg("--dns").arg(dns.to_string());
@@ -132,12 +159,27 @@ impl PasstManager {
 
         // Forward published TCP ports into the guest. libkrun discards the
         // TSI host_port_map once a virtio-net device is attached, so passt is
-        // what actually publishes `-p host:guest` in bridge mode. Auto-assigned
-        // host ports (host_port == 0) cannot be forwarded by passt and are
-        // skipped. passt accepts a comma-separated `host:guest,...` spec.
+        // what actually publishes `-p host:guest` in bridge mode. The CLI
+        // resolves auto-assigned host ports (`0:guest`) before boot; one that
+        // arrives unresolved cannot be forwarded by passt and is skipped.
+        // passt accepts a comma-separated `host:guest,...` spec, but applies
+        // an `ADDR/` prefix to the whole spec, so ports bound to a host
+        // address are passed one per `--tcp-ports`.
         let tcp_specs = passt_tcp_port_specs(port_map);
-        if !tcp_specs.is_empty() {
-            let spec = tcp_specs.join(",");
+        if tcp_specs.len() < port_map.len() {
+            tracing::warn!(
+                ?port_map,
+                "Skipping published ports without a concrete host port"
+            );
+        }
+        let (bound, unbound): (Vec<String>, Vec<String>) =
+            tcp_specs.into_iter().partition(|spec| spec.contains('/'));
+        if !unbound.is_empty() {
+            let spec = unbound.join(",");
+            tracing::info!(tcp_ports = %spec, "Configuring passt inbound TCP port forwarding");
+            cmd.arg("--tcp-ports").arg(spec);
+        }
+        for spec in bound {
             tracing::info!(tcp_ports = %spec, "Configuring passt inbound TCP port forwarding");
             cmd.arg("--tcp-ports").arg(spec);
         }
@@ -336,13 +378,17 @@ impl super::NetworkBackend for PasstManager {
     }
 }
 
-/// Convert published ports to passt's inbound TCP forwarding spec entries.
+/// Convert published ports to passt's inbound TCP forwarding spec entries
+/// (`host:guest`, or `ADDR/host:guest` for a port bound to one host address).
 fn passt_tcp_port_specs(port_map: &[String]) -> Vec<String> {
     port_map
         .iter()
         .filter_map(|m| a3s_box_core::parse_port_mapping(m).ok())
         .filter(|m| m.host_port != 0)
-        .map(|m| format!("{}:{}", m.host_port, m.guest_port))
+        .map(|m| match m.host_ip {
+            Some(ip) => format!("{ip}/{}:{}", m.host_port, m.guest_port),
+            None => format!("{}:{}", m.host_port, m.guest_port),
+        })
         .collect()
 }
 
@@ -381,6 +427,16 @@ mod tests {
         assert_eq!(specs, vec!["8080:80", "9000:90"]);
     }
 
+    #[test]
+    fn test_passt_tcp_port_specs_prefixes_bound_host_addresses() {
+        let specs = passt_tcp_port_specs(&[
+            "[::1]:8443:443".to_string(),
+            "127.0.0.1:9000:90".to_string(),
+        ]);
+
+        assert_eq!(specs, vec!["::1/8443:443", "127.0.0.1/9000:90"]);
+    }
+
     #[test]
     fn test_passt_manager_new() {
         let dir = tempfile::tempdir().unwrap();
@@ -477,6 +533,7 @@ mod tests {
                 Ipv4Addr::new(10, 0, 2, 15),
                 Ipv4Addr::new(10, 0, 2, 2),
                 24,
+                None,
                 &[Ipv4Addr::new(1, 1, 1, 1)],
                 &["8080:80".to_string()],
             )
@@ -1,7 +1,9 @@
 //! Persistent storage for network configurations.
 //!
-//! Networks are stored as JSON in `~/.a3s/networks.json` with atomic writes
-//! (write to tmp file, then rename) to prevent corruption.
+//! Networks are stored as JSON in `~/.a3s/networks.json` through the
+//! [`crate::storage`] backend. Writes are atomic and journaled, so a damaged
+//! file is restored from `networks.json.journal` (see
+//! [`crate::store_io::JournaledDocument`]).
 
 use a3s_box_core::error::{BoxError, Result};
 use a3s_box_core::network::NetworkConfig;
@@ -16,7 +18,7 @@ pub struct NetworkStore {
 }
 
 /// Serializable wrapper for the networks file.
-#[derive(Debug, serde::Serialize, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 struct NetworksFile {
     networks: HashMap<String, NetworkConfig>,
 }
@@ -33,79 +35,29 @@ impl NetworkStore {
         Ok(Self::new(home.join("networks.json")))
     }
 
-    /// Load all networks from disk.
-    pub fn load(&self) -> Result<HashMap<String, NetworkConfig>> {
-        if !self.path.exists() {
-            return Ok(HashMap::new());
+    /// The journaled `networks.json` document.
+    fn document(&self) -> crate::store_io::JournaledDocument<'_> {
+        crate::store_io::JournaledDocument {
+            path: &self.path,
+            label: "networks file",
+            error: BoxError::NetworkError,
         }
+    }
 
-        let data = std::fs::read_to_string(&self.path).map_err(|e| {
-            BoxError::NetworkError(format!(
-                "failed to read networks file {}: {}",
-                self.path.display(),
-                e
-            ))
-        })?;
-
-        // A corrupt/old-schema networks file must not brick the runtime: quarantine
-        // it and start from an empty set (create repopulates) rather than failing
-        // every network operation. Mirrors the boxes.json hardening.
-        let file: NetworksFile = match serde_json::from_str(&data) {
-            Ok(f) => f,
-            Err(e) => {
-                let preserved = crate::store_io::quarantine_label(&self.path);
-                tracing::warn!(
-                    "networks file {} is corrupt ({e}); preserved a copy at {preserved} \
-                     and started from an empty network set",
-                    self.path.display(),
-                );
-                return Ok(HashMap::new());
-            }
-        };
-
-        Ok(file.networks)
+    /// Load all networks from the storage backend.
+    ///
+    /// A corrupt file is restored from its journal. If that fails too, the
+    /// file is quarantined and reset, and this returns
+    /// [`BoxError::StoreCorrupted`].
+    pub fn load(&self) -> Result<HashMap<String, NetworkConfig>> {
+        Ok(self.document().load::<NetworksFile>()?.networks)
     }
 
-    /// Save all networks to disk (atomic write).
+    /// Save all networks (journaled, atomic replace).
     pub fn save(&self, networks: &HashMap<String, NetworkConfig>) -> Result<()> {
-        // Ensure parent directory exists
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| {
-                BoxError::NetworkError(format!(
-                    "failed to create directory {}: {}",
-                    parent.display(),
-                    e
-                ))
-            })?;
-        }
-
-        let file = NetworksFile {
+        self.document().save(&NetworksFile {
             networks: networks.clone(),
-        };
-
-        let json = serde_json::to_string_pretty(&file)
-            .map_err(|e| BoxError::NetworkError(format!("failed to serialize networks: {}", e)))?;
-
-        // Atomic write: write to tmp, then rename
-        let tmp_path = self.path.with_extension("json.tmp");
-        std::fs::write(&tmp_path, &json).map_err(|e| {
-            BoxError::NetworkError(format!(
-                "failed to write tmp file {}: {}",
-                tmp_path.display(),
-                e
-            ))
-        })?;
-
-        std::fs::rename(&tmp_path, &self.path).map_err(|e| {
-            BoxError::NetworkError(format!(
-                "failed to rename {} → {}: {}",
-                tmp_path.display(),
-                self.path.display(),
-                e
-            ))
-        })?;
-
-        Ok(())
+        })
     }
 
     /// Get a single network by name.
@@ -120,23 +72,16 @@ impl NetworkStore {
     /// so concurrent boots cannot assign duplicate IPs/MACs or silently lose
     /// each other's endpoints. The map is saved only if `f` returns `Ok`.
     ///
-    /// The lock is held across the whole load/mutate/save; `save` is itself
-    /// lock-free, so there is no re-entrant `flock` (which would self-deadlock).
+    /// The storage transaction is held across the whole load/mutate/save;
+    /// `f` must not call `save` or another locked method (the lock is not
+    /// re-entrant and would self-deadlock).
     pub fn with_write_lock<F, R, E>(&self, f: F) -> std::result::Result<R, E>
     where
         F: FnOnce(&mut HashMap<String, NetworkConfig>) -> std::result::Result<R, E>,
         E: From<BoxError>,
     {
-        let _lock = crate::file_lock::FileLock::acquire(&self.path).map_err(|e| {
-            E::from(BoxError::NetworkError(format!(
-                "failed to lock networks file {}: {e}",
-                self.path.display()
-            )))
-        })?;
-        let mut networks = self.load().map_err(E::from)?;
-        let r = f(&mut networks)?;
-        self.save(&networks).map_err(E::from)?;
-        Ok(r)
+        self.document()
+            .transact(|file: &mut NetworksFile| f(&mut file.networks))
     }
 
     /// Create a new network. Returns error if name already exists.
@@ -427,7 +372,9 @@ mod tests {
         std::fs::write(&path, "{ not valid json").unwrap();
         let store = NetworkStore::new(path.clone());
 
-        // load() must succeed (empty) instead of erroring every network op.
+        // With no journal to restore from, the corruption is reported once as
+        // a typed error, then every network op works again from an empty set.
+        assert!(matches!(store.load(), Err(BoxError::StoreCorrupted { .. })));
         assert!(store.load().unwrap().is_empty());
         // The corrupt file is preserved as a timestamped sibling, not lost.
         let quarantined = std::fs::read_dir(dir.path())
@@ -440,4 +387,16 @@ mod tests {
             });
         assert!(quarantined, "corrupt networks.json must be quarantined");
     }
+
+    #[test]
+    fn corrupt_networks_file_is_restored_from_journal() {
+        let (_dir, store) = temp_store();
+        store
+            .create(NetworkConfig::new("mynet", "10.88.0.0/24").unwrap())
+            .unwrap();
+        std::fs::write(store.path(), "").unwrap();
+
+        assert!(store.get("mynet").unwrap().is_some());
+        assert!(store.load().unwrap().contains_key("mynet"));
+    }
 }
@@ -0,0 +1,263 @@
+//! Host NUMA topology helpers for `--numa-node` placement.
+//!
+//! A box pinned to a NUMA node gets a cpuset derived from the node's CPU list
+//! (narrowed by any explicit `--cpuset-cpus`), and the shim binds guest memory
+//! to the node with `set_mempolicy(MPOL_BIND)`. Keeping vCPU threads and guest
+//! RAM on one socket avoids remote-memory accesses on multi-socket hosts.
+//!
+//! A running VM can also be re-pinned from outside: the CRI server moves a
+//! pod VM's threads onto the CPUs kubelet's CPU manager assigned to the pod.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use a3s_box_core::error::{BoxError, Result};
+
+const NODE_SYSFS: &str = "/sys/devices/system/node";
+
+/// NUMA nodes currently online on the host, in ascending order.
+///
+/// Empty when the host does not expose NUMA topology (non-Linux or
+/// `CONFIG_NUMA=n`).
+pub fn online_nodes() -> Vec<u32> {
+    online_nodes_in(Path::new(NODE_SYSFS))
+}
+
+fn online_nodes_in(root: &Path) -> Vec<u32> {
+    std::fs::read_to_string(root.join("online"))
+        .ok()
+        .and_then(|list| parse_cpu_list(&list).ok())
+        .map(|nodes| nodes.into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// Resolve the cpuset for a box pinned to `node`.
+///
+/// Without `requested`, the node's full CPU list is returned. With an explicit
+/// `--cpuset-cpus`, the result is its intersection with the node, and an empty
+/// intersection is rejected rather than silently running off-node.
+pub fn resolve_numa_cpuset(node: u32, requested: Option<&str>) -> Result<String> {
+    resolve_numa_cpuset_in(Path::new(NODE_SYSFS), node, requested)
+}
+
+fn resolve_numa_cpuset_in(root: &Path, node: u32, requested: Option<&str>) -> Result<String> {
+    let online = online_nodes_in(root);
+    if !online.contains(&node) {
+        return Err(BoxError::ConfigError(if online.is_empty() {
+            format!("--numa-node {node}: host does not expose NUMA topology")
+        } else {
+            format!(
+                "--numa-node {node}: no such online node (online: {})",
+                format_cpu_list(&online.into_iter().collect())
+            )
+        }));
+    }
+
+    let path = root.join(format!("node{node}")).join("cpulist");
+    let node_cpus = std::fs::read_to_string(&path)
+        .map_err(|e| {
+            BoxError::ConfigError(format!(
+                "--numa-node {node}: failed to read {}: {e}",
+                path.display()
+            ))
+        })
+        .and_then(|list| parse_cpu_list(&list).map_err(BoxError::ConfigError))?;
+    if node_cpus.is_empty() {
+        return Err(BoxError::ConfigError(format!(
+            "--numa-node {node}: node has no CPUs (memory-only node)"
+        )));
+    }
+
+    let cpus = match requested {
+        None => node_cpus,
+        Some(requested) => {
+            let requested = parse_cpu_list(requested)
+                .map_err(|e| BoxError::ConfigError(format!("Invalid --cpuset-cpus: {e}")))?;
+            let cpus: BTreeSet<u32> = requested.intersection(&node_cpus).copied().collect();
+            if cpus.is_empty() {
+                return Err(BoxError::ConfigError(format!(
+                    "--cpuset-cpus has no CPUs on NUMA node {node} (node CPUs: {})",
+                    format_cpu_list(&node_cpus)
+                )));
+            }
+            cpus
+        }
+    };
+    Ok(format_cpu_list(&cpus))
+}
+
+/// Parse a kernel CPU/node list such as `0-3,8,10-11`.
+pub fn parse_cpu_list(list: &str) -> std::result::Result<BTreeSet<u32>, String> {
+    let mut cpus = BTreeSet::new();
+    let list = list.trim();
+    if list.is_empty() {
+        return Ok(cpus);
+    }
+    for part in list.split(',') {
+        let part = part.trim();
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        let start: u32 = start
+            .parse()
+            .map_err(|_| format!("invalid CPU number in '{part}'"))?;
+        let end: u32 = end
+            .parse()
+            .map_err(|_| format!("invalid CPU number in '{part}'"))?;
+        if start > end {
+            return Err(format!("invalid CPU range '{part}'"));
+        }
+        cpus.extend(start..=end);
+    }
+    Ok(cpus)
+}
+
+/// Format a CPU set back into the compact kernel list syntax.
+pub fn format_cpu_list(cpus: &BTreeSet<u32>) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cpu => *end = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Restrict every thread of process `pid` to `cpus`.
+///
+/// Threads the process starts afterwards inherit the mask of the thread that
+/// creates them, so re-pinning a running VM covers its later vCPU threads too.
+#[cfg(target_os = "linux")]
+pub fn set_process_affinity(pid: u32, cpus: &BTreeSet<u32>) -> Result<()> {
+    if cpus.is_empty() {
+        return Err(BoxError::ConfigError("empty CPU affinity".to_string()));
+    }
+    // SAFETY: cpu_set_t is a plain bitmask; all-zero is the empty set.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        if cpu as usize >= libc::CPU_SETSIZE as usize {
+            return Err(BoxError::ConfigError(format!(
+                "CPU {cpu} is beyond the affinity mask"
+            )));
+        }
+        // SAFETY: `cpu` was checked against the mask size above.
+        unsafe { libc::CPU_SET(cpu as usize, &mut set) };
+    }
+
+    let tasks = Path::new("/proc").join(pid.to_string()).join("task");
+    let entries = std::fs::read_dir(&tasks).map_err(|e| {
+        BoxError::ExecError(format!(
+            "Failed to list threads in {}: {e}",
+            tasks.display()
+        ))
+    })?;
+    for entry in entries.flatten() {
+        let Some(tid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+            continue;
+        };
+        // SAFETY: `set` is a valid cpu_set_t of the size passed.
+        let ret =
+            unsafe { libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+        if ret != 0 {
+            let err = std::io::Error::last_os_error();
+            // The thread exited between listing and pinning.
+            if err.raw_os_error() == Some(libc::ESRCH) {
+                continue;
+            }
+            return Err(BoxError::ExecError(format!(
+                "sched_setaffinity for thread {tid} of pid {pid} failed: {err}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// CPU affinity is Linux-only.
+#[cfg(not(target_os = "linux"))]
+pub fn set_process_affinity(_pid: u32, _cpus: &BTreeSet<u32>) -> Result<()> {
+    Err(BoxError::ConfigError(
+        "CPU affinity is only supported on Linux".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fake_topology() -> TempDir {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("online"), "0-1\n").unwrap();
+        for (node, cpus) in [(0, "0-3,8-11\n"), (1, "4-7,12-15\n")] {
+            let dir = tmp.path().join(format!("node{node}"));
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("cpulist"), cpus).unwrap();
+        }
+        tmp
+    }
+
+    #[test]
+    fn test_parse_and_format_cpu_list_roundtrip() {
+        let cpus = parse_cpu_list("0-3,8,10-11\n").unwrap();
+        assert_eq!(cpus.len(), 7);
+        assert_eq!(format_cpu_list(&cpus), "0-3,8,10-11");
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+    }
+
+    #[test]
+    fn test_resolve_numa_cpuset_uses_node_cpus() {
+        let root = fake_topology();
+        assert_eq!(online_nodes_in(root.path()), vec![0, 1]);
+        assert_eq!(
+            resolve_numa_cpuset_in(root.path(), 1, None).unwrap(),
+            "4-7,12-15"
+        );
+    }
+
+    #[test]
+    fn test_resolve_numa_cpuset_intersects_requested_cpuset() {
+        let root = fake_topology();
+        assert_eq!(
+            resolve_numa_cpuset_in(root.path(), 0, Some("2-5")).unwrap(),
+            "2-3"
+        );
+        let err = resolve_numa_cpuset_in(root.path(), 0, Some("4-7")).unwrap_err();
+        assert!(err.to_string().contains("no CPUs on NUMA node 0"));
+    }
+
+    #[test]
+    fn test_resolve_numa_cpuset_rejects_unknown_node() {
+        let root = fake_topology();
+        let err = resolve_numa_cpuset_in(root.path(), 2, None).unwrap_err();
+        assert!(err.to_string().contains("online: 0-1"), "got: {err}");
+
+        let empty = TempDir::new().unwrap();
+        let err = resolve_numa_cpuset_in(empty.path(), 0, None).unwrap_err();
+        assert!(err.to_string().contains("does not expose NUMA"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_set_process_affinity_keeps_current_cpus() {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        let allowed = status
+            .lines()
+            .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+            .unwrap();
+        let cpus = parse_cpu_list(allowed).unwrap();
+        set_process_affinity(std::process::id(), &cpus).unwrap();
+
+        let err = set_process_affinity(std::process::id(), &BTreeSet::new()).unwrap_err();
+        assert!(err.to_string().contains("empty CPU affinity"));
+    }
+}
@@ -4296,6 +4296,7 @@ mod tests {
             no_cache: false,
             metrics: None,
             run_pool: None,
+            source_date_epoch: None,
         };
         let tmp = tempfile::TempDir::new().unwrap();
 
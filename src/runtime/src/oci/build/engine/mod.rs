@@ -59,6 +59,10 @@ pub struct BuildConfig {
     pub metrics: Option<crate::prom::RuntimeMetrics>,
     /// Execute Dockerfile RUN instructions through a warm-pool daemon lease.
     pub run_pool: Option<BuildRunPoolConfig>,
+    /// Creation time recorded in the image config and history, in seconds
+    /// since the Unix epoch (`SOURCE_DATE_EPOCH`). `None` records the build
+    /// time, so only layer digests are reproducible.
+    pub source_date_epoch: Option<i64>,
 }
 
 /// Configuration for executing Dockerfile RUN instructions in a warm-pool VM.
@@ -1246,6 +1250,7 @@ pub async fn build(config: BuildConfig, store: Arc<ImageStore>) -> Result<BuildR
         &final_layers_dir,
         &store,
         &target_platform,
+        config.source_date_epoch,
     )
     .await?;
 
@@ -1466,6 +1471,7 @@ async fn assemble_image(
     layers_dir: &Path,
     store: &Arc<ImageStore>,
     target_platform: &Platform,
+    source_date_epoch: Option<i64>,
 ) -> Result<BuildResult> {
     // Create output directory
     let output_dir = layers_dir.join("_output");
@@ -1507,7 +1513,7 @@ async fn assemble_image(
     all_diff_ids.extend(state.diff_ids.iter().cloned());
 
     // Build OCI config
-    let now = chrono::Utc::now().to_rfc3339();
+    let created = image_created(source_date_epoch)?;
     let arch = target_platform.oci_arch();
 
     let env_list: Vec<String> = state
@@ -1519,7 +1525,7 @@ async fn assemble_image(
     let mut config_obj = serde_json::json!({
         "architecture": arch,
         "os": "linux",
-        "created": now,
+        "created": created,
         "config": {},
         "rootfs": {
             "type": "layers",
@@ -1529,7 +1535,7 @@ async fn assemble_image(
         },
         "history": state.history.iter().map(|h| {
             let mut entry = serde_json::json!({
-                "created": now,
+                "created": created,
                 "created_by": h.created_by
             });
             if h.empty_layer {
@@ -1670,6 +1676,19 @@ async fn assemble_image(
     })
 }
 
+/// RFC 3339 creation time for an image config: `source_date_epoch` when set,
+/// otherwise the current time.
+fn image_created(source_date_epoch: Option<i64>) -> Result<String> {
+    match source_date_epoch {
+        Some(secs) => chrono::DateTime::from_timestamp(secs, 0)
+            .map(|created| created.to_rfc3339())
+            .ok_or_else(|| {
+                BoxError::BuildError(format!("SOURCE_DATE_EPOCH {secs} is out of range"))
+            }),
+        None => Ok(chrono::Utc::now().to_rfc3339()),
+    }
+}
+
 fn copy_layer_blob(layer: &LayerInfo, blob_path: &Path, label: &str) -> Result<()> {
     if !layer.path.exists() {
         return Err(BoxError::BuildError(format!(
@@ -5,8 +5,8 @@
 mod tests {
     use super::super::utils::*;
     use super::super::{
-        build, default_target_platform, scratch_config, validate_build_config, BuildConfig,
-        BuildState,
+        build, default_target_platform, image_created, scratch_config, validate_build_config,
+        BuildConfig, BuildState,
     };
     use crate::oci::{ImageStore, OciImage};
     use a3s_box_core::platform::Platform;
@@ -94,6 +94,7 @@ mod tests {
             no_cache: false,
             metrics: None,
             run_pool: None,
+            source_date_epoch: None,
         }
     }
 
@@ -214,6 +215,7 @@ LABEL org.opencontainers.image.title="scratch-smoke"
                 no_cache: false,
                 metrics: None,
                 run_pool: None,
+                source_date_epoch: None,
             },
             store.clone(),
         )
@@ -235,6 +237,67 @@ LABEL org.opencontainers.image.title="scratch-smoke"
         );
     }
 
+    #[tokio::test]
+    async fn test_build_with_source_date_epoch_is_reproducible() {
+        async fn build_once(files: &[&str], mtime: u64) -> String {
+            let tmp = tempfile::TempDir::new().unwrap();
+            let context = tmp.path().join("context");
+            std::fs::create_dir_all(context.join("app")).unwrap();
+            for file in files {
+                let path = context.join("app").join(file);
+                std::fs::write(&path, file.as_bytes()).unwrap();
+                std::fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(mtime))
+                    .unwrap();
+            }
+            std::fs::write(
+                context.join("Dockerfile"),
+                "FROM scratch\nCOPY app /app\nCMD [\"/app/run\"]\n",
+            )
+            .unwrap();
+
+            let store =
+                Arc::new(ImageStore::new(&tmp.path().join("images"), 1024 * 1024 * 100).unwrap());
+            let result = build(
+                BuildConfig {
+                    context_dir: context.clone(),
+                    dockerfile_path: context.join("Dockerfile"),
+                    tag: Some("reproducible:latest".to_string()),
+                    build_args: HashMap::new(),
+                    quiet: true,
+                    platforms: vec![],
+                    target: None,
+                    no_cache: true,
+                    metrics: None,
+                    run_pool: None,
+                    source_date_epoch: Some(1_700_000_000),
+                },
+                store,
+            )
+            .await
+            .unwrap();
+            result.digest
+        }
+
+        // Same inputs, written in a different order at a different time.
+        let first = build_once(&["run", "lib.so", "data"], 1_000_000).await;
+        let second = build_once(&["data", "run", "lib.so"], 2_000_000).await;
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_image_created_uses_source_date_epoch() {
+        assert_eq!(
+            image_created(Some(1_700_000_000)).unwrap(),
+            "2023-11-14T22:13:20+00:00"
+        );
+        assert!(image_created(Some(i64::MAX)).is_err());
+        assert!(image_created(None).is_ok());
+    }
+
     #[cfg(all(feature = "pool", not(windows)))]
     #[tokio::test]
     async fn test_build_run_pool_fences_each_run_before_capturing_rootfs_diff() {
@@ -437,6 +500,7 @@ CMD ["cat", "/app/copied.txt"]
                         timeout_ns: 12_000_000_000,
                         run_cache_dir: run_cache_dir.clone(),
                     }),
+                    source_date_epoch: None,
                 },
                 store.clone(),
             ),
@@ -590,6 +654,7 @@ RUN --mount=type=bind,source=src,target=. cat input.txt > /out.txt
                         timeout_ns: 12_000_000_000,
                         run_cache_dir: run_cache_dir.clone(),
                     }),
+                    source_date_epoch: None,
                 },
                 store.clone(),
             ),
@@ -773,6 +838,7 @@ RUN --mount=type=bind,from=builder,source=/artifact.txt,target=artifact.txt cat
                         timeout_ns: 12_000_000_000,
                         run_cache_dir: run_cache_dir.clone(),
                     }),
+                    source_date_epoch: None,
                 },
                 store.clone(),
             ),
@@ -843,6 +909,7 @@ RUN --mount=type=bind,from=external-bind-source:latest,source=/artifact.txt,targ
                 no_cache: true,
                 metrics: None,
                 run_pool: None,
+                source_date_epoch: None,
             },
             store.clone(),
         )
@@ -946,6 +1013,7 @@ RUN --mount=type=bind,from=external-bind-source:latest,source=/artifact.txt,targ
                         timeout_ns: 12_000_000_000,
                         run_cache_dir: run_cache_dir.clone(),
                     }),
+                    source_date_epoch: None,
                 },
                 store.clone(),
             ),
@@ -1093,6 +1161,7 @@ RUN --mount=type=tmpfs,target=tmp printf ok > /out.txt
                         timeout_ns: 12_000_000_000,
                         run_cache_dir: run_cache_dir.clone(),
                     }),
+                    source_date_epoch: None,
                 },
                 store.clone(),
             ),
@@ -1233,6 +1302,7 @@ RUN --mount=type=cache,id=failed,target=/root/.cache echo before-failure > /root
                         timeout_ns: 12_000_000_000,
                         run_cache_dir: run_cache_dir.clone(),
                     }),
+                    source_date_epoch: None,
                 },
                 store,
             ),
@@ -1391,6 +1461,7 @@ RUN --mount=type=cache,id=warm,target=/root/.cache cat /root/.cache/cache-only.t
                         timeout_ns: 60_000_000_000,
                         run_cache_dir,
                     }),
+                    source_date_epoch: None,
                 },
                 store.clone(),
             ),
@@ -1542,6 +1613,7 @@ RUN --mount=type=cache,id=seeded,sharing=locked,from=builder,source=/seed-cache,
                         timeout_ns: 60_000_000_000,
                         run_cache_dir: run_cache_dir.clone(),
                     }),
+                    source_date_epoch: None,
                 },
                 store.clone(),
             ),
@@ -1598,6 +1670,7 @@ RUN --mount=type=cache,id=seeded,sharing=locked,from=builder,source=/seed-cache,
                 no_cache: false,
                 metrics: None,
                 run_pool: None,
+                source_date_epoch: None,
             },
             store.clone(),
         )
@@ -1624,6 +1697,7 @@ RUN --mount=type=cache,id=seeded,sharing=locked,from=builder,source=/seed-cache,
                 no_cache: false,
                 metrics: None,
                 run_pool: None,
+                source_date_epoch: None,
             },
             store.clone(),
         )
@@ -1667,6 +1741,7 @@ RUN --mount=type=cache,id=seeded,sharing=locked,from=builder,source=/seed-cache,
                 no_cache: true,
                 metrics: None,
                 run_pool: None,
+                source_date_epoch: None,
             },
             store.clone(),
         )
@@ -1739,6 +1814,7 @@ CMD ["/work/run.sh"]
                 no_cache: false,
                 metrics: None,
                 run_pool: None,
+                source_date_epoch: None,
             },
             store.clone(),
         )
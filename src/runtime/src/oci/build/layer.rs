@@ -193,6 +193,11 @@ fn walk_dir(root: &Path, current: &Path, entries: &mut HashMap<PathBuf, FileEntr
     Ok(())
 }
 
+/// Timestamp written to every layer entry. Host mtimes would make layer
+/// digests depend on when and where a build ran; a fixed value keeps them a
+/// function of the files' contents, modes, and ownership alone.
+pub const LAYER_ENTRY_MTIME: u64 = 0;
+
 /// Create a tar.gz layer from a list of changed files in a rootfs.
 ///
 /// Returns the path to the created layer file and its SHA256 digest.
@@ -253,11 +258,7 @@ pub(super) fn create_layer_with_chown(
             Err(_) => continue,
         };
 
-        if meta.is_dir() {
-            append_dir_with_chown(&mut builder, relative_path, &full_path, chown)?;
-        } else {
-            append_file_with_chown(&mut builder, relative_path, &full_path, &meta, chown)?;
-        }
+        append_entry(&mut builder, relative_path, &full_path, &meta, chown)?;
     }
 
     // OCI whiteouts for deleted paths: an empty `.wh.<name>` marker beside the
@@ -278,7 +279,7 @@ pub(super) fn create_layer_with_chown(
         header.set_size(0);
         header.set_entry_type(tar::EntryType::Regular);
         header.set_mode(0o644);
-        header.set_mtime(0);
+        header.set_mtime(LAYER_ENTRY_MTIME);
         if let Some((uid, gid)) = chown {
             header.set_uid(uid as u64);
             header.set_gid(gid as u64);
@@ -383,18 +384,19 @@ fn add_dir_to_tar<W: std::io::Write>(
     target_prefix: &Path,
     chown: Option<(u32, u32)>,
 ) -> Result<()> {
-    let entries = std::fs::read_dir(current).map_err(|e| {
-        BoxError::BuildError(format!(
-            "Failed to read directory {}: {}",
-            current.display(),
-            e
-        ))
-    })?;
+    let mut entries = std::fs::read_dir(current)
+        .and_then(|entries| entries.collect::<std::io::Result<Vec<_>>>())
+        .map_err(|e| {
+            BoxError::BuildError(format!(
+                "Failed to read directory {}: {}",
+                current.display(),
+                e
+            ))
+        })?;
+    // read_dir order depends on the host filesystem; sort so the layer does not.
+    entries.sort_by_key(|entry| entry.file_name());
 
     for entry in entries {
-        let entry =
-            entry.map_err(|e| BoxError::BuildError(format!("Failed to read entry: {}", e)))?;
-
         let path = entry.path();
         let relative = path
             .strip_prefix(root)
@@ -409,96 +411,78 @@ fn add_dir_to_tar<W: std::io::Write>(
         let meta = std::fs::symlink_metadata(&path)
             .map_err(|e| BoxError::BuildError(format!("Failed to stat entry: {}", e)))?;
 
+        append_entry(builder, &tar_path, &path, &meta, chown)?;
         if file_type.is_dir() {
-            append_dir_with_chown(builder, &tar_path, &path, chown)?;
             add_dir_to_tar(builder, root, &path, target_prefix, chown)?;
-        } else {
-            append_file_with_chown(builder, &tar_path, &path, &meta, chown)?;
         }
     }
 
     Ok(())
 }
 
-/// Append a directory entry, overriding uid/gid when `chown` is set.
-fn append_dir_with_chown<W: std::io::Write>(
-    builder: &mut tar::Builder<W>,
-    tar_path: &Path,
-    dir_path: &Path,
-    chown: Option<(u32, u32)>,
-) -> Result<()> {
-    if let Some((uid, gid)) = chown {
-        let mut header = tar::Header::new_gnu();
-        let meta = std::fs::symlink_metadata(dir_path).map_err(|e| {
-            BoxError::BuildError(format!("Failed to stat {}: {}", dir_path.display(), e))
-        })?;
-        header.set_metadata_in_mode(&meta, tar::HeaderMode::Complete);
-        header.set_uid(uid as u64);
-        header.set_gid(gid as u64);
-        header.set_username("").ok();
-        header.set_groupname("").ok();
-        header.set_cksum();
-        builder
-            .append_data(&mut header, tar_path, std::io::empty())
-            .map_err(|e| BoxError::BuildError(format!("Failed to add dir to layer: {}", e)))
-    } else {
-        builder
-            .append_dir(tar_path, dir_path)
-            .map_err(|e| BoxError::BuildError(format!("Failed to add directory to layer: {}", e)))
-    }
-}
-
-/// Append a file/symlink entry, overriding uid/gid when `chown` is set.
-fn append_file_with_chown<W: std::io::Write>(
+/// Append a file, directory, or symlink entry with a normalized header.
+///
+/// Headers carry the entry's mode and ownership (or `chown`) but no host
+/// user/group names and a fixed [`LAYER_ENTRY_MTIME`] for every timestamp,
+/// so identical trees produce identical layers on any machine.
+fn append_entry<W: std::io::Write>(
     builder: &mut tar::Builder<W>,
     tar_path: &Path,
-    file_path: &Path,
+    path: &Path,
     meta: &std::fs::Metadata,
     chown: Option<(u32, u32)>,
 ) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata_in_mode(meta, tar::HeaderMode::Complete);
+    header.set_mtime(LAYER_ENTRY_MTIME);
+    if let Some(gnu) = header.as_gnu_mut() {
+        gnu.set_atime(LAYER_ENTRY_MTIME);
+        gnu.set_ctime(LAYER_ENTRY_MTIME);
+    }
     if let Some((uid, gid)) = chown {
-        let mut header = tar::Header::new_gnu();
-        header.set_metadata_in_mode(meta, tar::HeaderMode::Complete);
         header.set_uid(uid as u64);
         header.set_gid(gid as u64);
-        header.set_username("").ok();
-        header.set_groupname("").ok();
-        if meta.file_type().is_symlink() {
-            let target = std::fs::read_link(file_path).map_err(|e| {
-                BoxError::BuildError(format!(
-                    "Failed to read layer symlink {}: {}",
-                    file_path.display(),
-                    e
-                ))
-            })?;
-            header.set_entry_type(tar::EntryType::Symlink);
-            header.set_size(0);
-            header.set_link_name(&target).map_err(|e| {
-                BoxError::BuildError(format!(
-                    "Failed to encode layer symlink {} -> {}: {}",
-                    file_path.display(),
-                    target.display(),
-                    e
-                ))
-            })?;
-        }
-        header.set_cksum();
-        // For symlinks, the data is empty (target is in link_name header field).
-        let body: Box<dyn std::io::Read> = if meta.file_type().is_symlink() {
-            Box::new(std::io::empty())
-        } else {
-            Box::new(std::fs::File::open(file_path).map_err(|e| {
-                BoxError::BuildError(format!("Failed to open {}: {}", file_path.display(), e))
-            })?)
-        };
-        builder
-            .append_data(&mut header, tar_path, body)
-            .map_err(|e| BoxError::BuildError(format!("Failed to add file to layer: {}", e)))
-    } else {
-        builder
-            .append_path_with_name(file_path, tar_path)
-            .map_err(|e| BoxError::BuildError(format!("Failed to add file to layer: {}", e)))
     }
+    header.set_username("").ok();
+    header.set_groupname("").ok();
+    if meta.file_type().is_symlink() {
+        let target = std::fs::read_link(path).map_err(|e| {
+            BoxError::BuildError(format!(
+                "Failed to read layer symlink {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        header.set_link_name(&target).map_err(|e| {
+            BoxError::BuildError(format!(
+                "Failed to encode layer symlink {} -> {}: {}",
+                path.display(),
+                target.display(),
+                e
+            ))
+        })?;
+    }
+    header.set_cksum();
+    // Only regular files carry data; directories, symlinks (target is in the
+    // link_name header field), and special files are header-only.
+    let body: Box<dyn std::io::Read> = if meta.is_file() {
+        Box::new(std::fs::File::open(path).map_err(|e| {
+            BoxError::BuildError(format!("Failed to open {}: {}", path.display(), e))
+        })?)
+    } else {
+        Box::new(std::io::empty())
+    };
+    builder
+        .append_data(&mut header, tar_path, body)
+        .map_err(|e| {
+            BoxError::BuildError(format!(
+                "Failed to add {} to layer: {}",
+                tar_path.display(),
+                e
+            ))
+        })
 }
 
 /// Information about a created layer.
@@ -865,6 +849,56 @@ mod tests {
         assert!(paths.iter().any(|p| p.contains("workspace/lib")));
     }
 
+    #[test]
+    fn test_create_layer_from_dir_is_reproducible() {
+        let output_dir = TempDir::new().unwrap();
+        let build = |names: &[&str], mtime: u64, out: &str| {
+            let src = TempDir::new().unwrap();
+            fs::create_dir(src.path().join("sub")).unwrap();
+            for name in names {
+                let path = src.path().join(name);
+                fs::write(&path, name.as_bytes()).unwrap();
+                fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(mtime))
+                    .unwrap();
+            }
+            let out = output_dir.path().join(out);
+            create_layer_from_dir(src.path(), Path::new("app"), &out).unwrap()
+        };
+
+        // Same tree, created in a different order at a different time.
+        let first = build(&["b.txt", "a.txt", "sub/c.txt"], 1_000_000, "first.tar.gz");
+        let second = build(&["sub/c.txt", "a.txt", "b.txt"], 2_000_000, "second.tar.gz");
+        assert_eq!(first.digest, second.digest);
+
+        let file = fs::File::open(&first.path).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let entries: Vec<(String, u64)> = archive
+            .entries()
+            .unwrap()
+            .map(|e| {
+                let e = e.unwrap();
+                (
+                    e.path()
+                        .unwrap()
+                        .to_string_lossy()
+                        .trim_end_matches('/')
+                        .to_string(),
+                    e.header().mtime().unwrap(),
+                )
+            })
+            .collect();
+        let paths: Vec<&str> = entries.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["app/a.txt", "app/b.txt", "app/sub", "app/sub/c.txt"]
+        );
+        assert!(entries.iter().all(|(_, mtime)| *mtime == LAYER_ENTRY_MTIME));
+    }
+
     /// Regression: symlinks must be stored as symlink entries (Docker copies
     /// them verbatim), not followed into a duplicate of their target.
     #[test]
@@ -522,25 +522,7 @@ impl OciImage {
             "config blob",
         )?;
 
-        let oci_config: ImageConfiguration = serde_json::from_slice(&content)
-            .map_err(|e| BoxError::OciImageError(format!("Failed to parse config: {}", e)))?;
-
-        let raw_config: serde_json::Value = serde_json::from_slice(&content)
-            .map_err(|e| BoxError::OciImageError(format!("Failed to parse config JSON: {}", e)))?;
-
-        // oci-spec 0.6 does not model OnBuild or Healthcheck, so parse those
-        // Docker-compatible image fields directly from raw JSON.
-        let onbuild: Vec<String> = raw_config
-            .get("config")
-            .and_then(|c| c.get("OnBuild"))
-            .cloned()
-            .and_then(|v| serde_json::from_value(v).ok())
-            .unwrap_or_default();
-        let health_check = Self::parse_health_check_from_raw(&raw_config);
-
-        let mut config = OciImageConfig::from_oci_config(&oci_config, onbuild);
-        config.health_check = health_check;
-        Ok(config)
+        OciImageConfig::from_config_json(&content)
     }
 
     /// Parse Docker-compatible Healthcheck metadata from raw image config JSON.
@@ -604,6 +586,30 @@ fn u32_from_json(value: &serde_json::Value) -> Option<u32> {
 }
 
 impl OciImageConfig {
+    /// Parse an image config blob (the JSON a manifest's `config` descriptor
+    /// points at).
+    pub fn from_config_json(content: &[u8]) -> Result<Self> {
+        let oci_config: ImageConfiguration = serde_json::from_slice(content)
+            .map_err(|e| BoxError::OciImageError(format!("Failed to parse config: {}", e)))?;
+
+        let raw_config: serde_json::Value = serde_json::from_slice(content)
+            .map_err(|e| BoxError::OciImageError(format!("Failed to parse config JSON: {}", e)))?;
+
+        // oci-spec 0.6 does not model OnBuild or Healthcheck, so parse those
+        // Docker-compatible image fields directly from raw JSON.
+        let onbuild: Vec<String> = raw_config
+            .get("config")
+            .and_then(|c| c.get("OnBuild"))
+            .cloned()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+        let health_check = OciImage::parse_health_check_from_raw(&raw_config);
+
+        let mut config = Self::from_oci_config(&oci_config, onbuild);
+        config.health_check = health_check;
+        Ok(config)
+    }
+
     /// Create from OCI spec ImageConfiguration.
     fn from_oci_config(oci_config: &ImageConfiguration, onbuild: Vec<String>) -> Self {
         let config = oci_config.config();
@@ -655,7 +661,7 @@ impl OciImageConfig {
         // Parse stop signal
         let stop_signal = config.as_ref().and_then(|c| c.stop_signal().clone());
 
-        // Healthcheck is filled by load_config from raw JSON because oci-spec
+        // Healthcheck is filled by from_config_json from raw JSON because oci-spec
         // 0.6 does not expose the Docker-compatible field.
         let health_check = None;
 
@@ -73,47 +73,7 @@ fn extract_layer_with_cap(
         ))
     })?;
 
-    // Open layer file
-    let mut file = File::open(layer_path).map_err(|e| {
-        BoxError::OciImageError(format!(
-            "Failed to open layer file {}: {}",
-            layer_path.display(),
-            e
-        ))
-    })?;
-
-    // Detect the layer's compression from its magic bytes — OCI layers are gzip
-    // (1f 8b), zstd (28 b5 2f fd, e.g. buildkit/nerdctl `--compression zstd`), or
-    // an uncompressed tar. Peek, rewind, then pick the matching decoder; relying
-    // on the media type alone would miss layers stored without one.
-    let mut magic = [0u8; 4];
-    let read = file.read(&mut magic).map_err(|e| {
-        BoxError::OciImageError(format!(
-            "Failed to read layer header {}: {e}",
-            layer_path.display()
-        ))
-    })?;
-    file.seek(SeekFrom::Start(0)).map_err(|e| {
-        BoxError::OciImageError(format!(
-            "Failed to rewind layer {}: {e}",
-            layer_path.display()
-        ))
-    })?;
-
-    let decoder: Box<dyn Read> = if read >= 2 && magic[0] == 0x1f && magic[1] == 0x8b {
-        Box::new(GzDecoder::new(file))
-    } else if read >= 4 && magic == [0x28, 0xb5, 0x2f, 0xfd] {
-        Box::new(zstd::stream::read::Decoder::new(file).map_err(|e| {
-            BoxError::OciImageError(format!(
-                "Failed to init zstd decoder for {}: {e}",
-                layer_path.display()
-            ))
-        })?)
-    } else {
-        // Uncompressed tar (some registries / `--compression none`).
-        Box::new(file)
-    };
-
+    let (decoder, _) = open_layer_decoder(layer_path)?;
     let decoder = super::limited_reader::LimitedReader::new(decoder, max_layer_bytes);
 
     // Extract the tar archive, applying OCI whiteout semantics so files deleted
@@ -293,6 +253,173 @@ fn extract_layer_with_cap(
     Ok(())
 }
 
+/// Open a layer blob and wrap it in the decoder matching its compression.
+///
+/// Returns the decoder and whether the blob was compressed at all.
+fn open_layer_decoder(layer_path: &Path) -> Result<(Box<dyn Read + Send>, bool)> {
+    let mut file = File::open(layer_path).map_err(|e| {
+        BoxError::OciImageError(format!(
+            "Failed to open layer file {}: {}",
+            layer_path.display(),
+            e
+        ))
+    })?;
+
+    // Detect the layer's compression from its magic bytes — OCI layers are gzip
+    // (1f 8b), zstd (28 b5 2f fd, e.g. buildkit/nerdctl `--compression zstd`), or
+    // an uncompressed tar. Peek, rewind, then pick the matching decoder; relying
+    // on the media type alone would miss layers stored without one.
+    let mut magic = [0u8; 4];
+    let read = file.read(&mut magic).map_err(|e| {
+        BoxError::OciImageError(format!(
+            "Failed to read layer header {}: {e}",
+            layer_path.display()
+        ))
+    })?;
+    file.seek(SeekFrom::Start(0)).map_err(|e| {
+        BoxError::OciImageError(format!(
+            "Failed to rewind layer {}: {e}",
+            layer_path.display()
+        ))
+    })?;
+
+    if read >= 2 && magic[0] == 0x1f && magic[1] == 0x8b {
+        Ok((Box::new(GzDecoder::new(file)), true))
+    } else if read >= 4 && magic == [0x28, 0xb5, 0x2f, 0xfd] {
+        let decoder = zstd::stream::read::Decoder::new(file).map_err(|e| {
+            BoxError::OciImageError(format!(
+                "Failed to init zstd decoder for {}: {e}",
+                layer_path.display()
+            ))
+        })?;
+        Ok((Box::new(decoder), true))
+    } else {
+        // Uncompressed tar (some registries / `--compression none`).
+        Ok((Box::new(file), false))
+    }
+}
+
+/// Environment variable overriding the number of layers decompressed at once.
+const LAYER_DECOMPRESS_JOBS_ENV: &str = "A3S_BOX_LAYER_DECOMPRESS_JOBS";
+
+/// Upper bound on concurrent decompressions when the env var is unset.
+///
+/// Decompression is CPU-bound but the staged tars compete for the same disk,
+/// so going wider than this stops paying off on typical hosts.
+const DEFAULT_MAX_LAYER_DECOMPRESS_JOBS: usize = 8;
+
+/// Number of layers to decompress concurrently for an image of `layer_count`
+/// layers. `A3S_BOX_LAYER_DECOMPRESS_JOBS=1` restores sequential extraction.
+pub(crate) fn layer_decompress_jobs(layer_count: usize) -> usize {
+    let configured = std::env::var(LAYER_DECOMPRESS_JOBS_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|&jobs| jobs > 0);
+    let jobs = configured.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(DEFAULT_MAX_LAYER_DECOMPRESS_JOBS)
+    });
+    jobs.min(layer_count).max(1)
+}
+
+/// Decompress layers into plain tars under `staging_dir`, `jobs` at a time.
+///
+/// Decompression is the expensive, order-independent part of extraction, so
+/// it runs in parallel; the returned paths are in the original layer order
+/// and must still be applied sequentially so whiteouts and overwrites from
+/// upper layers land on top of lower ones. Layers that are already
+/// uncompressed are returned as-is instead of being copied.
+pub(crate) fn stage_layers(
+    layer_paths: &[PathBuf],
+    staging_dir: &Path,
+    jobs: usize,
+) -> Result<Vec<PathBuf>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let max_layer_bytes =
+        super::limited_reader::cap_from_env("A3S_BOX_MAX_LAYER_BYTES", 16 * 1024 * 1024 * 1024);
+    let next = AtomicUsize::new(0);
+    let mut staged: Vec<Option<Result<PathBuf>>> = Vec::new();
+    staged.resize_with(layer_paths.len(), || None);
+    let staged = parking_lot::Mutex::new(staged);
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.max(1).min(layer_paths.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(layer_path) = layer_paths.get(index) else {
+                    return;
+                };
+                let result = stage_layer(layer_path, staging_dir, index, max_layer_bytes);
+                let failed = result.is_err();
+                staged.lock()[index] = Some(result);
+                if failed {
+                    // Stop handing out new work; the first error is reported
+                    // in layer order once the in-flight layers finish.
+                    next.store(layer_paths.len(), Ordering::Relaxed);
+                    return;
+                }
+            });
+        }
+    });
+
+    let mut ordered = Vec::with_capacity(layer_paths.len());
+    for (index, result) in staged.into_inner().into_iter().enumerate() {
+        match result {
+            Some(result) => ordered.push(result?),
+            None => {
+                return Err(BoxError::OciImageError(format!(
+                    "Layer {} was not staged for extraction",
+                    layer_paths[index].display()
+                )))
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+fn stage_layer(
+    layer_path: &Path,
+    staging_dir: &Path,
+    index: usize,
+    max_layer_bytes: u64,
+) -> Result<PathBuf> {
+    if !layer_path.exists() {
+        return Err(BoxError::OciImageError(format!(
+            "Layer file not found: {}",
+            layer_path.display()
+        )));
+    }
+    let (decoder, compressed) = open_layer_decoder(layer_path)?;
+    if !compressed {
+        return Ok(layer_path.to_path_buf());
+    }
+
+    let staged_path = staging_dir.join(format!("{index:04}.tar"));
+    let mut decoder = super::limited_reader::LimitedReader::new(decoder, max_layer_bytes);
+    let mut output = File::create(&staged_path).map_err(|e| {
+        BoxError::OciImageError(format!(
+            "Failed to create staged layer {}: {e}",
+            staged_path.display()
+        ))
+    })?;
+    std::io::copy(&mut decoder, &mut output).map_err(|e| {
+        BoxError::OciImageError(format!(
+            "Failed to decompress layer {}: {e}",
+            layer_path.display()
+        ))
+    })?;
+
+    tracing::debug!(
+        layer = %layer_path.display(),
+        staged = %staged_path.display(),
+        "Decompressed OCI layer"
+    );
+    Ok(staged_path)
+}
+
 #[cfg(unix)]
 fn reject_overlay_private_xattrs<R: Read>(
     entry: &mut tar::Entry<'_, R>,
@@ -1030,6 +1157,60 @@ mod tests {
         assert!(!target.join("d/.wh..wh..opq").exists());
     }
 
+    #[test]
+    fn test_stage_layers_applies_whiteouts_in_layer_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let staging = temp_dir.path().join("staging");
+        let target = temp_dir.path().join("extracted");
+        fs::create_dir_all(&staging).unwrap();
+
+        let layers: Vec<PathBuf> = (0..4)
+            .map(|i| temp_dir.path().join(format!("layer{i}.tar.gz")))
+            .collect();
+        create_test_layer(&layers[0], &[("d/a.txt", b"a"), ("d/b.txt", b"b")]);
+        create_test_layer(&layers[1], &[("d/.wh.a.txt", b""), ("v.txt", b"one")]);
+        create_test_layer(&layers[2], &[("d/.wh..wh..opq", b""), ("d/c.txt", b"c")]);
+        create_test_layer(&layers[3], &[("v.txt", b"two")]);
+
+        let staged = stage_layers(&layers, &staging, 3).unwrap();
+        assert_eq!(staged.len(), layers.len());
+        for path in &staged {
+            assert!(path.starts_with(&staging), "compressed layers are staged");
+            extract_layer(path, &target).unwrap();
+        }
+
+        assert!(!target.join("d/a.txt").exists());
+        assert!(!target.join("d/b.txt").exists());
+        assert_eq!(fs::read_to_string(target.join("d/c.txt")).unwrap(), "c");
+        assert_eq!(fs::read_to_string(target.join("v.txt")).unwrap(), "two");
+    }
+
+    #[test]
+    fn test_stage_layers_passes_uncompressed_layers_through() {
+        let temp_dir = TempDir::new().unwrap();
+        let staging = temp_dir.path().join("staging");
+        fs::create_dir_all(&staging).unwrap();
+        let plain = temp_dir.path().join("layer.tar");
+        write_test_tar(File::create(&plain).unwrap(), &[("p.txt", b"plain")]);
+
+        let staged = stage_layers(std::slice::from_ref(&plain), &staging, 2).unwrap();
+
+        assert_eq!(staged, vec![plain]);
+        assert_eq!(fs::read_dir(&staging).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_stage_layers_reports_missing_layer() {
+        let temp_dir = TempDir::new().unwrap();
+        let present = temp_dir.path().join("present.tar.gz");
+        create_test_layer(&present, &[("x.txt", b"x")]);
+        let layers = vec![present, temp_dir.path().join("missing.tar.gz")];
+
+        let error = stage_layers(&layers, temp_dir.path(), 2).unwrap_err();
+
+        assert!(error.to_string().contains("Layer file not found"));
+    }
+
     #[test]
     fn tracked_metadata_preserves_header_ownership_and_whiteouts() {
         let temp_dir = TempDir::new().unwrap();
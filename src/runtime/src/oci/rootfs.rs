@@ -9,7 +9,9 @@ use std::path::PathBuf;
 use std::path::{Component, Path};
 
 use super::image::OciImage;
-use super::layers::{extract_layer_with_metadata, finalize_rootfs_metadata};
+use super::layers::{
+    extract_layer_with_metadata, finalize_rootfs_metadata, layer_decompress_jobs, stage_layers,
+};
 
 /// Builder for creating a guest rootfs from an OCI image.
 ///
@@ -169,8 +171,46 @@ impl OciRootfsBuilder {
             "Extracting OCI image"
         );
 
-        for layer_path in image.layer_paths() {
-            extract_layer_with_metadata(layer_path, &self.rootfs_path)?;
+        let layer_paths = image.layer_paths();
+        let jobs = layer_decompress_jobs(layer_paths.len());
+        if jobs <= 1 {
+            for layer_path in layer_paths {
+                extract_layer_with_metadata(layer_path, &self.rootfs_path)?;
+            }
+            return Ok(());
+        }
+
+        // Decompress every layer in parallel into a staging directory next to
+        // the rootfs (same filesystem, so no tmpfs pressure), then apply the
+        // plain tars in layer order so whiteouts still see the lower layers.
+        let staging_parent = self
+            .rootfs_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let staging = tempfile::Builder::new()
+            .prefix(".a3s-layers-")
+            .tempdir_in(staging_parent)
+            .map_err(|e| {
+                BoxError::BuildError(format!(
+                    "Failed to create layer staging directory in {}: {e}",
+                    staging_parent.display()
+                ))
+            })?;
+        tracing::debug!(
+            jobs,
+            staging = %staging.path().display(),
+            "Decompressing OCI layers in parallel"
+        );
+
+        let staged = stage_layers(layer_paths, staging.path(), jobs)?;
+        for staged_path in &staged {
+            extract_layer_with_metadata(staged_path, &self.rootfs_path)?;
+            // Release each staged tar once applied instead of holding every
+            // decompressed layer until the staging directory is dropped.
+            if staged_path.starts_with(staging.path()) {
+                let _ = std::fs::remove_file(staged_path);
+            }
         }
 
         Ok(())
@@ -36,6 +36,23 @@ pub struct ImageStore {
     max_size_bytes: u64,
 }
 
+fn read_index_error(index_path: &Path, e: std::io::Error) -> BoxError {
+    BoxError::OciImageError(format!(
+        "Failed to read image store index {}: {}",
+        index_path.display(),
+        e
+    ))
+}
+
+fn write_index_error(index_path: &Path, e: std::io::Error) -> BoxError {
+    BoxError::OciImageError(format!(
+        "Failed to write image store index {}: {}. {}",
+        index_path.display(),
+        e,
+        state_dir_hint()
+    ))
+}
+
 fn state_dir_hint() -> &'static str {
     "Set A3S_HOME to a writable directory to change the A3S Box state directory."
 }
@@ -367,21 +384,26 @@ impl ImageStore {
         Ok(())
     }
 
-    /// Read and parse `index.json` from disk into a fresh map (entries whose
-    /// content dir vanished are dropped). Does NOT touch `self.index`.
+    /// Read and parse `index.json` from the storage backend into a fresh map
+    /// (entries whose content dir vanished are dropped). Does NOT touch
+    /// `self.index`.
     fn read_index_from_disk(&self) -> Result<HashMap<String, StoredImage>> {
         let index_path = self.store_dir.join("index.json");
-        if !index_path.exists() {
-            return Ok(HashMap::new());
-        }
+        let data = crate::storage::backend()
+            .read(&index_path)
+            .map_err(|e| read_index_error(&index_path, e))?;
+        Ok(self.parse_index(&index_path, data))
+    }
 
-        let data = std::fs::read_to_string(&index_path).map_err(|e| {
-            BoxError::OciImageError(format!(
-                "Failed to read image store index {}: {}",
-                index_path.display(),
-                e
-            ))
-        })?;
+    /// Parse a stored index document; a missing one is an empty catalog.
+    fn parse_index(
+        &self,
+        index_path: &Path,
+        data: Option<Vec<u8>>,
+    ) -> HashMap<String, StoredImage> {
+        let Some(data) = data else {
+            return HashMap::new();
+        };
 
         // Parse resiliently so a corrupt/old-schema index never bricks the whole
         // catalog or blocks CRI/CLI startup. First read the `{ images: [...] }`
@@ -395,17 +417,17 @@ impl ImageStore {
             images: Vec<serde_json::Value>,
         }
 
-        let raw: RawIndex = match serde_json::from_str(&data) {
+        let raw: RawIndex = match serde_json::from_slice(&data) {
             Ok(raw) => raw,
             Err(err) => {
-                let preserved = crate::store_io::quarantine_label(&index_path);
+                let preserved = crate::store_io::quarantine_label(index_path, &data);
                 tracing::warn!(
                     "image store index {} is corrupt ({err}); preserved a copy at \
                      {preserved} and started from an empty catalog (re-pulled images \
                      will repopulate it)",
                     index_path.display(),
                 );
-                return Ok(HashMap::new());
+                return HashMap::new();
             }
         };
 
@@ -449,7 +471,7 @@ impl ImageStore {
             // Preserve the original (with the un-deserializable entries) before the
             // next save rewrites index.json with only the survivors — otherwise the
             // skipped records are erased with no backup, unlike the whole-file path.
-            let preserved = crate::store_io::quarantine_copy(&index_path)
+            let preserved = crate::store_io::quarantine_copy(index_path, &data)
                 .map(|p| p.display().to_string())
                 .unwrap_or_else(|| "<backup failed>".to_string());
             tracing::warn!(
@@ -458,25 +480,27 @@ impl ImageStore {
                 if skipped == 1 { "y" } else { "ies" },
             );
         }
-        Ok(index)
+        index
     }
 
-    /// Apply `f` to the image index under the **cross-process write lock**:
-    /// reload `index.json` from disk (so this process observes other processes'
-    /// pulls/removes), let `f` mutate the map, then save. Without this, two
-    /// processes pulling concurrently each load their own snapshot and the
-    /// second `save` drops the first's entry (and leaks its content dir).
+    /// Apply `f` to the image index inside a **cross-process storage
+    /// transaction**: reload `index.json` (so this process observes other
+    /// processes' pulls/removes), let `f` mutate the map, then write it back.
+    /// Without this, two processes pulling concurrently each load their own
+    /// snapshot and the second `save` drops the first's entry (and leaks its
+    /// content dir).
     ///
-    /// The blocking `flock` is acquired off the runtime via `spawn_blocking`;
-    /// `save_index_inner` is lock-free, so there is no re-entrant `flock`.
+    /// The blocking transaction start and commit run off the runtime via
+    /// `spawn_blocking`; `save_index_inner` is lock-free, so there is no
+    /// re-entrant lock.
     async fn with_index_lock<F, R>(&self, f: F) -> Result<R>
     where
         F: FnOnce(&mut HashMap<String, StoredImage>) -> Result<R>,
     {
         let index_path = self.store_dir.join("index.json");
-        let _lock = {
+        let mut transaction = {
             let p = index_path.clone();
-            tokio::task::spawn_blocking(move || crate::file_lock::FileLock::acquire(&p))
+            tokio::task::spawn_blocking(move || crate::storage::backend().begin(&p))
                 .await
                 .map_err(|e| BoxError::OciImageError(format!("index lock task failed: {e}")))?
                 .map_err(|e| {
@@ -487,52 +511,50 @@ impl ImageStore {
                     ))
                 })?
         };
-        // Sync the in-memory index with disk (pick up other processes' writes).
-        let fresh = self.read_index_from_disk()?;
+        // Sync the in-memory index with storage (pick up other processes' writes).
+        let data = transaction
+            .read()
+            .map_err(|e| read_index_error(&index_path, e))?;
+        let fresh = self.parse_index(&index_path, data);
         let result = {
             let mut idx = self.index.write().await;
             *idx = fresh;
             f(&mut idx)?
         };
-        self.save_index_inner().await?;
+        let data = self.encode_index().await?;
+        tokio::task::spawn_blocking(move || {
+            transaction.write(&data)?;
+            transaction.commit()
+        })
+        .await
+        .map_err(|e| BoxError::OciImageError(format!("index commit task failed: {e}")))?
+        .map_err(|e| write_index_error(&index_path, e))?;
         Ok(result)
     }
 
-    /// Save index to disk (async inner helper).
-    async fn save_index_inner(&self) -> Result<()> {
+    /// Serialize the in-memory index.
+    async fn encode_index(&self) -> Result<Vec<u8>> {
         let index = self.index.read().await;
         let store_index = StoreIndex {
             images: index.values().cloned().collect(),
         };
         drop(index);
+        Ok(serde_json::to_vec_pretty(&store_index)?)
+    }
 
-        let data = serde_json::to_string_pretty(&store_index)?;
+    /// Save index through the storage backend (async inner helper).
+    async fn save_index_inner(&self) -> Result<()> {
+        let data = self.encode_index().await?;
         let index_path = self.store_dir.join("index.json");
-        // Write atomically (tmp + rename) so a concurrent reader (e.g. another
-        // process running `create`/`run`) never observes a truncated/empty
-        // index.json mid-write — which previously surfaced as
+        // The backend replaces the document atomically, so a concurrent reader
+        // (e.g. another process running `create`/`run`) never observes a
+        // truncated/empty index.json mid-write — which previously surfaced as
         // "Failed to parse image store index: EOF".
-        let tmp_path = self.store_dir.join("index.json.tmp");
-        tokio::fs::write(&tmp_path, data).await.map_err(|e| {
-            BoxError::OciImageError(format!(
-                "Failed to write image store index {}: {}. {}",
-                tmp_path.display(),
-                e,
-                state_dir_hint()
-            ))
-        })?;
-        tokio::fs::rename(&tmp_path, &index_path)
+        let p = index_path.clone();
+        tokio::task::spawn_blocking(move || crate::storage::backend().write(&p, &data))
             .await
-            .map_err(|e| {
-                BoxError::OciImageError(format!(
-                    "Failed to commit image store index {}: {}. {}",
-                    index_path.display(),
-                    e,
-                    state_dir_hint()
-                ))
-            })?;
-
-        Ok(())
+            .map_err(|e| BoxError::OciImageError(format!("index write task failed: {e}")))?
+            .map_err(|e| write_index_error(&index_path, e))
     }
 
     /// Get the store directory path.
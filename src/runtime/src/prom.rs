@@ -40,6 +40,22 @@ pub struct RuntimeMetrics {
     /// VM memory usage in bytes (per VM, labeled by box_id).
     pub vm_memory_bytes: GaugeVec,
 
+    // -- Guest resources (reported by guest init) --
+    /// Guest CPU usage percentage; 100 per busy vCPU (labeled by box_id).
+    pub guest_cpu_percent: GaugeVec,
+    /// Guest memory in use in bytes (labeled by box_id).
+    pub guest_memory_used_bytes: GaugeVec,
+    /// Processes running in the guest (labeled by box_id).
+    pub guest_processes: GaugeVec,
+    /// Guest cgroup CPU time in seconds (labeled by box_id, cgroup).
+    pub guest_cgroup_cpu_seconds: GaugeVec,
+    /// Guest cgroup memory usage in bytes (labeled by box_id, cgroup).
+    pub guest_cgroup_memory_bytes: GaugeVec,
+    /// Processes in a guest cgroup (labeled by box_id, cgroup).
+    pub guest_cgroup_pids: GaugeVec,
+    /// OOM kills in a guest cgroup (labeled by box_id, cgroup).
+    pub guest_cgroup_oom_kills: GaugeVec,
+
     // -- Exec operations --
     /// Total exec commands executed.
     pub exec_total: IntCounter,
@@ -121,6 +137,57 @@ impl RuntimeMetrics {
             &["box_id"],
         )?;
 
+        // Guest resources
+        let guest_cpu_percent = GaugeVec::new(
+            Opts::new(
+                "a3s_box_guest_cpu_percent",
+                "Guest CPU usage percentage reported by guest init",
+            ),
+            &["box_id"],
+        )?;
+
+        let guest_memory_used_bytes = GaugeVec::new(
+            Opts::new(
+                "a3s_box_guest_memory_used_bytes",
+                "Guest memory in use in bytes reported by guest init",
+            ),
+            &["box_id"],
+        )?;
+
+        let guest_processes = GaugeVec::new(
+            Opts::new("a3s_box_guest_processes", "Processes running in the guest"),
+            &["box_id"],
+        )?;
+
+        let guest_cgroup_cpu_seconds = GaugeVec::new(
+            Opts::new(
+                "a3s_box_guest_cgroup_cpu_seconds",
+                "Guest cgroup CPU time in seconds",
+            ),
+            &["box_id", "cgroup"],
+        )?;
+
+        let guest_cgroup_memory_bytes = GaugeVec::new(
+            Opts::new(
+                "a3s_box_guest_cgroup_memory_bytes",
+                "Guest cgroup memory usage in bytes",
+            ),
+            &["box_id", "cgroup"],
+        )?;
+
+        let guest_cgroup_pids = GaugeVec::new(
+            Opts::new("a3s_box_guest_cgroup_pids", "Processes in a guest cgroup"),
+            &["box_id", "cgroup"],
+        )?;
+
+        let guest_cgroup_oom_kills = GaugeVec::new(
+            Opts::new(
+                "a3s_box_guest_cgroup_oom_kills",
+                "OOM kills in a guest cgroup",
+            ),
+            &["box_id", "cgroup"],
+        )?;
+
         // Exec operations
         let exec_total = IntCounter::new("a3s_box_exec_total", "Total exec commands executed")?;
 
@@ -180,6 +247,13 @@ impl RuntimeMetrics {
         registry.register(Box::new(vm_destroyed_total.clone()))?;
         registry.register(Box::new(vm_cpu_percent.clone()))?;
         registry.register(Box::new(vm_memory_bytes.clone()))?;
+        registry.register(Box::new(guest_cpu_percent.clone()))?;
+        registry.register(Box::new(guest_memory_used_bytes.clone()))?;
+        registry.register(Box::new(guest_processes.clone()))?;
+        registry.register(Box::new(guest_cgroup_cpu_seconds.clone()))?;
+        registry.register(Box::new(guest_cgroup_memory_bytes.clone()))?;
+        registry.register(Box::new(guest_cgroup_pids.clone()))?;
+        registry.register(Box::new(guest_cgroup_oom_kills.clone()))?;
         registry.register(Box::new(exec_total.clone()))?;
         registry.register(Box::new(exec_duration.clone()))?;
         registry.register(Box::new(exec_errors_total.clone()))?;
@@ -201,6 +275,13 @@ impl RuntimeMetrics {
             vm_destroyed_total,
             vm_cpu_percent,
             vm_memory_bytes,
+            guest_cpu_percent,
+            guest_memory_used_bytes,
+            guest_processes,
+            guest_cgroup_cpu_seconds,
+            guest_cgroup_memory_bytes,
+            guest_cgroup_pids,
+            guest_cgroup_oom_kills,
             exec_total,
             exec_duration,
             exec_errors_total,
@@ -216,6 +297,34 @@ impl RuntimeMetrics {
         })
     }
 
+    /// Set the guest gauges of `box_id` from a guest metrics sample.
+    pub fn record_guest_metrics(&self, box_id: &str, metrics: &a3s_box_core::GuestMetrics) {
+        self.guest_cpu_percent
+            .with_label_values(&[box_id])
+            .set(metrics.cpu_percent);
+        self.guest_memory_used_bytes
+            .with_label_values(&[box_id])
+            .set(metrics.memory_used_bytes as f64);
+        self.guest_processes
+            .with_label_values(&[box_id])
+            .set(metrics.processes as f64);
+        for cgroup in &metrics.cgroups {
+            let labels = [box_id, cgroup.name.as_str()];
+            self.guest_cgroup_cpu_seconds
+                .with_label_values(&labels)
+                .set(cgroup.cpu_usage_ns as f64 / 1e9);
+            self.guest_cgroup_memory_bytes
+                .with_label_values(&labels)
+                .set(cgroup.memory_bytes as f64);
+            self.guest_cgroup_pids
+                .with_label_values(&labels)
+                .set(cgroup.pids as f64);
+            self.guest_cgroup_oom_kills
+                .with_label_values(&labels)
+                .set(cgroup.oom_kills as f64);
+        }
+    }
+
     /// Encode all metrics in Prometheus text exposition format.
     pub fn encode(&self) -> String {
         use prometheus::Encoder;
@@ -330,6 +439,40 @@ mod tests {
         assert_eq!(m.vm_cpu_percent.with_label_values(&["box-123"]).get(), 45.5);
     }
 
+    #[test]
+    fn test_record_guest_metrics() {
+        let m = RuntimeMetrics::new();
+        m.record_guest_metrics(
+            "box-123",
+            &a3s_box_core::GuestMetrics {
+                cpu_percent: 150.0,
+                memory_used_bytes: 4096,
+                processes: 3,
+                cgroups: vec![a3s_box_core::GuestCgroupMetrics {
+                    name: "box-9-1".to_string(),
+                    cpu_usage_ns: 2_500_000_000,
+                    memory_bytes: 1024,
+                    pids: 2,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            m.guest_cpu_percent.with_label_values(&["box-123"]).get(),
+            150.0
+        );
+        assert_eq!(
+            m.guest_cgroup_cpu_seconds
+                .with_label_values(&["box-123", "box-9-1"])
+                .get(),
+            2.5
+        );
+        assert!(m
+            .encode()
+            .contains("a3s_box_guest_cgroup_pids{box_id=\"box-123\",cgroup=\"box-9-1\"} 2"));
+    }
+
     #[test]
     fn test_exec_metrics() {
         let m = RuntimeMetrics::new();
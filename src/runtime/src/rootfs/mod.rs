@@ -6,15 +6,22 @@
 //! Two rootfs providers are available:
 //! - `CopyProvider` — full recursive copy (works everywhere)
 //! - `OverlayProvider` — Linux overlayfs mount (near-instant CoW)
+//!
+//! Boxes with a disk quota use `DiskQuotaProvider`, which keeps the writable
+//! layer on a size-limited ext4 image (see [`quota`]).
 
 mod builder;
 mod layout;
 pub(crate) mod overlay;
 mod provider;
+pub mod quota;
 
 pub use builder::RootfsBuilder;
 pub use layout::{GuestLayout, GUEST_WORKDIR};
-pub use provider::{default_provider, CopyProvider, OverlayProvider, RootfsProvider};
+pub use provider::{
+    default_provider, provider_for_disk_limit, CopyProvider, OverlayProvider, RootfsProvider,
+};
+pub use quota::DiskQuotaProvider;
 
 use std::path::{Path, PathBuf};
 
@@ -26,6 +33,7 @@ use std::path::{Path, PathBuf};
 pub fn read_persisted_exit_code(box_dir: &Path) -> Option<i32> {
     let candidates = [
         box_dir.join("upper").join(".a3s_exit_code"),
+        box_dir.join("rootfs").join("upper").join(".a3s_exit_code"),
         box_dir
             .join("rootfs")
             .join(".a3s-rootfs")
@@ -201,7 +209,20 @@ pub fn unmount_box_rootfs(rootfs: &Path) {
         }
     }
 
-    #[cfg(not(target_os = "macos"))]
+    // A quota-backed box loop-mounts its ext4 image at `rootfs`; a plain
+    // rootfs directory is never a mountpoint, so this is a no-op for it.
+    #[cfg(target_os = "linux")]
+    if is_mountpoint(rootfs) {
+        if let Err(error) = overlay::overlay_unmount(rootfs) {
+            tracing::warn!(
+                path = %rootfs.display(),
+                %error,
+                "Failed to unmount quota-backed rootfs"
+            );
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     let _ = rootfs;
 }
 
@@ -375,6 +375,17 @@ impl RootfsProvider for OverlayProvider {
     }
 }
 
+/// Select the rootfs provider for a box, honoring an optional disk quota.
+pub fn provider_for_disk_limit(disk_limit_bytes: Option<u64>) -> Box<dyn RootfsProvider> {
+    match disk_limit_bytes {
+        Some(limit) => {
+            tracing::info!(limit_bytes = limit, "Using disk-quota rootfs provider");
+            Box::new(super::quota::DiskQuotaProvider::new(limit))
+        }
+        None => default_provider(),
+    }
+}
+
 /// Auto-detect the best available rootfs provider for the current platform.
 pub fn default_provider() -> Box<dyn RootfsProvider> {
     #[cfg(target_os = "macos")]
@@ -0,0 +1,323 @@
+//! Per-box disk quotas for the writable rootfs layer and workspace.
+//!
+//! A box with `ResourceLimits::disk_limit_bytes` gets a sparse ext4 image of
+//! exactly that size, loop-mounted at `box_dir/rootfs`. The overlay upper and
+//! workdir, the cache-miss rootfs generation, and the default per-box
+//! workspace all live on that filesystem, so the kernel rejects writes past the
+//! limit with `ENOSPC` no matter which path the guest writes through.
+//!
+//! Layout:
+//! ```text
+//! box_dir/rootfs-quota.ext4    ← sparse image sized to the quota
+//! box_dir/rootfs/              ← image mountpoint
+//! box_dir/rootfs/data/         ← cache-miss rootfs generation (overlay lower)
+//! box_dir/rootfs/upper/        ← overlay upper (per-box writes)
+//! box_dir/rootfs/work/         ← overlay workdir
+//! box_dir/rootfs/workspace/    ← default per-box workspace
+//! box_dir/merged/              ← merged view → InstanceSpec.rootfs_path
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use a3s_box_core::error::{BoxError, Result};
+
+use super::provider::RootfsProvider;
+
+/// File name of the sparse quota image inside the box directory.
+pub const QUOTA_IMAGE_NAME: &str = "rootfs-quota.ext4";
+
+/// Smallest quota accepted; ext4 needs room for its own metadata.
+pub const MIN_DISK_LIMIT_BYTES: u64 = 64 * 1024 * 1024;
+
+const DATA_DIR: &str = "data";
+
+/// Measured usage of a box's quota volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskQuotaUsage {
+    /// Bytes currently allocated on the quota volume.
+    pub used_bytes: u64,
+    /// Configured quota in bytes.
+    pub limit_bytes: u64,
+}
+
+/// Validate a requested quota size.
+pub fn validate_disk_limit(limit_bytes: u64) -> Result<()> {
+    if limit_bytes < MIN_DISK_LIMIT_BYTES {
+        return Err(BoxError::ConfigError(format!(
+            "disk quota must be at least {} MiB",
+            MIN_DISK_LIMIT_BYTES / (1024 * 1024)
+        )));
+    }
+    if !cfg!(target_os = "linux") {
+        return Err(BoxError::ConfigError(
+            "disk quotas are only supported on Linux hosts".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Mount (creating on first use) the quota volume for `box_dir`.
+///
+/// Returns the mountpoint. Idempotent: an already mounted volume is reused.
+pub fn mount_quota_volume(box_dir: &Path, limit_bytes: u64) -> Result<PathBuf> {
+    let mountpoint = box_dir.join("rootfs");
+    std::fs::create_dir_all(&mountpoint).map_err(|error| {
+        BoxError::BuildError(format!(
+            "Failed to create quota mountpoint {}: {error}",
+            mountpoint.display()
+        ))
+    })?;
+    if super::is_mountpoint(&mountpoint) {
+        return Ok(mountpoint);
+    }
+
+    let image = box_dir.join(QUOTA_IMAGE_NAME);
+    if !image.exists() {
+        create_quota_image(&image, limit_bytes)?;
+    }
+    loop_mount(&image, &mountpoint)?;
+    Ok(mountpoint)
+}
+
+/// Report quota usage for a box, if it has a quota volume.
+///
+/// A mounted volume is measured with `statvfs`; an unmounted (stopped) one
+/// falls back to the image's allocated size, which tracks the data written.
+pub fn disk_usage(box_dir: &Path, limit_bytes: u64) -> Option<DiskQuotaUsage> {
+    let image = box_dir.join(QUOTA_IMAGE_NAME);
+    if !image.is_file() {
+        return None;
+    }
+    let mountpoint = box_dir.join("rootfs");
+    let used_bytes = if super::is_mountpoint(&mountpoint) {
+        mounted_used_bytes(&mountpoint)?
+    } else {
+        allocated_bytes(&image)?
+    };
+    Some(DiskQuotaUsage {
+        used_bytes,
+        limit_bytes,
+    })
+}
+
+#[cfg(unix)]
+fn allocated_bytes(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.blocks() * 512)
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|metadata| metadata.len())
+}
+
+#[cfg(unix)]
+fn mounted_used_bytes(mountpoint: &Path) -> Option<u64> {
+    let path = std::ffi::CString::new(mountpoint.to_string_lossy().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid NUL-terminated string and `stat` is a
+    // writable statvfs buffer owned by this frame.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let fragment = stat.f_frsize as u64;
+    Some((stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64) * fragment)
+}
+
+#[cfg(not(unix))]
+fn mounted_used_bytes(_mountpoint: &Path) -> Option<u64> {
+    None
+}
+
+fn create_quota_image(image: &Path, limit_bytes: u64) -> Result<()> {
+    let file = std::fs::File::create(image).map_err(|error| {
+        BoxError::BuildError(format!(
+            "Failed to create quota image {}: {error}",
+            image.display()
+        ))
+    })?;
+    file.set_len(limit_bytes).map_err(|error| {
+        BoxError::BuildError(format!(
+            "Failed to size quota image {}: {error}",
+            image.display()
+        ))
+    })?;
+    drop(file);
+
+    let mut command = std::process::Command::new("mkfs.ext4");
+    command.args(["-q", "-F", "-m", "0"]);
+    #[cfg(unix)]
+    {
+        // Keep the volume root owned by the runtime user so rootless
+        // directory creation inside it keeps working after the mount.
+        let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+        command.args(["-E", &format!("root_owner={uid}:{gid}")]);
+    }
+    let output = command.arg(image).output().map_err(|error| {
+        let _ = std::fs::remove_file(image);
+        BoxError::BuildError(format!(
+            "Failed to run mkfs.ext4 for disk quota (is e2fsprogs installed?): {error}"
+        ))
+    })?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(image);
+        return Err(BoxError::BuildError(format!(
+            "mkfs.ext4 failed for quota image {}: {}",
+            image.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+fn loop_mount(image: &Path, mountpoint: &Path) -> Result<()> {
+    let output = std::process::Command::new("mount")
+        .args(["-t", "ext4", "-o", "loop,nosuid,nodev"])
+        .arg(image)
+        .arg(mountpoint)
+        .output()
+        .map_err(|error| BoxError::BuildError(format!("Failed to run mount: {error}")))?;
+    if !output.status.success() {
+        return Err(BoxError::BuildError(format!(
+            "Failed to mount quota image {} (disk quotas need root or loop-mount permission): {}",
+            image.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+fn create_dir(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir).map_err(|error| {
+        BoxError::BuildError(format!(
+            "Failed to create quota dir {}: {error}",
+            dir.display()
+        ))
+    })
+}
+
+/// Overlay provider whose writable layer lives on a size-limited ext4 image.
+pub struct DiskQuotaProvider {
+    limit_bytes: u64,
+}
+
+impl DiskQuotaProvider {
+    /// Create a provider enforcing `limit_bytes` per box.
+    pub fn new(limit_bytes: u64) -> Self {
+        Self { limit_bytes }
+    }
+}
+
+impl RootfsProvider for DiskQuotaProvider {
+    fn prepare(&self, box_dir: &Path, cache_dir: &Path) -> Result<PathBuf> {
+        let volume = mount_quota_volume(box_dir, self.limit_bytes)?;
+        let data = volume.join(DATA_DIR);
+        let lower = match std::fs::read_dir(&data) {
+            Ok(mut entries) if entries.next().is_some() => data,
+            _ => cache_dir.to_path_buf(),
+        };
+
+        if !super::overlay::is_overlay_supported() {
+            // Without overlayfs the whole rootfs is copied onto the volume, so
+            // the image itself counts against the quota.
+            if lower != data {
+                crate::cache::layer_cache::copy_dir_recursive(&lower, &data)?;
+            }
+            return Ok(data);
+        }
+
+        let upper = volume.join("upper");
+        let work = volume.join("work");
+        let merged = box_dir.join("merged");
+        for dir in [&upper, &work, &merged] {
+            create_dir(dir)?;
+        }
+        if super::is_mountpoint(&merged) {
+            return Ok(merged);
+        }
+        super::overlay::overlay_mount(&lower, &upper, &work, &merged)?;
+        tracing::info!(
+            lower = %lower.display(),
+            merged = %merged.display(),
+            limit_bytes = self.limit_bytes,
+            "Quota-backed overlay mount ready"
+        );
+        Ok(merged)
+    }
+
+    fn prepare_empty(&self, box_dir: &Path) -> Result<PathBuf> {
+        let data = mount_quota_volume(box_dir, self.limit_bytes)?.join(DATA_DIR);
+        create_dir(&data)?;
+        Ok(data)
+    }
+
+    fn cleanup(&self, box_dir: &Path, persistent: bool) -> Result<()> {
+        let merged = box_dir.join("merged");
+        super::unmount_box_overlay(&merged);
+        if merged.exists() {
+            if let Err(error) = std::fs::remove_dir_all(&merged) {
+                tracing::warn!(path = %merged.display(), %error, "Failed to remove overlay dir");
+            }
+        }
+
+        let volume = box_dir.join("rootfs");
+        if persistent {
+            let _ = std::fs::remove_dir_all(volume.join("work"));
+            super::unmount_box_rootfs(&volume);
+            tracing::info!("Persistent box: keeping quota image on disk");
+            return Ok(());
+        }
+
+        super::unmount_box_rootfs(&volume);
+        let image = box_dir.join(QUOTA_IMAGE_NAME);
+        if image.exists() {
+            std::fs::remove_file(&image).map_err(|error| {
+                BoxError::BuildError(format!(
+                    "Failed to remove quota image {}: {error}",
+                    image.display()
+                ))
+            })?;
+        }
+        if volume.exists() && !super::is_mountpoint(&volume) {
+            let _ = std::fs::remove_dir_all(&volume);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "disk-quota"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate_disk_limit_rejects_tiny_quota() {
+        let error = validate_disk_limit(1024 * 1024).unwrap_err();
+        assert!(error.to_string().contains("at least 64 MiB"));
+    }
+
+    #[test]
+    fn test_disk_usage_without_image_is_none() {
+        let tmp = TempDir::new().unwrap();
+        assert_eq!(disk_usage(tmp.path(), MIN_DISK_LIMIT_BYTES), None);
+    }
+
+    #[test]
+    fn test_disk_usage_reports_sparse_allocation_when_unmounted() {
+        let tmp = TempDir::new().unwrap();
+        let image = tmp.path().join(QUOTA_IMAGE_NAME);
+        let file = std::fs::File::create(&image).unwrap();
+        file.set_len(MIN_DISK_LIMIT_BYTES).unwrap();
+
+        let usage = disk_usage(tmp.path(), MIN_DISK_LIMIT_BYTES).unwrap();
+
+        assert_eq!(usage.limit_bytes, MIN_DISK_LIMIT_BYTES);
+        assert!(usage.used_bytes < MIN_DISK_LIMIT_BYTES);
+    }
+}
@@ -659,7 +659,7 @@ fn probe_seccomp_and_privileges(snapshot: &mut SandboxCapabilitySnapshot) {
 }
 
 #[cfg(target_os = "linux")]
-fn probe_cgroup_v2() -> CgroupV2Evidence {
+pub(crate) fn probe_cgroup_v2() -> CgroupV2Evidence {
     let mountpoint = read_trimmed("/proc/self/mountinfo")
         .and_then(|contents| parse_cgroup2_mountpoint(&contents));
     let relative = read_trimmed("/proc/self/cgroup")
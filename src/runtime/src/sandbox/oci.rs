@@ -4,6 +4,7 @@ use std::collections::{HashMap, HashSet};
 use std::path::{Component, Path, PathBuf};
 
 use a3s_box_core::config::BoxConfig;
+use a3s_box_core::entropy::RUNTIME_ENTROPY_SEED_PATH;
 use a3s_box_core::error::{BoxError, Result};
 use a3s_box_core::guest_exec::RUNTIME_EXEC_CONFIG_PATH;
 use a3s_box_core::rootfs_metadata::RUNTIME_ENV_PATH;
@@ -55,6 +56,7 @@ pub struct SandboxResources {
     pub cpu_quota: i64,
     pub cpu_period: u64,
     pub cpuset_cpus: Option<String>,
+    pub cpuset_mems: Option<String>,
     pub pids_limit: i64,
 }
 
@@ -166,6 +168,10 @@ impl SandboxResources {
             cpu_quota,
             cpu_period,
             cpuset_cpus: config.resource_limits.cpuset_cpus.clone(),
+            cpuset_mems: config
+                .resource_limits
+                .numa_node
+                .map(|node| node.to_string()),
             pids_limit,
         })
     }
@@ -179,6 +185,8 @@ pub struct SandboxBundleSpec {
     pub rootfs_read_only: bool,
     pub hostname: String,
     pub init_environment: Vec<(String, String)>,
+    /// Numeric `--group-add` gids carried as OCI `process.user.additionalGids`.
+    pub additional_gids: Vec<u32>,
     pub mounts: Vec<SandboxMount>,
     pub tmpfs: Vec<SandboxTmpfs>,
     pub id_mappings: SandboxIdMappingPlan,
@@ -197,16 +205,15 @@ pub fn compile_oci_spec(input: &SandboxBundleSpec) -> Result<Spec> {
     validate_digest("execution plan", &input.execution_plan_digest)?;
     validate_digest("runtime", &input.runtime_digest)?;
     validate_id_mapping_plan(&input.id_mappings)?;
+    validate_additional_gids(&input.additional_gids, &input.id_mappings)?;
 
+    let mut user = UserBuilder::default().uid(0u32).gid(0u32);
+    if !input.additional_gids.is_empty() {
+        user = user.additional_gids(input.additional_gids.clone());
+    }
     let process = ProcessBuilder::default()
         .terminal(false)
-        .user(
-            UserBuilder::default()
-                .uid(0u32)
-                .gid(0u32)
-                .build()
-                .map_err(oci_error)?,
-        )
+        .user(user.build().map_err(oci_error)?)
         .args(vec!["/sbin/init".to_string()])
         .env(compile_environment(&input.init_environment)?)
         .cwd(PathBuf::from("/"))
@@ -405,6 +412,9 @@ fn compile_resources(resources: &SandboxResources) -> Result<oci_spec::runtime::
     if let Some(cpuset) = resources.cpuset_cpus.as_ref() {
         cpu = cpu.cpus(cpuset.clone());
     }
+    if let Some(mems) = resources.cpuset_mems.as_ref() {
+        cpu = cpu.mems(mems.clone());
+    }
 
     let mut device_rules = vec![LinuxDeviceCgroupBuilder::default()
         .allow(false)
@@ -732,6 +742,16 @@ fn validate_id_mapping_plan(plan: &SandboxIdMappingPlan) -> Result<()> {
     Ok(())
 }
 
+fn validate_additional_gids(gids: &[u32], plan: &SandboxIdMappingPlan) -> Result<()> {
+    if let Some(gid) = gids.iter().find(|gid| **gid > plan.maximum_container_gid) {
+        return Err(BoxError::ConfigError(format!(
+            "Sandbox supplementary group {gid} is outside the mapped GID range (max {})",
+            plan.maximum_container_gid
+        )));
+    }
+    Ok(())
+}
+
 fn validate_mapping_set(mappings: &[IdMapping], maximum: u32, kind: &str) -> Result<()> {
     if mappings.is_empty() || mappings[0].container_id != 0 {
         return Err(BoxError::ConfigError(format!(
@@ -795,6 +815,7 @@ fn validate_user_mount(mount: &SandboxMount) -> Result<()> {
         "/sys",
         RUNTIME_EXEC_CONFIG_PATH,
         RUNTIME_ENV_PATH,
+        RUNTIME_ENTROPY_SEED_PATH,
         "/.a3s_image_metadata_v1.json",
         "/.a3s_image_metadata_v1.json.tmp",
         "/.a3s_rootfs_metadata_v1.json",
@@ -1248,6 +1269,7 @@ mod tests {
                     "attacker-value".to_string(),
                 ),
             ],
+            additional_gids: Vec::new(),
             mounts: vec![SandboxMount {
                 source: std::env::temp_dir().join("a3s/workspaces/box-123"),
                 destination: PathBuf::from("/workspace"),
@@ -1276,6 +1298,7 @@ mod tests {
                 cpu_quota: 200000,
                 cpu_period: 100000,
                 cpuset_cpus: Some("0-1".to_string()),
+                cpuset_mems: Some("0".to_string()),
                 pids_limit: 512,
             },
             requested_capabilities: Vec::new(),
@@ -1318,6 +1341,21 @@ mod tests {
         );
         assert_eq!(value["linux"]["resources"]["pids"]["limit"], 512);
         assert_eq!(value["linux"]["resources"]["cpu"]["cpus"], "0-1");
+        assert_eq!(value["linux"]["resources"]["cpu"]["mems"], "0");
+    }
+
+    #[test]
+    fn compiler_emits_mapped_additional_gids() {
+        let mut input = sample_input();
+        input.additional_gids = vec![44, 999];
+        let value = as_json(&compile_oci_spec(&input).unwrap());
+        assert_eq!(
+            value["process"]["user"]["additionalGids"],
+            serde_json::json!([44, 999])
+        );
+
+        input.additional_gids = vec![70000];
+        assert!(compile_oci_spec(&input).is_err());
     }
 
     #[test]
@@ -0,0 +1,133 @@
+//! Default backend: one JSON file per document.
+
+use std::path::{Path, PathBuf};
+
+use super::{StorageBackend, StorageTransaction};
+use crate::file_lock::FileLock;
+
+/// Keeps each document at its own path.
+///
+/// Transactions hold the `<path>.lock` advisory lock. Each write is an atomic,
+/// fsynced tmp+rename, so a crash never leaves a torn file, but there is no
+/// rollback: a write inside an uncommitted transaction is already visible.
+/// The journal is a `<path>.journal` sibling written the same way.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileBackend;
+
+impl StorageBackend for FileBackend {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+        read_file(path)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> std::io::Result<()> {
+        write_file(path, data)
+    }
+
+    fn begin(&self, path: &Path) -> std::io::Result<Box<dyn StorageTransaction>> {
+        let lock = FileLock::acquire(path)?;
+        Ok(Box::new(FileTransaction {
+            path: path.to_path_buf(),
+            _lock: lock,
+        }))
+    }
+}
+
+struct FileTransaction {
+    path: PathBuf,
+    _lock: FileLock,
+}
+
+impl StorageTransaction for FileTransaction {
+    fn read(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        read_file(&self.path)
+    }
+
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        write_file(&self.path, data)
+    }
+
+    fn write_journal(&mut self, data: &[u8]) -> std::io::Result<()> {
+        write_file(&sibling(&self.path, "journal"), data)
+    }
+
+    fn read_journal(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        read_file(&sibling(&self.path, "journal"))
+    }
+
+    fn commit(self: Box<Self>) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub(super) fn read_file(path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn write_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    a3s_box_core::fs_atomic::write_durable(&sibling(path, "tmp"), path, data)
+}
+
+/// `<path>.<suffix>`, e.g. `volumes.json.journal`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_reads_and_replaces_document() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("nested").join("volumes.json");
+
+        assert_eq!(FileBackend.read(&path).unwrap(), None);
+
+        let mut transaction = FileBackend.begin(&path).unwrap();
+        assert_eq!(transaction.read().unwrap(), None);
+        transaction.write(b"{}").unwrap();
+        transaction.commit().unwrap();
+
+        assert_eq!(
+            FileBackend.read(&path).unwrap().as_deref(),
+            Some(&b"{}"[..])
+        );
+        assert!(!path.with_extension("json.tmp").exists());
+
+        FileBackend.write(&path, b"[]").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"[]");
+    }
+
+    #[test]
+    fn journal_is_a_separate_sibling() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("networks.json");
+
+        let mut transaction = FileBackend.begin(&path).unwrap();
+        assert_eq!(transaction.read_journal().unwrap(), None);
+        transaction.write_journal(b"{\"networks\":{}}").unwrap();
+        transaction.write(b"{\"networks\":{}}").unwrap();
+        std::fs::write(&path, b"torn").unwrap();
+
+        assert_eq!(
+            transaction.read_journal().unwrap().as_deref(),
+            Some(&b"{\"networks\":{}}"[..])
+        );
+        assert!(directory.path().join("networks.json.journal").exists());
+        assert!(!directory.path().join("networks.json.journal.tmp").exists());
+    }
+}
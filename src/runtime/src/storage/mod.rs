@@ -0,0 +1,125 @@
+//! Pluggable persistence for the runtime's JSON stores.
+//!
+//! `boxes.json`, `volumes.json`, `networks.json`, and the image `index.json`
+//! are each one document: readers load the whole document, and writers run a
+//! load → mutate → save transaction over it. [`StorageBackend`] is the seam
+//! between those stores and where the bytes live; stores still name each
+//! document by its file path, so logs and recovery hints stay unchanged.
+//!
+//! - [`FileBackend`] (the default) keeps each document at its path,
+//!   serialises writers with the `<path>.lock` advisory lock, and replaces the
+//!   file with a durable tmp+rename. Journaled stores also keep a
+//!   `<path>.journal` copy of the last committed version. It needs nothing
+//!   beyond the filesystem.
+//! - `SqliteBackend` (the `sqlite-storage` feature) keeps documents as rows in
+//!   a `store.db` beside them and runs each transaction as `BEGIN IMMEDIATE`,
+//!   so a failed writer rolls back instead of leaving a half-applied update.
+//!   A document with no row yet is read from its legacy JSON file, which
+//!   migrates existing state on the first write.
+//!
+//! The backend is chosen once per process from `A3S_BOX_STORAGE` (`file` or
+//! `sqlite`); daemons opt in, and the CLI keeps the file default.
+
+mod file;
+#[cfg(feature = "sqlite-storage")]
+mod sqlite;
+
+pub use file::FileBackend;
+#[cfg(feature = "sqlite-storage")]
+pub use sqlite::SqliteBackend;
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Environment variable selecting the process-wide backend.
+pub const STORAGE_ENV: &str = "A3S_BOX_STORAGE";
+
+/// Where the runtime's JSON documents are persisted.
+pub trait StorageBackend: Send + Sync + std::fmt::Debug {
+    /// Short backend name for diagnostics (`file`, `sqlite`).
+    fn name(&self) -> &'static str;
+
+    /// Read the document stored for `path`; `None` if it was never written.
+    fn read(&self, path: &Path) -> std::io::Result<Option<Vec<u8>>>;
+
+    /// Replace the document without taking the writer lock.
+    ///
+    /// The replacement is atomic, but a concurrent transaction may overwrite
+    /// it. Read-modify-write callers must use [`StorageBackend::begin`].
+    fn write(&self, path: &Path, data: &[u8]) -> std::io::Result<()>;
+
+    /// Start an exclusive transaction on the document at `path`.
+    ///
+    /// Other writers of the same document block until the transaction is
+    /// committed or dropped. The transaction is not reentrant.
+    fn begin(&self, path: &Path) -> std::io::Result<Box<dyn StorageTransaction>>;
+}
+
+/// An exclusive read-modify-write transaction over one document.
+///
+/// Dropping a transaction without [`StorageTransaction::commit`] releases it;
+/// backends that support rollback discard its writes.
+pub trait StorageTransaction: Send {
+    /// Read the document as of this transaction.
+    fn read(&mut self) -> std::io::Result<Option<Vec<u8>>>;
+
+    /// Replace the document.
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()>;
+
+    /// Keep `data` as the document's recovery journal.
+    ///
+    /// Written ahead of [`StorageTransaction::write`], the journal lets a
+    /// reader restore the last committed version when the document itself is
+    /// damaged. Backends with their own write-ahead log keep nothing.
+    fn write_journal(&mut self, _data: &[u8]) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Read the journal from [`StorageTransaction::write_journal`], if any.
+    fn read_journal(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Make the writes durable and release the document.
+    fn commit(self: Box<Self>) -> std::io::Result<()>;
+}
+
+/// The backend selected for this process by [`STORAGE_ENV`].
+pub fn backend() -> &'static dyn StorageBackend {
+    static BACKEND: OnceLock<Box<dyn StorageBackend>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| select(std::env::var(STORAGE_ENV).ok().as_deref()))
+        .as_ref()
+}
+
+fn select(value: Option<&str>) -> Box<dyn StorageBackend> {
+    match value.map(str::trim) {
+        None | Some("") | Some("file") => Box::new(FileBackend),
+        #[cfg(feature = "sqlite-storage")]
+        Some("sqlite") => Box::new(SqliteBackend),
+        Some(other) => {
+            tracing::warn!(
+                "{STORAGE_ENV}={other} is not available in this build; using the file backend"
+            );
+            Box::new(FileBackend)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_defaults_to_file_backend() {
+        assert_eq!(select(None).name(), "file");
+        assert_eq!(select(Some("file")).name(), "file");
+        assert_eq!(select(Some("etcd")).name(), "file");
+    }
+
+    #[cfg(feature = "sqlite-storage")]
+    #[test]
+    fn select_sqlite_backend() {
+        assert_eq!(select(Some("sqlite")).name(), "sqlite");
+    }
+}
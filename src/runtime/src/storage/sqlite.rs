@@ -0,0 +1,204 @@
+//! SQLite backend: documents as rows with transactional writes.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio_rusqlite::rusqlite::{params, Connection, OptionalExtension};
+
+use super::file::read_file;
+use super::{StorageBackend, StorageTransaction};
+
+/// Database file holding every document of one state directory.
+const DATABASE_FILE: &str = "store.db";
+
+/// How long a writer waits for another process's transaction.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Keeps documents in `store.db` next to their JSON path, keyed by file name.
+///
+/// Each transaction is `BEGIN IMMEDIATE`: it takes the database write lock up
+/// front, and an uncommitted transaction rolls back when dropped. Documents
+/// without a row fall back to their legacy JSON file, so switching an existing
+/// state directory to SQLite keeps its contents.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SqliteBackend;
+
+impl StorageBackend for SqliteBackend {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+        // Plain reads stay side-effect free until a writer creates the database.
+        if !database_path(path).exists() {
+            return read_file(path);
+        }
+        let connection = open(path)?;
+        read_document(&connection, path)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> std::io::Result<()> {
+        let connection = open(path)?;
+        write_document(&connection, path, data)
+    }
+
+    fn begin(&self, path: &Path) -> std::io::Result<Box<dyn StorageTransaction>> {
+        let connection = open(path)?;
+        connection
+            .execute_batch("BEGIN IMMEDIATE")
+            .map_err(std::io::Error::other)?;
+        Ok(Box::new(SqliteTransaction {
+            connection,
+            path: path.to_path_buf(),
+            finished: false,
+        }))
+    }
+}
+
+struct SqliteTransaction {
+    connection: Connection,
+    path: PathBuf,
+    finished: bool,
+}
+
+impl StorageTransaction for SqliteTransaction {
+    fn read(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        read_document(&self.connection, &self.path)
+    }
+
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        write_document(&self.connection, &self.path, data)
+    }
+
+    fn commit(mut self: Box<Self>) -> std::io::Result<()> {
+        self.connection
+            .execute_batch("COMMIT")
+            .map_err(std::io::Error::other)?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Drop for SqliteTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.connection.execute_batch("ROLLBACK");
+        }
+    }
+}
+
+fn database_path(path: &Path) -> PathBuf {
+    path.parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(DATABASE_FILE)
+}
+
+fn open(path: &Path) -> std::io::Result<Connection> {
+    let database = database_path(path);
+    if let Some(directory) = database.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let connection = Connection::open(database).map_err(std::io::Error::other)?;
+    connection
+        .busy_timeout(BUSY_TIMEOUT)
+        .map_err(std::io::Error::other)?;
+    connection
+        .query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+        .map_err(std::io::Error::other)?;
+    connection
+        .execute_batch(
+            "CREATE TABLE IF NOT EXISTS documents (
+                key TEXT PRIMARY KEY,
+                data BLOB NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+        )
+        .map_err(std::io::Error::other)?;
+    Ok(connection)
+}
+
+fn key(path: &Path) -> std::io::Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("storage path {} has no file name", path.display()),
+            )
+        })
+}
+
+fn read_document(connection: &Connection, path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    let row = connection
+        .query_row(
+            "SELECT data FROM documents WHERE key = ?1",
+            params![key(path)?],
+            |row| row.get::<_, Vec<u8>>(0),
+        )
+        .optional()
+        .map_err(std::io::Error::other)?;
+    match row {
+        Some(data) => Ok(Some(data)),
+        None => read_file(path),
+    }
+}
+
+fn write_document(connection: &Connection, path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let updated_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0);
+    connection
+        .execute(
+            "INSERT INTO documents(key, data, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+            params![key(path)?, data, updated_at],
+        )
+        .map_err(std::io::Error::other)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropped_transaction_rolls_back() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("networks.json");
+
+        let mut transaction = SqliteBackend.begin(&path).unwrap();
+        transaction.write(b"{\"networks\":{}}").unwrap();
+        drop(transaction);
+        assert_eq!(SqliteBackend.read(&path).unwrap(), None);
+
+        let mut transaction = SqliteBackend.begin(&path).unwrap();
+        transaction.write(b"{\"networks\":{}}").unwrap();
+        transaction.commit().unwrap();
+        assert_eq!(
+            SqliteBackend.read(&path).unwrap().as_deref(),
+            Some(&b"{\"networks\":{}}"[..])
+        );
+        assert!(!path.exists(), "documents live in the database");
+        assert!(directory.path().join(DATABASE_FILE).exists());
+    }
+
+    #[test]
+    fn missing_row_falls_back_to_legacy_json_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("boxes.json");
+        std::fs::write(&path, b"[]").unwrap();
+
+        assert_eq!(
+            SqliteBackend.read(&path).unwrap().as_deref(),
+            Some(&b"[]"[..])
+        );
+
+        SqliteBackend.write(&path, b"[{}]").unwrap();
+        assert_eq!(
+            SqliteBackend.read(&path).unwrap().as_deref(),
+            Some(&b"[{}]"[..])
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"[]");
+    }
+}
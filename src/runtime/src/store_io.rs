@@ -5,9 +5,157 @@
 //! schema change in a binary upgrade, should degrade to an empty store the next
 //! pull/create repopulates — not a hard error that blocks CRI/CLI startup. This
 //! mirrors the CLI's `boxes.json` `parse_or_quarantine` hardening.
+//!
+//! [`JournaledDocument`] goes one step further for `networks.json` and
+//! `volumes.json`: every write leaves a journal of the committed version, so a
+//! damaged file is restored from it instead of losing the store's contents.
 
 use std::path::{Path, PathBuf};
 
+use a3s_box_core::error::{BoxError, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::storage::StorageTransaction;
+
+/// A JSON store document written through a journaled storage transaction.
+///
+/// Writers hold the backend's transaction for the whole load → mutate → save
+/// and write the journal ahead of the document, so a crash between the two
+/// still leaves one complete copy. A document that fails to parse is
+/// quarantined and replaced by the journal's version. When the journal cannot
+/// help either, the document is reset to empty and the caller receives
+/// [`BoxError::StoreCorrupted`] naming the preserved copy, once.
+pub(crate) struct JournaledDocument<'a> {
+    /// Path naming the document (also where quarantined copies go).
+    pub path: &'a Path,
+    /// Human-readable name for messages, e.g. `volumes file`.
+    pub label: &'static str,
+    /// Store-specific error for I/O failures.
+    pub error: fn(String) -> BoxError,
+}
+
+impl JournaledDocument<'_> {
+    /// Read the document; recovers it under a transaction if it is corrupt.
+    pub fn load<T>(&self) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned + Default + Clone,
+    {
+        let data = crate::storage::backend()
+            .read(self.path)
+            .map_err(|e| self.io_error("read", e))?;
+        match data.map(|data| serde_json::from_slice(&data)) {
+            None => Ok(T::default()),
+            Some(Ok(document)) => Ok(document),
+            // Re-read and repair under the writer lock: another process may
+            // be mid-recovery, and a repair must not race a writer.
+            Some(Err(_)) => self.transact(|document: &mut T| Ok::<_, BoxError>(document.clone())),
+        }
+    }
+
+    /// Replace the document and its journal in one transaction.
+    pub fn save<T: Serialize>(&self, document: &T) -> Result<()> {
+        let mut transaction = self.begin()?;
+        self.write(transaction.as_mut(), document)?;
+        transaction.commit().map_err(|e| self.io_error("commit", e))
+    }
+
+    /// Run `f` over the document inside a storage transaction and write the
+    /// result back. Nothing is written when `f` fails.
+    pub fn transact<T, R, E>(
+        &self,
+        f: impl FnOnce(&mut T) -> std::result::Result<R, E>,
+    ) -> std::result::Result<R, E>
+    where
+        T: Serialize + DeserializeOwned + Default,
+        E: From<BoxError>,
+    {
+        let mut transaction = self.begin()?;
+        let mut document = match self.read(transaction.as_mut()) {
+            Ok(document) => document,
+            Err(error @ BoxError::StoreCorrupted { .. }) => {
+                // Reset so the next operation starts clean; the corrupt bytes
+                // are already preserved next to the document.
+                self.write(transaction.as_mut(), &T::default())?;
+                transaction
+                    .commit()
+                    .map_err(|e| self.io_error("commit", e))?;
+                return Err(error.into());
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let output = f(&mut document)?;
+        self.write(transaction.as_mut(), &document)?;
+        transaction
+            .commit()
+            .map_err(|e| self.io_error("commit", e))?;
+        Ok(output)
+    }
+
+    fn begin(&self) -> Result<Box<dyn StorageTransaction>> {
+        crate::storage::backend()
+            .begin(self.path)
+            .map_err(|e| self.io_error("lock", e))
+    }
+
+    fn read<T>(&self, transaction: &mut dyn StorageTransaction) -> Result<T>
+    where
+        T: DeserializeOwned + Default,
+    {
+        let Some(data) = transaction.read().map_err(|e| self.io_error("read", e))? else {
+            return Ok(T::default());
+        };
+        let error = match serde_json::from_slice(&data) {
+            Ok(document) => return Ok(document),
+            Err(error) => error,
+        };
+
+        let preserved = quarantine_label(self.path, &data);
+        let journal = transaction
+            .read_journal()
+            .map_err(|e| self.io_error("read journal of", e))?;
+        if let Some(Ok(document)) = journal.map(|journal| serde_json::from_slice(&journal)) {
+            tracing::warn!(
+                "{} {} is corrupt ({error}); preserved a copy at {preserved} and \
+                 restored the last committed version from its journal",
+                self.label,
+                self.path.display(),
+            );
+            return Ok(document);
+        }
+        Err(BoxError::StoreCorrupted {
+            path: self.path.display().to_string(),
+            message: format!(
+                "{error}; no journaled version could be restored. Preserved a copy at \
+                 {preserved} and reset the {} to empty",
+                self.label
+            ),
+        })
+    }
+
+    fn write<T: Serialize>(
+        &self,
+        transaction: &mut dyn StorageTransaction,
+        document: &T,
+    ) -> Result<()> {
+        let data = serde_json::to_vec_pretty(document)?;
+        transaction
+            .write_journal(&data)
+            .map_err(|e| self.io_error("write journal of", e))?;
+        transaction
+            .write(&data)
+            .map_err(|e| self.io_error("write", e))
+    }
+
+    fn io_error(&self, action: &str, e: std::io::Error) -> BoxError {
+        (self.error)(format!(
+            "failed to {action} {} {}: {e}",
+            self.label,
+            self.path.display()
+        ))
+    }
+}
+
 /// Move a corrupt store file aside to a timestamped `*.corrupt-<unix-secs>`
 /// sibling so the next save cannot overwrite it (the original is preserved for
 /// recovery). Falls back to a copy if rename fails (e.g. cross-device). Returns
@@ -27,30 +175,43 @@ pub(crate) fn quarantine_corrupt(path: &Path) -> Option<PathBuf> {
     }
 }
 
-/// Render the backup path from [`quarantine_corrupt`] for a log message.
-pub(crate) fn quarantine_label(path: &Path) -> String {
-    quarantine_corrupt(path)
+/// Preserve a corrupt document read through [`crate::storage`] and render the
+/// backup path for a log message.
+///
+/// When `data` is the file at `path` (the file backend, or a database backend
+/// still reading its legacy JSON file) the file is moved aside exactly like
+/// [`quarantine_corrupt`]. Otherwise the bytes came from elsewhere and are
+/// written to the same `*.corrupt-<unix-secs>` sibling.
+pub(crate) fn quarantine_label(path: &Path, data: &[u8]) -> String {
+    let on_disk = std::fs::read(path).is_ok_and(|file| file == data);
+    let backup = if on_disk {
+        quarantine_corrupt(path)
+    } else {
+        quarantine_copy(path, data)
+    };
+    backup
         .map(|p| p.display().to_string())
         .unwrap_or_else(|| "<backup failed>".to_string())
 }
 
-/// Copy a store file aside to a timestamped `*.corrupt-<unix-secs>` sibling
-/// WITHOUT removing the original.
+/// Copy a store document (`data`, as read for `path`) aside to a timestamped
+/// `*.corrupt-<unix-secs>` sibling WITHOUT removing the original.
 ///
 /// Unlike [`quarantine_corrupt`] (whole-file unreadable → move aside), this is
 /// for the *per-entry* skip path: the file still parses and its surviving
 /// entries stay live, but the next save rewrites it with only the survivors and
 /// would erase the un-deserializable entries (e.g. a schema mismatch after an
 /// upgrade) with no backup. Copying — not moving — preserves those entries for
-/// recovery while leaving the live catalog untouched. Returns the backup path,
-/// `None` if the copy failed.
-pub(crate) fn quarantine_copy(path: &Path) -> Option<PathBuf> {
+/// recovery while leaving the live catalog untouched. Writing the bytes rather
+/// than copying the file works for every storage backend. Returns the backup
+/// path, `None` if the write failed.
+pub(crate) fn quarantine_copy(path: &Path, data: &[u8]) -> Option<PathBuf> {
     let secs = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0);
     let backup = path.with_extension(format!("json.corrupt-{secs}"));
-    std::fs::copy(path, &backup).ok().map(|_| backup)
+    std::fs::write(&backup, data).ok().map(|_| backup)
 }
 
 #[cfg(test)]
@@ -63,7 +224,7 @@ mod tests {
         let path = dir.path().join("index.json");
         std::fs::write(&path, b"original contents").unwrap();
 
-        let backup = quarantine_copy(&path).expect("copy should succeed");
+        let backup = quarantine_copy(&path, b"original contents").expect("copy should succeed");
 
         // The live file is untouched (per-entry skip keeps its survivors).
         assert!(path.exists(), "original must remain in place");
@@ -74,6 +235,18 @@ mod tests {
         assert!(backup.to_string_lossy().contains(".corrupt-"));
     }
 
+    #[test]
+    fn quarantine_label_writes_bytes_not_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("volumes.json");
+
+        let label = quarantine_label(&path, b"corrupt row");
+
+        assert!(label.contains(".corrupt-"));
+        assert_eq!(std::fs::read(&label).unwrap(), b"corrupt row");
+        assert!(!path.exists());
+    }
+
     #[test]
     fn quarantine_corrupt_moves_original_aside() {
         let dir = tempfile::tempdir().unwrap();
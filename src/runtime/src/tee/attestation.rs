@@ -86,6 +86,33 @@ pub struct PlatformInfo {
     /// 48 bytes, hex-encoded for readability.
     pub measurement: String,
 
+    /// FAMILY_ID from the launch ID block, hex-encoded (16 bytes). Zero
+    /// when the guest was launched without an ID block.
+    #[serde(default)]
+    pub family_id: String,
+
+    /// IMAGE_ID from the launch ID block, hex-encoded (16 bytes).
+    #[serde(default)]
+    pub image_id: String,
+
+    /// SHA-384 digest of the key that signed the launch ID block,
+    /// hex-encoded (48 bytes). Zero without an ID block.
+    #[serde(default)]
+    pub id_key_digest: String,
+
+    /// SHA-384 digest of the key that signed the ID key, hex-encoded
+    /// (48 bytes). Zero unless the ID block carried an author key.
+    #[serde(default)]
+    pub author_key_digest: String,
+
+    /// HOST_DATA supplied by the hypervisor at launch, hex-encoded (32 bytes).
+    #[serde(default)]
+    pub host_data: String,
+
+    /// REPORT_DATA supplied by the guest, hex-encoded (64 bytes).
+    #[serde(default)]
+    pub report_data: String,
+
     /// Current TCB (Trusted Computing Base) version.
     pub tcb_version: TcbVersion,
 
@@ -118,9 +145,23 @@ pub fn parse_platform_info(report: &[u8]) -> Option<PlatformInfo> {
     let guest_svn = u32::from_le_bytes(report[0x04..0x08].try_into().ok()?);
     let policy = u64::from_le_bytes(report[0x08..0x10].try_into().ok()?);
 
+    // family_id and image_id follow the policy, 16 bytes each
+    let family_id = hex::encode(&report[0x10..0x20]);
+    let image_id = hex::encode(&report[0x20..0x30]);
+
+    // report_data is at offset 0x50, 64 bytes
+    let report_data = hex::encode(&report[0x50..0x90]);
+
     // measurement is at offset 0x90, 48 bytes
     let measurement = hex::encode(&report[0x90..0xC0]);
 
+    // host_data is at offset 0xC0, 32 bytes
+    let host_data = hex::encode(&report[0xC0..0xE0]);
+
+    // id_key_digest and author_key_digest follow, 48 bytes each
+    let id_key_digest = hex::encode(&report[0xE0..0x110]);
+    let author_key_digest = hex::encode(&report[0x110..0x140]);
+
     // current_tcb is at offset 0x38, 8 bytes
     let tcb = TcbVersion {
         boot_loader: report[0x38],
@@ -137,6 +178,12 @@ pub fn parse_platform_info(report: &[u8]) -> Option<PlatformInfo> {
         guest_svn,
         policy,
         measurement,
+        family_id,
+        image_id,
+        id_key_digest,
+        author_key_digest,
+        host_data,
+        report_data,
         tcb_version: tcb,
         chip_id,
     })
@@ -224,6 +271,22 @@ mod tests {
         assert!(info.measurement.starts_with("abcd"));
     }
 
+    #[test]
+    fn test_parse_platform_info_id_block_fields() {
+        let mut report = vec![0u8; SNP_REPORT_SIZE];
+        report[0x10..0x20].fill(0x11); // family_id
+        report[0x20..0x30].fill(0x22); // image_id
+        report[0xE0..0x110].fill(0x33); // id_key_digest
+        report[0x110..0x140].fill(0x44); // author_key_digest
+
+        let info = parse_platform_info(&report).unwrap();
+        assert_eq!(info.family_id, "11".repeat(16));
+        assert_eq!(info.image_id, "22".repeat(16));
+        assert_eq!(info.id_key_digest, "33".repeat(48));
+        assert_eq!(info.author_key_digest, "44".repeat(48));
+        assert_eq!(info.host_data, "00".repeat(32));
+    }
+
     #[test]
     fn test_parse_platform_info_too_short() {
         let report = vec![0u8; 100]; // Too short
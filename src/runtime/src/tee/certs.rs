@@ -2,12 +2,22 @@
 //!
 //! Fetches VCEK, ASK, and ARK certificates from the AMD Key Distribution
 //! Service (KDS) at `kds.amd.com`. Certificates are cached locally to
-//! avoid repeated network requests.
+//! avoid repeated network requests, and the cache can be exported as a
+//! [`CertBundle`] and imported on hosts without network access. Bundles can
+//! also be preloaded as a read-only directory
+//! ([`AmdKdsClient::with_bundle_dir`], or [`AMD_CERT_BUNDLE_DIR_ENV`]) that is
+//! consulted after the cache, e.g. one provisioned onto air-gapped hosts.
+//!
+//! Cached chains carry no trust of their own: every chain is still checked
+//! against the pinned AMD roots when a report is verified, so a tampered
+//! cache or bundle can only cause verification to fail.
 
 use a3s_box_core::error::{BoxError, Result};
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use super::attestation::{CertificateChain, TcbVersion};
+use super::attestation::{parse_platform_info, AttestationReport, CertificateChain, TcbVersion};
 
 /// AMD KDS base URL for SEV-SNP certificates.
 const AMD_KDS_BASE_URL: &str = "https://kds.amd.com";
@@ -21,17 +31,107 @@ const PRODUCT_MILAN: &str = "Milan";
 /// AMD product name for Genoa (4th gen EPYC).
 const PRODUCT_GENOA: &str = "Genoa";
 
+/// How long a fetched chain is used before it is fetched again.
+pub const DEFAULT_CERT_CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Environment variable naming a directory of preloaded [`CertBundle`] files.
+pub const AMD_CERT_BUNDLE_DIR_ENV: &str = "A3S_AMD_CERT_BUNDLE_DIR";
+
+/// Offline bundle format version.
+const CERT_BUNDLE_VERSION: u32 = 1;
+
+/// A cached certificate chain for one chip at one TCB version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedCertChain {
+    /// Hex-encoded chip ID the chain was issued for.
+    pub chip_id: String,
+    /// TCB version the VCEK was issued for.
+    pub tcb: TcbVersion,
+    /// Unix timestamp (seconds) when the chain was fetched from AMD KDS.
+    pub fetched_at: u64,
+    /// The VCEK, ASK, and ARK certificates.
+    pub chain: CertificateChain,
+}
+
+/// Certificate chains exported from one host's cache for verifying
+/// reports on hosts that cannot reach AMD KDS.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertBundle {
+    /// Bundle format version.
+    pub version: u32,
+    /// Unix timestamp (seconds) when the bundle was exported.
+    pub created_at: u64,
+    /// Cached chains, one per chip and TCB version.
+    pub entries: Vec<CachedCertChain>,
+}
+
+impl CertBundle {
+    /// A bundle holding `entries`, created now.
+    pub fn new(entries: Vec<CachedCertChain>) -> Self {
+        Self {
+            version: CERT_BUNDLE_VERSION,
+            created_at: unix_now(),
+            entries,
+        }
+    }
+
+    /// Read a bundle written by [`CertBundle::save`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).map_err(|e| {
+            BoxError::AttestationError(format!(
+                "Failed to read certificate bundle {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let bundle: Self = serde_json::from_slice(&data).map_err(|e| {
+            BoxError::AttestationError(format!(
+                "Invalid certificate bundle {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        if bundle.version != CERT_BUNDLE_VERSION {
+            return Err(BoxError::AttestationError(format!(
+                "Unsupported certificate bundle version {} (expected {})",
+                bundle.version, CERT_BUNDLE_VERSION
+            )));
+        }
+        Ok(bundle)
+    }
+
+    /// Write the bundle as JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec(self)?;
+        std::fs::write(path, data).map_err(|e| {
+            BoxError::AttestationError(format!(
+                "Failed to write certificate bundle {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
 /// Client for fetching certificates from AMD KDS.
 pub struct AmdKdsClient {
     /// HTTP client for KDS requests.
     http: reqwest::Client,
     /// Local cache directory for certificates.
     cache_dir: Option<PathBuf>,
+    /// Age after which a cached chain is fetched again.
+    max_age: Duration,
+    /// Never contact AMD KDS; serve only cached chains.
+    offline: bool,
+    /// Read-only directory of preloaded bundles, consulted after the cache.
+    bundle_dir: Option<PathBuf>,
 }
 
 impl AmdKdsClient {
     /// Create a new AMD KDS client.
     ///
+    /// Preloads bundles from [`AMD_CERT_BUNDLE_DIR_ENV`] when it is set.
+    ///
     /// # Arguments
     /// * `cache_dir` - Optional directory for caching certificates locally.
     ///   If `None`, certificates are fetched on every request.
@@ -42,9 +142,131 @@ impl AmdKdsClient {
                 .build()
                 .expect("failed to build AMD KDS HTTP client"),
             cache_dir,
+            max_age: DEFAULT_CERT_CACHE_MAX_AGE,
+            offline: false,
+            bundle_dir: std::env::var_os(AMD_CERT_BUNDLE_DIR_ENV)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
         }
     }
 
+    /// Default cache directory, `~/.a3s/cache/amd-kds`.
+    pub fn default_cache_dir() -> PathBuf {
+        a3s_box_core::dirs_home().join("cache").join("amd-kds")
+    }
+
+    /// Refetch cached chains older than `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Serve only cached chains, whatever their age, and never contact
+    /// AMD KDS. For air-gapped hosts that import a [`CertBundle`].
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    /// Look up chains missing from the cache in the [`CertBundle`] files
+    /// (`*.json`) in `dir`. Their entries follow the same age rule as the
+    /// cache.
+    pub fn with_bundle_dir(mut self, dir: PathBuf) -> Self {
+        self.bundle_dir = Some(dir);
+        self
+    }
+
+    /// Fill in `report`'s certificate chain when the guest could return only
+    /// the bare report (no `SNP_GET_EXT_REPORT` support on the host).
+    ///
+    /// Reports that already carry a VCEK, and simulated reports, are left
+    /// unchanged.
+    pub async fn complete_report(
+        &self,
+        report: &mut AttestationReport,
+        product: &str,
+    ) -> Result<()> {
+        if !report.cert_chain.vcek.is_empty() || super::is_simulated_report(&report.report) {
+            return Ok(());
+        }
+        let platform = parse_platform_info(&report.report).ok_or_else(|| {
+            BoxError::AttestationError(format!("Invalid SNP report: {} bytes", report.report.len()))
+        })?;
+        report.cert_chain = self
+            .fetch_cert_chain(&platform.chip_id, &platform.tcb_version, product)
+            .await?;
+        Ok(())
+    }
+
+    /// Export every cached chain as an offline bundle.
+    pub async fn export_bundle(&self) -> Result<CertBundle> {
+        let mut entries = Vec::new();
+        if let Some(cache_dir) = &self.cache_dir {
+            let read_error = |e: std::io::Error| {
+                BoxError::AttestationError(format!(
+                    "Failed to read cert cache {}: {}",
+                    cache_dir.display(),
+                    e
+                ))
+            };
+            let mut dir = match tokio::fs::read_dir(cache_dir).await {
+                Ok(dir) => Some(dir),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(read_error(e)),
+            };
+            while let Some(dir) = dir.as_mut() {
+                let Some(entry) = dir.next_entry().await.map_err(read_error)? else {
+                    break;
+                };
+                let Ok(data) = tokio::fs::read(entry.path()).await else {
+                    continue;
+                };
+                // Skips foreign files and entries from older cache formats.
+                if let Ok(cached) = serde_json::from_slice::<CachedCertChain>(&data) {
+                    entries.push(cached);
+                }
+            }
+        }
+        entries.sort_by(|a, b| {
+            a.chip_id
+                .cmp(&b.chip_id)
+                .then(a.fetched_at.cmp(&b.fetched_at))
+        });
+        Ok(CertBundle::new(entries))
+    }
+
+    /// Add a bundle's chains to the cache, keeping their fetch times.
+    /// Returns how many chains were imported.
+    pub async fn import_bundle(&self, bundle: &CertBundle) -> Result<usize> {
+        let cache_dir = self.cache_dir.as_ref().ok_or_else(|| {
+            BoxError::AttestationError("No certificate cache directory configured".to_string())
+        })?;
+        let write_error = |e: std::io::Error| {
+            BoxError::AttestationError(format!(
+                "Failed to write cert cache {}: {}",
+                cache_dir.display(),
+                e
+            ))
+        };
+        tokio::fs::create_dir_all(cache_dir)
+            .await
+            .map_err(write_error)?;
+        for entry in &bundle.entries {
+            let chain = &entry.chain;
+            if chain.vcek.is_empty() || chain.ask.is_empty() || chain.ark.is_empty() {
+                return Err(BoxError::AttestationError(format!(
+                    "Bundle entry for chip {} is missing certificates",
+                    Self::short_chip_id(&entry.chip_id)
+                )));
+            }
+            let path = cache_dir.join(Self::cache_key(&entry.chip_id, &entry.tcb));
+            tokio::fs::write(&path, serde_json::to_vec(entry)?)
+                .await
+                .map_err(write_error)?;
+        }
+        Ok(bundle.entries.len())
+    }
+
     /// Fetch the complete certificate chain for verifying an SNP report.
     ///
     /// Tries the local cache first, then falls back to AMD KDS.
@@ -59,6 +281,19 @@ impl AmdKdsClient {
         tcb: &TcbVersion,
         product: &str,
     ) -> Result<CertificateChain> {
+        self.fetch_cached_chain(chip_id, tcb, product)
+            .await
+            .map(|cached| cached.chain)
+    }
+
+    /// Same as [`AmdKdsClient::fetch_cert_chain`], but returns the chain with
+    /// its chip, TCB version, and fetch time, as written to a [`CertBundle`].
+    pub async fn fetch_cached_chain(
+        &self,
+        chip_id: &str,
+        tcb: &TcbVersion,
+        product: &str,
+    ) -> Result<CachedCertChain> {
         // Try cache first
         if let Some(cached) = self.load_from_cache(chip_id, tcb).await {
             tracing::debug!(
@@ -67,6 +302,25 @@ impl AmdKdsClient {
             );
             return Ok(cached);
         }
+        if let Some(cached) = self.load_from_bundles(chip_id, tcb).await {
+            tracing::debug!(
+                chip_id = Self::short_chip_id(chip_id),
+                "Using preloaded certificate chain"
+            );
+            return Ok(cached);
+        }
+        if self.offline {
+            return Err(BoxError::AttestationError(format!(
+                "No cached certificate chain for chip {} at TCB bl{}/tee{}/snp{}/uc{}; \
+                 import one with `a3s-box tee import-certs` or preload a bundle \
+                 directory with {AMD_CERT_BUNDLE_DIR_ENV}",
+                Self::short_chip_id(chip_id),
+                tcb.boot_loader,
+                tcb.tee,
+                tcb.snp,
+                tcb.microcode,
+            )));
+        }
 
         // Fetch VCEK certificate
         let vcek = self.fetch_vcek(chip_id, tcb, product).await?;
@@ -77,9 +331,7 @@ impl AmdKdsClient {
         let chain = CertificateChain { vcek, ask, ark };
 
         // Cache for future use
-        self.save_to_cache(chip_id, tcb, &chain).await;
-
-        Ok(chain)
+        Ok(self.save_to_cache(chip_id, tcb, &chain).await)
     }
 
     /// Fetch the VCEK certificate from AMD KDS.
@@ -198,31 +450,90 @@ impl AmdKdsClient {
         })
     }
 
-    /// Try to load a cached certificate chain.
-    async fn load_from_cache(&self, chip_id: &str, tcb: &TcbVersion) -> Option<CertificateChain> {
+    /// Try to load a cached certificate chain that is still fresh.
+    async fn load_from_cache(&self, chip_id: &str, tcb: &TcbVersion) -> Option<CachedCertChain> {
         let cache_dir = self.cache_dir.as_ref()?;
         let cache_key = Self::cache_key(chip_id, tcb);
         let cache_path = cache_dir.join(&cache_key);
 
         let data = tokio::fs::read(&cache_path).await.ok()?;
-        serde_json::from_slice(&data).ok()
+        let cached: CachedCertChain = serde_json::from_slice(&data).ok()?;
+        if !self.is_fresh(cached.fetched_at) {
+            tracing::debug!(
+                chip_id = Self::short_chip_id(chip_id),
+                age_secs = unix_now().saturating_sub(cached.fetched_at),
+                "Cached certificate chain expired"
+            );
+            return None;
+        }
+        Some(cached)
     }
 
-    /// Save a certificate chain to the local cache.
-    async fn save_to_cache(&self, chip_id: &str, tcb: &TcbVersion, chain: &CertificateChain) {
+    /// Find the newest fresh chain for the chip and TCB version in the
+    /// preloaded bundle directory, skipping files that are not bundles.
+    async fn load_from_bundles(&self, chip_id: &str, tcb: &TcbVersion) -> Option<CachedCertChain> {
+        let bundle_dir = self.bundle_dir.as_ref()?;
+        let cache_key = Self::cache_key(chip_id, tcb);
+        let mut dir = tokio::fs::read_dir(bundle_dir).await.ok()?;
+        let mut newest: Option<CachedCertChain> = None;
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let bundle = match CertBundle::load(&path) {
+                Ok(bundle) => bundle,
+                Err(e) => {
+                    tracing::debug!(error = %e, "Skipping certificate bundle");
+                    continue;
+                }
+            };
+            for cached in bundle.entries {
+                if cached.chip_id.eq_ignore_ascii_case(chip_id)
+                    && Self::cache_key(&cached.chip_id, &cached.tcb) == cache_key
+                    && newest
+                        .as_ref()
+                        .is_none_or(|newest| cached.fetched_at > newest.fetched_at)
+                {
+                    newest = Some(cached);
+                }
+            }
+        }
+        newest.filter(|cached| self.is_fresh(cached.fetched_at))
+    }
+
+    /// Whether a chain fetched at `fetched_at` may be served without
+    /// refetching it.
+    fn is_fresh(&self, fetched_at: u64) -> bool {
+        self.offline || unix_now().saturating_sub(fetched_at) <= self.max_age.as_secs()
+    }
+
+    /// Save a certificate chain to the local cache and return the entry.
+    async fn save_to_cache(
+        &self,
+        chip_id: &str,
+        tcb: &TcbVersion,
+        chain: &CertificateChain,
+    ) -> CachedCertChain {
+        let cached = CachedCertChain {
+            chip_id: chip_id.to_string(),
+            tcb: tcb.clone(),
+            fetched_at: unix_now(),
+            chain: chain.clone(),
+        };
         let Some(cache_dir) = &self.cache_dir else {
-            return;
+            return cached;
         };
 
         if let Err(e) = tokio::fs::create_dir_all(cache_dir).await {
             tracing::warn!("Failed to create cert cache dir: {}", e);
-            return;
+            return cached;
         }
 
         let cache_key = Self::cache_key(chip_id, tcb);
         let cache_path = cache_dir.join(&cache_key);
 
-        match serde_json::to_vec(chain) {
+        match serde_json::to_vec(&cached) {
             Ok(data) => {
                 if let Err(e) = tokio::fs::write(&cache_path, &data).await {
                     tracing::warn!("Failed to cache certificate chain: {}", e);
@@ -232,6 +543,7 @@ impl AmdKdsClient {
                 tracing::warn!("Failed to serialize certificate chain for cache: {}", e);
             }
         }
+        cached
     }
 
     /// Generate a cache key from chip ID and TCB version.
@@ -263,6 +575,13 @@ impl AmdKdsClient {
     }
 }
 
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 /// Decode a base64 string (standard alphabet, tolerates whitespace and missing padding).
 fn base64_decode(input: &str) -> std::result::Result<Vec<u8>, String> {
     use base64::{engine::general_purpose, Engine};
@@ -428,7 +747,7 @@ mod tests {
         client.save_to_cache("abcdef1234567890", &tcb, &chain).await;
         let loaded = client.load_from_cache("abcdef1234567890", &tcb).await;
 
-        assert_chain_eq(&loaded.unwrap(), &chain);
+        assert_chain_eq(&loaded.unwrap().chain, &chain);
     }
 
     #[tokio::test]
@@ -464,6 +783,142 @@ mod tests {
         assert!(client.load_from_cache("abc", &tcb).await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_expired_cache_entry_is_served_only_offline() {
+        let temp = tempfile::tempdir().unwrap();
+        let tcb = sample_tcb();
+        let client = AmdKdsClient::new(Some(temp.path().to_path_buf()));
+        client.save_to_cache("abc", &tcb, &sample_chain()).await;
+
+        let expiring =
+            AmdKdsClient::new(Some(temp.path().to_path_buf())).with_max_age(Duration::ZERO);
+        let path = temp.path().join(AmdKdsClient::cache_key("abc", &tcb));
+        let mut cached: CachedCertChain =
+            serde_json::from_slice(&tokio::fs::read(&path).await.unwrap()).unwrap();
+        cached.fetched_at -= 60;
+        tokio::fs::write(&path, serde_json::to_vec(&cached).unwrap())
+            .await
+            .unwrap();
+        assert!(expiring.load_from_cache("abc", &tcb).await.is_none());
+
+        let offline = expiring.offline();
+        assert!(offline.load_from_cache("abc", &tcb).await.is_some());
+        let err = offline
+            .fetch_cert_chain("def", &tcb, PRODUCT_MILAN)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("import-certs"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_bundle_export_import_roundtrip() {
+        let online = tempfile::tempdir().unwrap();
+        let client = AmdKdsClient::new(Some(online.path().to_path_buf()));
+        client
+            .save_to_cache("abcdef1234567890ff", &sample_tcb(), &sample_chain())
+            .await;
+        tokio::fs::write(online.path().join("unrelated.txt"), b"x")
+            .await
+            .unwrap();
+
+        let bundle_path = online.path().join("bundle.json");
+        client
+            .export_bundle()
+            .await
+            .unwrap()
+            .save(&bundle_path)
+            .unwrap();
+        let bundle = CertBundle::load(&bundle_path).unwrap();
+        assert_eq!(bundle.entries.len(), 1);
+        assert_eq!(bundle.entries[0].chip_id, "abcdef1234567890ff");
+
+        let air_gapped = tempfile::tempdir().unwrap();
+        let offline = AmdKdsClient::new(Some(air_gapped.path().to_path_buf())).offline();
+        assert_eq!(offline.import_bundle(&bundle).await.unwrap(), 1);
+        let chain = offline
+            .fetch_cert_chain("abcdef1234567890ff", &sample_tcb(), PRODUCT_MILAN)
+            .await
+            .unwrap();
+        assert_chain_eq(&chain, &sample_chain());
+    }
+
+    #[tokio::test]
+    async fn test_preloaded_bundle_dir_serves_missing_chains() {
+        let bundles = tempfile::tempdir().unwrap();
+        let fetched_at = unix_now() - 60;
+        let entry = |fetched_at| CachedCertChain {
+            chip_id: "abcdef1234567890ff".to_string(),
+            tcb: sample_tcb(),
+            fetched_at,
+            chain: sample_chain(),
+        };
+        CertBundle::new(vec![entry(1), entry(fetched_at)])
+            .save(&bundles.path().join("milan.json"))
+            .unwrap();
+        std::fs::write(bundles.path().join("notes.json"), b"not a bundle").unwrap();
+
+        let cache = tempfile::tempdir().unwrap();
+        let offline = AmdKdsClient::new(Some(cache.path().to_path_buf()))
+            .with_bundle_dir(bundles.path().to_path_buf())
+            .offline();
+        let cached = offline
+            .fetch_cached_chain("abcdef1234567890ff", &sample_tcb(), PRODUCT_MILAN)
+            .await
+            .unwrap();
+        assert_eq!(cached.fetched_at, fetched_at);
+        assert_chain_eq(&cached.chain, &sample_chain());
+        assert!(offline
+            .fetch_cert_chain("0123456789abcdef00", &sample_tcb(), PRODUCT_MILAN)
+            .await
+            .is_err());
+
+        let expiring = AmdKdsClient::new(None)
+            .with_bundle_dir(bundles.path().to_path_buf())
+            .with_max_age(Duration::from_secs(30));
+        assert!(expiring
+            .load_from_bundles("abcdef1234567890ff", &sample_tcb())
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_bundle_rejects_incomplete_chain() {
+        let temp = tempfile::tempdir().unwrap();
+        let client = AmdKdsClient::new(Some(temp.path().to_path_buf()));
+        let bundle = CertBundle {
+            version: CERT_BUNDLE_VERSION,
+            created_at: 0,
+            entries: vec![CachedCertChain {
+                chip_id: "abc".to_string(),
+                tcb: sample_tcb(),
+                fetched_at: 0,
+                chain: CertificateChain::default(),
+            }],
+        };
+        assert!(client.import_bundle(&bundle).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_complete_report_fills_missing_chain_from_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let client = AmdKdsClient::new(Some(temp.path().to_path_buf())).offline();
+        let mut report = AttestationReport {
+            report: vec![0u8; super::super::attestation::SNP_REPORT_SIZE],
+            cert_chain: CertificateChain::default(),
+            platform: Default::default(),
+        };
+        let platform = parse_platform_info(&report.report).unwrap();
+        client
+            .save_to_cache(&platform.chip_id, &platform.tcb_version, &sample_chain())
+            .await;
+
+        client
+            .complete_report(&mut report, PRODUCT_MILAN)
+            .await
+            .unwrap();
+        assert_chain_eq(&report.cert_chain, &sample_chain());
+    }
+
     #[tokio::test]
     async fn test_save_to_cache_noops_without_cache_dir() {
         let client = AmdKdsClient::new(None);
@@ -0,0 +1,240 @@
+//! Runtime claims: what a TEE box runs, bound into its attestation reports.
+//!
+//! Hardware attestation alone proves the guest runs on a genuine SEV-SNP
+//! platform with a given launch measurement — the kernel and guest init, not
+//! the workload. Runtime claims cover the rest: the image digest and a hash
+//! of the launch configuration (process, environment, mounts, sizing).
+//!
+//! The bundled libkrun launches SNP guests with an all-zero HOST_DATA, so the
+//! claims digest travels in REPORT_DATA instead: the runtime hands it to guest
+//! init at launch ([`RUNTIME_CLAIMS_ENV`]), and guest init places it after the
+//! RA-TLS key hash. Verifiers compare it with the digest of the claims they
+//! expect (`AttestationPolicy::expected_runtime_claims`).
+//!
+//! A box with a measured rootfs ([`super::verity`]) also claims the
+//! dm-verity root hash. Guest init folds in the root hash it opened itself,
+//! so the runtime hands it [`RuntimeClaims::launch_digest`], the digest
+//! without the root hash.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use a3s_box_core::error::{BoxError, Result};
+pub use a3s_box_core::tee::RUNTIME_CLAIMS_ENV;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// File in a box directory recording the claims the box was launched with.
+pub const BOX_RUNTIME_CLAIMS_FILE: &str = "runtime-claims.json";
+
+/// Domain separator for the claims digest.
+const CLAIMS_DIGEST_DOMAIN: &[u8] = b"a3s-box/runtime-claims/v1";
+
+/// The launch configuration covered by [`RuntimeClaims::config_hash`].
+///
+/// Host-specific details (host paths, socket locations, box IDs) are left
+/// out, so the same image and configuration give the same hash on any host.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClaimedConfig {
+    /// Main process executable.
+    pub executable: String,
+    /// Main process arguments.
+    pub args: Vec<String>,
+    /// Main process environment.
+    pub env: BTreeMap<String, String>,
+    /// Working directory.
+    pub workdir: String,
+    /// User the main process runs as.
+    pub user: Option<String>,
+    /// Number of vCPUs.
+    pub vcpus: u32,
+    /// Guest memory in MiB.
+    pub memory_mb: u32,
+    /// Guest mount points, `PATH` or `PATH:ro`.
+    pub mounts: Vec<String>,
+    /// TEE workload identifier.
+    pub workload_id: String,
+}
+
+impl ClaimedConfig {
+    /// SHA-256 over the configuration, hex-encoded.
+    pub fn hash(&self) -> String {
+        // Struct fields serialize in declaration order and the environment is
+        // a BTreeMap, so the encoding is canonical.
+        let encoded = serde_json::to_vec(self).unwrap_or_default();
+        hex::encode(Sha256::digest(encoded))
+    }
+}
+
+/// Runtime facts a TEE box was launched with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeClaims {
+    /// Manifest digest of the image the rootfs was built from.
+    pub image_digest: String,
+    /// [`ClaimedConfig::hash`] of the launch configuration.
+    pub config_hash: String,
+    /// dm-verity root hash of the measured rootfs, if the box has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rootfs_root_hash: Option<String>,
+}
+
+impl RuntimeClaims {
+    /// Claims for `image_digest` launched with `config`.
+    pub fn new(image_digest: impl Into<String>, config: &ClaimedConfig) -> Self {
+        Self {
+            image_digest: image_digest.into(),
+            config_hash: config.hash(),
+            rootfs_root_hash: None,
+        }
+    }
+
+    /// Claim the measured rootfs with this dm-verity root hash.
+    pub fn with_rootfs_root_hash(mut self, root_hash: impl Into<String>) -> Self {
+        self.rootfs_root_hash = Some(root_hash.into());
+        self
+    }
+
+    /// The 32-byte digest bound into REPORT_DATA.
+    pub fn digest(&self) -> [u8; 32] {
+        let launch = self.launch_digest();
+        match &self.rootfs_root_hash {
+            Some(root_hash) => a3s_box_core::tee::bind_verity_root_hash(&launch, root_hash),
+            None => launch,
+        }
+    }
+
+    /// The digest handed to guest init: everything but the rootfs root hash.
+    pub fn launch_digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(CLAIMS_DIGEST_DOMAIN);
+        hasher.update([0]);
+        hasher.update(self.image_digest.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.config_hash.as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// [`RuntimeClaims::digest`], hex-encoded.
+    pub fn digest_hex(&self) -> String {
+        hex::encode(self.digest())
+    }
+
+    /// [`RuntimeClaims::launch_digest`], hex-encoded.
+    pub fn launch_digest_hex(&self) -> String {
+        hex::encode(self.launch_digest())
+    }
+
+    /// Load the claims recorded for a box, if it was launched with any.
+    pub fn load_for_box(box_dir: &Path) -> Result<Option<Self>> {
+        let path = box_dir.join(BOX_RUNTIME_CLAIMS_FILE);
+        match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).map(Some).map_err(|e| {
+                BoxError::AttestationError(format!(
+                    "Invalid runtime claims {}: {}",
+                    path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(BoxError::AttestationError(format!(
+                "Failed to read runtime claims {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    /// Record these claims in a box directory.
+    pub fn save_for_box(&self, box_dir: &Path) -> Result<()> {
+        let path = box_dir.join(BOX_RUNTIME_CLAIMS_FILE);
+        let data = serde_json::to_vec_pretty(self)?;
+        a3s_box_core::fs_atomic::write_durable(&path.with_extension("json.tmp"), &path, &data)
+            .map_err(|e| {
+                BoxError::AttestationError(format!(
+                    "Failed to write runtime claims {}: {}",
+                    path.display(),
+                    e
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a3s_box_core::tee::RUNTIME_CLAIMS_REPORT_DATA_RANGE;
+
+    fn sample_config() -> ClaimedConfig {
+        ClaimedConfig {
+            executable: "/usr/bin/server".to_string(),
+            args: vec!["--port".to_string(), "8080".to_string()],
+            env: BTreeMap::from([("MODE".to_string(), "prod".to_string())]),
+            workdir: "/app".to_string(),
+            user: Some("1000".to_string()),
+            vcpus: 2,
+            memory_mb: 1024,
+            mounts: vec!["/data:ro".to_string()],
+            workload_id: "server".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_claims_digest_covers_image_and_config() {
+        let config = sample_config();
+        let claims = RuntimeClaims::new("sha256:abc", &config);
+        assert_eq!(
+            claims.digest(),
+            RuntimeClaims::new("sha256:abc", &config).digest()
+        );
+        assert_eq!(claims.digest_hex().len(), 64);
+
+        assert_ne!(
+            claims.digest(),
+            RuntimeClaims::new("sha256:def", &config).digest()
+        );
+        let mut changed = config.clone();
+        changed.env.insert("DEBUG".to_string(), "1".to_string());
+        assert_ne!(
+            claims.digest(),
+            RuntimeClaims::new("sha256:abc", &changed).digest()
+        );
+    }
+
+    #[test]
+    fn test_rootfs_root_hash_is_bound_like_guest_init_binds_it() {
+        let claims = RuntimeClaims::new("sha256:abc", &sample_config());
+        assert_eq!(claims.digest(), claims.launch_digest());
+
+        let root_hash = "ab".repeat(32);
+        let measured = claims.clone().with_rootfs_root_hash(root_hash.clone());
+        assert_eq!(measured.launch_digest(), claims.launch_digest());
+        assert_eq!(
+            measured.digest(),
+            a3s_box_core::tee::bind_verity_root_hash(&claims.launch_digest(), &root_hash)
+        );
+        assert_ne!(measured.digest(), claims.digest());
+    }
+
+    #[test]
+    fn test_claims_digest_lands_in_platform_report_data() {
+        let claims = RuntimeClaims::new("sha256:abc", &sample_config());
+        let mut report = vec![0u8; super::super::attestation::SNP_REPORT_SIZE];
+        let range = RUNTIME_CLAIMS_REPORT_DATA_RANGE;
+        report[0x50 + range.start..0x50 + range.end].copy_from_slice(&claims.digest());
+        let platform = super::super::parse_platform_info(&report).unwrap();
+        assert_eq!(&platform.report_data[64..], claims.digest_hex());
+    }
+
+    #[test]
+    fn test_box_claims_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(RuntimeClaims::load_for_box(dir.path()).unwrap().is_none());
+
+        let claims = RuntimeClaims::new("sha256:abc", &sample_config());
+        claims.save_for_box(dir.path()).unwrap();
+        assert_eq!(
+            RuntimeClaims::load_for_box(dir.path()).unwrap(),
+            Some(claims)
+        );
+    }
+}
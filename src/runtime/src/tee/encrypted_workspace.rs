@@ -0,0 +1,240 @@
+//! Encrypted workspace for TEE boxes: a LUKS2 scratch disk mounted at
+//! `/workspace` in place of the virtio-fs workspace share.
+//!
+//! Each boot attaches a fresh, empty image ([`create_encrypted_workspace_disk`])
+//! and the box boots IDLE. Once the host has verified the guest's
+//! attestation, [`format_encrypted_workspace`] formats the image as LUKS2
+//! with a random volume key, the key is sent to the guest over RA-TLS, and
+//! the host drops it. Guest init maps the disk with dm-crypt and mounts it,
+//! so everything the workload writes to `/workspace` is ciphertext on the
+//! host, and the disk is unreadable once the box stops and the key is gone.
+//! The workspace does not survive a restart; the next boot starts from a new
+//! empty disk.
+//!
+//! Formatting opens the volume on the host once to create an empty ext4
+//! filesystem on it, before the guest has written anything. It needs
+//! `cryptsetup` and `mkfs.ext4` on the host and root, as SEV-SNP hosts run.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::tee::{
+    ENCRYPTED_WORKSPACE_BLOCK_ID, ENCRYPTED_WORKSPACE_CIPHER, ENCRYPTED_WORKSPACE_DATA_OFFSET,
+    ENCRYPTED_WORKSPACE_KEY_LEN,
+};
+use a3s_box_core::volume::BlockVolume;
+
+/// Encrypted workspace image inside the box directory.
+pub const ENCRYPTED_WORKSPACE_IMAGE: &str = "workspace-crypt.img";
+
+/// Smallest encrypted workspace accepted, header included.
+pub const MIN_ENCRYPTED_WORKSPACE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Volume key of an encrypted workspace. Zeroed when dropped.
+pub struct WorkspaceKey(Vec<u8>);
+
+impl WorkspaceKey {
+    /// The raw volume key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for WorkspaceKey {
+    fn drop(&mut self) {
+        self.0.fill(0);
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+impl std::fmt::Debug for WorkspaceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WorkspaceKey(..)")
+    }
+}
+
+/// Create a fresh, sparse encrypted workspace image of `size` bytes in
+/// `box_dir`, replacing the previous boot's, and return it as a virtio-blk
+/// disk.
+pub fn create_encrypted_workspace_disk(box_dir: &Path, size: u64) -> Result<BlockVolume> {
+    if size < MIN_ENCRYPTED_WORKSPACE_BYTES {
+        return Err(BoxError::ConfigError(format!(
+            "Encrypted workspace must be at least {} MiB",
+            MIN_ENCRYPTED_WORKSPACE_BYTES / (1024 * 1024)
+        )));
+    }
+    let image = box_dir.join(ENCRYPTED_WORKSPACE_IMAGE);
+    // Truncating discards the last boot's ciphertext along with its header.
+    std::fs::File::create(&image)
+        .and_then(|file| file.set_len(size.div_ceil(1024 * 1024) * 1024 * 1024))
+        .map_err(|e| {
+            BoxError::BuildError(format!(
+                "Failed to create encrypted workspace {}: {e}",
+                image.display()
+            ))
+        })?;
+    Ok(BlockVolume {
+        block_id: ENCRYPTED_WORKSPACE_BLOCK_ID.to_string(),
+        source: image.display().to_string(),
+        target: "/workspace".to_string(),
+        fstype: "ext4".to_string(),
+        read_only: false,
+    })
+}
+
+/// Format `image` as a LUKS2 volume holding an empty ext4 filesystem and
+/// return its freshly generated volume key.
+///
+/// The keyslot passphrase is random and discarded: the volume can only be
+/// opened with the returned key, which the caller hands to the attested
+/// guest and drops.
+pub fn format_encrypted_workspace(image: &Path) -> Result<WorkspaceKey> {
+    let key = WorkspaceKey(random_bytes(ENCRYPTED_WORKSPACE_KEY_LEN)?);
+    let passphrase = WorkspaceKey(random_bytes(32)?);
+
+    // cryptsetup reads a given volume key only from a file; keep it in
+    // memory-backed storage and unlink it as soon as the header is written.
+    let key_dir = Path::new("/dev/shm");
+    let mut key_file = if key_dir.is_dir() {
+        tempfile::Builder::new()
+            .prefix("a3s-workspace-key")
+            .tempfile_in(key_dir)
+    } else {
+        tempfile::Builder::new()
+            .prefix("a3s-workspace-key")
+            .tempfile()
+    }
+    .map_err(|e| BoxError::BuildError(format!("Failed to stage workspace key: {e}")))?;
+    key_file
+        .write_all(key.as_bytes())
+        .and_then(|()| key_file.flush())
+        .map_err(|e| BoxError::BuildError(format!("Failed to stage workspace key: {e}")))?;
+
+    let mut format = Command::new("cryptsetup");
+    format
+        .args(luks_format_args())
+        .arg("--volume-key-file")
+        .arg(key_file.path())
+        .arg(image);
+    let formatted = cryptsetup(format, passphrase.as_bytes());
+    drop(key_file);
+    formatted?;
+
+    let name = format!("a3s-ws-{}", hex::encode(random_bytes(4)?));
+    let mut open = Command::new("cryptsetup");
+    open.args(["open", "--type", "luks2", "--key-file=-"])
+        .arg(image)
+        .arg(&name);
+    cryptsetup(open, passphrase.as_bytes())?;
+
+    let mkfs = Command::new("mkfs.ext4")
+        .args(["-q", "-F"])
+        .arg(format!("/dev/mapper/{name}"))
+        .output();
+    let mut close = Command::new("cryptsetup");
+    close.args(["close", &name]);
+    let closed = cryptsetup(close, b"");
+
+    let output = mkfs.map_err(|e| {
+        BoxError::BuildError(format!(
+            "Failed to run mkfs.ext4 for the encrypted workspace (is e2fsprogs installed?): {e}"
+        ))
+    })?;
+    if !output.status.success() {
+        return Err(BoxError::BuildError(format!(
+            "mkfs.ext4 failed for the encrypted workspace: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    closed?;
+    Ok(key)
+}
+
+/// `cryptsetup luksFormat` arguments, up to the volume key file and image.
+fn luks_format_args() -> Vec<String> {
+    vec![
+        "luksFormat".to_string(),
+        "--batch-mode".to_string(),
+        "--type=luks2".to_string(),
+        format!("--cipher={ENCRYPTED_WORKSPACE_CIPHER}"),
+        format!("--key-size={}", ENCRYPTED_WORKSPACE_KEY_LEN * 8),
+        "--sector-size=512".to_string(),
+        // Guest init builds the dm-crypt table itself, so the data segment
+        // must sit where it expects it.
+        format!("--offset={}", ENCRYPTED_WORKSPACE_DATA_OFFSET / 512),
+        // The passphrase is random and thrown away; a memory-hard KDF would
+        // only slow down every boot.
+        "--pbkdf=pbkdf2".to_string(),
+        "--pbkdf-force-iterations=1000".to_string(),
+        "--key-file=-".to_string(),
+    ]
+}
+
+/// Run a cryptsetup command with `stdin` as its input.
+fn cryptsetup(mut command: Command, stdin: &[u8]) -> Result<()> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            BoxError::BuildError(format!(
+                "Failed to run cryptsetup for the encrypted workspace (is cryptsetup installed?): {e}"
+            ))
+        })?;
+    if let Some(mut input) = child.stdin.take() {
+        input
+            .write_all(stdin)
+            .map_err(|e| BoxError::BuildError(format!("Failed to write to cryptsetup: {e}")))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| BoxError::BuildError(format!("cryptsetup failed: {e}")))?;
+    if !output.status.success() {
+        return Err(BoxError::BuildError(format!(
+            "cryptsetup failed for the encrypted workspace: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut bytes)
+        .map_err(|_| BoxError::BuildError("Failed to generate workspace key".to_string()))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_disk_is_fresh_and_sparse() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join(ENCRYPTED_WORKSPACE_IMAGE);
+        std::fs::write(&image, b"previous boot").unwrap();
+
+        let disk = create_encrypted_workspace_disk(dir.path(), 100 * 1024 * 1024 + 1).unwrap();
+        assert_eq!(disk.block_id, ENCRYPTED_WORKSPACE_BLOCK_ID);
+        assert!(!disk.read_only);
+        assert_eq!(std::fs::metadata(&image).unwrap().len(), 101 * 1024 * 1024);
+        let mut head = [0xffu8; 13];
+        std::io::Read::read_exact(&mut std::fs::File::open(&image).unwrap(), &mut head).unwrap();
+        assert_eq!(head, [0u8; 13]);
+
+        assert!(create_encrypted_workspace_disk(dir.path(), 1024 * 1024).is_err());
+    }
+
+    #[test]
+    fn test_luks_format_matches_the_guest_table() {
+        let args = luks_format_args();
+        assert!(args.contains(&"--offset=32768".to_string()));
+        assert!(args.contains(&"--key-size=512".to_string()));
+        assert!(args.contains(&"--cipher=aes-xts-plain64".to_string()));
+        assert!(args.contains(&"--sector-size=512".to_string()));
+    }
+}
@@ -1,25 +1,41 @@
 //! KBS (Key Broker Service) client for TEE secret provisioning.
 //!
-//! Implements the IETF RATS (Remote ATtestation procedureS) challenge-response
-//! protocol for fetching secrets from a Key Broker Service:
+//! Implements the confidential-containers KBS protocol (RCAR: Request,
+//! Challenge, Attestation, Response), so boxes can fetch secrets from an
+//! existing CoCo key broker:
 //!
-//! 1. Client sends attestation evidence (SNP report) to KBS
-//! 2. KBS verifies the report against its policy
-//! 3. If verified, KBS returns the requested secret(s)
+//! 1. `POST /kbs/v0/auth` — announce the TEE type; the KBS answers with a
+//!    nonce and a `kbs-session-id` cookie
+//! 2. The guest answers the challenge ([`KbsEvidence`]): a fresh key of its
+//!    own plus an SNP report whose REPORT_DATA binds that key to the nonce
+//! 3. `POST /kbs/v0/attest` — the KBS verifies the evidence against its policy
+//! 4. `GET /kbs/v0/resource/<repository>/<type>/<tag>` — the KBS returns the
+//!    resource as a JWE encrypted to the guest's key
 //!
-//! Supports both single-key fetch and batch resource retrieval.
+//! The host only relays: evidence comes from the guest and the JWE goes back
+//! to it for decryption, so the secret never exists in plaintext outside the
+//! TEE. The guest key is P-256 and resources must be encrypted with
+//! `ECDH-ES` / `A256GCM`.
 
-use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
 
 use a3s_box_core::error::{BoxError, Result};
+pub use a3s_box_core::tee::KbsEvidence;
 use serde::{Deserialize, Serialize};
 
+/// KBS protocol version announced in the auth request.
+pub const KBS_PROTOCOL_VERSION: &str = "0.1.0";
+
+/// Cookie carrying the KBS session between RCAR requests.
+const KBS_SESSION_COOKIE: &str = "kbs-session-id";
+
 /// KBS endpoint configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KbsConfig {
     /// KBS server URL (e.g., "https://kbs.example.com")
     pub url: String,
-    /// Optional API key for authentication
+    /// Optional bearer token for KBS deployments behind an API gateway
     #[serde(default)]
     pub api_key: Option<String>,
     /// Request timeout in seconds (default: 30)
@@ -45,51 +61,27 @@ impl Default for KbsConfig {
     }
 }
 
-/// A request to the KBS for secret provisioning.
+/// Body of `POST /kbs/v0/auth`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct KbsRequest {
-    /// Resource path (e.g., "default/keys/my-secret")
-    pub resource_path: String,
-    /// TEE evidence: base64-encoded SNP attestation report
-    pub evidence: String,
-    /// TEE type identifier
-    #[serde(default = "default_tee_type")]
-    pub tee_type: String,
-    /// Additional claims to include in the request
-    #[serde(default)]
-    pub extra_claims: HashMap<String, String>,
-}
-
-fn default_tee_type() -> String {
-    "snp".to_string()
+pub struct KbsAuthRequest {
+    /// Protocol version ([`KBS_PROTOCOL_VERSION`])
+    pub version: String,
+    /// TEE type, `snp` for SEV-SNP
+    pub tee: String,
+    #[serde(rename = "extra-params", default)]
+    pub extra_params: String,
 }
 
-/// Response from the KBS.
+/// KBS answer to the auth request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct KbsResponse {
-    /// Whether the attestation was verified successfully
-    pub verified: bool,
-    /// The secret payload (base64-encoded), if verification passed
-    pub payload: Option<String>,
-    /// Error message if verification failed
-    pub error: Option<String>,
-    /// Token for subsequent requests (session-based KBS)
-    #[serde(default)]
-    pub token: Option<String>,
-}
-
-/// Result of a KBS secret fetch operation.
-#[derive(Debug, Clone)]
-pub struct KbsSecret {
-    /// Resource path that was fetched
-    pub resource_path: String,
-    /// Decoded secret bytes
-    pub secret: Vec<u8>,
-    /// Optional token for session continuity
-    pub token: Option<String>,
+pub struct KbsChallenge {
+    /// Nonce the evidence must bind
+    pub nonce: String,
+    #[serde(rename = "extra-params", default)]
+    pub extra_params: String,
 }
 
-/// KBS client for fetching secrets from a Key Broker Service.
+/// KBS client driving the RCAR exchange for one resource at a time.
 pub struct KbsClient {
     config: KbsConfig,
 }
@@ -100,60 +92,130 @@ impl KbsClient {
         Self { config }
     }
 
-    /// Build the attestation request payload.
-    pub fn build_request(&self, resource_path: &str, evidence: &[u8]) -> KbsRequest {
-        use base64::Engine;
-        KbsRequest {
-            resource_path: resource_path.to_string(),
-            evidence: base64::engine::general_purpose::STANDARD.encode(evidence),
-            tee_type: "snp".to_string(),
-            extra_claims: HashMap::new(),
+    /// Fetch one resource. `evidence` is called with the challenge nonce and
+    /// must return the guest's answer; the result is the resource's JWE, for
+    /// the guest to decrypt.
+    pub async fn fetch_resource<F, Fut>(
+        &self,
+        resource_path: &str,
+        evidence: F,
+    ) -> Result<serde_json::Value>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<KbsEvidence>>,
+    {
+        parse_resource_path(resource_path)?;
+        let http = self.http()?;
+
+        let mut auth = http.post(self.auth_url()).json(&KbsAuthRequest {
+            version: KBS_PROTOCOL_VERSION.to_string(),
+            tee: "snp".to_string(),
+            extra_params: String::new(),
+        });
+        if let Some(api_key) = &self.config.api_key {
+            auth = auth.bearer_auth(api_key);
         }
-    }
-
-    /// Parse a KBS response and extract the secret.
-    pub fn parse_response(&self, resource_path: &str, response: &KbsResponse) -> Result<KbsSecret> {
-        if !response.verified {
-            return Err(BoxError::AttestationError(format!(
-                "KBS attestation verification failed: {}",
-                response.error.as_deref().unwrap_or("unknown error")
-            )));
+        let response = send("auth", auth).await?;
+        let session = session_cookie(response.headers());
+        let challenge: KbsChallenge = response
+            .json()
+            .await
+            .map_err(|e| BoxError::AttestationError(format!("Invalid KBS challenge: {}", e)))?;
+
+        let evidence = evidence(challenge.nonce).await?;
+        let attest = with_session(http.post(self.attest_url()).json(&evidence), &session);
+        let response = send("attestation", attest).await?;
+        let token = response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|body| body.get("token")?.as_str().map(str::to_string));
+
+        let mut resource = with_session(http.get(self.resource_url(resource_path)), &session);
+        if let Some(token) = token {
+            resource = resource.bearer_auth(token);
         }
-
-        let payload = response.payload.as_ref().ok_or_else(|| {
-            BoxError::AttestationError("KBS response verified but no payload returned".to_string())
-        })?;
-
-        use base64::Engine;
-        let secret = base64::engine::general_purpose::STANDARD
-            .decode(payload)
-            .map_err(|e| {
-                BoxError::AttestationError(format!("Failed to decode KBS payload: {}", e))
-            })?;
-
-        Ok(KbsSecret {
-            resource_path: resource_path.to_string(),
-            secret,
-            token: response.token.clone(),
+        let response = send("resource", resource).await?;
+        response.json().await.map_err(|e| {
+            BoxError::AttestationError(format!("Invalid KBS response for {}: {}", resource_path, e))
         })
     }
 
     /// Get the KBS endpoint URL for a resource path.
     pub fn resource_url(&self, resource_path: &str) -> String {
-        let base = self.config.url.trim_end_matches('/');
-        format!("{}/kbs/v0/resource/{}", base, resource_path)
+        format!("{}/kbs/v0/resource/{}", self.base_url(), resource_path)
     }
 
     /// Get the KBS attestation endpoint URL.
     pub fn attest_url(&self) -> String {
-        let base = self.config.url.trim_end_matches('/');
-        format!("{}/kbs/v0/attest", base)
+        format!("{}/kbs/v0/attest", self.base_url())
+    }
+
+    /// Get the KBS auth (challenge) endpoint URL.
+    pub fn auth_url(&self) -> String {
+        format!("{}/kbs/v0/auth", self.base_url())
     }
 
     /// Get the configuration.
     pub fn config(&self) -> &KbsConfig {
         &self.config
     }
+
+    fn base_url(&self) -> &str {
+        self.config.url.trim_end_matches('/')
+    }
+
+    fn http(&self) -> Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.config.timeout_secs))
+            .danger_accept_invalid_certs(self.config.insecure_tls)
+            .build()
+            .map_err(|e| BoxError::AttestationError(format!("Failed to build KBS client: {}", e)))
+    }
+}
+
+/// Send one RCAR request, turning HTTP errors into attestation errors.
+async fn send(step: &str, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| BoxError::AttestationError(format!("KBS {} request failed: {}", step, e)))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(BoxError::AttestationError(format!(
+            "KBS rejected {} ({}): {}",
+            step,
+            status,
+            body.trim()
+        )));
+    }
+    Ok(response)
+}
+
+/// The `kbs-session-id` cookie set by the auth response, as a `Cookie` value.
+fn session_cookie(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get_all(reqwest::header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.split(';').next())
+        .map(str::trim)
+        .find(|pair| {
+            pair.split_once('=')
+                .is_some_and(|(name, _)| name == KBS_SESSION_COOKIE)
+        })
+        .map(str::to_string)
+}
+
+fn with_session(
+    request: reqwest::RequestBuilder,
+    session: &Option<String>,
+) -> reqwest::RequestBuilder {
+    match session {
+        Some(cookie) => request.header(reqwest::header::COOKIE, cookie),
+        None => request,
+    }
 }
 
 /// Parse a KBS resource path into (repository, type, tag).
@@ -200,103 +262,71 @@ mod tests {
     }
 
     #[test]
-    fn test_kbs_client_build_request() {
-        let config = KbsConfig {
-            url: "https://kbs.example.com".to_string(),
-            ..Default::default()
-        };
-        let client = KbsClient::new(config);
-        let evidence = b"fake-snp-report";
-        let request = client.build_request("default/keys/my-key", evidence);
-
-        assert_eq!(request.resource_path, "default/keys/my-key");
-        assert_eq!(request.tee_type, "snp");
-        assert!(!request.evidence.is_empty());
-
-        // Verify base64 roundtrip
-        use base64::Engine;
-        let decoded = base64::engine::general_purpose::STANDARD
-            .decode(&request.evidence)
-            .unwrap();
-        assert_eq!(decoded, evidence);
-    }
-
-    #[test]
-    fn test_kbs_client_parse_response_success() {
-        let config = KbsConfig::default();
-        let client = KbsClient::new(config);
-
-        use base64::Engine;
-        let secret_data = b"my-secret-value";
-        let payload = base64::engine::general_purpose::STANDARD.encode(secret_data);
-
-        let response = KbsResponse {
-            verified: true,
-            payload: Some(payload),
-            error: None,
-            token: Some("session-token".to_string()),
-        };
-
-        let secret = client
-            .parse_response("default/keys/test", &response)
-            .unwrap();
-        assert_eq!(secret.resource_path, "default/keys/test");
-        assert_eq!(secret.secret, secret_data);
-        assert_eq!(secret.token, Some("session-token".to_string()));
+    fn test_session_cookie() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.append(
+            reqwest::header::SET_COOKIE,
+            "other=1; Path=/".parse().unwrap(),
+        );
+        headers.append(
+            reqwest::header::SET_COOKIE,
+            "kbs-session-id=abc123; Expires=Wed, 21 Oct 2026 07:28:00 GMT"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            session_cookie(&headers).as_deref(),
+            Some("kbs-session-id=abc123")
+        );
+        assert!(session_cookie(&reqwest::header::HeaderMap::new()).is_none());
     }
 
     #[test]
-    fn test_kbs_client_parse_response_verification_failed() {
-        let config = KbsConfig::default();
-        let client = KbsClient::new(config);
+    fn test_kbs_protocol_messages() {
+        let auth = serde_json::to_value(KbsAuthRequest {
+            version: KBS_PROTOCOL_VERSION.to_string(),
+            tee: "snp".to_string(),
+            extra_params: String::new(),
+        })
+        .unwrap();
+        assert_eq!(
+            auth,
+            serde_json::json!({"version": "0.1.0", "tee": "snp", "extra-params": ""})
+        );
 
-        let response = KbsResponse {
-            verified: false,
-            payload: None,
-            error: Some("measurement mismatch".to_string()),
-            token: None,
-        };
+        let challenge: KbsChallenge =
+            serde_json::from_str(r#"{"nonce": "n0nce", "extra-params": ""}"#).unwrap();
+        assert_eq!(challenge.nonce, "n0nce");
 
-        let result = client.parse_response("default/keys/test", &response);
-        assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("measurement mismatch"));
+        let evidence = serde_json::to_value(KbsEvidence {
+            tee_pubkey: serde_json::json!({"kty": "EC"}),
+            tee_evidence: "{}".to_string(),
+        })
+        .unwrap();
+        assert_eq!(evidence["tee-pubkey"]["kty"], "EC");
+        assert_eq!(evidence["tee-evidence"], "{}");
     }
 
     #[test]
-    fn test_kbs_client_parse_response_no_payload() {
-        let config = KbsConfig::default();
-        let client = KbsClient::new(config);
-
-        let response = KbsResponse {
-            verified: true,
-            payload: None,
-            error: None,
-            token: None,
-        };
-
-        let result = client.parse_response("default/keys/test", &response);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("no payload"));
+    fn test_kbs_client_auth_url() {
+        let client = KbsClient::new(KbsConfig {
+            url: "https://kbs.example.com/".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(client.auth_url(), "https://kbs.example.com/kbs/v0/auth");
     }
 
-    #[test]
-    fn test_kbs_client_parse_response_invalid_base64() {
-        let config = KbsConfig::default();
-        let client = KbsClient::new(config);
-
-        let response = KbsResponse {
-            verified: true,
-            payload: Some("not-valid-base64!!!".to_string()),
-            error: None,
-            token: None,
-        };
-
-        let result = client.parse_response("default/keys/test", &response);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("decode"));
+    #[tokio::test]
+    async fn test_fetch_resource_rejects_bad_path_before_contacting_kbs() {
+        let client = KbsClient::new(KbsConfig {
+            url: "http://127.0.0.1:9".to_string(),
+            ..Default::default()
+        });
+        let error = client
+            .fetch_resource("no-type", |_| async { unreachable!() })
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("repository/type/tag"), "{error}");
     }
 
     #[test]
@@ -357,36 +387,4 @@ mod tests {
         assert!(parse_resource_path("just-one").is_err());
         assert!(parse_resource_path("").is_err());
     }
-
-    #[test]
-    fn test_kbs_request_serde_roundtrip() {
-        let request = KbsRequest {
-            resource_path: "default/keys/test".to_string(),
-            evidence: "base64evidence".to_string(),
-            tee_type: "snp".to_string(),
-            extra_claims: HashMap::from([("nonce".to_string(), "abc123".to_string())]),
-        };
-        let json = serde_json::to_string(&request).unwrap();
-        let parsed: KbsRequest = serde_json::from_str(&json).unwrap();
-        assert_eq!(parsed.resource_path, "default/keys/test");
-        assert_eq!(parsed.evidence, "base64evidence");
-        assert_eq!(parsed.tee_type, "snp");
-        assert_eq!(parsed.extra_claims.get("nonce").unwrap(), "abc123");
-    }
-
-    #[test]
-    fn test_kbs_response_serde_roundtrip() {
-        let response = KbsResponse {
-            verified: true,
-            payload: Some("c2VjcmV0".to_string()),
-            error: None,
-            token: Some("tok-123".to_string()),
-        };
-        let json = serde_json::to_string(&response).unwrap();
-        let parsed: KbsResponse = serde_json::from_str(&json).unwrap();
-        assert!(parsed.verified);
-        assert_eq!(parsed.payload, Some("c2VjcmV0".to_string()));
-        assert!(parsed.error.is_none());
-        assert_eq!(parsed.token, Some("tok-123".to_string()));
-    }
 }
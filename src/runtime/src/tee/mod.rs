@@ -8,11 +8,15 @@
 //! - `verifier`: Host-side report verification (signature + policy).
 //! - `policy`: Verification policy definitions.
 //! - `certs`: AMD KDS certificate fetching and caching.
+//! - `claims`: Runtime claims (image digest, launch config) bound into reports.
+//! - `encrypted_workspace`: LUKS2 `/workspace` disk keyed after attestation.
 //! - `ark_roots`: Pinned genuine AMD ARK root keys (chain trust anchor).
 
 pub mod ark_roots;
 pub mod attestation;
 pub mod certs;
+pub mod claims;
+pub mod encrypted_workspace;
 pub mod extension;
 pub mod kbs;
 pub mod policy;
@@ -23,21 +27,33 @@ pub mod sealed;
 pub mod simulate;
 pub mod snp;
 pub mod verifier;
+pub mod verity;
 
 pub use attestation::{
     parse_platform_info, AttestationReport, AttestationRequest, CertificateChain, PlatformInfo,
     TcbVersion,
 };
-pub use certs::AmdKdsClient;
+pub use certs::{
+    AmdKdsClient, CachedCertChain, CertBundle, AMD_CERT_BUNDLE_DIR_ENV, DEFAULT_CERT_CACHE_MAX_AGE,
+};
+pub use claims::{ClaimedConfig, RuntimeClaims, BOX_RUNTIME_CLAIMS_FILE};
+pub use encrypted_workspace::{
+    create_encrypted_workspace_disk, format_encrypted_workspace, WorkspaceKey,
+    ENCRYPTED_WORKSPACE_IMAGE,
+};
 #[cfg(unix)]
 pub use extension::{SnpTeeExtension, TeeExtension};
-pub use kbs::{KbsClient, KbsConfig, KbsRequest, KbsResponse, KbsSecret};
-pub use policy::{AttestationPolicy, MinTcbPolicy, PolicyResult, PolicyViolation};
+pub use kbs::{KbsClient, KbsConfig, KbsEvidence, KBS_PROTOCOL_VERSION};
+pub use policy::{
+    AttestationPolicy, MinTcbPolicy, PolicyResult, PolicyViolation, BOX_ATTESTATION_POLICY_FILE,
+};
 pub use reattest::{FailureAction, ReattestConfig, ReattestState, ReattestSummary};
 pub use rollback::{seal_versioned, unseal_versioned, VersionStore, VersionedSealedData};
 pub use sealed::{seal, unseal, SealedData, SealingPolicy};
 pub use simulate::{
-    build_simulated_report, is_simulate_mode, is_simulated_report, TEE_SIMULATE_ENV,
+    build_simulated_report, is_simulate_mode, is_simulated_report, SimulationFixture,
+    TEE_SIMULATE_ENV,
 };
 pub use snp::{check_sev_snp_support, require_sev_snp_support, SevSnpSupport};
 pub use verifier::{verify_attestation, verify_attestation_with_time, VerificationResult};
+pub use verity::{build_measured_rootfs, MeasuredRootfs};
@@ -3,9 +3,23 @@
 //! Defines the rules for accepting or rejecting an SNP attestation report.
 //! The verifier checks the report against these policies after validating
 //! the cryptographic signature and certificate chain.
+//!
+//! Policies can be written as YAML or JSON documents and loaded with
+//! [`AttestationPolicy::load`]; a document may `extends` other documents,
+//! so a fleet-wide baseline can be tightened per workload.
+
+use std::path::{Path, PathBuf};
 
+use a3s_box_core::error::{BoxError, Result};
 use serde::{Deserialize, Serialize};
 
+/// File in a box directory holding the policy chosen with
+/// `--attestation-policy`, resolved to JSON.
+pub const BOX_ATTESTATION_POLICY_FILE: &str = "attestation-policy.json";
+
+/// How deep `extends` chains may nest.
+const MAX_EXTENDS_DEPTH: usize = 8;
+
 /// Policy for verifying SNP attestation reports.
 ///
 /// Each field is optional — only set fields are checked. This allows
@@ -19,6 +33,34 @@ pub struct AttestationPolicy {
     #[serde(default)]
     pub expected_measurement: Option<String>,
 
+    /// Launch measurements to accept, hex-encoded. If non-empty, the
+    /// report's measurement must be one of them.
+    #[serde(default)]
+    pub allowed_measurements: Vec<String>,
+
+    /// Expected runtime-claims digest (`RuntimeClaims::digest_hex`). If
+    /// set, the report must carry it in REPORT_DATA.
+    #[serde(default)]
+    pub expected_runtime_claims: Option<String>,
+
+    /// ID block signing keys to accept, as hex-encoded SHA-384 digests. If
+    /// non-empty, the guest must have been launched with an ID block signed
+    /// by one of them.
+    #[serde(default)]
+    pub allowed_id_key_digests: Vec<String>,
+
+    /// ID block author keys to accept, as hex-encoded SHA-384 digests.
+    #[serde(default)]
+    pub allowed_author_key_digests: Vec<String>,
+
+    /// Expected FAMILY_ID from the launch ID block, hex-encoded (16 bytes).
+    #[serde(default)]
+    pub expected_family_id: Option<String>,
+
+    /// Expected IMAGE_ID from the launch ID block, hex-encoded (16 bytes).
+    #[serde(default)]
+    pub expected_image_id: Option<String>,
+
     /// Minimum TCB version requirements. Each component is checked
     /// independently — the report's value must be >= the policy value.
     #[serde(default)]
@@ -50,6 +92,12 @@ impl Default for AttestationPolicy {
     fn default() -> Self {
         Self {
             expected_measurement: None,
+            allowed_measurements: Vec::new(),
+            expected_runtime_claims: None,
+            allowed_id_key_digests: Vec::new(),
+            allowed_author_key_digests: Vec::new(),
+            expected_family_id: None,
+            expected_image_id: None,
             min_tcb: None,
             require_no_debug: true,
             require_no_smt: false,
@@ -59,6 +107,264 @@ impl Default for AttestationPolicy {
     }
 }
 
+impl AttestationPolicy {
+    /// Load a policy document (YAML or JSON), resolving `extends`.
+    ///
+    /// ```yaml
+    /// extends: [baseline.yaml]   # relative to this file; applied first
+    /// measurements:              # accept any of these launch measurements
+    ///   - "<96 hex characters>"
+    /// runtime_claims: "<64 hex>"  # expected RuntimeClaims digest
+    /// id_key_digests:            # ID block signing keys (SHA-384)
+    ///   - "<96 hex characters>"
+    /// author_key_digests: []     # ID block author keys (SHA-384)
+    /// family_id: "<32 hex>"      # ID block FAMILY_ID
+    /// image_id: "<32 hex>"       # ID block IMAGE_ID
+    /// min_tcb: { snp: 8, microcode: 115 }
+    /// require_no_debug: true
+    /// require_no_smt: true
+    /// required_policy_bits: 0x30000  # alias: allowed_policy_mask
+    /// max_report_age_secs: 300
+    /// ```
+    ///
+    /// Every field is optional. Fields set in a document override what it
+    /// extends; `min_tcb` overrides component by component. The JSON form of
+    /// an [`AttestationPolicy`] is also a valid document.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut policy = Self::default();
+        policy.apply_document(path, &mut Vec::new())?;
+        Ok(policy)
+    }
+
+    /// Names of the checks this policy makes, matching
+    /// [`PolicyViolation::check`], in the order the verifier runs them.
+    pub fn checks(&self) -> Vec<&'static str> {
+        let mut checks = Vec::new();
+        if self.expected_measurement.is_some() || !self.allowed_measurements.is_empty() {
+            checks.push("measurement");
+        }
+        if self.expected_runtime_claims.is_some() {
+            checks.push("runtime_claims");
+        }
+        if !self.allowed_id_key_digests.is_empty() {
+            checks.push("id_block.id_key");
+        }
+        if !self.allowed_author_key_digests.is_empty() {
+            checks.push("id_block.author_key");
+        }
+        if self.expected_family_id.is_some() {
+            checks.push("id_block.family_id");
+        }
+        if self.expected_image_id.is_some() {
+            checks.push("id_block.image_id");
+        }
+        if self.require_no_debug {
+            checks.push("debug");
+        }
+        if self.require_no_smt {
+            checks.push("smt");
+        }
+        if let Some(min_tcb) = &self.min_tcb {
+            for (check, min) in [
+                ("tcb.boot_loader", min_tcb.boot_loader),
+                ("tcb.tee", min_tcb.tee),
+                ("tcb.snp", min_tcb.snp),
+                ("tcb.microcode", min_tcb.microcode),
+            ] {
+                if min.is_some() {
+                    checks.push(check);
+                }
+            }
+        }
+        if self.allowed_policy_mask.is_some() {
+            checks.push("policy_mask");
+        }
+        checks
+    }
+
+    /// Load the policy recorded for a box, if it was started with one.
+    pub fn load_for_box(box_dir: &Path) -> Result<Option<Self>> {
+        let path = box_dir.join(BOX_ATTESTATION_POLICY_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).map(Some).map_err(|e| {
+                BoxError::AttestationError(format!(
+                    "Invalid attestation policy {}: {e}",
+                    path.display()
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(BoxError::AttestationError(format!(
+                "Failed to read attestation policy {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Record this policy as a box's attestation policy.
+    pub fn save_for_box(&self, box_dir: &Path) -> Result<()> {
+        let path = box_dir.join(BOX_ATTESTATION_POLICY_FILE);
+        let data = serde_json::to_vec_pretty(self)?;
+        a3s_box_core::fs_atomic::write_durable(&path.with_extension("json.tmp"), &path, &data)
+            .map_err(|e| {
+                BoxError::AttestationError(format!(
+                    "Failed to write attestation policy {}: {e}",
+                    path.display()
+                ))
+            })
+    }
+
+    /// Apply the document at `path` (after everything it extends) on top of
+    /// `self`. `chain` holds the documents currently being applied.
+    fn apply_document(&mut self, path: &Path, chain: &mut Vec<PathBuf>) -> Result<()> {
+        let error = |message: String| {
+            BoxError::AttestationError(format!(
+                "Invalid attestation policy {}: {message}",
+                path.display()
+            ))
+        };
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| error(format!("cannot read file: {e}")))?;
+        if chain.contains(&canonical) {
+            return Err(error("`extends` forms a cycle".to_string()));
+        }
+        if chain.len() == MAX_EXTENDS_DEPTH {
+            return Err(error(format!(
+                "`extends` nests deeper than {MAX_EXTENDS_DEPTH} files"
+            )));
+        }
+        let data = std::fs::read_to_string(&canonical)
+            .map_err(|e| error(format!("cannot read file: {e}")))?;
+        // YAML 1.2 is a superset of JSON, so one parser reads both forms.
+        let document: PolicyDocument =
+            serde_yaml::from_str(&data).map_err(|e| error(e.to_string()))?;
+        document.validate().map_err(error)?;
+
+        chain.push(canonical.clone());
+        let base_dir = canonical.parent().unwrap_or(Path::new("/"));
+        for parent in &document.extends {
+            self.apply_document(&base_dir.join(parent), chain)?;
+        }
+        chain.pop();
+
+        document.apply(self);
+        Ok(())
+    }
+}
+
+/// On-disk form of an [`AttestationPolicy`]; see [`AttestationPolicy::load`].
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyDocument {
+    #[serde(default)]
+    extends: Vec<PathBuf>,
+    #[serde(default, alias = "allowed_measurements")]
+    measurements: Vec<String>,
+    #[serde(default)]
+    expected_measurement: Option<String>,
+    #[serde(default, alias = "expected_runtime_claims")]
+    runtime_claims: Option<String>,
+    #[serde(default, alias = "allowed_id_key_digests")]
+    id_key_digests: Vec<String>,
+    #[serde(default, alias = "allowed_author_key_digests")]
+    author_key_digests: Vec<String>,
+    #[serde(default, alias = "expected_family_id")]
+    family_id: Option<String>,
+    #[serde(default, alias = "expected_image_id")]
+    image_id: Option<String>,
+    #[serde(default)]
+    min_tcb: Option<MinTcbPolicy>,
+    #[serde(default)]
+    require_no_debug: Option<bool>,
+    #[serde(default)]
+    require_no_smt: Option<bool>,
+    #[serde(default, alias = "required_policy_bits")]
+    allowed_policy_mask: Option<u64>,
+    #[serde(default)]
+    max_report_age_secs: Option<u64>,
+}
+
+impl PolicyDocument {
+    fn validate(&self) -> std::result::Result<(), String> {
+        let check_hex = |field: &str, value: &str, len: usize| {
+            if value.len() == len && value.bytes().all(|b| b.is_ascii_hexdigit()) {
+                Ok(())
+            } else {
+                Err(format!("{field} '{value}' must be {len} hex characters"))
+            }
+        };
+        for measurement in self.measurements.iter().chain(&self.expected_measurement) {
+            check_hex("measurement", measurement, 96)?;
+        }
+        for digest in &self.id_key_digests {
+            check_hex("id_key_digests", digest, 96)?;
+        }
+        for digest in &self.author_key_digests {
+            check_hex("author_key_digests", digest, 96)?;
+        }
+        if let Some(id) = &self.family_id {
+            check_hex("family_id", id, 32)?;
+        }
+        if let Some(id) = &self.image_id {
+            check_hex("image_id", id, 32)?;
+        }
+        if let Some(claims) = &self.runtime_claims {
+            if a3s_box_core::tee::decode_runtime_claims_digest(claims).is_none() {
+                return Err(format!(
+                    "runtime_claims '{claims}' must be 64 hex characters"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(self, policy: &mut AttestationPolicy) {
+        let lowercase = |measurement: String| measurement.to_ascii_lowercase();
+        if !self.measurements.is_empty() {
+            policy.allowed_measurements = self.measurements.into_iter().map(lowercase).collect();
+        }
+        if let Some(measurement) = self.expected_measurement {
+            policy.expected_measurement = Some(lowercase(measurement));
+        }
+        if let Some(claims) = self.runtime_claims {
+            policy.expected_runtime_claims = Some(lowercase(claims));
+        }
+        if !self.id_key_digests.is_empty() {
+            policy.allowed_id_key_digests =
+                self.id_key_digests.into_iter().map(lowercase).collect();
+        }
+        if !self.author_key_digests.is_empty() {
+            policy.allowed_author_key_digests =
+                self.author_key_digests.into_iter().map(lowercase).collect();
+        }
+        if let Some(id) = self.family_id {
+            policy.expected_family_id = Some(lowercase(id));
+        }
+        if let Some(id) = self.image_id {
+            policy.expected_image_id = Some(lowercase(id));
+        }
+        if let Some(min_tcb) = self.min_tcb {
+            let current = policy.min_tcb.get_or_insert_with(MinTcbPolicy::default);
+            current.boot_loader = min_tcb.boot_loader.or(current.boot_loader);
+            current.tee = min_tcb.tee.or(current.tee);
+            current.snp = min_tcb.snp.or(current.snp);
+            current.microcode = min_tcb.microcode.or(current.microcode);
+        }
+        if let Some(require) = self.require_no_debug {
+            policy.require_no_debug = require;
+        }
+        if let Some(require) = self.require_no_smt {
+            policy.require_no_smt = require;
+        }
+        if let Some(mask) = self.allowed_policy_mask {
+            policy.allowed_policy_mask = Some(mask);
+        }
+        if let Some(age) = self.max_report_age_secs {
+            policy.max_report_age_secs = Some(age);
+        }
+    }
+}
+
 /// Minimum TCB (Trusted Computing Base) version requirements.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MinTcbPolicy {
@@ -244,6 +550,12 @@ mod tests {
     fn test_attestation_policy_clone() {
         let policy = AttestationPolicy {
             expected_measurement: Some("abc123".to_string()),
+            allowed_measurements: vec!["def456".to_string()],
+            expected_runtime_claims: None,
+            allowed_id_key_digests: vec!["ab".repeat(48)],
+            allowed_author_key_digests: Vec::new(),
+            expected_family_id: None,
+            expected_image_id: Some("cd".repeat(16)),
             min_tcb: Some(MinTcbPolicy {
                 snp: Some(8),
                 ..Default::default()
@@ -261,6 +573,114 @@ mod tests {
         assert_eq!(cloned.max_report_age_secs, policy.max_report_age_secs);
     }
 
+    #[test]
+    fn test_load_policy_document_with_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("base.json"),
+            format!(
+                r#"{{"measurements":["{}"],"min_tcb":{{"snp":8,"microcode":115}},"require_no_smt":true}}"#,
+                "AB".repeat(48)
+            ),
+        )
+        .unwrap();
+        let path = dir.path().join("policy.yaml");
+        std::fs::write(
+            &path,
+            "extends: [base.json]\nmin_tcb:\n  snp: 21\nrequire_no_debug: false\nmax_report_age_secs: 300\n",
+        )
+        .unwrap();
+
+        let policy = AttestationPolicy::load(&path).unwrap();
+
+        assert_eq!(policy.allowed_measurements, vec!["ab".repeat(48)]);
+        let min_tcb = policy.min_tcb.unwrap();
+        assert_eq!(min_tcb.snp, Some(21));
+        assert_eq!(min_tcb.microcode, Some(115));
+        assert!(!policy.require_no_debug);
+        assert!(policy.require_no_smt);
+        assert_eq!(policy.max_report_age_secs, Some(300));
+    }
+
+    #[test]
+    fn test_load_policy_document_with_id_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.json");
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"id_key_digests":["{}"],"image_id":"{}","required_policy_bits":196608}}"#,
+                "AB".repeat(48),
+                "cd".repeat(16)
+            ),
+        )
+        .unwrap();
+
+        let policy = AttestationPolicy::load(&path).unwrap();
+
+        assert_eq!(policy.allowed_id_key_digests, vec!["ab".repeat(48)]);
+        assert_eq!(policy.expected_image_id, Some("cd".repeat(16)));
+        assert_eq!(policy.allowed_policy_mask, Some(0x30000));
+        assert_eq!(
+            policy.checks(),
+            vec![
+                "id_block.id_key",
+                "id_block.image_id",
+                "debug",
+                "policy_mask"
+            ]
+        );
+
+        std::fs::write(&path, "family_id: abcd\n").unwrap();
+        let error = AttestationPolicy::load(&path).unwrap_err().to_string();
+        assert!(error.contains("family_id"), "{error}");
+    }
+
+    #[test]
+    fn test_load_policy_document_rejects_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        for path in [
+            write("typo.yaml", "require_no_debgu: true\n"),
+            write("short.yaml", "measurements: [abcd]\n"),
+            write("loop-a.yaml", "extends: [loop-b.yaml]\n"),
+            write("missing.yaml", "extends: [nowhere.yaml]\n"),
+        ] {
+            write("loop-b.yaml", "extends: [loop-a.yaml]\n");
+            let error = AttestationPolicy::load(&path).unwrap_err().to_string();
+            assert!(error.contains("Invalid attestation policy"), "{error}");
+        }
+    }
+
+    #[test]
+    fn test_box_policy_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(AttestationPolicy::load_for_box(dir.path())
+            .unwrap()
+            .is_none());
+
+        let policy = AttestationPolicy {
+            allowed_measurements: vec!["cd".repeat(48)],
+            require_no_smt: true,
+            ..Default::default()
+        };
+        policy.save_for_box(dir.path()).unwrap();
+        let loaded = AttestationPolicy::load_for_box(dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.allowed_measurements, policy.allowed_measurements);
+        assert!(loaded.require_no_smt);
+
+        // The recorded JSON is itself a valid policy document.
+        let reloaded =
+            AttestationPolicy::load(&dir.path().join(BOX_ATTESTATION_POLICY_FILE)).unwrap();
+        assert_eq!(reloaded.allowed_measurements, policy.allowed_measurements);
+    }
+
     #[test]
     fn test_attestation_policy_debug() {
         let policy = AttestationPolicy::default();
@@ -3,6 +3,20 @@
 //! When `A3S_TEE_SIMULATE=1` is set, the guest attestation server generates
 //! fake SNP reports with correct field layout but no hardware signature.
 //! The host verifier can accept these with `allow_simulated: true`.
+//!
+//! A [`SimulationFixture`] sets the measurement, TCB, host data, and policy
+//! a simulated report carries, so attestation policies can be tested
+//! end-to-end against realistic values.
+
+use std::path::Path;
+
+use a3s_box_core::error::{BoxError, Result};
+use serde::{Deserialize, Serialize};
+
+use super::attestation::{
+    parse_platform_info, AttestationReport, CertificateChain, TcbVersion, SNP_MEASUREMENT_SIZE,
+    SNP_REPORT_SIZE, SNP_USER_DATA_SIZE,
+};
 
 /// Environment variable to enable TEE simulation mode.
 pub const TEE_SIMULATE_ENV: &str = "A3S_TEE_SIMULATE";
@@ -21,14 +35,134 @@ pub const SIMULATED_REPORT_VERSION: u32 = 0xA3;
 /// Simulated chip ID (all 0xA3 bytes, clearly fake).
 pub const SIMULATED_CHIP_ID: [u8; 64] = [0xA3; 64];
 
+/// Values a simulated report carries instead of the built-in defaults.
+///
+/// Loaded from a JSON fixture so a policy can be tested end-to-end against
+/// the exact measurement, TCB, and host data a deployment expects, without
+/// SEV hardware. Every field is optional:
+///
+/// ```json
+/// {
+///   "measurement": "<96 hex characters>",
+///   "host_data": "<64 hex characters>",
+///   "tcb": { "boot_loader": 3, "tee": 0, "snp": 8, "microcode": 115 },
+///   "guest_svn": 1,
+///   "policy": 196608
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulationFixture {
+    /// Launch measurement, hex-encoded (48 bytes).
+    #[serde(default)]
+    pub measurement: Option<String>,
+    /// HOST_DATA supplied at launch, hex-encoded (32 bytes).
+    #[serde(default)]
+    pub host_data: Option<String>,
+    /// Reported TCB version.
+    #[serde(default)]
+    pub tcb: Option<TcbVersion>,
+    /// Guest security version number.
+    #[serde(default)]
+    pub guest_svn: Option<u32>,
+    /// Guest policy bits (bit 19 is debug, bit 16 SMT).
+    #[serde(default)]
+    pub policy: Option<u64>,
+}
+
+impl SimulationFixture {
+    /// Load and validate a fixture file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            BoxError::TeeConfig(format!(
+                "Failed to read TEE simulation fixture {}: {e}",
+                path.display()
+            ))
+        })?;
+        Self::from_json(&contents)
+            .map_err(|e| BoxError::TeeConfig(format!("{}: {e}", path.display())))
+    }
+
+    /// Parse and validate a fixture from JSON.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let fixture: Self = serde_json::from_str(json)
+            .map_err(|e| BoxError::TeeConfig(format!("Invalid TEE simulation fixture: {e}")))?;
+        fixture.decoded()?;
+        Ok(fixture)
+    }
+
+    /// Build a simulated report carrying this fixture's values.
+    pub fn build_report(&self, report_data: &[u8; SNP_USER_DATA_SIZE]) -> Result<Vec<u8>> {
+        let (measurement, host_data) = self.decoded()?;
+        let mut report = default_report(report_data);
+        if let Some(measurement) = measurement {
+            report[0x90..0xC0].copy_from_slice(&measurement);
+        }
+        if let Some(host_data) = host_data {
+            report[0xC0..0xE0].copy_from_slice(&host_data);
+        }
+        if let Some(tcb) = &self.tcb {
+            write_tcb(&mut report, tcb);
+        }
+        if let Some(guest_svn) = self.guest_svn {
+            report[0x04..0x08].copy_from_slice(&guest_svn.to_le_bytes());
+        }
+        if let Some(policy) = self.policy {
+            report[0x08..0x10].copy_from_slice(&policy.to_le_bytes());
+        }
+        Ok(report)
+    }
+
+    /// Build a complete simulated [`AttestationReport`] answering `nonce`,
+    /// ready for [`verify_attestation`](super::verify_attestation) with
+    /// `allow_simulated` set.
+    pub fn attestation_report(&self, nonce: &[u8]) -> Result<AttestationReport> {
+        let mut report_data = [0u8; SNP_USER_DATA_SIZE];
+        let len = nonce.len().min(SNP_USER_DATA_SIZE);
+        report_data[..len].copy_from_slice(&nonce[..len]);
+        let report = self.build_report(&report_data)?;
+        let platform = parse_platform_info(&report).unwrap_or_default();
+        Ok(AttestationReport {
+            report,
+            cert_chain: CertificateChain::default(),
+            platform,
+        })
+    }
+
+    fn decoded(&self) -> Result<(Option<[u8; SNP_MEASUREMENT_SIZE]>, Option<[u8; 32]>)> {
+        Ok((
+            decode_field("measurement", self.measurement.as_deref())?,
+            decode_field("host_data", self.host_data.as_deref())?,
+        ))
+    }
+}
+
+fn decode_field<const N: usize>(name: &str, value: Option<&str>) -> Result<Option<[u8; N]>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let bytes = hex::decode(value.trim())
+        .map_err(|e| BoxError::TeeConfig(format!("Invalid fixture {name}: not hex ({e})")))?;
+    bytes.try_into().map(Some).map_err(|bytes: Vec<u8>| {
+        BoxError::TeeConfig(format!(
+            "Invalid fixture {name}: expected {N} bytes ({} hex characters), got {}",
+            N * 2,
+            bytes.len()
+        ))
+    })
+}
+
 /// Build a simulated 1184-byte SNP report with the given report_data.
 ///
 /// The report has correct field layout per AMD SEV-SNP ABI spec (Table 21)
 /// but uses a marker version (0xA3) and zero signature to indicate simulation.
 /// Nonce, measurement, TCB, and policy fields are populated normally so that
-/// policy checks still work.
+/// policy checks still work. Use [`SimulationFixture`] to choose the values.
 pub fn build_simulated_report(report_data: &[u8; 64]) -> Vec<u8> {
-    const SNP_REPORT_SIZE: usize = 1184;
+    default_report(report_data)
+}
+
+fn default_report(report_data: &[u8; SNP_USER_DATA_SIZE]) -> Vec<u8> {
     let mut report = vec![0u8; SNP_REPORT_SIZE];
 
     // version at 0x00 (4 bytes LE) — use simulated marker
@@ -41,10 +175,15 @@ pub fn build_simulated_report(report_data: &[u8; 64]) -> Vec<u8> {
     report[0x08..0x10].copy_from_slice(&0u64.to_le_bytes());
 
     // current_tcb at 0x38 (8 bytes)
-    report[0x38] = 3; // boot_loader
-    report[0x39] = 0; // tee
-    report[0x3E] = 8; // snp
-    report[0x3F] = 115; // microcode
+    write_tcb(
+        &mut report,
+        &TcbVersion {
+            boot_loader: 3,
+            tee: 0,
+            snp: 8,
+            microcode: 115,
+        },
+    );
 
     // report_data at 0x50 (64 bytes)
     report[0x50..0x90].copy_from_slice(report_data);
@@ -54,6 +193,8 @@ pub fn build_simulated_report(report_data: &[u8; 64]) -> Vec<u8> {
         report[0x90 + i] = (i as u8).wrapping_mul(0xA3);
     }
 
+    // host_data at 0xC0 (32 bytes) — zeros unless a fixture sets it
+
     // chip_id at 0x1A0 (64 bytes)
     report[0x1A0..0x1E0].copy_from_slice(&SIMULATED_CHIP_ID);
 
@@ -62,6 +203,13 @@ pub fn build_simulated_report(report_data: &[u8; 64]) -> Vec<u8> {
     report
 }
 
+fn write_tcb(report: &mut [u8], tcb: &TcbVersion) {
+    report[0x38] = tcb.boot_loader;
+    report[0x39] = tcb.tee;
+    report[0x3E] = tcb.snp;
+    report[0x3F] = tcb.microcode;
+}
+
 /// Check if an SNP report is a simulated report (version == 0xA3).
 pub fn is_simulated_report(report: &[u8]) -> bool {
     if report.len() < 4 {
@@ -159,4 +307,90 @@ mod tests {
     fn test_simulated_report_version_constant() {
         assert_eq!(SIMULATED_REPORT_VERSION, 0xA3);
     }
+
+    #[test]
+    fn test_empty_fixture_matches_default_report() {
+        let data = [7u8; 64];
+        let report = SimulationFixture::default().build_report(&data).unwrap();
+        assert_eq!(report, build_simulated_report(&data));
+    }
+
+    #[test]
+    fn test_fixture_values_reach_platform_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.json");
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"measurement":"{}","host_data":"{}","tcb":{{"boot_loader":4,"tee":1,"snp":22,"microcode":213}},"guest_svn":9,"policy":524288}}"#,
+                "ab".repeat(48),
+                "cd".repeat(32)
+            ),
+        )
+        .unwrap();
+
+        let fixture = SimulationFixture::load(&path).unwrap();
+        let report = fixture.attestation_report(&[1, 2, 3]).unwrap();
+
+        assert!(is_simulated_report(&report.report));
+        assert_eq!(report.platform.measurement, "ab".repeat(48));
+        assert_eq!(report.platform.host_data, "cd".repeat(32));
+        assert_eq!(report.platform.tcb_version.snp, 22);
+        assert_eq!(report.platform.tcb_version.microcode, 213);
+        assert_eq!(report.platform.guest_svn, 9);
+        assert_eq!(report.platform.policy, 1 << 19);
+        assert_eq!(&report.report[0x50..0x53], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_fixture_rejects_bad_values() {
+        for json in [
+            r#"{"measurement":"abcd"}"#,
+            r#"{"host_data":"zz"}"#,
+            r#"{"measurment":"00"}"#,
+            r#"{"tcb":{"snp":8}}"#,
+        ] {
+            assert!(SimulationFixture::from_json(json).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn test_fixture_drives_policy_end_to_end() {
+        use crate::tee::{verify_attestation, AttestationPolicy, MinTcbPolicy};
+
+        let measurement = "5a".repeat(48);
+        let fixture = SimulationFixture {
+            measurement: Some(measurement.clone()),
+            tcb: Some(TcbVersion {
+                boot_loader: 3,
+                tee: 0,
+                snp: 8,
+                microcode: 115,
+            }),
+            ..Default::default()
+        };
+        let nonce = [9u8; 32];
+        let report = fixture.attestation_report(&nonce).unwrap();
+
+        let policy = AttestationPolicy {
+            expected_measurement: Some(measurement),
+            ..Default::default()
+        };
+        assert!(
+            verify_attestation(&report, &nonce, &policy, true)
+                .unwrap()
+                .verified
+        );
+
+        let stricter = AttestationPolicy {
+            min_tcb: Some(MinTcbPolicy {
+                snp: Some(21),
+                ..Default::default()
+            }),
+            ..policy
+        };
+        let result = verify_attestation(&report, &nonce, &stricter, true).unwrap();
+        assert!(!result.verified);
+        assert!(!result.policy_result.passed);
+    }
 }
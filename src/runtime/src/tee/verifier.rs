@@ -481,6 +481,89 @@ fn check_policy(platform: &PlatformInfo, policy: &AttestationPolicy) -> PolicyRe
         }
     }
 
+    if !policy.allowed_measurements.is_empty()
+        && !policy
+            .allowed_measurements
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(&platform.measurement))
+    {
+        violations.push(PolicyViolation {
+            check: "measurement".to_string(),
+            reason: format!(
+                "{} is not one of {} allowed measurements",
+                &platform.measurement[..platform.measurement.len().min(16)],
+                policy.allowed_measurements.len(),
+            ),
+        });
+    }
+
+    if let Some(ref expected) = policy.expected_runtime_claims {
+        let actual = platform.report_data.get(64..).unwrap_or_default();
+        if !actual.eq_ignore_ascii_case(expected) {
+            violations.push(PolicyViolation {
+                check: "runtime_claims".to_string(),
+                reason: format!(
+                    "Expected runtime claims {}, got {}",
+                    &expected[..expected.len().min(16)],
+                    &actual[..actual.len().min(16)],
+                ),
+            });
+        }
+    }
+
+    // Check the launch ID block: the keys that signed it and the family
+    // and image IDs it carries.
+    let id_block_checks = [
+        (
+            "id_block.id_key",
+            "ID key",
+            &platform.id_key_digest,
+            &policy.allowed_id_key_digests[..],
+        ),
+        (
+            "id_block.author_key",
+            "Author key",
+            &platform.author_key_digest,
+            &policy.allowed_author_key_digests[..],
+        ),
+    ];
+    for (check, name, actual, allowed) in id_block_checks {
+        if !allowed.is_empty() && !allowed.iter().any(|a| a.eq_ignore_ascii_case(actual)) {
+            violations.push(PolicyViolation {
+                check: check.to_string(),
+                reason: format!(
+                    "{name} digest {} is not one of {} allowed digests",
+                    &actual[..actual.len().min(16)],
+                    allowed.len(),
+                ),
+            });
+        }
+    }
+    let id_checks = [
+        (
+            "id_block.family_id",
+            "family ID",
+            &platform.family_id,
+            &policy.expected_family_id,
+        ),
+        (
+            "id_block.image_id",
+            "image ID",
+            &platform.image_id,
+            &policy.expected_image_id,
+        ),
+    ];
+    for (check, name, actual, expected) in id_checks {
+        if let Some(expected) = expected {
+            if !actual.eq_ignore_ascii_case(expected) {
+                violations.push(PolicyViolation {
+                    check: check.to_string(),
+                    reason: format!("Expected {name} {expected}, got {actual}"),
+                });
+            }
+        }
+    }
+
     // Check debug mode (bit 19 of guest policy = debug enabled)
     if policy.require_no_debug {
         let debug_enabled = (platform.policy >> 19) & 1 == 1;
@@ -868,6 +951,12 @@ mod tests {
             guest_svn: 1,
             policy: 0, // no debug, no SMT
             measurement: "aabb".repeat(24),
+            family_id: "00".repeat(16),
+            image_id: "00".repeat(16),
+            id_key_digest: "00".repeat(48),
+            author_key_digest: "00".repeat(48),
+            host_data: "00".repeat(32),
+            report_data: "00".repeat(64),
             tcb_version: TcbVersion {
                 boot_loader: 3,
                 tee: 0,
@@ -945,6 +1034,70 @@ mod tests {
         assert!(result.passed);
     }
 
+    #[test]
+    fn test_check_policy_allowed_measurements() {
+        let platform = PlatformInfo {
+            measurement: "aa".repeat(48),
+            ..Default::default()
+        };
+        let mut policy = AttestationPolicy {
+            allowed_measurements: vec!["bb".repeat(48), "AA".repeat(48)],
+            require_no_debug: false,
+            ..Default::default()
+        };
+        assert!(check_policy(&platform, &policy).passed);
+
+        policy.allowed_measurements.pop();
+        let result = check_policy(&platform, &policy);
+        assert!(!result.passed);
+        assert!(result.violations.iter().any(|v| v.check == "measurement"));
+    }
+
+    #[test]
+    fn test_check_policy_runtime_claims() {
+        let claims = "ab".repeat(32);
+        let platform = PlatformInfo {
+            report_data: format!("{}{}", "00".repeat(32), claims),
+            ..Default::default()
+        };
+        let mut policy = AttestationPolicy {
+            expected_runtime_claims: Some(claims.to_uppercase()),
+            require_no_debug: false,
+            ..Default::default()
+        };
+        assert!(check_policy(&platform, &policy).passed);
+
+        policy.expected_runtime_claims = Some("cd".repeat(32));
+        let result = check_policy(&platform, &policy);
+        assert!(!result.passed);
+        assert!(result
+            .violations
+            .iter()
+            .any(|v| v.check == "runtime_claims"));
+    }
+
+    #[test]
+    fn test_check_policy_id_block() {
+        let platform = PlatformInfo {
+            image_id: "22".repeat(16),
+            id_key_digest: "ab".repeat(48),
+            ..Default::default()
+        };
+        let mut policy = AttestationPolicy {
+            allowed_id_key_digests: vec!["AB".repeat(48)],
+            expected_image_id: Some("22".repeat(16)),
+            require_no_debug: false,
+            ..Default::default()
+        };
+        assert!(check_policy(&platform, &policy).passed);
+
+        policy.allowed_author_key_digests = vec!["cd".repeat(48)];
+        policy.expected_image_id = Some("33".repeat(16));
+        let result = check_policy(&platform, &policy);
+        let checks: Vec<&str> = result.violations.iter().map(|v| v.check.as_str()).collect();
+        assert_eq!(checks, ["id_block.author_key", "id_block.image_id"]);
+    }
+
     #[test]
     fn test_check_policy_tcb_violation() {
         let platform = PlatformInfo {
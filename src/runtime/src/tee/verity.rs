@@ -0,0 +1,327 @@
+//! Measured rootfs for TEE boxes: a read-only ext4 image of the box's rootfs
+//! protected by a dm-verity hash tree.
+//!
+//! An SNP launch measurement covers the guest kernel and init, not the files
+//! the workload runs from. A TEE box therefore boots its workload from an
+//! image whose dm-verity root hash is part of its runtime claims: guest init
+//! opens the image with dm-verity, so every block read is checked against the
+//! tree, and binds the root hash it opened into its attestation reports
+//! ([`super::claims`]). Guest writes land in a tmpfs overlay and are lost
+//! when the box stops.
+//!
+//! Layout:
+//! ```text
+//! box_dir/rootfs-verity.img    ← ext4 image of the rootfs (data device)
+//! box_dir/rootfs-verity.hash   ← dm-verity hash tree (hash device)
+//! box_dir/rootfs-verity.json   ← MeasuredRootfs record
+//! ```
+//!
+//! Building needs `mkfs.ext4` (e2fsprogs 1.43+) and `veritysetup`
+//! (cryptsetup) on the host. The image is rebuilt only when the box boots a
+//! different image digest. Files keep their host ownership, which matches
+//! the image only when the runtime runs as root, as SEV-SNP hosts do.
+//!
+//! The filesystem UUID and directory hash seed derive from the image digest
+//! and `mkfs.ext4` runs with `SOURCE_DATE_EPOCH=0`, so e2fsprogs releases
+//! that honour it build the same image, and the same root hash, for the same
+//! image on any host.
+
+use std::path::{Path, PathBuf};
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::tee::{
+    VerityRootfs, VERITY_BLOCK_SIZE, VERITY_DATA_BLOCK_ID, VERITY_HASH_BLOCK_ID,
+};
+use a3s_box_core::volume::BlockVolume;
+use serde::{Deserialize, Serialize};
+
+/// Measured rootfs data image inside the box directory.
+pub const MEASURED_ROOTFS_IMAGE: &str = "rootfs-verity.img";
+
+/// Measured rootfs hash tree inside the box directory.
+pub const MEASURED_ROOTFS_HASH_TREE: &str = "rootfs-verity.hash";
+
+/// Record of the last measured rootfs built for a box.
+const MEASURED_ROOTFS_RECORD: &str = "rootfs-verity.json";
+
+/// Free space left in the image for ext4 metadata, as a fraction of the
+/// rootfs size, plus a fixed floor.
+const IMAGE_HEADROOM_DIVISOR: u64 = 4;
+const IMAGE_HEADROOM_MIN_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A built measured rootfs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeasuredRootfs {
+    /// Manifest digest of the image the rootfs was built from.
+    pub image_digest: String,
+    /// Root hash and size handed to guest init.
+    pub verity: VerityRootfs,
+    /// ext4 data image.
+    pub data_image: PathBuf,
+    /// dm-verity hash tree.
+    pub hash_tree: PathBuf,
+}
+
+impl MeasuredRootfs {
+    /// The data image and hash tree as read-only virtio-blk disks. Guest init
+    /// finds them by serial and does not mount them as volumes.
+    pub fn block_devices(&self) -> [BlockVolume; 2] {
+        let disk = |block_id: &str, source: &Path| BlockVolume {
+            block_id: block_id.to_string(),
+            source: source.display().to_string(),
+            target: "/".to_string(),
+            fstype: "ext4".to_string(),
+            read_only: true,
+        };
+        [
+            disk(VERITY_DATA_BLOCK_ID, &self.data_image),
+            disk(VERITY_HASH_BLOCK_ID, &self.hash_tree),
+        ]
+    }
+}
+
+/// Build (or reuse) the measured rootfs of `rootfs` for `box_dir`.
+///
+/// A record for the same `image_digest` whose files still exist is reused.
+pub fn build_measured_rootfs(
+    box_dir: &Path,
+    rootfs: &Path,
+    image_digest: &str,
+) -> Result<MeasuredRootfs> {
+    let record_path = box_dir.join(MEASURED_ROOTFS_RECORD);
+    if let Some(existing) = load_record(&record_path) {
+        if existing.image_digest == image_digest
+            && existing.data_image.is_file()
+            && existing.hash_tree.is_file()
+        {
+            return Ok(existing);
+        }
+    }
+
+    let data_image = box_dir.join(MEASURED_ROOTFS_IMAGE);
+    let hash_tree = box_dir.join(MEASURED_ROOTFS_HASH_TREE);
+    let _ = std::fs::remove_file(&record_path);
+
+    let rootfs_bytes = crate::cache::layer_cache::dir_size(rootfs).map_err(|e| {
+        BoxError::BuildError(format!(
+            "Failed to size rootfs {} for the measured image: {e}",
+            rootfs.display()
+        ))
+    })?;
+    let image_bytes = image_size(rootfs_bytes);
+    create_ext4_image(rootfs, &data_image, image_bytes, image_digest)?;
+    let root_hash = format_hash_tree(&data_image, &hash_tree)?;
+
+    let measured = MeasuredRootfs {
+        image_digest: image_digest.to_string(),
+        verity: VerityRootfs {
+            root_hash,
+            data_blocks: image_bytes / VERITY_BLOCK_SIZE,
+        },
+        data_image,
+        hash_tree,
+    };
+    let data = serde_json::to_vec_pretty(&measured)?;
+    a3s_box_core::fs_atomic::write_durable(
+        &record_path.with_extension("json.tmp"),
+        &record_path,
+        &data,
+    )
+    .map_err(|e| {
+        BoxError::BuildError(format!(
+            "Failed to write measured rootfs record {}: {e}",
+            record_path.display()
+        ))
+    })?;
+    tracing::info!(
+        image = %measured.data_image.display(),
+        root_hash = %measured.verity.root_hash,
+        "Built measured rootfs"
+    );
+    Ok(measured)
+}
+
+fn load_record(path: &Path) -> Option<MeasuredRootfs> {
+    let data = std::fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Image size for a rootfs of `rootfs_bytes`, in whole verity blocks.
+fn image_size(rootfs_bytes: u64) -> u64 {
+    let headroom = (rootfs_bytes / IMAGE_HEADROOM_DIVISOR).max(IMAGE_HEADROOM_MIN_BYTES);
+    (rootfs_bytes + headroom).div_ceil(VERITY_BLOCK_SIZE) * VERITY_BLOCK_SIZE
+}
+
+/// ext4 filesystem UUID for the measured rootfs of `image_digest`. mkfs.ext4
+/// otherwise picks a random one (and a random directory hash seed), which
+/// would change the root hash on every build.
+fn ext4_uuid(image_digest: &str) -> String {
+    use sha2::Digest as _;
+
+    let digest = sha2::Sha256::digest(image_digest.as_bytes());
+    uuid::Uuid::from_slice(&digest[..16])
+        .expect("16-byte slice")
+        .to_string()
+}
+
+fn create_ext4_image(rootfs: &Path, image: &Path, size: u64, image_digest: &str) -> Result<()> {
+    let file = std::fs::File::create(image).map_err(|e| {
+        BoxError::BuildError(format!(
+            "Failed to create measured rootfs image {}: {e}",
+            image.display()
+        ))
+    })?;
+    file.set_len(size).map_err(|e| {
+        BoxError::BuildError(format!(
+            "Failed to size measured rootfs image {}: {e}",
+            image.display()
+        ))
+    })?;
+    drop(file);
+
+    let uuid = ext4_uuid(image_digest);
+    let output = std::process::Command::new("mkfs.ext4")
+        .args(["-q", "-F", "-m", "0", "-b"])
+        .arg(VERITY_BLOCK_SIZE.to_string())
+        .arg("-U")
+        .arg(&uuid)
+        .arg("-E")
+        .arg(format!("hash_seed={uuid}"))
+        .env("SOURCE_DATE_EPOCH", "0")
+        .arg("-d")
+        .arg(rootfs)
+        .arg(image)
+        .output()
+        .map_err(|e| {
+            let _ = std::fs::remove_file(image);
+            BoxError::BuildError(format!(
+                "Failed to run mkfs.ext4 for the measured rootfs (is e2fsprogs installed?): {e}"
+            ))
+        })?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(image);
+        return Err(BoxError::BuildError(format!(
+            "mkfs.ext4 failed for measured rootfs {}: {}",
+            image.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Write the hash tree of `image` to `hash_tree` and return the root hash.
+fn format_hash_tree(image: &Path, hash_tree: &Path) -> Result<String> {
+    std::fs::File::create(hash_tree).map_err(|e| {
+        BoxError::BuildError(format!(
+            "Failed to create hash tree {}: {e}",
+            hash_tree.display()
+        ))
+    })?;
+    let block_size = VERITY_BLOCK_SIZE.to_string();
+    // No salt and no superblock: guest init builds the dm table itself, and
+    // the root hash then depends only on the image.
+    let output = std::process::Command::new("veritysetup")
+        .args(["format", "--no-superblock", "--salt=-", "--hash=sha256"])
+        .arg(format!("--data-block-size={block_size}"))
+        .arg(format!("--hash-block-size={block_size}"))
+        .arg(image)
+        .arg(hash_tree)
+        .output()
+        .map_err(|e| {
+            BoxError::BuildError(format!(
+                "Failed to run veritysetup for the measured rootfs (is cryptsetup installed?): {e}"
+            ))
+        })?;
+    if !output.status.success() {
+        return Err(BoxError::BuildError(format!(
+            "veritysetup format failed for {}: {}",
+            image.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    parse_root_hash(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| BoxError::BuildError("veritysetup format printed no root hash".to_string()))
+}
+
+/// Extract the root hash from `veritysetup format` output.
+fn parse_root_hash(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim() == "Root hash")
+        .map(|(_, value)| value.trim().to_ascii_lowercase())
+        .filter(|hash| a3s_box_core::tee::decode_runtime_claims_digest(hash).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_root_hash() {
+        let hash = "4f".repeat(32);
+        let output = format!(
+            "VERITY header information for rootfs.hash\n\
+             UUID:            \t\n\
+             Hash type:       \t1\n\
+             Data blocks:     \t2048\n\
+             Salt:            \t-\n\
+             Root hash:      \t{}\n",
+            hash.to_uppercase()
+        );
+        assert_eq!(parse_root_hash(&output), Some(hash));
+        assert_eq!(parse_root_hash("Root hash:\tnot-hex\n"), None);
+        assert_eq!(parse_root_hash(""), None);
+    }
+
+    #[test]
+    fn test_ext4_uuid_is_stable_per_image() {
+        let uuid = ext4_uuid("sha256:abc");
+        assert_eq!(uuid, ext4_uuid("sha256:abc"));
+        assert_ne!(uuid, ext4_uuid("sha256:abd"));
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+    }
+
+    #[test]
+    fn test_image_size_is_block_aligned_with_headroom() {
+        let small = image_size(1);
+        assert_eq!(small % VERITY_BLOCK_SIZE, 0);
+        assert!(small >= IMAGE_HEADROOM_MIN_BYTES);
+
+        let rootfs = 1024 * 1024 * 1024 + 1;
+        let large = image_size(rootfs);
+        assert_eq!(large % VERITY_BLOCK_SIZE, 0);
+        assert!(large >= rootfs + rootfs / IMAGE_HEADROOM_DIVISOR);
+    }
+
+    #[test]
+    fn test_matching_record_is_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let measured = MeasuredRootfs {
+            image_digest: "sha256:abc".to_string(),
+            verity: VerityRootfs {
+                root_hash: "ab".repeat(32),
+                data_blocks: 16,
+            },
+            data_image: dir.path().join(MEASURED_ROOTFS_IMAGE),
+            hash_tree: dir.path().join(MEASURED_ROOTFS_HASH_TREE),
+        };
+        std::fs::write(&measured.data_image, b"").unwrap();
+        std::fs::write(&measured.hash_tree, b"").unwrap();
+        std::fs::write(
+            dir.path().join(MEASURED_ROOTFS_RECORD),
+            serde_json::to_vec(&measured).unwrap(),
+        )
+        .unwrap();
+
+        // The rootfs path is never read when the record matches.
+        let reused =
+            build_measured_rootfs(dir.path(), &dir.path().join("missing"), "sha256:abc").unwrap();
+        assert_eq!(reused, measured);
+
+        let [data, hash] = measured.block_devices();
+        assert_eq!(data.block_id, VERITY_DATA_BLOCK_ID);
+        assert_eq!(hash.block_id, VERITY_HASH_BLOCK_ID);
+        assert!(data.read_only && hash.read_only);
+    }
+}
@@ -62,6 +62,13 @@ pub(crate) fn persistent_rootfs_generation_exists(box_dir: &Path) -> Result<bool
         return Ok(true);
     }
 
+    if box_dir
+        .join(crate::rootfs::quota::QUOTA_IMAGE_NAME)
+        .is_file()
+    {
+        return Ok(true);
+    }
+
     Ok(false)
 }
 
@@ -111,8 +118,15 @@ impl VmManager {
 
         // Resolve workspace path: empty config means use a per-box directory so the
         // host CWD is never accidentally exposed to the guest.
+        // With a disk quota the per-box workspace lives on the quota volume so
+        // workspace writes count against the same limit as rootfs writes.
         let workspace_path = if self.config.workspace.as_os_str().is_empty() {
-            box_dir.join("workspace")
+            match self.config.resource_limits.disk_limit_bytes {
+                Some(limit) => {
+                    crate::rootfs::quota::mount_quota_volume(&box_dir, limit)?.join("workspace")
+                }
+                None => box_dir.join("workspace"),
+            }
         } else {
             PathBuf::from(&self.config.workspace)
         };
@@ -121,6 +135,21 @@ impl VmManager {
                 message: format!("Failed to create workspace directory: {}", e),
                 hint: None,
             })?;
+            // Seed only a workspace created here, so restarts keep the
+            // box's edits instead of re-applying the template.
+            if let Some(template) = &self.config.workspace_template {
+                tracing::info!(
+                    source = %template.source,
+                    workspace = %workspace_path.display(),
+                    "Seeding workspace from template"
+                );
+                if let Err(error) =
+                    super::workspace_template::seed(template, &workspace_path, &self.box_id)
+                {
+                    let _ = std::fs::remove_dir_all(&workspace_path);
+                    return Err(error);
+                }
+            }
         }
         // Canonicalize to absolute path (libkrun requires absolute paths for virtiofs)
         let workspace_path = workspace_path
@@ -173,6 +202,7 @@ impl VmManager {
                     crate::resolved_image::persist_resolved_image_config(&box_dir, config)?;
                 }
                 let tee_instance_config = self.generate_tee_config(&box_dir)?;
+                let encrypted_workspace = self.create_encrypted_workspace(&box_dir)?;
                 return Ok(BoxLayout {
                     rootfs_path,
                     exec_socket_path: socket_dir.join("exec.sock"),
@@ -184,6 +214,9 @@ impl VmManager {
                     oci_config,
                     prefer_image_rootfs_metadata: false,
                     tee_instance_config,
+                    image_digest: None,
+                    measured_rootfs: None,
+                    encrypted_workspace,
                 });
             }
             tracing::warn!(
@@ -232,6 +265,7 @@ impl VmManager {
                 }
             }
             let tee_instance_config = self.generate_tee_config(&box_dir)?;
+            let encrypted_workspace = self.create_encrypted_workspace(&box_dir)?;
             return Ok(BoxLayout {
                 rootfs_path: prebuilt_rootfs,
                 exec_socket_path: socket_dir.join("exec.sock"),
@@ -243,6 +277,9 @@ impl VmManager {
                 oci_config,
                 prefer_image_rootfs_metadata: false,
                 tee_instance_config,
+                image_digest: None,
+                measured_rootfs: None,
+                encrypted_workspace,
             });
         }
 
@@ -267,6 +304,7 @@ impl VmManager {
                 // concurrent box's cache prune won't evict it mid-mount (ENOENT).
                 self.mark_rootfs_cache_key(&box_dir, &cache_key);
                 let tee_instance_config = self.generate_tee_config(&box_dir)?;
+                let encrypted_workspace = self.create_encrypted_workspace(&box_dir)?;
                 return Ok(BoxLayout {
                     rootfs_path,
                     exec_socket_path: socket_dir.join("exec.sock"),
@@ -278,6 +316,9 @@ impl VmManager {
                     oci_config: None,
                     prefer_image_rootfs_metadata: !has_persistent_rootfs_generation,
                     tee_instance_config,
+                    image_digest: None,
+                    measured_rootfs: None,
+                    encrypted_workspace,
                 });
             }
         }
@@ -400,6 +441,9 @@ impl VmManager {
 
         // Generate TEE configuration if enabled
         let tee_instance_config = self.generate_tee_config(&box_dir)?;
+        let image_digest = oci_image.manifest_digest().to_string();
+        let measured_rootfs = self.build_measured_rootfs(&box_dir, &rootfs_path, &image_digest)?;
+        let encrypted_workspace = self.create_encrypted_workspace(&box_dir)?;
 
         Ok(BoxLayout {
             rootfs_path,
@@ -412,6 +456,9 @@ impl VmManager {
             oci_config,
             prefer_image_rootfs_metadata,
             tee_instance_config,
+            image_digest: Some(image_digest),
+            measured_rootfs,
+            encrypted_workspace,
         })
     }
 
@@ -641,9 +688,14 @@ impl VmManager {
     pub(crate) fn prepare_preserved_rootfs(&self) -> Result<PathBuf> {
         let box_dir = self.home_dir.join("boxes").join(&self.box_id);
         let rootfs = box_dir.join("rootfs");
-        let populated_rootfs = std::fs::read_dir(&rootfs)
-            .map(|mut entries| entries.next().is_some())
-            .unwrap_or(false);
+        // A quota-backed box mounts its volume at `rootfs`; the provider picks
+        // the volume's own cache-miss generation, so resolve the image lower.
+        let populated_rootfs = !box_dir
+            .join(crate::rootfs::quota::QUOTA_IMAGE_NAME)
+            .is_file()
+            && std::fs::read_dir(&rootfs)
+                .map(|mut entries| entries.next().is_some())
+                .unwrap_or(false);
         let lower = if populated_rootfs {
             rootfs.clone()
         } else if let Some(snapshot_lower) = snapshot_lower_dir(&box_dir) {
@@ -698,6 +750,7 @@ impl VmManager {
                 workload_id,
                 generation,
                 simulate,
+                ..
             } => {
                 // In simulation mode, skip hardware check and TEE config
                 // (the guest will generate simulated reports via A3S_TEE_SIMULATE env)
@@ -774,6 +827,61 @@ impl VmManager {
         }
     }
 
+    /// Build the dm-verity measured rootfs of a hardware TEE box.
+    ///
+    /// Simulated TEE boxes boot from the virtio-fs rootfs as before; their
+    /// reports prove nothing about the platform either.
+    #[cfg(target_os = "linux")]
+    fn build_measured_rootfs(
+        &self,
+        box_dir: &Path,
+        rootfs_path: &Path,
+        image_digest: &str,
+    ) -> Result<Option<crate::tee::MeasuredRootfs>> {
+        match &self.config.tee {
+            TeeConfig::SevSnp {
+                simulate: false, ..
+            } => crate::tee::build_measured_rootfs(box_dir, rootfs_path, image_digest).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn build_measured_rootfs(
+        &self,
+        _box_dir: &Path,
+        _rootfs_path: &Path,
+        _image_digest: &str,
+    ) -> Result<Option<crate::tee::MeasuredRootfs>> {
+        Ok(None)
+    }
+
+    /// Attach a fresh encrypted workspace disk if the box asked for one.
+    fn create_encrypted_workspace(
+        &self,
+        box_dir: &Path,
+    ) -> Result<Option<a3s_box_core::volume::BlockVolume>> {
+        let Some(size) = self.config.encrypted_workspace_bytes else {
+            return Ok(None);
+        };
+        if matches!(self.config.tee, TeeConfig::None) {
+            return Err(BoxError::ConfigError(
+                "An encrypted workspace requires a TEE box".to_string(),
+            ));
+        }
+        #[cfg(unix)]
+        {
+            crate::tee::create_encrypted_workspace_disk(box_dir, size).map(Some)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (box_dir, size);
+            Err(BoxError::ConfigError(
+                "An encrypted workspace requires TEE support".to_string(),
+            ))
+        }
+    }
+
     /// Find the guest init binary in common locations.
     ///
     /// Searches in order:
@@ -782,7 +890,7 @@ impl VmManager {
     /// 3. PATH
     ///
     /// The binary must be a Linux ELF executable since it runs inside the VM.
-    pub(crate) fn find_guest_init() -> Result<PathBuf> {
+    pub fn find_guest_init() -> Result<PathBuf> {
         let mut candidates = Self::find_binary_candidates("a3s-box-guest-init");
 
         // Prefer the cross-compiled musl-static build over any host build on
@@ -1088,6 +1196,12 @@ mod tests {
             pull_progress_fn: None,
             log_config: a3s_box_core::log::LogConfig::default(),
             resolved_execution_plan: None,
+            #[cfg(unix)]
+            reattest_config: crate::tee::ReattestConfig::default(),
+            #[cfg(unix)]
+            reattest_task: None,
+            #[cfg(unix)]
+            service_events_task: None,
         }
     }
 
@@ -1,15 +1,22 @@
 //! VM Manager - Lifecycle management for MicroVM instances.
 
+mod http_proxy;
 mod layout;
 mod network;
 mod ready;
 pub mod reap;
+#[cfg(unix)]
+mod reattest;
 mod sandbox;
+#[cfg(unix)]
+mod services;
 mod spec;
 #[cfg(windows)]
 mod windows_stop;
+mod workspace_template;
 
 pub(crate) use layout::{persistent_rootfs_generation_exists, runtime_socket_dir};
+pub use spec::{map_image_config, ConfigMapping, MappedVolume, MappedVolumeSource};
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -79,6 +86,14 @@ pub(crate) struct BoxLayout {
     pub(crate) prefer_image_rootfs_metadata: bool,
     /// TEE instance configuration (if TEE is enabled)
     pub(crate) tee_instance_config: Option<crate::vmm::TeeInstanceConfig>,
+    /// Manifest digest of the image pulled for this boot; `None` when the
+    /// rootfs came from a snapshot or the restore fast path.
+    pub(crate) image_digest: Option<String>,
+    /// dm-verity image the guest boots the workload from (hardware TEE only).
+    pub(crate) measured_rootfs: Option<crate::tee::MeasuredRootfs>,
+    /// Fresh LUKS2 disk the guest mounts at `/workspace` once its key is
+    /// released after attestation (TEE only).
+    pub(crate) encrypted_workspace: Option<a3s_box_core::volume::BlockVolume>,
 }
 
 #[cfg(target_os = "windows")]
@@ -352,6 +367,20 @@ pub struct VmManager {
 
     /// Backend-neutral resolution captured before any boot side effects.
     pub(crate) resolved_execution_plan: Option<ResolvedExecutionPlan>,
+
+    /// Periodic re-attestation settings for TEE boxes (set via
+    /// [`VmManager::set_reattest_config`]).
+    #[cfg(unix)]
+    pub(crate) reattest_config: crate::tee::ReattestConfig,
+
+    /// Background re-attestation task, running while a TEE box is up.
+    #[cfg(unix)]
+    pub(crate) reattest_task: Option<tokio::task::JoinHandle<()>>,
+
+    /// Background task forwarding guest service states as events, running
+    /// while a box with services is up.
+    #[cfg(unix)]
+    pub(crate) service_events_task: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl VmManager {
@@ -359,6 +388,8 @@ impl VmManager {
     pub fn new(config: BoxConfig, event_emitter: EventEmitter) -> Self {
         let box_id = uuid::Uuid::new_v4().to_string();
         let home_dir = a3s_box_core::dirs_home();
+        let rootfs_provider =
+            crate::rootfs::provider_for_disk_limit(config.resource_limits.disk_limit_bytes);
 
         Self {
             config,
@@ -378,7 +409,7 @@ impl VmManager {
             preserve_rootfs_on_boot_failure: false,
             #[cfg(unix)]
             tee: None,
-            rootfs_provider: crate::rootfs::default_provider(),
+            rootfs_provider,
             exec_socket_path: None,
             pty_socket_path: None,
             port_forward_socket_path: None,
@@ -387,12 +418,20 @@ impl VmManager {
             pull_progress_fn: None,
             log_config: a3s_box_core::log::LogConfig::default(),
             resolved_execution_plan: None,
+            #[cfg(unix)]
+            reattest_config: crate::tee::ReattestConfig::default(),
+            #[cfg(unix)]
+            reattest_task: None,
+            #[cfg(unix)]
+            service_events_task: None,
         }
     }
 
     /// Create a new VM manager with a specific box ID.
     pub fn with_box_id(config: BoxConfig, event_emitter: EventEmitter, box_id: String) -> Self {
         let home_dir = a3s_box_core::dirs_home();
+        let rootfs_provider =
+            crate::rootfs::provider_for_disk_limit(config.resource_limits.disk_limit_bytes);
 
         Self {
             config,
@@ -412,7 +451,7 @@ impl VmManager {
             preserve_rootfs_on_boot_failure: false,
             #[cfg(unix)]
             tee: None,
-            rootfs_provider: crate::rootfs::default_provider(),
+            rootfs_provider,
             exec_socket_path: None,
             pty_socket_path: None,
             port_forward_socket_path: None,
@@ -421,6 +460,12 @@ impl VmManager {
             pull_progress_fn: None,
             log_config: a3s_box_core::log::LogConfig::default(),
             resolved_execution_plan: None,
+            #[cfg(unix)]
+            reattest_config: crate::tee::ReattestConfig::default(),
+            #[cfg(unix)]
+            reattest_task: None,
+            #[cfg(unix)]
+            service_events_task: None,
         }
     }
 
@@ -538,6 +583,8 @@ impl VmManager {
     ) -> Self {
         let box_id = uuid::Uuid::new_v4().to_string();
         let home_dir = a3s_box_core::dirs_home();
+        let rootfs_provider =
+            crate::rootfs::provider_for_disk_limit(config.resource_limits.disk_limit_bytes);
         Self {
             config,
             box_id,
@@ -556,7 +603,7 @@ impl VmManager {
             preserve_rootfs_on_boot_failure: false,
             #[cfg(unix)]
             tee: None,
-            rootfs_provider: crate::rootfs::default_provider(),
+            rootfs_provider,
             exec_socket_path: None,
             pty_socket_path: None,
             port_forward_socket_path: None,
@@ -565,6 +612,12 @@ impl VmManager {
             pull_progress_fn: None,
             log_config: a3s_box_core::log::LogConfig::default(),
             resolved_execution_plan: None,
+            #[cfg(unix)]
+            reattest_config: crate::tee::ReattestConfig::default(),
+            #[cfg(unix)]
+            reattest_task: None,
+            #[cfg(unix)]
+            service_events_task: None,
         }
     }
 
@@ -1142,7 +1195,11 @@ impl VmManager {
         }
 
         // 1.5. Override /etc/resolv.conf with configured DNS
-        let resolv_content = a3s_box_core::dns::generate_resolv_conf(&self.config.dns);
+        let resolv_content = a3s_box_core::dns::generate_resolv_conf_with_options(
+            &self.config.dns,
+            &self.config.dns_search,
+            &self.config.dns_options,
+        );
         if let Err(e) = crate::oci::rootfs::write_guest_file(
             &layout.rootfs_path,
             "etc/resolv.conf",
@@ -1186,7 +1243,7 @@ impl VmManager {
                 }
             };
 
-            // Write /etc/hosts for DNS service discovery
+            // Write /etc/hosts and the embedded resolver's peer records
             match self.write_hosts_file(&layout, &network_name) {
                 Ok(()) => (),
                 Err(e) => {
@@ -1214,6 +1271,48 @@ impl VmManager {
                     .collect::<Vec<_>>()
                     .join(","),
             ));
+            spec.entrypoint.env.push((
+                "A3S_NET_DNS_RECORDS".to_string(),
+                a3s_box_core::dns::EMBEDDED_DNS_RECORDS_PATH.to_string(),
+            ));
+            if let Some(ip6) = net_config.ipv6_address {
+                spec.entrypoint.env.push((
+                    "A3S_NET_IP6".to_string(),
+                    format!("{}/{}", ip6, net_config.ipv6_prefix_len),
+                ));
+            }
+            if let Some(gateway6) = net_config.ipv6_gateway {
+                spec.entrypoint
+                    .env
+                    .push(("A3S_NET_GATEWAY6".to_string(), gateway6.to_string()));
+            }
+            if !self.config.network_policy.is_empty() {
+                let policy = match a3s_box_core::egress::EgressPolicy::parse_specs(
+                    &self.config.network_policy,
+                ) {
+                    Ok(policy) => policy,
+                    Err(e) => {
+                        self.cleanup_boot_failure().await;
+                        return Err(BoxError::ConfigError(e));
+                    }
+                };
+                spec.entrypoint.env.push((
+                    a3s_box_core::egress::EGRESS_POLICY_ENV.to_string(),
+                    policy.to_env_value(),
+                ));
+            }
+            if let Some(bandwidth) = self.config.network_bandwidth {
+                spec.entrypoint.env.push((
+                    a3s_box_core::egress::BANDWIDTH_ENV.to_string(),
+                    bandwidth.to_string(),
+                ));
+            }
+            if let Some(max_conns) = self.config.network_max_conns {
+                spec.entrypoint.env.push((
+                    a3s_box_core::egress::MAX_CONNS_ENV.to_string(),
+                    max_conns.to_string(),
+                ));
+            }
 
             spec.network = Some(net_config);
         }
@@ -1357,8 +1456,13 @@ impl VmManager {
                 self.box_id.clone(),
                 layout.attest_socket_path.clone(),
             )));
+            self.start_reattestation(layout.attest_socket_path.clone());
         }
 
+        // 5d. Follow the states of the services guest init supervises
+        #[cfg(unix)]
+        self.start_service_events(layout.exec_socket_path.clone());
+
         // 6. Update state to Ready
         *self.state.write().await = BoxState::Ready;
 
@@ -1423,6 +1527,11 @@ impl VmManager {
         timeout_ms: u64,
         preserve_rootfs: bool,
     ) -> Result<()> {
+        #[cfg(unix)]
+        self.stop_reattestation();
+        #[cfg(unix)]
+        self.stop_service_events();
+
         let mut state = self.state.write().await;
 
         if *state == BoxState::Stopped {
@@ -1800,6 +1909,24 @@ impl VmManager {
         Some(vm_metrics)
     }
 
+    /// Sample resource usage from inside the guest, updating the guest
+    /// Prometheus gauges if metrics are attached.
+    #[cfg(unix)]
+    pub async fn guest_metrics(
+        &self,
+        request: &a3s_box_core::GuestMetricsRequest,
+    ) -> Result<a3s_box_core::GuestMetrics> {
+        let client = self
+            .exec_client
+            .as_ref()
+            .ok_or_else(|| BoxError::ExecError("Exec client not connected".to_string()))?;
+        let metrics = client.metrics(request).await?;
+        if let Some(ref prom) = self.prom {
+            prom.record_guest_metrics(&self.box_id, &metrics);
+        }
+        Ok(metrics)
+    }
+
     /// Get the PID of the VM shim process.
     pub async fn pid(&self) -> Option<u32> {
         self.handler
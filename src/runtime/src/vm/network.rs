@@ -42,6 +42,9 @@ impl VmManager {
             gateway,
             prefix_len,
             mac_address: [0x02, 0x42, 0x0a, 0x59, 0x00, 0x02],
+            ipv6_address: None,
+            ipv6_gateway: None,
+            ipv6_prefix_len: 0,
             dns_servers,
         };
         self.net_manager = Some(Box::new(netproxy));
@@ -122,6 +125,10 @@ impl VmManager {
 
         let ip = endpoint.ip_address;
         let gateway = net_config.gateway;
+        // Endpoints set up by a CNI plugin have their interface in a
+        // namespace of their own; passt joins it (Linux only).
+        #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+        let netns = endpoint.netns.clone();
 
         // Parse prefix length from subnet CIDR
         let prefix_len: u8 = net_config
@@ -131,6 +138,10 @@ impl VmManager {
             .and_then(|s| s.parse().ok())
             .unwrap_or(24);
 
+        // Dual-stack networks also hand the box an IPv6 address.
+        let ipv6 = endpoint.ip6_address.zip(net_config.gateway6);
+        let ipv6_prefix_len = net_config.ipv6_prefix_len().unwrap_or(64);
+
         // Parse MAC address from hex string "02:42:0a:58:00:02" into [u8; 6]
         let mac_address = parse_mac(&endpoint.mac_address).map_err(|e| {
             BoxError::NetworkError(format!(
@@ -161,7 +172,17 @@ impl VmManager {
             // (next to the exec/PTY sockets), not under the box's 0700 home.
             let passt_socket_dir = self.socket_dir();
             let mut passt = crate::network::PasstManager::new(&passt_socket_dir);
-            passt.spawn(ip, gateway, prefix_len, &dns_servers, &self.config.port_map)?;
+            if let Some(netns) = &netns {
+                passt.set_netns(netns);
+            }
+            passt.spawn(
+                ip,
+                gateway,
+                prefix_len,
+                ipv6,
+                &dns_servers,
+                &self.config.port_map,
+            )?;
             let path = passt.socket_path().to_path_buf();
             self.net_manager = Some(Box::new(passt));
             tracing::info!(network = network_name, ip = %ip, gateway = %gateway, "Bridge networking configured via passt");
@@ -170,6 +191,12 @@ impl VmManager {
 
         #[cfg(target_os = "macos")]
         let (socket_path, net_stats_path, net_socket_fd, net_proxy_fd) = {
+            if ipv6.is_some() {
+                tracing::warn!(
+                    network = network_name,
+                    "The built-in netproxy is IPv4-only; the box's IPv6 address has no route"
+                );
+            }
             let mut netproxy = crate::network::NetProxyManager::new(&box_dir);
             netproxy.spawn(ip, gateway, prefix_len, &dns_servers, &self.config.port_map)?;
             let fd = netproxy.net_socket_fd();
@@ -194,6 +221,9 @@ impl VmManager {
             gateway,
             prefix_len,
             mac_address,
+            ipv6_address: ipv6.map(|(ip6, _)| ip6),
+            ipv6_gateway: ipv6.map(|(_, gateway6)| gateway6),
+            ipv6_prefix_len,
             dns_servers,
         })
     }
@@ -210,10 +240,13 @@ impl VmManager {
         ))
     }
 
-    /// Write /etc/hosts to the guest rootfs for DNS service discovery.
+    /// Write `/etc/hosts` and the embedded resolver's name records for a box
+    /// on a bridge network.
     ///
-    /// Looks up the box's own endpoint and all peer endpoints in the network,
-    /// then generates a hosts file mapping IPs to box names.
+    /// `/etc/hosts` carries only the box's own names and `--add-host` entries.
+    /// Peer names are served by the in-guest resolver from the records file,
+    /// which the CLI rewrites on connect/disconnect, so peers that join later
+    /// resolve without a restart.
     pub(crate) fn write_hosts_file(
         &self,
         layout: &super::BoxLayout,
@@ -234,11 +267,24 @@ impl VmManager {
         })?;
 
         let own_ip = endpoint.ip_address.to_string();
-        let own_names = self.hostname_aliases(Some(&endpoint.box_name));
+        let mut own_names = self.hostname_aliases(Some(&endpoint.box_name));
+        for alias in &endpoint.aliases {
+            if !own_names.contains(alias) {
+                own_names.push(alias.clone());
+            }
+        }
         let peers = net_config.peer_endpoints(&self.box_id);
         let add_hosts = self.parse_add_hosts()?;
 
-        self.write_hosts_content(layout, Some(&own_ip), &own_names, &peers, &add_hosts)?;
+        self.write_hosts_content(layout, Some(&own_ip), &own_names, &[], &add_hosts)?;
+
+        let records = a3s_box_core::dns::generate_dns_records(&own_ip, &own_names, &peers);
+        crate::oci::rootfs::write_guest_file(
+            &layout.rootfs_path,
+            a3s_box_core::dns::EMBEDDED_DNS_RECORDS_PATH.trim_start_matches('/'),
+            &records,
+        )?;
+        tracing::debug!(records = %records.trim(), "Configured embedded DNS records");
 
         Ok(())
     }
@@ -347,6 +393,9 @@ mod tests {
             oci_config: None,
             prefer_image_rootfs_metadata: false,
             tee_instance_config: None,
+            image_digest: None,
+            measured_rootfs: None,
+            encrypted_workspace: None,
         }
     }
 
@@ -0,0 +1,339 @@
+//! Periodic re-attestation of a running TEE box.
+//!
+//! A box attested once at boot says little about the guest hours later.
+//! With re-attestation enabled, boot spawns a task that challenges the guest
+//! with a fresh nonce on every interval, verifies the report against the
+//! box's attestation policy, and emits `box.attestation.ok` or
+//! `box.attestation.failed`. Once consecutive failures reach the configured
+//! threshold, the failure action runs: `Warn` logs, `Event` only emits, and
+//! `Stop` stops the VM.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use a3s_box_core::config::TeeConfig;
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::event::{events, BoxEvent, EventEmitter};
+use tokio::sync::RwLock;
+
+use crate::grpc::RaTlsAttestationClient;
+use crate::tee::{
+    AmdKdsClient, AttestationPolicy, FailureAction, ReattestConfig, ReattestState, RuntimeClaims,
+};
+use crate::vmm::{VmHandler, DEFAULT_SHUTDOWN_TIMEOUT_MS};
+
+use super::VmManager;
+
+/// How often the task wakes to see whether a check is due.
+const REATTEST_POLL: Duration = Duration::from_secs(1);
+
+/// Everything the background task needs, detached from the manager.
+struct Reattester {
+    box_id: String,
+    attest_socket_path: PathBuf,
+    box_dir: PathBuf,
+    allow_simulated: bool,
+    product: &'static str,
+    event_emitter: EventEmitter,
+    handler: Arc<RwLock<Option<Box<dyn VmHandler>>>>,
+}
+
+impl VmManager {
+    /// Enable periodic re-attestation for TEE boxes. Call before `boot()`;
+    /// ignored for boxes without a TEE.
+    pub fn set_reattest_config(&mut self, config: ReattestConfig) -> Result<()> {
+        crate::tee::reattest::validate_config(&config)?;
+        self.reattest_config = config;
+        Ok(())
+    }
+
+    /// Spawn the re-attestation task after a TEE box reaches `Ready`.
+    pub(crate) fn start_reattestation(&mut self, attest_socket_path: PathBuf) {
+        self.stop_reattestation();
+        if !self.reattest_config.enabled {
+            return;
+        }
+        let (allow_simulated, product) = match &self.config.tee {
+            TeeConfig::SevSnp {
+                simulate,
+                generation,
+                ..
+            } => (*simulate, AmdKdsClient::product_name(generation.as_str())),
+            TeeConfig::Tdx { .. } | TeeConfig::None => return,
+        };
+        let reattester = Reattester {
+            box_id: self.box_id.clone(),
+            attest_socket_path,
+            box_dir: self.home_dir.join("boxes").join(&self.box_id),
+            allow_simulated,
+            product,
+            event_emitter: self.event_emitter.clone(),
+            handler: self.handler.clone(),
+        };
+        let config = self.reattest_config.clone();
+        tracing::info!(
+            box_id = %self.box_id,
+            interval_secs = config.interval_secs,
+            "Periodic re-attestation enabled"
+        );
+        self.reattest_task = Some(tokio::spawn(reattester.run(config)));
+    }
+
+    /// Abort the re-attestation task, if one is running.
+    pub(crate) fn stop_reattestation(&mut self) {
+        if let Some(task) = self.reattest_task.take() {
+            task.abort();
+        }
+    }
+}
+
+impl Reattester {
+    async fn run(self, config: ReattestConfig) {
+        let mut state = ReattestState::new(config);
+        loop {
+            tokio::time::sleep(REATTEST_POLL).await;
+            if !state.is_check_due() {
+                continue;
+            }
+            match self.attest_once().await {
+                Ok(()) => {
+                    state.record_success();
+                    tracing::debug!(box_id = %self.box_id, "Re-attestation passed");
+                    self.event_emitter.emit(self.event(
+                        events::BOX_ATTESTATION_OK,
+                        &state,
+                        None,
+                        None,
+                    ));
+                }
+                Err(error) => {
+                    let action = state.record_failure();
+                    self.event_emitter.emit(self.event(
+                        events::BOX_ATTESTATION_FAILED,
+                        &state,
+                        Some(&error),
+                        state.is_failed().then_some(action),
+                    ));
+                    if !state.is_failed() || action == FailureAction::Warn {
+                        tracing::warn!(
+                            box_id = %self.box_id,
+                            consecutive_failures = state.consecutive_failures(),
+                            error = %error,
+                            "Re-attestation failed"
+                        );
+                    }
+                    if state.is_failed() && action == FailureAction::Stop {
+                        self.stop_box().await;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Challenge the guest with a fresh nonce and verify the answer against
+    /// the box's policy.
+    async fn attest_once(&self) -> Result<()> {
+        let mut policy = AttestationPolicy::load_for_box(&self.box_dir)?.unwrap_or_default();
+        if policy.expected_runtime_claims.is_none() {
+            policy.expected_runtime_claims =
+                RuntimeClaims::load_for_box(&self.box_dir)?.map(|claims| claims.digest_hex());
+        }
+
+        let client = RaTlsAttestationClient::new(&self.attest_socket_path);
+        let mut challenge = client.challenge(self.allow_simulated).await?;
+        AmdKdsClient::new(Some(AmdKdsClient::default_cache_dir()))
+            .complete_report(&mut challenge.report, self.product)
+            .await?;
+        let result = crate::tee::verify_attestation_with_time(
+            &challenge.report,
+            &challenge.nonce,
+            &policy,
+            self.allow_simulated,
+            Some(challenge.issued_at),
+        )?;
+        if result.verified {
+            Ok(())
+        } else {
+            Err(BoxError::AttestationError(result.failures.join("; ")))
+        }
+    }
+
+    /// Stop the VM after persistent attestation failure. The owner's
+    /// `destroy` still tears down host resources once it sees the exit.
+    async fn stop_box(&self) {
+        tracing::error!(
+            box_id = %self.box_id,
+            "Re-attestation failed persistently, stopping box"
+        );
+        if let Some(handler) = self.handler.write().await.as_mut() {
+            if let Err(error) =
+                handler.stop(super::default_stop_signal(), DEFAULT_SHUTDOWN_TIMEOUT_MS)
+            {
+                tracing::warn!(box_id = %self.box_id, error = %error, "Failed to stop box");
+            }
+        }
+    }
+
+    fn event(
+        &self,
+        key: &str,
+        state: &ReattestState,
+        error: Option<&BoxError>,
+        action: Option<FailureAction>,
+    ) -> BoxEvent {
+        let mut map = HashMap::new();
+        map.insert("box_id".to_string(), self.box_id.clone().into());
+        map.insert(
+            "consecutive_failures".to_string(),
+            state.consecutive_failures().into(),
+        );
+        map.insert(
+            "total_successes".to_string(),
+            state.total_successes().into(),
+        );
+        map.insert("total_failures".to_string(), state.total_failures().into());
+        if let Some(error) = error {
+            map.insert("error".to_string(), error.to_string().into());
+        }
+        if let Some(action) = action {
+            map.insert("action".to_string(), action_name(action).into());
+        }
+        BoxEvent::with_map(key, map)
+    }
+}
+
+fn action_name(action: FailureAction) -> &'static str {
+    match action {
+        FailureAction::Warn => "warn",
+        FailureAction::Event => "event",
+        FailureAction::Stop => "stop",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a3s_box_core::config::BoxConfig;
+    use a3s_box_core::event::EventPayload;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct RecordingHandler {
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl VmHandler for RecordingHandler {
+        fn stop(&mut self, _signal: i32, _timeout_ms: u64) -> Result<()> {
+            self.stopped.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn metrics(&self) -> crate::vmm::VmMetrics {
+            crate::vmm::VmMetrics::default()
+        }
+
+        fn is_running(&self) -> bool {
+            true
+        }
+
+        fn pid(&self) -> u32 {
+            42
+        }
+    }
+
+    fn tee_config() -> BoxConfig {
+        BoxConfig {
+            tee: TeeConfig::SevSnp {
+                workload_id: "test".to_string(),
+                generation: Default::default(),
+                simulate: true,
+                kbs_url: None,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reattestation_needs_tee_and_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("attest.sock");
+
+        let mut vm = VmManager::with_box_id(tee_config(), EventEmitter::new(16), "b".into());
+        vm.start_reattestation(socket.clone());
+        assert!(vm.reattest_task.is_none());
+
+        let enabled = ReattestConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let mut vm =
+            VmManager::with_box_id(BoxConfig::default(), EventEmitter::new(16), "b".into());
+        vm.set_reattest_config(enabled.clone()).unwrap();
+        vm.start_reattestation(socket.clone());
+        assert!(vm.reattest_task.is_none());
+
+        let mut vm = VmManager::with_box_id(tee_config(), EventEmitter::new(16), "b".into());
+        vm.set_reattest_config(enabled).unwrap();
+        vm.start_reattestation(socket);
+        assert!(vm.reattest_task.is_some());
+        vm.stop_reattestation();
+        assert!(vm.reattest_task.is_none());
+
+        assert!(vm
+            .set_reattest_config(ReattestConfig {
+                enabled: true,
+                interval_secs: 0,
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_persistent_failure_emits_events_and_stops_box() {
+        let dir = tempfile::tempdir().unwrap();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let emitter = EventEmitter::new(16);
+        let mut rx = emitter.subscribe();
+        let reattester = Reattester {
+            box_id: "b".to_string(),
+            // No guest is listening, so every challenge fails.
+            attest_socket_path: dir.path().join("attest.sock"),
+            box_dir: dir.path().to_path_buf(),
+            allow_simulated: true,
+            product: "Milan",
+            event_emitter: emitter,
+            handler: Arc::new(RwLock::new(Some(Box::new(RecordingHandler {
+                stopped: stopped.clone(),
+            })))),
+        };
+        let config = ReattestConfig {
+            enabled: true,
+            interval_secs: 1,
+            max_failures: 2,
+            failure_action: FailureAction::Stop,
+            grace_period_secs: 0,
+        };
+
+        tokio::time::timeout(Duration::from_secs(10), reattester.run(config))
+            .await
+            .unwrap();
+        assert!(stopped.load(Ordering::SeqCst));
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!(first.key, events::BOX_ATTESTATION_FAILED);
+        let EventPayload::Map(map) = first.payload else {
+            panic!("expected a map payload");
+        };
+        assert_eq!(map["consecutive_failures"], 1);
+        assert!(!map.contains_key("action"));
+
+        let second = rx.try_recv().unwrap();
+        let EventPayload::Map(map) = second.payload else {
+            panic!("expected a map payload");
+        };
+        assert_eq!(map["consecutive_failures"], 2);
+        assert_eq!(map["action"], "stop");
+    }
+}
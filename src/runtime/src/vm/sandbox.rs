@@ -100,7 +100,11 @@ impl VmManager {
                 &layout.rootfs_path,
             )?;
             let instance_prepare_start = std::time::Instant::now();
-            let resolv_content = a3s_box_core::dns::generate_resolv_conf(&self.config.dns);
+            let resolv_content = a3s_box_core::dns::generate_resolv_conf_with_options(
+                &self.config.dns,
+                &self.config.dns_search,
+                &self.config.dns_options,
+            );
             crate::oci::rootfs::write_guest_file(
                 &layout.rootfs_path,
                 "etc/resolv.conf",
@@ -173,6 +177,10 @@ impl VmManager {
                     .clone()
                     .unwrap_or_else(|| self.box_id.clone()),
                 init_environment: instance_spec.entrypoint.env.clone(),
+                additional_gids: resolve_additional_gids(
+                    &layout.rootfs_path,
+                    &self.config.group_add,
+                )?,
                 mounts,
                 tmpfs,
                 id_mappings,
@@ -620,6 +628,48 @@ fn maximum_account_ids(rootfs: &Path) -> Result<(u32, u32)> {
     Ok((maximum_uid, maximum_gid))
 }
 
+/// `--group-add` entries as gids: numbers as given, names looked up in the
+/// image's `/etc/group`, as guest init does on the MicroVM path. An unknown
+/// name is an error rather than a group the process silently lacks.
+fn resolve_additional_gids(rootfs: &Path, group_add: &[String]) -> Result<Vec<u32>> {
+    let mut group_file: Option<String> = None;
+    let mut gids = Vec::new();
+    for entry in group_add
+        .iter()
+        .map(|entry| entry.trim())
+        .filter(|entry| !entry.is_empty())
+    {
+        let gid = match entry.parse::<u32>() {
+            Ok(gid) => gid,
+            Err(_) => {
+                if group_file.is_none() {
+                    group_file = Some(
+                        crate::oci::rootfs::read_guest_file_to_string(rootfs, "etc/group")?
+                            .unwrap_or_default(),
+                    );
+                }
+                group_file
+                    .as_deref()
+                    .unwrap_or_default()
+                    .lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .map(|line| line.split(':').collect::<Vec<_>>())
+                    .find(|fields| fields.len() >= 3 && fields[0] == entry)
+                    .and_then(|fields| fields[2].parse::<u32>().ok())
+                    .ok_or_else(|| {
+                        BoxError::ConfigError(format!(
+                            "--group-add group {entry:?} is not in the image's /etc/group"
+                        ))
+                    })?
+            }
+        };
+        if !gids.contains(&gid) {
+            gids.push(gid);
+        }
+    }
+    Ok(gids)
+}
+
 fn maximum_process_ids(rootfs: &Path, environment: &[(String, String)]) -> Result<(u32, u32)> {
     let user = if let Some(path) = environment
         .iter()
@@ -768,6 +818,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn group_add_names_resolve_from_image_group_file() {
+        let rootfs = tempfile::tempdir().unwrap();
+        std::fs::create_dir(rootfs.path().join("etc")).unwrap();
+        std::fs::write(
+            rootfs.path().join("etc/group"),
+            "root:x:0:\n# video:x:1:\nvideo:x:44:app\ndocker:x:999:\n",
+        )
+        .unwrap();
+        let group_add = |entries: &[&str]| {
+            resolve_additional_gids(
+                rootfs.path(),
+                &entries.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            group_add(&["video", "1234", "docker", "44"]).unwrap(),
+            vec![44, 1234, 999]
+        );
+        let error = group_add(&["video", "plugdev"]).unwrap_err().to_string();
+        assert!(error.contains("\"plugdev\" is not in the image's /etc/group"));
+    }
+
     #[cfg(unix)]
     #[test]
     fn mount_destination_rejects_symlink_parent() {
@@ -0,0 +1,110 @@
+//! Host side of guest service supervision.
+//!
+//! Guest init supervises the services a box declares and streams their state
+//! changes over the exec channel. Once such a box is ready, boot spawns a
+//! task that follows the stream and re-emits each change as a
+//! `box.service.state` event carrying the box ID and the service status.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::event::{events, BoxEvent};
+use a3s_box_core::service::ServiceStatus;
+
+use crate::grpc::ExecClient;
+
+use super::VmManager;
+
+impl VmManager {
+    /// Spawn the task forwarding guest service states as events; a no-op
+    /// for boxes without services.
+    pub(crate) fn start_service_events(&mut self, exec_socket_path: PathBuf) {
+        self.stop_service_events();
+        if self.config.services.is_empty() {
+            return;
+        }
+        let box_id = self.box_id.clone();
+        let event_emitter = self.event_emitter.clone();
+        self.service_events_task = Some(tokio::spawn(async move {
+            let result = match ExecClient::connect(&exec_socket_path).await {
+                Ok(client) => {
+                    client
+                        .watch_services(true, |status| {
+                            event_emitter.emit(service_event(&box_id, &status));
+                        })
+                        .await
+                }
+                Err(error) => Err(error),
+            };
+            // The stream ends when the VM stops; an error before then means
+            // the guest cannot report service states at all.
+            if let Err(error) = result {
+                tracing::warn!(
+                    box_id = %box_id,
+                    error = %error,
+                    "Stopped following guest service states"
+                );
+            }
+        }));
+    }
+
+    /// Abort the service event task, if one is running.
+    pub(crate) fn stop_service_events(&mut self) {
+        if let Some(task) = self.service_events_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Current state of each service guest init supervises.
+    pub async fn service_statuses(&self) -> Result<Vec<ServiceStatus>> {
+        let client = self
+            .exec_client
+            .as_ref()
+            .ok_or_else(|| BoxError::ExecError("Exec client not connected".to_string()))?;
+        let mut statuses = Vec::new();
+        client
+            .watch_services(false, |status| statuses.push(status))
+            .await?;
+        Ok(statuses)
+    }
+}
+
+fn service_event(box_id: &str, status: &ServiceStatus) -> BoxEvent {
+    let mut fields: HashMap<String, serde_json::Value> = match serde_json::to_value(status) {
+        Ok(serde_json::Value::Object(fields)) => fields.into_iter().collect(),
+        _ => HashMap::new(),
+    };
+    fields.insert("box_id".to_string(), box_id.into());
+    BoxEvent::with_map(events::BOX_SERVICE_STATE, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a3s_box_core::event::EventPayload;
+    use a3s_box_core::service::ServiceState;
+
+    #[test]
+    fn test_service_event_carries_box_and_status() {
+        let event = service_event(
+            "box-1",
+            &ServiceStatus {
+                name: "proxy".to_string(),
+                state: ServiceState::Restarting,
+                pid: None,
+                restarts: 2,
+                exit_code: Some(137),
+                message: None,
+            },
+        );
+        assert_eq!(event.key, "box.service.state");
+        let EventPayload::Map(fields) = event.payload else {
+            panic!("expected a map payload");
+        };
+        assert_eq!(fields["box_id"], "box-1");
+        assert_eq!(fields["name"], "proxy");
+        assert_eq!(fields["state"], "restarting");
+        assert_eq!(fields["exit_code"], 137);
+    }
+}
@@ -1,14 +1,23 @@
 //! Instance spec building — entrypoint resolution, volume mounts, OCI config.
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use a3s_box_core::config::{validate_vcpu_count, TeeConfig};
+use a3s_box_core::config::{validate_vcpu_count, BoxConfig, TeeConfig};
+#[cfg(unix)]
+use a3s_box_core::entropy::{ENTROPY_SEED_ENV, ENTROPY_SEED_LEN, RUNTIME_ENTROPY_SEED_PATH};
 use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::forward::FORWARD_SOCKET_FILE;
 use a3s_box_core::guest_exec::{
     GuestExecConfig, MAX_RUNTIME_EXEC_CONFIG_BYTES, RUNTIME_EXEC_CONFIG_PATH,
 };
+use a3s_box_core::http_proxy::{
+    HttpProxyInstanceConfig, HttpProxyMode, GUEST_PROXY_ADDR, HTTP_AUDIT_FILE, HTTP_PROXY_ENV,
+};
 use a3s_box_core::rootfs_metadata::RUNTIME_ENV_PATH;
 
+use serde::{Deserialize, Serialize};
+
 use crate::oci::OciImageConfig;
 use crate::rootfs::GUEST_WORKDIR;
 use crate::vmm::{Entrypoint, FsMount, InstanceSpec};
@@ -18,6 +27,11 @@ use super::{fnv1a_hash, BoxLayout, VmManager};
 const SBIN_INIT: &str = "/sbin/init";
 const USR_SBIN_INIT: &str = "/usr/sbin/init";
 
+/// Writable scratch space a `--read-only` rootfs gets, unless `--tmpfs` or a
+/// volume mounts at or below the path. Guest init mounts tmpfs after volumes,
+/// so a scratch tmpfs would hide a volume beneath it.
+const READ_ONLY_SCRATCH_TMPFS: &[&str] = &["/tmp:mode=1777", "/run:mode=755"];
+
 #[derive(Debug)]
 struct ParsedVolumeMount {
     host_path: PathBuf,
@@ -25,11 +39,145 @@ struct ParsedVolumeMount {
     read_only: bool,
 }
 
+/// How an image config and a box config combine into the container's main
+/// process: what runs, with which environment, where, as whom, and what gets
+/// mounted. It is the image-dependent part of an [`InstanceSpec`], free of
+/// host paths, so it can be checked against golden files and printed by
+/// `a3s-box debug map-config`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigMapping {
+    /// Program run as the container main process.
+    pub executable: String,
+    /// Arguments after the executable.
+    pub args: Vec<String>,
+    /// Container environment as `KEY=VALUE`, in the order it is staged.
+    pub env: Vec<String>,
+    /// Working directory of the main process.
+    pub workdir: String,
+    /// User the main process runs as; `None` means the image default (root).
+    pub user: Option<String>,
+    /// Guest mount points, user volumes first.
+    pub volumes: Vec<MappedVolume>,
+    /// tmpfs mounts (`path[:options]`).
+    pub tmpfs: Vec<String>,
+}
+
+/// A guest mount point in a [`ConfigMapping`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappedVolume {
+    /// Mount point in the guest.
+    pub guest_path: String,
+    /// Where the mount comes from.
+    pub source: MappedVolumeSource,
+    /// Mounted read-only.
+    pub read_only: bool,
+}
+
+/// Origin of a [`MappedVolume`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MappedVolumeSource {
+    /// A `-v host:guest` bind.
+    Bind,
+    /// An anonymous volume created for an image `VOLUME` directive.
+    Anonymous,
+}
+
+/// Map `oci_config` and `config` to the container process the runtime would
+/// boot, using the same resolution as the boot path.
+pub fn map_image_config(
+    config: &BoxConfig,
+    oci_config: Option<&OciImageConfig>,
+) -> Result<ConfigMapping> {
+    let (executable, args, env) = VmManager::resolve_process(config, oci_config);
+    let parsed_volumes = config
+        .volumes
+        .iter()
+        .map(|volume| VmManager::parse_volume_spec(volume))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mounted: std::collections::HashSet<String> = parsed_volumes
+        .iter()
+        .map(|volume| volume.guest_path.clone())
+        .chain(config.block_volumes.iter().map(|v| v.target.clone()))
+        .collect();
+    let mut volumes: Vec<MappedVolume> = parsed_volumes
+        .iter()
+        .map(|volume| MappedVolume {
+            guest_path: volume.guest_path.clone(),
+            source: MappedVolumeSource::Bind,
+            read_only: volume.read_only,
+        })
+        .collect();
+    if let Some(oci_config) = oci_config {
+        for vol_path in &oci_config.volumes {
+            let covered = parsed_volumes.iter().any(|v| &v.guest_path == vol_path)
+                || config.block_volumes.iter().any(|v| &v.target == vol_path);
+            if !covered {
+                volumes.push(MappedVolume {
+                    guest_path: vol_path.clone(),
+                    source: MappedVolumeSource::Anonymous,
+                    read_only: false,
+                });
+            }
+        }
+    }
+
+    Ok(ConfigMapping {
+        executable,
+        args,
+        env: env
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect(),
+        workdir: VmManager::effective_workdir(config, oci_config),
+        user: VmManager::effective_user(config, oci_config),
+        volumes,
+        tmpfs: guest_tmpfs(config, &mounted),
+    })
+}
+
+/// The box's tmpfs mounts plus, for a read-only rootfs, the scratch mounts
+/// that no `--tmpfs` or path in `mounted` sits at or below.
+fn guest_tmpfs(config: &BoxConfig, mounted: &std::collections::HashSet<String>) -> Vec<String> {
+    let mut tmpfs = config.tmpfs.clone();
+    if !config.read_only {
+        return tmpfs;
+    }
+    let mount_path = |spec: &str| {
+        let path = spec.split_once(':').map_or(spec, |(path, _)| path);
+        path.trim_end_matches('/').to_string()
+    };
+    for scratch in READ_ONLY_SCRATCH_TMPFS {
+        let path = mount_path(scratch);
+        let below = |target: &str| {
+            target == path
+                || target
+                    .strip_prefix(path.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        };
+        let covered = mounted.iter().any(|m| below(m.trim_end_matches('/')))
+            || config.tmpfs.iter().any(|spec| below(&mount_path(spec)));
+        if !covered {
+            tmpfs.push(scratch.to_string());
+        }
+    }
+    tmpfs
+}
+
 /// Read an environment variable, returning `None` if unset or empty.
 fn env_nonempty(name: &str) -> Option<String> {
     std::env::var(name).ok().filter(|v| !v.is_empty())
 }
 
+/// Fresh bytes from the host RNG for the guest to seed its CRNG with.
+#[cfg(unix)]
+fn entropy_seed() -> std::result::Result<Vec<u8>, ring::error::Unspecified> {
+    let mut seed = vec![0u8; ENTROPY_SEED_LEN];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut seed)?;
+    Ok(seed)
+}
+
 fn secure_guest_control_file(path: &Path) -> Result<()> {
     #[cfg(unix)]
     {
@@ -83,6 +231,12 @@ impl VmManager {
         let user_guest_paths: std::collections::HashSet<String> = parsed_volumes
             .iter()
             .map(|volume| volume.guest_path.clone())
+            .chain(
+                self.config
+                    .block_volumes
+                    .iter()
+                    .map(|volume| volume.target.clone()),
+            )
             .collect();
         let mut anon_vol_offset = self.config.volumes.len();
         let mut seen_anonymous_volumes = std::collections::HashSet::new();
@@ -158,28 +312,66 @@ impl VmManager {
         let workdir = Self::effective_workdir(&self.config, layout.oci_config.as_ref());
         let user = Self::effective_user(&self.config, layout.oci_config.as_ref());
 
+        // Guest init relays the workload's proxy traffic, so the proxy is only
+        // wired up when it is PID 1.
+        let mut http_proxy = None;
+        // Host devices the shim relays to guest init over vsock.
+        let mut device_proxies = Vec::new();
+        // Guest kernel tuning; guest init loads the modules, then applies the
+        // sysctls, at boot.
+        let mut sysctls = HashMap::new();
+        for (name, value) in &self.config.sysctls {
+            a3s_box_core::kernel::validate_sysctl_name(name).map_err(BoxError::ConfigError)?;
+            sysctls.insert(name.clone(), value.clone());
+        }
+        for module in &self.config.kernel_modules {
+            a3s_box_core::kernel::validate_kernel_module_name(module)
+                .map_err(BoxError::ConfigError)?;
+        }
+
         // Build entrypoint
         let mut entrypoint = if let Some(guest_init_exec) = guest_init_exec {
             // Guest init is PID 1. Pass fixed control pointers inline and stage
             // user-controlled process and environment data in the rootfs.
-            let (exec, args, mut container_env) = match &layout.oci_config {
-                Some(oci_config) => {
-                    let (exec, args) = Self::resolve_oci_entrypoint(
-                        oci_config,
-                        &self.config.cmd,
-                        self.config.entrypoint_override.as_deref(),
-                    );
-                    (exec, args, oci_config.env.clone())
-                }
-                None => {
-                    let (exec, args) = Self::resolve_config_entrypoint(
-                        &self.config.cmd,
-                        self.config.entrypoint_override.as_deref(),
-                    );
-                    (exec, args, vec![])
-                }
-            };
-            a3s_box_core::env::merge_env_pairs(&mut container_env, &self.config.extra_env);
+            let (exec, args, mut container_env) =
+                Self::resolve_process(&self.config, layout.oci_config.as_ref());
+
+            if let Some(mode) = self.config.http_proxy {
+                let box_dir = self.home_dir.join("boxes").join(&self.box_id);
+                super::http_proxy::apply_proxy_env(&mut container_env);
+                let ca = match mode {
+                    HttpProxyMode::Audit => None,
+                    HttpProxyMode::Mitm => {
+                        let ca = super::http_proxy::ensure_proxy_ca(&box_dir, &self.box_id)?;
+                        super::http_proxy::install_proxy_ca(
+                            &layout.rootfs_path,
+                            &ca,
+                            &mut container_env,
+                        )?;
+                        Some(ca)
+                    }
+                };
+                let socket_dir = layout
+                    .exec_socket_path
+                    .parent()
+                    .unwrap_or(&box_dir)
+                    .to_path_buf();
+                http_proxy = Some(HttpProxyInstanceConfig {
+                    mode,
+                    socket_path: socket_dir.join("http-proxy.sock"),
+                    audit_log: box_dir.join(HTTP_AUDIT_FILE),
+                    ca,
+                });
+            }
+
+            // Tell in-guest agents which key broker the box was configured with.
+            if let TeeConfig::SevSnp {
+                kbs_url: Some(kbs_url),
+                ..
+            } = &self.config.tee
+            {
+                container_env.push(("A3S_KBS_URL".to_string(), kbs_url.clone()));
+            }
 
             // Stage process configuration in the guest rootfs instead of adding
             // user-controlled exec/argv strings to libkrun's kernel command line.
@@ -236,7 +428,11 @@ impl VmManager {
             // Prototype: deferred-main-spawn. If the host set BOX_DEFERRED_MAIN=1,
             // tell guest init to boot IDLE; the runtime then sends a spawn-main
             // control frame post-readiness to run the command above as the main.
+            // A box awaiting secrets or an encrypted workspace boots IDLE the
+            // same way; its host sends spawn-main once they are provisioned.
             if self.config.deferred_main
+                || self.config.await_secrets
+                || layout.encrypted_workspace.is_some()
                 || std::env::var("BOX_DEFERRED_MAIN")
                     .map(|v| v == "1")
                     .unwrap_or(false)
@@ -279,6 +475,57 @@ impl VmManager {
                 ));
             }
 
+            // Seed the guest CRNG from the host RNG so early TLS and key
+            // generation do not block. A TEE guest does not take entropy from
+            // the host it is protected from.
+            #[cfg(unix)]
+            if matches!(self.config.tee, TeeConfig::None) {
+                match entropy_seed() {
+                    Ok(seed) => {
+                        let host_path = crate::oci::rootfs::replace_guest_file_no_follow(
+                            &layout.rootfs_path,
+                            RUNTIME_ENTROPY_SEED_PATH.trim_start_matches('/'),
+                            seed,
+                        )?;
+                        secure_guest_control_file(&host_path)?;
+                        env.push((
+                            ENTROPY_SEED_ENV.to_string(),
+                            RUNTIME_ENTROPY_SEED_PATH.to_string(),
+                        ));
+                    }
+                    // The guest still has virtio-rng; it just may start slower.
+                    Err(error) => tracing::warn!(
+                        box_id = %self.box_id,
+                        error = %error,
+                        "Failed to generate a guest entropy seed"
+                    ),
+                }
+            }
+
+            // Guest init binds the claims digest into every attestation report,
+            // folding in the root hash of the measured rootfs it opens.
+            #[cfg(unix)]
+            if let Some(claims) =
+                self.record_runtime_claims(layout, &exec_config, &container_env, &parsed_volumes)?
+            {
+                env.push((
+                    crate::tee::RUNTIME_CLAIMS_ENV.to_string(),
+                    claims.launch_digest_hex(),
+                ));
+            }
+            if let Some(measured) = &layout.measured_rootfs {
+                env.push((
+                    a3s_box_core::tee::VERITY_ROOTFS_ENV.to_string(),
+                    measured.verity.to_env_value(),
+                ));
+            }
+            if layout.encrypted_workspace.is_some() {
+                env.push((
+                    a3s_box_core::tee::ENCRYPTED_WORKSPACE_ENV.to_string(),
+                    "1".to_string(),
+                ));
+            }
+
             // Pass user volume mounts to guest init for mounting inside the VM.
             // Format: BOX_VOL_<index>=<tag>:<guest_path>[:ro]
             for (i, volume) in parsed_volumes.iter().enumerate() {
@@ -314,27 +561,107 @@ impl VmManager {
                 }
             }
 
-            // Pass tmpfs mounts to guest init.
+            // Pass tmpfs mounts to guest init, including the scratch mounts
+            // a read-only rootfs needs.
             // Format: BOX_TMPFS_<index>=<path>[:<options>]
-            for (i, tmpfs_spec) in self.config.tmpfs.iter().enumerate() {
-                env.push((format!("BOX_TMPFS_{}", i), tmpfs_spec.clone()));
+            for (i, tmpfs_spec) in guest_tmpfs(&self.config, &user_guest_paths)
+                .into_iter()
+                .enumerate()
+            {
+                env.push((format!("BOX_TMPFS_{}", i), tmpfs_spec));
+            }
+
+            // Pass block volumes to guest init.
+            // Format: BOX_BLOCK_<index>=<block_id>:<fstype>:<ro|rw>:<target>
+            for (i, volume) in self.config.block_volumes.iter().enumerate() {
+                env.push((
+                    format!("{}{}", a3s_box_core::volume::BLOCK_VOLUME_ENV_PREFIX, i),
+                    volume.to_env_value(),
+                ));
+            }
+
+            // Pass shared host sockets to guest init; the shim bridges each
+            // one's vsock port to its host socket.
+            // Format: BOX_SOCKET_<index>=<target>
+            for (i, mount) in self.config.socket_mounts.iter().enumerate() {
+                env.push((
+                    format!("{}{}", a3s_box_core::volume::SOCKET_MOUNT_ENV_PREFIX, i),
+                    mount.target.clone(),
+                ));
             }
 
-            // Pass pod sysctls to guest init.
-            // Format: BOX_SYSCTL_<index>=<name>=<value>
-            for (i, (name, value)) in self.config.sysctls.iter().enumerate() {
-                env.push((format!("BOX_SYSCTL_{}", i), format!("{}={}", name, value)));
+            // Pass allowlisted device nodes to guest init.
+            // Format: BOX_DEVICE_<index>=<path>:<major>:<minor>:<mode>
+            let (nodes, proxied) = a3s_box_core::device::partition_devices(&self.config.devices)
+                .map_err(BoxError::ConfigError)?;
+            for (i, node) in nodes.iter().enumerate() {
+                env.push((
+                    format!("{}{}", a3s_box_core::device::DEVICE_ENV_PREFIX, i),
+                    node.to_env_value(),
+                ));
+            }
+
+            // Pass proxied host devices to guest init; the shim serves each
+            // one's vsock port from the host device.
+            // Format: BOX_DEVICE_PROXY_<index>=<path>:<ro|rw>
+            if !proxied.is_empty() && cfg!(windows) {
+                return Err(BoxError::ConfigError(
+                    "Host device passthrough is not supported on Windows".to_string(),
+                ));
+            }
+            for (i, device) in proxied.iter().enumerate() {
+                a3s_box_core::device::check_host_device(&device.source)
+                    .map_err(BoxError::ConfigError)?;
+                env.push((
+                    format!("{}{}", a3s_box_core::device::DEVICE_PROXY_ENV_PREFIX, i),
+                    device.to_env_value(),
+                ));
+            }
+            device_proxies = proxied;
+
+            // Pass kernel modules and sysctls to guest init.
+            // Format: BOX_KERNEL_MODULE_<index>=<name>, BOX_SYSCTL_<index>=<name>=<value>
+            env.extend(a3s_box_core::kernel::guest_env(
+                &sysctls,
+                &self.config.kernel_modules,
+            ));
+
+            // Pass security configuration to guest init. Egress policy and
+            // limits are only as strong as the workload's inability to flush
+            // the guest firewall and qdisc, so the box loses NET_ADMIN.
+            let mut cap_drop = self.config.cap_drop.clone();
+            let egress_controls = !self.config.network_policy.is_empty()
+                || self.config.network_bandwidth.is_some()
+                || self.config.network_max_conns.is_some();
+            if egress_controls
+                && !cap_drop
+                    .iter()
+                    .any(|cap| matches!(cap.to_ascii_uppercase().as_str(), "ALL" | "NET_ADMIN"))
+            {
+                cap_drop.push("NET_ADMIN".to_string());
+            }
+            if http_proxy.is_some() {
+                env.push((HTTP_PROXY_ENV.to_string(), GUEST_PROXY_ADDR.to_string()));
             }
 
-            // Pass security configuration to guest init
             let security_config = a3s_box_core::SecurityConfig::from_options(
                 &self.config.security_opt,
                 &self.config.cap_add,
-                &self.config.cap_drop,
+                &cap_drop,
                 self.config.privileged,
             );
             env.extend(security_config.to_env_vars());
 
+            // Supplementary groups (`--group-add`). Names are resolved inside
+            // the guest against the image's /etc/group; guest-init applies the
+            // result with setgroups before dropping to the container user.
+            if !self.config.group_add.is_empty() {
+                env.push((
+                    "A3S_SEC_SUPPLEMENTAL_GROUPS".to_string(),
+                    self.config.group_add.join(","),
+                ));
+            }
+
             // Process-count cap (`--pids-limit`). Unlike `--memory`/`--cpus`
             // (enforced by sizing the microVM itself), a pids cap has no
             // VM-boundary equivalent, so guest-init enforces it via an in-guest
@@ -406,41 +733,19 @@ impl VmManager {
             }
         } else {
             // No guest init — exec the container entrypoint directly as PID 1
-            match &layout.oci_config {
-                Some(oci_config) => {
-                    let (executable, args) = Self::resolve_oci_entrypoint(
-                        oci_config,
-                        &self.config.cmd,
-                        self.config.entrypoint_override.as_deref(),
-                    );
-                    let mut env = oci_config.env.clone();
-                    a3s_box_core::env::merge_env_pairs(&mut env, &self.config.extra_env);
-
-                    tracing::debug!(
-                        executable = %executable,
-                        args = ?args,
-                        env_count = env.len(),
-                        workdir = ?oci_config.working_dir,
-                        "Using OCI image entrypoint directly"
-                    );
-
-                    Entrypoint {
-                        executable,
-                        args,
-                        env,
-                    }
-                }
-                None => {
-                    let (executable, args) = Self::resolve_config_entrypoint(
-                        &self.config.cmd,
-                        self.config.entrypoint_override.as_deref(),
-                    );
-                    Entrypoint {
-                        executable,
-                        args,
-                        env: self.config.extra_env.clone(),
-                    }
-                }
+            let (executable, args, env) =
+                Self::resolve_process(&self.config, layout.oci_config.as_ref());
+            tracing::debug!(
+                executable = %executable,
+                args = ?args,
+                env_count = env.len(),
+                workdir = %workdir,
+                "Using container entrypoint directly"
+            );
+            Entrypoint {
+                executable,
+                args,
+                env,
             }
         };
 
@@ -495,6 +800,17 @@ impl VmManager {
             ));
         }
 
+        if !self.config.services.is_empty() {
+            a3s_box_core::service::service_start_order(&self.config.services)
+                .map_err(BoxError::ConfigError)?;
+            let services = serde_json::to_string(&self.config.services).map_err(|error| {
+                BoxError::SerializationError(format!("Failed to encode services: {error}"))
+            })?;
+            entrypoint
+                .env
+                .push((a3s_box_core::service::SERVICES_ENV.to_string(), services));
+        }
+
         // The CLI validates this up front; this also guards compose, CRI, SDK,
         // and direct runtime callers against unsupported platform sizing.
         validate_vcpu_count(self.config.resources.vcpus).map_err(BoxError::ConfigError)?;
@@ -513,7 +829,32 @@ impl VmManager {
             pty_socket_path: layout.pty_socket_path.clone(),
             attest_socket_path: layout.attest_socket_path.clone(),
             port_forward_socket_path: layout.port_forward_socket_path.clone(),
+            // Guest init runs the forwarder; Windows shims have no vsock bridge.
+            forward_socket_path: if has_guest_init && !cfg!(windows) {
+                layout.exec_socket_path.with_file_name(FORWARD_SOCKET_FILE)
+            } else {
+                PathBuf::new()
+            },
             fs_mounts,
+            block_devices: self
+                .config
+                .block_volumes
+                .iter()
+                .cloned()
+                .chain(
+                    layout
+                        .measured_rootfs
+                        .iter()
+                        .flat_map(|measured| measured.block_devices()),
+                )
+                .chain(layout.encrypted_workspace.iter().cloned())
+                .collect(),
+            socket_mounts: if has_guest_init {
+                self.config.socket_mounts.clone()
+            } else {
+                Vec::new()
+            },
+            device_proxies,
             entrypoint,
             console_output: layout.console_output.clone(),
             workdir,
@@ -525,11 +866,17 @@ impl VmManager {
             network: None, // Network config is set by CLI when --network is specified
             resource_limits: self.config.resource_limits.clone(),
             log_config: self.log_config.clone(),
+            http_proxy,
+            sysctls,
+            modules: self.config.kernel_modules.clone(),
+            kernel_path: self.config.kernel.clone(),
             // KSM page-merging: config field, or the A3S_BOX_KSM env override.
             ksm: self.config.ksm
                 || std::env::var("A3S_BOX_KSM")
                     .map(|v| matches!(v.as_str(), "1" | "true" | "yes" | "on"))
                     .unwrap_or(false),
+            hugepages: self.config.hugepages,
+            mem_prealloc: self.config.mem_prealloc,
             // Snapshot-fork (per-VM): config field, or the env override (single-VM
             // `run`). The pool / fork daemon set these per-VM via config so one
             // process can drive a different template/restore per VM.
@@ -551,6 +898,33 @@ impl VmManager {
         })
     }
 
+    /// Resolve the container main process: executable, args, and environment
+    /// (image values overridden by the box's extra env).
+    fn resolve_process(
+        config: &BoxConfig,
+        oci_config: Option<&OciImageConfig>,
+    ) -> (String, Vec<String>, Vec<(String, String)>) {
+        let (executable, args, mut env) = match oci_config {
+            Some(oci_config) => {
+                let (executable, args) = Self::resolve_oci_entrypoint(
+                    oci_config,
+                    &config.cmd,
+                    config.entrypoint_override.as_deref(),
+                );
+                (executable, args, oci_config.env.clone())
+            }
+            None => {
+                let (executable, args) = Self::resolve_config_entrypoint(
+                    &config.cmd,
+                    config.entrypoint_override.as_deref(),
+                );
+                (executable, args, vec![])
+            }
+        };
+        a3s_box_core::env::merge_env_pairs(&mut env, &config.extra_env);
+        (executable, args, env)
+    }
+
     /// Resolve the executable and args from an OCI image config.
     ///
     /// Follows Docker semantics:
@@ -685,6 +1059,69 @@ impl VmManager {
             })
     }
 
+    /// Record the runtime claims of a TEE box in its box directory.
+    ///
+    /// Returns `None` without TEE, or when the rootfs did not come from a
+    /// pulled image (snapshot restore, prebuilt rootfs) and so has no image
+    /// digest to claim.
+    #[cfg(unix)]
+    fn record_runtime_claims(
+        &self,
+        layout: &BoxLayout,
+        exec_config: &GuestExecConfig,
+        container_env: &[(String, String)],
+        volumes: &[ParsedVolumeMount],
+    ) -> Result<Option<crate::tee::RuntimeClaims>> {
+        use crate::tee::{ClaimedConfig, RuntimeClaims, BOX_RUNTIME_CLAIMS_FILE};
+
+        let TeeConfig::SevSnp { workload_id, .. } = &self.config.tee else {
+            return Ok(None);
+        };
+        let box_dir = self.home_dir.join("boxes").join(&self.box_id);
+        let Some(image_digest) = &layout.image_digest else {
+            tracing::warn!(
+                "Rootfs has no image digest; attestation reports carry no runtime claims"
+            );
+            // Claims from an earlier boot would no longer match the reports.
+            let _ = std::fs::remove_file(box_dir.join(BOX_RUNTIME_CLAIMS_FILE));
+            return Ok(None);
+        };
+
+        let config = ClaimedConfig {
+            executable: exec_config.executable.clone(),
+            args: exec_config.args.clone(),
+            env: container_env.iter().cloned().collect(),
+            workdir: exec_config.workdir.clone(),
+            user: exec_config.user.clone(),
+            vcpus: self.config.resources.vcpus,
+            memory_mb: self.config.resources.memory_mb,
+            mounts: volumes
+                .iter()
+                .map(|volume| {
+                    if volume.read_only {
+                        format!("{}:ro", volume.guest_path)
+                    } else {
+                        volume.guest_path.clone()
+                    }
+                })
+                .collect(),
+            workload_id: workload_id.clone(),
+        };
+        let mut claims = RuntimeClaims::new(image_digest.clone(), &config);
+        if let Some(measured) = &layout.measured_rootfs {
+            claims = claims.with_rootfs_root_hash(measured.verity.root_hash.clone());
+        }
+        std::fs::create_dir_all(&box_dir).map_err(|e| {
+            BoxError::AttestationError(format!(
+                "Failed to create box directory {}: {}",
+                box_dir.display(),
+                e
+            ))
+        })?;
+        claims.save_for_box(&box_dir)?;
+        Ok(Some(claims))
+    }
+
     /// Parse a volume mount string from the right so colons in a host path do
     /// not consume the host/guest separator. The guest always uses an absolute
     /// Linux path, even when the host path is a Windows drive or UNC path.
@@ -941,6 +1378,9 @@ mod tests {
             oci_config,
             prefer_image_rootfs_metadata: false,
             tee_instance_config: None,
+            image_digest: None,
+            measured_rootfs: None,
+            encrypted_workspace: None,
         }
     }
 
@@ -975,6 +1415,76 @@ mod tests {
         assert_eq!(env_value(&spec, "A3S_VIRTIOFS_CACHE"), Some("always"));
     }
 
+    #[test]
+    fn test_build_instance_spec_passes_kernel_tuning_to_guest_init() {
+        let dir = tempdir().unwrap();
+        let layout = test_layout(dir.path(), Some(test_oci_config(None, None)), true);
+        let mut vm = test_vm_manager(BoxConfig {
+            sysctls: vec![("net.core.somaxconn".to_string(), "4096".to_string())],
+            kernel_modules: vec!["br_netfilter".to_string()],
+            ..Default::default()
+        });
+
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(spec.sysctls["net.core.somaxconn"], "4096");
+        assert_eq!(spec.modules, vec!["br_netfilter"]);
+        assert_eq!(
+            env_value(&spec, "BOX_SYSCTL_0"),
+            Some("net.core.somaxconn=4096")
+        );
+        assert_eq!(
+            env_value(&spec, "BOX_KERNEL_MODULE_0"),
+            Some("br_netfilter")
+        );
+
+        let mut vm = test_vm_manager(BoxConfig {
+            sysctls: vec![("../../proc/sysrq-trigger".to_string(), "b".to_string())],
+            ..Default::default()
+        });
+        assert!(vm.build_instance_spec(&layout).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_instance_spec_stages_entropy_seed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let layout = test_layout(dir.path(), Some(test_oci_config(None, None)), true);
+        let mut vm = test_vm_manager(BoxConfig::default());
+
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(
+            env_value(&spec, ENTROPY_SEED_ENV),
+            Some(RUNTIME_ENTROPY_SEED_PATH)
+        );
+        let seed_path = layout.rootfs_path.join(".a3s-box-entropy-seed");
+        let seed = fs::read(&seed_path).unwrap();
+        a3s_box_core::entropy::validate_seed(&seed).unwrap();
+        assert_eq!(
+            fs::metadata(&seed_path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        // Every boot gets a fresh seed.
+        vm.build_instance_spec(&layout).unwrap();
+        assert_ne!(fs::read(&seed_path).unwrap(), seed);
+
+        let mut vm = test_vm_manager(BoxConfig {
+            tee: TeeConfig::SevSnp {
+                workload_id: "app".to_string(),
+                generation: Default::default(),
+                simulate: true,
+                kbs_url: None,
+            },
+            ..Default::default()
+        });
+        vm.home_dir = dir.path().join("home");
+        let spec = vm.build_instance_spec(&layout).unwrap();
+        assert_eq!(env_value(&spec, ENTROPY_SEED_ENV), None);
+    }
+
     #[test]
     fn test_persistent_box_requests_terminal_rootfs_metadata() {
         let dir = tempdir().unwrap();
@@ -1002,6 +1512,30 @@ mod tests {
         assert_eq!(env_value(&spec, "BOX_WINDOWS_PORT_FWD"), Some("1"));
     }
 
+    #[test]
+    fn test_read_only_rootfs_gets_scratch_tmpfs() {
+        let temp = tempdir().unwrap();
+        let config = BoxConfig {
+            read_only: true,
+            tmpfs: vec!["/run/:size=8m".to_string()],
+            ..Default::default()
+        };
+
+        let mut vm = test_vm_manager(config.clone());
+        let layout = test_layout(temp.path(), Some(test_oci_config(None, None)), true);
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(env_value(&spec, "BOX_READONLY"), Some("1"));
+        assert_eq!(env_value(&spec, "BOX_TMPFS_0"), Some("/run/:size=8m"));
+        assert_eq!(env_value(&spec, "BOX_TMPFS_1"), Some("/tmp:mode=1777"));
+        assert_eq!(env_value(&spec, "BOX_TMPFS_2"), None);
+
+        let mounted = ["/tmp/cache".to_string()].into_iter().collect();
+        assert_eq!(guest_tmpfs(&config, &mounted), ["/run/:size=8m"]);
+        let writable = BoxConfig::default();
+        assert!(guest_tmpfs(&writable, &Default::default()).is_empty());
+    }
+
     #[test]
     fn test_run_path_plumbs_cpu_cgroup_limits_to_guest() {
         // The `run` boot path must hand the CPU cgroup limits to guest-init as
@@ -1025,6 +1559,216 @@ mod tests {
         assert_eq!(env_value(&spec, "A3S_SEC_PIDS_LIMIT"), Some("100"));
     }
 
+    #[test]
+    fn test_run_path_plumbs_group_add_to_guest() {
+        let temp = tempdir().unwrap();
+        let mut vm = test_vm_manager(BoxConfig {
+            group_add: vec!["video".to_string(), "1234".to_string()],
+            ..Default::default()
+        });
+        let layout = test_layout(temp.path(), Some(test_oci_config(None, None)), true);
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(
+            env_value(&spec, "A3S_SEC_SUPPLEMENTAL_GROUPS"),
+            Some("video,1234")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tee_box_records_runtime_claims() {
+        let temp = tempdir().unwrap();
+        let mut vm = test_vm_manager(BoxConfig {
+            tee: TeeConfig::SevSnp {
+                workload_id: "app".to_string(),
+                generation: Default::default(),
+                simulate: true,
+                kbs_url: None,
+            },
+            ..Default::default()
+        });
+        vm.home_dir = temp.path().join("home");
+        let mut layout = test_layout(temp.path(), Some(test_oci_config(None, None)), true);
+        layout.image_digest = Some("sha256:abc".to_string());
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        let box_dir = vm.home_dir.join("boxes").join("test-box");
+        let claims = crate::tee::RuntimeClaims::load_for_box(&box_dir)
+            .unwrap()
+            .unwrap();
+        assert_eq!(claims.image_digest, "sha256:abc");
+        assert_eq!(
+            env_value(&spec, crate::tee::RUNTIME_CLAIMS_ENV),
+            Some(claims.digest_hex().as_str())
+        );
+
+        // Without an image digest, stale claims are dropped.
+        layout.image_digest = None;
+        let spec = vm.build_instance_spec(&layout).unwrap();
+        assert_eq!(env_value(&spec, crate::tee::RUNTIME_CLAIMS_ENV), None);
+        assert!(crate::tee::RuntimeClaims::load_for_box(&box_dir)
+            .unwrap()
+            .is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_measured_rootfs_reaches_guest_and_claims() {
+        use a3s_box_core::tee::{VerityRootfs, VERITY_DATA_BLOCK_ID, VERITY_ROOTFS_ENV};
+
+        let temp = tempdir().unwrap();
+        let mut vm = test_vm_manager(BoxConfig {
+            tee: TeeConfig::SevSnp {
+                workload_id: "app".to_string(),
+                generation: Default::default(),
+                simulate: true,
+                kbs_url: None,
+            },
+            ..Default::default()
+        });
+        vm.home_dir = temp.path().join("home");
+        let mut layout = test_layout(temp.path(), Some(test_oci_config(None, None)), true);
+        layout.image_digest = Some("sha256:abc".to_string());
+        let measured = crate::tee::MeasuredRootfs {
+            image_digest: "sha256:abc".to_string(),
+            verity: VerityRootfs {
+                root_hash: "ab".repeat(32),
+                data_blocks: 1024,
+            },
+            data_image: temp.path().join("rootfs-verity.img"),
+            hash_tree: temp.path().join("rootfs-verity.hash"),
+        };
+        layout.measured_rootfs = Some(measured.clone());
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(
+            env_value(&spec, VERITY_ROOTFS_ENV),
+            Some(measured.verity.to_env_value().as_str())
+        );
+        assert!(spec
+            .block_devices
+            .iter()
+            .any(|device| device.block_id == VERITY_DATA_BLOCK_ID && device.read_only));
+
+        // Guest init folds the root hash into the launch digest it is given.
+        let box_dir = vm.home_dir.join("boxes").join("test-box");
+        let claims = crate::tee::RuntimeClaims::load_for_box(&box_dir)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            claims.rootfs_root_hash.as_deref(),
+            Some(measured.verity.root_hash.as_str())
+        );
+        assert_eq!(
+            env_value(&spec, crate::tee::RUNTIME_CLAIMS_ENV),
+            Some(claims.launch_digest_hex().as_str())
+        );
+        assert_ne!(claims.launch_digest(), claims.digest());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_encrypted_workspace_boots_idle_with_its_disk() {
+        use a3s_box_core::tee::{ENCRYPTED_WORKSPACE_BLOCK_ID, ENCRYPTED_WORKSPACE_ENV};
+
+        let temp = tempdir().unwrap();
+        let mut vm = test_vm_manager(BoxConfig {
+            tee: TeeConfig::SevSnp {
+                workload_id: "app".to_string(),
+                generation: Default::default(),
+                simulate: true,
+                kbs_url: None,
+            },
+            encrypted_workspace_bytes: Some(64 * 1024 * 1024),
+            ..Default::default()
+        });
+        vm.home_dir = temp.path().join("home");
+        let mut layout = test_layout(temp.path(), Some(test_oci_config(None, None)), true);
+        layout.encrypted_workspace = Some(
+            crate::tee::create_encrypted_workspace_disk(temp.path(), 64 * 1024 * 1024).unwrap(),
+        );
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(env_value(&spec, ENCRYPTED_WORKSPACE_ENV), Some("1"));
+        assert_eq!(env_value(&spec, "BOX_DEFERRED_MAIN"), Some("1"));
+        assert!(spec
+            .block_devices
+            .iter()
+            .any(|device| device.block_id == ENCRYPTED_WORKSPACE_BLOCK_ID && !device.read_only));
+    }
+
+    #[test]
+    fn test_run_path_plumbs_allowlisted_devices_to_guest() {
+        let temp = tempdir().unwrap();
+        let mut vm = test_vm_manager(BoxConfig {
+            devices: vec![
+                "/dev/fuse".to_string(),
+                "/dev/net/tun:/dev/tun0:r".to_string(),
+            ],
+            ..Default::default()
+        });
+        let layout = test_layout(temp.path(), Some(test_oci_config(None, None)), true);
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(
+            env_value(&spec, "BOX_DEVICE_0"),
+            Some("/dev/fuse:10:229:666")
+        );
+        assert_eq!(
+            env_value(&spec, "BOX_DEVICE_1"),
+            Some("/dev/tun0:10:200:444")
+        );
+
+        let mut vm = test_vm_manager(BoxConfig {
+            devices: vec!["/dev/sda".to_string()],
+            ..Default::default()
+        });
+        assert!(vm.build_instance_spec(&layout).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_path_proxies_host_character_devices() {
+        let temp = tempdir().unwrap();
+        let layout = test_layout(temp.path(), Some(test_oci_config(None, None)), true);
+        // Character devices outside the proxy allowlist are refused.
+        let mut vm = test_vm_manager(BoxConfig {
+            devices: vec!["/dev/null:/dev/ttyUSB0:r".to_string()],
+            ..Default::default()
+        });
+        assert!(vm.build_instance_spec(&layout).is_err());
+
+        // Plumbing needs a real allowlisted device on the host.
+        let Some(host_device) = ["/dev/ttyS0", "/dev/ttyUSB0", "/dev/ttyACM0"]
+            .into_iter()
+            .find(|path| a3s_box_core::device::check_host_device(path).is_ok())
+        else {
+            return;
+        };
+        let mut vm = test_vm_manager(BoxConfig {
+            devices: vec![
+                "/dev/fuse".to_string(),
+                format!("{host_device}:/dev/ttyUSB0:r"),
+            ],
+            ..Default::default()
+        });
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(
+            env_value(&spec, "BOX_DEVICE_0"),
+            Some("/dev/fuse:10:229:666")
+        );
+        assert_eq!(env_value(&spec, "BOX_DEVICE_1"), None);
+        assert_eq!(
+            env_value(&spec, "BOX_DEVICE_PROXY_0"),
+            Some("/dev/ttyUSB0:ro")
+        );
+        assert_eq!(spec.device_proxies.len(), 1);
+        assert_eq!(spec.device_proxies[0].source, host_device);
+        assert!(spec.device_proxies[0].read_only);
+    }
+
     #[test]
     fn test_run_path_plumbs_memory_reservation_and_swap_to_guest() {
         // --memory-reservation (memory.low) and --memory-swap (memory.swap.max)
@@ -1528,6 +2272,40 @@ mod tests {
         assert_eq!(spec.entrypoint.executable, "/bin/echo");
         assert_eq!(spec.entrypoint.args, vec!["prefix", "hello"]);
         assert_eq!(env_value(&spec, "FOO"), Some("bar"));
+        // Without guest init there is no forwarder to bridge to.
+        assert!(spec.forward_socket_path.as_os_str().is_empty());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_build_instance_spec_provisions_forward_socket_with_guest_init() {
+        let dir = tempdir().unwrap();
+        let layout = test_layout(dir.path(), Some(test_oci_config(None, None)), true);
+        let mut vm = test_vm_manager(BoxConfig::default());
+        vm.home_dir = dir.path().to_path_buf();
+
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(spec.forward_socket_path, dir.path().join("forward.sock"));
+    }
+
+    #[test]
+    fn test_build_instance_spec_passes_socket_mounts_to_shim_and_guest() {
+        let dir = tempdir().unwrap();
+        let layout = test_layout(dir.path(), Some(test_oci_config(None, None)), true);
+        let mount = a3s_box_core::volume::SocketMount {
+            source: "/run/user/1000/ssh-agent.sock".to_string(),
+            target: "/ssh-agent".to_string(),
+        };
+        let mut vm = test_vm_manager(BoxConfig {
+            socket_mounts: vec![mount.clone()],
+            ..Default::default()
+        });
+
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(spec.socket_mounts, vec![mount]);
+        assert_eq!(env_value(&spec, "BOX_SOCKET_0"), Some("/ssh-agent"));
     }
 
     #[test]
@@ -1696,6 +2474,46 @@ mod tests {
         assert_eq!(entries.get("BAZ").map(String::as_str), Some("cli"));
     }
 
+    #[test]
+    fn test_build_instance_spec_wires_http_proxy() {
+        let dir = tempdir().unwrap();
+        let layout = test_layout(dir.path(), Some(test_oci_config(None, None)), true);
+        let mut vm = test_vm_manager(BoxConfig {
+            http_proxy: Some(HttpProxyMode::Audit),
+            extra_env: vec![("NO_PROXY".to_string(), "internal".to_string())],
+            ..Default::default()
+        });
+        vm.home_dir = dir.path().to_path_buf();
+
+        let spec = vm.build_instance_spec(&layout).unwrap();
+
+        assert_eq!(env_value(&spec, HTTP_PROXY_ENV), Some(GUEST_PROXY_ADDR));
+        let proxy = spec.http_proxy.unwrap();
+        assert_eq!(proxy.mode, HttpProxyMode::Audit);
+        assert_eq!(proxy.socket_path, dir.path().join("http-proxy.sock"));
+        assert_eq!(
+            proxy.audit_log,
+            dir.path().join("boxes/test-box").join(HTTP_AUDIT_FILE)
+        );
+        assert!(proxy.ca.is_none());
+
+        let staged = std::fs::read_to_string(layout.rootfs_path.join(".a3s-box-env")).unwrap();
+        let entries: std::collections::HashMap<&str, String> = staged
+            .lines()
+            .filter_map(|l| l.split_once('='))
+            .map(|(k, v)| (k, b64d(v)))
+            .collect();
+        assert_eq!(
+            entries.get("HTTPS_PROXY").map(String::as_str),
+            Some("http://127.0.0.1:3128")
+        );
+        assert_eq!(
+            entries.get("NO_PROXY").map(String::as_str),
+            Some("internal")
+        );
+        assert!(!entries.contains_key("SSL_CERT_FILE"));
+    }
+
     #[test]
     fn test_build_instance_spec_stages_env_through_internal_guest_symlink() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,167 @@
+//! Seeding a fresh workspace from a template (`--workspace-template`).
+
+use std::path::Path;
+use std::process::Command;
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::workspace_template::{substitute, WorkspaceTemplate, BOX_ID_VAR};
+
+/// Populate `workspace` from `template`, substituting variables into text
+/// files. Git sources are shallow-cloned next to the workspace first; their
+/// `.git` directory is not seeded.
+pub(super) fn seed(template: &WorkspaceTemplate, workspace: &Path, box_id: &str) -> Result<()> {
+    let mut vars = vec![(BOX_ID_VAR.to_string(), box_id.to_string())];
+    vars.extend(template.vars.iter().cloned());
+
+    if !template.is_git() {
+        let source = Path::new(&template.source);
+        if !source.is_dir() {
+            return Err(seed_error(
+                format!("Workspace template {} is not a directory", source.display()),
+                None,
+            ));
+        }
+        return copy_tree(source, workspace, &vars);
+    }
+
+    let parent = workspace.parent().unwrap_or(workspace);
+    let checkout = tempfile::Builder::new()
+        .prefix(".workspace-template-")
+        .tempdir_in(parent)
+        .map_err(|e| seed_error(format!("Failed to create template checkout: {e}"), None))?;
+    clone(template, checkout.path())?;
+    copy_tree(checkout.path(), workspace, &vars)
+}
+
+fn clone(template: &WorkspaceTemplate, dest: &Path) -> Result<()> {
+    let (url, reference) = template.git_ref();
+    let mut command = Command::new("git");
+    command.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(reference) = reference {
+        command.args(["--branch", reference]);
+    }
+    command.arg("--").arg(url).arg(dest);
+    let output = command.output().map_err(|e| {
+        seed_error(
+            format!("Failed to run git for workspace template {url}: {e}"),
+            Some("Install git, or pass a local template directory".to_string()),
+        )
+    })?;
+    if !output.status.success() {
+        return Err(seed_error(
+            format!(
+                "Failed to clone workspace template {url}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Copy `source` into `dest`, rendering UTF-8 files through [`substitute`]
+/// and copying everything else byte for byte.
+fn copy_tree(source: &Path, dest: &Path, vars: &[(String, String)]) -> Result<()> {
+    let entries = std::fs::read_dir(source).map_err(|e| io_error(source, e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| io_error(source, e))?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let from = entry.path();
+        let to = dest.join(entry.file_name());
+        let file_type = entry.file_type().map_err(|e| io_error(&from, e))?;
+        if file_type.is_dir() {
+            std::fs::create_dir_all(&to).map_err(|e| io_error(&to, e))?;
+            copy_tree(&from, &to, vars)?;
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(&from).map_err(|e| io_error(&from, e))?;
+            symlink(&target, &to).map_err(|e| io_error(&to, e))?;
+        } else if file_type.is_file() {
+            copy_file(&from, &to, vars)?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_file(from: &Path, to: &Path, vars: &[(String, String)]) -> Result<()> {
+    let bytes = std::fs::read(from).map_err(|e| io_error(from, e))?;
+    match String::from_utf8(bytes) {
+        Ok(text) => {
+            std::fs::write(to, substitute(&text, vars)).map_err(|e| io_error(to, e))?;
+            let permissions = std::fs::metadata(from)
+                .map_err(|e| io_error(from, e))?
+                .permissions();
+            std::fs::set_permissions(to, permissions).map_err(|e| io_error(to, e))
+        }
+        Err(_) => std::fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| io_error(from, e)),
+    }
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> BoxError {
+    seed_error(
+        format!("Failed to seed workspace from {}: {error}", path.display()),
+        None,
+    )
+}
+
+fn seed_error(message: String, hint: Option<String>) -> BoxError {
+    BoxError::BoxBootError { message, hint }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_copies_directory_and_substitutes_text() {
+        let template_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(template_dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(template_dir.path().join(".git")).unwrap();
+        std::fs::write(
+            template_dir.path().join("README.md"),
+            "# {{PROJECT}} in {{BOX_ID}} {{UNSET}}",
+        )
+        .unwrap();
+        std::fs::write(template_dir.path().join("src/data.bin"), [0xff, 0xfe, b'{']).unwrap();
+
+        let workspace = tempfile::tempdir().unwrap();
+        let template = WorkspaceTemplate {
+            source: template_dir.path().display().to_string(),
+            vars: vec![("PROJECT".to_string(), "demo".to_string())],
+        };
+        seed(&template, workspace.path(), "box-1").unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("README.md")).unwrap(),
+            "# demo in box-1 {{UNSET}}"
+        );
+        assert_eq!(
+            std::fs::read(workspace.path().join("src/data.bin")).unwrap(),
+            [0xff, 0xfe, b'{']
+        );
+        assert!(!workspace.path().join(".git").exists());
+    }
+
+    #[test]
+    fn test_seed_rejects_missing_directory() {
+        let workspace = tempfile::tempdir().unwrap();
+        let template = WorkspaceTemplate {
+            source: workspace.path().join("missing").display().to_string(),
+            vars: vec![],
+        };
+        assert!(seed(&template, workspace.path(), "box-1").is_err());
+    }
+}
@@ -526,6 +526,15 @@ impl VmmProvider for VmController {
             cmd.env("A3S_BOX_KSM", "1");
         }
 
+        // External guest kernel: per-VM from the spec. Windows also honors an
+        // inherited A3S_BOX_KERNEL (it has no bundled kernel); elsewhere an
+        // unset spec keeps the libkrunfw kernel.
+        if let Some(kernel) = &spec.kernel_path {
+            cmd.env("A3S_BOX_KERNEL", kernel);
+        } else if cfg!(not(target_os = "windows")) {
+            cmd.env_remove("A3S_BOX_KERNEL");
+        }
+
         // Snapshot-fork: set the file-backed-RAM / snapshot-trigger / restore paths
         // for the shim/libkrun. PER-VM values from the InstanceSpec take precedence —
         // this is what lets ONE process (the pool / fork daemon) drive a different
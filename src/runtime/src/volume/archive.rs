@@ -0,0 +1,414 @@
+//! Volume backup archives.
+//!
+//! `volume export` writes a tar stream (zstd-compressed unless the caller asks
+//! for plain tar) with a small metadata entry followed by the volume contents
+//! under `data/`. Ownership, permissions, timestamps, and extended attributes
+//! are preserved; xattrs travel as `SCHILY.xattr.*` PAX records so GNU tar and
+//! bsdtar can read the archive too. `volume import` accepts both compressed
+//! and plain archives.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::volume::VolumeConfig;
+use serde::{Deserialize, Serialize};
+
+/// Name of the metadata entry at the start of a volume archive.
+pub const VOLUME_ARCHIVE_METADATA: &str = "a3s-volume.json";
+
+const DATA_PREFIX: &str = "data";
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Volume settings carried alongside the archived data.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeArchiveMetadata {
+    /// Name of the exported volume.
+    pub name: String,
+    /// Labels of the exported volume.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Size limit of the exported volume in bytes (0 = unlimited).
+    #[serde(default)]
+    pub size_limit: u64,
+}
+
+impl VolumeArchiveMetadata {
+    /// Capture the portable settings of a volume.
+    pub fn from_config(config: &VolumeConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            labels: config.labels.clone(),
+            size_limit: config.size_limit,
+        }
+    }
+}
+
+/// Write `dir` as a volume archive to `writer`.
+///
+/// Returns the number of filesystem entries archived. Sockets cannot be
+/// represented in tar and are skipped with a warning.
+pub fn export_volume_archive<W: Write>(
+    dir: &Path,
+    metadata: &VolumeArchiveMetadata,
+    writer: W,
+    compress: bool,
+) -> Result<u64> {
+    if compress {
+        let mut encoder = zstd::Encoder::new(writer, 3).map_err(archive_error("start zstd"))?;
+        let entries = write_archive(dir, metadata, &mut encoder)?;
+        encoder.finish().map_err(archive_error("finish zstd"))?;
+        Ok(entries)
+    } else {
+        write_archive(dir, metadata, writer)
+    }
+}
+
+fn write_archive<W: Write>(dir: &Path, metadata: &VolumeArchiveMetadata, writer: W) -> Result<u64> {
+    let mut builder = tar::Builder::new(writer);
+    builder.mode(tar::HeaderMode::Complete);
+    builder.follow_symlinks(false);
+
+    let json = serde_json::to_vec_pretty(metadata).map_err(|e| {
+        BoxError::SerializationError(format!("Failed to encode volume archive metadata: {e}"))
+    })?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, VOLUME_ARCHIVE_METADATA, json.as_slice())
+        .map_err(archive_error("write metadata"))?;
+
+    let mut entries = 0;
+    append_tree(&mut builder, dir, Path::new(DATA_PREFIX), &mut entries)?;
+    builder.finish().map_err(archive_error("finish tar"))?;
+    Ok(entries)
+}
+
+fn append_tree<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &Path,
+    name: &Path,
+    entries: &mut u64,
+) -> Result<()> {
+    let metadata = std::fs::symlink_metadata(path).map_err(io_error(path, "inspect"))?;
+    if is_socket(&metadata) {
+        tracing::warn!(path = %path.display(), "Skipping socket in volume export");
+        return Ok(());
+    }
+
+    append_xattrs(builder, path)?;
+    builder
+        .append_path_with_name(path, name)
+        .map_err(io_error(path, "archive"))?;
+    *entries += 1;
+
+    if metadata.is_dir() {
+        let mut children = std::fs::read_dir(path)
+            .map_err(io_error(path, "read"))?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(io_error(path, "read"))?;
+        children.sort();
+        for child in children {
+            append_tree(builder, &path.join(&child), &name.join(&child), entries)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_socket(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    metadata.file_type().is_socket()
+}
+
+#[cfg(not(unix))]
+fn is_socket(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn append_xattrs<W: Write>(builder: &mut tar::Builder<W>, path: &Path) -> Result<()> {
+    let names = match xattr::list(path) {
+        Ok(names) => names,
+        // Filesystems without xattr support simply have none to preserve.
+        Err(error) if error.raw_os_error() == Some(libc::ENOTSUP) => return Ok(()),
+        Err(error) => return Err(io_error(path, "list xattrs")(error)),
+    };
+    let mut records = Vec::new();
+    for name in names {
+        let Some(value) = xattr::get(path, &name).map_err(io_error(path, "read xattr"))? else {
+            continue;
+        };
+        records.push((format!("SCHILY.xattr.{}", name.to_string_lossy()), value));
+    }
+    if records.is_empty() {
+        return Ok(());
+    }
+    builder
+        .append_pax_extensions(
+            records
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_slice())),
+        )
+        .map_err(io_error(path, "archive xattrs"))
+}
+
+#[cfg(not(unix))]
+fn append_xattrs<W: Write>(_builder: &mut tar::Builder<W>, _path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Unpack a volume archive from `reader` into `dir`.
+///
+/// Compression is detected from the stream. Entries outside `data/` other
+/// than the metadata entry are rejected, as are paths that would escape
+/// `dir`. Ownership is restored only when running as root.
+pub fn import_volume_archive<R: Read>(reader: R, dir: &Path) -> Result<VolumeArchiveMetadata> {
+    let mut reader = std::io::BufReader::new(reader);
+    let magic = {
+        use std::io::BufRead;
+        let buffered = reader.fill_buf().map_err(archive_error("read archive"))?;
+        buffered.starts_with(&ZSTD_MAGIC)
+    };
+    if magic {
+        let decoder = zstd::Decoder::with_buffer(reader).map_err(archive_error("start zstd"))?;
+        read_archive(decoder, dir)
+    } else {
+        read_archive(reader, dir)
+    }
+}
+
+fn read_archive<R: Read>(reader: R, dir: &Path) -> Result<VolumeArchiveMetadata> {
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+    archive.set_unpack_xattrs(true);
+    archive.set_overwrite(true);
+    #[cfg(unix)]
+    archive.set_preserve_ownerships(unsafe { libc::geteuid() } == 0);
+
+    let root = dir.canonicalize().map_err(io_error(dir, "resolve"))?;
+    let mut metadata = None;
+    for entry in archive.entries().map_err(archive_error("read archive"))? {
+        let mut entry = entry.map_err(archive_error("read entry"))?;
+        let path = entry
+            .path()
+            .map_err(archive_error("read entry path"))?
+            .into_owned();
+        if path == Path::new(VOLUME_ARCHIVE_METADATA) {
+            let mut json = Vec::new();
+            entry
+                .read_to_end(&mut json)
+                .map_err(archive_error("read metadata"))?;
+            metadata = Some(serde_json::from_slice(&json).map_err(|e| {
+                BoxError::SerializationError(format!("Invalid volume archive metadata: {e}"))
+            })?);
+            continue;
+        }
+
+        let relative = data_relative_path(&path)?;
+        if relative.as_os_str().is_empty() {
+            // The volume root itself: apply its mode but keep the directory.
+            if let Ok(mode) = entry.header().mode() {
+                set_dir_mode(dir, mode)?;
+            }
+            continue;
+        }
+        let target = contained_target(&root, &relative)?;
+        entry.unpack(&target).map_err(|e| {
+            BoxError::Other(format!(
+                "Failed to unpack {} into volume: {e}",
+                relative.display()
+            ))
+        })?;
+    }
+
+    metadata.ok_or_else(|| {
+        BoxError::ConfigError(format!(
+            "Not a volume archive: missing {VOLUME_ARCHIVE_METADATA}"
+        ))
+    })
+}
+
+/// Destination for `relative` under `root`.
+///
+/// The parent must already exist (archives list directories before their
+/// contents) and must resolve inside `root`, so a symlink unpacked earlier
+/// cannot redirect later entries out of the volume.
+fn contained_target(root: &Path, relative: &Path) -> Result<PathBuf> {
+    let target = root.join(relative);
+    let parent = target.parent().unwrap_or(root);
+    let resolved = parent.canonicalize().map_err(|e| {
+        BoxError::ConfigError(format!(
+            "Volume archive entry {} has no parent directory: {e}",
+            relative.display()
+        ))
+    })?;
+    if !resolved.starts_with(root) {
+        return Err(BoxError::ConfigError(format!(
+            "Volume archive entry {} escapes the volume directory",
+            relative.display()
+        )));
+    }
+    Ok(target)
+}
+
+/// Strip the `data/` prefix from an archive path, rejecting anything else.
+fn data_relative_path(path: &Path) -> Result<PathBuf> {
+    let mut components = path.components();
+    match components.next() {
+        Some(Component::Normal(first)) if first == DATA_PREFIX => {}
+        _ => {
+            return Err(BoxError::ConfigError(format!(
+                "Unexpected entry {} in volume archive",
+                path.display()
+            )))
+        }
+    }
+    let relative: PathBuf = components.collect();
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err(BoxError::ConfigError(format!(
+            "Volume archive entry {} escapes the volume directory",
+            path.display()
+        )));
+    }
+    Ok(relative)
+}
+
+#[cfg(unix)]
+fn set_dir_mode(dir: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(mode & 0o7777))
+        .map_err(io_error(dir, "set mode on"))
+}
+
+#[cfg(not(unix))]
+fn set_dir_mode(_dir: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+fn archive_error(action: &'static str) -> impl Fn(std::io::Error) -> BoxError {
+    move |error| BoxError::Other(format!("Volume archive: failed to {action}: {error}"))
+}
+
+fn io_error<'a>(path: &'a Path, action: &'static str) -> impl Fn(std::io::Error) -> BoxError + 'a {
+    move |error| BoxError::Other(format!("Failed to {action} {}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn metadata() -> VolumeArchiveMetadata {
+        VolumeArchiveMetadata {
+            name: "workspace".to_string(),
+            labels: HashMap::from([("team".to_string(), "agents".to_string())]),
+            size_limit: 1024 * 1024,
+        }
+    }
+
+    fn roundtrip(compress: bool) {
+        let src = TempDir::new().unwrap();
+        std::fs::create_dir(src.path().join("notes")).unwrap();
+        std::fs::write(src.path().join("notes/todo.md"), b"ship it\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::os::unix::fs::symlink("notes/todo.md", src.path().join("link")).unwrap();
+            std::fs::set_permissions(
+                src.path().join("notes/todo.md"),
+                std::fs::Permissions::from_mode(0o600),
+            )
+            .unwrap();
+        }
+
+        let mut archive = Vec::new();
+        let entries =
+            export_volume_archive(src.path(), &metadata(), &mut archive, compress).unwrap();
+        assert!(entries >= 3);
+        assert_eq!(archive.starts_with(&ZSTD_MAGIC), compress);
+
+        let dst = TempDir::new().unwrap();
+        let restored = import_volume_archive(archive.as_slice(), dst.path()).unwrap();
+        assert_eq!(restored, metadata());
+        assert_eq!(
+            std::fs::read(dst.path().join("notes/todo.md")).unwrap(),
+            b"ship it\n"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                std::fs::read_link(dst.path().join("link")).unwrap(),
+                Path::new("notes/todo.md")
+            );
+            let mode = std::fs::metadata(dst.path().join("notes/todo.md"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_volume_archive_roundtrip_zstd() {
+        roundtrip(true);
+    }
+
+    #[test]
+    fn test_volume_archive_roundtrip_plain_tar() {
+        roundtrip(false);
+    }
+
+    #[test]
+    fn test_import_rejects_foreign_tar() {
+        let mut archive = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, "etc/passwd", b"x\n".as_slice())
+            .unwrap();
+        let bytes = archive.into_inner().unwrap();
+
+        let dst = TempDir::new().unwrap();
+        let err = import_volume_archive(bytes.as_slice(), dst.path()).unwrap_err();
+        assert!(err.to_string().contains("Unexpected entry"), "got: {err}");
+        assert!(!dst.path().join("etc").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_contained_target_rejects_symlinked_parent() {
+        let root = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("escape")).unwrap();
+        let root_path = root.path().canonicalize().unwrap();
+
+        assert!(contained_target(&root_path, Path::new("escape/passwd")).is_err());
+        assert_eq!(
+            contained_target(&root_path, Path::new("file")).unwrap(),
+            root_path.join("file")
+        );
+    }
+
+    #[test]
+    fn test_data_relative_path_rejects_traversal() {
+        assert_eq!(
+            data_relative_path(Path::new("data/a/b")).unwrap(),
+            PathBuf::from("a/b")
+        );
+        assert!(data_relative_path(Path::new("data/../x")).is_err());
+        assert!(data_relative_path(Path::new("/data/x")).is_err());
+    }
+}
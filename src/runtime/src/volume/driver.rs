@@ -0,0 +1,354 @@
+//! Volume drivers.
+//!
+//! A named volume's `driver` decides where its data lives and how a box sees
+//! it:
+//!
+//! - `local` — a directory under `~/.a3s/volumes/<name>/`, shared over virtio-fs.
+//! - `tmpfs` — an in-guest tmpfs; nothing is stored on the host.
+//! - `nfs` — the host mounts the export on the volume directory, which is then
+//!   shared over virtio-fs like a local volume.
+//! - `block` — a host block device or raw disk image attached as a virtio-blk
+//!   disk and mounted by guest init.
+
+use std::path::{Path, PathBuf};
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::volume::VolumeConfig;
+
+/// Names of the built-in drivers.
+pub const VOLUME_DRIVERS: &[&str] = &["local", "tmpfs", "nfs", "block"];
+
+/// How a box attaches a volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VolumeAttachment {
+    /// Share a host directory into the guest over virtio-fs.
+    HostDir(PathBuf),
+    /// Mount a fresh tmpfs inside the guest.
+    Tmpfs {
+        /// tmpfs `size=` in bytes (`None` = kernel default).
+        size_bytes: Option<u64>,
+        /// tmpfs `mode=` as an octal string.
+        mode: Option<String>,
+    },
+    /// Attach a block device or disk image and mount it in the guest.
+    Block {
+        /// Host block device or raw image.
+        source: PathBuf,
+        /// Filesystem on the device.
+        fstype: String,
+    },
+}
+
+/// Backend for one volume driver.
+pub trait VolumeDriver: Send + Sync {
+    /// Driver name as stored in [`VolumeConfig::driver`].
+    fn name(&self) -> &'static str;
+
+    /// Option keys accepted by `volume create -o` / `--mount volume-opt=`.
+    fn options(&self) -> &'static [&'static str];
+
+    /// Check that a volume's options are complete and well-formed.
+    fn validate(&self, _config: &VolumeConfig) -> Result<()> {
+        Ok(())
+    }
+
+    /// Make the volume ready for a box and describe how to attach it.
+    fn attach(&self, config: &VolumeConfig) -> Result<VolumeAttachment>;
+
+    /// Release host resources once no box uses the volume.
+    fn release(&self, _config: &VolumeConfig) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Look up a built-in driver by name.
+pub fn volume_driver(name: &str) -> Result<&'static dyn VolumeDriver> {
+    static LOCAL: LocalDriver = LocalDriver;
+    static TMPFS: TmpfsDriver = TmpfsDriver;
+    static NFS: NfsDriver = NfsDriver;
+    static BLOCK: BlockDriver = BlockDriver;
+    match name {
+        "local" => Ok(&LOCAL),
+        "tmpfs" => Ok(&TMPFS),
+        "nfs" => Ok(&NFS),
+        "block" => Ok(&BLOCK),
+        _ => Err(BoxError::ConfigError(format!(
+            "unsupported volume driver '{name}' (supported: {})",
+            VOLUME_DRIVERS.join(", ")
+        ))),
+    }
+}
+
+fn required_option<'a>(config: &'a VolumeConfig, key: &str) -> Result<&'a str> {
+    config
+        .options
+        .get(key)
+        .map(String::as_str)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| {
+            BoxError::ConfigError(format!(
+                "volume '{}': the {} driver requires -o {key}=...",
+                config.name, config.driver
+            ))
+        })
+}
+
+/// Directory-backed volume under the volume store.
+pub struct LocalDriver;
+
+impl VolumeDriver for LocalDriver {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["size"]
+    }
+
+    fn attach(&self, config: &VolumeConfig) -> Result<VolumeAttachment> {
+        Ok(VolumeAttachment::HostDir(PathBuf::from(
+            &config.mount_point,
+        )))
+    }
+}
+
+/// Guest-side tmpfs; data lives only as long as the box.
+pub struct TmpfsDriver;
+
+impl VolumeDriver for TmpfsDriver {
+    fn name(&self) -> &'static str {
+        "tmpfs"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["size", "mode"]
+    }
+
+    fn validate(&self, config: &VolumeConfig) -> Result<()> {
+        if let Some(mode) = config.options.get("mode") {
+            if !u32::from_str_radix(mode, 8).is_ok_and(|mode| mode <= 0o7777) {
+                return Err(BoxError::ConfigError(format!(
+                    "volume '{}': invalid tmpfs mode '{mode}' (expected octal, e.g. 1777)",
+                    config.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn attach(&self, config: &VolumeConfig) -> Result<VolumeAttachment> {
+        Ok(VolumeAttachment::Tmpfs {
+            size_bytes: (config.size_limit > 0).then_some(config.size_limit),
+            mode: config.options.get("mode").cloned(),
+        })
+    }
+}
+
+/// NFS export mounted on the host and shared over virtio-fs.
+pub struct NfsDriver;
+
+impl NfsDriver {
+    fn source(config: &VolumeConfig) -> Result<String> {
+        let addr = required_option(config, "addr")?;
+        let device = required_option(config, "device")?;
+        Ok(format!("{addr}:{}", device.trim_start_matches(':')))
+    }
+}
+
+impl VolumeDriver for NfsDriver {
+    fn name(&self) -> &'static str {
+        "nfs"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["addr", "device", "o"]
+    }
+
+    fn validate(&self, config: &VolumeConfig) -> Result<()> {
+        Self::source(config).map(|_| ())
+    }
+
+    fn attach(&self, config: &VolumeConfig) -> Result<VolumeAttachment> {
+        let mountpoint = PathBuf::from(&config.mount_point);
+        if crate::rootfs::is_mountpoint(&mountpoint) {
+            return Ok(VolumeAttachment::HostDir(mountpoint));
+        }
+
+        let source = Self::source(config)?;
+        let mut command = std::process::Command::new("mount");
+        command.args(["-t", "nfs"]);
+        if let Some(options) = config.options.get("o").filter(|o| !o.is_empty()) {
+            command.args(["-o", options]);
+        }
+        let output = command
+            .arg(&source)
+            .arg(&mountpoint)
+            .output()
+            .map_err(|e| BoxError::BuildError(format!("Failed to run mount: {e}")))?;
+        if !output.status.success() {
+            return Err(BoxError::BuildError(format!(
+                "Failed to mount NFS export {source} for volume '{}' (NFS volumes need root and nfs-utils): {}",
+                config.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        tracing::info!(volume = %config.name, source, "Mounted NFS volume");
+        Ok(VolumeAttachment::HostDir(mountpoint))
+    }
+
+    fn release(&self, config: &VolumeConfig) -> Result<()> {
+        let mountpoint = Path::new(&config.mount_point);
+        if !crate::rootfs::is_mountpoint(mountpoint) {
+            return Ok(());
+        }
+        let output = std::process::Command::new("umount")
+            .arg(mountpoint)
+            .output()
+            .map_err(|e| BoxError::BuildError(format!("Failed to run umount: {e}")))?;
+        if !output.status.success() {
+            return Err(BoxError::BuildError(format!(
+                "Failed to unmount NFS volume '{}': {}",
+                config.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Host block device or raw disk image attached as a virtio-blk disk.
+pub struct BlockDriver;
+
+impl VolumeDriver for BlockDriver {
+    fn name(&self) -> &'static str {
+        "block"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["device", "fstype"]
+    }
+
+    fn validate(&self, config: &VolumeConfig) -> Result<()> {
+        let device = required_option(config, "device")?;
+        if !device.starts_with('/') {
+            return Err(BoxError::ConfigError(format!(
+                "volume '{}': block device '{device}' must be an absolute path",
+                config.name
+            )));
+        }
+        if let Some(fstype) = config.options.get("fstype") {
+            if fstype.is_empty() || !fstype.bytes().all(|b| b.is_ascii_alphanumeric()) {
+                return Err(BoxError::ConfigError(format!(
+                    "volume '{}': invalid fstype '{fstype}'",
+                    config.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn attach(&self, config: &VolumeConfig) -> Result<VolumeAttachment> {
+        self.validate(config)?;
+        let source = PathBuf::from(required_option(config, "device")?);
+        if !source.exists() {
+            return Err(BoxError::ConfigError(format!(
+                "volume '{}': block device {} does not exist",
+                config.name,
+                source.display()
+            )));
+        }
+        Ok(VolumeAttachment::Block {
+            source,
+            fstype: config
+                .options
+                .get("fstype")
+                .cloned()
+                .unwrap_or_else(|| "ext4".to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(driver: &str, options: &[(&str, &str)]) -> VolumeConfig {
+        let mut config = VolumeConfig::new("data", "/tmp/a3s-volume-data");
+        config.driver = driver.to_string();
+        for (key, value) in options {
+            config.options.insert(key.to_string(), value.to_string());
+        }
+        config
+    }
+
+    #[test]
+    fn test_volume_driver_lookup() {
+        for name in VOLUME_DRIVERS {
+            assert_eq!(volume_driver(name).unwrap().name(), *name);
+        }
+        let err = volume_driver("ceph").err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("supported: local, tmpfs, nfs, block"));
+    }
+
+    #[test]
+    fn test_tmpfs_driver_attaches_in_guest() {
+        let driver = volume_driver("tmpfs").unwrap();
+        let mut config = volume("tmpfs", &[("mode", "1777")]);
+        config.size_limit = 64 * 1024 * 1024;
+        driver.validate(&config).unwrap();
+        assert_eq!(
+            driver.attach(&config).unwrap(),
+            VolumeAttachment::Tmpfs {
+                size_bytes: Some(64 * 1024 * 1024),
+                mode: Some("1777".to_string()),
+            }
+        );
+        assert!(driver
+            .validate(&volume("tmpfs", &[("mode", "rwx")]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_nfs_driver_requires_addr_and_device() {
+        let driver = volume_driver("nfs").unwrap();
+        let err = driver
+            .validate(&volume("nfs", &[("device", ":/export")]))
+            .unwrap_err();
+        assert!(err.to_string().contains("-o addr="), "got: {err}");
+
+        let config = volume("nfs", &[("addr", "10.0.0.5"), ("device", ":/export")]);
+        driver.validate(&config).unwrap();
+        assert_eq!(NfsDriver::source(&config).unwrap(), "10.0.0.5:/export");
+    }
+
+    #[test]
+    fn test_block_driver_validates_and_attaches_image() {
+        let driver = volume_driver("block").unwrap();
+        assert!(driver
+            .validate(&volume("block", &[("device", "disk.img")]))
+            .is_err());
+        assert!(driver
+            .validate(&volume(
+                "block",
+                &[("device", "/dev/sdb"), ("fstype", "ext4:x")]
+            ))
+            .is_err());
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let image = tmp.path().join("disk.img");
+        let device = image.to_string_lossy().into_owned();
+        let config = volume("block", &[("device", device.as_str()), ("fstype", "xfs")]);
+        assert!(driver.attach(&config).is_err());
+
+        std::fs::write(&image, b"").unwrap();
+        assert_eq!(
+            driver.attach(&config).unwrap(),
+            VolumeAttachment::Block {
+                source: image,
+                fstype: "xfs".to_string(),
+            }
+        );
+    }
+}
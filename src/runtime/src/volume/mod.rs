@@ -1,8 +1,14 @@
 //! Volume management for persistent named volumes.
 //!
 //! Provides `VolumeStore` for persisting volume state and
-//! managing volume data directories.
+//! managing volume data directories, the `VolumeDriver` backends that
+//! decide how a volume is attached to a box, and the backup archive format
+//! used by `volume export`/`volume import`.
 
+pub mod archive;
+pub mod driver;
 mod store;
 
+pub use archive::{export_volume_archive, import_volume_archive, VolumeArchiveMetadata};
+pub use driver::{volume_driver, VolumeAttachment, VolumeDriver, VOLUME_DRIVERS};
 pub use store::VolumeStore;
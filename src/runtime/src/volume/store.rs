@@ -1,7 +1,9 @@
 //! Persistent storage for volume configurations.
 //!
-//! Volumes are stored as JSON in `~/.a3s/volumes.json` with atomic writes
-//! (write to tmp file, then rename) to prevent corruption.
+//! Volumes are stored as JSON in `~/.a3s/volumes.json` through the
+//! [`crate::storage`] backend. Writes are atomic and journaled, so a damaged
+//! file is restored from `volumes.json.journal` (see
+//! [`crate::store_io::JournaledDocument`]).
 //! Volume data is stored under `~/.a3s/volumes/<name>/`.
 
 use a3s_box_core::error::{BoxError, Result};
@@ -19,7 +21,7 @@ pub struct VolumeStore {
 }
 
 /// Serializable wrapper for the volumes file.
-#[derive(Debug, serde::Serialize, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 struct VolumesFile {
     volumes: HashMap<String, VolumeConfig>,
 }
@@ -39,105 +41,48 @@ impl VolumeStore {
         Ok(Self::new(home.join("volumes.json"), home.join("volumes")))
     }
 
-    /// Load all volumes from disk.
-    pub fn load(&self) -> Result<HashMap<String, VolumeConfig>> {
-        if !self.path.exists() {
-            return Ok(HashMap::new());
+    /// The journaled `volumes.json` document.
+    fn document(&self) -> crate::store_io::JournaledDocument<'_> {
+        crate::store_io::JournaledDocument {
+            path: &self.path,
+            label: "volumes file",
+            error: BoxError::ConfigError,
         }
+    }
 
-        let data = std::fs::read_to_string(&self.path).map_err(|e| {
-            BoxError::ConfigError(format!(
-                "failed to read volumes file {}: {}",
-                self.path.display(),
-                e
-            ))
-        })?;
-
-        // A corrupt/old-schema volumes file must not brick the runtime: quarantine
-        // it and start from an empty set (create repopulates) rather than failing
-        // every volume operation. Mirrors the boxes.json hardening.
-        let file: VolumesFile = match serde_json::from_str(&data) {
-            Ok(f) => f,
-            Err(e) => {
-                let preserved = crate::store_io::quarantine_label(&self.path);
-                tracing::warn!(
-                    "volumes file {} is corrupt ({e}); preserved a copy at {preserved} \
-                     and started from an empty volume set",
-                    self.path.display(),
-                );
-                return Ok(HashMap::new());
-            }
-        };
-
-        Ok(file.volumes)
+    /// Load all volumes from the storage backend.
+    ///
+    /// A corrupt file is restored from its journal. If that fails too, the
+    /// file is quarantined and reset, and this returns
+    /// [`BoxError::StoreCorrupted`].
+    pub fn load(&self) -> Result<HashMap<String, VolumeConfig>> {
+        Ok(self.document().load::<VolumesFile>()?.volumes)
     }
 
-    /// Save all volumes to disk (atomic write).
+    /// Save all volumes (journaled, atomic replace).
     pub fn save(&self, volumes: &HashMap<String, VolumeConfig>) -> Result<()> {
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| {
-                BoxError::ConfigError(format!(
-                    "failed to create directory {}: {}",
-                    parent.display(),
-                    e
-                ))
-            })?;
-        }
-
-        let file = VolumesFile {
+        self.document().save(&VolumesFile {
             volumes: volumes.clone(),
-        };
-
-        let json = serde_json::to_string_pretty(&file).map_err(|e| {
-            BoxError::SerializationError(format!("failed to serialize volumes: {}", e))
-        })?;
-
-        let tmp_path = self.path.with_extension("json.tmp");
-        std::fs::write(&tmp_path, &json).map_err(|e| {
-            BoxError::ConfigError(format!(
-                "failed to write tmp file {}: {}",
-                tmp_path.display(),
-                e
-            ))
-        })?;
-
-        std::fs::rename(&tmp_path, &self.path).map_err(|e| {
-            BoxError::ConfigError(format!(
-                "failed to rename {} → {}: {}",
-                tmp_path.display(),
-                self.path.display(),
-                e
-            ))
-        })?;
-
-        Ok(())
+        })
     }
 
-    /// Run `f` over the volume map under a cross-process advisory lock,
-    /// re-loading fresh from disk inside the lock and saving the result.
+    /// Run `f` over the volume map inside a storage transaction, re-loading
+    /// fresh inside it and writing the result back.
     ///
     /// `create`/`remove`/`update`/`modify`/`get_or_create` all funnel through
     /// here so concurrent `a3s-box` processes cannot lose each other's writes.
-    /// The atomic tmp+rename in `save` only prevents a *torn* read — two
+    /// The atomic replace in `save` only prevents a *torn* read — two
     /// processes that both load, mutate a different entry, and save would still
     /// clobber one update (and, for attach/detach, silently drop a volume's
     /// `in_use_by` entry, letting `prune`/`remove` delete data a live box still
-    /// has mounted). `save` itself stays lock-free: the guard is held here for
-    /// the whole load → mutate → save, and the lock is non-reentrant.
+    /// has mounted). The transaction is held for the whole load → mutate →
+    /// save, and it is not reentrant. When `f` fails, nothing is written.
     fn with_write_lock<F, R>(&self, f: F) -> Result<R>
     where
         F: FnOnce(&mut HashMap<String, VolumeConfig>) -> Result<R>,
     {
-        let _lock = crate::file_lock::FileLock::acquire(&self.path).map_err(|e| {
-            BoxError::ConfigError(format!(
-                "failed to lock volumes file {}: {e}",
-                self.path.display()
-            ))
-        })?;
-        let mut volumes = self.load()?;
-        let r = f(&mut volumes)?;
-        self.save(&volumes)?;
-        Ok(r)
+        self.document()
+            .transact(|file: &mut VolumesFile| f(&mut file.volumes))
     }
 
     /// Get a single volume by name.
@@ -214,10 +159,19 @@ impl VolumeStore {
             Ok(config)
         })?;
 
+        // Let the driver release host resources (e.g. unmount an NFS export)
+        // before the data directory goes away.
+        if let Ok(driver) = super::driver::volume_driver(&config.driver) {
+            if let Err(e) = driver.release(&config) {
+                tracing::warn!(volume = name, error = %e, "Failed to release volume");
+            }
+        }
+
         // Remove the data directory outside the lock; it is keyed by name and
-        // the removal is idempotent.
+        // the removal is idempotent. A directory that is still a mountpoint is
+        // left alone so a failed release never deletes remote data.
         let vol_dir = self.volumes_dir.join(name);
-        if vol_dir.exists() {
+        if vol_dir.exists() && !crate::rootfs::is_mountpoint(&vol_dir) {
             std::fs::remove_dir_all(&vol_dir).ok();
         }
 
@@ -683,7 +637,9 @@ mod tests {
         std::fs::write(&path, "{ not valid json").unwrap();
         let store = VolumeStore::new(path.clone(), dir.path().join("volumes"));
 
-        // load() must succeed (empty) instead of erroring every volume op.
+        // With no journal to restore from, the corruption is reported once as
+        // a typed error, then every volume op works again from an empty set.
+        assert!(matches!(store.load(), Err(BoxError::StoreCorrupted { .. })));
         assert!(store.load().unwrap().is_empty());
         let quarantined = std::fs::read_dir(dir.path())
             .unwrap()
@@ -695,4 +651,27 @@ mod tests {
             });
         assert!(quarantined, "corrupt volumes.json must be quarantined");
     }
+
+    #[test]
+    fn corrupt_volumes_file_is_restored_from_journal() {
+        let (dir, store) = temp_store();
+        store.create(VolumeConfig::new("kept", "")).unwrap();
+        store.modify("kept", |c| c.attach("box-1")).unwrap();
+
+        // A crash or a stray writer truncates the live file.
+        std::fs::write(store.path(), "{ \"volumes\": {").unwrap();
+
+        let volumes = store.load().unwrap();
+        assert!(volumes["kept"].in_use_by.contains(&"box-1".to_string()));
+        // The restore is persisted, and the damaged bytes are preserved.
+        let data = std::fs::read_to_string(store.path()).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&data).is_ok());
+        assert!(std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .any(|e| e
+                .file_name()
+                .to_string_lossy()
+                .contains("volumes.json.corrupt-")));
+    }
 }
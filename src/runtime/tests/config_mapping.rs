@@ -0,0 +1,108 @@
+//! Golden-file conformance tests for the image config → container process
+//! mapping.
+//!
+//! Each directory under `fixtures/config_mapping/` is one case:
+//! `image-config.json` is a real-world image config blob, the optional
+//! `box.json` holds run-time overrides, and `expected.json` is the mapping
+//! the runtime must produce. Set `A3S_UPDATE_GOLDEN=1` to rewrite the
+//! expected files after an intentional mapping change, then review the diff.
+#![cfg(feature = "vm")]
+
+use std::path::{Path, PathBuf};
+
+use a3s_box_core::config::BoxConfig;
+use a3s_box_runtime::map_image_config;
+use a3s_box_runtime::oci::OciImageConfig;
+use serde::Deserialize;
+
+/// Run-time options a case can apply on top of the image config.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BoxOverrides {
+    cmd: Vec<String>,
+    entrypoint: Option<Vec<String>>,
+    user: Option<String>,
+    workdir: Option<String>,
+    env: Vec<String>,
+    volumes: Vec<String>,
+    tmpfs: Vec<String>,
+}
+
+impl BoxOverrides {
+    fn into_box_config(self) -> BoxConfig {
+        BoxConfig {
+            cmd: self.cmd,
+            entrypoint_override: self.entrypoint,
+            user: self.user,
+            workdir: self.workdir,
+            extra_env: self
+                .env
+                .iter()
+                .map(|pair| {
+                    let (key, value) = pair.split_once('=').expect("env override is KEY=VALUE");
+                    (key.to_string(), value.to_string())
+                })
+                .collect(),
+            volumes: self.volumes,
+            tmpfs: self.tmpfs,
+            ..Default::default()
+        }
+    }
+}
+
+fn fixture_cases() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/config_mapping");
+    let mut cases: Vec<PathBuf> = std::fs::read_dir(&root)
+        .expect("read config mapping fixtures")
+        .map(|entry| entry.expect("fixture entry").path())
+        .filter(|path| path.join("image-config.json").is_file())
+        .collect();
+    cases.sort();
+    cases
+}
+
+fn render_case(case: &Path) -> String {
+    let image_config = std::fs::read(case.join("image-config.json")).expect("read image config");
+    let oci_config = OciImageConfig::from_config_json(&image_config).expect("parse image config");
+    let overrides = match std::fs::read_to_string(case.join("box.json")) {
+        Ok(content) => serde_json::from_str::<BoxOverrides>(&content).expect("parse box.json"),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => BoxOverrides::default(),
+        Err(error) => panic!("read box.json: {error}"),
+    };
+
+    let mapping = map_image_config(&overrides.into_box_config(), Some(&oci_config))
+        .expect("map image config");
+    serde_json::to_string_pretty(&mapping).expect("serialize mapping") + "\n"
+}
+
+#[test]
+fn image_configs_map_to_golden_process_specs() {
+    let cases = fixture_cases();
+    assert!(!cases.is_empty(), "no config mapping fixtures found");
+    let update = std::env::var_os("A3S_UPDATE_GOLDEN").is_some();
+
+    let mut mismatches = Vec::new();
+    for case in &cases {
+        let actual = render_case(case);
+        let expected_path = case.join("expected.json");
+        if update {
+            std::fs::write(&expected_path, &actual).expect("write golden file");
+            continue;
+        }
+        let expected = std::fs::read_to_string(&expected_path)
+            .expect("read expected.json")
+            .replace("\r\n", "\n");
+        if actual != expected {
+            mismatches.push(format!(
+                "{}:\n--- expected\n{expected}+++ actual\n{actual}",
+                case.display()
+            ));
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "config mapping differs from golden files (rerun with A3S_UPDATE_GOLDEN=1 if intended):\n{}",
+        mismatches.join("\n")
+    );
+}
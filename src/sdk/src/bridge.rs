@@ -539,6 +539,26 @@ async fn execute_request(
             let snapshot = sandbox.create_filesystem_snapshot(snapshot_id).await?;
             Ok(execution_snapshot_value(&snapshot))
         }
+        #[cfg(unix)]
+        BridgeRequest::SandboxAttest {
+            sandbox_id,
+            generation,
+            policy,
+            allow_simulated,
+            offline,
+        } => {
+            let sandbox = connected_sandbox(client, sandbox_id, generation).await?;
+            let request = crate::VerifyAttestation {
+                policy,
+                allow_simulated,
+                offline,
+            };
+            serialize_field("attestation", sandbox.verify_attestation(&request).await?)
+        }
+        #[cfg(not(unix))]
+        BridgeRequest::SandboxAttest { .. } => Err(invalid(
+            "sandbox attestation requires the Unix attestation channel",
+        )),
         BridgeRequest::FilesystemSnapshotList => {
             serialize_field("snapshots", client.list_snapshots()?)
         }
@@ -47,6 +47,7 @@ pub const BRIDGE_OPERATIONS: &[&str] = &[
     "sandbox_logs",
     "sandbox_stats",
     "sandbox_snapshot_create",
+    "sandbox_attest",
     "filesystem_snapshot_list",
     "filesystem_snapshot_get",
     "filesystem_snapshot_size",
@@ -208,6 +209,16 @@ pub enum BridgeRequest {
         generation: u64,
         snapshot_id: String,
     },
+    SandboxAttest {
+        sandbox_id: String,
+        generation: u64,
+        #[serde(default)]
+        policy: Option<a3s_box_runtime::tee::AttestationPolicy>,
+        #[serde(default)]
+        allow_simulated: bool,
+        #[serde(default)]
+        offline: bool,
+    },
     FilesystemSnapshotList,
     FilesystemSnapshotGet {
         snapshot_id: String,
@@ -327,6 +338,7 @@ impl BridgeRequest {
             Self::SandboxLogs { .. } => "sandbox_logs",
             Self::SandboxStats { .. } => "sandbox_stats",
             Self::SandboxSnapshotCreate { .. } => "sandbox_snapshot_create",
+            Self::SandboxAttest { .. } => "sandbox_attest",
             Self::FilesystemSnapshotList => "filesystem_snapshot_list",
             Self::FilesystemSnapshotGet { .. } => "filesystem_snapshot_get",
             Self::FilesystemSnapshotSize { .. } => "filesystem_snapshot_size",
@@ -239,6 +239,10 @@ fn lifecycle_observability_and_inspection_shapes_are_typed() {
             r#"{"operation":"sandbox_stats","sandbox_id":"box-1","generation":2}"#,
             "sandbox_stats",
         ),
+        (
+            r#"{"operation":"sandbox_attest","sandbox_id":"box-1","generation":2,"allow_simulated":true,"policy":{"require_no_smt":true}}"#,
+            "sandbox_attest",
+        ),
         (
             r#"{"operation":"filesystem_snapshot_list"}"#,
             "filesystem_snapshot_list",
@@ -259,6 +263,16 @@ fn lifecycle_observability_and_inspection_shapes_are_typed() {
                 stop_timeout_seconds,
                 ..
             } => assert_eq!(stop_timeout_seconds, Some(7)),
+            BridgeRequest::SandboxAttest {
+                policy,
+                allow_simulated,
+                offline,
+                ..
+            } => {
+                let policy = policy.unwrap();
+                assert!(policy.require_no_smt && policy.require_no_debug);
+                assert!(allow_simulated && !offline);
+            }
             _ => {}
         }
     }
@@ -734,6 +734,15 @@ impl A3sBoxClient {
             resource_limits: a3s_box_core::config::ResourceLimits::default(),
             log_config: a3s_box_core::log::LogConfig::default(),
             add_host: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            dns_option: vec![],
+            network_aliases: vec![],
+            network_policy: vec![],
+            network_bandwidth: None,
+            network_max_conns: None,
+            http_proxy: None,
+            socket_mounts: vec![],
             platform: None,
             init: false,
             read_only: false,
@@ -748,6 +757,8 @@ impl A3sBoxClient {
             stop_timeout: None,
             oom_kill_disable: false,
             oom_score_adj: None,
+            workspace_template: None,
+            metadata: Default::default(),
         };
         let summary = BoxSummary::from_record(&record);
         let registered = StateFile::modify(&self.paths.boxes_file, |state| {
@@ -889,6 +900,76 @@ impl A3sBoxClient {
             .await?)
     }
 
+    /// Challenge a running TEE box with a fresh nonce and verify the report
+    /// it answers with.
+    ///
+    /// A verdict with `verified: false` is returned, not an error, when the
+    /// report is genuine but fails the policy or signature checks.
+    #[cfg(unix)]
+    pub async fn verify_box_attestation(
+        &self,
+        query: &str,
+        request: &VerifyAttestation,
+    ) -> Result<AttestationVerdict> {
+        use a3s_box_runtime::tee::{is_simulated_report, RuntimeClaims};
+        use a3s_box_runtime::{verify_attestation_with_time, AmdKdsClient, RaTlsAttestationClient};
+
+        let socket = self.require_runtime_socket(query, RuntimeSocket::Attest)?;
+        let (box_id, box_dir) = {
+            let state = self.load_state()?;
+            let record = resolve_required_record(&state, query)?;
+            (record.id.clone(), record.box_dir.clone())
+        };
+        let mut policy = match &request.policy {
+            Some(policy) => policy.clone(),
+            None => {
+                a3s_box_runtime::tee::AttestationPolicy::load_for_box(&box_dir)?.unwrap_or_default()
+            }
+        };
+        if policy.expected_runtime_claims.is_none() {
+            policy.expected_runtime_claims =
+                RuntimeClaims::load_for_box(&box_dir)?.map(|claims| claims.digest_hex());
+        }
+
+        let mut challenge = RaTlsAttestationClient::new(&socket)
+            .challenge(request.allow_simulated)
+            .await?;
+        let mut kds = AmdKdsClient::new(Some(AmdKdsClient::default_cache_dir()));
+        if request.offline {
+            kds = kds.offline();
+        }
+        kds.complete_report(&mut challenge.report, AmdKdsClient::product_name("milan"))
+            .await?;
+        let result = verify_attestation_with_time(
+            &challenge.report,
+            &challenge.nonce,
+            &policy,
+            request.allow_simulated,
+            Some(challenge.issued_at),
+        )?;
+
+        let tcb = &result.platform.tcb_version;
+        Ok(AttestationVerdict {
+            box_id,
+            verified: result.verified,
+            simulated: is_simulated_report(&challenge.report.report),
+            nonce: challenge
+                .nonce
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+            measurement: result.platform.measurement.clone(),
+            chip_id: result.platform.chip_id.clone(),
+            tcb: format!(
+                "{}:{}:{}:{}",
+                tcb.boot_loader, tcb.tee, tcb.snp, tcb.microcode
+            ),
+            runtime_claims: policy.expected_runtime_claims,
+            failures: result.failures,
+            verified_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+
     fn load_state(&self) -> Result<StateFile> {
         Ok(StateFile::load(&self.paths.boxes_file)?)
     }
@@ -527,3 +527,23 @@ impl From<SnapshotMetadata> for SnapshotSummary {
         }
     }
 }
+
+/// Verdict of a nonce challenge against a running TEE box.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationVerdict {
+    pub box_id: String,
+    pub verified: bool,
+    /// The report came from simulation mode, not hardware.
+    pub simulated: bool,
+    /// Challenge nonce, hex-encoded.
+    pub nonce: String,
+    /// Launch measurement, hex-encoded.
+    pub measurement: String,
+    pub chip_id: String,
+    /// TCB version as `BOOT_LOADER:TEE:SNP:MICROCODE`.
+    pub tcb: String,
+    /// Runtime-claims digest the policy required, if any.
+    pub runtime_claims: Option<String>,
+    pub failures: Vec<String>,
+    pub verified_at: String,
+}
@@ -81,6 +81,7 @@
             .create_snapshot("active-api", CreateSnapshot::new())
             .unwrap_err();
 
+        assert_eq!(error.code(), None);
         assert!(matches!(
             error,
             ClientError::Validation(message)
@@ -20,6 +20,18 @@ pub enum ClientError {
     AmbiguousBoxQuery { query: String, matches: Vec<String> },
 }
 
+impl ClientError {
+    /// The `A3S-xxxx` catalog code for runtime and state errors; SDK-level
+    /// errors (validation, lookup, guest) have none.
+    pub fn code(&self) -> Option<a3s_box_core::ErrorCode> {
+        match self {
+            Self::State(_) => Some(a3s_box_core::ErrorCode::IO),
+            Self::Runtime(error) => Some(error.code()),
+            _ => None,
+        }
+    }
+}
+
 /// Filesystem locations used by [`A3sBoxClient`].
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct A3sBoxPaths {
@@ -536,3 +548,37 @@ pub struct StopBoxSummary {
     pub auto_removed: bool,
     pub box_summary: Option<BoxSummary>,
 }
+
+/// Options for verifying a running TEE box with a nonce challenge.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerifyAttestation {
+    /// Policy to verify against. Defaults to the policy the box was started
+    /// with; either way, reports must carry the box's runtime claims unless
+    /// the policy pins its own.
+    pub policy: Option<a3s_box_runtime::tee::AttestationPolicy>,
+    /// Accept simulated (non-hardware) reports.
+    pub allow_simulated: bool,
+    /// Never contact AMD KDS for missing certificates.
+    pub offline: bool,
+}
+
+impl VerifyAttestation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn policy(mut self, policy: a3s_box_runtime::tee::AttestationPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn allow_simulated(mut self, allow_simulated: bool) -> Self {
+        self.allow_simulated = allow_simulated;
+        self
+    }
+
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+}
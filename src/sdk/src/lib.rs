@@ -3,25 +3,28 @@
 //! Provides [`client`]: typed, runtime-backed local management APIs for boxes,
 //! pause/unpause/stop/remove/prune lifecycle transitions, images, volumes,
 //! networks, snapshot create/restore/list/remove/prune, image build/pull/push,
-//! and guest control sockets.
+//! and guest control sockets. [`plugin`] gives `a3s-box-<name>` CLI plugins
+//! the invoking CLI's context.
 
 mod box_state;
 
 pub mod bridge;
 pub mod client;
+pub mod plugin;
 pub mod sandbox;
 
 #[cfg(feature = "pipeline-cli")]
 pub mod pipeline;
 
 pub use client::{
-    A3sBoxClient, A3sBoxPaths, BoxLogLine, BoxStatsSummary, BoxSummary, BuildImage,
-    BuildImageSummary, ClientError, CreateNetwork, CreateSnapshot, CreateVolume, ImageBuilder,
-    ImageHealthCheckSummary, ImageHistoryEntry, ImageInspectSummary, ImageSummary,
+    A3sBoxClient, A3sBoxPaths, AttestationVerdict, BoxLogLine, BoxStatsSummary, BoxSummary,
+    BuildImage, BuildImageSummary, ClientError, CreateNetwork, CreateSnapshot, CreateVolume,
+    ImageBuilder, ImageHealthCheckSummary, ImageHistoryEntry, ImageInspectSummary, ImageSummary,
     ListBoxesOptions, NetworkBuilder, NetworkEndpointSummary, NetworkSummary, PullImage, PushImage,
     PushImageSummary, ReadBoxLogsOptions, RegistryCredentials, RemoveBox, RemoveBoxSummary,
     RestoreSnapshot, Result, RuntimeDiagnostics, RuntimeDiskUsage, RuntimeVirtualizationSummary,
-    SnapshotSummary, StopBox, StopBoxSummary, StopOutcome, TagImage, VolumeBuilder, VolumeSummary,
+    SnapshotSummary, StopBox, StopBoxSummary, StopOutcome, TagImage, VerifyAttestation,
+    VolumeBuilder, VolumeSummary,
 };
 pub use sandbox::{
     CommandResult, CommandRunOptions, Commands, Filesystem, FilesystemOptions, Sandbox,
@@ -0,0 +1,172 @@
+//! Support for `a3s-box-<name>` CLI plugins.
+//!
+//! `a3s-box <name> [args...]` runs the first executable named
+//! `a3s-box-<name>` in `~/.a3s/plugins` or on `PATH` when `<name>` is not a
+//! built-in command, in the style of git and Docker plugins. The CLI passes
+//! its context to the plugin as JSON in [`CONTEXT_ENV`], and as separate
+//! variables for shell plugins; a plugin written in Rust reads it with
+//! [`PluginContext::from_env`] and manages boxes and images through
+//! [`PluginContext::client`], the same state the CLI uses.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::{A3sBoxClient, A3sBoxPaths};
+
+/// Prefix of plugin executable names.
+pub const PLUGIN_PREFIX: &str = "a3s-box-";
+
+/// Environment variable holding the a3s-box home directory.
+pub const HOME_ENV: &str = "A3S_HOME";
+
+/// Environment variable holding the path of the invoking `a3s-box` binary.
+pub const CLI_ENV: &str = "A3S_BOX_CLI";
+
+/// Environment variable holding the subcommand name the plugin was run as.
+pub const PLUGIN_NAME_ENV: &str = "A3S_BOX_PLUGIN";
+
+/// Environment variable holding the whole [`PluginContext`] as JSON.
+pub const CONTEXT_ENV: &str = "A3S_BOX_PLUGIN_CONTEXT";
+
+/// Version of the context the CLI hands to plugins. It changes only when a
+/// field changes meaning or goes away; new fields keep the version.
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// Directory under the home directory searched for plugins before `PATH`.
+pub const PLUGINS_DIR: &str = "plugins";
+
+/// Where plugins installed for the a3s-box home `home` live.
+pub fn plugins_dir(home: &Path) -> PathBuf {
+    home.join(PLUGINS_DIR)
+}
+
+/// The executable name for plugin subcommand `name`.
+pub fn executable_name(name: &str) -> String {
+    format!("{PLUGIN_PREFIX}{name}")
+}
+
+/// What the CLI tells a plugin about the invocation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginContext {
+    /// [`PLUGIN_API_VERSION`] of the CLI that ran the plugin.
+    pub api_version: u32,
+    /// Subcommand name, e.g. `scan` for `a3s-box scan`.
+    pub name: String,
+    /// a3s-box home directory (`~/.a3s` unless `A3S_HOME` is set).
+    pub home: PathBuf,
+    /// The `a3s-box` binary that ran the plugin, for calling back into it.
+    pub cli: Option<PathBuf>,
+    /// Socket of a daemon serving the home directory. Always `None` today:
+    /// every a3s-box command works on the home directory directly.
+    #[serde(default)]
+    pub daemon_socket: Option<PathBuf>,
+}
+
+impl PluginContext {
+    /// The context for running plugin `name` against `home`.
+    pub fn new(name: impl Into<String>, home: impl Into<PathBuf>) -> Self {
+        Self {
+            api_version: PLUGIN_API_VERSION,
+            name: name.into(),
+            home: home.into(),
+            cli: None,
+            daemon_socket: None,
+        }
+    }
+
+    /// Read the context from the environment the CLI set up. Outside the CLI
+    /// the name is empty and the home falls back to the default.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        if let Some(context) = lookup(CONTEXT_ENV).and_then(|json| Self::from_json(&json).ok()) {
+            return context;
+        }
+        Self {
+            cli: lookup(CLI_ENV).map(PathBuf::from),
+            ..Self::new(
+                lookup(PLUGIN_NAME_ENV).unwrap_or_default(),
+                lookup(HOME_ENV)
+                    .map(PathBuf::from)
+                    .unwrap_or_else(a3s_box_core::dirs_home),
+            )
+        }
+    }
+
+    /// Parse the [`CONTEXT_ENV`] handshake.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// The [`CONTEXT_ENV`] handshake for this context.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("plugin context serializes")
+    }
+
+    /// State, image, volume and network paths under the home directory.
+    pub fn paths(&self) -> A3sBoxPaths {
+        A3sBoxPaths::from_home(&self.home)
+    }
+
+    /// A client over the same box and image stores as the CLI.
+    pub fn client(&self) -> A3sBoxClient {
+        A3sBoxClient::from_home(&self.home)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_from_lookup() {
+        let context = PluginContext::from_lookup(|key| match key {
+            PLUGIN_NAME_ENV => Some("scan".to_string()),
+            HOME_ENV => Some("/srv/a3s".to_string()),
+            CLI_ENV => Some("/usr/local/bin/a3s-box".to_string()),
+            _ => None,
+        });
+        assert_eq!(context.name, "scan");
+        assert_eq!(
+            context.paths().boxes_file,
+            PathBuf::from("/srv/a3s/boxes.json")
+        );
+        assert_eq!(context.cli, Some(PathBuf::from("/usr/local/bin/a3s-box")));
+        assert_eq!(context.api_version, PLUGIN_API_VERSION);
+        assert_eq!(executable_name("scan"), "a3s-box-scan");
+    }
+
+    #[test]
+    fn test_json_context_takes_precedence() {
+        let mut sent = PluginContext::new("scan", "/srv/a3s");
+        sent.cli = Some(PathBuf::from("/usr/local/bin/a3s-box"));
+        let json = sent.to_json();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["api_version"], 1);
+        assert_eq!(value["home"], "/srv/a3s");
+        assert!(value["daemon_socket"].is_null());
+
+        let received = PluginContext::from_lookup(|key| match key {
+            CONTEXT_ENV => Some(json.clone()),
+            PLUGIN_NAME_ENV => Some("other".to_string()),
+            _ => None,
+        });
+        assert_eq!(received, sent);
+
+        // A malformed handshake falls back to the separate variables.
+        let fallback = PluginContext::from_lookup(|key| match key {
+            CONTEXT_ENV => Some("{".to_string()),
+            PLUGIN_NAME_ENV => Some("scan".to_string()),
+            HOME_ENV => Some("/srv/a3s".to_string()),
+            _ => None,
+        });
+        assert_eq!(fallback, PluginContext::new("scan", "/srv/a3s"));
+        assert_eq!(
+            plugins_dir(Path::new("/srv/a3s")),
+            PathBuf::from("/srv/a3s/plugins")
+        );
+    }
+}
@@ -110,6 +110,7 @@ impl SandboxBuilder {
 
     pub fn publish_tcp(mut self, host_port: u16, guest_port: u16) -> Self {
         self.options.ports.push(PortMapping {
+            host_ip: None,
             host_port,
             guest_port,
             protocol: a3s_box_core::PortProtocol::Tcp,
@@ -110,6 +110,18 @@ pub struct CommandResult {
     pub(crate) stderr_bytes: Vec<u8>,
 }
 
+impl CommandResult {
+    /// Raw stdout, without the lossy UTF-8 conversion applied to `stdout`.
+    pub fn stdout_bytes(&self) -> &[u8] {
+        &self.stdout_bytes
+    }
+
+    /// Raw stderr, without the lossy UTF-8 conversion applied to `stderr`.
+    pub fn stderr_bytes(&self) -> &[u8] {
+        &self.stderr_bytes
+    }
+}
+
 /// E2B-style command namespace attached to a local [`super::Sandbox`].
 #[derive(Clone)]
 pub struct Commands {
@@ -10,7 +10,7 @@ mod lifecycle;
 mod options;
 mod script;
 
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
 use a3s_box_core::{
     ExecutionGeneration, ExecutionId, ExecutionIsolation, ExecutionSnapshot, ExecutionSnapshotId,
@@ -27,7 +27,7 @@ pub use options::{
 };
 pub use script::ScriptBuilder;
 
-use crate::{A3sBoxClient, ClientError, Result};
+use crate::{A3sBoxClient, AttestationVerdict, ClientError, Result};
 
 #[derive(Debug, Clone, Copy)]
 struct SandboxState {
@@ -41,6 +41,8 @@ pub(crate) struct SandboxInner {
     execution_id: ExecutionId,
     isolation: ExecutionIsolation,
     state: RwLock<SandboxState>,
+    /// Last attestation verdict and the generation it was obtained for.
+    attestation: Mutex<Option<(ExecutionGeneration, AttestationVerdict)>>,
 }
 
 impl SandboxInner {
@@ -182,6 +184,7 @@ impl Sandbox {
                 state,
                 closed: false,
             }),
+            attestation: Mutex::new(None),
         });
         Self {
             commands: Commands {
@@ -212,6 +215,43 @@ impl Sandbox {
         self.inner.isolation
     }
 
+    /// Challenge this Sandbox's TEE with a fresh nonce and verify the report.
+    ///
+    /// The verdict is cached on this handle, and shared by its clones, until
+    /// the Sandbox moves to a new generation; see [`Sandbox::attestation`].
+    #[cfg(unix)]
+    pub async fn verify_attestation(
+        &self,
+        request: &crate::VerifyAttestation,
+    ) -> Result<AttestationVerdict> {
+        let (execution_id, generation) = self.inner.active_execution()?;
+        let verdict = self
+            .inner
+            .client
+            .verify_box_attestation(execution_id.as_str(), request)
+            .await?;
+        *self
+            .inner
+            .attestation
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) =
+            Some((generation, verdict.clone()));
+        Ok(verdict)
+    }
+
+    /// The cached verdict of [`Sandbox::verify_attestation`], if it was
+    /// obtained for the Sandbox's current generation.
+    pub fn attestation(&self) -> Option<AttestationVerdict> {
+        let generation = self.inner.state().generation;
+        self.inner
+            .attestation
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .as_ref()
+            .filter(|(verified_generation, _)| *verified_generation == generation)
+            .map(|(_, verdict)| verdict.clone())
+    }
+
     /// Build an explicitly interpreted script execution.
     pub fn script(&self, source: impl AsRef<[u8]>) -> ScriptBuilder {
         self.commands.script(source)
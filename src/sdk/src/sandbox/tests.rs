@@ -689,6 +689,34 @@ async fn sandbox_logs_reject_invalid_bounds_and_stale_generations() {
     sandbox.kill().await.unwrap();
 }
 
+#[cfg(unix)]
+#[tokio::test]
+async fn sandbox_attestation_fails_closed_without_a_tee_box() {
+    let temp = tempfile::tempdir().unwrap();
+    let runtime = Arc::new(RecordingRuntime::new());
+    let sandbox = Sandbox::create_with_client(
+        test_client(Arc::clone(&runtime), temp.path()),
+        SandboxCreateOptions::new("alpine:3.20"),
+    )
+    .await
+    .unwrap();
+
+    assert!(sandbox.attestation().is_none());
+    let error = sandbox
+        .verify_attestation(&crate::VerifyAttestation::new().allow_simulated(true))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ClientError::BoxNotFound(_)));
+    assert!(sandbox.attestation().is_none());
+
+    sandbox.kill().await.unwrap();
+    let error = sandbox
+        .verify_attestation(&crate::VerifyAttestation::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ClientError::Validation(_)));
+}
+
 #[test]
 fn local_sandbox_handle_is_send_and_sync() {
     fn assert_send_sync<T: Send + Sync>() {}
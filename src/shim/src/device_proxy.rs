@@ -0,0 +1,139 @@
+//! Host end of proxied `--device` passthrough (see [`a3s_box_core::device`]).
+//!
+//! libkrun connects guest init's stream for the `n`th proxied device to the
+//! socket the shim serves here. Each connection opens the host device and
+//! relays bytes both ways until either side closes; connections are served
+//! one at a time, so the device has a single user as it would on the host.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use a3s_box_core::device::{check_host_device, ProxiedDevice};
+use a3s_box_core::error::{BoxError, Result};
+
+/// Bind `socket_path` and serve `device` from a background thread.
+///
+/// The socket is bound before this returns, so it exists when libkrun first
+/// connects a guest stream to it.
+pub fn spawn(device: &ProxiedDevice, socket_path: &Path) -> Result<()> {
+    check_host_device(&device.source).map_err(|message| BoxError::BoxBootError {
+        message,
+        hint: None,
+    })?;
+    let _ = std::fs::remove_file(socket_path);
+    let listener = UnixListener::bind(socket_path).map_err(|error| BoxError::BoxBootError {
+        message: format!(
+            "failed to bind device proxy socket {}: {error}",
+            socket_path.display()
+        ),
+        hint: None,
+    })?;
+
+    let device = device.clone();
+    let socket_path = socket_path.to_path_buf();
+    std::thread::Builder::new()
+        .name("device-proxy".to_string())
+        .spawn(move || serve(listener, device, socket_path))
+        .map_err(|error| BoxError::BoxBootError {
+            message: format!("failed to start device proxy thread: {error}"),
+            hint: None,
+        })?;
+    Ok(())
+}
+
+fn serve(listener: UnixListener, device: ProxiedDevice, socket_path: PathBuf) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(error) = relay(stream, &device) {
+                    tracing::debug!(
+                        %error,
+                        device = %device.source,
+                        "Device proxy connection ended"
+                    );
+                }
+            }
+            Err(error) => {
+                tracing::warn!(
+                    %error,
+                    socket = %socket_path.display(),
+                    "Device proxy accept failed"
+                );
+            }
+        }
+    }
+}
+
+fn relay(guest: UnixStream, device: &ProxiedDevice) -> io::Result<()> {
+    let host = open_device(device)?;
+    tracing::debug!(device = %device.source, target = %device.target, "Device proxy connected");
+
+    // Device reads block until data arrives, so the inbound copy only ends
+    // when the device reports EOF or an error; the guest side ending the
+    // stream closes the relay.
+    let mut device_reader = host.try_clone()?;
+    let mut guest_writer = guest.try_clone()?;
+    std::thread::Builder::new()
+        .name("device-proxy-read".to_string())
+        .spawn(move || {
+            let _ = io::copy(&mut device_reader, &mut guest_writer);
+            let _ = guest_writer.shutdown(Shutdown::Write);
+        })?;
+
+    let mut guest_reader = guest;
+    let copied = if device.read_only {
+        io::copy(&mut guest_reader, &mut io::sink())
+    } else {
+        let mut device_writer = host;
+        io::copy(&mut guest_reader, &mut device_writer)
+    };
+    let _ = guest_reader.shutdown(Shutdown::Both);
+    copied.map(|_| ())
+}
+
+fn open_device(device: &ProxiedDevice) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(!device.read_only)
+        .custom_flags(libc::O_NOCTTY)
+        .open(&device.source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_relay_serves_the_host_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("device-0.sock");
+        let device = ProxiedDevice {
+            source: "/dev/zero".to_string(),
+            target: "/dev/ttyUSB0".to_string(),
+            read_only: true,
+        };
+        spawn(&device, &socket).unwrap();
+
+        let mut stream = UnixStream::connect(&socket).unwrap();
+        stream.write_all(b"ignored").unwrap();
+        let mut buf = [0xffu8; 16];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0u8; 16]);
+    }
+
+    #[test]
+    fn test_spawn_rejects_non_character_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        let device = ProxiedDevice {
+            source: dir.path().display().to_string(),
+            target: "/dev/ttyUSB0".to_string(),
+            read_only: false,
+        };
+        assert!(spawn(&device, &dir.path().join("device-0.sock")).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Opt-in tuning of libkrun's guest RAM (hugepages / preallocation).
+//!
+//! libkrun allocates guest memory as anonymous `mmap` regions inside
+//! `krun_start_enter()` and exposes no knob for the backing. The shim instead
+//! runs a short-lived watcher thread next to the VM: once regions covering the
+//! configured RAM size appear in `/proc/self/maps`, it `madvise`s them
+//! `MADV_HUGEPAGE` (+ `MADV_COLLAPSE`) and/or `MADV_POPULATE_WRITE`. Every step
+//! is best-effort; failures are logged and the VM keeps running on 4K pages.
+
+/// Smallest anonymous mapping treated as guest RAM.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const MIN_REGION_BYTES: u64 = 64 * 1024 * 1024;
+
+/// How long to wait for libkrun to map guest RAM.
+#[cfg(target_os = "linux")]
+const DISCOVERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Start the watcher thread when hugepages or preallocation were requested.
+#[cfg(target_os = "linux")]
+pub(crate) fn spawn_guest_memory_tuner(memory_mib: u32, hugepages: bool, prealloc: bool) {
+    if !hugepages && !prealloc {
+        return;
+    }
+    let memory_bytes = u64::from(memory_mib) * 1024 * 1024;
+    let spawned = std::thread::Builder::new()
+        .name("guest-memory".to_string())
+        .spawn(move || {
+            let Some(regions) = wait_for_guest_ram(memory_bytes) else {
+                tracing::warn!(
+                    memory_mib,
+                    "Guest RAM not found in /proc/self/maps; skipping memory tuning"
+                );
+                return;
+            };
+            for (start, len) in regions {
+                if hugepages {
+                    advise(start, len, libc::MADV_HUGEPAGE, "MADV_HUGEPAGE");
+                    // MADV_COLLAPSE (Linux 6.1+) backs the range synchronously
+                    // instead of waiting for khugepaged.
+                    advise(start, len, 25, "MADV_COLLAPSE");
+                }
+                if prealloc {
+                    // MADV_POPULATE_WRITE (Linux 5.14+).
+                    advise(start, len, 23, "MADV_POPULATE_WRITE");
+                }
+            }
+            tracing::info!(
+                hugepages,
+                prealloc,
+                memory_mib,
+                "Guest memory tuning applied"
+            );
+        });
+    if let Err(error) = spawned {
+        tracing::warn!(%error, "Failed to start guest memory tuner");
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn spawn_guest_memory_tuner(memory_mib: u32, hugepages: bool, prealloc: bool) {
+    if hugepages || prealloc {
+        tracing::warn!(
+            memory_mib,
+            "Hugepages and memory preallocation are only supported on Linux hosts"
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn wait_for_guest_ram(memory_bytes: u64) -> Option<Vec<(usize, usize)>> {
+    let deadline = std::time::Instant::now() + DISCOVERY_TIMEOUT;
+    loop {
+        let maps = std::fs::read_to_string("/proc/self/maps").ok()?;
+        let regions = guest_ram_regions(&maps, memory_bytes);
+        let total: u64 = regions.iter().map(|(_, len)| *len as u64).sum();
+        if total >= memory_bytes {
+            return Some(regions);
+        }
+        if std::time::Instant::now() >= deadline {
+            return (!regions.is_empty()).then_some(regions);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn advise(start: usize, len: usize, advice: libc::c_int, name: &str) {
+    // SAFETY: the range is a live anonymous mapping owned by this process (read
+    // from /proc/self/maps); these advices never unmap or change its contents.
+    let rc = unsafe { libc::madvise(start as *mut libc::c_void, len, advice) };
+    if rc != 0 {
+        tracing::warn!(
+            error = %std::io::Error::last_os_error(),
+            advice = name,
+            "madvise on guest RAM failed"
+        );
+    }
+}
+
+/// Anonymous private read-write mappings large enough to be guest RAM.
+///
+/// libkrun may split RAM around the x86 MMIO hole, so every qualifying region
+/// counts; regions are taken largest-first until `memory_bytes` is covered.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn guest_ram_regions(maps: &str, memory_bytes: u64) -> Vec<(usize, usize)> {
+    let min_len = MIN_REGION_BYTES.min(memory_bytes);
+    let mut regions: Vec<(usize, usize)> = maps
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let range = fields.next()?;
+            let perms = fields.next()?;
+            let _offset = fields.next()?;
+            let _dev = fields.next()?;
+            let inode = fields.next()?;
+            let path = fields.next();
+            let anonymous = inode == "0" && path.is_none_or(|path| path.starts_with("[anon"));
+            if !anonymous || !perms.starts_with("rw") || !perms.ends_with('p') {
+                return None;
+            }
+            let (start, end) = range.split_once('-')?;
+            let start = usize::from_str_radix(start, 16).ok()?;
+            let end = usize::from_str_radix(end, 16).ok()?;
+            let len = end.checked_sub(start)?;
+            (len as u64 >= min_len).then_some((start, len))
+        })
+        .collect();
+    regions.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let mut covered = 0u64;
+    regions
+        .into_iter()
+        .take_while(|(_, len)| {
+            let take = covered < memory_bytes;
+            covered += *len as u64;
+            take
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_ram_regions_picks_large_anonymous_mappings() {
+        let maps = "\
+55d0c0000000-55d0c0100000 r-xp 00000000 08:01 1234 /usr/bin/a3s-box-shim
+7f0000000000-7f00c0000000 rw-p 00000000 00:00 0
+7f00c0000000-7f0100000000 rw-p 00000000 00:00 0
+7f0100000000-7f0100200000 rw-p 00000000 00:00 0 [heap]
+7f0200000000-7f0204000000 rw-s 00000000 00:05 99 /dev/zero (deleted)
+7f0300000000-7f0300100000 rw-p 00000000 00:00 0
+";
+        // 3 GiB + 1 GiB regions cover a 4 GiB guest; the heap, shared, and
+        // small mappings are ignored.
+        let regions = guest_ram_regions(maps, 4 * 1024 * 1024 * 1024);
+        assert_eq!(
+            regions,
+            vec![(0x7f0000000000, 0xc0000000), (0x7f00c0000000, 0x40000000),]
+        );
+    }
+
+    #[test]
+    fn test_guest_ram_regions_stops_once_memory_is_covered() {
+        let maps = "\
+7f0000000000-7f0040000000 rw-p 00000000 00:00 0
+7f0040000000-7f0048000000 rw-p 00000000 00:00 0 [anon:rust-alloc]
+";
+        let regions = guest_ram_regions(maps, 1024 * 1024 * 1024);
+        assert_eq!(regions, vec![(0x7f0000000000, 0x40000000)]);
+    }
+}
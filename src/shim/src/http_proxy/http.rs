@@ -0,0 +1,384 @@
+//! Just enough HTTP/1.1 for a forward proxy: request and response heads, and
+//! copying a message body with the framing its headers declare.
+
+use std::io::{self, BufRead, Read, Write};
+
+/// Upper bound on a request or response head.
+const MAX_HEAD_BYTES: u64 = 64 * 1024;
+
+/// Upper bound on one chunk-size or trailer line.
+const MAX_LINE_BYTES: u64 = 8 * 1024;
+
+/// A parsed request or response head.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Head {
+    /// Request: method, target, version. Response: version, status, reason.
+    pub start: [String; 3],
+    pub headers: Vec<(String, String)>,
+}
+
+impl Head {
+    /// First value of header `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether a comma-separated header lists `token`.
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        self.headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .flat_map(|(_, value)| value.split(','))
+            .any(|item| item.trim().eq_ignore_ascii_case(token))
+    }
+
+    pub fn remove_header(&mut self, name: &str) {
+        self.headers
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.remove_header(name);
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    /// Response status code.
+    pub fn status(&self) -> Option<u16> {
+        self.start[1].parse().ok()
+    }
+
+    /// The head as sent on the wire.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = format!("{} {} {}\r\n", self.start[0], self.start[1], self.start[2]);
+        for (key, value) in &self.headers {
+            out.push_str(key);
+            out.push_str(": ");
+            out.push_str(value);
+            out.push_str("\r\n");
+        }
+        out.push_str("\r\n");
+        out.into_bytes()
+    }
+}
+
+/// How a message body is delimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Framing {
+    None,
+    Length(u64),
+    Chunked,
+    UntilClose,
+}
+
+/// Read one head; `Ok(None)` if the peer closed before sending a byte.
+pub(super) fn read_head<R: BufRead>(reader: &mut R) -> io::Result<Option<(Head, u64)>> {
+    let mut raw = Vec::new();
+    let mut limited = reader.take(MAX_HEAD_BYTES);
+    loop {
+        let start = raw.len();
+        let read = limited.read_until(b'\n', &mut raw)?;
+        if read == 0 {
+            if raw.is_empty() {
+                return Ok(None);
+            }
+            return Err(invalid(if limited.limit() == 0 {
+                "message head too large"
+            } else {
+                "connection closed inside message head"
+            }));
+        }
+        let line = &raw[start..];
+        // Tolerate blank lines before a request line (RFC 9112 §2.2).
+        if start == 0 && (line == b"\r\n" || line == b"\n") {
+            raw.clear();
+            continue;
+        }
+        if line == b"\r\n" || line == b"\n" {
+            break;
+        }
+    }
+    let len = raw.len() as u64;
+    parse_head(&raw).map(|head| Some((head, len)))
+}
+
+fn parse_head(raw: &[u8]) -> io::Result<Head> {
+    let text = std::str::from_utf8(raw).map_err(|_| invalid("message head is not UTF-8"))?;
+    let mut lines = text.lines().map(|line| line.trim_end_matches('\r'));
+    let start_line = lines.next().ok_or_else(|| invalid("empty message head"))?;
+    let mut parts = start_line.splitn(3, ' ');
+    let start = [
+        parts.next().unwrap_or_default().to_string(),
+        parts.next().unwrap_or_default().to_string(),
+        parts.next().unwrap_or_default().to_string(),
+    ];
+    if start[0].is_empty() || start[1].is_empty() {
+        return Err(invalid("malformed start line"));
+    }
+    let mut headers = Vec::new();
+    for line in lines.filter(|line| !line.is_empty()) {
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid("malformed header line"))?;
+        if key.is_empty() || key.ends_with(' ') || key.ends_with('\t') {
+            return Err(invalid("malformed header name"));
+        }
+        headers.push((key.to_string(), value.trim().to_string()));
+    }
+    Ok(Head { start, headers })
+}
+
+/// Body framing of a request.
+pub(super) fn request_framing(head: &Head) -> io::Result<Framing> {
+    if head.header("Transfer-Encoding").is_some() {
+        // Requests have no until-close form, so anything but chunked is
+        // unframeable (RFC 9112 §6.3).
+        return if head.has_token("Transfer-Encoding", "chunked") {
+            Ok(Framing::Chunked)
+        } else {
+            Err(invalid("unsupported request Transfer-Encoding"))
+        };
+    }
+    match content_length(head)? {
+        Some(0) | None => Ok(Framing::None),
+        Some(len) => Ok(Framing::Length(len)),
+    }
+}
+
+/// Body framing of a response to a `method` request.
+pub(super) fn response_framing(method: &str, head: &Head) -> io::Result<Framing> {
+    let status = head.status().unwrap_or(0);
+    if method.eq_ignore_ascii_case("HEAD")
+        || (100..200).contains(&status)
+        || status == 204
+        || status == 304
+    {
+        return Ok(Framing::None);
+    }
+    if head.header("Transfer-Encoding").is_some() {
+        return Ok(if head.has_token("Transfer-Encoding", "chunked") {
+            Framing::Chunked
+        } else {
+            Framing::UntilClose
+        });
+    }
+    Ok(match content_length(head)? {
+        Some(len) => Framing::Length(len),
+        None => Framing::UntilClose,
+    })
+}
+
+fn content_length(head: &Head) -> io::Result<Option<u64>> {
+    let mut length = None;
+    for (key, value) in &head.headers {
+        if !key.eq_ignore_ascii_case("Content-Length") {
+            continue;
+        }
+        let parsed: u64 = value
+            .trim()
+            .parse()
+            .map_err(|_| invalid("invalid Content-Length"))?;
+        if length.is_some_and(|existing| existing != parsed) {
+            return Err(invalid("conflicting Content-Length headers"));
+        }
+        length = Some(parsed);
+    }
+    Ok(length)
+}
+
+/// Copy one body from `src` to `dst`; returns the bytes copied.
+pub(super) fn copy_body<R: BufRead, W: Write>(
+    framing: Framing,
+    src: &mut R,
+    dst: &mut W,
+) -> io::Result<u64> {
+    match framing {
+        Framing::None => Ok(0),
+        Framing::Length(len) => copy_exact(src, dst, len),
+        Framing::Chunked => copy_chunked(src, dst),
+        Framing::UntilClose => io::copy(src, dst),
+    }
+}
+
+fn copy_exact<R: Read, W: Write>(src: &mut R, dst: &mut W, len: u64) -> io::Result<u64> {
+    let copied = io::copy(&mut src.take(len), dst)?;
+    if copied < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed inside message body",
+        ));
+    }
+    Ok(copied)
+}
+
+fn copy_chunked<R: BufRead, W: Write>(src: &mut R, dst: &mut W) -> io::Result<u64> {
+    let mut total = 0;
+    let mut line = Vec::new();
+    loop {
+        total += copy_line(src, dst, &mut line)?;
+        let size = std::str::from_utf8(&line)
+            .ok()
+            .and_then(|line| line.trim().split(';').next())
+            .and_then(|size| u64::from_str_radix(size.trim(), 16).ok())
+            .ok_or_else(|| invalid("invalid chunk size"))?;
+        if size == 0 {
+            // Trailer section, ended by an empty line.
+            loop {
+                total += copy_line(src, dst, &mut line)?;
+                if line == b"\r\n" || line == b"\n" {
+                    return Ok(total);
+                }
+            }
+        }
+        total += copy_exact(src, dst, size)?;
+        total += copy_line(src, dst, &mut line)?;
+    }
+}
+
+fn copy_line<R: BufRead, W: Write>(
+    src: &mut R,
+    dst: &mut W,
+    line: &mut Vec<u8>,
+) -> io::Result<u64> {
+    line.clear();
+    src.take(MAX_LINE_BYTES).read_until(b'\n', line)?;
+    if !line.ends_with(b"\n") {
+        return Err(invalid("truncated chunked body"));
+    }
+    dst.write_all(line)?;
+    Ok(line.len() as u64)
+}
+
+/// Split an `authority` (`host`, `host:port`, `[v6]:port`) into host and port.
+pub(super) fn split_authority(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let authority = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        match rest.strip_prefix(':') {
+            Some(port) => (host, Some(port)),
+            None if rest.is_empty() => (host, None),
+            None => return None,
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default_port,
+    };
+    Some((host.to_string(), port))
+}
+
+/// Split an absolute-form `http://` target into host, port, and origin-form
+/// path.
+pub(super) fn split_absolute_target(target: &str) -> Option<(String, u16, String)> {
+    let scheme_len = "http://".len();
+    if target.len() < scheme_len || !target[..scheme_len].eq_ignore_ascii_case("http://") {
+        return None;
+    }
+    let rest = &target[scheme_len..];
+    let split = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(split);
+    let (host, port) = split_authority(authority, 80)?;
+    let path = match path {
+        "" => "/".to_string(),
+        path if path.starts_with('?') => format!("/{path}"),
+        path => path.to_string(),
+    };
+    Some((host, port, path))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(raw: &str) -> Head {
+        read_head(&mut raw.as_bytes()).unwrap().unwrap().0
+    }
+
+    #[test]
+    fn test_read_head_parses_request() {
+        let raw =
+            "\r\nGET http://example.com/a HTTP/1.1\r\nHost: example.com\r\nX-A: 1\r\n\r\nbody";
+        let mut reader = raw.as_bytes();
+        let (head, len) = read_head(&mut reader).unwrap().unwrap();
+        assert_eq!(head.start, ["GET", "http://example.com/a", "HTTP/1.1"]);
+        assert_eq!(head.header("host"), Some("example.com"));
+        assert_eq!(len as usize, raw.len() - 2 - 4);
+        assert_eq!(reader, b"body");
+
+        assert!(read_head(&mut &b""[..]).unwrap().is_none());
+        assert!(read_head(&mut &b"GET / HTTP/1.1\r\nHost"[..]).is_err());
+        assert!(read_head(&mut &b"GET / HTTP/1.1\r\nHost : x\r\n\r\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_framing() {
+        let request = head("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\n");
+        assert_eq!(request_framing(&request).unwrap(), Framing::Length(5));
+        let request = head("POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n");
+        assert_eq!(request_framing(&request).unwrap(), Framing::Chunked);
+        let request = head("POST / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\n");
+        assert!(request_framing(&request).is_err());
+
+        let response = head("HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\n");
+        assert_eq!(response_framing("HEAD", &response).unwrap(), Framing::None);
+        assert_eq!(
+            response_framing("GET", &response).unwrap(),
+            Framing::Length(9)
+        );
+        let response = head("HTTP/1.1 304 Not Modified\r\nContent-Length: 9\r\n\r\n");
+        assert_eq!(response_framing("GET", &response).unwrap(), Framing::None);
+        let response = head("HTTP/1.0 200 OK\r\n\r\n");
+        assert_eq!(
+            response_framing("GET", &response).unwrap(),
+            Framing::UntilClose
+        );
+    }
+
+    #[test]
+    fn test_copy_chunked_body() {
+        let body = "4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\nX-Trailer: y\r\n\r\nnext";
+        let mut src = body.as_bytes();
+        let mut dst = Vec::new();
+        let copied = copy_body(Framing::Chunked, &mut src, &mut dst).unwrap();
+        assert_eq!(copied as usize, body.len() - 4);
+        assert_eq!(dst, &body.as_bytes()[..body.len() - 4]);
+        assert_eq!(src, b"next");
+
+        let mut truncated = &b"4\r\nWi"[..];
+        assert!(copy_body(Framing::Chunked, &mut truncated, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_split_targets() {
+        assert_eq!(
+            split_absolute_target("http://example.com:8080/a?b=1"),
+            Some(("example.com".to_string(), 8080, "/a?b=1".to_string()))
+        );
+        assert_eq!(
+            split_absolute_target("HTTP://user@[::1]?q"),
+            Some(("::1".to_string(), 80, "/?q".to_string()))
+        );
+        assert_eq!(split_absolute_target("/relative"), None);
+        assert_eq!(
+            split_authority("api.example.com:443", 443),
+            Some(("api.example.com".to_string(), 443))
+        );
+        assert_eq!(split_authority("api.example.com:https", 443), None);
+    }
+}
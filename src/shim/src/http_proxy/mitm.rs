@@ -0,0 +1,224 @@
+//! TLS interception for `--http-proxy=mitm`: per-host leaf certificates
+//! signed by the box's CA, and the host trust store for upstream servers.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::http_proxy::ProxyCaPaths;
+use rcgen::{CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair, KeyUsagePurpose};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+/// Host trust bundles, most common first. `SSL_CERT_FILE` takes precedence.
+const HOST_CA_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/cert.pem",
+    "/opt/homebrew/etc/openssl@3/cert.pem",
+    "/usr/local/etc/openssl@3/cert.pem",
+];
+
+/// Leaf server configs kept at once. A workload reaching more hosts than
+/// this re-signs leaves for the ones it used least recently.
+const MAX_CACHED_LEAVES: usize = 256;
+
+/// Signs interception certificates and verifies upstream servers.
+pub(super) struct Interceptor {
+    issuer: rcgen::Certificate,
+    issuer_key: KeyPair,
+    upstream: Arc<rustls::ClientConfig>,
+    /// Server configs by host, so a host's leaf is not generated per request.
+    leaves: Mutex<LeafCache>,
+}
+
+/// Least-recently-used cache of leaf server configs by host.
+struct LeafCache {
+    capacity: usize,
+    /// Config and the tick it was last used at, per host.
+    configs: HashMap<String, (Arc<rustls::ServerConfig>, u64)>,
+    tick: u64,
+}
+
+impl LeafCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            configs: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, host: &str) -> Option<Arc<rustls::ServerConfig>> {
+        self.tick += 1;
+        let (config, last_used) = self.configs.get_mut(host)?;
+        *last_used = self.tick;
+        Some(Arc::clone(config))
+    }
+
+    /// Add `host`'s config, dropping the least recently used one when full.
+    fn insert(&mut self, host: &str, config: Arc<rustls::ServerConfig>) {
+        if self.configs.len() >= self.capacity && !self.configs.contains_key(host) {
+            let oldest = self
+                .configs
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(host, _)| host.clone());
+            if let Some(oldest) = oldest {
+                self.configs.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.configs.insert(host.to_string(), (config, self.tick));
+    }
+}
+
+impl Interceptor {
+    /// Load the box CA and the host's trust store.
+    pub fn load(ca: &ProxyCaPaths) -> Result<Self> {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let read = |path: &Path| {
+            std::fs::read_to_string(path).map_err(|error| BoxError::BoxBootError {
+                message: format!("failed to read HTTP proxy CA {}: {error}", path.display()),
+                hint: None,
+            })
+        };
+        let rcgen_error = |error: rcgen::Error| BoxError::BoxBootError {
+            message: format!("invalid HTTP proxy CA: {error}"),
+            hint: None,
+        };
+        let issuer_key = KeyPair::from_pem(&read(&ca.key)?).map_err(rcgen_error)?;
+        // Re-signing the parsed parameters with the same key reproduces the
+        // CA's subject and key identifier, which is all a leaf references.
+        let issuer = CertificateParams::from_ca_cert_pem(&read(&ca.cert)?)
+            .and_then(|params| params.self_signed(&issuer_key))
+            .map_err(rcgen_error)?;
+
+        let mut roots = rustls::RootCertStore::empty();
+        let override_bundle = std::env::var("SSL_CERT_FILE").ok();
+        for bundle in override_bundle
+            .iter()
+            .map(String::as_str)
+            .chain(HOST_CA_BUNDLES.iter().copied())
+        {
+            let Ok(certs) = CertificateDer::pem_file_iter(bundle) else {
+                continue;
+            };
+            let (added, _) = roots.add_parsable_certificates(certs.flatten());
+            if added > 0 {
+                tracing::debug!(bundle, added, "Loaded upstream trust roots for HTTP proxy");
+                break;
+            }
+        }
+        if roots.is_empty() {
+            tracing::warn!("No host CA bundle found; intercepted HTTPS requests will fail");
+        }
+        let mut upstream = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        upstream.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        Ok(Self {
+            issuer,
+            issuer_key,
+            upstream: Arc::new(upstream),
+            leaves: Mutex::new(LeafCache::new(MAX_CACHED_LEAVES)),
+        })
+    }
+
+    /// Client config for connections to real servers.
+    pub fn upstream(&self) -> Arc<rustls::ClientConfig> {
+        Arc::clone(&self.upstream)
+    }
+
+    /// Server config presenting a certificate for `host`.
+    pub fn server_config(
+        &self,
+        host: &str,
+    ) -> std::result::Result<Arc<rustls::ServerConfig>, String> {
+        let mut leaves = self.leaves.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(config) = leaves.get(host) {
+            return Ok(config);
+        }
+
+        let key = KeyPair::generate().map_err(|e| e.to_string())?;
+        let mut params =
+            CertificateParams::new(vec![host.to_string()]).map_err(|e| e.to_string())?;
+        params.distinguished_name.push(DnType::CommonName, host);
+        params.is_ca = IsCa::ExplicitNoCa;
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.use_authority_key_identifier_extension = true;
+        let cert = params
+            .signed_by(&key, &self.issuer, &self.issuer_key)
+            .map_err(|e| e.to_string())?;
+
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der()));
+        let mut config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.der().clone()], key)
+            .map_err(|e| e.to_string())?;
+        // The proxy speaks HTTP/1.1 only; keep clients from negotiating h2.
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        let config = Arc::new(config);
+        leaves.insert(host, Arc::clone(&config));
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_interceptor(dir: &Path) -> Interceptor {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Constrained(0));
+        let cert = params.self_signed(&key).unwrap();
+        let ca = ProxyCaPaths::in_dir(dir);
+        std::fs::write(&ca.cert, cert.pem()).unwrap();
+        std::fs::write(&ca.key, key.serialize_pem()).unwrap();
+        Interceptor::load(&ca).unwrap()
+    }
+
+    #[test]
+    fn test_leaf_is_cached_per_host() {
+        let dir = tempfile::tempdir().unwrap();
+        let interceptor = load_interceptor(dir.path());
+        let first = interceptor.server_config("api.example.com").unwrap();
+        let again = interceptor.server_config("api.example.com").unwrap();
+        let other = interceptor.server_config("10.0.0.1").unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(first.alpn_protocols, vec![b"http/1.1".to_vec()]);
+    }
+
+    #[test]
+    fn test_leaf_cache_evicts_least_recently_used_host() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut interceptor = load_interceptor(dir.path());
+        interceptor.leaves = Mutex::new(LeafCache::new(2));
+
+        let a = interceptor.server_config("a.example.com").unwrap();
+        let b = interceptor.server_config("b.example.com").unwrap();
+        // Touch `a` so `b` is the least recently used when `c` arrives.
+        interceptor.server_config("a.example.com").unwrap();
+        interceptor.server_config("c.example.com").unwrap();
+
+        let leaves = interceptor.leaves.lock().unwrap();
+        assert_eq!(leaves.configs.len(), 2);
+        assert!(!leaves.configs.contains_key("b.example.com"));
+        drop(leaves);
+        assert!(Arc::ptr_eq(
+            &a,
+            &interceptor.server_config("a.example.com").unwrap()
+        ));
+        assert!(!Arc::ptr_eq(
+            &b,
+            &interceptor.server_config("b.example.com").unwrap()
+        ));
+    }
+}
@@ -0,0 +1,730 @@
+//! Host end of `--http-proxy` (see [`a3s_box_core::http_proxy`]).
+//!
+//! libkrun connects every guest connection to vsock port
+//! [`HTTP_PROXY_VSOCK_PORT`](a3s_box_core::http_proxy::HTTP_PROXY_VSOCK_PORT)
+//! to the box's proxy socket, which the shim serves for the box's lifetime
+//! with one thread per connection. Each request or tunnel is appended to the
+//! box's audit log once it finishes.
+//!
+//! - Plain HTTP requests (absolute-form targets) are forwarded to the origin
+//!   over a fresh connection and recorded in full.
+//! - `CONNECT` tunnels are spliced and recorded as `host:port` with their
+//!   byte counts, unless the mode is MITM and the client starts a TLS
+//!   handshake: then the proxy terminates TLS with a leaf signed by the box
+//!   CA and forwards each request to the origin over verified TLS.
+
+mod http;
+mod mitm;
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use a3s_box_core::error::{BoxError, Result};
+use a3s_box_core::http_proxy::{HttpAuditRecord, HttpProxyInstanceConfig, HttpProxyMode};
+use chrono::Utc;
+
+use http::{Framing, Head};
+
+/// How long to wait for an origin to accept a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Read timeout while relaying an upgraded (e.g. WebSocket) connection; one
+/// idle direction delays the other by at most this much.
+const RELAY_POLL: Duration = Duration::from_millis(20);
+
+/// First byte of a TLS handshake record.
+const TLS_HANDSHAKE: u8 = 0x16;
+
+/// Start serving `config.socket_path` on a background thread.
+///
+/// The socket is bound before this returns, so it exists when libkrun first
+/// connects a guest stream to it.
+pub fn spawn(config: &HttpProxyInstanceConfig) -> Result<()> {
+    let interceptor = match (config.mode, &config.ca) {
+        (HttpProxyMode::Audit, _) => None,
+        (HttpProxyMode::Mitm, Some(ca)) => Some(mitm::Interceptor::load(ca)?),
+        (HttpProxyMode::Mitm, None) => {
+            return Err(BoxError::BoxBootError {
+                message: "--http-proxy=mitm requires a CA".to_string(),
+                hint: None,
+            })
+        }
+    };
+    let audit = AuditLog::open(&config.audit_log)?;
+
+    let _ = std::fs::remove_file(&config.socket_path);
+    let listener =
+        UnixListener::bind(&config.socket_path).map_err(|error| BoxError::BoxBootError {
+            message: format!(
+                "failed to bind HTTP proxy socket {}: {error}",
+                config.socket_path.display()
+            ),
+            hint: None,
+        })?;
+
+    let proxy = Arc::new(Proxy { interceptor, audit });
+    std::thread::Builder::new()
+        .name("http-proxy".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let proxy = Arc::clone(&proxy);
+                        let spawned = std::thread::Builder::new()
+                            .name("http-proxy-conn".to_string())
+                            .spawn(move || proxy.serve(stream));
+                        if let Err(error) = spawned {
+                            tracing::warn!(%error, "Failed to spawn HTTP proxy connection thread");
+                        }
+                    }
+                    Err(error) => {
+                        tracing::warn!(%error, "HTTP proxy accept failed");
+                    }
+                }
+            }
+        })
+        .map_err(|error| BoxError::BoxBootError {
+            message: format!("failed to start HTTP proxy: {error}"),
+            hint: None,
+        })?;
+    Ok(())
+}
+
+struct Proxy {
+    interceptor: Option<mitm::Interceptor>,
+    audit: AuditLog,
+}
+
+/// Where one request goes.
+struct Target {
+    scheme: &'static str,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Proxy {
+    /// Serve one guest connection: proxy requests until the client closes,
+    /// or hand the connection over to a `CONNECT` tunnel.
+    fn serve(&self, client: UnixStream) {
+        let mut client = BufReader::new(client);
+        loop {
+            let (head, head_len) = match http::read_head(&mut client) {
+                Ok(Some(head)) => head,
+                Ok(None) => return,
+                Err(_) => {
+                    respond(client.get_mut(), 400, "Bad Request");
+                    return;
+                }
+            };
+            if head.start[0].eq_ignore_ascii_case("CONNECT") {
+                self.tunnel(client, &head, head_len);
+                return;
+            }
+            let Some((host, port, path)) = http::split_absolute_target(&head.start[1]) else {
+                respond(client.get_mut(), 400, "Bad Request");
+                return;
+            };
+            let target = Target {
+                scheme: "http",
+                host,
+                port,
+                path,
+            };
+            if !self.exchange(&mut client, head, head_len, &target) {
+                return;
+            }
+        }
+    }
+
+    /// Handle a `CONNECT host:port` request.
+    fn tunnel(&self, mut client: BufReader<UnixStream>, head: &Head, head_len: u64) {
+        let started = Instant::now();
+        let Some((host, port)) = http::split_authority(&head.start[1], 443) else {
+            respond(client.get_mut(), 400, "Bad Request");
+            return;
+        };
+        let mut record = new_record("CONNECT", "https", &host, port, None, head_len);
+
+        if let Some(interceptor) = &self.interceptor {
+            record.status = Some(200);
+            record.response_bytes += established(client.get_mut());
+            // Only TLS is intercepted; anything else tunnelled through
+            // CONNECT (SSH, plain WebSocket) stays opaque.
+            let is_tls = match client.fill_buf() {
+                Ok(buf) => buf.first() == Some(&TLS_HANDSHAKE),
+                Err(error) => {
+                    record.error = Some(error.to_string());
+                    self.finish(record, started);
+                    return;
+                }
+            };
+            if is_tls {
+                if let Err(error) = self.intercept(interceptor, client, &host, port) {
+                    record.error = Some(error);
+                    self.finish(record, started);
+                }
+                return;
+            }
+            match dial(&host, port) {
+                Ok(upstream) => self.splice(client, upstream, &mut record),
+                Err(error) => record.error = Some(error.to_string()),
+            }
+        } else {
+            match dial(&host, port) {
+                Ok(upstream) => {
+                    record.status = Some(200);
+                    record.response_bytes += established(client.get_mut());
+                    self.splice(client, upstream, &mut record);
+                }
+                Err(error) => {
+                    record.status = Some(502);
+                    record.response_bytes += respond(client.get_mut(), 502, "Bad Gateway");
+                    record.error = Some(error.to_string());
+                }
+            }
+        }
+        self.finish(record, started);
+    }
+
+    /// Relay an opaque tunnel until both sides are done, counting its bytes.
+    fn splice(
+        &self,
+        client: BufReader<UnixStream>,
+        mut upstream: TcpStream,
+        record: &mut HttpAuditRecord,
+    ) {
+        let result = (|| -> io::Result<(u64, u64)> {
+            // Bytes the client sent ahead of our 200 belong to the tunnel.
+            let early = client.buffer().to_vec();
+            upstream.write_all(&early)?;
+            let client = client.into_inner();
+            let mut client_reader = client.try_clone()?;
+            let mut upstream_writer = upstream.try_clone()?;
+            let outbound = std::thread::spawn(move || {
+                let copied = io::copy(&mut client_reader, &mut upstream_writer);
+                let _ = upstream_writer.shutdown(Shutdown::Write);
+                copied
+            });
+            let mut client_writer = client;
+            let inbound = io::copy(&mut upstream, &mut client_writer);
+            // Once the origin is done the tunnel is over; also unblock the
+            // outbound copy if the client keeps its end open.
+            let _ = client_writer.shutdown(Shutdown::Both);
+            let outbound = outbound
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("tunnel thread panicked")));
+            Ok((early.len() as u64 + outbound?, inbound?))
+        })();
+        match result {
+            Ok((sent, received)) => {
+                record.request_bytes += sent;
+                record.response_bytes += received;
+            }
+            Err(error) => record.error = Some(error.to_string()),
+        }
+    }
+
+    /// Terminate the client's TLS and proxy the requests inside it.
+    fn intercept(
+        &self,
+        interceptor: &mitm::Interceptor,
+        client: BufReader<UnixStream>,
+        host: &str,
+        port: u16,
+    ) -> std::result::Result<(), String> {
+        let config = interceptor.server_config(host)?;
+        let connection = rustls::ServerConnection::new(config).map_err(|e| e.to_string())?;
+        let mut tls = rustls::StreamOwned::new(connection, Buffered(client));
+        // Finish the handshake up front so a client that rejects the box CA
+        // (certificate pinning) is recorded as such.
+        while tls.conn.is_handshaking() {
+            tls.conn
+                .complete_io(&mut tls.sock)
+                .map_err(|error| format!("TLS handshake with client failed: {error}"))?;
+        }
+
+        let mut client = BufReader::new(tls);
+        loop {
+            let (head, head_len) = match http::read_head(&mut client) {
+                Ok(Some(head)) => head,
+                Ok(None) => break,
+                Err(_) => {
+                    respond(client.get_mut(), 400, "Bad Request");
+                    break;
+                }
+            };
+            if !head.start[1].starts_with('/') {
+                respond(client.get_mut(), 400, "Bad Request");
+                break;
+            }
+            let target = Target {
+                scheme: "https",
+                host: host.to_string(),
+                port,
+                path: head.start[1].clone(),
+            };
+            if !self.exchange(&mut client, head, head_len, &target) {
+                break;
+            }
+        }
+        let tls = client.get_mut();
+        tls.conn.send_close_notify();
+        let _ = tls.flush();
+        Ok(())
+    }
+
+    /// Forward one request and relay its response, then record it. Returns
+    /// whether the client connection can carry another request.
+    fn exchange<C: Conn>(
+        &self,
+        client: &mut BufReader<C>,
+        head: Head,
+        head_len: u64,
+        target: &Target,
+    ) -> bool {
+        let started = Instant::now();
+        let mut record = new_record(
+            &head.start[0],
+            target.scheme,
+            &target.host,
+            target.port,
+            Some(target.path.clone()),
+            head_len,
+        );
+        let result = self.forward(client, head, target, &mut record);
+        let reusable = match result {
+            Ok(reusable) => reusable,
+            Err(error) => {
+                if record.status.is_none() {
+                    record.status = Some(502);
+                    record.response_bytes += respond(client.get_mut(), 502, "Bad Gateway");
+                }
+                record.error = Some(error.to_string());
+                false
+            }
+        };
+        self.finish(record, started);
+        reusable
+    }
+
+    fn forward<C: Conn>(
+        &self,
+        client: &mut BufReader<C>,
+        mut head: Head,
+        target: &Target,
+        record: &mut HttpAuditRecord,
+    ) -> io::Result<bool> {
+        let method = head.start[0].clone();
+        let framing = http::request_framing(&head)?;
+        let upgrade = head.header("Upgrade").is_some();
+        let client_close = head.has_token("Connection", "close");
+        let expects_continue = head.has_token("Expect", "100-continue");
+
+        head.start[1] = target.path.clone();
+        for name in ["Proxy-Connection", "Proxy-Authorization", "Expect"] {
+            head.remove_header(name);
+        }
+        if !upgrade {
+            // One origin connection per request keeps framing errors from
+            // leaking into the next request.
+            head.set_header("Connection", "close");
+        }
+        if head.header("Host").is_none() {
+            head.set_header("Host", &host_header(target));
+        }
+
+        let mut upstream = self.open(target)?;
+        upstream.write_all(&head.encode())?;
+        if expects_continue {
+            // The origin never sees the Expect header, so answer it here.
+            client
+                .get_mut()
+                .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+            client.get_mut().flush()?;
+        }
+        record.request_bytes += http::copy_body(framing, client, &mut upstream)?;
+        upstream.flush()?;
+
+        let mut upstream = BufReader::new(upstream);
+        loop {
+            let (response, _) = http::read_head(&mut upstream)?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "origin closed the connection without responding",
+                )
+            })?;
+            let status = response.status().unwrap_or(0);
+            let encoded = response.encode();
+            client.get_mut().write_all(&encoded)?;
+            record.response_bytes += encoded.len() as u64;
+            record.status = Some(status);
+
+            if status == 101 {
+                client.get_mut().flush()?;
+                let (sent, received) = relay(client, &mut upstream)?;
+                record.request_bytes += sent;
+                record.response_bytes += received;
+                return Ok(false);
+            }
+            if (100..200).contains(&status) {
+                continue;
+            }
+
+            let framing = http::response_framing(&method, &response)?;
+            record.response_bytes += http::copy_body(framing, &mut upstream, client.get_mut())?;
+            client.get_mut().flush()?;
+            return Ok(framing != Framing::UntilClose
+                && !client_close
+                && !response.has_token("Connection", "close"));
+        }
+    }
+
+    /// Connect to the origin of `target`, over TLS for `https`.
+    fn open(&self, target: &Target) -> io::Result<Box<dyn Conn>> {
+        let stream = dial(&target.host, target.port)?;
+        if target.scheme != "https" {
+            return Ok(Box::new(stream));
+        }
+        let interceptor = self
+            .interceptor
+            .as_ref()
+            .ok_or_else(|| io::Error::other("TLS origin without interception"))?;
+        let name = rustls::pki_types::ServerName::try_from(target.host.clone())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        let connection = rustls::ClientConnection::new(interceptor.upstream(), name)
+            .map_err(io::Error::other)?;
+        Ok(Box::new(rustls::StreamOwned::new(connection, stream)))
+    }
+
+    fn finish(&self, mut record: HttpAuditRecord, started: Instant) {
+        record.duration_ms = started.elapsed().as_millis() as u64;
+        self.audit.append(&record);
+    }
+}
+
+/// A byte stream the proxy relays: a socket, or TLS over one.
+trait Conn: Read + Write + Send {
+    /// Bound blocking reads on the underlying socket.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl Conn for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+}
+
+impl Conn for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+impl Conn for Box<dyn Conn> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        (**self).set_read_timeout(timeout)
+    }
+}
+
+impl Conn for rustls::StreamOwned<rustls::ServerConnection, Buffered> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.sock.0.get_ref().set_read_timeout(timeout)
+    }
+}
+
+impl Conn for rustls::StreamOwned<rustls::ClientConnection, TcpStream> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.sock.set_read_timeout(timeout)
+    }
+}
+
+/// The guest connection with whatever was read ahead of the TLS handshake.
+struct Buffered(BufReader<UnixStream>);
+
+impl Read for Buffered {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Buffered {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.get_mut().flush()
+    }
+}
+
+/// Relay an upgraded connection in both directions until either side
+/// closes. Returns the bytes sent by the client and by the origin.
+fn relay<A: Conn, B: Conn>(
+    client: &mut BufReader<A>,
+    upstream: &mut BufReader<B>,
+) -> io::Result<(u64, u64)> {
+    client.get_ref().set_read_timeout(Some(RELAY_POLL))?;
+    upstream.get_ref().set_read_timeout(Some(RELAY_POLL))?;
+    let (mut sent, mut received) = (0, 0);
+    loop {
+        match pump(client, upstream.get_mut())? {
+            Some(copied) => sent += copied,
+            None => break,
+        }
+        match pump(upstream, client.get_mut())? {
+            Some(copied) => received += copied,
+            None => break,
+        }
+    }
+    Ok((sent, received))
+}
+
+/// Move whatever `src` has ready to `dst`: `None` once `src` is closed.
+fn pump<R: Read, W: Write>(src: &mut BufReader<R>, dst: &mut W) -> io::Result<Option<u64>> {
+    let buf = match src.fill_buf() {
+        Ok([]) => return Ok(None),
+        Ok(buf) => buf,
+        Err(error)
+            if matches!(
+                error.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+            ) =>
+        {
+            return Ok(Some(0))
+        }
+        Err(error) => return Err(error),
+    };
+    let len = buf.len();
+    dst.write_all(buf)?;
+    dst.flush()?;
+    src.consume(len);
+    Ok(Some(len as u64))
+}
+
+fn dial(host: &str, port: u16) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("{host} has no addresses"))
+    }))
+}
+
+fn host_header(target: &Target) -> String {
+    let default_port = if target.scheme == "https" { 443 } else { 80 };
+    let host = if target.host.contains(':') {
+        format!("[{}]", target.host)
+    } else {
+        target.host.clone()
+    };
+    if target.port == default_port {
+        host
+    } else {
+        format!("{host}:{}", target.port)
+    }
+}
+
+fn new_record(
+    method: &str,
+    scheme: &str,
+    host: &str,
+    port: u16,
+    path: Option<String>,
+    request_bytes: u64,
+) -> HttpAuditRecord {
+    HttpAuditRecord {
+        timestamp: Utc::now(),
+        method: method.to_string(),
+        scheme: scheme.to_string(),
+        host: host.to_string(),
+        port,
+        path,
+        status: None,
+        request_bytes,
+        response_bytes: 0,
+        duration_ms: 0,
+        error: None,
+    }
+}
+
+/// Answer a `CONNECT`; returns the bytes written.
+fn established(client: &mut UnixStream) -> u64 {
+    const RESPONSE: &[u8] = b"HTTP/1.1 200 Connection Established\r\n\r\n";
+    let _ = client.write_all(RESPONSE);
+    RESPONSE.len() as u64
+}
+
+/// Send a short error response; returns the bytes written.
+fn respond<W: Write>(client: &mut W, status: u16, reason: &str) -> u64 {
+    let body = format!("{status} {reason}\n");
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = client.write_all(response.as_bytes());
+    let _ = client.flush();
+    response.len() as u64
+}
+
+/// The box's append-only audit log.
+struct AuditLog(Mutex<File>);
+
+impl AuditLog {
+    fn open(path: &Path) -> Result<Self> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map(|file| Self(Mutex::new(file)))
+            .map_err(|error| BoxError::BoxBootError {
+                message: format!("failed to open HTTP audit log {}: {error}", path.display()),
+                hint: None,
+            })
+    }
+
+    /// Append one record as a single write, so concurrent connections never
+    /// interleave lines.
+    fn append(&self, record: &HttpAuditRecord) {
+        let Ok(mut line) = serde_json::to_vec(record) else {
+            return;
+        };
+        line.push(b'\n');
+        let mut file = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(error) = file.write_all(&line) {
+            tracing::warn!(%error, "Failed to append HTTP audit record");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn start(mode: HttpProxyMode) -> (tempfile::TempDir, HttpProxyInstanceConfig) {
+        let dir = tempfile::tempdir().unwrap();
+        let config = HttpProxyInstanceConfig {
+            mode,
+            socket_path: dir.path().join("http-proxy.sock"),
+            audit_log: dir.path().join("http-audit.jsonl"),
+            ca: None,
+        };
+        spawn(&config).unwrap();
+        (dir, config)
+    }
+
+    fn wait_for_records(path: &Path, count: usize) -> Vec<HttpAuditRecord> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let records = a3s_box_core::http_proxy::read_audit_log(path).unwrap();
+            if records.len() >= count || Instant::now() > deadline {
+                return records;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_plain_request_is_forwarded_and_recorded() {
+        let origin = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = origin.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = origin.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let (head, _) = http::read_head(&mut reader).unwrap().unwrap();
+            let mut body = [0u8; 5];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(
+                    b"HTTP/1.1 201 Created\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                )
+                .unwrap();
+            (head, body)
+        });
+
+        let (_dir, config) = start(HttpProxyMode::Audit);
+        let mut client = UnixStream::connect(&config.socket_path).unwrap();
+        write!(
+            client,
+            "POST http://127.0.0.1:{port}/items?x=1 HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nProxy-Connection: keep-alive\r\nContent-Length: 5\r\n\r\nhello"
+        )
+        .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 201 Created\r\n"));
+        assert!(response.ends_with("\r\n\r\nok"));
+
+        let (head, body) = server.join().unwrap();
+        assert_eq!(head.start[1], "/items?x=1");
+        assert_eq!(head.header("Proxy-Connection"), None);
+        assert_eq!(head.header("Connection"), Some("close"));
+        assert_eq!(&body, b"hello");
+
+        let records = wait_for_records(&config.audit_log, 1);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].method, "POST");
+        assert_eq!(records[0].status, Some(201));
+        assert_eq!(
+            records[0].target(),
+            format!("http://127.0.0.1:{port}/items?x=1")
+        );
+        assert_eq!(records[0].response_bytes as usize, response.len());
+    }
+
+    #[test]
+    fn test_connect_tunnel_is_spliced_and_recorded() {
+        let origin = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = origin.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = origin.accept().unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(b"pong!").unwrap();
+        });
+
+        let (_dir, config) = start(HttpProxyMode::Audit);
+        let mut client = UnixStream::connect(&config.socket_path).unwrap();
+        write!(client, "CONNECT 127.0.0.1:{port} HTTP/1.1\r\n\r\nping").unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(response, "HTTP/1.1 200 Connection Established\r\n\r\npong!");
+
+        let records = wait_for_records(&config.audit_log, 1);
+        assert_eq!(records[0].method, "CONNECT");
+        assert_eq!(records[0].target(), format!("127.0.0.1:{port}"));
+        assert_eq!(records[0].path, None);
+        assert_eq!(
+            records[0].request_bytes as usize,
+            format!("CONNECT 127.0.0.1:{port} HTTP/1.1\r\n\r\n").len() + 4
+        );
+    }
+
+    #[test]
+    fn test_unreachable_origin_is_recorded_as_bad_gateway() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let (_dir, config) = start(HttpProxyMode::Audit);
+        let mut client = UnixStream::connect(&config.socket_path).unwrap();
+        write!(client, "GET http://127.0.0.1:{port}/ HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 502 Bad Gateway\r\n"));
+
+        let records = wait_for_records(&config.audit_log, 1);
+        assert_eq!(records[0].status, Some(502));
+        assert!(records[0].error.is_some());
+    }
+}
@@ -14,19 +14,21 @@ use a3s_box_core::error::{BoxError, Result};
 use libkrun_sys::krun_add_net_unixgram;
 #[cfg(not(target_os = "windows"))]
 use libkrun_sys::krun_add_vsock_port2;
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use libkrun_sys::krun_set_kernel;
 #[cfg(not(target_os = "windows"))]
 use libkrun_sys::krun_set_port_map;
+use libkrun_sys::{
+    krun_add_disk, krun_add_virtiofs, krun_create_ctx, krun_free_ctx, krun_init_log,
+    krun_set_console_output, krun_set_env, krun_set_exec, krun_set_rlimits, krun_set_root,
+    krun_set_vm_config, krun_set_workdir, krun_setgid, krun_setuid, krun_start_enter,
+};
 #[cfg(target_os = "windows")]
-use libkrun_sys::{krun_add_net_tcp, krun_add_vsock_port_windows, krun_set_kernel};
+use libkrun_sys::{krun_add_net_tcp, krun_add_vsock_port_windows};
 #[cfg(target_os = "linux")]
 use libkrun_sys::{krun_add_net_unixstream, krun_split_irqchip};
 #[cfg(unix)]
 use libkrun_sys::{krun_add_virtio_console_default, krun_disable_implicit_console};
-use libkrun_sys::{
-    krun_add_virtiofs, krun_create_ctx, krun_free_ctx, krun_init_log, krun_set_console_output,
-    krun_set_env, krun_set_exec, krun_set_rlimits, krun_set_root, krun_set_vm_config,
-    krun_set_workdir, krun_setgid, krun_setuid, krun_start_enter,
-};
 
 /// Thin wrapper that owns a libkrun context.
 pub struct KrunContext {
@@ -260,6 +262,35 @@ impl KrunContext {
         )
     }
 
+    /// Attach a raw block device or disk image as a virtio-blk disk.
+    ///
+    /// # Arguments
+    /// * `block_id` - Device ID; the guest sees it as the disk's serial
+    /// * `disk_path` - Host block device or raw image path
+    /// * `read_only` - Attach the disk read-only
+    pub unsafe fn add_disk(&self, block_id: &str, disk_path: &str, read_only: bool) -> Result<()> {
+        tracing::debug!(block_id, disk_path, read_only, "Adding block device");
+
+        let block_id_c = CString::new(block_id).map_err(|e| BoxError::BoxBootError {
+            message: format!("invalid block id: {}", e),
+            hint: None,
+        })?;
+        let disk_path_c = CString::new(disk_path).map_err(|e| BoxError::BoxBootError {
+            message: format!("invalid disk path: {}", e),
+            hint: None,
+        })?;
+
+        check_status(
+            "krun_add_disk",
+            krun_add_disk(
+                self.ctx_id,
+                block_id_c.as_ptr(),
+                disk_path_c.as_ptr(),
+                read_only,
+            ),
+        )
+    }
+
     /// Configure vsock port with Unix socket bridge.
     ///
     /// # Arguments
@@ -504,14 +535,15 @@ impl KrunContext {
 
     /// Set the kernel image for the microVM (required on Windows).
     ///
-    /// On Windows, this **must** be called before `start_enter()`.
+    /// On Windows, this **must** be called before `start_enter()`. On Linux it
+    /// replaces the libkrunfw kernel.
     ///
     /// # Arguments
     /// * `kernel_path` - Path to the kernel image file
     /// * `kernel_format` - One of the `KRUN_KERNEL_FORMAT_*` constants
     /// * `initramfs` - Optional path to initramfs image
     /// * `cmdline` - Optional kernel command line string
-    #[cfg(target_os = "windows")]
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
     #[allow(dead_code)]
     pub unsafe fn set_kernel(
         &self,
@@ -12,14 +12,27 @@
 // Allow large error types - this is a binary, not a library
 #![allow(clippy::result_large_err)]
 
+#[cfg(unix)]
+mod device_proxy;
+mod guest_memory;
+#[cfg(unix)]
+mod http_proxy;
 mod krun;
 
 #[cfg(target_os = "windows")]
 use a3s_box_core::config::validate_vcpu_count;
+#[cfg(not(target_os = "windows"))]
+use a3s_box_core::device::{device_proxy_socket_file, device_proxy_vsock_port};
 use a3s_box_core::error::{BoxError, Result};
 #[cfg(target_os = "windows")]
 use a3s_box_core::exec::WINDOWS_STOP_REQUEST_FILE;
+#[cfg(not(target_os = "windows"))]
+use a3s_box_core::forward::FORWARD_VSOCK_PORT;
+#[cfg(not(target_os = "windows"))]
+use a3s_box_core::http_proxy::HTTP_PROXY_VSOCK_PORT;
 use a3s_box_core::vmm::InstanceSpec;
+#[cfg(not(target_os = "windows"))]
+use a3s_box_core::volume::socket_mount_vsock_port;
 use a3s_box_core::EXEC_VSOCK_PORT;
 #[cfg(target_os = "windows")]
 use a3s_box_core::PORT_FWD_VSOCK_PORT;
@@ -199,6 +212,8 @@ fn run() -> Result<()> {
 
     // Opt-in KSM: mark guest memory mergeable before libkrun allocates it.
     maybe_enable_ksm_merge();
+    // Opt-in hugepages / prealloc: advise guest RAM once libkrun maps it.
+    guest_memory::spawn_guest_memory_tuner(spec.memory_mib, spec.hugepages, spec.mem_prealloc);
 
     // Validate rootfs exists
     if !spec.rootfs_path.exists() {
@@ -466,6 +481,32 @@ fn parse_cpuset_spec(spec: &str) -> std::result::Result<Vec<usize>, String> {
     Ok(cpus)
 }
 
+/// Bind this process's future memory allocations to one NUMA node via
+/// set_mempolicy(MPOL_BIND) (Linux only).
+///
+/// Must run on the thread that calls `krun_start_enter()`: libkrun maps guest
+/// RAM there, and the vCPU threads it spawns inherit the policy.
+#[cfg(target_os = "linux")]
+fn apply_numa_membind(node: u32) -> std::result::Result<(), String> {
+    const MPOL_BIND: libc::c_long = 2;
+    let bits = libc::c_ulong::BITS;
+    let mut mask = vec![0 as libc::c_ulong; node as usize / bits as usize + 1];
+    mask[node as usize / bits as usize] |= 1 << (node % bits);
+    let max_node = (mask.len() as libc::c_ulong) * libc::c_ulong::from(bits);
+
+    // SAFETY: `mask` is a live nodemask of `max_node` bits for the duration
+    // of the call; set_mempolicy only reads it.
+    let ret = unsafe { libc::syscall(libc::SYS_set_mempolicy, MPOL_BIND, mask.as_ptr(), max_node) };
+    if ret != 0 {
+        return Err(format!(
+            "set_mempolicy(MPOL_BIND, node {node}) failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    tracing::info!(node, "Bound guest memory to NUMA node");
+    Ok(())
+}
+
 #[cfg(not(target_os = "windows"))]
 #[cfg_attr(target_os = "macos", allow(dead_code))]
 fn tsi_port_map_for_spec(spec: &InstanceSpec) -> Vec<String> {
@@ -476,10 +517,19 @@ fn tsi_port_map_for_spec(spec: &InstanceSpec) -> Vec<String> {
     spec.port_map
         .iter()
         .filter(|mapping| !is_auto_assigned_host_port(mapping))
+        .filter(|mapping| !has_bound_host_ip(mapping))
         .cloned()
         .collect()
 }
 
+// libkrun's TSI port map always listens on every host address, so a mapping
+// bound to one address is left out rather than silently widened.
+#[cfg(not(target_os = "windows"))]
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn has_bound_host_ip(mapping: &str) -> bool {
+    a3s_box_core::parse_port_mapping(mapping).is_ok_and(|mapping| mapping.host_ip.is_some())
+}
+
 // On both macOS (netproxy) and Linux (passt), bridge-mode published ports are
 // forwarded by the native network backend, not TSI. libkrun discards the TSI
 // host_port_map once a virtio-net device is attached anyway, so feeding it the
@@ -605,6 +655,8 @@ unsafe fn configure_and_start_vm(spec: &InstanceSpec) -> Result<()> {
 
     #[cfg(target_os = "windows")]
     configure_windows_kernel(&ctx)?;
+    #[cfg(target_os = "linux")]
+    configure_external_kernel(&ctx)?;
 
     // Raise RLIMIT_NOFILE to maximum - CRITICAL for virtio-fs
     #[cfg(unix)]
@@ -700,6 +752,17 @@ unsafe fn configure_and_start_vm(spec: &InstanceSpec) -> Result<()> {
         ctx.add_virtiofs(&mount.tag, path_str)?;
     }
 
+    // Attach block volumes; guest init mounts them by block ID.
+    for device in &spec.block_devices {
+        tracing::info!(
+            "  {} → {} ({})",
+            device.block_id,
+            device.source,
+            if device.read_only { "ro" } else { "rw" }
+        );
+        ctx.add_disk(&device.block_id, &device.source, device.read_only)?;
+    }
+
     // Set root filesystem
     let rootfs_str = spec
         .rootfs_path
@@ -828,6 +891,90 @@ unsafe fn configure_and_start_vm(spec: &InstanceSpec) -> Result<()> {
             );
             ctx.add_vsock_port(PORT_FWD_VSOCK_PORT, port_forward_socket_str, true)?;
         }
+
+        // `a3s-box forward` streams: each host connection to this socket
+        // becomes one vsock connection to guest init's forwarder.
+        if !spec.forward_socket_path.as_os_str().is_empty() {
+            let forward_socket_str =
+                spec.forward_socket_path
+                    .to_str()
+                    .ok_or_else(|| BoxError::BoxBootError {
+                        message: format!(
+                            "Invalid forward socket path: {}",
+                            spec.forward_socket_path.display()
+                        ),
+                        hint: None,
+                    })?;
+            tracing::debug!(
+                socket_path = forward_socket_str,
+                guest_port = FORWARD_VSOCK_PORT,
+                "Configuring vsock bridge for port forwarding"
+            );
+            ctx.add_vsock_port(FORWARD_VSOCK_PORT, forward_socket_str, true)?;
+        }
+
+        // Shared host sockets (`--mount type=socket`): guest init dials each
+        // one's vsock port, and libkrun connects to the host socket itself.
+        for (index, mount) in spec.socket_mounts.iter().enumerate() {
+            let vsock_port = socket_mount_vsock_port(index);
+            tracing::debug!(
+                socket_path = %mount.source,
+                guest_port = vsock_port,
+                target = %mount.target,
+                "Configuring vsock bridge for shared socket"
+            );
+            ctx.add_vsock_port(vsock_port, &mount.source, false)?;
+        }
+
+        // Proxied host devices (`--device`): the shim serves each device on a
+        // socket next to the exec socket, and guest init dials its vsock port.
+        for (index, device) in spec.device_proxies.iter().enumerate() {
+            let socket_path = spec
+                .exec_socket_path
+                .with_file_name(device_proxy_socket_file(index));
+            let socket_str = socket_path.to_str().ok_or_else(|| BoxError::BoxBootError {
+                message: format!(
+                    "Invalid device proxy socket path: {}",
+                    socket_path.display()
+                ),
+                hint: None,
+            })?;
+            device_proxy::spawn(device, &socket_path)?;
+            let vsock_port = device_proxy_vsock_port(index);
+            tracing::debug!(
+                socket_path = socket_str,
+                guest_port = vsock_port,
+                device = %device.source,
+                target = %device.target,
+                "Configuring vsock bridge for proxied device"
+            );
+            ctx.add_vsock_port(vsock_port, socket_str, false)?;
+        }
+
+        // Serve the --http-proxy socket for the box's lifetime. Guest init
+        // dials the vsock port, so libkrun connects to our socket
+        // (listen=false) rather than listening on it.
+        if let Some(proxy) = &spec.http_proxy {
+            let proxy_socket_str =
+                proxy
+                    .socket_path
+                    .to_str()
+                    .ok_or_else(|| BoxError::BoxBootError {
+                        message: format!(
+                            "Invalid HTTP proxy socket path: {}",
+                            proxy.socket_path.display()
+                        ),
+                        hint: None,
+                    })?;
+            http_proxy::spawn(proxy)?;
+            tracing::debug!(
+                socket_path = proxy_socket_str,
+                guest_port = HTTP_PROXY_VSOCK_PORT,
+                mode = %proxy.mode,
+                "Configuring vsock bridge for HTTP proxy"
+            );
+            ctx.add_vsock_port(HTTP_PROXY_VSOCK_PORT, proxy_socket_str, false)?;
+        }
     }
 
     // Configure exec communication channel on Windows (Named Pipe bridged to vsock)
@@ -1058,6 +1205,19 @@ unsafe fn configure_and_start_vm(spec: &InstanceSpec) -> Result<()> {
         }
     }
 
+    // Bind guest memory to the NUMA node whose CPUs the cpuset above selected.
+    #[cfg(target_os = "linux")]
+    if let Some(node) = spec.resource_limits.numa_node {
+        if let Err(e) = apply_numa_membind(node) {
+            tracing::warn!(node, error = %e, "Failed to apply NUMA memory binding");
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    if spec.resource_limits.numa_node.is_some() {
+        tracing::warn!("NUMA placement is only supported on Linux; ignoring");
+    }
+
     // CPU/memory cgroup limits (--cpu-shares/--cpu-quota/--memory-reservation/
     // --memory-swap) are NOT applied to the host VM process: they are enforced
     // INSIDE the guest by guest-init's per-container cgroup (the workload runs in
@@ -1283,6 +1443,51 @@ fn configure_windows_kernel(ctx: &KrunContext) -> Result<()> {
     unsafe { ctx.set_kernel(kernel_path_str, kernel_format, None, None) }
 }
 
+/// Boot the kernel named by `A3S_BOX_KERNEL` (set per VM from
+/// `InstanceSpec::kernel_path`) instead of the libkrunfw one.
+#[cfg(target_os = "linux")]
+fn configure_external_kernel(ctx: &KrunContext) -> Result<()> {
+    use std::io::Read;
+
+    let Some(kernel_path) = std::env::var_os("A3S_BOX_KERNEL").map(std::path::PathBuf::from) else {
+        return Ok(());
+    };
+
+    let mut magic = [0u8; 4];
+    std::fs::File::open(&kernel_path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_err(|e| BoxError::BoxBootError {
+            message: format!("Failed to read guest kernel {}: {e}", kernel_path.display()),
+            hint: None,
+        })?;
+    if magic != [0x7f, b'E', b'L', b'F'] {
+        return Err(BoxError::BoxBootError {
+            message: format!(
+                "Unsupported guest kernel format in {}",
+                kernel_path.display()
+            ),
+            hint: Some("Expected an uncompressed ELF vmlinux".to_string()),
+        });
+    }
+    let kernel_path_str = kernel_path.to_str().ok_or_else(|| BoxError::BoxBootError {
+        message: format!(
+            "A3S_BOX_KERNEL is not valid UTF-8: {}",
+            kernel_path.display()
+        ),
+        hint: None,
+    })?;
+
+    tracing::info!(kernel = %kernel_path.display(), "Using external guest kernel");
+    unsafe {
+        ctx.set_kernel(
+            kernel_path_str,
+            libkrun_sys::KRUN_KERNEL_FORMAT_ELF,
+            None,
+            None,
+        )
+    }
+}
+
 #[cfg(target_os = "windows")]
 fn detect_windows_kernel_format(path: &Path) -> Result<u32> {
     let mut file = File::open(path).map_err(|e| BoxError::BoxBootError {
@@ -1353,10 +1558,13 @@ fn log_inherited_net_fd(fd: i32) {
 
 /// Apply OCI USER directive to the krun context.
 ///
+/// Only used when the rootfs has no guest init; with one, guest init resolves
+/// the user against the container's `/etc/passwd` and `/etc/group` instead.
+///
 /// Supports formats:
 /// - "uid" (e.g., "1000")
 /// - "uid:gid" (e.g., "1000:1000")
-/// - Non-numeric names are logged and skipped (would require /etc/passwd lookup)
+/// - Non-numeric names are logged and skipped
 unsafe fn apply_user_config(ctx: &KrunContext, user: &str) -> Result<()> {
     if user.is_empty() {
         return Ok(());
@@ -1373,10 +1581,10 @@ unsafe fn apply_user_config(ctx: &KrunContext, user: &str) -> Result<()> {
             ctx.set_uid(uid)?;
         }
         Err(_) => {
-            // Non-numeric user name — would need /etc/passwd lookup inside rootfs
+            // Non-numeric user name — only guest init can look it up in the rootfs
             tracing::warn!(
                 user = uid_str,
-                "Non-numeric USER directive; skipping (name lookup not yet supported)"
+                "Non-numeric USER directive; skipping (name lookup needs guest init)"
             );
             return Ok(());
         }
@@ -1598,6 +1806,17 @@ mod tests {
         );
     }
 
+    #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+    #[test]
+    fn test_tsi_port_map_for_spec_skips_host_ip_bound_ports() {
+        let spec = InstanceSpec {
+            port_map: vec!["[::1]:8080:80".to_string(), "9090:90".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(tsi_port_map_for_spec(&spec), vec!["9090:90".to_string()]);
+    }
+
     #[cfg(target_os = "macos")]
     #[test]
     fn test_tsi_port_map_for_spec_skips_macos_bridge_ports() {
@@ -1638,6 +1857,9 @@ mod tests {
             gateway: "10.89.0.1".parse().unwrap(),
             prefix_len: 24,
             mac_address: [0x02, 0x42, 0x0a, 0x59, 0x00, 0x02],
+            ipv6_address: None,
+            ipv6_gateway: None,
+            ipv6_prefix_len: 0,
             dns_servers: vec!["8.8.8.8".parse().unwrap()],
         }
     }